@@ -0,0 +1,231 @@
+"""Drive a running VibeLang session from Python.
+
+The module wraps the HTTP API of `vibe run` / `vibe perform` with the same
+object model as VibeLang scripts: voices, patterns, melodies and sequences.
+Objects are written with idempotent `PUT` upserts, so a Python script can be
+re-run against a live session the same way a `.vibe` file is hot-reloaded.
+
+    from pyvibelang import Session
+
+    vibe = Session()                       # http://localhost:1606
+    kick = vibe.voice("kick", synth="kick_808", gain=0.5)
+    groove = vibe.pattern("groove", kick, "x... x... x..x ....")
+    bass = vibe.voice("bass", synth="sub_deep")
+    line = vibe.melody("line", bass, "C3 - - - | C3 - G2 -")
+    vibe.sequence("intro", 16).clip(0, 16, groove).clip(0, 16, line).start()
+    vibe.eval('set_tempo(124);')           # anything else goes through Rhai
+
+Only the Python standard library is used.
+"""
+
+import json
+import urllib.error
+import urllib.request
+
+__all__ = ["Session", "VibeError", "Voice", "Pattern", "Melody", "Sequence", "API_VERSION"]
+
+#: Object API version this client was written against (see `GET /schema`).
+API_VERSION = 1
+
+
+class VibeError(Exception):
+    """An HTTP API call failed."""
+
+    def __init__(self, status, message):
+        super().__init__(f"{status}: {message}")
+        self.status = status
+        self.message = message
+
+
+class Session:
+    """Connection to a VibeLang HTTP API server."""
+
+    def __init__(self, url="http://localhost:1606", timeout=10.0, check_version=True, token=None):
+        self.url = url.rstrip("/")
+        self.timeout = timeout
+        self.token = token
+        if check_version:
+            version = self.schema().get("api_version")
+            if version != API_VERSION:
+                raise VibeError(
+                    "version",
+                    f"server speaks object API v{version}, client expects v{API_VERSION}",
+                )
+
+    # === Raw requests ===
+
+    def request(self, method, path, body=None):
+        """Send a request and return the decoded JSON response (or None)."""
+        data = None if body is None else json.dumps(body).encode()
+        req = urllib.request.Request(self.url + path, data=data, method=method)
+        req.add_header("Content-Type", "application/json")
+        if self.token:
+            req.add_header("Authorization", f"Bearer {self.token}")
+        try:
+            with urllib.request.urlopen(req, timeout=self.timeout) as resp:
+                payload = resp.read()
+        except urllib.error.HTTPError as e:
+            payload = e.read()
+            try:
+                message = json.loads(payload).get("message", payload.decode())
+            except ValueError:
+                message = payload.decode(errors="replace")
+            raise VibeError(e.code, message) from None
+        return json.loads(payload) if payload else None
+
+    def schema(self):
+        """API version and JSON schemas of the upsert bodies."""
+        return self.request("GET", "/schema")
+
+    # === Objects ===
+
+    def voice(self, name, synth=None, gain=1.0, polyphony=8, group="main", **params):
+        """Create or replace a voice. Extra keyword arguments are synth params."""
+        return Voice(self, name, {
+            "synth_name": synth,
+            "gain": gain,
+            "polyphony": polyphony,
+            "group_path": group,
+            "params": params,
+        }).put()
+
+    def pattern(self, name, voice, steps=None, loop_beats=4.0, events=None, **params):
+        """Create or replace a pattern from a step string or `[(beat, params)]` events."""
+        body = {
+            "voice_name": _name(voice),
+            "loop_beats": loop_beats,
+            "pattern_string": steps,
+            "params": params,
+        }
+        if events is not None:
+            body["events"] = [{"beat": beat, "params": p} for beat, p in events]
+        return Pattern(self, name, body).put()
+
+    def melody(self, name, voice, notes=None, loop_beats=4.0, lanes=None, **params):
+        """Create or replace a melody from a note string or a list of lanes."""
+        return Melody(self, name, {
+            "voice_name": _name(voice),
+            "loop_beats": loop_beats,
+            "melody_string": notes,
+            "lanes": lanes,
+            "params": params,
+        }).put()
+
+    def sequence(self, name, loop_beats=16.0):
+        """Start building a sequence; clips are added with `.clip()`."""
+        return Sequence(self, name, {"loop_beats": loop_beats, "clips": []})
+
+    # === Session ===
+
+    def eval(self, code):
+        """Evaluate Rhai code in the session and return its result."""
+        resp = self.request("POST", "/eval", {"code": code})
+        if not resp.get("success"):
+            raise VibeError("eval", resp.get("error") or "evaluation failed")
+        return resp.get("result")
+
+    def transport(self):
+        return self.request("GET", "/transport")
+
+    def set_tempo(self, bpm):
+        self.request("PATCH", "/transport", {"bpm": bpm})
+
+    def set_macro(self, name, value):
+        """Set a macro control (0..1) defined by the script."""
+        self.request("PUT", f"/macros/{_quote(name)}", {"value": value})
+
+
+class _Object:
+    """An object stored under `/{kind}/{name}`."""
+
+    kind = ""
+
+    def __init__(self, session, name, body):
+        self.session = session
+        self.name = name
+        self.body = body
+        self.state = None
+
+    @property
+    def path(self):
+        return f"/{self.kind}/{_quote(self.name)}"
+
+    def put(self):
+        """Create or replace the object (safe to repeat)."""
+        self.state = self.session.request("PUT", self.path, {"name": self.name, **self.body})
+        return self
+
+    def get(self):
+        """Fetch the current server-side state."""
+        self.state = self.session.request("GET", self.path)
+        return self.state
+
+    def delete(self):
+        self.session.request("DELETE", self.path)
+
+    def __repr__(self):
+        return f"{type(self).__name__}({self.name!r})"
+
+
+class Voice(_Object):
+    kind = "voices"
+
+    def set(self, param, value, fade_beats=None):
+        body = {"value": value}
+        if fade_beats is not None:
+            body["fade_beats"] = fade_beats
+        self.session.request("PUT", f"{self.path}/params/{_quote(param)}", body)
+
+    def note_on(self, note, velocity=100):
+        self.session.request("POST", f"{self.path}/note-on", {"note": note, "velocity": velocity})
+
+    def note_off(self, note):
+        self.session.request("POST", f"{self.path}/note-off", {"note": note})
+
+
+class _Loop(_Object):
+    def start(self):
+        self.session.request("POST", f"{self.path}/start", {})
+        return self
+
+    def stop(self):
+        self.session.request("POST", f"{self.path}/stop", {})
+        return self
+
+
+class Pattern(_Loop):
+    kind = "patterns"
+
+
+class Melody(_Loop):
+    kind = "melodies"
+
+
+class Sequence(_Loop):
+    kind = "sequences"
+
+    def clip(self, start, end, source, mode="loop"):
+        """Add a pattern, melody or sequence clip (mode: "loop", "once" or "loop:N")."""
+        kinds = {Pattern: "pattern", Melody: "melody", Sequence: "sequence"}
+        clip_type = kinds.get(type(source), "pattern")
+        self.body["clips"].append({
+            "type": clip_type,
+            "name": _name(source),
+            "start_beat": float(start),
+            "end_beat": float(end),
+            "mode": mode,
+        })
+        return self
+
+    def start(self, play_once=False):
+        self.put()
+        self.session.request("POST", f"{self.path}/start", {"play_once": play_once})
+        return self
+
+
+def _name(obj):
+    return obj.name if isinstance(obj, _Object) else str(obj)
+
+
+def _quote(segment):
+    return urllib.request.quote(segment, safe="")
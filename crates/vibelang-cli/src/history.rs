@@ -0,0 +1,70 @@
+//! Viewer for session history files.
+//!
+//! Reads the JSONL audit log written by the HTTP API when `--history-file`
+//! is set and prints it as a table, optionally filtered by bar range or path.
+
+use crate::HistoryArgs;
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use vibelang_http::{filter_entries, read_history_file, HistoryEntry, HistoryQuery};
+
+/// Maximum number of payload characters shown per line.
+const MAX_PAYLOAD_CHARS: usize = 60;
+
+/// Print the entries of a history file.
+pub fn show_history(args: HistoryArgs) -> Result<()> {
+    let entries = read_history_file(&args.file)
+        .with_context(|| format!("Failed to read history file: {}", args.file.display()))?;
+
+    let query = HistoryQuery {
+        since_bar: args.since_bar,
+        until_bar: args.until_bar,
+        path: args.path,
+        limit: args.limit,
+    };
+    let entries = filter_entries(entries.into_iter(), &query);
+
+    if entries.is_empty() {
+        println!("No matching entries.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:>5} {:>9}  {:<6} {:<40} {:>6}  PAYLOAD",
+        "TIME", "BAR", "BEAT", "METHOD", "PATH", "STATUS"
+    );
+    for entry in &entries {
+        println!("{}", format_entry(entry));
+    }
+
+    Ok(())
+}
+
+fn format_entry(entry: &HistoryEntry) -> String {
+    let time = Local
+        .timestamp_millis_opt(entry.timestamp as i64)
+        .single()
+        .map(|t| t.format("%H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    let payload = entry
+        .payload
+        .as_ref()
+        .map(|p| truncate(&p.to_string(), MAX_PAYLOAD_CHARS))
+        .unwrap_or_default();
+
+    format!(
+        "{:<12} {:>5} {:>9.2}  {:<6} {:<40} {:>6}  {}",
+        time, entry.bar, entry.beat, entry.method, entry.path, entry.status, payload
+    )
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(max_chars - 1).collect();
+        out.push('…');
+        out
+    }
+}
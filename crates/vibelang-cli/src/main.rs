@@ -13,21 +13,52 @@
 //! # Commands
 //!
 //! - `vibe run <file>` - Run a .vibe file interactively (default)
+//! - `vibe perform <set>` - Perform a composition with a .vibeset live set
 //! - `vibe render <file>` - Render a .vibe file to audio
+//! - `vibe history <file>` - View a recorded API history file
+//! - `vibe warmup <file>` - Write a preload manifest so the next run starts instantly
+//! - `vibe mirror <url>` - Show another session's TUI read-only, without audio
+//! - `vibe list-sessions` - Find running sessions on the LAN
+//! - `vibe osc-dump <file>` - Show OSC traffic captured with `capture_osc()` or the TUI
+//! - `vibe stress` - Burn-in test: synthetic load, then a timing and stability report
+//!
+//! # Signals
+//!
+//! SIGINT and SIGTERM shut down gracefully and write a session snapshot next
+//! to the script (also written periodically); SIGHUP reloads the script.
+//! `--resume` picks the set up from the snapshot.
 
+mod history;
+mod mirror;
+mod osc_dump;
 mod render;
+mod resume;
+mod sandbox;
+mod session_file;
+mod sessions;
+mod simulate;
+mod stdlib;
+mod stress;
 mod tui;
+mod warmup;
 
 use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
 use std::fs;
-use std::path::PathBuf;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use rhai::AST;
 use vibelang_core::api::context;
-use vibelang_core::state::StateMessage;
-use vibelang_core::{AudioConfig, RuntimeHandle};
+use vibelang_core::api::checkpoint;
+use vibelang_core::api::incremental::IncrementalScript;
+use vibelang_core::api::watchdog::{self, EvalLimits};
+use vibelang_core::liveset::{BindingTrigger, LiveSet, LIVE_SET_EXTENSION};
+use vibelang_core::session::SessionSnapshot;
+use vibelang_core::state::{NetSyncRole, StateMessage};
+use vibelang_core::synthdef_dir::SynthDefDirWatcher;
+use vibelang_core::{AudioConfig, Beats, RuntimeHandle};
 
 /// VibeLang - SuperCollider Live Coding
 #[derive(Parser, Debug)]
@@ -54,13 +85,71 @@ struct Cli {
     #[arg(short = 'I', long = "import-path", value_name = "PATH", global = true)]
     import_paths: Vec<PathBuf>,
 
-    /// Enable HTTP REST API server (can run without a file)
-    #[arg(long, global = true)]
+    /// Run with only the HTTP REST API server, without a file
+    #[arg(long, conflicts_with = "no_server", global = true)]
     api: bool,
 
-    /// HTTP API server port (default: 1606)
-    #[arg(long, value_name = "PORT", default_value = "1606", global = true)]
+    /// Don't start the HTTP REST API server
+    #[arg(long, global = true)]
+    no_server: bool,
+
+    /// HTTP API server port; the next free port is used if it is busy
+    #[arg(long, value_name = "PORT", default_value_t = vibelang_http::DEFAULT_PORT, global = true)]
     api_port: u16,
+
+    /// Address to serve the HTTP API on. Defaults to 127.0.0.1 (this machine
+    /// only; earlier versions listened on 0.0.0.0). Use 0.0.0.0 to expose the
+    /// API on the LAN (sessions with --sandbox are advertised via zeroconf)
+    #[arg(long, value_name = "HOST", default_value_t = vibelang_http::DEFAULT_HOST, global = true)]
+    api_host: IpAddr,
+
+    /// Append all API mutations to this JSONL file (view with `vibe history`)
+    #[arg(long, value_name = "PATH", global = true)]
+    history_file: Option<PathBuf>,
+
+    /// Sandbox profile for `/eval` requests ("workshop" or "trusted")
+    #[arg(long, value_name = "PROFILE", global = true)]
+    sandbox: Option<String>,
+
+    /// Sandbox profile for an `/eval` bearer token (repeatable)
+    #[arg(long = "eval-token", value_name = "TOKEN=PROFILE", global = true)]
+    eval_tokens: Vec<String>,
+
+    /// Let trusted `/eval` requests register hooks, which run shell commands
+    #[arg(long, global = true)]
+    allow_hooks: bool,
+
+    /// Cancel a script evaluation after this many seconds (0 = never)
+    #[arg(long, value_name = "SECS", default_value = "10", global = true)]
+    max_eval_time: f64,
+
+    /// Cancel a script evaluation after this many operations (0 = never)
+    #[arg(long, value_name = "N", default_value = "0", global = true)]
+    max_operations: u64,
+
+    /// On reload, only evaluate top-level statements that changed (and their dependents)
+    #[arg(long, global = true)]
+    incremental: bool,
+
+    /// Evaluate the file only up to `checkpoint("NAME")`
+    #[arg(long, value_name = "NAME", global = true)]
+    until: Option<String>,
+
+    /// Restore tempo, position, playing sequences and the mixer from the last session snapshot
+    #[arg(long, global = true)]
+    resume: bool,
+
+    /// Write a session snapshot every SECS seconds for --resume (0 = only on shutdown)
+    #[arg(long, value_name = "SECS", default_value = "10", global = true)]
+    snapshot_interval: f64,
+
+    /// Lead a network transport sync: followers play in time with this session
+    #[arg(long, value_name = "PORT", default_missing_value = "57140", num_args = 0..=1, global = true)]
+    sync_leader: Option<u16>,
+
+    /// Follow the network sync leader at HOST[:PORT]
+    #[arg(long, value_name = "HOST[:PORT]", global = true, conflicts_with = "sync_leader")]
+    sync_follow: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -68,9 +157,49 @@ enum Commands {
     /// Run a .vibe file interactively (default behavior)
     Run(RunArgs),
 
+    /// Perform a composition with a .vibeset live set (scenes, cues, bindings)
+    Perform(PerformArgs),
+
     /// Render a .vibe file to an audio file (offline)
     Render(RenderArgs),
 
+    /// List the events a .vibe file fires, in virtual time without audio
+    /// (e.g. `vibe simulate song.vibe --bars 16 --dump events.json`)
+    Simulate(SimulateArgs),
+
+    /// Show API mutations recorded with --history-file
+    History(HistoryArgs),
+
+    /// Record the synthdefs, samples and SFZ instruments a .vibe file uses,
+    /// so `vibe run` preloads them before the first bar
+    Warmup(WarmupArgs),
+
+    /// Show a running session's TUI read-only, without audio
+    /// (e.g. `vibe mirror ws://stage:1606/ws` for a venue screen)
+    Mirror(MirrorArgs),
+
+    /// Find running sessions on the LAN (advertised over zeroconf)
+    ListSessions(ListSessionsArgs),
+
+    /// Show OSC traffic captured with `capture_osc()` or the TUI 'O' key
+    /// (e.g. `vibe osc-dump capture.oscdump --filter /s_new`)
+    OscDump(OscDumpArgs),
+
+    /// Play synthetic load for hours and report timing jitter, dropped
+    /// events, failed sends and memory growth
+    /// (e.g. `vibe stress --voices 64 --events-per-beat 200 --hours 2`)
+    Stress(StressArgs),
+
+    /// Search the standard library's synthdefs and effects
+    /// (e.g. `vibe stdlib search kick techno`)
+    #[command(subcommand)]
+    Stdlib(StdlibCommand),
+
+    /// Save a running session to a file, or load one into it
+    /// (e.g. `vibe session save set.vibesession`)
+    #[command(subcommand)]
+    Session(SessionCommand),
+
     /// Start the Language Server Protocol (LSP) server
     Lsp,
 
@@ -105,14 +234,72 @@ struct RunArgs {
     #[arg(long, value_name = "NAME")]
     exit_after_sequence: Option<String>,
 
-    /// Enable HTTP REST API server
-    #[arg(long)]
+    /// Run with only the HTTP REST API server, without a file
+    #[arg(long, conflicts_with = "no_server")]
     api: bool,
 
-    /// HTTP API server port (default: 1606)
-    #[arg(long, value_name = "PORT", default_value = "1606")]
+    /// Don't start the HTTP REST API server
+    #[arg(long)]
+    no_server: bool,
+
+    /// HTTP API server port; the next free port is used if it is busy
+    #[arg(long, value_name = "PORT", default_value_t = vibelang_http::DEFAULT_PORT)]
     api_port: u16,
 
+    /// Address to serve the HTTP API on. Defaults to 127.0.0.1 (this machine
+    /// only; earlier versions listened on 0.0.0.0). Use 0.0.0.0 to expose the
+    /// API on the LAN (sessions with --sandbox are advertised via zeroconf)
+    #[arg(long, value_name = "HOST", default_value_t = vibelang_http::DEFAULT_HOST)]
+    api_host: IpAddr,
+
+    /// Append all API mutations to this JSONL file (view with `vibe history`)
+    #[arg(long, value_name = "PATH")]
+    history_file: Option<PathBuf>,
+
+    /// Sandbox profile for `/eval` requests ("workshop" or "trusted")
+    #[arg(long, value_name = "PROFILE")]
+    sandbox: Option<String>,
+
+    /// Sandbox profile for an `/eval` bearer token (repeatable)
+    #[arg(long = "eval-token", value_name = "TOKEN=PROFILE")]
+    eval_tokens: Vec<String>,
+
+    /// Let trusted `/eval` requests register hooks, which run shell commands
+    #[arg(long)]
+    allow_hooks: bool,
+
+    /// Cancel a script evaluation after this many seconds (0 = never)
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    max_eval_time: f64,
+
+    /// Cancel a script evaluation after this many operations (0 = never)
+    #[arg(long, value_name = "N", default_value = "0")]
+    max_operations: u64,
+
+    /// On reload, only evaluate top-level statements that changed (and their dependents)
+    #[arg(long)]
+    incremental: bool,
+
+    /// Evaluate the file only up to `checkpoint("NAME")`
+    #[arg(long, value_name = "NAME")]
+    until: Option<String>,
+
+    /// Restore tempo, position, playing sequences and the mixer from the last session snapshot
+    #[arg(long)]
+    resume: bool,
+
+    /// Write a session snapshot every SECS seconds for --resume (0 = only on shutdown)
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    snapshot_interval: f64,
+
+    /// Lead a network transport sync: followers play in time with this session
+    #[arg(long, value_name = "PORT", default_missing_value = "57140", num_args = 0..=1)]
+    sync_leader: Option<u16>,
+
+    /// Follow the network sync leader at HOST[:PORT]
+    #[arg(long, value_name = "HOST[:PORT]", conflicts_with = "sync_leader")]
+    sync_follow: Option<String>,
+
     /// Audio input device name
     #[arg(long, value_name = "DEVICE")]
     input_device: Option<String>,
@@ -134,11 +321,96 @@ struct RunArgs {
     sample_rate: Option<u32>,
 }
 
+#[derive(Args, Debug)]
+struct PerformArgs {
+    /// Path to the .vibeset live set
+    #[arg(value_name = "SET")]
+    set: PathBuf,
+
+    /// Disable watch mode for the composition (watching is enabled by default)
+    #[arg(long)]
+    no_watch: bool,
+
+    /// Enable TUI mode (required for key bindings)
+    #[arg(long)]
+    tui: bool,
+
+    /// Additional import directories
+    #[arg(short = 'I', long = "import-path", value_name = "PATH")]
+    import_paths: Vec<PathBuf>,
+
+    /// Run with only the HTTP REST API server, without a file
+    #[arg(long, conflicts_with = "no_server")]
+    api: bool,
+
+    /// Don't start the HTTP REST API server
+    #[arg(long)]
+    no_server: bool,
+
+    /// HTTP API server port; the next free port is used if it is busy
+    #[arg(long, value_name = "PORT", default_value_t = vibelang_http::DEFAULT_PORT)]
+    api_port: u16,
+
+    /// Address to serve the HTTP API on. Defaults to 127.0.0.1 (this machine
+    /// only; earlier versions listened on 0.0.0.0). Use 0.0.0.0 to expose the
+    /// API on the LAN (sessions with --sandbox are advertised via zeroconf)
+    #[arg(long, value_name = "HOST", default_value_t = vibelang_http::DEFAULT_HOST)]
+    api_host: IpAddr,
+
+    /// Append all API mutations to this JSONL file (view with `vibe history`)
+    #[arg(long, value_name = "PATH")]
+    history_file: Option<PathBuf>,
+
+    /// Sandbox profile for `/eval` requests ("workshop" or "trusted")
+    #[arg(long, value_name = "PROFILE")]
+    sandbox: Option<String>,
+
+    /// Sandbox profile for an `/eval` bearer token (repeatable)
+    #[arg(long = "eval-token", value_name = "TOKEN=PROFILE")]
+    eval_tokens: Vec<String>,
+
+    /// Let trusted `/eval` requests register hooks, which run shell commands
+    #[arg(long)]
+    allow_hooks: bool,
+
+    /// Cancel a script evaluation after this many seconds (0 = never)
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    max_eval_time: f64,
+
+    /// Cancel a script evaluation after this many operations (0 = never)
+    #[arg(long, value_name = "N", default_value = "0")]
+    max_operations: u64,
+
+    /// On reload, only evaluate top-level statements that changed (and their dependents)
+    #[arg(long)]
+    incremental: bool,
+
+    /// Evaluate the file only up to `checkpoint("NAME")`
+    #[arg(long, value_name = "NAME")]
+    until: Option<String>,
+
+    /// Restore tempo, position, playing sequences and the mixer from the last session snapshot
+    #[arg(long)]
+    resume: bool,
+
+    /// Write a session snapshot every SECS seconds for --resume (0 = only on shutdown)
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    snapshot_interval: f64,
+
+    /// Lead a network transport sync: followers play in time with this session
+    #[arg(long, value_name = "PORT", default_missing_value = "57140", num_args = 0..=1)]
+    sync_leader: Option<u16>,
+
+    /// Follow the network sync leader at HOST[:PORT]
+    #[arg(long, value_name = "HOST[:PORT]", conflicts_with = "sync_leader")]
+    sync_follow: Option<String>,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct RenderArgs {
-    /// Path to the score file (.osc) to render
-    #[arg(value_name = "SCORE_FILE")]
-    pub score_file: PathBuf,
+    /// Path to the .vibe script or recorded .vibescore file to render
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
 
     /// Output audio file path
     #[arg(value_name = "OUTPUT")]
@@ -159,6 +431,195 @@ pub struct RenderArgs {
     /// Add tail time at the end (seconds)
     #[arg(long, default_value = "2.0")]
     pub tail: f64,
+
+    /// Number of bars to render (.vibe scripts)
+    #[arg(long, default_value_t = 16)]
+    pub bars: u32,
+
+    /// Additional import directories (.vibe scripts)
+    #[arg(short = 'I', long = "import-path", value_name = "PATH")]
+    pub import_paths: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct HistoryArgs {
+    /// Path to the history file (.jsonl) written with --history-file
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Only show entries at or after this bar
+    #[arg(long, value_name = "BAR")]
+    pub since_bar: Option<i64>,
+
+    /// Only show entries at or before this bar
+    #[arg(long, value_name = "BAR")]
+    pub until_bar: Option<i64>,
+
+    /// Only show entries whose path contains this string
+    #[arg(long, value_name = "SUBSTRING")]
+    pub path: Option<String>,
+
+    /// Show at most this many (most recent) entries
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct WarmupArgs {
+    /// Path to the .vibe file to warm up
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Additional import directories
+    #[arg(short = 'I', long = "import-path", value_name = "PATH")]
+    pub import_paths: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SimulateArgs {
+    /// Path to the .vibe file to simulate
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Number of bars to simulate
+    #[arg(long, default_value_t = 16)]
+    pub bars: u32,
+
+    /// Write the event log to this file (CSV for .csv, JSON otherwise) instead of printing events
+    #[arg(long, value_name = "PATH")]
+    pub dump: Option<PathBuf>,
+
+    /// Additional import directories
+    #[arg(short = 'I', long = "import-path", value_name = "PATH")]
+    pub import_paths: Vec<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StressArgs {
+    /// Number of voices playing
+    #[arg(long, default_value_t = 64)]
+    pub voices: u32,
+
+    /// Events fired per beat, spread over the voices
+    #[arg(long, default_value_t = 200)]
+    pub events_per_beat: u32,
+
+    /// How long to run (fractions allowed, e.g. 0.25 for 15 minutes)
+    #[arg(long, default_value_t = 1.0)]
+    pub hours: f64,
+
+    /// Tempo of the load
+    #[arg(long, default_value_t = 120.0)]
+    pub bpm: f64,
+
+    /// Address of the running scsynth to load (its nodes are freed first)
+    #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:57110")]
+    pub server: String,
+
+    /// Load an in-process mock server instead of scsynth
+    #[arg(long, conflicts_with = "server")]
+    pub mock: bool,
+
+    /// Seconds between progress lines
+    #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+    pub report_interval: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct OscDumpArgs {
+    /// Path to the capture file
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Only show messages whose address starts with this (repeatable)
+    #[arg(long, value_name = "ADDRESS")]
+    pub filter: Vec<String>,
+
+    /// Only show packets travelling this way ("out" to scsynth or "in" from it)
+    #[arg(long, value_name = "DIR")]
+    pub direction: Option<String>,
+
+    /// Only show packets at or after this beat
+    #[arg(long, value_name = "BEAT")]
+    pub since_beat: Option<f64>,
+
+    /// Only show packets at or before this beat
+    #[arg(long, value_name = "BEAT")]
+    pub until_beat: Option<f64>,
+
+    /// Show at most this many (most recent) messages
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum StdlibCommand {
+    /// List definitions matching all search terms (name, category, genre, description)
+    Search(StdlibSearchArgs),
+
+    /// Show a definition's parameters and import statement
+    Show {
+        /// Synthdef name
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StdlibSearchArgs {
+    /// Search terms (all definitions when empty)
+    #[arg(value_name = "TERMS")]
+    pub terms: Vec<String>,
+
+    /// Only show definitions of this category (e.g. drums, effects)
+    #[arg(long, value_name = "CATEGORY")]
+    pub category: Option<String>,
+
+    /// Only show effects
+    #[arg(long)]
+    pub fx: bool,
+
+    /// Show at most this many results
+    #[arg(long, value_name = "N", default_value = "30")]
+    pub limit: usize,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum SessionCommand {
+    /// Write the full session (definitions, mixer, what is playing) to a file
+    Save(SessionArgs),
+
+    /// Replace the session with the one in a file
+    Load(SessionArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SessionArgs {
+    /// Path of the session file
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// URL of the session's HTTP API
+    #[arg(long, value_name = "URL", default_value_t = format!("http://127.0.0.1:{}", vibelang_http::DEFAULT_PORT))]
+    pub url: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct MirrorArgs {
+    /// WebSocket URL of the session (its HTTP API server)
+    #[arg(value_name = "URL")]
+    pub url: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ListSessionsArgs {
+    /// How long to listen for sessions, in seconds
+    #[arg(long, value_name = "SECONDS", default_value = "2")]
+    pub timeout: f64,
+
+    /// Print the sessions as JSON (for editor extensions and scripts)
+    #[arg(long)]
+    pub json: bool,
 }
 
 fn main() -> Result<()> {
@@ -183,11 +644,46 @@ fn main() -> Result<()> {
                 .with_input_channels(args.input_channels)
                 .with_output_channels(args.output_channels)
                 .with_sample_rate(args.sample_rate);
-            run_vibe_file(args.file, watch, args.tui, args.import_paths, args.record, args.exit_after_sequence, args.api, args.api_port, audio_config)
+            run_vibe_file(args.file, watch, args.tui, args.import_paths, args.record, args.exit_after_sequence, !args.no_server, SocketAddr::new(args.api_host, args.api_port), args.history_file, audio_config, None, sandbox::EvalSandbox::new(args.sandbox.as_deref(), &args.eval_tokens, args.allow_hooks)?, eval_limits(args.max_eval_time, args.max_operations), args.incremental, args.until, resume::SessionOptions::new(args.resume, args.snapshot_interval), net_sync_role(args.sync_leader, args.sync_follow))
+        }
+        Some(Commands::Perform(args)) => {
+            if args.set.extension().and_then(|s| s.to_str()) != Some(LIVE_SET_EXTENSION) {
+                eprintln!("warning: live set doesn't have .{} extension", LIVE_SET_EXTENSION);
+            }
+            let live_set = LiveSet::load(&args.set)?;
+            let watch = !args.no_watch;
+            run_vibe_file(Some(live_set.composition.clone()), watch, args.tui, args.import_paths, None, None, !args.no_server, SocketAddr::new(args.api_host, args.api_port), args.history_file, AudioConfig::default(), Some((args.set, live_set)), sandbox::EvalSandbox::new(args.sandbox.as_deref(), &args.eval_tokens, args.allow_hooks)?, eval_limits(args.max_eval_time, args.max_operations), args.incremental, args.until, resume::SessionOptions::new(args.resume, args.snapshot_interval), net_sync_role(args.sync_leader, args.sync_follow))
         }
         Some(Commands::Render(args)) => {
             render::render(args)
         }
+        Some(Commands::Simulate(args)) => {
+            simulate::simulate(args)
+        }
+        Some(Commands::History(args)) => {
+            history::show_history(args)
+        }
+        Some(Commands::Warmup(args)) => {
+            warmup::warmup(args)
+        }
+        Some(Commands::Mirror(args)) => {
+            mirror::mirror(args)
+        }
+        Some(Commands::ListSessions(args)) => {
+            sessions::list_sessions(args)
+        }
+        Some(Commands::OscDump(args)) => {
+            osc_dump::show_capture(args)
+        }
+        Some(Commands::Stress(args)) => {
+            stress::stress(args)
+        }
+        Some(Commands::Stdlib(command)) => {
+            stdlib::stdlib(command)
+        }
+        Some(Commands::Session(command)) => {
+            session_file::session(command)
+        }
         Some(Commands::Lsp) => {
             // Run the LSP server
             let rt = tokio::runtime::Runtime::new()?;
@@ -201,15 +697,19 @@ fn main() -> Result<()> {
             // No subcommand - check if a file was provided directly or if --api is enabled
             if cli.file.is_some() || cli.api {
                 let watch = !cli.no_watch;
-                run_vibe_file(cli.file, watch, cli.tui, cli.import_paths, None, None, cli.api, cli.api_port, AudioConfig::default())
+                run_vibe_file(cli.file, watch, cli.tui, cli.import_paths, None, None, !cli.no_server, SocketAddr::new(cli.api_host, cli.api_port), cli.history_file, AudioConfig::default(), None, sandbox::EvalSandbox::new(cli.sandbox.as_deref(), &cli.eval_tokens, cli.allow_hooks)?, eval_limits(cli.max_eval_time, cli.max_operations), cli.incremental, cli.until, resume::SessionOptions::new(cli.resume, cli.snapshot_interval), net_sync_role(cli.sync_leader, cli.sync_follow))
             } else {
                 anyhow::bail!(
                     "Missing required argument: FILE\n\n\
                     Usage: vibe <FILE> [OPTIONS]\n\
                            vibe run <FILE> [OPTIONS]\n\
                            vibe --api               (API-only mode, no file needed)\n\
+                           vibe perform <SET>       (perform with a .vibeset live set)\n\
                            vibe devices             (list available audio devices)\n\
-                           vibe render <SCORE_FILE> [OPTIONS]\n\n\
+                           vibe render <SCORE_FILE> [OPTIONS]\n\
+                           vibe history <FILE>      (view recorded API history)\n\
+                           vibe warmup <FILE>       (write a preload manifest)\n\
+                           vibe stdlib search <TERMS> (search the standard library)\n\n\
                     For more information, try '--help'"
                 )
             }
@@ -217,6 +717,108 @@ fn main() -> Result<()> {
     }
 }
 
+/// Run a compiled script, or only its changed statements with `--incremental`.
+///
+/// Also publishes the script's checkpoints.
+fn run_script(
+    engine: &rhai::Engine,
+    incremental: Option<&mut IncrementalScript>,
+    script: &str,
+    ast: &AST,
+) -> std::result::Result<(), Box<rhai::EvalAltResult>> {
+    let handle = vibelang_core::get_handle();
+    if let Some(h) = handle.as_ref() {
+        let _ = h.send(StateMessage::SetCheckpoints { names: checkpoint::find_checkpoints(script) });
+    }
+
+    let result = match incremental {
+        // Skipped statements can't stop at a checkpoint, so evaluate everything
+        Some(incremental) if handle.as_ref().is_some_and(|h| h.with_state(|s| s.checkpoints.selected.is_some())) => {
+            incremental.reset();
+            incremental.run(engine, script).map(|_| ())
+        }
+        Some(incremental) => incremental.run(engine, script).map(|stats| {
+            if !stats.full {
+                log::info!(
+                    "   ↻ Incremental: {} statement(s) evaluated, {} replayed",
+                    stats.evaluated, stats.replayed
+                );
+            }
+        }),
+        None => engine.run_ast(ast),
+    };
+
+    // Stopping at the selected checkpoint is not an error
+    match result {
+        Err(e) if checkpoint::reached(&e).is_some() => Ok(()),
+        result => result,
+    }
+}
+
+/// Dry-run a changed script against a no-op backend before reloading it.
+///
+/// A script that fails partway would leave the session half-updated, so the
+/// reload is skipped when validation finds errors; they are logged, which
+/// also raises them in the TUI. Unknown synthdefs only warn, since the
+/// session may have loaded them some other way.
+fn validate_before_reload(file: &Path, script: &str, import_paths: &[PathBuf], eval_limits: &EvalLimits) -> bool {
+    let result = vibelang_core::validate_reload(script, Some(file), import_paths, eval_limits);
+
+    for reference in &result.undefined_synthdefs {
+        log::warn!(
+            "   ⚠ line {}: voice '{}' uses unknown synthdef '{}'",
+            reference.line, reference.voice_name, reference.name
+        );
+    }
+
+    let errors = result.all_errors();
+    if errors.is_empty() {
+        return true;
+    }
+    // The last error logged is the one the TUI shows
+    log::error!("   Reload skipped: {} error(s), the session keeps playing the previous version", errors.len());
+    for error in &errors {
+        match error.line {
+            Some(line) => log::error!("   line {}: {}", line, error.message),
+            None => log::error!("   {}", error.message),
+        }
+    }
+    false
+}
+
+/// Whether a checkpoint was selected and the script must be re-evaluated.
+fn checkpoint_reload_requested() -> bool {
+    vibelang_core::get_handle().is_some_and(|h| {
+        h.with_state_mut(|state| std::mem::take(&mut state.checkpoints.reload_requested))
+    })
+}
+
+/// Register SIGHUP; the returned flag is set when the script should be reloaded
+/// (e.g. `systemctl reload`), whether or not watch mode is enabled.
+fn register_reload_signal() -> Arc<AtomicBool> {
+    let hangup = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&hangup))
+        .expect("Failed to register signal handler");
+    hangup
+}
+
+/// Network sync role from `--sync-leader` / `--sync-follow` (clap rejects both).
+fn net_sync_role(leader: Option<u16>, follow: Option<String>) -> Option<NetSyncRole> {
+    match (leader, follow) {
+        (Some(port), _) => Some(NetSyncRole::Leader { port }),
+        (None, Some(leader)) => Some(NetSyncRole::Follower { leader }),
+        (None, None) => None,
+    }
+}
+
+/// Build the eval watchdog limits from the CLI arguments (0 = unlimited).
+fn eval_limits(max_eval_time: f64, max_operations: u64) -> EvalLimits {
+    EvalLimits {
+        max_operations,
+        max_eval_time: (max_eval_time > 0.0).then(|| std::time::Duration::from_secs_f64(max_eval_time)),
+    }
+}
+
 fn run_vibe_file(
     file: Option<PathBuf>,
     watch: bool,
@@ -225,8 +827,16 @@ fn run_vibe_file(
     record: Option<PathBuf>,
     exit_after_sequence: Option<String>,
     api_enabled: bool,
-    api_port: u16,
+    api_addr: SocketAddr,
+    history_file: Option<PathBuf>,
     audio_config: AudioConfig,
+    live_set: Option<(PathBuf, LiveSet)>,
+    eval_sandbox: sandbox::EvalSandbox,
+    eval_limits: EvalLimits,
+    incremental: bool,
+    until: Option<String>,
+    session: resume::SessionOptions,
+    net_sync: Option<NetSyncRole>,
 ) -> Result<()> {
     use vibelang_core::JackMidiOutput;
 
@@ -318,6 +928,14 @@ fn run_vibe_file(
     vibelang_core::api::group::create_main_group();
     log::info!("   ✓ Main group created");
 
+    // 5. Preload what `vibe warmup` recorded, so the first bar doesn't wait on loads
+    if let Some(ref f) = file {
+        log::info!("5. Preloading assets...");
+        if let Err(e) = warmup::preload(handle, f, tui_mode) {
+            log::warn!("Preload failed: {:#}", e);
+        }
+    }
+
     // 6. Create Rhai engine
     log::info!("6. Initializing Rhai engine...");
     let base_path = file
@@ -337,10 +955,13 @@ fn run_vibe_file(
     vibelang_core::api::context::set_script_dir(base_path.clone());
     vibelang_core::api::context::set_import_paths(all_import_paths.clone());
 
-    let mut engine = vibelang_core::create_engine_with_paths(base_path, all_import_paths);
+    let mut engine = vibelang_core::create_engine_with_paths(base_path, all_import_paths.clone());
 
     // Register DSP functions (UGens, NodeRef, etc.)
     vibelang_dsp::register_dsp_api(&mut engine);
+
+    // Cancel runaway evaluations (e.g. `while true {}`) instead of hanging
+    watchdog::install(&mut engine, &eval_limits);
     log::info!("   ✓ Engine ready");
 
     // Enable score capture BEFORE script runs if --record flag is set
@@ -377,10 +998,26 @@ fn run_vibe_file(
     vibelang_core::api::clear_callbacks();
     vibelang_core::api::clear_midi_devices();
 
+    // Reloads evaluate only changed statements with --incremental
+    let mut incremental = incremental.then(IncrementalScript::new);
+
+    // Pick the set up where the last session stopped with --resume
+    let resume_snapshot = file
+        .as_deref()
+        .filter(|_| session.resume)
+        .and_then(resume::load_snapshot);
+
+    // Stop evaluation at a checkpoint with --until (or the one the session had selected)
+    let until = until.or_else(|| resume_snapshot.as_ref().and_then(|s| s.checkpoint.clone()));
+    if until.is_some() {
+        handle.with_state_mut(|state| state.checkpoints.selected = until);
+    }
+
     // 7. Read and compile the script (if a file was provided)
+    let mut script = String::new();
     let mut current_ast: Option<AST> = if let Some(ref f) = file {
         log::info!("7. Compiling .vibe file...");
-        let script = fs::read_to_string(f)
+        script = fs::read_to_string(f)
             .with_context(|| format!("Failed to read file: {}", f.display()))?;
 
         // Compile the script to AST (we need the AST for callback execution)
@@ -407,47 +1044,139 @@ fn run_vibe_file(
             context::set_current_script_file(Some(abs_path.to_string_lossy().to_string()));
         }
 
-        match engine.run_ast(ast) {
+        match run_script(&engine, incremental.as_mut(), &script, ast) {
             Ok(_) => {
                 log::info!("   ✓ Script executed successfully");
             }
             Err(e) => {
-                log::error!("Script error: {}", e);
+                log::error!("Script error: {}", watchdog::explain(&e));
                 // Continue running to allow sounds to play
             }
         }
     }
 
+    // Restore position, playing loops and the mixer before the transport starts
+    if let Some(snapshot) = resume_snapshot {
+        handle.send(StateMessage::RestoreSession { snapshot })?;
+        log::info!("   ✓ Session restored");
+    }
+
     // Start the scheduler AFTER script evaluation
     // This ensures sequences started during initial evaluation anchor at beat 0.0
     // (before this, transport.beat_at() returns 0.0, so quantization gives beat 0.0)
     handle.send(StateMessage::StartScheduler)?;
     log::info!("   ✓ Scheduler started");
 
+    // Play in time with other machines
+    if let Some(role) = net_sync {
+        vibelang_core::netsync::start(handle.clone(), role)?;
+    }
+
     // Finalize groups
     handle.send(StateMessage::FinalizeGroups)?;
     std::thread::sleep(std::time::Duration::from_millis(200));
 
+    // Load the live set once the composition has defined its groups and sequences
+    if let Some((path, set)) = live_set {
+        let has_key_bindings = set.bindings.iter().any(|b| matches!(b.trigger, BindingTrigger::Key(_)));
+        if has_key_bindings && !tui_mode {
+            log::warn!("Live set key bindings are only active in the TUI (--tui)");
+        }
+        handle.send(StateMessage::LoadLiveSet { path, set })?;
+        log::info!("   ✓ Live set loaded");
+    }
+
+    // Keep a recent snapshot on disk so a crash can be resumed with --resume
+    if let (Some(f), Some(interval)) = (&file, session.snapshot_interval) {
+        resume::spawn_snapshot_thread(handle.clone(), f.clone(), interval);
+    }
+
     // Create eval channel for the HTTP server to send code evaluation requests
     let (eval_tx, eval_rx) = std::sync::mpsc::channel::<vibelang_http::EvalJob>();
 
-    // Start HTTP API server if enabled
-    if api_enabled {
+    // Start HTTP API server unless disabled, on the next free port if the requested one is busy
+    // The advertisement lives as long as the session, so companion apps can find it
+    let (api_port, _advertisement) = if api_enabled {
+        let listener = vibelang_http::bind_available_port(api_addr.ip(), api_addr.port()).with_context(|| {
+            format!(
+                "No free port for the HTTP API on {} from {} on (use --api-port or --no-server)",
+                api_addr.ip(),
+                api_addr.port()
+            )
+        })?;
+        let port = listener.local_addr()?.port();
         let api_handle = handle.clone();
         let eval_sender = eval_tx.clone();
         std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+            let rt = match tokio::runtime::Runtime::new() {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::error!("Failed to create Tokio runtime for the HTTP API: {}", e);
+                    return;
+                }
+            };
             rt.block_on(async {
-                vibelang_http::start_server(api_handle, api_port, Some(eval_sender)).await;
+                if let Err(e) = vibelang_http::serve_std(listener, api_handle, Some(eval_sender), history_file).await {
+                    log::error!("HTTP API server stopped: {}", e);
+                }
             });
         });
-        log::info!("   ✓ HTTP API server started on port {}", api_port);
-    }
+        if port != api_addr.port() {
+            log::info!("   ✓ Port {} is busy, using {}", api_addr.port(), port);
+        }
+        let exposed = !api_addr.ip().is_loopback();
+        let url_host = if exposed && !api_addr.ip().is_unspecified() {
+            api_addr.ip().to_string()
+        } else {
+            "localhost".to_string()
+        };
+        if tui_mode {
+            log::info!("   ✓ HTTP API server running at http://{}:{}", url_host, port);
+        } else {
+            println!("🌐 HTTP API: http://{}:{}\n", url_host, port);
+        }
+        if exposed {
+            log::warn!("   ⚠ HTTP API is reachable from other machines on {}", api_addr.ip());
+            if eval_sandbox.default_profile().is_none() {
+                log::warn!("   ⚠ /eval runs any script it is sent; use --sandbox workshop or --eval-token to restrict it");
+            }
+        }
+        if let Some(profile) = eval_sandbox.default_profile() {
+            log::info!("   ✓ /eval sandboxed with the '{}' profile", profile);
+        }
+        let name = file
+            .as_ref()
+            .and_then(|f| f.file_stem())
+            .map_or_else(|| "vibelang".to_string(), |stem| stem.to_string_lossy().into_owned());
+        let bound = SocketAddr::new(api_addr.ip(), port);
+        let advertisement = match eval_sandbox.default_profile() {
+            Some(sandbox) if vibelang_http::discovery::should_advertise(bound, Some(sandbox)) => {
+                match vibelang_http::discovery::advertise(&name, bound, env!("CARGO_PKG_VERSION"), sandbox) {
+                    Ok(advertisement) => {
+                        log::info!("   ✓ Advertised on the LAN as '{}'", advertisement.fullname());
+                        Some(advertisement)
+                    }
+                    Err(e) => {
+                        log::warn!("   ⚠ Could not advertise the session over zeroconf: {}", e);
+                        None
+                    }
+                }
+            }
+            None if exposed => {
+                log::info!("   ✓ Not advertised on the LAN while /eval is trusted (use --sandbox to advertise)");
+                None
+            }
+            _ => None,
+        };
+        (port, advertisement)
+    } else {
+        (api_addr.port(), None)
+    };
 
     // Keep the process running
     if tui_mode {
         // TUI mode - run the TUI event loop
-        run_tui_loop(file.as_ref(), engine, handle.clone(), watch, &import_paths, current_ast, jack_keyboard)?;
+        run_tui_loop(file.as_ref(), engine, handle.clone(), watch, &all_import_paths, &eval_limits, current_ast, incremental, jack_keyboard)?;
     } else {
         // Set up signal handlers for graceful shutdown (SIGINT and SIGTERM)
         let shutdown = Arc::new(AtomicBool::new(false));
@@ -457,6 +1186,7 @@ fn run_vibe_file(
             signal_hook::flag::register(sig, Arc::clone(&shutdown))
                 .expect("Failed to register signal handler");
         }
+        let hangup = register_reload_signal();
 
         // Log status message
         if let Some(ref seq_name) = exit_after_sequence {
@@ -464,7 +1194,7 @@ fn run_vibe_file(
         } else if watch && file.is_some() {
             log::info!("\n8. Watch mode enabled - monitoring file for changes...");
             log::info!("   (Press Ctrl+C to exit)\n");
-        } else if api_enabled {
+        } else if file.is_none() {
             log::info!("\n8. API server running on http://localhost:{}", api_port);
             log::info!("   (Press Ctrl+C to exit)\n");
         } else {
@@ -475,6 +1205,7 @@ fn run_vibe_file(
         let mut last_modified = file.as_ref()
             .and_then(|f| fs::metadata(f).ok())
             .and_then(|m| m.modified().ok());
+        let mut synthdef_watcher = SynthDefDirWatcher::default();
 
         // Create a scope for callback execution
         let mut callback_scope = rhai::Scope::new();
@@ -496,12 +1227,14 @@ fn run_vibe_file(
                     if let Some(ref output_path) = render_output_path {
                         log::info!("🎬 Rendering audio...");
                         let render_args = crate::RenderArgs {
-                            score_file: score_capture_path.clone(),
+                            file: score_capture_path.clone(),
                             output: output_path.clone(),
                             format: None,
                             sample_rate: 48000,
                             bit_depth: 24,
                             tail: 2.0,
+                            bars: 0,
+                            import_paths: Vec::new(),
                         };
                         if let Err(e) = crate::render::render_score(render_args) {
                             log::error!("Render failed: {}", e);
@@ -517,18 +1250,7 @@ fn run_vibe_file(
 
             // Process any pending eval requests from the HTTP server
             while let Ok(job) = eval_rx.try_recv() {
-                let result = match engine.eval::<rhai::Dynamic>(&job.code) {
-                    Ok(val) => vibelang_http::EvalResult {
-                        success: true,
-                        result: if val.is_unit() { None } else { Some(format!("{:?}", val)) },
-                        error: None,
-                    },
-                    Err(e) => vibelang_http::EvalResult {
-                        success: false,
-                        result: None,
-                        error: Some(e.to_string()),
-                    },
-                };
+                let result = eval_sandbox.eval(&engine, &job.code, job.token.as_deref());
                 let _ = job.response_tx.send(result);
             }
 
@@ -544,6 +1266,9 @@ fn run_vibe_file(
                 }
             }
 
+            // Evaluate code snippets of launched live set cues
+            vibelang_core::api::execute_pending_cue_evals(&engine);
+
             // Check for sequence completion if --exit-after-sequence was specified
             if let Some(ref seq_name) = exit_after_sequence {
                 if handle.is_sequence_completed(seq_name) {
@@ -558,12 +1283,14 @@ fn run_vibe_file(
                         if let Some(ref output_path) = render_output_path {
                             log::info!("🎬 Rendering audio...");
                             let render_args = crate::RenderArgs {
-                                score_file: score_capture_path.clone(),
+                                file: score_capture_path.clone(),
                                 output: output_path.clone(),
                                 format: None,
                                 sample_rate: 48000,
                                 bit_depth: 24,
                                 tail: 2.0,
+                                bars: 0,
+                                import_paths: Vec::new(),
                             };
                             if let Err(e) = crate::render::render_score(render_args) {
                                 log::error!("Render failed: {}", e);
@@ -578,16 +1305,42 @@ fn run_vibe_file(
                 }
             }
 
-            // Check for file changes if watch mode is enabled and a file was provided
+            // Reload synthdef directories whose files changed
             if watch {
+                synthdef_watcher.poll(handle);
+            }
+
+            // Check for file changes if watch mode is enabled and a file was provided,
+            // and re-evaluate when a different checkpoint was selected
+            let checkpoint_requested = checkpoint_reload_requested();
+            let hangup_requested = hangup.swap(false, Ordering::Relaxed);
+            if watch || checkpoint_requested || hangup_requested {
                 if let Some(ref f) = file {
                     let current_modified = fs::metadata(f)
                         .ok()
                         .and_then(|m| m.modified().ok());
 
-                    if current_modified != last_modified {
+                    if current_modified != last_modified || checkpoint_requested || hangup_requested {
                         last_modified = current_modified;
-                        log::info!("\n🔄 File changed, reloading...");
+                        if hangup_requested {
+                            log::info!("\n🔄 SIGHUP received, reloading...");
+                        } else if checkpoint_requested {
+                            log::info!("\n🔄 Checkpoint selected, reloading...");
+                        } else {
+                            log::info!("\n🔄 File changed, reloading...");
+                        }
+
+                        // Re-read and validate before touching the session
+                        let new_script = match fs::read_to_string(f) {
+                            Ok(new_script) => new_script,
+                            Err(e) => {
+                                log::error!("   Failed to read file: {}", e);
+                                continue;
+                            }
+                        };
+                        if !validate_before_reload(f, &new_script, &all_import_paths, &eval_limits) {
+                            continue;
+                        }
 
                         // Signal reload
                         if let Some(h) = vibelang_core::get_handle() {
@@ -598,33 +1351,26 @@ fn run_vibe_file(
                         vibelang_core::api::clear_callbacks();
                         vibelang_core::api::clear_midi_devices();
 
-                        // Re-read, compile, and execute
-                        match fs::read_to_string(f) {
-                            Ok(new_script) => {
-                                match engine.compile(&new_script) {
-                                    Ok(ast) => {
-                                        // Set the current script file for source location tracking
-                                        let abs_path = f.canonicalize().unwrap_or_else(|_| f.clone());
-                                        context::set_current_script_file(Some(abs_path.to_string_lossy().to_string()));
-
-                                        match engine.run_ast(&ast) {
-                                            Ok(_) => {
-                                                log::info!("   ✓ Reload successful");
-                                                // Update the current AST for callback execution
-                                                current_ast = Some(ast);
-                                            }
-                                            Err(e) => {
-                                                log::error!("   Reload failed: {}", e);
-                                            }
-                                        }
+                        // Compile and execute
+                        match engine.compile(&new_script) {
+                            Ok(ast) => {
+                                // Set the current script file for source location tracking
+                                let abs_path = f.canonicalize().unwrap_or_else(|_| f.clone());
+                                context::set_current_script_file(Some(abs_path.to_string_lossy().to_string()));
+
+                                match run_script(&engine, incremental.as_mut(), &new_script, &ast) {
+                                    Ok(_) => {
+                                        log::info!("   ✓ Reload successful");
+                                        // Update the current AST for callback execution
+                                        current_ast = Some(ast);
                                     }
                                     Err(e) => {
-                                        log::error!("   Compile failed: {}", e);
+                                        log::error!("   Reload failed: {}", watchdog::explain(&e));
                                     }
                                 }
                             }
                             Err(e) => {
-                                log::error!("   Failed to read file: {}", e);
+                                log::error!("   Compile failed: {}", e);
                             }
                         }
 
@@ -638,9 +1384,17 @@ fn run_vibe_file(
         }
     }
 
-    // Explicitly shutdown the runtime to ensure scsynth is killed
-    // This triggers Runtime::drop() which kills the scsynth child process
-    log::info!("🔌 Shutting down runtime...");
+    // Write the final session snapshot so `--resume` can restore the set
+    if let Some(ref f) = file {
+        match handle.with_state(SessionSnapshot::capture).save(f) {
+            Ok(path) => log::info!("💾 Session snapshot written to {}", path.display()),
+            Err(e) => log::warn!("Failed to write session snapshot: {:#}", e),
+        }
+    }
+
+    // Explicitly shutdown the runtime: Runtime::drop() fades out, frees all
+    // nodes and buffers and stops the scsynth child process
+    log::info!("🔌 Shutting down runtime (fading out)...");
     drop(runtime);
     log::info!("   ✓ Runtime shutdown complete");
 
@@ -648,13 +1402,16 @@ fn run_vibe_file(
 }
 
 /// Run the TUI event loop
+#[allow(clippy::too_many_arguments)]
 fn run_tui_loop(
     vibe_file: Option<&PathBuf>,
     engine: rhai::Engine,
     handle: RuntimeHandle,
     watch: bool,
-    _import_paths: &[PathBuf],
+    import_paths: &[PathBuf],
+    eval_limits: &EvalLimits,
     initial_ast: Option<AST>,
+    mut incremental: Option<IncrementalScript>,
     jack_keyboard: Option<vibelang_core::JackMidiOutput>,
 ) -> Result<()> {
     // Shutdown signal shared between threads
@@ -667,10 +1424,16 @@ fn run_tui_loop(
     // Spawn TUI rendering thread (pass in the pre-created JACK output)
     let tui_thread = std::thread::spawn(move || run_tui_render_thread(shutdown_clone, tui_handle, jack_keyboard));
 
+    // SIGTERM shuts down gracefully like quitting from the TUI
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&shutdown))
+        .expect("Failed to register signal handler");
+    let hangup = register_reload_signal();
+
     // Main thread handles file watching, reloading, and callback execution
     let mut last_modified = vibe_file
         .and_then(|f| fs::metadata(f).ok())
         .and_then(|m| m.modified().ok());
+    let mut synthdef_watcher = SynthDefDirWatcher::default();
 
     // Track current AST for callback execution
     let mut current_ast = initial_ast;
@@ -694,56 +1457,76 @@ fn run_tui_loop(
             }
         }
 
-        // Check for file changes if watch mode is enabled and file provided
+        // Evaluate code snippets of launched live set cues
+        vibelang_core::api::execute_pending_cue_evals(&engine);
+
+        // Reload synthdef directories whose files changed
         if watch {
+            synthdef_watcher.poll(&handle);
+        }
+
+        // Check for file changes if watch mode is enabled and file provided,
+        // and re-evaluate when a different checkpoint was selected
+        let checkpoint_requested = checkpoint_reload_requested();
+        let hangup_requested = hangup.swap(false, Ordering::Relaxed);
+        if watch || checkpoint_requested || hangup_requested {
             if let Some(vibe_file) = vibe_file {
                 let current_modified = fs::metadata(vibe_file)
                     .ok()
                     .and_then(|m| m.modified().ok());
 
-                if current_modified != last_modified {
+                if current_modified != last_modified || checkpoint_requested || hangup_requested {
                     last_modified = current_modified;
-                    log::info!("🔄 File changed, reloading...");
-
-                    // Signal reload
-                    let _ = handle.send(StateMessage::BeginReload);
-
-                    // Clear existing callbacks and MIDI devices before reload
-                    vibelang_core::api::clear_callbacks();
-                    vibelang_core::api::clear_midi_devices();
-
-                    // Re-read, compile, and execute
-                    match fs::read_to_string(vibe_file) {
-                        Ok(new_script) => {
-                            match engine.compile(&new_script) {
-                                Ok(ast) => {
-                                    // Set the current script file for source location tracking
-                                    let abs_path = vibe_file.canonicalize().unwrap_or_else(|_| vibe_file.to_path_buf());
-                                    context::set_current_script_file(Some(abs_path.to_string_lossy().to_string()));
-
-                                    match engine.run_ast(&ast) {
-                                        Ok(_) => {
-                                            log::info!("✅ Reload successful");
-                                            // Update the current AST for callback execution
-                                            current_ast = Some(ast);
-                                        }
-                                        Err(e) => {
-                                            log::error!("Reload failed: {}", e);
-                                        }
+                    if hangup_requested {
+                        log::info!("🔄 SIGHUP received, reloading...");
+                    } else if checkpoint_requested {
+                        log::info!("🔄 Checkpoint selected, reloading...");
+                    } else {
+                        log::info!("🔄 File changed, reloading...");
+                    }
+
+                    // Re-read and validate before touching the session
+                    let new_script = fs::read_to_string(vibe_file)
+                        .map_err(|e| log::error!("Failed to read file: {}", e))
+                        .ok()
+                        .filter(|script| validate_before_reload(vibe_file, script, import_paths, eval_limits));
+
+                    if let Some(new_script) = new_script {
+                        // Signal reload
+                        let _ = handle.send(StateMessage::BeginReload);
+
+                        // Clear existing callbacks and MIDI devices before reload
+                        vibelang_core::api::clear_callbacks();
+                        vibelang_core::api::clear_midi_devices();
+
+                        // Compile and execute
+                        match engine.compile(&new_script) {
+                            Ok(ast) => {
+                                // Set the current script file for source location tracking
+                                let abs_path = vibe_file.canonicalize().unwrap_or_else(|_| vibe_file.to_path_buf());
+                                context::set_current_script_file(Some(abs_path.to_string_lossy().to_string()));
+
+                                match run_script(&engine, incremental.as_mut(), &new_script, &ast) {
+                                    Ok(_) => {
+                                        log::info!("✅ Reload successful");
+                                        // Dismiss the error of an earlier, broken version
+                                        tui::clear_error();
+                                        // Update the current AST for callback execution
+                                        current_ast = Some(ast);
+                                    }
+                                    Err(e) => {
+                                        log::error!("Reload failed: {}", watchdog::explain(&e));
+                                    }
                                 }
                             }
                             Err(e) => {
                                 log::error!("Compile failed: {}", e);
                             }
                         }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to read file: {}", e);
-                    }
-                }
 
-                // Finalize groups after reload
-                let _ = handle.send(StateMessage::FinalizeGroups);
+                        // Finalize groups after reload
+                        let _ = handle.send(StateMessage::FinalizeGroups);
+                    }
                 }
             }
         }
@@ -844,6 +1627,11 @@ fn run_tui_render_thread(
 
     // Main TUI render loop
     let result = loop {
+        // Stop when the main thread saw SIGTERM (Ctrl+C arrives as a key in raw mode)
+        if shutdown.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+
         // Process TUI log events
         while let Ok(tui_event) = tui_receiver.try_recv() {
             app.process_event(tui_event);
@@ -862,7 +1650,7 @@ fn run_tui_render_thread(
                 // Get current beat and compute new position
                 let current_beat = handle.with_state(|s| s.current_beat);
                 let new_beat = (current_beat + seek_offset).max(0.0);
-                let _ = handle.send(StateMessage::SeekTransport { beat: new_beat });
+                let _ = handle.send(StateMessage::SeekTransport { position: Beats(new_beat).into() });
             }
         }
 
@@ -1036,9 +1824,108 @@ fn run_tui_render_thread(
                             }
                             _ => {}
                         }
-                    } else if app.keyboard_active() && jack_output.is_some() {
+                    } else if app.pads.visible {
+                        // Trigger pads panel mode
+                        match key.code {
+                            // Close panel
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => {
+                                app.pads.visible = false;
+                            }
+                            // Toggle quantized triggering
+                            KeyCode::Tab => {
+                                app.pads_toggle_quantize();
+                            }
+                            // Page through pads
+                            KeyCode::Left | KeyCode::Char('h') => {
+                                app.pads_prev_page();
+                            }
+                            KeyCode::Right | KeyCode::Char('l') => {
+                                app.pads_next_page();
+                            }
+                            // Play/pause transport
+                            KeyCode::Char(' ') => {
+                                let is_running = handle.with_state(|s| s.transport_running);
+                                if is_running {
+                                    let _ = handle.send(StateMessage::StopScheduler);
+                                } else {
+                                    let _ = handle.send(StateMessage::StartScheduler);
+                                }
+                            }
+                            // Quit with Ctrl+C
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                shutdown.store(true, Ordering::Relaxed);
+                                break Ok(());
+                            }
+                            // Trigger pad
+                            KeyCode::Char(c) if key.kind != KeyEventKind::Release => {
+                                if let Some(voice) = app.pad_hit(c) {
+                                    let _ = handle.send(StateMessage::TriggerVoice {
+                                        name: voice,
+                                        synth_name: None,
+                                        group_path: None,
+                                        params: Vec::new(),
+                                        quantize: app.pads.quantize,
+                                    });
+                                }
+                            }
+                            _ => {}
+                        }
+                    } else if let Some(selected) = app.goto_menu {
+                        // Goto menu mode: jump to a locator on the next bar
+                        let target = match key.code {
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('g') => {
+                                app.goto_menu = None;
+                                None
+                            }
+                            KeyCode::Up | KeyCode::Char('k') => {
+                                app.goto_move(-1);
+                                None
+                            }
+                            KeyCode::Down | KeyCode::Char('j') => {
+                                app.goto_move(1);
+                                None
+                            }
+                            KeyCode::Enter if key.kind == KeyEventKind::Press => Some(selected),
+                            KeyCode::Char(c @ '1'..='9') if key.kind == KeyEventKind::Press => {
+                                Some(c as usize - '1' as usize)
+                            }
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                shutdown.store(true, Ordering::Relaxed);
+                                break Ok(());
+                            }
+                            _ => None,
+                        };
+                        if let Some(index) = target {
+                            if let Some((name, _)) = app.markers().get(index) {
+                                let _ = handle.send(StateMessage::JumpToMarker {
+                                    name: name.clone(),
+                                    quantization: None,
+                                });
+                            }
+                            app.goto_menu = None;
+                        }
+                    } else if app.show_cue_panel {
+                        // Cue panel mode
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => {
+                                app.show_cue_panel = false;
+                            }
+                            // GO: launch the standby cue
+                            KeyCode::Char(' ') if key.kind == KeyEventKind::Press => {
+                                let _ = handle.send(StateMessage::StepCue { delta: 1 });
+                            }
+                            // Back to the previous cue
+                            KeyCode::Backspace | KeyCode::Char('b') if key.kind == KeyEventKind::Press => {
+                                let _ = handle.send(StateMessage::StepCue { delta: -1 });
+                            }
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                shutdown.store(true, Ordering::Relaxed);
+                                break Ok(());
+                            }
+                            _ => {}
+                        }
+                    } else if let Some(jack) = jack_output.as_ref().filter(|_| app.keyboard_active()) {
                         // Virtual keyboard mode - intercept note keys
-                        let jack = jack_output.as_ref().unwrap();
                         let channel = app.virtual_keyboard.channel();
                         log::debug!("Keyboard mode active - processing key: code={:?}, kind={:?}", key.code, key.kind);
 
@@ -1123,6 +2010,9 @@ fn run_tui_render_thread(
                             // Log unexpected event kinds
                             log::debug!("Unexpected key event kind: {:?}", key.kind);
                         }
+                    } else if let Some(target) = app.live_set_key_target(&key) {
+                        // Live set key bindings take precedence over the default keys
+                        let _ = handle.send(target.to_message());
                     } else {
                         // Normal mode key handling
                         match key.code {
@@ -1159,6 +2049,86 @@ fn run_tui_render_thread(
                             KeyCode::Char('M') => {
                                 app.toggle_midi_export_panel();
                             }
+                            // Toggle trigger pads panel
+                            KeyCode::Char('P') => {
+                                app.toggle_pads_panel();
+                            }
+                            // Toggle live set cue panel
+                            KeyCode::Char('C') => {
+                                app.show_cue_panel = !app.show_cue_panel;
+                            }
+                            // Goto menu: jump to a song locator
+                            KeyCode::Char('g') => {
+                                app.toggle_goto_menu();
+                            }
+                            // Panic: silence all sounding notes
+                            KeyCode::Char('!') if key.kind == KeyEventKind::Press => {
+                                let _ = handle.send(StateMessage::Panic);
+                            }
+                            // Start/stop capturing OSC traffic (view with `vibe osc-dump`)
+                            KeyCode::Char('O') if key.kind == KeyEventKind::Press => {
+                                let capturing = app.state.as_ref().is_some_and(|s| s.osc_capture.is_some());
+                                let path = (!capturing).then(|| {
+                                    PathBuf::from(format!(
+                                        "vibe-osc-{}.oscdump",
+                                        chrono::Local::now().format("%Y%m%d-%H%M%S")
+                                    ))
+                                });
+                                let _ = handle.send(StateMessage::SetOscCapture { path });
+                            }
+                            // Start/stop recording live parameter tweaks as automation
+                            KeyCode::Char('A') if key.kind == KeyEventKind::Press => {
+                                let recording = app.state.as_ref().is_some_and(|s| s.automation.recording.is_some());
+                                let _ = handle.send(if recording {
+                                    StateMessage::StopAutomationRecording
+                                } else {
+                                    StateMessage::StartAutomationRecording {
+                                        bars: vibelang_core::automation::DEFAULT_AUTOMATION_BARS,
+                                    }
+                                });
+                            }
+                            // Evaluate up to the previous/next checkpoint
+                            KeyCode::Char('<') | KeyCode::Char('>') if key.kind == KeyEventKind::Press => {
+                                let delta = if key.code == KeyCode::Char('>') { 1 } else { -1 };
+                                if let Some(name) = app.state.as_ref().and_then(|s| s.checkpoints.step(delta)) {
+                                    let _ = handle.send(StateMessage::SelectCheckpoint { name });
+                                }
+                            }
+                            // Randomize the selected voice's ranged params
+                            KeyCode::Char('R') if key.kind == KeyEventKind::Press => {
+                                let voice = app.selected_voice().and_then(|name| {
+                                    let voice = app.state.as_ref()?.voices.get(&name)?;
+                                    Some((name, voice.synth_name.clone()?, voice.params.clone()))
+                                });
+                                if let Some((name, synth, params)) = voice {
+                                    let amount = vibelang_core::sound_design::DEFAULT_RANDOMIZE_AMOUNT;
+                                    let values = vibelang_core::sound_design::randomize(&synth, &params, amount, &[]);
+                                    if values.is_empty() {
+                                        log::warn!("Voice '{}' has no parameters with a declared range", name);
+                                    } else {
+                                        log::info!("Randomized {} param(s) of '{}'", values.len(), name);
+                                        let _ = handle.send(StateMessage::SetVoiceParams { name, params: values });
+                                    }
+                                }
+                            }
+                            // Open the selected entity's definition in $EDITOR
+                            KeyCode::Char('o') if key.kind == KeyEventKind::Press => {
+                                match app.selected_source_location() {
+                                    Some(location) => {
+                                        let file = location.file.unwrap_or_default();
+                                        let line = location.line.unwrap_or(1);
+                                        match std::env::var("EDITOR").ok().filter(|e| !e.trim().is_empty()) {
+                                            Some(editor) => {
+                                                if let Err(e) = open_in_editor(&mut terminal, &editor, &file, line) {
+                                                    log::error!("Could not run '{}': {}", editor, e);
+                                                }
+                                            }
+                                            None => log::info!("Defined at {}:{} (set $EDITOR to open it)", file, line),
+                                        }
+                                    }
+                                    None => log::warn!("No source location known for the selected item"),
+                                }
+                            }
                             // Filter toggle
                             KeyCode::Char('f') => {
                                 app.toggle_hide_inactive();
@@ -1263,7 +2233,7 @@ fn run_tui_render_thread(
                                     let _ = handle.send(StateMessage::SetScrubMute { muted: false });
                                 }
                                 app.cancel_pending_seek();
-                                let _ = handle.send(StateMessage::SeekTransport { beat: 0.0 });
+                                let _ = handle.send(StateMessage::SeekTransport { position: Beats(0.0).into() });
                             }
                             _ => {}
                         }
@@ -1324,6 +2294,41 @@ fn run_tui_render_thread(
     result
 }
 
+/// Suspend the TUI and open `file` at `line` with `editor` (`$EDITOR`, which
+/// may carry its own arguments), restoring the TUI once the editor exits.
+fn open_in_editor(
+    terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
+    editor: &str,
+    file: &str,
+    line: u32,
+) -> std::io::Result<()> {
+    use crossterm::{
+        event::{DisableMouseCapture, EnableMouseCapture},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    };
+
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(editor);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(format!("+{}", line))
+        .arg(file)
+        .status();
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+
+    let status = status?;
+    if !status.success() {
+        log::warn!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
 /// Extract the synthdef name from SuperCollider synthdef bytes.
 ///
 /// SuperCollider synthdef format:
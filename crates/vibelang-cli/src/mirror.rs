@@ -0,0 +1,126 @@
+//! `vibe mirror`: show another session's TUI, read-only.
+//!
+//! Connects to a running session's WebSocket (`vibe run --api`), subscribes
+//! to state snapshots and applies them to a local state that only the TUI
+//! reads. Nothing is played locally and controls have no effect on the
+//! mirrored session. Lost connections are retried until the TUI is closed.
+
+use crate::MirrorArgs;
+use anyhow::Result;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+use vibelang_core::state::StateManager;
+use vibelang_core::{RuntimeHandle, Scsynth};
+use vibelang_http::{MirrorSnapshot, SNAPSHOT_EVENT};
+
+/// Socket read timeout, bounding how late shutdown and pings are noticed.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How often the connection is checked with a ping.
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A connection that hasn't answered for this long is considered lost.
+const STALE_TIMEOUT: Duration = Duration::from_secs(6);
+
+/// Reconnect delays grow from the first to the second value.
+const RECONNECT_DELAY: (Duration, Duration) = (Duration::from_secs(1), Duration::from_secs(10));
+
+/// Mirror a remote session in the TUI until it is closed.
+pub fn mirror(args: MirrorArgs) -> Result<()> {
+    crate::tui::init_tui_logger();
+    log::info!("🪞 Mirroring {} (read-only)", args.url);
+
+    // A handle without a runtime thread: messages sent by the TUI go nowhere
+    let (message_tx, _) = crossbeam_channel::unbounded();
+    let (midi_tx, _) = crossbeam_channel::unbounded();
+    let handle = RuntimeHandle::new_validation(message_tx, StateManager::new(), Scsynth::noop(), midi_tx);
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&shutdown))?;
+
+    let client_handle = handle.clone();
+    let client_shutdown = shutdown.clone();
+    let client = std::thread::spawn(move || run_client(args.url, client_handle, client_shutdown));
+
+    let result = crate::run_tui_render_thread(shutdown.clone(), handle, None);
+    shutdown.store(true, Ordering::Relaxed);
+    let _ = client.join();
+    result
+}
+
+/// Keep a connection to the session open, reconnecting with backoff.
+fn run_client(url: String, handle: RuntimeHandle, shutdown: Arc<AtomicBool>) {
+    let mut delay = RECONNECT_DELAY.0;
+    while !shutdown.load(Ordering::Relaxed) {
+        match connect(&url) {
+            Ok(mut socket) => {
+                log::info!("🔗 Connected to {}", url);
+                delay = RECONNECT_DELAY.0;
+                let reason = follow(&mut socket, &handle, &shutdown);
+                let _ = socket.close(None);
+                if shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                log::warn!("Lost connection to {}: {}; reconnecting", url, reason);
+            }
+            Err(e) => log::warn!("Could not connect to {}: {:#}; retrying in {}s", url, e, delay.as_secs()),
+        }
+
+        let retry_at = Instant::now() + delay;
+        while Instant::now() < retry_at && !shutdown.load(Ordering::Relaxed) {
+            std::thread::sleep(READ_TIMEOUT);
+        }
+        delay = (delay * 2).min(RECONNECT_DELAY.1);
+    }
+}
+
+fn connect(url: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>> {
+    let (mut socket, _) = tungstenite::connect(url)?;
+    if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    }
+    let subscribe = format!(r#"{{"action":"subscribe","events":["{}"]}}"#, SNAPSHOT_EVENT);
+    socket.send(Message::text(subscribe))?;
+    Ok(socket)
+}
+
+/// Apply snapshots until the connection fails; returns why it ended.
+fn follow(
+    socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    handle: &RuntimeHandle,
+    shutdown: &AtomicBool,
+) -> String {
+    let mut last_heard = Instant::now();
+    let mut last_ping = Instant::now();
+
+    while !shutdown.load(Ordering::Relaxed) {
+        if last_heard.elapsed() > STALE_TIMEOUT {
+            return "no response".to_string();
+        }
+        if last_ping.elapsed() >= PING_INTERVAL {
+            last_ping = Instant::now();
+            if let Err(e) = socket.send(Message::Ping(Default::default())) {
+                return e.to_string();
+            }
+        }
+
+        match socket.read() {
+            Ok(message) => {
+                last_heard = Instant::now();
+                if let Message::Text(text) = message {
+                    if let Some(snapshot) = MirrorSnapshot::from_event(&text) {
+                        handle.with_state_mut(|state| snapshot.apply(state));
+                    }
+                }
+            }
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => return e.to_string(),
+        }
+    }
+    "shutdown".to_string()
+}
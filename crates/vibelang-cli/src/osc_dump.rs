@@ -0,0 +1,123 @@
+//! Viewer for OSC capture files.
+//!
+//! Reads the traffic recorded with `capture_osc()` (or the TUI 'O' key) and
+//! prints one line per OSC message, optionally filtered by address,
+//! direction or beat range. The LEAD column shows how far ahead of sending a
+//! bundle was scheduled, which is where late events show up.
+
+use crate::OscDumpArgs;
+use anyhow::{bail, Context, Result};
+use chrono::{Local, TimeZone};
+use rosc::{OscMessage, OscTime, OscType};
+use vibelang_core::osc_capture::{read_capture, timetag_unix_micros, Direction};
+
+/// Maximum number of argument characters shown per line.
+const MAX_ARGS_CHARS: usize = 80;
+
+/// Print the messages of a capture file.
+pub fn show_capture(args: OscDumpArgs) -> Result<()> {
+    let direction = match args.direction.as_deref() {
+        None => None,
+        Some("out") => Some(Direction::Out),
+        Some("in") => Some(Direction::In),
+        Some(other) => bail!("Unknown direction '{}' (expected \"out\" or \"in\")", other),
+    };
+    let packets = read_capture(&args.file)
+        .with_context(|| format!("Failed to read OSC capture: {}", args.file.display()))?;
+    let total = packets.len();
+
+    let mut lines: Vec<String> = Vec::new();
+    for captured in &packets {
+        if direction.is_some_and(|d| d != captured.direction)
+            || args.since_beat.is_some_and(|beat| captured.beat < beat)
+            || args.until_beat.is_some_and(|beat| captured.beat > beat)
+        {
+            continue;
+        }
+        for (timetag, message) in captured.messages() {
+            if !args.filter.is_empty() && !args.filter.iter().any(|f| message.addr.starts_with(f.as_str())) {
+                continue;
+            }
+            lines.push(format_message(captured.unix_micros, captured.beat, captured.direction, timetag, message));
+        }
+    }
+
+    if lines.is_empty() {
+        println!("No matching messages.");
+        return Ok(());
+    }
+    if let Some(limit) = args.limit {
+        lines.drain(..lines.len().saturating_sub(limit));
+    }
+
+    println!(
+        "{:<12} {:>9}  {:<3} {:>9}  {:<16} ARGS",
+        "TIME", "BEAT", "DIR", "LEAD", "ADDRESS"
+    );
+    for line in &lines {
+        println!("{}", line);
+    }
+    println!("{} messages shown from {} packets", lines.len(), total);
+
+    Ok(())
+}
+
+fn format_message(
+    unix_micros: u64,
+    beat: f64,
+    direction: Direction,
+    timetag: Option<OscTime>,
+    message: &OscMessage,
+) -> String {
+    let time = Local
+        .timestamp_micros(unix_micros as i64)
+        .single()
+        .map(|t| t.format("%H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    // How far ahead of the packet the bundle was scheduled to run
+    let lead = match timetag.and_then(timetag_unix_micros) {
+        Some(at) => format!("{:+.1}ms", (at as f64 - unix_micros as f64) / 1000.0),
+        None => "now".to_string(),
+    };
+
+    let args: Vec<String> = message.args.iter().map(format_arg).collect();
+    let arrow = match direction {
+        Direction::Out => "→",
+        Direction::In => "←",
+    };
+
+    format!(
+        "{:<12} {:>9.3}  {:<3} {:>9}  {:<16} {}",
+        time,
+        beat,
+        arrow,
+        lead,
+        message.addr,
+        truncate(&args.join(" "), MAX_ARGS_CHARS)
+    )
+}
+
+fn format_arg(arg: &OscType) -> String {
+    match arg {
+        OscType::Int(v) => v.to_string(),
+        OscType::Long(v) => v.to_string(),
+        OscType::Float(v) => format!("{}", v),
+        OscType::Double(v) => format!("{}", v),
+        OscType::String(s) => format!("{:?}", s),
+        OscType::Blob(bytes) => format!("<{} bytes>", bytes.len()),
+        OscType::Bool(v) => v.to_string(),
+        OscType::Nil => "nil".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(max_chars - 1).collect();
+        out.push('…');
+        out
+    }
+}
@@ -3,6 +3,9 @@
 //! This module provides functionality to render a `.vibescore` file to an audio file
 //! using SuperCollider's non-realtime (NRT) mode.
 //!
+//! A `.vibe` script is first run in virtual time for `--bars` bars with score
+//! capture enabled, which records the `.vibescore` `vibe run --record` would.
+//!
 //! The `.vibescore` format is a tar archive containing:
 //! - `score.osc`: OSC events for playback
 //! - `synthdefs/*.scsyndef`: Individual synthdef files
@@ -15,27 +18,68 @@ use std::io::{BufReader, Read};
 use std::path::Path;
 use std::process::Command;
 use tar::Archive;
+use vibelang_core::state::StateMessage;
+use vibelang_core::Simulation;
 
-/// Render a score file to an audio file (standalone command with logger init).
+/// Render a script or score file to an audio file (standalone command with logger init).
 pub fn render(args: RenderArgs) -> Result<()> {
-    // Initialize logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+    // Initialize logger; the runtime logs every message at info level
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info,vibelang_core=warn"))
         .format_timestamp(None)
         .init();
 
-    render_score(args)
+    let is_script = args.file.extension().map(|e| e == "vibe").unwrap_or(false);
+    if !is_script {
+        return render_score(args);
+    }
+
+    let temp_dir = tempfile::tempdir().context("Failed to create temp directory")?;
+    let score_path = temp_dir.path().join("render.vibescore");
+    capture_script(&args, &score_path)?;
+    render_score(RenderArgs { file: score_path, ..args })
+}
+
+/// Run a script in virtual time for `args.bars` bars and write what it
+/// sends to scsynth as a `.vibescore` at `score_path`.
+fn capture_script(args: &RenderArgs, score_path: &Path) -> Result<()> {
+    if args.bars == 0 {
+        anyhow::bail!("Nothing to render: --bars must be at least 1");
+    }
+    log::info!("Running {} for {} bars...", args.file.display(), args.bars);
+
+    let mut sim = Simulation::new();
+    let handle = sim.handle().clone();
+    let (engine, script) = crate::simulate::load_script(&sim, &args.file, args.import_paths.clone())?;
+
+    // Capture before the script runs, so the score has everything from beat 0
+    handle.send(StateMessage::EnableScoreCapture { path: score_path.to_path_buf() })?;
+    if let Err(e) = engine.run(&script) {
+        anyhow::bail!("Script failed: {}", e);
+    }
+
+    sim.start();
+    let beats_per_bar = handle.with_state(|state| state.time_signature.beats_per_bar());
+    sim.advance(args.bars as f64 * beats_per_bar);
+    handle.send(StateMessage::DisableScoreCapture)?;
+    sim.advance(0.0);
+
+    if !score_path.exists() {
+        anyhow::bail!("Failed to write the score of {}", args.file.display());
+    }
+    log::info!("Captured {} bars ({:.1}s)", args.bars, sim.seconds());
+    Ok(())
 }
 
 /// Render a score file to an audio file (callable from other code).
 pub fn render_score(args: RenderArgs) -> Result<()> {
     log::info!("VibeLang Render");
     log::info!("==============");
-    log::info!("Input:  {}", args.score_file.display());
+    log::info!("Input:  {}", args.file.display());
     log::info!("Output: {}", args.output.display());
 
     // Validate input file
-    if !args.score_file.exists() {
-        anyhow::bail!("Score file not found: {}", args.score_file.display());
+    if !args.file.exists() {
+        anyhow::bail!("Score file not found: {}", args.file.display());
     }
 
     // Determine output format from extension or flag
@@ -56,25 +100,25 @@ pub fn render_score(args: RenderArgs) -> Result<()> {
     };
 
     // Validate input file extension
-    let is_vibescore = args.score_file.extension()
+    let is_vibescore = args.file.extension()
         .map(|e| e == "vibescore")
         .unwrap_or(false);
 
     if !is_vibescore {
         anyhow::bail!(
-            "Invalid input file: expected .vibescore format\n\
+            "Invalid input file: expected a .vibe script or .vibescore file\n\
             Record a .vibescore file using: vibe run <file.vibe> --record output.vibescore"
         );
     }
 
-    log::info!("Score file size: {} bytes", fs::metadata(&args.score_file)?.len());
+    log::info!("Score file size: {} bytes", fs::metadata(&args.file)?.len());
 
     // Create temp directory for extraction
     let temp_dir = tempfile::tempdir().context("Failed to create temp directory")?;
 
     // Extract vibescore archive
     log::info!("\nExtracting vibescore archive...");
-    let (score_path, synthdef_dir, samples_dir) = extract_vibescore(&args.score_file, temp_dir.path())?;
+    let (score_path, synthdef_dir, samples_dir) = extract_vibescore(&args.file, temp_dir.path())?;
 
     // Render with scsynth
     log::info!("\n[1/2] Rendering audio with scsynth...");
@@ -0,0 +1,81 @@
+//! Session resume: periodic snapshots and `--resume`.
+//!
+//! While a script runs, its live state is written to a session snapshot every
+//! few seconds (and once more on shutdown). After a crash or restart,
+//! `vibe run --resume <file>` evaluates the script as usual and then restores
+//! tempo, transport position, what was playing and the mixer from the
+//! snapshot, so the set continues with as little dead air as possible.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use vibelang_core::session::SessionSnapshot;
+use vibelang_core::RuntimeHandle;
+
+/// How often the snapshot thread checks for shutdown.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Session persistence settings from the CLI.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionOptions {
+    /// Restore the last snapshot after evaluating the script.
+    pub resume: bool,
+    /// Write a snapshot this often (`None` = only on shutdown).
+    pub snapshot_interval: Option<Duration>,
+}
+
+impl SessionOptions {
+    /// Build the options from the CLI arguments (an interval of 0 disables periodic snapshots).
+    pub fn new(resume: bool, snapshot_interval: f64) -> Self {
+        Self {
+            resume,
+            snapshot_interval: (snapshot_interval > 0.0).then(|| Duration::from_secs_f64(snapshot_interval)),
+        }
+    }
+}
+
+/// Load the snapshot to resume from, logging what was found.
+pub fn load_snapshot(file: &Path) -> Option<SessionSnapshot> {
+    match SessionSnapshot::load(file) {
+        Ok(Some(snapshot)) => {
+            log::info!(
+                "↺ Resuming from {} (beat {:.1}, {} BPM)",
+                SessionSnapshot::snapshot_path(file).display(),
+                snapshot.beat,
+                snapshot.tempo
+            );
+            Some(snapshot)
+        }
+        Ok(None) => {
+            log::warn!("--resume: no session snapshot for {}, starting fresh", file.display());
+            None
+        }
+        Err(e) => {
+            log::warn!("--resume: {:#}; starting fresh", e);
+            None
+        }
+    }
+}
+
+/// Write a snapshot of the running session every `interval` until the runtime stops.
+pub fn spawn_snapshot_thread(handle: RuntimeHandle, file: PathBuf, interval: Duration) {
+    std::thread::spawn(move || {
+        let mut last_write = Instant::now();
+        let mut failing = false;
+        while !handle.is_stopped() {
+            std::thread::sleep(POLL_INTERVAL);
+            if last_write.elapsed() < interval || handle.is_stopped() {
+                continue;
+            }
+            last_write = Instant::now();
+            match handle.with_state(SessionSnapshot::capture).save(&file) {
+                Ok(_) => failing = false,
+                // Only warn once per failure streak (e.g. a read-only directory)
+                Err(e) if !failing => {
+                    log::warn!("Failed to write session snapshot: {:#}", e);
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
@@ -0,0 +1,104 @@
+//! Sandbox selection for `/eval` requests (workshop mode).
+//!
+//! `--sandbox <PROFILE>` sets the profile for every request, and
+//! `--eval-token <TOKEN>=<PROFILE>` overrides it for requests carrying
+//! `Authorization: Bearer <TOKEN>`, e.g. a `trusted` token for the teacher.
+//! Trusted requests may only register hooks with `--allow-hooks`.
+
+use anyhow::{anyhow, Result};
+use rhai::Engine;
+use std::collections::HashMap;
+use vibelang_core::api::sandbox::{self, SandboxError, SandboxProfile};
+use vibelang_core::api::watchdog;
+use vibelang_http::EvalResult;
+
+/// Sandbox profiles for eval requests and their restricted engines.
+#[derive(Default)]
+pub struct EvalSandbox {
+    /// Profile for requests without a known token (`None` = trusted).
+    default: Option<SandboxProfile>,
+    /// Profiles by bearer token.
+    tokens: HashMap<String, Option<SandboxProfile>>,
+    /// Restricted engines by profile name.
+    engines: HashMap<String, Engine>,
+    /// Whether trusted requests may register hooks.
+    allow_hooks: bool,
+}
+
+impl EvalSandbox {
+    /// Build from the `--sandbox`, `--eval-token` and `--allow-hooks` arguments.
+    pub fn new(default: Option<&str>, tokens: &[String], allow_hooks: bool) -> Result<Self> {
+        let mut sandbox = Self {
+            default: default.map(SandboxProfile::parse).transpose().map_err(|e| anyhow!(e))?.flatten(),
+            allow_hooks,
+            ..Self::default()
+        };
+
+        for spec in tokens {
+            let (token, profile) = spec
+                .split_once('=')
+                .ok_or_else(|| anyhow!("--eval-token expects TOKEN=PROFILE, got '{}'", spec))?;
+            let profile = SandboxProfile::parse(profile).map_err(|e| anyhow!(e))?;
+            sandbox.tokens.insert(token.to_string(), profile);
+        }
+
+        let profiles: Vec<SandboxProfile> = sandbox
+            .default
+            .iter()
+            .chain(sandbox.tokens.values().flatten())
+            .cloned()
+            .collect();
+        for profile in profiles {
+            sandbox.engines.entry(profile.name.clone()).or_insert_with(|| {
+                let mut engine = vibelang_core::create_engine();
+                vibelang_dsp::register_dsp_api(&mut engine);
+                sandbox::restrict_engine(&mut engine, &profile);
+                engine
+            });
+        }
+
+        Ok(sandbox)
+    }
+
+    /// Name of the profile applied to requests without a token.
+    pub fn default_profile(&self) -> Option<&str> {
+        self.default.as_ref().map(|p| p.name.as_str())
+    }
+
+    /// Evaluate a request's code, sandboxed unless its token is trusted.
+    pub fn eval(&self, engine: &Engine, code: &str, token: Option<&str>) -> EvalResult {
+        let profile = match token.and_then(|t| self.tokens.get(t)) {
+            Some(profile) => profile.as_ref(),
+            None => self.default.as_ref(),
+        };
+
+        let result = match profile {
+            Some(profile) => sandbox::eval_sandboxed(&self.engines[&profile.name], profile, code),
+            None => sandbox::eval_remote(engine, code, self.allow_hooks),
+        };
+
+        match result {
+            Ok(val) => EvalResult {
+                success: true,
+                result: if val.is_unit() { None } else { Some(format!("{:?}", val)) },
+                error: None,
+                violation: None,
+            },
+            Err(SandboxError::Violation(v)) => {
+                log::warn!("[SANDBOX] Rejected eval ({}): {}", v.kind.as_str(), v.message);
+                EvalResult {
+                    success: false,
+                    result: None,
+                    error: Some(v.message.clone()),
+                    violation: Some(v),
+                }
+            }
+            Err(SandboxError::Script(e)) => EvalResult {
+                success: false,
+                result: None,
+                error: Some(watchdog::explain(&e)),
+                violation: None,
+            },
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! `vibe session save/load`: session files of a running session.
+//!
+//! Talks to the session's HTTP API (`vibe run` starts it by default). A
+//! saved file holds the full session (see [`vibelang_core::session_file`]);
+//! loading one replaces whatever the session is playing.
+
+use crate::{SessionArgs, SessionCommand};
+use anyhow::{bail, Context, Result};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use vibelang_core::session_file::{SessionFile, SESSION_FILE_EXTENSION};
+
+/// How long to wait for the session to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run a `vibe session` subcommand.
+pub fn session(command: SessionCommand) -> Result<()> {
+    match command {
+        SessionCommand::Save(args) => save(args),
+        SessionCommand::Load(args) => load(args),
+    }
+}
+
+fn save(args: SessionArgs) -> Result<()> {
+    let api = Api::parse(&args.url)?;
+    let file = SessionFile::from_json(&api.request("GET", "/session", None)?)?;
+    file.save(&args.file)?;
+    println!(
+        "Saved {} voices, {} patterns, {} melodies and {} sequences to {}",
+        file.voices.len(),
+        file.patterns.len(),
+        file.melodies.len(),
+        file.sequences.len(),
+        args.file.display()
+    );
+    Ok(())
+}
+
+fn load(args: SessionArgs) -> Result<()> {
+    if args.file.extension().and_then(|s| s.to_str()) != Some(SESSION_FILE_EXTENSION) {
+        eprintln!("warning: session file doesn't have .{} extension", SESSION_FILE_EXTENSION);
+    }
+    let file = SessionFile::load(&args.file)?;
+    let api = Api::parse(&args.url)?;
+    if api.is_local() {
+        // The session reads the file itself, however large it is
+        let path = std::fs::canonicalize(&args.file)?;
+        let body = serde_json::json!({ "path": path }).to_string();
+        api.request("POST", "/session/load", Some(&body))?;
+    } else {
+        api.request("PUT", "/session", Some(&file.to_json()))?;
+    }
+    println!("Loaded {} into {}", args.file.display(), args.url);
+    Ok(())
+}
+
+/// Host and port of a session's HTTP API.
+struct Api {
+    host: String,
+    port: u16,
+}
+
+impl Api {
+    fn parse(url: &str) -> Result<Self> {
+        let Some(rest) = url.strip_prefix("http://") else {
+            bail!("Only http:// URLs are supported, got '{}'", url);
+        };
+        let authority = rest.split('/').next().unwrap_or_default();
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().with_context(|| format!("Invalid port in '{}'", url))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            bail!("Missing host in '{}'", url);
+        }
+        Ok(Self { host: host.to_string(), port })
+    }
+
+    fn is_local(&self) -> bool {
+        matches!(self.host.as_str(), "localhost" | "127.0.0.1" | "[::1]")
+    }
+
+    /// Send a request and return the response body.
+    ///
+    /// Speaks HTTP/1.0 so the response comes unchunked and ends with the
+    /// connection.
+    fn request(&self, method: &str, path: &str, body: Option<&str>) -> Result<String> {
+        let addr = (self.host.trim_matches(|c| c == '[' || c == ']'), self.port)
+            .to_socket_addrs()?
+            .next()
+            .with_context(|| format!("Cannot resolve '{}'", self.host))?;
+        let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)
+            .with_context(|| format!("No session at {}:{} (is `vibe run` serving its API?)", self.host, self.port))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let body = body.unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            self.host,
+            body.len(),
+            body
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+        let status = head.lines().next().unwrap_or_default();
+        match status.split_whitespace().nth(1).and_then(|code| code.parse::<u16>().ok()) {
+            Some(code) if (200..300).contains(&code) => Ok(body.to_string()),
+            Some(_) => {
+                let message = serde_json::from_str::<serde_json::Value>(body)
+                    .ok()
+                    .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+                    .unwrap_or_else(|| body.to_string());
+                bail!("{} {} failed: {}", method, path, message)
+            }
+            None => bail!("No HTTP response from {}:{}", self.host, self.port),
+        }
+    }
+}
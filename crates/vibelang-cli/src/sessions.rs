@@ -0,0 +1,40 @@
+//! Discovery of running sessions on the LAN.
+//!
+//! Sessions serving their HTTP API on the LAN (`--api-host`) with a
+//! sandboxed `/eval` (`--sandbox`) advertise themselves over zeroconf;
+//! `vibe list-sessions` browses for them and prints how to reach each one.
+
+use crate::ListSessionsArgs;
+use anyhow::{Context, Result};
+use std::time::Duration;
+use vibelang_http::discovery;
+
+/// Print the sessions found within the browse timeout.
+pub fn list_sessions(args: ListSessionsArgs) -> Result<()> {
+    let sessions = discovery::browse(Duration::from_secs_f64(args.timeout.max(0.1)))
+        .context("Failed to browse for sessions (is multicast DNS available?)")?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
+    }
+
+    if sessions.is_empty() {
+        println!("No sessions found.");
+        return Ok(());
+    }
+
+    println!("{:<24} {:<24} {:<8} {:<10} URL", "NAME", "HOST", "VERSION", "SANDBOX");
+    for session in &sessions {
+        println!(
+            "{:<24} {:<24} {:<8} {:<10} {}",
+            session.name,
+            session.host,
+            session.version.as_deref().unwrap_or("-"),
+            session.sandbox.as_deref().unwrap_or("-"),
+            session.url()
+        );
+    }
+
+    Ok(())
+}
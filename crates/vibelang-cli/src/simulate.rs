@@ -0,0 +1,152 @@
+//! `vibe simulate`: run a script in virtual time.
+//!
+//! Evaluates a script without SuperCollider, runs the scheduler for a number
+//! of bars on virtual time and prints every event that would fire, or dumps
+//! its event log (see [`vibelang_core::event_log`]) to a JSON or CSV file
+//! for tests and analysis.
+
+use crate::SimulateArgs;
+use anyhow::{bail, Context, Result};
+use rhai::Engine;
+use std::path::{Path, PathBuf};
+use vibelang_core::api::context;
+use vibelang_core::event_log::write_events;
+use vibelang_core::state::StateMessage;
+use vibelang_core::{extract_synthdef_name, SimulatedEvent, Simulation};
+
+/// Maximum number of control characters shown per line.
+const MAX_CONTROLS_CHARS: usize = 60;
+
+/// Simulate a script and print or dump the events it fires.
+pub fn simulate(args: SimulateArgs) -> Result<()> {
+    // The runtime logs every message at info level; only show problems
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let mut sim = Simulation::new();
+    let handle = sim.handle().clone();
+    let (engine, script) = load_script(&sim, &args.file, args.import_paths)?;
+    if let Err(e) = engine.run(&script) {
+        bail!("Script failed: {}", e);
+    }
+
+    sim.start();
+    let beats_per_bar = handle.with_state(|state| state.time_signature.beats_per_bar());
+    let end_beat = args.bars as f64 * beats_per_bar;
+    sim.advance(end_beat);
+
+    if let Some(path) = &args.dump {
+        // The log runs ahead of the transport by the lookahead
+        let logged: Vec<_> = sim.logged_events().iter().filter(|e| e.beat < end_beat).cloned().collect();
+        let count = write_events(path, &logged)?;
+        println!("Wrote {} events of {} bars to {}", count, args.bars, path.display());
+        return Ok(());
+    }
+
+    let events: Vec<SimulatedEvent> = sim.take_events().into_iter().filter(|e| e.beat < end_beat).collect();
+
+    if events.is_empty() {
+        println!("No events in {} bars.", args.bars);
+        return Ok(());
+    }
+    println!(
+        "{:<10} {:>9} {:>9}  {:<14} {:<18} {:<16} CONTROLS",
+        "POSITION", "BEAT", "TIME", "VOICE", "SYNTHDEF", "SOURCE"
+    );
+    for event in &events {
+        println!("{}", format_event(event, beats_per_bar));
+    }
+    println!(
+        "{} events in {} bars ({:.1}s)",
+        events.len(),
+        args.bars,
+        sim.seconds()
+    );
+    Ok(())
+}
+
+/// Set up the scripting API on `sim` and create an engine for `file`,
+/// returning it with the script source. Run the script once the engine is
+/// ready; the simulation picks up what it sends on `start()`.
+pub fn load_script(sim: &Simulation, file: &Path, mut import_paths: Vec<PathBuf>) -> Result<(Engine, String)> {
+    let script = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read file: {}", file.display()))?;
+
+    let handle = sim.handle().clone();
+    vibelang_core::init_api(handle.clone());
+    let deploy_handle = handle;
+    vibelang_dsp::set_deploy_callback(move |bytes| {
+        let name = extract_synthdef_name(&bytes).unwrap_or_else(|| "unknown".to_string());
+        let _ = deploy_handle.send(StateMessage::LoadSynthDef { name, bytes });
+        Ok(())
+    });
+    vibelang_core::api::group::create_main_group();
+
+    let base_path = file
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    let stdlib_path = PathBuf::from(vibelang_std::stdlib_path());
+    import_paths.push(stdlib_path.clone());
+    if let Some(parent) = stdlib_path.parent() {
+        import_paths.push(parent.to_path_buf());
+    }
+    let abs_path = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    context::set_current_script_file(Some(abs_path.to_string_lossy().to_string()));
+    context::set_script_dir(base_path.clone());
+    context::set_import_paths(import_paths.clone());
+
+    let mut engine = vibelang_core::create_engine_with_paths(base_path, import_paths);
+    vibelang_dsp::register_dsp_api(&mut engine);
+    Ok((engine, script))
+}
+
+/// Bar and beat of an absolute beat, 1-based ("3.2" = bar 3, beat 2).
+fn position(beat: f64, beats_per_bar: f64) -> String {
+    let bar = (beat / beats_per_bar).floor();
+    let in_bar = beat - bar * beats_per_bar;
+    let whole = in_bar.floor();
+    let fraction = in_bar - whole;
+    if fraction < 1e-6 {
+        format!("{}.{}", bar as i64 + 1, whole as i64 + 1)
+    } else {
+        format!("{}.{}+{:.2}", bar as i64 + 1, whole as i64 + 1, fraction)
+    }
+}
+
+/// Where an event came from: its pattern, melody or fade.
+fn source(event: &SimulatedEvent) -> String {
+    let event = &event.event;
+    if let Some(fade) = &event.fade {
+        return format!("fade {}", fade.name);
+    }
+    event
+        .pattern_name
+        .clone()
+        .or_else(|| event.melody_name.clone())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_event(event: &SimulatedEvent, beats_per_bar: f64) -> String {
+    let mut controls = event
+        .event
+        .controls
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if controls.chars().count() > MAX_CONTROLS_CHARS {
+        controls = controls.chars().take(MAX_CONTROLS_CHARS - 1).collect::<String>() + "…";
+    }
+    let minutes = (event.seconds / 60.0).floor();
+    format!(
+        "{:<10} {:>9.3} {:>2}:{:06.3}  {:<14} {:<18} {:<16} {}",
+        position(event.beat, beats_per_bar),
+        event.beat,
+        minutes as i64,
+        event.seconds - minutes * 60.0,
+        event.event.voice_name.as_deref().unwrap_or("-"),
+        event.event.synth_def,
+        source(event),
+        controls
+    )
+}
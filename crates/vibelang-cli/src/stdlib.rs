@@ -0,0 +1,83 @@
+//! Standard library search.
+//!
+//! Queries the stdlib index generated at build time (see
+//! [`vibelang_std::stdlib_index`]), so definitions can be found and imported
+//! without browsing the stdlib directory.
+
+use crate::{StdlibCommand, StdlibSearchArgs};
+use anyhow::Result;
+use vibelang_std::{EntryKind, StdlibEntry};
+
+/// Maximum number of description characters shown per line.
+const MAX_DESCRIPTION_CHARS: usize = 50;
+
+/// Run a `vibe stdlib` subcommand.
+pub fn stdlib(command: StdlibCommand) -> Result<()> {
+    match command {
+        StdlibCommand::Search(args) => search(args),
+        StdlibCommand::Show { name } => show(&name),
+    }
+}
+
+fn search(args: StdlibSearchArgs) -> Result<()> {
+    let entries: Vec<&StdlibEntry> = vibelang_std::search(&args.terms.join(" "))
+        .into_iter()
+        .filter(|e| args.category.as_ref().is_none_or(|c| e.category.eq_ignore_ascii_case(c)))
+        .filter(|e| !args.fx || e.kind == EntryKind::Effect)
+        .collect();
+
+    if entries.is_empty() {
+        println!("No matching definitions.");
+        return Ok(());
+    }
+
+    println!("{:<24} {:<8} {:<50}  IMPORT", "NAME", "KIND", "DESCRIPTION");
+    for entry in entries.iter().take(args.limit) {
+        println!(
+            "{:<24} {:<8} {:<50}  {}",
+            entry.name,
+            entry.kind.as_str(),
+            truncate(entry.description, MAX_DESCRIPTION_CHARS),
+            entry.import_path()
+        );
+    }
+    if entries.len() > args.limit {
+        println!("... and {} more (use --limit to show more)", entries.len() - args.limit);
+    }
+
+    Ok(())
+}
+
+fn show(name: &str) -> Result<()> {
+    let Some(entry) = vibelang_std::find_definition(name) else {
+        let suggestions: Vec<&str> = vibelang_std::search(name).iter().take(5).map(|e| e.name).collect();
+        if suggestions.is_empty() {
+            anyhow::bail!("No stdlib definition named '{}'", name);
+        }
+        anyhow::bail!("No stdlib definition named '{}' (did you mean {}?)", name, suggestions.join(", "));
+    };
+
+    println!("{} ({}, {})", entry.name, entry.kind.as_str(), entry.category);
+    println!("  {}", entry.description);
+    if !entry.genres.is_empty() {
+        println!("  Genres: {}", entry.genres.join(", "));
+    }
+    println!();
+    println!("Parameters:");
+    for (param, default) in entry.params {
+        println!("  {:<16} {}", param, default);
+    }
+    println!();
+    println!("{}", entry.import_statement());
+
+    Ok(())
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let cut: String = text.chars().take(max_chars - 3).collect();
+        format!("{}...", cut)
+    }
+}
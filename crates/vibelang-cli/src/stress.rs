@@ -0,0 +1,274 @@
+//! `vibe stress`: burn-in test for long-running deployments.
+//!
+//! Plays a generated script with a number of silent voices firing a given
+//! number of events per beat against a running scsynth (or an in-process
+//! mock server) for hours, and reports how stable the timing stayed:
+//! scheduling jitter, late and dropped events, failed OSC sends and the
+//! memory growth of the process. Fails when events were dropped or sends
+//! failed, so it can gate an installation going live.
+
+use crate::StressArgs;
+use anyhow::{bail, Context, Result};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use vibelang_core::mock_scsynth::MockScsynth;
+use vibelang_core::performance::OscStats;
+use vibelang_core::state::StateMessage;
+use vibelang_core::{extract_synthdef_name, Runtime, RuntimeHandle};
+
+/// Synthdef the stress voices play; silent, so a burn-in can run on the venue's system.
+const STRESS_SYNTHDEF: &str = "stress_blip";
+
+/// Counters sampled while the test runs.
+#[derive(Default)]
+struct Sample {
+    rss_kb: Option<u64>,
+    dropped_events: u64,
+    stale_dropped_events: u64,
+    late_events: u64,
+}
+
+/// Run the stress test and print its report.
+pub fn stress(args: StressArgs) -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    if args.voices == 0 {
+        bail!("--voices must be at least 1");
+    }
+    let duration = Duration::from_secs_f64((args.hours * 3600.0).max(0.0));
+
+    // The mock has to outlive the runtime that talks to it
+    let mock = if args.mock { Some(MockScsynth::start()?) } else { None };
+    let runtime = match &mock {
+        Some(mock) => Runtime::start_mock(mock)?,
+        None => Runtime::connect(&args.server)
+            .with_context(|| format!("Failed to connect to scsynth at {} (start it, or use --mock)", args.server))?,
+    };
+    let handle = runtime.handle().clone();
+
+    vibelang_core::init_api(handle.clone());
+    let deploy_handle = handle.clone();
+    vibelang_dsp::set_deploy_callback(move |bytes| {
+        let name = extract_synthdef_name(&bytes).unwrap_or_else(|| "unknown".to_string());
+        let _ = deploy_handle.send(StateMessage::LoadSynthDef {
+            name,
+            bytes: bytes.clone(),
+        });
+        deploy_handle.scsynth().d_recv_bytes(bytes).map_err(|e| e.to_string())
+    });
+    vibelang_core::api::group::create_main_group();
+
+    let mut engine = vibelang_core::create_engine();
+    vibelang_dsp::register_dsp_api(&mut engine);
+    let script = stress_script(args.voices, args.events_per_beat, args.bpm);
+    if let Err(e) = engine.run(&script) {
+        bail!("Stress script failed: {}", e);
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    for sig in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        signal_hook::flag::register(sig, Arc::clone(&interrupted)).context("Failed to register signal handler")?;
+    }
+
+    println!(
+        "🔥 Stress test: {} voices, {} events/beat at {} BPM for {} against {}",
+        args.voices,
+        args.events_per_beat,
+        args.bpm,
+        format_duration(duration),
+        if args.mock { "a mock server".to_string() } else { args.server.clone() }
+    );
+    println!("   Ctrl+C stops early and prints the report");
+
+    handle.send(StateMessage::StartScheduler)?;
+    handle.send(StateMessage::FinalizeGroups)?;
+
+    let started = Instant::now();
+    let first = sample(&handle);
+    let mut peak_rss_kb = first.rss_kb;
+    let mut totals = Sample::default();
+    let mut last = Sample { rss_kb: first.rss_kb, ..Sample::default() };
+    let report_interval = Duration::from_secs(args.report_interval.max(1));
+    let mut next_report = report_interval;
+
+    while started.elapsed() < duration && !interrupted.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(250).min(duration.saturating_sub(started.elapsed())));
+        let now = sample(&handle);
+        // The CPU budget counter restarts whenever the load goes over budget
+        totals.dropped_events += now.dropped_events.checked_sub(last.dropped_events).unwrap_or(now.dropped_events);
+        totals.stale_dropped_events = now.stale_dropped_events;
+        totals.late_events = now.late_events;
+        peak_rss_kb = peak_rss_kb.max(now.rss_kb);
+        last = now;
+
+        if started.elapsed() >= next_report {
+            next_report += report_interval;
+            let osc = handle.with_state(|state| state.performance.osc.clone());
+            println!(
+                "   {}  {} bundles, {} late, {} dropped, {} failed sends, RSS {}",
+                format_duration(started.elapsed()),
+                osc.bundles,
+                osc.margins_ms.count_below(0.0),
+                totals.dropped_events + totals.stale_dropped_events,
+                osc.failures,
+                format_kb(last.rss_kb)
+            );
+        }
+    }
+
+    let elapsed = started.elapsed();
+    let (osc, status, beat) = handle.with_state(|state| {
+        (state.performance.osc.clone(), state.performance.status.clone(), state.current_beat)
+    });
+    runtime.shutdown();
+    drop(mock);
+
+    println!("{}", report(elapsed, beat, &osc, &totals, first.rss_kb, last.rss_kb, peak_rss_kb));
+    if let Some(status) = status {
+        println!(
+            "   Server: {:.1}% avg / {:.1}% peak CPU, {} synths, {} UGens",
+            status.avg_cpu, status.peak_cpu, status.num_synths, status.num_ugens
+        );
+    }
+
+    let dropped = totals.dropped_events + totals.stale_dropped_events;
+    if dropped > 0 || osc.failures > 0 {
+        bail!("Stability check failed: {} dropped events, {} failed sends", dropped, osc.failures);
+    }
+    println!("✓ Stable");
+    Ok(())
+}
+
+/// A script with `voices` silent voices firing `events_per_beat` events per beat between them.
+fn stress_script(voices: u32, events_per_beat: u32, bpm: f64) -> String {
+    let mut script = format!(
+        "set_tempo({bpm:?});\n\
+         define_synthdef(\"{STRESS_SYNTHDEF}\")\n    \
+         .param(\"freq\", 440.0)\n    \
+         .param(\"amp\", 0.5)\n    \
+         .param(\"level\", 0.0)\n    \
+         .body(|freq, amp, level| {{\n        \
+         let env = envelope().perc(0.001, 0.05).cleanup_on_finish().build();\n        \
+         sin_osc_ar(freq) * env * amp * level\n    \
+         }});\n\
+         define_group(\"stress\", || {{\n"
+    );
+    // Spread the events of a bar evenly over the voices
+    let events_per_bar = events_per_beat as u64 * 4;
+    for i in 0..voices as u64 {
+        let hits = events_per_bar / voices as u64 + u64::from(i < events_per_bar % voices as u64);
+        let _ = writeln!(
+            script,
+            "    let v{i} = voice(\"stress_{i}\").synth(\"{STRESS_SYNTHDEF}\").set_param(\"freq\", {}.0);",
+            220 + i * 10
+        );
+        if hits > 0 {
+            let _ = writeln!(
+                script,
+                "    pattern(\"stress_{i}\").on(v{i}).step(\"{}\").start();",
+                "x".repeat(hits as usize)
+            );
+        }
+    }
+    script.push_str("});\n");
+    script
+}
+
+/// Current counters of the runtime and the process.
+fn sample(handle: &RuntimeHandle) -> Sample {
+    let (dropped_events, stale_dropped_events, late_events) = handle.with_state(|state| {
+        let perf = &state.performance;
+        (perf.dropped_events, perf.stale_dropped_events, perf.late_events)
+    });
+    Sample {
+        rss_kb: resident_memory_kb(),
+        dropped_events,
+        stale_dropped_events,
+        late_events,
+    }
+}
+
+/// Resident memory of this process in KiB (Linux only).
+fn resident_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// The final report.
+///
+/// Jitter is how much later than usual a bundle left for scsynth: the
+/// median time between sending a bundle and its timetag, minus that time.
+fn report(
+    elapsed: Duration,
+    beat: f64,
+    osc: &OscStats,
+    totals: &Sample,
+    start_rss_kb: Option<u64>,
+    end_rss_kb: Option<u64>,
+    peak_rss_kb: Option<u64>,
+) -> String {
+    let margins = &osc.margins_ms;
+    let mut out = format!("\nStress test report ({}, {:.0} beats)\n", format_duration(elapsed), beat);
+    let _ = writeln!(out, "   Bundles sent:   {}", osc.bundles);
+    match margins.percentile(50.0) {
+        Some(median) => {
+            let jitter = |percent: f64| median - margins.percentile(100.0 - percent).unwrap_or(median);
+            let _ = writeln!(
+                out,
+                "   Timing jitter:  p50 {:.1} ms, p95 {:.1} ms, p99 {:.1} ms, max {:.1} ms (median send margin {:.1} ms)",
+                jitter(50.0),
+                jitter(95.0),
+                jitter(99.0),
+                median - margins.min().unwrap_or(median),
+                median
+            );
+        }
+        None => out.push_str("   Timing jitter:  no bundles sent\n"),
+    }
+    let _ = writeln!(
+        out,
+        "   Late:           {} bundles past their timetag, {} events fired late",
+        margins.count_below(0.0),
+        totals.late_events
+    );
+    let _ = writeln!(
+        out,
+        "   Dropped events: {} over CPU budget, {} stale",
+        totals.dropped_events, totals.stale_dropped_events
+    );
+    let _ = writeln!(out, "   OSC failures:   {}", osc.failures);
+    match (start_rss_kb, end_rss_kb) {
+        (Some(start), Some(end)) => {
+            let growth = end as f64 - start as f64;
+            let hours = elapsed.as_secs_f64() / 3600.0;
+            let _ = write!(
+                out,
+                "   Memory:         {} → {} (peak {}), {:+.1} MiB",
+                format_kb(Some(start)),
+                format_kb(Some(end)),
+                format_kb(peak_rss_kb),
+                growth / 1024.0
+            );
+            if hours > 0.0 {
+                let _ = write!(out, " ({:+.1} MiB/hour)", growth / 1024.0 / hours);
+            }
+        }
+        _ => out.push_str("   Memory:         not available on this platform"),
+    }
+    out
+}
+
+fn format_kb(kb: Option<u64>) -> String {
+    match kb {
+        Some(kb) => format!("{:.1} MiB", kb as f64 / 1024.0),
+        None => "-".to_string(),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
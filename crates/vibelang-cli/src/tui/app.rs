@@ -1,11 +1,17 @@
 //! TUI application state and logic
 
+use vibelang_core::api::context::SourceLocation;
+use vibelang_core::api::helpers::format_amp_db;
+use vibelang_core::liveset::BindingTarget;
+use vibelang_core::pitch;
 use vibelang_core::sequences::ClipSource;
 use vibelang_core::state::{
-    EffectState, GroupState, LoopStatus, MelodyState, PatternState, ScriptState, VoiceState,
+    EffectState, GroupState, LiveSetState, LoopStatus, LoudnessState, MelodyState, PatternState,
+    ScriptState, VoiceState,
 };
 use crate::tui::keyboard::VirtualKeyboard;
 use crate::tui::TuiEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use log::Level;
 use ratatui::style::Color;
 use ratatui::widgets::ListState;
@@ -64,6 +70,27 @@ impl Default for MidiExportState {
     }
 }
 
+/// Number of trigger pads per page (mapped to keys 1-9 and 0)
+pub const PADS_PER_PAGE: usize = 10;
+
+/// How long a pad stays highlighted after being hit
+const PAD_FLASH_MS: u64 = 150;
+
+/// State for the voice trigger pads panel
+#[derive(Debug, Clone, Default)]
+pub struct PadsState {
+    /// Whether the panel is visible
+    pub visible: bool,
+    /// Align triggers to the current quantization (only while the transport runs)
+    pub quantize: bool,
+    /// Current page of pads
+    pub page: usize,
+    /// Voices assigned to pads, sorted by name
+    pub voices: Vec<String>,
+    /// Last hit pad (index into `voices`) for highlighting
+    pub last_hit: Option<(usize, Instant)>,
+}
+
 /// Main TUI application state - simplified for unified hierarchy view
 pub struct TuiApp {
     /// Log messages buffer
@@ -132,6 +159,12 @@ pub struct TuiApp {
     pub focus_events_supported: bool,
     /// MIDI recording export panel state
     pub midi_export: MidiExportState,
+    /// Voice trigger pads panel state
+    pub pads: PadsState,
+    /// Show the live set cue panel
+    pub show_cue_panel: bool,
+    /// Selected locator while the goto menu is open
+    pub goto_menu: Option<usize>,
 }
 
 impl TuiApp {
@@ -172,6 +205,9 @@ impl TuiApp {
             has_focus: true, // Assume we have focus initially
             focus_events_supported: false,
             midi_export: MidiExportState::default(),
+            pads: PadsState::default(),
+            show_cue_panel: false,
+            goto_menu: None,
         }
     }
 
@@ -231,6 +267,22 @@ impl TuiApp {
         }
     }
 
+    /// Name of the voice selected in the hierarchy, if a voice is selected.
+    pub fn selected_voice(&self) -> Option<String> {
+        let entries = self.hierarchy_entries();
+        let entry = entries.get(self.hierarchy_selection)?;
+        entry.id.strip_prefix("voice:").map(str::to_string)
+    }
+
+    /// Where the entity selected in the hierarchy is defined, when its
+    /// script file is known. A sequence clip leads to what it plays.
+    pub fn selected_source_location(&self) -> Option<SourceLocation> {
+        let state = self.state.as_ref()?;
+        let entries = self.hierarchy_entries();
+        let (kind, name) = entries.get(self.hierarchy_selection)?.id.split_once(':')?;
+        source_location_of(state, kind, name).filter(|location| location.file.is_some())
+    }
+
     /// Check if an item is collapsed
     pub fn is_collapsed(&self, id: &str) -> bool {
         self.collapsed_items.contains(id)
@@ -240,6 +292,9 @@ impl TuiApp {
     pub fn update_state(&mut self, state: ScriptState) {
         self.state = Some(state);
         self.sync_selection_bounds();
+        if self.pads.visible {
+            self.refresh_pad_voices();
+        }
     }
 
     /// Process a TUI event
@@ -617,6 +672,70 @@ impl TuiApp {
         Some(new_quant)
     }
 
+    /// Toggle the voice trigger pads panel
+    pub fn toggle_pads_panel(&mut self) {
+        self.pads.visible = !self.pads.visible;
+        if self.pads.visible {
+            self.refresh_pad_voices();
+        }
+    }
+
+    /// Refresh the voices assigned to pads from the current state
+    pub fn refresh_pad_voices(&mut self) {
+        if let Some(state) = &self.state {
+            let mut voices: Vec<String> = state.voices.keys().cloned().collect();
+            voices.sort();
+            self.pads.voices = voices;
+        }
+        let pages = self.pads_page_count();
+        if self.pads.page >= pages {
+            self.pads.page = pages - 1;
+        }
+    }
+
+    /// Number of pad pages (at least one)
+    pub fn pads_page_count(&self) -> usize {
+        self.pads.voices.len().div_ceil(PADS_PER_PAGE).max(1)
+    }
+
+    /// Go to the next page of pads
+    pub fn pads_next_page(&mut self) {
+        if self.pads.page + 1 < self.pads_page_count() {
+            self.pads.page += 1;
+        }
+    }
+
+    /// Go to the previous page of pads
+    pub fn pads_prev_page(&mut self) {
+        self.pads.page = self.pads.page.saturating_sub(1);
+    }
+
+    /// Toggle quantized pad triggering
+    pub fn pads_toggle_quantize(&mut self) {
+        self.pads.quantize = !self.pads.quantize;
+    }
+
+    /// Resolve a pad key ('1'-'9', '0') to a voice and mark the pad as hit
+    pub fn pad_hit(&mut self, key: char) -> Option<String> {
+        let slot = match key {
+            '1'..='9' => key as usize - '1' as usize,
+            '0' => 9,
+            _ => return None,
+        };
+        let index = self.pads.page * PADS_PER_PAGE + slot;
+        let voice = self.pads.voices.get(index)?.clone();
+        self.pads.last_hit = Some((index, Instant::now()));
+        Some(voice)
+    }
+
+    /// Check if a pad (index into the pad voices) was hit recently
+    pub fn pad_is_flashing(&self, index: usize) -> bool {
+        matches!(
+            self.pads.last_hit,
+            Some((hit, at)) if hit == index && at.elapsed() < Duration::from_millis(PAD_FLASH_MS)
+        )
+    }
+
     /// Jump to first active pattern/melody
     pub fn jump_to_active(&mut self) {
         let entries = self.hierarchy_entries();
@@ -858,8 +977,9 @@ impl TuiApp {
                     let loop_beats = sequence.loop_beats.max(0.001);
 
                     // Always use transport-relative timing for consistent visualization
-                    // All sequences sync to the global transport beat
-                    let elapsed_beats = view_beat.max(0.0);
+                    // All sequences sync to the global transport beat, measured in the
+                    // sequence's own beats when it plays at half or double time
+                    let elapsed_beats = view_beat.max(0.0) * sequence.speed;
 
                     // Position within current loop (wrapped)
                     let position = elapsed_beats.rem_euclid(loop_beats);
@@ -921,6 +1041,55 @@ impl TuiApp {
         }
     }
 
+    pub fn loudness(&self) -> LoudnessState {
+        self.state
+            .as_ref()
+            .map(|state| state.loudness.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn live_set(&self) -> Option<LiveSetState> {
+        self.state.as_ref().and_then(|state| state.live_set.clone())
+    }
+
+    /// Song locators in song order with their beat positions.
+    pub fn markers(&self) -> Vec<(String, f64)> {
+        self.state.as_ref().map(|state| state.locators.sorted()).unwrap_or_default()
+    }
+
+    /// Open or close the goto menu.
+    pub fn toggle_goto_menu(&mut self) {
+        self.goto_menu = match self.goto_menu {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    /// Move the goto menu selection, clamped to the locator list.
+    pub fn goto_move(&mut self, delta: isize) {
+        let count = self.markers().len();
+        if let Some(selected) = self.goto_menu.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    /// Resolve a key press to a live set binding, if the loaded set binds it.
+    pub fn live_set_key_target(&self, key: &KeyEvent) -> Option<BindingTarget> {
+        if key.kind != KeyEventKind::Press || key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        self.state
+            .as_ref()?
+            .live_set
+            .as_ref()?
+            .set
+            .key_target(c)
+            .cloned()
+    }
+
     fn sync_selection_bounds(&mut self) {
         let hierarchy_len = self.hierarchy_entries().len();
         if hierarchy_len == 0 {
@@ -1124,6 +1293,12 @@ pub struct ResourceStats {
     pub buffers_used: i32,
     pub buses_used: i32,
     pub samples: usize,
+    /// Average and peak server CPU load in percent.
+    pub cpu: Option<(f32, f32)>,
+    pub over_cpu_budget: bool,
+    /// Events fired late and stale events dropped by the tick loop.
+    pub late_events: u64,
+    pub stale_dropped_events: u64,
 }
 
 impl ResourceStats {
@@ -1136,6 +1311,14 @@ impl ResourceStats {
             buffers_used: state.next_buffer_id - 100,
             buses_used: state.next_audio_bus - 64,
             samples: state.samples.len(),
+            cpu: state
+                .performance
+                .status
+                .as_ref()
+                .map(|s| (s.avg_cpu, s.peak_cpu)),
+            over_cpu_budget: state.performance.budget.over_budget,
+            late_events: state.performance.late_events,
+            stale_dropped_events: state.performance.stale_dropped_events,
         }
     }
 }
@@ -1293,7 +1476,7 @@ impl HierarchyEntry {
         let params: Vec<(String, String)> = group
             .params
             .iter()
-            .map(|(k, v)| (k.clone(), format_param_value(*v)))
+            .map(|(k, v)| (k.clone(), format_named_param(k, *v)))
             .collect();
 
         Self {
@@ -1347,6 +1530,20 @@ impl HierarchyEntry {
     }
 }
 
+/// Format a parameter for display, showing levels (`amp`, `gain`, ...) in dB.
+fn format_named_param(name: &str, v: f32) -> String {
+    if is_level_param(name) {
+        format_amp_db(v as f64)
+    } else {
+        format_param_value(v)
+    }
+}
+
+/// Whether a parameter holds a linear amplitude.
+fn is_level_param(name: &str) -> bool {
+    matches!(name, "amp" | "gain" | "level") || name.ends_with("_amp") || name.ends_with("_gain")
+}
+
 /// Format a parameter value for display
 fn format_param_value(v: f32) -> String {
     if v.abs() < 0.0001 {
@@ -1432,7 +1629,7 @@ fn build_hierarchy_entries(state: &ScriptState, collapsed: &HashSet<String>) ->
         grouped_items
             .entry(voice.group_path.clone())
             .or_default()
-            .push(hierarchy_item_for_voice(voice));
+            .push(hierarchy_item_for_voice(voice, state));
     }
 
     for pattern in state.patterns.values() {
@@ -1563,7 +1760,28 @@ fn collect_group_entries(
     }
 }
 
-fn hierarchy_item_for_voice(voice: &VoiceState) -> HierarchyItem {
+/// Source location of a hierarchy entry, by the kind prefix of its id.
+fn source_location_of(state: &ScriptState, kind: &str, name: &str) -> Option<SourceLocation> {
+    match kind {
+        "group" => state.groups.get(name).map(|g| g.source_location.clone()),
+        "voice" => state.voices.get(name).map(|v| v.source_location.clone()),
+        "pattern" | "pat" => state.patterns.get(name).map(|p| p.source_location.clone()),
+        "melody" | "mel" => state.melodies.get(name).map(|m| m.source_location.clone()),
+        "effect" => state.effects.get(name).map(|e| e.source_location.clone()),
+        "seq" => state.sequences.get(name).map(|s| s.source_location.clone()),
+        "fade" => state.fade_defs.get(name).map(|f| f.source_location.clone()),
+        "clip" => {
+            let (sequence, source) = name.split_once(':')?;
+            let (kind, name) = source.split_once(':')?;
+            source_location_of(state, kind, name)
+                .filter(|location| !location.is_empty())
+                .or_else(|| source_location_of(state, "seq", sequence))
+        }
+        _ => None,
+    }
+}
+
+fn hierarchy_item_for_voice(voice: &VoiceState, state: &ScriptState) -> HierarchyItem {
     let mut detail = Vec::new();
     if let Some(synth) = &voice.synth_name {
         detail.push(synth.clone());
@@ -1574,6 +1792,38 @@ fn hierarchy_item_for_voice(voice: &VoiceState) -> HierarchyItem {
     if voice.soloed {
         detail.push("solo".to_string());
     }
+    let sounding = voice.sounding_notes(Instant::now());
+    if !sounding.is_empty() {
+        // Pitch, retrigger count, age and source help spot stuck notes;
+        // the synth's provenance names the step, clip and line behind a wrong one
+        let notes: Vec<String> = sounding
+            .iter()
+            .map(|n| {
+                let mut label = pitch::note_name(n.note);
+                if n.count > 1 {
+                    label.push_str(&format!("×{}", n.count));
+                }
+                let synth = voice
+                    .active_notes
+                    .get(&n.note)
+                    .and_then(|nodes| nodes.first())
+                    .and_then(|node_id| state.active_synths.get(node_id));
+                let source = match (synth, &n.source) {
+                    (Some(synth), _) => synth.provenance(),
+                    (None, Some(source)) => source.name().unwrap_or(source.kind()).to_string(),
+                    (None, None) => "?".to_string(),
+                };
+                let stuck = if n.stuck { "⚠" } else { "" };
+                format!("{}{} ({} {:.1}s)", stuck, label, source, n.age.as_secs_f64())
+            })
+            .collect();
+        detail.push(format!(
+            "♪ {}/{} {}",
+            voice.active_note_count(),
+            voice.polyphony,
+            notes.join(" ")
+        ));
+    }
 
     // Build params list - combine gain with amp for unified display
     let mut params: Vec<(String, String)> = Vec::new();
@@ -1587,7 +1837,7 @@ fn hierarchy_item_for_voice(voice: &VoiceState) -> HierarchyItem {
 
     // Only show amp if not default (1.0)
     if (effective_amp - 1.0).abs() > 0.001 {
-        params.push(("amp".to_string(), format_param_value(effective_amp as f32)));
+        params.push(("amp".to_string(), format_amp_db(effective_amp)));
     }
 
     if voice.polyphony > 1 {
@@ -1597,10 +1847,15 @@ fn hierarchy_item_for_voice(voice: &VoiceState) -> HierarchyItem {
     // Add other params (excluding amp since we already handled it)
     for (k, v) in &voice.params {
         if k != "amp" {
-            params.push((k.clone(), format_param_value(*v)));
+            params.push((k.clone(), format_named_param(k, *v)));
         }
     }
 
+    // Live diagnostic outputs (e.g. envelope) read back from the synth
+    for (name, value) in &voice.diag_values {
+        params.push((format!("~{}", name), format_param_value(*value)));
+    }
+
     HierarchyItem {
         id: format!("voice:{}", voice.name),
         kind: HierarchyKind::Voice,
@@ -1620,7 +1875,9 @@ fn hierarchy_item_for_pattern(pattern: &PatternState) -> HierarchyItem {
         LoopStatus::QueuedStop { stop_beat, .. } => detail_parts.push(format!("⏹@{:.0}", stop_beat)),
         LoopStatus::Stopped => detail_parts.push("⏸".to_string()),
     };
-    if let Some(voice) = &pattern.voice_name {
+    if let Some(target) = &pattern.midi_target {
+        detail_parts.push(format!("→MIDI ch{} {}", target.channel + 1, pitch::note_name(target.note)));
+    } else if let Some(voice) = &pattern.voice_name {
         detail_parts.push(format!("→{}", voice));
     }
 
@@ -1628,7 +1885,7 @@ fn hierarchy_item_for_pattern(pattern: &PatternState) -> HierarchyItem {
     let params: Vec<(String, String)> = pattern
         .params
         .iter()
-        .map(|(k, v)| (k.clone(), format_param_value(*v)))
+        .map(|(k, v)| (k.clone(), format_named_param(k, *v)))
         .collect();
 
     HierarchyItem {
@@ -1653,12 +1910,15 @@ fn hierarchy_item_for_melody(melody: &MelodyState) -> HierarchyItem {
     if let Some(voice) = &melody.voice_name {
         detail_parts.push(format!("→{}", voice));
     }
+    if let Some(range) = melody_range(melody) {
+        detail_parts.push(range);
+    }
 
     // Include melody params
     let params: Vec<(String, String)> = melody
         .params
         .iter()
-        .map(|(k, v)| (k.clone(), format_param_value(*v)))
+        .map(|(k, v)| (k.clone(), format_named_param(k, *v)))
         .collect();
 
     HierarchyItem {
@@ -1672,12 +1932,28 @@ fn hierarchy_item_for_melody(melody: &MelodyState) -> HierarchyItem {
     }
 }
 
+/// Lowest and highest pitch of a melody, e.g. "C3–G4".
+fn melody_range(melody: &MelodyState) -> Option<String> {
+    let pitches = melody.loop_pattern.as_ref()?.events.iter().filter_map(|event| {
+        let (_, freq) = event.controls.iter().find(|(k, _)| k == "freq")?;
+        pitch::Pitch::from_freq(*freq as f64)
+    });
+    let (low, high) = pitches.fold(None, |range: Option<(pitch::Pitch, pitch::Pitch)>, p| match range {
+        None => Some((p, p)),
+        Some((low, high)) => Some((
+            if p.freq() < low.freq() { p } else { low },
+            if p.freq() > high.freq() { p } else { high },
+        )),
+    })?;
+    Some(if low == high { low.to_string() } else { format!("{}–{}", low, high) })
+}
+
 fn hierarchy_item_for_effect(effect: &EffectState) -> HierarchyItem {
     // Show effect params
     let params: Vec<(String, String)> = effect
         .params
         .iter()
-        .map(|(k, v)| (k.clone(), format_param_value(*v)))
+        .map(|(k, v)| (k.clone(), format_named_param(k, *v)))
         .collect();
 
     HierarchyItem {
@@ -2,11 +2,13 @@
 
 use crate::tui::app::{
     BeatInfo, ExportMode, HierarchyEntry, HierarchyKind, LogEntry, PanelFocus, QueueMetrics,
-    ResourceStats, SequenceDisplay, SummaryStats, TuiApp,
+    ResourceStats, SequenceDisplay, SummaryStats, TuiApp, PADS_PER_PAGE,
 };
 use crate::tui::keyboard::{note_name, VirtualKeyboard};
 use crate::tui::layout::{create_layout_with_keyboard, truncate_string};
 use log::Level;
+use vibelang_core::api::helpers::{amp_to_db, format_amp_db};
+use vibelang_core::state::{LiveSetState, LoudnessState};
 use ratatui::{
     layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
@@ -40,6 +42,21 @@ pub fn render_ui(frame: &mut Frame, app: &mut TuiApp) {
         return;
     }
 
+    if app.pads.visible {
+        render_pads_panel(frame, app, area);
+        return;
+    }
+
+    if app.show_cue_panel {
+        render_cue_panel(frame, app, area);
+        return;
+    }
+
+    if let Some(selected) = app.goto_menu {
+        render_goto_menu(frame, app, selected, area);
+        return;
+    }
+
     let sequences = app.sequence_entries();
     let hierarchy = if app.search_query.is_empty() {
         app.hierarchy_entries()
@@ -50,6 +67,8 @@ pub fn render_ui(frame: &mut Frame, app: &mut TuiApp) {
     let queue_metrics = app.queue_metrics();
     let resource_stats = app.resource_stats();
     let beat_info = app.get_beat_info();
+    let loudness = app.loudness();
+    let live_set = app.live_set();
 
     // Render header with all stats and VU meter
     render_header(
@@ -61,6 +80,8 @@ pub fn render_ui(frame: &mut Frame, app: &mut TuiApp) {
         &resource_stats,
         app.timeline_offset_beats,
         app.vu_level,
+        &loudness,
+        live_set.as_ref(),
     );
 
     // Handle maximized log view
@@ -122,6 +143,8 @@ fn render_header(
     resources: &ResourceStats,
     time_offset: f64,
     vu_level: f32,
+    loudness: &LoudnessState,
+    live_set: Option<&LiveSetState>,
 ) {
     let status_color = if beat_info.running {
         Color::Green
@@ -138,7 +161,7 @@ fn render_header(
         bar_width,
     );
 
-    let header_lines = vec![
+    let mut header_lines = vec![
         // Line 1: Transport status, Bar, Beat, BPM, Time signature
         Line::from(vec![
             Span::styled(
@@ -267,6 +290,31 @@ fn render_header(
                 format!("{}", resources.samples),
                 Style::default().fg(Color::White),
             ),
+            Span::raw("  │  "),
+            Span::styled("CPU", Style::default().fg(Color::Magenta)),
+            Span::raw(" "),
+            Span::styled(
+                resources
+                    .cpu
+                    .map(|(avg, peak)| format!("{:.0}% ({:.0}%)", avg, peak))
+                    .unwrap_or_else(|| "-".to_string()),
+                Style::default().fg(if resources.over_cpu_budget {
+                    Color::Red
+                } else {
+                    Color::White
+                }),
+            ),
+            Span::raw("  │  "),
+            Span::styled("Late", Style::default().fg(Color::Magenta)),
+            Span::raw(" "),
+            Span::styled(
+                format!("{} ({} dropped)", resources.late_events, resources.stale_dropped_events),
+                Style::default().fg(if resources.stale_dropped_events > 0 {
+                    Color::Yellow
+                } else {
+                    Color::White
+                }),
+            ),
         ]),
         // Line 5: Gain, VU meter, and time offset
         Line::from(vec![
@@ -274,20 +322,46 @@ fn render_header(
             Span::styled("Gain", Style::default().fg(Color::Magenta)),
             Span::raw(" avg "),
             Span::styled(
-                format!("{:.2}", summary.avg_voice_gain),
+                format_amp_db(summary.avg_voice_gain as f64),
                 Style::default().fg(Color::White),
             ),
             Span::raw(" max "),
             Span::styled(
-                format!("{:.2}", summary.max_voice_gain),
+                format_amp_db(summary.max_voice_gain as f64),
                 Style::default().fg(Color::White),
             ),
             Span::raw("  │  "),
             Span::styled("VU ", Style::default().fg(Color::Green)),
             Span::styled(
-                vu_meter_bar(vu_level, 12),
-                Style::default().fg(vu_meter_color(vu_level)),
+                vu_meter_bar(vu_meter_fraction(vu_level), 12),
+                Style::default().fg(vu_meter_color(vu_meter_fraction(vu_level))),
+            ),
+            Span::raw(" "),
+            Span::styled(format_amp_db(vu_level as f64), Style::default().fg(Color::White)),
+            Span::raw("  │  "),
+            Span::styled("LUFS", Style::default().fg(Color::Green)),
+            Span::raw(" S "),
+            Span::styled(
+                format_lufs(loudness.short_term_lufs),
+                Style::default().fg(if loudness.over_target {
+                    Color::Red
+                } else {
+                    Color::White
+                }),
+            ),
+            Span::raw(" I "),
+            Span::styled(
+                format_lufs(loudness.integrated_lufs),
+                Style::default().fg(Color::White),
             ),
+            if let Some(target) = loudness.target_lufs {
+                Span::styled(
+                    format!(" (target {:.1})", target),
+                    Style::default().fg(Color::DarkGray),
+                )
+            } else {
+                Span::raw("")
+            },
             if time_offset.abs() > 0.01 {
                 Span::raw("  │  ")
             } else {
@@ -304,6 +378,24 @@ fn render_header(
         ]),
     ];
 
+    // Live set: active scene and the cue that GO will launch next
+    if let Some(live) = live_set {
+        header_lines[0].spans.extend([
+            Span::raw("  │  Scene "),
+            Span::styled(
+                live.active_scene.clone().unwrap_or_else(|| "-".to_string()),
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  Next "),
+            Span::styled(
+                live.next_cue().unwrap_or("-").to_string(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+    }
+
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray));
@@ -312,6 +404,12 @@ fn render_header(
     frame.render_widget(paragraph, area);
 }
 
+/// Format a loudness value, showing silence as "-inf".
+fn format_lufs(lufs: Option<f64>) -> String {
+    lufs.map(|l| format!("{:.1}", l))
+        .unwrap_or_else(|| "-inf".to_string())
+}
+
 /// Render unified hierarchy view with integrated sequences
 fn render_unified_hierarchy(
     frame: &mut Frame,
@@ -865,6 +963,21 @@ fn render_footer(frame: &mut Frame, area: Rect, app: &TuiApp) {
         ));
     }
 
+    // Script checkpoints: where evaluation currently stops
+    if let Some(checkpoints) = app.state.as_ref().map(|s| &s.checkpoints).filter(|c| !c.names.is_empty()) {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled("< >", Style::default().fg(Color::White)));
+        spans.push(Span::styled(
+            format!(
+                " until {} ({}/{})",
+                checkpoints.selected.as_deref().unwrap_or("end"),
+                checkpoints.position() + 1,
+                checkpoints.names.len() + 1
+            ),
+            Style::default().fg(if checkpoints.selected.is_some() { Color::Yellow } else { Color::DarkGray }),
+        ));
+    }
+
     if app.error_message.is_some() {
         spans.push(Span::raw("  "));
         spans.push(Span::styled(
@@ -951,7 +1064,7 @@ fn log_level_style(level: Level) -> Style {
 /// Render help modal with all keyboard shortcuts
 fn render_help_modal(frame: &mut Frame, area: Rect) {
     let modal_width = area.width.saturating_sub(10).min(70);
-    let modal_height = area.height.saturating_sub(6).min(32);
+    let modal_height = area.height.saturating_sub(6).min(33);
 
     let modal_x = (area.width.saturating_sub(modal_width)) / 2;
     let modal_y = (area.height.saturating_sub(modal_height)) / 2;
@@ -1059,6 +1172,42 @@ fn render_help_modal(frame: &mut Frame, area: Rect) {
             Span::styled("  < >         ", Style::default().fg(Color::White)),
             Span::styled("Octave down/up (when keyboard active)", Style::default().fg(Color::Gray)),
         ]),
+        Line::from(vec![
+            Span::styled("  P (capital) ", Style::default().fg(Color::White)),
+            Span::styled("Trigger pads: audition voices with 1-9,0", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  C (capital) ", Style::default().fg(Color::White)),
+            Span::styled("Cue panel: Space = GO (vibe perform)", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  g           ", Style::default().fg(Color::White)),
+            Span::styled("Goto menu: jump to a song locator on the next bar", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  o           ", Style::default().fg(Color::White)),
+            Span::styled("Open the selected item's definition in $EDITOR", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  R (capital) ", Style::default().fg(Color::White)),
+            Span::styled("Randomize the selected voice's ranged params", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  !           ", Style::default().fg(Color::White)),
+            Span::styled("Panic: silence all sounding notes", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  O (capital) ", Style::default().fg(Color::White)),
+            Span::styled("Start/stop OSC capture (view with vibe osc-dump)", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  A (capital) ", Style::default().fg(Color::White)),
+            Span::styled("Start/stop recording param tweaks as automation", Style::default().fg(Color::Gray)),
+        ]),
+        Line::from(vec![
+            Span::styled("  < >         ", Style::default().fg(Color::White)),
+            Span::styled("Evaluate up to previous/next checkpoint", Style::default().fg(Color::Gray)),
+        ]),
         Line::from(vec![
             Span::styled("  Lower oct   ", Style::default().fg(Color::White)),
             Span::styled("Y-M row (white), SFGJKL (black): A2-C4", Style::default().fg(Color::Gray)),
@@ -1304,6 +1453,323 @@ fn render_midi_export_panel(frame: &mut Frame, app: &TuiApp, area: Rect) {
     frame.render_widget(text, modal_area);
 }
 
+/// Render voice trigger pads panel for auditioning
+fn render_pads_panel(frame: &mut Frame, app: &TuiApp, area: Rect) {
+    let modal_width = area.width.saturating_sub(10).min(80);
+    let modal_height = area.height.saturating_sub(4).min(14);
+
+    let modal_x = (area.width.saturating_sub(modal_width)) / 2;
+    let modal_y = (area.height.saturating_sub(modal_height)) / 2;
+
+    let modal_area = Rect {
+        x: modal_x,
+        y: modal_y,
+        width: modal_width,
+        height: modal_height,
+    };
+
+    let quantization = app.state.as_ref().map(|s| s.quantization_beats).unwrap_or(4.0);
+    let page_count = app.pads_page_count();
+
+    let mut lines: Vec<Line> = Vec::new();
+
+    lines.push(Line::from(vec![
+        Span::styled("  Quantize: ", Style::default().fg(Color::White)),
+        Span::styled(
+            if app.pads.quantize {
+                format!(" {} beats ", format_quantization(quantization))
+            } else {
+                " off ".to_string()
+            },
+            if app.pads.quantize {
+                Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            },
+        ),
+        Span::styled("   (Tab)", Style::default().fg(Color::DarkGray)),
+        Span::styled("   Page: ", Style::default().fg(Color::White)),
+        Span::styled(
+            format!("{}/{}", app.pads.page + 1, page_count),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" (←/→)", Style::default().fg(Color::DarkGray)),
+    ]));
+
+    lines.push(Line::from(""));
+
+    if app.pads.voices.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("    ", Style::default()),
+            Span::styled("(no voices defined)", Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)),
+        ]));
+    } else {
+        // Two rows of five pads: 1-5 and 6-0
+        let pad_width = ((modal_width as usize).saturating_sub(4) / 5).max(6);
+        let page_start = app.pads.page * PADS_PER_PAGE;
+        for row in 0..2 {
+            let mut key_spans = vec![Span::raw("  ")];
+            let mut name_spans = vec![Span::raw("  ")];
+            for col in 0..5 {
+                let slot = row * 5 + col;
+                let index = page_start + slot;
+                let key = if slot == 9 { '0' } else { (b'1' + slot as u8) as char };
+                let Some(voice) = app.pads.voices.get(index) else {
+                    key_spans.push(Span::raw(" ".repeat(pad_width)));
+                    name_spans.push(Span::raw(" ".repeat(pad_width)));
+                    continue;
+                };
+                let muted = app
+                    .state
+                    .as_ref()
+                    .and_then(|s| s.voices.get(voice))
+                    .map(|v| v.muted)
+                    .unwrap_or(false);
+
+                let style = if app.pad_is_flashing(index) {
+                    Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else if muted {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default().fg(Color::Green)
+                };
+                let label = truncate_string(voice, pad_width.saturating_sub(2));
+                key_spans.push(Span::styled(
+                    format!(" [{}]{}", key, " ".repeat(pad_width.saturating_sub(4))),
+                    Style::default().fg(Color::Cyan),
+                ));
+                name_spans.push(Span::styled(
+                    format!(" {:<width$}", label, width = pad_width.saturating_sub(2)),
+                    style,
+                ));
+                name_spans.push(Span::raw(" "));
+            }
+            lines.push(Line::from(key_spans));
+            lines.push(Line::from(name_spans));
+            lines.push(Line::from(""));
+        }
+    }
+
+    lines.push(Line::from(vec![
+        Span::styled(
+            "  1-9,0: Trigger | Space: Play/pause | Esc/q: Close",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Trigger Pads ")
+        .style(Style::default().bg(Color::Black));
+
+    let text = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    // Clear background and render
+    frame.render_widget(ratatui::widgets::Clear, modal_area);
+    frame.render_widget(text, modal_area);
+}
+
+/// Render the live set cue list with the current and standby cue
+fn render_cue_panel(frame: &mut Frame, app: &TuiApp, area: Rect) {
+    let modal_width = area.width.saturating_sub(10).min(70);
+    let modal_height = area.height.saturating_sub(4).min(24);
+
+    let modal_x = (area.width.saturating_sub(modal_width)) / 2;
+    let modal_y = (area.height.saturating_sub(modal_height)) / 2;
+
+    let modal_area = Rect {
+        x: modal_x,
+        y: modal_y,
+        width: modal_width,
+        height: modal_height,
+    };
+
+    let mut lines: Vec<Line> = Vec::new();
+
+    match app.live_set() {
+        None => {
+            lines.push(Line::from(vec![
+                Span::styled("    ", Style::default()),
+                Span::styled(
+                    "(no live set loaded - start with `vibe perform <set.vibeset>`)",
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ),
+            ]));
+        }
+        Some(live) => {
+            let standby = live.step_cue(1);
+            lines.push(Line::from(vec![
+                Span::styled("  Standby: ", Style::default().fg(Color::White)),
+                Span::styled(
+                    match standby {
+                        Some(index) => format!(" {} {} ", index + 1, live.set.cues[index]),
+                        None => " end of cue list ".to_string(),
+                    },
+                    Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("   Scene: ", Style::default().fg(Color::White)),
+                Span::styled(
+                    live.active_scene.clone().unwrap_or_else(|| "-".to_string()),
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                ),
+            ]));
+            lines.push(Line::from(""));
+
+            // Keep the standby cue visible in long cue lists
+            let visible = (modal_height as usize).saturating_sub(6).max(1);
+            let focus = standby.or(live.cue_index).unwrap_or(0);
+            let first = focus.saturating_sub(visible / 2);
+            for (index, scene) in live.set.cues.iter().enumerate().skip(first).take(visible) {
+                let actions = live.set.scene(scene).map(|s| s.actions.len()).unwrap_or(0);
+                let (marker, style) = if live.cue_index == Some(index) {
+                    ("▶ ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+                } else if standby == Some(index) {
+                    ("SB", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                } else {
+                    ("  ", Style::default().fg(Color::Gray))
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} ", marker), style),
+                    Span::styled(format!("{:>3}  ", index + 1), Style::default().fg(Color::DarkGray)),
+                    Span::styled(truncate_string(scene, (modal_width as usize).saturating_sub(30)), style),
+                    Span::styled(
+                        format!("  ({} action{})", actions, if actions == 1 { "" } else { "s" }),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+            }
+        }
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            "  Space: GO | Backspace/b: Back | Esc/q: Close",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Cues ")
+        .style(Style::default().bg(Color::Black));
+
+    let text = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    // Clear background and render
+    frame.render_widget(ratatui::widgets::Clear, modal_area);
+    frame.render_widget(text, modal_area);
+}
+
+/// Render the goto menu listing the song locators
+fn render_goto_menu(frame: &mut Frame, app: &TuiApp, selected: usize, area: Rect) {
+    let modal_width = area.width.saturating_sub(10).min(60);
+    let modal_height = area.height.saturating_sub(4).min(20);
+
+    let modal_x = (area.width.saturating_sub(modal_width)) / 2;
+    let modal_y = (area.height.saturating_sub(modal_height)) / 2;
+
+    let modal_area = Rect {
+        x: modal_x,
+        y: modal_y,
+        width: modal_width,
+        height: modal_height,
+    };
+
+    let markers = app.markers();
+    let beats_per_bar = app
+        .state
+        .as_ref()
+        .map(|state| state.time_signature.beats_per_bar())
+        .unwrap_or(4.0);
+    let pending = app.state.as_ref().and_then(|state| state.locators.pending.clone());
+
+    let mut lines: Vec<Line> = Vec::new();
+
+    if markers.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled("    ", Style::default()),
+            Span::styled(
+                "(no locators - add some with `marker(\"drop\", 64.bars)`)",
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            ),
+        ]));
+    } else {
+        // Keep the selection visible in long locator lists
+        let visible = (modal_height as usize).saturating_sub(6).max(1);
+        let first = selected.saturating_sub(visible / 2);
+        for (index, (name, beat)) in markers.iter().enumerate().skip(first).take(visible) {
+            let is_pending = pending.as_ref().is_some_and(|jump| &jump.marker == name);
+            let (marker, style) = if index == selected {
+                ("▶ ", Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD))
+            } else if is_pending {
+                ("… ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                ("  ", Style::default().fg(Color::Gray))
+            };
+            let key = if index < 9 { format!("{}", index + 1) } else { " ".to_string() };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} ", marker), style),
+                Span::styled(format!("{}  ", key), Style::default().fg(Color::DarkGray)),
+                Span::styled(truncate_string(name, (modal_width as usize).saturating_sub(24)), style),
+                Span::styled(
+                    format!("  bar {}", format_quantization(beat / beats_per_bar + 1.0)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+    }
+
+    if let Some(jump) = pending {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("  Jumping to ", Style::default().fg(Color::White)),
+            Span::styled(jump.marker, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!(" at bar {}", format_quantization(jump.at_beat / beats_per_bar + 1.0)),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            "  ↑↓/jk: Select | Enter/1-9: Jump on next bar | Esc/g: Close",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Goto ")
+        .style(Style::default().bg(Color::Black));
+
+    let text = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    // Clear background and render
+    frame.render_widget(ratatui::widgets::Clear, modal_area);
+    frame.render_widget(text, modal_area);
+}
+
+/// Format a quantization value in beats (e.g. "4", "0.5")
+fn format_quantization(beats: f64) -> String {
+    if beats.fract().abs() < f64::EPSILON {
+        format!("{}", beats as i64)
+    } else {
+        format!("{}", beats)
+    }
+}
+
 /// Render maximized log panel (takes main area)
 fn render_log_maximized(frame: &mut Frame, area: Rect, app: &TuiApp, focused: bool) {
     let border_style = if focused {
@@ -1401,6 +1867,15 @@ fn render_search_bar(frame: &mut Frame, area: Rect, app: &TuiApp) {
     frame.render_widget(paragraph, search_area);
 }
 
+/// Range of the VU meter below 0 dBFS.
+const VU_RANGE_DB: f64 = 48.0;
+
+/// Position of a linear level on the dB-scaled VU meter (0.0 - 1.0)
+fn vu_meter_fraction(level: f32) -> f32 {
+    let db = amp_to_db(level as f64);
+    ((db + VU_RANGE_DB) / VU_RANGE_DB).clamp(0.0, 1.0) as f32
+}
+
 /// Generate VU meter bar
 fn vu_meter_bar(level: f32, width: usize) -> String {
     let filled = ((level * width as f32).round() as usize).min(width);
@@ -0,0 +1,135 @@
+//! Preload manifests: `vibe warmup` and startup preloading.
+//!
+//! `vibe warmup <file>` validates a script without starting SuperCollider and
+//! writes the synthdefs, samples and SFZ instruments it uses to a manifest.
+//! When a manifest exists, `vibe run` loads everything in it before the
+//! script is evaluated, showing a progress bar.
+
+use crate::WarmupArgs;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use vibelang_core::preload::PreloadManifest;
+use vibelang_core::state::StateMessage;
+use vibelang_core::RuntimeHandle;
+
+/// Give up waiting for preloads after this long (the script loads the rest lazily).
+const PRELOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Width of the progress bar in characters.
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Validate a script and write its preload manifest.
+pub fn warmup(args: WarmupArgs) -> Result<()> {
+    // Validation logs every load at info level; only show problems
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let script = std::fs::read_to_string(&args.file)
+        .with_context(|| format!("Failed to read file: {}", args.file.display()))?;
+
+    let mut import_paths = args.import_paths;
+    let stdlib_path = PathBuf::from(vibelang_std::stdlib_path());
+    import_paths.push(stdlib_path.clone());
+    if let Some(parent) = stdlib_path.parent() {
+        import_paths.push(parent.to_path_buf());
+    }
+
+    println!("🔥 Warming up {}", args.file.display());
+    let result = vibelang_core::validate_script(&script, Some(&args.file), &import_paths);
+    for error in result.all_errors() {
+        match error.line {
+            Some(line) => eprintln!("   ⚠ line {}: {}", line, error.message),
+            None => eprintln!("   ⚠ {}", error.message),
+        }
+    }
+    if !result.parse_errors.is_empty() {
+        anyhow::bail!("Script has parse errors; no manifest written");
+    }
+
+    let manifest = PreloadManifest::from_validation(&result);
+    manifest.save(&args.file, &result.synthdef_bytes)?;
+
+    println!(
+        "   ✓ {} synthdef(s), {} sample(s), {} SFZ instrument(s)",
+        manifest.synthdefs.len(),
+        manifest.samples.len(),
+        manifest.sfz_instruments.len()
+    );
+    println!("   ✓ Wrote {}", PreloadManifest::manifest_path(&args.file).display());
+    Ok(())
+}
+
+/// Load everything in the script's preload manifest, if it has one.
+///
+/// Synthdefs are sent right away; samples and SFZ instruments are loaded by
+/// the runtime, and this waits (with a progress bar unless `quiet`) until
+/// they are all in state.
+pub fn preload(handle: &RuntimeHandle, file: &Path, quiet: bool) -> Result<()> {
+    let Some(mut manifest) = PreloadManifest::load(file)? else {
+        return Ok(());
+    };
+    // Files removed since the warmup would never finish loading
+    for assets in [&mut manifest.samples, &mut manifest.sfz_instruments] {
+        assets.retain(|asset| {
+            let exists = asset.path.exists();
+            if !exists {
+                log::warn!("[PRELOAD] '{}' no longer exists: {}", asset.id, asset.path.display());
+            }
+            exists
+        });
+    }
+    let started_at = Instant::now();
+
+    for (name, bytes) in manifest.read_synthdefs(file) {
+        let _ = handle.send(StateMessage::LoadSynthDef {
+            name: name.clone(),
+            bytes: bytes.clone(),
+        });
+        if let Err(e) = handle.scsynth().d_recv_bytes(bytes) {
+            log::warn!("[PRELOAD] Failed to send synthdef '{}': {}", name, e);
+        }
+    }
+
+    let total = manifest.asset_count();
+    if total > 0 {
+        handle.send(manifest.to_message())?;
+        loop {
+            let loaded = handle.with_state(|state| manifest.loaded_count(state));
+            if !quiet {
+                draw_progress(loaded, total);
+            }
+            if loaded >= total {
+                break;
+            }
+            if started_at.elapsed() > PRELOAD_TIMEOUT {
+                log::warn!("[PRELOAD] Timed out with {}/{} assets loaded", loaded, total);
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        if !quiet {
+            eprintln!();
+        }
+    }
+
+    log::info!(
+        "   ✓ Preloaded {} synthdef(s) and {} asset(s) in {:.1}s",
+        manifest.synthdefs.len(),
+        total,
+        started_at.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+fn draw_progress(loaded: usize, total: usize) {
+    let filled = PROGRESS_BAR_WIDTH * loaded / total.max(1);
+    eprint!(
+        "\r   Preloading [{}{}] {}/{}",
+        "█".repeat(filled),
+        "░".repeat(PROGRESS_BAR_WIDTH - filled),
+        loaded,
+        total
+    );
+    let _ = std::io::stderr().flush();
+}
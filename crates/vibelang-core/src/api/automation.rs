@@ -0,0 +1,63 @@
+//! Automation recording API for Rhai scripts.
+//!
+//! While recording, live parameter changes (HTTP, TUI, MIDI CC routes) are
+//! written into automation lanes that replay on every pass (see
+//! [`crate::automation`]).
+//!
+//! ```rhai
+//! automation_record(4);        // record 4-bar lanes from now on
+//! // ... sweep the lead's cutoff from the controller ...
+//! automation_stop();           // the sweep replays every 4 bars
+//! clear_automation(lead);      // drop the lead's lanes
+//! ```
+
+use crate::automation::DEFAULT_AUTOMATION_BARS;
+use crate::state::StateMessage;
+use rhai::{Dynamic, Engine, EvalAltResult};
+
+use super::macros::target_of;
+use super::require_handle;
+
+/// Record live parameter changes into lanes of `bars` bars.
+pub fn automation_record_bars(bars: i64) {
+    let _ = require_handle().send(StateMessage::StartAutomationRecording { bars: bars.max(1) as u32 });
+}
+
+/// Record live parameter changes into lanes of the default length.
+pub fn automation_record() {
+    automation_record_bars(DEFAULT_AUTOMATION_BARS as i64);
+}
+
+/// Stop recording automation.
+pub fn automation_stop() {
+    let _ = require_handle().send(StateMessage::StopAutomationRecording);
+}
+
+/// Whether automation is being recorded.
+pub fn automation_recording() -> bool {
+    require_handle().with_state(|state| state.automation.recording.is_some())
+}
+
+/// Remove all automation lanes.
+pub fn clear_all_automation() {
+    let _ = require_handle().send(StateMessage::ClearAutomation { target: None });
+}
+
+/// Remove the automation lanes of a voice, group, pattern, melody or fx.
+pub fn clear_automation(target: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let Some(target) = target_of(&target) else {
+        return Err("clear_automation() takes a voice, group, pattern, melody or fx".into());
+    };
+    let _ = require_handle().send(StateMessage::ClearAutomation { target: Some(target) });
+    Ok(())
+}
+
+/// Register the automation API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.register_fn("automation_record", automation_record);
+    engine.register_fn("automation_record", automation_record_bars);
+    engine.register_fn("automation_stop", automation_stop);
+    engine.register_fn("automation_recording", automation_recording);
+    engine.register_fn("clear_automation", clear_all_automation);
+    engine.register_fn("clear_automation", clear_automation);
+}
@@ -80,6 +80,25 @@ pub fn count_bars(input: &str) -> usize {
     split_into_bars(input).len()
 }
 
+/// Loop length for content ending at `content_end` beats: rounded up to
+/// whole bars of `beats_per_bar`, and at least one bar.
+///
+/// # Examples
+///
+/// ```
+/// use vibelang_core::api::auto_loop_length;
+///
+/// assert_eq!(auto_loop_length(0.0, 4.0), 4.0);
+/// assert_eq!(auto_loop_length(4.0, 4.0), 4.0);
+/// assert_eq!(auto_loop_length(4.5, 4.0), 8.0);
+/// assert_eq!(auto_loop_length(4.5, 3.0), 6.0);
+/// ```
+pub fn auto_loop_length(content_end: f64, beats_per_bar: f64) -> f64 {
+    // Content ending a hair past a bar line after float math still fits it
+    let bars = (content_end / beats_per_bar - 1e-9).ceil().max(1.0);
+    bars * beats_per_bar
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,84 @@
+//! Named checkpoints within a script.
+//!
+//! `checkpoint("groove")` marks a point in a file. When it is the selected
+//! checkpoint (`vibe run --until groove`, or stepping with `<`/`>` in the
+//! TUI), evaluation ends there, so a performer can stage one file in parts
+//! and reveal them progressively. Unselected checkpoints do nothing.
+
+use super::get_handle;
+use rhai::{Dynamic, Engine, EvalAltResult, NativeCallContext, Token};
+
+/// Prefix of the value passed to `ErrorTerminated` at the selected checkpoint.
+const CHECKPOINT_PREFIX: &str = "vibelang:checkpoint:";
+
+/// Register the checkpoint API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.register_fn("checkpoint", checkpoint);
+}
+
+/// Mark a checkpoint; ends evaluation when it is the selected one.
+fn checkpoint(ctx: NativeCallContext, name: &str) -> Result<(), Box<EvalAltResult>> {
+    let selected = get_handle().and_then(|h| h.with_state(|state| state.checkpoints.selected.clone()));
+    if selected.as_deref() == Some(name) {
+        log::info!("[CHECKPOINT] Stopped at checkpoint '{}'", name);
+        return Err(EvalAltResult::ErrorTerminated(
+            Dynamic::from(format!("{}{}", CHECKPOINT_PREFIX, name)),
+            ctx.call_position(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The checkpoint an evaluation stopped at, if that's what the error is.
+///
+/// Stopping at the selected checkpoint is not a failure.
+pub fn reached(err: &EvalAltResult) -> Option<String> {
+    match err.unwrap_inner() {
+        EvalAltResult::ErrorTerminated(value, _) => value
+            .read_lock::<rhai::ImmutableString>()?
+            .strip_prefix(CHECKPOINT_PREFIX)
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Names of the `checkpoint("...")` calls in a script, in order.
+pub fn find_checkpoints(script: &str) -> Vec<String> {
+    let engine = Engine::new_raw();
+    let inputs = [script];
+    let tokens: Vec<Token> = engine
+        .lex(&inputs)
+        .0
+        .map(|(token, _)| token)
+        .take_while(|token| !matches!(token, Token::EOF))
+        .collect();
+
+    let mut names: Vec<String> = Vec::new();
+    for window in tokens.windows(3) {
+        if let [Token::Identifier(id), Token::LeftParen, Token::StringConstant(name)] = window {
+            if id.as_str() == "checkpoint" && !names.iter().any(|n| n == name.as_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_checkpoints() {
+        let script = r#"
+            let kick = voice("kick");
+            checkpoint("intro");
+            // checkpoint("commented");
+            checkpoint( "groove" );
+            print("checkpoint(\"not a call\")");
+            checkpoint("intro");
+        "#;
+        assert_eq!(find_checkpoints(script), vec!["intro", "groove"]);
+    }
+}
@@ -0,0 +1,50 @@
+//! Chord API for Rhai scripts.
+//!
+//! `chord("C4", "maj7")` (or `chord("C4:maj7")`) builds a [`Chord`] that
+//! melodies take in their note arrays; see [`crate::chord`].
+//!
+//! ```rhai
+//! let pad = [chord("C4", "maj7").inversion(1), "~", chord("A3:m7").voicing("drop2"), "~"];
+//! melody("keys").on(piano).notes(pad).arp("updown", "1/16").start();
+//! ```
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+
+use crate::chord::{Chord, Voicing};
+use crate::scale::parse_root_note;
+
+/// Create a chord from a root ("C4", "F#") and a quality ("maj7", "m9").
+pub fn chord(root: &str, quality: &str) -> Result<Chord, Box<EvalAltResult>> {
+    Chord::new(parse_root_note(root), quality)
+        .ok_or_else(|| format!("chord: unknown chord quality '{}'", quality).into())
+}
+
+/// Create a chord from its name, e.g. "C4:maj7".
+pub fn chord_from_name(name: &str) -> Result<Chord, Box<EvalAltResult>> {
+    Chord::parse(name).ok_or_else(|| format!("chord: invalid chord '{}' (expected e.g. \"C4:maj7\")", name).into())
+}
+
+/// Voice the chord: "close", "drop2", "drop3" or "spread".
+fn voicing(chord: Chord, name: &str) -> Result<Chord, Box<EvalAltResult>> {
+    let Some(voicing) = Voicing::parse(name) else {
+        return Err(format!("voicing: unknown voicing '{}' (expected close, drop2, drop3 or spread)", name).into());
+    };
+    Ok(chord.with_voicing(voicing))
+}
+
+/// Register the chord API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.register_type_with_name::<Chord>("Chord");
+
+    engine.register_fn("chord", chord);
+    engine.register_fn("chord", chord_from_name);
+    engine.register_fn("inversion", |chord: Chord, inversion: i64| chord.with_inversion(inversion as i32));
+    engine.register_fn("voicing", voicing);
+    engine.register_fn("notes", |chord: &mut Chord| -> Array {
+        chord.notes().into_iter().map(|n| Dynamic::from(n as i64)).collect()
+    });
+    engine.register_get("root", |chord: &mut Chord| chord.root as i64);
+
+    engine.register_fn("to_string", |chord: &mut Chord| chord.to_string());
+    engine.register_fn("to_debug", |chord: &mut Chord| chord.to_string());
+}
@@ -0,0 +1,103 @@
+//! Clock output API for Rhai scripts.
+//!
+//! Sends audio-rate clock pulses derived from the transport tempo to a
+//! hardware output, for clocking Eurorack gear through a DC-coupled interface
+//! (see [`crate::clock_out`]).
+//!
+//! ```rhai
+//! // Sixteenth-note clock on output 3, run gate on output 4
+//! clock_out(3).ppqn(4).width(5.0).run_gate(4);
+//!
+//! clock_out_stop();
+//! ```
+
+use crate::clock_out::{ClockOutput, MAX_PPQN};
+use crate::state::StateMessage;
+use rhai::{CustomType, Engine, TypeBuilder};
+
+use super::{get_handle, require_handle};
+
+/// A clock output builder.
+///
+/// Every builder call updates the running clock, and `clock_out(channel)`
+/// starts from the current settings, so reloading a script leaves an
+/// unchanged clock running undisturbed.
+#[derive(Debug, Clone, CustomType)]
+pub struct ClockOut {
+    config: ClockOutput,
+}
+
+impl ClockOut {
+    /// Create a clock output builder, keeping the existing clock's settings.
+    pub fn new(channel: i64) -> Self {
+        let channel = channel.max(1) as u32;
+        let existing = get_handle().and_then(|h| h.with_state(|state| state.clock_output.clone()));
+        let config = match existing {
+            Some(config) => ClockOutput { channel, ..config },
+            None => ClockOutput::new(channel),
+        };
+        Self { config }
+    }
+
+    // === Builder methods ===
+
+    /// Set the pulses per quarter note (4 = sixteenths, 24 = MIDI clock rate).
+    pub fn ppqn(mut self, ppqn: i64) -> Self {
+        self.config.ppqn = ppqn.clamp(1, MAX_PPQN as i64) as u32;
+        self.sync_state();
+        self
+    }
+
+    /// Set the pulse length in milliseconds.
+    pub fn width(mut self, width_ms: f64) -> Self {
+        self.config.width_ms = width_ms.max(0.1);
+        self.sync_state();
+        self
+    }
+
+    /// Set the pulse and gate level (1.0 = full scale of the interface).
+    pub fn level(mut self, level: f64) -> Self {
+        self.config.level = level.clamp(-1.0, 1.0) as f32;
+        self.sync_state();
+        self
+    }
+
+    /// Hold a run gate high on another output while the transport plays.
+    pub fn run_gate(mut self, channel: i64) -> Self {
+        self.config.run_channel = Some(channel.max(1) as u32);
+        self.sync_state();
+        self
+    }
+
+    fn sync_state(&self) {
+        let _ = require_handle().send(StateMessage::SetClockOutput {
+            config: Some(self.config.clone()),
+        });
+    }
+}
+
+/// Start (or move) the clock output on a hardware output (1-based).
+pub fn clock_out(channel: i64) -> ClockOut {
+    let clock = ClockOut::new(channel);
+    clock.sync_state();
+    clock
+}
+
+/// Stop the clock output.
+pub fn clock_out_stop() {
+    let _ = require_handle().send(StateMessage::SetClockOutput { config: None });
+}
+
+/// Register the clock output API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<ClockOut>();
+
+    engine.register_fn("clock_out", clock_out);
+    engine.register_fn("clock_out_stop", clock_out_stop);
+
+    // Builder methods
+    engine.register_fn("ppqn", ClockOut::ppqn);
+    engine.register_fn("width", ClockOut::width);
+    engine.register_fn("level", ClockOut::level);
+    engine.register_fn("run_gate", ClockOut::run_gate);
+}
@@ -2,7 +2,8 @@
 //!
 //! Manages per-script state like current group path and script directory.
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 /// Source location information for an entity definition.
@@ -33,6 +34,21 @@ impl SourceLocation {
     }
 }
 
+/// How plain numbers given where a time is expected are treated.
+///
+/// Plain numbers are read as beats. Scripts migrating to explicit units
+/// (`8.beats`, `4.bars`) can have them reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnitChecks {
+    /// Accept plain numbers silently.
+    #[default]
+    Off,
+    /// Log a warning the first time each function gets a plain number.
+    Warn,
+    /// Fail the script on plain numbers.
+    Error,
+}
+
 /// A callback error captured during script execution.
 #[derive(Clone, Debug)]
 pub struct CallbackError {
@@ -61,6 +77,29 @@ thread_local! {
 
     /// Callback errors collected during script execution.
     static CALLBACK_ERRORS: RefCell<Vec<CallbackError>> = const { RefCell::new(Vec::new()) };
+
+    /// Current entity namespace stack for `namespace()` blocks and imports.
+    static NAMESPACE_STACK: RefCell<Vec<NamespaceFrame>> = const { RefCell::new(Vec::new()) };
+
+    /// Names exported unprefixed, with the namespace that exported them.
+    static EXPORTED_NAMES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+
+    /// Namespaces of imported modules, with the import path that claimed them.
+    static IMPORT_NAMESPACES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+
+    /// How plain numbers given as times are treated.
+    static UNIT_CHECKS: Cell<UnitChecks> = const { Cell::new(UnitChecks::Off) };
+
+    /// Functions already warned about plain numbers.
+    static UNIT_WARNINGS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// A namespace on the context stack.
+struct NamespaceFrame {
+    /// Full prefix, e.g. "song.verse1".
+    prefix: String,
+    /// Names created without the prefix (see [`export_name`]).
+    exports: HashSet<String>,
 }
 
 /// Push a group onto the context stack.
@@ -98,6 +137,91 @@ pub fn current_group_path() -> String {
     })
 }
 
+/// Push a namespace onto the context stack.
+pub fn push_namespace(name: &str) {
+    NAMESPACE_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let prefix = match stack.last() {
+            Some(current) => format!("{}.{}", current.prefix, name),
+            None => name.to_string(),
+        };
+        stack.push(NamespaceFrame {
+            prefix,
+            exports: HashSet::new(),
+        });
+    });
+}
+
+/// Pop a namespace from the context stack.
+pub fn pop_namespace() {
+    NAMESPACE_STACK.with(|stack| {
+        stack.borrow_mut().pop();
+    });
+}
+
+/// Qualify an entity name with the current namespace, e.g. "verse1.kick".
+///
+/// Names exported from the current namespace are left as they are.
+pub fn namespaced(name: &str) -> String {
+    NAMESPACE_STACK.with(|stack| match stack.borrow().last() {
+        Some(frame) if !frame.exports.contains(name) => format!("{}.{}", frame.prefix, name),
+        _ => name.to_string(),
+    })
+}
+
+/// Export a name from the current namespace, so entities created with it
+/// aren't prefixed.
+///
+/// Fails if another namespace already exported the same name.
+pub fn export_name(name: &str) -> Result<(), String> {
+    NAMESPACE_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let Some(frame) = stack.last_mut() else {
+            return Ok(());
+        };
+        EXPORTED_NAMES.with(|exported| {
+            let mut exported = exported.borrow_mut();
+            match exported.get(name) {
+                Some(owner) if *owner != frame.prefix => Err(format!(
+                    "'{}' is exported by both '{}' and '{}'",
+                    name, owner, frame.prefix
+                )),
+                _ => {
+                    exported.insert(name.to_string(), frame.prefix.clone());
+                    frame.exports.insert(name.to_string());
+                    Ok(())
+                }
+            }
+        })
+    })
+}
+
+/// Pick the namespace of an imported module.
+///
+/// The file name without extension ("stdlib/leads/lead.vibe" -> "lead"),
+/// qualified with parent directories when another import already uses it
+/// ("pads.lead").
+pub fn import_namespace(path: &str) -> String {
+    let trimmed = path.trim_end_matches(".vibe");
+    let parts: Vec<&str> = trimmed
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty() && *p != "." && *p != "..")
+        .collect();
+    IMPORT_NAMESPACES.with(|claimed| {
+        let mut claimed = claimed.borrow_mut();
+        let mut name = trimmed.to_string();
+        for len in 1..=parts.len() {
+            let candidate = parts[parts.len() - len..].join(".");
+            if claimed.get(&candidate).is_none_or(|owner| owner == trimmed) {
+                name = candidate;
+                break;
+            }
+        }
+        claimed.insert(name.clone(), trimmed.to_string());
+        name
+    })
+}
+
 /// Set the script directory.
 pub fn set_script_dir(dir: PathBuf) {
     SCRIPT_DIR.with(|d| {
@@ -133,6 +257,34 @@ pub fn reset() {
     CALLBACK_ERRORS.with(|e| {
         e.borrow_mut().clear();
     });
+    NAMESPACE_STACK.with(|stack| {
+        stack.borrow_mut().clear();
+    });
+    EXPORTED_NAMES.with(|exported| {
+        exported.borrow_mut().clear();
+    });
+    IMPORT_NAMESPACES.with(|claimed| {
+        claimed.borrow_mut().clear();
+    });
+    UNIT_CHECKS.with(|checks| checks.set(UnitChecks::Off));
+    UNIT_WARNINGS.with(|warned| {
+        warned.borrow_mut().clear();
+    });
+}
+
+/// Set how plain numbers given as times are treated.
+pub fn set_unit_checks(checks: UnitChecks) {
+    UNIT_CHECKS.with(|c| c.set(checks));
+}
+
+/// How plain numbers given as times are treated.
+pub fn unit_checks() -> UnitChecks {
+    UNIT_CHECKS.with(|c| c.get())
+}
+
+/// Whether `function` should warn about a plain number; true only once per function.
+pub fn first_unit_warning(function: &str) -> bool {
+    UNIT_WARNINGS.with(|warned| warned.borrow_mut().insert(function.to_string()))
 }
 
 /// Record a callback error.
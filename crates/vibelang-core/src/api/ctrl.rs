@@ -0,0 +1,386 @@
+//! Control pattern API for Rhai scripts.
+//!
+//! Control patterns sequence a parameter instead of notes: each step sets a
+//! parameter of a voice, group or effect. In step mode (the default) the
+//! parameter jumps to each value; in slide mode it glides from one value to
+//! the next over the steps between them, for 303-style filter sweeps.
+//!
+//! They are stored and scheduled as patterns whose events carry fades, so
+//! they loop, launch and clip into sequences like any pattern.
+
+use crate::events::{BeatEvent, FadeClip, FadeCurve, FadeTargetType, Pattern as PatternData};
+use crate::scheduler::LoopKind;
+use crate::sequences::{ClipMode, ClipSource, SequenceClip, SequenceDefinition};
+use crate::state::{LoopStatus, QuotaKind, StateMessage};
+use rhai::{CustomType, Dynamic, Engine, EvalAltResult, NativeCallContext, Position, TypeBuilder};
+
+use super::context::{self, SourceLocation};
+use super::helpers::{beats_arg, current_time_signature};
+use super::{check_quota, require_handle};
+
+/// How a control pattern moves between its steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrlMode {
+    /// Jump to each step's value.
+    Step,
+    /// Glide from each step's value to the next one.
+    Slide,
+}
+
+impl CtrlMode {
+    /// Parse a mode name ("step" or "slide").
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "step" => Some(CtrlMode::Step),
+            "slide" => Some(CtrlMode::Slide),
+            _ => None,
+        }
+    }
+}
+
+/// A control pattern builder.
+#[derive(Debug, Clone, CustomType)]
+pub struct Ctrl {
+    /// Control pattern name.
+    pub name: String,
+    /// Kind of entity the parameter belongs to.
+    target_type: FadeTargetType,
+    /// Name of the voice, group or effect.
+    target_name: Option<String>,
+    /// Parameter set by the steps.
+    param: Option<String>,
+    /// Value of each step; `None` holds the previous value.
+    steps: Vec<Option<f64>>,
+    /// Loop length in beats; one bar when `None`.
+    length: Option<f64>,
+    /// Step or slide.
+    mode: CtrlMode,
+    /// Launch grid in beats, overriding the global quantization.
+    launch_quantization: Option<f64>,
+    /// Group path.
+    group_path: String,
+    /// Source location where this control pattern was defined.
+    source_location: SourceLocation,
+}
+
+impl Ctrl {
+    /// Create a new control pattern with the given name and source location from NativeCallContext.
+    pub fn new(ctx: NativeCallContext, name: String) -> Self {
+        let pos = ctx.call_position();
+        let source_location = SourceLocation::new(
+            context::get_current_script_file(),
+            if pos.is_none() { None } else { pos.line().map(|l| l as u32) },
+            if pos.is_none() { None } else { pos.position().map(|c| c as u32) },
+        );
+        Self {
+            name,
+            target_type: FadeTargetType::Voice,
+            target_name: None,
+            param: None,
+            steps: Vec::new(),
+            length: None,
+            mode: CtrlMode::Step,
+            launch_quantization: None,
+            group_path: context::current_group_path(),
+            source_location,
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Set the step values, spread evenly over the loop.
+    ///
+    /// `"."`, `"-"`, `"_"` or `()` hold the previous value for a step.
+    ///
+    /// # Example
+    /// ```rhai
+    /// ctrl("acid_sweep").steps([200, 400, 800, 1600]).target(bass, "cutoff").start();
+    /// ```
+    pub fn steps(mut self, values: rhai::Array) -> Result<Self, Box<EvalAltResult>> {
+        self.steps = values
+            .into_iter()
+            .map(|value| {
+                if value.is_unit() {
+                    return Ok(None);
+                }
+                if let Ok(v) = value.as_float() {
+                    return Ok(Some(v));
+                }
+                if let Ok(v) = value.as_int() {
+                    return Ok(Some(v as f64));
+                }
+                match value.into_immutable_string() {
+                    Ok(hold) if matches!(hold.as_str(), "." | "-" | "_") => Ok(None),
+                    Ok(other) => Err(format!("steps: expected numbers or \".\", got \"{}\"", other).into()),
+                    Err(t) => Err(format!("steps: expected numbers or \".\", got {}", t).into()),
+                }
+            })
+            .collect::<Result<_, Box<EvalAltResult>>>()?;
+        Ok(self)
+    }
+
+    /// Set a parameter of a voice.
+    pub fn target(mut self, voice: super::voice::Voice, param: String) -> Self {
+        self.target_type = FadeTargetType::Voice;
+        self.target_name = Some(voice.name);
+        self.param = Some(param);
+        self
+    }
+
+    /// Set a parameter of a voice, by name.
+    pub fn target_by_name(mut self, voice_name: String, param: String) -> Self {
+        self.target_type = FadeTargetType::Voice;
+        self.target_name = Some(voice_name);
+        self.param = Some(param);
+        self
+    }
+
+    /// Set a parameter of a group.
+    pub fn target_group(mut self, group_path: String, param: String) -> Self {
+        self.target_type = FadeTargetType::Group;
+        self.target_name = Some(group_path);
+        self.param = Some(param);
+        self
+    }
+
+    /// Set a parameter of an effect.
+    pub fn target_effect(mut self, effect_id: String, param: String) -> Self {
+        self.target_type = FadeTargetType::Effect;
+        self.target_name = Some(effect_id);
+        self.param = Some(param);
+        self
+    }
+
+    /// Set how the parameter moves between steps: "step" or "slide".
+    pub fn mode(mut self, mode: String) -> Result<Self, Box<EvalAltResult>> {
+        self.mode = CtrlMode::parse(&mode)
+            .ok_or_else(|| format!("mode: expected \"step\" or \"slide\", got \"{}\"", mode))?;
+        Ok(self)
+    }
+
+    /// Glide from each step's value to the next one.
+    pub fn slide(self) -> Self {
+        Self { mode: CtrlMode::Slide, ..self }
+    }
+
+    /// Set the loop length the steps spread over (e.g. `2.bars`; one bar by default).
+    pub fn len(mut self, length: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.length = Some(beats_arg("len", &length)?);
+        Ok(self)
+    }
+
+    /// Launch on `grid` instead of the global quantization (0 = immediately).
+    pub fn launch_quantize(mut self, grid: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.launch_quantization = Some(beats_arg("launch_quantize", &grid)?.max(0.0));
+        Ok(self)
+    }
+
+    // === Actions ===
+
+    /// Loop length in beats: the explicit length, or one bar.
+    fn loop_length(&self) -> f64 {
+        self.length.unwrap_or_else(|| current_time_signature().beats_per_bar())
+    }
+
+    /// Register and apply the control pattern (chainable).
+    pub fn apply(self) -> Result<Self, Box<EvalAltResult>> {
+        let (Some(target_name), Some(param)) = (&self.target_name, &self.param) else {
+            return Err(format!("ctrl '{}': set the parameter with .target(voice, \"param\")", self.name).into());
+        };
+        let loop_length = self.loop_length();
+        let events = ctrl_events(&self.steps, loop_length, self.mode)
+            .into_iter()
+            .map(|step| {
+                let mut event = BeatEvent::new(step.beat, "ctrl");
+                event.group_path = Some(self.group_path.clone());
+                event.fade = Some(FadeClip {
+                    name: self.name.clone(),
+                    sequence_name: None,
+                    target_type: self.target_type.clone(),
+                    target_name: target_name.clone(),
+                    param_name: param.clone(),
+                    start_value: step.from as f32,
+                    target_value: step.to as f32,
+                    duration_beats: step.duration,
+                    curve: FadeCurve::Linear,
+                });
+                event
+            })
+            .collect();
+
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::CreatePattern {
+            name: self.name.clone(),
+            group_path: self.group_path.clone(),
+            voice_name: None,
+            pattern: PatternData {
+                name: self.name.clone(),
+                events,
+                loop_length_beats: loop_length,
+                phase_offset: 0.0,
+            },
+            source_location: self.source_location.clone(),
+            step_pattern: None,
+            text: None,
+        });
+        let _ = handle.send(StateMessage::SetLoopConditions {
+            name: self.name.clone(),
+            kind: LoopKind::Pattern,
+            conditions: Vec::new(),
+        });
+
+        Ok(self)
+    }
+
+    /// Start the control pattern looping (chainable).
+    ///
+    /// Like `pattern.start()`, this plays it in an implicit sequence.
+    pub fn start(self) -> Result<Self, Box<EvalAltResult>> {
+        let applied = self.apply()?;
+        let loop_length = applied.loop_length();
+
+        let seq_name = format!("_seq_{}", applied.name);
+        let mut seq_def = SequenceDefinition::new(seq_name.clone())
+            .with_loop_beats(loop_length)
+            .with_clip(SequenceClip::new(
+                0.0,
+                loop_length,
+                ClipSource::Pattern(applied.name.clone()),
+                ClipMode::Loop,
+            ));
+        seq_def.launch_quantization = applied.launch_quantization;
+
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::CreateSequence { sequence: seq_def });
+        let _ = handle.send(StateMessage::StartSequence { name: seq_name });
+
+        Ok(applied)
+    }
+
+    /// Stop the control pattern; the parameter keeps its last value.
+    pub fn stop(&mut self) {
+        let seq_name = format!("_seq_{}", self.name);
+        let _ = require_handle().send(StateMessage::StopSequence { name: seq_name });
+    }
+
+    /// Check if the control pattern is playing.
+    pub fn is_playing(&mut self) -> bool {
+        require_handle().with_state(|state| {
+            state
+                .patterns
+                .get(&self.name)
+                .is_some_and(|p| matches!(p.status, LoopStatus::Playing { .. }))
+        })
+    }
+}
+
+/// A parameter change of a control pattern.
+#[derive(Debug, Clone, PartialEq)]
+struct CtrlStep {
+    /// Beat of the step within the loop.
+    beat: f64,
+    /// Value the step starts at.
+    from: f64,
+    /// Value the step ends at.
+    to: f64,
+    /// Beats from `from` to `to` (0 jumps).
+    duration: f64,
+}
+
+/// Parameter changes of `steps` spread over `loop_length` beats.
+///
+/// Held steps add no change. In slide mode each value glides to the next
+/// one (wrapping around the loop) over the steps up to it.
+fn ctrl_events(steps: &[Option<f64>], loop_length: f64, mode: CtrlMode) -> Vec<CtrlStep> {
+    let step_beats = loop_length / steps.len().max(1) as f64;
+    let set: Vec<(usize, f64)> = steps.iter().enumerate().filter_map(|(i, v)| v.map(|v| (i, v))).collect();
+    set.iter()
+        .enumerate()
+        .map(|(n, &(index, value))| {
+            let (to, duration) = match mode {
+                CtrlMode::Slide if set.len() > 1 => {
+                    let (next_index, next_value) = set[(n + 1) % set.len()];
+                    let gap = (next_index + steps.len() - index) % steps.len();
+                    (next_value, gap as f64 * step_beats)
+                }
+                _ => (value, 0.0),
+            };
+            CtrlStep { beat: index as f64 * step_beats, from: value, to, duration }
+        })
+        .collect()
+}
+
+/// Create a new control pattern builder with source location tracking.
+pub fn ctrl(ctx: NativeCallContext, name: String) -> Result<Ctrl, Box<EvalAltResult>> {
+    let name = context::namespaced(&name);
+    check_quota(QuotaKind::Patterns, &name)?;
+    Ok(Ctrl::new(ctx, name))
+}
+
+/// Register control pattern API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<Ctrl>();
+
+    // Constructor
+    engine.register_fn("ctrl", ctrl);
+
+    // Builder methods
+    engine.register_fn("steps", Ctrl::steps);
+    engine.register_fn("target", Ctrl::target);
+    engine.register_fn("target", Ctrl::target_by_name);
+    engine.register_fn("target_group", Ctrl::target_group);
+    engine.register_fn("target_effect", Ctrl::target_effect);
+    engine.register_fn("mode", Ctrl::mode);
+    engine.register_fn("slide", Ctrl::slide);
+    engine.register_fn("len", Ctrl::len);
+    engine.register_fn("launch_quantize", Ctrl::launch_quantize);
+
+    // Actions
+    engine.register_fn("apply", Ctrl::apply);
+    engine.register_fn("start", Ctrl::start);
+    engine.register_fn("stop", Ctrl::stop);
+    engine.register_fn("launch", Ctrl::start);
+    engine.register_fn("is_playing", Ctrl::is_playing);
+
+    // Properties
+    engine.register_get("name", |c: &mut Ctrl| c.name.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ctrl_steps_jump_or_slide_and_drive_the_parameter() {
+        let steps = [Some(200.0), None, Some(800.0), Some(1600.0)];
+        let jumps = ctrl_events(&steps, 4.0, CtrlMode::Step);
+        assert_eq!(jumps.iter().map(|s| (s.beat, s.to, s.duration)).collect::<Vec<_>>(), vec![(0.0, 200.0, 0.0), (2.0, 800.0, 0.0), (3.0, 1600.0, 0.0)]);
+        // Slides reach the next value when it plays; the last one wraps around
+        let slides = ctrl_events(&steps, 4.0, CtrlMode::Slide);
+        assert_eq!(slides.iter().map(|s| (s.from, s.to, s.duration)).collect::<Vec<_>>(), vec![(200.0, 800.0, 2.0), (800.0, 1600.0, 1.0), (1600.0, 200.0, 1.0)]);
+
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                let bass = voice("bass").synth("acid");
+                ctrl("acid_sweep").steps([200, 400, ".", 1600]).target(bass, "cutoff").start();
+                "#,
+            )
+            .unwrap();
+        sim.start();
+        let cutoff = |sim: &crate::runtime::Simulation| sim.handle().with_state(|s| s.voices["bass"].params.get("cutoff").copied());
+        // Changes land in state with the lookahead, like the notes they go with
+        sim.advance(0.25);
+        assert_eq!(cutoff(&sim), Some(200.0));
+        sim.advance(1.0);
+        assert_eq!(cutoff(&sim), Some(400.0));
+        sim.advance(2.0);
+        assert_eq!(cutoff(&sim), Some(1600.0));
+        let beats: Vec<f64> = sim.events().iter().filter(|e| e.event.fade.is_some()).map(|e| e.beat).collect();
+        assert_eq!(beats, vec![0.0, 1.0, 3.0]);
+        assert!(engine.run(r#"ctrl("nowhere").steps([1, 2]).start();"#).is_err());
+    }
+}
@@ -1,10 +1,17 @@
 //! Global API functions.
 //!
-//! These functions control global runtime state like tempo, transport, and quantization.
+//! These functions control global runtime state like tempo, transport, quantization
+//! and the session key.
 
-use crate::state::StateMessage;
-use rhai::Engine;
+use crate::gc::GcPolicy;
+use crate::musical_key::MusicalKey;
+use crate::quantization::LaunchKind;
+use crate::state::{Quotas, StateMessage};
+use crate::timing::{Beats, TimeSignature};
+use rhai::{Dynamic, Engine, EvalAltResult};
+use std::time::Duration;
 
+use super::helpers::{beats_arg, span_arg};
 use super::require_handle;
 
 /// Register global functions with the Rhai engine.
@@ -19,15 +26,58 @@ pub fn register(engine: &mut Engine) {
 
     // Quantization
     engine.register_fn("set_quantization", set_quantization);
+    engine.register_fn("set_launch_quantization", set_launch_quantization);
+    engine.register_fn("clear_launch_quantization", clear_launch_quantization);
+
+    // Session key
+    engine.register_fn("set_key", set_key);
+    engine.register_fn("get_key", get_key);
+    engine.register_fn("clear_key", clear_key);
 
     // Transport
     engine.register_fn("get_current_beat", get_current_beat);
     engine.register_fn("get_current_bar", get_current_bar);
     engine.register_fn("nudge_transport", nudge_transport);
     engine.register_fn("jump_to_start", jump_to_start);
+    engine.register_fn("panic", panic);
+    engine.register_fn("capture_osc", capture_osc);
+    engine.register_fn("stop_osc_capture", stop_osc_capture);
+    engine.register_fn("log_events", log_events);
+    engine.register_fn("stop_event_log", stop_event_log);
+
+    // Locators
+    engine.register_fn("marker", marker);
+    engine.register_fn("remove_marker", remove_marker);
+    engine.register_fn("jump_to", jump_to);
+    engine.register_fn("jump_to", jump_to_quantized);
+
+    // Loudness
+    engine.register_fn("set_loudness_target", set_loudness_target);
+    engine.register_fn("set_loudness_target", set_loudness_target_int);
+    engine.register_fn("clear_loudness_target", clear_loudness_target);
+    engine.register_fn("reset_loudness", reset_loudness);
+
+    // Click-free parameter smoothing
+    engine.register_fn("set_param_smoothing", set_param_smoothing);
+    engine.register_fn("set_param_smoothing", set_param_smoothing_int);
 
-    // Latency - TODO: Add SetLatency message
-    // engine.register_fn("set_latency_ms", set_latency_ms);
+    // CPU budget
+    engine.register_fn("set_cpu_budget", set_cpu_budget);
+    engine.register_fn("set_cpu_budget", set_cpu_budget_int);
+    engine.register_fn("set_cpu_policy", set_cpu_policy);
+    engine.register_fn("get_cpu_usage", get_cpu_usage);
+
+    // Quotas
+    engine.register_fn("set_quotas", set_quotas);
+
+    // Garbage collection
+    engine.register_fn("enable_gc", enable_gc);
+    engine.register_fn("set_gc_policy", set_gc_policy);
+
+    // Latency compensation
+    engine.register_fn("set_output_latency_ms", set_output_latency_ms);
+    engine.register_fn("set_output_latency_ms", set_output_latency_ms_int);
+    engine.register_fn("get_output_latency_ms", get_output_latency_ms);
 }
 
 /// Set the tempo in BPM.
@@ -48,18 +98,79 @@ pub fn get_tempo() -> f64 {
 }
 
 /// Set the time signature.
+///
+/// Bars given later in the script already convert with the new signature.
 pub fn set_time_signature(numerator: i64, denominator: i64) {
     let handle = require_handle();
+    let signature = TimeSignature::new(numerator.max(1) as u32, denominator.max(1) as u32);
+    handle.with_state_mut(|state| state.time_signature = signature);
     let _ = handle.send(StateMessage::SetTimeSignature {
-        numerator: numerator as u32,
-        denominator: denominator as u32,
+        numerator: signature.numerator,
+        denominator: signature.denominator,
     });
 }
 
-/// Set the quantization in beats.
-pub fn set_quantization(beats: f64) {
+/// Set the quantization grid (e.g. `set_quantization(1.bars)`).
+pub fn set_quantization(grid: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let grid = span_arg("set_quantization", &grid)?;
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetQuantization { grid });
+    Ok(())
+}
+
+/// Set the default launch grid of an entity type: "pattern", "melody",
+/// "sequence" or "fade" (e.g. `set_launch_quantization("sequence", 8.bars)`).
+///
+/// Entities launched with their own `launch_quantize` keep it; the rest of
+/// the type launches on this grid instead of the global quantization.
+pub fn set_launch_quantization(kind: &str, grid: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let kind = launch_kind_arg("set_launch_quantization", kind)?;
+    let grid = span_arg("set_launch_quantization", &grid)?;
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetLaunchQuantization { kind, grid: Some(grid) });
+    Ok(())
+}
+
+/// Launch an entity type on the global quantization again.
+pub fn clear_launch_quantization(kind: &str) -> Result<(), Box<EvalAltResult>> {
+    let kind = launch_kind_arg("clear_launch_quantization", kind)?;
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetLaunchQuantization { kind, grid: None });
+    Ok(())
+}
+
+fn launch_kind_arg(function: &str, kind: &str) -> Result<LaunchKind, Box<EvalAltResult>> {
+    LaunchKind::parse(kind).ok_or_else(|| {
+        format!("{}: unknown type '{}' (expected \"pattern\", \"melody\", \"sequence\" or \"fade\")", function, kind).into()
+    })
+}
+
+/// Set the session key (e.g. "A minor", "F#m", "Eb") that key-matched
+/// sample voices are transposed to.
+pub fn set_key(key: &str) -> Result<(), Box<EvalAltResult>> {
+    let Some(key) = MusicalKey::parse(key) else {
+        return Err(format!("set_key: unknown key '{}' (expected e.g. \"A minor\" or \"Eb\")", key).into());
+    };
     let handle = require_handle();
-    let _ = handle.send(StateMessage::SetQuantization { beats });
+    // Visible to the rest of the script right away (melodies snap to it)
+    handle.with_state_mut(|state| state.session_key = Some(key));
+    let _ = handle.send(StateMessage::SetSessionKey { key: Some(key) });
+    Ok(())
+}
+
+/// Get the session key (e.g. "A minor"), or "" if none is set.
+pub fn get_key() -> String {
+    let handle = require_handle();
+    handle
+        .with_state(|state| state.session_key.map(|key| key.to_string()))
+        .unwrap_or_default()
+}
+
+/// Clear the session key; key-matched voices play untransposed.
+pub fn clear_key() {
+    let handle = require_handle();
+    handle.with_state_mut(|state| state.session_key = None);
+    let _ = handle.send(StateMessage::SetSessionKey { key: None });
 }
 
 /// Get the current beat position.
@@ -77,16 +188,398 @@ pub fn get_current_bar() -> i64 {
     })
 }
 
-/// Nudge the transport by a number of beats.
-pub fn nudge_transport(beats: f64) {
+/// Nudge the transport by a time (e.g. `nudge_transport(-1.beats)`).
+pub fn nudge_transport(amount: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let beats = beats_arg("nudge_transport", &amount)?;
     let handle = require_handle();
     let current = handle.with_state(|state| state.current_beat);
     let new_beat = (current + beats).max(0.0);
-    let _ = handle.send(StateMessage::SeekTransport { beat: new_beat });
+    let _ = handle.send(StateMessage::SeekTransport {
+        position: Beats(new_beat).into(),
+    });
+    Ok(())
 }
 
 /// Jump to the start of the transport (beat 0).
 pub fn jump_to_start() {
     let handle = require_handle();
-    let _ = handle.send(StateMessage::SeekTransport { beat: 0.0 });
+    let _ = handle.send(StateMessage::SeekTransport {
+        position: Beats(0.0).into(),
+    });
+}
+
+/// Silence all sounding notes: gate off and free note synths, drop pending
+/// note-offs and send all-notes-off to MIDI outputs.
+pub fn panic() {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::Panic);
+}
+
+/// Capture all OSC traffic with scsynth to `path` (view with `vibe osc-dump`).
+pub fn capture_osc(path: String) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetOscCapture {
+        path: Some(path.into()),
+    });
+}
+
+/// Stop the OSC capture.
+pub fn stop_osc_capture() {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetOscCapture { path: None });
+}
+
+/// Log every fired event to `path`, as CSV for `.csv` files and JSON
+/// otherwise. The file is written when the log stops or the runtime exits.
+pub fn log_events(path: String) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetEventLog {
+        path: Some(path.into()),
+    });
+}
+
+/// Stop the event log and write its file.
+pub fn stop_event_log() {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetEventLog { path: None });
+}
+
+/// Set a named song position locator (e.g. `marker("drop", 64.bars)`).
+pub fn marker(name: String, position: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let position = span_arg("marker", &position)?;
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetMarker { name, position });
+    Ok(())
+}
+
+/// Remove a locator.
+pub fn remove_marker(name: String) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::RemoveMarker { name });
+}
+
+/// Jump to a locator at the next bar line.
+pub fn jump_to(name: String) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::JumpToMarker { name, quantization: None });
+}
+
+/// Jump to a locator at the next multiple of `grid` (0 = immediately).
+pub fn jump_to_quantized(name: String, grid: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let grid = span_arg("jump_to", &grid)?;
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::JumpToMarker {
+        name,
+        quantization: Some(grid),
+    });
+    Ok(())
+}
+
+/// Set the master loudness target in LUFS (e.g. -14.0 for streaming).
+///
+/// A warning is logged whenever short-term loudness goes above the target.
+pub fn set_loudness_target(lufs: f64) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetLoudnessTarget {
+        target_lufs: Some(lufs),
+    });
+}
+
+/// Set the master loudness target in LUFS (integer overload).
+pub fn set_loudness_target_int(lufs: i64) {
+    set_loudness_target(lufs as f64);
+}
+
+/// Remove the master loudness target.
+pub fn clear_loudness_target() {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetLoudnessTarget { target_lufs: None });
+}
+
+/// Restart the integrated loudness measurement.
+pub fn reset_loudness() {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::ResetLoudness);
+}
+
+/// Set how long changes of `param` on running nodes are ramped, in milliseconds.
+///
+/// `amp` is smoothed by 10 ms by default, which also sets the fade time of
+/// group mutes. 0 applies changes immediately.
+///
+/// # Example
+/// ```rhai
+/// set_param_smoothing("amp", 20);
+/// set_param_smoothing("cutoff", 5.0);
+/// ```
+pub fn set_param_smoothing(param: String, ms: f64) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetParamSmoothing {
+        param,
+        seconds: ms.max(0.0) / 1000.0,
+    });
+}
+
+/// Set a parameter's smoothing time in milliseconds (integer overload).
+pub fn set_param_smoothing_int(param: String, ms: i64) {
+    set_param_smoothing(param, ms as f64);
+}
+
+/// Set the average server CPU load (percent) above which the mix is degraded.
+pub fn set_cpu_budget(percent: f64) {
+    let handle = require_handle();
+    let mut policy = handle.with_state(|state| state.performance.policy.clone());
+    policy.threshold = percent as f32;
+    let _ = handle.send(StateMessage::SetCpuPolicy { policy });
+}
+
+/// Set the CPU budget in percent (integer overload).
+pub fn set_cpu_budget_int(percent: i64) {
+    set_cpu_budget(percent as f64);
+}
+
+/// Configure what happens when the CPU budget is exceeded.
+///
+/// Keys: `threshold`, `recovery_margin` (percent), `drop_voices`,
+/// `reduce_polyphony`, `postpone_fades` (bool), and for a tick loop that
+/// falls behind `drop_stale` (bool, off by default), `stale_ms` (lateness before an event is
+/// stale) and `ghost_ratio` (fraction of the loudest note of a pattern or
+/// melody below which its notes may be dropped).
+/// Missing keys keep their current value.
+pub fn set_cpu_policy(options: rhai::Map) {
+    let handle = require_handle();
+    let mut policy = handle.with_state(|state| state.performance.policy.clone());
+
+    let number = |key: &str| {
+        options.get(key).and_then(|v| {
+            v.as_float()
+                .ok()
+                .or_else(|| v.as_int().ok().map(|i| i as f64))
+        })
+    };
+    let flag = |key: &str| options.get(key).and_then(|v| v.as_bool().ok());
+
+    if let Some(v) = number("threshold") {
+        policy.threshold = v as f32;
+    }
+    if let Some(v) = number("recovery_margin") {
+        policy.recovery_margin = v as f32;
+    }
+    if let Some(v) = flag("drop_voices") {
+        policy.drop_low_priority_voices = v;
+    }
+    if let Some(v) = flag("reduce_polyphony") {
+        policy.reduce_polyphony = v;
+    }
+    if let Some(v) = flag("postpone_fades") {
+        policy.postpone_fades = v;
+    }
+    if let Some(v) = flag("drop_stale") {
+        policy.drop_stale_events = v;
+    }
+    if let Some(v) = number("stale_ms") {
+        policy.stale_threshold_ms = v.max(0.0) as f32;
+    }
+    if let Some(v) = number("ghost_ratio") {
+        policy.ghost_ratio = v.clamp(0.0, 1.0) as f32;
+    }
+
+    let _ = handle.send(StateMessage::SetCpuPolicy { policy });
+}
+
+/// Limit what the session may create.
+///
+/// Keys: `voices`, `patterns` (patterns and melodies together),
+/// `sequences`, `samples` and `buffer_mb` (memory of loaded samples).
+/// Missing keys are unlimited; `set_quotas(#{})` lifts all quotas. Creating
+/// an entity beyond a quota fails with a script error.
+pub fn set_quotas(options: rhai::Map) -> Result<(), Box<EvalAltResult>> {
+    let mut quotas = Quotas::default();
+    for (key, value) in options {
+        let number = value
+            .as_float()
+            .ok()
+            .or_else(|| value.as_int().ok().map(|i| i as f64))
+            .filter(|v| *v >= 0.0)
+            .ok_or_else(|| format!("set_quotas: '{}' must be a non-negative number", key))?;
+        let count = Some(number as usize);
+        match key.as_str() {
+            "voices" => quotas.max_voices = count,
+            "patterns" => quotas.max_patterns = count,
+            "sequences" => quotas.max_sequences = count,
+            "samples" => quotas.max_samples = count,
+            "buffer_mb" => quotas.max_buffer_mb = Some(number),
+            _ => {
+                return Err(format!(
+                    "set_quotas: unknown quota '{}' (expected voices, patterns, sequences, samples or buffer_mb)",
+                    key
+                )
+                .into())
+            }
+        }
+    }
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetQuotas { quotas });
+    Ok(())
+}
+
+/// Turn on garbage collection with settings for multi-hour sessions.
+pub fn enable_gc() {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetGcPolicy {
+        policy: GcPolicy::recommended(),
+    });
+}
+
+/// Configure garbage collection.
+///
+/// Keys: `sample_idle_minutes` (free buffers of samples no voice used for
+/// that long), `synth_max_age_minutes` (forget tracked synths older than
+/// that) and `max_log_entries` (cap of the sequence run log). `()` turns a
+/// kind of collection off; missing keys keep their current value.
+pub fn set_gc_policy(options: rhai::Map) -> Result<(), Box<EvalAltResult>> {
+    let handle = require_handle();
+    let mut policy = handle.with_state(|state| state.gc.policy.clone());
+
+    for (key, value) in options {
+        let number = if value.is_unit() {
+            None
+        } else {
+            Some(
+                value
+                    .as_float()
+                    .ok()
+                    .or_else(|| value.as_int().ok().map(|i| i as f64))
+                    .filter(|v| *v >= 0.0)
+                    .ok_or_else(|| format!("set_gc_policy: '{}' must be a non-negative number or ()", key))?,
+            )
+        };
+        let minutes = number.map(|m| Duration::from_secs_f64(m * 60.0));
+        match key.as_str() {
+            "sample_idle_minutes" => policy.sample_idle = minutes,
+            "synth_max_age_minutes" => policy.synth_max_age = minutes,
+            "max_log_entries" => policy.max_log_entries = number.map(|n| n as usize),
+            _ => {
+                return Err(format!(
+                    "set_gc_policy: unknown key '{}' (expected sample_idle_minutes, synth_max_age_minutes or max_log_entries)",
+                    key
+                )
+                .into())
+            }
+        }
+    }
+
+    let _ = handle.send(StateMessage::SetGcPolicy { policy });
+    Ok(())
+}
+
+/// Get the average server CPU load in percent (0.0 until the first status reply).
+pub fn get_cpu_usage() -> f64 {
+    let handle = require_handle();
+    handle.with_state(|state| {
+        state
+            .performance
+            .status
+            .as_ref()
+            .map(|s| s.avg_cpu as f64)
+            .unwrap_or(0.0)
+    })
+}
+
+/// Set the output latency of the audio interface in milliseconds.
+///
+/// Hardware synths played over MIDI are triggered this much later (less
+/// their own `latency_ms`) so they sound together with the audio.
+pub fn set_output_latency_ms(ms: f64) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetOutputLatency { ms });
+}
+
+/// Set the output latency in milliseconds (integer overload).
+pub fn set_output_latency_ms_int(ms: i64) {
+    set_output_latency_ms(ms as f64);
+}
+
+/// Get the output latency of the audio interface in milliseconds.
+pub fn get_output_latency_ms() -> f64 {
+    let handle = require_handle();
+    handle.with_state(|state| state.output_latency_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quantization::LaunchKind;
+
+    #[test]
+    fn test_quotas_fail_the_script() {
+        let sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+
+        let err = engine
+            .run(
+                r#"
+                set_quotas(#{ voices: 2, patterns: 1 });
+                for i in 0..100 {
+                    voice(`v${i}`).synth("blip");
+                }
+                "#,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("voice 'v2' exceeds the quota of 2 voices"), "{}", err);
+
+        // Updating what exists is fine; one pattern more is not
+        engine.run(r#"voice("v1").gain(0.5); pattern("a").step("x...").apply();"#).unwrap();
+        assert!(engine.run(r#"melody("b").notes("C4");"#).is_err());
+        assert!(engine.run(r#"set_quotas(#{ voicez: 1 });"#).is_err());
+        engine.run(r#"set_quotas(#{}); melody("b").notes("C4");"#).unwrap();
+    }
+
+    #[test]
+    fn test_launch_quantization_per_type() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                set_quantization(1.bars);
+                set_launch_quantization("pattern", 1.beats);
+                set_launch_quantization("sequence", 8.bars);
+                voice("kick").synth("kick_909");
+                let hat = voice("hat").synth("hihat_808");
+                pattern("verse_hats").on(hat).step("x...").apply();
+                "#,
+            )
+            .unwrap();
+        sim.start();
+        sim.advance(5.5);
+
+        // Drums come in on the next beat, the section waits for the phrase,
+        // a pattern with its own grid keeps it
+        engine
+            .run(
+                r#"
+                pattern("four").on("kick").step("x...").start();
+                sequence("verse").loop_bars(1).clip(0..4, "verse_hats").start();
+                pattern("late").on("kick").step("x...").launch_quantize(2.bars).start();
+                "#,
+            )
+            .unwrap();
+        sim.advance(32.0);
+        let first = |pattern: &str| {
+            sim.events()
+                .iter()
+                .find(|e| e.event.pattern_name.as_deref() == Some(pattern))
+                .map(|e| e.beat)
+        };
+        assert_eq!(first("four"), Some(6.0));
+        assert_eq!(first("verse_hats"), Some(32.0));
+        assert_eq!(first("late"), Some(8.0));
+
+        assert!(engine.run(r#"set_launch_quantization("voice", 1.beats);"#).is_err());
+        engine.run(r#"clear_launch_quantization("sequences");"#).unwrap();
+        sim.advance(0.0);
+        assert!(sim.handle().with_state(|s| s.launch_quantization.get(LaunchKind::Sequence).is_none()));
+    }
 }
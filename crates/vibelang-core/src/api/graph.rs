@@ -0,0 +1,264 @@
+//! Playback graph API for Rhai scripts.
+//!
+//! Playback graphs switch between sequences ("sections") when transition
+//! conditions hold, for adaptive music in games and installations.
+//!
+//! ```rhai
+//! let music = playback_graph("game")
+//!     .section("explore", explore)
+//!     .section("combat", combat, "Combat")       // group used for crossfades
+//!     .transition("explore", "combat", when("intensity", ">", 0.7), "crossfade")
+//!     .transition("combat", "explore", after(bars(16)), "next_bar")
+//!     .transition("explore", "calm", on_cue(), "immediate")
+//!     .crossfade(bars(2));
+//! music.start();
+//! music.set("intensity", 0.9);
+//! ```
+
+use crate::playback_graph::{
+    CompareOp, GraphSection, GraphTransition, PlaybackGraph, TransitionCondition, TransitionStyle,
+};
+use crate::state::StateMessage;
+use rhai::{CustomType, Dynamic, Engine, EvalAltResult, TypeBuilder};
+
+use super::helpers::beats_arg;
+use super::require_handle;
+
+/// A playback graph builder.
+#[derive(Debug, Clone, CustomType)]
+pub struct Graph {
+    /// Graph name.
+    pub name: String,
+    /// Graph definition.
+    graph: PlaybackGraph,
+}
+
+impl Graph {
+    /// Create a new graph with the given name.
+    pub fn new(name: String) -> Self {
+        Self {
+            graph: PlaybackGraph::new(name.clone()),
+            name,
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Add a section played by a sequence (or sequence name).
+    pub fn section(self, name: String, sequence: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.add_section(name, sequence, None)
+    }
+
+    /// Add a section with a group that crossfades fade in and out.
+    pub fn section_with_group(
+        self,
+        name: String,
+        sequence: Dynamic,
+        group: String,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        self.add_section(name, sequence, Some(group))
+    }
+
+    fn add_section(
+        mut self,
+        name: String,
+        sequence: Dynamic,
+        group: Option<String>,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let sequence = if let Some(seq) = sequence.clone().try_cast::<super::sequence::Sequence>() {
+            seq.name
+        } else if let Ok(seq) = sequence.into_immutable_string() {
+            seq.to_string()
+        } else {
+            return Err(format!("section '{}': expected a sequence or sequence name", name).into());
+        };
+        if self.graph.section(&name).is_some() {
+            return Err(format!("graph '{}': duplicate section '{}'", self.name, name).into());
+        }
+        self.graph.sections.push(GraphSection { name, sequence, group });
+        Ok(self)
+    }
+
+    /// Add a transition. Style is "immediate", "next_bar" or "crossfade".
+    pub fn transition(
+        mut self,
+        from: String,
+        to: String,
+        condition: TransitionCondition,
+        style: &str,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let style = TransitionStyle::parse(style).ok_or_else(|| {
+            format!(
+                "unknown transition style '{}' (expected \"immediate\", \"next_bar\" or \"crossfade\")",
+                style
+            )
+        })?;
+        self.graph.transitions.push(GraphTransition {
+            from,
+            to,
+            condition,
+            style,
+        });
+        Ok(self)
+    }
+
+    /// Add a transition that switches at the next bar line.
+    pub fn transition_next_bar(
+        self,
+        from: String,
+        to: String,
+        condition: TransitionCondition,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        self.transition(from, to, condition, "next_bar")
+    }
+
+    /// Set the crossfade length (e.g. `2.bars`).
+    pub fn crossfade(mut self, length: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.graph.crossfade_beats = beats_arg("crossfade", &length)?.max(0.0);
+        Ok(self)
+    }
+
+    // === Actions ===
+
+    fn do_apply(&self) -> Result<(), Box<EvalAltResult>> {
+        self.graph.validate()?;
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::CreatePlaybackGraph {
+            graph: self.graph.clone(),
+        });
+        Ok(())
+    }
+
+    /// Register the graph (chainable).
+    pub fn apply(self) -> Result<Self, Box<EvalAltResult>> {
+        self.do_apply()?;
+        Ok(self)
+    }
+
+    /// Start the graph at its first section.
+    pub fn start(&mut self) -> Result<(), Box<EvalAltResult>> {
+        self.do_apply()?;
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::StartPlaybackGraph {
+            name: self.name.clone(),
+            section: None,
+        });
+        Ok(())
+    }
+
+    /// Start the graph at the given section.
+    pub fn start_at(&mut self, section: String) -> Result<(), Box<EvalAltResult>> {
+        self.do_apply()?;
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::StartPlaybackGraph {
+            name: self.name.clone(),
+            section: Some(section),
+        });
+        Ok(())
+    }
+
+    /// Stop the graph.
+    pub fn stop(&mut self) {
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::StopPlaybackGraph {
+            name: self.name.clone(),
+        });
+    }
+
+    /// Cue the graph, firing `on_cue()` transitions of the current section.
+    pub fn cue(&mut self) {
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::CueGraph {
+            name: self.name.clone(),
+        });
+    }
+
+    /// Set a variable tested by `when()` conditions.
+    pub fn set(&mut self, var: String, value: f64) {
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::SetGraphVar {
+            name: self.name.clone(),
+            var,
+            value,
+        });
+    }
+
+    /// Set a variable (integer version).
+    pub fn set_int(&mut self, var: String, value: i64) {
+        self.set(var, value as f64);
+    }
+
+    /// Get the current section ("" while stopped).
+    pub fn current_section(&mut self) -> String {
+        let handle = require_handle();
+        handle.with_state(|state| {
+            state
+                .playback_graphs
+                .get(&self.name)
+                .and_then(|graph| graph.current.clone())
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Create a new playback graph builder.
+pub fn playback_graph(name: String) -> Graph {
+    Graph::new(name)
+}
+
+/// Condition: the graph was cued.
+pub fn on_cue() -> TransitionCondition {
+    TransitionCondition::OnCue
+}
+
+/// Condition: the section has played for `length` (e.g. `after(8.bars)`).
+pub fn after(length: Dynamic) -> Result<TransitionCondition, Box<EvalAltResult>> {
+    Ok(TransitionCondition::After(beats_arg("after", &length)?))
+}
+
+/// Condition: a graph variable compares true, e.g. `when("intensity", ">", 0.7)`.
+pub fn when(var: String, op: &str, value: f64) -> Result<TransitionCondition, Box<EvalAltResult>> {
+    let op = CompareOp::parse(op).ok_or_else(|| {
+        format!("unknown comparison '{}' (expected >, >=, <, <=, == or !=)", op)
+    })?;
+    Ok(TransitionCondition::When { var, op, value })
+}
+
+/// Condition on a graph variable (integer version).
+pub fn when_int(var: String, op: &str, value: i64) -> Result<TransitionCondition, Box<EvalAltResult>> {
+    when(var, op, value as f64)
+}
+
+/// Register playback graph API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    // Register types
+    engine.build_type::<Graph>();
+    engine.register_type_with_name::<TransitionCondition>("Condition");
+
+    // Constructors
+    engine.register_fn("playback_graph", playback_graph);
+
+    // Conditions
+    engine.register_fn("on_cue", on_cue);
+    engine.register_fn("after", after);
+    engine.register_fn("when", when);
+    engine.register_fn("when", when_int);
+
+    // Builder methods
+    engine.register_fn("section", Graph::section);
+    engine.register_fn("section", Graph::section_with_group);
+    engine.register_fn("transition", Graph::transition);
+    engine.register_fn("transition", Graph::transition_next_bar);
+    engine.register_fn("crossfade", Graph::crossfade);
+
+    // Actions
+    engine.register_fn("apply", Graph::apply);
+    engine.register_fn("start", Graph::start);
+    engine.register_fn("start", Graph::start_at);
+    engine.register_fn("stop", Graph::stop);
+    engine.register_fn("cue", Graph::cue);
+    engine.register_fn("set", Graph::set);
+    engine.register_fn("set", Graph::set_int);
+    engine.register_fn("current_section", Graph::current_section);
+    engine.register_get("name", |g: &mut Graph| g.name.clone());
+}
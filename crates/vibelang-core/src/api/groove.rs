@@ -0,0 +1,179 @@
+//! Groove template API for Rhai scripts.
+//!
+//! A groove template gives voices their timing feel, so a multi-voice
+//! groove plays like a band (see [`crate::groove`]). Templates are saved to
+//! `.groove` files and loaded in other pieces; a voice's own `.feel()`
+//! takes precedence over the applied template.
+//!
+//! ```rhai
+//! groove("band").feel("bass", 10, 3).feel("hats", -4, 1).apply().save("band.groove");
+//! load_groove("band.groove").apply();
+//! ```
+
+use crate::groove::{GrooveTemplate, JitterDistribution, TimingFeel};
+use crate::state::StateMessage;
+use rhai::{CustomType, Engine, EvalAltResult, TypeBuilder};
+use std::path::PathBuf;
+
+use super::voice::Voice;
+use super::{context, get_handle, require_handle};
+
+/// A groove template builder.
+#[derive(Debug, Clone, CustomType)]
+pub struct Groove {
+    template: GrooveTemplate,
+}
+
+impl Groove {
+    /// Create a groove builder, starting from the applied template if it has this name.
+    pub fn new(name: String) -> Self {
+        let applied = get_handle()
+            .and_then(|h| h.with_state(|state| state.groove.clone()))
+            .filter(|groove| groove.name == name);
+        Self {
+            template: applied.unwrap_or_else(|| GrooveTemplate::new(name)),
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Set a voice's feel: `offset_ms` late (negative rushes) with `jitter_ms` of variation.
+    pub fn feel(mut self, voice: String, offset_ms: f64, jitter_ms: f64) -> Self {
+        self.template.feels.insert(voice, TimingFeel::new(offset_ms, jitter_ms));
+        self
+    }
+
+    /// Set a voice's feel (integer overload).
+    pub fn feel_int(self, voice: String, offset_ms: i64, jitter_ms: i64) -> Self {
+        self.feel(voice, offset_ms as f64, jitter_ms as f64)
+    }
+
+    /// Set a voice's feel with a jitter distribution ("normal" or "uniform").
+    pub fn feel_with(
+        mut self,
+        voice: String,
+        offset_ms: f64,
+        jitter_ms: f64,
+        distribution: String,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let Some(distribution) = JitterDistribution::parse(&distribution) else {
+            return Err(format!("feel: unknown jitter distribution '{}' (normal or uniform)", distribution).into());
+        };
+        self.template
+            .feels
+            .insert(voice, TimingFeel::new(offset_ms, jitter_ms).with_distribution(distribution));
+        Ok(self)
+    }
+
+    /// Set the feel of a voice object.
+    pub fn feel_voice(self, voice: Voice, offset_ms: f64, jitter_ms: f64) -> Self {
+        self.feel(voice.name, offset_ms, jitter_ms)
+    }
+
+    /// Set the feel of a voice object (integer overload).
+    pub fn feel_voice_int(self, voice: Voice, offset_ms: i64, jitter_ms: i64) -> Self {
+        self.feel(voice.name, offset_ms as f64, jitter_ms as f64)
+    }
+
+    /// Remove a voice from the template.
+    pub fn remove(mut self, voice: String) -> Self {
+        self.template.feels.remove(&voice);
+        self
+    }
+
+    /// Apply the template, replacing the applied one.
+    pub fn apply(self) -> Self {
+        let _ = require_handle().send(StateMessage::SetGroove {
+            groove: Some(self.template.clone()),
+        });
+        self
+    }
+
+    /// Save the template to a `.groove` file (relative to the script's directory).
+    pub fn save(self, path: String) -> Result<Self, Box<EvalAltResult>> {
+        let mut file = PathBuf::from(&path);
+        if file.is_relative() {
+            if let Some(dir) = context::get_script_dir() {
+                file = dir.join(file);
+            }
+        }
+        self.template.save(&file).map_err(|e| format!("{:#}", e))?;
+        Ok(self)
+    }
+}
+
+/// Create a groove template, or continue the applied one of this name.
+pub fn groove(name: String) -> Groove {
+    Groove::new(name)
+}
+
+/// Load a groove template from a `.groove` file (not applied yet).
+pub fn load_groove(path: String) -> Result<Groove, Box<EvalAltResult>> {
+    let file = context::resolve_file_or_error(&path)?;
+    let template = GrooveTemplate::load(&file).map_err(|e| format!("{:#}", e))?;
+    Ok(Groove { template })
+}
+
+/// Remove the applied groove template; voices keep only their own feel.
+pub fn clear_groove() {
+    let _ = require_handle().send(StateMessage::SetGroove { groove: None });
+}
+
+/// Register the groove API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<Groove>();
+
+    engine.register_fn("groove", groove);
+    engine.register_fn("load_groove", load_groove);
+    engine.register_fn("clear_groove", clear_groove);
+
+    // Builder methods
+    engine.register_fn("feel", Groove::feel);
+    engine.register_fn("feel", Groove::feel_int);
+    engine.register_fn("feel", Groove::feel_with);
+    engine.register_fn("feel", Groove::feel_voice);
+    engine.register_fn("feel", Groove::feel_voice_int);
+    engine.register_fn("remove", Groove::remove);
+    engine.register_fn("apply", Groove::apply);
+    engine.register_fn("save", Groove::save);
+    engine.register_get("name", |g: &mut Groove| g.template.name.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::Simulation;
+
+    #[test]
+    fn test_groove_feel_moves_voices_off_the_grid() {
+        let dir = std::env::temp_dir().join(format!("vibelang-groove-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("band.groove");
+
+        let mut sim = Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let script = format!(
+            r#"
+            set_tempo(120);
+            let kick = voice("kick").synth("kick_909");
+            let bass = voice("bass").synth("acid").feel(-20, 0);
+            pattern("four").on(kick).step("x... x... x... x...").start();
+            pattern("line").on(bass).step("x... x... x... x...").start();
+            groove("band").feel("kick", 10, 0).feel("bass", 30, 2).apply().save("{}");
+            "#,
+            file.display()
+        );
+        crate::api::create_engine().run(&script).unwrap();
+        sim.start();
+        sim.advance(2.0);
+
+        let state = sim.handle().with_state(|state| (state.voice_feel("kick"), state.voice_feel("bass")));
+        assert_eq!(state.0, Some(crate::groove::TimingFeel::new(10.0, 0.0)));
+        // The voice's own feel wins over the template
+        assert_eq!(state.1, Some(crate::groove::TimingFeel::new(-20.0, 0.0)));
+
+        let saved = crate::groove::GrooveTemplate::load(&file).unwrap();
+        assert_eq!(saved.name, "band");
+        assert_eq!(saved.feel("bass"), Some(&crate::groove::TimingFeel::new(30.0, 2.0)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
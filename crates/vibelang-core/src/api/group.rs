@@ -2,10 +2,12 @@
 //!
 //! Groups organize voices and provide hierarchical mixing.
 
-use crate::state::StateMessage;
-use rhai::{CustomType, Engine, FnPtr, NativeCallContext, TypeBuilder};
+use crate::state::{GroupTree, StateMessage};
+use crate::timing::TimeSpan;
+use rhai::{Array, CustomType, Dynamic, Engine, FnPtr, Map, NativeCallContext, TypeBuilder};
 
 use super::context::{self, SourceLocation};
+use super::helpers::Decibels;
 use super::require_handle;
 
 /// Extract line and position from Rhai error message text.
@@ -50,6 +52,11 @@ impl GroupHandle {
         Self { path, name }
     }
 
+    /// Full path to the group.
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
     /// Get the group name.
     pub fn name(&mut self) -> String {
         self.name.clone()
@@ -75,6 +82,11 @@ impl GroupHandle {
         self
     }
 
+    /// Set the group gain in decibels (`gain(-6.db)`).
+    pub fn gain_db(self, level: Decibels) -> Self {
+        self.gain(level.amp())
+    }
+
     /// Mute the group.
     pub fn mute(&mut self) -> MuteBuilder {
         MuteBuilder {
@@ -150,8 +162,20 @@ impl GroupHandle {
         handle.with_state(|state| state.groups.contains_key(&self.path))
     }
 
-    /// Add an effect to the group.
-    pub fn add_effect(&mut self, id: String, synthdef: String, params: rhai::Map) {
+    /// Add an effect to the group, recording the call site from NativeCallContext.
+    pub fn add_effect(
+        ctx: NativeCallContext,
+        group: &mut Self,
+        id: String,
+        synthdef: String,
+        params: rhai::Map,
+    ) {
+        let pos = ctx.call_position();
+        let source_location = SourceLocation::new(
+            context::get_current_script_file(),
+            if pos.is_none() { None } else { pos.line().map(|l| l as u32) },
+            if pos.is_none() { None } else { pos.position().map(|c| c as u32) },
+        );
         let handle = require_handle();
         let mut param_map = std::collections::HashMap::new();
 
@@ -166,11 +190,13 @@ impl GroupHandle {
         let _ = handle.send(StateMessage::AddEffect {
             id,
             synthdef,
-            group_path: self.path.clone(),
+            group_path: group.path.clone(),
             params: param_map,
             bus_in: 0,
             bus_out: 0,
-            source_location: SourceLocation::default(),
+            vst_plugin: None,
+            voice: None,
+            source_location,
         });
     }
 
@@ -209,6 +235,42 @@ impl GroupHandle {
         })
     }
 
+    /// Freeze the group for the default number of bars.
+    pub fn freeze(self) -> Self {
+        self.freeze_bars(crate::freeze::DEFAULT_FREEZE_BARS as i64)
+    }
+
+    /// Bounce the group's output for `bars` bars and play the recording
+    /// instead of the live synths.
+    pub fn freeze_bars(self, bars: i64) -> Self {
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::FreezeGroup {
+            path: self.path.clone(),
+            bars: bars.clamp(1, crate::freeze::MAX_FREEZE_BARS as i64) as u32,
+        });
+        self
+    }
+
+    /// Remove the bounce and resume live synthesis.
+    pub fn unfreeze(self) -> Self {
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::UnfreezeGroup {
+            path: self.path.clone(),
+        });
+        self
+    }
+
+    /// Check if the group is frozen (or recording its bounce).
+    pub fn is_frozen(&mut self) -> bool {
+        let handle = require_handle();
+        handle.with_state(|state| {
+            state
+                .groups
+                .get(&self.path)
+                .is_some_and(|g| g.freeze.is_some())
+        })
+    }
+
     /// Add a stutter effect.
     pub fn stutter(self, _length: f64, _count: i64) -> Self {
         // TODO: Implement stutter
@@ -307,8 +369,8 @@ impl ParamFadeBuilder {
     /// Set duration.
     pub fn over(mut self, duration: String) -> Self {
         let handle = require_handle();
-        let tempo = handle.with_state(|s| s.tempo);
-        self.duration = super::helpers::parse_time_spec(&duration, tempo);
+        let (tempo, signature) = handle.with_state(|s| (s.tempo, s.time_signature));
+        self.duration = super::helpers::parse_time_spec(&duration, tempo, signature);
         self
     }
 
@@ -397,8 +459,8 @@ impl SequenceTime {
     /// Add time string.
     pub fn add_time_string(mut self, spec: String) -> Self {
         let handle = require_handle();
-        let tempo = handle.with_state(|s| s.tempo);
-        self.beat += super::helpers::parse_time_spec(&spec, tempo);
+        let (tempo, signature) = handle.with_state(|s| (s.tempo, s.time_signature));
+        self.beat += super::helpers::parse_time_spec(&spec, tempo, signature);
         self
     }
 }
@@ -492,29 +554,66 @@ pub fn define_group(ctx: NativeCallContext, name: String, closure: FnPtr) -> Gro
 
 /// Get a group handle by path.
 pub fn group(path: String) -> GroupHandle {
-    let full_path = if path.starts_with("main/") || path == "main" {
+    GroupHandle::new(resolve_group_path(path))
+}
+
+/// Full path of a group given relative to the current group, or from `main`.
+fn resolve_group_path(path: String) -> String {
+    if path.starts_with("main/") || path == "main" {
         path
     } else {
         format!("{}/{}", context::current_group_path(), path)
-    };
-    GroupHandle::new(full_path)
+    }
 }
 
 /// Set a group's gain.
 pub fn set_group_gain(path: String, value: f64) {
     let handle = require_handle();
-    let full_path = if path.starts_with("main/") || path == "main" {
-        path
-    } else {
-        format!("{}/{}", context::current_group_path(), path)
-    };
     let _ = handle.send(StateMessage::SetGroupParam {
-        path: full_path,
+        path: resolve_group_path(path),
         param: "amp".to_string(),
         value: value as f32,
     });
 }
 
+/// The whole group hierarchy, from `main` (see [`group_tree_at`]).
+pub fn group_tree() -> Dynamic {
+    group_tree_at("main".to_string())
+}
+
+/// The group hierarchy from a group down, as nested maps.
+///
+/// Each map has `name`, `path`, `node_id`, `audio_bus`, `link_node_id`,
+/// `effects` (effect IDs in chain order), `voices` (voice names) and
+/// `children` (maps of the sub-groups). Returns `()` for unknown groups.
+///
+/// # Example
+/// ```rhai
+/// for child in group_tree().children {
+///     print(`${child.path}: ${child.voices.len()} voices`);
+/// }
+/// ```
+pub fn group_tree_at(path: String) -> Dynamic {
+    let path = resolve_group_path(path);
+    require_handle()
+        .with_state(|state| state.group_tree(&path))
+        .map_or(Dynamic::UNIT, tree_to_dynamic)
+}
+
+fn tree_to_dynamic(tree: GroupTree) -> Dynamic {
+    let node_id = |id: Option<i32>| id.map_or(Dynamic::UNIT, |id| Dynamic::from(id as i64));
+    let mut map = Map::new();
+    map.insert("name".into(), Dynamic::from(tree.name));
+    map.insert("path".into(), Dynamic::from(tree.path));
+    map.insert("node_id".into(), node_id(tree.node_id));
+    map.insert("audio_bus".into(), Dynamic::from(tree.audio_bus as i64));
+    map.insert("link_node_id".into(), node_id(tree.link_synth_node_id));
+    map.insert("effects".into(), Dynamic::from(tree.effects.into_iter().map(Dynamic::from).collect::<Array>()));
+    map.insert("voices".into(), Dynamic::from(tree.voices.into_iter().map(Dynamic::from).collect::<Array>()));
+    map.insert("children".into(), Dynamic::from(tree.children.into_iter().map(tree_to_dynamic).collect::<Array>()));
+    Dynamic::from_map(map)
+}
+
 /// Update the main group's generation for reload tracking.
 ///
 /// The main group is created automatically at runtime startup with:
@@ -554,11 +653,14 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("define_group", define_group);
     engine.register_fn("group", group);
     engine.register_fn("set_group_gain", set_group_gain);
+    engine.register_fn("group_tree", group_tree);
+    engine.register_fn("group_tree", group_tree_at);
 
     // GroupHandle methods
     engine.register_fn("name", GroupHandle::name);
     engine.register_fn("parent", GroupHandle::parent);
     engine.register_fn("gain", GroupHandle::gain);
+    engine.register_fn("gain", GroupHandle::gain_db);
     engine.register_fn("mute", GroupHandle::mute);
     engine.register_fn("unmute", GroupHandle::unmute);
     engine.register_fn("solo", GroupHandle::solo);
@@ -574,6 +676,10 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("get_effects", GroupHandle::get_effects);
     engine.register_fn("clear_effects", GroupHandle::clear_effects);
     engine.register_fn("effect_count", GroupHandle::effect_count);
+    engine.register_fn("freeze", GroupHandle::freeze);
+    engine.register_fn("freeze", GroupHandle::freeze_bars);
+    engine.register_fn("unfreeze", GroupHandle::unfreeze);
+    engine.register_fn("is_frozen", GroupHandle::is_frozen);
 
     // MuteBuilder methods
     engine.register_fn("now", MuteBuilder::now);
@@ -602,5 +708,50 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("beat", SequenceTime::beat);
     engine.register_fn("+", SequenceTime::add_beats);
     engine.register_fn("+", |t: SequenceTime, b: i64| t.add_beats(b as f64));
+    engine.register_fn("+", |t: SequenceTime, span: TimeSpan| t.add_beats(super::helpers::span_beats(span)));
     engine.register_fn("+", SequenceTime::add_time_string);
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_group_tree_nests_groups_with_voices_and_effects() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                define_group("drums", || {
+                    voice("kick").synth("kick_909");
+                    voice("snare").synth("snare");
+                    fx("room").synth("reverb").apply();
+                    define_group("hats", || {
+                        voice("hat").synth("hihat");
+                    });
+                });
+                define_group("bass", || {});
+                "#,
+            )
+            .unwrap();
+        sim.advance(0.0);
+
+        let tree = engine.eval::<rhai::Map>("group_tree()").unwrap();
+        let children = tree["children"].clone().into_array().unwrap();
+        let paths: Vec<String> = children.iter().map(|c| c.clone().cast::<rhai::Map>()["path"].to_string()).collect();
+        assert_eq!(paths, vec!["main/bass", "main/drums"]);
+
+        let drums = engine.eval::<rhai::Map>(r#"group_tree("main/drums")"#).unwrap();
+        assert_eq!(drums["voices"].to_string(), r#"["kick", "snare"]"#);
+        assert_eq!(drums["effects"].to_string(), r#"["room"]"#);
+        assert!(drums["node_id"].is_int());
+        let hats = drums["children"].clone().into_array().unwrap();
+        assert_eq!(hats[0].clone().cast::<rhai::Map>()["voices"].to_string(), r#"["hat"]"#);
+        assert!(engine.eval::<rhai::Dynamic>(r#"group_tree("main/keys")"#).unwrap().is_unit());
+
+        // Scripts and the HTTP API see the same hierarchy
+        let roots = sim.handle().with_state(|state| state.group_trees());
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].children[1].children[0].path, "main/drums/hats");
+    }
+}
@@ -2,13 +2,75 @@
 //!
 //! Utility functions for common operations like dB conversion, note parsing, etc.
 
-use rhai::{Array, Dynamic, Engine};
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+use std::fmt;
+
+use super::context::{self, UnitChecks};
+use crate::timing::{Bars, Beats, TimeSignature, TimeSpan};
+
+/// A level in decibels, written `-6.db` in scripts.
+///
+/// Amplitudes stay linear in the runtime; APIs that take a gain accept a
+/// `Decibels` as well and convert it with [`Decibels::amp`]. Fades whose
+/// values are given in decibels also interpolate in decibels.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Decibels(pub f64);
+
+impl Decibels {
+    /// Linear amplitude of this level.
+    pub fn amp(self) -> f64 {
+        db(self.0)
+    }
+
+    /// Level of a linear amplitude (`-inf dB` for silence).
+    pub fn from_amp(amp: f64) -> Self {
+        Decibels(amp_to_db(amp))
+    }
+}
+
+impl fmt::Display for Decibels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == f64::NEG_INFINITY {
+            write!(f, "-inf dB")
+        } else {
+            write!(f, "{:.1} dB", self.0)
+        }
+    }
+}
+
+/// Format a linear amplitude as decibels (`-6.0 dB`, `-inf dB`).
+pub fn format_amp_db(amp: f64) -> String {
+    Decibels::from_amp(amp).to_string()
+}
 
 /// Register helper functions with the Rhai engine.
 pub fn register(engine: &mut Engine) {
     // dB conversion
     engine.register_fn("db", db);
     engine.register_fn("db", db_int);
+    engine.register_fn("db_to_amp", db);
+    engine.register_fn("db_to_amp", db_int);
+    engine.register_fn("amp_to_db", amp_to_db);
+    engine.register_fn("amp_to_db", |amp: i64| amp_to_db(amp as f64));
+
+    // dB levels: `-6.db`, `gain(-3.5.db)`
+    engine.register_type_with_name::<Decibels>("Decibels");
+    engine.register_get("db", |value: &mut f64| Decibels(*value));
+    engine.register_get("db", |value: &mut i64| Decibels(*value as f64));
+    engine.register_get("amp", |level: &mut Decibels| level.amp());
+    engine.register_get("value", |level: &mut Decibels| level.0);
+    engine.register_fn("-", |level: Decibels| Decibels(-level.0));
+    engine.register_fn("+", |a: Decibels, b: Decibels| Decibels(a.0 + b.0));
+    engine.register_fn("-", |a: Decibels, b: Decibels| Decibels(a.0 - b.0));
+    engine.register_fn("+", |a: Decibels, b: f64| Decibels(a.0 + b));
+    engine.register_fn("+", |a: Decibels, b: i64| Decibels(a.0 + b as f64));
+    engine.register_fn("-", |a: Decibels, b: f64| Decibels(a.0 - b));
+    engine.register_fn("-", |a: Decibels, b: i64| Decibels(a.0 - b as f64));
+    engine.register_fn("==", |a: Decibels, b: Decibels| a == b);
+    engine.register_fn("<", |a: Decibels, b: Decibels| a < b);
+    engine.register_fn(">", |a: Decibels, b: Decibels| a > b);
+    engine.register_fn("to_string", |level: &mut Decibels| level.to_string());
+    engine.register_fn("to_debug", |level: &mut Decibels| level.to_string());
 
     // Note parsing
     engine.register_fn("note", note);
@@ -17,10 +79,45 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("bars", bars);
     engine.register_fn("bars", bars_int);
 
-    // Range operators for mixed types (needed for patterns like `0..bars(8)`)
+    // Times with units: `8.beats`, `4.bars`
+    engine.register_type_with_name::<TimeSpan>("TimeSpan");
+    engine.register_get("beats", |count: &mut f64| TimeSpan::Beats(Beats(*count)));
+    engine.register_get("beats", |count: &mut i64| TimeSpan::Beats(Beats(*count as f64)));
+    engine.register_get("bars", |count: &mut f64| TimeSpan::Bars(Bars(*count)));
+    engine.register_get("bars", |count: &mut i64| TimeSpan::Bars(Bars(*count as f64)));
+    engine.register_get("in_beats", |span: &mut TimeSpan| span_beats(*span));
+    engine.register_get("value", |span: &mut TimeSpan| match span {
+        TimeSpan::Beats(beats) => beats.0,
+        TimeSpan::Bars(bars) => bars.0,
+    });
+    engine.register_fn("+", |a: TimeSpan, b: TimeSpan| add_spans(a, b, 1.0));
+    engine.register_fn("-", |a: TimeSpan, b: TimeSpan| add_spans(a, b, -1.0));
+    engine.register_fn("+", |beat: f64, span: TimeSpan| beat + span_beats(span));
+    engine.register_fn("+", |beat: i64, span: TimeSpan| beat as f64 + span_beats(span));
+    engine.register_fn("-", |beat: f64, span: TimeSpan| beat - span_beats(span));
+    engine.register_fn("-", |beat: i64, span: TimeSpan| beat as f64 - span_beats(span));
+    engine.register_fn("*", |span: TimeSpan, factor: f64| span.scale(factor));
+    engine.register_fn("*", |span: TimeSpan, factor: i64| span.scale(factor as f64));
+    engine.register_fn("*", |factor: f64, span: TimeSpan| span.scale(factor));
+    engine.register_fn("*", |factor: i64, span: TimeSpan| span.scale(factor as f64));
+    engine.register_fn("/", |span: TimeSpan, divisor: f64| span.scale(1.0 / divisor));
+    engine.register_fn("/", |span: TimeSpan, divisor: i64| span.scale(1.0 / divisor as f64));
+    engine.register_fn("==", |a: TimeSpan, b: TimeSpan| span_beats(a) == span_beats(b));
+    engine.register_fn("!=", |a: TimeSpan, b: TimeSpan| span_beats(a) != span_beats(b));
+    engine.register_fn("<", |a: TimeSpan, b: TimeSpan| span_beats(a) < span_beats(b));
+    engine.register_fn(">", |a: TimeSpan, b: TimeSpan| span_beats(a) > span_beats(b));
+    engine.register_fn("<=", |a: TimeSpan, b: TimeSpan| span_beats(a) <= span_beats(b));
+    engine.register_fn(">=", |a: TimeSpan, b: TimeSpan| span_beats(a) >= span_beats(b));
+    engine.register_fn("to_string", |span: &mut TimeSpan| span.to_string());
+    engine.register_fn("to_debug", |span: &mut TimeSpan| span.to_string());
+    engine.register_fn("set_unit_checks", set_unit_checks);
+
+    // Range operators for mixed types (needed for patterns like `0..bars(8)` and `0..8.bars`)
     engine.register_fn("..", make_range_if);
     engine.register_fn("..", make_range_ff);
     engine.register_fn("..", make_range_fi);
+    engine.register_fn("..", |start: i64, end: TimeSpan| make_range_if(start, span_beats(end)));
+    engine.register_fn("..", |start: TimeSpan, end: TimeSpan| make_range_ff(span_beats(start), span_beats(end)));
 
     // Sleep (useful for scripts that need to wait)
     engine.register_fn("sleep", sleep);
@@ -50,6 +147,20 @@ pub fn db_int(decibels: i64) -> f64 {
     db(decibels as f64)
 }
 
+/// Convert linear amplitude to decibels.
+///
+/// # Example
+/// ```rhai
+/// let level = amp_to_db(0.5);  // Returns ~-6.02
+/// ```
+pub fn amp_to_db(amp: f64) -> f64 {
+    if amp <= 0.0 {
+        f64::NEG_INFINITY
+    } else {
+        20.0 * amp.log10()
+    }
+}
+
 /// Parse a note name to MIDI note number.
 ///
 /// # Example
@@ -63,14 +174,14 @@ pub fn note(name: &str) -> i64 {
 
 /// Convert bars to beats using current time signature.
 ///
+/// Prefer `2.bars`, which keeps its unit; this returns a plain number.
+///
 /// # Example
 /// ```rhai
 /// let beats = bars(2.0);  // Returns 8.0 in 4/4 time
 /// ```
 pub fn bars(num_bars: f64) -> f64 {
-    let handle = super::require_handle();
-    let beats_per_bar = handle.with_state(|state| state.time_signature.beats_per_bar());
-    num_bars * beats_per_bar
+    num_bars * current_time_signature().beats_per_bar()
 }
 
 /// Convert bars to beats (integer overload).
@@ -78,6 +189,75 @@ pub fn bars_int(num_bars: i64) -> f64 {
     bars(num_bars as f64)
 }
 
+/// Time signature scripts convert bars with.
+pub(crate) fn current_time_signature() -> TimeSignature {
+    let handle = super::require_handle();
+    handle.with_state(|state| state.time_signature)
+}
+
+/// Beats of a span under the current time signature.
+pub fn span_beats(span: TimeSpan) -> f64 {
+    match span {
+        TimeSpan::Beats(beats) => beats.0,
+        TimeSpan::Bars(bars) => bars.to_beats(current_time_signature()).0,
+    }
+}
+
+/// `a + sign * b`, in their unit when they share one and in beats otherwise.
+fn add_spans(a: TimeSpan, b: TimeSpan, sign: f64) -> TimeSpan {
+    match (a, b) {
+        (TimeSpan::Beats(a), TimeSpan::Beats(b)) => TimeSpan::Beats(Beats(a.0 + sign * b.0)),
+        (TimeSpan::Bars(a), TimeSpan::Bars(b)) => TimeSpan::Bars(Bars(a.0 + sign * b.0)),
+        _ => TimeSpan::Beats(Beats(span_beats(a) + sign * span_beats(b))),
+    }
+}
+
+/// Set how plain numbers given as times are treated: "off", "warn" or "error".
+///
+/// # Example
+/// ```rhai
+/// set_unit_checks("warn");
+/// pattern("kick").len(4);   // warns: write 4.beats or give bars
+/// ```
+pub fn set_unit_checks(mode: &str) -> Result<(), Box<EvalAltResult>> {
+    let checks = match mode {
+        "off" => UnitChecks::Off,
+        "warn" => UnitChecks::Warn,
+        "error" => UnitChecks::Error,
+        _ => return Err(format!("set_unit_checks: unknown mode '{}' (expected \"off\", \"warn\" or \"error\")", mode).into()),
+    };
+    context::set_unit_checks(checks);
+    Ok(())
+}
+
+/// A time argument of `function`: a span (`8.beats`, `4.bars`) or a plain
+/// number of beats, subject to the unit checks.
+pub(crate) fn span_arg(function: &str, value: &Dynamic) -> Result<TimeSpan, Box<EvalAltResult>> {
+    if let Some(span) = value.read_lock::<TimeSpan>() {
+        return Ok(*span);
+    }
+    let Some(beats) = value.as_float().ok().or_else(|| value.as_int().ok().map(|v| v as f64)) else {
+        return Err(format!("{}: expected a time like 8.beats or 2.bars, got {}", function, value.type_name()).into());
+    };
+    match context::unit_checks() {
+        UnitChecks::Off => {}
+        UnitChecks::Warn => {
+            if context::first_unit_warning(function) {
+                log::warn!("{}: plain number {} read as beats; write {}.beats or give bars", function, beats, beats);
+            }
+        }
+        UnitChecks::Error => {
+            return Err(format!("{}: give the time with a unit, e.g. {}.beats or 1.bars", function, beats).into());
+        }
+    }
+    Ok(TimeSpan::Beats(Beats(beats)))
+}
+
+/// Beats of a time argument of `function` (see [`span_arg`]).
+pub(crate) fn beats_arg(function: &str, value: &Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    span_arg(function, value).map(span_beats)
+}
+
 /// Create a range from int to float - needed for patterns like `0..bars(8)`
 pub fn make_range_if(start: i64, end: f64) -> std::ops::Range<i64> {
     (start)..(end as i64)
@@ -105,11 +285,19 @@ pub fn sleep_secs(secs: f64) {
 
 /// Exit the script.
 pub fn exit() {
-    std::process::exit(0);
+    exit_with_code(0);
 }
 
 /// Exit with a specific code.
+///
+/// The runtime fades out and frees its nodes and buffers first, so exiting
+/// doesn't leave sound hanging in scsynth.
 pub fn exit_with_code(code: i64) {
+    if let Some(handle) = super::get_handle() {
+        if !handle.shutdown_and_wait(std::time::Duration::from_secs(3)) {
+            log::warn!("Runtime did not shut down in time, exiting anyway");
+        }
+    }
     std::process::exit(code as i32);
 }
 
@@ -179,27 +367,15 @@ pub fn parse_note_name(name: &str) -> Option<u8> {
     }
 }
 
-/// Parse a time specification string (e.g., "2b", "1/4", "500ms") to beats.
-pub fn parse_time_spec(spec: &str, tempo: f64) -> f64 {
+/// Parse a time specification string (e.g., "2b", "4 bars", "1/4", "500ms") to beats.
+///
+/// Bars and fractions of a bar are converted with `signature`.
+pub fn parse_time_spec(spec: &str, tempo: f64, signature: TimeSignature) -> f64 {
     let spec = spec.trim().to_lowercase();
 
-    // Beats: "4b", "2.5b"
-    if spec.ends_with('b') {
-        if let Ok(beats) = spec[..spec.len() - 1].parse::<f64>() {
-            return beats;
-        }
-    }
-
-    // Bars: "2bars", "1bar"
-    if spec.ends_with("bars") || spec.ends_with("bar") {
-        let num_str = spec
-            .trim_end_matches("bars")
-            .trim_end_matches("bar")
-            .trim();
-        if let Ok(num_bars) = num_str.parse::<f64>() {
-            // Assume 4/4 time for simplicity
-            return num_bars * 4.0;
-        }
+    // Beats and bars: "4b", "2.5 beats", "2bars", "1 bar"
+    if let Some(span) = TimeSpan::parse(&spec) {
+        return span.to_beats(signature).0;
     }
 
     // Milliseconds: "500ms"
@@ -223,8 +399,8 @@ pub fn parse_time_spec(spec: &str, tempo: f64) -> f64 {
         let parts: Vec<&str> = spec.split('/').collect();
         if parts.len() == 2 {
             if let (Ok(num), Ok(denom)) = (parts[0].parse::<f64>(), parts[1].parse::<f64>()) {
-                // Interpret as fraction of a bar (4 beats)
-                return 4.0 * num / denom;
+                // Interpret as fraction of a bar
+                return signature.beats_per_bar() * num / denom;
             }
         }
     }
@@ -237,6 +413,55 @@ pub fn parse_time_spec(spec: &str, tempo: f64) -> f64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_decibels_in_scripts() {
+        let mut engine = Engine::new();
+        register(&mut engine);
+
+        let level: Decibels = engine.eval("-6.db").unwrap();
+        assert_eq!(level, Decibels(-6.0));
+        assert!((level.amp() - 0.501).abs() < 0.001);
+        assert_eq!(engine.eval::<Decibels>("-3.5.db - 2").unwrap(), Decibels(-5.5));
+        assert!((engine.eval::<f64>("(-12).db.amp").unwrap() - 0.251).abs() < 0.001);
+        assert_eq!(engine.eval::<String>("`${-6.db}`").unwrap(), "-6.0 dB");
+        assert_eq!(format_amp_db(0.0), "-inf dB");
+    }
+
+    #[test]
+    fn test_time_units_in_scripts() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+
+        // Bars convert with the signature set earlier in the script
+        engine
+            .run(
+                r#"
+                set_time_signature(3, 4);
+                marker("drop", 4.bars);
+                set_quantization(1.bars);
+                pattern("kick").step("x...").len(2.bars).launch_quantize(1.5.beats).apply();
+                "#,
+            )
+            .unwrap();
+        sim.advance(0.0);
+        sim.handle().with_state(|state| {
+            assert_eq!(state.locators.get("drop"), Some(12.0));
+            assert_eq!(state.quantization_beats, 3.0);
+        });
+        assert_eq!(engine.eval::<f64>("(2.bars + 1.beats).in_beats").unwrap(), 7.0);
+        assert_eq!(engine.eval::<TimeSpan>("4.bars / 2").unwrap(), TimeSpan::Bars(Bars(2.0)));
+        assert_eq!(engine.eval::<i64>("(0..2.bars).end").unwrap(), 6);
+        assert!(engine.eval::<bool>("1.bars > 2.beats").unwrap());
+
+        // Plain numbers stay beats; unit checks report them
+        engine.run(r#"sequence("song").loop_beats(16);"#).unwrap();
+        let err = engine.run(r#"set_unit_checks("error"); sequence("song").loop_beats(16);"#).unwrap_err();
+        assert!(err.to_string().contains("loop_beats: give the time with a unit"), "{}", err);
+        engine.run(r#"sequence("song").loop_beats(16.beats); set_unit_checks("off");"#).unwrap();
+        assert!(engine.run(r#"marker("x", "soon");"#).is_err());
+    }
+
     #[test]
     fn test_parse_note_name() {
         assert_eq!(parse_note_name("C4"), Some(60));
@@ -257,9 +482,11 @@ mod tests {
     #[test]
     fn test_parse_time_spec() {
         let tempo = 120.0;
-        assert!((parse_time_spec("4b", tempo) - 4.0).abs() < 0.001);
-        assert!((parse_time_spec("1bar", tempo) - 4.0).abs() < 0.001);
-        assert!((parse_time_spec("500ms", tempo) - 1.0).abs() < 0.001);
-        assert!((parse_time_spec("1/4", tempo) - 1.0).abs() < 0.001);
+        let common = TimeSignature::default();
+        assert!((parse_time_spec("4b", tempo, common) - 4.0).abs() < 0.001);
+        assert!((parse_time_spec("1bar", tempo, common) - 4.0).abs() < 0.001);
+        assert!((parse_time_spec("500ms", tempo, common) - 1.0).abs() < 0.001);
+        assert!((parse_time_spec("1/4", tempo, common) - 1.0).abs() < 0.001);
+        assert!((parse_time_spec("2 bars", tempo, TimeSignature::new(3, 4)) - 6.0).abs() < 0.001);
     }
 }
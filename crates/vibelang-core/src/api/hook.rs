@@ -0,0 +1,189 @@
+//! Hook API for Rhai scripts.
+//!
+//! Hooks fire shell commands or webhooks from the timeline (see
+//! [`crate::hooks`]), off the audio thread and with a timeout.
+//!
+//! ```rhai
+//! // Switch the lights at the drop
+//! on_beat_hook(16.bars, "curl -X POST http://lights/scene/3");
+//!
+//! // Pulse a strobe every bar, giving up after half a second
+//! hook("strobe")
+//!     .every(1.bars)
+//!     .webhook("http://strobe.local/flash")
+//!     .timeout(0.5)
+//!     .apply();
+//!
+//! remove_hook("strobe");
+//! ```
+
+use std::time::Duration;
+
+use crate::hooks::{HookAction, DEFAULT_HOOK_TIMEOUT};
+use crate::state::StateMessage;
+use rhai::{CustomType, Dynamic, Engine, EvalAltResult, NativeCallContext, Position, TypeBuilder};
+
+use super::context::{self, SourceLocation};
+use super::helpers::beats_arg;
+use super::require_handle;
+
+/// A hook builder.
+#[derive(Debug, Clone, CustomType)]
+pub struct Hook {
+    /// Hook name.
+    pub name: String,
+    /// First beat the hook fires on.
+    beat: f64,
+    /// Repeat interval in beats.
+    every: Option<f64>,
+    /// Shell command to run.
+    command: Option<String>,
+    /// Webhook URL to call.
+    url: Option<String>,
+    /// Webhook request body.
+    body: Option<String>,
+    /// Webhook HTTP method.
+    method: String,
+    /// How long the hook may run.
+    timeout: Duration,
+    /// Source location where this hook was defined.
+    source_location: SourceLocation,
+}
+
+impl Hook {
+    /// Create a new hook with the given name and source location from NativeCallContext.
+    pub fn new(ctx: NativeCallContext, name: String) -> Self {
+        let pos = ctx.call_position();
+        let source_location = SourceLocation::new(
+            context::get_current_script_file(),
+            if pos.is_none() { None } else { pos.line().map(|l| l as u32) },
+            if pos.is_none() { None } else { pos.position().map(|c| c as u32) },
+        );
+        Self {
+            name,
+            beat: 0.0,
+            every: None,
+            command: None,
+            url: None,
+            body: None,
+            method: "POST".to_string(),
+            timeout: DEFAULT_HOOK_TIMEOUT,
+            source_location,
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Set the beat the hook fires on (e.g. `16.bars`).
+    pub fn at(mut self, position: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.beat = beats_arg("at", &position)?.max(0.0);
+        Ok(self)
+    }
+
+    /// Repeat the hook every `span` after its first beat.
+    pub fn every(mut self, span: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        let every = beats_arg("every", &span)?;
+        if every <= 0.0 {
+            return Err(format!("hook '{}': every() needs a positive time", self.name).into());
+        }
+        self.every = Some(every);
+        Ok(self)
+    }
+
+    /// Run a shell command when the hook fires.
+    pub fn command(mut self, command: String) -> Self {
+        self.command = Some(command);
+        self.url = None;
+        self
+    }
+
+    /// Call an `http://` URL when the hook fires.
+    pub fn webhook(mut self, url: String) -> Self {
+        self.url = Some(url);
+        self.command = None;
+        self
+    }
+
+    /// Call an `http://` URL with a request body when the hook fires.
+    pub fn webhook_with_body(self, url: String, body: String) -> Self {
+        let mut hook = self.webhook(url);
+        hook.body = Some(body);
+        hook
+    }
+
+    /// Set the webhook's HTTP method (default "POST").
+    pub fn method(mut self, method: String) -> Self {
+        self.method = method.to_uppercase();
+        self
+    }
+
+    /// Kill the hook when it runs longer than `seconds`.
+    pub fn timeout(mut self, seconds: f64) -> Self {
+        self.timeout = Duration::from_secs_f64(seconds.max(0.0));
+        self
+    }
+
+    /// Register the hook with the runtime.
+    pub fn apply(&mut self) -> Result<(), Box<EvalAltResult>> {
+        let action = match (&self.command, &self.url) {
+            (Some(command), _) => HookAction::Command(command.clone()),
+            (None, Some(url)) => HookAction::Webhook {
+                url: url.clone(),
+                method: self.method.clone(),
+                body: self.body.clone(),
+            },
+            (None, None) => {
+                return Err(format!("hook '{}': give a command() or webhook() before apply()", self.name).into())
+            }
+        };
+        require_handle()
+            .send(StateMessage::SetHook {
+                name: self.name.clone(),
+                action,
+                beat: self.beat,
+                every: self.every,
+                timeout: self.timeout,
+                source_location: self.source_location.clone(),
+            })
+            .map_err(|e| e.to_string().into())
+    }
+}
+
+/// Create a hook builder.
+pub fn hook(ctx: NativeCallContext, name: String) -> Hook {
+    Hook::new(ctx, name)
+}
+
+/// Run a shell command once when the transport reaches `position`.
+pub fn on_beat_hook(ctx: NativeCallContext, position: Dynamic, command: String) -> Result<(), Box<EvalAltResult>> {
+    let beat = beats_arg("on_beat_hook", &position)?.max(0.0);
+    let mut hook = Hook::new(ctx, format!("{} @ {}", command, beat)).command(command);
+    hook.beat = beat;
+    hook.apply()
+}
+
+/// Remove a hook.
+pub fn remove_hook(name: String) {
+    let _ = require_handle().send(StateMessage::RemoveHook { name });
+}
+
+/// Register the hook API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<Hook>();
+
+    engine.register_fn("hook", hook);
+    engine.register_fn("on_beat_hook", on_beat_hook);
+    engine.register_fn("remove_hook", remove_hook);
+
+    // Builder methods
+    engine.register_fn("at", Hook::at);
+    engine.register_fn("every", Hook::every);
+    engine.register_fn("command", Hook::command);
+    engine.register_fn("webhook", Hook::webhook);
+    engine.register_fn("webhook", Hook::webhook_with_body);
+    engine.register_fn("method", Hook::method);
+    engine.register_fn("timeout", Hook::timeout);
+    engine.register_fn("timeout", |h: Hook, seconds: i64| h.timeout(seconds as f64));
+    engine.register_fn("apply", Hook::apply);
+    engine.register_get("name", |h: &mut Hook| h.name.clone());
+}
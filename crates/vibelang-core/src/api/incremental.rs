@@ -0,0 +1,558 @@
+//! Incremental evaluation for watch reloads.
+//!
+//! Re-running a large script on every save re-evaluates everything, even
+//! when one line changed. [`IncrementalScript`] splits a script into its
+//! top-level statements and, on reload, only evaluates the statements whose
+//! tokens changed plus the statements that depend on them (through the
+//! variables and functions they define). Variables of skipped statements
+//! live on in a persistent scope.
+//!
+//! The reload protocol still expects every entity to be touched by the
+//! script (untouched entities are removed), so the state messages each
+//! statement sent during its last evaluation are recorded and replayed, in
+//! script order, for skipped statements. Replaying an upsert is cheap and
+//! keeps the runtime identity of unchanged voices and patterns.
+//!
+//! Statements that register things outside the runtime state (MIDI
+//! callbacks, MIDI devices) can't be replayed and are always evaluated, as
+//! are `import` statements. Anything the splitter doesn't understand (e.g.
+//! interpolated strings) falls back to evaluating the whole script.
+//!
+//! Dependencies are tracked by name: `let`/`const` and `fn` definitions,
+//! and plain assignments (`x = ...`, `x += ...`), which re-run the statement
+//! that defined `x`. Mutation through method calls (`list.push(1)`) is not
+//! tracked, so scripts are expected to be mostly declarative.
+
+use crate::state::StateMessage;
+use rhai::{Engine, EvalAltResult, Position, Scope};
+use rhai::Token;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// Function called after each evaluated statement to attribute messages.
+const STATEMENT_END_FN: &str = "__vibe_statement_end";
+
+/// A top-level statement and what its last evaluation sent.
+#[derive(Clone, Debug)]
+struct Statement {
+    /// Normalized token text, used to detect changes.
+    key: String,
+    kind: StatementKind,
+    /// Variables or functions the statement defines.
+    defines: Vec<String>,
+    /// Variable the statement assigns to without defining it.
+    assigns: Option<String>,
+    /// Identifiers the statement refers to.
+    refs: HashSet<String>,
+    /// Byte range in the script source.
+    start: usize,
+    end: usize,
+    /// State messages sent by the last evaluation.
+    messages: Vec<StateMessage>,
+    /// Whether the last evaluation registered state that can't be replayed.
+    volatile: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StatementKind {
+    Fn,
+    Import,
+    Other,
+}
+
+/// Outcome of an incremental run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IncrementalStats {
+    /// Statements that were evaluated.
+    pub evaluated: usize,
+    /// Statements whose recorded messages were replayed instead.
+    pub replayed: usize,
+    /// Whether the whole script was evaluated.
+    pub full: bool,
+}
+
+/// A script evaluated statement by statement across reloads.
+#[derive(Default)]
+pub struct IncrementalScript {
+    statements: Vec<Statement>,
+    scope: Scope<'static>,
+}
+
+/// Recording state of the running evaluation.
+struct Recorder {
+    /// Messages sent since the last statement ended.
+    pending: Vec<StateMessage>,
+    volatile: bool,
+    /// Messages and volatility per evaluated statement.
+    recorded: HashMap<usize, (Vec<StateMessage>, bool)>,
+    /// Skipped statements (index and messages) not replayed yet, in order.
+    replays: Vec<(usize, Vec<StateMessage>)>,
+    /// Indices of the statements being evaluated, in order.
+    evaluated: Vec<usize>,
+    replaying: bool,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Record a message sent by the running incremental evaluation.
+pub(crate) fn record(msg: &StateMessage) {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            if !recorder.replaying {
+                recorder.pending.push(msg.clone());
+            }
+        }
+    });
+}
+
+/// Mark the running statement as registering state outside the runtime,
+/// so it is evaluated on every reload.
+pub(crate) fn mark_volatile() {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.volatile = true;
+        }
+    });
+}
+
+/// Register the statement marker used by [`IncrementalScript`].
+pub fn register(engine: &mut Engine) {
+    engine.register_fn(STATEMENT_END_FN, |index: i64| {
+        end_statement(index as usize);
+    });
+}
+
+/// Attribute pending messages to a statement, then replay the skipped
+/// statements that come before the next evaluated one.
+fn end_statement(index: usize) {
+    let replays = RECORDER.with(|recorder| {
+        let mut recorder = recorder.borrow_mut();
+        let recorder = recorder.as_mut()?;
+        let messages = std::mem::take(&mut recorder.pending);
+        let volatile = std::mem::replace(&mut recorder.volatile, false);
+        recorder.recorded.insert(index, (messages, volatile));
+
+        let next = recorder.evaluated.iter().copied().find(|&i| i > index).unwrap_or(usize::MAX);
+        let due = recorder.replays.iter().take_while(|(i, _)| *i < next).count();
+        Some(recorder.replays.drain(..due).collect::<Vec<_>>())
+    });
+    replay(replays.unwrap_or_default());
+}
+
+fn replay(statements: Vec<(usize, Vec<StateMessage>)>) {
+    let Some(handle) = super::get_handle() else {
+        return;
+    };
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.replaying = true;
+        }
+    });
+    for msg in statements.into_iter().flat_map(|(_, messages)| messages) {
+        let _ = handle.send(msg);
+    }
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.replaying = false;
+        }
+    });
+}
+
+impl IncrementalScript {
+    /// Create a runner; the first run evaluates the whole script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all recorded statements so the next run is a full one.
+    pub fn reset(&mut self) {
+        self.statements.clear();
+        self.scope.clear();
+    }
+
+    /// Evaluate a script, skipping top-level statements that didn't change
+    /// since the last successful run.
+    ///
+    /// On error the runner resets, so the next run evaluates everything.
+    pub fn run(&mut self, engine: &Engine, script: &str) -> Result<IncrementalStats, Box<EvalAltResult>> {
+        let Some(mut statements) = split_statements(engine, script) else {
+            log::debug!("[RELOAD] Incremental eval not possible, evaluating the whole script");
+            self.reset();
+            engine.run_with_scope(&mut self.scope, script)?;
+            return Ok(IncrementalStats {
+                full: true,
+                ..IncrementalStats::default()
+            });
+        };
+
+        let full = self.statements.is_empty();
+        let skipped = if full {
+            self.scope.clear();
+            vec![false; statements.len()]
+        } else {
+            self.plan(&mut statements)
+        };
+
+        // Blank out skipped statements (keeping line breaks so positions
+        // stay valid) and mark the end of every evaluated one
+        let mut code = String::with_capacity(script.len() + statements.len() * 32);
+        let mut cursor = 0;
+        let mut evaluated = Vec::new();
+        let mut replays = Vec::new();
+        for (i, stmt) in statements.iter().enumerate() {
+            code.push_str(&script[cursor..stmt.start]);
+            let text = &script[stmt.start..stmt.end];
+            if skipped[i] {
+                code.extend(text.chars().map(|c| if c == '\n' { '\n' } else { ' ' }));
+                replays.push((i, stmt.messages.clone()));
+            } else if stmt.kind == StatementKind::Fn {
+                code.push_str(text);
+            } else {
+                code.push_str(text);
+                if !text.ends_with(';') && !text.ends_with('}') {
+                    // Last statement without `;`, possibly followed by a comment
+                    code.push_str("\n;");
+                }
+                code.push_str(&format!("{}({});", STATEMENT_END_FN, i));
+                evaluated.push(i);
+            }
+            cursor = stmt.end;
+        }
+        code.push_str(&script[cursor..]);
+
+        let stats = IncrementalStats {
+            evaluated: evaluated.len(),
+            replayed: replays.len(),
+            full,
+        };
+
+        // Skipped statements before the first evaluated one go out first
+        let first = evaluated.first().copied().unwrap_or(usize::MAX);
+        let due = replays.iter().take_while(|(i, _)| *i < first).count();
+        let early: Vec<_> = replays.drain(..due).collect();
+
+        RECORDER.with(|recorder| {
+            *recorder.borrow_mut() = Some(Recorder {
+                pending: Vec::new(),
+                volatile: false,
+                recorded: HashMap::new(),
+                replays,
+                evaluated,
+                replaying: false,
+            });
+        });
+        replay(early);
+        let result = engine.run_with_scope(&mut self.scope, &code);
+        let recorder = RECORDER.with(|recorder| recorder.borrow_mut().take());
+
+        if let Err(e) = result {
+            self.reset();
+            return Err(e);
+        }
+
+        let mut recorder = recorder.expect("recorder is set during the run");
+        replay(std::mem::take(&mut recorder.replays));
+        for (i, (messages, volatile)) in recorder.recorded {
+            statements[i].messages = messages;
+            statements[i].volatile = volatile;
+        }
+        self.statements = statements;
+
+        Ok(stats)
+    }
+
+    /// Decide which statements can be skipped, carrying over the recorded
+    /// messages of unchanged statements.
+    fn plan(&self, statements: &mut [Statement]) -> Vec<bool> {
+        let mut previous: HashMap<&str, Vec<&Statement>> = HashMap::new();
+        for stmt in self.statements.iter().rev() {
+            previous.entry(stmt.key.as_str()).or_default().push(stmt);
+        }
+
+        let mut changed = vec![false; statements.len()];
+        let mut dirty: HashSet<String> = HashSet::new();
+        for (i, stmt) in statements.iter_mut().enumerate() {
+            match previous.get_mut(stmt.key.as_str()).and_then(|v| v.pop()) {
+                Some(old) if !old.volatile && stmt.kind != StatementKind::Import => {
+                    stmt.messages = old.messages.clone();
+                }
+                _ => {
+                    changed[i] = true;
+                    dirty.extend(stmt.defines.iter().cloned());
+                }
+            }
+        }
+
+        // Names defined by removed statements are dirty too
+        for old in previous.values().flatten() {
+            dirty.extend(old.defines.iter().cloned());
+        }
+
+        // Propagate to dependents; re-assigning a variable re-runs its definition
+        loop {
+            let mut grew = false;
+            for i in 0..statements.len() {
+                if !changed[i] && statements[i].refs.iter().any(|r| dirty.contains(r)) {
+                    changed[i] = true;
+                    dirty.extend(statements[i].defines.iter().cloned());
+                    grew = true;
+                }
+                if let Some(var) = statements[i].assigns.clone().filter(|_| changed[i]) {
+                    let definer = (0..i).rev().find(|&j| statements[j].defines.contains(&var));
+                    if let Some(j) = definer.filter(|&j| !changed[j]) {
+                        changed[j] = true;
+                        dirty.extend(statements[j].defines.iter().cloned());
+                        grew = true;
+                    }
+                }
+            }
+            if !grew {
+                break;
+            }
+        }
+
+        statements
+            .iter()
+            .zip(changed)
+            .map(|(stmt, changed)| !changed && stmt.kind == StatementKind::Other)
+            .collect()
+    }
+}
+
+/// Split a script into top-level statements.
+///
+/// Returns `None` for scripts the token-level splitter can't handle.
+fn split_statements(engine: &Engine, script: &str) -> Option<Vec<Statement>> {
+    let inputs = [script];
+    let tokens: Vec<(Token, Position)> = engine
+        .lex(&inputs)
+        .0
+        .take_while(|(token, _)| !matches!(token, Token::EOF))
+        .filter(|(token, _)| !matches!(token, Token::Comment(_)))
+        .collect();
+    if tokens
+        .iter()
+        .any(|(token, _)| matches!(token, Token::LexError(_) | Token::InterpolatedString(_)))
+    {
+        return None;
+    }
+
+    let offsets = LineOffsets::new(script);
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut current: Vec<&(Token, Position)> = Vec::new();
+    let mut depth = 0usize;
+
+    for (i, entry) in tokens.iter().enumerate() {
+        let token = &entry.0;
+        current.push(entry);
+        match token {
+            Token::LeftBrace | Token::MapStart | Token::LeftParen | Token::LeftBracket => depth += 1,
+            Token::RightBrace | Token::RightParen | Token::RightBracket => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+        if depth > 0 {
+            continue;
+        }
+
+        let ends = match token {
+            Token::SemiColon => true,
+            Token::RightBrace => {
+                let next = tokens.get(i + 1).map(|(t, _)| t);
+                is_block_statement(&current) && !matches!(next, Some(Token::Else | Token::Catch))
+            }
+            _ => false,
+        };
+        if ends {
+            // A lone `;` belongs to the statement before it
+            if current.len() == 1 && matches!(token, Token::SemiColon) {
+                if let Some(prev) = statements.last_mut() {
+                    prev.end = offsets.offset(entry.1)? + 1;
+                }
+                current.clear();
+                continue;
+            }
+            let end = offsets.offset(entry.1)? + 1;
+            statements.push(statement(&current, offsets.offset(current[0].1)?, end));
+            current.clear();
+        }
+    }
+
+    if !current.is_empty() {
+        statements.push(statement(&current, offsets.offset(current[0].1)?, script.trim_end().len()));
+    }
+
+    Some(statements)
+}
+
+fn is_block_statement(tokens: &[&(Token, Position)]) -> bool {
+    match tokens.first().map(|(t, _)| t) {
+        Some(Token::Private) => matches!(tokens.get(1).map(|(t, _)| t), Some(Token::Fn)),
+        Some(
+            Token::Fn
+            | Token::If
+            | Token::While
+            | Token::Loop
+            | Token::For
+            | Token::Switch
+            | Token::Try
+            | Token::LeftBrace,
+        ) => true,
+        _ => false,
+    }
+}
+
+fn statement(tokens: &[&(Token, Position)], start: usize, end: usize) -> Statement {
+    let first = tokens.first().map(|(t, _)| t);
+    let second = tokens.get(1).map(|(t, _)| t);
+    let name_after = |keyword: usize| match tokens.get(keyword + 1).map(|(t, _)| t) {
+        Some(Token::Identifier(name)) => Some(name.to_string()),
+        _ => None,
+    };
+
+    let (kind, defines) = match first {
+        Some(Token::Fn) => (StatementKind::Fn, name_after(0).into_iter().collect()),
+        Some(Token::Private) if matches!(second, Some(Token::Fn)) => {
+            (StatementKind::Fn, name_after(1).into_iter().collect())
+        }
+        Some(Token::Import) => (StatementKind::Import, Vec::new()),
+        Some(Token::Let | Token::Const) => (StatementKind::Other, name_after(0).into_iter().collect()),
+        _ => (StatementKind::Other, Vec::new()),
+    };
+
+    let assigns = match (first, second) {
+        (Some(Token::Identifier(name)), Some(op)) if is_assignment(op) => Some(name.to_string()),
+        _ => None,
+    };
+
+    let refs = tokens
+        .iter()
+        .filter_map(|(t, _)| match t {
+            Token::Identifier(name) => Some(name.to_string()),
+            _ => None,
+        })
+        .filter(|name| !defines.contains(name))
+        .collect();
+
+    let key = tokens.iter().map(|(t, _)| t.to_string()).collect::<Vec<_>>().join(" ");
+
+    Statement {
+        key,
+        kind,
+        defines,
+        assigns,
+        refs,
+        start,
+        end,
+        messages: Vec::new(),
+        volatile: false,
+    }
+}
+
+fn is_assignment(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Equals
+            | Token::PlusAssign
+            | Token::MinusAssign
+            | Token::MultiplyAssign
+            | Token::DivideAssign
+            | Token::ModuloAssign
+            | Token::PowerOfAssign
+            | Token::LeftShiftAssign
+            | Token::RightShiftAssign
+            | Token::AndAssign
+            | Token::OrAssign
+            | Token::XOrAssign
+            // `x[i] = ...` writes to `x` as well
+            | Token::LeftBracket
+    )
+}
+
+/// Maps Rhai positions (1-based line and character column) to byte offsets.
+struct LineOffsets<'a> {
+    script: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineOffsets<'a> {
+    fn new(script: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(script.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { script, starts }
+    }
+
+    fn offset(&self, pos: Position) -> Option<usize> {
+        let line_start = *self.starts.get(pos.line()?.checked_sub(1)?)?;
+        let column = pos.position()?.checked_sub(1)?;
+        let line = &self.script[line_start..];
+        line.char_indices()
+            .nth(column)
+            .map(|(i, _)| line_start + i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(script: &str) -> Vec<String> {
+        let engine = Engine::new();
+        split_statements(&engine, script)
+            .unwrap()
+            .iter()
+            .map(|s| script[s.start..s.end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_top_level_statements() {
+        let script = "let a = [1, 2];\nfn f(x) { x + 1 }\nif a.len() > 1 { print(1); } else { print(2); }\nlet m = #{ x: 1 };  // note\nprint(f(a[0]))\n";
+        assert_eq!(
+            keys(script),
+            vec![
+                "let a = [1, 2];",
+                "fn f(x) { x + 1 }",
+                "if a.len() > 1 { print(1); } else { print(2); }",
+                "let m = #{ x: 1 };",
+                "print(f(a[0]))",
+            ]
+        );
+        assert!(split_statements(&Engine::new(), "let s = `a ${1} b`;").is_none());
+    }
+
+    #[test]
+    fn test_only_changed_statements_and_dependents_run() {
+        let mut engine = Engine::new();
+        register(&mut engine);
+        let runs = std::sync::Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let log = runs.clone();
+        engine.register_fn("mark", move |name: &str| log.lock().unwrap().push(name.to_string()));
+
+        let mut script = IncrementalScript::new();
+        let v1 = "let a = 1; mark(\"a\");\nlet b = 2;\nmark(\"b\" + b);\nmark(\"ab\" + a);\n";
+        assert_eq!(script.run(&engine, v1).unwrap().evaluated, 5);
+        assert_eq!(runs.lock().unwrap().len(), 3);
+        runs.lock().unwrap().clear();
+
+        // Changing `b` re-runs its dependents only; `a` stays in scope
+        let v2 = "let a = 1; mark(\"a\");\nlet b = 3;\nmark(\"b\" + b);\nmark(\"ab\" + a);\n";
+        let stats = script.run(&engine, v2).unwrap();
+        assert_eq!(stats, IncrementalStats { evaluated: 2, replayed: 3, full: false });
+        assert_eq!(*runs.lock().unwrap(), vec!["b3".to_string()]);
+        runs.lock().unwrap().clear();
+
+        // Re-assigning a variable re-runs its definition and every user
+        let v3 = "let a = 1; mark(\"a\");\nlet b = 3;\nb += 1;\nmark(\"b\" + b);\nmark(\"ab\" + a);\n";
+        script.run(&engine, v3).unwrap();
+        assert_eq!(*runs.lock().unwrap(), vec!["b4".to_string()]);
+
+        // Errors reset the runner so the next run is a full one
+        assert!(script.run(&engine, "throw 1;").is_err());
+        runs.lock().unwrap().clear();
+        assert!(script.run(&engine, v1).unwrap().full);
+        assert_eq!(runs.lock().unwrap().len(), 3);
+    }
+}
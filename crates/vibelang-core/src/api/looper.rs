@@ -0,0 +1,169 @@
+//! Looper API for Rhai scripts.
+//!
+//! A looper records a hardware input for a number of bars and loops it in
+//! time with the transport (see [`crate::looper`]).
+//!
+//! ```rhai
+//! let guitar = looper("guitar_loop").input(1).bars(4).gain(db(-3));
+//!
+//! // Footswitch on note 60 records, 62 overdubs, 64 clears
+//! let pedal = midi_open("FCB1010");
+//! pedal.on_note(60).callback(|| looper("guitar_loop").record());
+//! pedal.on_note(62).callback(|| looper("guitar_loop").overdub());
+//! pedal.on_note(64).callback(|| looper("guitar_loop").clear());
+//! ```
+//!
+//! Live sets can bind the same controls to keys, notes and CCs, e.g.
+//! `key r = looper guitar_loop record`.
+
+use crate::looper::{LooperAction, DEFAULT_LOOPER_BARS, MAX_LOOPER_BARS};
+use crate::state::{LooperStatus, StateMessage};
+use rhai::{CustomType, Engine, TypeBuilder};
+
+use super::helpers::Decibels;
+use super::{context, get_handle, require_handle};
+
+/// A looper builder.
+///
+/// Every builder call updates the running looper, and `looper(name)` starts
+/// from the looper's current settings, so controls can be fired from
+/// callbacks without repeating its configuration.
+#[derive(Debug, Clone, CustomType)]
+pub struct Looper {
+    /// Looper name.
+    pub name: String,
+    /// Group the loop plays into.
+    group_path: String,
+    /// Hardware input channel (1-based).
+    input: u32,
+    /// Loop length in bars.
+    bars: u32,
+    /// Playback gain (linear).
+    gain: f64,
+}
+
+impl Looper {
+    /// Create a looper builder, starting from the existing looper's settings.
+    pub fn new(name: String) -> Self {
+        let existing = get_handle().and_then(|h| {
+            h.with_state(|state| {
+                state
+                    .loopers
+                    .get(&name)
+                    .map(|l| (l.group_path.clone(), l.input, l.bars, l.gain as f64))
+            })
+        });
+        let (group_path, input, bars, gain) =
+            existing.unwrap_or_else(|| (context::current_group_path(), 1, DEFAULT_LOOPER_BARS, 1.0));
+        Self {
+            name,
+            group_path,
+            input,
+            bars,
+            gain,
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Set the hardware input channel (1 = first input).
+    pub fn input(mut self, channel: i64) -> Self {
+        self.input = channel.max(1) as u32;
+        self.sync_state();
+        self
+    }
+
+    /// Set the loop length in bars.
+    pub fn bars(mut self, bars: i64) -> Self {
+        self.bars = bars.clamp(1, MAX_LOOPER_BARS as i64) as u32;
+        self.sync_state();
+        self
+    }
+
+    /// Set the playback gain (linear).
+    pub fn gain(mut self, gain: f64) -> Self {
+        self.gain = gain;
+        self.sync_state();
+        self
+    }
+
+    /// Set the playback gain in decibels.
+    pub fn gain_db(self, level: Decibels) -> Self {
+        self.gain(level.amp())
+    }
+
+    // === Actions ===
+
+    /// Record a new loop from the next bar line.
+    pub fn record(self) -> Self {
+        self.control(LooperAction::Record)
+    }
+
+    /// Record one more pass on top of the loop.
+    pub fn overdub(self) -> Self {
+        self.control(LooperAction::Overdub)
+    }
+
+    /// Stop the loop and empty it.
+    pub fn clear(self) -> Self {
+        self.control(LooperAction::Clear)
+    }
+
+    /// Current status: "empty", "recording", "playing" or "overdubbing".
+    pub fn status(&mut self) -> String {
+        let status = get_handle().and_then(|h| h.with_state(|state| state.loopers.get(&self.name).map(|l| l.status)));
+        match status.unwrap_or(LooperStatus::Empty) {
+            LooperStatus::Empty => "empty",
+            LooperStatus::Recording => "recording",
+            LooperStatus::Playing => "playing",
+            LooperStatus::Overdubbing => "overdubbing",
+        }
+        .to_string()
+    }
+
+    fn control(self, action: LooperAction) -> Self {
+        self.sync_state();
+        let _ = require_handle().send(StateMessage::LooperControl {
+            name: self.name.clone(),
+            action,
+        });
+        self
+    }
+
+    fn sync_state(&self) {
+        let _ = require_handle().send(StateMessage::UpsertLooper {
+            name: self.name.clone(),
+            group_path: self.group_path.clone(),
+            input: self.input,
+            bars: self.bars,
+            gain: self.gain as f32,
+        });
+    }
+}
+
+/// Create or look up a looper.
+pub fn looper(name: String) -> Looper {
+    let looper = Looper::new(name);
+    looper.sync_state();
+    looper
+}
+
+/// Register the looper API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<Looper>();
+
+    engine.register_fn("looper", looper);
+
+    // Builder methods
+    engine.register_fn("input", Looper::input);
+    engine.register_fn("bars", Looper::bars);
+    engine.register_fn("gain", Looper::gain);
+    engine.register_fn("gain", Looper::gain_db);
+
+    // Actions
+    engine.register_fn("record", Looper::record);
+    engine.register_fn("overdub", Looper::overdub);
+    engine.register_fn("clear", Looper::clear);
+    engine.register_fn("status", Looper::status);
+    engine.register_get("name", |l: &mut Looper| l.name.clone());
+}
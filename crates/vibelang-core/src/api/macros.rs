@@ -0,0 +1,208 @@
+//! Macro control API for Rhai scripts.
+//!
+//! A macro maps one normalized value (0..1) onto parameters of many
+//! voices, groups, patterns, melodies and effects.
+//!
+//! ```rhai
+//! let intensity = macro("intensity")
+//!     .maps(lead, "cutoff", 200..4000)
+//!     .maps(drums, "amp", [0.5, 1.0])          // fractional ranges as array
+//!     .maps(reverb, "mix", 0.1, 0.4)           // ... or as min, max
+//!     .apply();                                // register for set_macro, HTTP and MIDI
+//! intensity.set(0.8);
+//! set_macro("intensity", 0.2);
+//! midi_open("Launch Control").cc(21).to_macro("intensity");
+//! ```
+//!
+//! Note that `a..b` truncates float bounds to integers, so use `[min, max]`
+//! or separate min/max arguments for fractional ranges.
+
+use crate::events::FadeTargetType;
+use crate::macros::{MacroControl, MacroTarget};
+use crate::state::StateMessage;
+use rhai::{CustomType, Dynamic, Engine, EvalAltResult, TypeBuilder};
+use std::ops::{Range, RangeInclusive};
+
+use super::require_handle;
+
+/// A macro control builder.
+#[derive(Debug, Clone, CustomType)]
+pub struct Macro {
+    /// Macro name.
+    pub name: String,
+    /// Macro definition.
+    control: MacroControl,
+}
+
+impl Macro {
+    /// Create a new macro with the given name.
+    pub fn new(name: String) -> Self {
+        Self {
+            control: MacroControl::new(name.clone()),
+            name,
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Map a parameter over a range (`a..b`, `a..=b` or `[min, max]`).
+    pub fn maps(self, target: Dynamic, param: String, range: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        let (min, max) = range_bounds(&range).ok_or_else(|| {
+            format!(
+                "macro '{}': expected a range like 200..4000 or [0.5, 1.0] for '{}'",
+                self.name, param
+            )
+        })?;
+        self.add_target(target, param, min, max)
+    }
+
+    /// Map a parameter from `min` (macro at 0) to `max` (macro at 1).
+    pub fn maps_min_max(
+        self,
+        target: Dynamic,
+        param: String,
+        min: Dynamic,
+        max: Dynamic,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        match (as_number(&min), as_number(&max)) {
+            (Some(min), Some(max)) => self.add_target(target, param, min, max),
+            _ => Err(format!("macro '{}': min and max for '{}' must be numbers", self.name, param).into()),
+        }
+    }
+
+    fn add_target(
+        mut self,
+        target: Dynamic,
+        param: String,
+        min: f64,
+        max: f64,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (target_type, name) = target_of(&target).ok_or_else(|| {
+            format!(
+                "macro '{}': expected a voice, group, pattern, melody, fx or group path",
+                self.name
+            )
+        })?;
+        self.control.targets.push(MacroTarget {
+            target_type,
+            name,
+            param,
+            min: min as f32,
+            max: max as f32,
+        });
+        Ok(self)
+    }
+
+    // === Actions ===
+
+    fn do_apply(&self) {
+        let handle = require_handle();
+        let _ = handle.send(StateMessage::DefineMacro {
+            control: self.control.clone(),
+        });
+    }
+
+    /// Register the macro (chainable).
+    pub fn apply(self) -> Self {
+        self.do_apply();
+        self
+    }
+
+    /// Register the macro and set its normalized value (0..1).
+    pub fn set(&mut self, value: f64) {
+        self.do_apply();
+        set_macro(self.name.clone(), value);
+    }
+
+    /// Set the normalized value (integer version).
+    pub fn set_int(&mut self, value: i64) {
+        self.set(value as f64);
+    }
+
+    /// Get the current value (0.0 if the macro was not applied).
+    pub fn value(&mut self) -> f64 {
+        let handle = require_handle();
+        handle.with_state(|state| state.macros.get(&self.name).map(|m| m.value).unwrap_or(0.0))
+    }
+}
+
+/// Get the target type and name of a voice, group, pattern, melody or fx.
+///
+/// Strings are taken as group paths.
+pub(super) fn target_of(target: &Dynamic) -> Option<(FadeTargetType, String)> {
+    if let Some(voice) = target.read_lock::<super::voice::Voice>() {
+        return Some((FadeTargetType::Voice, voice.name.clone()));
+    }
+    if let Some(group) = target.read_lock::<super::group::GroupHandle>() {
+        return Some((FadeTargetType::Group, group.path().to_string()));
+    }
+    if let Some(pattern) = target.read_lock::<super::pattern::Pattern>() {
+        return Some((FadeTargetType::Pattern, pattern.name.clone()));
+    }
+    if let Some(melody) = target.read_lock::<super::melody::Melody>() {
+        return Some((FadeTargetType::Melody, melody.name.clone()));
+    }
+    if let Some(fx) = target.read_lock::<super::sequence::Fx>() {
+        return Some((FadeTargetType::Effect, fx.id.clone()));
+    }
+    let path = target.clone().into_string().ok()?;
+    let group = super::group::group(path);
+    Some((FadeTargetType::Group, group.path().to_string()))
+}
+
+/// Get the bounds of an integer range or a `[min, max]` array.
+fn range_bounds(range: &Dynamic) -> Option<(f64, f64)> {
+    if let Some(r) = range.read_lock::<Range<i64>>() {
+        return Some((r.start as f64, r.end as f64));
+    }
+    if let Some(r) = range.read_lock::<RangeInclusive<i64>>() {
+        return Some((*r.start() as f64, *r.end() as f64));
+    }
+    let arr = range.clone().into_array().ok()?;
+    match arr.as_slice() {
+        [min, max] => Some((as_number(min)?, as_number(max)?)),
+        _ => None,
+    }
+}
+
+fn as_number(value: &Dynamic) -> Option<f64> {
+    value.as_float().ok().or_else(|| value.as_int().ok().map(|v| v as f64))
+}
+
+/// Create a new macro builder.
+pub fn macro_control(name: String) -> Macro {
+    Macro::new(name)
+}
+
+/// Set a macro's normalized value (0..1) by name.
+pub fn set_macro(name: String, value: f64) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::SetMacro { name, value });
+}
+
+/// Set a macro's value by name (integer version).
+pub fn set_macro_int(name: String, value: i64) {
+    set_macro(name, value as f64);
+}
+
+/// Register macro control API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    // Register types
+    engine.build_type::<Macro>();
+
+    // Constructors
+    engine.register_fn("macro", macro_control);
+    engine.register_fn("set_macro", set_macro);
+    engine.register_fn("set_macro", set_macro_int);
+
+    // Builder methods
+    engine.register_fn("maps", Macro::maps);
+    engine.register_fn("maps", Macro::maps_min_max);
+
+    // Actions
+    engine.register_fn("apply", Macro::apply);
+    engine.register_fn("set", Macro::set);
+    engine.register_fn("set", Macro::set_int);
+    engine.register_fn("value", Macro::value);
+    engine.register_get("name", |m: &mut Macro| m.name.clone());
+}
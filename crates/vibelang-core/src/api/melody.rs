@@ -1,16 +1,26 @@
 //! Melody API for Rhai scripts.
 //!
 //! Melodies are pitched sequences that trigger voices with note information.
+//!
+//! `melody.to_string()` writes a melody in the shareable text format of
+//! [`crate::loop_text`] and `melody_from_string(text)` reads it back.
 
+use crate::chord::{ArpMode, Arpeggiator, Chord};
 use crate::events::{BeatEvent, Pattern as PatternData};
+use crate::groove::Humanize;
+use crate::loop_text::{LoopText, LoopTextKind, DEFAULT_MELODY_GATE};
+use crate::meter_condition::MeterCondition;
+use crate::scale::{parse_root_note, Scale, ScaleKind, ScaleSnap};
+use crate::scheduler::LoopKind;
 use crate::sequences::{ClipMode, ClipSource, SequenceClip, SequenceDefinition};
-use crate::state::{LoopStatus, StateMessage};
+use crate::state::{LoopStatus, QuotaKind, StateMessage};
 use rhai::{CustomType, Dynamic, Engine, EvalAltResult, NativeCallContext, Position, TypeBuilder};
 use std::collections::HashMap;
 
-use super::bar_utils::split_into_bars;
+use super::bar_utils::{auto_loop_length, split_into_bars};
 use super::context::{self, SourceLocation};
-use super::require_handle;
+use super::helpers::{beats_arg, current_time_signature};
+use super::{check_quota, require_handle};
 
 /// A Melody builder for creating melodic patterns.
 #[derive(Debug, Clone, CustomType)]
@@ -24,8 +34,10 @@ pub struct Melody {
     /// Notes pattern strings (one per lane, from .notes() calls).
     /// Multiple .notes() calls create polyphonic melodies with multiple lanes.
     notes_strings: Vec<String>,
-    /// Loop length in beats.
-    length: f64,
+    /// Loop length in beats; inferred from the notes when `None`.
+    length: Option<f64>,
+    /// Beats the notes given so far span.
+    content_length: f64,
     /// Default gate (note duration as fraction of step).
     gate: f64,
     /// Transpose in semitones.
@@ -36,10 +48,22 @@ pub struct Melody {
     scale: Option<String>,
     /// Root note.
     root: Option<String>,
+    /// Whether notes are snapped to the scale (or the session key without one).
+    quantize_to_scale: bool,
+    /// Arpeggiator playing the held notes one after another.
+    arp: Option<Arpeggiator>,
     /// Group path.
     group_path: String,
     /// Parameters.
     params: HashMap<String, f64>,
+    /// Meter conditions that must all hold for an event to fire.
+    conditions: Vec<MeterCondition>,
+    /// Random timing and velocity variation of the events.
+    humanize: Option<Humanize>,
+    /// Launch grid in beats, overriding the global quantization.
+    launch_quantization: Option<f64>,
+    /// Whether launching takes over the phase of what plays the same voice.
+    legato: bool,
     /// Source location where this melody was defined.
     source_location: SourceLocation,
 }
@@ -68,14 +92,21 @@ impl Melody {
             voice_name: None,
             notes: Vec::new(),
             notes_strings: Vec::new(),
-            length: 4.0,
-            gate: 0.5,
+            length: None,
+            content_length: 0.0,
+            gate: DEFAULT_MELODY_GATE,
             transpose: 0,
             swing: 0.0,
             scale: None,
             root: None,
+            quantize_to_scale: false,
+            arp: None,
             group_path: context::current_group_path(),
             params: HashMap::new(),
+            conditions: Vec::new(),
+            humanize: None,
+            launch_quantization: None,
+            legato: false,
             source_location,
         }
     }
@@ -100,32 +131,127 @@ impl Melody {
         self
     }
 
+    /// Set the root note and the scale (e.g. `.scale("D", "dorian")`).
+    pub fn scale_on(mut self, root_note: String, scale_name: String) -> Result<Self, Box<EvalAltResult>> {
+        if ScaleKind::parse(&scale_name).is_none() {
+            return Err(format!("melody '{}': unknown scale '{}'", self.name, scale_name).into());
+        }
+        self.root = Some(root_note);
+        self.scale = Some(scale_name);
+        Ok(self)
+    }
+
     /// Set the root note.
     pub fn root(mut self, root_note: String) -> Self {
         self.root = Some(root_note);
         self
     }
 
-    /// Set notes from an array.
+    /// Set notes from scale degrees, spread over the loop length like `.notes([...])`.
+    ///
+    /// Degrees count like in music theory: 1 is the root, 3 the third, 8 the
+    /// root an octave up and -1 the step below the root; 0 is a rest. `"~"`
+    /// holds the previous note. Set the scale first.
     ///
     /// # Example
     /// ```rhai
-    /// melody("lead").notes(["C4", "E4", "G4", "C5"])
+    /// melody("lead").scale("D", "dorian").degrees([1, 3, 5, 7])
+    /// ```
+    pub fn degrees(self, degrees: rhai::Array) -> Result<Self, Box<EvalAltResult>> {
+        let scale = melody_scale(&self.scale, &self.root);
+        let notes = degrees
+            .into_iter()
+            .map(|degree| match degree.as_int() {
+                Ok(0) => Ok(Dynamic::from("r")),
+                Ok(d) if d > 0 => Ok(Dynamic::from(scale.note(d as i32 - 1) as i64)),
+                Ok(d) => Ok(Dynamic::from(scale.note(d as i32) as i64)),
+                Err(_) if degree.is_string() => Ok(degree),
+                Err(kind) => Err(format!("melody '{}': degrees() takes integers, not {}", self.name, kind)),
+            })
+            .collect::<Result<rhai::Array, String>>()?;
+        Ok(self.notes_array(notes))
+    }
+
+    /// Snap notes that don't belong to the scale to the nearest one that does.
+    ///
+    /// Without a `.scale()`, notes snap to the session key (`set_key`). Notes
+    /// recorded into the melody from MIDI takes are snapped as well.
+    pub fn quantize_to_scale(mut self) -> Self {
+        self.quantize_to_scale = true;
+        self
+    }
+
+    /// What the melody's notes snap to, if they snap.
+    fn scale_snap(&self) -> Option<ScaleSnap> {
+        match (self.quantize_to_scale, &self.scale) {
+            (false, _) => None,
+            (true, Some(_)) => Some(ScaleSnap::Scale(melody_scale(&self.scale, &self.root))),
+            (true, None) => Some(ScaleSnap::SessionKey),
+        }
+    }
+
+    /// Play held notes and chords one after another, a step every `rate`
+    /// (a note value like `"1/16"`, or a time like `0.25.beats`).
+    ///
+    /// Modes: "up", "down", "updown", "downup", "played" and "random".
+    ///
+    /// # Example
+    /// ```rhai
+    /// melody("pad").notes("C4:maj7 - - - | A3:m7 - - -").arp("updown", "1/16")
+    /// ```
+    pub fn arp(self, mode: String, rate: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.arp_octaves(mode, rate, 1)
+    }
+
+    /// Like `.arp(mode, rate)`, over `octaves` octaves.
+    pub fn arp_octaves(mut self, mode: String, rate: Dynamic, octaves: i64) -> Result<Self, Box<EvalAltResult>> {
+        let Some(mode) = ArpMode::parse(&mode) else {
+            return Err(format!(
+                "melody '{}': unknown arp mode '{}' (expected up, down, updown, downup, played or random)",
+                self.name, mode
+            )
+            .into());
+        };
+        let rate = arp_rate(&self.name, &rate)?;
+        self.arp = Some(Arpeggiator::new(mode, rate, octaves.clamp(1, 8) as u32));
+        Ok(self)
+    }
+
+    /// Set notes from an array, spread over the loop length (one bar by default).
+    ///
+    /// Entries are note names, MIDI numbers or `chord(..)`s. `"~"` holds the previous note for another step; `"-"`, `"."`, `"_"`
+    /// and `"r"` are rests.
+    ///
+    /// # Example
+    /// ```rhai
+    /// melody("lead").notes(["C4", "~", "G4", "r"])
     /// ```
     pub fn notes_array(mut self, note_array: rhai::Array) -> Self {
         self.notes.clear();
-        let step_duration = self.length / note_array.len().max(1) as f64;
+        let span = self.length.unwrap_or_else(|| current_time_signature().beats_per_bar());
+        self.content_length = span;
+        let step_duration = span / note_array.len().max(1) as f64;
+        // Whether a `~` extends the last note (no rest since)
+        let mut tied = false;
 
         for (i, note_val) in note_array.iter().enumerate() {
             let beat = i as f64 * step_duration;
 
             if let Ok(note_str) = note_val.clone().into_immutable_string() {
                 let note_str = note_str.as_str();
-                if note_str == "-" || note_str == "." || note_str == "_" {
+                if note_str == "~" {
+                    if let Some(note) = self.notes.last_mut().filter(|_| tied) {
+                        note.gate += step_duration;
+                    }
+                    continue;
+                }
+                tied = false;
+                if matches!(note_str, "-" | "." | "_" | "r" | "R") {
                     // Rest
                     continue;
                 }
                 if let Some(midi_notes) = parse_note(note_str) {
+                    tied = true;
                     self.notes.push(MelodyNote {
                         beat,
                         notes: midi_notes,
@@ -133,7 +259,16 @@ impl Melody {
                         gate: self.gate,
                     });
                 }
+            } else if let Some(chord) = note_val.read_lock::<Chord>() {
+                tied = true;
+                self.notes.push(MelodyNote {
+                    beat,
+                    notes: chord.notes(),
+                    velocity: 1.0,
+                    gate: self.gate,
+                });
             } else if let Ok(midi) = note_val.as_int() {
+                tied = midi > 0;
                 if midi > 0 {
                     self.notes.push(MelodyNote {
                         beat,
@@ -169,24 +304,20 @@ impl Melody {
     ///
     /// Format:
     /// - Note names like "C4", "E1", "G#3"
-    /// - `-` extends the previous note (tie)
-    /// - `.` is a rest
-    /// - `|` separates bars (each bar is 4 beats in 4/4 time)
+    /// - `-` or `~` extends the previous note (tie); tied steps become one
+    ///   note with a longer gate
+    /// - `.`, `_` or `r` is a rest
+    /// - `|` separates bars (of the current time signature)
     /// - Whitespace is optional (for readability)
     /// - Leading/trailing `|` are ignored
     /// - Consecutive `||` are collapsed to single bar separator
     pub fn notes(mut self, notes_str: String) -> Self {
         // Use unified bar splitting (handles leading/trailing/consecutive pipes)
         let bars = split_into_bars(&notes_str);
-        let num_bars = bars.len().max(1); // At least 1 bar
-        let beats_per_bar = 4.0; // Standard 4/4 time
-
-        // Calculate loop length from pattern
-        // Use the maximum length across all lanes
-        let loop_length = num_bars as f64 * beats_per_bar;
-        if loop_length > self.length {
-            self.length = loop_length;
-        }
+        let beats_per_bar = current_time_signature().beats_per_bar();
+
+        // The longest lane sets the inferred loop length
+        self.content_length = self.content_length.max(bars.len() as f64 * beats_per_bar);
 
         let mut current_beat = 0.0;
         let mut current_notes: Option<Vec<u8>> = None;
@@ -279,12 +410,25 @@ impl Melody {
         self
     }
 
-    /// Set the loop length in beats.
-    pub fn len(mut self, beats: f64) -> Self {
-        self.length = beats;
+    /// Set the loop length (e.g. `2.bars`).
+    pub fn len(mut self, length: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.length = Some(beats_arg("len", &length)?);
+        Ok(self)
+    }
+
+    /// Infer the loop length from the notes: the bars they span (the default).
+    pub fn auto_length(mut self) -> Self {
+        self.length = None;
         self
     }
 
+    /// Loop length in beats: the explicit length, or the notes rounded up to whole bars.
+    fn loop_length(&self) -> f64 {
+        self.length.unwrap_or_else(|| {
+            auto_loop_length(self.content_length, current_time_signature().beats_per_bar())
+        })
+    }
+
     /// Set the default gate (note duration).
     pub fn gate(mut self, gate: f64) -> Self {
         self.gate = gate.clamp(0.0, 1.0);
@@ -303,6 +447,25 @@ impl Melody {
         self
     }
 
+    /// Loosen the melody up: each note moves up to `timing_ms` early or late and
+    /// its amp up to `velocity_pct` percent up or down, drawn anew on every
+    /// pass through the loop. `humanize(0, 0)` plays it straight again.
+    ///
+    /// # Example
+    /// ```rhai
+    /// melody("lead").on(lead).notes("C4 E4 G4 B4").humanize(8, 10)
+    /// ```
+    pub fn humanize(mut self, timing_ms: f64, velocity_pct: f64) -> Self {
+        let humanize = Humanize::new(timing_ms, velocity_pct);
+        self.humanize = (humanize != Humanize::default()).then_some(humanize);
+        self
+    }
+
+    /// Humanize with whole milliseconds and percent.
+    pub fn humanize_int(self, timing_ms: i64, velocity_pct: i64) -> Self {
+        self.humanize(timing_ms as f64, velocity_pct as f64)
+    }
+
     /// Set quantization.
     pub fn quantize(self, _beats: f64) -> Self {
         // TODO: Implement quantization
@@ -315,6 +478,29 @@ impl Melody {
         self
     }
 
+    /// Only fire events while a meter condition holds (chained calls must all hold).
+    ///
+    /// # Example
+    /// ```rhai
+    /// melody("fill").on("snare").only_when(meter("main/Bass") < 0.1)
+    /// ```
+    pub fn only_when(mut self, condition: MeterCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Launch on `grid` instead of the global quantization (0 = immediately).
+    pub fn launch_quantize(mut self, grid: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.launch_quantization = Some(beats_arg("launch_quantize", &grid)?.max(0.0));
+        Ok(self)
+    }
+
+    /// Launch in legato: take over from whatever plays the same voice at its phase.
+    pub fn legato(mut self, legato: bool) -> Self {
+        self.legato = legato;
+        self
+    }
+
     /// Create a lane for multi-parameter melodies.
     pub fn lane(self, param: String) -> MelodyLaneBuilder {
         MelodyLaneBuilder {
@@ -323,6 +509,24 @@ impl Melody {
         }
     }
 
+    /// The melody in the shareable text format.
+    ///
+    /// Holds the lanes given as strings with `.notes("...")`; notes given
+    /// as an array are not part of it.
+    pub fn to_text(&self) -> LoopText {
+        let mut text = LoopText::new(LoopTextKind::Melody, &self.name);
+        text.voice = self.voice_name.clone();
+        text.scale = self.scale.clone();
+        text.root = self.root.clone();
+        text.lanes = self.notes_strings.clone();
+        text.length = self.length;
+        text.swing = self.swing;
+        text.gate = self.gate;
+        text.transpose = self.transpose;
+        text.params = self.params.iter().map(|(param, value)| (param.clone(), *value)).collect();
+        text
+    }
+
     // === Actions ===
 
     /// Register and apply the melody (chainable, returns self for use in sequences).
@@ -337,20 +541,33 @@ impl Melody {
 
         // Capture transpose before the closure to avoid borrow issues
         let transpose = self.transpose;
+        let snap = self.scale_snap();
+        let snap_scale = snap.and_then(|snap| snap.resolve(handle.with_state(|s| s.session_key)));
+        let loop_length = self.loop_length();
+        if self.content_length > loop_length + 1e-9 {
+            log::warn!(
+                "melody '{}': notes span {} beats but the loop is {} beats; later notes never play",
+                self.name, self.content_length, loop_length
+            );
+        }
 
         // Convert notes to events (chords generate multiple events at the same beat)
         let events: Vec<BeatEvent> = self
             .notes
             .iter()
-            .flat_map(|n| {
+            .enumerate()
+            .flat_map(|(index, n)| {
                 let beat = n.beat;
                 let velocity = n.velocity;
                 let gate = n.gate;
                 n.notes.iter().map(move |&note| {
-                    let transposed_note = (note as i64 + transpose).clamp(0, 127) as u8;
-                    // Convert MIDI note to frequency: freq = 440 * 2^((note - 69) / 12)
-                    let freq = 440.0 * 2.0_f64.powf((transposed_note as f64 - 69.0) / 12.0);
+                    let mut transposed_note = (note as i64 + transpose).clamp(0, 127) as u8;
+                    if let Some(scale) = snap_scale {
+                        transposed_note = scale.quantize(transposed_note);
+                    }
+                    let freq = crate::pitch::note_to_freq(transposed_note as f64);
                     let mut event = BeatEvent::new(beat, "melody_note");
+                    event.step = Some(index);
                     event.controls.push(("freq".to_string(), freq as f32));
                     event.controls.push(("amp".to_string(), velocity as f32));
                     event.controls.push(("gate".to_string(), gate as f32));
@@ -362,7 +579,7 @@ impl Melody {
         let loop_pattern = PatternData {
             name: self.name.clone(),
             events,
-            loop_length_beats: self.length,
+            loop_length_beats: loop_length,
             phase_offset: 0.0,
         };
 
@@ -377,6 +594,24 @@ impl Melody {
             source_location: self.source_location.clone(),
             notes_patterns,
         });
+        let _ = handle.send(StateMessage::SetMelodyScaleSnap {
+            name: self.name.clone(),
+            snap,
+        });
+        let _ = handle.send(StateMessage::SetMelodyArp {
+            name: self.name.clone(),
+            arp: self.arp,
+        });
+        let _ = handle.send(StateMessage::SetLoopConditions {
+            name: self.name.clone(),
+            kind: LoopKind::Melody,
+            conditions: self.conditions.clone(),
+        });
+        let _ = handle.send(StateMessage::SetLoopHumanize {
+            name: self.name.clone(),
+            kind: LoopKind::Melody,
+            humanize: self.humanize,
+        });
     }
 
     /// Start the melody playing (chainable).
@@ -389,14 +624,17 @@ impl Melody {
 
         // Create an implicit sequence for this melody
         let seq_name = format!("_seq_{}", self.name);
-        let seq_def = SequenceDefinition::new(seq_name.clone())
-            .with_loop_beats(self.length)
+        let loop_length = self.loop_length();
+        let mut seq_def = SequenceDefinition::new(seq_name.clone())
+            .with_loop_beats(loop_length)
             .with_clip(SequenceClip::new(
                 0.0,
-                self.length,
+                loop_length,
                 ClipSource::Melody(self.name.clone()),
                 ClipMode::Loop,
-            ));
+            ))
+            .with_legato(self.legato);
+        seq_def.launch_quantization = self.launch_quantization;
 
         // Register and start the sequence
         let _ = handle.send(StateMessage::CreateSequence { sequence: seq_def });
@@ -454,8 +692,10 @@ impl MelodyLaneBuilder {
 }
 
 /// Create a new melody builder with source location tracking.
-pub fn melody(ctx: NativeCallContext, name: String) -> Melody {
-    Melody::new(ctx, name)
+pub fn melody(ctx: NativeCallContext, name: String) -> Result<Melody, Box<EvalAltResult>> {
+    let name = context::namespaced(&name);
+    check_quota(QuotaKind::Patterns, &name)?;
+    Ok(Melody::new(ctx, name))
 }
 
 /// Token type for bar parsing.
@@ -465,9 +705,9 @@ enum NoteToken {
     Notes(Vec<u8>),
     /// A scale degree (0 = root, positive = up the scale, negative = below root) with optional chord quality
     ScaleDegree(i8, Option<String>),
-    /// Tie/continuation marker (-)
+    /// Tie/continuation marker (- or ~)
     Tie,
-    /// Rest marker (. or _)
+    /// Rest marker (., _ or r)
     Rest,
 }
 
@@ -483,6 +723,10 @@ fn tokenize_bar(bar: &str) -> Vec<NoteToken> {
             // Whitespace is ignored (just for visual separation)
             ' ' | '\t' | '\n' | '\r' => {}
 
+            '~' => {
+                tokens.push(NoteToken::Tie);
+            }
+
             // Tie/continuation marker OR negative scale degree
             // If '-' is directly followed by a digit, it's a negative scale degree
             '-' => {
@@ -521,7 +765,7 @@ fn tokenize_bar(bar: &str) -> Vec<NoteToken> {
             }
 
             // Rest markers
-            '.' | '_' => {
+            '.' | '_' | 'r' | 'R' => {
                 tokens.push(NoteToken::Rest);
             }
 
@@ -603,87 +847,42 @@ fn tokenize_bar(bar: &str) -> Vec<NoteToken> {
     tokens
 }
 
-/// Get scale intervals by name.
-/// Returns semitone offsets from root for each scale degree (1-7).
-fn get_scale_intervals(scale_name: &str) -> Vec<i8> {
-    match scale_name.to_lowercase().as_str() {
-        "major" | "ionian" => vec![0, 2, 4, 5, 7, 9, 11],
-        "minor" | "natural_minor" | "aeolian" => vec![0, 2, 3, 5, 7, 8, 10],
-        "dorian" => vec![0, 2, 3, 5, 7, 9, 10],
-        "phrygian" => vec![0, 1, 3, 5, 7, 8, 10],
-        "lydian" => vec![0, 2, 4, 6, 7, 9, 11],
-        "mixolydian" => vec![0, 2, 4, 5, 7, 9, 10],
-        "locrian" => vec![0, 1, 3, 5, 6, 8, 10],
-        "harmonic_minor" => vec![0, 2, 3, 5, 7, 8, 11],
-        "melodic_minor" => vec![0, 2, 3, 5, 7, 9, 11],
-        "pentatonic" | "major_pentatonic" => vec![0, 2, 4, 7, 9, 12, 14], // Extended to 7 degrees
-        "minor_pentatonic" => vec![0, 3, 5, 7, 10, 12, 15],
-        "blues" => vec![0, 3, 5, 6, 7, 10, 12],
-        _ => vec![0, 2, 4, 5, 7, 9, 11], // Default to major
-    }
+/// Scale of a melody's `.scale()` and `.root()`; C4 major by default, and
+/// major for unknown scale names.
+fn melody_scale(scale: &Option<String>, root: &Option<String>) -> Scale {
+    let kind = scale.as_deref().and_then(ScaleKind::parse).unwrap_or(ScaleKind::Major);
+    // parse_root_note returns full MIDI note (e.g., "D4" -> 62, "D" -> 62, "D2" -> 38)
+    Scale::new(root.as_deref().map(parse_root_note).unwrap_or(60), kind)
 }
 
-/// Parse a root note name to MIDI note number.
-/// Supports formats like "D", "D4", "F#", "F#3", etc.
-/// Returns the MIDI note number (defaults to octave 4 if not specified).
-fn parse_root_note(root: &str) -> u8 {
-    let root = root.trim();
-    if root.is_empty() {
-        return 60; // Default to C4
-    }
-
-    let mut chars = root.chars().peekable();
-
-    // Parse note letter
-    let base: i16 = match chars.next().unwrap_or('C').to_ascii_uppercase() {
-        'C' => 0,
-        'D' => 2,
-        'E' => 4,
-        'F' => 5,
-        'G' => 7,
-        'A' => 9,
-        'B' => 11,
-        _ => 0,
-    };
-
-    // Parse accidental
-    let mut accidental: i16 = 0;
-    while let Some(&c) = chars.peek() {
-        match c {
-            '#' | '♯' => {
-                accidental += 1;
-                chars.next();
+/// Step length in beats of `.arp()`: a note value ("1/16", "1/8t" for
+/// triplets, "1/8." dotted) or a time.
+fn arp_rate(melody: &str, rate: &Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    let beats = match rate.clone().into_immutable_string().ok() {
+        Some(value) => {
+            let (value, factor) = if let Some(v) = value.strip_suffix('t') {
+                (v, 2.0 / 3.0)
+            } else if let Some(v) = value.strip_suffix('.') {
+                (v, 1.5)
+            } else {
+                (value.as_str(), 1.0)
+            };
+            let fraction = value
+                .split_once('/')
+                .and_then(|(n, d)| Some(n.trim().parse::<f64>().ok()? / d.trim().parse::<f64>().ok()?));
+            match fraction {
+                // A whole note is four beats
+                Some(fraction) => fraction * 4.0 * factor,
+                None => return Err(format!("melody '{}': arp rate '{}' is not a note value like \"1/16\"", melody, value).into()),
             }
-            'b' | '♭' => {
-                accidental -= 1;
-                chars.next();
-            }
-            _ => break,
-        }
-    }
-
-    // Parse octave (default to 4 if not specified)
-    let octave_str: String = {
-        let mut result = String::new();
-        // Optional leading minus for negative octaves (e.g., "C-1")
-        if chars.peek() == Some(&'-') {
-            result.push(chars.next().unwrap());
         }
-        // Collect digits
-        while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
-            result.push(chars.next().unwrap());
-        }
-        result
-    };
-    let octave: i16 = if octave_str.is_empty() {
-        4 // Default octave
-    } else {
-        octave_str.parse().unwrap_or(4)
+        None => beats_arg("arp", rate)?,
     };
-
-    // Calculate MIDI note: (octave + 1) * 12 + base + accidental
-    let midi = (octave + 1) * 12 + base + accidental;
-    midi.clamp(0, 127) as u8
+    if !beats.is_finite() || beats <= 0.0 {
+        // `1/16` without quotes is integer division: 0
+        return Err(format!("melody '{}': arp rate must be above zero; write \"1/16\" or 0.25.beats", melody).into());
+    }
+    Ok(beats)
 }
 
 /// Resolve a scale degree to MIDI note(s).
@@ -695,82 +894,19 @@ fn resolve_scale_degree(
     scale: &Option<String>,
     root: &Option<String>,
 ) -> Vec<u8> {
-    let scale_intervals = scale
-        .as_ref()
-        .map(|s| get_scale_intervals(s))
-        .unwrap_or_else(|| vec![0, 2, 4, 5, 7, 9, 11]); // Default to major
-
-    // parse_root_note returns full MIDI note (e.g., "D4" -> 62, "D" -> 62, "D2" -> 38)
-    let base_midi = root.as_ref().map(|r| parse_root_note(r)).unwrap_or(60) as i16;
-
-    let scale_len = scale_intervals.len() as i16;
-    let degree_i16 = degree as i16;
-
-    // Use euclidean division for proper handling of negative degrees
     // degree 0 = root, 1 = second, ..., 6 = seventh
     // degree -1 = seventh one octave down, -2 = sixth one octave down, etc.
-    let octave_offset = degree_i16.div_euclid(scale_len);
-    let degree_idx = degree_i16.rem_euclid(scale_len) as usize;
-    let interval = scale_intervals[degree_idx] as i16;
-
-    let root_note = (base_midi + octave_offset * 12 + interval).clamp(0, 127) as u8;
+    let root_note = melody_scale(scale, root).note(degree as i32);
 
     // If chord quality is specified, build the chord
-    if let Some(quality) = chord_quality {
-        if let Some(chord_intervals) = get_chord_intervals(quality) {
-            return chord_intervals
-                .iter()
-                .filter_map(|&offset| {
-                    let midi = root_note as i16 + offset as i16;
-                    if (0..=127).contains(&midi) {
-                        Some(midi as u8)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-        }
+    if let Some(chord) = chord_quality.as_deref().and_then(|quality| Chord::new(root_note, quality)) {
+        return chord.notes();
     }
 
     // Single note
     vec![root_note]
 }
 
-/// Get chord intervals by quality name.
-/// Returns semitone offsets from root note.
-fn get_chord_intervals(quality: &str) -> Option<Vec<i8>> {
-    match quality.to_lowercase().as_str() {
-        // Triads
-        "maj" | "major" => Some(vec![0, 4, 7]),
-        "min" | "m" | "minor" => Some(vec![0, 3, 7]),
-        "dim" | "diminished" => Some(vec![0, 3, 6]),
-        "aug" | "augmented" => Some(vec![0, 4, 8]),
-        "sus2" => Some(vec![0, 2, 7]),
-        "sus4" => Some(vec![0, 5, 7]),
-
-        // Seventh chords
-        "maj7" | "major7" => Some(vec![0, 4, 7, 11]),
-        "7" | "dom7" => Some(vec![0, 4, 7, 10]),
-        "min7" | "m7" => Some(vec![0, 3, 7, 10]),
-        "dim7" => Some(vec![0, 3, 6, 9]),
-        "m7b5" | "half-dim" => Some(vec![0, 3, 6, 10]),
-        "mmaj7" | "minmaj7" => Some(vec![0, 3, 7, 11]),
-
-        // Extended
-        "9" => Some(vec![0, 4, 7, 10, 14]),
-        "maj9" => Some(vec![0, 4, 7, 11, 14]),
-        "m9" | "min9" => Some(vec![0, 3, 7, 10, 14]),
-        "add9" => Some(vec![0, 4, 7, 14]),
-        "6" => Some(vec![0, 4, 7, 9]),
-        "m6" | "min6" => Some(vec![0, 3, 7, 9]),
-
-        // Power chord
-        "5" | "power" => Some(vec![0, 7]),
-
-        _ => None,
-    }
-}
-
 /// Parse a note or chord to MIDI note number(s).
 /// Supports single notes ("C4") and chords ("C4:maj7").
 fn parse_note(name: &str) -> Option<Vec<u8>> {
@@ -787,25 +923,7 @@ fn parse_note(name: &str) -> Option<Vec<u8>> {
         // Parse the root note
         let root = parse_single_note(note_part)?;
 
-        // Get chord intervals and build note list
-        let intervals = get_chord_intervals(quality)?;
-        let notes: Vec<u8> = intervals
-            .iter()
-            .filter_map(|&interval| {
-                let midi = root as i16 + interval as i16;
-                if (0..=127).contains(&midi) {
-                    Some(midi as u8)
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        if notes.is_empty() {
-            None
-        } else {
-            Some(notes)
-        }
+        Chord::new(root, quality).map(|chord| chord.notes()).filter(|notes| !notes.is_empty())
     } else {
         // No colon - single note (backward compatible)
         parse_single_note(name).map(|n| vec![n])
@@ -882,6 +1000,27 @@ fn parse_single_note(name: &str) -> Option<u8> {
     }
 }
 
+/// Read a melody from the shareable text format (see [`crate::loop_text`]).
+pub fn melody_from_string(ctx: NativeCallContext, text: String) -> Result<Melody, Box<EvalAltResult>> {
+    let text = LoopText::parse(&text).map_err(|e| format!("melody_from_string: {:#}", e))?;
+    if text.kind != LoopTextKind::Melody {
+        return Err(format!("melody_from_string: '{}' is a {}, not a melody", text.name, text.kind.as_str()).into());
+    }
+
+    let mut melody = melody(ctx, text.name)?;
+    melody.voice_name = text.voice;
+    // Degrees resolve against the scale when the notes are read
+    melody.scale = text.scale;
+    melody.root = text.root;
+    melody.length = text.length;
+    melody = melody.gate(text.gate).transpose(text.transpose).swing(text.swing);
+    melody.params = text.params.into_iter().collect();
+    for lane in text.lanes {
+        melody = melody.notes(lane);
+    }
+    Ok(melody)
+}
+
 /// Register melody API with the Rhai engine.
 pub fn register(engine: &mut Engine) {
     // Register Melody type
@@ -890,20 +1029,33 @@ pub fn register(engine: &mut Engine) {
 
     // Constructor
     engine.register_fn("melody", melody);
+    engine.register_fn("melody_from_string", melody_from_string);
 
     // Builder methods
     engine.register_fn("on", Melody::on);
     engine.register_fn("on", Melody::on_voice);
     engine.register_fn("scale", Melody::scale);
+    engine.register_fn("scale", Melody::scale_on);
     engine.register_fn("root", Melody::root);
+    engine.register_fn("degrees", Melody::degrees);
+    engine.register_fn("quantize_to_scale", Melody::quantize_to_scale);
+    engine.register_fn("arp", Melody::arp);
+    engine.register_fn("arp", Melody::arp_octaves);
     engine.register_fn("notes", Melody::notes);
     engine.register_fn("notes", Melody::notes_array);
     engine.register_fn("len", Melody::len);
+    engine.register_fn("auto_length", Melody::auto_length);
     engine.register_fn("gate", Melody::gate);
     engine.register_fn("transpose", Melody::transpose);
     engine.register_fn("swing", Melody::swing);
+    engine.register_fn("humanize", Melody::humanize);
+    engine.register_fn("humanize", Melody::humanize_int);
     engine.register_fn("quantize", Melody::quantize);
     engine.register_fn("set_param", Melody::set_param);
+    engine.register_fn("only_when", Melody::only_when);
+    engine.register_fn("launch_quantize", Melody::launch_quantize);
+    engine.register_fn("legato", Melody::legato);
+    engine.register_fn("legato", |x: Melody| x.legato(true));
     engine.register_fn("lane", Melody::lane);
 
     // Actions
@@ -912,7 +1064,134 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("stop", Melody::stop);
     engine.register_fn("launch", Melody::launch);
     engine.register_fn("is_playing", Melody::is_playing);
+    engine.register_fn("to_string", |m: &mut Melody| m.to_text().to_string());
 
     // Lane builder
     engine.register_fn("values", MelodyLaneBuilder::values);
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_rests_and_ties_merge_into_notes() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                let bass = voice("bass").synth("bass");
+                melody("line").on(bass).notes("c3 ~ ~ e3 | r g3 - R | ~ . c4 ~").apply();
+                melody("steps").on(bass).notes(["C3", "~", "r", "~"]).apply();
+                "#,
+            )
+            .unwrap();
+        sim.advance(0.0);
+
+        let notes = |name: &str| {
+            sim.handle().with_state(|state| {
+                let pattern = state.melodies[name].loop_pattern.clone().unwrap();
+                pattern
+                    .events
+                    .iter()
+                    .map(|e| {
+                        let freq = e.controls.iter().find(|(k, _)| k == "freq").unwrap().1 as f64;
+                        let gate = e.controls.iter().find(|(k, _)| k == "gate").unwrap().1 as f64;
+                        (e.beat, crate::pitch::freq_to_midi_note(freq), gate)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        // A tie after a rest stays silent; ties run across bar lines
+        assert_eq!(notes("line"), vec![(0.0, 48, 3.0), (3.0, 52, 1.0), (5.0, 55, 2.0), (10.0, 60, 2.0)]);
+        assert_eq!(notes("steps"), vec![(0.0, 48, 1.5)]);
+    }
+
+    #[test]
+    fn test_scale_degrees_and_quantize_to_scale() {
+        use crate::state::{RecordedMidiNote, StateMessage, TakeMode, TakeTargetKind};
+
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        crate::api::create_engine()
+            .run(
+                r#"
+                set_key("A minor");
+                let lead = voice("lead").synth("saw");
+                melody("arp").on(lead).scale("D", "dorian").degrees([1, 3, 0, 5, 7, 8, -1, "~"]).apply();
+                melody("snapped").on(lead).notes("C4 C#4 F#4 G#4").quantize_to_scale().apply();
+                "#,
+            )
+            .unwrap();
+        sim.advance(0.0);
+
+        let notes = |sim: &crate::runtime::Simulation, name: &str| {
+            sim.handle().with_state(|state| {
+                state.melodies[name]
+                    .loop_pattern
+                    .as_ref()
+                    .unwrap()
+                    .events
+                    .iter()
+                    .map(|e| {
+                        let freq = e.controls.iter().find(|(k, _)| k == "freq").unwrap().1 as f64;
+                        crate::pitch::freq_to_midi_note(freq)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(notes(&sim, "arp"), vec![62, 65, 69, 72, 74, 60]);
+        // Without a scale, notes snap to the session key
+        assert_eq!(notes(&sim, "snapped"), vec![60, 60, 65, 67]);
+
+        // Takes recorded into the melody snap as well
+        sim.handle().with_state_mut(|state| {
+            state.midi_recording.arm_take("snapped".to_string(), TakeTargetKind::Melody, None, TakeMode::Replace, 4.0);
+            state.midi_recording.capture_take_note(&RecordedMidiNote {
+                beat: 1.0,
+                note: 73,
+                velocity: 100,
+                duration: 0.5,
+                raw_beat: 1.0,
+                channel: 1,
+                voice_name: "lead".to_string(),
+            });
+        });
+        let id = sim.handle().with_state_mut(|state| state.midi_recording.finish_take()).unwrap();
+        sim.handle().send(StateMessage::MidiKeepTake { id }).unwrap();
+        sim.advance(0.0);
+        assert_eq!(notes(&sim, "snapped"), vec![72]);
+    }
+
+    #[test]
+    fn test_chords_arpeggiated_when_scheduled() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        crate::api::create_engine()
+            .run(
+                r#"
+                let keys = voice("keys").synth("saw");
+                melody("pad").on(keys).notes([chord("C4", "maj").inversion(1), "~", "r", "r"]).arp("up", "1/8").start();
+                "#,
+            )
+            .unwrap();
+        assert!(crate::api::create_engine().run(r#"melody("bad").arp("up", 1/16)"#).is_err());
+        sim.start();
+        sim.advance(3.5);
+
+        // The melody keeps its chord; the arpeggio is played from it
+        let held = sim.handle().with_state(|state| state.melodies["pad"].loop_pattern.as_ref().unwrap().events.len());
+        assert_eq!(held, 3);
+        let played: Vec<(f64, u8)> = sim
+            .events()
+            .iter()
+            .filter(|e| e.event.melody_name.as_deref() == Some("pad"))
+            .map(|e| {
+                let freq = e.event.controls.iter().find(|(k, _)| k == "freq").unwrap().1 as f64;
+                (e.beat, crate::pitch::freq_to_midi_note(freq))
+            })
+            .collect();
+        // Held for a beat and a half (gate 0.5 plus the tie)
+        assert_eq!(played, vec![(0.0, 64), (0.5, 67), (1.0, 72)]);
+    }
+}
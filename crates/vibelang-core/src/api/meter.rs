@@ -0,0 +1,125 @@
+//! Meter API for Rhai scripts.
+//!
+//! `meter(path)` reads a group's level and compares against a threshold to
+//! build a [`MeterCondition`] for `only_when` (see [`crate::meter_condition`]).
+//!
+//! ```rhai
+//! // Fill only while the bass is quiet
+//! pattern("fill").on("snare").step("..x.x.xx").only_when(meter("main/Bass") < 0.1).start();
+//!
+//! // Sparkles only while the pads are loud (RMS)
+//! melody("sparkle").on("bell").notes("C5 E5 G5 C6").only_when(meter("Pads").rms() > 0.3).start();
+//! ```
+
+use crate::meter_condition::{Comparison, MeterCondition, MeterMeasure};
+use rhai::{CustomType, Engine, TypeBuilder};
+use std::time::Instant;
+
+use super::get_handle;
+
+/// A group meter reading, compared with a number to build a condition.
+#[derive(Debug, Clone, CustomType)]
+pub struct Meter {
+    /// Full group path (e.g. "main/Bass").
+    pub group_path: String,
+    /// Peak or RMS.
+    measure: MeterMeasure,
+}
+
+impl Meter {
+    /// Create a meter for a group path; paths not starting with "main" are
+    /// taken relative to the main group.
+    pub fn new(path: String) -> Self {
+        let group_path = if path == "main" || path.starts_with("main/") {
+            path
+        } else {
+            format!("main/{}", path.trim_start_matches('/'))
+        };
+        Self {
+            group_path,
+            measure: MeterMeasure::Peak,
+        }
+    }
+
+    /// Compare the peak level (default).
+    pub fn peak(mut self) -> Self {
+        self.measure = MeterMeasure::Peak;
+        self
+    }
+
+    /// Compare the RMS level.
+    pub fn rms(mut self) -> Self {
+        self.measure = MeterMeasure::Rms;
+        self
+    }
+
+    /// Current level (linear amplitude, 0 if the group is not metered).
+    pub fn level(&mut self) -> f64 {
+        let condition = self.condition(Comparison::Greater, 0.0);
+        get_handle()
+            .map(|h| h.with_state(|state| condition.level(&state.meter_levels, Instant::now())))
+            .unwrap_or(0.0) as f64
+    }
+
+    fn condition(&self, comparison: Comparison, threshold: f64) -> MeterCondition {
+        MeterCondition {
+            group_path: self.group_path.clone(),
+            measure: self.measure,
+            comparison,
+            threshold: threshold as f32,
+        }
+    }
+}
+
+/// Create a meter for a group.
+pub fn meter(path: String) -> Meter {
+    Meter::new(path)
+}
+
+/// Register the meter API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<Meter>();
+    engine.register_type_with_name::<MeterCondition>("MeterCondition");
+
+    engine.register_fn("meter", meter);
+    engine.register_fn("peak", Meter::peak);
+    engine.register_fn("rms", Meter::rms);
+    engine.register_fn("level", Meter::level);
+    engine.register_get("group_path", |m: &mut Meter| m.group_path.clone());
+
+    // Comparisons build conditions
+    for (op, comparison) in [
+        ("<", Comparison::Less),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        (">=", Comparison::GreaterOrEqual),
+    ] {
+        engine.register_fn(op, move |m: Meter, threshold: f64| m.condition(comparison, threshold));
+        engine.register_fn(op, move |m: Meter, threshold: i64| m.condition(comparison, threshold as f64));
+    }
+
+    engine.register_fn("to_string", |c: &mut MeterCondition| c.to_string());
+    engine.register_fn("to_debug", |c: &mut MeterCondition| c.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_comparison_builds_condition() {
+        let mut engine = Engine::new();
+        register(&mut engine);
+
+        let condition: MeterCondition = engine.eval(r#"meter("Bass").rms() < 0.1"#).unwrap();
+        assert_eq!(condition.group_path, "main/Bass");
+        assert_eq!(condition.measure, MeterMeasure::Rms);
+        assert_eq!(condition.comparison, Comparison::Less);
+        assert!((condition.threshold - 0.1).abs() < 1e-6);
+
+        let condition: MeterCondition = engine.eval(r#"meter("main/Drums") >= 1"#).unwrap();
+        assert_eq!(condition.group_path, "main/Drums");
+        assert_eq!(condition.measure, MeterMeasure::Peak);
+        assert_eq!(condition.comparison, Comparison::GreaterOrEqual);
+    }
+}
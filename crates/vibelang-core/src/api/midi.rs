@@ -7,7 +7,8 @@
 //! - CC/fader mapping (input)
 //! - Callbacks for custom logic (input)
 //! - Sending MIDI notes, CCs, pitch bend (output)
-//! - MIDI clock output
+//! - Program changes and SysEx for patch switching (output)
+//! - MIDI clock output, and following an external MIDI clock
 
 use crate::api::require_handle;
 use crate::api::voice::Voice;
@@ -16,6 +17,7 @@ use crate::midi::{
     MidiOutputHandle, MidiOutputManager, NoteRoute, ParameterCurve, VelocityCurve,
 };
 use crate::state::StateMessage;
+use crate::timing::ClockSource;
 use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, Map};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -23,9 +25,18 @@ use std::sync::{Arc, Mutex, RwLock};
 
 // === Callback Storage ===
 
+/// A stored MIDI callback.
+#[derive(Clone)]
+struct StoredCallback {
+    fn_ptr: FnPtr,
+    /// Registered by an evaluation that may not register hooks (a remote
+    /// `/eval` or a sandbox); the callback runs under the same restriction.
+    hooks_denied: bool,
+}
+
 /// Global storage for MIDI callback FnPtrs.
 /// Callbacks are stored by ID and executed when MIDI events trigger them.
-static CALLBACK_STORAGE: std::sync::LazyLock<RwLock<HashMap<u64, FnPtr>>> =
+static CALLBACK_STORAGE: std::sync::LazyLock<RwLock<HashMap<u64, StoredCallback>>> =
     std::sync::LazyLock::new(|| RwLock::new(HashMap::new()));
 
 /// Counter for generating unique callback IDs.
@@ -45,6 +56,7 @@ static MIDI_OUTPUT_MANAGER: std::sync::LazyLock<Mutex<MidiOutputManager>> =
 
 /// Store a MIDI device to keep it alive, keyed by name for reuse.
 fn store_active_device(device: MidiDevice) {
+    super::incremental::mark_volatile();
     ACTIVE_MIDI_DEVICES
         .write()
         .unwrap()
@@ -70,14 +82,25 @@ pub fn clear_midi_devices() {
 
 /// Register a callback and return its ID.
 fn register_callback_fnptr(fn_ptr: FnPtr) -> u64 {
+    super::incremental::mark_volatile();
     let id = CALLBACK_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
-    CALLBACK_STORAGE.write().unwrap().insert(id, fn_ptr);
+    let callback = StoredCallback {
+        fn_ptr,
+        hooks_denied: super::sandbox::hooks_denied(),
+    };
+    CALLBACK_STORAGE.write().unwrap().insert(id, callback);
     id
 }
 
 /// Get a callback FnPtr by ID.
 pub fn get_callback_fnptr(id: u64) -> Option<FnPtr> {
-    CALLBACK_STORAGE.read().unwrap().get(&id).cloned()
+    CALLBACK_STORAGE.read().unwrap().get(&id).map(|c| c.fn_ptr.clone())
+}
+
+/// IDs of all stored callbacks.
+#[cfg(test)]
+pub(crate) fn callback_ids() -> Vec<u64> {
+    CALLBACK_STORAGE.read().unwrap().keys().copied().collect()
 }
 
 /// Clear all stored callbacks (called on script reload).
@@ -107,9 +130,11 @@ pub fn execute_pending_callbacks(
     let mut executed = 0;
 
     for callback in pending {
-        if let Some(fn_ptr) = get_callback_fnptr(callback.callback_id) {
+        let stored = CALLBACK_STORAGE.read().unwrap().get(&callback.callback_id).cloned();
+        if let Some(stored) = stored {
             // Call the callback with the velocity/value as argument
-            let result: Result<(), _> = fn_ptr.call(engine, ast, (callback.value,));
+            let call = || stored.fn_ptr.call::<()>(engine, ast, (callback.value,));
+            let result = if stored.hooks_denied { super::sandbox::deny_hooks(call) } else { call() };
 
             match result {
                 Ok(_) => {
@@ -121,7 +146,11 @@ pub fn execute_pending_callbacks(
                     );
                 }
                 Err(e) => {
-                    log::warn!("MIDI callback {} failed: {}", callback.callback_id, e);
+                    log::warn!(
+                        "MIDI callback {} failed: {}",
+                        callback.callback_id,
+                        super::watchdog::explain(&e)
+                    );
                 }
             }
         } else {
@@ -157,6 +186,18 @@ pub struct MidiDevice {
 }
 
 impl MidiDevice {
+    /// A device with neither input nor output.
+    #[cfg(test)]
+    pub(crate) fn detached(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            info: None,
+            input_manager: None,
+            output_handle: None,
+            output_device_id: None,
+        }
+    }
+
     /// Get the device name.
     pub fn name(&mut self) -> String {
         self.name.clone()
@@ -246,6 +287,61 @@ impl MidiDevice {
             .map_err(|e| Box::new(EvalAltResult::ErrorSystem("MIDI output error".into(), e.into())))
     }
 
+    /// Switch patches: program change on channel 1-16, program 0-127.
+    pub fn program_change(&mut self, channel: i64, program: i64) -> Result<(), Box<EvalAltResult>> {
+        self.send_raw(crate::midi_patch::program_change(
+            (channel.clamp(1, 16) - 1) as u8,
+            None,
+            program.clamp(0, 127) as u8,
+        ))
+    }
+
+    /// Switch patches with a bank select (0-16383) before the program change.
+    pub fn program_change_bank(&mut self, channel: i64, bank: i64, program: i64) -> Result<(), Box<EvalAltResult>> {
+        self.send_raw(crate::midi_patch::program_change(
+            (channel.clamp(1, 16) - 1) as u8,
+            Some(bank.clamp(0, 16383) as u16),
+            program.clamp(0, 127) as u8,
+        ))
+    }
+
+    /// Send SysEx from an array of bytes or a hex string like "F0 41 10 F7".
+    /// The F0/F7 framing is added if left out.
+    pub fn send_sysex(&mut self, data: Dynamic) -> Result<(), Box<EvalAltResult>> {
+        let messages = if let Some(text) = data.clone().try_cast::<String>() {
+            crate::midi_patch::parse_sysex_hex(&text)
+        } else if let Some(array) = data.try_cast::<Array>() {
+            let bytes = array
+                .iter()
+                .map(|b| b.as_int().ok().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| Box::new(EvalAltResult::from("SysEx bytes must be integers 0-255")))?;
+            crate::midi_patch::frame_sysex(bytes)
+        } else {
+            return Err("send_sysex() expects an array of bytes or a hex string".into());
+        };
+        self.send_raw(messages.map_err(|e| format!("Invalid SysEx: {:#}", e))?)
+    }
+
+    /// Send all SysEx messages of a `.syx` file.
+    pub fn send_sysex_file(&mut self, path: &str) -> Result<(), Box<EvalAltResult>> {
+        let path = crate::api::context::resolve_file_or_error(path)?;
+        let messages = crate::midi_patch::load_syx(&path).map_err(|e| format!("{:#}", e))?;
+        self.send_raw(messages)
+    }
+
+    fn send_raw(&self, messages: Vec<Vec<u8>>) -> Result<(), Box<EvalAltResult>> {
+        let handle = self.output_handle.as_ref().ok_or_else(|| {
+            Box::new(EvalAltResult::from("This MIDI device was not opened for output"))
+        })?;
+        for bytes in messages {
+            handle
+                .send(crate::midi::QueuedMidiEvent { bytes })
+                .map_err(|e| Box::new(EvalAltResult::ErrorSystem("MIDI output error".into(), e.into())))?;
+        }
+        Ok(())
+    }
+
     /// Get the output handle for use by Voice routing.
     pub fn get_output_handle(&self) -> Option<&MidiOutputHandle> {
         self.output_handle.as_ref()
@@ -485,6 +581,45 @@ impl CcRouteBuilder {
             max_value: max as f32,
             curve: self.curve.clone(),
             channel: self.channel,
+            param_range: false,
+        };
+
+        handle
+            .send(StateMessage::MidiAddCcRoute {
+                channel: self.channel,
+                cc_number: self.cc_number,
+                route,
+            })
+            .map_err(|e| Box::new(EvalAltResult::from(e.to_string())) as Box<EvalAltResult>)?;
+
+        Ok(())
+    }
+
+    /// Route to a voice parameter, scaled over the range its synthdef
+    /// declares (`param("cutoff", 1200, 20, 20000, "hz", "exp")`).
+    ///
+    /// Parameters without a declared range get 0..1.
+    pub fn to_voice_range(&mut self, voice: Dynamic, param: &str) -> Result<(), Box<EvalAltResult>> {
+        let voice_name = get_voice_name(&voice)?;
+        self.add_param_range_route(CcTarget::Voice(voice_name), param)
+    }
+
+    /// Route to an effect parameter, scaled over its declared range.
+    pub fn to_effect_range(&mut self, effect_id: &str, param: &str) -> Result<(), Box<EvalAltResult>> {
+        self.add_param_range_route(CcTarget::Effect(effect_id.to_string()), param)
+    }
+
+    fn add_param_range_route(&self, target: CcTarget, param: &str) -> Result<(), Box<EvalAltResult>> {
+        let handle = require_handle();
+
+        let route = CcRoute {
+            target,
+            param_name: param.to_string(),
+            min_value: 0.0,
+            max_value: 1.0,
+            curve: self.curve.clone(),
+            channel: self.channel,
+            param_range: true,
         };
 
         handle
@@ -515,6 +650,7 @@ impl CcRouteBuilder {
             max_value: max as f32,
             curve: self.curve.clone(),
             channel: self.channel,
+            param_range: false,
         };
 
         handle
@@ -545,6 +681,7 @@ impl CcRouteBuilder {
             max_value: max as f32,
             curve: self.curve.clone(),
             channel: self.channel,
+            param_range: false,
         };
 
         handle
@@ -574,6 +711,32 @@ impl CcRouteBuilder {
             max_value: max as f32,
             curve: self.curve.clone(),
             channel: self.channel,
+            param_range: false,
+        };
+
+        handle
+            .send(StateMessage::MidiAddCcRoute {
+                channel: self.channel,
+                cc_number: self.cc_number,
+                route,
+            })
+            .map_err(|e| Box::new(EvalAltResult::from(e.to_string())) as Box<EvalAltResult>)?;
+
+        Ok(())
+    }
+
+    /// Route to a macro control (0..1 over the CC range).
+    pub fn to_macro(&mut self, name: &str) -> Result<(), Box<EvalAltResult>> {
+        let handle = require_handle();
+
+        let route = CcRoute {
+            target: CcTarget::Macro(name.to_string()),
+            param_name: name.to_string(),
+            min_value: 0.0,
+            max_value: 1.0,
+            curve: self.curve.clone(),
+            channel: self.channel,
+            param_range: false,
         };
 
         handle
@@ -630,6 +793,7 @@ impl PitchBendRouteBuilder {
             max_value: max as f32,
             curve: self.curve.clone(),
             channel: self.channel,
+            param_range: false,
         };
 
         handle
@@ -878,7 +1042,7 @@ fn midi_devices() -> Array {
 ///
 /// Opens the device for both input AND output if both are available.
 /// If only input or only output is available, opens what's available.
-fn midi_open_by_name(name: &str) -> Result<MidiDevice, Box<EvalAltResult>> {
+pub(crate) fn midi_open_by_name(name: &str) -> Result<MidiDevice, Box<EvalAltResult>> {
     let runtime_handle = require_handle();
     let name_lower = name.to_lowercase();
 
@@ -1007,6 +1171,15 @@ fn midi_open_by_index(index: i64) -> Result<MidiDevice, Box<EvalAltResult>> {
     midi_open_by_name(&name)
 }
 
+/// Open a device for sending to external gear; fails if it has no output.
+fn midi_out(name: &str) -> Result<MidiDevice, Box<EvalAltResult>> {
+    let device = midi_open_by_name(name)?;
+    if device.output_handle.is_none() {
+        return Err(format!("MIDI device '{}' has no output", device.name).into());
+    }
+    Ok(device)
+}
+
 /// Open the first available MIDI device.
 fn midi_open_first() -> Result<MidiDevice, Box<EvalAltResult>> {
     midi_open_by_index(0)
@@ -1029,6 +1202,27 @@ fn midi_clock_enable(device: &mut MidiDevice) -> Result<(), Box<EvalAltResult>>
     Ok(())
 }
 
+/// Set how long the hardware on a MIDI output takes to sound, in
+/// milliseconds; its notes are sent that much earlier. Cleared on reload
+/// like the routing, so the script sets it again.
+///
+/// ```rhai
+/// set_output_latency_ms(20);
+/// midi_out("Prophet").latency_ms(12);
+/// ```
+fn midi_device_latency_ms(device: &mut MidiDevice, ms: f64) -> Result<MidiDevice, Box<EvalAltResult>> {
+    let device_id = device.output_device_id.ok_or_else(|| {
+        Box::new(EvalAltResult::from("This MIDI device was not opened for output"))
+    })?;
+    let _ = require_handle().send(StateMessage::MidiOutputSetDeviceLatency { device_id, ms });
+    Ok(device.clone())
+}
+
+/// Set the latency of a MIDI output in milliseconds (integer overload).
+fn midi_device_latency_ms_int(device: &mut MidiDevice, ms: i64) -> Result<MidiDevice, Box<EvalAltResult>> {
+    midi_device_latency_ms(device, ms as f64)
+}
+
 /// Disable MIDI clock output.
 fn midi_clock_disable() {
     let handle = require_handle();
@@ -1036,6 +1230,30 @@ fn midi_clock_disable() {
     log::info!("[MIDI] Clock output disabled");
 }
 
+/// Take tempo and position from the internal clock (`"internal"`) or follow
+/// the MIDI clock of hardware on an open MIDI input (`"midi"`): its ticks set
+/// the tempo, and start, stop, continue and song position drive the transport.
+///
+/// ```rhai
+/// midi_open("Digitakt", "input");
+/// set_clock_source("midi");
+/// ```
+fn set_clock_source(name: &str) -> Result<(), Box<EvalAltResult>> {
+    let source = ClockSource::parse(name).ok_or_else(|| {
+        Box::new(EvalAltResult::from(format!(
+            "set_clock_source: unknown clock source '{}' (expected \"internal\" or \"midi\")",
+            name
+        )))
+    })?;
+    let _ = require_handle().send(StateMessage::SetClockSource { source });
+    Ok(())
+}
+
+/// Name of the clock the transport follows ("internal" or "midi").
+fn clock_source() -> String {
+    require_handle().with_state(|state| state.clock_source).as_str().to_string()
+}
+
 /// Enable or disable MIDI monitoring.
 fn midi_monitor(enabled: bool) {
     let handle = require_handle();
@@ -1160,10 +1378,13 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("midi_open", midi_open_first);     // midi_open()
 
     // Global functions - monitoring and control
+    engine.register_fn("midi_out", midi_out);
     engine.register_fn("midi_monitor", midi_monitor);
     engine.register_fn("midi_clear", midi_clear);
     engine.register_fn("midi_clock_enable", midi_clock_enable);
     engine.register_fn("midi_clock_disable", midi_clock_disable);
+    engine.register_fn("set_clock_source", set_clock_source);
+    engine.register_fn("clock_source", clock_source);
 
     // MidiDevice info methods
     engine.register_fn("name", MidiDevice::name);
@@ -1189,6 +1410,12 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("note_off", MidiDevice::note_off);
     engine.register_fn("send_cc", MidiDevice::send_cc);
     engine.register_fn("send_pitch_bend", MidiDevice::send_pitch_bend);
+    engine.register_fn("program_change", MidiDevice::program_change);
+    engine.register_fn("program_change", MidiDevice::program_change_bank);
+    engine.register_fn("send_sysex", MidiDevice::send_sysex);
+    engine.register_fn("send_sysex_file", MidiDevice::send_sysex_file);
+    engine.register_fn("latency_ms", midi_device_latency_ms);
+    engine.register_fn("latency_ms", midi_device_latency_ms_int);
 
     // KeyboardRouteBuilder methods
     engine.register_fn("channel", KeyboardRouteBuilder::channel);
@@ -1210,9 +1437,12 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("channel", CcRouteBuilder::channel);
     engine.register_fn("curve", CcRouteBuilder::curve);
     engine.register_fn("to", CcRouteBuilder::to_voice);
+    engine.register_fn("to", CcRouteBuilder::to_voice_range);
     engine.register_fn("to_effect", CcRouteBuilder::to_effect);
+    engine.register_fn("to_effect", CcRouteBuilder::to_effect_range);
     engine.register_fn("to_group", CcRouteBuilder::to_group);
     engine.register_fn("to_global", CcRouteBuilder::to_global);
+    engine.register_fn("to_macro", CcRouteBuilder::to_macro);
 
     // PitchBendRouteBuilder methods
     engine.register_fn("channel", PitchBendRouteBuilder::channel);
@@ -16,25 +16,47 @@ pub mod global;
 pub mod voice;
 pub mod pattern;
 pub mod melody;
+pub mod chord;
+pub mod ctrl;
 pub mod sequence;
 pub mod group;
+pub mod namespace;
+pub mod graph;
+pub mod macros;
+pub mod automation;
 pub mod synthdef;
 pub mod sfz;
+pub mod vst;
 pub mod sample;
+pub mod looper;
+pub mod hook;
+pub mod return_channel;
+pub mod groove;
+pub mod clock_out;
+pub mod meter;
 pub mod audio_device;
 pub mod midi;
+pub mod plugin;
+pub mod sandbox;
+pub mod watchdog;
+pub mod incremental;
+pub mod checkpoint;
 
 // Re-export bar utilities for external use
-pub use bar_utils::{count_bars, normalize_bars, split_into_bars};
+pub use bar_utils::{auto_loop_length, count_bars, normalize_bars, split_into_bars};
 
 // Re-export MIDI callback functions for use by CLI
 pub use midi::{clear_callbacks, clear_midi_devices, execute_pending_callbacks, get_callback_fnptr};
 
 // Re-export sample types
-pub use sample::{SampleHandle, BpmAnalysis, detect_bpm, detect_bpm_from_file};
+pub use sample::{
+    SampleHandle, BpmAnalysis, WaveformPeaks, compute_peaks, compute_peaks_from_file, detect_bpm,
+    detect_bpm_from_file,
+};
 
 use crate::runtime::RuntimeHandle;
-use rhai::Engine;
+use crate::state::QuotaKind;
+use rhai::{Engine, EvalAltResult};
 use std::cell::RefCell;
 
 // Thread-local storage for the runtime handle.
@@ -67,6 +89,14 @@ pub fn require_handle() -> RuntimeHandle {
     get_handle().expect("VibeLang API not initialized. Call init_api() first.")
 }
 
+/// Fail with a script error if a new entity would exceed the session's quotas.
+pub(crate) fn check_quota(kind: QuotaKind, name: &str) -> Result<(), Box<EvalAltResult>> {
+    require_handle()
+        .state()
+        .check_quota(kind, name)
+        .map_err(|e| e.to_string().into())
+}
+
 /// Register all VibeLang API functions with a Rhai engine.
 ///
 /// This registers:
@@ -74,6 +104,7 @@ pub fn require_handle() -> RuntimeHandle {
 /// - Voice builder and methods
 /// - Pattern builder and methods
 /// - Melody builder and methods
+/// - Chords
 /// - Group management
 /// - SynthDef definition
 /// - Helper functions (db, note, bars)
@@ -90,29 +121,78 @@ pub fn register_api(engine: &mut Engine) {
     // Register melody API
     melody::register(engine);
 
+    // Register chord API (chords for melodies)
+    chord::register(engine);
+
+    // Register control pattern API
+    ctrl::register(engine);
+
     // Register sequence API
     sequence::register(engine);
 
     // Register group API
     group::register(engine);
 
+    // Register entity namespaces for template functions
+    namespace::register(engine);
+
+    // Register playback graph API
+    graph::register(engine);
+
+    // Register macro control API
+    macros::register(engine);
+
+    // Register automation recording API
+    automation::register(engine);
+
     // Register synthdef API
     synthdef::register(engine);
 
+    // Register the statement marker of incremental reloads
+    incremental::register(engine);
+
+    // Register checkpoint markers
+    checkpoint::register(engine);
+
     // Register helper functions
     helpers::register(engine);
 
     // Register SFZ API
     sfz::register(engine);
 
+    // Register VST plugin API
+    vst::register(engine);
+
     // Register sample API
     sample::register(engine);
 
+    // Register audio input looper API
+    looper::register(engine);
+
+    // Register timeline hook API (external commands and webhooks)
+    hook::register(engine);
+
+    // Register return channel API (external processing returns)
+    return_channel::register(engine);
+
+    // Register groove template API (per-voice timing feel)
+    groove::register(engine);
+
+    // Register modular clock output API
+    clock_out::register(engine);
+
+    // Register meter API (meter-driven trigger conditions)
+    meter::register(engine);
+
     // Register unified MIDI API (includes both input and output)
     midi::register(engine);
 
     // Register audio device API
     audio_device::register(engine);
+
+    // Register plugin API and the plugins' own APIs (last, so plugins can
+    // build on everything above)
+    plugin::register(engine);
 }
 
 /// Create a Rhai engine with all VibeLang API registered.
@@ -174,7 +254,36 @@ pub fn create_engine_with_paths(
         collection.push(resolver);
     }
 
-    engine.set_module_resolver(collection);
+    // Evaluate every imported module in its own entity namespace
+    engine.set_module_resolver(namespace::ImportNamespaces::new(collection));
 
     engine
 }
+
+/// Evaluate the `eval` snippets of launched live set scenes.
+///
+/// This should be called periodically by the main execution loop, like
+/// `execute_pending_callbacks`. Returns the number of snippets evaluated.
+pub fn execute_pending_cue_evals(engine: &Engine) -> usize {
+    let handle = match get_handle() {
+        Some(h) => h,
+        None => return 0,
+    };
+
+    let pending = handle.with_state_mut(|state| {
+        state
+            .live_set
+            .as_mut()
+            .map(|live| std::mem::take(&mut live.pending_evals))
+            .unwrap_or_default()
+    });
+
+    let mut executed = 0;
+    for code in pending {
+        match engine.eval::<rhai::Dynamic>(&code) {
+            Ok(_) => executed += 1,
+            Err(e) => log::warn!("Cue eval '{}' failed: {}", code, watchdog::explain(&e)),
+        }
+    }
+    executed
+}
@@ -0,0 +1,161 @@
+//! Entity namespaces for Rhai scripts.
+//!
+//! Voices, patterns, melodies, sequences and fades created inside a
+//! `namespace()` block get the namespace as a name prefix, so a template
+//! function can be stamped out several times without its entities colliding.
+//!
+//! ```rhai
+//! fn four_on_floor(voice, accent) {
+//!     pattern("kick").on(voice).step("x...x...x...x...").param("amp", accent)
+//! }
+//!
+//! let verse = namespace("verse1", || four_on_floor(kick, 0.8)); // "verse1.kick"
+//! let chorus = namespace("chorus", || four_on_floor(kick, 1.0)); // "chorus.kick"
+//! ```
+//!
+//! Names passed as strings (`.on("kick")`) are not rewritten; pass handles to
+//! refer to entities of the same namespace. Outside the block, e.g. in MIDI
+//! callbacks, use the full name or `namespaced()`.
+//!
+//! Imported `.vibe` modules get a namespace too, named after the file: a
+//! `voice("lead")` in `leads/lead.vibe` is "lead.lead". Entities a module
+//! means to share are created with `exported()`, and two modules exporting
+//! the same name fail the import instead of clobbering each other:
+//!
+//! ```rhai
+//! // leads/supersaw.vibe
+//! let lead = voice(exported("lead")).synth("supersaw"); // "lead"
+//! let layer = voice("layer").synth("saw_pad");          // "supersaw.layer"
+//! ```
+//!
+//! Synthdefs are not namespaced.
+
+use rhai::module_resolvers::ModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, GlobalRuntimeState, Module, NativeCallContext, Position, Scope, Shared};
+
+use super::context;
+
+/// Run a closure with entity names prefixed by `name`.
+///
+/// Namespaces nest ("song.verse1.kick"). Returns the closure's result.
+pub fn namespace(ctx: NativeCallContext, name: String, closure: FnPtr) -> Result<Dynamic, Box<EvalAltResult>> {
+    context::push_namespace(&name);
+    let result = closure.call_within_context::<Dynamic>(&ctx, ());
+    context::pop_namespace();
+    result
+}
+
+/// The full name of an entity in the current namespace.
+pub fn namespaced(name: String) -> String {
+    context::namespaced(&name)
+}
+
+/// Export a name from the current namespace or module, so the entity created
+/// with it keeps the plain name.
+pub fn exported(name: String) -> Result<String, Box<EvalAltResult>> {
+    context::export_name(&name)?;
+    Ok(name)
+}
+
+/// Module resolver that evaluates each imported module in its own namespace.
+pub struct ImportNamespaces<R: ModuleResolver> {
+    inner: R,
+}
+
+impl<R: ModuleResolver> ImportNamespaces<R> {
+    /// Wrap a resolver.
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    fn in_namespace(
+        &self,
+        path: &str,
+        resolve: impl FnOnce(&R) -> Result<Shared<Module>, Box<EvalAltResult>>,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        context::push_namespace(&context::import_namespace(path));
+        let result = resolve(&self.inner);
+        context::pop_namespace();
+        result
+    }
+}
+
+impl<R: ModuleResolver> ModuleResolver for ImportNamespaces<R> {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        source: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        self.in_namespace(path, |inner| inner.resolve(engine, source, path, pos))
+    }
+
+    fn resolve_raw(
+        &self,
+        engine: &Engine,
+        global: &mut GlobalRuntimeState,
+        scope: &mut Scope,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        self.in_namespace(path, |inner| inner.resolve_raw(engine, global, scope, path, pos))
+    }
+}
+
+/// Register the namespace API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.register_fn("namespace", namespace);
+    engine.register_fn("namespaced", namespaced);
+    engine.register_fn("exported", exported);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaces_nest_and_unwind() {
+        let mut engine = Engine::new();
+        register(&mut engine);
+
+        let names: rhai::Array = engine
+            .eval(
+                r#"
+                fn stamp() { namespaced("kick") }
+                let names = [stamp()];
+                names.push(namespace("verse1", || stamp()));
+                names.push(namespace("song", || namespace("chorus", || stamp())));
+                names.push(stamp());
+                names
+                "#,
+            )
+            .unwrap();
+        let names: Vec<String> = names.into_iter().map(|n| n.into_string().unwrap()).collect();
+        assert_eq!(names, vec!["kick", "verse1.kick", "song.chorus.kick", "kick"]);
+
+        // A failing block doesn't leave its namespace behind
+        assert!(engine.eval::<Dynamic>(r#"namespace("broken", || throw "oops")"#).is_err());
+        assert_eq!(context::namespaced("kick"), "kick");
+    }
+
+    #[test]
+    fn test_import_namespaces_and_exports() {
+        context::reset();
+        assert_eq!(context::import_namespace("stdlib/leads/lead.vibe"), "lead");
+        assert_eq!(context::import_namespace("stdlib/leads/lead.vibe"), "lead");
+        assert_eq!(context::import_namespace("pads/lead"), "pads.lead");
+
+        context::push_namespace("lead");
+        assert_eq!(context::export_name("bass"), Ok(()));
+        assert_eq!(context::namespaced("bass"), "bass");
+        assert_eq!(context::namespaced("layer"), "lead.layer");
+        context::pop_namespace();
+
+        context::push_namespace("pads.lead");
+        let err = context::export_name("bass").unwrap_err();
+        assert!(err.contains("exported by both 'lead' and 'pads.lead'"), "{}", err);
+        context::pop_namespace();
+        context::reset();
+    }
+}
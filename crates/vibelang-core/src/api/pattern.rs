@@ -1,16 +1,30 @@
 //! Pattern API for Rhai scripts.
 //!
-//! Patterns are rhythmic sequences that trigger voices.
+//! Patterns are rhythmic sequences that trigger voices, or external MIDI
+//! gear with `.midi(device, channel)`. A pattern can hold several takes with
+//! `.variations([...])`; each pass through the loop plays one of them (see
+//! [`crate::variations`]).
+//!
+//! `pattern.to_string()` writes a pattern in the shareable text format of
+//! [`crate::loop_text`] and `pattern_from_string(text)` reads it back.
 
+use crate::drumkit::DrumKit;
 use crate::events::{BeatEvent, Pattern as PatternData};
+use crate::groove::Humanize;
+use crate::loop_text::{LoopText, LoopTextKind};
+use crate::meter_condition::MeterCondition;
+use crate::scheduler::LoopKind;
 use crate::sequences::{ClipMode, ClipSource, SequenceClip, SequenceDefinition};
-use crate::state::{LoopStatus, StateMessage};
+use crate::state::{LoopStatus, PatternMidiTarget, QuotaKind, StateMessage};
+use crate::variations::{EventVariation, PatternVariations};
 use rhai::{CustomType, Dynamic, Engine, EvalAltResult, NativeCallContext, Position, TypeBuilder};
 use std::collections::HashMap;
 
-use super::bar_utils::{count_bars, split_into_bars};
+use super::bar_utils::{auto_loop_length, count_bars, split_into_bars};
 use super::context::{self, SourceLocation};
-use super::require_handle;
+use super::helpers::{beats_arg, current_time_signature};
+use super::midi::MidiDevice;
+use super::{check_quota, require_handle};
 
 /// A Pattern builder for creating rhythmic patterns.
 #[derive(Debug, Clone, CustomType)]
@@ -19,10 +33,20 @@ pub struct Pattern {
     pub name: String,
     /// Voice name to trigger.
     voice_name: Option<String>,
+    /// Pads of the voice, when it is a drum kit.
+    drum_kit: Option<DrumKit>,
     /// Step pattern string (e.g., "x..x..x.").
     steps: Option<String>,
-    /// Loop length in beats.
-    length: f64,
+    /// Takes played instead of `steps`, one per pass through the loop.
+    variations: Vec<String>,
+    /// Relative weight of each take.
+    weights: Vec<f64>,
+    /// Seed of the take picks.
+    seed: u64,
+    /// Take held since `lock_variation()`, if any.
+    locked_variation: Option<usize>,
+    /// Loop length in beats; inferred from the steps when `None`.
+    length: Option<f64>,
     /// Swing amount (0.0 to 1.0).
     swing: f64,
     /// Quantization in beats.
@@ -31,10 +55,30 @@ pub struct Pattern {
     group_path: String,
     /// Parameters to pass to voice.
     params: HashMap<String, f64>,
+    /// Meter conditions that must all hold for an event to fire.
+    conditions: Vec<MeterCondition>,
+    /// Random timing and velocity variation of the events.
+    humanize: Option<Humanize>,
+    /// MIDI output device ID and channel (0-15), when sequencing external gear.
+    midi_output: Option<(u32, u8)>,
+    /// MIDI note sent for every hit.
+    midi_note: u8,
+    /// MIDI note length in beats.
+    note_length: f64,
+    /// Launch grid in beats, overriding the global quantization.
+    launch_quantization: Option<f64>,
+    /// Whether launching takes over the phase of what plays the same voice.
+    legato: bool,
     /// Source location where this pattern was defined.
     source_location: SourceLocation,
 }
 
+/// Default MIDI note of a pattern (middle C).
+const DEFAULT_MIDI_NOTE: u8 = 60;
+
+/// Default MIDI note length in beats (a sixteenth note).
+const DEFAULT_NOTE_LENGTH: f64 = 0.25;
+
 impl Pattern {
     /// Create a new pattern with the given name and source location from NativeCallContext.
     pub fn new(ctx: NativeCallContext, name: String) -> Self {
@@ -47,12 +91,24 @@ impl Pattern {
         Self {
             name,
             voice_name: None,
+            drum_kit: None,
             steps: None,
-            length: 4.0,
+            variations: Vec::new(),
+            weights: Vec::new(),
+            seed: 0,
+            locked_variation: None,
+            length: None,
             swing: 0.0,
             quantize: 0.0,
             group_path: context::current_group_path(),
             params: HashMap::new(),
+            conditions: Vec::new(),
+            humanize: None,
+            midi_output: None,
+            midi_note: DEFAULT_MIDI_NOTE,
+            note_length: DEFAULT_NOTE_LENGTH,
+            launch_quantization: None,
+            legato: false,
             source_location,
         }
     }
@@ -61,13 +117,19 @@ impl Pattern {
 
     /// Set the voice to trigger (by name).
     pub fn on(mut self, voice_name: String) -> Self {
+        self.drum_kit = require_handle()
+            .with_state(|state| state.voices.get(&voice_name).and_then(|voice| voice.drum_kit.clone()));
         self.voice_name = Some(voice_name);
         self
     }
 
     /// Set the voice to trigger (by Voice object).
+    ///
+    /// On a drum kit voice, each pad's step character plays that pad
+    /// (uppercase accents it); `x`, `o` and digits play the first pad.
     pub fn on_voice(mut self, voice: super::voice::Voice) -> Self {
         self.voice_name = Some(voice.name.clone());
+        self.drum_kit = voice.drum_kit().cloned();
         self
     }
 
@@ -82,20 +144,106 @@ impl Pattern {
         self
     }
 
+    /// Set the takes of the pattern; each pass through the loop plays one.
+    ///
+    /// All takes loop over the length of the longest one.
+    ///
+    /// # Example
+    /// ```rhai
+    /// pattern("hats").on(hat).variations(["x.x.x.x.", "x.xxx.x.", "xxxxxxxx"]).weights([0.6, 0.3, 0.1])
+    /// ```
+    pub fn variations(mut self, takes: rhai::Array) -> Result<Self, Box<EvalAltResult>> {
+        self.variations = takes
+            .into_iter()
+            .map(|take| {
+                take.into_string()
+                    .map_err(|t| format!("variations: takes must be step strings, got {}", t).into())
+            })
+            .collect::<Result<_, Box<EvalAltResult>>>()?;
+        Ok(self)
+    }
+
+    /// Set the relative weight of each take (missing weights count as 1).
+    pub fn weights(mut self, weights: rhai::Array) -> Result<Self, Box<EvalAltResult>> {
+        self.weights = weights
+            .into_iter()
+            .map(|w| {
+                w.as_float()
+                    .or_else(|_| w.as_int().map(|i| i as f64))
+                    .map_err(|t| format!("weights: expected numbers, got {}", t).into())
+            })
+            .collect::<Result<_, Box<EvalAltResult>>>()?;
+        Ok(self)
+    }
+
+    /// Seed the take picks; another seed plays the takes in another order.
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.seed = seed as u64;
+        self
+    }
+
+    /// Hold take `index` (0-based) instead of picking one per pass.
+    ///
+    /// Takes effect right away, so it can be used during a performance.
+    pub fn lock_variation(mut self, index: i64) -> Self {
+        self.locked_variation = Some(index.max(0) as usize);
+        let _ = require_handle().send(StateMessage::LockPatternVariation {
+            name: self.name.clone(),
+            index: self.locked_variation,
+        });
+        self
+    }
+
+    /// Pick a take per pass again.
+    pub fn unlock_variation(mut self) -> Self {
+        self.locked_variation = None;
+        let _ = require_handle().send(StateMessage::LockPatternVariation {
+            name: self.name.clone(),
+            index: None,
+        });
+        self
+    }
+
     /// Generate a Euclidean rhythm.
     ///
     /// # Arguments
     /// * `hits` - Number of hits
     /// * `steps` - Total number of steps
-    pub fn euclid(mut self, hits: i64, total_steps: i64) -> Self {
-        let pattern = generate_euclidean(hits as usize, total_steps as usize);
-        self.steps = Some(pattern);
+    pub fn euclid(self, hits: i64, total_steps: i64) -> Self {
+        self.euclid_rotated(hits, total_steps, 0)
+    }
+
+    /// Generate a Euclidean rhythm rotated `rotation` steps to the left, so
+    /// `euclid(3, 8, 2)` starts two steps into `x..x..x.`.
+    pub fn euclid_rotated(mut self, hits: i64, total_steps: i64, rotation: i64) -> Self {
+        let mut pattern: Vec<char> =
+            generate_euclidean(hits.max(0) as usize, total_steps.max(0) as usize).chars().collect();
+        if !pattern.is_empty() {
+            let shift = rotation.rem_euclid(pattern.len() as i64) as usize;
+            pattern.rotate_left(shift);
+        }
+        self.steps = Some(pattern.into_iter().collect());
         self
     }
 
-    /// Set the loop length in beats.
-    pub fn len(mut self, beats: f64) -> Self {
-        self.length = beats;
+    /// Accent `count` of the pattern's hits, spread evenly across them the
+    /// way `euclid` spreads hits across steps.
+    pub fn accents(mut self, count: i64) -> Self {
+        if let Some(steps) = self.steps.take() {
+            self.steps = Some(accent_hits(&steps, count.max(0) as usize));
+        }
+        self
+    }
+
+    /// Set the loop length (e.g. `2.bars`).
+    pub fn len(mut self, length: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.length = Some(beats_arg("len", &length)?);
+        Ok(self)
+    }
+
+    /// Infer the loop length from the steps: the bars they span (the default).
+    pub fn auto_length(mut self) -> Self {
+        self.length = None;
         self
     }
 
@@ -105,18 +253,104 @@ impl Pattern {
         self
     }
 
-    /// Set the quantization.
-    pub fn quantize(mut self, beats: f64) -> Self {
-        self.quantize = beats;
+    /// Loosen the pattern up: each hit moves up to `timing_ms` early or late and
+    /// its amp up to `velocity_pct` percent up or down, drawn anew on every
+    /// pass through the loop. `humanize(0, 0)` plays it straight again.
+    ///
+    /// # Example
+    /// ```rhai
+    /// pattern("hats").on(hats).step("x.x.x.x.x.x.x.x.").humanize(6, 15)
+    /// ```
+    pub fn humanize(mut self, timing_ms: f64, velocity_pct: f64) -> Self {
+        let humanize = Humanize::new(timing_ms, velocity_pct);
+        self.humanize = (humanize != Humanize::default()).then_some(humanize);
         self
     }
 
+    /// Humanize with whole milliseconds and percent.
+    pub fn humanize_int(self, timing_ms: i64, velocity_pct: i64) -> Self {
+        self.humanize(timing_ms as f64, velocity_pct as f64)
+    }
+
+    /// Set the quantization.
+    pub fn quantize(mut self, grid: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.quantize = beats_arg("quantize", &grid)?;
+        Ok(self)
+    }
+
     /// Set a parameter.
     pub fn set_param(mut self, param: String, value: f64) -> Self {
         self.params.insert(param, value);
         self
     }
 
+    /// Only fire events while a meter condition holds (chained calls must all hold).
+    ///
+    /// # Example
+    /// ```rhai
+    /// pattern("fill").on("snare").only_when(meter("main/Bass") < 0.1)
+    /// ```
+    pub fn only_when(mut self, condition: MeterCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Launch on `grid` instead of the global quantization (0 = immediately).
+    pub fn launch_quantize(mut self, grid: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.launch_quantization = Some(beats_arg("launch_quantize", &grid)?.max(0.0));
+        Ok(self)
+    }
+
+    /// Launch in legato: take over from whatever plays the same voice at its phase.
+    pub fn legato(mut self, legato: bool) -> Self {
+        self.legato = legato;
+        self
+    }
+
+    /// Sequence external MIDI gear instead of a voice.
+    ///
+    /// Every hit sends a note-on (velocity from the step) and, after the
+    /// note length, a note-off, with the same timing as synth events.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let tr8s = midi_open("TR-8S", "output");
+    /// pattern("hw_kick").midi(tr8s, 10).note(36).step("x...x...x...x...").start();
+    /// ```
+    pub fn midi(mut self, device: MidiDevice, channel: i64) -> Result<Self, Box<EvalAltResult>> {
+        let device_id = device.output_device_id.ok_or_else(|| {
+            Box::new(EvalAltResult::from(
+                "MIDI device was not opened for output. Use midi_open(\"name\", \"output\") or midi_open(\"name\", \"both\")"
+            ))
+        })?;
+        // Convert 1-16 to 0-15 internally
+        self.midi_output = Some((device_id, (channel.clamp(1, 16) - 1) as u8));
+        Ok(self)
+    }
+
+    /// Sequence external MIDI gear by device name (opened for output if needed).
+    ///
+    /// # Example
+    /// ```rhai
+    /// pattern("hw_drums").midi("TR-8S", 10).step("x.x.x.x.")
+    /// ```
+    pub fn midi_by_name(self, device: String, channel: i64) -> Result<Self, Box<EvalAltResult>> {
+        let device = super::midi::midi_open_by_name(&device)?;
+        self.midi(device, channel)
+    }
+
+    /// Set the MIDI note sent for every hit (0-127, with `.midi(...)`).
+    pub fn note(mut self, note: i64) -> Self {
+        self.midi_note = note.clamp(0, 127) as u8;
+        self
+    }
+
+    /// Set the MIDI note length (with `.midi(...)`).
+    pub fn note_length(mut self, length: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.note_length = beats_arg("note_length", &length)?.max(0.0);
+        Ok(self)
+    }
+
     /// Create a lane for multi-parameter patterns.
     pub fn lane(self, _param: String) -> PatternLaneBuilder {
         PatternLaneBuilder {
@@ -125,25 +359,64 @@ impl Pattern {
         }
     }
 
+    /// The pattern in the shareable text format (voice, steps, takes and params).
+    pub fn to_text(&self) -> LoopText {
+        let mut text = LoopText::new(LoopTextKind::Pattern, &self.name);
+        text.voice = self.voice_name.clone();
+        text.lanes = if self.variations.is_empty() {
+            self.steps.iter().cloned().collect()
+        } else {
+            self.variations.clone()
+        };
+        if self.variations.len() > 1 {
+            text.weights = self.weights.clone();
+            text.seed = self.seed;
+            text.locked = self.locked_variation;
+        }
+        text.length = self.length;
+        text.swing = self.swing;
+        text.params = self.params.iter().map(|(param, value)| (param.clone(), *value)).collect();
+        text
+    }
+
     // === Actions ===
 
+    /// Beats the steps span: those of the longest take, or of the steps.
+    fn content_length(&self, beats_per_bar: f64) -> f64 {
+        let takes = if self.variations.is_empty() {
+            self.steps.as_slice()
+        } else {
+            self.variations.as_slice()
+        };
+        takes
+            .iter()
+            .map(|take| count_bars(take) as f64 * beats_per_bar)
+            .fold(0.0, f64::max)
+    }
+
+    /// Loop length in beats: the explicit length, or the steps rounded up to whole bars.
+    fn loop_length(&self, beats_per_bar: f64) -> f64 {
+        self.length
+            .unwrap_or_else(|| auto_loop_length(self.content_length(beats_per_bar), beats_per_bar))
+    }
+
     /// Register and apply the pattern (chainable).
     pub fn apply(self) -> Self {
         let handle = require_handle();
 
-        // Calculate loop length from pattern if available, otherwise use explicit length
-        let loop_length = if let Some(ref steps) = self.steps {
-            calculate_loop_length_from_pattern(steps)
-        } else {
-            self.length
-        };
+        let beats_per_bar = current_time_signature().beats_per_bar();
+        let loop_length = self.loop_length(beats_per_bar);
+        let content = self.content_length(beats_per_bar);
+        if content > loop_length + 1e-9 {
+            log::warn!(
+                "pattern '{}': steps span {} beats but the loop is {} beats; later steps never play",
+                self.name, content, loop_length
+            );
+        }
 
-        // Parse steps into events
-        let events = if let Some(ref steps) = self.steps {
-            parse_pattern_steps(steps, loop_length, self.swing)
-        } else {
-            Vec::new()
-        };
+        let events = pattern_events(self.steps.as_deref(), &self.variations, beats_per_bar, self.swing, self.drum_kit.as_ref());
+        let step_pattern = self.variations.first().cloned().or_else(|| self.steps.clone());
+        let text = step_pattern.is_some().then(|| self.to_text());
 
         let loop_pattern = PatternData {
             name: self.name.clone(),
@@ -158,8 +431,41 @@ impl Pattern {
             voice_name: self.voice_name.clone(),
             pattern: loop_pattern,
             source_location: self.source_location.clone(),
-            step_pattern: self.steps.clone(),
+            step_pattern,
+            text,
         });
+        let _ = handle.send(StateMessage::SetLoopConditions {
+            name: self.name.clone(),
+            kind: LoopKind::Pattern,
+            conditions: self.conditions.clone(),
+        });
+        let _ = handle.send(StateMessage::SetLoopHumanize {
+            name: self.name.clone(),
+            kind: LoopKind::Pattern,
+            humanize: self.humanize,
+        });
+        let _ = handle.send(StateMessage::SetPatternMidiTarget {
+            name: self.name.clone(),
+            target: self.midi_output.map(|(device_id, channel)| PatternMidiTarget {
+                device_id,
+                channel,
+                note: self.midi_note,
+                note_length: self.note_length,
+            }),
+        });
+        let _ = handle.send(StateMessage::SetPatternVariations {
+            name: self.name.clone(),
+            variations: (!self.variations.is_empty()).then(|| PatternVariations {
+                seed: self.seed,
+                ..PatternVariations::new(self.variations.len()).with_weights(&self.weights)
+            }),
+        });
+        if self.locked_variation.is_some() {
+            let _ = handle.send(StateMessage::LockPatternVariation {
+                name: self.name.clone(),
+                index: self.locked_variation,
+            });
+        }
 
         self
     }
@@ -172,23 +478,20 @@ impl Pattern {
         let applied = self.apply();
         let handle = require_handle();
 
-        // Calculate loop length from pattern if available, otherwise use explicit length
-        let loop_length = if let Some(ref steps) = applied.steps {
-            calculate_loop_length_from_pattern(steps)
-        } else {
-            applied.length
-        };
+        let loop_length = applied.loop_length(current_time_signature().beats_per_bar());
 
         // Create an implicit sequence for this pattern
         let seq_name = format!("_seq_{}", applied.name);
-        let seq_def = SequenceDefinition::new(seq_name.clone())
+        let mut seq_def = SequenceDefinition::new(seq_name.clone())
             .with_loop_beats(loop_length)
             .with_clip(SequenceClip::new(
                 0.0,
                 loop_length,
                 ClipSource::Pattern(applied.name.clone()),
                 ClipMode::Loop,
-            ));
+            ))
+            .with_legato(applied.legato);
+        seq_def.launch_quantization = applied.launch_quantization;
 
         // Register and start the sequence
         let _ = handle.send(StateMessage::CreateSequence { sequence: seq_def });
@@ -247,19 +550,75 @@ impl PatternLaneBuilder {
 }
 
 /// Create a new pattern builder with source location tracking.
-pub fn pattern(ctx: NativeCallContext, name: String) -> Pattern {
-    Pattern::new(ctx, name)
+pub fn pattern(ctx: NativeCallContext, name: String) -> Result<Pattern, Box<EvalAltResult>> {
+    let name = context::namespaced(&name);
+    check_quota(QuotaKind::Patterns, &name)?;
+    Ok(Pattern::new(ctx, name))
+}
+
+/// Read a pattern from the shareable text format (see [`crate::loop_text`]).
+///
+/// # Example
+/// ```rhai
+/// pattern_from_string("pattern hats\non hat\nsteps x.x.x.x.").start();
+/// ```
+pub fn pattern_from_string(ctx: NativeCallContext, text: String) -> Result<Pattern, Box<EvalAltResult>> {
+    let text = LoopText::parse(&text).map_err(|e| format!("pattern_from_string: {:#}", e))?;
+    if text.kind != LoopTextKind::Pattern {
+        return Err(format!("pattern_from_string: '{}' is a {}, not a pattern", text.name, text.kind.as_str()).into());
+    }
+
+    let mut pattern = pattern(ctx, text.name)?;
+    if let Some(voice) = text.voice {
+        pattern = pattern.on(voice);
+    }
+    match text.lanes.len() {
+        0 => {}
+        1 => pattern.steps = text.lanes.into_iter().next(),
+        _ => {
+            pattern.variations = text.lanes;
+            pattern.weights = text.weights;
+            pattern.seed = text.seed;
+            pattern.locked_variation = text.locked;
+        }
+    }
+    pattern.length = text.length;
+    pattern.swing = text.swing.clamp(0.0, 1.0);
+    pattern.params = text.params.into_iter().collect();
+    Ok(pattern)
+}
+
+/// Events of a pattern: those of its takes when it has some, each tagged
+/// with its take, otherwise those of its steps.
+pub fn pattern_events(
+    steps: Option<&str>,
+    takes: &[String],
+    beats_per_bar: f64,
+    swing: f64,
+    kit: Option<&DrumKit>,
+) -> Vec<BeatEvent> {
+    if takes.is_empty() {
+        return steps.map_or_else(Vec::new, |steps| parse_pattern_steps(steps, beats_per_bar, swing, kit));
+    }
+    let mut events = Vec::new();
+    for (index, take) in takes.iter().enumerate() {
+        events.extend(parse_pattern_steps(take, beats_per_bar, swing, kit).into_iter().map(|mut ev| {
+            ev.variation = Some(EventVariation { index, offset: ev.beat });
+            ev
+        }));
+    }
+    events
 }
 
 /// Parse a step pattern string into beat events.
-/// Uses bar-aware parsing: each bar separated by `|` is 4 beats.
+/// Uses bar-aware parsing: each bar separated by `|` is `beats_per_bar` beats.
 /// Supports leading/trailing pipes and consecutive pipes via split_into_bars.
-fn parse_pattern_steps(steps: &str, _length: f64, swing: f64) -> Vec<BeatEvent> {
+/// With a drum kit, hits carry the note of the pad they play.
+fn parse_pattern_steps(steps: &str, beats_per_bar: f64, swing: f64, kit: Option<&DrumKit>) -> Vec<BeatEvent> {
     let mut events = Vec::new();
 
     // Use unified bar splitting (handles leading/trailing/consecutive pipes)
     let bars = split_into_bars(steps);
-    let beats_per_bar = 4.0; // Standard 4/4 time
 
     let mut current_beat = 0.0;
     let mut step_index = 0;
@@ -285,8 +644,12 @@ fn parse_pattern_steps(steps: &str, _length: f64, swing: f64) -> Vec<BeatEvent>
                 beat
             };
 
+            // A pad's step character plays it, accented in uppercase
+            let pad = kit.and_then(|kit| kit.pad_for_step(*ch));
+
             // Parse velocity from token character
             let velocity = match ch {
+                _ if pad.is_some() => Some(if ch.is_uppercase() { 1.2 } else { 1.0 }),
                 'x' => Some(1.0),
                 'X' | 'o' | 'O' => Some(1.2),
                 '1'..='9' => {
@@ -299,7 +662,11 @@ fn parse_pattern_steps(steps: &str, _length: f64, swing: f64) -> Vec<BeatEvent>
 
             if let Some(vel) = velocity {
                 let mut event = BeatEvent::new(swung_beat, "trigger");
+                event.step = Some(step_index);
                 event.controls.push(("amp".to_string(), vel as f32));
+                if let Some(pad) = pad.or_else(|| kit.and_then(|kit| kit.pads.first())) {
+                    event.controls.push(("freq".to_string(), crate::pitch::note_to_freq(pad.note as f64) as f32));
+                }
                 events.push(event);
             }
 
@@ -318,43 +685,56 @@ fn tokenize_bar_chars(bar: &str) -> Vec<char> {
     bar.chars().filter(|c| !c.is_whitespace()).collect()
 }
 
-/// Calculate loop length from pattern: number of bars × 4 beats
-/// Uses count_bars to handle leading/trailing/consecutive pipes.
-fn calculate_loop_length_from_pattern(pattern: &str) -> f64 {
-    let num_bars = count_bars(pattern);
-    let beats_per_bar = 4.0;
-    // Ensure at least 1 bar for empty patterns
-    let effective_bars = if num_bars == 0 { 1 } else { num_bars };
-    effective_bars as f64 * beats_per_bar
-}
-
 /// Generate a Euclidean rhythm pattern.
 fn generate_euclidean(hits: usize, steps: usize) -> String {
-    if steps == 0 {
-        return String::new();
-    }
-    if hits >= steps {
-        return "x".repeat(steps);
-    }
+    bjorklund(hits, steps)
+        .into_iter()
+        .map(|hit| if hit { 'x' } else { '.' })
+        .collect()
+}
+
+/// Spread `hits` onsets as evenly as possible over `steps` slots with
+/// Bjorklund's algorithm, starting on a hit.
+fn bjorklund(hits: usize, steps: usize) -> Vec<bool> {
+    let hits = hits.min(steps);
     if hits == 0 {
-        return ".".repeat(steps);
+        return vec![false; steps];
     }
 
-    // Bresenham-style Euclidean algorithm
-    let mut pattern = vec![false; steps];
-    let mut bucket = 0;
-
-    for slot in pattern.iter_mut() {
-        bucket += hits;
-        if bucket >= steps {
-            bucket -= steps;
-            *slot = true;
+    // Pair off the leading groups with the remainder until at most one
+    // remainder group is left
+    let mut heads: Vec<Vec<bool>> = vec![vec![true]; hits];
+    let mut rest: Vec<Vec<bool>> = vec![vec![false]; steps - hits];
+    while rest.len() > 1 {
+        let paired = heads.len().min(rest.len());
+        let remainder = if heads.len() > paired {
+            heads.split_off(paired)
+        } else {
+            rest.split_off(paired)
+        };
+        for (head, tail) in heads.iter_mut().zip(rest) {
+            head.extend(tail);
         }
+        rest = remainder;
     }
 
-    pattern
-        .into_iter()
-        .map(|hit| if hit { 'x' } else { '.' })
+    heads.into_iter().chain(rest).flatten().collect()
+}
+
+/// Uppercase `count` of the hits in a step string, spread evenly across
+/// them. Rests, bar lines and velocity digits are left alone.
+fn accent_hits(steps: &str, count: usize) -> String {
+    let hits = steps.chars().filter(|ch| ch.is_alphabetic()).count();
+    let mut accents = bjorklund(count, hits).into_iter();
+    steps
+        .chars()
+        .map(|ch| {
+            if ch.is_alphabetic() && accents.next().unwrap_or(false) {
+                ch.to_ascii_uppercase()
+            } else {
+                ch
+            }
+        })
         .collect()
 }
 
@@ -366,17 +746,37 @@ pub fn register(engine: &mut Engine) {
 
     // Constructor
     engine.register_fn("pattern", pattern);
+    engine.register_fn("pattern_from_string", pattern_from_string);
 
     // Builder methods
     engine.register_fn("on", Pattern::on);
     engine.register_fn("on", Pattern::on_voice);
     engine.register_fn("step", Pattern::step);
     engine.register_fn("euclid", Pattern::euclid);
+    engine.register_fn("euclid", Pattern::euclid_rotated);
+    engine.register_fn("accents", Pattern::accents);
     engine.register_fn("len", Pattern::len);
+    engine.register_fn("auto_length", Pattern::auto_length);
     engine.register_fn("swing", Pattern::swing);
+    engine.register_fn("humanize", Pattern::humanize);
+    engine.register_fn("humanize", Pattern::humanize_int);
     engine.register_fn("quantize", Pattern::quantize);
     engine.register_fn("set_param", Pattern::set_param);
+    engine.register_fn("only_when", Pattern::only_when);
+    engine.register_fn("launch_quantize", Pattern::launch_quantize);
+    engine.register_fn("legato", Pattern::legato);
+    engine.register_fn("legato", |x: Pattern| x.legato(true));
     engine.register_fn("lane", Pattern::lane);
+    engine.register_fn("midi", Pattern::midi);
+    engine.register_fn("midi", Pattern::midi_by_name);
+    engine.register_fn("note", Pattern::note);
+    engine.register_fn("note_length", Pattern::note_length);
+    engine.register_fn("variations", Pattern::variations);
+    engine.register_fn("weights", Pattern::weights);
+    engine.register_fn("seed", Pattern::seed);
+    engine.register_fn("lock_variation", Pattern::lock_variation);
+    engine.register_fn("unlock_variation", Pattern::unlock_variation);
+    engine.register_fn("to_string", |p: &mut Pattern| p.to_text().to_string());
 
     // Actions
     engine.register_fn("apply", Pattern::apply);
@@ -397,14 +797,148 @@ mod tests {
 
     #[test]
     fn test_parse_pattern_steps() {
-        let events = parse_pattern_steps("x.x.", 4.0, 0.0);
+        let events = parse_pattern_steps("x.x.", 4.0, 0.0, None);
         assert_eq!(events.len(), 2);
     }
 
+    #[test]
+    fn test_drum_kit_steps_play_their_pads() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                let kit = drumkit("drums").pad("kick", "kick.wav").pad("snare", "snare.wav", #{ note: 40, step: "n" });
+                pattern("beat").on(kit).step("k.nK").start();
+                "#,
+            )
+            .unwrap();
+        sim.start();
+        sim.advance(3.5);
+
+        let hits: Vec<(u8, f32)> = sim
+            .events()
+            .iter()
+            .map(|e| {
+                let control = |name: &str| e.event.controls.iter().find(|(k, _)| k == name).map(|(_, v)| *v).unwrap();
+                (crate::pitch::freq_to_midi_note(control("freq") as f64), control("amp"))
+            })
+            .collect();
+        assert_eq!(hits, vec![(36, 1.0), (40, 1.0), (36, 1.2)]);
+        sim.handle().with_state(|state| {
+            let kit = state.voices["drums"].drum_kit.as_ref().unwrap();
+            assert_eq!(kit.pads.iter().map(|pad| pad.sample_id.as_str()).collect::<Vec<_>>(), vec!["drums.kick", "drums.snare"]);
+        });
+    }
+
+    #[test]
+    fn test_pattern_text_round_trips_through_scripts() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        let text: String = engine
+            .eval(
+                r#"
+                let hat = voice("hat").synth("hihat");
+                let hats = pattern("hats").on(hat).variations(["x.x.", "xxxx"]).weights([3, 1]).swing(0.2);
+                hats.set_param("amp", 0.5).lock_variation(1).apply().to_string()
+                "#,
+            )
+            .unwrap();
+        assert_eq!(text, "pattern hats\non hat\nsteps x.x.\nsteps xxxx\nweights 3 1\nlock 1\nswing 0.2\nparam amp 0.5\n");
+
+        let copy = text.replace("pattern hats", "pattern copy");
+        engine.run(&format!("pattern_from_string({:?}).apply();", copy)).unwrap();
+        sim.advance(0.0);
+        sim.handle().with_state(|state| {
+            let (hats, copy) = (&state.patterns["hats"], &state.patterns["copy"]);
+            assert_eq!(copy.text.as_ref().unwrap().to_string(), text.replace("pattern hats", "pattern copy"));
+            assert_eq!(copy.locked_variation, Some(1));
+            assert_eq!(copy.variations, hats.variations);
+            let beats = |p: &crate::state::PatternState| p.loop_pattern.as_ref().unwrap().events.iter().map(|e| e.beat).collect::<Vec<_>>();
+            assert_eq!(beats(copy), beats(hats));
+        });
+        assert!(engine.run(r#"pattern_from_string("melody lead\nnotes C4");"#).is_err());
+    }
+
     #[test]
     fn test_generate_euclidean() {
         assert_eq!(generate_euclidean(3, 8), "x..x..x.");
         assert_eq!(generate_euclidean(4, 8), "x.x.x.x.");
         assert_eq!(generate_euclidean(5, 8), "x.xx.xx.");
+        assert_eq!(generate_euclidean(0, 4), "....");
+        assert_eq!(generate_euclidean(6, 4), "xxxx");
+        assert_eq!(accent_hits("x.xx.xx.", 2), "X.xX.xx.");
+
+        let sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        let steps = |script: &str| engine.eval::<Pattern>(script).unwrap().steps.unwrap();
+        assert_eq!(steps(r#"pattern("tresillo").euclid(3, 8, 2)"#), ".x..x.x.");
+        assert_eq!(steps(r#"pattern("tresillo").euclid(3, 8, -1)"#), ".x..x..x");
+        assert_eq!(steps(r#"pattern("cinquillo").euclid(5, 8).accents(2)"#), "X.xX.xx.");
+    }
+
+    #[test]
+    fn test_pattern_variations_pick_one_take_per_pass() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                let hat = voice("hat").synth("hihat");
+                pattern("hats").on(hat).variations(["x...", "xx..", "xxxx"]).weights([0.6, 0.3, 0.1]).start();
+                "#,
+            )
+            .unwrap();
+        sim.start();
+        sim.advance(64.0);
+
+        // Every pass plays exactly one whole take (1, 2 or 4 hits per bar)
+        let mut hits = [0usize; 16];
+        for event in sim.events().iter().filter(|e| e.beat < 64.0) {
+            assert_eq!(event.event.pattern_name.as_deref(), Some("hats"));
+            hits[(event.beat / 4.0) as usize] += 1;
+        }
+        assert!(hits.iter().all(|h| [1, 2, 4].contains(h)), "{:?}", hits);
+        assert!(hits.iter().filter(|h| **h == 1).count() > hits.iter().filter(|h| **h == 4).count());
+
+        // A locked take plays on every pass (beat 64 fired before the lock)
+        engine.run(r#"pattern("hats").lock_variation(2);"#).unwrap();
+        sim.take_events();
+        sim.advance(16.0);
+        let beats: Vec<f64> = sim.events().iter().map(|e| e.beat).filter(|b| *b < 80.0).collect();
+        assert_eq!(beats, (65..80).map(|b| b as f64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_loop_length_inferred_from_content() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        engine
+            .run(
+                r#"
+                let kick = voice("kick").synth("kick");
+                pattern("fill").on(kick).step("x... | x.x. | xxxx").apply();
+                pattern("short").on(kick).step("x... | x...").len(4.0).apply();
+                pattern("auto").on(kick).len(16.0).step("x...").auto_length().apply();
+                melody("lead").on(kick).notes("C4 - - - | E4").apply();
+                melody("pinned").on(kick).len(2.bars).notes("C4 - - -").apply();
+                set_time_signature(3, 4);
+                pattern("waltz").on(kick).step("x.. | x..").apply();
+                "#,
+            )
+            .unwrap();
+        sim.advance(0.0);
+
+        let lengths = sim.handle().with_state(|state| {
+            let pattern = |name: &str| state.patterns[name].loop_pattern.as_ref().unwrap().loop_length_beats;
+            let melody = |name: &str| state.melodies[name].loop_pattern.as_ref().unwrap().loop_length_beats;
+            [pattern("fill"), pattern("short"), pattern("auto"), melody("lead"), melody("pinned"), pattern("waltz")]
+        });
+        assert_eq!(lengths, [12.0, 4.0, 4.0, 8.0, 8.0, 6.0]);
     }
 }
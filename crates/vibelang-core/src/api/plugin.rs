@@ -0,0 +1,46 @@
+//! Plugin API for Rhai scripts.
+//!
+//! Plugins (see [`crate::plugin`]) usually register functions of their own;
+//! these reach any plugin by name.
+//!
+//! ```rhai
+//! if "dmx" in plugins() {
+//!     plugin_send("dmx", "scene", #{ id: 3, fade: 0.5 });
+//! }
+//! ```
+
+use crate::state::StateMessage;
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+
+use super::require_handle;
+
+/// Send `payload` to the plugin named `plugin` through the runtime.
+///
+/// Also meant for plugins' own Rhai functions, so their messages reach the
+/// runtime in order with the script's other messages.
+pub fn plugin_send(plugin: &str, name: &str, payload: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    if !crate::plugin::is_registered(plugin) {
+        return Err(format!("plugin_send: no plugin named '{}'", plugin).into());
+    }
+    require_handle()
+        .send(StateMessage::Plugin {
+            plugin: plugin.to_string(),
+            name: name.to_string(),
+            payload,
+        })
+        .map_err(|e| e.to_string().into())
+}
+
+/// Names of the registered plugins.
+pub fn plugins() -> Array {
+    crate::plugin::plugin_names().into_iter().map(Dynamic::from).collect()
+}
+
+/// Register the plugin API, and the registered plugins' own APIs, with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.register_fn("plugin_send", plugin_send);
+    engine.register_fn("plugin_send", |plugin: &str, name: &str| plugin_send(plugin, name, Dynamic::UNIT));
+    engine.register_fn("plugins", plugins);
+
+    crate::plugin::register_apis(engine);
+}
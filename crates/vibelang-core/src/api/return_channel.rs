@@ -0,0 +1,144 @@
+//! Return channel API for Rhai scripts.
+//!
+//! A return channel plays hardware inputs into a group, so audio sent out
+//! with `voice.output()` comes back from external gear and is mixed,
+//! metered and faded like the group's voices (see [`crate::return_channel`]).
+//!
+//! ```rhai
+//! let fx = define_group("outboard", || {});
+//! let vocal = voice("vocal").synth("lead").output(4);
+//! return_channel("outboard_reverb").input(3).stereo().into(fx).gain(db(-6));
+//! ```
+
+use crate::state::StateMessage;
+use rhai::{CustomType, Engine, TypeBuilder};
+
+use super::group::GroupHandle;
+use super::helpers::Decibels;
+use super::{context, get_handle, require_handle};
+
+/// A return channel builder.
+///
+/// Every builder call updates the running return channel, and
+/// `return_channel(name)` starts from its current settings.
+#[derive(Debug, Clone, CustomType)]
+pub struct ReturnChannel {
+    /// Return channel name.
+    pub name: String,
+    /// Group the returned audio plays into.
+    group_path: String,
+    /// First hardware input channel (1-based).
+    input: u32,
+    /// Number of input channels (1 or 2).
+    channels: u32,
+    /// Gain (linear).
+    gain: f64,
+}
+
+impl ReturnChannel {
+    /// Create a return channel builder, starting from the existing channel's settings.
+    pub fn new(name: String) -> Self {
+        let existing = get_handle().and_then(|h| {
+            h.with_state(|state| {
+                state
+                    .return_channels
+                    .get(&name)
+                    .map(|c| (c.group_path.clone(), c.input, c.channels, c.gain as f64))
+            })
+        });
+        let (group_path, input, channels, gain) =
+            existing.unwrap_or_else(|| (context::current_group_path(), 1, 1, 1.0));
+        Self {
+            name,
+            group_path,
+            input,
+            channels,
+            gain,
+        }
+    }
+
+    // === Builder methods ===
+
+    /// Set the first hardware input channel (1 = first input).
+    pub fn input(mut self, channel: i64) -> Self {
+        self.input = channel.max(1) as u32;
+        self.sync_state();
+        self
+    }
+
+    /// Set the number of input channels (1 = mono, 2 = stereo pair).
+    pub fn channels(mut self, channels: i64) -> Self {
+        self.channels = channels.clamp(1, 2) as u32;
+        self.sync_state();
+        self
+    }
+
+    /// Return a stereo pair starting at the input channel.
+    pub fn stereo(self) -> Self {
+        self.channels(2)
+    }
+
+    /// Play the returned audio into a group.
+    pub fn into_group(mut self, group: GroupHandle) -> Self {
+        self.group_path = group.path().to_string();
+        self.sync_state();
+        self
+    }
+
+    /// Play the returned audio into a group given by name or path.
+    pub fn into_group_named(mut self, group: String) -> Self {
+        self.group_path = if group.starts_with("main/") || group == "main" {
+            group
+        } else {
+            format!("{}/{}", context::current_group_path(), group)
+        };
+        self.sync_state();
+        self
+    }
+
+    /// Set the gain (linear).
+    pub fn gain(mut self, gain: f64) -> Self {
+        self.gain = gain;
+        self.sync_state();
+        self
+    }
+
+    /// Set the gain in decibels.
+    pub fn gain_db(self, level: Decibels) -> Self {
+        self.gain(level.amp())
+    }
+
+    fn sync_state(&self) {
+        let _ = require_handle().send(StateMessage::UpsertReturnChannel {
+            name: self.name.clone(),
+            group_path: self.group_path.clone(),
+            input: self.input,
+            channels: self.channels,
+            gain: self.gain as f32,
+        });
+    }
+}
+
+/// Create or look up a return channel.
+pub fn return_channel(name: String) -> ReturnChannel {
+    let channel = ReturnChannel::new(name);
+    channel.sync_state();
+    channel
+}
+
+/// Register the return channel API with the Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<ReturnChannel>();
+
+    engine.register_fn("return_channel", return_channel);
+
+    // Builder methods
+    engine.register_fn("input", ReturnChannel::input);
+    engine.register_fn("channels", ReturnChannel::channels);
+    engine.register_fn("stereo", ReturnChannel::stereo);
+    engine.register_fn("into", ReturnChannel::into_group);
+    engine.register_fn("into", ReturnChannel::into_group_named);
+    engine.register_fn("gain", ReturnChannel::gain);
+    engine.register_fn("gain", ReturnChannel::gain_db);
+    engine.register_get("name", |c: &mut ReturnChannel| c.name.clone());
+}
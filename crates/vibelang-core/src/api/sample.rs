@@ -1,14 +1,16 @@
 //! Sample API for VibeLang.
 //!
-//! Provides sample loading, slicing, playback configuration, and BPM detection.
+//! Provides sample loading, slicing, playback configuration, BPM detection and
+//! key detection.
 //! Supports time-stretching and pitch-shifting via the Warp1 UGen.
 
-use crate::state::StateMessage;
-use rhai::Engine;
+use crate::musical_key::{estimate_key, MusicalKey};
+use crate::state::{QuotaKind, StateMessage};
+use rhai::{Engine, EvalAltResult};
 use std::path::Path;
 
 use super::context;
-use super::require_handle;
+use super::{check_quota, require_handle};
 
 // =============================================================================
 // BPM Detection (requires aubio)
@@ -100,11 +102,8 @@ pub fn detect_bpm(samples: &[f32], sample_rate: u32) -> BpmAnalysis {
 
 /// Detect BPM from a WAV file.
 pub fn detect_bpm_from_file(path: &Path) -> BpmAnalysis {
-    use std::fs::File;
-    use std::io::BufReader;
-
-    let Ok(file) = File::open(path) else {
-        log::warn!("[BPM] Failed to open file: {:?}", path);
+    let Some((raw_samples, channels, sample_rate)) = read_wav_interleaved(path) else {
+        log::warn!("[BPM] Failed to read WAV file: {:?}", path);
         return BpmAnalysis {
             bpm: 0.0,
             confidence: 0.0,
@@ -112,42 +111,173 @@ pub fn detect_bpm_from_file(path: &Path) -> BpmAnalysis {
         };
     };
 
-    let reader = BufReader::new(file);
-    let Ok(wav_reader) = hound::WavReader::new(reader) else {
-        log::warn!("[BPM] Failed to parse WAV file: {:?}", path);
-        return BpmAnalysis {
-            bpm: 0.0,
-            confidence: 0.0,
-            beat_count: 0,
+    detect_bpm(&mix_to_mono(&raw_samples, channels), sample_rate)
+}
+
+// =============================================================================
+// Key Detection (requires aubio)
+// =============================================================================
+
+/// Result of key analysis.
+#[derive(Clone, Debug)]
+pub struct KeyAnalysis {
+    /// Estimated key.
+    pub key: MusicalKey,
+    /// Correlation with the key profile, -1.0 to 1.0.
+    pub confidence: f64,
+    /// Fraction of analysed frames with a clear pitch.
+    pub pitched_ratio: f64,
+}
+
+/// Detect the approximate key of audio samples.
+///
+/// Uses aubio's YIN-FFT pitch tracker to build a pitch-class histogram of the
+/// confidently pitched frames, then matches it against key profiles. Returns
+/// `None` for unpitched material such as drums.
+pub fn detect_key(samples: &[f32], sample_rate: u32) -> Option<KeyAnalysis> {
+    use aubio_rs::{Pitch, PitchMode, PitchUnit};
+
+    const BUF_SIZE: usize = 4096;
+    const HOP_SIZE: usize = 1024;
+    /// Pitch confidence a frame needs to count.
+    const MIN_CONFIDENCE: f32 = 0.8;
+    /// Fraction of pitched frames below which a sample counts as unpitched.
+    const MIN_PITCHED_RATIO: f64 = 0.2;
+    /// Only the start of long samples is analysed.
+    const MAX_SECONDS: usize = 60;
+
+    let samples = &samples[..samples.len().min(MAX_SECONDS * sample_rate as usize)];
+    if samples.len() < BUF_SIZE {
+        return None;
+    }
+
+    let Ok(pitch) = Pitch::new(PitchMode::Yinfft, BUF_SIZE, HOP_SIZE, sample_rate) else {
+        log::warn!("[KEY] Failed to create pitch detector");
+        return None;
+    };
+    let mut pitch = pitch.with_unit(PitchUnit::Midi);
+
+    let mut chroma = [0.0f32; 12];
+    let mut frames = 0usize;
+    let mut pitched = 0usize;
+    for chunk in samples.chunks_exact(HOP_SIZE) {
+        let Ok(note) = pitch.do_result(chunk) else {
+            continue;
         };
+        frames += 1;
+        let confidence = pitch.get_confidence();
+        if note > 0.0 && confidence >= MIN_CONFIDENCE {
+            pitched += 1;
+            chroma[(note.round() as usize) % 12] += confidence;
+        }
+    }
+
+    let pitched_ratio = pitched as f64 / frames.max(1) as f64;
+    if pitched_ratio < MIN_PITCHED_RATIO {
+        return None;
+    }
+    let (key, score) = estimate_key(&chroma)?;
+    Some(KeyAnalysis {
+        key,
+        confidence: score as f64,
+        pitched_ratio,
+    })
+}
+
+/// Detect the approximate key of a WAV file.
+pub fn detect_key_from_file(path: &Path) -> Option<KeyAnalysis> {
+    let (raw_samples, channels, sample_rate) = read_wav_interleaved(path)?;
+    detect_key(&mix_to_mono(&raw_samples, channels), sample_rate)
+}
+
+// =============================================================================
+// Onset Detection
+// =============================================================================
+
+/// Level relative to the peak at which a sample counts as started (-20 dB).
+const ONSET_THRESHOLD: f32 = 0.1;
+
+/// Find the first onset in interleaved samples, in seconds from the start.
+///
+/// The onset is the first frame reaching 20 dB below the peak, so it covers
+/// both leading silence and slow attacks. Returns `None` for silent audio.
+pub fn detect_onset(samples: &[f32], channels: usize, sample_rate: u32) -> Option<f64> {
+    let channels = channels.max(1);
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    if peak <= f32::EPSILON || sample_rate == 0 {
+        return None;
+    }
+    let threshold = peak * ONSET_THRESHOLD;
+    let frame = samples.iter().position(|s| s.abs() >= threshold)? / channels;
+    Some(frame as f64 / sample_rate as f64)
+}
+
+/// Find every onset in interleaved samples, in seconds from the start.
+///
+/// Uses aubio's onset detector on the mono mix; an onset is reported about
+/// one hop (~10 ms) after it starts, so each is moved back by a hop.
+pub fn detect_onsets(samples: &[f32], channels: usize, sample_rate: u32) -> Vec<f64> {
+    use aubio_rs::{Onset, OnsetMode};
+
+    const BUF_SIZE: usize = 1024;
+    const HOP_SIZE: usize = 512;
+    /// Shortest time between two onsets.
+    const MIN_INTER_ONSET_MS: f32 = 50.0;
+
+    let mono = mix_to_mono(samples, channels.max(1));
+    if mono.len() < BUF_SIZE || sample_rate == 0 {
+        return Vec::new();
+    }
+    let Ok(onset) = Onset::new(OnsetMode::default(), BUF_SIZE, HOP_SIZE, sample_rate) else {
+        log::warn!("[ONSET] Failed to create onset detector");
+        return Vec::new();
     };
+    let mut onset = onset.with_minioi_ms(MIN_INTER_ONSET_MS);
 
-    let spec = wav_reader.spec();
-    let sample_rate = spec.sample_rate;
-    let channels = spec.channels as usize;
+    let mut onsets = Vec::new();
+    for chunk in mono.chunks_exact(HOP_SIZE) {
+        if onset.do_result(chunk).is_ok_and(|detected| detected > 0.0) {
+            let frame = onset.get_last().saturating_sub(HOP_SIZE);
+            onsets.push(frame as f64 / sample_rate as f64);
+        }
+    }
+    onsets
+}
+
+/// Find the first onset of a WAV file, in seconds from the start.
+pub fn detect_onset_from_file(path: &Path) -> Option<f64> {
+    let (samples, channels, sample_rate) = read_wav_interleaved(path)?;
+    detect_onset(&samples, channels, sample_rate)
+}
 
-    // Read all samples and convert to f32 mono
+/// Read a WAV file as interleaved f32 samples.
+///
+/// Returns the samples, the channel count and the sample rate.
+pub(crate) fn read_wav_interleaved(path: &Path) -> Option<(Vec<f32>, usize, u32)> {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    let file = File::open(path).ok()?;
+    let wav_reader = hound::WavReader::new(BufReader::new(file)).ok()?;
+
+    let spec = wav_reader.spec();
     let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Float => {
-            let raw_samples: Vec<f32> = wav_reader
-                .into_samples::<f32>()
-                .filter_map(|s| s.ok())
-                .collect();
-            mix_to_mono(&raw_samples, channels)
-        }
+        hound::SampleFormat::Float => wav_reader
+            .into_samples::<f32>()
+            .filter_map(|s| s.ok())
+            .collect(),
         hound::SampleFormat::Int => {
             let bits = spec.bits_per_sample;
             let max_value = (1i64 << (bits - 1)) as f32;
-            let raw_samples: Vec<f32> = wav_reader
+            wav_reader
                 .into_samples::<i32>()
                 .filter_map(|s| s.ok())
                 .map(|s| s as f32 / max_value)
-                .collect();
-            mix_to_mono(&raw_samples, channels)
+                .collect()
         }
     };
 
-    detect_bpm(&samples, sample_rate)
+    Some((samples, spec.channels as usize, spec.sample_rate))
 }
 
 /// Mix multi-channel audio to mono by averaging channels.
@@ -162,6 +292,71 @@ fn mix_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
         .collect()
 }
 
+// =============================================================================
+// Waveform Peaks
+// =============================================================================
+
+/// Min/max peak data for drawing a waveform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaveformPeaks {
+    /// Number of peak bins per channel.
+    pub resolution: usize,
+    /// Total number of frames in the source audio.
+    pub num_frames: usize,
+    /// Number of source frames covered by each bin.
+    pub frames_per_peak: f64,
+    /// Per-channel minimum value of each bin.
+    pub min: Vec<Vec<f32>>,
+    /// Per-channel maximum value of each bin.
+    pub max: Vec<Vec<f32>>,
+}
+
+/// Compute min/max peaks from interleaved samples.
+///
+/// The audio is split into `resolution` equally sized bins. If there are
+/// fewer frames than bins, the resolution is reduced to one bin per frame.
+pub fn compute_peaks(samples: &[f32], channels: usize, resolution: usize) -> WaveformPeaks {
+    let channels = channels.max(1);
+    let num_frames = samples.len() / channels;
+    let resolution = resolution.min(num_frames);
+    let frames_per_peak = if resolution > 0 {
+        num_frames as f64 / resolution as f64
+    } else {
+        0.0
+    };
+
+    let mut min = vec![vec![0.0f32; resolution]; channels];
+    let mut max = vec![vec![0.0f32; resolution]; channels];
+
+    for bin in 0..resolution {
+        let start = (bin as f64 * frames_per_peak) as usize;
+        let end = (((bin + 1) as f64 * frames_per_peak) as usize).clamp(start + 1, num_frames);
+        for ch in 0..channels {
+            let (lo, hi) = samples[start * channels..end * channels]
+                .iter()
+                .skip(ch)
+                .step_by(channels)
+                .fold((f32::MAX, f32::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+            min[ch][bin] = lo;
+            max[ch][bin] = hi;
+        }
+    }
+
+    WaveformPeaks {
+        resolution,
+        num_frames,
+        frames_per_peak,
+        min,
+        max,
+    }
+}
+
+/// Compute min/max peaks from a WAV file.
+pub fn compute_peaks_from_file(path: &Path, resolution: usize) -> Option<WaveformPeaks> {
+    let (samples, channels, _) = read_wav_interleaved(path)?;
+    Some(compute_peaks(&samples, channels, resolution))
+}
+
 // =============================================================================
 // Sample Handle
 // =============================================================================
@@ -256,8 +451,9 @@ impl SampleHandle {
                 warp_to_bpm: None,
             });
 
-            // Wait for sample to load
-            for attempt in 0..50 {
+            // Wait for sample to load (nothing loads it during validation)
+            let attempts = if handle.scsynth().is_noop() { 0 } else { 50 };
+            for attempt in 0..attempts {
                 std::thread::sleep(std::time::Duration::from_millis(100));
 
                 let loaded = handle.with_state(|state| state.samples.contains_key(&id));
@@ -535,6 +731,54 @@ impl SampleHandle {
 
     // === Sample info getters ===
 
+    /// ID of the loaded sample this handle plays (the parent for slices).
+    pub(crate) fn sample_id(&self) -> &str {
+        self.parent_id.as_deref().unwrap_or(&self.id)
+    }
+
+    /// Get the time from the playback start to the first onset, in milliseconds.
+    ///
+    /// Uses the onset measured on load for whole samples; slices and offset
+    /// playback are analysed from their start position.
+    pub fn onset_ms(&self) -> f64 {
+        let start_frame = self.get_start_frame();
+        if start_frame <= 0 {
+            let handle = require_handle();
+            return handle
+                .with_state(|state| state.samples.get(self.sample_id()).map(|info| info.onset_ms))
+                .unwrap_or(0.0);
+        }
+
+        let Some(path) = context::resolve_file(&self.path) else {
+            return 0.0;
+        };
+        let Some((samples, channels, sample_rate)) = read_wav_interleaved(&path) else {
+            return 0.0;
+        };
+        let start = (start_frame as usize * channels).min(samples.len());
+        let end = match self.get_end_frame() {
+            end if end > 0 => (end as usize * channels).clamp(start, samples.len()),
+            _ => samples.len(),
+        };
+        detect_onset(&samples[start..end], channels, sample_rate)
+            .map(|seconds| seconds * 1000.0)
+            .unwrap_or(0.0)
+    }
+
+    /// Get the key detected on load (e.g. "A minor"), or "" for unpitched samples.
+    pub fn detected_key(&self) -> String {
+        let handle = require_handle();
+        handle
+            .with_state(|state| {
+                state
+                    .samples
+                    .get(self.sample_id())
+                    .and_then(|info| info.detected_key)
+                    .map(|key| key.to_string())
+            })
+            .unwrap_or_default()
+    }
+
     /// Get the SuperCollider buffer ID for this sample.
     pub fn buffer_id(&self) -> i64 {
         let handle = require_handle();
@@ -735,6 +979,14 @@ fn sample_detected_bpm(sample: &mut SampleHandle) -> f64 {
     sample.detected_bpm
 }
 
+fn sample_onset_ms(sample: &mut SampleHandle) -> f64 {
+    sample.onset_ms()
+}
+
+fn sample_detected_key(sample: &mut SampleHandle) -> String {
+    sample.detected_key()
+}
+
 fn sample_warp_mode(sample: &mut SampleHandle) -> bool {
     sample.warp_mode
 }
@@ -760,13 +1012,35 @@ fn sample_overlaps(sample: &mut SampleHandle) -> f64 {
 }
 
 /// Load a sample from a file path.
-pub fn sample(id: String, path: String) -> SampleHandle {
-    SampleHandle::new(id, path)
+pub fn sample(id: String, path: String) -> Result<SampleHandle, Box<EvalAltResult>> {
+    check_quota(QuotaKind::Samples, &id)?;
+    Ok(SampleHandle::new(id, path))
 }
 
 /// Load a sample from a file path (alias).
-pub fn load_sample(id: String, path: String) -> SampleHandle {
-    SampleHandle::new(id, path)
+pub fn load_sample(id: String, path: String) -> Result<SampleHandle, Box<EvalAltResult>> {
+    sample(id, path)
+}
+
+/// Play a loaded sample once through the audition group.
+///
+/// Useful for browsing a sample library without wiring up a voice and pattern.
+pub fn preview(id: &str) {
+    let handle = require_handle();
+    let _ = handle.send(StateMessage::PreviewSample {
+        id: id.to_string(),
+        amp: 1.0,
+    });
+}
+
+/// Preview a sample handle at its configured amplitude.
+fn sample_preview(sample: &mut SampleHandle) {
+    let handle = require_handle();
+    let sample_id = sample.parent_id.as_ref().unwrap_or(&sample.id);
+    let _ = handle.send(StateMessage::PreviewSample {
+        id: sample_id.clone(),
+        amp: sample.amp as f32,
+    });
 }
 
 /// Register sample API with the Rhai engine.
@@ -778,6 +1052,10 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("sample", sample);
     engine.register_fn("load_sample", load_sample);
 
+    // Auditioning
+    engine.register_fn("preview", preview);
+    engine.register_fn("preview", sample_preview);
+
     // Info getters
     engine.register_fn("buffer_id", sample_buffer_id);
     engine.register_fn("synthdef_name", sample_synthdef_name);
@@ -792,9 +1070,13 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("path", sample_path);
     engine.register_get("path", sample_path);
 
-    // BPM and warp getters
+    // BPM, key and warp getters
     engine.register_fn("detected_bpm", sample_detected_bpm);
     engine.register_get("detected_bpm", sample_detected_bpm);
+    engine.register_fn("detected_key", sample_detected_key);
+    engine.register_get("detected_key", sample_detected_key);
+    engine.register_fn("onset_ms", sample_onset_ms);
+    engine.register_get("onset_ms", sample_onset_ms);
     engine.register_fn("warp_mode", sample_warp_mode);
     engine.register_get("warp_mode", sample_warp_mode);
     engine.register_fn("speed", sample_speed);
@@ -832,3 +1114,54 @@ pub fn register(engine: &mut Engine) {
     // Slicing
     engine.register_fn("slice", SampleHandle::slice_range);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_peaks_stereo() {
+        // 8 stereo frames: left ramps up, right is the negated left
+        let samples: Vec<f32> = (0..8)
+            .flat_map(|i| {
+                let v = i as f32 / 10.0;
+                [v, -v]
+            })
+            .collect();
+
+        let peaks = compute_peaks(&samples, 2, 4);
+        assert_eq!(peaks.resolution, 4);
+        assert_eq!(peaks.num_frames, 8);
+        assert_eq!(peaks.frames_per_peak, 2.0);
+        assert_eq!(peaks.min[0], vec![0.0, 0.2, 0.4, 0.6]);
+        assert_eq!(peaks.max[0], vec![0.1, 0.3, 0.5, 0.7]);
+        assert_eq!(peaks.min[1], vec![-0.1, -0.3, -0.5, -0.7]);
+        assert_eq!(peaks.max[1], vec![0.0, -0.2, -0.4, -0.6]);
+    }
+
+    #[test]
+    fn test_detect_onset() {
+        // 100 frames of silence, then a stereo hit at 1000 Hz sample rate
+        let mut samples = vec![0.0f32; 200];
+        samples.extend([0.02, -0.8, 0.5, 0.4]);
+        let onset = detect_onset(&samples, 2, 1000).unwrap();
+        assert!((onset - 0.1).abs() < 1e-9);
+
+        // Noise below 20 dB under the peak does not count
+        samples[10] = 0.01;
+        assert!((detect_onset(&samples, 2, 1000).unwrap() - 0.1).abs() < 1e-9);
+
+        assert!(detect_onset(&[0.0; 64], 1, 1000).is_none());
+    }
+
+    #[test]
+    fn test_compute_peaks_clamps_resolution() {
+        let peaks = compute_peaks(&[0.5, -0.5, 0.25], 1, 1024);
+        assert_eq!(peaks.resolution, 3);
+        assert_eq!(peaks.max[0], vec![0.5, -0.5, 0.25]);
+
+        let empty = compute_peaks(&[], 2, 16);
+        assert_eq!(empty.resolution, 0);
+        assert!(empty.min.iter().all(|c| c.is_empty()));
+    }
+}
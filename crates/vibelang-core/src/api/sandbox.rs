@@ -0,0 +1,633 @@
+//! Sandbox profiles for untrusted code evaluation.
+//!
+//! In workshop mode students submit snippets through `/eval` to a shared
+//! machine. Their code runs in a dedicated engine restricted by a
+//! [`SandboxProfile`]:
+//!
+//! - operation count and wall-clock limits per evaluation
+//! - no file or module access (`import`, `import_scd`, `synthdef_dir`,
+//!   samples, SFZ, recording, OSC capture, event logs)
+//! - no process control (`exit`, `sleep`, `set_quotas`)
+//! - caps on the voices and synths one evaluation may create
+//!
+//! Limits on runtime resources are enforced where the API talks to the
+//! runtime: [`RuntimeHandle::send`](crate::RuntimeHandle::send) rejects
+//! messages that would break the active profile, and the evaluation is
+//! aborted with a [`SandboxViolation`] at its next operation.
+//!
+//! Trusted remote evaluations go through [`eval_remote`]: they run with the
+//! full API, but still may not register hooks, which run shell commands on
+//! the host, unless the session allows it.
+
+use crate::state::StateMessage;
+use rhai::module_resolvers::ModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Module, Position, Shared};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::get_handle;
+
+/// Limits applied to sandboxed evaluations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SandboxProfile {
+    /// Profile name.
+    pub name: String,
+    /// Maximum number of script operations per evaluation.
+    pub max_operations: u64,
+    /// Maximum wall-clock time per evaluation.
+    pub max_eval_time: Duration,
+    /// Whether imports and file-loading functions are allowed.
+    pub allow_file_access: bool,
+    /// Maximum number of voices one evaluation may create.
+    pub max_new_voices: usize,
+    /// Maximum number of synths one evaluation may start.
+    pub max_synths: usize,
+}
+
+impl SandboxProfile {
+    /// Profile for students on a shared machine.
+    pub fn workshop() -> Self {
+        Self {
+            name: "workshop".to_string(),
+            max_operations: 1_000_000,
+            max_eval_time: Duration::from_secs(2),
+            allow_file_access: false,
+            max_new_voices: 8,
+            max_synths: 64,
+        }
+    }
+
+    /// Parse a profile name: `workshop`, or `trusted` for no sandbox.
+    pub fn parse(name: &str) -> Result<Option<Self>, String> {
+        match name {
+            "workshop" => Ok(Some(Self::workshop())),
+            "trusted" => Ok(None),
+            _ => Err(format!(
+                "unknown sandbox profile '{}' (expected \"workshop\" or \"trusted\")",
+                name
+            )),
+        }
+    }
+}
+
+/// Kind of sandbox limit that was hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Too many script operations.
+    Operations,
+    /// Evaluation ran too long.
+    Timeout,
+    /// Import or file access.
+    FileAccess,
+    /// `exit`, `sleep`, `set_quotas`, a timeline hook or a plugin message.
+    /// Also a hook from a trusted remote evaluation without hooks allowed.
+    ProcessControl,
+    /// Too many new voices.
+    VoiceLimit,
+    /// Too many synths started.
+    SynthLimit,
+}
+
+impl ViolationKind {
+    /// Stable identifier used in API responses.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ViolationKind::Operations => "operations",
+            ViolationKind::Timeout => "timeout",
+            ViolationKind::FileAccess => "file_access",
+            ViolationKind::ProcessControl => "process_control",
+            ViolationKind::VoiceLimit => "voice_limit",
+            ViolationKind::SynthLimit => "synth_limit",
+        }
+    }
+}
+
+/// A sandbox limit hit by an evaluation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SandboxViolation {
+    /// Limit that was hit.
+    pub kind: ViolationKind,
+    /// Human-readable description.
+    pub message: String,
+}
+
+/// Error of a sandboxed evaluation.
+#[derive(Debug)]
+pub enum SandboxError {
+    /// The code broke a sandbox limit.
+    Violation(SandboxViolation),
+    /// The code failed on its own.
+    Script(Box<EvalAltResult>),
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxError::Violation(v) => write!(f, "sandbox violation ({}): {}", v.kind.as_str(), v.message),
+            SandboxError::Script(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Bookkeeping of the evaluation currently running in the sandbox.
+struct ActiveSandbox {
+    profile: SandboxProfile,
+    started: Instant,
+    known_voices: HashSet<String>,
+    new_voices: HashSet<String>,
+    synths: usize,
+    violation: Option<SandboxViolation>,
+}
+
+impl ActiveSandbox {
+    /// Check a message against the profile's limits.
+    fn admit(&mut self, msg: &StateMessage) -> Option<SandboxViolation> {
+        match msg {
+            StateMessage::LoadSample { .. }
+            | StateMessage::LoadSfzInstrument { .. }
+            | StateMessage::PreloadAssets { .. }
+            | StateMessage::LoadVstInstrument { .. }
+            | StateMessage::AddEffect { vst_plugin: Some(_), .. }
+            | StateMessage::LoadLiveSet { .. }
+            | StateMessage::EnableScoreCapture { .. }
+            | StateMessage::SetOscCapture { path: Some(_) }
+            | StateMessage::SetEventLog { path: Some(_) }
+            | StateMessage::LoadSynthDefDir { .. }
+                if !self.profile.allow_file_access =>
+            {
+                Some(violation(
+                    ViolationKind::FileAccess,
+                    format!("{} is not allowed in the sandbox", msg.type_name()),
+                ))
+            }
+            // Hooks run shell commands on the host
+            StateMessage::SetHook { .. } => Some(violation(
+                ViolationKind::ProcessControl,
+                "hooks are not allowed in the sandbox".to_string(),
+            )),
+            // Plugins reach beyond the session (lights, hardware, ...)
+            StateMessage::Plugin { plugin, .. } => Some(violation(
+                ViolationKind::ProcessControl,
+                format!("messages to plugin '{}' are not allowed in the sandbox", plugin),
+            )),
+            StateMessage::UpsertVoice { name, .. }
+                if !self.known_voices.contains(name) && !self.new_voices.contains(name) =>
+            {
+                if self.new_voices.len() >= self.profile.max_new_voices {
+                    return Some(violation(
+                        ViolationKind::VoiceLimit,
+                        format!(
+                            "voice '{}' exceeds the limit of {} new voices per evaluation",
+                            name, self.profile.max_new_voices
+                        ),
+                    ));
+                }
+                self.new_voices.insert(name.clone());
+                None
+            }
+            StateMessage::TriggerVoice { .. }
+            | StateMessage::RunVoice { .. }
+            | StateMessage::NoteOn { .. }
+            | StateMessage::PreviewSample { .. }
+            | StateMessage::VstNoteOn { .. } => {
+                if self.synths >= self.profile.max_synths {
+                    return Some(violation(
+                        ViolationKind::SynthLimit,
+                        format!("more than {} synths started in one evaluation", self.profile.max_synths),
+                    ));
+                }
+                self.synths += 1;
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Bookkeeping of the trusted remote evaluation currently running.
+struct RemoteEval {
+    allow_hooks: bool,
+    violation: Option<SandboxViolation>,
+}
+
+impl RemoteEval {
+    /// Check a message against what remote clients may do.
+    fn admit(&self, msg: &StateMessage) -> Option<SandboxViolation> {
+        match msg {
+            StateMessage::SetHook { .. } if !self.allow_hooks => Some(violation(
+                ViolationKind::ProcessControl,
+                "hooks from /eval are disabled (start the session with --allow-hooks)".to_string(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<ActiveSandbox>> = const { RefCell::new(None) };
+    static REMOTE: RefCell<Option<RemoteEval>> = const { RefCell::new(None) };
+}
+
+fn violation(kind: ViolationKind, message: String) -> SandboxViolation {
+    SandboxViolation { kind, message }
+}
+
+/// Record a violation of the running evaluation (the first one wins).
+fn record(v: SandboxViolation) -> Box<EvalAltResult> {
+    let message = v.message.clone();
+    ACTIVE.with(|active| {
+        if let Some(sandbox) = active.borrow_mut().as_mut() {
+            sandbox.violation.get_or_insert(v);
+        }
+    });
+    message.into()
+}
+
+/// Check a message against the active sandbox or remote evaluation, if any.
+///
+/// Called by the runtime handle for every message; a no-op outside
+/// sandboxed and remote evaluations.
+pub(crate) fn admit(msg: &StateMessage) -> Result<(), String> {
+    REMOTE.with(|remote| {
+        let mut remote = remote.borrow_mut();
+        let Some(remote) = remote.as_mut() else {
+            return Ok(());
+        };
+        match remote.admit(msg) {
+            Some(v) => {
+                let message = v.message.clone();
+                remote.violation.get_or_insert(v);
+                Err(message)
+            }
+            None => Ok(()),
+        }
+    })?;
+
+    ACTIVE.with(|active| {
+        let mut active = active.borrow_mut();
+        let Some(sandbox) = active.as_mut() else {
+            return Ok(());
+        };
+        match sandbox.admit(msg) {
+            Some(v) => {
+                let message = v.message.clone();
+                sandbox.violation.get_or_insert(v);
+                Err(message)
+            }
+            None => Ok(()),
+        }
+    })
+}
+
+/// Module resolver that refuses every import.
+struct DenyImports;
+
+impl ModuleResolver for DenyImports {
+    fn resolve(
+        &self,
+        _engine: &Engine,
+        _source: Option<&str>,
+        path: &str,
+        _pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        Err(record(violation(
+            ViolationKind::FileAccess,
+            format!("import \"{}\" is not allowed in the sandbox", path),
+        )))
+    }
+}
+
+fn deny<T>(kind: ViolationKind, what: &str) -> Result<T, Box<EvalAltResult>> {
+    Err(record(violation(kind, format!("{} is not allowed in the sandbox", what))))
+}
+
+/// Restrict an engine to a sandbox profile.
+///
+/// Use a dedicated engine: the restrictions replace API functions and the
+/// module resolver. Evaluate with [`eval_sandboxed`].
+pub fn restrict_engine(engine: &mut Engine, profile: &SandboxProfile) {
+    engine.set_max_operations(profile.max_operations);
+
+    // Abort once a limit was hit or the time is up
+    engine.on_progress(|_| {
+        ACTIVE.with(|active| {
+            let mut active = active.borrow_mut();
+            let sandbox = active.as_mut()?;
+            if sandbox.violation.is_none() && sandbox.started.elapsed() > sandbox.profile.max_eval_time {
+                sandbox.violation = Some(violation(
+                    ViolationKind::Timeout,
+                    format!(
+                        "evaluation exceeded {} ms",
+                        sandbox.profile.max_eval_time.as_millis()
+                    ),
+                ));
+            }
+            sandbox.violation.as_ref().map(|_| Dynamic::UNIT)
+        })
+    });
+
+    // Process control blocks or ends the shared session
+    engine.register_fn("exit", || deny::<()>(ViolationKind::ProcessControl, "exit()"));
+    engine.register_fn("exit_with_code", |_: i64| deny::<()>(ViolationKind::ProcessControl, "exit_with_code()"));
+    engine.register_fn("sleep", |_: i64| deny::<()>(ViolationKind::ProcessControl, "sleep()"));
+    engine.register_fn("sleep_secs", |_: f64| deny::<()>(ViolationKind::ProcessControl, "sleep_secs()"));
+    engine.register_fn("set_quotas", |_: rhai::Map| deny::<()>(ViolationKind::ProcessControl, "set_quotas()"));
+
+    if !profile.allow_file_access {
+        engine.set_module_resolver(DenyImports);
+        engine.register_fn("sample", |_: String, _: String| {
+            deny::<super::sample::SampleHandle>(ViolationKind::FileAccess, "sample()")
+        });
+        engine.register_fn("load_sample", |_: String, _: String| {
+            deny::<super::sample::SampleHandle>(ViolationKind::FileAccess, "load_sample()")
+        });
+        engine.register_fn("load_sfz", |_: String, _: String| {
+            deny::<vibelang_sfz::SfzInstrumentHandle>(ViolationKind::FileAccess, "load_sfz()")
+        });
+        engine.register_fn("load_groove", |_: String| {
+            deny::<super::groove::Groove>(ViolationKind::FileAccess, "load_groove()")
+        });
+        engine.register_fn("save", |_: super::groove::Groove, _: String| {
+            deny::<super::groove::Groove>(ViolationKind::FileAccess, "groove.save()")
+        });
+        engine.register_fn("import_scd", |_: String| deny::<rhai::Array>(ViolationKind::FileAccess, "import_scd()"));
+        engine.register_fn("synthdef_dir", |_: String| deny::<rhai::Array>(ViolationKind::FileAccess, "synthdef_dir()"));
+        engine.register_fn("capture_osc", |_: String| deny::<()>(ViolationKind::FileAccess, "capture_osc()"));
+        engine.register_fn("log_events", |_: String| deny::<()>(ViolationKind::FileAccess, "log_events()"));
+        engine.register_fn("send_sysex_file", |_: &mut super::midi::MidiDevice, _: &str| {
+            deny::<()>(ViolationKind::FileAccess, "send_sysex_file()")
+        });
+    }
+}
+
+/// Evaluate code in an engine restricted by [`restrict_engine`].
+pub fn eval_sandboxed(engine: &Engine, profile: &SandboxProfile, code: &str) -> Result<Dynamic, SandboxError> {
+    let known_voices = get_handle()
+        .map(|h| h.with_state(|s| s.voices.keys().cloned().collect()))
+        .unwrap_or_default();
+
+    ACTIVE.with(|active| {
+        *active.borrow_mut() = Some(ActiveSandbox {
+            profile: profile.clone(),
+            started: Instant::now(),
+            known_voices,
+            new_voices: HashSet::new(),
+            synths: 0,
+            violation: None,
+        });
+    });
+
+    let result = engine.eval::<Dynamic>(code);
+    let recorded = ACTIVE.with(|active| active.borrow_mut().take().and_then(|s| s.violation));
+
+    match (recorded, result) {
+        (Some(v), _) => Err(SandboxError::Violation(v)),
+        (None, Ok(value)) => Ok(value),
+        (None, Err(e)) => match *e {
+            EvalAltResult::ErrorTooManyOperations(_) => Err(SandboxError::Violation(violation(
+                ViolationKind::Operations,
+                format!("evaluation exceeded {} operations", profile.max_operations),
+            ))),
+            _ => Err(SandboxError::Script(e)),
+        },
+    }
+}
+
+/// Whether the running evaluation may not register hooks: a sandboxed one,
+/// or a remote one without hooks allowed. Closures it registers for later
+/// (MIDI callbacks) are run with [`deny_hooks`].
+pub(crate) fn hooks_denied() -> bool {
+    ACTIVE.with(|active| active.borrow().is_some())
+        || REMOTE.with(|remote| remote.borrow().as_ref().is_some_and(|r| !r.allow_hooks))
+}
+
+/// Run `f` refusing hooks, like a remote evaluation without hooks allowed.
+pub(crate) fn deny_hooks<R>(f: impl FnOnce() -> R) -> R {
+    let remote = RemoteEval {
+        allow_hooks: false,
+        violation: None,
+    };
+    let previous = REMOTE.with(|r| r.borrow_mut().replace(remote));
+    let result = f();
+    REMOTE.with(|r| *r.borrow_mut() = previous);
+    result
+}
+
+/// Evaluate code from a trusted remote client (`/eval` without a sandbox).
+///
+/// The code runs in the session's engine with the full API, except that
+/// hooks are refused unless `allow_hooks` is set.
+pub fn eval_remote(engine: &Engine, code: &str, allow_hooks: bool) -> Result<Dynamic, SandboxError> {
+    REMOTE.with(|remote| {
+        *remote.borrow_mut() = Some(RemoteEval {
+            allow_hooks,
+            violation: None,
+        });
+    });
+
+    let result = engine.eval::<Dynamic>(code);
+    let recorded = REMOTE.with(|remote| remote.borrow_mut().take().and_then(|r| r.violation));
+
+    match (recorded, result) {
+        (Some(v), _) => Err(SandboxError::Violation(v)),
+        (None, result) => result.map_err(SandboxError::Script),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(profile: &SandboxProfile) -> Engine {
+        let mut engine = Engine::new();
+        restrict_engine(&mut engine, profile);
+        engine
+    }
+
+    fn kind(result: Result<Dynamic, SandboxError>) -> Option<ViolationKind> {
+        match result {
+            Err(SandboxError::Violation(v)) => Some(v.kind),
+            _ => None,
+        }
+    }
+
+    /// Evaluate `code` in the full API restricted to the workshop profile.
+    fn workshop(code: &str) -> Option<ViolationKind> {
+        let sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let profile = SandboxProfile::workshop();
+        let mut engine = crate::api::create_engine();
+        restrict_engine(&mut engine, &profile);
+        kind(eval_sandboxed(&engine, &profile, code))
+    }
+
+    /// Check a message against a fresh workshop sandbox.
+    fn workshop_admit(msg: &StateMessage) -> Option<ViolationKind> {
+        let mut sandbox = ActiveSandbox {
+            profile: SandboxProfile::workshop(),
+            started: Instant::now(),
+            known_voices: HashSet::new(),
+            new_voices: HashSet::new(),
+            synths: 0,
+            violation: None,
+        };
+        sandbox.admit(msg).map(|v| v.kind)
+    }
+
+    #[test]
+    fn test_script_limits() {
+        let mut profile = SandboxProfile::workshop();
+        profile.max_operations = 10_000;
+        let engine = engine(&profile);
+
+        assert_eq!(eval_sandboxed(&engine, &profile, "1 + 2").unwrap().as_int(), Ok(3));
+        assert_eq!(kind(eval_sandboxed(&engine, &profile, "loop {}")), Some(ViolationKind::Operations));
+        assert_eq!(kind(eval_sandboxed(&engine, &profile, "exit()")), Some(ViolationKind::ProcessControl));
+        assert_eq!(kind(eval_sandboxed(&engine, &profile, "import \"x\" as x;")), Some(ViolationKind::FileAccess));
+        assert!(matches!(eval_sandboxed(&engine, &profile, "undefined_fn()"), Err(SandboxError::Script(_))));
+
+        profile.max_operations = u64::MAX;
+        profile.max_eval_time = Duration::from_millis(20);
+        let engine = self::engine(&profile);
+        assert_eq!(kind(eval_sandboxed(&engine, &profile, "loop {}")), Some(ViolationKind::Timeout));
+    }
+
+    #[test]
+    fn test_message_limits() {
+        let mut sandbox = ActiveSandbox {
+            profile: SandboxProfile { max_new_voices: 1, max_synths: 2, ..SandboxProfile::workshop() },
+            started: Instant::now(),
+            known_voices: ["bass".to_string()].into_iter().collect(),
+            new_voices: HashSet::new(),
+            synths: 0,
+            violation: None,
+        };
+        let run = |name: &str| StateMessage::RunVoice { name: name.to_string() };
+
+        assert!(sandbox.admit(&run("a")).is_none());
+        assert!(sandbox.admit(&run("a")).is_none());
+        assert_eq!(sandbox.admit(&run("a")).unwrap().kind, ViolationKind::SynthLimit);
+
+        let free = StateMessage::FreeSample { id: "kick".to_string() };
+        assert!(sandbox.admit(&free).is_none());
+        let load = StateMessage::EnableScoreCapture { path: "/tmp/x".into() };
+        assert_eq!(sandbox.admit(&load).unwrap().kind, ViolationKind::FileAccess);
+        let hook = StateMessage::SetHook {
+            name: "lights".to_string(),
+            action: crate::hooks::HookAction::Command("true".to_string()),
+            beat: 0.0,
+            every: None,
+            timeout: crate::hooks::DEFAULT_HOOK_TIMEOUT,
+            source_location: Default::default(),
+        };
+        assert_eq!(sandbox.admit(&hook).unwrap().kind, ViolationKind::ProcessControl);
+        let plugin = StateMessage::Plugin {
+            plugin: "dmx".to_string(),
+            name: "scene".to_string(),
+            payload: rhai::Dynamic::UNIT,
+        };
+        assert_eq!(sandbox.admit(&plugin).unwrap().kind, ViolationKind::ProcessControl);
+        assert!(SandboxProfile::parse("trusted").unwrap().is_none());
+        assert!(SandboxProfile::parse("root").is_err());
+    }
+
+    #[test]
+    fn test_workshop_denies_sample() {
+        assert_eq!(workshop(r#"sample("kick", "/etc/passwd")"#), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_load_sample() {
+        assert_eq!(workshop(r#"load_sample("kick", "/etc/passwd")"#), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_load_sfz() {
+        assert_eq!(workshop(r#"load_sfz("piano", "/etc/passwd")"#), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_load_groove() {
+        assert_eq!(workshop(r#"load_groove("/etc/passwd")"#), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_groove_save() {
+        assert_eq!(workshop(r#"groove("swing").save("/tmp/swing.groove")"#), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_send_sysex_file() {
+        let profile = SandboxProfile::workshop();
+        let mut engine = crate::api::create_engine();
+        engine.register_fn("test_device", || crate::api::midi::MidiDevice::detached("synth"));
+        restrict_engine(&mut engine, &profile);
+        let code = r#"test_device().send_sysex_file("/etc/passwd")"#;
+        assert_eq!(kind(eval_sandboxed(&engine, &profile, code)), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_capture_osc() {
+        assert_eq!(workshop(r#"capture_osc("/tmp/session.osc")"#), Some(ViolationKind::FileAccess));
+        let capture = StateMessage::SetOscCapture { path: Some("/tmp/session.osc".into()) };
+        assert_eq!(workshop_admit(&capture), Some(ViolationKind::FileAccess));
+        assert_eq!(workshop_admit(&StateMessage::SetOscCapture { path: None }), None);
+    }
+
+    #[test]
+    fn test_workshop_denies_import_scd() {
+        assert_eq!(workshop(r#"import_scd("/tmp/acid.scd")"#), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_synthdef_dir() {
+        assert_eq!(workshop(r#"synthdef_dir("/tmp")"#), Some(ViolationKind::FileAccess));
+        let load = StateMessage::LoadSynthDefDir { path: "/tmp".into(), synthdefs: Vec::new() };
+        assert_eq!(workshop_admit(&load), Some(ViolationKind::FileAccess));
+    }
+
+    #[test]
+    fn test_workshop_denies_log_events() {
+        assert_eq!(workshop(r#"log_events("/tmp/events.csv")"#), Some(ViolationKind::FileAccess));
+        let log = StateMessage::SetEventLog { path: Some("/tmp/events.csv".into()) };
+        assert_eq!(workshop_admit(&log), Some(ViolationKind::FileAccess));
+        assert_eq!(workshop_admit(&StateMessage::SetEventLog { path: None }), None);
+    }
+
+    #[test]
+    fn test_remote_hooks() {
+        let sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        let code = r#"hook("lights").command("true").apply();"#;
+
+        assert_eq!(kind(eval_remote(&engine, code, false)), Some(ViolationKind::ProcessControl));
+        assert!(eval_remote(&engine, "1 + 2", false).is_ok());
+        assert!(eval_remote(&engine, code, true).is_ok());
+    }
+
+    #[test]
+    fn test_remote_callbacks_keep_hooks_denied() {
+        let sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let mut engine = crate::api::create_engine();
+        engine.register_fn("test_device", || crate::api::midi::MidiDevice::detached("pads"));
+        let code = r#"test_device().on_note(60).callback(|v| hook("lights").command("true").apply());"#;
+
+        // Fire the callbacks `code` registers and count the ones that succeeded
+        let run = |allow_hooks: bool| {
+            let before = crate::api::midi::callback_ids();
+            assert!(eval_remote(&engine, code, allow_hooks).is_ok());
+            for id in crate::api::midi::callback_ids().into_iter().filter(|id| !before.contains(id)) {
+                sim.handle().with_state_mut(|s| s.midi_config.routing.queue_callback(id, 100));
+            }
+            crate::api::execute_pending_callbacks(&engine, &rhai::AST::empty(), &mut rhai::Scope::new())
+        };
+
+        // The callback runs after the request, but still may not add hooks
+        assert_eq!(run(false), 0);
+        assert_eq!(run(true), 1);
+    }
+}
@@ -3,13 +3,16 @@
 //! Sequences arrange patterns, melodies, fades, and other sequences
 //! on a timeline for structured musical composition.
 
-use crate::sequences::{ClipMode, ClipSource, FadeDefinition, SequenceClip, SequenceDefinition};
-use crate::state::StateMessage;
+use crate::events::FadeCurve;
+use crate::sequences::{ClipMode, ClipSource, FadeDefinition, KeyChange, SequenceClip, SequenceDefinition};
+use crate::state::{QuotaKind, StateMessage};
+use crate::timing::TimeSpan;
 use rhai::{CustomType, Dynamic, Engine, EvalAltResult, NativeCallContext, Position, TypeBuilder};
 use std::ops::Range;
 
 use super::context::{self, SourceLocation};
-use super::require_handle;
+use super::helpers::{beats_arg, span_beats, Decibels};
+use super::{check_quota, require_handle};
 
 /// A Sequence builder for creating timeline arrangements.
 #[derive(Debug, Clone, CustomType)]
@@ -19,7 +22,17 @@ pub struct Sequence {
     /// Loop length in beats.
     loop_beats: f64,
     /// Clips in the sequence.
-    clips: Vec<(f64, f64, ClipSource, ClipMode)>,
+    clips: Vec<SequenceClip>,
+    /// Tempo factor of the whole sequence.
+    speed: f64,
+    /// Launch grid in beats, overriding the global quantization.
+    launch_quantization: Option<f64>,
+    /// Whether launching takes over the phase of what plays the same voices.
+    legato: bool,
+    /// Semitones the melodic events are transposed by.
+    transpose: i32,
+    /// Timed key changes.
+    key_changes: Vec<KeyChange>,
     /// Group path.
     group_path: String,
     /// Source location where this sequence was defined.
@@ -39,6 +52,11 @@ impl Sequence {
             name,
             loop_beats: 16.0,
             clips: Vec::new(),
+            speed: 1.0,
+            launch_quantization: None,
+            legato: false,
+            transpose: 0,
+            key_changes: Vec::new(),
             group_path: context::current_group_path(),
             source_location,
         }
@@ -48,32 +66,24 @@ impl Sequence {
 
     /// Set the loop length in bars.
     pub fn loop_bars(mut self, bars: f64) -> Self {
-        // Assuming 4 beats per bar
-        self.loop_beats = bars * 4.0;
+        self.loop_beats = super::helpers::bars(bars);
         self
     }
 
     /// Set the loop length in bars (integer version).
-    pub fn loop_bars_int(mut self, bars: i64) -> Self {
-        self.loop_beats = bars as f64 * 4.0;
-        self
+    pub fn loop_bars_int(self, bars: i64) -> Self {
+        self.loop_bars(bars as f64)
     }
 
-    /// Set the loop length in beats.
-    pub fn loop_beats(mut self, beats: f64) -> Self {
-        self.loop_beats = beats;
-        self
-    }
-
-    /// Set the loop length in beats (integer version).
-    pub fn loop_beats_int(mut self, beats: i64) -> Self {
-        self.loop_beats = beats as f64;
-        self
+    /// Set the loop length (e.g. `loop_beats(16)` or `loop_beats(4.bars)`).
+    pub fn loop_beats(mut self, length: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.loop_beats = beats_arg("loop_beats", &length)?;
+        Ok(self)
     }
 
     /// Add a clip from a Pattern.
     pub fn clip_pattern(mut self, range: Range<f64>, pattern: super::pattern::Pattern) -> Self {
-        self.clips.push((
+        self.clips.push(SequenceClip::new(
             range.start,
             range.end,
             ClipSource::Pattern(pattern.name.clone()),
@@ -84,7 +94,7 @@ impl Sequence {
 
     /// Add a clip from a Melody.
     pub fn clip_melody(mut self, range: Range<f64>, melody: super::melody::Melody) -> Self {
-        self.clips.push((
+        self.clips.push(SequenceClip::new(
             range.start,
             range.end,
             ClipSource::Melody(melody.name.clone()),
@@ -95,7 +105,7 @@ impl Sequence {
 
     /// Add a clip from a Fade.
     pub fn clip_fade(mut self, range: Range<f64>, fade: Fade) -> Self {
-        self.clips.push((
+        self.clips.push(SequenceClip::new(
             range.start,
             range.end,
             ClipSource::Fade(fade.name.clone()),
@@ -106,7 +116,7 @@ impl Sequence {
 
     /// Add a clip from another Sequence.
     pub fn clip_sequence(mut self, range: Range<f64>, seq: Sequence) -> Self {
-        self.clips.push((
+        self.clips.push(SequenceClip::new(
             range.start,
             range.end,
             ClipSource::Sequence(seq.name.clone()),
@@ -119,7 +129,7 @@ impl Sequence {
     pub fn clip_name(mut self, range: Range<f64>, name: String) -> Self {
         // Detect type by checking if pattern, melody, fade, or sequence exists
         // Default to pattern for now
-        self.clips.push((
+        self.clips.push(SequenceClip::new(
             range.start,
             range.end,
             ClipSource::Pattern(name),
@@ -130,34 +140,96 @@ impl Sequence {
 
     /// Add a clip from a Range and a Clip source (Dynamic).
     pub fn clip_dynamic(mut self, range: rhai::Dynamic, source: rhai::Dynamic) -> Self {
-        // Try to get the range - it's passed as a Range<i64> from Rhai
-        let (start, end) = if let Some(r) = range.clone().try_cast::<std::ops::Range<i64>>() {
-            (r.start as f64, r.end as f64)
-        } else if let Ok(arr) = range.into_array() {
-            if arr.len() >= 2 {
-                let s = arr[0].as_int().unwrap_or(0) as f64;
-                let e = arr[1].as_int().unwrap_or(0) as f64;
-                (s, e)
-            } else {
-                return self;
-            }
-        } else {
+        let Some((start, end)) = beat_range(range) else {
             return self;
         };
 
         // Detect source type - just store the name, runtime resolves from global state
         if let Some(p) = source.clone().try_cast::<super::pattern::Pattern>() {
-            self.clips.push((start, end, ClipSource::Pattern(p.name.clone()), ClipMode::Loop));
+            self.clips.push(SequenceClip::new(start, end, ClipSource::Pattern(p.name.clone()), ClipMode::Loop));
+        } else if let Some(c) = source.clone().try_cast::<super::ctrl::Ctrl>() {
+            self.clips.push(SequenceClip::new(start, end, ClipSource::Pattern(c.name.clone()), ClipMode::Loop));
         } else if let Some(m) = source.clone().try_cast::<super::melody::Melody>() {
-            self.clips.push((start, end, ClipSource::Melody(m.name.clone()), ClipMode::Loop));
+            self.clips.push(SequenceClip::new(start, end, ClipSource::Melody(m.name.clone()), ClipMode::Loop));
         } else if let Some(f) = source.clone().try_cast::<Fade>() {
-            self.clips.push((start, end, ClipSource::Fade(f.name.clone()), ClipMode::Once));
+            self.clips.push(SequenceClip::new(start, end, ClipSource::Fade(f.name.clone()), ClipMode::Once));
         } else if let Some(s) = source.clone().try_cast::<Sequence>() {
-            self.clips.push((start, end, ClipSource::Sequence(s.name.clone()), ClipMode::Loop));
+            self.clips.push(SequenceClip::new(start, end, ClipSource::Sequence(s.name.clone()), ClipMode::Loop));
         } else if let Ok(name) = source.into_immutable_string() {
-            self.clips.push((start, end, ClipSource::Pattern(name.to_string()), ClipMode::Loop));
+            self.clips.push(SequenceClip::new(start, end, ClipSource::Pattern(name.to_string()), ClipMode::Loop));
+        }
+
+        self
+    }
+
+    /// Add a clip whose source plays at `speed` times its tempo.
+    pub fn clip_dynamic_at_speed(self, range: rhai::Dynamic, source: rhai::Dynamic, speed: f64) -> Self {
+        let added = self.clips.len();
+        let mut seq = self.clip_dynamic(range, source);
+        if let Some(clip) = seq.clips.get_mut(added) {
+            clip.speed = crate::sequences::sanitize_speed(speed);
         }
+        seq
+    }
+
+    /// Play the whole sequence at `speed` times its tempo (0.5 = half time).
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = crate::sequences::sanitize_speed(speed);
+        self
+    }
+
+    /// Play the whole sequence at half time.
+    pub fn half_time(self) -> Self {
+        self.speed(0.5)
+    }
+
+    /// Play the whole sequence at double time.
+    pub fn double_time(self) -> Self {
+        self.speed(2.0)
+    }
+
+    /// Launch on `grid` instead of the global quantization (0 = immediately).
+    pub fn launch_quantize(mut self, grid: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.launch_quantization = Some(beats_arg("launch_quantize", &grid)?.max(0.0));
+        Ok(self)
+    }
+
+    /// Launch in legato: take over from whatever plays the same voices at its phase.
+    pub fn legato(mut self, legato: bool) -> Self {
+        self.legato = legato;
+        self
+    }
+
+    /// Transpose every melody the sequence plays by `semitones`.
+    ///
+    /// Moves whole melodies, so scale-degree melodies stay in their scale in
+    /// the new key. Patterns are not transposed.
+    pub fn transpose(mut self, semitones: i64) -> Self {
+        self.transpose = semitones.clamp(-48, 48) as i32;
+        self
+    }
 
+    /// Change key by `semitones` from a beat to the end of the sequence, or
+    /// within a range of beats (`key_change(32..48, 1)`).
+    ///
+    /// Key changes add up with the sequence's transposition and with each
+    /// other where they overlap, so a build can step up one key at a time.
+    pub fn key_change(mut self, at: Dynamic, semitones: i64) -> Self {
+        let (start, end) = if let Ok(beat) = at.as_float() {
+            (beat, None)
+        } else if let Ok(beat) = at.as_int() {
+            (beat as f64, None)
+        } else if let Some((start, end)) = beat_range(at) {
+            (start, Some(end))
+        } else {
+            log::warn!("[SEQUENCE] key_change() on '{}' needs a beat or a range of beats", self.name);
+            return self;
+        };
+        self.key_changes.push(KeyChange {
+            start,
+            end,
+            semitones: semitones.clamp(-48, 48) as i32,
+        });
         self
     }
 
@@ -167,17 +239,18 @@ impl Sequence {
     fn do_apply(&self) {
         let handle = require_handle();
 
-        let clips: Vec<SequenceClip> = self.clips.iter().map(|(start, end, source, mode)| {
-            SequenceClip::new(*start, *end, source.clone(), mode.clone())
-        }).collect();
-
         let def = SequenceDefinition {
             name: self.name.clone(),
             loop_beats: self.loop_beats,
-            clips,
+            clips: self.clips.clone(),
             generation: 0,
             play_once: false,
             source_location: self.source_location.clone(),
+            speed: self.speed,
+            launch_quantization: self.launch_quantization,
+            legato: self.legato,
+            transpose: self.transpose,
+            key_changes: self.key_changes.clone(),
         };
 
         let _ = handle.send(StateMessage::CreateSequence {
@@ -225,6 +298,25 @@ impl Sequence {
     }
 }
 
+/// Start and end beat of a clip range (`0..16`, `[0, 16]` or `[2.bars, 4.bars]`).
+fn beat_range(range: Dynamic) -> Option<(f64, f64)> {
+    let bound = |value: &Dynamic| match value.read_lock::<TimeSpan>() {
+        Some(span) => span_beats(*span),
+        None => value.as_float().or_else(|_| value.as_int().map(|v| v as f64)).unwrap_or(0.0),
+    };
+    if let Some(r) = range.clone().try_cast::<std::ops::Range<i64>>() {
+        Some((r.start as f64, r.end as f64))
+    } else if let Ok(arr) = range.into_array() {
+        if arr.len() >= 2 {
+            Some((bound(&arr[0]), bound(&arr[1])))
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
 /// A Fade builder for creating parameter automation.
 #[derive(Debug, Clone, CustomType)]
 pub struct Fade {
@@ -242,6 +334,12 @@ pub struct Fade {
     to_value: f64,
     /// Duration in beats.
     duration_beats: f64,
+    /// Interpolation, decibels once `from`/`to` were given as levels.
+    curve: FadeCurve,
+    /// Launch grid in beats when started on its own.
+    launch_quantization: Option<f64>,
+    /// Source location where this fade was defined.
+    source_location: SourceLocation,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -252,8 +350,14 @@ enum FadeTargetType {
 }
 
 impl Fade {
-    /// Create a new fade with the given name.
-    pub fn new(name: String) -> Self {
+    /// Create a new fade with the given name and source location from NativeCallContext.
+    pub fn new(ctx: NativeCallContext, name: String) -> Self {
+        let pos = ctx.call_position();
+        let source_location = SourceLocation::new(
+            context::get_current_script_file(),
+            if pos.is_none() { None } else { pos.line().map(|l| l as u32) },
+            if pos.is_none() { None } else { pos.position().map(|c| c as u32) },
+        );
         Self {
             name,
             target_type: FadeTargetType::Group,
@@ -262,6 +366,9 @@ impl Fade {
             from_value: 0.0,
             to_value: 1.0,
             duration_beats: 4.0,
+            curve: FadeCurve::Linear,
+            launch_quantization: None,
+            source_location,
         }
     }
 
@@ -306,18 +413,36 @@ impl Fade {
         self
     }
 
-    /// Set duration in beats.
-    pub fn over(mut self, beats: f64) -> Self {
-        self.duration_beats = beats;
-        self
+    /// Set the start level in decibels; the fade then interpolates in dB.
+    pub fn from_db(mut self, level: Decibels) -> Self {
+        self.curve = FadeCurve::Decibels;
+        self.from(level.amp())
     }
 
-    /// Set duration in bars (assuming 4 beats per bar).
+    /// Set the end level in decibels; the fade then interpolates in dB.
+    pub fn to_db(mut self, level: Decibels) -> Self {
+        self.curve = FadeCurve::Decibels;
+        self.to(level.amp())
+    }
+
+    /// Set the duration (e.g. `over(2.bars)`).
+    pub fn over(mut self, duration: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.duration_beats = beats_arg("over", &duration)?;
+        Ok(self)
+    }
+
+    /// Set duration in bars.
     pub fn over_bars(mut self, bars: i64) -> Self {
-        self.duration_beats = bars as f64 * 4.0;
+        self.duration_beats = super::helpers::bars(bars as f64);
         self
     }
 
+    /// Start on `grid` instead of the fade default or global quantization (0 = immediately).
+    pub fn launch_quantize(mut self, grid: Dynamic) -> Result<Self, Box<EvalAltResult>> {
+        self.launch_quantization = Some(beats_arg("launch_quantize", &grid)?.max(0.0));
+        Ok(self)
+    }
+
     // === Actions ===
 
     /// Register and apply the fade definition (chainable).
@@ -337,7 +462,13 @@ impl Fade {
             &self.param_name,
         )
         .with_range(self.from_value as f32, self.to_value as f32)
-        .with_duration(self.duration_beats);
+        .with_duration(self.duration_beats)
+        .with_curve(self.curve)
+        .with_source_location(self.source_location.clone());
+        let def = FadeDefinition {
+            launch_quantization: self.launch_quantization,
+            ..def
+        };
 
         let _ = handle.send(StateMessage::CreateFadeDefinition {
             fade: def,
@@ -346,11 +477,12 @@ impl Fade {
         self
     }
 
-    /// Start the fade immediately (chainable).
+    /// Start the fade on its launch grid (chainable).
     pub fn start(self) -> Self {
         let applied = self.apply();
-        // For now, starting is handled by sequences that include this fade
-        // Immediate fades could use a different mechanism
+        let _ = require_handle().send(StateMessage::StartFade {
+            name: applied.name.clone(),
+        });
         applied
     }
 }
@@ -366,6 +498,8 @@ pub struct Fx {
     params: std::collections::HashMap<String, f64>,
     /// Group path.
     group_path: String,
+    /// VST plugin hosted by the effect (if using a VST).
+    vst_plugin: Option<String>,
     /// Source location where this effect was defined.
     source_location: SourceLocation,
 }
@@ -384,6 +518,7 @@ impl Fx {
             synth_name: None,
             params: std::collections::HashMap::new(),
             group_path: context::current_group_path(),
+            vst_plugin: None,
             source_location,
         }
     }
@@ -393,6 +528,14 @@ impl Fx {
     /// Set the synthdef for this effect.
     pub fn synth(mut self, synth_name: String) -> Self {
         self.synth_name = Some(synth_name);
+        self.vst_plugin = None;
+        self
+    }
+
+    /// Host a VST effect plugin; parameters set the plugin's parameters by name.
+    pub fn vst(mut self, plugin: String) -> Self {
+        self.vst_plugin = Some(super::vst::resolve_plugin(&plugin));
+        self.synth_name = None;
         self
     }
 
@@ -402,10 +545,25 @@ impl Fx {
         self
     }
 
+    /// Set a gain parameter in decibels (`param("mix", -6.db)`).
+    pub fn param_db(self, key: String, level: Decibels) -> Self {
+        self.param(key, level.amp())
+    }
+
     // === Actions ===
 
     /// Apply the effect to the current group.
     pub fn apply(self) {
+        self.send(None);
+    }
+
+    /// Insert the effect on a voice (see `Voice::insert`).
+    pub(crate) fn insert_on(mut self, voice: &str, group_path: &str) {
+        self.group_path = group_path.to_string();
+        self.send(Some(voice.to_string()));
+    }
+
+    fn send(self, voice: Option<String>) {
         let handle = require_handle();
 
         let params: std::collections::HashMap<String, f32> = self
@@ -421,19 +579,23 @@ impl Fx {
             params,
             bus_in: 0,
             bus_out: 0,
+            vst_plugin: self.vst_plugin,
+            voice,
             source_location: self.source_location.clone(),
         });
     }
 }
 
 /// Create a new sequence builder with source location tracking.
-pub fn sequence(ctx: NativeCallContext, name: String) -> Sequence {
-    Sequence::new(ctx, name)
+pub fn sequence(ctx: NativeCallContext, name: String) -> Result<Sequence, Box<EvalAltResult>> {
+    let name = context::namespaced(&name);
+    check_quota(QuotaKind::Sequences, &name)?;
+    Ok(Sequence::new(ctx, name))
 }
 
-/// Create a new fade builder.
-pub fn fade(name: String) -> Fade {
-    Fade::new(name)
+/// Create a new fade builder with source location tracking.
+pub fn fade(ctx: NativeCallContext, name: String) -> Fade {
+    Fade::new(ctx, context::namespaced(&name))
 }
 
 /// Create a new fx builder with source location tracking.
@@ -457,13 +619,25 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("loop_bars", Sequence::loop_bars);
     engine.register_fn("loop_bars", Sequence::loop_bars_int);
     engine.register_fn("loop_beats", Sequence::loop_beats);
-    engine.register_fn("loop_beats", Sequence::loop_beats_int);
     engine.register_fn("clip", Sequence::clip_dynamic);
     engine.register_fn("clip", Sequence::clip_pattern);
     engine.register_fn("clip", Sequence::clip_melody);
     engine.register_fn("clip", Sequence::clip_fade);
     engine.register_fn("clip", Sequence::clip_sequence);
     engine.register_fn("clip", Sequence::clip_name);
+    engine.register_fn("clip", Sequence::clip_dynamic_at_speed);
+    engine.register_fn("clip", |seq: Sequence, range: Dynamic, source: Dynamic, speed: i64| {
+        seq.clip_dynamic_at_speed(range, source, speed as f64)
+    });
+    engine.register_fn("speed", Sequence::speed);
+    engine.register_fn("speed", |seq: Sequence, speed: i64| seq.speed(speed as f64));
+    engine.register_fn("half_time", Sequence::half_time);
+    engine.register_fn("double_time", Sequence::double_time);
+    engine.register_fn("launch_quantize", Sequence::launch_quantize);
+    engine.register_fn("legato", Sequence::legato);
+    engine.register_fn("legato", |x: Sequence| x.legato(true));
+    engine.register_fn("transpose", Sequence::transpose);
+    engine.register_fn("key_change", Sequence::key_change);
 
     // Sequence actions
     engine.register_fn("apply", Sequence::apply);
@@ -480,8 +654,11 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("param", Fade::param);
     engine.register_fn("from", Fade::from);
     engine.register_fn("to", Fade::to);
+    engine.register_fn("from", Fade::from_db);
+    engine.register_fn("to", Fade::to_db);
     engine.register_fn("over", Fade::over);
     engine.register_fn("over_bars", Fade::over_bars);
+    engine.register_fn("launch_quantize", Fade::launch_quantize);
 
     // Fade actions
     engine.register_fn("apply", Fade::apply);
@@ -489,8 +666,56 @@ pub fn register(engine: &mut Engine) {
 
     // Fx builder methods
     engine.register_fn("synth", Fx::synth);
+    engine.register_fn("vst", Fx::vst);
     engine.register_fn("param", Fx::param);
+    engine.register_fn("param", Fx::param_db);
 
     // Fx actions
     engine.register_fn("apply", Fx::apply);
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::Simulation;
+
+    #[test]
+    fn test_sequence_transpose_and_key_change() {
+        let mut sim = Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        crate::api::create_engine()
+            .run(
+                r#"
+                set_tempo(120);
+                let lead = voice("lead").synth("saw");
+                let kick = voice("kick").synth("kick_909");
+                let riff = melody("riff").on(lead).notes("A4 A4 A4 A4").apply();
+                let four = pattern("four").on(kick).step("x... x... x... x...").apply();
+                sequence("song").loop_beats(8).clip(0..8, riff).clip(0..8, four).transpose(12).key_change(4, 2).start();
+                "#,
+            )
+            .unwrap();
+        sim.start();
+        sim.advance(8.0);
+
+        let freqs: Vec<(f64, f64)> = sim
+            .events()
+            .iter()
+            .filter(|e| e.beat < 8.0 && e.event.melody_name.is_some())
+            .filter_map(|e| {
+                let freq = e.event.controls.iter().find(|(name, _)| name == "freq")?.1;
+                Some((e.beat, freq as f64))
+            })
+            .collect();
+        assert_eq!(freqs.len(), 8);
+        for (beat, freq) in freqs {
+            let expected = if beat < 4.0 { 880.0 } else { 880.0 * 2f64.powf(2.0 / 12.0) };
+            assert!((freq - expected).abs() < 0.01, "beat {}: {} Hz", beat, freq);
+        }
+        // Patterns are left alone
+        assert!(sim
+            .events()
+            .iter()
+            .filter(|e| e.event.pattern_name.is_some())
+            .all(|e| e.event.controls.iter().all(|(name, _)| name != "freq")));
+    }
+}
@@ -54,6 +54,11 @@ pub fn load_sfz(id: String, path: String) -> SfzInstrumentHandle {
         sfz_path: sfz_path.clone(),
     });
 
+    // Nothing loads the instrument during validation
+    if handle.scsynth().is_noop() {
+        return SfzInstrumentHandle::new(id, path, 0);
+    }
+
     // Wait for the instrument to be loaded (poll state)
     let start = std::time::Instant::now();
     let timeout = std::time::Duration::from_secs(10);
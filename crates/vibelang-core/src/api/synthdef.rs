@@ -1,15 +1,18 @@
 //! SynthDef API for Rhai scripts.
 //!
 //! This module provides the `define_synthdef` and `define_fx` functions
-//! that allow users to create SuperCollider SynthDefs from Rhai closures.
+//! that allow users to create SuperCollider SynthDefs from Rhai closures,
+//! `import_scd` for SynthDefs written in sclang and `synthdef_dir` for
+//! directories of precompiled `.scsyndef` files.
 //!
 //! Note: This module requires vibelang-dsp for actual SynthDef generation.
 //! The actual DSP registration (UGens, NodeRef, etc.) must be done by the
 //! CLI or host application that imports both vibelang-core and vibelang-dsp.
 
-use rhai::Engine;
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
 
-use super::require_handle;
+use super::{context, require_handle};
+use crate::state::StateMessage;
 
 /// Register synthdef placeholder functions.
 ///
@@ -32,6 +35,49 @@ pub fn register(engine: &mut Engine) {
 
     // We can register some utility functions here
     engine.register_fn("load_synthdef_bytes", load_synthdef_bytes);
+    engine.register_fn("import_scd", import_scd);
+    engine.register_fn("synthdef_dir", synthdef_dir);
+}
+
+/// Import the SynthDefs of an sclang `.scd` file, returning their names.
+///
+/// The file is compiled with `sclang` (cached until it changes) and every
+/// SynthDef it adds becomes available to voices by name.
+///
+/// ```rhai
+/// import_scd("synths/acid.scd");
+/// let bass = voice("bass").synth("acid");
+/// ```
+pub fn import_scd(path: String) -> Result<Array, Box<EvalAltResult>> {
+    let resolved = context::resolve_file_or_error(&path).map_err(|e| format!("import_scd: {}", e))?;
+    let synthdefs = crate::scd::compile_scd(&resolved).map_err(|e| format!("import_scd: {:#}", e))?;
+    let handle = require_handle();
+    let mut names = Array::new();
+    for (name, bytes) in synthdefs {
+        log::info!("[SCD] Loaded synthdef '{}' from {}", name, resolved.display());
+        names.push(Dynamic::from(name.clone()));
+        let _ = handle.send(StateMessage::LoadSynthDef { name, bytes });
+    }
+    Ok(names)
+}
+
+/// Load a directory of precompiled `.scsyndef` files, returning their names.
+///
+/// In watch mode the directory is reloaded whenever one of its files
+/// changes, e.g. after `.writeDefFile` in the SuperCollider IDE.
+///
+/// ```rhai
+/// synthdef_dir("./synthdefs");
+/// let pad = voice("pad").synth("warm_pad");
+/// ```
+pub fn synthdef_dir(path: String) -> Result<Array, Box<EvalAltResult>> {
+    let resolved = context::resolve_file_or_error(&path).map_err(|e| format!("synthdef_dir: {}", e))?;
+    let dir = resolved.canonicalize().unwrap_or(resolved);
+    let synthdefs = crate::synthdef_dir::read_synthdef_dir(&dir).map_err(|e| format!("synthdef_dir: {:#}", e))?;
+    log::info!("[SYNTHDEF] Loading {} synthdefs from {}", synthdefs.len(), dir.display());
+    let names = synthdefs.iter().map(|(name, _)| Dynamic::from(name.clone())).collect();
+    let _ = require_handle().send(StateMessage::LoadSynthDefDir { path: dir, synthdefs });
+    Ok(names)
 }
 
 /// Load a pre-compiled synthdef from bytes.
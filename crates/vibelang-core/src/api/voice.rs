@@ -2,14 +2,28 @@
 //!
 //! Voices are the basic sound-producing units in VibeLang.
 
-use crate::state::StateMessage;
+use crate::drumkit::{DrumKit, DrumPad};
+use crate::groove::{JitterDistribution, TimingFeel};
+use crate::mono::{MonoMode, NotePriority};
+use crate::state::{QuotaKind, StateMessage};
+use crate::timing::TimeSpan;
 use rhai::{CustomType, Dynamic, Engine, EvalAltResult, NativeCallContext, Position, TypeBuilder};
 use std::collections::HashMap;
 use vibelang_sfz::SfzInstrumentHandle;
 
 use super::context::{self, SourceLocation};
+use super::group::GroupHandle;
+use super::helpers::Decibels;
 use super::midi::MidiDevice;
-use super::require_handle;
+use super::sequence::Fx;
+use super::vst::VstInstrument;
+use super::{check_quota, require_handle};
+
+/// Longest pre-roll a voice can request, in milliseconds.
+pub const MAX_PRE_ROLL_MS: f64 = 500.0;
+
+/// Polyphony of drum kit voices, shared by all their pads.
+const DRUM_KIT_POLYPHONY: i64 = 16;
 
 /// A Voice builder for creating and configuring voices.
 #[derive(Debug, Clone, CustomType)]
@@ -32,6 +46,8 @@ pub struct Voice {
     soloed: bool,
     /// SFZ instrument ID (if using SFZ).
     sfz_instrument: Option<String>,
+    /// VST instrument ID (if playing a VST instrument).
+    vst_instrument: Option<String>,
     /// Source location where this voice was defined.
     source_location: SourceLocation,
     /// MIDI output device ID (if routing to external MIDI hardware).
@@ -40,6 +56,26 @@ pub struct Voice {
     midi_channel: Option<u8>,
     /// CC mappings: parameter_name -> CC number.
     cc_mappings: HashMap<String, u8>,
+    /// Priority when the CPU budget is exceeded.
+    priority: i64,
+    /// Loaded sample this voice plays (if using a sample).
+    sample_id: Option<String>,
+    /// Whether the sample is transposed to the session key.
+    match_key: bool,
+    /// Onset offset of the voice's sample in milliseconds (if using a sample).
+    sample_onset_ms: Option<f64>,
+    /// How far ahead of the beat events are sent, in milliseconds.
+    pre_roll_ms: f64,
+    /// Timing feel of the voice's events.
+    feel: Option<TimingFeel>,
+    /// Note priority in mono mode (`None` = polyphonic).
+    mono: Option<NotePriority>,
+    /// Glide time between notes in mono mode, in milliseconds.
+    glide_ms: f64,
+    /// Bus the voice writes to instead of its group's bus.
+    output_bus: Option<i64>,
+    /// Pads of a drum kit voice.
+    drum_kit: Option<DrumKit>,
 }
 
 impl Voice {
@@ -61,10 +97,21 @@ impl Voice {
             muted: false,
             soloed: false,
             sfz_instrument: None,
+            vst_instrument: None,
             source_location,
             midi_output_device_id: None,
             midi_channel: None,
             cc_mappings: HashMap::new(),
+            priority: 0,
+            sample_id: None,
+            match_key: false,
+            sample_onset_ms: None,
+            pre_roll_ms: 0.0,
+            feel: None,
+            mono: None,
+            glide_ms: 0.0,
+            output_bus: None,
+            drum_kit: None,
         }
     }
 
@@ -133,6 +180,9 @@ impl Voice {
     /// Set the sound source (synthdef name).
     pub fn on(mut self, source: String) -> Self {
         self.synth_name = Some(source);
+        self.vst_instrument = None;
+        self.sample_id = None;
+        self.sample_onset_ms = None;
         self.sync_state();
         self
     }
@@ -140,8 +190,22 @@ impl Voice {
     /// Set the sound source to an SFZ instrument.
     pub fn on_sfz(mut self, sfz: SfzInstrumentHandle) -> Self {
         self.sfz_instrument = Some(sfz.id.clone());
+        self.vst_instrument = None;
         // Use sfz_voice synthdef for SFZ playback
         self.synth_name = Some("sfz_voice".to_string());
+        self.sample_id = None;
+        self.sample_onset_ms = None;
+        self.sync_state();
+        self
+    }
+
+    /// Play a VST instrument; notes are sent to the plugin as MIDI.
+    pub fn on_vst(mut self, instrument: VstInstrument) -> Self {
+        self.vst_instrument = Some(instrument.id);
+        self.synth_name = None;
+        self.sfz_instrument = None;
+        self.sample_id = None;
+        self.sample_onset_ms = None;
         self.sync_state();
         self
     }
@@ -163,6 +227,8 @@ impl Voice {
         );
 
         let num_channels = sample.num_channels();
+        self.sample_id = Some(sample.sample_id().to_string());
+        self.sample_onset_ms = Some(sample.onset_ms());
 
         if sample.warp_mode {
             // Use warp_voice synthdef for time-stretching/pitch-shifting
@@ -242,6 +308,89 @@ impl Voice {
         self
     }
 
+    /// Add a pad playing a sample file to a drum kit voice.
+    ///
+    /// The sample is loaded as `"<kit>.<pad>"`. See [`Voice::pad_with`] for
+    /// the pad's note and step character.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let kit = drumkit("kit").pad("kick", "kick01.wav").pad("snare", "snare01.wav");
+    /// pattern("beat").on(kit).step("k.s.k.s.");
+    /// ```
+    pub fn pad(self, name: String, path: String) -> Result<Self, Box<EvalAltResult>> {
+        self.pad_with(name, path, rhai::Map::new())
+    }
+
+    /// Add a pad playing a sample file, with options:
+    /// `note` (MIDI note, default the General MIDI note of drum names or the
+    /// next free note from 60), `step` (pattern character, default the first
+    /// free letter of the name), `gain` (linear or `-6.db`), `pitch`
+    /// (semitones) and `choke` (group number; pads of a group cut each other off).
+    ///
+    /// # Example
+    /// ```rhai
+    /// drumkit("kit")
+    ///     .pad("hat", "hat.wav", #{ choke: 1 })
+    ///     .pad("open", "open_hat.wav", #{ note: 46, step: "q", gain: -3.db, choke: 1 });
+    /// ```
+    pub fn pad_with(self, name: String, path: String, options: rhai::Map) -> Result<Self, Box<EvalAltResult>> {
+        let sample = super::sample::sample(format!("{}.{}", self.name, name), path)?;
+        self.pad_sample_with(name, sample, options)
+    }
+
+    /// Add a pad playing a loaded sample (or slice) to a drum kit voice.
+    pub fn pad_sample(self, name: String, sample: super::sample::SampleHandle) -> Result<Self, Box<EvalAltResult>> {
+        self.pad_sample_with(name, sample, rhai::Map::new())
+    }
+
+    /// Add a pad playing a loaded sample, with options (see [`Voice::pad_with`]).
+    pub fn pad_sample_with(
+        mut self,
+        name: String,
+        sample: super::sample::SampleHandle,
+        options: rhai::Map,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let mut kit = self.drum_kit.take().unwrap_or_default();
+        let mut pad = DrumPad {
+            note: kit.default_note(&name),
+            step: kit.default_step(&name),
+            sample_id: sample.sample_id().to_string(),
+            start_frame: sample.get_start_frame(),
+            end_frame: sample.get_end_frame(),
+            rate: sample.rate as f32,
+            gain: sample.amp as f32,
+            pitch: 0.0,
+            choke: None,
+            name,
+        };
+        for (key, value) in &options {
+            let number = value.as_float().ok().or_else(|| value.as_int().ok().map(|v| v as f64));
+            match (key.as_str(), number) {
+                ("note", Some(note)) if (0.0..=127.0).contains(&note) => pad.note = note as u8,
+                ("gain", Some(gain)) => pad.gain *= gain as f32,
+                ("gain", None) if value.is::<Decibels>() => pad.gain *= value.clone_cast::<Decibels>().amp() as f32,
+                ("pitch", Some(pitch)) => pad.pitch = pitch as f32,
+                ("choke", Some(group)) => pad.choke = Some(group as i64),
+                ("step", None) => {
+                    let text = value.clone().into_string().unwrap_or_default();
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(step), None) if !crate::drumkit::is_reserved_step(step) => {
+                            pad.step = Some(step.to_ascii_lowercase())
+                        }
+                        _ => return Err(format!("pad '{}': step must be one character other than x, o, digits and rests, got \"{}\"", pad.name, text).into()),
+                    }
+                }
+                _ => return Err(format!("pad '{}': invalid option {}: {}", pad.name, key, value).into()),
+            }
+        }
+        kit.add(pad);
+        self.drum_kit = Some(kit);
+        self.sync_state();
+        Ok(self)
+    }
+
     /// Set the sound source to a MIDI output device.
     ///
     /// When a voice is routed to a MIDI output device, note and parameter
@@ -308,9 +457,147 @@ impl Voice {
         self
     }
 
-    /// Set the polyphony.
+    /// Set the polyphony (a mono voice becomes polyphonic again).
     pub fn poly(mut self, count: i64) -> Self {
         self.polyphony = count;
+        self.mono = None;
+        self.sync_state();
+        self
+    }
+
+    /// Play one note at a time on a single synth, like a monosynth.
+    ///
+    /// A note starting while another is held glides the running synth to
+    /// the note `priority` picks: "last", "low" or "high". Notes that touch
+    /// count as held, so melodies play legato. Gateless triggers and SFZ or
+    /// MIDI voices play as usual.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let bass = voice("bass").synth("acid").mono("last").glide_ms(60);
+    /// ```
+    pub fn mono(mut self, priority: String) -> Self {
+        let priority = NotePriority::parse(&priority).unwrap_or_else(|| {
+            log::warn!("[VOICE] Unknown note priority '{}' on '{}', using last", priority, self.name);
+            NotePriority::Last
+        });
+        self.mono = Some(priority);
+        self.sync_state();
+        self
+    }
+
+    /// Play one note at a time with last note priority.
+    pub fn mono_last(self) -> Self {
+        self.mono("last".to_string())
+    }
+
+    /// Set how long a mono voice takes to glide to a new note, in milliseconds.
+    ///
+    /// Gliding needs a synthdef with a `freq` parameter; without a glide the
+    /// pitch jumps.
+    pub fn glide_ms(mut self, ms: f64) -> Self {
+        self.glide_ms = ms.max(0.0);
+        self.sync_state();
+        self
+    }
+
+    /// Set the glide time (integer overload).
+    pub fn glide_ms_int(self, ms: i64) -> Self {
+        self.glide_ms(ms as f64)
+    }
+
+    /// Mono mode as sent to the runtime.
+    fn mono_mode(&self) -> Option<MonoMode> {
+        self.mono.map(|priority| MonoMode {
+            priority,
+            glide_ms: self.glide_ms,
+        })
+    }
+
+    /// Set the priority used when the CPU budget is exceeded.
+    ///
+    /// Voices in lower priority tiers are dropped first; the highest tier
+    /// keeps playing.
+    pub fn priority(mut self, value: i64) -> Self {
+        self.priority = value;
+        self.sync_state();
+        self
+    }
+
+    /// Transpose the voice's sample to the session key (see `set_key`).
+    ///
+    /// The sample's key is detected when it is loaded; the playback rate (or
+    /// warp pitch) is shifted by the nearest interval that moves it onto the
+    /// session key, or its relative major/minor. Unpitched samples play as-is.
+    pub fn match_key(mut self) -> Self {
+        self.match_key = true;
+        self.sync_state();
+        self
+    }
+
+    /// Send this voice's events early, in milliseconds (up to 500).
+    ///
+    /// Compensates synthdefs that sound late next to drums, such as pads with
+    /// a slow attack or samples with silence at the start.
+    pub fn pre_roll_ms(mut self, ms: f64) -> Self {
+        self.pre_roll_ms = ms.clamp(0.0, MAX_PRE_ROLL_MS);
+        self.sync_state();
+        self
+    }
+
+    /// Send this voice's events early (integer overload).
+    pub fn pre_roll_ms_int(self, ms: i64) -> Self {
+        self.pre_roll_ms(ms as f64)
+    }
+
+    /// Set the pre-roll to the sample's measured onset offset.
+    ///
+    /// Only sample voices have a measured onset; other voices keep their pre-roll.
+    pub fn auto_pre_roll(self) -> Self {
+        match self.sample_onset_ms {
+            Some(onset) => self.pre_roll_ms(onset),
+            None => {
+                log::warn!("[VOICE] auto_pre_roll() on '{}' needs a sample voice", self.name);
+                self
+            }
+        }
+    }
+
+    /// Play off the grid like a player in a band: `offset_ms` late (negative
+    /// rushes) with `jitter_ms` of normally distributed variation.
+    ///
+    /// Overrides the voice's feel in the applied groove template.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let bass = voice("bass").synth("acid").feel(10, 3);   // drags 10ms
+    /// let hats = voice("hats").synth("hihat").feel(-4, 1);  // rushes 4ms
+    /// ```
+    pub fn feel(mut self, offset_ms: f64, jitter_ms: f64) -> Self {
+        self.feel = Some(TimingFeel::new(offset_ms, jitter_ms));
+        self.sync_state();
+        self
+    }
+
+    /// Play off the grid (integer overload).
+    pub fn feel_int(self, offset_ms: i64, jitter_ms: i64) -> Self {
+        self.feel(offset_ms as f64, jitter_ms as f64)
+    }
+
+    /// Play off the grid with a jitter distribution ("normal" or "uniform").
+    pub fn feel_with(mut self, offset_ms: f64, jitter_ms: f64, distribution: String) -> Self {
+        let distribution = JitterDistribution::parse(&distribution).unwrap_or_else(|| {
+            log::warn!("[VOICE] Unknown jitter distribution '{}' on '{}', using normal", distribution, self.name);
+            JitterDistribution::Normal
+        });
+        self.feel = Some(TimingFeel::new(offset_ms, jitter_ms).with_distribution(distribution));
+        self.sync_state();
+        self
+    }
+
+    /// Play on the grid again (or as the groove template says).
+    pub fn clear_feel(mut self) -> Self {
+        self.feel = None;
         self.sync_state();
         self
     }
@@ -322,6 +609,11 @@ impl Voice {
         self
     }
 
+    /// Set the gain in decibels (`gain(-6.db)`).
+    pub fn gain_db(self, level: Decibels) -> Self {
+        self.gain(level.amp())
+    }
+
     /// Set a parameter.
     pub fn set_param(mut self, param: String, value: f64) -> Self {
         self.params.insert(param, value);
@@ -329,6 +621,86 @@ impl Voice {
         self
     }
 
+    /// Set several parameters at once.
+    ///
+    /// A voice the runtime already knows gets them as one update, applied
+    /// to its sounding synths in a single bundle; non-numeric values are
+    /// skipped with a warning.
+    ///
+    /// # Example
+    /// ```rhai
+    /// bass.set_params(#{ cutoff: 800, res: 0.4 });
+    /// ```
+    pub fn set_params(mut self, params: rhai::Map) -> Self {
+        let values = param_map(&params, &self.name);
+        self.apply_params(values);
+        self
+    }
+
+    /// Move the parameters with a declared range randomly by up to a fifth
+    /// of their range (see [`crate::sound_design`]).
+    pub fn randomize_params(self) -> Self {
+        self.randomize_params_by(crate::sound_design::DEFAULT_RANDOMIZE_AMOUNT)
+    }
+
+    /// Move the parameters with a declared range randomly by up to `amount`
+    /// (0..1) of their range.
+    pub fn randomize_params_by(self, amount: f64) -> Self {
+        self.randomize_params_only(amount, rhai::Array::new())
+    }
+
+    /// Randomize only the listed parameters.
+    ///
+    /// # Example
+    /// ```rhai
+    /// bass.randomize_params(0.2, ["cutoff", "decay"]);
+    /// ```
+    pub fn randomize_params_only(mut self, amount: f64, only: rhai::Array) -> Self {
+        let Some(synth) = self.synth_name.clone() else {
+            log::warn!("[VOICE] randomize_params() on '{}' needs a synth", self.name);
+            return self;
+        };
+        let only: Vec<String> = only.into_iter().map(|param| param.to_string()).collect();
+        let current = self.params.iter().map(|(k, v)| (k.clone(), *v as f32)).collect();
+        let values = crate::sound_design::randomize(&synth, &current, amount, &only);
+        if values.is_empty() {
+            log::warn!("[VOICE] '{}' has no parameters with a declared range to randomize", self.name);
+            return self;
+        }
+        self.apply_params(values);
+        self
+    }
+
+    /// Set the parameters to a point between two parameter maps: `t` = 0 is
+    /// `from`, 1 is `to`. Parameters with a declared range move along its curve.
+    ///
+    /// # Example
+    /// ```rhai
+    /// pad.morph(#{ cutoff: 300, res: 0.2 }, #{ cutoff: 4000, res: 0.7 }, 0.5);
+    /// ```
+    pub fn morph(mut self, from: rhai::Map, to: rhai::Map, t: f64) -> Self {
+        let from = param_map(&from, &self.name);
+        let to = param_map(&to, &self.name);
+        let values = crate::sound_design::morph(self.synth_name.as_deref(), &from, &to, t);
+        self.apply_params(values);
+        self
+    }
+
+    /// Set parameters in one update: a voice the runtime already knows gets
+    /// them as one message, a new one is registered with them.
+    fn apply_params(&mut self, values: std::collections::HashMap<String, f32>) {
+        self.params.extend(values.iter().map(|(param, value)| (param.clone(), *value as f64)));
+        let handle = require_handle();
+        if handle.with_state(|state| state.voices.contains_key(&self.name)) {
+            let _ = handle.send(StateMessage::SetVoiceParams {
+                name: self.name.clone(),
+                params: values,
+            });
+        } else {
+            self.sync_state();
+        }
+    }
+
     /// Mute the voice.
     pub fn mute(mut self) -> Self {
         self.muted = true;
@@ -343,9 +715,43 @@ impl Voice {
         self
     }
 
-    /// Set the output bus.
-    pub fn set_output_bus(self, _bus: i64) -> Self {
-        // TODO: Implement output bus routing
+    /// Send the voice straight to a bus, bypassing its group's effects and
+    /// fader.
+    ///
+    /// The first buses are the hardware outputs, so `.output(4)` feeds
+    /// outputs 5/6 of the audio interface (a stereo synth writes two
+    /// adjacent buses), e.g. into an external analog processing chain. A
+    /// negative bus routes the voice through its group again.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let bass = voice("bass").synth("acid").output(4);
+    /// ```
+    pub fn output(mut self, bus: i64) -> Self {
+        self.output_bus = (bus >= 0).then_some(bus);
+        self.sync_state();
+        self
+    }
+
+    /// Set the output bus (same as `output`).
+    pub fn set_output_bus(self, bus: i64) -> Self {
+        self.output(bus)
+    }
+
+    /// Insert an effect on this voice alone.
+    ///
+    /// The voice gets a private bus that its inserts process in the order
+    /// they are added, before the signal joins its group (or `.output()`
+    /// bus) and the group's effects.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let lead = voice("lead").synth("saw_lead")
+    ///     .insert(fx("lead_verb").synth("reverb").param("room", 0.8));
+    /// ```
+    pub fn insert(self, effect: Fx) -> Self {
+        self.sync_state();
+        effect.insert_on(&self.name, &self.group_path);
         self
     }
 
@@ -368,8 +774,50 @@ impl Voice {
         self
     }
 
+    /// Play a hardware input through this voice, like `SoundIn.ar(channel)`:
+    /// 0 is the first input of the audio interface.
+    ///
+    /// The input runs right away, through the voice's inserts, gain, pan
+    /// (`.set_param("pan", ...)`) and mute, into its group and the group's
+    /// effects.
+    ///
+    /// # Example
+    /// ```rhai
+    /// let gtr = voice("gtr").input(0).gain(db(-3))
+    ///     .insert(fx("gtr_drive").synth("overdrive"));
+    /// ```
+    pub fn input(self, channel: i64) -> Self {
+        self.play_input(channel, 1)
+    }
+
+    /// Play a stereo pair of hardware inputs, `channel` and the one after
+    /// it, through this voice (see [`input`](Self::input)).
+    pub fn stereo_input(self, channel: i64) -> Self {
+        self.play_input(channel, 2)
+    }
+
+    fn play_input(mut self, channel: i64, channels: u32) -> Self {
+        self.synth_name = Some(crate::input_voice::input_synthdef(channels).to_string());
+        self.sfz_instrument = None;
+        self.vst_instrument = None;
+        self.sample_id = None;
+        self.sample_onset_ms = None;
+        self.params.insert("input".to_string(), channel.max(0) as f64);
+        self.run()
+    }
+
     // === Actions ===
 
+    /// Pads of a drum kit voice.
+    pub(crate) fn drum_kit(&self) -> Option<&DrumKit> {
+        self.drum_kit.as_ref()
+    }
+
+    /// Sample to key-match, if `.match_key()` was called on a sample voice.
+    fn key_match(&self) -> Option<String> {
+        self.sample_id.clone().filter(|_| self.match_key)
+    }
+
     /// Sync this voice's state with the runtime.
     fn sync_state(&self) {
         let handle = require_handle();
@@ -390,14 +838,20 @@ impl Voice {
             gain: self.gain,
             muted: self.muted,
             soloed: self.soloed,
-            output_bus: None,
+            output_bus: self.output_bus,
             params,
             sfz_instrument: self.sfz_instrument.clone(),
-            vst_instrument: None,
+            vst_instrument: self.vst_instrument.clone(),
             source_location: self.source_location.clone(),
             midi_output_device_id: self.midi_output_device_id,
             midi_channel: self.midi_channel,
             cc_mappings: self.cc_mappings.clone(),
+            priority: self.priority,
+            key_match: self.key_match(),
+            pre_roll_ms: self.pre_roll_ms,
+            feel: self.feel,
+            mono: self.mono_mode(),
+            drum_kit: self.drum_kit.clone(),
         });
     }
 
@@ -422,14 +876,20 @@ impl Voice {
             gain: self.gain,
             muted: self.muted,
             soloed: self.soloed,
-            output_bus: None,
+            output_bus: self.output_bus,
             params,
             sfz_instrument: self.sfz_instrument.clone(),
-            vst_instrument: None,
+            vst_instrument: self.vst_instrument.clone(),
             source_location: self.source_location.clone(),
             midi_output_device_id: self.midi_output_device_id,
             midi_channel: self.midi_channel,
             cc_mappings: self.cc_mappings.clone(),
+            priority: self.priority,
+            key_match: self.key_match(),
+            pre_roll_ms: self.pre_roll_ms,
+            feel: self.feel,
+            mono: self.mono_mode(),
+            drum_kit: self.drum_kit.clone(),
         });
 
         self
@@ -437,8 +897,123 @@ impl Voice {
 }
 
 /// Create a new voice builder with source location tracking.
-pub fn voice(ctx: NativeCallContext, name: String) -> Voice {
-    Voice::new(ctx, name)
+pub fn voice(ctx: NativeCallContext, name: String) -> Result<Voice, Box<EvalAltResult>> {
+    let name = context::namespaced(&name);
+    check_quota(QuotaKind::Voices, &name)?;
+    Ok(Voice::new(ctx, name))
+}
+
+/// Create a drum kit voice: one voice playing a sample per pad (`.pad()`),
+/// picked by the note or pattern step character of each hit.
+///
+/// # Example
+/// ```rhai
+/// let kit = drumkit("drums")
+///     .pad("kick", "kick01.wav")
+///     .pad("snare", "snare01.wav", #{ gain: 0.8 })
+///     .pad("hat", "hat.wav", #{ choke: 1 })
+///     .pad("open hat", "open_hat.wav", #{ choke: 1 });
+/// // "p" is the open hat ("o" stays an accented hit); uppercase accents a pad
+/// pattern("beat").on(kit).step("k.h.s.h.K.h.s.p.");
+/// ```
+pub fn drumkit(ctx: NativeCallContext, name: String) -> Result<Voice, Box<EvalAltResult>> {
+    let mut kit = voice(ctx, name)?;
+    kit.synth_name = Some(crate::drumkit::synthdef(1).to_string());
+    kit.polyphony = DRUM_KIT_POLYPHONY;
+    kit.params = HashMap::from([
+        ("attack".to_string(), 0.001),
+        ("sustain".to_string(), 1.0),
+        ("release".to_string(), 0.01),
+        ("loop".to_string(), 0.0),
+    ]);
+    kit.drum_kit = Some(DrumKit::default());
+    Ok(kit)
+}
+
+/// Resample a group: record `bars` bars of its output from the next bar line,
+/// slice the recording at its onsets and play the slices as the pads of a
+/// drum kit voice named after the group (`"drums_resample"`). Slice 1 plays
+/// on note 60 and step `a`, slice 2 on note 61 and step `b`, and so on, once
+/// the recording has been chopped. `"main"` resamples the master.
+///
+/// # Example
+/// ```rhai
+/// let chops = resample("drums", 2.bars);
+/// pattern("chopped").on(chops).step("a.c.b.a.d.c.b.e.");
+/// ```
+pub fn resample(ctx: NativeCallContext, group: String, bars: i64) -> Result<Voice, Box<EvalAltResult>> {
+    resample_group(ctx, super::group::group(group), bars)
+}
+
+/// Resample a group for a time span, rounded up to whole bars.
+pub fn resample_span(ctx: NativeCallContext, group: String, span: TimeSpan) -> Result<Voice, Box<EvalAltResult>> {
+    resample_group(ctx, super::group::group(group), span_bars(span))
+}
+
+/// Resample a group handle (see [`resample`]).
+pub fn resample_group(ctx: NativeCallContext, group: GroupHandle, bars: i64) -> Result<Voice, Box<EvalAltResult>> {
+    let group_path = group.path().to_string();
+    let bars = bars.clamp(1, crate::resample::MAX_RESAMPLE_BARS as i64) as u32;
+    let mut kit = drumkit(ctx, crate::resample::resample_voice_name(&group_path))?;
+
+    // An unchanged resample keeps its chops across reloads
+    let handle = require_handle();
+    let chops = handle.with_state(|state| {
+        state
+            .resamples
+            .get(&kit.name)
+            .filter(|r| r.group_path == group_path && r.bars == bars)
+            .and_then(|_| state.voices.get(&kit.name)?.drum_kit.clone())
+    });
+    if chops.is_some() {
+        kit.drum_kit = chops;
+    }
+    kit.sync_state();
+
+    let _ = handle.send(StateMessage::Resample {
+        name: kit.name.clone(),
+        group_path,
+        bars,
+    });
+    Ok(kit)
+}
+
+/// Resample a group handle for a time span, rounded up to whole bars.
+pub fn resample_group_span(ctx: NativeCallContext, group: GroupHandle, span: TimeSpan) -> Result<Voice, Box<EvalAltResult>> {
+    resample_group(ctx, group, span_bars(span))
+}
+
+/// Whole bars covering a time span.
+fn span_bars(span: TimeSpan) -> i64 {
+    let beats_per_bar = super::helpers::current_time_signature().beats_per_bar();
+    (super::helpers::span_beats(span) / beats_per_bar - 1e-9).ceil() as i64
+}
+
+/// Numeric entries of a Rhai map as parameter values; others are skipped
+/// with a warning.
+fn param_map(params: &rhai::Map, voice: &str) -> std::collections::HashMap<String, f32> {
+    let mut values = std::collections::HashMap::new();
+    for (param, value) in params {
+        match value.as_float().ok().or_else(|| value.as_int().ok().map(|v| v as f64)) {
+            Some(value) => {
+                values.insert(param.to_string(), value as f32);
+            }
+            None => log::warn!("[VOICE] Ignoring non-numeric value for param '{}' on '{}'", param, voice),
+        }
+    }
+    values
+}
+
+/// Values between two parameter maps (`t` = 0 is `a`, 1 is `b`), for the
+/// parameters both set.
+///
+/// # Example
+/// ```rhai
+/// let halfway = morph(#{ cutoff: 300, amp: 0.2 }, #{ cutoff: 4000, amp: 0.6 }, 0.5);
+/// ```
+pub fn morph_params(a: rhai::Map, b: rhai::Map, t: f64) -> rhai::Map {
+    let values = crate::sound_design::morph(None, &param_map(&a, "morph"), &param_map(&b, "morph"), t);
+    values.into_iter().map(|(param, value)| (param.into(), rhai::Dynamic::from(value as f64))).collect()
 }
 
 /// Trigger a voice with parameters.
@@ -459,6 +1034,7 @@ pub fn voice_trigger(voice: &mut Voice, params: rhai::Map) {
         synth_name: voice.synth_name.clone(),
         group_path: Some(voice.group_path.clone()),
         params: param_vec,
+        quantize: false,
     });
 }
 
@@ -470,14 +1046,31 @@ pub fn voice_trigger_no_params(voice: &mut Voice) {
         synth_name: voice.synth_name.clone(),
         group_path: Some(voice.group_path.clone()),
         params: Vec::new(),
+        quantize: false,
     });
 }
 
-/// Stop all sounds from a voice.
+/// Stop all sounds from a voice, letting notes ring out through their release.
 pub fn voice_stop(voice: &mut Voice) {
+    send_stop_voice(voice, None, false);
+}
+
+/// Stop all sounds from a voice, fading them out over `seconds`.
+pub fn voice_stop_fade(voice: &mut Voice, seconds: f64) {
+    send_stop_voice(voice, Some(seconds), false);
+}
+
+/// Stop all sounds from a voice right away, cutting off their release.
+pub fn voice_choke(voice: &mut Voice) {
+    send_stop_voice(voice, None, true);
+}
+
+fn send_stop_voice(voice: &Voice, fade: Option<f64>, choke: bool) {
     let handle = require_handle();
     let _ = handle.send(StateMessage::StopVoice {
         name: voice.name.clone(),
+        fade,
+        choke,
     });
 }
 
@@ -540,16 +1133,24 @@ pub fn voice_note_off(voice: &mut Voice, note: i64) {
     });
 }
 
-/// Send control change.
-pub fn voice_control_change(voice: &mut Voice, cc: i64, value: f64) {
+/// Send control change with a MIDI value (0-127).
+///
+/// MIDI output and VST voices receive the CC; other voices apply the MIDI
+/// CC routes of the controller.
+pub fn voice_control_change(voice: &mut Voice, cc: i64, value: i64) {
     let handle = require_handle();
     let _ = handle.send(StateMessage::ControlChange {
         voice_name: voice.name.clone(),
-        cc_num: cc as u8,
-        value: (value * 127.0) as u8,
+        cc_num: cc.clamp(0, 127) as u8,
+        value: value.clamp(0, 127) as u8,
     });
 }
 
+/// Send control change with a normalized value (0.0-1.0).
+pub fn voice_control_change_float(voice: &mut Voice, cc: i64, value: f64) {
+    voice_control_change(voice, cc, (value.clamp(0.0, 1.0) * 127.0).round() as i64)
+}
+
 /// Register voice API with the Rhai engine.
 pub fn register(engine: &mut Engine) {
     // Register Voice type
@@ -557,6 +1158,11 @@ pub fn register(engine: &mut Engine) {
 
     // Constructor
     engine.register_fn("voice", voice);
+    engine.register_fn("drumkit", drumkit);
+    engine.register_fn("resample", resample);
+    engine.register_fn("resample", resample_span);
+    engine.register_fn("resample", resample_group);
+    engine.register_fn("resample", resample_group_span);
 
     // Getters
     engine.register_fn("id", Voice::id);
@@ -580,23 +1186,55 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("synth", Voice::synth);
     engine.register_fn("on", Voice::on);
     engine.register_fn("on", Voice::on_sfz);     // SFZ overload
+    engine.register_fn("on", Voice::on_vst);     // VST overload
     engine.register_fn("on", Voice::on_sample);  // Sample overload
     engine.register_fn("on", Voice::on_midi);    // MIDI output overload
+    engine.register_fn("pad", Voice::pad);
+    engine.register_fn("pad", Voice::pad_with);
+    engine.register_fn("pad", Voice::pad_sample);
+    engine.register_fn("pad", Voice::pad_sample_with);
     engine.register_fn("channel", Voice::channel);
     engine.register_fn("cc", Voice::cc);
     engine.register_fn("poly", Voice::poly);
+    engine.register_fn("mono", Voice::mono);
+    engine.register_fn("mono", Voice::mono_last);
+    engine.register_fn("glide_ms", Voice::glide_ms);
+    engine.register_fn("glide_ms", Voice::glide_ms_int);
+    engine.register_fn("priority", Voice::priority);
+    engine.register_fn("match_key", Voice::match_key);
+    engine.register_fn("pre_roll_ms", Voice::pre_roll_ms);
+    engine.register_fn("pre_roll_ms", Voice::pre_roll_ms_int);
+    engine.register_fn("auto_pre_roll", Voice::auto_pre_roll);
+    engine.register_fn("feel", Voice::feel);
+    engine.register_fn("feel", Voice::feel_int);
+    engine.register_fn("feel", Voice::feel_with);
+    engine.register_fn("clear_feel", Voice::clear_feel);
     engine.register_fn("gain", Voice::gain);
+    engine.register_fn("gain", Voice::gain_db);
     engine.register_fn("set_param", Voice::set_param);
+    engine.register_fn("set_params", Voice::set_params);
+    engine.register_fn("randomize_params", Voice::randomize_params);
+    engine.register_fn("randomize_params", Voice::randomize_params_by);
+    engine.register_fn("randomize_params", Voice::randomize_params_only);
+    engine.register_fn("morph", Voice::morph);
+    engine.register_fn("morph", morph_params);
     engine.register_fn("mute", Voice::mute);
     engine.register_fn("solo", Voice::solo);
+    engine.register_fn("output", Voice::output);
     engine.register_fn("set_output_bus", Voice::set_output_bus);
+    engine.register_fn("insert", Voice::insert);
 
     // Actions
     engine.register_fn("apply", Voice::apply);
     engine.register_fn("run", Voice::run);
+    engine.register_fn("input", Voice::input);
+    engine.register_fn("stereo_input", Voice::stereo_input);
     engine.register_fn("trigger", voice_trigger);
     engine.register_fn("trigger", voice_trigger_no_params);
     engine.register_fn("stop", voice_stop);
+    engine.register_fn("stop", voice_stop_fade);
+    engine.register_fn("stop", |voice: &mut Voice, seconds: i64| voice_stop_fade(voice, seconds as f64));
+    engine.register_fn("choke", voice_choke);
     engine.register_fn("stop_all", voice_stop_all);
     engine.register_fn("note_on", voice_note_on);
     engine.register_fn("note_on", voice_note_on_int);
@@ -605,4 +1243,38 @@ pub fn register(engine: &mut Engine) {
     engine.register_fn("note_on", voice_note_on_float_vel);
     engine.register_fn("note_off", voice_note_off);
     engine.register_fn("control_change", voice_control_change);
+    engine.register_fn("control_change", voice_control_change_float);
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_set_params_applies_a_map_in_one_update() {
+        let mut sim = crate::runtime::Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        let engine = crate::api::create_engine();
+        let mut scope = rhai::Scope::new();
+        engine
+            .run_with_scope(&mut scope, r#"let bass = voice("bass").synth("acid").set_param("cutoff", 200.0);"#)
+            .unwrap();
+        sim.advance(0.0);
+        let before = sim.handle().with_state(|state| state.version);
+
+        engine
+            .run_with_scope(&mut scope, r#"bass.set_params(#{ cutoff: 800, res: 0.4, mode: "lp" });"#)
+            .unwrap();
+        sim.advance(0.0);
+        sim.handle().with_state(|state| {
+            let params = &state.voices["bass"].params;
+            assert_eq!(params["cutoff"], 800.0);
+            assert_eq!(params["res"], 0.4);
+            assert!(!params.contains_key("mode"));
+            assert_eq!(state.version, before + 1);
+        });
+
+        // A voice the runtime doesn't know yet is created with the params
+        engine.run(r#"voice("lead").set_params(#{ amp: 0.5 });"#).unwrap();
+        sim.advance(0.0);
+        sim.handle().with_state(|state| assert_eq!(state.voices["lead"].params["amp"], 0.5));
+    }
 }
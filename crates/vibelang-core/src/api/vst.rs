@@ -0,0 +1,108 @@
+//! VST plugin API for VibeLang scripts.
+//!
+//! Instruments are loaded into the current group and played by voices;
+//! effects are inserted into the current group's chain like any other fx.
+//! Plugins are hosted by SuperCollider's VSTPlugin extension (see
+//! [`crate::vst`]).
+//!
+//! ```rhai
+//! let pad = load_vst_instrument("pad", "Surge XT").param("Filter 1 Cutoff", 0.4);
+//! let keys = voice("keys").on(pad);
+//! load_vst_effect("room", "ValhallaRoom");
+//! fx("plate").vst("ValhallaPlate").param("Mix", 0.3).apply();
+//! ```
+
+use crate::state::StateMessage;
+use rhai::{CustomType, Engine, NativeCallContext, TypeBuilder};
+
+use super::sequence::Fx;
+use super::{context, require_handle};
+
+/// A VST instrument loaded into a group.
+#[derive(Debug, Clone, CustomType)]
+pub struct VstInstrument {
+    /// Instrument ID.
+    pub id: String,
+    /// Plugin path or name.
+    pub plugin: String,
+}
+
+impl VstInstrument {
+    /// Set a plugin parameter by name (chainable).
+    pub fn param(self, name: String, value: f64) -> Self {
+        let _ = require_handle().send(StateMessage::SetVstParamByName {
+            instrument_id: self.id.clone(),
+            param_name: name,
+            value: value as f32,
+        });
+        self
+    }
+
+    /// Set a plugin parameter by index (chainable).
+    pub fn param_index(self, index: i64, value: f64) -> Self {
+        let _ = require_handle().send(StateMessage::SetVstParam {
+            instrument_id: self.id.clone(),
+            param_index: index as i32,
+            value: value as f32,
+        });
+        self
+    }
+
+    /// Start a note right away.
+    pub fn note_on(&mut self, note: i64, velocity: i64) {
+        let _ = require_handle().send(StateMessage::VstNoteOn {
+            instrument_id: self.id.clone(),
+            note: note.clamp(0, 127) as u8,
+            velocity: velocity.clamp(0, 127) as u8,
+        });
+    }
+
+    /// Release a note right away.
+    pub fn note_off(&mut self, note: i64) {
+        let _ = require_handle().send(StateMessage::VstNoteOff {
+            instrument_id: self.id.clone(),
+            note: note.clamp(0, 127) as u8,
+        });
+    }
+}
+
+/// Plugin files resolve like other assets; anything else is a plugin name
+/// for VSTPlugin's search.
+pub(crate) fn resolve_plugin(plugin: &str) -> String {
+    context::resolve_file(plugin)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|| plugin.to_string())
+}
+
+/// Load a VST instrument into the current group.
+///
+/// Loading the same ID again replaces the plugin, or moves it to another group.
+pub fn load_vst_instrument(id: String, plugin: String) -> VstInstrument {
+    let plugin = resolve_plugin(&plugin);
+    let _ = require_handle().send(StateMessage::LoadVstInstrument {
+        id: id.clone(),
+        plugin_key: plugin.clone(),
+        group_path: context::current_group_path(),
+    });
+    VstInstrument { id, plugin }
+}
+
+/// Insert a VST effect into the current group.
+pub fn load_vst_effect(ctx: NativeCallContext, id: String, plugin: String) {
+    Fx::new(ctx, id).vst(plugin).apply();
+}
+
+/// Register the VST API with a Rhai engine.
+pub fn register(engine: &mut Engine) {
+    engine.build_type::<VstInstrument>();
+
+    engine.register_fn("load_vst_instrument", load_vst_instrument);
+    engine.register_fn("load_vst_effect", load_vst_effect);
+
+    engine.register_fn("param", VstInstrument::param);
+    engine.register_fn("param_index", VstInstrument::param_index);
+    engine.register_fn("note_on", VstInstrument::note_on);
+    engine.register_fn("note_off", VstInstrument::note_off);
+    engine.register_get("id", |v: &mut VstInstrument| v.id.clone());
+    engine.register_get("plugin", |v: &mut VstInstrument| v.plugin.clone());
+}
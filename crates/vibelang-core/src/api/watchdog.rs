@@ -0,0 +1,126 @@
+//! Eval watchdog for the script engine.
+//!
+//! An accidental `while true {}` would otherwise block the thread that
+//! evaluates scripts forever: hot reloads, `/eval` requests and MIDI
+//! callbacks all run there. The watchdog uses Rhai's progress callback to
+//! cancel any single evaluation that runs too long or too many operations.
+//! Only the evaluation is cancelled — the runtime and scheduler keep playing.
+
+use rhai::{Dynamic, Engine, EvalAltResult};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Prefix of the message passed to `ErrorTerminated` by the watchdog.
+const WATCHDOG_PREFIX: &str = "evaluation cancelled by the watchdog";
+
+/// Limits applied to every evaluation of an engine.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalLimits {
+    /// Maximum Rhai operations per evaluation (0 = unlimited).
+    pub max_operations: u64,
+    /// Maximum wall-clock time per evaluation (`None` = unlimited).
+    pub max_eval_time: Option<Duration>,
+}
+
+impl Default for EvalLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 0,
+            max_eval_time: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+thread_local! {
+    /// Start time and last operation count of the running evaluation.
+    static CURRENT: Cell<Option<(Instant, u64)>> = const { Cell::new(None) };
+}
+
+/// Install the watchdog on an engine.
+///
+/// The operation count restarts with every evaluation (`run_ast`, `eval`,
+/// `FnPtr::call`, ...), which is how the start of a new evaluation is
+/// detected — no guard is needed around the call sites.
+pub fn install(engine: &mut Engine, limits: &EvalLimits) {
+    engine.set_max_operations(limits.max_operations);
+
+    let Some(max_time) = limits.max_eval_time else {
+        return;
+    };
+
+    engine.on_progress(move |ops| {
+        CURRENT.with(|current| {
+            let now = Instant::now();
+            let started = match current.get() {
+                Some((started, last)) if ops > last => started,
+                _ => now,
+            };
+            current.set(Some((started, ops)));
+
+            (now.duration_since(started) > max_time)
+                .then(|| Dynamic::from(format!("{} after {:?}", WATCHDOG_PREFIX, max_time)))
+        })
+    });
+}
+
+/// Describe an evaluation error, spelling out watchdog cancellations.
+///
+/// Other errors are formatted as usual.
+pub fn explain(err: &EvalAltResult) -> String {
+    match err.unwrap_inner() {
+        EvalAltResult::ErrorTerminated(value, pos) if is_marker(value) => {
+            format!("{} ({}); the runtime keeps playing", value, pos)
+        }
+        EvalAltResult::ErrorTooManyOperations(pos) => format!(
+            "{} at the operation limit ({}); the runtime keeps playing",
+            WATCHDOG_PREFIX, pos
+        ),
+        _ => err.to_string(),
+    }
+}
+
+/// Whether an error is a watchdog cancellation.
+pub fn is_cancelled(err: &EvalAltResult) -> bool {
+    match err.unwrap_inner() {
+        EvalAltResult::ErrorTerminated(value, _) => is_marker(value),
+        EvalAltResult::ErrorTooManyOperations(_) => true,
+        _ => false,
+    }
+}
+
+fn is_marker(value: &Dynamic) -> bool {
+    value
+        .read_lock::<rhai::ImmutableString>()
+        .is_some_and(|s| s.starts_with(WATCHDOG_PREFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_cancels_runaway_loops() {
+        let limits = EvalLimits {
+            max_operations: 0,
+            max_eval_time: Some(Duration::from_millis(50)),
+        };
+        let mut engine = Engine::new();
+        install(&mut engine, &limits);
+
+        let err = engine.run("fn spin() { while true {} } spin();").unwrap_err();
+        assert!(is_cancelled(&err));
+        assert!(explain(&err).contains("watchdog after 50ms"));
+
+        // The next evaluation starts with a fresh budget
+        assert_eq!(engine.eval::<i64>("40 + 2").unwrap(), 42);
+
+        let limits = EvalLimits {
+            max_operations: 1_000,
+            max_eval_time: None,
+        };
+        install(&mut engine, &limits);
+        let err = engine.run("loop {}").unwrap_err();
+        assert!(explain(&err).contains("operation limit"));
+        assert!(!is_cancelled(&engine.run("throw 1").unwrap_err()));
+    }
+}
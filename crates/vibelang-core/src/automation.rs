@@ -0,0 +1,266 @@
+//! Automation recorded from live parameter tweaks.
+//!
+//! While automation recording is on, every parameter change that reaches
+//! the runtime from outside — HTTP, the TUI, a MIDI CC route — is written
+//! with its transport beat into an automation lane of the parameter's
+//! voice, group, effect, pattern or melody. A lane is as long as the
+//! recording (a number of bars) and replays on every pass from then on, so
+//! a filter sweep performed once keeps sweeping in the arrangement.
+//!
+//! A lane is a list of steps: each point holds its value until the next
+//! one. Tweaking a parameter again in a later recording pass replaces the
+//! points it passes over and keeps the rest ("touch" recording). Lanes are
+//! saved with the session file.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::events::FadeTargetType;
+
+/// Bars recorded when no length is given.
+pub const DEFAULT_AUTOMATION_BARS: u32 = 4;
+
+/// Maximum number of bars of an automation lane.
+pub const MAX_AUTOMATION_BARS: u32 = 64;
+
+/// Name of a target type, as used in lane keys and session files.
+pub fn target_type_name(target_type: &FadeTargetType) -> &'static str {
+    match target_type {
+        FadeTargetType::Group => "group",
+        FadeTargetType::Voice => "voice",
+        FadeTargetType::Pattern => "pattern",
+        FadeTargetType::Melody => "melody",
+        FadeTargetType::Effect => "effect",
+    }
+}
+
+/// Parse a target type name ("group", "voice", "pattern", "melody" or "effect").
+pub fn parse_target_type(name: &str) -> Option<FadeTargetType> {
+    Some(match name {
+        "group" => FadeTargetType::Group,
+        "voice" => FadeTargetType::Voice,
+        "pattern" => FadeTargetType::Pattern,
+        "melody" => FadeTargetType::Melody,
+        "effect" => FadeTargetType::Effect,
+        _ => return None,
+    })
+}
+
+/// Key of the lane of a parameter, e.g. `voice:lead:cutoff`.
+pub fn lane_key(target_type: &FadeTargetType, target: &str, param: &str) -> String {
+    format!("{}:{}:{}", target_type_name(target_type), target, param)
+}
+
+/// A recorded value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutomationPoint {
+    /// Position in the lane, in beats from its start.
+    pub beat: f64,
+    pub value: f32,
+}
+
+/// The recorded values of one parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutomationLane {
+    /// Kind of entity the parameter belongs to.
+    pub target_type: FadeTargetType,
+    /// Entity name (group path, voice, pattern, melody or effect id).
+    pub target: String,
+    /// Parameter name.
+    pub param: String,
+    /// Length of the lane; it repeats from transport beat 0.
+    pub length_beats: f64,
+    /// Points ordered by position.
+    pub points: Vec<AutomationPoint>,
+}
+
+impl AutomationLane {
+    /// Create an empty lane.
+    pub fn new(target_type: FadeTargetType, target: impl Into<String>, param: impl Into<String>, length_beats: f64) -> Self {
+        Self {
+            target_type,
+            target: target.into(),
+            param: param.into(),
+            length_beats: length_beats.max(1.0),
+            points: Vec::new(),
+        }
+    }
+
+    /// Key of the lane in [`Automation::lanes`].
+    pub fn key(&self) -> String {
+        lane_key(&self.target_type, &self.target, &self.param)
+    }
+
+    /// Position of a transport beat in the lane.
+    pub fn position(&self, beat: f64) -> f64 {
+        beat.rem_euclid(self.length_beats)
+    }
+
+    /// Record `value` at transport beat `beat`.
+    ///
+    /// `since` is the transport beat of the point recorded into the lane
+    /// just before, in the same recording: the points passed over since
+    /// then are replaced. Otherwise only a point at the same position is.
+    pub fn record(&mut self, beat: f64, value: f32, since: Option<f64>) {
+        let position = self.position(beat);
+        match since.map(|since| (since, beat - since)) {
+            Some((_, span)) if span >= self.length_beats => self.points.clear(),
+            Some((since, span)) if span > 0.0 => {
+                let from = self.position(since);
+                let length = self.length_beats;
+                self.points.retain(|p| {
+                    let distance = (p.beat - from).rem_euclid(length);
+                    distance == 0.0 || distance > span
+                });
+            }
+            _ => {}
+        }
+        self.points.retain(|p| (p.beat - position).abs() > 1e-9);
+        let index = self.points.partition_point(|p| p.beat < position);
+        self.points.insert(index, AutomationPoint { beat: position, value });
+    }
+
+    /// Value at a transport beat: that of the last point at or before its
+    /// position, or of the lane's last point before the first one.
+    pub fn value_at(&self, beat: f64) -> Option<f32> {
+        let position = self.position(beat);
+        self.points
+            .iter()
+            .rev()
+            .find(|p| p.beat <= position)
+            .or_else(|| self.points.last())
+            .map(|p| p.value)
+    }
+
+    /// Value of the last point passed moving from transport beat `from`
+    /// (exclusive) to `to` (inclusive); `None` if no point was passed.
+    pub fn value_passed(&self, from: f64, to: f64) -> Option<f32> {
+        let span = to - from;
+        if span <= 0.0 {
+            return None;
+        }
+        if span >= self.length_beats {
+            return self.value_at(to);
+        }
+        let start = self.position(from);
+        self.points
+            .iter()
+            .map(|p| ((p.beat - start).rem_euclid(self.length_beats), p.value))
+            .filter(|(distance, _)| *distance > 0.0 && *distance <= span)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, value)| value)
+    }
+}
+
+/// A recording in progress.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AutomationRecording {
+    /// Length of the lanes recorded.
+    pub length_beats: f64,
+    /// Transport beat of the last point recorded into each lane so far.
+    pub touched: HashMap<String, f64>,
+}
+
+/// The automation lanes of a session and the recording in progress, if any.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Automation {
+    /// Lanes by [`lane_key`].
+    pub lanes: BTreeMap<String, AutomationLane>,
+    /// Recording in progress.
+    pub recording: Option<AutomationRecording>,
+}
+
+impl Automation {
+    /// Start recording lanes of `length_beats`.
+    pub fn start_recording(&mut self, length_beats: f64) {
+        self.recording = Some(AutomationRecording {
+            length_beats: length_beats.max(1.0),
+            touched: HashMap::new(),
+        });
+    }
+
+    /// Stop recording; returns the number of lanes recorded into.
+    pub fn stop_recording(&mut self) -> usize {
+        self.recording.take().map_or(0, |r| r.touched.len())
+    }
+
+    /// Record a parameter change at transport beat `beat`, if recording.
+    ///
+    /// A lane of another length than the recording's starts over.
+    pub fn record(&mut self, target_type: FadeTargetType, target: &str, param: &str, beat: f64, value: f32) -> bool {
+        let Some(recording) = self.recording.as_mut() else {
+            return false;
+        };
+        let key = lane_key(&target_type, target, param);
+        let lane = self
+            .lanes
+            .entry(key.clone())
+            .or_insert_with(|| AutomationLane::new(target_type.clone(), target, param, recording.length_beats));
+        if lane.length_beats != recording.length_beats {
+            *lane = AutomationLane::new(target_type, target, param, recording.length_beats);
+        }
+        lane.record(beat, value, recording.touched.get(&key).copied());
+        recording.touched.insert(key, beat);
+        true
+    }
+
+    /// Lanes that replay: all but those being recorded into.
+    pub fn replaying(&self) -> impl Iterator<Item = &AutomationLane> {
+        self.lanes
+            .iter()
+            .filter(|(key, _)| !self.recording.as_ref().is_some_and(|r| r.touched.contains_key(*key)))
+            .map(|(_, lane)| lane)
+    }
+
+    /// Remove the lanes of an entity (all lanes with `None`); returns how many.
+    pub fn clear(&mut self, target: Option<(&FadeTargetType, &str)>) -> usize {
+        let before = self.lanes.len();
+        self.lanes.retain(|_, lane| match target {
+            Some((target_type, name)) => lane.target_type != *target_type || lane.target != name,
+            None => false,
+        });
+        before - self.lanes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_automation_records_and_replays_per_pass() {
+        let mut automation = Automation::default();
+        assert!(!automation.record(FadeTargetType::Voice, "lead", "cutoff", 1.0, 400.0));
+
+        // A 4-beat lane recorded in the second pass
+        automation.start_recording(4.0);
+        for (beat, value) in [(5.0, 400.0), (6.0, 800.0), (7.5, 1200.0)] {
+            assert!(automation.record(FadeTargetType::Voice, "lead", "cutoff", beat, value));
+        }
+        assert_eq!(automation.replaying().count(), 0);
+        assert_eq!(automation.stop_recording(), 1);
+
+        let lane = &automation.lanes["voice:lead:cutoff"];
+        assert_eq!(lane.points.iter().map(|p| p.beat).collect::<Vec<_>>(), vec![1.0, 2.0, 3.5]);
+        assert_eq!(lane.value_at(8.5), Some(1200.0));
+        assert_eq!(lane.value_at(10.0), Some(800.0));
+        assert_eq!(lane.value_passed(9.5, 10.5), Some(800.0));
+        assert_eq!(lane.value_passed(10.5, 10.9), None);
+        // Across the loop end
+        assert_eq!(lane.value_passed(11.0, 13.2), Some(400.0));
+
+        // Touch recording replaces the points passed over, keeps the rest
+        automation.start_recording(4.0);
+        automation.record(FadeTargetType::Voice, "lead", "cutoff", 12.5, 300.0);
+        automation.record(FadeTargetType::Voice, "lead", "cutoff", 14.0, 500.0);
+        automation.stop_recording();
+        let lane = &automation.lanes["voice:lead:cutoff"];
+        assert_eq!(
+            lane.points.iter().map(|p| (p.beat, p.value)).collect::<Vec<_>>(),
+            vec![(0.5, 300.0), (2.0, 500.0), (3.5, 1200.0)]
+        );
+
+        assert_eq!(automation.clear(Some((&FadeTargetType::Group, "lead"))), 0);
+        assert_eq!(automation.clear(Some((&FadeTargetType::Voice, "lead"))), 1);
+        assert_eq!(parse_target_type(target_type_name(&FadeTargetType::Effect)), Some(FadeTargetType::Effect));
+    }
+}
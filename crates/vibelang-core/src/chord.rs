@@ -0,0 +1,423 @@
+//! Chords and the arpeggiator.
+//!
+//! A [`Chord`] is a root note and a quality ("maj7", "m9", ...), played in
+//! an inversion and a [`Voicing`]. Melodies take chords as `"C4:maj7"` in
+//! their note strings or as `chord("C4", "maj7").inversion(1)` objects.
+//!
+//! An [`Arpeggiator`] plays the notes a melody holds one after another
+//! (`.arp("updown", "1/16")`). The melody keeps its chords; they are
+//! expanded into single notes when the melody is scheduled, in beats, so
+//! arpeggios follow tempo changes and stop at the end of the held chord
+//! and of the loop.
+
+use std::fmt;
+
+use crate::events::{BeatEvent, Pattern};
+
+/// Shortest arpeggiator step in beats.
+const MIN_ARP_RATE: f64 = 1.0 / 64.0;
+
+/// Most steps an arpeggiator plays for one held chord.
+const MAX_ARP_STEPS: usize = 4096;
+
+/// Semitones of a chord quality above its root, e.g. `[0, 4, 7, 11]` for "maj7".
+pub fn quality_intervals(quality: &str) -> Option<&'static [i8]> {
+    Some(match quality.to_lowercase().as_str() {
+        // Triads
+        "maj" | "major" => &[0, 4, 7],
+        "min" | "m" | "minor" => &[0, 3, 7],
+        "dim" | "diminished" => &[0, 3, 6],
+        "aug" | "augmented" => &[0, 4, 8],
+        "sus2" => &[0, 2, 7],
+        "sus4" => &[0, 5, 7],
+
+        // Seventh chords
+        "maj7" | "major7" => &[0, 4, 7, 11],
+        "7" | "dom7" => &[0, 4, 7, 10],
+        "min7" | "m7" => &[0, 3, 7, 10],
+        "dim7" => &[0, 3, 6, 9],
+        "m7b5" | "half-dim" => &[0, 3, 6, 10],
+        "mmaj7" | "minmaj7" => &[0, 3, 7, 11],
+
+        // Extended
+        "9" => &[0, 4, 7, 10, 14],
+        "maj9" => &[0, 4, 7, 11, 14],
+        "m9" | "min9" => &[0, 3, 7, 10, 14],
+        "add9" => &[0, 4, 7, 14],
+        "6" => &[0, 4, 7, 9],
+        "m6" | "min6" => &[0, 3, 7, 9],
+
+        // Power chord
+        "5" | "power" => &[0, 7],
+
+        _ => return None,
+    })
+}
+
+/// How a chord's notes are spread over octaves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Voicing {
+    /// All notes within an octave.
+    #[default]
+    Close,
+    /// The second-highest note dropped an octave.
+    Drop2,
+    /// The third-highest note dropped an octave.
+    Drop3,
+    /// Every other note from the bottom raised an octave.
+    Spread,
+}
+
+impl Voicing {
+    /// Parse a voicing name: "close", "drop2", "drop3" or "spread" ("open").
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.trim().to_lowercase().replace([' ', '-', '_'], "").as_str() {
+            "close" => Voicing::Close,
+            "drop2" => Voicing::Drop2,
+            "drop3" => Voicing::Drop3,
+            "spread" | "open" => Voicing::Spread,
+            _ => return None,
+        })
+    }
+
+    /// Name of the voicing, as accepted by [`parse`](Self::parse).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Voicing::Close => "close",
+            Voicing::Drop2 => "drop2",
+            Voicing::Drop3 => "drop3",
+            Voicing::Spread => "spread",
+        }
+    }
+
+    /// Apply the voicing to notes sorted from low to high.
+    fn apply(&self, notes: &mut [i16]) {
+        let len = notes.len();
+        match self {
+            Voicing::Close => {}
+            Voicing::Drop2 if len >= 2 => notes[len - 2] -= 12,
+            Voicing::Drop3 if len >= 3 => notes[len - 3] -= 12,
+            Voicing::Spread => notes.iter_mut().skip(1).step_by(2).for_each(|note| *note += 12),
+            _ => {}
+        }
+    }
+}
+
+/// A chord: a root note, a quality, an inversion and a voicing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chord {
+    /// MIDI note of the root.
+    pub root: u8,
+    /// Quality as written, e.g. "maj7".
+    pub quality: String,
+    /// Lowest notes moved up an octave (negative: highest notes moved down).
+    pub inversion: i32,
+    pub voicing: Voicing,
+}
+
+impl Chord {
+    /// A chord in root position and close voicing; `None` for unknown qualities.
+    pub fn new(root: u8, quality: &str) -> Option<Self> {
+        quality_intervals(quality)?;
+        Some(Self {
+            root: root.min(127),
+            quality: quality.to_string(),
+            inversion: 0,
+            voicing: Voicing::Close,
+        })
+    }
+
+    /// Parse a chord as written by its `Display` form: "C4:maj7", optionally
+    /// followed by an inversion and a voicing ("C4:maj7 inv1 drop2").
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let (root, quality) = words.next()?.split_once(':')?;
+        let mut chord = Self::new(crate::scale::parse_root_note(root), quality)?;
+        for word in words {
+            match word.strip_prefix("inv") {
+                Some(inversion) => chord.inversion = inversion.parse().ok()?,
+                None => chord.voicing = Voicing::parse(word)?,
+            }
+        }
+        Some(chord)
+    }
+
+    /// The chord in an inversion: 1 puts the lowest note on top, 2 the two
+    /// lowest, and so on; -1 puts the highest note at the bottom.
+    pub fn with_inversion(mut self, inversion: i32) -> Self {
+        self.inversion = inversion;
+        self
+    }
+
+    /// The chord in a voicing.
+    pub fn with_voicing(mut self, voicing: Voicing) -> Self {
+        self.voicing = voicing;
+        self
+    }
+
+    /// MIDI notes of the chord from low to high; notes outside the MIDI range are left out.
+    pub fn notes(&self) -> Vec<u8> {
+        let intervals = quality_intervals(&self.quality).unwrap_or(&[0]);
+        let mut notes: Vec<i16> = intervals.iter().map(|&i| self.root as i16 + i as i16).collect();
+        for _ in 0..self.inversion.unsigned_abs().min(64) {
+            if self.inversion > 0 {
+                notes[0] += 12;
+            } else {
+                let last = notes.len() - 1;
+                notes[last] -= 12;
+            }
+            notes.sort_unstable();
+        }
+        self.voicing.apply(&mut notes);
+        notes.sort_unstable();
+        notes.into_iter().filter(|n| (0..=127).contains(n)).map(|n| n as u8).collect()
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", crate::pitch::note_name(self.root), self.quality)?;
+        if self.inversion != 0 {
+            write!(f, " inv{}", self.inversion)?;
+        }
+        if self.voicing != Voicing::Close {
+            write!(f, " {}", self.voicing.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// Order an arpeggiator plays held notes in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArpMode {
+    Up,
+    Down,
+    /// Up, then down, without repeating the top and bottom notes.
+    UpDown,
+    /// Down, then up, without repeating the bottom and top notes.
+    DownUp,
+    /// In the order the notes were written.
+    Played,
+    /// A random note each step; the same ones on every pass of the loop.
+    Random,
+}
+
+impl ArpMode {
+    /// Parse a mode name such as "up", "updown" or "random".
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.trim().to_lowercase().replace([' ', '-', '_'], "").as_str() {
+            "up" => ArpMode::Up,
+            "down" => ArpMode::Down,
+            "updown" | "pingpong" => ArpMode::UpDown,
+            "downup" => ArpMode::DownUp,
+            "played" | "order" => ArpMode::Played,
+            "random" => ArpMode::Random,
+            _ => return None,
+        })
+    }
+
+    /// Name of the mode, as accepted by [`parse`](Self::parse).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArpMode::Up => "up",
+            ArpMode::Down => "down",
+            ArpMode::UpDown => "updown",
+            ArpMode::DownUp => "downup",
+            ArpMode::Played => "played",
+            ArpMode::Random => "random",
+        }
+    }
+}
+
+/// Plays the notes a melody holds one after another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arpeggiator {
+    pub mode: ArpMode,
+    /// Length of a step in beats (0.25 for sixteenth notes).
+    pub rate: f64,
+    /// Octaves the held notes are played over, from 1.
+    pub octaves: u32,
+}
+
+impl Arpeggiator {
+    /// Create an arpeggiator; the rate is at least a 1/64 beat.
+    pub fn new(mode: ArpMode, rate: f64, octaves: u32) -> Self {
+        Self {
+            mode,
+            rate: rate.max(MIN_ARP_RATE),
+            octaves: octaves.clamp(1, 8),
+        }
+    }
+
+    /// Parse an arpeggiator as written by its `Display` form, e.g. "updown 0.25 2".
+    pub fn parse_text(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let mode = ArpMode::parse(words.next()?)?;
+        let rate = words.next()?.parse().ok()?;
+        let octaves = words.next().map_or(Some(1), |o| o.parse().ok())?;
+        Some(Self::new(mode, rate, octaves))
+    }
+
+    /// The pattern with the notes that start together played one after
+    /// another, each step `rate` beats long, for as long as they are held
+    /// (their longest `gate`) and no further than the end of the loop.
+    ///
+    /// Events without a pitch pass through unchanged.
+    pub fn expand(&self, pattern: &Pattern) -> Pattern {
+        let mut held: Vec<&BeatEvent> = Vec::new();
+        let mut events = Vec::new();
+        for event in &pattern.events {
+            if freq(event).is_some() {
+                held.push(event);
+            } else {
+                events.push(event.clone());
+            }
+        }
+        held.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+
+        for chord in held.chunk_by(|a, b| (a.beat - b.beat).abs() < 1e-9) {
+            let start = chord[0].beat;
+            let held_for = chord.iter().filter_map(|e| gate(e)).fold(0.0, f64::max).max(self.rate);
+            let end = (start + held_for).min(pattern.loop_length_beats);
+            let notes = self.notes(chord);
+            let order = self.order(notes.len(), start);
+
+            for (step, &index) in order.iter().cycle().take(MAX_ARP_STEPS).enumerate() {
+                let beat = start + step as f64 * self.rate;
+                if beat >= end - 1e-9 {
+                    break;
+                }
+                let mut event = notes[index].clone();
+                event.beat = beat;
+                for (name, value) in event.controls.iter_mut() {
+                    if name == "gate" {
+                        *value = self.rate.min(end - beat) as f32;
+                    }
+                }
+                events.push(event);
+            }
+        }
+
+        Pattern {
+            name: pattern.name.clone(),
+            events,
+            loop_length_beats: pattern.loop_length_beats,
+            phase_offset: pattern.phase_offset,
+        }
+    }
+
+    /// The notes of a chord over the arpeggiator's octaves: in the order
+    /// written for [`ArpMode::Played`], otherwise from low to high.
+    fn notes(&self, chord: &[&BeatEvent]) -> Vec<BeatEvent> {
+        let mut base: Vec<BeatEvent> = chord.iter().map(|e| (*e).clone()).collect();
+        if self.mode != ArpMode::Played {
+            base.sort_by(|a, b| freq(a).unwrap_or(0.0).total_cmp(&freq(b).unwrap_or(0.0)));
+        }
+        (0..self.octaves)
+            .flat_map(|octave| {
+                base.iter().map(move |event| {
+                    let mut event = event.clone();
+                    event.transpose(12 * octave as i32);
+                    event
+                })
+            })
+            .collect()
+    }
+
+    /// Indices into `len` notes for one pass of the mode; random passes are
+    /// seeded with the chord's beat so every loop plays the same ones.
+    fn order(&self, len: usize, beat: f64) -> Vec<usize> {
+        let up: Vec<usize> = (0..len).collect();
+        let down: Vec<usize> = (0..len).rev().collect();
+        match self.mode {
+            ArpMode::Up | ArpMode::Played => up,
+            ArpMode::Down => down,
+            ArpMode::UpDown => up.iter().chain(down.iter().skip(1).take(len.saturating_sub(2))).copied().collect(),
+            ArpMode::DownUp => down.iter().chain(up.iter().skip(1).take(len.saturating_sub(2))).copied().collect(),
+            ArpMode::Random => {
+                let mut seed = beat.to_bits() ^ 0x9E37_79B9_7F4A_7C15;
+                (0..len.max(1) * 4)
+                    .map(|_| {
+                        // xorshift64
+                        seed ^= seed << 13;
+                        seed ^= seed >> 7;
+                        seed ^= seed << 17;
+                        (seed % len as u64) as usize
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Arpeggiator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.mode.as_str(), self.rate, self.octaves)
+    }
+}
+
+fn freq(event: &BeatEvent) -> Option<f64> {
+    event.controls.iter().find(|(k, _)| k == "freq").map(|(_, v)| *v as f64)
+}
+
+fn gate(event: &BeatEvent) -> Option<f64> {
+    event.controls.iter().find(|(k, _)| k == "gate").map(|(_, v)| *v as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(beat: f64, note: u8, gate: f64) -> BeatEvent {
+        BeatEvent::new(beat, "melody_note")
+            .with_control("freq", crate::pitch::note_to_freq(note as f64) as f32)
+            .with_control("gate", gate as f32)
+    }
+
+    fn notes(pattern: &Pattern) -> Vec<(f64, u8)> {
+        let mut notes: Vec<(f64, u8)> = pattern
+            .events
+            .iter()
+            .map(|e| (e.beat, crate::pitch::freq_to_midi_note(freq(e).unwrap())))
+            .collect();
+        notes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        notes
+    }
+
+    #[test]
+    fn test_chord_inversions_and_voicings() {
+        let chord = Chord::parse("C4:maj7").unwrap();
+        assert_eq!(chord.notes(), vec![60, 64, 67, 71]);
+        assert_eq!(chord.clone().with_inversion(1).notes(), vec![64, 67, 71, 72]);
+        assert_eq!(chord.clone().with_inversion(-1).notes(), vec![59, 60, 64, 67]);
+        assert_eq!(chord.clone().with_voicing(Voicing::Drop2).notes(), vec![55, 60, 64, 71]);
+        assert_eq!(chord.clone().with_voicing(Voicing::Spread).notes(), vec![60, 67, 76, 83]);
+
+        let voiced = chord.with_inversion(2).with_voicing(Voicing::Drop3);
+        assert_eq!(Chord::parse(&voiced.to_string()), Some(voiced));
+        assert_eq!(Chord::parse("C4:nope"), None);
+    }
+
+    #[test]
+    fn test_arpeggiator_expands_held_chords() {
+        // A C major triad held for one beat, then a single E held for half a beat
+        let pattern = Pattern {
+            name: "arp".to_string(),
+            events: vec![note(0.0, 64, 1.0), note(0.0, 60, 1.0), note(0.0, 67, 1.0), note(1.0, 76, 0.5)],
+            loop_length_beats: 1.75,
+            phase_offset: 0.0,
+        };
+
+        let up = Arpeggiator::new(ArpMode::UpDown, 0.25, 1).expand(&pattern);
+        assert_eq!(notes(&up), vec![(0.0, 60), (0.25, 64), (0.5, 67), (0.75, 64), (1.0, 76), (1.25, 76)]);
+        assert!(up.events.iter().all(|e| gate(e) == Some(0.25)));
+
+        // Two octaves, played order; the held E is cut at the loop end
+        let played = Arpeggiator::new(ArpMode::Played, 0.5, 2).expand(&Pattern { loop_length_beats: 1.25, ..pattern });
+        assert_eq!(notes(&played), vec![(0.0, 64), (0.5, 60), (1.0, 76)]);
+        assert_eq!(gate(played.events.last().unwrap()), Some(0.25));
+
+        let arp = Arpeggiator::parse_text("downup 0.125 2").unwrap();
+        assert_eq!(Arpeggiator::parse_text(&arp.to_string()), Some(arp));
+    }
+}
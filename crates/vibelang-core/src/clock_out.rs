@@ -0,0 +1,205 @@
+//! Audio-rate clock output for modular synths.
+//!
+//! DC-coupled audio interfaces (Expert Sleepers ES-8/ES-9 and similar) pass
+//! output channels straight through as control voltage, so pulses written to a
+//! spare output clock a Eurorack system directly. A system synth derives the
+//! pulses from the transport tempo and can hold a run gate high on a second
+//! channel while the transport plays.
+//!
+//! The synth is (re)started with a timestamped bundle on the pulse grid, so
+//! its first pulse is sample-aligned with events scheduled on the same beat.
+//! It lives in its own group, and every change frees the group before starting
+//! a new synth, so at most one clock runs however the bundles interleave.
+
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the clock output synthdef.
+pub const CLOCK_OUT_SYNTHDEF: &str = "system_clock_out";
+
+/// Fixed node ID of the group holding the clock output synth (tail of root).
+pub const CLOCK_OUT_GROUP_ID: i32 = 4;
+
+/// Default pulses per quarter note (sixteenth notes).
+pub const DEFAULT_PPQN: u32 = 4;
+
+/// Highest supported pulses per quarter note.
+pub const MAX_PPQN: u32 = 96;
+
+/// Default pulse length in milliseconds.
+pub const DEFAULT_WIDTH_MS: f64 = 5.0;
+
+/// Clock output settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClockOutput {
+    /// Hardware output channel of the clock pulses (1-based).
+    pub channel: u32,
+    /// Pulses per quarter note.
+    pub ppqn: u32,
+    /// Pulse length in milliseconds.
+    pub width_ms: f64,
+    /// Pulse and gate level (1.0 = full scale).
+    pub level: f32,
+    /// Hardware output channel of the run gate (1-based), if any.
+    pub run_channel: Option<u32>,
+}
+
+impl ClockOutput {
+    /// Clock pulses on `channel` with default settings.
+    pub fn new(channel: u32) -> Self {
+        Self {
+            channel: channel.max(1),
+            ppqn: DEFAULT_PPQN,
+            width_ms: DEFAULT_WIDTH_MS,
+            level: 1.0,
+            run_channel: None,
+        }
+    }
+
+    /// Distance between two pulses in beats.
+    pub fn pulse_beats(&self) -> f64 {
+        1.0 / self.ppqn.clamp(1, MAX_PPQN) as f64
+    }
+
+    /// The first pulse at or after `beat`.
+    pub fn next_pulse_beat(&self, beat: f64) -> f64 {
+        let pulse = self.pulse_beats();
+        // Tolerate float noise on beats that are already on the grid
+        ((beat / pulse) - 1e-9).ceil().max(0.0) * pulse
+    }
+
+    /// Synth controls at the given tempo.
+    pub fn controls(&self, bpm: f64) -> Vec<(&'static str, f32)> {
+        let out = (self.channel.max(1) - 1) as f32;
+        // Without a run gate, write silence to the clock channel
+        let (run_out, run_level) = match self.run_channel {
+            Some(channel) => ((channel.max(1) - 1) as f32, self.level),
+            None => (out, 0.0),
+        };
+        vec![
+            ("out", out),
+            ("freq", (bpm / 60.0 / self.pulse_beats()) as f32),
+            ("width", (self.width_ms.max(0.0) / 1000.0) as f32),
+            ("level", self.level),
+            ("run_out", run_out),
+            ("run_level", run_level),
+        ]
+    }
+}
+
+/// Create the clock output synthdef.
+pub fn create_clock_out_synthdef() -> Option<(String, Vec<u8>)> {
+    match encode_synthdef(&clock_out_graph()) {
+        Ok(bytes) => Some((CLOCK_OUT_SYNTHDEF.to_string(), bytes)),
+        Err(e) => {
+            log::error!("[CLOCK OUT] Failed to encode clock output synthdef: {}", e);
+            None
+        }
+    }
+}
+
+/// Signal flow:
+///   Impulse.ar(freq) → Trig1.ar(width) * level → Out.ar(out)
+///   K2A.ar(run_level) → Out.ar(run_out)
+fn clock_out_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("out".to_string(), vec![0.0], None); // 0
+    builder.add_param("freq".to_string(), vec![8.0], None); // 1
+    builder.add_param("width".to_string(), vec![0.005], None); // 2
+    builder.add_param("level".to_string(), vec![1.0], None); // 3
+    builder.add_param("run_out".to_string(), vec![0.0], None); // 4
+    builder.add_param("run_level".to_string(), vec![0.0], None); // 5
+    builder.create_control_ugen();
+
+    // Impulse fires on its first sample, so the synth starts on a pulse
+    builder.add_constant(0.0);
+    let impulse = builder.add_node(
+        "Impulse".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 1), Input::Constant(0.0)],
+        1,
+        0,
+    );
+    let pulse = builder.add_node(
+        "Trig1".to_string(),
+        Rate::Audio,
+        vec![Input::node(impulse.0, 0), Input::node(0, 2)],
+        1,
+        0,
+    );
+    let scaled = builder.add_node(
+        "BinaryOpUGen".to_string(),
+        Rate::Audio,
+        vec![Input::node(pulse.0, 0), Input::node(0, 3)],
+        1,
+        2, // multiplication
+    );
+    builder.add_node(
+        "Out".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 0), Input::node(scaled.0, 0)],
+        0,
+        0,
+    );
+
+    let run_gate = builder.add_node("K2A".to_string(), Rate::Audio, vec![Input::node(0, 5)], 1, 0);
+    builder.add_node(
+        "Out".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 4), Input::node(run_gate.0, 0)],
+        0,
+        0,
+    );
+
+    GraphIR::from_builder(CLOCK_OUT_SYNTHDEF.to_string(), builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_output_grid_and_controls() {
+        let clock = ClockOutput {
+            run_channel: Some(4),
+            ..ClockOutput::new(3)
+        };
+        assert_eq!(clock.next_pulse_beat(0.0), 0.0);
+        assert_eq!(clock.next_pulse_beat(1.0), 1.0);
+        assert_eq!(clock.next_pulse_beat(1.1), 1.25);
+
+        let controls = clock.controls(120.0);
+        let control = |name: &str| controls.iter().find(|(k, _)| *k == name).unwrap().1;
+        assert_eq!(control("out"), 2.0);
+        assert_eq!(control("freq"), 8.0);
+        assert_eq!(control("width"), 0.005);
+        assert_eq!(control("run_out"), 3.0);
+        assert_eq!(control("run_level"), 1.0);
+
+        let no_gate = ClockOutput::new(1).controls(120.0);
+        assert!(no_gate.contains(&("run_level", 0.0)));
+    }
+
+    #[test]
+    fn test_clock_out_graph_writes_pulses_and_run_gate() {
+        // Parameter slots: out 0, freq 1, width 2, level 3, run_out 4, run_level 5
+        let ir = clock_out_graph();
+        assert!(ir.validate().is_ok());
+        let (impulse, pulses) = ir.ugens("Impulse").next().unwrap();
+        assert_eq!(pulses.inputs, vec![Input::node(0, 1), Input::Constant(0.0)]);
+        let (trig, pulse) = ir.ugens("Trig1").next().unwrap();
+        assert_eq!(pulse.inputs, vec![Input::node(impulse, 0), Input::node(0, 2)]);
+        let (scaled, level) = ir.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(level.inputs, vec![Input::node(trig, 0), Input::node(0, 3)]);
+        let (run_gate, _) = ir.ugens("K2A").next().unwrap();
+
+        let outs: Vec<_> = ir.ugens("Out").map(|(_, out)| out.inputs.clone()).collect();
+        assert_eq!(
+            outs,
+            vec![
+                vec![Input::node(0, 0), Input::node(scaled, 0)],
+                vec![Input::node(0, 4), Input::node(run_gate, 0)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Drum kits: one voice playing a sample per pad.
+//!
+//! A kit maps MIDI notes and pattern step characters to pads. Each pad plays
+//! its own sample with its own gain and pitch, and pads sharing a choke group
+//! cut each other off (an open hat silenced by the closed hat).
+//!
+//! Pads default to their General MIDI note when their name is a common drum
+//! name ("kick" plays on 36, "snare" on 38) and to the first free letter of
+//! their name as step character, so `pattern("beat").on(kit).step("k.s.k.s.")`
+//! works without any mapping.
+
+/// Step characters that keep their usual pattern meaning on kits: plain hits,
+/// velocity digits, rests and bar lines.
+const RESERVED_STEPS: &[char] = &['x', 'o', '.', '_', '-', '|', '~'];
+
+/// First note handed to pads without a General MIDI drum name.
+const FIRST_FREE_NOTE: u8 = 60;
+
+/// A pad of a drum kit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrumPad {
+    /// Pad name ("kick").
+    pub name: String,
+    /// MIDI note playing the pad.
+    pub note: u8,
+    /// Pattern step character playing the pad (lowercase; uppercase accents).
+    pub step: Option<char>,
+    /// Loaded sample the pad plays.
+    pub sample_id: String,
+    /// First frame played.
+    pub start_frame: i32,
+    /// Frame playback stops at (-1 for the end of the sample).
+    pub end_frame: i32,
+    /// Playback rate of the sample before the pad's pitch.
+    pub rate: f32,
+    /// Linear gain of the pad.
+    pub gain: f32,
+    /// Pitch of the pad in semitones.
+    pub pitch: f32,
+    /// Choke group; pads in the same group cut each other off.
+    pub choke: Option<i64>,
+}
+
+impl DrumPad {
+    /// Playback rate including the pad's pitch.
+    pub fn playback_rate(&self) -> f32 {
+        self.rate * 2f32.powf(self.pitch / 12.0)
+    }
+
+    /// Sample controls of a hit on this pad playing buffer `bufnum`.
+    pub fn controls(&self, bufnum: i32) -> Vec<(String, f32)> {
+        let mut controls = vec![
+            ("bufnum".to_string(), bufnum as f32),
+            ("rate".to_string(), self.playback_rate()),
+            ("startPos".to_string(), self.start_frame as f32),
+        ];
+        if self.end_frame > 0 {
+            controls.push(("endPos".to_string(), self.end_frame as f32));
+        }
+        controls
+    }
+}
+
+/// What a hit on a pad plays.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PadHit {
+    /// Sample playback synthdef.
+    pub synthdef: &'static str,
+    /// Sample controls (see [`DrumPad::controls`]).
+    pub controls: Vec<(String, f32)>,
+    /// Linear gain of the pad.
+    pub gain: f32,
+    /// Notes of the pads the hit cuts off.
+    pub chokes: Vec<u8>,
+}
+
+/// The pads of a drum kit voice, in the order they were added.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrumKit {
+    /// The kit's pads.
+    pub pads: Vec<DrumPad>,
+}
+
+impl DrumKit {
+    /// Add a pad, replacing the pad of the same name.
+    pub fn add(&mut self, pad: DrumPad) {
+        match self.pads.iter_mut().find(|existing| existing.name == pad.name) {
+            Some(existing) => *existing = pad,
+            None => self.pads.push(pad),
+        }
+    }
+
+    /// Pad played by a MIDI note.
+    pub fn pad_for_note(&self, note: u8) -> Option<&DrumPad> {
+        self.pads.iter().find(|pad| pad.note == note)
+    }
+
+    /// Pad played by a pattern step character, in either case.
+    pub fn pad_for_step(&self, step: char) -> Option<&DrumPad> {
+        let step = step.to_ascii_lowercase();
+        self.pads.iter().find(|pad| pad.step == Some(step))
+    }
+
+    /// A hit on `pad`, whose sample is loaded in buffer `bufnum`.
+    pub fn hit(&self, pad: &DrumPad, bufnum: i32, num_channels: i32) -> PadHit {
+        PadHit {
+            synthdef: synthdef(num_channels),
+            controls: pad.controls(bufnum),
+            gain: pad.gain,
+            chokes: self.choked_by(pad),
+        }
+    }
+
+    /// Notes of the other pads in `pad`'s choke group.
+    pub fn choked_by(&self, pad: &DrumPad) -> Vec<u8> {
+        let Some(group) = pad.choke else {
+            return Vec::new();
+        };
+        self.pads
+            .iter()
+            .filter(|other| other.choke == Some(group) && other.note != pad.note)
+            .map(|other| other.note)
+            .collect()
+    }
+
+    /// Note of a new pad named `name` (other than `name` itself): its
+    /// General MIDI note if it is a drum name and the note is free, otherwise
+    /// the first free note from 60.
+    pub fn default_note(&self, name: &str) -> u8 {
+        let taken = |note: u8| self.pads.iter().any(|pad| pad.name != name && pad.note == note);
+        gm_drum_note(name)
+            .filter(|note| !taken(*note))
+            .unwrap_or_else(|| (FIRST_FREE_NOTE..=127).find(|note| !taken(*note)).unwrap_or(127))
+    }
+
+    /// Step character of a new pad named `name`: the first letter of its
+    /// name no other pad uses, if any.
+    pub fn default_step(&self, name: &str) -> Option<char> {
+        name.chars()
+            .map(|c| c.to_ascii_lowercase())
+            .filter(|c| c.is_ascii_alphabetic())
+            .find(|c| !is_reserved_step(*c) && !self.pads.iter().any(|pad| pad.name != name && pad.step == Some(*c)))
+    }
+}
+
+/// Whether a step character already means something in patterns.
+pub fn is_reserved_step(step: char) -> bool {
+    step.is_ascii_digit() || step.is_whitespace() || RESERVED_STEPS.contains(&step.to_ascii_lowercase())
+}
+
+/// Sample playback synthdef for a buffer with `num_channels` channels.
+pub fn synthdef(num_channels: i32) -> &'static str {
+    if num_channels == 1 {
+        "sample_voice_mono"
+    } else {
+        "sample_voice_stereo"
+    }
+}
+
+/// General MIDI percussion note of a common drum name.
+fn gm_drum_note(name: &str) -> Option<u8> {
+    let name = name.to_ascii_lowercase().replace(['_', '-'], " ");
+    let note = match name.trim() {
+        "kick" | "bd" | "bass drum" => 36,
+        "rim" | "rimshot" | "rs" => 37,
+        "snare" | "sd" => 38,
+        "clap" | "cp" => 39,
+        "hat" | "hh" | "closed hat" | "chh" => 42,
+        "pedal hat" => 44,
+        "low tom" | "lt" => 45,
+        "open hat" | "oh" | "ohh" => 46,
+        "tom" | "mid tom" | "mt" => 47,
+        "crash" => 49,
+        "high tom" | "hi tom" | "ht" => 50,
+        "ride" => 51,
+        "tambourine" => 54,
+        "cowbell" | "cb" => 56,
+        "shaker" => 70,
+        _ => return None,
+    };
+    Some(note)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(kit: &DrumKit, name: &str, choke: Option<i64>) -> DrumPad {
+        DrumPad {
+            name: name.to_string(),
+            note: kit.default_note(name),
+            step: kit.default_step(name),
+            sample_id: format!("kit.{}", name),
+            start_frame: 0,
+            end_frame: -1,
+            rate: 1.0,
+            gain: 1.0,
+            pitch: 0.0,
+            choke,
+        }
+    }
+
+    #[test]
+    fn test_pads_default_to_gm_notes_and_name_letters_and_choke_their_group() {
+        let mut kit = DrumKit::default();
+        for (name, choke) in [("kick", None), ("snare", None), ("closed_hat", Some(1)), ("open hat", Some(1)), ("shaker", None), ("zap", None)] {
+            let pad = pad(&kit, name, choke);
+            kit.add(pad);
+        }
+        let notes: Vec<u8> = kit.pads.iter().map(|pad| pad.note).collect();
+        assert_eq!(notes, vec![36, 38, 42, 46, 70, 60]);
+        // "o" stays a plain accented hit, so the open hat takes "p"; the shaker's "s" is the snare's
+        let steps: Vec<Option<char>> = kit.pads.iter().map(|pad| pad.step).collect();
+        assert_eq!(steps, vec![Some('k'), Some('s'), Some('c'), Some('p'), Some('h'), Some('z')]);
+
+        assert_eq!(kit.pad_for_step('K').map(|pad| pad.note), Some(36));
+        assert_eq!(kit.pad_for_note(46).map(|pad| pad.name.as_str()), Some("open hat"));
+        assert!(kit.pad_for_step('x').is_none());
+        assert_eq!(kit.choked_by(kit.pad_for_note(42).unwrap()), vec![46]);
+        assert!(kit.choked_by(kit.pad_for_note(36).unwrap()).is_empty());
+
+        // Re-adding a pad replaces it in place; a taken GM note moves to the free range
+        let mut snare = pad(&kit, "snare", None);
+        snare.pitch = 12.0;
+        kit.add(snare);
+        assert_eq!(kit.pads.len(), 6);
+        assert_eq!(kit.pads[1].playback_rate(), 2.0);
+        assert_eq!(kit.default_note("sd"), 61);
+    }
+}
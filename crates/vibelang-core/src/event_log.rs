@@ -0,0 +1,293 @@
+//! Structured log of fired events.
+//!
+//! While an event log runs, every synth the scheduler starts is recorded
+//! with its beat, song time, voice, group, synthdef, source, node ID and the
+//! controls it was started with. The log is written as JSON or CSV (chosen
+//! by the file extension) when it stops, so a piece can be analyzed or
+//! visualized outside of vibelang. Scripts start it with `log_events(path)`;
+//! `vibe simulate --dump` writes the same log for a virtual-time run.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::events::BeatEvent;
+use crate::timing::TransportClock;
+
+/// File format of an event log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventLogFormat {
+    /// An array of event objects.
+    Json,
+    /// One row per event, one column per control.
+    Csv,
+}
+
+impl EventLogFormat {
+    /// CSV for `.csv` files, JSON otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => EventLogFormat::Csv,
+            _ => EventLogFormat::Json,
+        }
+    }
+}
+
+/// A fired event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedEvent {
+    /// Absolute beat the event fires at.
+    pub beat: f64,
+    /// Bar of the beat (1-based), in the time signature it fired in.
+    pub bar: i64,
+    /// Seconds of song time since the log started, following tempo changes.
+    pub seconds: f64,
+    pub voice: Option<String>,
+    pub group: Option<String>,
+    /// Synthdef the synth was started with.
+    pub synthdef: String,
+    pub pattern: Option<String>,
+    pub melody: Option<String>,
+    /// Node ID of the started synth.
+    pub node_id: i32,
+    /// Controls sent with `/s_new`, after voice and group params are merged in.
+    pub controls: Vec<(String, f32)>,
+}
+
+/// Events recorded since the log started.
+#[derive(Debug)]
+pub struct EventLog {
+    /// File the log is written to when it stops (`None` keeps it in memory).
+    path: Option<PathBuf>,
+    /// Last beat whose song time is known, and that time.
+    anchor: (f64, f64),
+    events: Vec<LoggedEvent>,
+}
+
+impl EventLog {
+    /// Start a log at `beat`, to be written to `path`.
+    pub fn new(path: Option<PathBuf>, beat: f64) -> Self {
+        Self {
+            path,
+            anchor: (beat, 0.0),
+            events: Vec::new(),
+        }
+    }
+
+    /// File the log is written to.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Record a synth started at `beat`, at the transport's current tempo.
+    pub fn record(
+        &mut self,
+        beat: f64,
+        transport: &TransportClock,
+        event: &BeatEvent,
+        synthdef: &str,
+        node_id: i32,
+        controls: &[(String, f32)],
+    ) {
+        let (anchor_beat, anchor_seconds) = self.anchor;
+        let seconds = anchor_seconds + (beat - anchor_beat) * 60.0 / transport.bpm();
+        // Tempo changes apply from the latest event on
+        if beat > anchor_beat {
+            self.anchor = (beat, seconds);
+        }
+        self.events.push(LoggedEvent {
+            beat,
+            bar: (beat / transport.time_signature().beats_per_bar()).floor() as i64 + 1,
+            seconds,
+            voice: event.voice_name.clone(),
+            group: event.group_path.clone(),
+            synthdef: synthdef.to_string(),
+            pattern: event.pattern_name.clone(),
+            melody: event.melody_name.clone(),
+            node_id,
+            controls: controls.to_vec(),
+        });
+    }
+
+    /// Recorded events, in the order they were scheduled.
+    pub fn events(&self) -> &[LoggedEvent] {
+        &self.events
+    }
+
+    /// Write the log to its file, sorted by beat. Returns the number of events.
+    pub fn write(&self) -> Result<usize> {
+        match &self.path {
+            Some(path) => write_events(path, &self.events),
+            None => Ok(0),
+        }
+    }
+}
+
+/// Write events to `path` as JSON or CSV, sorted by beat.
+pub fn write_events(path: &Path, events: &[LoggedEvent]) -> Result<usize> {
+    let mut sorted = events.to_vec();
+    sorted.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+    let text = match EventLogFormat::from_path(path) {
+        EventLogFormat::Json => to_json(&sorted),
+        EventLogFormat::Csv => to_csv(&sorted),
+    };
+    std::fs::write(path, text).with_context(|| format!("Failed to write event log: {}", path.display()))?;
+    Ok(sorted.len())
+}
+
+/// Events as a JSON array of objects with a `controls` object each.
+pub fn to_json(events: &[LoggedEvent]) -> String {
+    let mut out = String::from("[");
+    for (i, event) in events.iter().enumerate() {
+        out.push_str(if i == 0 { "\n  {" } else { ",\n  {" });
+        let _ = write!(
+            out,
+            "\"beat\": {}, \"bar\": {}, \"seconds\": {}, \"voice\": {}, \"group\": {}, \"synthdef\": {}, \
+             \"pattern\": {}, \"melody\": {}, \"node_id\": {}, \"controls\": {{",
+            json_number(event.beat),
+            event.bar,
+            json_number(event.seconds),
+            json_optional(&event.voice),
+            json_optional(&event.group),
+            json_string(&event.synthdef),
+            json_optional(&event.pattern),
+            json_optional(&event.melody),
+            event.node_id
+        );
+        for (j, (name, value)) in event.controls.iter().enumerate() {
+            if j > 0 {
+                out.push_str(", ");
+            }
+            let _ = write!(out, "{}: {}", json_string(name), json_number(*value as f64));
+        }
+        out.push_str("}}");
+    }
+    out.push_str(if events.is_empty() { "]\n" } else { "\n]\n" });
+    out
+}
+
+/// Events as CSV with a header row; every control gets its own column.
+pub fn to_csv(events: &[LoggedEvent]) -> String {
+    let control_names: BTreeSet<&str> = events
+        .iter()
+        .flat_map(|e| e.controls.iter().map(|(name, _)| name.as_str()))
+        .collect();
+    let mut out = String::from("beat,bar,seconds,voice,group,synthdef,pattern,melody,node_id");
+    for name in &control_names {
+        out.push(',');
+        out.push_str(&csv_field(name));
+    }
+    out.push('\n');
+    for event in events {
+        let _ = write!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            event.beat,
+            event.bar,
+            event.seconds,
+            csv_field(event.voice.as_deref().unwrap_or("")),
+            csv_field(event.group.as_deref().unwrap_or("")),
+            csv_field(&event.synthdef),
+            csv_field(event.pattern.as_deref().unwrap_or("")),
+            csv_field(event.melody.as_deref().unwrap_or("")),
+            event.node_id
+        );
+        for name in &control_names {
+            out.push(',');
+            // The last value wins if a control was sent twice
+            if let Some((_, value)) = event.controls.iter().rev().find(|(n, _)| n == name) {
+                let _ = write!(out, "{}", value);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_optional(value: &Option<String>) -> String {
+    value.as_deref().map(json_string).unwrap_or_else(|| "null".to_string())
+}
+
+/// JSON has no NaN or infinity; those become `null`.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(voice: &str, pattern: &str) -> BeatEvent {
+        let mut event = BeatEvent::new(0.0, "trigger".to_string());
+        event.voice_name = Some(voice.to_string());
+        event.group_path = Some("main/drums".to_string());
+        event.pattern_name = Some(pattern.to_string());
+        event
+    }
+
+    #[test]
+    fn test_event_log_tracks_song_time_and_exports() {
+        let mut transport = TransportClock::new();
+        let mut log = EventLog::new(None, 4.0);
+        let kick = event("kick", "four");
+        log.record(4.0, &transport, &kick, "kick_909", 1000, &[("amp".to_string(), 0.5)]);
+        log.record(5.0, &transport, &kick, "kick_909", 1001, &[("amp".to_string(), 1.0)]);
+        // Tempo halves: the next beat takes a second
+        transport.set_bpm(60.0, std::time::Instant::now());
+        log.record(6.0, &transport, &event("hat", "off,beat"), "hihat", 1002, &[("freq".to_string(), 440.0)]);
+
+        let seconds: Vec<f64> = log.events().iter().map(|e| e.seconds).collect();
+        assert_eq!(seconds, vec![0.0, 0.5, 1.5]);
+
+        let csv = to_csv(log.events());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "beat,bar,seconds,voice,group,synthdef,pattern,melody,node_id,amp,freq");
+        assert_eq!(lines[1], "4,2,0,kick,main/drums,kick_909,four,,1000,0.5,");
+        assert_eq!(lines[3], "6,2,1.5,hat,main/drums,hihat,\"off,beat\",,1002,,440");
+
+        let json = to_json(&log.events()[..1]);
+        assert_eq!(
+            json,
+            "[\n  {\"beat\": 4, \"bar\": 2, \"seconds\": 0, \"voice\": \"kick\", \"group\": \"main/drums\", \
+             \"synthdef\": \"kick_909\", \"pattern\": \"four\", \"melody\": null, \"node_id\": 1000, \
+             \"controls\": {\"amp\": 0.5}}\n]\n"
+        );
+        assert_eq!(to_json(&[]), "[]\n");
+
+        assert_eq!(EventLogFormat::from_path(Path::new("song.CSV")), EventLogFormat::Csv);
+        assert_eq!(EventLogFormat::from_path(Path::new("song.json")), EventLogFormat::Json);
+    }
+}
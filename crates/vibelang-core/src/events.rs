@@ -4,11 +4,14 @@
 //!
 //! - [`BeatEvent`] - A single scheduled event with controls
 //! - [`Pattern`] - A collection of events with loop length
+//! - [`EventClip`] - Sequence clip an event was played by
 //! - [`FadeClip`] - Parameter automation trigger
 //! - [`ActiveFade`] - Runtime state for an active fade
 
 use std::time::Instant;
 
+use crate::variations::EventVariation;
+
 /// An event to be scheduled at a specific beat position.
 ///
 /// Events carry all the information needed to trigger a synth,
@@ -30,8 +33,16 @@ pub struct BeatEvent {
     pub melody_name: Option<String>,
     /// Name of the voice that created this event.
     pub voice_name: Option<String>,
+    /// SynthDef played instead of the voice's own (`trigger` events only).
+    pub synth_override: Option<String>,
     /// Optional automation trigger attached to this event.
     pub fade: Option<FadeClip>,
+    /// Take of a pattern with variations this event belongs to.
+    pub variation: Option<EventVariation>,
+    /// Index of the pattern step or melody note that created this event.
+    pub step: Option<usize>,
+    /// Sequence clip that played this event (unset for `start()`ed loops).
+    pub clip: Option<EventClip>,
 }
 
 impl BeatEvent {
@@ -45,7 +56,11 @@ impl BeatEvent {
             pattern_name: None,
             melody_name: None,
             voice_name: None,
+            synth_override: None,
             fade: None,
+            variation: None,
+            step: None,
+            clip: None,
         }
     }
 
@@ -66,6 +81,16 @@ impl BeatEvent {
         self.voice_name = Some(name.into());
         self
     }
+
+    /// Shift the event's pitch (its `freq` control) by `semitones`.
+    pub fn transpose(&mut self, semitones: i32) {
+        let ratio = 2f64.powf(semitones as f64 / 12.0);
+        for (name, value) in self.controls.iter_mut() {
+            if name == "freq" {
+                *value = (*value as f64 * ratio) as f32;
+            }
+        }
+    }
 }
 
 /// A pattern containing multiple events scheduled at specific beats.
@@ -106,6 +131,56 @@ impl Pattern {
         self.phase_offset = offset;
         self
     }
+
+    /// Copy of the pattern played at `speed` times its tempo.
+    ///
+    /// Positions, the loop length and the phase offset are divided by
+    /// `speed`, and so are note lengths (`gate`, in beats) and fade
+    /// durations: 0.5 plays the pattern at half time.
+    pub fn time_scaled(&self, speed: f64) -> Pattern {
+        let events = self
+            .events
+            .iter()
+            .map(|ev| {
+                let mut ev = ev.clone();
+                ev.beat /= speed;
+                for (name, value) in ev.controls.iter_mut() {
+                    if name == "gate" {
+                        *value = (*value as f64 / speed) as f32;
+                    }
+                }
+                if let Some(fade) = ev.fade.as_mut() {
+                    fade.duration_beats /= speed;
+                }
+                if let Some(variation) = ev.variation.as_mut() {
+                    variation.offset /= speed;
+                }
+                ev
+            })
+            .collect();
+        Pattern {
+            name: self.name.clone(),
+            events,
+            loop_length_beats: self.loop_length_beats / speed,
+            phase_offset: self.phase_offset / speed,
+        }
+    }
+}
+
+/// The clip of a sequence an event was played by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventClip {
+    /// Sequence holding the clip.
+    pub sequence: String,
+    /// Index of the clip in the sequence.
+    pub index: usize,
+}
+
+impl std::fmt::Display for EventClip {
+    /// "verse clip 2", counting clips from 1.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} clip {}", self.sequence, self.index + 1)
+    }
 }
 
 /// Target type for parameter fades.
@@ -125,6 +200,51 @@ pub enum FadeTargetType {
     Effect,
 }
 
+/// How a fade interpolates between its start and target values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FadeCurve {
+    /// Straight line between the raw values.
+    #[default]
+    Linear,
+    /// Straight line in decibels, for amplitudes. Values at or below
+    /// [`FadeCurve::FLOOR_DB`] are treated as silence.
+    Decibels,
+}
+
+impl FadeCurve {
+    /// Level used in place of silence when interpolating in decibels.
+    pub const FLOOR_DB: f64 = -80.0;
+
+    /// Value at progress `t` (0..=1) of a fade from `from` to `to`.
+    pub fn interpolate(self, from: f32, to: f32, t: f64) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => from + (to - from) * t as f32,
+            FadeCurve::Decibels => {
+                if t >= 1.0 {
+                    return to;
+                }
+                let to_db = |amp: f32| vibelang_dsp::amp_to_db(amp.max(0.0) as f64).max(Self::FLOOR_DB);
+                let (from_db, to_db) = (to_db(from), to_db(to));
+                let db = from_db + (to_db - from_db) * t;
+                if db <= Self::FLOOR_DB {
+                    0.0
+                } else {
+                    vibelang_dsp::db_to_amp(db) as f32
+                }
+            }
+        }
+    }
+
+    /// Short name used in logs and the HTTP API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FadeCurve::Linear => "linear",
+            FadeCurve::Decibels => "db",
+        }
+    }
+}
+
 /// A fade automation trigger scheduled at a specific beat.
 ///
 /// FadeClips are created from FadeDefinitions and attached to BeatEvents
@@ -147,6 +267,8 @@ pub struct FadeClip {
     pub target_value: f32,
     /// Duration of the fade in beats.
     pub duration_beats: f64,
+    /// Interpolation between start and target value.
+    pub curve: FadeCurve,
 }
 
 /// Runtime state for an active parameter fade operation.
@@ -176,6 +298,8 @@ pub struct ActiveFade {
     pub last_update_time: Option<Instant>,
     /// Last value sent (for deduplication).
     pub last_sent_value: Option<f32>,
+    /// Interpolation between start and target value.
+    pub curve: FadeCurve,
 }
 
 impl ActiveFade {
@@ -189,7 +313,7 @@ impl ActiveFade {
             return self.target_value;
         }
         let t = elapsed / self.duration_seconds;
-        self.start_value + (self.target_value - self.start_value) * t as f32
+        self.curve.interpolate(self.start_value, self.target_value, t)
     }
 
     /// Check if the fade has completed.
@@ -229,6 +353,22 @@ mod tests {
         assert!((pattern.phase_offset - 0.5).abs() < 0.001);
     }
 
+    #[test]
+    fn test_pattern_time_scaled() {
+        let pattern = Pattern::new("breaks", 4.0)
+            .with_event(BeatEvent::new(1.0, "snare").with_control("gate", 0.5).with_control("freq", 200.0))
+            .with_phase_offset(0.5);
+
+        let half = pattern.time_scaled(0.5);
+        assert!((half.loop_length_beats - 8.0).abs() < 0.001);
+        assert!((half.phase_offset - 1.0).abs() < 0.001);
+        assert!((half.events[0].beat - 2.0).abs() < 0.001);
+        assert_eq!(half.events[0].controls, vec![("gate".to_string(), 1.0), ("freq".to_string(), 200.0)]);
+
+        let double = pattern.time_scaled(2.0);
+        assert!((double.events[0].beat - 0.5).abs() < 0.001);
+    }
+
     #[test]
     fn test_active_fade_interpolation() {
         let fade = ActiveFade {
@@ -243,10 +383,25 @@ mod tests {
             delay_seconds: 0.0,
             last_update_time: None,
             last_sent_value: None,
+            curve: FadeCurve::Linear,
         };
 
         // At the start, value should be close to start_value
         let val = fade.current_value();
         assert!(val >= 0.0 && val <= 1.0);
     }
+
+    #[test]
+    fn test_fade_curve_decibels() {
+        // Halfway between 1.0 (0 dB) and 0.25 (~-12 dB) is ~-6 dB, not 0.625
+        let mid = FadeCurve::Decibels.interpolate(1.0, 0.25, 0.5);
+        assert!((mid - 0.5).abs() < 0.001);
+        assert!((FadeCurve::Linear.interpolate(1.0, 0.25, 0.5) - 0.625).abs() < 0.001);
+
+        // Silence is reached through the floor and hit exactly at the end
+        let near_end = FadeCurve::Decibels.interpolate(1.0, 0.0, 0.99);
+        assert!(near_end > 0.0 && near_end < 0.001);
+        assert_eq!(FadeCurve::Decibels.interpolate(1.0, 0.0, 1.0), 0.0);
+        assert_eq!(FadeCurve::Decibels.interpolate(0.0, 1.0, 0.0), 0.0);
+    }
 }
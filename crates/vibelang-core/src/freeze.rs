@@ -0,0 +1,230 @@
+//! Group freezing (bouncing a group's output to audio).
+//!
+//! Freezing records a group's bus into a buffer for a number of bars, then
+//! replaces the live synthesis with a looping player of that buffer and pauses
+//! the original synths to save CPU. Unfreezing removes the player and resumes
+//! the original nodes.
+
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the synthdef that records a group bus into a buffer.
+pub const FREEZE_RECORDER_SYNTHDEF: &str = "system_freeze_recorder";
+
+/// Name of the synthdef that loops a frozen buffer back onto a group bus.
+pub const FREEZE_PLAYER_SYNTHDEF: &str = "system_freeze_player";
+
+/// Number of bars recorded when no length is given.
+pub const DEFAULT_FREEZE_BARS: u32 = 8;
+
+/// Maximum number of bars that can be frozen.
+pub const MAX_FREEZE_BARS: u32 = 64;
+
+/// Sample rate used to size freeze buffers until scsynth has reported its own.
+///
+/// Buffers are sized for the highest common rate then. The player loops on
+/// `SampleRate.ir * dur`, so at lower rates the tail of the buffer is simply
+/// never read.
+const FALLBACK_SAMPLE_RATE: f64 = 96_000.0;
+
+/// Number of channels recorded from a group bus.
+const FREEZE_CHANNELS: i32 = 2;
+
+/// Duration in seconds of a freeze of `bars` bars.
+pub fn freeze_duration_seconds(bars: u32, beats_per_bar: f64, tempo: f64) -> f64 {
+    bars as f64 * beats_per_bar * 60.0 / tempo.max(1.0)
+}
+
+/// Number of frames to allocate for a freeze buffer of the given duration.
+///
+/// `sample_rate` is the server's nominal rate from its last status reply
+/// (`None` or 0 before the first one).
+pub fn freeze_buffer_frames(seconds: f64, sample_rate: Option<f64>) -> i32 {
+    let rate = sample_rate.filter(|rate| *rate > 0.0).unwrap_or(FALLBACK_SAMPLE_RATE);
+    (seconds * rate).ceil() as i32
+}
+
+/// Number of channels of a freeze buffer.
+pub fn freeze_buffer_channels() -> i32 {
+    FREEZE_CHANNELS
+}
+
+/// Create and encode the freeze recorder and player synthdefs.
+pub fn create_freeze_synthdefs() -> Vec<(String, Vec<u8>)> {
+    let mut defs = Vec::new();
+    for (name, ir) in [
+        (FREEZE_RECORDER_SYNTHDEF, recorder_graph()),
+        (FREEZE_PLAYER_SYNTHDEF, player_graph()),
+    ] {
+        match encode_synthdef(&ir) {
+            Ok(bytes) => defs.push((name.to_string(), bytes)),
+            Err(e) => log::error!("[FREEZE] Failed to encode {} synthdef: {}", name, e),
+        }
+    }
+    defs
+}
+
+/// Recorder: In.ar(inbus, 2) → RecordBuf (no loop, runs until the buffer is full).
+///
+/// Parameters:
+/// - inbus: group bus to record (0)
+/// - bufnum: target buffer (1)
+fn recorder_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("inbus".to_string(), vec![0.0], None); // 0
+    builder.add_param("bufnum".to_string(), vec![0.0], None); // 1
+    builder.create_control_ugen();
+
+    builder.add_constant(0.0);
+    builder.add_constant(1.0);
+
+    let input = builder.add_node(
+        "In".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 0)],
+        FREEZE_CHANNELS as u32,
+        0,
+    );
+
+    builder.add_node(
+        "RecordBuf".to_string(),
+        Rate::Audio,
+        vec![
+            Input::node(0, 1),    // bufnum
+            Input::Constant(0.0), // offset
+            Input::Constant(1.0), // recLevel
+            Input::Constant(0.0), // preLevel
+            Input::Constant(1.0), // run
+            Input::Constant(0.0), // loop
+            Input::Constant(1.0), // trigger
+            Input::Constant(0.0), // doneAction (freed by the runtime)
+            Input::node(input.0, 0),
+            Input::node(input.0, 1),
+        ],
+        1,
+        0,
+    );
+
+    GraphIR::from_builder(FREEZE_RECORDER_SYNTHDEF.to_string(), builder)
+}
+
+/// Player: Phasor over `dur` seconds → BufRd (looping) → Out.ar(out).
+///
+/// Parameters:
+/// - out: group bus to write to (0)
+/// - bufnum: frozen buffer (1)
+/// - dur: loop length in seconds (2)
+fn player_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("out".to_string(), vec![0.0], None); // 0
+    builder.add_param("bufnum".to_string(), vec![0.0], None); // 1
+    builder.add_param("dur".to_string(), vec![1.0], None); // 2
+    builder.create_control_ugen();
+
+    builder.add_constant(0.0);
+    builder.add_constant(1.0);
+    builder.add_constant(2.0);
+
+    // Loop length in frames: SampleRate.ir * dur
+    let sample_rate = builder.add_node("SampleRate".to_string(), Rate::Scalar, vec![], 1, 0);
+    let loop_frames = builder.add_node(
+        "BinaryOpUGen".to_string(),
+        Rate::Control,
+        vec![Input::node(sample_rate.0, 0), Input::node(0, 2)],
+        1,
+        2, // multiplication
+    );
+
+    let phase = builder.add_node(
+        "Phasor".to_string(),
+        Rate::Audio,
+        vec![
+            Input::Constant(0.0), // trig
+            Input::Constant(1.0), // rate
+            Input::Constant(0.0), // start
+            Input::node(loop_frames.0, 0),
+            Input::Constant(0.0), // resetPos
+        ],
+        1,
+        0,
+    );
+
+    let playback = builder.add_node(
+        "BufRd".to_string(),
+        Rate::Audio,
+        vec![
+            Input::node(0, 1),       // bufnum
+            Input::node(phase.0, 0), // phase
+            Input::Constant(1.0),    // loop
+            Input::Constant(2.0),    // linear interpolation
+        ],
+        FREEZE_CHANNELS as u32,
+        0,
+    );
+
+    builder.add_node(
+        "Out".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 0), Input::node(playback.0, 0), Input::node(playback.0, 1)],
+        0,
+        0,
+    );
+
+    GraphIR::from_builder(FREEZE_PLAYER_SYNTHDEF.to_string(), builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freeze_duration() {
+        // 8 bars of 4/4 at 120 BPM = 32 beats = 16 seconds
+        assert!((freeze_duration_seconds(8, 4.0, 120.0) - 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_freeze_buffer_follows_server_sample_rate() {
+        assert_eq!(freeze_buffer_frames(16.0, Some(48_000.0)), 768_000);
+        assert_eq!(freeze_buffer_frames(16.0, Some(192_000.0)), 3_072_000);
+        assert_eq!(freeze_buffer_frames(0.5, Some(44_100.0)), 22_050);
+        // Before the first status reply
+        assert_eq!(freeze_buffer_frames(1.0, None), 96_000);
+        assert_eq!(freeze_buffer_frames(1.0, Some(0.0)), 96_000);
+    }
+
+    #[test]
+    fn test_recorder_graph_records_both_channels_of_the_bus() {
+        // Parameter slots: inbus 0, bufnum 1
+        let ir = recorder_graph();
+        assert!(ir.validate().is_ok());
+        let (input, bus) = ir.ugens("In").next().unwrap();
+        assert_eq!(bus.inputs, vec![Input::node(0, 0)]);
+        assert_eq!(bus.num_outputs, 2);
+
+        let (_, record) = ir.ugens("RecordBuf").next().unwrap();
+        assert_eq!(record.inputs[0], Input::node(0, 1));
+        assert_eq!(record.inputs[5], Input::Constant(0.0), "records once, no loop");
+        assert_eq!(record.inputs[8..], [Input::node(input, 0), Input::node(input, 1)]);
+    }
+
+    #[test]
+    fn test_player_graph_loops_dur_seconds_of_the_buffer() {
+        // Parameter slots: out 0, bufnum 1, dur 2
+        let ir = player_graph();
+        assert!(ir.validate().is_ok());
+        let (sample_rate, _) = ir.ugens("SampleRate").next().unwrap();
+        let (frames, length) = ir.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(length.inputs, vec![Input::node(sample_rate, 0), Input::node(0, 2)]);
+        assert_eq!(length.special_index, 2);
+        let (phasor, phase) = ir.ugens("Phasor").next().unwrap();
+        assert_eq!(phase.inputs[3], Input::node(frames, 0));
+
+        let (playback, read) = ir.ugens("BufRd").next().unwrap();
+        assert_eq!(read.num_outputs, 2);
+        assert_eq!(read.inputs[..3], [Input::node(0, 1), Input::node(phasor, 0), Input::Constant(1.0)]);
+        let (_, out) = ir.ugens("Out").next().unwrap();
+        assert_eq!(out.inputs, vec![Input::node(0, 0), Input::node(playback, 0), Input::node(playback, 1)]);
+    }
+}
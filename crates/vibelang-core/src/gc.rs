@@ -0,0 +1,218 @@
+//! Time-based garbage collection for long sessions.
+//!
+//! Without it a session running for hours keeps every sample buffer it ever
+//! loaded, tracks synths whose end notification got lost forever, and logs
+//! every sequence run. With a [`GcPolicy`] set, the runtime sweeps the state
+//! every [`GC_INTERVAL`]:
+//!
+//! - samples no voice has referenced for a while get their buffers freed;
+//!   the sample stays known and is reloaded when a voice plays it again
+//! - synths tracked for longer than the maximum age are forgotten
+//! - the sequence run log is capped and fired one-shot events are dropped
+//!
+//! All collection is off by default.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::state::ScriptState;
+
+/// How often the runtime sweeps the state.
+pub const GC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Beats a one-shot event is kept after it fired.
+const FIRED_EVENT_GRACE_BEATS: f64 = 4.0;
+
+/// What the garbage collector reclaims (`None` = keep forever).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GcPolicy {
+    /// Free sample buffers not referenced by any voice for this long.
+    pub sample_idle: Option<Duration>,
+    /// Forget tracked synths this long after they were first seen.
+    ///
+    /// Synths are normally forgotten when scsynth reports their end; this
+    /// catches the ones whose notification got lost. A voice's running synth
+    /// is never forgotten.
+    pub synth_max_age: Option<Duration>,
+    /// Maximum number of entries kept in the sequence run log; also drops
+    /// one-shot events that have fired.
+    pub max_log_entries: Option<usize>,
+}
+
+impl GcPolicy {
+    /// Settings for multi-hour sessions.
+    pub fn recommended() -> Self {
+        Self {
+            sample_idle: Some(Duration::from_secs(10 * 60)),
+            synth_max_age: Some(Duration::from_secs(10 * 60)),
+            max_log_entries: Some(1000),
+        }
+    }
+
+    /// Whether anything is collected.
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+/// Garbage collector settings and bookkeeping.
+#[derive(Clone, Debug, Default)]
+pub struct GcState {
+    /// Active policy.
+    pub policy: GcPolicy,
+    /// Samples no voice references, by when that was first noticed.
+    pub idle_since: HashMap<String, Instant>,
+    /// Samples whose buffers were freed; reloaded on next use.
+    pub evicted: HashSet<String>,
+    /// Tracked synths by when a sweep first saw them.
+    pub synths_seen: HashMap<i32, Instant>,
+}
+
+/// What a sweep reclaimed; the runtime frees the buffers and nodes.
+#[derive(Debug, Default, PartialEq)]
+pub struct GcSweep {
+    /// Evicted samples and their buffers.
+    pub evicted: Vec<(String, i32)>,
+    /// Synths to forget.
+    pub expired_nodes: Vec<i32>,
+    /// Log entries and fired events dropped.
+    pub pruned_entries: usize,
+}
+
+/// Sweep the state according to its GC policy.
+pub fn sweep(state: &mut ScriptState, now: Instant) -> GcSweep {
+    let policy = state.gc.policy.clone();
+    let mut result = GcSweep::default();
+
+    if let Some(idle) = policy.sample_idle {
+        let referenced = referenced_samples(state);
+        let gc = &mut state.gc;
+        gc.idle_since.retain(|id, _| state.samples.contains_key(id));
+        gc.evicted.retain(|id| state.samples.contains_key(id));
+        for (id, sample) in &state.samples {
+            if referenced.contains(id) {
+                gc.idle_since.remove(id);
+                continue;
+            }
+            if gc.evicted.contains(id) {
+                continue;
+            }
+            let since = *gc.idle_since.entry(id.clone()).or_insert(now);
+            if now.duration_since(since) >= idle {
+                gc.idle_since.remove(id);
+                gc.evicted.insert(id.clone());
+                result.evicted.push((id.clone(), sample.buffer_id));
+            }
+        }
+    }
+
+    if let Some(max_age) = policy.synth_max_age {
+        let running: HashSet<i32> = state.voices.values().filter_map(|v| v.running_node_id).collect();
+        let gc = &mut state.gc;
+        gc.synths_seen.retain(|node_id, _| state.active_synths.contains_key(node_id));
+        for node_id in state.active_synths.keys() {
+            let seen = *gc.synths_seen.entry(*node_id).or_insert(now);
+            if now.duration_since(seen) >= max_age && !running.contains(node_id) {
+                result.expired_nodes.push(*node_id);
+            }
+        }
+        result.expired_nodes.sort_unstable();
+    }
+
+    if let Some(max_entries) = policy.max_log_entries {
+        let excess = state.sequence_runs.len().saturating_sub(max_entries);
+        state.sequence_runs.drain(..excess);
+        let before = state.scheduled_events.len();
+        let horizon = state.current_beat - FIRED_EVENT_GRACE_BEATS;
+        state.scheduled_events.retain(|e| e.beat >= horizon);
+        result.pruned_entries = excess + before - state.scheduled_events.len();
+    }
+
+    result
+}
+
+/// Samples a voice plays: through its buffer, its synthdef or key matching.
+fn referenced_samples(state: &ScriptState) -> HashSet<String> {
+    let mut referenced = HashSet::new();
+    for voice in state.voices.values() {
+        let bufnum = voice.params.get("bufnum").map(|b| *b as i32);
+        for sample in state.samples.values() {
+            let plays_synthdef = voice.synth_name.as_deref().is_some_and(|synth| {
+                synth == sample.synthdef_name || sample.slices.iter().any(|s| s.synthdef_name == synth)
+            });
+            if bufnum == Some(sample.buffer_id) || plays_synthdef || voice.key_match.as_deref() == Some(sample.id.as_str()) {
+                referenced.insert(sample.id.clone());
+            }
+        }
+    }
+    referenced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ActiveSynth, SampleInfo, VoiceState};
+
+    fn sample(id: &str, buffer_id: i32) -> SampleInfo {
+        SampleInfo {
+            id: id.to_string(),
+            path: format!("{}.wav", id),
+            buffer_id,
+            num_channels: 2,
+            num_frames: 48_000,
+            sample_rate: 48_000.0,
+            synthdef_name: format!("__sample_{}", id),
+            slices: Vec::new(),
+            detected_key: None,
+            onset_ms: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_sweep_reclaims_idle_samples_old_synths_and_logs() {
+        let mut state = ScriptState::new();
+        state.gc.policy = GcPolicy {
+            sample_idle: Some(Duration::from_secs(60)),
+            synth_max_age: Some(Duration::from_secs(60)),
+            max_log_entries: Some(2),
+        };
+        state.samples.insert("kick".to_string(), sample("kick", 10));
+        state.samples.insert("pad".to_string(), sample("pad", 11));
+        let mut drums = VoiceState::new("drums".to_string(), "main".to_string());
+        drums.params.insert("bufnum".to_string(), 10.0);
+        drums.running_node_id = Some(1001);
+        state.voices.insert("drums".to_string(), drums);
+        for node_id in [1000, 1001] {
+            state.active_synths.insert(
+                node_id,
+                ActiveSynth {
+                    node_id,
+                    ..Default::default()
+                },
+            );
+        }
+        for name in ["a", "b", "c"] {
+            state.sequence_runs.push(crate::state::SequenceRunLog {
+                name: name.to_string(),
+                anchor_beat: 0.0,
+                started_at: std::time::SystemTime::now(),
+            });
+        }
+
+        let start = Instant::now();
+        let first = sweep(&mut state, start);
+        assert!(first.evicted.is_empty() && first.expired_nodes.is_empty());
+        assert_eq!(first.pruned_entries, 1);
+        assert_eq!(state.sequence_runs.first().unwrap().name, "b");
+
+        // Only the unreferenced sample and the finished one-shot go
+        let later = sweep(&mut state, start + Duration::from_secs(61));
+        assert_eq!(later.evicted, vec![("pad".to_string(), 11)]);
+        assert_eq!(later.expired_nodes, vec![1000]);
+        assert!(state.gc.evicted.contains("pad"));
+
+        // An evicted sample is not evicted again
+        assert!(sweep(&mut state, start + Duration::from_secs(200)).evicted.is_empty());
+        assert!(!GcPolicy::default().is_enabled());
+    }
+}
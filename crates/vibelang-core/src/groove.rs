@@ -0,0 +1,300 @@
+//! Ensemble timing feel.
+//!
+//! A [`TimingFeel`] moves a voice's events off the grid by a mean offset
+//! plus jitter ("bass drags 10ms, hats rush 4ms"), so voices playing the same
+//! grid sound like players in a band. The jitter of an event only depends on
+//! the voice and beat, so a song plays (and simulates) the same every time.
+//!
+//! [`Humanize`] does the same for the events of one pattern or melody, and
+//! varies their velocity as well.
+//!
+//! A [`GrooveTemplate`] holds the feel of each voice of a piece. Templates
+//! are saved as `.groove` text files to reuse and share them, one voice per
+//! line:
+//!
+//! ```text
+//! # offset and jitter in ms (positive = late)
+//! bass   10  3
+//! hats   -4  1.5  uniform
+//! ```
+
+use anyhow::{bail, Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// Largest mean offset of a feel, in milliseconds.
+pub const MAX_FEEL_OFFSET_MS: f64 = 100.0;
+
+/// Largest jitter of a feel, in milliseconds.
+pub const MAX_FEEL_JITTER_MS: f64 = 50.0;
+
+/// How a feel's jitter is spread around its mean offset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JitterDistribution {
+    /// Bell curve with the jitter as standard deviation, cut off at 3 deviations.
+    #[default]
+    Normal,
+    /// Even spread within plus or minus the jitter.
+    Uniform,
+}
+
+impl JitterDistribution {
+    /// Parse "normal" or "uniform".
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "normal" | "gaussian" => Some(JitterDistribution::Normal),
+            "uniform" => Some(JitterDistribution::Uniform),
+            _ => None,
+        }
+    }
+
+    /// "normal" or "uniform".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JitterDistribution::Normal => "normal",
+            JitterDistribution::Uniform => "uniform",
+        }
+    }
+
+    /// Largest deviation from the mean, in multiples of the jitter.
+    fn spread(&self) -> f64 {
+        match self {
+            JitterDistribution::Normal => 3.0,
+            JitterDistribution::Uniform => 1.0,
+        }
+    }
+}
+
+/// Timing of a voice relative to the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimingFeel {
+    /// Mean offset in milliseconds; positive drags behind the beat, negative rushes.
+    pub offset_ms: f64,
+    /// Spread of the offset from event to event, in milliseconds.
+    pub jitter_ms: f64,
+    pub distribution: JitterDistribution,
+}
+
+impl TimingFeel {
+    /// A feel with normally distributed jitter, clamped to the allowed range.
+    pub fn new(offset_ms: f64, jitter_ms: f64) -> Self {
+        Self {
+            offset_ms: offset_ms.clamp(-MAX_FEEL_OFFSET_MS, MAX_FEEL_OFFSET_MS),
+            jitter_ms: jitter_ms.clamp(0.0, MAX_FEEL_JITTER_MS),
+            distribution: JitterDistribution::Normal,
+        }
+    }
+
+    /// The same feel with another jitter distribution.
+    pub fn with_distribution(mut self, distribution: JitterDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// Offset of `voice`'s event at `beat` from the grid, in milliseconds.
+    pub fn offset_at(&self, voice: &str, beat: f64) -> f64 {
+        if self.jitter_ms <= 0.0 {
+            return self.offset_ms;
+        }
+        let deviation = match self.distribution {
+            JitterDistribution::Uniform => unit_random(voice, beat, 0) * 2.0 - 1.0,
+            JitterDistribution::Normal => {
+                // Box-Muller transform
+                let u1 = unit_random(voice, beat, 0).max(f64::MIN_POSITIVE);
+                let u2 = unit_random(voice, beat, 1);
+                let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                z.clamp(-3.0, 3.0)
+            }
+        };
+        self.offset_ms + deviation * self.jitter_ms
+    }
+
+    /// Furthest an event can be moved ahead of the beat, in milliseconds.
+    pub fn max_early_ms(&self) -> f64 {
+        (self.jitter_ms * self.distribution.spread() - self.offset_ms).max(0.0)
+    }
+}
+
+/// Largest velocity variation of [`Humanize`], in percent.
+pub const MAX_HUMANIZE_VELOCITY_PCT: f64 = 100.0;
+
+/// Random timing and velocity variation of a pattern's or melody's events.
+///
+/// Every event moves up to `timing_ms` off the grid either way and has its
+/// amp scaled by up to `velocity_pct` percent up or down. The variation is
+/// drawn per event and absolute beat, so each pass of a loop plays
+/// differently while a song still plays (and simulates) the same every
+/// time. Notes starting together (a chord) move and scale together.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Humanize {
+    pub timing_ms: f64,
+    pub velocity_pct: f64,
+}
+
+impl Humanize {
+    /// A humanize amount, clamped to the allowed ranges.
+    pub fn new(timing_ms: f64, velocity_pct: f64) -> Self {
+        Self {
+            timing_ms: timing_ms.clamp(0.0, MAX_FEEL_JITTER_MS),
+            velocity_pct: velocity_pct.clamp(0.0, MAX_HUMANIZE_VELOCITY_PCT),
+        }
+    }
+
+    /// Offset of the event of loop `name` at `beat` from the grid, in
+    /// milliseconds (positive = late).
+    pub fn offset_ms(&self, name: &str, beat: f64) -> f64 {
+        (unit_random(name, beat, 2) * 2.0 - 1.0) * self.timing_ms
+    }
+
+    /// Factor the amp of the event of loop `name` at `beat` is scaled by.
+    pub fn amp_factor(&self, name: &str, beat: f64) -> f64 {
+        1.0 + (unit_random(name, beat, 3) * 2.0 - 1.0) * self.velocity_pct / 100.0
+    }
+}
+
+/// Deterministic number in `[0, 1)` for a voice's event at a beat.
+fn unit_random(voice: &str, beat: f64, salt: u8) -> f64 {
+    let mut hasher = DefaultHasher::new();
+    voice.hash(&mut hasher);
+    beat.to_bits().hash(&mut hasher);
+    salt.hash(&mut hasher);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Named timing feels of the voices of a piece.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GrooveTemplate {
+    pub name: String,
+    /// Feel of each voice, by voice name.
+    pub feels: BTreeMap<String, TimingFeel>,
+}
+
+impl GrooveTemplate {
+    /// An empty template.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            feels: BTreeMap::new(),
+        }
+    }
+
+    /// Feel of a voice, if the template has one.
+    pub fn feel(&self, voice: &str) -> Option<&TimingFeel> {
+        self.feels.get(voice)
+    }
+
+    /// The template in `.groove` file format.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("# groove: {}\n# voice  offset_ms  jitter_ms  distribution (positive = late)\n", self.name);
+        for (voice, feel) in &self.feels {
+            let _ = writeln!(
+                out,
+                "{}  {}  {}  {}",
+                voice,
+                feel.offset_ms,
+                feel.jitter_ms,
+                feel.distribution.as_str()
+            );
+        }
+        out
+    }
+
+    /// Parse a template in `.groove` file format.
+    pub fn parse(name: impl Into<String>, text: &str) -> Result<Self> {
+        let mut template = Self::new(name);
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| -> Result<f64> {
+                let field = fields.get(i).copied().unwrap_or("0").trim_end_matches("ms");
+                field
+                    .parse()
+                    .with_context(|| format!("line {}: '{}' is not a number of milliseconds", index + 1, field))
+            };
+            if fields.len() > 4 {
+                bail!("line {}: expected 'voice offset_ms [jitter_ms] [distribution]'", index + 1);
+            }
+            let distribution = match fields.get(3) {
+                Some(name) => JitterDistribution::parse(name).with_context(|| {
+                    format!("line {}: unknown jitter distribution '{}' (normal or uniform)", index + 1, name)
+                })?,
+                None => JitterDistribution::Normal,
+            };
+            let feel = TimingFeel::new(number(1)?, number(2)?).with_distribution(distribution);
+            template.feels.insert(fields[0].to_string(), feel);
+        }
+        Ok(template)
+    }
+
+    /// Save the template to a `.groove` file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write groove template: {}", path.display()))
+    }
+
+    /// Load a template from a `.groove` file, named after the file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read groove template: {}", path.display()))?;
+        let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        Self::parse(name, &text).with_context(|| format!("Invalid groove template: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_feel_and_groove_template() {
+        let drag = TimingFeel::new(10.0, 3.0);
+        let offsets: Vec<f64> = (0..2000).map(|i| drag.offset_at("bass", i as f64 * 0.25)).collect();
+        let mean = offsets.iter().sum::<f64>() / offsets.len() as f64;
+        assert!((mean - 10.0).abs() < 0.5, "mean {}", mean);
+        assert!(offsets.iter().all(|o| (1.0..=19.0).contains(o)));
+        // The same event always gets the same offset, other voices another one
+        assert_eq!(drag.offset_at("bass", 8.5), drag.offset_at("bass", 8.5));
+        assert_ne!(drag.offset_at("bass", 8.5), drag.offset_at("keys", 8.5));
+
+        let rush = TimingFeel::new(-4.0, 1.0).with_distribution(JitterDistribution::Uniform);
+        assert!((0..500).all(|i| (-5.0..=-3.0).contains(&rush.offset_at("hats", i as f64))));
+        assert_eq!(rush.max_early_ms(), 5.0);
+        assert_eq!(drag.max_early_ms(), 0.0);
+        assert_eq!(TimingFeel::new(0.0, 0.0).offset_at("kick", 1.0), 0.0);
+        assert_eq!(TimingFeel::new(500.0, -1.0), TimingFeel::new(100.0, 0.0));
+
+        let mut template = GrooveTemplate::new("band");
+        template.feels.insert("bass".to_string(), drag);
+        template.feels.insert("hats".to_string(), rush);
+        let parsed = GrooveTemplate::parse("band", &template.to_text()).unwrap();
+        assert_eq!(parsed, template);
+
+        let parsed = GrooveTemplate::parse("live", "snare 6ms # a bit late\n\nkick -2 0.5 uniform\n").unwrap();
+        assert_eq!(parsed.feel("snare"), Some(&TimingFeel::new(6.0, 0.0)));
+        assert_eq!(parsed.feel("kick").unwrap().distribution, JitterDistribution::Uniform);
+        assert!(GrooveTemplate::parse("bad", "bass late").is_err());
+        assert!(GrooveTemplate::parse("bad", "bass 1 2 skewed").is_err());
+    }
+
+    #[test]
+    fn test_humanize_stays_in_bounds_and_varies_per_pass() {
+        let loose = Humanize::new(8.0, 20.0);
+        let offsets: Vec<f64> = (0..1000).map(|i| loose.offset_ms("hats", i as f64 * 0.25)).collect();
+        let factors: Vec<f64> = (0..1000).map(|i| loose.amp_factor("hats", i as f64 * 0.25)).collect();
+        assert!(offsets.iter().all(|o| (-8.0..=8.0).contains(o)));
+        assert!(factors.iter().all(|f| (0.8..=1.2).contains(f)));
+        assert!(offsets.iter().any(|o| *o < -6.0) && offsets.iter().any(|o| *o > 6.0));
+
+        // The same step of the next 4-beat pass gets another offset
+        assert_ne!(loose.offset_ms("hats", 1.0), loose.offset_ms("hats", 5.0));
+        assert_eq!(loose.offset_ms("hats", 1.0), loose.offset_ms("hats", 1.0));
+        assert_eq!(Humanize::new(0.0, 0.0).amp_factor("hats", 1.0), 1.0);
+        assert_eq!(Humanize::new(500.0, 300.0), Humanize::new(50.0, 100.0));
+    }
+}
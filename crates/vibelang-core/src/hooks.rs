@@ -0,0 +1,216 @@
+//! Hooks: external commands and webhooks fired from the timeline.
+//!
+//! A hook runs a shell command or sends an HTTP request when the transport
+//! reaches a beat, once or repeating, so lights, video and other non-audio
+//! show elements follow the same timeline as the music. The runtime picks
+//! due hooks up with the same lookahead as audio events and hands them to a
+//! worker thread, which waits for the exact moment the beat plays and runs
+//! the hook there — a slow command never holds up the runtime, and one that
+//! hangs is killed after its timeout.
+//!
+//! Webhooks speak plain HTTP/1.1 (no TLS); for `https://` endpoints use a
+//! command hook with `curl`.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a hook may run when no timeout is given.
+pub const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a hook does when it fires.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HookAction {
+    /// Run a command with the system shell.
+    Command(String),
+    /// Send an HTTP request.
+    Webhook {
+        url: String,
+        method: String,
+        body: Option<String>,
+    },
+}
+
+impl HookAction {
+    /// One-line description for logs and the UI.
+    pub fn describe(&self) -> String {
+        match self {
+            HookAction::Command(command) => format!("$ {}", command),
+            HookAction::Webhook { url, method, .. } => format!("{} {}", method, url),
+        }
+    }
+}
+
+/// First beat at or after `from` a hook starting at `beat` fires on,
+/// repeating every `every` beats. `None` once a one-shot hook has passed.
+pub fn next_hook_beat(beat: f64, every: Option<f64>, from: f64) -> Option<f64> {
+    if beat >= from {
+        return Some(beat);
+    }
+    let every = every.filter(|e| *e > 0.0)?;
+    Some(beat + ((from - beat) / every).ceil() * every)
+}
+
+/// Run `action` on a worker thread at `at`, killing it after `timeout`.
+pub fn spawn_hook(name: String, action: HookAction, at: Instant, timeout: Duration) {
+    let spawned = std::thread::Builder::new()
+        .name(format!("hook-{}", name))
+        .spawn(move || {
+            std::thread::sleep(at.saturating_duration_since(Instant::now()));
+            match run_hook(&action, timeout) {
+                Ok(outcome) => log::info!("[HOOK] '{}' ({}): {}", name, action.describe(), outcome),
+                Err(e) => log::warn!("[HOOK] '{}' ({}) failed: {}", name, action.describe(), e),
+            }
+        });
+    if let Err(e) = spawned {
+        log::error!("[HOOK] Failed to start a thread for hook: {}", e);
+    }
+}
+
+/// Run a hook right away, returning a short description of the outcome.
+pub fn run_hook(action: &HookAction, timeout: Duration) -> Result<String, String> {
+    match action {
+        HookAction::Command(command) => run_command(command, timeout),
+        HookAction::Webhook { url, method, body } => send_webhook(url, method, body.as_deref(), timeout),
+    }
+}
+
+fn run_command(command: &str, timeout: Duration) -> Result<String, String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    // Output would garble the TUI, only the exit status is reported
+    let mut child = shell
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) if status.success() => return Ok("done".to_string()),
+            Some(status) => return Err(status.to_string()),
+            None if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("killed after {:?}", timeout));
+            }
+            None => std::thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+/// Host, port and path of an `http://` URL.
+fn parse_http_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("only http:// webhooks are supported, got '{}'", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.strip_prefix('[') {
+        // IPv6 literal
+        Some(rest) => {
+            let (host, after) = rest.split_once(']').ok_or_else(|| format!("invalid host in '{}'", url))?;
+            (host, after.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| format!("invalid port in '{}'", url))?,
+        None => 80,
+    };
+    if host.is_empty() {
+        return Err(format!("missing host in '{}'", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+fn send_webhook(url: &str, method: &str, body: Option<&str>, timeout: Duration) -> Result<String, String> {
+    let (host, port, path) = parse_http_url(url)?;
+    let addr = (host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("cannot resolve '{}'", host))?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+
+    let body = body.unwrap_or_default();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: vibelang\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        host,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut response = [0u8; 256];
+    let read = stream.read(&mut response).map_err(|e| e.to_string())?;
+    let status_line = String::from_utf8_lossy(&response[..read]).lines().next().unwrap_or_default().to_string();
+    match status_line.split_whitespace().nth(1).and_then(|code| code.parse::<u16>().ok()) {
+        Some(code) if (200..300).contains(&code) => Ok(status_line),
+        Some(_) => Err(status_line),
+        None => Err("no HTTP response".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_schedule_and_webhook() {
+        assert_eq!(next_hook_beat(64.0, None, 10.0), Some(64.0));
+        assert_eq!(next_hook_beat(64.0, None, 70.0), None);
+        assert_eq!(next_hook_beat(0.0, Some(16.0), 17.0), Some(32.0));
+        assert_eq!(next_hook_beat(0.0, Some(16.0), 32.0), Some(32.0));
+
+        assert_eq!(
+            parse_http_url("http://lights:8080/scene/3"),
+            Ok(("lights".to_string(), 8080, "/scene/3".to_string()))
+        );
+        assert_eq!(parse_http_url("http://[::1]/go"), Ok(("::1".to_string(), 80, "/go".to_string())));
+        assert!(parse_http_url("https://lights/scene").is_err());
+
+        // A local server answering the webhook
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/scene/3", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 512];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+        let action = HookAction::Webhook {
+            url,
+            method: "POST".to_string(),
+            body: Some("{\"on\":true}".to_string()),
+        };
+        assert_eq!(run_hook(&action, Duration::from_secs(2)), Ok("HTTP/1.1 204 No Content".to_string()));
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /scene/3 HTTP/1.1\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"on\":true}"));
+
+        assert!(run_hook(&HookAction::Command("exit 3".to_string()), Duration::from_secs(2)).is_err());
+    }
+}
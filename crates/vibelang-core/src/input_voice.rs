@@ -0,0 +1,128 @@
+//! Audio input voices.
+//!
+//! An input voice (`voice("gtr").input(0)`) plays a hardware input, the way
+//! `SoundIn.ar` does, as a voice: it runs while the script is loaded and
+//! writes to the voice's bus, so a guitar or microphone goes through the
+//! voice's inserts, its gain, pan and mute, and its group's effects and fader.
+
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the synthdef that plays one hardware input, panned.
+pub const INPUT_MONO_SYNTHDEF: &str = "system_input_mono";
+
+/// Name of the synthdef that plays two adjacent hardware inputs, balanced.
+pub const INPUT_STEREO_SYNTHDEF: &str = "system_input_stereo";
+
+/// Input synthdef for a number of input channels (1 or 2).
+pub fn input_synthdef(channels: u32) -> &'static str {
+    if channels >= 2 {
+        INPUT_STEREO_SYNTHDEF
+    } else {
+        INPUT_MONO_SYNTHDEF
+    }
+}
+
+/// Whether a synthdef is one of the input voice synthdefs.
+pub fn is_input_synthdef(name: &str) -> bool {
+    name == INPUT_MONO_SYNTHDEF || name == INPUT_STEREO_SYNTHDEF
+}
+
+/// Create and encode the input voice synthdefs.
+pub fn create_input_synthdefs() -> Vec<(String, Vec<u8>)> {
+    let mut defs = Vec::new();
+    for (name, channels) in [(INPUT_MONO_SYNTHDEF, 1), (INPUT_STEREO_SYNTHDEF, 2)] {
+        match encode_synthdef(&input_graph(name, channels)) {
+            Ok(bytes) => defs.push((name.to_string(), bytes)),
+            Err(e) => log::error!("[INPUT] Failed to encode {} synthdef: {}", name, e),
+        }
+    }
+    defs
+}
+
+/// Pan2 (mono) or Balance2 (stereo) of SoundIn.ar(input, channels)
+/// → Out.ar(out, [l, r]).
+///
+/// Parameters:
+/// - out: voice bus to write to (0)
+/// - input: first hardware input channel, 0-based (1)
+/// - amp: output gain (2)
+/// - pan: position, -1 (left) to 1 (right) (3)
+fn input_graph(name: &str, channels: u32) -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("out".to_string(), vec![0.0], None); // 0
+    builder.add_param("input".to_string(), vec![0.0], None); // 1
+    builder.add_param("amp".to_string(), vec![1.0], None); // 2
+    builder.add_param("pan".to_string(), vec![0.0], None); // 3
+    builder.create_control_ugen();
+
+    let input = builder.add_sound_in(Input::node(0, 1), channels);
+
+    let panned = if channels >= 2 {
+        builder.add_node(
+            "Balance2".to_string(),
+            Rate::Audio,
+            vec![
+                Input::node(input.0, 0),
+                Input::node(input.0, 1),
+                Input::node(0, 3),
+                Input::node(0, 2),
+            ],
+            2,
+            0,
+        )
+    } else {
+        builder.add_node(
+            "Pan2".to_string(),
+            Rate::Audio,
+            vec![Input::node(input.0, 0), Input::node(0, 3), Input::node(0, 2)],
+            2,
+            0,
+        )
+    };
+
+    builder.add_node(
+        "Out".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 0), Input::node(panned.0, 0), Input::node(panned.0, 1)],
+        0,
+        0,
+    );
+
+    GraphIR::from_builder(name.to_string(), builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_graphs_pan_the_hardware_input() {
+        // Parameter slots: out 0, input 1, amp 2, pan 3
+        let mono = input_graph(INPUT_MONO_SYNTHDEF, 1);
+        assert!(mono.validate().is_ok());
+        let (input, sound_in) = mono.ugens("In").next().unwrap();
+        assert_eq!(sound_in.num_outputs, 1);
+        let (_, bus) = mono.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(bus.inputs[1], Input::node(0, 1));
+        let (pan, pan2) = mono.ugens("Pan2").next().unwrap();
+        assert_eq!(pan2.inputs, vec![Input::node(input, 0), Input::node(0, 3), Input::node(0, 2)]);
+        let (_, out) = mono.ugens("Out").next().unwrap();
+        assert_eq!(out.inputs, vec![Input::node(0, 0), Input::node(pan, 0), Input::node(pan, 1)]);
+
+        let stereo = input_graph(INPUT_STEREO_SYNTHDEF, 2);
+        let (input, sound_in) = stereo.ugens("In").next().unwrap();
+        assert_eq!(sound_in.num_outputs, 2);
+        assert_eq!(stereo.ugens("Pan2").count(), 0);
+        let (_, balance) = stereo.ugens("Balance2").next().unwrap();
+        assert_eq!(
+            balance.inputs,
+            vec![Input::node(input, 0), Input::node(input, 1), Input::node(0, 3), Input::node(0, 2)]
+        );
+
+        assert_eq!(input_synthdef(1), INPUT_MONO_SYNTHDEF);
+        assert_eq!(input_synthdef(2), INPUT_STEREO_SYNTHDEF);
+        assert!(is_input_synthdef(INPUT_STEREO_SYNTHDEF));
+        assert!(!is_input_synthdef(crate::return_channel::RETURN_MONO_SYNTHDEF));
+    }
+}
@@ -4,12 +4,14 @@
 //!
 //! - **Timing** - Transport clock, beat time, time signatures
 //! - **Events** - Beat events, patterns, melodies, fades
+//! - **Pitch** - Note/frequency conversion and note names for logs and UIs
 //! - **Sequences** - Declarative clip arrangement system
 //! - **State** - Central state model and message passing
 //! - **OSC** - Open Sound Control client for SuperCollider communication
 //! - **Scheduler** - Beat-based event scheduling engine
 //! - **Scsynth** - High-level SuperCollider server API
 //! - **API** - Rhai scripting API
+//! - **Plugins** - Rust extensions adding Rhai APIs and following the runtime
 //!
 //! # Architecture
 //!
@@ -23,14 +25,49 @@
 //! - `native` (default) - Full native support with UDP OSC, JACK/ALSA MIDI, cpal audio
 
 pub mod api;
+pub mod automation;
+pub mod chord;
+pub mod clock_out;
+pub mod drumkit;
+pub mod event_log;
 pub mod events;
+pub mod freeze;
+pub mod gc;
+pub mod groove;
+pub mod hooks;
+pub mod input_voice;
+pub mod liveset;
+pub mod locators;
+pub mod loop_text;
+pub mod looper;
+pub mod loudness;
+pub mod macros;
+pub mod meter_condition;
+pub mod midi_patch;
+pub mod mono;
+pub mod musical_key;
+pub mod performance;
+pub mod pitch;
+pub mod playback_graph;
+pub mod plugin;
+pub mod preload;
+pub mod quantization;
 pub mod reload;
+pub mod resample;
+pub mod return_channel;
 pub mod sample_synthdef;
+pub mod scale;
 pub mod scheduler;
 pub mod sequences;
+pub mod session;
+pub mod session_file;
+pub mod shutdown;
+pub mod smoothing;
+pub mod sound_design;
 pub mod state;
 pub mod timing;
 pub mod validation;
+pub mod variations;
 
 // Native-only modules (require system dependencies)
 #[cfg(feature = "native")]
@@ -41,21 +78,36 @@ pub mod midi;
 pub mod midi_osc_handler;
 #[cfg(feature = "native")]
 pub mod midi_synthdefs;
+#[cfg(all(feature = "native", any(test, feature = "mock-scsynth")))]
+pub mod mock_scsynth;
+#[cfg(feature = "native")]
+pub mod netsync;
 #[cfg(feature = "native")]
 pub mod osc;
 #[cfg(feature = "native")]
+pub mod osc_capture;
+#[cfg(feature = "native")]
 pub mod osc_sender;
 #[cfg(feature = "native")]
 pub mod runtime;
 #[cfg(feature = "native")]
+pub mod scd;
+#[cfg(feature = "native")]
 pub mod score;
 #[cfg(feature = "native")]
 pub mod scsynth;
 #[cfg(feature = "native")]
 pub mod scsynth_process;
+#[cfg(feature = "native")]
+pub mod synthdef_dir;
+#[cfg(feature = "native")]
+pub mod synthdef_versions;
+#[cfg(feature = "native")]
+pub mod vst;
 
 // Re-export main types for convenience (platform-independent)
-pub use events::{ActiveFade, BeatEvent, FadeClip, FadeTargetType, Pattern};
+pub use events::{ActiveFade, BeatEvent, FadeClip, FadeCurve, FadeTargetType, Pattern};
+pub use plugin::{register_plugin, VibePlugin};
 pub use scheduler::{EventScheduler, LoopKind, LoopSnapshot};
 pub use sequences::{ClipMode, ClipSource, FadeDefinition, SequenceClip, SequenceDefinition};
 pub use state::{
@@ -65,7 +117,7 @@ pub use state::{
     VstInstrumentInfo,
 };
 pub use timing::{
-    Bars, BeatTime, Beats, LatencyCompensation, TimeSignature, TransportClock,
+    Bars, BeatTime, Beats, LatencyCompensation, TimeSignature, TimeSpan, TransportClock,
 };
 
 // Native-only re-exports
@@ -80,7 +132,7 @@ pub use scsynth::{AddAction, BufNum, NodeId, Scsynth, Target};
 #[cfg(feature = "native")]
 pub use scsynth_process::ScsynthProcess;
 #[cfg(feature = "native")]
-pub use runtime::{Runtime, RuntimeHandle};
+pub use runtime::{Runtime, RuntimeHandle, SimulatedEvent, Simulation};
 #[cfg(feature = "native")]
 pub use score::{ScoreWriter, ScoredEvent, beats_to_seconds, seconds_to_osc_time, extract_synthdef_name};
 #[cfg(feature = "native")]
@@ -101,9 +153,9 @@ pub use api::create_engine_with_paths;
 pub use api::require_handle;
 
 // Re-export validation module (types are platform-independent, validate_script is native-only)
-pub use validation::{ValidationResult, ValidationError, SynthdefReference};
+pub use validation::{AssetReference, ValidationResult, ValidationError, SynthdefReference};
 #[cfg(feature = "native")]
-pub use validation::validate_script;
+pub use validation::{validate_reload, validate_script};
 
 #[cfg(test)]
 mod tests {
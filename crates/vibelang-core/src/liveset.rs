@@ -0,0 +1,616 @@
+//! Live sets (`.vibeset` files).
+//!
+//! A live set describes how a composition is performed: named scenes, the
+//! order in which they are cued, and the keyboard/MIDI bindings that launch
+//! them. The cue list is run theater-style: each GO launches the cue on
+//! standby and moves standby to the one after it. It references a `.vibe` composition instead of containing code, so
+//! the same composition can be played with different sets and vice versa.
+//!
+//! ```text
+//! # Friday show
+//! composition = "song.vibe"
+//!
+//! [scene intro]
+//! tempo 118
+//! start intro_seq
+//! mute Drums
+//!
+//! [scene drop]
+//! stop intro_seq
+//! start drop_seq
+//! unmute Drums
+//! set Bass.amp 0.8
+//! fade Pads.amp 0.0 8        # over 8 beats
+//! eval log("drop!")
+//! program TR-8S 10 3          # channel 10, program 3
+//! sysex Prophet patches/drop.syx
+//!
+//! [cues]
+//! intro
+//! drop
+//!
+//! [bindings]
+//! key 1 = scene intro
+//! key n = go
+//! note 10:36 = scene drop   # channel 10, note 36
+//! cc 64 = go
+//! key r = looper guitar record
+//! ```
+//!
+//! Scene actions: `start`/`stop` (sequences, patterns or melodies), `mute`,
+//! `unmute`, `solo`, `unsolo` (groups), `tempo <bpm>`,
+//! `set <group>.<param> <value>`, `fade <group>.<param> <value> <beats>` and
+//! `eval <code>` (Rhai, run by the script thread) and `cue <graph>` (fires
+//! the `on_cue` transitions of a playback graph), and for hardware rigs
+//! `program <device> <channel> [<bank>] <program>` and
+//! `sysex <device> <file.syx | hex bytes>`; devices are MIDI outputs opened
+//! by the composition, matched by (partial) name. Binding targets are `scene <name>`, `go`
+//! (next cue), `back` (previous cue) and `looper <name> record|overdub|clear`.
+//! MIDI channels are 1-16 and optional.
+
+use crate::looper::LooperAction;
+use crate::midi_patch;
+use crate::state::StateMessage;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// File extension of live sets.
+pub const LIVE_SET_EXTENSION: &str = "vibeset";
+
+/// A parsed live set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LiveSet {
+    /// Composition to load (relative paths are resolved by [`LiveSet::load`]).
+    pub composition: PathBuf,
+    /// Scenes in file order.
+    pub scenes: Vec<Scene>,
+    /// Scene names in cue order.
+    pub cues: Vec<String>,
+    /// Keyboard and MIDI bindings.
+    pub bindings: Vec<Binding>,
+}
+
+/// A named set of performance actions applied together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scene {
+    /// Scene name.
+    pub name: String,
+    /// Actions in the order they are applied.
+    pub actions: Vec<SceneAction>,
+}
+
+/// A single action of a scene.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneAction {
+    /// Start a sequence, pattern or melody.
+    Start(String),
+    /// Stop a sequence, pattern or melody.
+    Stop(String),
+    /// Mute a group.
+    Mute(String),
+    /// Unmute a group.
+    Unmute(String),
+    /// Solo a group.
+    Solo(String),
+    /// Remove a group's solo.
+    Unsolo(String),
+    /// Change the tempo.
+    Tempo(f64),
+    /// Set a group parameter.
+    Set {
+        group: String,
+        param: String,
+        value: f32,
+    },
+    /// Fade a group parameter from its current value.
+    Fade {
+        group: String,
+        param: String,
+        value: f32,
+        beats: f64,
+    },
+    /// Evaluate a Rhai snippet.
+    Eval(String),
+    /// Cue a playback graph.
+    CueGraph(String),
+    /// Send raw MIDI messages (program changes, SysEx) to an output device.
+    SendMidi { device: String, messages: Vec<Vec<u8>> },
+    /// Send the SysEx messages of a `.syx` file (read by [`LiveSet::load`]).
+    SysexFile { device: String, path: PathBuf },
+}
+
+/// A binding from an input to a live set action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    /// Input that fires the binding.
+    pub trigger: BindingTrigger,
+    /// What the binding does.
+    pub target: BindingTarget,
+}
+
+/// Input that fires a binding.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BindingTrigger {
+    /// A key in the TUI.
+    Key(char),
+    /// A MIDI note-on. Channel is 0-15, `None` matches any channel.
+    Note { channel: Option<u8>, note: u8 },
+    /// A MIDI CC with a non-zero value. Channel is 0-15, `None` matches any channel.
+    Cc { channel: Option<u8>, controller: u8 },
+}
+
+/// What a binding does.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BindingTarget {
+    /// Launch a scene.
+    Scene(String),
+    /// Launch the next cue.
+    Go,
+    /// Launch the previous cue.
+    Back,
+    /// Control an audio input looper.
+    Looper { name: String, action: LooperAction },
+}
+
+impl BindingTarget {
+    /// Message that performs this binding.
+    pub fn to_message(&self) -> StateMessage {
+        match self {
+            BindingTarget::Scene(name) => StateMessage::LaunchScene { name: name.clone() },
+            BindingTarget::Go => StateMessage::StepCue { delta: 1 },
+            BindingTarget::Back => StateMessage::StepCue { delta: -1 },
+            BindingTarget::Looper { name, action } => StateMessage::LooperControl {
+                name: name.clone(),
+                action: *action,
+            },
+        }
+    }
+}
+
+impl LiveSet {
+    /// Read and parse a live set file.
+    ///
+    /// Relative composition and SysEx file paths are resolved against the
+    /// set's directory; SysEx files are read here, so scenes send them
+    /// without touching the disk.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read live set: {}", path.display()))?;
+        let mut set =
+            Self::parse(&source).with_context(|| format!("Invalid live set: {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        if set.composition.is_relative() {
+            set.composition = dir.join(&set.composition);
+        }
+        for action in set.scenes.iter_mut().flat_map(|scene| scene.actions.iter_mut()) {
+            if let SceneAction::SysexFile { device, path } = action {
+                *action = SceneAction::SendMidi {
+                    device: device.clone(),
+                    messages: midi_patch::load_syx(&dir.join(&*path))?,
+                };
+            }
+        }
+        Ok(set)
+    }
+
+    /// Parse the contents of a live set file.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut set = LiveSet::default();
+        let mut section = Section::Preamble;
+
+        for (index, raw_line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |e: anyhow::Error| anyhow!("line {}: {}", line_number, e);
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| anyhow!("line {}: unterminated section header", line_number))?
+                    .trim();
+                section = parse_section(header, &mut set).map_err(at_line)?;
+                continue;
+            }
+
+            match section {
+                Section::Preamble => parse_setting(line, &mut set).map_err(at_line)?,
+                Section::Scene(index) => {
+                    let action = parse_action(line).map_err(at_line)?;
+                    set.scenes[index].actions.push(action);
+                }
+                Section::Cues => set.cues.push(line.to_string()),
+                Section::Bindings => set.bindings.push(parse_binding(line).map_err(at_line)?),
+            }
+        }
+
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Look up a scene by name.
+    pub fn scene(&self, name: &str) -> Option<&Scene> {
+        self.scenes.iter().find(|s| s.name == name)
+    }
+
+    /// Target bound to a TUI key.
+    pub fn key_target(&self, key: char) -> Option<&BindingTarget> {
+        self.bindings
+            .iter()
+            .find(|b| b.trigger == BindingTrigger::Key(key))
+            .map(|b| &b.target)
+    }
+
+    /// Targets bound to a MIDI note-on (channel 0-15).
+    pub fn note_targets(&self, channel: u8, note: u8) -> Vec<BindingTarget> {
+        self.bindings
+            .iter()
+            .filter(|b| match b.trigger {
+                BindingTrigger::Note { channel: ch, note: n } => {
+                    n == note && ch.is_none_or(|ch| ch == channel)
+                }
+                _ => false,
+            })
+            .map(|b| b.target.clone())
+            .collect()
+    }
+
+    /// Targets bound to a MIDI CC (channel 0-15).
+    pub fn cc_targets(&self, channel: u8, controller: u8) -> Vec<BindingTarget> {
+        self.bindings
+            .iter()
+            .filter(|b| match b.trigger {
+                BindingTrigger::Cc { channel: ch, controller: c } => {
+                    c == controller && ch.is_none_or(|ch| ch == channel)
+                }
+                _ => false,
+            })
+            .map(|b| b.target.clone())
+            .collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.composition.as_os_str().is_empty() {
+            bail!("missing `composition = \"<file>.vibe\"`");
+        }
+        for cue in &self.cues {
+            if self.scene(cue).is_none() {
+                bail!("cue refers to unknown scene '{}'", cue);
+            }
+        }
+        for binding in &self.bindings {
+            if let BindingTarget::Scene(name) = &binding.target {
+                if self.scene(name).is_none() {
+                    bail!("binding refers to unknown scene '{}'", name);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Section of a live set file currently being parsed.
+enum Section {
+    Preamble,
+    Scene(usize),
+    Cues,
+    Bindings,
+}
+
+/// Remove a trailing `#` comment outside of double quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_section(header: &str, set: &mut LiveSet) -> Result<Section> {
+    match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["cues"] => Ok(Section::Cues),
+        ["bindings"] => Ok(Section::Bindings),
+        ["scene", name] => {
+            if set.scene(name).is_some() {
+                bail!("duplicate scene '{}'", name);
+            }
+            set.scenes.push(Scene {
+                name: name.to_string(),
+                actions: Vec::new(),
+            });
+            Ok(Section::Scene(set.scenes.len() - 1))
+        }
+        _ => bail!("unknown section [{}]", header),
+    }
+}
+
+fn parse_setting(line: &str, set: &mut LiveSet) -> Result<()> {
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected `key = value`, got '{}'", line))?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    match key.trim() {
+        "composition" => set.composition = PathBuf::from(value),
+        other => bail!("unknown setting '{}'", other),
+    }
+    Ok(())
+}
+
+fn parse_action(line: &str) -> Result<SceneAction> {
+    if let Some(code) = line.strip_prefix("eval ") {
+        return Ok(SceneAction::Eval(code.trim().to_string()));
+    }
+
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let action = match words.as_slice() {
+        ["start", name] => SceneAction::Start(name.to_string()),
+        ["stop", name] => SceneAction::Stop(name.to_string()),
+        ["mute", group] => SceneAction::Mute(group.to_string()),
+        ["unmute", group] => SceneAction::Unmute(group.to_string()),
+        ["solo", group] => SceneAction::Solo(group.to_string()),
+        ["unsolo", group] => SceneAction::Unsolo(group.to_string()),
+        ["cue", graph] => SceneAction::CueGraph(graph.to_string()),
+        ["program", device, spec @ ..] if matches!(spec.len(), 2 | 3) => {
+            let channel = spec[0]
+                .parse::<u8>()
+                .ok()
+                .filter(|c| (1..=16).contains(c))
+                .ok_or_else(|| anyhow!("MIDI channel must be 1-16, got '{}'", spec[0]))?;
+            let number = |word: &str, max: u16, what: &str| {
+                word.parse::<u16>()
+                    .ok()
+                    .filter(|n| *n <= max)
+                    .ok_or_else(|| anyhow!("{} must be 0-{}, got '{}'", what, max, word))
+            };
+            let bank = match spec.len() {
+                3 => Some(number(spec[1], 16383, "bank")?),
+                _ => None,
+            };
+            let program = number(spec[spec.len() - 1], 127, "program")? as u8;
+            SceneAction::SendMidi {
+                device: device.to_string(),
+                messages: midi_patch::program_change(channel - 1, bank, program),
+            }
+        }
+        ["sysex", device, file] if file.ends_with(".syx") => SceneAction::SysexFile {
+            device: device.to_string(),
+            path: PathBuf::from(file),
+        },
+        ["sysex", device, bytes @ ..] if !bytes.is_empty() => SceneAction::SendMidi {
+            device: device.to_string(),
+            messages: midi_patch::parse_sysex_hex(&bytes.join(" "))?,
+        },
+        ["tempo", bpm] => {
+            let bpm: f64 = bpm.parse().map_err(|_| anyhow!("invalid tempo '{}'", bpm))?;
+            if bpm <= 0.0 {
+                bail!("tempo must be positive");
+            }
+            SceneAction::Tempo(bpm)
+        }
+        ["set", target, value] => {
+            let (group, param) = target
+                .rsplit_once('.')
+                .ok_or_else(|| anyhow!("expected `set <group>.<param> <value>`"))?;
+            let value = value.parse().map_err(|_| anyhow!("invalid value '{}'", value))?;
+            SceneAction::Set {
+                group: group.to_string(),
+                param: param.to_string(),
+                value,
+            }
+        }
+        ["fade", target, value, beats] => {
+            let (group, param) = target
+                .rsplit_once('.')
+                .ok_or_else(|| anyhow!("expected `fade <group>.<param> <value> <beats>`"))?;
+            let value = value.parse().map_err(|_| anyhow!("invalid value '{}'", value))?;
+            let beats: f64 = beats.parse().map_err(|_| anyhow!("invalid fade length '{}'", beats))?;
+            if beats < 0.0 {
+                bail!("fade length must not be negative");
+            }
+            SceneAction::Fade {
+                group: group.to_string(),
+                param: param.to_string(),
+                value,
+                beats,
+            }
+        }
+        _ => bail!("unknown scene action '{}'", line),
+    };
+    Ok(action)
+}
+
+fn parse_binding(line: &str) -> Result<Binding> {
+    let (trigger, target) = line
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected `<trigger> = <target>`, got '{}'", line))?;
+
+    let trigger = match trigger.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["key", key] => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => BindingTrigger::Key(c),
+                _ => bail!("key bindings take a single character, got '{}'", key),
+            }
+        }
+        ["note", spec] => {
+            let (channel, note) = parse_midi_spec(spec)?;
+            BindingTrigger::Note { channel, note }
+        }
+        ["cc", spec] => {
+            let (channel, controller) = parse_midi_spec(spec)?;
+            BindingTrigger::Cc { channel, controller }
+        }
+        _ => bail!("unknown binding trigger '{}'", trigger.trim()),
+    };
+
+    let target = match target.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["scene", name] => BindingTarget::Scene(name.to_string()),
+        ["go"] => BindingTarget::Go,
+        ["back"] => BindingTarget::Back,
+        ["looper", name, action] => BindingTarget::Looper {
+            name: name.to_string(),
+            action: LooperAction::parse(action)
+                .ok_or_else(|| anyhow!("looper actions are record, overdub and clear, got '{}'", action))?,
+        },
+        _ => bail!("unknown binding target '{}'", target.trim()),
+    };
+
+    Ok(Binding { trigger, target })
+}
+
+/// Parse `[<channel 1-16>:]<number 0-127>` into a 0-based channel and number.
+fn parse_midi_spec(spec: &str) -> Result<(Option<u8>, u8)> {
+    let (channel, number) = match spec.split_once(':') {
+        Some((channel, number)) => {
+            let channel: u8 = channel
+                .parse()
+                .ok()
+                .filter(|c| (1..=16).contains(c))
+                .ok_or_else(|| anyhow!("MIDI channel must be 1-16, got '{}'", channel))?;
+            (Some(channel - 1), number)
+        }
+        None => (None, spec),
+    };
+    let number: u8 = number
+        .parse()
+        .ok()
+        .filter(|n| *n <= 127)
+        .ok_or_else(|| anyhow!("MIDI number must be 0-127, got '{}'", number))?;
+    Ok((channel, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: &str = r#"
+# Friday show
+composition = "song.vibe"
+
+[scene intro]
+tempo 118
+start intro_seq
+mute Drums
+cue game
+
+[scene drop]
+stop intro_seq   # trailing comment
+set Bass.amp 0.8
+fade Pads.amp 0 8
+eval print("drop")
+
+[scene patches]
+program TR-8S 10 1 3
+sysex Prophet patches/drop.syx
+sysex Prophet 01 02
+
+[cues]
+intro
+drop
+
+[bindings]
+key 1 = scene intro
+key n = go
+note 10:36 = scene drop
+cc 64 = back
+key r = looper guitar overdub
+"#;
+
+    #[test]
+    fn test_parse_live_set() {
+        let set = LiveSet::parse(SET).expect("valid live set");
+        assert_eq!(set.composition, PathBuf::from("song.vibe"));
+        assert_eq!(set.cues, vec!["intro", "drop"]);
+        assert_eq!(
+            set.scene("intro").unwrap().actions,
+            vec![
+                SceneAction::Tempo(118.0),
+                SceneAction::Start("intro_seq".to_string()),
+                SceneAction::Mute("Drums".to_string()),
+                SceneAction::CueGraph("game".to_string()),
+            ]
+        );
+        assert_eq!(
+            set.scene("drop").unwrap().actions[1],
+            SceneAction::Set {
+                group: "Bass".to_string(),
+                param: "amp".to_string(),
+                value: 0.8,
+            }
+        );
+        assert_eq!(
+            set.scene("drop").unwrap().actions[2..],
+            [
+                SceneAction::Fade {
+                    group: "Pads".to_string(),
+                    param: "amp".to_string(),
+                    value: 0.0,
+                    beats: 8.0,
+                },
+                SceneAction::Eval("print(\"drop\")".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            set.scene("patches").unwrap().actions,
+            vec![
+                SceneAction::SendMidi {
+                    device: "TR-8S".to_string(),
+                    messages: vec![vec![0xB9, 0, 0], vec![0xB9, 32, 1], vec![0xC9, 3]],
+                },
+                SceneAction::SysexFile {
+                    device: "Prophet".to_string(),
+                    path: PathBuf::from("patches/drop.syx"),
+                },
+                SceneAction::SendMidi {
+                    device: "Prophet".to_string(),
+                    messages: vec![vec![0xF0, 0x01, 0x02, 0xF7]],
+                },
+            ]
+        );
+
+        assert_eq!(set.key_target('n'), Some(&BindingTarget::Go));
+        assert_eq!(set.key_target('x'), None);
+        assert_eq!(
+            set.note_targets(9, 36),
+            vec![BindingTarget::Scene("drop".to_string())]
+        );
+        assert!(set.note_targets(0, 36).is_empty());
+        assert_eq!(set.cc_targets(3, 64), vec![BindingTarget::Back]);
+        assert_eq!(
+            set.key_target('r'),
+            Some(&BindingTarget::Looper {
+                name: "guitar".to_string(),
+                action: LooperAction::Overdub,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_report_line() {
+        let err = LiveSet::parse("composition = \"a.vibe\"\n[scene a]\nexplode now\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{}", err);
+
+        let err = LiveSet::parse("composition = \"a.vibe\"\n[cues]\nmissing\n").unwrap_err();
+        assert!(err.to_string().contains("unknown scene 'missing'"), "{}", err);
+
+        let err = LiveSet::parse("[scene a]\n").unwrap_err();
+        assert!(err.to_string().contains("composition"), "{}", err);
+
+        let err = LiveSet::parse("composition = \"a.vibe\"\n[bindings]\nnote 17:1 = go\n").unwrap_err();
+        assert!(err.to_string().contains("1-16"), "{}", err);
+
+        let err = LiveSet::parse("composition = \"a.vibe\"\n[bindings]\nkey r = looper a undo\n").unwrap_err();
+        assert!(err.to_string().contains("record, overdub and clear"), "{}", err);
+    }
+}
@@ -0,0 +1,94 @@
+//! Song position locators.
+//!
+//! Scripts name positions in the song (`marker("drop", 64.bars)`) and the
+//! transport jumps to them on request. A jump normally waits for the next
+//! bar (or another grid) so it lands in time; until then it is pending.
+
+use std::collections::BTreeMap;
+
+/// A jump to a locator waiting for its launch point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingJump {
+    /// Name of the locator jumped to.
+    pub marker: String,
+    /// Transport beat the jump happens at.
+    pub at_beat: f64,
+    /// Beat the transport continues from.
+    pub target_beat: f64,
+}
+
+impl PendingJump {
+    /// Transport position after the jump when carried out at `beat`.
+    ///
+    /// Ticks rarely fall exactly on `at_beat`; the overshoot is carried over
+    /// so the song stays on the beat grid.
+    pub fn landing_beat(&self, beat: f64) -> f64 {
+        self.target_beat + (beat - self.at_beat).max(0.0)
+    }
+}
+
+/// Named song positions plus the jump waiting to happen, if any.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Locators {
+    markers: BTreeMap<String, f64>,
+    /// Jump waiting for its launch point.
+    pub pending: Option<PendingJump>,
+}
+
+impl Locators {
+    /// Set (or move) a locator.
+    pub fn set(&mut self, name: &str, beat: f64) {
+        self.markers.insert(name.to_string(), beat.max(0.0));
+    }
+
+    /// Remove a locator, returning whether it existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.markers.remove(name).is_some()
+    }
+
+    /// Position of a locator in beats.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.markers.get(name).copied()
+    }
+
+    /// All locators in song order.
+    pub fn sorted(&self) -> Vec<(String, f64)> {
+        let mut markers: Vec<(String, f64)> = self.markers.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        markers.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        markers
+    }
+
+    /// Whether no locators are set.
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locators_and_pending_jump() {
+        let mut locators = Locators::default();
+        locators.set("drop", 256.0);
+        locators.set("intro", 0.0);
+        locators.set("break", 128.0);
+        locators.set("drop", 192.0);
+        assert_eq!(locators.get("drop"), Some(192.0));
+        assert_eq!(
+            locators.sorted().iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+            vec!["intro", "break", "drop"]
+        );
+        assert!(locators.remove("break"));
+        assert!(!locators.remove("break"));
+
+        let jump = PendingJump {
+            marker: "drop".to_string(),
+            at_beat: 16.0,
+            target_beat: 192.0,
+        };
+        assert_eq!(jump.landing_beat(16.25), 192.25);
+        assert_eq!(jump.landing_beat(15.9), 192.0);
+    }
+}
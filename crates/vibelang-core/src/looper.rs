@@ -0,0 +1,265 @@
+//! Audio input loopers.
+//!
+//! A looper records a hardware input into a buffer for a number of bars,
+//! starting and ending on bar lines, then loops the buffer in time with the
+//! transport. Overdubbing records one more pass on top of the loop, aligned to
+//! the loop start; clearing stops playback and empties the buffer.
+//!
+//! The loop plays at the tempo it was recorded at, so a tempo change after
+//! recording drifts it out of sync until it is recorded again.
+
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the synthdef that records a hardware input into a looper buffer.
+pub const LOOPER_RECORDER_SYNTHDEF: &str = "system_looper_recorder";
+
+/// Name of the synthdef that loops a looper buffer onto a group bus.
+pub const LOOPER_PLAYER_SYNTHDEF: &str = "system_looper_player";
+
+/// Number of bars recorded when no length is given.
+pub const DEFAULT_LOOPER_BARS: u32 = 4;
+
+/// Maximum number of bars a looper can hold.
+pub const MAX_LOOPER_BARS: u32 = 32;
+
+/// Sample rate used to size looper buffers until scsynth has reported its own
+/// (see the freeze module).
+const FALLBACK_SAMPLE_RATE: f64 = 96_000.0;
+
+/// What a looper control does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LooperAction {
+    /// Record a new loop from the next bar line, replacing the current one.
+    Record,
+    /// Record one more pass on top of the loop from its next start.
+    Overdub,
+    /// Stop playback and empty the buffer.
+    Clear,
+}
+
+impl LooperAction {
+    /// Parse an action name ("record", "overdub" or "clear").
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "record" => Some(Self::Record),
+            "overdub" => Some(Self::Overdub),
+            "clear" => Some(Self::Clear),
+            _ => None,
+        }
+    }
+
+    /// Action name as written in live sets.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Record => "record",
+            Self::Overdub => "overdub",
+            Self::Clear => "clear",
+        }
+    }
+}
+
+/// Duration in seconds of a loop of `bars` bars.
+pub fn looper_duration_seconds(bars: u32, beats_per_bar: f64, tempo: f64) -> f64 {
+    bars as f64 * beats_per_bar * 60.0 / tempo.max(1.0)
+}
+
+/// Number of frames to allocate for a loop of the given duration at the
+/// server's nominal sample rate (`None` or 0 before its first status reply).
+pub fn looper_buffer_frames(seconds: f64, sample_rate: Option<f64>) -> i32 {
+    let rate = sample_rate.filter(|rate| *rate > 0.0).unwrap_or(FALLBACK_SAMPLE_RATE);
+    (seconds * rate).ceil() as i32
+}
+
+/// First loop start at or after `beat` for a loop anchored at `anchor_beat`.
+pub fn next_loop_start(anchor_beat: f64, loop_beats: f64, beat: f64) -> f64 {
+    if beat <= anchor_beat || loop_beats <= 0.0 {
+        return anchor_beat;
+    }
+    anchor_beat + ((beat - anchor_beat) / loop_beats).ceil() * loop_beats
+}
+
+/// Create and encode the looper recorder and player synthdefs.
+pub fn create_looper_synthdefs() -> Vec<(String, Vec<u8>)> {
+    let mut defs = Vec::new();
+    for (name, ir) in [
+        (LOOPER_RECORDER_SYNTHDEF, recorder_graph()),
+        (LOOPER_PLAYER_SYNTHDEF, player_graph()),
+    ] {
+        match encode_synthdef(&ir) {
+            Ok(bytes) => defs.push((name.to_string(), bytes)),
+            Err(e) => log::error!("[LOOPER] Failed to encode {} synthdef: {}", name, e),
+        }
+    }
+    defs
+}
+
+/// Recorder: SoundIn.ar(input) → RecordBuf (no loop).
+///
+/// Parameters:
+/// - input: hardware input channel, 0-based (0)
+/// - bufnum: target buffer (1)
+/// - pre: level of the existing buffer content, 1 to overdub (2)
+fn recorder_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("input".to_string(), vec![0.0], None); // 0
+    builder.add_param("bufnum".to_string(), vec![0.0], None); // 1
+    builder.add_param("pre".to_string(), vec![0.0], None); // 2
+    builder.create_control_ugen();
+
+    builder.add_constant(0.0);
+    builder.add_constant(1.0);
+
+    let input = builder.add_sound_in(Input::node(0, 0), 1);
+
+    builder.add_node(
+        "RecordBuf".to_string(),
+        Rate::Audio,
+        vec![
+            Input::node(0, 1),    // bufnum
+            Input::Constant(0.0), // offset
+            Input::Constant(1.0), // recLevel
+            Input::node(0, 2),    // preLevel
+            Input::Constant(1.0), // run
+            Input::Constant(0.0), // loop
+            Input::Constant(1.0), // trigger
+            Input::Constant(0.0), // doneAction (freed by the runtime)
+            Input::node(input.0, 0),
+        ],
+        1,
+        0,
+    );
+
+    GraphIR::from_builder(LOOPER_RECORDER_SYNTHDEF.to_string(), builder)
+}
+
+/// Player: Phasor over `dur` seconds → BufRd (looping) → Out.ar(out, [sig, sig] * amp).
+///
+/// Parameters:
+/// - out: group bus to write to (0)
+/// - bufnum: looper buffer (1)
+/// - dur: loop length in seconds (2)
+/// - amp: output gain (3)
+fn player_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("out".to_string(), vec![0.0], None); // 0
+    builder.add_param("bufnum".to_string(), vec![0.0], None); // 1
+    builder.add_param("dur".to_string(), vec![1.0], None); // 2
+    builder.add_param("amp".to_string(), vec![1.0], None); // 3
+    builder.create_control_ugen();
+
+    builder.add_constant(0.0);
+    builder.add_constant(1.0);
+    builder.add_constant(2.0);
+
+    // Loop length in frames: SampleRate.ir * dur
+    let sample_rate = builder.add_node("SampleRate".to_string(), Rate::Scalar, vec![], 1, 0);
+    let loop_frames = builder.add_node(
+        "BinaryOpUGen".to_string(),
+        Rate::Control,
+        vec![Input::node(sample_rate.0, 0), Input::node(0, 2)],
+        1,
+        2, // multiplication
+    );
+
+    let phase = builder.add_node(
+        "Phasor".to_string(),
+        Rate::Audio,
+        vec![
+            Input::Constant(0.0), // trig
+            Input::Constant(1.0), // rate
+            Input::Constant(0.0), // start
+            Input::node(loop_frames.0, 0),
+            Input::Constant(0.0), // resetPos
+        ],
+        1,
+        0,
+    );
+
+    let playback = builder.add_node(
+        "BufRd".to_string(),
+        Rate::Audio,
+        vec![
+            Input::node(0, 1),       // bufnum
+            Input::node(phase.0, 0), // phase
+            Input::Constant(1.0),    // loop
+            Input::Constant(2.0),    // linear interpolation
+        ],
+        1,
+        0,
+    );
+
+    let scaled = builder.add_node(
+        "BinaryOpUGen".to_string(),
+        Rate::Audio,
+        vec![Input::node(playback.0, 0), Input::node(0, 3)],
+        1,
+        2, // multiplication
+    );
+
+    builder.add_node(
+        "Out".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 0), Input::node(scaled.0, 0), Input::node(scaled.0, 0)],
+        0,
+        0,
+    );
+
+    GraphIR::from_builder(LOOPER_PLAYER_SYNTHDEF.to_string(), builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_loop_start() {
+        assert!((next_loop_start(16.0, 16.0, 10.0) - 16.0).abs() < 1e-9);
+        assert!((next_loop_start(16.0, 16.0, 16.0) - 16.0).abs() < 1e-9);
+        assert!((next_loop_start(16.0, 16.0, 17.0) - 32.0).abs() < 1e-9);
+        assert!((next_loop_start(16.0, 16.0, 48.0) - 48.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_looper_buffer_follows_server_sample_rate() {
+        assert_eq!(looper_buffer_frames(8.0, Some(48_000.0)), 384_000);
+        assert_eq!(looper_buffer_frames(8.0, None), 768_000);
+    }
+
+    #[test]
+    fn test_recorder_graph_records_the_hardware_input_once() {
+        // Parameter slots: input 0, bufnum 1, pre 2
+        let ir = recorder_graph();
+        assert!(ir.validate().is_ok());
+        let (_, bus) = ir.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(bus.inputs[1], Input::node(0, 0));
+        let (input, _) = ir.ugens("In").next().unwrap();
+
+        let (_, record) = ir.ugens("RecordBuf").next().unwrap();
+        assert_eq!(record.inputs[0], Input::node(0, 1));
+        // `pre` sets the preLevel, so overdubs keep the existing loop
+        assert_eq!(record.inputs[3], Input::node(0, 2));
+        assert_eq!(record.inputs[5], Input::Constant(0.0), "records once, no loop");
+        assert_eq!(record.inputs[8..], [Input::node(input, 0)]);
+    }
+
+    #[test]
+    fn test_player_graph_loops_dur_seconds_of_the_buffer() {
+        // Parameter slots: out 0, bufnum 1, dur 2, amp 3
+        let ir = player_graph();
+        assert!(ir.validate().is_ok());
+        let (sample_rate, _) = ir.ugens("SampleRate").next().unwrap();
+        let (frames, length) = ir.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(length.inputs, vec![Input::node(sample_rate, 0), Input::node(0, 2)]);
+        let (phasor, phase) = ir.ugens("Phasor").next().unwrap();
+        assert_eq!(phase.inputs[3], Input::node(frames, 0));
+
+        let (playback, read) = ir.ugens("BufRd").next().unwrap();
+        assert_eq!(read.inputs[..3], [Input::node(0, 1), Input::node(phasor, 0), Input::Constant(1.0)]);
+        let (scaled, gain) = ir.ugens("BinaryOpUGen").nth(1).unwrap();
+        assert_eq!(gain.inputs, vec![Input::node(playback, 0), Input::node(0, 3)]);
+        let (_, out) = ir.ugens("Out").next().unwrap();
+        assert_eq!(out.inputs, vec![Input::node(0, 0), Input::node(scaled, 0), Input::node(scaled, 0)]);
+    }
+}
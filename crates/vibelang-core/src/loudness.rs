@@ -0,0 +1,357 @@
+//! Loudness metering (ITU-R BS.1770 / EBU R128).
+//!
+//! A system synth after the main group taps the master bus, applies
+//! K-weighting and reports the mean square of each channel every 100ms via
+//! SendTrig. [`LoudnessMeter`] turns those 100ms blocks into momentary (400ms),
+//! short-term (3s) and gated integrated loudness.
+
+use std::collections::VecDeque;
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the loudness meter synthdef.
+pub const LOUDNESS_METER_SYNTHDEF: &str = "system_loudness_meter";
+
+/// SendTrig ID for the left channel mean square.
+pub const TRIG_MEAN_SQUARE_LEFT: i32 = 10;
+
+/// SendTrig ID for the right channel mean square (completes a block).
+pub const TRIG_MEAN_SQUARE_RIGHT: i32 = 11;
+
+/// Length of one measurement block in seconds.
+const BLOCK_SECONDS: f32 = 0.1;
+
+/// Number of blocks in the momentary (400ms) window.
+const MOMENTARY_BLOCKS: usize = 4;
+
+/// Number of blocks in the short-term (3s) window.
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Absolute gating threshold for integrated loudness.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// Relative gating offset for integrated loudness.
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Convert a summed, channel-weighted mean square to LUFS.
+pub fn power_to_lufs(power: f64) -> f64 {
+    if power <= 0.0 {
+        f64::NEG_INFINITY
+    } else {
+        -0.691 + 10.0 * power.log10()
+    }
+}
+
+/// Running loudness measurement built from 100ms mean-square blocks.
+#[derive(Clone, Debug, Default)]
+pub struct LoudnessMeter {
+    /// Most recent block powers (newest last), up to the short-term window.
+    recent: VecDeque<f64>,
+    /// Powers of all 400ms gating blocks that passed the absolute gate.
+    gating_blocks: Vec<f64>,
+    /// Left channel value waiting for its right channel partner.
+    pending_left: Option<f64>,
+    /// Highest momentary loudness since the last reset.
+    max_momentary: f64,
+}
+
+impl LoudnessMeter {
+    /// Create an empty meter.
+    pub fn new() -> Self {
+        Self {
+            max_momentary: f64::NEG_INFINITY,
+            ..Default::default()
+        }
+    }
+
+    /// Discard all measurements.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Handle a SendTrig value from the meter synth.
+    ///
+    /// Returns `true` when a block was completed.
+    pub fn handle_trigger(&mut self, trig_id: i32, value: f32) -> bool {
+        match trig_id {
+            TRIG_MEAN_SQUARE_LEFT => {
+                self.pending_left = Some(value as f64);
+                false
+            }
+            TRIG_MEAN_SQUARE_RIGHT => {
+                let left = self.pending_left.take().unwrap_or(0.0);
+                self.push_block(left, value as f64);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Add a 100ms block given the mean square of each channel.
+    pub fn push_block(&mut self, left: f64, right: f64) {
+        self.recent.push_back(left.max(0.0) + right.max(0.0));
+        if self.recent.len() > SHORT_TERM_BLOCKS {
+            self.recent.pop_front();
+        }
+
+        // Every block completes a 400ms gating block with 75% overlap
+        if self.recent.len() >= MOMENTARY_BLOCKS {
+            let power = self.window_power(MOMENTARY_BLOCKS);
+            let lufs = power_to_lufs(power);
+            if lufs > ABSOLUTE_GATE_LUFS {
+                self.gating_blocks.push(power);
+            }
+            self.max_momentary = self.max_momentary.max(lufs);
+        }
+    }
+
+    fn window_power(&self, blocks: usize) -> f64 {
+        let n = blocks.min(self.recent.len());
+        if n == 0 {
+            return 0.0;
+        }
+        self.recent.iter().rev().take(n).sum::<f64>() / n as f64
+    }
+
+    /// Momentary loudness (400ms window).
+    pub fn momentary(&self) -> f64 {
+        power_to_lufs(self.window_power(MOMENTARY_BLOCKS))
+    }
+
+    /// Short-term loudness (3s window).
+    pub fn short_term(&self) -> f64 {
+        power_to_lufs(self.window_power(SHORT_TERM_BLOCKS))
+    }
+
+    /// Highest momentary loudness since the last reset.
+    pub fn max_momentary(&self) -> f64 {
+        self.max_momentary
+    }
+
+    /// Gated integrated loudness since the last reset.
+    pub fn integrated(&self) -> f64 {
+        if self.gating_blocks.is_empty() {
+            return f64::NEG_INFINITY;
+        }
+        let ungated = self.gating_blocks.iter().sum::<f64>() / self.gating_blocks.len() as f64;
+        let relative_gate = power_to_lufs(ungated) + RELATIVE_GATE_LU;
+
+        let (sum, count) = self
+            .gating_blocks
+            .iter()
+            .filter(|&&p| power_to_lufs(p) > relative_gate)
+            .fold((0.0, 0usize), |(sum, count), p| (sum + p, count + 1));
+        if count == 0 {
+            f64::NEG_INFINITY
+        } else {
+            power_to_lufs(sum / count as f64)
+        }
+    }
+}
+
+/// Create the loudness meter synthdef.
+pub fn create_loudness_meter_synthdef() -> Option<(String, Vec<u8>)> {
+    match encode_synthdef(&meter_graph()) {
+        Ok(bytes) => Some((LOUDNESS_METER_SYNTHDEF.to_string(), bytes)),
+        Err(e) => {
+            log::error!("[LOUDNESS] Failed to encode meter synthdef: {}", e);
+            None
+        }
+    }
+}
+
+/// Signal flow (per channel of bus 0/1):
+///   In.ar → BHiShelf(1682Hz, +4dB) → HPF(38Hz) → squared
+///         → RunningSum(100ms) / n → SendTrig at 10Hz
+///
+/// The two filters approximate the BS.1770 K-weighting curve.
+fn meter_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("inbus".to_string(), vec![0.0], None); // 0
+    builder.create_control_ugen();
+
+    let input = builder.add_node(
+        "In".to_string(),
+        Rate::Audio,
+        vec![Input::node(0, 0)],
+        2,
+        0,
+    );
+
+    // Block length in samples: SampleRate.ir * 0.1
+    let sample_rate = builder.add_node("SampleRate".to_string(), Rate::Scalar, vec![], 1, 0);
+    builder.add_constant(BLOCK_SECONDS);
+    let block_samples = builder.add_node(
+        "BinaryOpUGen".to_string(),
+        Rate::Scalar,
+        vec![Input::node(sample_rate.0, 0), Input::Constant(BLOCK_SECONDS)],
+        1,
+        2, // multiplication
+    );
+
+    builder.add_constant(1.0 / BLOCK_SECONDS);
+    let impulse = builder.add_node(
+        "Impulse".to_string(),
+        Rate::Control,
+        vec![Input::Constant(1.0 / BLOCK_SECONDS), Input::Constant(0.0)],
+        1,
+        0,
+    );
+
+    for &c in &[1681.97f32, 1.0, 4.0, 38.13, 0.0] {
+        builder.add_constant(c);
+    }
+
+    for (channel, trig_id) in [(0u32, TRIG_MEAN_SQUARE_LEFT), (1, TRIG_MEAN_SQUARE_RIGHT)] {
+        // Stage 1: high shelf (head effects)
+        let shelf = builder.add_node(
+            "BHiShelf".to_string(),
+            Rate::Audio,
+            vec![
+                Input::node(input.0, channel),
+                Input::Constant(1681.97),
+                Input::Constant(1.0),
+                Input::Constant(4.0),
+            ],
+            1,
+            0,
+        );
+        // Stage 2: high pass (RLB weighting)
+        let hpf = builder.add_node(
+            "HPF".to_string(),
+            Rate::Audio,
+            vec![Input::node(shelf.0, 0), Input::Constant(38.13)],
+            1,
+            0,
+        );
+        let squared = builder.add_node(
+            "BinaryOpUGen".to_string(),
+            Rate::Audio,
+            vec![Input::node(hpf.0, 0), Input::node(hpf.0, 0)],
+            1,
+            2, // multiplication
+        );
+        let sum = builder.add_node(
+            "RunningSum".to_string(),
+            Rate::Audio,
+            vec![Input::node(squared.0, 0), Input::node(block_samples.0, 0)],
+            1,
+            0,
+        );
+        let mean_square = builder.add_node(
+            "BinaryOpUGen".to_string(),
+            Rate::Audio,
+            vec![Input::node(sum.0, 0), Input::node(block_samples.0, 0)],
+            1,
+            4, // division
+        );
+        builder.add_constant(trig_id as f32);
+        builder.add_node(
+            "SendTrig".to_string(),
+            Rate::Control,
+            vec![
+                Input::node(impulse.0, 0),
+                Input::Constant(trig_id as f32),
+                Input::node(mean_square.0, 0),
+            ],
+            0,
+            0,
+        );
+    }
+
+    GraphIR::from_builder(LOUDNESS_METER_SYNTHDEF.to_string(), builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mean square of a full-scale sine on one channel.
+    const SINE_MS: f64 = 0.5;
+
+    #[test]
+    fn test_power_to_lufs() {
+        assert!((power_to_lufs(1.0) - (-0.691)).abs() < 1e-9);
+        assert_eq!(power_to_lufs(0.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_momentary_and_short_term_windows() {
+        let mut meter = LoudnessMeter::new();
+        for _ in 0..SHORT_TERM_BLOCKS {
+            meter.push_block(SINE_MS, 0.0);
+        }
+        let expected = power_to_lufs(SINE_MS);
+        assert!((meter.momentary() - expected).abs() < 1e-9);
+        assert!((meter.short_term() - expected).abs() < 1e-9);
+
+        // Silence for 400ms drops momentary but not short-term to -inf
+        for _ in 0..MOMENTARY_BLOCKS {
+            meter.push_block(0.0, 0.0);
+        }
+        assert_eq!(meter.momentary(), f64::NEG_INFINITY);
+        assert!(meter.short_term() > f64::NEG_INFINITY);
+        assert!((meter.max_momentary() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_integrated_gating() {
+        let mut meter = LoudnessMeter::new();
+        let loud = 10f64.powf((-20.0 + 0.691) / 10.0);
+        let quiet = 10f64.powf((-40.0 + 0.691) / 10.0);
+
+        for _ in 0..100 {
+            meter.push_block(loud, 0.0);
+        }
+        // Quiet passages more than 10 LU below are excluded by the relative gate
+        for _ in 0..100 {
+            meter.push_block(quiet, 0.0);
+        }
+        // Silence is excluded by the absolute gate
+        for _ in 0..100 {
+            meter.push_block(0.0, 0.0);
+        }
+
+        let integrated = meter.integrated();
+        assert!((integrated - -20.0).abs() < 0.5, "integrated = {}", integrated);
+
+        meter.reset();
+        assert_eq!(meter.integrated(), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_meter_graph_k_weights_each_channel() {
+        let ir = meter_graph();
+        assert!(ir.validate().is_ok());
+        let (input, bus) = ir.ugens("In").next().unwrap();
+        assert_eq!((bus.inputs.clone(), bus.num_outputs), (vec![Input::node(0, 0)], 2));
+
+        let shelves: Vec<_> = ir.ugens("BHiShelf").collect();
+        let hpfs: Vec<_> = ir.ugens("HPF").collect();
+        let triggers: Vec<_> = ir.ugens("SendTrig").collect();
+        assert_eq!((shelves.len(), hpfs.len(), triggers.len()), (2, 2, 2));
+        for (channel, trig_id) in [(0, TRIG_MEAN_SQUARE_LEFT), (1, TRIG_MEAN_SQUARE_RIGHT)] {
+            let (shelf, node) = shelves[channel];
+            assert_eq!(node.inputs[0], Input::node(input, channel as u32));
+            assert_eq!(node.inputs[1], Input::Constant(1681.97));
+            let (_, hpf) = hpfs[channel];
+            assert_eq!(hpf.inputs, vec![Input::node(shelf, 0), Input::Constant(38.13)]);
+            assert_eq!(triggers[channel].1.inputs[1], Input::Constant(trig_id as f32));
+        }
+
+        // Mean squares are reported ten times a second
+        let (impulse, pulse) = ir.ugens("Impulse").next().unwrap();
+        assert_eq!(pulse.inputs[0], Input::Constant(1.0 / BLOCK_SECONDS));
+        assert!(triggers.iter().all(|(_, trig)| trig.inputs[0] == Input::node(impulse, 0)));
+    }
+
+    #[test]
+    fn test_trigger_pairs() {
+        let mut meter = LoudnessMeter::new();
+        assert!(!meter.handle_trigger(TRIG_MEAN_SQUARE_LEFT, 0.25));
+        assert!(meter.handle_trigger(TRIG_MEAN_SQUARE_RIGHT, 0.25));
+        assert!(!meter.handle_trigger(3, 1.0));
+        assert_eq!(meter.recent.len(), 1);
+        assert!((meter.recent[0] - 0.5).abs() < 1e-9);
+    }
+}
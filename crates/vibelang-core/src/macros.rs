@@ -0,0 +1,97 @@
+//! Macro controls.
+//!
+//! A macro is one normalized control (0..1) mapped onto parameters of many
+//! voices, groups, patterns, melodies and effects, each with its own range.
+//! Setting the macro writes every mapped parameter, so a single "intensity"
+//! knob can open a filter on one voice while raising the level of a group.
+
+use crate::events::FadeTargetType;
+
+/// A macro control and its parameter mappings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroControl {
+    /// Macro name.
+    pub name: String,
+    /// Parameters driven by the macro.
+    pub targets: Vec<MacroTarget>,
+    /// Current normalized value (0..1).
+    pub value: f64,
+}
+
+/// A parameter driven by a macro.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroTarget {
+    /// Kind of entity the parameter belongs to.
+    pub target_type: FadeTargetType,
+    /// Entity name (group path, voice, pattern, melody or effect id).
+    pub name: String,
+    /// Parameter name.
+    pub param: String,
+    /// Parameter value at macro value 0.
+    pub min: f32,
+    /// Parameter value at macro value 1.
+    pub max: f32,
+}
+
+impl MacroTarget {
+    /// Parameter value for a normalized macro value.
+    pub fn value_at(&self, value: f64) -> f32 {
+        self.min + (self.max - self.min) * value.clamp(0.0, 1.0) as f32
+    }
+}
+
+impl MacroControl {
+    /// Create a macro without mappings, at value 0.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            targets: Vec::new(),
+            value: 0.0,
+        }
+    }
+
+    /// Set the normalized value, clamped to 0..1.
+    pub fn set(&mut self, value: f64) {
+        self.value = if value.is_finite() { value.clamp(0.0, 1.0) } else { 0.0 };
+    }
+
+    /// Parameter writes for the current value.
+    pub fn writes(&self) -> impl Iterator<Item = (&MacroTarget, f32)> {
+        self.targets.iter().map(move |t| (t, t.value_at(self.value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_writes() {
+        let mut control = MacroControl::new("intensity");
+        control.targets.push(MacroTarget {
+            target_type: FadeTargetType::Voice,
+            name: "lead".to_string(),
+            param: "cutoff".to_string(),
+            min: 200.0,
+            max: 4000.0,
+        });
+        control.targets.push(MacroTarget {
+            target_type: FadeTargetType::Group,
+            name: "main/drums".to_string(),
+            param: "amp".to_string(),
+            min: 1.0,
+            max: 0.5,
+        });
+
+        control.set(0.5);
+        let writes: Vec<f32> = control.writes().map(|(_, v)| v).collect();
+        assert_eq!(writes, vec![2100.0, 0.75]);
+
+        control.set(3.0);
+        assert_eq!(control.value, 1.0);
+        assert_eq!(control.writes().next().unwrap().1, 4000.0);
+
+        control.set(f64::NAN);
+        assert_eq!(control.value, 0.0);
+    }
+}
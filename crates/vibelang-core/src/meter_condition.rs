@@ -0,0 +1,137 @@
+//! Meter-driven trigger conditions.
+//!
+//! A pattern or melody with conditions only fires an event when every
+//! condition holds at fire time, e.g. a fill that plays only while the bass
+//! group is quiet. Conditions compare a group's meter (peak or RMS, the louder
+//! of both channels, linear amplitude) against a threshold.
+
+use crate::state::MeterLevel;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Meter readings older than this count as silence (the group stopped reporting).
+const STALE_METER: Duration = Duration::from_secs(1);
+
+/// Which meter reading a condition looks at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeterMeasure {
+    /// Peak level.
+    Peak,
+    /// RMS level.
+    Rms,
+}
+
+/// How the reading is compared with the threshold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Operator symbol.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// A condition on a group's meter level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterCondition {
+    /// Group whose meter is read.
+    pub group_path: String,
+    /// Peak or RMS.
+    pub measure: MeterMeasure,
+    /// Comparison with the threshold.
+    pub comparison: Comparison,
+    /// Threshold in linear amplitude.
+    pub threshold: f32,
+}
+
+impl MeterCondition {
+    /// Current level of the condition's meter (0 if missing or stale).
+    pub fn level(&self, meters: &HashMap<String, MeterLevel>, now: Instant) -> f32 {
+        let Some(meter) = meters.get(&self.group_path) else {
+            return 0.0;
+        };
+        if meter.last_update.is_none_or(|t| now.duration_since(t) > STALE_METER) {
+            return 0.0;
+        }
+        match self.measure {
+            MeterMeasure::Peak => meter.peak_left.max(meter.peak_right),
+            MeterMeasure::Rms => meter.rms_left.max(meter.rms_right),
+        }
+    }
+
+    /// Whether the condition holds for the current meter levels.
+    pub fn holds(&self, meters: &HashMap<String, MeterLevel>, now: Instant) -> bool {
+        let level = self.level(meters, now);
+        match self.comparison {
+            Comparison::Less => level < self.threshold,
+            Comparison::LessOrEqual => level <= self.threshold,
+            Comparison::Greater => level > self.threshold,
+            Comparison::GreaterOrEqual => level >= self.threshold,
+        }
+    }
+}
+
+impl std::fmt::Display for MeterCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let measure = match self.measure {
+            MeterMeasure::Peak => "peak",
+            MeterMeasure::Rms => "rms",
+        };
+        write!(
+            f,
+            "meter(\"{}\").{}() {} {}",
+            self.group_path,
+            measure,
+            self.comparison.symbol(),
+            self.threshold
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_condition() {
+        let now = Instant::now();
+        let mut meters = HashMap::new();
+        meters.insert(
+            "main/Bass".to_string(),
+            MeterLevel {
+                peak_left: 0.5,
+                peak_right: 0.3,
+                rms_left: 0.05,
+                rms_right: 0.08,
+                last_update: Some(now),
+            },
+        );
+        let quiet = MeterCondition {
+            group_path: "main/Bass".to_string(),
+            measure: MeterMeasure::Peak,
+            comparison: Comparison::Less,
+            threshold: 0.1,
+        };
+        assert!(!quiet.holds(&meters, now));
+        assert!(MeterCondition { measure: MeterMeasure::Rms, ..quiet.clone() }.holds(&meters, now));
+
+        // Missing or stale meters read as silence
+        assert!(MeterCondition { group_path: "main/Pads".to_string(), ..quiet.clone() }.holds(&meters, now));
+        assert!(quiet.holds(&meters, now + Duration::from_secs(2)));
+    }
+}
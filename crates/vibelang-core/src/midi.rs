@@ -88,6 +88,8 @@ pub enum MidiMessage {
     Stop { timestamp: u64 },
     /// Continue playback
     Continue { timestamp: u64 },
+    /// Song position pointer, in sixteenth notes since the song start
+    SongPosition { position: u16, timestamp: u64 },
 }
 
 impl MidiMessage {
@@ -105,6 +107,10 @@ impl MidiMessage {
             0xFA => return Some(MidiMessage::Start { timestamp }),
             0xFB => return Some(MidiMessage::Continue { timestamp }),
             0xFC => return Some(MidiMessage::Stop { timestamp }),
+            0xF2 if bytes.len() >= 3 => {
+                let position = (bytes[1] as u16 & 0x7F) | ((bytes[2] as u16 & 0x7F) << 7);
+                return Some(MidiMessage::SongPosition { position, timestamp });
+            }
             _ => {}
         }
 
@@ -333,6 +339,9 @@ pub struct CcRoute {
     pub curve: ParameterCurve,
     /// MIDI channel filter (None = all channels)
     pub channel: Option<u8>,
+    /// Scale over the range the target's synthdef declares for the
+    /// parameter instead of min/max.
+    pub param_range: bool,
 }
 
 impl CcRoute {
@@ -345,6 +354,7 @@ impl CcRoute {
             max_value: max,
             curve: ParameterCurve::default(),
             channel: None,
+            param_range: false,
         }
     }
 
@@ -357,6 +367,7 @@ impl CcRoute {
             max_value: max,
             curve: ParameterCurve::default(),
             channel: None,
+            param_range: false,
         }
     }
 
@@ -369,6 +380,7 @@ impl CcRoute {
             max_value: max,
             curve: ParameterCurve::default(),
             channel: None,
+            param_range: false,
         }
     }
 
@@ -390,6 +402,8 @@ pub enum CcTarget {
     Group(String),
     /// Global parameter (tempo, master volume, etc.)
     Global(String),
+    /// Macro control (route range should be 0..1)
+    Macro(String),
 }
 
 /// A pending MIDI callback waiting to be executed.
@@ -459,6 +473,8 @@ pub struct MidiRouting {
     pub pending_callbacks: Vec<PendingMidiCallback>,
     /// Enable MIDI monitoring (print all events)
     pub monitor_enabled: bool,
+    /// Latency of hardware on MIDI outputs: output device_id -> milliseconds
+    pub device_latency_ms: HashMap<u32, f64>,
 }
 
 impl MidiRouting {
@@ -538,6 +554,20 @@ impl MidiRouting {
         routes
     }
 
+    /// Set the latency of the hardware on an output device (0 clears it).
+    pub fn set_device_latency(&mut self, device_id: u32, ms: f64) {
+        if ms > 0.0 {
+            self.device_latency_ms.insert(device_id, ms);
+        } else {
+            self.device_latency_ms.remove(&device_id);
+        }
+    }
+
+    /// Latency of the hardware on an output device (0 if not set).
+    pub fn device_latency(&self, device_id: u32) -> f64 {
+        self.device_latency_ms.get(&device_id).copied().unwrap_or(0.0)
+    }
+
     /// Clear all routes.
     pub fn clear(&mut self) {
         self.keyboard_routes.clear();
@@ -550,6 +580,7 @@ impl MidiRouting {
         self.cc_callbacks.clear();
         self.last_cc_values.clear();
         self.pending_callbacks.clear();
+        self.device_latency_ms.clear();
     }
 
     /// Queue a callback for execution.
@@ -1722,6 +1753,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_song_position() {
+        let bytes = [0xF2, 0x10, 0x01]; // Song position 16 + 128 sixteenths
+        match MidiMessage::from_bytes(&bytes, 0).unwrap() {
+            MidiMessage::SongPosition { position, .. } => assert_eq!(position, 144),
+            _ => panic!("Expected SongPosition"),
+        }
+    }
+
     #[test]
     fn test_velocity_curves() {
         let linear = VelocityCurve::Linear;
@@ -0,0 +1,108 @@
+//! Patch switching for external MIDI gear.
+//!
+//! Builds program changes (with optional bank select) and reads SysEx
+//! messages from hex text or `.syx` dumps, so hardware synths can be switched
+//! to the right patch per song section, from scripts or live set scenes.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+
+/// SysEx start byte.
+const SYSEX_START: u8 = 0xF0;
+
+/// SysEx end byte.
+const SYSEX_END: u8 = 0xF7;
+
+/// Raw MIDI messages for a program change on `channel` (0-15).
+///
+/// With a bank (0-16383), bank select MSB (CC 0) and LSB (CC 32) come first.
+pub fn program_change(channel: u8, bank: Option<u16>, program: u8) -> Vec<Vec<u8>> {
+    let status = channel & 0x0F;
+    let mut messages = Vec::new();
+    if let Some(bank) = bank {
+        messages.push(vec![0xB0 | status, 0, ((bank >> 7) & 0x7F) as u8]);
+        messages.push(vec![0xB0 | status, 32, (bank & 0x7F) as u8]);
+    }
+    messages.push(vec![0xC0 | status, program & 0x7F]);
+    messages
+}
+
+/// Split raw bytes into SysEx messages, checking each is `F0 <7-bit data> F7`.
+pub fn parse_sysex(bytes: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    for (offset, &byte) in bytes.iter().enumerate() {
+        match (byte, current.as_mut()) {
+            (SYSEX_START, None) => current = Some(vec![byte]),
+            (SYSEX_END, Some(message)) => {
+                message.push(byte);
+                messages.push(current.take().unwrap_or_default());
+            }
+            (0x00..=0x7F, Some(message)) => message.push(byte),
+            (_, Some(_)) => bail!("byte {}: unexpected 0x{:02X} inside a SysEx message", offset, byte),
+            (_, None) => bail!("byte {}: expected 0xF0 to start a SysEx message, got 0x{:02X}", offset, byte),
+        }
+    }
+    if current.is_some() {
+        bail!("SysEx message is missing its closing 0xF7");
+    }
+    if messages.is_empty() {
+        bail!("no SysEx messages found");
+    }
+    Ok(messages)
+}
+
+/// Parse SysEx messages from hex text like `F0 41 10 42 12 F7`.
+///
+/// The start and end bytes are added if they are left out (one message).
+pub fn parse_sysex_hex(text: &str) -> Result<Vec<Vec<u8>>> {
+    let bytes = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let digits = word.trim_start_matches("0x").trim_start_matches("0X");
+            u8::from_str_radix(digits, 16).map_err(|_| anyhow!("invalid hex byte '{}'", word))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    frame_sysex(bytes)
+}
+
+/// Like [`parse_sysex`], adding the start and end bytes if they are left out.
+pub fn frame_sysex(mut bytes: Vec<u8>) -> Result<Vec<Vec<u8>>> {
+    if bytes.first() != Some(&SYSEX_START) {
+        bytes.insert(0, SYSEX_START);
+    }
+    if bytes.last() != Some(&SYSEX_END) {
+        bytes.push(SYSEX_END);
+    }
+    parse_sysex(&bytes)
+}
+
+/// Read the SysEx messages of a `.syx` file.
+pub fn load_syx(path: &Path) -> Result<Vec<Vec<u8>>> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read SysEx file: {}", path.display()))?;
+    parse_sysex(&bytes).with_context(|| format!("Invalid SysEx file: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_change_with_bank() {
+        assert_eq!(program_change(9, None, 5), vec![vec![0xC9, 5]]);
+        assert_eq!(
+            program_change(0, Some(130), 12),
+            vec![vec![0xB0, 0, 1], vec![0xB0, 32, 2], vec![0xC0, 12]]
+        );
+    }
+
+    #[test]
+    fn test_parse_sysex() {
+        let dump = [0xF0, 0x41, 0x10, 0xF7, 0xF0, 0x7E, 0xF7];
+        assert_eq!(parse_sysex(&dump).unwrap(), vec![vec![0xF0, 0x41, 0x10, 0xF7], vec![0xF0, 0x7E, 0xF7]]);
+        assert!(parse_sysex(&[0xF0, 0x41]).is_err());
+        assert!(parse_sysex(&[0xF0, 0x90, 0xF7]).is_err());
+        assert_eq!(parse_sysex_hex("41 10 42").unwrap(), vec![vec![0xF0, 0x41, 0x10, 0x42, 0xF7]]);
+    }
+}
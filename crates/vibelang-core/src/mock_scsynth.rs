@@ -0,0 +1,1062 @@
+//! In-process stand-in for scsynth.
+//!
+//! [`MockScsynth`] listens on a local UDP port and answers the OSC commands
+//! the runtime sends (`/notify`, `/s_new`, `/n_set`, `/b_allocRead`, `/sync`,
+//! `/status`, ...) the way scsynth would, without producing any audio. Every
+//! received message is recorded in a timeline with its bundle timetag, and a
+//! node tree is kept up to date, so tests can drive a full [`Runtime`]
+//! (`Runtime::start_mock`) and check scheduling, fades and reload logic
+//! without a SuperCollider installation.
+//!
+//! Timed bundles take effect on the node tree when they are due, like on
+//! the real server; the timeline shows them as soon as they arrive.
+//! `/b_write` writes a silent WAV file of the buffer's allocated size.
+//!
+//! Available in tests and with the `mock-scsynth` feature.
+//!
+//! [`Runtime`]: crate::runtime::Runtime
+
+use anyhow::{Context, Result};
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::score::extract_synthdef_name;
+
+/// First ID handed out for nodes created with ID -1.
+const AUTO_NODE_ID_START: i32 = 1_000_000;
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch.
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Sample rate the mock reports and writes buffers at.
+const MOCK_SAMPLE_RATE: u32 = 48_000;
+
+/// How long the server thread waits for a packet before running due bundles.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A message received by the mock server.
+#[derive(Debug, Clone)]
+pub struct MockEvent {
+    /// When the message arrived, relative to the start of the server.
+    pub received: Duration,
+    /// When the message takes effect, relative to the start of the server.
+    pub due: Duration,
+    /// Timetag of the bundle the message came in (`None` for plain messages).
+    pub timetag: Option<OscTime>,
+    /// The message itself.
+    pub message: OscMessage,
+}
+
+impl MockEvent {
+    /// OSC address of the message.
+    pub fn addr(&self) -> &str {
+        &self.message.addr
+    }
+
+    /// Integer argument at `index`.
+    pub fn int(&self, index: usize) -> Option<i32> {
+        match self.message.args.get(index)? {
+            OscType::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// String argument at `index`.
+    pub fn string(&self, index: usize) -> Option<&str> {
+        match self.message.args.get(index)? {
+            OscType::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Value of a named control in the message's `name value` pairs.
+    pub fn control(&self, name: &str) -> Option<f32> {
+        self.message
+            .args
+            .windows(2)
+            .find(|pair| matches!(&pair[0], OscType::String(s) if s == name))
+            .and_then(|pair| number(&pair[1]))
+    }
+}
+
+/// A node on the mock server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockNode {
+    /// Node ID.
+    pub id: i32,
+    /// Synthdef of a synth (`None` for groups).
+    pub synthdef: Option<String>,
+    /// Parent group (`None` for the root group).
+    pub parent: Option<i32>,
+    /// Controls set by `/s_new` and `/n_set`.
+    pub controls: BTreeMap<String, f32>,
+}
+
+impl MockNode {
+    /// Whether the node is a group.
+    pub fn is_group(&self) -> bool {
+        self.synthdef.is_none()
+    }
+}
+
+/// A bundle waiting for its timetag.
+struct PendingMessage {
+    due: Instant,
+    message: OscMessage,
+    client: SocketAddr,
+}
+
+/// Server state shared between the server thread and the test.
+struct MockServer {
+    started: Instant,
+    timeline: Vec<MockEvent>,
+    pending: Vec<PendingMessage>,
+    /// Node tree in creation order within each group.
+    nodes: BTreeMap<i32, MockNode>,
+    order: Vec<i32>,
+    buffers: BTreeMap<i32, String>,
+    /// Frames and channels of buffers allocated with `/b_alloc`.
+    allocated: BTreeMap<i32, (u32, u16)>,
+    synthdefs: BTreeSet<String>,
+    next_auto_id: i32,
+}
+
+/// An in-process OSC responder that behaves like scsynth.
+pub struct MockScsynth {
+    addr: SocketAddr,
+    server: Arc<Mutex<MockServer>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockScsynth {
+    /// Start a mock server on a free local port.
+    pub fn start() -> Result<Self> {
+        let socket = UdpSocket::bind("127.0.0.1:0").context("Failed to bind mock scsynth socket")?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let addr = socket.local_addr()?;
+
+        let mut root = MockServer {
+            started: Instant::now(),
+            timeline: Vec::new(),
+            pending: Vec::new(),
+            nodes: BTreeMap::new(),
+            order: Vec::new(),
+            buffers: BTreeMap::new(),
+            allocated: BTreeMap::new(),
+            synthdefs: BTreeSet::new(),
+            next_auto_id: AUTO_NODE_ID_START,
+        };
+        root.insert_node(MockNode {
+            id: 0,
+            synthdef: None,
+            parent: None,
+            controls: BTreeMap::new(),
+        });
+        let server = Arc::new(Mutex::new(root));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread_server = server.clone();
+        let thread_shutdown = shutdown.clone();
+        let thread = thread::Builder::new()
+            .name("mock-scsynth".to_string())
+            .spawn(move || serve(socket, thread_server, thread_shutdown))?;
+        log::debug!("[MOCK] scsynth mock listening on {}", addr);
+
+        Ok(Self {
+            addr,
+            server,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the mock listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Every message received so far, in arrival order.
+    pub fn timeline(&self) -> Vec<MockEvent> {
+        self.lock().timeline.clone()
+    }
+
+    /// Received messages with the given OSC address.
+    pub fn messages(&self, addr: &str) -> Vec<MockEvent> {
+        self.lock().timeline.iter().filter(|e| e.message.addr == addr).cloned().collect()
+    }
+
+    /// `/s_new` messages of a synthdef, in arrival order.
+    pub fn synths_started(&self, synthdef: &str) -> Vec<MockEvent> {
+        self.lock()
+            .timeline
+            .iter()
+            .filter(|e| e.message.addr == "/s_new" && e.string(0) == Some(synthdef))
+            .cloned()
+            .collect()
+    }
+
+    /// Forget the messages received so far (the node tree is kept).
+    pub fn clear_timeline(&self) {
+        self.lock().timeline.clear();
+    }
+
+    /// Nodes currently on the server, in creation order.
+    pub fn nodes(&self) -> Vec<MockNode> {
+        let server = self.lock();
+        server.order.iter().filter_map(|id| server.nodes.get(id).cloned()).collect()
+    }
+
+    /// A node currently on the server.
+    pub fn node(&self, id: i32) -> Option<MockNode> {
+        self.lock().nodes.get(&id).cloned()
+    }
+
+    /// Buffers allocated with `/b_allocRead`, by buffer number.
+    pub fn buffers(&self) -> BTreeMap<i32, String> {
+        self.lock().buffers.clone()
+    }
+
+    /// Names of the synthdefs received with `/d_recv`.
+    pub fn synthdefs(&self) -> BTreeSet<String> {
+        self.lock().synthdefs.clone()
+    }
+
+    /// Poll `condition` until it holds or `timeout` passes.
+    pub fn wait_until(&self, timeout: Duration, mut condition: impl FnMut(&Self) -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if condition(self) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockServer> {
+        self.server.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockScsynth {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Server thread: receive packets, run due bundles, send replies.
+fn serve(socket: UdpSocket, server: Arc<Mutex<MockServer>>, shutdown: Arc<AtomicBool>) {
+    let mut buf = vec![0u8; 65536];
+    while !shutdown.load(Ordering::Relaxed) {
+        let mut replies = Vec::new();
+        if let Ok((size, client)) = socket.recv_from(&mut buf) {
+            match rosc::decoder::decode_udp(&buf[..size]) {
+                Ok((_, packet)) => {
+                    let mut server = server.lock().unwrap_or_else(|e| e.into_inner());
+                    server.receive(packet, None, client, &mut replies);
+                }
+                Err(e) => log::warn!("[MOCK] Undecodable packet from {}: {:?}", client, e),
+            }
+        }
+        server.lock().unwrap_or_else(|e| e.into_inner()).run_due(&mut replies);
+
+        for (client, reply) in replies {
+            match rosc::encoder::encode(&OscPacket::Message(reply)) {
+                Ok(bytes) => {
+                    let _ = socket.send_to(&bytes, client);
+                }
+                Err(e) => log::warn!("[MOCK] Failed to encode reply: {:?}", e),
+            }
+        }
+    }
+}
+
+impl MockServer {
+    /// Record a packet and run its messages now or queue them for later.
+    fn receive(
+        &mut self,
+        packet: OscPacket,
+        timetag: Option<OscTime>,
+        client: SocketAddr,
+        replies: &mut Vec<(SocketAddr, OscMessage)>,
+    ) {
+        match packet {
+            OscPacket::Message(message) => {
+                let now = Instant::now();
+                let due = timetag.map(due_instant).unwrap_or(now).max(now);
+                self.timeline.push(MockEvent {
+                    received: now - self.started,
+                    due: due - self.started,
+                    timetag,
+                    message: message.clone(),
+                });
+                if due > now {
+                    self.pending.push(PendingMessage { due, message, client });
+                } else {
+                    self.execute(message, client, replies);
+                }
+            }
+            OscPacket::Bundle(OscBundle { timetag, content }) => {
+                for packet in content {
+                    self.receive(packet, Some(timetag), client, replies);
+                }
+            }
+        }
+    }
+
+    /// Execute queued messages whose timetag has passed.
+    fn run_due(&mut self, replies: &mut Vec<(SocketAddr, OscMessage)>) {
+        let now = Instant::now();
+        if !self.pending.iter().any(|p| p.due <= now) {
+            return;
+        }
+        // Stable sort keeps the arrival order of messages due at the same time
+        self.pending.sort_by_key(|p| p.due);
+        let split = self.pending.partition_point(|p| p.due <= now);
+        let due: Vec<PendingMessage> = self.pending.drain(..split).collect();
+        for pending in due {
+            self.execute(pending.message, pending.client, replies);
+        }
+    }
+
+    /// Apply a message to the node tree and queue scsynth's replies.
+    fn execute(&mut self, message: OscMessage, client: SocketAddr, replies: &mut Vec<(SocketAddr, OscMessage)>) {
+        let args = &message.args;
+        let mut reply = |addr: &str, args: Vec<OscType>| {
+            replies.push((
+                client,
+                OscMessage {
+                    addr: addr.to_string(),
+                    args,
+                },
+            ))
+        };
+        let done = |cmd: &str| OscType::String(cmd.to_string());
+
+        match message.addr.as_str() {
+            "/notify" => reply("/done", vec![done("/notify"), OscType::Int(0)]),
+            "/status" => {
+                let synths = self.nodes.values().filter(|n| !n.is_group()).count() as i32;
+                let groups = self.nodes.values().filter(|n| n.is_group()).count() as i32;
+                reply(
+                    "/status.reply",
+                    vec![
+                        OscType::Int(1),
+                        OscType::Int(0),
+                        OscType::Int(synths),
+                        OscType::Int(groups),
+                        OscType::Int(self.synthdefs.len() as i32),
+                        OscType::Float(0.0),
+                        OscType::Float(0.0),
+                        OscType::Double(MOCK_SAMPLE_RATE as f64),
+                        OscType::Double(MOCK_SAMPLE_RATE as f64),
+                    ],
+                );
+            }
+            "/sync" => reply("/synced", args.iter().take(1).cloned().collect()),
+            "/d_recv" => {
+                if let Some(OscType::Blob(bytes)) = args.first() {
+                    if let Some(name) = extract_synthdef_name(bytes) {
+                        self.synthdefs.insert(name);
+                    }
+                }
+                reply("/done", vec![done("/d_recv")]);
+            }
+            "/d_load" | "/d_loadDir" => reply("/done", vec![done(&message.addr)]),
+            "/b_allocRead" => {
+                if let (Some(bufnum), Some(OscType::String(path))) = (int_arg(args, 0), args.get(1)) {
+                    self.buffers.insert(bufnum, path.clone());
+                    reply("/done", vec![done("/b_allocRead"), OscType::Int(bufnum)]);
+                }
+            }
+            "/b_alloc" => {
+                if let Some(bufnum) = int_arg(args, 0) {
+                    let frames = int_arg(args, 1).unwrap_or(0).max(0) as u32;
+                    let channels = int_arg(args, 2).unwrap_or(1).max(1) as u16;
+                    self.allocated.insert(bufnum, (frames, channels));
+                    reply("/done", vec![done("/b_alloc"), OscType::Int(bufnum)]);
+                }
+            }
+            "/b_write" => {
+                if let (Some(bufnum), Some(OscType::String(path))) = (int_arg(args, 0), args.get(1)) {
+                    let (frames, channels) = self.allocated.get(&bufnum).copied().unwrap_or((0, 1));
+                    if let Err(e) = write_silence(path, frames, channels) {
+                        log::warn!("[MOCK] Failed to write buffer {} to {}: {}", bufnum, path, e);
+                    }
+                    reply("/done", vec![done("/b_write"), OscType::Int(bufnum)]);
+                }
+            }
+            "/b_read" | "/b_zero" | "/b_close" => {
+                if let Some(bufnum) = int_arg(args, 0) {
+                    reply("/done", vec![done(&message.addr), OscType::Int(bufnum)]);
+                }
+            }
+            "/b_free" => {
+                if let Some(bufnum) = int_arg(args, 0) {
+                    self.buffers.remove(&bufnum);
+                    self.allocated.remove(&bufnum);
+                    reply("/done", vec![done("/b_free"), OscType::Int(bufnum)]);
+                }
+            }
+            "/c_get" | "/c_getn" => {
+                // Control buses always read as silence
+                let mut values = Vec::new();
+                for pair in args.chunks(if message.addr == "/c_get" { 1 } else { 2 }) {
+                    let index = pair.first().and_then(number).unwrap_or(0.0) as i32;
+                    let count = pair.get(1).and_then(number).unwrap_or(1.0) as i32;
+                    values.push(OscType::Int(index));
+                    values.push(OscType::Int(count));
+                    values.extend((0..count).map(|_| OscType::Float(0.0)));
+                }
+                reply("/c_setn", values);
+            }
+            "/s_new" => {
+                let (Some(OscType::String(synthdef)), Some(id)) = (args.first(), int_arg(args, 1)) else {
+                    return;
+                };
+                let id = if id < 0 { self.auto_id() } else { id };
+                let action = int_arg(args, 2).unwrap_or(0);
+                let target = int_arg(args, 3).unwrap_or(0);
+                let Some(parent) = self.parent_for(action, target) else {
+                    reply("/fail", vec![done("/s_new"), OscType::String(format!("Group {} not found", target))]);
+                    return;
+                };
+                let mut controls = BTreeMap::new();
+                set_controls(&mut controls, &args[4.min(args.len())..]);
+                self.insert_node(MockNode {
+                    id,
+                    synthdef: Some(synthdef.clone()),
+                    parent: Some(parent),
+                    controls,
+                });
+                if action == 4 {
+                    self.free_node(target, &mut reply);
+                }
+                reply("/n_go", node_reply(id, parent, false));
+            }
+            "/g_new" | "/p_new" => {
+                for triple in args.chunks(3) {
+                    let (Some(id), Some(action), Some(target)) =
+                        (int_arg(triple, 0), int_arg(triple, 1), int_arg(triple, 2))
+                    else {
+                        continue;
+                    };
+                    let Some(parent) = self.parent_for(action, target) else {
+                        continue;
+                    };
+                    self.insert_node(MockNode {
+                        id,
+                        synthdef: None,
+                        parent: Some(parent),
+                        controls: BTreeMap::new(),
+                    });
+                    reply("/n_go", node_reply(id, parent, true));
+                }
+            }
+            "/n_set" => {
+                let Some(id) = int_arg(args, 0) else {
+                    return;
+                };
+                // Setting a group sets every synth inside it
+                for target in self.synths_under(id) {
+                    if let Some(node) = self.nodes.get_mut(&target) {
+                        set_controls(&mut node.controls, &args[1..]);
+                    }
+                }
+            }
+            "/n_free" => {
+                for id in args.iter().filter_map(number) {
+                    self.free_node(id as i32, &mut reply);
+                }
+            }
+            "/g_freeAll" | "/g_deepFree" => {
+                for id in args.iter().filter_map(number) {
+                    let children: Vec<i32> = self.children(id as i32);
+                    for child in children {
+                        if message.addr == "/g_deepFree" && self.nodes.get(&child).is_some_and(|n| n.is_group()) {
+                            let synths = self.synths_under(child);
+                            for synth in synths {
+                                self.free_node(synth, &mut reply);
+                            }
+                        } else {
+                            self.free_node(child, &mut reply);
+                        }
+                    }
+                }
+            }
+            "/n_order" => {
+                // /n_order action target ids...: move nodes to the target's group
+                if let (Some(action), Some(target)) = (int_arg(args, 0), int_arg(args, 1)) {
+                    if let Some(parent) = self.parent_for(action, target) {
+                        for id in args.iter().skip(2).filter_map(number) {
+                            if let Some(node) = self.nodes.get_mut(&(id as i32)) {
+                                node.parent = Some(parent);
+                            }
+                        }
+                    }
+                }
+            }
+            "/quit" => reply("/done", vec![done("/quit")]),
+            _ => {}
+        }
+    }
+
+    fn auto_id(&mut self) -> i32 {
+        self.next_auto_id += 1;
+        self.next_auto_id
+    }
+
+    fn insert_node(&mut self, node: MockNode) {
+        self.order.retain(|id| *id != node.id);
+        self.order.push(node.id);
+        self.nodes.insert(node.id, node);
+    }
+
+    /// Group a node added relative to `target` ends up in.
+    fn parent_for(&self, action: i32, target: i32) -> Option<i32> {
+        let target_node = self.nodes.get(&target)?;
+        match action {
+            // Head or tail of a group
+            0 | 1 => target_node.is_group().then_some(target),
+            // Before, after or replacing a node
+            _ => target_node.parent,
+        }
+    }
+
+    fn children(&self, group: i32) -> Vec<i32> {
+        self.order
+            .iter()
+            .copied()
+            .filter(|id| self.nodes.get(id).is_some_and(|n| n.parent == Some(group)))
+            .collect()
+    }
+
+    /// The node itself if it is a synth, or every synth below a group.
+    fn synths_under(&self, id: i32) -> Vec<i32> {
+        match self.nodes.get(&id) {
+            Some(node) if node.is_group() => self.children(id).into_iter().flat_map(|c| self.synths_under(c)).collect(),
+            Some(_) => vec![id],
+            None => Vec::new(),
+        }
+    }
+
+    /// Remove a node and everything below it, sending `/n_end` for each.
+    fn free_node(&mut self, id: i32, reply: &mut impl FnMut(&str, Vec<OscType>)) {
+        if id == 0 {
+            return;
+        }
+        for child in self.children(id) {
+            self.free_node(child, reply);
+        }
+        if let Some(node) = self.nodes.remove(&id) {
+            self.order.retain(|n| *n != id);
+            reply("/n_end", node_reply(id, node.parent.unwrap_or(0), node.is_group()));
+        }
+    }
+}
+
+/// Instant at which a bundle timetag is due (now for past or immediate tags).
+fn due_instant(timetag: OscTime) -> Instant {
+    let now = Instant::now();
+    // Tags before 1970 (like the "immediately" tag) can't be a SystemTime
+    if u64::from(timetag.seconds) < NTP_UNIX_OFFSET {
+        return now;
+    }
+    match SystemTime::from(timetag).duration_since(SystemTime::now()) {
+        Ok(ahead) => now + ahead,
+        Err(_) => now,
+    }
+}
+
+/// `/n_go` and `/n_end` arguments: node, parent, prev, next, is_group.
+fn node_reply(id: i32, parent: i32, is_group: bool) -> Vec<OscType> {
+    vec![
+        OscType::Int(id),
+        OscType::Int(parent),
+        OscType::Int(-1),
+        OscType::Int(-1),
+        OscType::Int(is_group as i32),
+    ]
+}
+
+/// Apply `name value` (or `index value`) pairs to a control map.
+fn set_controls(controls: &mut BTreeMap<String, f32>, args: &[OscType]) {
+    for pair in args.chunks(2) {
+        let name = match pair.first() {
+            Some(OscType::String(name)) => name.clone(),
+            Some(OscType::Int(index)) => index.to_string(),
+            _ => continue,
+        };
+        if let Some(value) = pair.get(1).and_then(number) {
+            controls.insert(name, value);
+        }
+    }
+}
+
+/// Write `frames` frames of silence as a float WAV file.
+fn write_silence(path: &str, frames: u32, channels: u16) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate: MOCK_SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for _ in 0..frames as u64 * channels as u64 {
+        writer.write_sample(0.0f32)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+fn int_arg(args: &[OscType], index: usize) -> Option<i32> {
+    match args.get(index)? {
+        OscType::Int(v) => Some(*v),
+        OscType::Float(v) => Some(*v as i32),
+        _ => None,
+    }
+}
+
+fn number(arg: &OscType) -> Option<f32> {
+    match arg {
+        OscType::Int(v) => Some(*v as f32),
+        OscType::Float(v) => Some(*v),
+        OscType::Double(v) => Some(*v as f32),
+        OscType::Long(v) => Some(*v as f32),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{Runtime, RuntimeHandle};
+    use crate::osc::OscClient;
+    use crate::scsynth::{AddAction, BufNum, NodeId, Scsynth, Target};
+    use crate::state::StateMessage;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Start a runtime on a mock and run `script` like the CLI does.
+    fn start_script(mock: &MockScsynth, script: &str) -> (Runtime, rhai::Engine) {
+        let runtime = Runtime::start_mock(mock).unwrap();
+        let engine = start_script_on(&runtime, script);
+        (runtime, engine)
+    }
+
+    /// [`start_script`] on a runtime that is already running.
+    fn start_script_on(runtime: &Runtime, script: &str) -> rhai::Engine {
+        crate::api::init_api(runtime.handle().clone());
+        let engine = crate::api::create_engine();
+        engine.run(script).unwrap();
+        runtime.handle().send(StateMessage::StartScheduler).unwrap();
+        runtime.handle().send(StateMessage::FinalizeGroups).unwrap();
+        engine
+    }
+
+    fn group_nodes(handle: &RuntimeHandle, path: &str) -> (Option<i32>, Option<i32>) {
+        handle.with_state(|state| {
+            state
+                .groups
+                .get(path)
+                .map(|g| (g.node_id, g.link_synth_node_id))
+                .unwrap_or((None, None))
+        })
+    }
+
+    #[test]
+    fn test_mock_answers_like_scsynth() {
+        let mock = MockScsynth::start().unwrap();
+        let sc = Scsynth::new(&mock.addr().to_string()).unwrap();
+
+        sc.g_new(NodeId::new(1), AddAction::AddToTail, Target::root()).unwrap();
+        sc.s_new("pad", NodeId::new(1000), AddAction::AddToHead, Target::from(1), &[("freq", 220.0)])
+            .unwrap();
+        sc.n_set(NodeId::new(1), &[("amp", 0.5)]).unwrap();
+        sc.b_alloc_read(BufNum::new(7), "/samples/kick.wav").unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| m.buffers().contains_key(&7)));
+
+        let synth = mock.node(1000).unwrap();
+        assert_eq!(synth.synthdef.as_deref(), Some("pad"));
+        assert_eq!(synth.parent, Some(1));
+        assert_eq!(synth.controls.get("freq"), Some(&220.0));
+        assert_eq!(synth.controls.get("amp"), Some(&0.5));
+
+        // Replies arrive on the client socket in scsynth's format
+        let mut replies = Vec::new();
+        let deadline = Instant::now() + TIMEOUT;
+        while replies.len() < 4 && Instant::now() < deadline {
+            if let Some(OscPacket::Message(msg)) = sc.osc.try_recv_msg().unwrap() {
+                replies.push(msg);
+            }
+        }
+        let addrs: Vec<&str> = replies.iter().map(|m| m.addr.as_str()).collect();
+        assert_eq!(addrs, vec!["/done", "/n_go", "/n_go", "/done"]);
+        assert_eq!(replies[3].args, vec![OscType::String("/b_allocRead".into()), OscType::Int(7)]);
+
+        // Timed bundles are recorded on arrival and applied when due
+        let timetag = OscTime::try_from(SystemTime::now() + Duration::from_millis(200)).unwrap();
+        sc.osc
+            .send_bundle(Some(timetag), vec![OscClient::msg("/n_free", vec![OscType::Int(1)])])
+            .unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| !m.messages("/n_free").is_empty()));
+        let free = mock.messages("/n_free").remove(0);
+        assert_eq!(free.timetag, Some(timetag));
+        assert!(free.due >= free.received + Duration::from_millis(150));
+        assert!(mock.node(1000).is_some());
+        assert!(mock.wait_until(TIMEOUT, |m| m.node(1).is_none() && m.node(1000).is_none()));
+    }
+
+    #[test]
+    fn test_runtime_schedules_patterns_on_mock() {
+        let mock = MockScsynth::start().unwrap();
+        let (runtime, _engine) = start_script(
+            &mock,
+            r#"
+            set_tempo(240);
+            define_group("drums", || {
+                let kick = voice("kick").synth("kick_909");
+                pattern("four").on(kick).step("x... x... x... x...").start();
+            });
+            "#,
+        );
+
+        assert!(mock.wait_until(TIMEOUT, |m| m.synths_started("kick_909").len() >= 4));
+        let kicks = mock.synths_started("kick_909");
+        let (group, _) = group_nodes(runtime.handle(), "main/drums");
+        for kick in &kicks {
+            // Scheduled ahead in timed bundles, into the voice's group
+            assert!(kick.timetag.is_some());
+            assert!(kick.due > kick.received);
+            assert_eq!(kick.int(3), group);
+        }
+        // One kick per beat at 240 BPM
+        for pair in kicks.windows(2) {
+            let gap = pair[1].due.as_secs_f64() - pair[0].due.as_secs_f64();
+            assert!((gap - 0.25).abs() < 0.02, "kicks {:.3}s apart", gap);
+        }
+        assert!(mock.synthdefs().contains("system_link_audio"));
+    }
+
+    #[test]
+    fn test_runtime_group_fade_on_mock() {
+        let mock = MockScsynth::start().unwrap();
+        let (runtime, _engine) = start_script(
+            &mock,
+            r#"
+            set_tempo(240);
+            define_group("pads", || {});
+            let out = fade("out").on_group("pads").param("amp").from(1.0).to(0.0).over(2.0).apply();
+            sequence("outro").loop_bars(4).clip(0..1, out).start();
+            "#,
+        );
+
+        assert!(mock.wait_until(TIMEOUT, |m| {
+            let (_, link) = group_nodes(runtime.handle(), "main/pads");
+            link.and_then(|id| m.node(id)).is_some_and(|n| n.controls.get("amp") == Some(&0.0))
+        }));
+        let (_, link) = group_nodes(runtime.handle(), "main/pads");
+        let steps: Vec<f32> = mock
+            .messages("/n_set")
+            .iter()
+            .filter(|e| e.int(0) == link)
+            .filter_map(|e| e.control("amp"))
+            .collect();
+        // A half-second fade is sent as a ramp of falling values
+        assert!(steps.len() > 5, "only {} fade steps", steps.len());
+        assert!(steps.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(steps.last(), Some(&0.0));
+    }
+
+    #[test]
+    fn test_runtime_reload_on_mock() {
+        let mock = MockScsynth::start().unwrap();
+        let (runtime, engine) = start_script(
+            &mock,
+            r#"
+            define_group("drums", || {
+                let kick = voice("kick").synth("kick_909");
+                pattern("four").on(kick).step("x... x... x... x...").start();
+            });
+            define_group("keys", || {});
+            "#,
+        );
+        let handle = runtime.handle();
+        assert!(mock.wait_until(TIMEOUT, |m| !m.synths_started("kick_909").is_empty()));
+        let (drums, _) = group_nodes(handle, "main/drums");
+        let (keys, _) = group_nodes(handle, "main/keys");
+        assert!(drums.and_then(|id| mock.node(id)).is_some());
+
+        // Reload without the drums: their group goes, the rest stays
+        handle.send(StateMessage::BeginReload).unwrap();
+        engine.run(r#"define_group("keys", || {});"#).unwrap();
+        handle.send(StateMessage::FinalizeGroups).unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| drums.and_then(|id| m.node(id)).is_none()));
+        assert!(keys.and_then(|id| mock.node(id)).is_some());
+        assert_eq!(group_nodes(handle, "main/keys").0, keys);
+
+        // No kicks are scheduled once the pattern is gone
+        thread::sleep(Duration::from_millis(600));
+        mock.clear_timeline();
+        thread::sleep(Duration::from_millis(600));
+        assert!(mock.synths_started("kick_909").is_empty());
+    }
+
+    #[test]
+    fn test_runtime_voice_inserts_on_mock() {
+        let mock = MockScsynth::start().unwrap();
+        let (runtime, _engine) = start_script(
+            &mock,
+            r#"
+            set_tempo(240);
+            let keys = define_group("keys", || {
+                let lead = voice("lead").synth("saw_lead")
+                    .insert(fx("drive").synth("distortion").param("drive", 0.5))
+                    .insert(fx("echo").synth("delay"));
+                pattern("line").on(lead).step("x... x... x... x...").start();
+            });
+            keys.add_effect("verb", "reverb", #{ mix: 0.3 });
+            "#,
+        );
+
+        assert!(mock.wait_until(TIMEOUT, |m| !m.synths_started("saw_lead").is_empty()));
+        let handle = runtime.handle();
+        let (bus, link, group_bus) = handle.with_state(|state| {
+            let voice = &state.voices["lead"];
+            (voice.insert_bus.unwrap(), voice.insert_link_node_id.unwrap(), state.groups["main/keys"].audio_bus)
+        });
+        let (group, _) = group_nodes(handle, "main/keys");
+        assert_ne!(bus, group_bus);
+
+        // The voice plays into its private bus, which the inserts process in
+        // place before the link carries it on to the group
+        assert!(mock.synths_started("saw_lead").iter().all(|s| s.control("out") == Some(bus as f32)));
+        let link_node = mock.node(link).unwrap();
+        assert_eq!(link_node.parent, group);
+        assert_eq!(link_node.controls.get("inbus"), Some(&(bus as f32)));
+        assert_eq!(link_node.controls.get("outbus"), Some(&(group_bus as f32)));
+        let inserts: Vec<MockEvent> = mock
+            .messages("/s_new")
+            .into_iter()
+            .filter(|e| matches!(e.string(0), Some("distortion" | "delay")))
+            .collect();
+        assert_eq!(inserts.len(), 2);
+        for insert in &inserts {
+            assert_eq!(insert.control("__fx_bus_in"), Some(bus as f32));
+            assert_eq!(insert.control("__fx_bus_out"), Some(bus as f32));
+            assert_eq!((insert.int(2), insert.int(3)), (Some(AddAction::AddBefore as i32), Some(link)));
+        }
+        assert_eq!(inserts[0].control("drive"), Some(0.5));
+
+        // The group's own effect stays on the group bus
+        let verb = handle.with_state(|state| state.effects["verb"].clone());
+        assert_eq!((verb.voice, verb.bus_in), (None, group_bus));
+        let echo = handle.with_state(|state| state.effects["echo"].clone());
+        assert_eq!((echo.voice.as_deref(), echo.position, echo.group_path.as_str()), (Some("lead"), 1, "main/keys"));
+    }
+
+    #[test]
+    fn test_runtime_input_voices_on_mock() {
+        use crate::input_voice::{INPUT_MONO_SYNTHDEF, INPUT_STEREO_SYNTHDEF};
+
+        let mock = MockScsynth::start().unwrap();
+        let (runtime, engine) = start_script(
+            &mock,
+            r#"
+            define_group("guitars", || {
+                voice("gtr").input(1).gain(0.5).insert(fx("drive").synth("distortion"));
+            });
+            "#,
+        );
+
+        assert!(mock.synthdefs().contains(INPUT_MONO_SYNTHDEF));
+        assert!(mock.wait_until(TIMEOUT, |m| !m.synths_started(INPUT_MONO_SYNTHDEF).is_empty()));
+        let handle = runtime.handle();
+        let input = mock.synths_started(INPUT_MONO_SYNTHDEF)[0].clone();
+        let node_id = input.int(1).unwrap();
+        assert_eq!(input.control("input"), Some(1.0));
+
+        // The input plays into the voice's insert chain, ahead of it in the group
+        assert!(mock.wait_until(TIMEOUT, |m| m.node(node_id).is_some_and(|n| n.controls.get("amp") == Some(&0.5))));
+        let bus = handle.with_state(|state| state.voices["gtr"].insert_bus.unwrap());
+        assert!(mock.wait_until(TIMEOUT, |m| m.node(node_id).is_some_and(|n| n.controls.get("out") == Some(&(bus as f32)))));
+        let (group, _) = group_nodes(handle, "main/guitars");
+        assert_eq!(mock.node(node_id).unwrap().parent, group);
+
+        // Muting silences the running input
+        engine.run(r#"voice("gtr").mute();"#).unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| m.node(node_id).is_some_and(|n| n.controls.get("amp") == Some(&0.0))));
+
+        // Switching to a stereo pair restarts the input synth
+        engine.run(r#"voice("gtr").stereo_input(2);"#).unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| !m.synths_started(INPUT_STEREO_SYNTHDEF).is_empty()));
+        assert!(mock.wait_until(TIMEOUT, |m| m.node(node_id).is_none()));
+        assert_eq!(mock.synths_started(INPUT_STEREO_SYNTHDEF)[0].control("input"), Some(2.0));
+    }
+
+    #[test]
+    fn test_runtime_resamples_group_into_drum_kit_on_mock() {
+        use crate::freeze::FREEZE_RECORDER_SYNTHDEF;
+        use crate::state::ResampleStatus;
+
+        let mock = MockScsynth::start().unwrap();
+        let (runtime, engine) = start_script(
+            &mock,
+            r#"
+            set_tempo(240);
+            define_group("drums", || {
+                pattern("beat").on(voice("kick").synth("kick_909")).step("x.x.").start();
+            });
+            let chops = resample("drums", 1.bars);
+            "#,
+        );
+        let handle = runtime.handle();
+
+        // The group bus is recorded from the next bar line, before its link synth
+        assert!(mock.wait_until(TIMEOUT, |m| !m.synths_started(FREEZE_RECORDER_SYNTHDEF).is_empty()));
+        let recorder = mock.synths_started(FREEZE_RECORDER_SYNTHDEF)[0].clone();
+        let (bus, link) = handle.with_state(|state| {
+            let drums = &state.groups["main/drums"];
+            (drums.audio_bus, drums.link_synth_node_id)
+        });
+        assert_eq!(recorder.int(2), Some(AddAction::AddBefore.into()));
+        assert_eq!(recorder.int(3), link);
+        assert_eq!(recorder.control("inbus"), Some(bus as f32));
+
+        // After the bar the recording is written, chopped and loaded as the kit's sample
+        let done = |_: &MockScsynth| {
+            handle.with_state(|state| state.resamples["drums_resample"].status == ResampleStatus::Done)
+        };
+        assert!(mock.wait_until(TIMEOUT, done));
+        let write = mock.messages("/b_write")[0].clone();
+        assert!(write.string(1).is_some_and(|path| path.ends_with(".raw.wav")));
+        assert!(mock.wait_until(TIMEOUT, |m| m.node(recorder.int(1).unwrap()).is_none()));
+        let (pads, sample_path) = handle.with_state(|state| {
+            let kit = state.voices["drums_resample"].drum_kit.clone().unwrap();
+            (kit.pads, state.samples["drums_resample"].path.clone())
+        });
+        // Silence has no onsets: one slice, the whole bar
+        assert_eq!(pads.len(), 1);
+        assert_eq!((pads[0].note, pads[0].step, pads[0].end_frame), (60, Some('a'), 48_000));
+        assert!(sample_path.ends_with(".wav") && !sample_path.ends_with(".raw.wav"));
+
+        // Running the same call again keeps the chops without recording again
+        engine.run(r#"resample("drums", 1.bars);"#).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(mock.synths_started(FREEZE_RECORDER_SYNTHDEF).len(), 1);
+        assert!(handle.with_state(|state| state.voices["drums_resample"].drum_kit.as_ref().is_some_and(|k| k.pads.len() == 1)));
+    }
+
+    #[test]
+    fn test_runtime_redefines_playing_synthdefs_on_mock() {
+        let synthdef = |body: u8| {
+            let mut bytes = b"SCgf\0\0\0\x02\0\x01\x03pad".to_vec();
+            bytes.extend_from_slice(&[0, 0, 0, body]);
+            bytes
+        };
+        let mock = MockScsynth::start().unwrap();
+        let runtime = Runtime::start_mock(&mock).unwrap();
+        let handle = runtime.handle();
+        handle.send(StateMessage::LoadSynthDef { name: "pad".into(), bytes: synthdef(1) }).unwrap();
+        let engine = start_script_on(&runtime, r#"voice("pad").synth("pad").run();"#);
+        assert!(mock.wait_until(TIMEOUT, |m| m.synths_started("pad").len() == 1));
+        let drone = mock.synths_started("pad")[0].int(1).unwrap();
+
+        // The drone keeps the old definition, new notes get the new one
+        handle.send(StateMessage::LoadSynthDef { name: "pad".into(), bytes: synthdef(2) }).unwrap();
+        engine.run(r#"voice("pad").synth("pad").trigger();"#).unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| !m.synths_started("pad__v1").is_empty()));
+        assert!(mock.synthdefs().contains("pad__v1"));
+        assert_eq!(mock.node(drone).unwrap().synthdef.as_deref(), Some("pad"));
+        assert!(mock.messages("/d_free").is_empty());
+
+        // The old definition is freed with its last node
+        engine.run(r#"voice("pad").synth("pad").choke();"#).unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| !m.messages("/d_free").is_empty()));
+        assert_eq!(mock.messages("/d_free")[0].string(0), Some("pad"));
+
+        // Reloading an unchanged definition doesn't add a version
+        handle.send(StateMessage::LoadSynthDef { name: "pad".into(), bytes: synthdef(2) }).unwrap();
+        engine.run(r#"voice("pad").synth("pad").trigger();"#).unwrap();
+        assert!(mock.wait_until(TIMEOUT, |m| m.synths_started("pad__v1").len() == 2));
+        assert!(!mock.synthdefs().contains("pad__v2"));
+    }
+
+    #[test]
+    fn test_runtime_humanize_on_mock() {
+        let mock = MockScsynth::start().unwrap();
+        let (_runtime, _engine) = start_script(
+            &mock,
+            r#"
+            set_tempo(240);
+            let kick = voice("kick").synth("kick_909");
+            let hat = voice("hat").synth("hat_909");
+            pattern("loose").on(kick).step("x... x... x... x...").humanize(8, 20).start();
+            pattern("tight").on(hat).step("x... x... x... x...").start();
+            "#,
+        );
+
+        assert!(mock.wait_until(TIMEOUT, |m| m.synths_started("hat_909").len() >= 9));
+        let kicks = mock.synths_started("kick_909");
+        let hats = mock.synths_started("hat_909");
+        // Each kick lands within 8ms of the straight hat on its beat (but the
+        // first, which can't start before the song does), differently each pass
+        let gaps: Vec<f64> = kicks
+            .iter()
+            .zip(&hats)
+            .skip(1)
+            .map(|(kick, hat)| (kick.due.as_secs_f64() - hat.due.as_secs_f64()) * 1000.0)
+            .collect();
+        assert!(gaps.len() >= 8);
+        assert!(gaps.iter().all(|gap| gap.abs() <= 8.5), "gaps {:?}", gaps);
+        assert!(gaps.iter().any(|gap| gap.abs() > 0.5), "gaps {:?}", gaps);
+        assert_ne!(gaps[3], gaps[7]);
+
+        let hat_amp = hats[0].control("amp").unwrap();
+        let ratios: Vec<f32> = kicks.iter().map(|kick| kick.control("amp").unwrap() / hat_amp).collect();
+        assert!(ratios.iter().all(|r| (0.79..=1.21).contains(r)), "ratios {:?}", ratios);
+        assert!(ratios.windows(2).any(|w| w[0] != w[1]));
+        assert!(hats.iter().all(|hat| hat.control("amp") == Some(hat_amp)));
+    }
+
+    #[test]
+    fn test_runtime_timing_feel_on_mock() {
+        let mock = MockScsynth::start().unwrap();
+        let (_runtime, _engine) = start_script(
+            &mock,
+            r#"
+            set_tempo(240);
+            let kick = voice("kick").synth("kick_909").feel(10, 0);
+            let bass = voice("bass").synth("acid");
+            pattern("four").on(kick).step("x... x... x... x...").start();
+            pattern("line").on(bass).step("x... x... x... x...").start();
+            groove("band").feel("bass", -20, 0).apply();
+            "#,
+        );
+
+        assert!(mock.wait_until(TIMEOUT, |m| m.synths_started("acid").len() >= 4));
+        let kicks = mock.synths_started("kick_909");
+        let basses = mock.synths_started("acid");
+        // The dragging kick lands 30ms after the rushing bass on every beat
+        // (but the first, where the bass can't start before the song does)
+        let gaps: Vec<f64> = kicks
+            .iter()
+            .zip(&basses)
+            .skip(1)
+            .map(|(kick, bass)| kick.due.as_secs_f64() - bass.due.as_secs_f64())
+            .collect();
+        assert!(gaps.len() >= 3);
+        assert!(gaps.iter().all(|gap| (gap - 0.030).abs() < 0.001), "gaps {:?}", gaps);
+    }
+}
@@ -0,0 +1,212 @@
+//! Mono (legato) voices.
+//!
+//! A voice in mono mode plays one note at a time on a single synth, like a
+//! classic monosynth. A note starting while another is held doesn't start a
+//! synth of its own: the running one glides to the pitch the [`NotePriority`]
+//! picks among the held notes. Releasing a note returns to the note the
+//! priority picks among the ones still held, and the synth is released once
+//! no note is held.
+//!
+//! Notes that touch (one ends where the next starts, like consecutive melody
+//! notes) count as overlapping, so a melody on a mono voice plays legato.
+//! Only notes with a length take part; gateless triggers play as usual.
+
+/// Which held note a mono voice sounds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotePriority {
+    /// The most recently pressed note.
+    #[default]
+    Last,
+    /// The lowest held note.
+    Low,
+    /// The highest held note.
+    High,
+}
+
+impl NotePriority {
+    /// Parse "last", "low" or "high".
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "last" | "latest" => Some(NotePriority::Last),
+            "low" | "lowest" => Some(NotePriority::Low),
+            "high" | "highest" => Some(NotePriority::High),
+            _ => None,
+        }
+    }
+
+    /// "last", "low" or "high".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotePriority::Last => "last",
+            NotePriority::Low => "low",
+            NotePriority::High => "high",
+        }
+    }
+
+    /// Note to sound among `held`, which are in the order they were pressed.
+    fn pick(&self, held: &[HeldNote]) -> Option<u8> {
+        let notes = held.iter().map(|h| h.note);
+        match self {
+            NotePriority::Last => held.last().map(|h| h.note),
+            NotePriority::Low => notes.min(),
+            NotePriority::High => notes.max(),
+        }
+    }
+}
+
+/// Mono mode of a voice (`voice.mono()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MonoMode {
+    /// Which held note sounds.
+    pub priority: NotePriority,
+    /// Time the synth takes to glide to a new pitch, in milliseconds.
+    pub glide_ms: f64,
+}
+
+/// A note held on a mono voice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct HeldNote {
+    note: u8,
+    off_beat: f64,
+}
+
+/// What happens to a mono voice's synth when a note starts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MonoNoteOn {
+    /// No synth is running: start one, then report it with [`MonoState::started`].
+    Start,
+    /// Glide the running synth to `note`.
+    Glide { node_id: i32, note: u8 },
+    /// The running synth keeps its pitch (a held note has priority).
+    Hold,
+}
+
+/// A change to a mono voice's synth when held notes end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MonoChange {
+    /// Glide the synth back to a note still held.
+    Glide { beat: f64, node_id: i32, note: u8 },
+    /// Release the synth; `note` is the note it was started with.
+    Release { beat: f64, node_id: i32, note: u8 },
+}
+
+/// Held notes and the running synth of a mono voice.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MonoState {
+    /// Running synth and the note it was started with (its note tracking key).
+    synth: Option<(i32, u8)>,
+    /// Held notes in the order they were pressed.
+    held: Vec<HeldNote>,
+    /// Note the synth currently sounds.
+    sounding: Option<u8>,
+}
+
+impl MonoState {
+    /// Whether a synth is running.
+    pub fn is_active(&self) -> bool {
+        self.synth.is_some()
+    }
+
+    /// End the held notes released before `beat`, in the order they end.
+    pub fn release_until(&mut self, beat: f64, priority: NotePriority) -> Vec<MonoChange> {
+        let mut changes = Vec::new();
+        while let Some(off_beat) = self
+            .held
+            .iter()
+            .map(|h| h.off_beat)
+            .filter(|off| *off < beat - 1e-9)
+            .min_by(f64::total_cmp)
+        {
+            // Notes ending together end at once
+            self.held.retain(|h| h.off_beat > off_beat + 1e-9);
+            let Some((node_id, start_note)) = self.synth else {
+                continue;
+            };
+            match priority.pick(&self.held) {
+                None => {
+                    self.synth = None;
+                    self.sounding = None;
+                    changes.push(MonoChange::Release { beat: off_beat, node_id, note: start_note });
+                }
+                Some(note) if Some(note) != self.sounding => {
+                    self.sounding = Some(note);
+                    changes.push(MonoChange::Glide { beat: off_beat, node_id, note });
+                }
+                Some(_) => {}
+            }
+        }
+        changes
+    }
+
+    /// Press `note` until `off_beat`; call [`release_until`](Self::release_until)
+    /// with the note's beat first.
+    pub fn note_on(&mut self, note: u8, off_beat: f64, priority: NotePriority) -> MonoNoteOn {
+        self.held.retain(|h| h.note != note);
+        self.held.push(HeldNote { note, off_beat });
+        let Some((node_id, _)) = self.synth else {
+            self.sounding = Some(note);
+            return MonoNoteOn::Start;
+        };
+        match priority.pick(&self.held) {
+            Some(pick) if Some(pick) != self.sounding => {
+                self.sounding = Some(pick);
+                MonoNoteOn::Glide { node_id, note: pick }
+            }
+            _ => MonoNoteOn::Hold,
+        }
+    }
+
+    /// Report the synth started for a [`MonoNoteOn::Start`].
+    pub fn started(&mut self, node_id: i32, note: u8) {
+        self.synth = Some((node_id, note));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mono_note_priorities() {
+        // Last note priority: glide up, back down when the later note ends first
+        let mut mono = MonoState::default();
+        let last = NotePriority::Last;
+        assert!(mono.release_until(0.0, last).is_empty());
+        assert_eq!(mono.note_on(48, 4.0, last), MonoNoteOn::Start);
+        mono.started(1000, 48);
+        assert!(mono.release_until(1.0, last).is_empty());
+        assert_eq!(mono.note_on(55, 2.0, last), MonoNoteOn::Glide { node_id: 1000, note: 55 });
+        assert_eq!(
+            mono.release_until(3.0, last),
+            vec![MonoChange::Glide { beat: 2.0, node_id: 1000, note: 48 }]
+        );
+        // A note starting right where the held one ends plays legato
+        assert_eq!(mono.release_until(4.0, last), vec![]);
+        assert_eq!(mono.note_on(50, 5.0, last), MonoNoteOn::Glide { node_id: 1000, note: 50 });
+        assert_eq!(
+            mono.release_until(6.0, last),
+            vec![MonoChange::Release { beat: 5.0, node_id: 1000, note: 48 }]
+        );
+        assert!(!mono.is_active());
+        assert_eq!(mono.note_on(52, 7.0, last), MonoNoteOn::Start);
+
+        // Low note priority: a higher note doesn't take over
+        let mut mono = MonoState::default();
+        let low = NotePriority::Low;
+        assert_eq!(mono.note_on(48, 4.0, low), MonoNoteOn::Start);
+        mono.started(1001, 48);
+        assert_eq!(mono.note_on(55, 4.0, low), MonoNoteOn::Hold);
+        assert_eq!(mono.note_on(43, 2.0, low), MonoNoteOn::Glide { node_id: 1001, note: 43 });
+        assert_eq!(
+            mono.release_until(5.0, low),
+            vec![
+                MonoChange::Glide { beat: 2.0, node_id: 1001, note: 48 },
+                MonoChange::Release { beat: 4.0, node_id: 1001, note: 48 },
+            ]
+        );
+
+        assert_eq!(NotePriority::parse("HIGH"), Some(NotePriority::High));
+        assert_eq!(NotePriority::High.pick(&[HeldNote { note: 40, off_beat: 1.0 }, HeldNote { note: 60, off_beat: 1.0 }]), Some(60));
+        assert_eq!(NotePriority::parse("first"), None);
+    }
+}
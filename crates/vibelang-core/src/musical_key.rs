@@ -0,0 +1,181 @@
+//! Musical keys and key estimation.
+//!
+//! Loaded samples are analysed for an approximate key (see
+//! [`crate::api::sample::detect_key`]): pitched frames are folded into a
+//! pitch-class histogram, which is correlated with the Krumhansl-Kessler major
+//! and minor key profiles. Sample voices with `.match_key()` are then
+//! transposed so their key lines up with the session key.
+
+use crate::pitch::PITCH_CLASS_NAMES;
+use std::fmt;
+
+/// Krumhansl-Kessler major key profile (tonic first).
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+
+/// Krumhansl-Kessler minor key profile (tonic first).
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// A key: tonic pitch class and mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MusicalKey {
+    /// Tonic pitch class (0 = C, 11 = B).
+    pub root: u8,
+    /// Minor (true) or major (false).
+    pub minor: bool,
+}
+
+impl MusicalKey {
+    /// Parse a key such as "A minor", "Am", "F# major", "Eb" or "c#m".
+    ///
+    /// A bare note name is a major key.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let mut chars = text.char_indices().peekable();
+        let (_, letter) = chars.next()?;
+        let base: i32 = match letter.to_ascii_uppercase() {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return None,
+        };
+        let mut accidental = 0;
+        let mut rest = &text[letter.len_utf8()..];
+        while let Some(&(i, c)) = chars.peek() {
+            match c {
+                '#' | '♯' => accidental += 1,
+                'b' | '♭' => accidental -= 1,
+                _ => break,
+            }
+            chars.next();
+            rest = &text[i + c.len_utf8()..];
+        }
+        let minor = match rest.trim().to_ascii_lowercase().as_str() {
+            "" | "maj" | "major" => false,
+            "m" | "min" | "minor" => true,
+            _ => return None,
+        };
+        Some(Self {
+            root: (base + accidental).rem_euclid(12) as u8,
+            minor,
+        })
+    }
+
+    /// Tonic of this key's relative key in the given mode (the key itself if
+    /// the mode already matches): A minor's relative major tonic is C.
+    pub fn tonic_in_mode(&self, minor: bool) -> u8 {
+        match (self.minor, minor) {
+            (false, true) => (self.root + 9) % 12,
+            (true, false) => (self.root + 3) % 12,
+            _ => self.root,
+        }
+    }
+
+    /// Smallest transposition in semitones (-6..=5) that moves this key onto
+    /// `target`, via the relative key when the modes differ.
+    pub fn semitones_to(&self, target: &MusicalKey) -> i32 {
+        let target_tonic = target.tonic_in_mode(self.minor) as i32;
+        let up = (target_tonic - self.root as i32).rem_euclid(12);
+        if up > 5 {
+            up - 12
+        } else {
+            up
+        }
+    }
+}
+
+impl fmt::Display for MusicalKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.minor { "minor" } else { "major" };
+        write!(f, "{} {}", PITCH_CLASS_NAMES[self.root as usize % 12], mode)
+    }
+}
+
+/// Estimate the key of a pitch-class histogram.
+///
+/// Returns the best-matching key and its correlation with the key profile
+/// (-1..1), or `None` for an empty histogram.
+pub fn estimate_key(chroma: &[f32; 12]) -> Option<(MusicalKey, f32)> {
+    if chroma.iter().sum::<f32>() <= 0.0 {
+        return None;
+    }
+    let mut best: Option<(MusicalKey, f32)> = None;
+    for root in 0..12u8 {
+        for (minor, profile) in [(false, &MAJOR_PROFILE), (true, &MINOR_PROFILE)] {
+            let rotated: [f32; 12] = std::array::from_fn(|pc| profile[(pc + 12 - root as usize) % 12]);
+            let score = correlation(chroma, &rotated);
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((MusicalKey { root, minor }, score));
+            }
+        }
+    }
+    best
+}
+
+/// Pearson correlation of two 12-bin vectors.
+fn correlation(a: &[f32; 12], b: &[f32; 12]) -> f32 {
+    let mean_a = a.iter().sum::<f32>() / 12.0;
+    let mean_b = b.iter().sum::<f32>() / 12.0;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a) * (x - mean_a);
+        var_b += (y - mean_b) * (y - mean_b);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return 0.0;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(MusicalKey::parse("A minor"), Some(MusicalKey { root: 9, minor: true }));
+        assert_eq!(MusicalKey::parse("c#m"), Some(MusicalKey { root: 1, minor: true }));
+        assert_eq!(MusicalKey::parse("Eb"), Some(MusicalKey { root: 3, minor: false }));
+        assert_eq!(MusicalKey::parse("Cb major"), Some(MusicalKey { root: 11, minor: false }));
+        assert_eq!(MusicalKey::parse("H dorian"), None);
+        assert_eq!(MusicalKey { root: 6, minor: false }.to_string(), "F# major");
+    }
+
+    #[test]
+    fn test_semitones_to() {
+        let c_major = MusicalKey { root: 0, minor: false };
+        let a_minor = MusicalKey { root: 9, minor: true };
+        let g_major = MusicalKey { root: 7, minor: false };
+        // Relative keys need no transposition
+        assert_eq!(a_minor.semitones_to(&c_major), 0);
+        // The nearest way round: G → C is a fourth up, C → G a fourth down
+        assert_eq!(g_major.semitones_to(&c_major), 5);
+        assert_eq!(c_major.semitones_to(&g_major), -5);
+        assert_eq!(MusicalKey { root: 2, minor: false }.semitones_to(&c_major), -2);
+    }
+
+    #[test]
+    fn test_estimate_key() {
+        // C major triad and scale tones, weighted towards the tonic
+        let mut chroma = [0.0f32; 12];
+        for (pc, weight) in [(0, 5.0), (2, 1.0), (4, 3.0), (5, 1.0), (7, 4.0), (9, 1.0), (11, 1.0)] {
+            chroma[pc] = weight;
+        }
+        let (key, score) = estimate_key(&chroma).unwrap();
+        assert_eq!(key, MusicalKey { root: 0, minor: false });
+        assert!(score > 0.5);
+
+        // A minor triad
+        let mut chroma = [0.0f32; 12];
+        for (pc, weight) in [(9, 5.0), (0, 3.0), (4, 4.0), (2, 1.0), (11, 1.0)] {
+            chroma[pc] = weight;
+        }
+        assert_eq!(estimate_key(&chroma).unwrap().0, MusicalKey { root: 9, minor: true });
+
+        assert!(estimate_key(&[0.0; 12]).is_none());
+    }
+}
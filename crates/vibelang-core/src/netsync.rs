@@ -0,0 +1,308 @@
+//! Network transport sync for multi-machine ensembles.
+//!
+//! One session is the leader: it answers clock pings and broadcasts its beat,
+//! tempo and meter to every follower it has heard from. Followers estimate
+//! the offset between their clock and the leader's (NTP style, see
+//! [`ClockOffsetEstimator`]) and pull their transport towards the leader's
+//! position, so several machines play one piece in time.
+//!
+//! The protocol is plain OSC over UDP; times are wall-clock seconds:
+//!
+//! ```text
+//! follower → leader  /vibe/sync/ping  id sent
+//! leader → follower  /vibe/sync/pong  id sent received replied
+//! leader → follower  /vibe/sync/beat  time beat bpm running numerator denominator
+//! ```
+
+use crate::runtime::RuntimeHandle;
+use crate::state::{NetSyncRole, NetSyncState, StateMessage};
+use crate::timing::{ClockOffsetEstimator, ClockSample};
+use anyhow::{Context, Result};
+use rosc::{decoder, encoder, OscMessage, OscPacket, OscType};
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Default UDP port of a sync leader.
+pub const DEFAULT_SYNC_PORT: u16 = 57140;
+
+/// Largest correction applied per beat update, in milliseconds of transport time.
+pub const MAX_SLEW_MS: f64 = 2.0;
+
+/// Phase errors above this jump to the leader's position instead of slewing.
+pub const MAX_PHASE_ERROR_MS: f64 = 250.0;
+
+/// How often the leader broadcasts its position.
+const BEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often followers measure the clock offset.
+const PING_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Followers (and the leader, for followers) not heard from for this long are gone.
+const PEER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Number of clock exchanges the offset estimate is based on.
+const CLOCK_SAMPLES: usize = 16;
+
+/// Socket read timeout, bounding how late a periodic send can be.
+const READ_TIMEOUT: Duration = Duration::from_millis(5);
+
+/// Current wall-clock time in seconds.
+pub fn wall_clock_seconds() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Resolve a leader address, adding [`DEFAULT_SYNC_PORT`] when no port is given.
+pub fn resolve_leader(leader: &str) -> Result<SocketAddr> {
+    let with_port = if leader.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+        leader.to_string()
+    } else {
+        format!("{}:{}", leader, DEFAULT_SYNC_PORT)
+    };
+    with_port
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid sync leader address '{}'", leader))?
+        .next()
+        .with_context(|| format!("Sync leader '{}' did not resolve", leader))
+}
+
+/// Start syncing in the background until the runtime stops.
+pub fn start(handle: RuntimeHandle, role: NetSyncRole) -> Result<()> {
+    match &role {
+        NetSyncRole::Leader { port } => {
+            let socket = UdpSocket::bind(("0.0.0.0", *port))
+                .with_context(|| format!("Failed to bind sync port {}", port))?;
+            socket.set_read_timeout(Some(READ_TIMEOUT))?;
+            log::info!("[SYNC] Leading on UDP port {}", port);
+            std::thread::spawn(move || run_leader(handle, socket, role));
+        }
+        NetSyncRole::Follower { leader } => {
+            let leader_addr = resolve_leader(leader)?;
+            let socket = UdpSocket::bind("0.0.0.0:0").context("Failed to bind sync socket")?;
+            socket.set_read_timeout(Some(READ_TIMEOUT))?;
+            log::info!("[SYNC] Following {}", leader_addr);
+            std::thread::spawn(move || run_follower(handle, socket, leader_addr, role));
+        }
+    }
+    Ok(())
+}
+
+fn send(socket: &UdpSocket, addr: SocketAddr, path: &str, args: Vec<OscType>) {
+    let packet = OscPacket::Message(OscMessage {
+        addr: path.to_string(),
+        args,
+    });
+    match encoder::encode(&packet) {
+        Ok(buf) => {
+            if let Err(e) = socket.send_to(&buf, addr) {
+                log::debug!("[SYNC] Failed to send {} to {}: {}", path, addr, e);
+            }
+        }
+        Err(e) => log::debug!("[SYNC] Failed to encode {}: {}", path, e),
+    }
+}
+
+/// Receive one OSC message, if one arrives before the read timeout.
+fn recv(socket: &UdpSocket, buf: &mut [u8]) -> Option<(OscMessage, SocketAddr)> {
+    let (size, from) = socket.recv_from(buf).ok()?;
+    match decoder::decode_udp(&buf[..size]) {
+        Ok((_, OscPacket::Message(msg))) => Some((msg, from)),
+        _ => None,
+    }
+}
+
+fn arg_f64(args: &[OscType], index: usize) -> Option<f64> {
+    match args.get(index)? {
+        OscType::Double(v) => Some(*v),
+        OscType::Float(v) => Some(*v as f64),
+        OscType::Int(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+fn arg_i32(args: &[OscType], index: usize) -> Option<i32> {
+    match args.get(index)? {
+        OscType::Int(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn run_leader(handle: RuntimeHandle, socket: UdpSocket, role: NetSyncRole) {
+    let mut buf = [0u8; 1024];
+    let mut followers: HashMap<SocketAddr, Instant> = HashMap::new();
+    let mut last_beat = Instant::now();
+    let mut reported_peers = None;
+
+    while !handle.is_stopped() {
+        if let Some((msg, from)) = recv(&socket, &mut buf) {
+            let received = wall_clock_seconds();
+            if msg.addr == "/vibe/sync/ping" {
+                if let (Some(id), Some(sent)) = (arg_i32(&msg.args, 0), arg_f64(&msg.args, 1)) {
+                    if followers.insert(from, Instant::now()).is_none() {
+                        log::info!("[SYNC] Follower joined: {}", from);
+                    }
+                    let args = vec![
+                        OscType::Int(id),
+                        OscType::Double(sent),
+                        OscType::Double(received),
+                        OscType::Double(wall_clock_seconds()),
+                    ];
+                    send(&socket, from, "/vibe/sync/pong", args);
+                }
+            }
+        }
+
+        if last_beat.elapsed() >= BEAT_INTERVAL {
+            last_beat = Instant::now();
+            followers.retain(|addr, seen| {
+                let alive = seen.elapsed() < PEER_TIMEOUT;
+                if !alive {
+                    log::info!("[SYNC] Follower left: {}", addr);
+                }
+                alive
+            });
+            let (beat, bpm, running, signature) = handle
+                .with_state(|s| (s.current_beat, s.tempo, s.transport_running, s.time_signature));
+            let time = wall_clock_seconds();
+            for addr in followers.keys() {
+                let args = vec![
+                    OscType::Double(time),
+                    OscType::Double(beat),
+                    OscType::Double(bpm),
+                    OscType::Int(running as i32),
+                    OscType::Int(signature.numerator as i32),
+                    OscType::Int(signature.denominator as i32),
+                ];
+                send(&socket, *addr, "/vibe/sync/beat", args);
+            }
+
+            if reported_peers != Some(followers.len()) {
+                reported_peers = Some(followers.len());
+                let _ = handle.send(StateMessage::SetNetSyncStatus {
+                    status: NetSyncState {
+                        role: Some(role.clone()),
+                        peers: followers.len(),
+                        ..Default::default()
+                    },
+                });
+            }
+        }
+    }
+}
+
+fn run_follower(handle: RuntimeHandle, socket: UdpSocket, leader: SocketAddr, role: NetSyncRole) {
+    let mut buf = [0u8; 1024];
+    let mut estimator = ClockOffsetEstimator::new(CLOCK_SAMPLES);
+    let mut next_id = 0i32;
+    let mut last_ping: Option<Instant> = None;
+    let mut last_heard: Option<Instant> = None;
+    let mut connected = false;
+
+    let report = |estimator: &ClockOffsetEstimator, connected: bool| {
+        let _ = handle.send(StateMessage::SetNetSyncStatus {
+            status: NetSyncState {
+                role: Some(role.clone()),
+                peers: connected as usize,
+                offset_ms: estimator.offset().map(|s| s * 1000.0),
+                round_trip_ms: estimator.round_trip().map(|s| s * 1000.0),
+                ..Default::default()
+            },
+        });
+    };
+    report(&estimator, false);
+
+    while !handle.is_stopped() {
+        if last_ping.is_none_or(|t| t.elapsed() >= PING_INTERVAL) {
+            last_ping = Some(Instant::now());
+            next_id = next_id.wrapping_add(1);
+            let args = vec![OscType::Int(next_id), OscType::Double(wall_clock_seconds())];
+            send(&socket, leader, "/vibe/sync/ping", args);
+
+            if connected && last_heard.is_some_and(|t| t.elapsed() > PEER_TIMEOUT) {
+                log::warn!("[SYNC] Lost the leader at {}; playing on freely", leader);
+                connected = false;
+                report(&estimator, connected);
+            }
+        }
+
+        let Some((msg, from)) = recv(&socket, &mut buf) else {
+            continue;
+        };
+        let received = wall_clock_seconds();
+        let at = Instant::now();
+        if from != leader {
+            continue;
+        }
+        last_heard = Some(at);
+
+        match msg.addr.as_str() {
+            "/vibe/sync/pong" => {
+                let (Some(sent), Some(remote_received), Some(remote_sent)) =
+                    (arg_f64(&msg.args, 1), arg_f64(&msg.args, 2), arg_f64(&msg.args, 3))
+                else {
+                    continue;
+                };
+                estimator.add(ClockSample {
+                    sent,
+                    remote_received,
+                    remote_sent,
+                    received,
+                });
+                if !connected {
+                    log::info!("[SYNC] Connected to the leader at {}", leader);
+                    connected = true;
+                }
+                report(&estimator, connected);
+            }
+            "/vibe/sync/beat" => {
+                // Positions can't be placed in local time until the offset is known
+                let Some(leader_now) = estimator.to_remote(received) else {
+                    continue;
+                };
+                let (Some(time), Some(beat), Some(bpm)) =
+                    (arg_f64(&msg.args, 0), arg_f64(&msg.args, 1), arg_f64(&msg.args, 2))
+                else {
+                    continue;
+                };
+                let running = arg_i32(&msg.args, 3).unwrap_or(1) != 0;
+                let numerator = arg_i32(&msg.args, 4).unwrap_or(4).max(1) as u32;
+                let denominator = arg_i32(&msg.args, 5).unwrap_or(4).max(1) as u32;
+                // Where the leader is now, by its own clock
+                let beat = if running {
+                    beat + (leader_now - time).max(0.0) * bpm / 60.0
+                } else {
+                    beat
+                };
+                let _ = handle.send(StateMessage::SyncTransport {
+                    beat,
+                    at,
+                    bpm,
+                    running,
+                    numerator,
+                    denominator,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_leader() {
+        assert_eq!(
+            resolve_leader("127.0.0.1").unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], DEFAULT_SYNC_PORT))
+        );
+        assert_eq!(
+            resolve_leader("127.0.0.1:9000").unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 9000))
+        );
+    }
+}
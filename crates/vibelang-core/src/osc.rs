@@ -2,12 +2,61 @@
 //!
 //! OSC is the protocol used by SuperCollider's synthesis server (scsynth)
 //! for real-time control. This module provides a simple UDP-based client.
+//!
+//! Bundles that would not fit in a single datagram are split into several
+//! bundles with the same timetag (see [`split_bundle`]), so dense beats are
+//! still delivered instead of failing to send.
 
+use crate::osc_capture::{Direction, OscCapture};
 use anyhow::Result;
 use rosc::{encoder, OscBundle, OscMessage, OscPacket, OscTime, OscType};
 use std::net::UdpSocket;
 use std::sync::Arc;
 
+/// Largest encoded bundle sent as one datagram.
+///
+/// Well below the 64 KiB UDP limit, matching the bundle size sclang keeps to,
+/// so bundles are not fragmented or rejected on the way to a remote scsynth.
+pub const MAX_BUNDLE_SIZE: usize = 8192;
+
+/// Encoded size of a bundle's header ("#bundle\0" and the timetag).
+const BUNDLE_HEADER_SIZE: usize = 16;
+
+/// Split packets into bundles that each encode to at most `max_size` bytes.
+///
+/// All bundles share `timetag`, so scsynth still executes their contents at
+/// the same time, and packet order is kept. A packet too large to fit on its
+/// own is sent alone in its own bundle.
+pub fn split_bundle(timetag: OscTime, packets: Vec<OscPacket>, max_size: usize) -> Result<Vec<OscBundle>> {
+    let mut bundles = Vec::new();
+    let mut content = Vec::new();
+    let mut size = BUNDLE_HEADER_SIZE;
+    for packet in packets {
+        // Each element is prefixed with its 4-byte length
+        let packet_size = 4 + encoder::encode(&packet)?.len();
+        if !content.is_empty() && size + packet_size > max_size {
+            bundles.push(OscBundle {
+                timetag,
+                content: std::mem::take(&mut content),
+            });
+            size = BUNDLE_HEADER_SIZE;
+        }
+        if BUNDLE_HEADER_SIZE + packet_size > max_size {
+            log::warn!(
+                "OSC packet of {} bytes exceeds the {} byte bundle limit; sending it alone",
+                packet_size,
+                max_size
+            );
+        }
+        size += packet_size;
+        content.push(packet);
+    }
+    if !content.is_empty() || bundles.is_empty() {
+        bundles.push(OscBundle { timetag, content });
+    }
+    Ok(bundles)
+}
+
 /// UDP-based OSC client for sending messages to scsynth.
 #[derive(Clone)]
 pub struct OscClient {
@@ -15,6 +64,8 @@ pub struct OscClient {
     sock: Option<Arc<UdpSocket>>,
     /// Target address in "host:port" format.
     pub addr: String,
+    /// Capture of the traffic to a file (see `vibe osc-dump`).
+    pub capture: OscCapture,
 }
 
 impl OscClient {
@@ -30,6 +81,7 @@ impl OscClient {
         Ok(Self {
             sock: Some(Arc::new(sock)),
             addr: addr.into(),
+            capture: OscCapture::default(),
         })
     }
 
@@ -41,6 +93,7 @@ impl OscClient {
         Self {
             sock: None,
             addr: "noop".to_string(),
+            capture: OscCapture::default(),
         }
     }
 
@@ -66,11 +119,15 @@ impl OscClient {
         let packet = OscPacket::Message(msg);
         let buf = encoder::encode(&packet)?;
         sock.send_to(&buf, &self.addr)?;
+        self.capture.record(Direction::Out, &buf);
         Ok(())
     }
 
     /// Send an OSC bundle with a timetag for scheduled execution.
     ///
+    /// Bundles larger than [`MAX_BUNDLE_SIZE`] are split into several
+    /// datagrams with the same timetag.
+    ///
     /// # Arguments
     /// * `timetag` - Optional NTP timestamp for scheduling (None = immediately)
     /// * `packets` - The messages/bundles to include
@@ -79,12 +136,12 @@ impl OscClient {
             Some(s) => s,
             None => return Ok(()), // noop mode
         };
-        let bundle = OscBundle {
-            timetag: timetag.unwrap_or_else(|| OscTime::from((1, 0))),
-            content: packets,
-        };
-        let buf = encoder::encode(&OscPacket::Bundle(bundle))?;
-        sock.send_to(&buf, &self.addr)?;
+        let timetag = timetag.unwrap_or_else(|| OscTime::from((1, 0)));
+        for bundle in split_bundle(timetag, packets, MAX_BUNDLE_SIZE)? {
+            let buf = encoder::encode(&OscPacket::Bundle(bundle))?;
+            sock.send_to(&buf, &self.addr)?;
+            self.capture.record(Direction::Out, &buf);
+        }
         Ok(())
     }
 
@@ -105,6 +162,7 @@ impl OscClient {
             None => return Ok(()), // noop mode
         };
         sock.send_to(bytes, &self.addr)?;
+        self.capture.record(Direction::Out, bytes);
         Ok(())
     }
 
@@ -120,6 +178,7 @@ impl OscClient {
         };
         let mut buf = [0u8; 65536];
         let (size, _) = sock.recv_from(&mut buf)?;
+        self.capture.record(Direction::In, &buf[..size]);
         let (_, packet) = rosc::decoder::decode_udp(&buf[..size])?;
         Ok(packet)
     }
@@ -138,10 +197,13 @@ impl OscClient {
         sock.set_nonblocking(true)?;
         let mut buf = [0u8; 65536];
         let result = match sock.recv_from(&mut buf) {
-            Ok((size, _)) => match rosc::decoder::decode_udp(&buf[..size]) {
-                Ok((_, packet)) => Ok(Some(packet)),
-                Err(e) => Err(anyhow::anyhow!("Failed to decode OSC packet: {}", e)),
-            },
+            Ok((size, _)) => {
+                self.capture.record(Direction::In, &buf[..size]);
+                match rosc::decoder::decode_udp(&buf[..size]) {
+                    Ok((_, packet)) => Ok(Some(packet)),
+                    Err(e) => Err(anyhow::anyhow!("Failed to decode OSC packet: {}", e)),
+                }
+            }
             Err(e) => {
                 if e.kind() == std::io::ErrorKind::WouldBlock {
                     Ok(None)
@@ -185,4 +247,53 @@ mod tests {
             panic!("Expected message packet");
         }
     }
+
+    fn s_new(node: i32) -> OscPacket {
+        OscClient::msg(
+            "/s_new",
+            vec![
+                OscType::String("sample_voice_stereo".into()),
+                OscType::Int(node),
+                OscType::Int(0),
+                OscType::Int(1),
+                OscType::String("amp".into()),
+                OscType::Float(0.5),
+                OscType::String("rate".into()),
+                OscType::Float(1.0),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_split_bundle() {
+        let timetag = OscTime::from((3_900_000_000, 42));
+
+        // Small bundles are sent as they are
+        let bundles = split_bundle(timetag, vec![s_new(1000), s_new(1001)], MAX_BUNDLE_SIZE).unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].content.len(), 2);
+
+        // An oversized bundle is split by encoded size, keeping order and timetag
+        let packets: Vec<OscPacket> = (0..500).map(|i| s_new(1000 + i)).collect();
+        let full = encoder::encode(&OscPacket::Bundle(OscBundle {
+            timetag,
+            content: packets.clone(),
+        }))
+        .unwrap();
+        assert!(full.len() > MAX_BUNDLE_SIZE);
+
+        let bundles = split_bundle(timetag, packets.clone(), MAX_BUNDLE_SIZE).unwrap();
+        assert!(bundles.len() > 1);
+        for bundle in &bundles {
+            assert_eq!(bundle.timetag, timetag);
+            let encoded = encoder::encode(&OscPacket::Bundle(bundle.clone())).unwrap();
+            assert!(encoded.len() <= MAX_BUNDLE_SIZE);
+        }
+        let rejoined: Vec<OscPacket> = bundles.into_iter().flat_map(|b| b.content).collect();
+        assert_eq!(rejoined, packets);
+
+        // A single packet over the limit still goes out, on its own
+        let bundles = split_bundle(timetag, vec![s_new(1), s_new(2), s_new(3)], 64).unwrap();
+        assert_eq!(bundles.len(), 3);
+    }
 }
@@ -0,0 +1,248 @@
+//! Capture of OSC traffic to a file.
+//!
+//! While a capture is running, every datagram sent to or received from
+//! scsynth is appended to the capture file together with the wall-clock
+//! time and transport beat it passed at. `vibe osc-dump` reads the file back
+//! to debug timing and ordering without rebuilding with trace logging.
+//!
+//! The file starts with [`CAPTURE_MAGIC`], followed by one record per
+//! datagram: unix time in microseconds (u64), beat (f64), direction (u8),
+//! payload length (u32), all little-endian, and the raw OSC payload.
+
+use anyhow::{bail, Context, Result};
+use rosc::{OscMessage, OscPacket, OscTime};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// First bytes of every capture file.
+pub const CAPTURE_MAGIC: &[u8; 8] = b"VIBEOSC1";
+
+/// Seconds between the NTP epoch (1900) used by OSC timetags and the unix epoch.
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+/// Size of a record header in bytes.
+const RECORD_HEADER_SIZE: usize = 8 + 8 + 1 + 4;
+
+/// Which way a packet travelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent to scsynth.
+    Out,
+    /// Received from scsynth.
+    In,
+}
+
+impl Direction {
+    /// "out" or "in".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Out => "out",
+            Direction::In => "in",
+        }
+    }
+}
+
+#[derive(Default)]
+struct CaptureShared {
+    active: AtomicBool,
+    beat_bits: AtomicU64,
+    file: Mutex<Option<(PathBuf, File)>>,
+}
+
+/// Handle to the OSC capture of a client; clones share the same capture.
+#[derive(Clone, Default)]
+pub struct OscCapture {
+    shared: Arc<CaptureShared>,
+}
+
+impl OscCapture {
+    /// Start capturing to `path`, replacing a running capture.
+    pub fn start(&self, path: &Path) -> Result<()> {
+        let mut file = File::create(path)
+            .with_context(|| format!("Failed to create OSC capture file: {}", path.display()))?;
+        file.write_all(CAPTURE_MAGIC)?;
+        *self.shared.file.lock().unwrap() = Some((path.to_path_buf(), file));
+        self.shared.active.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Stop capturing, returning the file that was written.
+    pub fn stop(&self) -> Option<PathBuf> {
+        self.shared.active.store(false, Ordering::Relaxed);
+        self.shared.file.lock().unwrap().take().map(|(path, _)| path)
+    }
+
+    /// Whether a capture is running.
+    pub fn is_active(&self) -> bool {
+        self.shared.active.load(Ordering::Relaxed)
+    }
+
+    /// Set the transport beat stamped on the following records.
+    pub fn set_beat(&self, beat: f64) {
+        self.shared.beat_bits.store(beat.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Append an encoded packet if a capture is running.
+    pub fn record(&self, direction: Direction, payload: &[u8]) {
+        if !self.is_active() {
+            return;
+        }
+        let micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+        let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+        record.extend_from_slice(&micros.to_le_bytes());
+        record.extend_from_slice(&self.shared.beat_bits.load(Ordering::Relaxed).to_le_bytes());
+        record.push(match direction {
+            Direction::Out => 0,
+            Direction::In => 1,
+        });
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(payload);
+
+        let mut file = self.shared.file.lock().unwrap();
+        // Written in one go so a crash leaves at most one partial record
+        if let Some((path, f)) = file.as_mut() {
+            if let Err(e) = f.write_all(&record) {
+                log::warn!("[OSC] Stopping capture to {}: {}", path.display(), e);
+                self.shared.active.store(false, Ordering::Relaxed);
+                *file = None;
+            }
+        }
+    }
+}
+
+/// A captured packet.
+#[derive(Clone, Debug)]
+pub struct CapturedPacket {
+    /// Unix time the packet passed at, in microseconds.
+    pub unix_micros: u64,
+    /// Transport beat at that time.
+    pub beat: f64,
+    /// Which way it travelled.
+    pub direction: Direction,
+    /// The decoded packet.
+    pub packet: OscPacket,
+}
+
+impl CapturedPacket {
+    /// The messages of the packet with the timetag of their bundle, in order.
+    pub fn messages(&self) -> Vec<(Option<OscTime>, &OscMessage)> {
+        fn collect<'a>(packet: &'a OscPacket, timetag: Option<OscTime>, out: &mut Vec<(Option<OscTime>, &'a OscMessage)>) {
+            match packet {
+                OscPacket::Message(message) => out.push((timetag, message)),
+                OscPacket::Bundle(bundle) => {
+                    for content in &bundle.content {
+                        collect(content, Some(bundle.timetag), out);
+                    }
+                }
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.packet, None, &mut out);
+        out
+    }
+}
+
+/// Unix time in microseconds a timetag stands for, `None` for "immediately".
+pub fn timetag_unix_micros(timetag: OscTime) -> Option<u64> {
+    if timetag.seconds == 0 && timetag.fractional <= 1 {
+        return None;
+    }
+    let secs = (timetag.seconds as u64).checked_sub(NTP_UNIX_OFFSET_SECS)?;
+    let frac_micros = (timetag.fractional as u64 * 1_000_000) >> 32;
+    Some(secs * 1_000_000 + frac_micros)
+}
+
+/// Read all packets of a capture file.
+///
+/// A record cut short at the end (the session crashed mid-write) is ignored.
+pub fn read_capture(path: &Path) -> Result<Vec<CapturedPacket>> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .with_context(|| format!("Failed to open OSC capture file: {}", path.display()))?
+        .read_to_end(&mut bytes)?;
+    parse_capture(&bytes)
+}
+
+fn parse_capture(bytes: &[u8]) -> Result<Vec<CapturedPacket>> {
+    if !bytes.starts_with(CAPTURE_MAGIC) {
+        bail!("Not an OSC capture file");
+    }
+    let mut packets = Vec::new();
+    let mut rest = &bytes[CAPTURE_MAGIC.len()..];
+    while rest.len() >= RECORD_HEADER_SIZE {
+        let unix_micros = u64::from_le_bytes(rest[0..8].try_into().unwrap());
+        let beat = f64::from_le_bytes(rest[8..16].try_into().unwrap());
+        let direction = if rest[16] == 0 { Direction::Out } else { Direction::In };
+        let len = u32::from_le_bytes(rest[17..21].try_into().unwrap()) as usize;
+        let Some(payload) = rest.get(RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + len) else {
+            break;
+        };
+        match rosc::decoder::decode_udp(payload) {
+            Ok((_, packet)) => packets.push(CapturedPacket {
+                unix_micros,
+                beat,
+                direction,
+                packet,
+            }),
+            Err(e) => log::warn!("Skipping undecodable OSC packet in capture: {}", e),
+        }
+        rest = &rest[RECORD_HEADER_SIZE + len..];
+    }
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosc::{encoder, OscBundle, OscType};
+
+    #[test]
+    fn test_capture_round_trip() {
+        let path = std::env::temp_dir().join(format!("vibelang-osc-capture-{}.oscdump", std::process::id()));
+        let capture = OscCapture::default();
+        let message = |addr: &str| {
+            OscPacket::Message(OscMessage {
+                addr: addr.to_string(),
+                args: vec![OscType::Int(1001)],
+            })
+        };
+
+        // Nothing is recorded before the capture starts
+        capture.record(Direction::Out, &encoder::encode(&message("/status")).unwrap());
+        capture.start(&path).unwrap();
+        capture.set_beat(4.5);
+        let timetag = OscTime {
+            seconds: (NTP_UNIX_OFFSET_SECS + 10) as u32,
+            fractional: 1 << 31,
+        };
+        let bundle = OscPacket::Bundle(OscBundle {
+            timetag,
+            content: vec![message("/s_new"), message("/n_set")],
+        });
+        capture.record(Direction::Out, &encoder::encode(&bundle).unwrap());
+        capture.record(Direction::In, &encoder::encode(&message("/n_go")).unwrap());
+        assert_eq!(capture.stop(), Some(path.clone()));
+        capture.record(Direction::Out, &encoder::encode(&message("/status")).unwrap());
+
+        let packets = read_capture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].beat, 4.5);
+        assert_eq!(packets[1].direction, Direction::In);
+        let messages = packets[0].messages();
+        assert_eq!(
+            messages.iter().map(|(_, m)| m.addr.as_str()).collect::<Vec<_>>(),
+            vec!["/s_new", "/n_set"]
+        );
+        assert_eq!(timetag_unix_micros(messages[0].0.unwrap()), Some(10_500_000));
+        assert_eq!(timetag_unix_micros(OscTime::from((0, 1))), None);
+        assert!(parse_capture(b"garbage").is_err());
+    }
+}
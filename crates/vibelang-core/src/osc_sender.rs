@@ -3,13 +3,15 @@
 //! This module provides a single point for all OSC communication with SuperCollider,
 //! enabling consistent score capture for offline rendering.
 
+use crate::performance::OscStats;
 use crate::scsynth::{AddAction, BufNum, NodeId, Scsynth, Target};
 use crate::score::ScoreWriter;
+use crate::synthdef_versions::SynthDefVersions;
 use crate::timing::{BeatTime, TransportClock};
 use anyhow::Result;
-use rosc::{OscMessage, OscPacket, OscType};
+use rosc::{OscMessage, OscPacket, OscTime, OscType};
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 /// Convert OscTiming to seconds for score capture.
 ///
@@ -63,6 +65,10 @@ pub struct OscSender {
     score_capture: Option<ScoreCaptureState>,
     /// Current tempo in BPM (for time calculations).
     tempo: f64,
+    /// Sent bundles, their timing and failed sends.
+    stats: OscStats,
+    /// Versions of redefined synthdefs, which every `/s_new` is pointed at.
+    synthdef_versions: SynthDefVersions,
 }
 
 impl OscSender {
@@ -72,9 +78,29 @@ impl OscSender {
             sc,
             score_capture: None,
             tempo: 120.0,
+            stats: OscStats::default(),
+            synthdef_versions: SynthDefVersions::default(),
         }
     }
 
+    /// Bundles sent so far, their timing and failed sends.
+    pub fn stats(&self) -> &OscStats {
+        &self.stats
+    }
+
+    /// Count a failed send.
+    fn counted<T>(&mut self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.stats.failures += 1;
+        }
+        result
+    }
+
+    /// Versions of the synthdefs redefined during the session.
+    pub fn synthdef_versions_mut(&mut self) -> &mut SynthDefVersions {
+        &mut self.synthdef_versions
+    }
+
     /// Get a reference to the underlying Scsynth.
     pub fn scsynth(&self) -> &Scsynth {
         &self.sc
@@ -152,6 +178,10 @@ impl OscSender {
         args: Vec<OscType>,
         current_beat: f64,
     ) -> Result<()> {
+        let mut msg = OscMessage { addr: addr.to_string(), args };
+        self.synthdef_versions.rewrite_message(&mut msg);
+        let args = msg.args;
+
         // Capture to score if enabled
         if let Some(ref mut capture) = self.score_capture {
             let time_seconds = timing_to_seconds(timing, current_beat, capture.start_beat, self.tempo);
@@ -159,16 +189,19 @@ impl OscSender {
         }
 
         // Send to scsynth
-        self.sc.osc.send_msg(addr, args)
+        let result = self.sc.osc.send_msg(addr, args);
+        self.counted(result)
     }
 
     /// Send a raw OSC packet with the specified timing.
     pub fn send_packet(
         &mut self,
         timing: OscTiming,
-        packet: OscPacket,
+        mut packet: OscPacket,
         current_beat: f64,
     ) -> Result<()> {
+        self.synthdef_versions.rewrite(&mut packet);
+
         // Capture to score if enabled
         if let Some(ref mut capture) = self.score_capture {
             let time_seconds = timing_to_seconds(timing, current_beat, capture.start_beat, self.tempo);
@@ -177,8 +210,8 @@ impl OscSender {
 
         // Send to scsynth
         let encoded = rosc::encoder::encode(&packet)?;
-        self.sc.osc.send_raw(&encoded)?;
-        Ok(())
+        let result = self.sc.osc.send_raw(&encoded);
+        self.counted(result)
     }
 
     /// Send a timed bundle at a specific beat.
@@ -190,13 +223,29 @@ impl OscSender {
         packets: Vec<OscPacket>,
         transport: &TransportClock,
         now: Instant,
+    ) -> Result<()> {
+        self.send_bundle_at_beat_with_offset(beat_time, packets, transport, now, 0.0)
+    }
+
+    /// Send a timed bundle at a specific beat, moved by `offset_ms`.
+    ///
+    /// Used for the MIDI triggers of hardware with its own latency; the
+    /// score keeps the beat.
+    pub fn send_bundle_at_beat_with_offset(
+        &mut self,
+        beat_time: BeatTime,
+        mut packets: Vec<OscPacket>,
+        transport: &TransportClock,
+        now: Instant,
+        offset_ms: f64,
     ) -> Result<()> {
         if packets.is_empty() {
             return Ok(());
         }
+        packets.iter_mut().for_each(|p| self.synthdef_versions.rewrite(p));
 
         // Convert beat time to OSC timestamp
-        let (_, timestamp) = transport.beat_to_timestamp_and_instant(beat_time, now);
+        let (due, timestamp) = transport.beat_to_timestamp_and_instant_with_offset(beat_time, now, offset_ms);
 
         // Capture to score if enabled
         if let Some(ref mut capture) = self.score_capture {
@@ -206,7 +255,62 @@ impl OscSender {
         }
 
         // Send to scsynth
-        self.sc.osc.send_bundle(Some(timestamp), packets)
+        let sent = Instant::now();
+        let margin = if due >= sent {
+            (due - sent).as_secs_f64()
+        } else {
+            -(sent - due).as_secs_f64()
+        };
+        self.stats.bundles += 1;
+        self.stats.margins_ms.record(margin * 1000.0);
+        let result = self.sc.osc.send_bundle(Some(timestamp), packets);
+        self.counted(result)
+    }
+
+    /// Send several messages as one bundle for immediate execution.
+    ///
+    /// Used to batch per-tick updates into a single packet.
+    pub fn send_bundle_now(&mut self, mut packets: Vec<OscPacket>, current_beat: f64) -> Result<()> {
+        if packets.is_empty() {
+            return Ok(());
+        }
+        packets.iter_mut().for_each(|p| self.synthdef_versions.rewrite(p));
+
+        // Capture to score if enabled
+        if let Some(ref mut capture) = self.score_capture {
+            let time_seconds =
+                timing_to_seconds(OscTiming::Now, current_beat, capture.start_beat, self.tempo);
+            capture.writer.add_bundle(time_seconds, packets.clone());
+        }
+
+        // Send to scsynth
+        let result = self.sc.osc.send_bundle(None, packets);
+        self.counted(result)
+    }
+
+    /// Send several messages as one bundle to execute `seconds` from now.
+    ///
+    /// Unlike [`send_bundle_at_beat`](Self::send_bundle_at_beat) this does not
+    /// follow the transport, so it also works while it is stopped.
+    pub fn send_bundle_after(&mut self, seconds: f64, mut packets: Vec<OscPacket>, current_beat: f64) -> Result<()> {
+        if packets.is_empty() {
+            return Ok(());
+        }
+        packets.iter_mut().for_each(|p| self.synthdef_versions.rewrite(p));
+        let offset = Duration::from_secs_f64(seconds.max(0.0));
+
+        // Capture to score if enabled
+        if let Some(ref mut capture) = self.score_capture {
+            let time_seconds =
+                timing_to_seconds(OscTiming::Now, current_beat, capture.start_beat, self.tempo) + offset.as_secs_f64();
+            capture.writer.add_bundle(time_seconds, packets.clone());
+        }
+
+        // Send to scsynth
+        let timetag = OscTime::try_from(SystemTime::now() + offset)
+            .map_err(|e| anyhow::anyhow!("Invalid bundle time: {:?}", e))?;
+        let result = self.sc.osc.send_bundle(Some(timetag), packets);
+        self.counted(result)
     }
 
     // ========================================================================
@@ -267,6 +371,9 @@ impl OscSender {
         controls: &[(impl AsRef<str>, f32)],
         current_beat: f64,
     ) -> Result<()> {
+        let def = self.synthdef_versions.resolve(def).into_owned();
+        self.synthdef_versions.node_started(node_id.as_i32(), def.clone());
+
         // Build args
         let mut args: Vec<OscType> = vec![
             OscType::String(def.to_string()),
@@ -292,7 +399,7 @@ impl OscSender {
         }
 
         // Send to scsynth
-        self.sc.s_new(def, node_id, add_action, target, controls)
+        self.sc.s_new(&def, node_id, add_action, target, controls)
     }
 
     /// Set control values on an existing node.
@@ -336,6 +443,30 @@ impl OscSender {
         self.sc.n_free(node_id)
     }
 
+    /// Move an existing node, placing it relative to `target` like `s_new` does.
+    ///
+    /// Uses `/g_head`, `/g_tail`, `/n_before` or `/n_after`; replacing is not
+    /// a move and is rejected.
+    pub fn n_move(
+        &mut self,
+        timing: OscTiming,
+        node_id: NodeId,
+        add_action: AddAction,
+        target: Target,
+        current_beat: f64,
+    ) -> Result<()> {
+        let node = OscType::Int(node_id.as_i32());
+        let target = OscType::Int(target.0);
+        let (addr, args) = match add_action {
+            AddAction::AddToHead => ("/g_head", vec![target, node]),
+            AddAction::AddToTail => ("/g_tail", vec![target, node]),
+            AddAction::AddBefore => ("/n_before", vec![node, target]),
+            AddAction::AddAfter => ("/n_after", vec![node, target]),
+            AddAction::AddReplace => anyhow::bail!("Cannot move node {} by replacing another", node_id.as_i32()),
+        };
+        self.send_msg(timing, addr, args, current_beat)
+    }
+
     /// Pause or resume a node.
     pub fn n_run(
         &mut self,
@@ -402,6 +533,33 @@ impl OscSender {
         self.sc.b_alloc_read(bufnum, path)
     }
 
+    /// Allocate an empty buffer.
+    pub fn b_alloc(
+        &mut self,
+        timing: OscTiming,
+        bufnum: BufNum,
+        num_frames: i32,
+        num_channels: i32,
+        current_beat: f64,
+    ) -> Result<()> {
+        // Capture to score if enabled
+        if let Some(ref mut capture) = self.score_capture {
+            let time_seconds = timing_to_seconds(timing, current_beat, capture.start_beat, self.tempo);
+            capture.writer.add_message(
+                time_seconds,
+                "/b_alloc",
+                vec![
+                    OscType::Int(bufnum.as_i32()),
+                    OscType::Int(num_frames),
+                    OscType::Int(num_channels),
+                ],
+            );
+        }
+
+        // Send to scsynth
+        self.sc.b_alloc(bufnum, num_frames, num_channels)
+    }
+
     /// Free a buffer.
     pub fn b_free(&mut self, timing: OscTiming, bufnum: BufNum, current_beat: f64) -> Result<()> {
         // Capture to score if enabled
@@ -415,7 +573,8 @@ impl OscSender {
         }
 
         // Send to scsynth
-        self.sc.osc.send_msg("/b_free", vec![OscType::Int(bufnum.as_i32())])
+        let result = self.sc.osc.send_msg("/b_free", vec![OscType::Int(bufnum.as_i32())]);
+        self.counted(result)
     }
 }
 
@@ -0,0 +1,472 @@
+//! Server CPU monitoring and automatic degradation.
+//!
+//! The runtime polls scsynth's `/status` once per second. When the average
+//! DSP load exceeds the configured budget, the [`CpuPolicy`] decides how to
+//! thin the mix: drop events of low-priority voices, halve polyphony and
+//! postpone sequence fades until the load recovers.
+//!
+//! When the tick loop itself falls behind, overdue events would fire late in
+//! a burst. The policy can be set to drop stale non-essential events (ghost
+//! notes and modulation) instead, while anchors (downbeat hits) always fire.
+//! A ghost note is measured against its own pattern or melody: it is much
+//! quieter than the loop's loudest event. Note-offs are scheduled separately
+//! and are never dropped.
+//!
+//! [`OscStats`] count the timed bundles sent to scsynth, how long before
+//! their timetag they left, and failed sends; `vibe stress` reports them.
+
+use crate::events::BeatEvent;
+#[cfg(feature = "native")]
+use rosc::OscType;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// How often scsynth is asked for its status.
+pub const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Server load and node counts from a `/status.reply`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerStatus {
+    /// Number of running unit generators.
+    pub num_ugens: i32,
+    /// Number of synth nodes.
+    pub num_synths: i32,
+    /// Number of group nodes.
+    pub num_groups: i32,
+    /// Number of loaded synthdefs.
+    pub num_synthdefs: i32,
+    /// Average DSP load in percent.
+    pub avg_cpu: f32,
+    /// Peak DSP load in percent.
+    pub peak_cpu: f32,
+    /// Nominal sample rate.
+    pub nominal_sample_rate: f64,
+    /// Measured sample rate.
+    pub actual_sample_rate: f64,
+}
+
+#[cfg(feature = "native")]
+impl ServerStatus {
+    /// Parse the arguments of a `/status.reply` message.
+    ///
+    /// Layout: unused, #ugens, #synths, #groups, #synthdefs, avg cpu,
+    /// peak cpu, nominal sample rate, actual sample rate.
+    pub fn from_reply(args: &[OscType]) -> Option<Self> {
+        let int = |i: usize| match args.get(i)? {
+            OscType::Int(v) => Some(*v),
+            _ => None,
+        };
+        let float = |i: usize| match args.get(i)? {
+            OscType::Float(v) => Some(*v as f64),
+            OscType::Double(v) => Some(*v),
+            _ => None,
+        };
+        Some(Self {
+            num_ugens: int(1)?,
+            num_synths: int(2)?,
+            num_groups: int(3)?,
+            num_synthdefs: int(4)?,
+            avg_cpu: float(5)? as f32,
+            peak_cpu: float(6)? as f32,
+            nominal_sample_rate: float(7).unwrap_or(0.0),
+            actual_sample_rate: float(8).unwrap_or(0.0),
+        })
+    }
+}
+
+/// What to do when the server runs over its CPU budget.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpuPolicy {
+    /// Average CPU load (percent) above which the mix is degraded.
+    pub threshold: f32,
+    /// Load must fall this many percent below the threshold to recover.
+    pub recovery_margin: f32,
+    /// Drop events of the lowest-priority voices, one tier per poll.
+    pub drop_low_priority_voices: bool,
+    /// Halve the polyphony of every voice.
+    pub reduce_polyphony: bool,
+    /// Hold back fades started by sequences until the load recovers.
+    pub postpone_fades: bool,
+    /// Drop non-anchor events the tick loop reaches too late (off by default).
+    pub drop_stale_events: bool,
+    /// Lateness (ms) after which an event counts as stale.
+    pub stale_threshold_ms: f32,
+    /// Events quieter than this fraction of the loudest event of their
+    /// pattern or melody are ghost notes.
+    pub ghost_ratio: f32,
+}
+
+impl Default for CpuPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: 80.0,
+            recovery_margin: 10.0,
+            drop_low_priority_voices: true,
+            reduce_polyphony: true,
+            postpone_fades: true,
+            drop_stale_events: false,
+            stale_threshold_ms: 30.0,
+            ghost_ratio: 0.5,
+        }
+    }
+}
+
+/// Result of evaluating a new load measurement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetTransition {
+    /// Nothing changed.
+    Unchanged,
+    /// The load crossed the threshold.
+    OverBudget,
+    /// Still over budget; degradation was escalated.
+    Escalated,
+    /// The load fell below the recovery level.
+    Recovered,
+}
+
+/// Degradation state driven by successive load measurements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CpuBudget {
+    /// Whether the load is currently over budget.
+    pub over_budget: bool,
+    /// Number of voice priority tiers being dropped.
+    pub degrade_level: u32,
+}
+
+impl CpuBudget {
+    /// Update the budget with a new average load.
+    ///
+    /// `max_level` caps how many priority tiers may be dropped.
+    pub fn update(&mut self, avg_cpu: f32, policy: &CpuPolicy, max_level: u32) -> BudgetTransition {
+        if avg_cpu > policy.threshold {
+            if !self.over_budget {
+                self.over_budget = true;
+                self.degrade_level = 1.min(max_level);
+                return BudgetTransition::OverBudget;
+            }
+            if self.degrade_level < max_level {
+                self.degrade_level += 1;
+                return BudgetTransition::Escalated;
+            }
+        } else if self.over_budget && avg_cpu < policy.threshold - policy.recovery_margin {
+            *self = Self::default();
+            return BudgetTransition::Recovered;
+        }
+        BudgetTransition::Unchanged
+    }
+}
+
+/// Priority below which voice events are dropped at a degradation level.
+///
+/// The `level` lowest distinct priorities are dropped, but the highest tier
+/// always keeps playing. Returns `None` if nothing should be dropped.
+pub fn drop_priority_cutoff(priorities: impl IntoIterator<Item = i64>, level: u32) -> Option<i64> {
+    if level == 0 {
+        return None;
+    }
+    let tiers: Vec<i64> = priorities.into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+    let dropped = (level as usize).min(tiers.len().saturating_sub(1));
+    if dropped == 0 {
+        None
+    } else {
+        Some(tiers[dropped])
+    }
+}
+
+/// Number of droppable priority tiers among the given priorities.
+pub fn max_degrade_level(priorities: impl IntoIterator<Item = i64>) -> u32 {
+    let tiers = priorities.into_iter().collect::<BTreeSet<_>>().len();
+    tiers.saturating_sub(1) as u32
+}
+
+fn event_amp(event: &BeatEvent) -> Option<f32> {
+    event.controls.iter().find(|(name, _)| name == "amp").map(|(_, amp)| *amp)
+}
+
+/// Loudest `amp` of a loop's events, the level its ghost notes are measured
+/// against.
+pub fn peak_amp(events: &[BeatEvent]) -> Option<f32> {
+    events.iter().filter_map(event_amp).reduce(f32::max)
+}
+
+/// Whether a late event must fire even so.
+///
+/// Downbeat hits are anchors; fades (modulation) are not, nor are ghost
+/// notes: events quieter than the policy's `ghost_ratio` of `peak_amp`, the
+/// loudest event of their pattern or melody ([`peak_amp`]). Everything else,
+/// including events outside a loop, is an anchor.
+pub fn is_anchor_event(event: &BeatEvent, beat: f64, beats_per_bar: f64, peak_amp: Option<f32>, policy: &CpuPolicy) -> bool {
+    let bar_position = beat.rem_euclid(beats_per_bar.max(f64::EPSILON));
+    if bar_position < 1e-6 || beats_per_bar - bar_position < 1e-6 {
+        return true;
+    }
+    if event.fade.is_some() {
+        return false;
+    }
+    match (event_amp(event), peak_amp) {
+        (Some(amp), Some(peak)) if peak > 0.0 => amp >= peak * policy.ghost_ratio,
+        _ => true,
+    }
+}
+
+/// Whether the tick loop is so late that the policy drops non-anchor events.
+pub fn is_stale(late_ms: f64, policy: &CpuPolicy) -> bool {
+    policy.drop_stale_events && late_ms > policy.stale_threshold_ms as f64
+}
+
+/// Whether a late event should be dropped rather than fired (see
+/// [`is_anchor_event`] for `peak_amp`).
+pub fn drop_stale_event(
+    event: &BeatEvent,
+    beat: f64,
+    late_ms: f64,
+    beats_per_bar: f64,
+    peak_amp: Option<f32>,
+    policy: &CpuPolicy,
+) -> bool {
+    is_stale(late_ms, policy) && !is_anchor_event(event, beat, beats_per_bar, peak_amp, policy)
+}
+
+/// Resolution of a [`TimingHistogram`] in milliseconds.
+const HISTOGRAM_BUCKET_MS: f64 = 0.5;
+
+/// Range of a [`TimingHistogram`]: times beyond it count in the outermost buckets.
+const HISTOGRAM_RANGE_MS: f64 = 1000.0;
+
+/// Distribution of times in milliseconds, from -1s to 1s at 0.5ms resolution.
+///
+/// Takes the same memory however many times are recorded, so it can run
+/// for hours.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+    min_ms: f64,
+    max_ms: f64,
+}
+
+impl Default for TimingHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; (2.0 * HISTOGRAM_RANGE_MS / HISTOGRAM_BUCKET_MS) as usize],
+            count: 0,
+            min_ms: f64::INFINITY,
+            max_ms: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl TimingHistogram {
+    /// Record a time.
+    pub fn record(&mut self, ms: f64) {
+        if !ms.is_finite() {
+            return;
+        }
+        let index = ((ms + HISTOGRAM_RANGE_MS) / HISTOGRAM_BUCKET_MS).floor();
+        let index = index.clamp(0.0, (self.buckets.len() - 1) as f64) as usize;
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.min_ms = self.min_ms.min(ms);
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    /// Number of recorded times.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Smallest recorded time.
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min_ms)
+    }
+
+    /// Largest recorded time.
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max_ms)
+    }
+
+    /// Time below which `percent` of the recorded times fall (to the bucket resolution).
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((percent.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                // The outermost buckets also hold the times beyond the range
+                if index == 0 {
+                    return Some(self.min_ms);
+                }
+                if index == self.buckets.len() - 1 {
+                    return Some(self.max_ms);
+                }
+                // Upper edge of the bucket, within the recorded range
+                let ms = (index + 1) as f64 * HISTOGRAM_BUCKET_MS - HISTOGRAM_RANGE_MS;
+                return Some(ms.clamp(self.min_ms, self.max_ms));
+            }
+        }
+        Some(self.max_ms)
+    }
+
+    /// Number of recorded times below `ms` (to the bucket resolution).
+    pub fn count_below(&self, ms: f64) -> u64 {
+        let end = ((ms + HISTOGRAM_RANGE_MS) / HISTOGRAM_BUCKET_MS).floor();
+        let end = end.clamp(0.0, self.buckets.len() as f64) as usize;
+        self.buckets[..end].iter().sum()
+    }
+}
+
+/// Timed bundles sent to scsynth.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OscStats {
+    /// Timed bundles sent.
+    pub bundles: u64,
+    /// Sends that failed (bundles and plain messages).
+    pub failures: u64,
+    /// How long before its timetag each timed bundle was sent, in milliseconds
+    /// (negative = the bundle was late).
+    pub margins_ms: TimingHistogram,
+}
+
+/// Events the runtime thread dropped or fired late.
+///
+/// Counted by the runtime thread as it fires events, without locking the
+/// state, and published to it with each status poll.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EventStats {
+    /// Events dropped since the load last went over budget.
+    pub dropped: u64,
+    /// Events fired after their time because the tick loop fell behind.
+    pub late: u64,
+    /// Stale non-anchor events dropped because the tick loop fell behind.
+    pub stale_dropped: u64,
+}
+
+/// Polyphony to enforce while the mix is degraded.
+pub fn reduced_polyphony(polyphony: i64) -> i64 {
+    if polyphony <= 0 {
+        polyphony
+    } else {
+        (polyphony / 2).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_parse_status_reply() {
+        let args = vec![
+            OscType::Int(1),
+            OscType::Int(120),
+            OscType::Int(14),
+            OscType::Int(6),
+            OscType::Int(42),
+            OscType::Float(37.5),
+            OscType::Float(61.0),
+            OscType::Double(48000.0),
+            OscType::Double(47999.7),
+        ];
+        let status = ServerStatus::from_reply(&args).expect("valid reply");
+        assert_eq!(status.num_ugens, 120);
+        assert_eq!(status.num_synths, 14);
+        assert_eq!(status.num_synthdefs, 42);
+        assert!((status.avg_cpu - 37.5).abs() < 1e-6);
+        assert!((status.nominal_sample_rate - 48000.0).abs() < 1e-9);
+
+        assert!(ServerStatus::from_reply(&args[..3]).is_none());
+    }
+
+    #[test]
+    fn test_timing_histogram_percentiles() {
+        let mut histogram = TimingHistogram::default();
+        assert_eq!(histogram.percentile(50.0), None);
+        for i in 0..100 {
+            histogram.record(200.0 + i as f64);
+        }
+        histogram.record(-12.0);
+        histogram.record(5000.0);
+        assert_eq!(histogram.count(), 102);
+        assert_eq!(histogram.min(), Some(-12.0));
+        assert_eq!(histogram.max(), Some(5000.0));
+        assert_eq!(histogram.percentile(0.0), Some(-11.5));
+        assert_eq!(histogram.percentile(50.0), Some(249.5));
+        assert_eq!(histogram.percentile(100.0), Some(5000.0));
+        assert_eq!(histogram.count_below(0.0), 1);
+        assert_eq!(histogram.count_below(250.0), 51);
+        histogram.record(f64::NAN);
+        assert_eq!(histogram.count(), 102);
+    }
+
+    #[test]
+    fn test_budget_hysteresis() {
+        let policy = CpuPolicy::default();
+        let mut budget = CpuBudget::default();
+
+        assert_eq!(budget.update(50.0, &policy, 2), BudgetTransition::Unchanged);
+        assert_eq!(budget.update(90.0, &policy, 2), BudgetTransition::OverBudget);
+        assert_eq!(budget.degrade_level, 1);
+        assert_eq!(budget.update(95.0, &policy, 2), BudgetTransition::Escalated);
+        assert_eq!(budget.update(95.0, &policy, 2), BudgetTransition::Unchanged);
+        assert_eq!(budget.degrade_level, 2);
+
+        // Between recovery level and threshold: stay degraded
+        assert_eq!(budget.update(75.0, &policy, 2), BudgetTransition::Unchanged);
+        assert!(budget.over_budget);
+
+        assert_eq!(budget.update(60.0, &policy, 2), BudgetTransition::Recovered);
+        assert_eq!(budget, CpuBudget::default());
+    }
+
+    #[test]
+    fn test_drop_priority_cutoff() {
+        let priorities = [0, 0, -1, 5, -1];
+        assert_eq!(drop_priority_cutoff(priorities, 0), None);
+        assert_eq!(drop_priority_cutoff(priorities, 1), Some(0));
+        assert_eq!(drop_priority_cutoff(priorities, 2), Some(5));
+        // The highest tier is never dropped
+        assert_eq!(drop_priority_cutoff(priorities, 5), Some(5));
+        assert_eq!(drop_priority_cutoff([0, 0], 1), None);
+        assert_eq!(max_degrade_level(priorities), 2);
+    }
+
+    #[test]
+    fn test_reduced_polyphony() {
+        assert_eq!(reduced_polyphony(8), 4);
+        assert_eq!(reduced_polyphony(1), 1);
+        assert_eq!(reduced_polyphony(0), 0);
+    }
+
+    #[test]
+    fn test_drop_stale_events() {
+        let snare = BeatEvent::new(0.0, "trigger").with_control("amp", 0.8);
+        let ghost = BeatEvent::new(0.5, "trigger").with_control("amp", 0.2);
+        let peak = peak_amp(&[snare.clone(), ghost.clone()]);
+        assert_eq!(peak, Some(0.8));
+
+        // Nothing is dropped unless the policy asks for it
+        let disabled = CpuPolicy::default();
+        assert!(!drop_stale_event(&ghost, 9.5, 100.0, 4.0, peak, &disabled));
+
+        let policy = CpuPolicy {
+            drop_stale_events: true,
+            ..CpuPolicy::default()
+        };
+        // Downbeats always fire, even ghost notes
+        assert!(!drop_stale_event(&ghost, 8.0, 100.0, 4.0, peak, &policy));
+        // Off-beat ghost notes are dropped once stale, full hits are not
+        assert!(drop_stale_event(&ghost, 9.5, 100.0, 4.0, peak, &policy));
+        assert!(!drop_stale_event(&snare, 9.5, 100.0, 4.0, peak, &policy));
+        // Slightly late events still fire
+        assert!(!drop_stale_event(&ghost, 9.5, 10.0, 4.0, peak, &policy));
+
+        // A quiet bass line is not made of ghost notes, nor are events outside a loop
+        let bass = BeatEvent::new(0.5, "bass").with_control("amp", 0.25);
+        assert!(!drop_stale_event(&bass, 9.5, 100.0, 4.0, Some(0.3), &policy));
+        assert!(!drop_stale_event(&ghost, 9.5, 100.0, 4.0, None, &policy));
+    }
+}
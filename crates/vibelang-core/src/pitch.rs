@@ -0,0 +1,118 @@
+//! Pitch conversion and note names.
+//!
+//! The one place where MIDI notes and frequencies are converted (A4 = 440 Hz,
+//! middle C = C4 = 60). Logs, the TUI and the HTTP API name event pitches
+//! through [`Pitch`], so a frequency shows up as `C#4`, or `C#4 +14c` when it
+//! falls between equal-tempered notes (microtonal tunings, detuned voices).
+
+use std::fmt;
+
+/// Pitch class names, C = 0.
+pub(crate) const PITCH_CLASS_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// Reference pitch of A4 in Hz.
+const A4_FREQ: f64 = 440.0;
+
+/// MIDI note number of A4.
+const A4_NOTE: f64 = 69.0;
+
+/// Offsets smaller than this (in cents) are shown as in tune.
+const CENTS_TOLERANCE: f64 = 0.5;
+
+/// Frequency in Hz of a (possibly fractional) MIDI note.
+pub fn note_to_freq(note: f64) -> f64 {
+    A4_FREQ * 2.0_f64.powf((note - A4_NOTE) / 12.0)
+}
+
+/// Fractional MIDI note of a frequency in Hz.
+pub fn freq_to_note(freq: f64) -> f64 {
+    A4_NOTE + 12.0 * (freq / A4_FREQ).log2()
+}
+
+/// Nearest MIDI note (0-127) of a frequency in Hz.
+pub fn freq_to_midi_note(freq: f64) -> u8 {
+    freq_to_note(freq).round().clamp(0.0, 127.0) as u8
+}
+
+/// Name of a MIDI note, e.g. 61 -> "C#4".
+pub fn note_name(note: u8) -> String {
+    let octave = (note / 12) as i8 - 1;
+    format!("{}{}", PITCH_CLASS_NAMES[(note % 12) as usize], octave)
+}
+
+/// A pitch as the nearest MIDI note plus an offset in cents.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    /// Nearest MIDI note.
+    pub note: u8,
+    /// Offset from that note in cents (-50 to +50).
+    pub cents: f64,
+}
+
+impl Pitch {
+    /// The pitch of a frequency, or `None` outside the MIDI note range.
+    pub fn from_freq(freq: f64) -> Option<Self> {
+        if !freq.is_finite() || freq <= 0.0 {
+            return None;
+        }
+        let exact = freq_to_note(freq);
+        let nearest = exact.round();
+        if !(0.0..=127.0).contains(&nearest) {
+            return None;
+        }
+        Some(Self {
+            note: nearest as u8,
+            cents: (exact - nearest) * 100.0,
+        })
+    }
+
+    /// Frequency of this pitch in Hz.
+    pub fn freq(&self) -> f64 {
+        note_to_freq(self.note as f64 + self.cents / 100.0)
+    }
+}
+
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", note_name(self.note))?;
+        if self.cents.abs() >= CENTS_TOLERANCE {
+            write!(f, " {:+.0}c", self.cents)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe the pitch of event controls, e.g. "C#4 (vel 96)".
+///
+/// Uses the `freq` control and, when present, `amp` as a 0-127 velocity.
+/// Returns `None` for unpitched events such as drum hits.
+pub fn describe_controls(controls: &[(String, f32)]) -> Option<String> {
+    let control = |name: &str| controls.iter().find(|(k, _)| k == name).map(|(_, v)| *v as f64);
+    let pitch = Pitch::from_freq(control("freq")?)?;
+    Some(match control("amp") {
+        Some(amp) => format!("{} (vel {})", pitch, (amp * 127.0).round().clamp(0.0, 127.0) as u8),
+        None => pitch.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_names() {
+        assert_eq!(note_name(60), "C4");
+        assert_eq!(note_name(61), "C#4");
+        assert_eq!(note_name(0), "C-1");
+        assert_eq!(freq_to_midi_note(note_to_freq(61.0)), 61);
+
+        assert_eq!(Pitch::from_freq(440.0).unwrap().to_string(), "A4");
+        assert_eq!(Pitch::from_freq(note_to_freq(61.14)).unwrap().to_string(), "C#4 +14c");
+        assert_eq!(Pitch::from_freq(note_to_freq(59.7)).unwrap().to_string(), "C4 -30c");
+        assert!(Pitch::from_freq(0.0).is_none());
+
+        let controls = vec![("freq".to_string(), note_to_freq(49.0) as f32), ("amp".to_string(), 0.756)];
+        assert_eq!(describe_controls(&controls).as_deref(), Some("C#3 (vel 96)"));
+        assert_eq!(describe_controls(&[("amp".to_string(), 1.0)]), None);
+    }
+}
@@ -0,0 +1,289 @@
+//! Branching playback graphs for adaptive music.
+//!
+//! A playback graph arranges sequences as *sections* connected by
+//! transitions. While a graph plays, the runtime checks the transitions
+//! leaving the current section every tick and takes the first one whose
+//! condition holds:
+//!
+//! - `on_cue` - the graph was cued (Rhai, HTTP or a live set scene)
+//! - `after` - the section has played for a number of beats
+//! - `when` - a graph variable compares true against a threshold
+//!
+//! The transition style decides how the next section comes in: right away,
+//! at the next bar line, or crossfaded at the next bar line when both
+//! sections have a group to fade.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A playback graph definition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaybackGraph {
+    /// Graph name.
+    pub name: String,
+    /// Sections in declaration order.
+    pub sections: Vec<GraphSection>,
+    /// Transitions in priority order.
+    pub transitions: Vec<GraphTransition>,
+    /// Length of crossfade transitions in beats.
+    pub crossfade_beats: f64,
+}
+
+/// A section of a playback graph, played by a sequence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphSection {
+    /// Section name.
+    pub name: String,
+    /// Sequence that plays while the section is current.
+    pub sequence: String,
+    /// Group whose `amp` is faded by crossfade transitions.
+    pub group: Option<String>,
+}
+
+/// A conditional transition between two sections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphTransition {
+    /// Section the transition leaves.
+    pub from: String,
+    /// Section the transition enters.
+    pub to: String,
+    /// When the transition is taken.
+    pub condition: TransitionCondition,
+    /// How the next section comes in.
+    pub style: TransitionStyle,
+}
+
+/// When a transition is taken.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransitionCondition {
+    /// The graph was cued.
+    OnCue,
+    /// The section has played for this many beats.
+    After(f64),
+    /// A graph variable compares true against a value.
+    When {
+        var: String,
+        op: CompareOp,
+        value: f64,
+    },
+}
+
+/// Comparison operator of a `when` condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+/// How the next section comes in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionStyle {
+    /// Switch sequences now.
+    Immediate,
+    /// Switch sequences at the next bar line.
+    NextBar,
+    /// Start the next section at the next bar line and crossfade the
+    /// section groups over the graph's crossfade length.
+    Crossfade,
+}
+
+impl CompareOp {
+    /// Parse `>`, `>=`, `<`, `<=`, `==` or `!=`.
+    pub fn parse(op: &str) -> Option<Self> {
+        match op {
+            ">" => Some(CompareOp::Gt),
+            ">=" => Some(CompareOp::Ge),
+            "<" => Some(CompareOp::Lt),
+            "<=" => Some(CompareOp::Le),
+            "==" => Some(CompareOp::Eq),
+            "!=" => Some(CompareOp::Ne),
+            _ => None,
+        }
+    }
+
+    /// Operator symbol.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+        }
+    }
+
+    /// Compare `lhs` against `rhs`.
+    pub fn apply(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            CompareOp::Gt => lhs > rhs,
+            CompareOp::Ge => lhs >= rhs,
+            CompareOp::Lt => lhs < rhs,
+            CompareOp::Le => lhs <= rhs,
+            CompareOp::Eq => (lhs - rhs).abs() < f64::EPSILON,
+            CompareOp::Ne => (lhs - rhs).abs() >= f64::EPSILON,
+        }
+    }
+}
+
+impl TransitionStyle {
+    /// Parse `immediate`, `next_bar` or `crossfade`.
+    pub fn parse(style: &str) -> Option<Self> {
+        match style {
+            "immediate" => Some(TransitionStyle::Immediate),
+            "next_bar" => Some(TransitionStyle::NextBar),
+            "crossfade" => Some(TransitionStyle::Crossfade),
+            _ => None,
+        }
+    }
+
+    /// Style name as accepted by [`TransitionStyle::parse`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransitionStyle::Immediate => "immediate",
+            TransitionStyle::NextBar => "next_bar",
+            TransitionStyle::Crossfade => "crossfade",
+        }
+    }
+}
+
+impl fmt::Display for TransitionCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionCondition::OnCue => write!(f, "on_cue"),
+            TransitionCondition::After(beats) => write!(f, "after {} beats", beats),
+            TransitionCondition::When { var, op, value } => {
+                write!(f, "{} {} {}", var, op.as_str(), value)
+            }
+        }
+    }
+}
+
+impl PlaybackGraph {
+    /// Create an empty graph with a one-bar (4 beat) crossfade.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            sections: Vec::new(),
+            transitions: Vec::new(),
+            crossfade_beats: 4.0,
+        }
+    }
+
+    /// Look up a section by name.
+    pub fn section(&self, name: &str) -> Option<&GraphSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    /// Check that every transition connects known sections.
+    pub fn validate(&self) -> Result<(), String> {
+        for transition in &self.transitions {
+            for name in [&transition.from, &transition.to] {
+                if self.section(name).is_none() {
+                    return Err(format!(
+                        "graph '{}': transition refers to unknown section '{}'",
+                        self.name, name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// First transition out of `current` whose condition holds.
+    ///
+    /// `elapsed_beats` is how long the section has played, `cued` whether
+    /// the graph was cued since the section was entered.
+    pub fn next_transition(
+        &self,
+        current: &str,
+        elapsed_beats: f64,
+        cued: bool,
+        vars: &HashMap<String, f64>,
+    ) -> Option<&GraphTransition> {
+        self.transitions
+            .iter()
+            .filter(|t| t.from == current)
+            .find(|t| match &t.condition {
+                TransitionCondition::OnCue => cued,
+                TransitionCondition::After(beats) => elapsed_beats >= *beats,
+                TransitionCondition::When { var, op, value } => {
+                    vars.get(var).is_some_and(|v| op.apply(*v, *value))
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> PlaybackGraph {
+        let mut graph = PlaybackGraph::new("game");
+        for name in ["explore", "combat", "calm"] {
+            graph.sections.push(GraphSection {
+                name: name.to_string(),
+                sequence: format!("{}_seq", name),
+                group: None,
+            });
+        }
+        graph.transitions = vec![
+            GraphTransition {
+                from: "explore".to_string(),
+                to: "combat".to_string(),
+                condition: TransitionCondition::When {
+                    var: "intensity".to_string(),
+                    op: CompareOp::Gt,
+                    value: 0.7,
+                },
+                style: TransitionStyle::Crossfade,
+            },
+            GraphTransition {
+                from: "explore".to_string(),
+                to: "calm".to_string(),
+                condition: TransitionCondition::OnCue,
+                style: TransitionStyle::Immediate,
+            },
+            GraphTransition {
+                from: "combat".to_string(),
+                to: "explore".to_string(),
+                condition: TransitionCondition::After(32.0),
+                style: TransitionStyle::NextBar,
+            },
+        ];
+        graph
+    }
+
+    #[test]
+    fn test_next_transition() {
+        let graph = graph();
+        let mut vars = HashMap::new();
+
+        assert!(graph.next_transition("explore", 100.0, false, &vars).is_none());
+        assert_eq!(graph.next_transition("explore", 0.0, true, &vars).unwrap().to, "calm");
+
+        vars.insert("intensity".to_string(), 0.9);
+        // Earlier transitions win when several conditions hold
+        assert_eq!(graph.next_transition("explore", 0.0, true, &vars).unwrap().to, "combat");
+
+        assert!(graph.next_transition("combat", 31.5, false, &vars).is_none());
+        assert_eq!(graph.next_transition("combat", 32.0, false, &vars).unwrap().to, "explore");
+    }
+
+    #[test]
+    fn test_validate_and_parse() {
+        let mut graph = graph();
+        assert!(graph.validate().is_ok());
+        graph.transitions[0].to = "boss".to_string();
+        assert!(graph.validate().unwrap_err().contains("'boss'"));
+
+        assert_eq!(CompareOp::parse(">="), Some(CompareOp::Ge));
+        assert_eq!(CompareOp::parse("=>"), None);
+        assert_eq!(TransitionStyle::parse("next_bar"), Some(TransitionStyle::NextBar));
+        assert_eq!(TransitionStyle::parse("fade"), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! Plugins: Rust crates that extend the runtime.
+//!
+//! A plugin implements [`VibePlugin`] and is registered once at startup,
+//! before engines are created and the runtime starts. It can add functions
+//! and types to every Rhai engine, follow the messages the runtime
+//! processes and run on each runtime tick — enough to drive lights, a game
+//! engine or custom hardware from a script without forking the runtime.
+//!
+//! ```ignore
+//! struct Dmx;
+//!
+//! impl VibePlugin for Dmx {
+//!     fn name(&self) -> &str {
+//!         "dmx"
+//!     }
+//!
+//!     fn register_api(&self, engine: &mut rhai::Engine) {
+//!         engine.register_fn("dmx_scene", |scene: i64| {
+//!             vibelang_core::api::plugin::plugin_send("dmx", "scene", scene.into())
+//!         });
+//!     }
+//!
+//!     fn handle_message(&self, msg: &StateMessage, _state: &StateManager) {
+//!         if let Some(("scene", payload)) = plugin_message(msg, "dmx") {
+//!             // switch the scene
+//!         }
+//!     }
+//! }
+//!
+//! vibelang_core::plugin::register_plugin(Dmx)?;
+//! ```
+//!
+//! Scripts talk to plugins through the plugin's own functions, or with
+//! `plugin_send(plugin, name, payload)`, which sends a
+//! [`StateMessage::Plugin`] through the runtime in order with the
+//! script's other messages. The sandbox refuses plugin messages.
+//!
+//! Plugins run on the runtime thread: `handle_message` and `on_tick` must
+//! return quickly and hand slow work to threads of their own. A plugin
+//! that panics is logged and skipped; the runtime carries on.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{bail, Result};
+use rhai::{Dynamic, Engine};
+
+use crate::state::{StateManager, StateMessage};
+
+/// A runtime extension.
+pub trait VibePlugin: Send + Sync {
+    /// Name of the plugin, unique among the registered plugins.
+    fn name(&self) -> &str;
+
+    /// Add functions and types to a Rhai engine; called for every engine created.
+    fn register_api(&self, _engine: &mut Engine) {}
+
+    /// Follow a message sent to the runtime, after the sender's checks and
+    /// before the runtime applies it.
+    fn handle_message(&self, _msg: &StateMessage, _state: &StateManager) {}
+
+    /// Run on each runtime tick while the transport is running.
+    fn on_tick(&self, _beat: f64, _state: &StateManager) {}
+}
+
+static PLUGINS: RwLock<Vec<Arc<dyn VibePlugin>>> = RwLock::new(Vec::new());
+
+/// Register a plugin.
+///
+/// Engines created before the registration don't have its API.
+pub fn register_plugin(plugin: impl VibePlugin + 'static) -> Result<()> {
+    let mut plugins = PLUGINS.write().unwrap_or_else(|e| e.into_inner());
+    if plugins.iter().any(|p| p.name() == plugin.name()) {
+        bail!("A plugin named '{}' is already registered", plugin.name());
+    }
+    log::info!("[PLUGIN] Registered '{}'", plugin.name());
+    plugins.push(Arc::new(plugin));
+    Ok(())
+}
+
+/// Names of the registered plugins, in registration order.
+pub fn plugin_names() -> Vec<String> {
+    registered().iter().map(|p| p.name().to_string()).collect()
+}
+
+/// Whether a plugin named `name` is registered.
+pub fn is_registered(name: &str) -> bool {
+    registered().iter().any(|p| p.name() == name)
+}
+
+/// Name and payload of a [`StateMessage::Plugin`] sent to `plugin`.
+pub fn plugin_message<'a>(msg: &'a StateMessage, plugin: &str) -> Option<(&'a str, &'a Dynamic)> {
+    match msg {
+        StateMessage::Plugin { plugin: to, name, payload } if to == plugin => Some((name, payload)),
+        _ => None,
+    }
+}
+
+fn registered() -> Vec<Arc<dyn VibePlugin>> {
+    PLUGINS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Run `f` for each plugin, logging the ones that panic.
+fn each(what: &str, mut f: impl FnMut(&dyn VibePlugin)) {
+    for plugin in registered() {
+        if catch_unwind(AssertUnwindSafe(|| f(plugin.as_ref()))).is_err() {
+            log::error!("[PLUGIN] '{}' panicked in {}", plugin.name(), what);
+        }
+    }
+}
+
+/// Add the plugins' APIs to an engine.
+pub(crate) fn register_apis(engine: &mut Engine) {
+    each("register_api", |plugin| plugin.register_api(engine));
+}
+
+/// Show a message to the plugins.
+pub(crate) fn dispatch_message(msg: &StateMessage, state: &StateManager) {
+    each("handle_message", |plugin| plugin.handle_message(msg, state));
+}
+
+/// Run the plugins' tick hooks.
+pub(crate) fn dispatch_tick(beat: f64, state: &StateManager) {
+    each("on_tick", |plugin| plugin.on_tick(beat, state));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Simulation;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        messages: Arc<Mutex<Vec<(String, i64)>>>,
+        ticks: Arc<Mutex<Vec<f64>>>,
+    }
+
+    impl VibePlugin for Recorder {
+        fn name(&self) -> &str {
+            "test_recorder"
+        }
+
+        fn register_api(&self, engine: &mut Engine) {
+            engine.register_fn("recorder_mark", |n: i64| {
+                crate::api::plugin::plugin_send("test_recorder", "mark", n.into())
+            });
+        }
+
+        fn handle_message(&self, msg: &StateMessage, _state: &StateManager) {
+            if let Some((name, payload)) = plugin_message(msg, "test_recorder") {
+                self.messages.lock().unwrap().push((name.to_string(), payload.as_int().unwrap_or_default()));
+            }
+        }
+
+        fn on_tick(&self, beat: f64, _state: &StateManager) {
+            self.ticks.lock().unwrap().push(beat);
+        }
+    }
+
+    #[test]
+    fn test_plugin_api_messages_and_ticks() {
+        let recorder = Recorder::default();
+        let (messages, ticks) = (recorder.messages.clone(), recorder.ticks.clone());
+        register_plugin(recorder).unwrap();
+        assert!(register_plugin(Recorder::default()).is_err());
+        assert!(plugin_names().contains(&"test_recorder".to_string()));
+
+        let mut sim = Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        crate::api::create_engine()
+            .run(r#"recorder_mark(1); plugin_send("test_recorder", "mark", 2);"#)
+            .unwrap();
+        sim.start();
+        sim.advance(1.0);
+
+        assert_eq!(*messages.lock().unwrap(), vec![("mark".to_string(), 1), ("mark".to_string(), 2)]);
+        // Other simulations tick the plugin as well
+        assert!(ticks.lock().unwrap().iter().any(|beat| *beat > 0.0));
+    }
+}
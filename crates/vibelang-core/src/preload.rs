@@ -0,0 +1,287 @@
+//! Preload manifests for instant session start.
+//!
+//! `vibe warmup song.vibe` validates the script and records the synthdefs,
+//! samples and SFZ instruments it uses. `vibe run` then loads all of them
+//! (samples are analysed in parallel) before the script is evaluated, so the
+//! first bar doesn't wait on lazy loads.
+//!
+//! Manifests live in a `.vibe-warmup` directory next to the script, with the
+//! compiled synthdefs in a directory named after the script:
+//!
+//! ```text
+//! # Preload manifest for song.vibe (written by `vibe warmup`)
+//! [synthdefs]
+//! acid_bass
+//!
+//! [samples]
+//! kick = /home/me/samples/kick.wav
+//!
+//! [sfz]
+//! piano = /home/me/sfz/piano.sfz
+//! ```
+
+use crate::state::{ScriptState, StateMessage};
+use crate::validation::{AssetReference, ValidationResult};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory (next to the script) holding preload manifests.
+pub const PRELOAD_DIR: &str = ".vibe-warmup";
+
+/// File extension of preload manifests.
+pub const PRELOAD_EXTENSION: &str = "preload";
+
+/// The assets a script loads.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PreloadManifest {
+    /// Synthdefs defined by the script (bytes are stored beside the manifest).
+    pub synthdefs: Vec<String>,
+    /// Samples by id.
+    pub samples: Vec<AssetReference>,
+    /// SFZ instruments by id.
+    pub sfz_instruments: Vec<AssetReference>,
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Preamble,
+    Synthdefs,
+    Samples,
+    Sfz,
+}
+
+impl PreloadManifest {
+    /// Build a manifest from the assets recorded while validating a script.
+    pub fn from_validation(result: &ValidationResult) -> Self {
+        let mut synthdefs: Vec<String> = result.synthdef_bytes.keys().cloned().collect();
+        synthdefs.sort();
+        Self {
+            synthdefs,
+            samples: result.samples.clone(),
+            sfz_instruments: result.sfz_instruments.clone(),
+        }
+    }
+
+    /// Path of the manifest for a script.
+    pub fn manifest_path(script: &Path) -> PathBuf {
+        Self::preload_dir(script).join(script_stem(script)).with_extension(PRELOAD_EXTENSION)
+    }
+
+    /// Directory holding the compiled synthdefs for a script.
+    pub fn synthdef_dir(script: &Path) -> PathBuf {
+        Self::preload_dir(script).join(script_stem(script))
+    }
+
+    fn preload_dir(script: &Path) -> PathBuf {
+        script.parent().unwrap_or(Path::new(".")).join(PRELOAD_DIR)
+    }
+
+    /// Load the manifest for a script, if `vibe warmup` has written one.
+    pub fn load(script: &Path) -> Result<Option<Self>> {
+        let path = Self::manifest_path(script);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read preload manifest: {}", path.display()))?;
+        Self::parse(&source)
+            .map(Some)
+            .with_context(|| format!("Invalid preload manifest: {}", path.display()))
+    }
+
+    /// Parse manifest text.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut manifest = Self::default();
+        let mut section = Section::Preamble;
+
+        for (index, raw_line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            // Only whole-line comments: paths may contain '#'
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                section = match header.strip_suffix(']').map(str::trim) {
+                    Some("synthdefs") => Section::Synthdefs,
+                    Some("samples") => Section::Samples,
+                    Some("sfz") => Section::Sfz,
+                    _ => bail!("line {}: unknown section '{}'", line_number, line),
+                };
+                continue;
+            }
+
+            match section {
+                Section::Preamble => bail!("line {}: expected a section header", line_number),
+                Section::Synthdefs => manifest.synthdefs.push(line.to_string()),
+                Section::Samples => manifest.samples.push(parse_asset(line, line_number)?),
+                Section::Sfz => manifest.sfz_instruments.push(parse_asset(line, line_number)?),
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Render the manifest as text.
+    pub fn to_text(&self, script: &Path) -> String {
+        let mut out = format!(
+            "# Preload manifest for {} (written by `vibe warmup`)\n[synthdefs]\n",
+            script.file_name().unwrap_or_default().to_string_lossy()
+        );
+        for name in &self.synthdefs {
+            out.push_str(&format!("{}\n", name));
+        }
+        out.push_str("\n[samples]\n");
+        for asset in &self.samples {
+            out.push_str(&format!("{} = {}\n", asset.id, asset.path.display()));
+        }
+        out.push_str("\n[sfz]\n");
+        for asset in &self.sfz_instruments {
+            out.push_str(&format!("{} = {}\n", asset.id, asset.path.display()));
+        }
+        out
+    }
+
+    /// Write the manifest and the compiled synthdefs for a script.
+    pub fn save(&self, script: &Path, synthdef_bytes: &HashMap<String, Vec<u8>>) -> Result<()> {
+        let synthdef_dir = Self::synthdef_dir(script);
+        // Drop synthdefs the script no longer defines
+        if synthdef_dir.exists() {
+            fs::remove_dir_all(&synthdef_dir)
+                .with_context(|| format!("Failed to clear {}", synthdef_dir.display()))?;
+        }
+        fs::create_dir_all(&synthdef_dir)
+            .with_context(|| format!("Failed to create {}", synthdef_dir.display()))?;
+        for name in &self.synthdefs {
+            let bytes = synthdef_bytes
+                .get(name)
+                .ok_or_else(|| anyhow!("No compiled bytes for synthdef '{}'", name))?;
+            fs::write(synthdef_dir.join(format!("{}.scsyndef", name)), bytes)?;
+        }
+
+        let path = Self::manifest_path(script);
+        fs::write(&path, self.to_text(script))
+            .with_context(|| format!("Failed to write preload manifest: {}", path.display()))
+    }
+
+    /// Read the compiled synthdefs stored for a script.
+    ///
+    /// Missing files are skipped; the script defines them again when it runs.
+    pub fn read_synthdefs(&self, script: &Path) -> Vec<(String, Vec<u8>)> {
+        let dir = Self::synthdef_dir(script);
+        self.synthdefs
+            .iter()
+            .filter_map(|name| match fs::read(dir.join(format!("{}.scsyndef", name))) {
+                Ok(bytes) => Some((name.clone(), bytes)),
+                Err(e) => {
+                    log::warn!("[PRELOAD] Skipping synthdef '{}': {}", name, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Message asking the runtime to load the samples and SFZ instruments.
+    pub fn to_message(&self) -> StateMessage {
+        StateMessage::PreloadAssets {
+            samples: self
+                .samples
+                .iter()
+                .map(|a| (a.id.clone(), a.path.to_string_lossy().to_string()))
+                .collect(),
+            sfz_instruments: self
+                .sfz_instruments
+                .iter()
+                .map(|a| (a.id.clone(), a.path.clone()))
+                .collect(),
+        }
+    }
+
+    /// Number of samples and SFZ instruments to load.
+    pub fn asset_count(&self) -> usize {
+        self.samples.len() + self.sfz_instruments.len()
+    }
+
+    /// Number of samples and SFZ instruments already loaded in `state`.
+    pub fn loaded_count(&self, state: &ScriptState) -> usize {
+        let samples = self
+            .samples
+            .iter()
+            .filter(|a| {
+                state
+                    .samples
+                    .get(&a.id)
+                    .is_some_and(|s| Path::new(&s.path) == a.path)
+            })
+            .count();
+        let sfz = self
+            .sfz_instruments
+            .iter()
+            .filter(|a| {
+                state
+                    .sfz_instruments
+                    .get(&a.id)
+                    .is_some_and(|inst| inst.source_file == a.path)
+            })
+            .count();
+        samples + sfz
+    }
+}
+
+fn script_stem(script: &Path) -> String {
+    script
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "script".to_string())
+}
+
+fn parse_asset(line: &str, line_number: usize) -> Result<AssetReference> {
+    let (id, path) = line
+        .split_once('=')
+        .ok_or_else(|| anyhow!("line {}: expected 'id = path'", line_number))?;
+    let (id, path) = (id.trim(), path.trim());
+    if id.is_empty() || path.is_empty() {
+        bail!("line {}: expected 'id = path'", line_number);
+    }
+    Ok(AssetReference {
+        id: id.to_string(),
+        path: PathBuf::from(path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = PreloadManifest {
+            synthdefs: vec!["acid_bass".to_string(), "pad".to_string()],
+            samples: vec![AssetReference {
+                id: "kick".to_string(),
+                path: PathBuf::from("/samples/kick.wav"),
+            }],
+            sfz_instruments: vec![AssetReference {
+                id: "piano".to_string(),
+                path: PathBuf::from("/sfz/piano.sfz"),
+            }],
+        };
+        let script = Path::new("/songs/song.vibe");
+        let text = manifest.to_text(script);
+        assert!(text.starts_with("# Preload manifest for song.vibe"));
+        assert_eq!(PreloadManifest::parse(&text).unwrap(), manifest);
+
+        assert_eq!(
+            PreloadManifest::manifest_path(script),
+            PathBuf::from("/songs/.vibe-warmup/song.preload")
+        );
+        assert_eq!(PreloadManifest::synthdef_dir(script), PathBuf::from("/songs/.vibe-warmup/song"));
+
+        let err = PreloadManifest::parse("kick = a.wav").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+        assert!(PreloadManifest::parse("[samples]\nkick").is_err());
+    }
+}
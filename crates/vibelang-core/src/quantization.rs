@@ -0,0 +1,121 @@
+//! Launch quantization per entity type.
+//!
+//! Patterns, melodies, sequences and fades start on the next point of their
+//! launch grid. The grid comes from the most specific setting: the entity's
+//! own (`launch_quantize(1.beats)`), the default for its type
+//! (`set_launch_quantization("sequence", 8.bars)`), then the global
+//! quantization. Type defaults let drums come in on the next beat while new
+//! sections wait for the phrase boundary (16 or 32 beats in 4/4).
+
+use std::collections::BTreeMap;
+
+use crate::sequences::{ClipSource, SequenceDefinition};
+
+/// Kind of entity a launch grid applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LaunchKind {
+    Pattern,
+    Melody,
+    Sequence,
+    Fade,
+}
+
+impl LaunchKind {
+    /// All kinds, in order.
+    pub const ALL: [LaunchKind; 4] = [LaunchKind::Pattern, LaunchKind::Melody, LaunchKind::Sequence, LaunchKind::Fade];
+
+    /// Name in scripts, session files and the HTTP API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LaunchKind::Pattern => "pattern",
+            LaunchKind::Melody => "melody",
+            LaunchKind::Sequence => "sequence",
+            LaunchKind::Fade => "fade",
+        }
+    }
+
+    /// Parse "pattern", "melody", "sequence" or "fade" (plurals accepted).
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.trim().to_ascii_lowercase().as_str() {
+            "pattern" | "patterns" => LaunchKind::Pattern,
+            "melody" | "melodies" => LaunchKind::Melody,
+            "sequence" | "sequences" => LaunchKind::Sequence,
+            "fade" | "fades" => LaunchKind::Fade,
+            _ => return None,
+        })
+    }
+
+    /// Kind a sequence launches as: the sequences `start()` creates for a
+    /// pattern (or ctrl) or melody launch as that, others as sequences.
+    pub fn of_sequence(def: &SequenceDefinition) -> Self {
+        if !def.is_implicit() {
+            return LaunchKind::Sequence;
+        }
+        match def.clips.first().map(|clip| &clip.source) {
+            Some(ClipSource::Pattern(_)) => LaunchKind::Pattern,
+            Some(ClipSource::Melody(_)) => LaunchKind::Melody,
+            Some(ClipSource::Fade(_)) => LaunchKind::Fade,
+            _ => LaunchKind::Sequence,
+        }
+    }
+}
+
+/// Default launch grids by entity type, overriding the global quantization.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaunchQuantization {
+    /// Grid in beats by kind; 0 launches immediately.
+    pub defaults: BTreeMap<LaunchKind, f64>,
+}
+
+impl LaunchQuantization {
+    /// Default grid of a kind, if set.
+    pub fn get(&self, kind: LaunchKind) -> Option<f64> {
+        self.defaults.get(&kind).copied()
+    }
+
+    /// Set (or with `None`, clear) the default grid of a kind.
+    pub fn set(&mut self, kind: LaunchKind, beats: Option<f64>) {
+        match beats {
+            Some(beats) => self.defaults.insert(kind, beats.max(0.0)),
+            None => self.defaults.remove(&kind),
+        };
+    }
+
+    /// Grid an entity of `kind` launches on: its own grid, else the
+    /// default of its kind, else the global quantization.
+    pub fn resolve(&self, kind: LaunchKind, entity: Option<f64>, global: f64) -> f64 {
+        entity.or_else(|| self.get(kind)).unwrap_or(global)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequences::{next_launch_beat, ClipMode, SequenceClip};
+
+    #[test]
+    fn test_launch_quantization_resolves_entity_type_global() {
+        let mut defaults = LaunchQuantization::default();
+        assert_eq!(defaults.resolve(LaunchKind::Pattern, None, 4.0), 4.0);
+
+        // Drums on the beat, sections on the 32-beat phrase
+        defaults.set(LaunchKind::Pattern, Some(1.0));
+        defaults.set(LaunchKind::Sequence, Some(32.0));
+        assert_eq!(defaults.resolve(LaunchKind::Pattern, None, 4.0), 1.0);
+        assert_eq!(defaults.resolve(LaunchKind::Pattern, Some(0.0), 4.0), 0.0);
+        assert_eq!(defaults.resolve(LaunchKind::Melody, None, 4.0), 4.0);
+        let sequence = defaults.resolve(LaunchKind::Sequence, None, 4.0);
+        assert_eq!(next_launch_beat(37.5, sequence), 64.0);
+        defaults.set(LaunchKind::Sequence, None);
+        assert_eq!(defaults.resolve(LaunchKind::Sequence, None, 4.0), 4.0);
+
+        // Implicit sequences launch as what they play
+        let clip = |source| SequenceClip::new(0.0, 4.0, source, ClipMode::Loop);
+        let implicit = SequenceDefinition::new("_seq_kick").with_clip(clip(ClipSource::Pattern("kick".into())));
+        assert_eq!(LaunchKind::of_sequence(&implicit), LaunchKind::Pattern);
+        let verse = SequenceDefinition::new("verse").with_clip(clip(ClipSource::Melody("lead".into())));
+        assert_eq!(LaunchKind::of_sequence(&verse), LaunchKind::Sequence);
+        assert_eq!(LaunchKind::parse("Melodies"), Some(LaunchKind::Melody));
+        assert_eq!(LaunchKind::parse("voice"), None);
+    }
+}
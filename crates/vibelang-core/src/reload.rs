@@ -472,7 +472,14 @@ impl ReloadManager {
         diff_entities(&before.melodies, &after.melodies, EntityKind::Melody, &mut changes);
         diff_entities(&before.sequences, &after.sequences, EntityKind::Sequence, &mut changes);
         diff_entities(&before.effects, &after.effects, EntityKind::Effect, &mut changes);
+        let first_group = changes.len();
         diff_entities(&before.groups, &after.groups, EntityKind::Group, &mut changes);
+        // Remove child groups before their parents, as freeing a group's node
+        // also frees everything inside it
+        changes[first_group..].sort_by_key(|op| match op {
+            ChangeOp::Remove { id, .. } => (0, std::cmp::Reverse(id.matches('/').count())),
+            _ => (1, std::cmp::Reverse(0)),
+        });
 
         changes
     }
@@ -509,6 +516,26 @@ mod tests {
         assert_ne!(hash1, hash3);
     }
 
+    #[test]
+    fn test_diff_removes_child_groups_first() {
+        let mut before = StateSnapshot::new();
+        for path in ["main", "main/A", "main/A/B", "main/A/B/C", "main/D"] {
+            before.add(EntityKind::Group, path.to_string(), 1);
+        }
+        let mut after = StateSnapshot::new();
+        after.add(EntityKind::Group, "main".to_string(), 1);
+        after.add(EntityKind::Group, "main/D".to_string(), 2);
+
+        let changes = ReloadManager::compute_diff(&before, &after);
+        let removed: Vec<&str> = changes
+            .iter()
+            .filter(|op| matches!(op, ChangeOp::Remove { .. }))
+            .map(|op| op.id())
+            .collect();
+        assert_eq!(removed, vec!["main/A/B/C", "main/A/B", "main/A"]);
+        assert_eq!(changes.len(), 5);
+    }
+
     #[test]
     fn test_diff_empty() {
         let before = StateSnapshot::new();
@@ -0,0 +1,201 @@
+//! Resampling: chopping a group's own output into a drum kit.
+//!
+//! `resample("drums", 2.bars)` records the group's output (the master with
+//! `"main"`) from the next bar line, the same way a freeze does, but keeps the
+//! live synths playing. When the pass ends the buffer is written to a WAV
+//! file, trimmed to the recorded length and sliced at its onsets. The
+//! recording is loaded as a sample and each slice becomes a pad of a drum kit
+//! voice named after the group (`drums_resample`): slice 1 plays on note 60
+//! and step `a`, slice 2 on note 61 and step `b`, and so on.
+//!
+//! Running the script again does not record again while the group and length
+//! are unchanged, so the chops survive reloads.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::drumkit::{is_reserved_step, DrumKit, DrumPad};
+
+/// Number of bars recorded when no length is given.
+pub const DEFAULT_RESAMPLE_BARS: u32 = 2;
+
+/// Maximum number of bars that can be resampled.
+pub const MAX_RESAMPLE_BARS: u32 = 16;
+
+/// Maximum number of slices (one per step letter).
+pub const MAX_RESAMPLE_SLICES: usize = 24;
+
+/// Shortest slice; onsets closer than this to the previous one are merged.
+const MIN_SLICE_SECONDS: f64 = 0.05;
+
+/// First note of the slice pads.
+const FIRST_SLICE_NOTE: u8 = 60;
+
+/// Name of the voice playing the chops of a group.
+pub fn resample_voice_name(group_path: &str) -> String {
+    let name = group_path.rsplit(['/', '.']).next().unwrap_or(group_path);
+    format!("{}_resample", name)
+}
+
+/// Directory the recordings are written to.
+pub fn resample_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("vibelang-resample-{}", std::process::id()))
+}
+
+/// Files a take is written to (`<name>-<buffer>.raw.wav`, as written by
+/// scsynth) and chopped from (`<name>-<buffer>.wav`, trimmed).
+pub fn recording_paths(name: &str, buffer_id: i32) -> (PathBuf, PathBuf) {
+    let stem: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let dir = resample_dir();
+    (
+        dir.join(format!("{}-{}.raw.wav", stem, buffer_id)),
+        dir.join(format!("{}-{}.wav", stem, buffer_id)),
+    )
+}
+
+/// Step character of slice `index` (0-based): the letters that have no
+/// pattern meaning of their own, in order.
+pub fn slice_step(index: usize) -> Option<char> {
+    ('a'..='z').filter(|c| !is_reserved_step(*c)).nth(index)
+}
+
+/// Slices of a recording of `duration` seconds with onsets at `onsets`
+/// seconds, as (start, end) seconds.
+///
+/// The first slice always starts at 0 so nothing before the first onset is
+/// lost; onsets within [`MIN_SLICE_SECONDS`] of a slice boundary are dropped.
+pub fn slice_points(onsets: &[f64], duration: f64) -> Vec<(f64, f64)> {
+    let mut onsets: Vec<f64> = onsets.iter().copied().filter(|t| t.is_finite()).collect();
+    onsets.sort_by(f64::total_cmp);
+
+    let mut starts = vec![0.0];
+    for onset in onsets {
+        let last = *starts.last().unwrap_or(&0.0);
+        if onset - last >= MIN_SLICE_SECONDS && duration - onset >= MIN_SLICE_SECONDS {
+            starts.push(onset);
+        }
+    }
+    starts.truncate(MAX_RESAMPLE_SLICES);
+
+    let ends = starts.iter().skip(1).copied().chain(std::iter::once(duration));
+    starts.iter().copied().zip(ends).collect()
+}
+
+/// Drum kit with one pad per slice of `sample_id`, a recording at
+/// `sample_rate` (slices in seconds, see [`slice_points`]).
+pub fn slice_kit(sample_id: &str, sample_rate: u32, slices: &[(f64, f64)]) -> DrumKit {
+    let frame = |seconds: f64| (seconds * sample_rate as f64).round() as i32;
+    let mut kit = DrumKit::default();
+    for (index, (start, end)) in slices.iter().enumerate() {
+        kit.add(DrumPad {
+            name: (index + 1).to_string(),
+            note: FIRST_SLICE_NOTE.saturating_add(index as u8).min(127),
+            step: slice_step(index),
+            sample_id: sample_id.to_string(),
+            start_frame: frame(*start),
+            end_frame: frame(*end),
+            rate: 1.0,
+            gain: 1.0,
+            pitch: 0.0,
+            choke: None,
+        });
+    }
+    kit
+}
+
+/// A recording trimmed and sliced by [`chop`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chop {
+    /// Sample rate of the recording.
+    pub sample_rate: u32,
+    /// Slices in seconds (see [`slice_points`]).
+    pub slices: Vec<(f64, f64)>,
+}
+
+/// Trim the buffer written to `raw` to `duration_seconds`, write it to
+/// `out` and slice it at its onsets.
+pub fn chop(raw: &Path, out: &Path, duration_seconds: f64) -> Result<Chop> {
+    let (samples, channels, sample_rate) = trim_recording(raw, out, duration_seconds)?;
+    let onsets = crate::api::sample::detect_onsets(&samples, channels, sample_rate);
+    Ok(Chop {
+        sample_rate,
+        slices: slice_points(&onsets, (samples.len() / channels) as f64 / sample_rate as f64),
+    })
+}
+
+/// Write the first `duration_seconds` of the WAV file `raw` to `out`;
+/// returns the trimmed samples, the channel count and the sample rate.
+///
+/// Buffers are sized for the highest common sample rate, so the tail past
+/// the recorded length is silence.
+pub fn trim_recording(raw: &Path, out: &Path, duration_seconds: f64) -> Result<(Vec<f32>, usize, u32)> {
+    let (mut samples, channels, sample_rate) = crate::api::sample::read_wav_interleaved(raw)
+        .ok_or_else(|| anyhow!("cannot read recording {}", raw.display()))?;
+    let channels = channels.max(1);
+    let frames = ((duration_seconds * sample_rate as f64).round() as usize).min(samples.len() / channels);
+    samples.truncate(frames * channels);
+
+    let spec = hound::WavSpec {
+        channels: channels as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(out, spec).with_context(|| format!("cannot write {}", out.display()))?;
+    for sample in &samples {
+        writer.write_sample(*sample)?;
+    }
+    writer.finalize()?;
+    Ok((samples, channels, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_trims_and_slices_recording() {
+        // A 2 s stereo buffer of which 1.5 s were recorded
+        let sample_rate = 48_000;
+        let samples: Vec<f32> = (0..2 * 2 * sample_rate).map(|i| (i % 100) as f32 / 100.0).collect();
+        let dir = std::env::temp_dir().join(format!("vibelang-resample-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let raw = dir.join("raw.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&raw, spec).unwrap();
+        for sample in &samples {
+            writer.write_sample(*sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let out = dir.join("chops.wav");
+        let (trimmed, channels, rate) = trim_recording(&raw, &out, 1.5).unwrap();
+        assert_eq!((trimmed.len(), channels, rate), (144_000, 2, sample_rate));
+        assert_eq!(hound::WavReader::open(&out).unwrap().duration(), 72_000);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // The first slice starts at 0; the last ends with the recording
+        let slices = slice_points(&[1.0, 0.5], 1.5);
+        assert_eq!(slices, vec![(0.0, 0.5), (0.5, 1.0), (1.0, 1.5)]);
+        let kit = slice_kit("drums_resample", sample_rate, &slices);
+        assert_eq!(kit.pads.len(), 3);
+        assert_eq!(kit.pads[1].start_frame, 24_000);
+        assert_eq!(kit.pads[1].note, 61);
+        assert_eq!(kit.pads[1].step, Some('b'));
+        assert_eq!(kit.pads[2].end_frame, 72_000);
+
+        // Onsets too close together or to the end are merged
+        assert_eq!(slice_points(&[0.01, 0.5, 0.52, 0.98], 1.0), vec![(0.0, 0.5), (0.5, 1.0)]);
+        assert_eq!(slice_step(14), Some('p'));
+        assert_eq!(resample_voice_name("main/drums"), "drums_resample");
+    }
+}
@@ -0,0 +1,116 @@
+//! Return channels for external processing.
+//!
+//! The counterpart of `voice.output()`: a return channel reads hardware
+//! inputs (the output of an outboard reverb, a tape machine, a modular)
+//! and plays them into a group like a voice, so the returned audio goes
+//! through the group's effects, meter and fader.
+
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the synthdef that returns one hardware input (to both sides).
+pub const RETURN_MONO_SYNTHDEF: &str = "system_return_mono";
+
+/// Name of the synthdef that returns two adjacent hardware inputs.
+pub const RETURN_STEREO_SYNTHDEF: &str = "system_return_stereo";
+
+/// Return synthdef for a number of input channels (1 or 2).
+pub fn return_synthdef(channels: u32) -> &'static str {
+    if channels >= 2 {
+        RETURN_STEREO_SYNTHDEF
+    } else {
+        RETURN_MONO_SYNTHDEF
+    }
+}
+
+/// Create and encode the return channel synthdefs.
+pub fn create_return_synthdefs() -> Vec<(String, Vec<u8>)> {
+    let mut defs = Vec::new();
+    for (name, channels) in [(RETURN_MONO_SYNTHDEF, 1), (RETURN_STEREO_SYNTHDEF, 2)] {
+        match encode_synthdef(&return_graph(name, channels)) {
+            Ok(bytes) => defs.push((name.to_string(), bytes)),
+            Err(e) => log::error!("[RETURN] Failed to encode {} synthdef: {}", name, e),
+        }
+    }
+    defs
+}
+
+/// SoundIn.ar(input, channels) * amp → Out.ar(out, [l, r]).
+///
+/// Parameters:
+/// - out: group bus to write to (0)
+/// - input: first hardware input channel, 0-based (1)
+/// - amp: output gain (2)
+fn return_graph(name: &str, channels: u32) -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("out".to_string(), vec![0.0], None); // 0
+    builder.add_param("input".to_string(), vec![0.0], None); // 1
+    builder.add_param("amp".to_string(), vec![1.0], None); // 2
+    builder.create_control_ugen();
+
+    let input = builder.add_sound_in(Input::node(0, 1), channels);
+
+    let scaled: Vec<Input> = (0..channels)
+        .map(|channel| {
+            let id = builder.add_node(
+                "BinaryOpUGen".to_string(),
+                Rate::Audio,
+                vec![Input::node(input.0, channel), Input::node(0, 2)],
+                1,
+                2, // multiplication
+            );
+            Input::node(id.0, 0)
+        })
+        .collect();
+    let (left, right) = (scaled[0].clone(), scaled[scaled.len() - 1].clone());
+
+    builder.add_node("Out".to_string(), Rate::Audio, vec![Input::node(0, 0), left, right], 0, 0);
+
+    GraphIR::from_builder(name.to_string(), builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inputs of the Out UGen, and the In channel each one plays scaled by `amp`.
+    fn returned_channels(ir: &GraphIR) -> Vec<u32> {
+        let (input, _) = ir.ugens("In").next().unwrap();
+        let (_, out) = ir.ugens("Out").next().unwrap();
+        assert_eq!(out.inputs[0], Input::node(0, 0));
+        out.inputs[1..]
+            .iter()
+            .map(|side| {
+                let Input::Node { node_id, .. } = side else {
+                    panic!("Out reads a constant");
+                };
+                let scaled = &ir.nodes[*node_id as usize];
+                assert_eq!((scaled.name.as_str(), scaled.special_index), ("BinaryOpUGen", 2));
+                assert_eq!(scaled.inputs[1], Input::node(0, 2));
+                match scaled.inputs[0] {
+                    Input::Node { node_id, output_index } if node_id == input => output_index,
+                    _ => panic!("scaled signal doesn't come from In"),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_return_graphs_read_the_hardware_input() {
+        let mono = return_graph(RETURN_MONO_SYNTHDEF, 1);
+        assert!(mono.validate().is_ok());
+        assert_eq!(mono.ugens("In").next().unwrap().1.num_outputs, 1);
+        // The `input` parameter picks the hardware channel
+        let (_, bus) = mono.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(bus.inputs[1], Input::node(0, 1));
+        // One input plays on both sides
+        assert_eq!(returned_channels(&mono), vec![0, 0]);
+
+        let stereo = return_graph(RETURN_STEREO_SYNTHDEF, 2);
+        assert_eq!(stereo.ugens("In").next().unwrap().1.num_outputs, 2);
+        assert_eq!(returned_channels(&stereo), vec![0, 1]);
+
+        assert_eq!(return_synthdef(1), RETURN_MONO_SYNTHDEF);
+        assert_eq!(return_synthdef(2), RETURN_STEREO_SYNTHDEF);
+    }
+}
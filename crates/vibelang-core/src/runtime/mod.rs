@@ -5,7 +5,10 @@
 //! - State manager thread
 //! - Beat scheduling
 //! - Message passing between API and audio engine
+//! - Virtual-time simulation runs
 
+pub mod simulation;
 pub mod thread;
 
+pub use simulation::{SimulatedEvent, Simulation};
 pub use thread::{Runtime, RuntimeHandle};
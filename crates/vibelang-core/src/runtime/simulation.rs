@@ -0,0 +1,639 @@
+//! Deterministic simulation runs.
+//!
+//! A [`Simulation`] runs the runtime thread without scsynth and with the
+//! transport on virtual time: the song only moves when
+//! [`Simulation::advance`] is called, and every event the scheduler fires
+//! is recorded with its beat. Tests and `vibe simulate` use it to check
+//! exactly which events a script produces and when.
+//!
+//! Fades started by the song are recorded as events; their ramps run on
+//! wall-clock time and aren't simulated.
+//!
+//! The started synths are also kept in an [event log](crate::event_log),
+//! with their node IDs and the controls sent to scsynth.
+//!
+//! ```ignore
+//! let mut sim = Simulation::new();
+//! vibelang_core::init_api(sim.handle().clone());
+//! engine.run(script)?;
+//! sim.start();
+//! sim.advance(16.0);
+//! for event in sim.events() { ... }
+//! ```
+
+use crossbeam_channel::unbounded;
+
+use super::thread::{register_main_group, RuntimeThread};
+use super::RuntimeHandle;
+use crate::event_log::{EventLog, LoggedEvent};
+use crate::events::BeatEvent;
+use crate::scsynth::Scsynth;
+use crate::state::{StateManager, StateMessage};
+
+/// Resolution of a simulation run: the transport moves this many beats per tick.
+const TICK_BEATS: f64 = 1.0 / 64.0;
+
+/// An event fired during a simulation run.
+#[derive(Debug, Clone)]
+pub struct SimulatedEvent {
+    /// Absolute beat the event fires at.
+    pub beat: f64,
+    /// Seconds from the start of the run, following tempo changes.
+    pub seconds: f64,
+    /// The fired event, with the voice's synthdef filled in (its own `beat`
+    /// is relative to its pattern).
+    pub event: BeatEvent,
+}
+
+/// The runtime on virtual time, driven step by step.
+pub struct Simulation {
+    thread: RuntimeThread,
+    handle: RuntimeHandle,
+    /// Seconds of song time simulated so far.
+    seconds: f64,
+    /// Events collected ahead of the transport (within the lookahead).
+    upcoming: Vec<(f64, BeatEvent)>,
+    /// Events whose beat the transport has passed.
+    fired: Vec<SimulatedEvent>,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulation {
+    /// Create a stopped simulation at beat 0.
+    pub fn new() -> Self {
+        let (message_tx, message_rx) = unbounded();
+        let (midi_tx, midi_rx) = unbounded();
+        let (completion_tx, _) = unbounded();
+        let state_manager = StateManager::new();
+        register_main_group(&state_manager);
+
+        let scsynth = Scsynth::noop();
+        let handle = RuntimeHandle::new_validation(message_tx, state_manager.clone(), scsynth.clone(), midi_tx);
+        let mut thread =
+            RuntimeThread::with_completion_channel(scsynth, state_manager, message_rx, completion_tx, midi_rx);
+        thread.transport.set_virtual(true, std::time::Instant::now());
+        thread.event_log = Some(EventLog::new(None, 0.0));
+
+        Self {
+            thread,
+            handle,
+            seconds: 0.0,
+            upcoming: Vec::new(),
+            fired: Vec::new(),
+        }
+    }
+
+    /// Handle for the scripting API (`init_api`) and for sending messages.
+    pub fn handle(&self) -> &RuntimeHandle {
+        &self.handle
+    }
+
+    /// Start the transport after the script has run, like `vibe run` does.
+    pub fn start(&mut self) {
+        let _ = self.handle.send(StateMessage::StartScheduler);
+        let _ = self.handle.send(StateMessage::FinalizeGroups);
+        self.thread.drain_messages();
+    }
+
+    /// Current beat of the transport.
+    pub fn beat(&self) -> f64 {
+        self.thread.transport.current_beat()
+    }
+
+    /// Seconds of song time simulated so far.
+    pub fn seconds(&self) -> f64 {
+        self.seconds
+    }
+
+    /// Run the song for `beats`, processing pending messages first.
+    pub fn advance(&mut self, beats: f64) {
+        self.thread.drain_messages();
+        let end = self.beat() + beats.max(0.0);
+        while self.beat() < end - 1e-9 {
+            let step = TICK_BEATS.min(end - self.beat());
+            self.seconds += step * 60.0 / self.thread.transport.bpm();
+            self.thread.transport.advance(step);
+            self.thread.drain_messages();
+            self.thread.tick();
+            self.collect();
+            if !self.thread.transport.is_running() {
+                break;
+            }
+        }
+    }
+
+    /// Events fired so far, in beat order.
+    pub fn events(&self) -> &[SimulatedEvent] {
+        &self.fired
+    }
+
+    /// Take the events fired so far.
+    pub fn take_events(&mut self) -> Vec<SimulatedEvent> {
+        std::mem::take(&mut self.fired)
+    }
+
+    /// Synths started so far with their node IDs and controls, including
+    /// those scheduled within the lookahead ahead of the transport.
+    pub fn logged_events(&self) -> &[LoggedEvent] {
+        self.thread.event_log.as_ref().map(|log| log.events()).unwrap_or_default()
+    }
+
+    /// Move scheduled events the transport has reached into the fired list.
+    fn collect(&mut self) {
+        self.upcoming.extend(
+            self.thread
+                .simulated_events
+                .drain(..)
+                .map(|(beat, event)| (beat.to_float(), event)),
+        );
+        let now = self.beat();
+        let bpm = self.thread.transport.bpm();
+        let (due, later): (Vec<_>, Vec<_>) = self.upcoming.drain(..).partition(|(beat, _)| *beat <= now + 1e-9);
+        self.upcoming = later;
+        for (beat, mut event) in due {
+            // Pattern and melody events name their synthdef through the voice
+            if event.synth_def == "trigger" || event.synth_def == "melody_note" {
+                let synth = event.synth_override.clone().or_else(|| {
+                    event.voice_name.as_ref().and_then(|voice| {
+                        self.handle
+                            .with_state(|state| state.voices.get(voice).and_then(|v| v.synth_name.clone()))
+                    })
+                });
+                if let Some(synth) = synth {
+                    event.synth_def = synth;
+                }
+            }
+            self.fired.push(SimulatedEvent {
+                beat,
+                seconds: self.seconds - (now - beat) * 60.0 / bpm,
+                event,
+            });
+        }
+        self.fired.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventClip;
+    use crate::state::ActiveSynth;
+
+    fn run(script: &str) -> Simulation {
+        let mut sim = Simulation::new();
+        crate::api::init_api(sim.handle().clone());
+        crate::api::create_engine().run(script).unwrap();
+        sim.start();
+        sim
+    }
+
+    #[test]
+    fn test_simulation_fires_pattern_events_in_virtual_time() {
+        let mut sim = run(
+            r#"
+            set_tempo(120);
+            define_group("drums", || {
+                let kick = voice("kick").synth("kick_909");
+                let hat = voice("hat").synth("hihat");
+                pattern("four").on(kick).step("x... x... x... x...").start();
+                pattern("offbeat").on(hat).step("..x. ..x. ..x. ..x.").start();
+            });
+            "#,
+        );
+
+        sim.advance(4.0);
+        assert_eq!(sim.beat(), 4.0);
+        assert!((sim.seconds() - 2.0).abs() < 1e-9);
+
+        let fired: Vec<(f64, &str)> = sim.events().iter().map(|e| (e.beat, e.event.synth_def.as_str())).collect();
+        assert_eq!(
+            fired,
+            vec![
+                (0.0, "kick_909"),
+                (0.5, "hihat"),
+                (1.0, "kick_909"),
+                (1.5, "hihat"),
+                (2.0, "kick_909"),
+                (2.5, "hihat"),
+                (3.0, "kick_909"),
+                (3.5, "hihat"),
+                (4.0, "kick_909"),
+            ]
+        );
+        let kick = &sim.events()[2];
+        assert_eq!(kick.event.voice_name.as_deref(), Some("kick"));
+        assert_eq!(kick.event.group_path.as_deref(), Some("main/drums"));
+        assert!((kick.seconds - 0.5).abs() < 1e-9);
+
+        // The event log has the same synths with node IDs and sent controls
+        let logged = sim.logged_events().iter().find(|e| e.beat == 1.0).unwrap();
+        assert_eq!(logged.synthdef, "kick_909");
+        assert_eq!(logged.pattern.as_deref(), Some("four"));
+        assert!(logged.node_id > 0);
+        assert!((logged.seconds - 0.5).abs() < 1e-9);
+        assert!(logged.controls.iter().any(|(name, _)| name == "out"));
+
+        // Running again continues where the last run stopped
+        sim.take_events();
+        sim.advance(1.0);
+        let beats: Vec<f64> = sim.events().iter().map(|e| e.beat).collect();
+        assert_eq!(beats, vec![4.5, 5.0]);
+    }
+
+    #[test]
+    fn test_mono_voice_glides_instead_of_stacking_synths() {
+        let mut sim = run(
+            r#"
+            let lead = voice("lead").synth("saw").mono("last").glide_ms(50);
+            melody("line").on(lead).notes("C3 E3 r G3").start();
+            "#,
+        );
+        sim.advance(7.5);
+
+        // Legato notes glide the running synth (across the loop point too);
+        // the rest releases it, so G3 starts a new one
+        let started: Vec<(f64, f32)> = sim
+            .logged_events()
+            .iter()
+            .map(|e| (e.beat, e.controls.iter().find(|(k, _)| k == "freq").unwrap().1))
+            .collect();
+        let freq = |note: f64| crate::pitch::note_to_freq(note) as f32;
+        assert_eq!(started, vec![(0.0, freq(48.0)), (3.0, freq(55.0)), (7.0, freq(55.0))]);
+        assert!(sim.handle().with_state(|state| state.voices["lead"].mono_state.is_active()));
+    }
+
+    #[test]
+    fn test_active_synths_record_the_step_clip_and_line_behind_them() {
+        let mut sim = run(
+            r#"
+            let bass = voice("bass").synth("saw");
+            let hat = voice("hat").synth("hihat");
+            let riff = pattern("riff").on(bass).step("x.x. ..x.").apply();
+            sequence("verse").loop_beats(8).clip(4..8, riff).start();
+            pattern("hats").on(hat).step("x...").start();
+            "#,
+        );
+        sim.advance(6.0);
+
+        let mut riff: Vec<ActiveSynth> = sim.handle().with_state(|state| {
+            state.active_synths.values().filter(|s| s.pattern_names == ["riff"]).cloned().collect()
+        });
+        riff.sort_by(|a, b| a.start_beat.partial_cmp(&b.start_beat).unwrap());
+        let steps: Vec<(Option<f64>, Option<usize>)> = riff.iter().map(|s| (s.start_beat, s.step)).collect();
+        assert_eq!(steps, vec![(Some(4.0), Some(0)), (Some(5.0), Some(2))]);
+        let synth = &riff[1];
+        assert_eq!(synth.synth_def, "saw");
+        assert_eq!(synth.clip, Some(EventClip { sequence: "verse".to_string(), index: 0 }));
+        assert_eq!(synth.source_location.line, Some(4));
+        assert_eq!(synth.provenance(), "pattern riff step 3 · verse clip 1");
+
+        // Loops started on their own play in no clip anyone arranged
+        let hats = sim.handle().with_state(|state| {
+            state.active_synths.values().find(|s| s.pattern_names == ["hats"]).cloned()
+        });
+        assert_eq!(hats.unwrap().clip, None);
+    }
+
+    #[test]
+    fn test_fades_and_effects_know_where_they_were_defined() {
+        let mut sim = run(
+            r#"
+            let pads = define_group("pads", || {});
+            pads.add_effect("verb", "reverb", #{ mix: 0.3 });
+            let out = fade("out").on_group("pads").param("amp").from(1.0).to(0.0).over(64.0).apply();
+            sequence("outro").loop_bars(4).clip(0..1, out).start();
+            "#,
+        );
+        sim.advance(1.0);
+
+        let (def_line, effect_line, job) = sim.handle().with_state(|state| {
+            (
+                state.fade_defs.get("out").map(|f| f.source_location.line),
+                state.effects.get("verb").map(|e| e.source_location.line),
+                state.fades.first().map(|f| (f.name.clone(), f.source_location.line)),
+            )
+        });
+        assert_eq!(def_line, Some(Some(4)));
+        assert_eq!(effect_line, Some(Some(3)));
+        assert_eq!(job, Some((Some("out".to_string()), Some(4))));
+    }
+
+    #[test]
+    fn test_vst_instruments_and_effects() {
+        let mut sim = run(
+            r#"
+            let pad = load_vst_instrument("pad", "Surge XT").param("Cutoff", 0.4);
+            let keys = voice("keys").on(pad);
+            define_group("wet", || {
+                fx("room").vst("ValhallaRoom").param("Mix", 0.3).apply();
+            });
+            "#,
+        );
+        sim.advance(1.0);
+
+        let instrument = |sim: &Simulation| {
+            sim.handle().with_state(|state| {
+                let pad = state.vst_instruments.get("pad").unwrap();
+                (pad.plugin_key.clone(), pad.group_path.clone(), pad.node_id, pad.params.get("Cutoff").copied())
+            })
+        };
+        let (plugin, group, node_id, cutoff) = instrument(&sim);
+        assert_eq!((plugin.as_str(), group.as_str(), cutoff), ("Surge XT", "main", Some(0.4)));
+        assert!(node_id.is_some());
+
+        let (voice_instrument, effect) = sim.handle().with_state(|state| {
+            let effect = state.effects.get("room").unwrap();
+            (
+                state.voices.get("keys").and_then(|v| v.vst_instrument.clone()),
+                (effect.synthdef_name.clone(), effect.vst_plugin.clone(), effect.params.get("Mix").copied()),
+            )
+        });
+        assert_eq!(voice_instrument.as_deref(), Some("pad"));
+        assert_eq!(
+            effect,
+            (crate::vst::VST_EFFECT_SYNTHDEF.to_string(), Some("ValhallaRoom".to_string()), Some(0.3))
+        );
+
+        // Another plugin replaces the host synth but keeps the parameters
+        sim.handle()
+            .send(StateMessage::LoadVstInstrument {
+                id: "pad".to_string(),
+                plugin_key: "Vital".to_string(),
+                group_path: "main".to_string(),
+            })
+            .unwrap();
+        sim.advance(0.25);
+        let (plugin, _, new_node_id, cutoff) = instrument(&sim);
+        assert_eq!((plugin.as_str(), cutoff), ("Vital", Some(0.4)));
+        assert_ne!(new_node_id, node_id);
+    }
+
+    #[test]
+    fn test_stop_voice_releases_only_its_synths() {
+        let mut sim = run(
+            r#"
+            let pad = voice("pad").synth("saw");
+            let bass = voice("bass").synth("saw");
+            pad.note_on(60, 100);
+            pad.note_on(64, 100);
+            bass.note_on(36, 100);
+            "#,
+        );
+        sim.advance(0.5);
+
+        let sounding = |sim: &Simulation, name: &str| {
+            sim.handle().with_state(|state| {
+                let synths = state.active_synths.values().filter(|s| s.voice_names.iter().any(|v| v == name)).count();
+                (state.voices[name].active_note_count(), synths)
+            })
+        };
+        assert_eq!(sounding(&sim, "pad").0, 2);
+
+        sim.handle()
+            .send(StateMessage::StopVoice {
+                name: "pad".to_string(),
+                fade: Some(0.25),
+                choke: false,
+            })
+            .unwrap();
+        sim.advance(0.25);
+        assert_eq!(sounding(&sim, "pad"), (0, 0));
+        assert_eq!(sounding(&sim, "bass").0, 1);
+    }
+
+    #[test]
+    fn test_seek_releases_notes_with_pending_note_offs() {
+        let mut sim = run(r#"voice("pad").synth("saw");"#);
+        sim.handle()
+            .send(StateMessage::NoteOn {
+                voice_name: "pad".to_string(),
+                note: 60,
+                velocity: 100,
+                duration: Some(8.0),
+            })
+            .unwrap();
+        sim.advance(1.0);
+
+        let pending = |sim: &Simulation| {
+            sim.handle()
+                .with_state(|state| state.scheduled_note_offs.iter().map(|n| n.gate_scheduled).collect::<Vec<_>>())
+        };
+        // The gate-off stays with the runtime until it's inside the lookahead
+        assert_eq!(pending(&sim), vec![false]);
+        sim.advance(6.8);
+        assert_eq!(pending(&sim), vec![true]);
+
+        sim.handle()
+            .send(StateMessage::SeekTransport { position: crate::timing::Beats(0.0).into() })
+            .unwrap();
+        sim.advance(0.25);
+        assert!(pending(&sim).is_empty());
+        assert_eq!(sim.handle().with_state(|state| state.voices["pad"].active_note_count()), 0);
+    }
+
+    #[test]
+    fn test_quantized_trigger_snaps_to_the_grid() {
+        let mut sim = run(
+            r#"
+            voice("pad").synth("saw");
+            set_quantization(1.bars);
+            "#,
+        );
+        let trigger = |synth_name: Option<&str>| StateMessage::TriggerVoice {
+            name: "pad".to_string(),
+            synth_name: synth_name.map(String::from),
+            group_path: None,
+            params: Vec::new(),
+            quantize: true,
+        };
+        sim.advance(0.3);
+        sim.handle().send(trigger(None)).unwrap();
+        sim.handle().send(trigger(Some("pluck"))).unwrap();
+        sim.advance(4.0);
+
+        let started: Vec<(f64, &str)> = sim.logged_events().iter().map(|e| (e.beat, e.synthdef.as_str())).collect();
+        assert_eq!(started, vec![(4.0, "saw"), (4.0, "pluck")]);
+    }
+
+    #[test]
+    fn test_quantized_trigger_without_a_grid_fires_right_away() {
+        let mut sim = run(r#"voice("pad").synth("saw");"#);
+        sim.advance(0.3);
+        for grid in [0.0, -1.0, f64::NAN] {
+            sim.handle().with_state_mut(|state| state.quantization_beats = grid);
+            sim.handle()
+                .send(StateMessage::TriggerVoice {
+                    name: "pad".to_string(),
+                    synth_name: None,
+                    group_path: None,
+                    params: Vec::new(),
+                    quantize: true,
+                })
+                .unwrap();
+            let beat = sim.beat();
+            sim.advance(0.25);
+            assert_eq!(sim.logged_events().last().map(|e| e.beat), Some(beat));
+        }
+        assert_eq!(sim.logged_events().len(), 3);
+    }
+
+    #[test]
+    fn test_control_change_applies_cc_routes() {
+        use crate::midi::{CcRoute, CcTarget, ParameterCurve};
+
+        let mut sim = run(
+            r#"
+            voice("lead").synth("saw");
+            voice("pad").synth("saw");
+            "#,
+        );
+        let route = |voice: &str| CcRoute {
+            target: CcTarget::Voice(voice.to_string()),
+            param_name: "cutoff".to_string(),
+            min_value: 200.0,
+            max_value: 8000.0,
+            curve: ParameterCurve::Exponential,
+            channel: None,
+            param_range: false,
+        };
+        for voice in ["lead", "pad"] {
+            sim.handle()
+                .send(StateMessage::MidiAddCcRoute {
+                    channel: None,
+                    cc_number: 74,
+                    route: route(voice),
+                })
+                .unwrap();
+        }
+        sim.handle()
+            .send(StateMessage::ControlChange {
+                voice_name: "lead".to_string(),
+                cc_num: 74,
+                value: 64,
+            })
+            .unwrap();
+        sim.advance(0.25);
+
+        let cutoff = |voice: &str| sim.handle().with_state(|state| state.voices[voice].params.get("cutoff").copied());
+        assert_eq!(cutoff("lead"), Some(route("lead").apply(64)));
+        assert!(cutoff("lead").unwrap() < 4100.0);
+        // Routes to other voices ignore the lead's CC
+        assert_eq!(cutoff("pad"), None);
+    }
+
+    #[test]
+    fn test_hooks_follow_the_timeline() {
+        let mut sim = run(
+            r#"
+            hook("lights").at(2.beats).every(4.beats).command("true").apply();
+            on_beat_hook(3.beats, "true");
+            "#,
+        );
+        sim.advance(11.0);
+
+        let hooks = sim.handle().with_state(|state| {
+            let mut hooks: Vec<_> = state.hooks.values().map(|h| (h.name.clone(), h.fired, h.last_fired_beat)).collect();
+            hooks.sort_by(|a, b| a.0.cmp(&b.0));
+            hooks
+        });
+        assert_eq!(
+            hooks,
+            vec![("lights".to_string(), 3, Some(10.0)), ("true @ 3".to_string(), 1, Some(3.0))]
+        );
+
+        sim.handle().send(StateMessage::RemoveHook { name: "lights".to_string() }).unwrap();
+        sim.advance(1.0);
+        assert_eq!(sim.handle().with_state(|state| state.hooks.len()), 1);
+    }
+
+    #[test]
+    fn test_session_file_round_trip() {
+        let mut sim = run(
+            r#"
+            set_tempo(96);
+            set_key("D minor");
+            define_group("drums", || {
+                let kick = voice("kick").synth("kick_909").gain(0.8);
+                pattern("four").on(kick).step("x... x... x... x...").humanize(5, 10).start();
+            });
+            let lead = voice("lead").synth("saw").mono("last").glide_ms(30);
+            let line = melody("line").on(lead).notes("C3 E3 r G3").quantize_to_scale().arp("up", 1.beats).apply();
+            fade("swell").on_group("drums").param("amp").from(0.0).to(1.0).over(4.beats).apply();
+            sequence("verse").loop_beats(8).clip(0..8, line).clip(0..4, fade("swell")).start();
+            "#,
+        );
+        sim.advance(6.0);
+        sim.handle().send(StateMessage::SetVoiceParam { name: "kick".to_string(), param: "cutoff".to_string(), value: 900.0 }).unwrap();
+        sim.advance(0.0);
+
+        let saved = sim.handle().state().session_file();
+        let file = crate::session_file::SessionFile::from_json(&saved.to_json()).unwrap();
+        assert_eq!(file, saved);
+        assert!(file.playing.sequences.contains(&("verse".to_string(), 0.0)));
+
+        let mut restored = Simulation::new();
+        restored.handle().load_session(&file).unwrap();
+        restored.advance(0.0);
+        assert_eq!(restored.handle().state().session_file(), saved);
+
+        // The restored session plays on like the saved one (which already
+        // fired the events on beat 6)
+        restored.start();
+        restored.advance(1.0);
+        sim.take_events();
+        sim.advance(1.0);
+        let fired = |sim: &Simulation| -> Vec<(f64, String)> {
+            let mut fired: Vec<_> =
+                sim.events().iter().filter(|e| e.beat > 6.0).map(|e| (e.beat, e.event.synth_def.clone())).collect();
+            fired.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            fired
+        };
+        assert_eq!(fired(&restored), fired(&sim));
+        assert_eq!(fired(&restored).len(), 2);
+    }
+
+    #[test]
+    fn test_automation_records_live_tweaks_and_replays_them() {
+        let mut sim = run(r#"voice("lead").synth("saw");"#);
+        let cutoff = |sim: &Simulation| sim.handle().with_state(|state| state.voices["lead"].params.get("cutoff").copied());
+        let tweak = |sim: &mut Simulation, value: f32| {
+            let message = StateMessage::SetVoiceParam { name: "lead".to_string(), param: "cutoff".to_string(), value };
+            sim.handle().send(message).unwrap();
+            sim.advance(0.0);
+        };
+
+        // Record a one-bar lane: 400 on beat 1, 1200 on beat 3
+        sim.handle().send(StateMessage::StartAutomationRecording { bars: 1 }).unwrap();
+        sim.advance(1.0);
+        tweak(&mut sim, 400.0);
+        sim.advance(2.0);
+        tweak(&mut sim, 1200.0);
+        sim.handle().send(StateMessage::StopAutomationRecording).unwrap();
+
+        // The next bar plays the tweaks back
+        sim.advance(1.5);
+        tweak(&mut sim, 50.0);
+        assert_eq!(cutoff(&sim), Some(50.0));
+        sim.advance(0.75);
+        assert_eq!(cutoff(&sim), Some(400.0));
+        sim.advance(2.0);
+        assert_eq!(cutoff(&sim), Some(1200.0));
+        let lane = sim.handle().with_state(|state| state.automation.lanes["voice:lead:cutoff"].clone());
+        assert_eq!(lane.length_beats, 4.0);
+        assert_eq!(lane.points.len(), 2);
+
+        // Lanes are saved with the session
+        let saved = sim.handle().state().session_file();
+        assert_eq!(saved.automation.len(), 1);
+        let mut restored = Simulation::new();
+        restored.handle().load_session(&saved).unwrap();
+        restored.advance(0.0);
+        let lanes = |sim: &Simulation| sim.handle().with_state(|state| state.automation.lanes.clone());
+        assert_eq!(lanes(&restored), lanes(&sim));
+    }
+}
@@ -7,23 +7,39 @@
 //! - Communicates with SuperCollider
 
 use crate::audio_device::AudioConfig;
-use crate::events::{BeatEvent, FadeTargetType};
+use crate::drumkit::PadHit;
+use crate::event_log::EventLog;
+use crate::groove::{Humanize, TimingFeel};
+use crate::hooks::{next_hook_beat, spawn_hook, HookAction};
+use crate::events::{BeatEvent, FadeCurve, FadeTargetType, Pattern};
+use crate::liveset::SceneAction;
+use crate::looper::LooperAction;
+use crate::macros::MacroControl;
+use crate::musical_key::MusicalKey;
+use crate::playback_graph::{GraphSection, GraphTransition, TransitionStyle};
 use crate::midi::{MidiMessage, MidiRouting};
+use crate::mono::{MonoChange, MonoMode, MonoNoteOn};
 use crate::osc_sender::{OscSender, OscTiming};
+use crate::pitch;
+use crate::quantization::LaunchKind;
 use crate::reload::{ChangeOp, EntityKind, ReloadManager, StateSnapshot};
 use crate::scheduler::{EventScheduler, LoopKind, LoopSnapshot};
 use crate::scsynth::{AddAction, BufNum, NodeId, Scsynth, Target};
 use crate::scsynth_process::ScsynthProcess;
+use crate::session::SessionSnapshot;
+use crate::session_file::SessionFile;
 use rosc::{OscMessage, OscPacket, OscType};
 use crate::state::{
-    ActiveFadeJob, ActiveSequence, ActiveSynth, EffectState, GroupState, LoopStatus,
-    MelodyState, PatternState, SampleInfo, ScheduledEvent, ScheduledNoteOff,
-    ScriptState, SequenceRunLog, StateManager, StateMessage, VoiceState,
+    ActiveFadeJob, ActiveSequence, ActiveSynth, EffectState, FadingSection, GroupFreeze, GroupState, HookState, LiveSetState,
+    LoopStatus, LooperState, LooperStatus, MelodyState, ResampleState, ResampleStatus, ReturnChannelState, NetSyncState, NoteOrigin, NoteSource, PatternState, PendingTransition, PlaybackGraphState, SampleInfo, ScheduledEvent,
+    QuotaKind, ScheduledNoteOff, ScriptState, SequenceRunLog, StateManager, StateMessage, MidiTake, TakeAudition, TakeTargetKind,
+    VoiceState, VstInstrumentInfo,
 };
-use crate::timing::{BeatTime, TimeSignature, TransportClock};
+use crate::timing::{BeatTime, Beats, ClockSource, MidiClockFollower, MidiClockUpdate, TimeSignature, TimeSpan, TransportClock};
 use anyhow::Result;
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
@@ -32,6 +48,18 @@ use std::time::{Duration, Instant};
 const EPSILON: f64 = 1e-6;
 const LOOKAHEAD_MS: u64 = 250;
 
+/// Group used for sample previews (created on demand under `main`).
+const AUDITION_GROUP_PATH: &str = "main/__audition";
+
+/// How often voices' diagnostic control buses are read back from scsynth.
+const DIAG_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often voices are checked for stuck notes.
+const STUCK_NOTE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Release time granted by a panic before the nodes are freed.
+const PANIC_RELEASE_SECONDS: f64 = 0.05;
+
 /// Handle to the running VibeLang runtime.
 ///
 /// This is the main interface for interacting with VibeLang from the API layer.
@@ -46,6 +74,8 @@ pub struct RuntimeHandle {
     scsynth: Scsynth,
     /// Flag to signal shutdown.
     shutdown: Arc<AtomicBool>,
+    /// Set once the runtime thread has finished shutting down.
+    stopped: Arc<AtomicBool>,
     /// Receiver for sequence completion notifications.
     completion_rx: Option<crossbeam_channel::Receiver<String>>,
     /// Sender for MIDI messages to the runtime thread.
@@ -54,12 +84,34 @@ pub struct RuntimeHandle {
 
 impl RuntimeHandle {
     /// Send a message to the runtime thread.
+    ///
+    /// Fails without sending while a sandboxed evaluation would break its
+    /// profile's limits with this message, or when the message would create
+    /// an entity beyond the session's quotas.
     pub fn send(&self, msg: StateMessage) -> Result<()> {
+        crate::api::sandbox::admit(&msg).map_err(anyhow::Error::msg)?;
+        self.state_manager.admit(&msg)?;
+        crate::api::incremental::record(&msg);
         self.message_tx
             .send(msg)
             .map_err(|e| anyhow::anyhow!("Failed to send message: {}", e))
     }
 
+    /// Replace the session with the one in a session file.
+    ///
+    /// Nothing is sent when the file doesn't hold a valid session.
+    pub fn load_session(&self, file: &SessionFile) -> Result<()> {
+        for msg in file.messages()? {
+            self.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Number of messages sent to the runtime thread and not processed yet.
+    pub fn queued_messages(&self) -> usize {
+        self.message_tx.len()
+    }
+
     /// Get the state manager for read access.
     pub fn state(&self) -> &StateManager {
         &self.state_manager
@@ -101,6 +153,26 @@ impl RuntimeHandle {
         self.shutdown.load(Ordering::Relaxed)
     }
 
+    /// Check if the runtime thread has finished its graceful shutdown
+    /// (always true for validation handles, which have no thread).
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Signal shutdown and wait until the runtime has faded out and freed
+    /// its nodes, or `timeout` passes.
+    pub fn shutdown_and_wait(&self, timeout: Duration) -> bool {
+        self.shutdown();
+        let start = Instant::now();
+        while !self.is_stopped() {
+            if start.elapsed() > timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
     /// Wait for a sequence to complete (for play_once sequences).
     /// Returns true if the sequence completed, false if timeout or no channel.
     pub fn wait_for_sequence(&self, name: &str, timeout: Option<Duration>) -> bool {
@@ -190,6 +262,7 @@ impl RuntimeHandle {
             state_manager,
             scsynth,
             shutdown: Arc::new(AtomicBool::new(false)),
+            stopped: Arc::new(AtomicBool::new(true)),
             completion_rx: None,
             midi_tx,
         }
@@ -200,8 +273,8 @@ impl RuntimeHandle {
 ///
 /// Manages the SuperCollider process and runtime thread.
 pub struct Runtime {
-    /// The scsynth process (owned, will be killed on drop).
-    _process: ScsynthProcess,
+    /// The scsynth process (owned, will be killed on drop; `None` for a mock or already running server).
+    _process: Option<ScsynthProcess>,
     /// Handle for interacting with the runtime.
     handle: RuntimeHandle,
     /// Join handle for the runtime thread.
@@ -264,10 +337,35 @@ impl Runtime {
         log::info!("1. Starting scsynth server...");
         let process = ScsynthProcess::start_with_config(port, &audio_config)?;
 
-        // Connect to scsynth (no additional sleep needed - start_with_config waits for readiness)
+        // No additional sleep needed - start_with_config waits for readiness
+        Self::launch(Some(process), &format!("127.0.0.1:{}", port), system_synthdef_bytes)
+    }
+
+    /// Start the VibeLang runtime against an scsynth already running at `addr`.
+    ///
+    /// The server is not started or stopped by the runtime, but its nodes
+    /// are freed like on startup. The scheduler is not started, like with
+    /// [`Runtime::start_full`].
+    pub fn connect(addr: &str) -> Result<Self> {
+        let system_synthdef_bytes = create_system_link_audio_bytes()?;
+        Self::launch(None, addr, &system_synthdef_bytes)
+    }
+
+    /// Start the VibeLang runtime against a [`MockScsynth`](crate::mock_scsynth::MockScsynth).
+    ///
+    /// No scsynth process is started; everything the runtime sends ends up
+    /// in the mock's timeline. The scheduler is not started, like with
+    /// [`Runtime::start_full`].
+    #[cfg(any(test, feature = "mock-scsynth"))]
+    pub fn start_mock(mock: &crate::mock_scsynth::MockScsynth) -> Result<Self> {
+        let system_synthdef_bytes = create_system_link_audio_bytes()?;
+        Self::launch(None, &mock.addr().to_string(), &system_synthdef_bytes)
+    }
+
+    /// Connect to a running server, set it up and start the runtime thread.
+    fn launch(process: Option<ScsynthProcess>, addr: &str, system_synthdef_bytes: &[u8]) -> Result<Self> {
         log::info!("2. Connecting to scsynth...");
-        let addr = format!("127.0.0.1:{}", port);
-        let scsynth = Scsynth::new(&addr)?;
+        let scsynth = Scsynth::new(addr)?;
         log::info!("   Connected to scsynth");
 
         // Load system synthdefs and collect bytes for later storage in state
@@ -298,6 +396,62 @@ impl Runtime {
             log::info!("   Loaded {} synthdef", name);
         }
 
+        // Load the master loudness meter synthdef
+        if let Some((name, bytes)) = crate::loudness::create_loudness_meter_synthdef() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load the clock output synthdef for modular gear
+        if let Some((name, bytes)) = crate::clock_out::create_clock_out_synthdef() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load the master fader used to fade out on shutdown
+        if let Some((name, bytes)) = crate::shutdown::create_master_fader_synthdef() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load group freeze recorder/player synthdefs
+        for (name, bytes) in crate::freeze::create_freeze_synthdefs() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load audio input looper recorder/player synthdefs
+        for (name, bytes) in crate::looper::create_looper_synthdefs() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load return channel input synthdefs
+        for (name, bytes) in crate::return_channel::create_return_synthdefs() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load audio input voice synthdefs
+        for (name, bytes) in crate::input_voice::create_input_synthdefs() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
+        // Load VST plugin hosts (scsynth rejects them without the VSTPlugin extension)
+        for (name, bytes) in crate::vst::create_vst_synthdefs() {
+            scsynth.d_recv_bytes(bytes.clone())?;
+            system_synthdefs.push((name.clone(), bytes));
+            log::info!("   Loaded {} synthdef", name);
+        }
+
         // Free all existing groups
         log::info!("   Freeing existing groups...");
         if let Err(e) = scsynth.g_free_all(0) {
@@ -309,6 +463,7 @@ impl Runtime {
         let state_manager = StateManager::new();
         let (message_tx, message_rx) = unbounded();
         let shutdown = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
 
         // Store system synthdefs in state for score capture
         state_manager.with_state_write(|state| {
@@ -323,18 +478,32 @@ impl Runtime {
             log::error!("Failed to create main group: {}", e);
         }
 
-        // Register the main group in state with bus 0 (the main output)
-        // This is the root of the group hierarchy - all other groups are children of main
-        state_manager.with_state_write(|state| {
-            let mut main_group = GroupState::new(
-                "main".to_string(),
-                "main".to_string(),
-                None,  // No parent - this is the root
-                0,     // Bus 0 is the main output (hardware output)
-            );
-            main_group.node_id = Some(1);
-            state.groups.insert("main".to_string(), main_group);
-        });
+        // Tap the master bus for loudness metering (after the main group)
+        let loudness_meter_node_id = state_manager.with_state_write(|state| state.allocate_synth_node());
+        let loudness_meter_node_id = match scsynth.s_new(
+            crate::loudness::LOUDNESS_METER_SYNTHDEF,
+            NodeId::new(loudness_meter_node_id),
+            AddAction::AddToTail,
+            Target::root(),
+            &[("inbus", 0.0)],
+        ) {
+            Ok(()) => Some(loudness_meter_node_id),
+            Err(e) => {
+                log::warn!("Failed to start loudness meter: {}", e);
+                None
+            }
+        };
+
+        // Group for the clock output synth, so restarts can free it wholesale
+        if let Err(e) = scsynth.g_new(
+            NodeId::new(crate::clock_out::CLOCK_OUT_GROUP_ID),
+            AddAction::AddToTail,
+            Target::root(),
+        ) {
+            log::warn!("Failed to create clock output group: {}", e);
+        }
+
+        register_main_group(&state_manager);
 
         // Create completion notification channel for play_once sequences
         let (completion_tx, completion_rx) = crossbeam_channel::unbounded();
@@ -348,6 +517,7 @@ impl Runtime {
             state_manager: state_manager.clone(),
             scsynth: scsynth.clone(),
             shutdown: shutdown.clone(),
+            stopped: stopped.clone(),
             completion_rx: Some(completion_rx),
             midi_tx,
         };
@@ -365,7 +535,9 @@ impl Runtime {
                 completion_tx,
                 midi_rx,
             );
+            rt.loudness_meter_node_id = loudness_meter_node_id;
             rt.run(thread_shutdown);
+            stopped.store(true, Ordering::Relaxed);
         });
 
         // Note: Scheduler is NOT started here - the CLI starts it after script evaluation
@@ -402,8 +574,24 @@ impl Drop for Runtime {
     }
 }
 
+/// Register the main group in state with bus 0 (the main output).
+///
+/// This is the root of the group hierarchy - all other groups are children of main.
+pub(super) fn register_main_group(state_manager: &StateManager) {
+    state_manager.with_state_write(|state| {
+        let mut main_group = GroupState::new(
+            "main".to_string(),
+            "main".to_string(),
+            None, // No parent - this is the root
+            0,    // Bus 0 is the main output (hardware output)
+        );
+        main_group.node_id = Some(1);
+        state.groups.insert("main".to_string(), main_group);
+    });
+}
+
 /// The runtime thread that processes messages and runs the scheduler.
-struct RuntimeThread {
+pub(super) struct RuntimeThread {
     /// Centralized OSC sender - handles all scsynth communication and score capture.
     osc_sender: OscSender,
     /// Scsynth connection for receiving (the OscSender wraps a clone of this).
@@ -411,7 +599,11 @@ struct RuntimeThread {
     shared: StateManager,
     message_rx: Receiver<StateMessage>,
     scheduler: EventScheduler,
-    transport: TransportClock,
+    pub(super) transport: TransportClock,
+    /// Events fired while the transport runs on virtual time, for a simulation to take.
+    pub(super) simulated_events: Vec<(BeatTime, BeatEvent)>,
+    /// Log of fired events, while one runs.
+    pub(super) event_log: Option<EventLog>,
     last_tick: Instant,
     /// Manages live reload state transitions.
     reload_manager: ReloadManager,
@@ -423,10 +615,40 @@ struct RuntimeThread {
     midi_osc_handler: crate::midi_osc_handler::MidiOscHandler,
     /// Node ID for the SC-managed MIDI clock synth (None = not running).
     sc_midi_clock_node_id: Option<i32>,
+    /// Whether a clock output synth is running (or scheduled to start).
+    clock_out_running: bool,
+    /// When the last clock output change is scheduled to reach the server.
+    clock_out_scheduled_at: Instant,
+    /// Position and tempo of the external MIDI clock, when following one.
+    midi_clock: MidiClockFollower,
+    /// Reference of MIDI clock tick times without a device timestamp.
+    midi_clock_epoch: Instant,
+    /// Master bus loudness measurement.
+    loudness_meter: crate::loudness::LoudnessMeter,
+    /// Node ID of the loudness meter synth (None = not running).
+    loudness_meter_node_id: Option<i32>,
+    /// When scsynth was last asked for its status.
+    last_status_poll: Instant,
+    /// Dropped and late events, published with each status poll.
+    event_stats: crate::performance::EventStats,
+    /// When voice diagnostic buses were last polled.
+    last_diag_poll: Instant,
+    /// When voices were last checked for stuck notes.
+    last_stuck_check: Instant,
+    /// When the state was last swept by the garbage collector.
+    last_gc: Instant,
+    /// Sequence fades held back while over the CPU budget.
+    postponed_fades: Vec<crate::events::FadeClip>,
+    /// Synthdef parameters already warned about being clamped to their range.
+    clamp_warnings: HashSet<(String, String)>,
+    /// VST host synths whose plugin has finished opening.
+    open_vst_nodes: HashSet<i32>,
+    /// Transport beat automation lanes were last replayed up to.
+    automation_beat: Option<f64>,
 }
 
 impl RuntimeThread {
-    fn with_completion_channel(
+    pub(super) fn with_completion_channel(
         sc: Scsynth,
         shared: StateManager,
         message_rx: Receiver<StateMessage>,
@@ -441,12 +663,29 @@ impl RuntimeThread {
             message_rx,
             scheduler: EventScheduler::new(),
             transport: TransportClock::new(),
+            simulated_events: Vec::new(),
+            event_log: None,
             last_tick: Instant::now(),
             reload_manager: ReloadManager::new(),
             completion_tx: Some(completion_tx),
             midi_rx,
             midi_osc_handler: crate::midi_osc_handler::MidiOscHandler::new(),
             sc_midi_clock_node_id: None,
+            clock_out_running: false,
+            clock_out_scheduled_at: Instant::now(),
+            midi_clock: MidiClockFollower::new(),
+            midi_clock_epoch: Instant::now(),
+            loudness_meter: crate::loudness::LoudnessMeter::new(),
+            loudness_meter_node_id: None,
+            last_status_poll: Instant::now(),
+            event_stats: crate::performance::EventStats::default(),
+            last_diag_poll: Instant::now(),
+            last_stuck_check: Instant::now(),
+            last_gc: Instant::now(),
+            postponed_fades: Vec::new(),
+            clamp_warnings: HashSet::new(),
+            open_vst_nodes: HashSet::new(),
+            automation_beat: None,
         }
     }
 
@@ -457,9 +696,57 @@ impl RuntimeThread {
             self.drain_messages();
             self.drain_midi_messages();
             self.poll_osc_messages();
+            self.poll_server_status();
+            self.poll_voice_diagnostics();
+            self.check_stuck_notes();
+            self.collect_garbage();
             self.tick();
             thread::sleep(interval);
         }
+
+        self.set_event_log(None);
+        self.shutdown_gracefully();
+    }
+
+    /// Fade out and clean up scsynth before the runtime stops.
+    ///
+    /// Fades the master bus to silence, gates off all synths, frees every
+    /// node and buffer of the session and asks scsynth to quit.
+    fn shutdown_gracefully(&mut self) {
+        if self.sc.is_noop() {
+            return;
+        }
+        log::info!("[SHUTDOWN] Fading out...");
+        let fade = crate::shutdown::SHUTDOWN_FADE_SECONDS;
+        let fader = NodeId::new(crate::shutdown::MASTER_FADER_NODE_ID);
+
+        // The fader is created at full gain and then ramped down (see create_master_fader_synthdef)
+        if let Err(e) = self.sc.s_new(
+            crate::shutdown::MASTER_FADER_SYNTHDEF,
+            fader,
+            AddAction::AddAfter,
+            Target::from(1),
+            &[("amp", 1.0), ("lag", fade)],
+        ) {
+            log::warn!("[SHUTDOWN] Failed to start master fade: {}", e);
+        }
+        let _ = self.sc.n_set(fader, &[("amp", 0.0)]);
+        thread::sleep(Duration::from_secs_f32(fade));
+
+        // Release every synth in the main group, then free everything
+        let _ = self.sc.n_set(NodeId::new(1), &[("gate", 0.0)]);
+        let _ = self.sc.g_free_all(1);
+        let _ = self.sc.n_free(fader);
+
+        let buffers = self.shared.with_state_read(crate::shutdown::allocated_buffers);
+        for buffer_id in &buffers {
+            let _ = self.sc.b_free(BufNum::new(*buffer_id));
+        }
+        log::info!("[SHUTDOWN] Freed all nodes and {} buffer(s)", buffers.len());
+
+        if let Err(e) = self.sc.osc.send_msg("/quit", vec![]) {
+            log::warn!("[SHUTDOWN] Failed to ask scsynth to quit: {}", e);
+        }
     }
 
     /// Process all pending MIDI messages.
@@ -568,6 +855,91 @@ impl RuntimeThread {
         }
     }
 
+    /// (Re)start the clock output on the next pulse of the transport.
+    ///
+    /// Used whenever the pulse grid moves: transport start, seek, tempo and
+    /// settings changes. Stops the clock if it is off or the transport isn't
+    /// running.
+    fn restart_clock_output(&mut self) {
+        let config = self.shared.with_state_read(|state| state.clock_output.clone());
+        let config = match config {
+            Some(config) if self.transport.is_running() => config,
+            _ => {
+                self.stop_clock_output();
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let start_beat = config.next_pulse_beat(self.transport.beat_at(now).to_float());
+        let node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
+        let mut args = vec![
+            OscType::String(crate::clock_out::CLOCK_OUT_SYNTHDEF.to_string()),
+            OscType::Int(node_id),
+            OscType::Int(AddAction::AddToHead.into()),
+            OscType::Int(crate::clock_out::CLOCK_OUT_GROUP_ID),
+        ];
+        for (name, value) in config.controls(self.transport.bpm()) {
+            args.push(OscType::String(name.to_string()));
+            args.push(OscType::Float(value));
+        }
+        let packets = vec![
+            OscPacket::Message(OscMessage {
+                addr: "/g_freeAll".to_string(),
+                args: vec![OscType::Int(crate::clock_out::CLOCK_OUT_GROUP_ID)],
+            }),
+            OscPacket::Message(OscMessage {
+                addr: "/s_new".to_string(),
+                args,
+            }),
+        ];
+
+        log::info!(
+            "[CLOCK OUT] Clocking output {} at {} PPQN from beat {:.2}",
+            config.channel, config.ppqn, start_beat
+        );
+        self.schedule_clock_output(start_beat, config.pulse_beats(), packets);
+        self.clock_out_running = true;
+    }
+
+    /// Stop the clock output at the current transport position.
+    fn stop_clock_output(&mut self) {
+        if !self.clock_out_running {
+            return;
+        }
+        let beat = self.transport.beat_at(Instant::now()).to_float();
+        let pulse = self
+            .shared
+            .with_state_read(|state| state.clock_output.as_ref().map(|c| c.pulse_beats()))
+            .unwrap_or(1.0);
+        let packets = vec![OscPacket::Message(OscMessage {
+            addr: "/g_freeAll".to_string(),
+            args: vec![OscType::Int(crate::clock_out::CLOCK_OUT_GROUP_ID)],
+        })];
+
+        log::info!("[CLOCK OUT] Stopping clock output at beat {:.2}", beat);
+        self.schedule_clock_output(beat, pulse, packets);
+        self.clock_out_running = false;
+    }
+
+    /// Send a clock output change timestamped at `beat`.
+    ///
+    /// Changes must reach the server in the order they were made, so the beat
+    /// is pushed back a pulse at a time until it lands after the previous one.
+    fn schedule_clock_output(&mut self, mut beat: f64, pulse: f64, packets: Vec<OscPacket>) {
+        let now = Instant::now();
+        let mut at = self.transport.beat_to_timestamp_and_instant(BeatTime::from_float(beat), now).0;
+        while at < self.clock_out_scheduled_at {
+            beat += pulse;
+            at = self.transport.beat_to_timestamp_and_instant(BeatTime::from_float(beat), now).0;
+        }
+        self.clock_out_scheduled_at = at;
+
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(BeatTime::from_float(beat), packets, &self.transport, now) {
+            log::error!("[CLOCK OUT] Failed to schedule clock output change: {}", e);
+        }
+    }
+
     /// Process a single MIDI message according to routing configuration.
     fn process_midi_message(&mut self, routing: &MidiRouting, msg: MidiMessage) {
         // Log if monitoring is enabled
@@ -591,13 +963,117 @@ impl RuntimeThread {
             MidiMessage::ChannelAftertouch { channel, pressure, .. } => {
                 self.handle_midi_aftertouch(routing, channel, pressure);
             }
+            MidiMessage::Clock { .. }
+            | MidiMessage::Start { .. }
+            | MidiMessage::Continue { .. }
+            | MidiMessage::Stop { .. }
+            | MidiMessage::SongPosition { .. }
+                if self.transport.clock_source() == ClockSource::Midi =>
+            {
+                self.handle_midi_clock(msg);
+            }
             // Ignore other messages for now
             _ => {}
         }
     }
 
+    /// Follow an external MIDI clock message.
+    fn handle_midi_clock(&mut self, msg: MidiMessage) {
+        let update = match msg {
+            MidiMessage::Clock { timestamp } => {
+                // Device timestamps (microseconds) are steadier than arrival times
+                let at = if timestamp > 0 {
+                    timestamp as f64 / 1_000_000.0
+                } else {
+                    self.midi_clock_epoch.elapsed().as_secs_f64()
+                };
+                self.midi_clock.tick(at)
+            }
+            MidiMessage::Start { .. } => {
+                self.midi_clock.start();
+                None
+            }
+            MidiMessage::Continue { .. } => {
+                self.midi_clock.resume();
+                None
+            }
+            MidiMessage::SongPosition { position, .. } => {
+                self.midi_clock.song_position(position);
+                None
+            }
+            MidiMessage::Stop { .. } => self.midi_clock.stop(),
+            _ => None,
+        };
+        if let Some(update) = update {
+            self.apply_midi_clock(update);
+        }
+    }
+
+    /// Move the transport with the external MIDI clock.
+    ///
+    /// The measured tempo is taken over once it moves by more than the
+    /// jitter tolerance; between ticks the transport runs on that tempo and
+    /// each tick slews it towards the clock's position, so the scheduler's
+    /// lookahead stays in step without audible jumps.
+    fn apply_midi_clock(&mut self, update: MidiClockUpdate) {
+        use crate::timing::{MIDI_CLOCK_MAX_PHASE_ERROR_MS, MIDI_CLOCK_MAX_SLEW_MS, MIDI_CLOCK_TEMPO_TOLERANCE_BPM};
+
+        if let Some(bpm) = self.midi_clock.bpm() {
+            if (self.transport.bpm() - bpm).abs() > MIDI_CLOCK_TEMPO_TOLERANCE_BPM {
+                self.handle_message(StateMessage::SetBpm { bpm: (bpm * 100.0).round() / 100.0 });
+            }
+        }
+
+        let beat = match update {
+            MidiClockUpdate::Stop => {
+                log::info!("[CLOCK] MIDI clock stopped the transport");
+                self.handle_message(StateMessage::StopScheduler);
+                return;
+            }
+            MidiClockUpdate::Start { beat } => {
+                log::info!("[CLOCK] MIDI clock started the transport at beat {:.2}", beat);
+                if (self.transport.beat_at(Instant::now()).to_float() - beat).abs() > 1e-6 {
+                    self.handle_message(StateMessage::SeekTransport { position: Beats(beat).into() });
+                }
+                if !self.transport.is_running() {
+                    self.handle_message(StateMessage::StartScheduler);
+                }
+                return;
+            }
+            MidiClockUpdate::Position { beat } => beat,
+        };
+        if !self.transport.is_running() {
+            return;
+        }
+
+        let now = Instant::now();
+        let beats_per_ms = self.transport.bpm() / 60_000.0;
+        let max_error = MIDI_CLOCK_MAX_PHASE_ERROR_MS * beats_per_ms;
+        let error = self.transport.correct_phase(
+            BeatTime::from_float(beat),
+            now,
+            MIDI_CLOCK_MAX_SLEW_MS * beats_per_ms,
+            max_error,
+        );
+        if error.abs() > max_error {
+            log::info!("[CLOCK] Jumped {:+.2} beats to the MIDI clock's position", error);
+            self.scheduler.reset_to_beat(beat);
+            if self.clock_out_running {
+                self.restart_clock_output();
+            }
+        }
+    }
+
     /// Handle MIDI note on event.
     fn handle_midi_note_on(&mut self, routing: &MidiRouting, channel: u8, note: u8, velocity: u8) {
+        // Live set bindings
+        let targets = self.shared.with_state_read(|state| {
+            state.live_set.as_ref().map(|live| live.set.note_targets(channel, note)).unwrap_or_default()
+        });
+        for target in targets {
+            self.handle_message(target.to_message());
+        }
+
         // First check for note callbacks and queue them
         let callback_ids: Vec<u64> = routing
             .find_note_callbacks(channel, note, true)
@@ -629,7 +1105,7 @@ impl RuntimeThread {
             // Build parameters
             let mut params = vec![
                 ("note".to_string(), note as f32),
-                ("freq".to_string(), 440.0 * 2.0_f32.powf((note as f32 - 69.0) / 12.0)),
+                ("freq".to_string(), pitch::note_to_freq(note as f64) as f32),
                 ("velocity".to_string(), vel),
                 ("gate".to_string(), 1.0),
             ];
@@ -654,7 +1130,7 @@ impl RuntimeThread {
             self.record_midi_note_on(channel, note, velocity, &route.voice_name);
 
             // Send note on to the voice
-            self.handle_note_on(&route.voice_name, transposed_note, (vel * 127.0) as u8, None);
+            self.handle_note_on(&route.voice_name, transposed_note, (vel * 127.0) as u8, None, NoteSource::Midi);
         }
     }
 
@@ -812,98 +1288,234 @@ impl RuntimeThread {
         };
 
         self.shared.with_state_write(|state| {
+            state.midi_recording.capture_take_note(&recorded_note);
             state
                 .midi_recording
                 .add_note(recorded_note, current_beat, beats_per_bar);
         });
     }
 
+    /// Restore the loop a take is being auditioned on.
+    fn end_take_audition(state: &mut ScriptState) {
+        let Some(audition) = state.midi_recording.audition.take() else {
+            return;
+        };
+        if let Some(loop_pattern) = Self::take_loop_mut(state, audition.kind, &audition.target) {
+            *loop_pattern = audition.original;
+        }
+    }
+
+    /// The loop a take targets.
+    /// A melody take with its notes snapped to the melody's scale, if it has one.
+    fn snap_take(state: &ScriptState, take: MidiTake) -> MidiTake {
+        let scale = match take.kind {
+            TakeTargetKind::Melody => state
+                .melodies
+                .get(&take.target)
+                .and_then(|m| m.scale_snap)
+                .and_then(|snap| snap.resolve(state.session_key)),
+            TakeTargetKind::Pattern => None,
+        };
+        match scale {
+            Some(scale) => take.quantized_to(&scale),
+            None => take,
+        }
+    }
+
+    fn take_loop_mut<'a>(state: &'a mut ScriptState, kind: TakeTargetKind, target: &str) -> Option<&'a mut Pattern> {
+        match kind {
+            TakeTargetKind::Pattern => state.patterns.get_mut(target).and_then(|p| p.loop_pattern.as_mut()),
+            TakeTargetKind::Melody => state.melodies.get_mut(target).and_then(|m| m.loop_pattern.as_mut()),
+        }
+    }
+
+    /// Parameter value of a CC route for a CC value (0-127).
+    ///
+    /// Voice and effect targets are clamped to the declared range of their
+    /// synthdef's parameter, which `param_range` routes scale over.
+    fn cc_route_value(&mut self, route: &crate::midi::CcRoute, cc_value: u8) -> f32 {
+        use crate::midi::CcTarget;
+
+        let (target_type, target) = match &route.target {
+            CcTarget::Voice(name) => (FadeTargetType::Voice, name),
+            CcTarget::Effect(id) => (FadeTargetType::Effect, id),
+            _ => return route.apply(cc_value),
+        };
+        let range = self.shared.with_state_read(|state| {
+            let synth = state.target_synthdef(&target_type, target)?;
+            state.param_range(synth, &route.param_name).cloned()
+        });
+        match range {
+            Some(range) if route.param_range => range.from_normalized(cc_value as f32 / 127.0),
+            _ if route.param_range => cc_value as f32 / 127.0,
+            _ => self.clamp_target_param(&target_type, target, &route.param_name, route.apply(cc_value)),
+        }
+    }
+
     /// Handle MIDI control change event.
     fn handle_midi_cc(&mut self, routing: &MidiRouting, channel: u8, controller: u8, value: u8) {
+        // Live set bindings fire on press (non-zero value)
+        if value > 0 {
+            let targets = self.shared.with_state_read(|state| {
+                state.live_set.as_ref().map(|live| live.set.cc_targets(channel, controller)).unwrap_or_default()
+            });
+            for target in targets {
+                self.handle_message(target.to_message());
+            }
+        }
+
         // Check for CC callbacks and queue any that should trigger
         self.shared.with_state_write(|state| {
             state.midi_config.routing.check_and_queue_cc_callbacks(channel, controller, value);
         });
 
         // Then process CC routes
-        let cc_routes = routing.find_cc_routes(channel, controller);
-
-        for route in cc_routes {
-            let param_value = route.apply(value);
+        for route in routing.find_cc_routes(channel, controller) {
+            self.apply_cc_route(route, value);
+        }
+    }
 
-            match &route.target {
-                crate::midi::CcTarget::Voice(voice_name) => {
-                    self.shared.with_state_write(|state| {
-                        if let Some(voice) = state.voices.get_mut(voice_name) {
-                            voice.params.insert(route.param_name.clone(), param_value);
-                            state.bump_version();
-                        }
-                    });
+    /// Set the parameter a CC route targets from a CC value (0-127).
+    fn apply_cc_route(&mut self, route: &crate::midi::CcRoute, cc_value: u8) {
+        let param_value = self.cc_route_value(route, cc_value);
 
-                    // Also update any currently playing synths for this voice
-                    let nodes: Vec<i32> = self.shared.with_state_read(|state| {
-                        state.active_synths
-                            .iter()
-                            .filter(|(_, s)| s.voice_names.contains(voice_name))
-                            .map(|(id, _)| *id)
-                            .collect()
-                    });
+        let recorded = match &route.target {
+            crate::midi::CcTarget::Voice(name) => Some((FadeTargetType::Voice, name)),
+            crate::midi::CcTarget::Effect(id) => Some((FadeTargetType::Effect, id)),
+            crate::midi::CcTarget::Group(path) => Some((FadeTargetType::Group, path)),
+            _ => None,
+        };
+        if let Some((target_type, target)) = recorded {
+            self.record_automation_value(target_type, target, &route.param_name, param_value);
+        }
 
-                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
-                    for node_id in nodes {
-                        let _ = self.osc_sender.n_set(
-                            OscTiming::Now,
-                            NodeId::new(node_id),
-                            &[(&route.param_name, param_value)],
-                            current_beat,
-                        );
+        match &route.target {
+            crate::midi::CcTarget::Voice(voice_name) => {
+                self.shared.with_state_write(|state| {
+                    if let Some(voice) = state.voices.get_mut(voice_name) {
+                        voice.params.insert(route.param_name.clone(), param_value);
+                        state.bump_version();
                     }
-                }
-                crate::midi::CcTarget::Effect(effect_id) => {
-                    let node_id = self.shared.with_state_read(|state| {
-                        state.effects.get(effect_id).and_then(|e| e.node_id)
-                    });
+                });
 
-                    if let Some(node_id) = node_id {
-                        let current_beat = self.transport.beat_at(Instant::now()).to_float();
-                        let _ = self.osc_sender.n_set(
-                            OscTiming::Now,
-                            NodeId::new(node_id),
-                            &[(&route.param_name, param_value)],
-                            current_beat,
-                        );
-                    }
-                }
-                crate::midi::CcTarget::Group(group_path) => {
-                    self.handle_set_group_param(group_path, &route.param_name, param_value);
-                }
-                crate::midi::CcTarget::Global(param_name) => {
-                    // Handle global parameters (e.g., tempo)
-                    match param_name.as_str() {
-                        "tempo" | "bpm" => {
-                            let now = Instant::now();
-                            self.transport.set_bpm(param_value as f64, now);
-                            self.shared.with_state_write(|state| {
-                                state.tempo = param_value as f64;
-                                state.bump_version();
-                            });
-                            self.osc_sender.set_tempo(param_value as f64);
-                        }
-                        _ => {
-                            log::debug!("Unknown global parameter: {}", param_name);
-                        }
-                    }
+                // Also update any currently playing synths for this voice
+                let nodes: Vec<i32> = self.shared.with_state_read(|state| {
+                    state.active_synths
+                        .iter()
+                        .filter(|(_, s)| s.voice_names.contains(voice_name))
+                        .map(|(id, _)| *id)
+                        .collect()
+                });
+
+                let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                for node_id in nodes {
+                    let _ = self.osc_sender.n_set(
+                        OscTiming::Now,
+                        NodeId::new(node_id),
+                        &[(&route.param_name, param_value)],
+                        current_beat,
+                    );
                 }
             }
-        }
-    }
-
-    /// Handle MIDI pitch bend event.
-    fn handle_midi_pitch_bend(&mut self, routing: &MidiRouting, channel: u8, value: i16) {
-        let routes = routing.find_pitch_bend_routes(channel);
-
-        // Convert pitch bend to 0.0-1.0 range
-        let normalized = (value as f32 + 8192.0) / 16383.0;
+            crate::midi::CcTarget::Effect(effect_id) => {
+                let node_id = self.shared.with_state_read(|state| {
+                    state.effects.get(effect_id).and_then(|e| e.node_id)
+                });
+
+                if let Some(node_id) = node_id {
+                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                    let _ = self.osc_sender.n_set(
+                        OscTiming::Now,
+                        NodeId::new(node_id),
+                        &[(&route.param_name, param_value)],
+                        current_beat,
+                    );
+                }
+            }
+            crate::midi::CcTarget::Group(group_path) => {
+                self.handle_set_group_param(group_path, &route.param_name, param_value);
+            }
+            crate::midi::CcTarget::Macro(name) => {
+                self.handle_message(StateMessage::SetMacro {
+                    name: name.clone(),
+                    value: param_value as f64,
+                });
+            }
+            crate::midi::CcTarget::Global(param_name) => {
+                // Handle global parameters (e.g., tempo)
+                match param_name.as_str() {
+                    "tempo" | "bpm" => {
+                        let now = Instant::now();
+                        self.transport.set_bpm(param_value as f64, now);
+                        self.shared.with_state_write(|state| {
+                            state.tempo = param_value as f64;
+                            state.bump_version();
+                        });
+                        self.osc_sender.set_tempo(param_value as f64);
+                    }
+                    _ => {
+                        log::debug!("Unknown global parameter: {}", param_name);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handle a control change a script sends to a voice.
+    ///
+    /// VST and MIDI output voices receive the CC itself. Other voices apply
+    /// the CC routes of the controller on the voice's channel, as if the CC
+    /// came from a controller; routes to other voices are left alone.
+    fn handle_control_change(&mut self, voice_name: &str, cc_num: u8, value: u8) {
+        if let Some((node_id, channel)) = self.vst_voice_target(voice_name) {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            let packet = crate::vst::control_change_packet(node_id, channel, cc_num, value);
+            let _ = self.osc_sender.send_packet(OscTiming::Now, packet, current_beat);
+            return;
+        }
+
+        let target = self.shared.with_state_read(|state| {
+            let voice = state.voices.get(voice_name)?;
+            let channel = voice.midi_channel.unwrap_or(0);
+            let midi_output = voice
+                .midi_output_device_id
+                .and_then(|id| state.midi_output_config.devices.get(&id))
+                .map(|device| device.event_tx.clone());
+            let routes: Vec<crate::midi::CcRoute> = state
+                .midi_config
+                .routing
+                .find_cc_routes(channel, cc_num)
+                .into_iter()
+                .filter(|route| !matches!(&route.target, crate::midi::CcTarget::Voice(name) if name != voice_name))
+                .cloned()
+                .collect();
+            Some((channel, midi_output, routes))
+        });
+        let Some((channel, midi_output, routes)) = target else {
+            log::warn!("[CC] Unknown voice '{}'", voice_name);
+            return;
+        };
+
+        if let Some(event_tx) = midi_output {
+            let _ = event_tx.send(crate::midi::QueuedMidiEvent::control_change(channel, cc_num, value));
+            log::debug!("[MIDI_OUT] Voice '{}' CC: {}={} (ch={})", voice_name, cc_num, value, channel + 1);
+            return;
+        }
+
+        if routes.is_empty() {
+            log::debug!("[CC] No route for CC {} of voice '{}'", cc_num, voice_name);
+        }
+        for route in &routes {
+            self.apply_cc_route(route, value);
+        }
+    }
+
+    /// Handle MIDI pitch bend event.
+    fn handle_midi_pitch_bend(&mut self, routing: &MidiRouting, channel: u8, value: i16) {
+        let routes = routing.find_pitch_bend_routes(channel);
+
+        // Convert pitch bend to 0.0-1.0 range
+        let normalized = (value as f32 + 8192.0) / 16383.0;
 
         for route in routes {
             let param_value = route.curve.apply(normalized, route.min_value, route.max_value);
@@ -1057,10 +1669,23 @@ impl RuntimeThread {
                                     self.handle_message(StateMessage::BufferLoaded {
                                         buffer_id: *buffer_id,
                                     });
+                                } else if cmd == "/b_write" {
+                                    self.handle_resample_written(*buffer_id);
                                 }
                             }
                         }
                     }
+                    "/status.reply" => {
+                        self.handle_status_reply(&msg.args);
+                    }
+                    "/vst_open" => {
+                        if let Some((node_id, success)) = crate::vst::parse_open_reply(&msg.args) {
+                            self.handle_vst_opened(node_id, success);
+                        }
+                    }
+                    "/c_setn" => {
+                        self.handle_control_bus_reply(&msg.args);
+                    }
                     "/fail" => {
                         // Log failures - temporarily at debug level to diagnose MIDI issues
                         log::debug!("[OSC] scsynth failure: {:?}", msg.args);
@@ -1090,8 +1715,12 @@ impl RuntimeThread {
                                 Some(rosc::OscType::Float(value)),
                             ) = (msg.args.first(), msg.args.get(1), msg.args.get(2))
                             {
-                                // Not a MIDI trigger, try meter trigger
-                                self.handle_meter_trigger(*node_id, *trig_id, *value);
+                                if self.loudness_meter_node_id == Some(*node_id) {
+                                    self.handle_loudness_trigger(*trig_id, *value);
+                                } else {
+                                    // Not a MIDI trigger, try meter trigger
+                                    self.handle_meter_trigger(*node_id, *trig_id, *value);
+                                }
                             }
                         }
                     }
@@ -1106,6 +1735,430 @@ impl RuntimeThread {
         }
     }
 
+    /// Ask scsynth for its status (CPU load, node counts) once per poll interval.
+    fn poll_server_status(&mut self) {
+        if self.last_status_poll.elapsed() < crate::performance::STATUS_POLL_INTERVAL {
+            return;
+        }
+        self.last_status_poll = Instant::now();
+        let osc = self.osc_sender.stats().clone();
+        let events = self.event_stats;
+        self.shared.with_state_write(|state| {
+            let perf = &mut state.performance;
+            perf.osc = osc;
+            perf.dropped_events = events.dropped;
+            perf.late_events = events.late;
+            perf.stale_dropped_events = events.stale_dropped;
+            perf.heartbeat = Some(Instant::now());
+        });
+        // Sent directly rather than through the OscSender: status polls are not part of the score
+        if let Err(e) = self.sc.osc.send_msg("/status", vec![]) {
+            log::debug!("[CPU] Failed to poll server status: {}", e);
+        }
+    }
+
+    /// Warn about notes sounding far longer than their expected length.
+    fn check_stuck_notes(&mut self) {
+        if self.last_stuck_check.elapsed() < STUCK_NOTE_CHECK_INTERVAL {
+            return;
+        }
+        let now = Instant::now();
+        self.last_stuck_check = now;
+        let stuck: Vec<(String, u8)> = self.shared.with_state_write(|state| {
+            let mut stuck = Vec::new();
+            for voice in state.voices.values_mut() {
+                for note in voice.flag_stuck_notes(now) {
+                    stuck.push((voice.name.clone(), note));
+                }
+            }
+            stuck
+        });
+        for (voice_name, note) in stuck {
+            log::warn!(
+                "[STUCK] Voice '{}' note {} is sounding far longer than expected - use panic() to silence it",
+                voice_name,
+                pitch::note_name(note)
+            );
+        }
+    }
+
+    /// Sweep the state with the garbage collection policy, if one is set.
+    fn collect_garbage(&mut self) {
+        if self.last_gc.elapsed() < crate::gc::GC_INTERVAL {
+            return;
+        }
+        let now = Instant::now();
+        self.last_gc = now;
+        if !self.shared.with_state_read(|state| state.gc.policy.is_enabled()) {
+            return;
+        }
+
+        let sweep = self.shared.with_state_write(|state| crate::gc::sweep(state, now));
+        let current_beat = self.transport.beat_at(now).to_float();
+        for (id, buffer_id) in &sweep.evicted {
+            log::info!("[GC] Freeing buffer {} of unused sample '{}' (reloaded on next use)", buffer_id, id);
+            let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(*buffer_id), current_beat);
+        }
+        if !sweep.expired_nodes.is_empty() {
+            log::debug!("[GC] Forgetting {} synths past their maximum age", sweep.expired_nodes.len());
+        }
+        for node_id in sweep.expired_nodes {
+            self.handle_message(StateMessage::NodeDestroyed { node_id });
+        }
+        if sweep.pruned_entries > 0 {
+            log::debug!("[GC] Dropped {} log entries and fired events", sweep.pruned_entries);
+        }
+    }
+
+    /// Reload a sample whose buffer the garbage collector freed, before
+    /// something plays `buffer_id` again.
+    fn reload_evicted_sample(&mut self, buffer_id: i32) {
+        let Some((id, path)) = self.shared.with_state_write(|state| {
+            let sample = state
+                .samples
+                .values()
+                .find(|s| s.buffer_id == buffer_id && state.gc.evicted.contains(&s.id))?;
+            let reload = (sample.id.clone(), sample.path.clone());
+            state.gc.evicted.remove(&reload.0);
+            Some(reload)
+        }) else {
+            return;
+        };
+        log::info!("[GC] Reloading sample '{}' into buffer {}", id, buffer_id);
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if let Err(e) = self.osc_sender.b_alloc_read(OscTiming::Now, BufNum::new(buffer_id), &path, current_beat) {
+            log::error!("[GC] Failed to reload sample '{}' from '{}': {}", id, path, e);
+        }
+    }
+
+    /// Silence everything: gate off and free all note synths, drop pending
+    /// note-offs and send all-notes-off to every MIDI output.
+    ///
+    /// Effects, running voices and the transport are left alone, so the
+    /// song carries on with the next scheduled notes.
+    fn panic(&mut self) {
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let (nodes, outputs) = self.shared.with_state_write(|state| {
+            let mut nodes: HashSet<i32> = HashSet::new();
+            for voice in state.voices.values_mut() {
+                nodes.extend(voice.active_notes.values().flatten().copied().filter(|&id| id >= 0));
+                voice.clear_notes();
+            }
+            nodes.extend(state.active_synths.keys().copied());
+            nodes.extend(state.pending_nodes.keys().copied());
+            state.active_synths.clear();
+            state.pending_nodes.clear();
+            state.scheduled_note_offs.clear();
+            state.bump_version();
+            let outputs: Vec<_> = state.midi_output_config.devices.values().map(|d| d.event_tx.clone()).collect();
+            (nodes.into_iter().collect::<Vec<i32>>(), outputs)
+        });
+
+        log::warn!(
+            "[PANIC] Releasing {} synths and sending all-notes-off to {} MIDI outputs",
+            nodes.len(),
+            outputs.len()
+        );
+        for &node_id in &nodes {
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("gate", 0.0f32)], current_beat);
+        }
+        if !nodes.is_empty() {
+            // Free after a short release so the gate-off doesn't click
+            let _ = self.osc_sender.send_bundle_after(PANIC_RELEASE_SECONDS, vec![n_free_packet(&nodes)], current_beat);
+        }
+
+        for event_tx in outputs {
+            for channel in 0..16 {
+                // Sustain pedal up, then all notes off (CC 64 / CC 123)
+                let _ = event_tx.send(crate::midi::QueuedMidiEvent::control_change(channel, 64, 0));
+                let _ = event_tx.send(crate::midi::QueuedMidiEvent::control_change(channel, 123, 0));
+            }
+        }
+
+        let instruments: Vec<OscPacket> = self
+            .open_vst_instrument_nodes()
+            .into_iter()
+            .flat_map(|node_id| (0..16).map(move |channel| crate::vst::all_notes_off_packet(node_id, channel)))
+            .collect();
+        let _ = self.osc_sender.send_bundle_now(instruments, current_beat);
+    }
+
+    /// Remember a synthdef's bytes (for score capture) and parameter ranges.
+    fn store_synthdef(&mut self, name: &str, bytes: &[u8]) {
+        let ranges = vibelang_dsp::get_param_ranges(name);
+        self.shared.with_state_write(|state| {
+            state.synthdefs.insert(name.to_string(), bytes.to_vec());
+            if ranges.is_empty() {
+                state.param_ranges.remove(name);
+            } else {
+                state.param_ranges.insert(name.to_string(), ranges);
+            }
+        });
+        self.clamp_warnings.retain(|(synth_def, _)| synth_def != name);
+
+        // Capture to score if enabled - add /d_recv at time 0
+        if let Some(writer) = self.osc_sender.score_writer_mut() {
+            let packet = rosc::OscPacket::Message(rosc::OscMessage {
+                addr: "/d_recv".to_string(),
+                args: vec![rosc::OscType::Blob(bytes.to_vec())],
+            });
+            // Synthdefs should be at time 0 (before any notes play)
+            writer.add_packet(0.0, packet);
+            log::debug!("[SCORE] Captured synthdef '{}' at time 0", name);
+        }
+    }
+
+    /// Start or stop capturing the OSC traffic with scsynth.
+    fn set_osc_capture(&mut self, path: Option<std::path::PathBuf>) {
+        let capture = &self.sc.osc.capture;
+        if let Some(stopped) = capture.stop() {
+            log::info!("[OSC] Capture written to {} (view with `vibe osc-dump`)", stopped.display());
+        }
+        let path = match path {
+            Some(path) => match capture.start(&path) {
+                Ok(()) => {
+                    log::info!("[OSC] Capturing OSC traffic to {}", path.display());
+                    Some(path)
+                }
+                Err(e) => {
+                    log::error!("[OSC] {:#}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        self.shared.with_state_write(|state| {
+            state.osc_capture = path;
+            state.bump_version();
+        });
+    }
+
+    /// Start or stop logging fired events; stopping writes the log file.
+    fn set_event_log(&mut self, path: Option<std::path::PathBuf>) {
+        if let Some(log) = self.event_log.take() {
+            if let Some(file) = log.path() {
+                match log.write() {
+                    Ok(count) => log::info!("[EVENTS] Wrote {} events to {}", count, file.display()),
+                    Err(e) => log::error!("[EVENTS] {:#}", e),
+                }
+            }
+        }
+        if let Some(path) = &path {
+            log::info!("[EVENTS] Logging fired events to {}", path.display());
+            self.event_log = Some(EventLog::new(Some(path.clone()), self.transport.current_beat()));
+        }
+        self.shared.with_state_write(|state| {
+            state.event_log = path;
+            state.bump_version();
+        });
+    }
+
+    /// Wall-clock length of `beats` at the current tempo.
+    fn beats_to_duration(&self, beats: f64) -> Duration {
+        Duration::from_secs_f64((beats.max(0.0) * 60.0 / self.transport.bpm()).min(86_400.0))
+    }
+
+    /// Read voices' diagnostic control buses once per poll interval.
+    fn poll_voice_diagnostics(&mut self) {
+        if self.last_diag_poll.elapsed() < DIAG_POLL_INTERVAL {
+            return;
+        }
+        self.last_diag_poll = Instant::now();
+        let ranges: Vec<(i32, usize)> = self.shared.with_state_read(|state| {
+            state
+                .voices
+                .values()
+                .filter_map(|v| Some((v.diag_bus?, v.diag_values.len())))
+                .filter(|(_, count)| *count > 0)
+                .collect()
+        });
+        for (bus, count) in ranges {
+            // Like status polls, not part of the score
+            if let Err(e) = self.sc.osc.send_msg("/c_getn", vec![OscType::Int(bus), OscType::Int(count as i32)]) {
+                log::debug!("[DIAG] Failed to poll control bus {}: {}", bus, e);
+                return;
+            }
+        }
+    }
+
+    /// Store a `/c_setn bus count values...` reply in the voice reading that bus.
+    fn handle_control_bus_reply(&mut self, args: &[OscType]) {
+        let Some(OscType::Int(bus)) = args.first() else {
+            return;
+        };
+        let values: Vec<f32> = args
+            .iter()
+            .skip(2)
+            .filter_map(|arg| match arg {
+                OscType::Float(v) => Some(*v),
+                _ => None,
+            })
+            .collect();
+        self.shared.with_state_write(|state| {
+            if let Some(voice) = state.voices.values_mut().find(|v| v.diag_bus == Some(*bus)) {
+                for ((_, value), new) in voice.diag_values.iter_mut().zip(values) {
+                    *value = new;
+                }
+            }
+        });
+    }
+
+    /// Control bus for a voice's diagnostic outputs, if its synthdef declares any.
+    ///
+    /// Buses are allocated the first time the voice plays such a synthdef and
+    /// kept across reloads; every voice gets room for the maximum number of
+    /// outputs so switching synthdefs never moves them.
+    fn voice_diag_bus(&self, voice_name: &str, synth_def: &str) -> Option<i32> {
+        let outputs = vibelang_dsp::get_synthdef_diag_outputs(synth_def);
+        if outputs.is_empty() {
+            return None;
+        }
+        self.shared.with_state_write(|state| {
+            let bus = match state.voices.get(voice_name)?.diag_bus {
+                Some(bus) => bus,
+                None => state.allocate_control_buses(vibelang_dsp::MAX_DIAG_OUTPUTS as i32),
+            };
+            let voice = state.voices.get_mut(voice_name)?;
+            voice.diag_bus = Some(bus);
+            if voice.diag_values.iter().map(|(name, _)| name).ne(outputs.iter()) {
+                voice.diag_values = outputs.into_iter().map(|name| (name, 0.0)).collect();
+                state.bump_version();
+            }
+            Some(bus)
+        })
+    }
+
+    /// Clamp a synthdef parameter to its declared range.
+    ///
+    /// The first clamp of each parameter is logged, so out-of-range values
+    /// from scripts, HTTP, MIDI or fades don't go unnoticed.
+    fn clamp_synth_param(&mut self, synth_def: &str, param: &str, value: f32) -> f32 {
+        let Some(range) = self.shared.with_state_read(|state| state.param_range(synth_def, param).cloned()) else {
+            return value;
+        };
+        let clamped = range.clamp(value);
+        if clamped != value && self.clamp_warnings.insert((synth_def.to_string(), param.to_string())) {
+            log::warn!(
+                "'{}' param '{}' = {} is outside its range {}, clamped to {}",
+                synth_def, param, value, range.describe(), clamped
+            );
+        }
+        clamped
+    }
+
+    /// Clamp a parameter of a voice, pattern, melody or effect to the range
+    /// declared by the synthdef it plays.
+    fn clamp_target_param(&mut self, target_type: &FadeTargetType, target: &str, param: &str, value: f32) -> f32 {
+        let synth_def = self
+            .shared
+            .with_state_read(|state| state.target_synthdef(target_type, target).map(str::to_string));
+        match synth_def {
+            Some(synth_def) => self.clamp_synth_param(&synth_def, param, value),
+            None => value,
+        }
+    }
+
+    /// Clamp `/s_new` controls to the synthdef's declared ranges.
+    fn clamp_controls(&mut self, synth_def: &str, controls: &mut [(String, f32)]) {
+        if !self.shared.with_state_read(|state| state.param_ranges.contains_key(synth_def)) {
+            return;
+        }
+        for (param, value) in controls.iter_mut() {
+            *value = self.clamp_synth_param(synth_def, param, *value);
+        }
+    }
+
+    /// Warn about MIDI devices slower than the lookahead can make up for.
+    ///
+    /// Their events can only be sent `LOOKAHEAD_MS` early (see
+    /// `LatencyCompensation::device_offset_ms`), so they sound late by the rest.
+    fn warn_clamped_device_latencies(&self) {
+        let latency = self.transport.latency();
+        let max_advance = LOOKAHEAD_MS as f64;
+        self.shared.with_state_read(|state| {
+            for (device_id, &ms) in &state.midi_config.routing.device_latency_ms {
+                if latency.device_offset_ms(ms, max_advance) > latency.output_latency_ms - ms {
+                    log::warn!(
+                        "[MIDI OUTPUT] Device {} latency {:.1} ms exceeds the {} ms lookahead plus {:.1} ms output latency; its events will sound {:.1} ms late",
+                        device_id,
+                        ms,
+                        LOOKAHEAD_MS,
+                        latency.output_latency_ms,
+                        ms - latency.output_latency_ms - max_advance
+                    );
+                }
+            }
+        });
+    }
+
+    /// Nominal sample rate from scsynth's last `/status.reply`, if any.
+    fn server_sample_rate(&self) -> Option<f64> {
+        self.shared
+            .with_state_read(|state| state.performance.status.as_ref().map(|status| status.nominal_sample_rate))
+    }
+
+    /// Handle a `/status.reply` from scsynth and apply the CPU policy.
+    fn handle_status_reply(&mut self, args: &[OscType]) {
+        use crate::performance::{max_degrade_level, BudgetTransition, ServerStatus};
+
+        let Some(status) = ServerStatus::from_reply(args) else {
+            log::debug!("[CPU] Malformed /status.reply: {:?}", args);
+            return;
+        };
+        let avg_cpu = status.avg_cpu;
+
+        let (transition, threshold, level) = self.shared.with_state_write(|state| {
+            let max_level = if state.performance.policy.drop_low_priority_voices {
+                max_degrade_level(state.voices.values().map(|v| v.priority))
+            } else {
+                0
+            };
+            let perf = &mut state.performance;
+            let transition = perf.budget.update(avg_cpu, &perf.policy, max_level);
+            if transition == BudgetTransition::OverBudget {
+                perf.dropped_events = 0;
+            }
+            perf.status = Some(status);
+            perf.last_update = Some(Instant::now());
+            let result = (transition, perf.policy.threshold, perf.budget.degrade_level);
+            state.bump_version();
+            result
+        });
+        if transition == BudgetTransition::OverBudget {
+            self.event_stats.dropped = 0;
+        }
+
+        match transition {
+            BudgetTransition::OverBudget => log::warn!(
+                "[CPU] Server load {:.1}% exceeds budget of {:.0}%, degrading mix",
+                avg_cpu, threshold
+            ),
+            BudgetTransition::Escalated => log::warn!(
+                "[CPU] Server load still {:.1}%, dropping {} voice priority tier(s)",
+                avg_cpu, level
+            ),
+            BudgetTransition::Recovered => {
+                log::info!("[CPU] Server load back to {:.1}%, restoring full mix", avg_cpu);
+                self.release_postponed_fades();
+            }
+            BudgetTransition::Unchanged => {}
+        }
+    }
+
+    /// Start all fades that were held back while over the CPU budget.
+    fn release_postponed_fades(&mut self) {
+        if self.postponed_fades.is_empty() {
+            return;
+        }
+        for fade in std::mem::take(&mut self.postponed_fades) {
+            self.start_fade_from_clip(fade);
+        }
+        self.shared.with_state_write(|state| {
+            state.performance.postponed_fades = 0;
+            state.bump_version();
+        });
+    }
+
     /// Handle meter trigger data from link synths.
     /// Called when we receive /tr messages from SendTrig UGens.
     /// Trig IDs: 0=peak_left, 1=peak_right, 2=rms_left, 3=rms_right
@@ -1151,9 +2204,110 @@ impl RuntimeThread {
         }
     }
 
-    fn drain_messages(&mut self) {
+    /// Handle mean-square data from the master loudness meter synth.
+    fn handle_loudness_trigger(&mut self, trig_id: i32, value: f32) {
+        if !self.loudness_meter.handle_trigger(trig_id, value) {
+            return;
+        }
+
+        let finite = |v: f64| v.is_finite().then_some(v);
+        let meter = &self.loudness_meter;
+        let short_term = meter.short_term();
+        let (target, was_over) = self.shared.with_state_write(|state| {
+            let loudness = &mut state.loudness;
+            loudness.momentary_lufs = finite(meter.momentary());
+            loudness.short_term_lufs = finite(short_term);
+            loudness.integrated_lufs = finite(meter.integrated());
+            loudness.max_momentary_lufs = finite(meter.max_momentary());
+            loudness.last_update = Some(Instant::now());
+            let was_over = loudness.over_target;
+            loudness.over_target = loudness.target_lufs.is_some_and(|t| short_term > t);
+            (loudness.target_lufs, was_over)
+        });
+
+        // Warn once each time the mix goes over the target
+        if let Some(target) = target {
+            if short_term > target && !was_over {
+                log::warn!(
+                    "[LOUDNESS] Mix is over target: {:.1} LUFS short-term (target {:.1} LUFS)",
+                    short_term,
+                    target
+                );
+            }
+        }
+    }
+
+    pub(super) fn drain_messages(&mut self) {
         while let Ok(msg) = self.message_rx.try_recv() {
+            let admitted = QuotaKind::of_message(&msg).map(|(kind, name)| (kind, name.to_string()));
+            crate::plugin::dispatch_message(&msg, &self.shared);
+            self.record_automation(&msg);
             self.handle_message(msg);
+            // Created (or refused) now, so the state counts it from here on
+            if let Some((kind, name)) = admitted {
+                self.shared.settle(kind, &name);
+            }
+        }
+    }
+
+    /// Record a parameter change sent to the runtime into its automation
+    /// lane, while automation recording is on.
+    ///
+    /// Only messages from outside the runtime get here; the changes fades,
+    /// macros and the lanes themselves make are not recorded.
+    fn record_automation(&mut self, msg: &StateMessage) {
+        let changes: Vec<(FadeTargetType, &str, &str, f32)> = match msg {
+            StateMessage::SetVoiceParam { name, param, value } => vec![(FadeTargetType::Voice, name, param, *value)],
+            StateMessage::SetVoiceParams { name, params } => params
+                .iter()
+                .map(|(param, value)| (FadeTargetType::Voice, name.as_str(), param.as_str(), *value))
+                .collect(),
+            StateMessage::SetGroupParam { path, param, value } => vec![(FadeTargetType::Group, path, param, *value)],
+            StateMessage::SetEffectParam { id, param, value } => vec![(FadeTargetType::Effect, id, param, *value)],
+            StateMessage::SetPatternParam { name, param, value } => vec![(FadeTargetType::Pattern, name, param, *value)],
+            StateMessage::SetMelodyParam { name, param, value } => vec![(FadeTargetType::Melody, name, param, *value)],
+            _ => return,
+        };
+        for (target_type, target, param, value) in changes {
+            self.record_automation_value(target_type, target, param, value);
+        }
+    }
+
+    /// Record a parameter value at the current transport beat, while
+    /// automation recording is on and the transport runs.
+    fn record_automation_value(&mut self, target_type: FadeTargetType, target: &str, param: &str, value: f32) {
+        if !self.shared.with_state_read(|s| s.transport_running && s.automation.recording.is_some()) {
+            return;
+        }
+        let beat = self.transport.beat_at(Instant::now()).to_float();
+        self.shared.with_state_write(|state| {
+            if state.automation.record(target_type, target, param, beat, value) {
+                state.bump_version();
+            }
+        });
+    }
+
+    /// Play the automation points passed since the last tick.
+    ///
+    /// After a start, a jump or a seek, every lane is set to its value at
+    /// the new position.
+    fn replay_automation(&mut self, beat: f64) {
+        let previous = self.automation_beat.replace(beat);
+        let writes: Vec<StateMessage> = self.shared.with_state_read(|state| {
+            state
+                .automation
+                .replaying()
+                .filter_map(|lane| {
+                    let value = match previous {
+                        Some(previous) if previous <= beat => lane.value_passed(previous, beat),
+                        _ => lane.value_at(beat),
+                    }?;
+                    Some(param_message(&lane.target_type, lane.target.clone(), lane.param.clone(), value))
+                })
+                .collect()
+        });
+        for message in writes {
+            self.handle_message(message);
         }
     }
 
@@ -1172,10 +2326,22 @@ impl RuntimeThread {
 
                 // Update SC-managed MIDI clock synth tempo if running
                 self.update_sc_midi_clock_tempo(bpm);
-            }
-            StateMessage::SetQuantization { beats } => {
+
+                // Re-phase the modular clock output at the new tempo
+                if self.clock_out_running {
+                    self.restart_clock_output();
+                }
+            }
+            StateMessage::SetQuantization { grid } => {
+                self.shared.with_state_write(|state| {
+                    state.quantization_beats = grid.to_beats(state.time_signature).0.max(EPSILON);
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetLaunchQuantization { kind, grid } => {
                 self.shared.with_state_write(|state| {
-                    state.quantization_beats = beats.max(EPSILON);
+                    let beats = grid.map(|grid| grid.to_beats(state.time_signature).0);
+                    state.launch_quantization.set(kind, beats);
                     state.bump_version();
                 });
             }
@@ -1190,6 +2356,30 @@ impl RuntimeThread {
                     state.bump_version();
                 });
             }
+            StateMessage::SetGroove { groove } => {
+                if let Some(groove) = &groove {
+                    log::info!("[GROOVE] Applying groove '{}' ({} voices)", groove.name, groove.feels.len());
+                }
+                self.shared.with_state_write(|state| {
+                    state.groove = groove;
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetSessionKey { key } => {
+                self.shared.with_state_write(|state| {
+                    state.session_key = key;
+                    let matched: Vec<String> = state
+                        .voices
+                        .values()
+                        .filter(|v| v.key_match.is_some())
+                        .map(|v| v.name.clone())
+                        .collect();
+                    for name in matched {
+                        state.apply_key_match(&name);
+                    }
+                    state.bump_version();
+                });
+            }
             StateMessage::StartScheduler => {
                 let now = Instant::now();
                 self.transport.start(now);
@@ -1207,6 +2397,8 @@ impl RuntimeThread {
                     self.send_midi_clock_message(crate::midi::QueuedMidiEvent::start());
                     log::info!("[MIDI CLOCK] Sent START message");
                 }
+
+                self.restart_clock_output();
             }
             StateMessage::StopScheduler => {
                 let now = Instant::now();
@@ -1228,6 +2420,7 @@ impl RuntimeThread {
                     state.transport_running = false;
                     state.bump_version();
                 });
+                self.stop_clock_output();
 
                 // Collect all nodes that need gate=0 (active notes + pending)
                 let nodes_to_release: Vec<i32> = self.shared.with_state_write(|state| {
@@ -1239,7 +2432,7 @@ impl RuntimeThread {
                         for node_ids in voice.active_notes.values() {
                             nodes.extend(node_ids.iter().copied());
                         }
-                        voice.active_notes.clear();
+                        voice.clear_notes();
                     }
 
                     // All active synths
@@ -1273,23 +2466,109 @@ impl RuntimeThread {
                     );
                 }
             }
-            StateMessage::SeekTransport { beat } => {
+            StateMessage::SeekTransport { position } => {
                 let now = Instant::now();
-                let target_beat = beat.max(0.0);
+                let target_beat = self.shared.with_state_read(|state| position.to_beats(state.time_signature)).0.max(0.0);
+                self.flush_scheduled_note_offs();
                 self.transport.seek(BeatTime::from_float(target_beat), now);
                 // Reset scheduler to target beat to prevent event burst
                 self.scheduler.reset_to_beat(target_beat);
                 self.shared.with_state_write(|state| {
                     state.current_beat = target_beat;
+                    state.locators.pending = None;
+                    // Sequences handing over to a legato successor are done
+                    state.active_sequences.retain(|_, active| active.stop_beat.is_none());
                     // Reset sequence anchors so they restart cleanly from target beat
                     for active in state.active_sequences.values_mut() {
                         active.anchor_beat = target_beat;
+                        active.launch_beat = target_beat;
                         active.triggered_clips.clear();
                         active.last_iteration = 0;
                         active.completed = false;
                     }
                     state.bump_version();
                 });
+
+                if self.clock_out_running {
+                    self.restart_clock_output();
+                }
+            }
+            StateMessage::SetMarker { name, position } => {
+                self.shared.with_state_write(|state| {
+                    let beat = position.to_beats(state.time_signature).0;
+                    state.locators.set(&name, beat);
+                    state.bump_version();
+                });
+            }
+            StateMessage::RemoveMarker { name } => {
+                self.shared.with_state_write(|state| {
+                    if state.locators.remove(&name) {
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::JumpToMarker { name, quantization } => {
+                self.jump_to_marker(&name, quantization);
+            }
+            StateMessage::SyncTransport {
+                beat,
+                at,
+                bpm,
+                running,
+                numerator,
+                denominator,
+            } => {
+                self.handle_sync_transport(beat, at, bpm, running, (numerator, denominator));
+            }
+            StateMessage::SetClockOutput { config } => {
+                let changed = self.shared.with_state_write(|state| {
+                    if state.clock_output == config {
+                        return false;
+                    }
+                    state.clock_output = config;
+                    state.bump_version();
+                    true
+                });
+                if changed {
+                    self.restart_clock_output();
+                }
+            }
+            StateMessage::SetClockSource { source } => {
+                if source == self.transport.clock_source() {
+                    return;
+                }
+                log::info!("[CLOCK] Following the {} clock", source.as_str());
+                self.transport.set_clock_source(source);
+                self.midi_clock = MidiClockFollower::new();
+                self.shared.with_state_write(|state| {
+                    state.clock_source = source;
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetOutputLatency { ms } => {
+                let ms = ms.max(0.0);
+                self.transport.latency_mut().output_latency_ms = ms;
+                self.shared.with_state_write(|state| {
+                    state.output_latency_ms = ms;
+                    state.bump_version();
+                });
+                log::info!("[LATENCY] Audio output latency {:.1} ms", ms);
+                self.warn_clamped_device_latencies();
+            }
+            StateMessage::SetNetSyncStatus { status } => {
+                self.shared.with_state_write(|state| {
+                    // The phase error is measured by the runtime, not the sync thread
+                    let phase_error_ms = state.net_sync.phase_error_ms;
+                    state.net_sync = NetSyncState {
+                        phase_error_ms,
+                        last_update: Some(Instant::now()),
+                        ..status
+                    };
+                    state.bump_version();
+                });
+            }
+            StateMessage::RestoreSession { snapshot } => {
+                self.handle_restore_session(snapshot);
             }
             StateMessage::BeginReload => {
                 // Capture a snapshot of current state BEFORE incrementing generation.
@@ -1322,26 +2601,39 @@ impl RuntimeThread {
             // === SynthDefs ===
             StateMessage::LoadSynthDef { name, bytes } => {
                 log::debug!("Loading synthdef '{}'", name);
-                // Store bytes in state for score capture
-                self.shared.with_state_write(|state| {
-                    state.synthdefs.insert(name.clone(), bytes.clone());
+                // A changed definition with sounding nodes is loaded as a new
+                // version (see crate::synthdef_versions)
+                let changed = self.shared.with_state_read(|state| {
+                    state.synthdefs.get(&name).is_some_and(|loaded| *loaded != bytes)
                 });
-
-                // Capture to score if enabled - add /d_recv at time 0
-                if let Some(writer) = self.osc_sender.score_writer_mut() {
-                    let packet = rosc::OscPacket::Message(rosc::OscMessage {
-                        addr: "/d_recv".to_string(),
-                        args: vec![rosc::OscType::Blob(bytes.clone())],
-                    });
-                    // Synthdefs should be at time 0 (before any notes play)
-                    writer.add_packet(0.0, packet);
-                    log::debug!("[SCORE] Captured synthdef '{}' at time 0", name);
-                }
-
-                if let Err(e) = self.sc.d_recv_bytes(bytes) {
+                let (loaded_name, loaded_bytes) = self.osc_sender.synthdef_versions_mut().load(&name, &bytes, changed);
+                self.store_synthdef(&name, &bytes);
+                let result = if loaded_name == name {
+                    self.sc.d_recv_bytes(loaded_bytes)
+                } else {
+                    log::info!("[SYNTHDEF] '{}' redefined while playing; new nodes use '{}'", name, loaded_name);
+                    self.osc_sender.d_recv(loaded_bytes)
+                };
+                if let Err(e) = result {
                     log::error!("Failed to load synthdef '{}': {}", name, e);
                 }
             }
+            StateMessage::LoadSynthDefDir { path, synthdefs } => {
+                log::debug!("Loading synthdef directory {}", path.display());
+                let mut names = Vec::with_capacity(synthdefs.len());
+                for (name, bytes) in &synthdefs {
+                    self.store_synthdef(name, bytes);
+                    names.push(name.clone());
+                }
+                let dir = path.clone();
+                self.shared.with_state_write(|state| {
+                    state.synthdef_dirs.insert(dir, names);
+                    state.bump_version();
+                });
+                if let Err(e) = self.sc.d_load_dir(&path) {
+                    log::error!("Failed to load synthdef directory {}: {}", path.display(), e);
+                }
+            }
 
             // === Groups ===
             StateMessage::RegisterGroup {
@@ -1376,10 +2668,197 @@ impl RuntimeThread {
                     }
                 });
             }
+            StateMessage::FreezeGroup { path, bars } => {
+                self.handle_freeze_group(&path, bars);
+            }
+            StateMessage::UnfreezeGroup { path } => {
+                self.handle_unfreeze_group(&path);
+            }
             StateMessage::FinalizeGroups => {
                 self.finalize_groups();
             }
 
+            // === Loudness ===
+            StateMessage::SetLoudnessTarget { target_lufs } => {
+                self.shared.with_state_write(|state| {
+                    state.loudness.target_lufs = target_lufs;
+                    state.loudness.over_target = false;
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetGcPolicy { policy } => {
+                self.shared.with_state_write(|state| {
+                    state.gc.policy = policy;
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetCpuPolicy { policy } => {
+                let postpone = policy.postpone_fades;
+                self.shared.with_state_write(|state| {
+                    state.performance.policy = policy;
+                    state.bump_version();
+                });
+                if !postpone {
+                    self.release_postponed_fades();
+                }
+            }
+            StateMessage::LoadLiveSet { path, set } => {
+                log::info!(
+                    "[LIVESET] Loaded {} ({} scenes, {} cues, {} bindings)",
+                    path.display(), set.scenes.len(), set.cues.len(), set.bindings.len()
+                );
+                self.shared.with_state_write(|state| {
+                    state.live_set = Some(LiveSetState::new(path, set));
+                    state.bump_version();
+                });
+            }
+            StateMessage::LaunchScene { name } => {
+                self.handle_launch_scene(&name);
+            }
+            StateMessage::StepCue { delta } => {
+                self.handle_step_cue(delta);
+            }
+            StateMessage::CreatePlaybackGraph { graph } => {
+                self.shared.with_state_write(|state| {
+                    match state.playback_graphs.get_mut(&graph.name) {
+                        Some(existing) => {
+                            // Keep the position across reloads
+                            if let Some(current) = existing.current.as_deref() {
+                                if graph.section(current).is_none() {
+                                    log::warn!(
+                                        "[GRAPH] '{}': current section '{}' no longer exists",
+                                        graph.name, current
+                                    );
+                                }
+                            }
+                            existing.graph = graph;
+                        }
+                        None => {
+                            state
+                                .playback_graphs
+                                .insert(graph.name.clone(), PlaybackGraphState::new(graph));
+                        }
+                    }
+                    state.bump_version();
+                });
+            }
+            StateMessage::StartPlaybackGraph { name, section } => {
+                self.start_playback_graph(&name, section.as_deref());
+            }
+            StateMessage::StopPlaybackGraph { name } => {
+                self.stop_playback_graph(&name);
+            }
+            StateMessage::CueGraph { name } => {
+                self.shared.with_state_write(|state| {
+                    match state.playback_graphs.get_mut(&name) {
+                        Some(graph) => {
+                            graph.cued = true;
+                            state.bump_version();
+                        }
+                        None => log::warn!("[GRAPH] Cannot cue unknown graph '{}'", name),
+                    }
+                });
+            }
+            StateMessage::SetGraphVar { name, var, value } => {
+                self.shared.with_state_write(|state| {
+                    match state.playback_graphs.get_mut(&name) {
+                        Some(graph) => {
+                            graph.vars.insert(var, value);
+                            state.bump_version();
+                        }
+                        None => log::warn!("[GRAPH] Cannot set variable on unknown graph '{}'", name),
+                    }
+                });
+            }
+            StateMessage::DefineMacro { mut control } => {
+                let reloaded = self.shared.with_state_write(|state| {
+                    // Keep the value across reloads
+                    let existing = state.macros.get(&control.name).map(|m| m.value);
+                    if let Some(value) = existing {
+                        control.value = value;
+                    }
+                    state.macros.insert(control.name.clone(), control.clone());
+                    state.bump_version();
+                    existing.is_some()
+                });
+                if reloaded {
+                    self.apply_macro(&control);
+                }
+            }
+            StateMessage::SetMacro { name, value } => {
+                let control = self.shared.with_state_write(|state| {
+                    let control = state.macros.get_mut(&name)?;
+                    control.set(value);
+                    let control = control.clone();
+                    state.bump_version();
+                    Some(control)
+                });
+                match control {
+                    Some(control) => self.apply_macro(&control),
+                    None => log::warn!("[MACRO] Cannot set unknown macro '{}'", name),
+                }
+            }
+            StateMessage::StartAutomationRecording { bars } => {
+                let bars = bars.clamp(1, crate::automation::MAX_AUTOMATION_BARS);
+                self.shared.with_state_write(|state| {
+                    let length = bars as f64 * state.time_signature.beats_per_bar();
+                    state.automation.start_recording(length);
+                    state.bump_version();
+                });
+                log::info!("[AUTOMATION] Recording {}-bar lanes", bars);
+            }
+            StateMessage::StopAutomationRecording => {
+                let recorded = self.shared.with_state_write(|state| {
+                    let recorded = state.automation.stop_recording();
+                    state.bump_version();
+                    recorded
+                });
+                log::info!("[AUTOMATION] Recording stopped ({} lanes recorded)", recorded);
+            }
+            StateMessage::SetAutomationLane { lane } => {
+                self.shared.with_state_write(|state| {
+                    state.automation.lanes.insert(lane.key(), lane);
+                    state.bump_version();
+                });
+            }
+            StateMessage::ClearAutomation { target } => {
+                self.shared.with_state_write(|state| {
+                    state.automation.clear(target.as_ref().map(|(target_type, name)| (target_type, name.as_str())));
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetCheckpoints { names } => {
+                self.shared.with_state_write(|state| {
+                    state.checkpoints.names = names;
+                    state.bump_version();
+                });
+            }
+            StateMessage::SelectCheckpoint { name } => {
+                log::info!("[CHECKPOINT] Evaluating up to {}", name.as_deref().unwrap_or("the end"));
+                self.shared.with_state_write(|state| {
+                    state.checkpoints.selected = name;
+                    state.checkpoints.reload_requested = true;
+                    state.bump_version();
+                });
+            }
+            StateMessage::SetParamSmoothing { param, seconds } => {
+                self.shared.with_state_write(|state| {
+                    state.param_smoothing.set(&param, seconds);
+                    state.bump_version();
+                });
+            }
+            StateMessage::ResetLoudness => {
+                self.loudness_meter.reset();
+                self.shared.with_state_write(|state| {
+                    let target_lufs = state.loudness.target_lufs;
+                    state.loudness = crate::state::LoudnessState {
+                        target_lufs,
+                        ..Default::default()
+                    };
+                    state.bump_version();
+                });
+            }
+
             // === Voices ===
             StateMessage::UpsertVoice {
                 name,
@@ -1398,15 +2877,22 @@ impl RuntimeThread {
                 midi_output_device_id,
                 midi_channel,
                 cc_mappings,
+                priority,
+                key_match,
+                pre_roll_ms,
+                feel,
+                mono,
+                drum_kit,
             } => {
                 let generation = self.shared.with_state_read(|s| s.reload_generation);
                 // Check if gain changed and get running node if any
-                let (gain_changed, running_node) = self.shared.with_state_read(|state| {
+                let (gain_changed, running_node, old_group, output_changed) = self.shared.with_state_read(|state| {
                     if let Some(voice) = state.voices.get(&name) {
-                        let changed = (voice.gain - gain).abs() > 0.0001;
-                        (changed, voice.running_node_id)
+                        let changed = (voice.gain - gain).abs() > 0.0001 || voice.muted != muted;
+                        let old_group = Some(voice.group_path.clone()).filter(|old| *old != group_path);
+                        (changed, voice.running_node_id, old_group, voice.output_bus != output_bus)
                     } else {
-                        (false, None)
+                        (false, None, None, false)
                     }
                 });
 
@@ -1430,86 +2916,76 @@ impl RuntimeThread {
                     voice.midi_output_device_id = midi_output_device_id;
                     voice.midi_channel = midi_channel;
                     voice.cc_mappings = cc_mappings;
+                    voice.priority = priority;
+                    voice.pre_roll_ms = pre_roll_ms;
+                    voice.feel = feel;
+                    voice.mono = mono;
+                    voice.drum_kit = drum_kit;
+                    // Fresh params are untransposed
+                    voice.key_match = key_match;
+                    voice.key_transpose = 0;
+                    state.apply_key_match(&name);
                     state.bump_version();
                 });
 
-                // If gain changed and voice has a running synth, update it
+                // If gain or mute changed and voice has a running synth, update it
                 if gain_changed {
                     if let Some(node_id) = running_node {
+                        let amp = if muted { 0.0 } else { gain as f32 };
                         let current_beat = self.transport.beat_at(Instant::now()).to_float();
                         let _ = self.osc_sender.n_set(
                             OscTiming::Now,
                             NodeId::new(node_id),
-                            &[("amp", gain as f32)],
+                            &[("amp", amp)],
                             current_beat,
                         );
-                        log::debug!("[VOICE] Updated running node {} gain to {}", node_id, gain);
+                        log::debug!("[VOICE] Updated running node {} gain to {}", node_id, amp);
                     }
                 }
+
+                // Sounding nodes and inserts follow the voice into its new group
+                if let Some(old_group) = &old_group {
+                    self.move_voice_nodes(&name, old_group);
+                }
+                if old_group.is_some() {
+                    self.place_voice_inserts(&name);
+                } else if output_changed {
+                    self.reroute_voice_nodes(&name);
+                }
             }
             StateMessage::DeleteVoice { name } => {
+                self.free_voice_inserts(&name);
                 self.shared.with_state_write(|state| {
                     state.voices.remove(&name);
                     state.bump_version();
                 });
             }
             StateMessage::SetVoiceParam { name, param, value } => {
-                // Check if this voice has MIDI CC mapping for this param
-                let midi_cc_info = self.shared.with_state_read(|state| {
-                    if let Some(voice) = state.voices.get(&name) {
-                        if let Some(device_id) = voice.midi_output_device_id {
-                            if let Some(&cc_num) = voice.cc_mappings.get(&param) {
-                                let channel = voice.midi_channel.unwrap_or(0);
-                                if let Some(device) = state.midi_output_config.devices.get(&device_id) {
-                                    return Some((device.event_tx.clone(), channel, cc_num));
-                                }
-                            }
-                        }
-                    }
-                    None
-                });
-
-                // Send MIDI CC if mapped
-                if let Some((event_tx, channel, cc_num)) = midi_cc_info {
-                    // Convert 0.0-1.0 to 0-127
-                    let cc_value = (value.clamp(0.0, 1.0) * 127.0) as u8;
-                    let midi_event = crate::midi::QueuedMidiEvent::control_change(channel, cc_num, cc_value);
-                    let _ = event_tx.send(midi_event);
-                    log::debug!("[MIDI_OUT] Voice '{}' CC: {}={} (param='{}', ch={})",
-                        name, cc_num, cc_value, param, channel + 1);
-                }
-
-                // Always update the local state too
-                self.shared.with_state_write(|state| {
-                    if let Some(voice) = state.voices.get_mut(&name) {
-                        voice.params.insert(param, value);
-                        state.bump_version();
-                    }
-                });
+                self.set_voice_params(&name, vec![(param, value)]);
+            }
+            StateMessage::SetVoiceParams { name, params } => {
+                let mut params: Vec<(String, f32)> = params.into_iter().collect();
+                params.sort_by(|a, b| a.0.cmp(&b.0));
+                self.set_voice_params(&name, params);
             }
             StateMessage::MuteVoice { name } => {
-                self.shared.with_state_write(|state| {
-                    if let Some(voice) = state.voices.get_mut(&name) {
-                        voice.muted = true;
-                        state.bump_version();
-                    }
-                });
+                self.set_voice_muted(&name, true);
             }
             StateMessage::UnmuteVoice { name } => {
-                self.shared.with_state_write(|state| {
-                    if let Some(voice) = state.voices.get_mut(&name) {
-                        voice.muted = false;
-                        state.bump_version();
-                    }
-                });
+                self.set_voice_muted(&name, false);
             }
             StateMessage::TriggerVoice {
                 name,
                 synth_name,
                 group_path,
                 params,
+                quantize,
             } => {
-                self.trigger_voice(&name, synth_name, group_path, params);
+                if quantize && self.shared.with_state_read(|s| s.transport_running) {
+                    self.trigger_voice_quantized(&name, synth_name, group_path, params);
+                } else {
+                    self.trigger_voice(&name, synth_name, group_path, params);
+                }
             }
             StateMessage::NoteOn {
                 voice_name,
@@ -1517,7 +2993,16 @@ impl RuntimeThread {
                 velocity,
                 duration,
             } => {
-                self.handle_note_on(&voice_name, note, velocity, duration);
+                self.handle_note_on(&voice_name, note, velocity, duration, NoteSource::Direct);
+            }
+            StateMessage::Panic => {
+                self.panic();
+            }
+            StateMessage::SetOscCapture { path } => {
+                self.set_osc_capture(path);
+            }
+            StateMessage::SetEventLog { path } => {
+                self.set_event_log(path);
             }
             StateMessage::NoteOff { voice_name, note } => {
                 self.handle_note_off(&voice_name, note, None);
@@ -1531,6 +3016,7 @@ impl RuntimeThread {
                 pattern,
                 source_location,
                 step_pattern,
+                text,
             } => {
                 let generation = self.shared.with_state_read(|s| s.reload_generation);
                 self.shared.with_state_write(|state| {
@@ -1539,10 +3025,20 @@ impl RuntimeThread {
                     });
                     ps.loop_pattern = Some(pattern);
                     ps.generation = generation;
+                    // A redefinition supersedes the loop an audition would restore
+                    if state
+                        .midi_recording
+                        .audition
+                        .as_ref()
+                        .is_some_and(|a| a.kind == TakeTargetKind::Pattern && a.target == name)
+                    {
+                        state.midi_recording.audition = None;
+                    }
                     ps.group_path = group_path;
                     ps.voice_name = voice_name;
                     ps.source_location = source_location;
                     ps.step_pattern = step_pattern;
+                    ps.text = text;
                     state.bump_version();
                 });
             }
@@ -1555,6 +3051,7 @@ impl RuntimeThread {
                 });
             }
             StateMessage::SetPatternParam { name, param, value } => {
+                let value = self.clamp_target_param(&FadeTargetType::Pattern, &name, &param, value);
                 self.shared.with_state_write(|state| {
                     if let Some(p) = state.patterns.get_mut(&name) {
                         p.params.insert(param, value);
@@ -1585,6 +3082,15 @@ impl RuntimeThread {
                     });
                     ms.loop_pattern = Some(pattern);
                     ms.generation = generation;
+                    // A redefinition supersedes the loop an audition would restore
+                    if state
+                        .midi_recording
+                        .audition
+                        .as_ref()
+                        .is_some_and(|a| a.kind == TakeTargetKind::Melody && a.target == name)
+                    {
+                        state.midi_recording.audition = None;
+                    }
                     ms.group_path = group_path;
                     ms.voice_name = voice_name;
                     ms.source_location = source_location;
@@ -1592,6 +3098,72 @@ impl RuntimeThread {
                     state.bump_version();
                 });
             }
+            StateMessage::SetLoopConditions { name, kind, conditions } => {
+                self.shared.with_state_write(|state| {
+                    let target = match kind {
+                        LoopKind::Pattern => state.patterns.get_mut(&name).map(|p| &mut p.conditions),
+                        LoopKind::Melody => state.melodies.get_mut(&name).map(|m| &mut m.conditions),
+                        _ => None,
+                    };
+                    if let Some(target) = target {
+                        *target = conditions;
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::SetLoopHumanize { name, kind, humanize } => {
+                self.shared.with_state_write(|state| {
+                    let target = match kind {
+                        LoopKind::Pattern => state.patterns.get_mut(&name).map(|p| &mut p.humanize),
+                        LoopKind::Melody => state.melodies.get_mut(&name).map(|m| &mut m.humanize),
+                        _ => None,
+                    };
+                    if let Some(target) = target {
+                        *target = humanize;
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::SetPatternMidiTarget { name, target } => {
+                self.shared.with_state_write(|state| {
+                    if let Some(pattern) = state.patterns.get_mut(&name) {
+                        pattern.midi_target = target;
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::SetPatternVariations { name, variations } => {
+                self.shared.with_state_write(|state| {
+                    if let Some(pattern) = state.patterns.get_mut(&name) {
+                        pattern.variations = variations;
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::SetMelodyScaleSnap { name, snap } => {
+                self.shared.with_state_write(|state| {
+                    if let Some(melody) = state.melodies.get_mut(&name) {
+                        melody.scale_snap = snap;
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::SetMelodyArp { name, arp } => {
+                self.shared.with_state_write(|state| {
+                    if let Some(melody) = state.melodies.get_mut(&name) {
+                        melody.arp = arp;
+                        state.bump_version();
+                    }
+                });
+            }
+            StateMessage::LockPatternVariation { name, index } => {
+                self.shared.with_state_write(|state| {
+                    if let Some(pattern) = state.patterns.get_mut(&name) {
+                        pattern.locked_variation = index;
+                        state.bump_version();
+                    }
+                });
+            }
             StateMessage::DeleteMelody { name } => {
                 self.shared.with_state_write(|state| {
                     state.melodies.remove(&name);
@@ -1599,6 +3171,7 @@ impl RuntimeThread {
                 });
             }
             StateMessage::SetMelodyParam { name, param, value } => {
+                let value = self.clamp_target_param(&FadeTargetType::Melody, &name, &param, value);
                 self.shared.with_state_write(|state| {
                     if let Some(m) = state.melodies.get_mut(&name) {
                         m.params.insert(param, value);
@@ -1613,6 +3186,11 @@ impl RuntimeThread {
                 self.stop_loop(&name, LoopKind::Melody);
             }
 
+            // === Quotas ===
+            StateMessage::SetQuotas { quotas } => {
+                self.shared.set_quotas(quotas);
+            }
+
             // === Sequences ===
             StateMessage::CreateSequence { sequence } => {
                 use crate::sequences::ClipSource;
@@ -1755,6 +3333,9 @@ impl RuntimeThread {
                     state.bump_version();
                 });
             }
+            StateMessage::StartFade { name } => {
+                self.start_fade(&name);
+            }
             StateMessage::FadeGroupParam { .. }
             | StateMessage::FadeVoiceParam { .. }
             | StateMessage::FadePatternParam { .. }
@@ -1788,10 +3369,13 @@ impl RuntimeThread {
                 params,
                 bus_in: _,
                 bus_out: _,
+                vst_plugin,
                 source_location,
-            } => {
-                self.handle_add_effect(id, synthdef, group_path, params, source_location);
-            }
+                voice,
+            } => match voice {
+                Some(voice) => self.handle_add_voice_insert(id, synthdef, voice, params, vst_plugin, source_location),
+                None => self.handle_add_effect(id, synthdef, group_path, params, vst_plugin, source_location),
+            },
             StateMessage::RemoveEffect { id } => {
                 let node_to_free = self.shared.with_state_write(|state| {
                     let node = state.effects.remove(&id).and_then(|e| e.node_id);
@@ -1803,18 +3387,35 @@ impl RuntimeThread {
                     let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(node_id), current_beat);
                 }
             }
+            StateMessage::SetEffectParam { id, param, value } if self.is_vst_effect(&id) => {
+                // Plugin parameters are set by name once the plugin is open
+                let node_id = self.shared.with_state_write(|state| {
+                    let effect = state.effects.get_mut(&id)?;
+                    effect.params.insert(param.clone(), value);
+                    let node_id = effect.node_id;
+                    state.bump_version();
+                    node_id
+                });
+                if let Some(node_id) = node_id.filter(|n| self.open_vst_nodes.contains(n)) {
+                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                    let packet = crate::vst::set_param_packet(node_id, crate::vst::VST_EFFECT_UGEN_INDEX, &param, value);
+                    let _ = self.osc_sender.send_packet(OscTiming::Now, packet, current_beat);
+                }
+            }
             StateMessage::SetEffectParam { id, param, value } => {
+                let value = self.clamp_target_param(&FadeTargetType::Effect, &id, &param, value);
                 let node_to_update = self.shared.with_state_write(|state| {
-                    let node_id = state.effects.get_mut(&id).and_then(|effect| {
-                        effect.params.insert(param.clone(), value);
-                        effect.node_id
+                    let node = state.effects.get_mut(&id).and_then(|effect| {
+                        let defaults = vibelang_dsp::get_effect_param_defaults(&effect.synthdef_name);
+                        let has_lag = defaults.contains_key(&vibelang_dsp::ramp_lag_control(&param));
+                        let previous = effect.params.insert(param.clone(), value).or_else(|| defaults.get(&param).copied());
+                        effect.node_id.map(|node_id| (node_id, previous, has_lag))
                     });
                     state.bump_version();
-                    node_id
+                    node
                 });
-                if let Some(node_id) = node_to_update {
-                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
-                    let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[(param.as_str(), value)], current_beat);
+                if let Some((node_id, previous, has_lag)) = node_to_update {
+                    self.set_node_param_smoothed(node_id, &param, previous, value, has_lag);
                 }
             }
 
@@ -1835,60 +3436,83 @@ impl RuntimeThread {
                     let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(buffer_id), current_beat);
                 }
             }
+            StateMessage::PreviewSample { id, amp } => {
+                self.handle_preview_sample(&id, amp);
+            }
 
-            // === SFZ ===
-            StateMessage::LoadSfzInstrument { id, sfz_path } => {
-                log::info!("Loading SFZ instrument '{}' from {:?}", id, sfz_path);
-
-                // Get the next buffer ID from state
-                let mut next_buffer_id = self.shared.with_state_read(|state| state.next_buffer_id);
-
-                // Clone sc for the closure
-                let sc_clone = self.sc.clone();
+            // === Loopers ===
+            StateMessage::UpsertLooper { name, group_path, input, bars, gain } => {
+                self.handle_upsert_looper(name, group_path, input, bars, gain);
+            }
+            StateMessage::LooperControl { name, action } => match action {
+                LooperAction::Record => self.handle_looper_record(&name),
+                LooperAction::Overdub => self.handle_looper_overdub(&name),
+                LooperAction::Clear => self.handle_looper_clear(&name),
+            },
 
-                // Load the SFZ instrument using the callback-based loader
-                let result = vibelang_sfz::load_sfz_instrument(
-                    &sfz_path,
-                    id.clone(),
-                    &mut |path, buffer_id| {
-                        sc_clone.b_alloc_read(BufNum::new(buffer_id), path)
-                            .map_err(|e| anyhow::anyhow!("Buffer load failed: {}", e))
-                    },
-                    &mut next_buffer_id,
-                );
+            // === Resampling ===
+            StateMessage::Resample { name, group_path, bars } => {
+                self.handle_resample(name, group_path, bars);
+            }
 
-                match result {
-                    Ok(instrument) => {
-                        log::info!(
-                            "Loaded SFZ instrument '{}' with {} regions",
-                            id,
-                            instrument.num_regions()
-                        );
-
-                        // Give SuperCollider time to load buffers
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-
-                        // Store in state
-                        self.shared.with_state_write(|state| {
-                            state.sfz_instruments.insert(id.clone(), instrument);
-                            state.next_buffer_id = next_buffer_id;
-                            state.bump_version();
-                        });
-                    }
-                    Err(e) => {
-                        log::error!("Failed to load SFZ instrument '{}': {}", id, e);
+            // === Hooks ===
+            StateMessage::SetHook { name, action, beat, every, timeout, source_location } => {
+                self.handle_set_hook(name, action, beat, every, timeout, source_location);
+            }
+            StateMessage::RemoveHook { name } => {
+                self.shared.with_state_write(|state| {
+                    if state.hooks.remove(&name).is_some() {
+                        state.bump_version();
                     }
+                });
+            }
+
+            // === Plugins ===
+            StateMessage::Plugin { plugin, name, .. } => {
+                // Plugins saw the message before it got here
+                if !crate::plugin::is_registered(&plugin) {
+                    log::warn!("[PLUGIN] No plugin '{}' for message '{}'", plugin, name);
                 }
             }
 
+            // === Return Channels ===
+            StateMessage::UpsertReturnChannel { name, group_path, input, channels, gain } => {
+                self.handle_upsert_return_channel(name, group_path, input, channels, gain);
+            }
+
+            // === SFZ ===
+            StateMessage::LoadSfzInstrument { id, sfz_path } => {
+                self.handle_load_sfz(id, sfz_path);
+            }
+
+            // === Preloading ===
+            StateMessage::PreloadAssets { samples, sfz_instruments } => {
+                self.handle_preload_assets(samples, sfz_instruments);
+            }
+
             // === VST ===
-            StateMessage::LoadVstInstrument { .. }
-            | StateMessage::VstNoteOn { .. }
-            | StateMessage::VstNoteOff { .. }
-            | StateMessage::SetVstParam { .. }
-            | StateMessage::SetVstParamByName { .. } => {
-                // TODO: Implement VST support
-                log::debug!("VST not yet implemented");
+            StateMessage::LoadVstInstrument { id, plugin_key, group_path } => {
+                self.handle_load_vst_instrument(id, plugin_key, group_path);
+            }
+            StateMessage::VstNoteOn { instrument_id, note, velocity } => {
+                if let Some(node_id) = self.open_vst_instrument(&instrument_id) {
+                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                    let packet = crate::vst::note_on_packet(node_id, 0, note, velocity);
+                    let _ = self.osc_sender.send_packet(OscTiming::Now, packet, current_beat);
+                }
+            }
+            StateMessage::VstNoteOff { instrument_id, note } => {
+                if let Some(node_id) = self.open_vst_instrument(&instrument_id) {
+                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                    let packet = crate::vst::note_off_packet(node_id, 0, note);
+                    let _ = self.osc_sender.send_packet(OscTiming::Now, packet, current_beat);
+                }
+            }
+            StateMessage::SetVstParam { instrument_id, param_index, value } => {
+                self.set_vst_instrument_param(&instrument_id, param_index.to_string(), value);
+            }
+            StateMessage::SetVstParamByName { instrument_id, param_name, value } => {
+                self.set_vst_instrument_param(&instrument_id, param_name, value);
             }
 
             // === Events ===
@@ -1913,11 +3537,11 @@ impl RuntimeThread {
             }
 
             // === Control Change ===
-            StateMessage::ControlChange { .. } => {
-                // TODO: Implement MIDI CC
+            StateMessage::ControlChange { voice_name, cc_num, value } => {
+                self.handle_control_change(&voice_name, cc_num, value);
             }
-            StateMessage::StopVoice { .. } => {
-                // TODO: Implement voice stopping
+            StateMessage::StopVoice { name, fade, choke } => {
+                self.handle_stop_voice(&name, fade, choke);
             }
             StateMessage::PauseSequence { .. } | StateMessage::ResumeSequence { .. } => {
                 // TODO: Implement sequence pause/resume
@@ -2120,6 +3744,90 @@ impl RuntimeThread {
                 });
                 log::info!("[MIDI] Recording history cleared");
             }
+            StateMessage::MidiArmTake { target, mode } => {
+                let armed = self.shared.with_state_write(|state| {
+                    let (kind, voice_name, loop_pattern) = if let Some(p) = state.patterns.get(&target) {
+                        (TakeTargetKind::Pattern, p.voice_name.clone(), p.loop_pattern.as_ref())
+                    } else if let Some(m) = state.melodies.get(&target) {
+                        (TakeTargetKind::Melody, m.voice_name.clone(), m.loop_pattern.as_ref())
+                    } else {
+                        return false;
+                    };
+                    let loop_beats = loop_pattern
+                        .map(|p| p.loop_length_beats)
+                        .unwrap_or_else(|| state.time_signature.beats_per_bar());
+                    state.midi_recording.arm_take(target.clone(), kind, voice_name, mode, loop_beats);
+                    state.bump_version();
+                    true
+                });
+                if armed {
+                    log::info!("[MIDI] Recording {} take for '{}'", mode.as_str(), target);
+                } else {
+                    log::warn!("[MIDI] Cannot record a take: no pattern or melody named '{}'", target);
+                }
+            }
+            StateMessage::MidiStopTake => {
+                let finished = self.shared.with_state_write(|state| {
+                    let id = state.midi_recording.finish_take();
+                    state.bump_version();
+                    id
+                });
+                match finished {
+                    Some(id) => log::info!("[MIDI] Take {} recorded", id),
+                    None => log::info!("[MIDI] Take stopped without notes"),
+                }
+            }
+            StateMessage::MidiAuditionTake { id } => {
+                self.shared.with_state_write(|state| {
+                    Self::end_take_audition(state);
+                    let Some(take) = id.and_then(|id| state.midi_recording.take(id)).cloned() else {
+                        state.bump_version();
+                        return;
+                    };
+                    let take = Self::snap_take(state, take);
+                    if let Some(loop_pattern) = Self::take_loop_mut(state, take.kind, &take.target) {
+                        let original = std::mem::replace(loop_pattern, Pattern::new(take.target.clone(), 0.0));
+                        *loop_pattern = take.apply_to(&original);
+                        state.midi_recording.audition = Some(TakeAudition {
+                            take_id: take.id,
+                            target: take.target.clone(),
+                            kind: take.kind,
+                            original,
+                        });
+                        log::info!("[MIDI] Auditioning take {} on '{}'", take.id, take.target);
+                    }
+                    state.bump_version();
+                });
+            }
+            StateMessage::MidiKeepTake { id } => {
+                self.shared.with_state_write(|state| {
+                    let Some(take) = state.midi_recording.remove_take(id) else {
+                        return;
+                    };
+                    let take = Self::snap_take(state, take);
+                    // An auditioned take is already applied; keeping it just forgets the original
+                    if state.midi_recording.auditioning() == Some(id) {
+                        state.midi_recording.audition = None;
+                    } else {
+                        if let Some(loop_pattern) = Self::take_loop_mut(state, take.kind, &take.target) {
+                            *loop_pattern = take.apply_to(loop_pattern);
+                        }
+                    }
+                    log::info!("[MIDI] Kept take {} on '{}'", id, take.target);
+                    state.bump_version();
+                });
+            }
+            StateMessage::MidiDiscardTake { id } => {
+                self.shared.with_state_write(|state| {
+                    if state.midi_recording.auditioning() == Some(id) {
+                        Self::end_take_audition(state);
+                    }
+                    if state.midi_recording.remove_take(id).is_some() {
+                        log::info!("[MIDI] Discarded take {}", id);
+                    }
+                    state.bump_version();
+                });
+            }
 
             // === MIDI Output ===
             StateMessage::MidiOutputOpenDevice { device_id, info, event_tx } => {
@@ -2197,6 +3905,26 @@ impl RuntimeThread {
                 }
             }
 
+            StateMessage::MidiOutputSendRaw { device, messages } => {
+                let needle = device.to_lowercase();
+                let event_tx = self.shared.with_state_read(|state| {
+                    state
+                        .midi_output_config
+                        .devices
+                        .values()
+                        .find(|d| d.info.name.to_lowercase().contains(&needle))
+                        .map(|d| d.event_tx.clone())
+                });
+                match event_tx {
+                    Some(event_tx) => {
+                        for bytes in messages {
+                            let _ = event_tx.send(crate::midi::QueuedMidiEvent { bytes });
+                        }
+                    }
+                    None => log::warn!("[MIDI OUTPUT] No open output device matching '{}'", device),
+                }
+            }
+
             StateMessage::MidiOutputSetClockEnabled { enabled } => {
                 // Get clock device ID and tempo before updating state
                 let (device_id, bpm) = self.shared.with_state_read(|state| {
@@ -2231,6 +3959,15 @@ impl RuntimeThread {
                 });
             }
 
+            StateMessage::MidiOutputSetDeviceLatency { device_id, ms } => {
+                self.shared.with_state_write(|state| {
+                    state.midi_config.routing.set_device_latency(device_id, ms);
+                    state.bump_version();
+                });
+                log::info!("[MIDI OUTPUT] Device {} latency {:.1} ms", device_id, ms);
+                self.warn_clamped_device_latencies();
+            }
+
             StateMessage::MidiOutputSendStart => {
                 self.send_midi_clock_message(crate::midi::QueuedMidiEvent::start());
             }
@@ -2246,6 +3983,11 @@ impl RuntimeThread {
             // === OSC Feedback ===
             StateMessage::NodeCreated { .. } => {}
             StateMessage::NodeDestroyed { node_id } => {
+                if let Some(def) = self.osc_sender.synthdef_versions_mut().node_ended(node_id) {
+                    log::info!("[SYNTHDEF] Freeing '{}', superseded and no longer playing", def);
+                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                    let _ = self.osc_sender.send_msg(OscTiming::Now, "/d_free", vec![rosc::OscType::String(def)], current_beat);
+                }
                 self.shared.with_state_write(|state| {
                     state.active_synths.remove(&node_id);
                     // Also clean up node from voices' active_notes
@@ -2377,6 +4119,12 @@ impl RuntimeThread {
 
                     // Add final events to the score writer before disabling
                     if let Some(writer) = self.osc_sender.score_writer_mut() {
+                        // Notes the scheduler already sent for after the stop don't belong in it
+                        let dropped = writer.drop_synths_after(current_time);
+                        if dropped > 0 {
+                            log::info!("[SCORE] Dropped {} events scheduled past the stop", dropped);
+                        }
+
                         // Free all synths in the default group (node 1) at end_time
                         // This ensures all synths stop and scsynth can finish rendering
                         writer.add_message(end_time, "/g_freeAll", vec![
@@ -2408,12 +4156,13 @@ impl RuntimeThread {
         }
     }
 
-    fn tick(&mut self) {
+    pub(super) fn tick(&mut self) {
         let now = Instant::now();
 
         // Skip if transport not running
         if !self.shared.with_state_read(|s| s.transport_running) {
             self.last_tick = now;
+            self.automation_beat = None;
             return;
         }
 
@@ -2422,8 +4171,9 @@ impl RuntimeThread {
             state.pending_nodes.retain(|_, live_instant| *live_instant > now);
         });
 
-        // Get current beat
-        let current_beat = self.transport.beat_at(now).to_float();
+        // Get current beat, after a locator jump that is due
+        let current_beat = self.process_pending_jump(self.transport.beat_at(now).to_float());
+        self.sc.osc.capture.set_beat(current_beat);
         self.shared.with_state_write(|state| {
             state.current_beat = current_beat;
         });
@@ -2436,8 +4186,35 @@ impl RuntimeThread {
         // Process pending reload changes at quantization boundary
         self.process_pending_reload(current_beat);
 
-        // Collect loops that need event expansion
-        let loops = self.collect_active_loops();
+        // Take playback graph transitions (before collecting events, so a
+        // section entered at a bar line gets its first events scheduled)
+        self.update_playback_graphs(current_beat);
+
+        // Swap in finished group bounces
+        self.process_group_freezes(current_beat);
+
+        // Start loopers whose recording pass is ending
+        self.process_loopers(current_beat);
+
+        // Write finished resample recordings to disk
+        self.process_resamples(current_beat);
+
+        // Hand due hooks to their worker threads
+        self.process_hooks(current_beat);
+
+        crate::plugin::dispatch_tick(current_beat, &self.shared);
+
+        // Replay recorded parameter tweaks
+        self.replay_automation(current_beat);
+
+        // Collect loops that need event expansion; nothing is scheduled past a
+        // pending locator jump, the song continues at the locator from there
+        let mut loops = self.collect_active_loops();
+        if let Some(at_beat) = self.shared.with_state_read(|s| s.locators.pending.as_ref().map(|j| j.at_beat)) {
+            for lp in &mut loops {
+                lp.stop_beat = Some(lp.stop_beat.map_or(at_beat, |stop| stop.min(at_beat)));
+            }
+        }
 
         // Log active patterns for debugging (only every ~100 ticks to reduce spam)
         static TICK_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
@@ -2460,33 +4237,105 @@ impl RuntimeThread {
                 .collect()
         });
 
+        // Look further ahead for voices whose events are sent early (pre-roll,
+        // rushing feel) and humanized loops, which can rush as well
+        let max_pre_roll_ms = self.shared.with_state_read(|state| {
+            let voices = state
+                .voices
+                .values()
+                .map(|v| v.pre_roll_ms + state.voice_feel(&v.name).map_or(0.0, |feel| feel.max_early_ms()))
+                .fold(0.0, f64::max);
+            let humanized = state
+                .patterns
+                .values()
+                .filter_map(|p| p.humanize)
+                .chain(state.melodies.values().filter_map(|m| m.humanize))
+                .map(|h| h.timing_ms)
+                .fold(0.0, f64::max);
+            voices + humanized
+        });
+
         // Collect due events from the scheduler
         let due_events = self.scheduler.collect_due_events(
             &self.transport,
             now,
             &loops,
             &scheduled_events,
-            LOOKAHEAD_MS,
+            LOOKAHEAD_MS + max_pre_roll_ms.ceil() as u64,
         );
 
         // Log all due events for debugging
         for (beat_time, events) in &due_events {
             for event in events {
                 if event.fade.is_none() {
-                    log::debug!("[SCHEDULER] Due event at beat {:.3}: pattern={:?} synth={}",
+                    log::debug!("[SCHEDULER] Due event at beat {:.3}: pattern={:?} synth={} {}",
                         beat_time.to_float(),
                         event.pattern_name,
-                        event.synth_def);
+                        event.synth_def,
+                        pitch::describe_controls(&event.controls).unwrap_or_default());
                 }
             }
         }
 
+        let postpone_fades = self.shared.with_state_read(|s| {
+            s.performance.budget.over_budget && s.performance.policy.postpone_fades
+        });
+
+        // If the tick loop fell behind, overdue events fire late; stale
+        // non-anchor events are dropped instead when the policy allows it
+        let (overload_policy, beats_per_bar, tempo) = self.shared.with_state_read(|s| {
+            (s.performance.policy.clone(), s.time_signature.beats_per_bar(), s.tempo)
+        });
+
         // Fire due events using timed OSC bundles for precise scheduling
         for (beat_time, events) in due_events {
+            let beat = beat_time.to_float();
+            let events = self.select_variations(beat, events);
+            let late_ms = (current_beat - beat) * 60_000.0 / tempo.max(1.0);
+            let events = if late_ms > 0.0 {
+                let before = events.len();
+                let stale = crate::performance::is_stale(late_ms, &overload_policy);
+                let kept: Vec<BeatEvent> = events
+                    .into_iter()
+                    .filter(|event| {
+                        !stale
+                            || !crate::performance::drop_stale_event(
+                                event,
+                                beat,
+                                late_ms,
+                                beats_per_bar,
+                                self.loop_peak_amp(event),
+                                &overload_policy,
+                            )
+                    })
+                    .collect();
+                let dropped = before - kept.len();
+                if dropped > 0 {
+                    log::debug!("[OVERLOAD] Dropped {} stale events at beat {:.2} ({:.0} ms late)", dropped, beat, late_ms);
+                }
+                self.event_stats.late += kept.len() as u64;
+                self.event_stats.stale_dropped += dropped as u64;
+                kept
+            } else {
+                events
+            };
+            if self.transport.is_virtual() {
+                self.simulated_events.extend(events.iter().map(|event| (beat_time, event.clone())));
+            }
+
             // Separate fades from synth events
             let mut synth_events = Vec::new();
             for event in events {
                 if let Some(fade) = event.fade {
+                    if postpone_fades {
+                        log::info!("[CPU] Postponing fade '{}' until the server load recovers", fade.name);
+                        self.postponed_fades.push(fade);
+                        let count = self.postponed_fades.len();
+                        self.shared.with_state_write(|state| {
+                            state.performance.postponed_fades = count;
+                        });
+                        continue;
+                    }
                     // Handle fades immediately (they update internal state)
                     log::info!("[FADE] Starting fade '{}' on {}:{} from {} to {} over {} beats",
                         fade.name, fade.target_name, fade.param_name,
@@ -2504,6 +4353,7 @@ impl RuntimeThread {
         }
 
         // Process scheduled note-offs
+        self.process_mono_releases(current_beat, now);
         self.process_scheduled_note_offs(current_beat);
 
         // Update active fades
@@ -2526,6 +4376,15 @@ impl RuntimeThread {
 
         // First pass: update iteration tracking, detect play_once completion, and clear triggered_clips on new iterations
         self.shared.with_state_write(|state| {
+            // Sequences a legato launch took over from end at its launch beat
+            let before = state.active_sequences.len();
+            state
+                .active_sequences
+                .retain(|_, active| !matches!(active.stop_beat, Some(stop) if stop <= current_beat));
+            if state.active_sequences.len() != before {
+                state.bump_version();
+            }
+
             for (seq_name, active) in state.active_sequences.iter_mut() {
                 if active.paused || active.completed {
                     continue;
@@ -2533,7 +4392,7 @@ impl RuntimeThread {
                 if let Some(seq_def) = state.sequences.get(seq_name) {
                     if seq_def.loop_beats > EPSILON {
                         let elapsed = (current_beat - active.anchor_beat).max(0.0);
-                        let current_iteration = (elapsed / seq_def.loop_beats).floor() as u64;
+                        let current_iteration = (elapsed / seq_def.playback_beats()).floor() as u64;
 
                         if current_iteration > active.last_iteration {
                             log::debug!(
@@ -2555,7 +4414,7 @@ impl RuntimeThread {
                         // Early completion detection for play_once: if lookahead would reach iteration 1,
                         // mark as completed early to prevent scheduling events in the next iteration
                         if seq_def.play_once && !active.completed {
-                            let end_beat = active.anchor_beat + seq_def.loop_beats;
+                            let end_beat = active.anchor_beat + seq_def.playback_beats();
                             if lookahead_beat >= end_beat {
                                 log::info!("[SEQUENCE] '{}' completing early (lookahead reached end)", seq_name);
                                 active.completed = true;
@@ -2577,6 +4436,8 @@ impl RuntimeThread {
                             name: name.clone(),
                             pattern: lp.clone(),
                             start_beat,
+                            launch_beat: start_beat,
+                            stop_beat: None,
                             voice_name: pattern.voice_name.clone(),
                             group_path: Some(pattern.group_path.clone()),
                         });
@@ -2587,12 +4448,14 @@ impl RuntimeThread {
             // Collect melodies that are directly playing (via melody.start())
             for (name, melody) in &state.melodies {
                 if let LoopStatus::Playing { start_beat } = melody.status {
-                    if let Some(ref lp) = melody.loop_pattern {
+                    if let Some(lp) = melody.played_pattern() {
                         loops.push(LoopSnapshot {
                             kind: LoopKind::Melody,
                             name: name.clone(),
-                            pattern: lp.clone(),
+                            pattern: lp.into_owned(),
                             start_beat,
+                            launch_beat: start_beat,
+                            stop_beat: None,
                             voice_name: melody.voice_name.clone(),
                             group_path: Some(melody.group_path.clone()),
                         });
@@ -2629,6 +4492,8 @@ impl RuntimeThread {
                             name: seq_name.clone(),
                             pattern,
                             start_beat: active.anchor_beat,
+                            launch_beat: active.launch_beat,
+                            stop_beat: active.stop_beat,
                             voice_name: None,
                             group_path: None,
                         });
@@ -2688,9 +4553,10 @@ impl RuntimeThread {
 
         let mut events: Vec<BeatEvent> = Vec::new();
 
-        for clip in &def.clips {
+        for (clip_index, clip) in def.clips.iter().enumerate() {
             let clip_start = clip.start.max(0.0);
             let clip_end = clip.end.min(def.loop_beats);
+            let first_event = events.len();
             if clip_end - clip_start <= EPSILON {
                 continue;
             }
@@ -2720,6 +4586,7 @@ impl RuntimeThread {
             match &clip.source {
                 ClipSource::Pattern(name) => {
                     if let Some(pat) = state.patterns.get(name).and_then(|p| p.loop_pattern.as_ref()) {
+                        let pat = Self::at_clip_speed(pat, clip.speed);
                         Self::append_looping_events(
                             &mut events,
                             &pat.events,
@@ -2735,6 +4602,7 @@ impl RuntimeThread {
                                 state.patterns.get(name).and_then(|p| p.voice_name.clone()),
                             ),
                         );
+                        Self::stamp_clip(&mut events[first_event..], def, clip_index);
                         // Mark as triggered if clip_once
                         if matches!(clip.mode, crate::sequences::ClipMode::Once) {
                             newly_triggered.push(format!("pattern:{}", name));
@@ -2744,7 +4612,8 @@ impl RuntimeThread {
                     }
                 }
                 ClipSource::Melody(name) => {
-                    if let Some(mel) = state.melodies.get(name).and_then(|m| m.loop_pattern.as_ref()) {
+                    if let Some(mel) = state.melodies.get(name).and_then(|m| m.played_pattern()) {
+                        let mel = Self::at_clip_speed(&mel, clip.speed);
                         let melody_group_path = state.melodies.get(name).map(|m| m.group_path.clone());
                         let voice_name = state.melodies.get(name).and_then(|m| m.voice_name.clone());
                         log::trace!("[SEQUENCE] Melody '{}' group_path={:?} voice={:?} events={}",
@@ -2764,6 +4633,7 @@ impl RuntimeThread {
                                 voice_name,
                             ),
                         );
+                        Self::stamp_clip(&mut events[first_event..], def, clip_index);
                         // Mark as triggered if clip_once
                         if matches!(clip.mode, crate::sequences::ClipMode::Once) {
                             newly_triggered.push(format!("melody:{}", name));
@@ -2778,6 +4648,7 @@ impl RuntimeThread {
                         if let Some(nested_pat) = Self::materialize_sequence(
                             nested_def, state, stack, triggered_set, newly_triggered
                         ) {
+                            let nested_pat = Self::at_clip_speed(&nested_pat, clip.speed);
                             let fade_count = nested_pat.events.iter().filter(|e| e.fade.is_some()).count();
                             log::trace!("[SEQUENCE] Nested sequence '{}' has {} events ({} fades)",
                                 name, nested_pat.events.len(), fade_count);
@@ -2809,9 +4680,11 @@ impl RuntimeThread {
                     log::trace!("[SEQUENCE] Processing fade clip '{}' in sequence '{}'", name, def.name);
                     if let Some(fade_def) = state.fade_defs.get(name) {
                         log::trace!("[SEQUENCE] Found fade def '{}', adding events at {}-{}", name, clip_start, clip_end);
+                        let mut fade_def = fade_def.clone();
+                        fade_def.duration_beats /= clip.speed;
                         Self::append_fade_events(
                             &mut events,
-                            fade_def,
+                            &fade_def,
                             clip_start,
                             clip_end,
                             &clip.mode,
@@ -2826,6 +4699,17 @@ impl RuntimeThread {
 
         stack.pop();
 
+        // Transposition and key changes move melodic events, including the
+        // melodies of nested sequences
+        if def.transpose != 0 || !def.key_changes.is_empty() {
+            for event in events.iter_mut().filter(|e| e.melody_name.is_some()) {
+                let semitones = def.transposition_at(event.beat);
+                if semitones != 0 {
+                    event.transpose(semitones);
+                }
+            }
+        }
+
         // Sort events by beat, but ensure fades come BEFORE notes at the same beat.
         // This is critical: fades must set voice.params before synths are created,
         // so that synths pick up the correct initial amp value.
@@ -2848,12 +4732,38 @@ impl RuntimeThread {
         log::trace!("[SEQUENCE] Returning materialized '{}' with {} total events ({} fades)",
             def.name, events.len(), fade_count);
 
-        Some(crate::events::Pattern {
+        let pattern = crate::events::Pattern {
             name: def.name.clone(),
             events,
             loop_length_beats: def.loop_beats,
             phase_offset: 0.0,
-        })
+        };
+        if def.speed != 1.0 {
+            Some(pattern.time_scaled(def.speed))
+        } else {
+            Some(pattern)
+        }
+    }
+
+    /// Mark events as played by clip `index` of `def`. The implicit
+    /// sequences of `start()` are no clip anyone arranged, so they leave
+    /// events unmarked.
+    fn stamp_clip(events: &mut [BeatEvent], def: &crate::sequences::SequenceDefinition, index: usize) {
+        if def.is_implicit() {
+            return;
+        }
+        for event in events {
+            event.clip = Some(crate::events::EventClip { sequence: def.name.clone(), index });
+        }
+    }
+
+    /// Source pattern of a clip, time-scaled when the clip plays it at another speed.
+    fn at_clip_speed(pattern: &crate::events::Pattern, speed: f64) -> std::borrow::Cow<'_, crate::events::Pattern> {
+        if speed != 1.0 {
+            std::borrow::Cow::Owned(pattern.time_scaled(speed))
+        } else {
+            std::borrow::Cow::Borrowed(pattern)
+        }
     }
 
     /// Append events from a source pattern to the destination, looping/repeating as needed.
@@ -2970,6 +4880,7 @@ impl RuntimeThread {
                 pattern_name: None,
                 melody_name: None,
                 voice_name: None,
+                synth_override: None,
                 fade: Some(FadeClip {
                     name: fade.name.clone(),
                     sequence_name: Some(sequence_name.to_string()),
@@ -2979,7 +4890,11 @@ impl RuntimeThread {
                     start_value: fade.from,
                     target_value: fade.to,
                     duration_beats: fade.duration_beats,
+                    curve: fade.curve,
                 }),
+                variation: None,
+                step: None,
+                clip: None,
             });
 
             iteration += 1;
@@ -2989,6 +4904,46 @@ impl RuntimeThread {
         }
     }
 
+    /// Loudest `amp` of the pattern or melody an event belongs to.
+    fn loop_peak_amp(&self, event: &BeatEvent) -> Option<f32> {
+        self.shared.with_state_read(|state| {
+            let looped = match (&event.pattern_name, &event.melody_name) {
+                (Some(name), _) => state.patterns.get(name)?.loop_pattern.as_ref(),
+                (None, Some(name)) => state.melodies.get(name)?.loop_pattern.as_ref(),
+                _ => None,
+            }?;
+            crate::performance::peak_amp(&looped.events)
+        })
+    }
+
+    /// Keep only the events of the take each pattern with variations plays
+    /// on the pass they belong to.
+    fn select_variations(&self, beat: f64, events: Vec<BeatEvent>) -> Vec<BeatEvent> {
+        if events.iter().all(|event| event.variation.is_none()) {
+            return events;
+        }
+        self.shared.with_state_read(|state| {
+            events
+                .into_iter()
+                .filter(|event| {
+                    let (Some(variation), Some(name)) = (&event.variation, &event.pattern_name) else {
+                        return true;
+                    };
+                    let Some(pattern) = state.patterns.get(name) else {
+                        return true;
+                    };
+                    let picked = match (pattern.locked_variation, &pattern.variations) {
+                        (Some(index), Some(variations)) => index.min(variations.weights.len().saturating_sub(1)),
+                        (Some(index), None) => index,
+                        (None, Some(variations)) => variations.pick(name, variation.pass_start(beat)),
+                        (None, None) => 0,
+                    };
+                    variation.index == picked
+                })
+                .collect()
+        })
+    }
+
     /// Fire multiple events at a specific beat using a timed OSC bundle.
     /// This ensures sample-accurate timing by scheduling with scsynth's timestamp mechanism.
     fn fire_events_bundled(&mut self, beat_time: BeatTime, events: Vec<BeatEvent>, now: Instant) {
@@ -3003,14 +4958,215 @@ impl RuntimeThread {
             return;
         }
 
+        // Drop events of frozen groups - their bounce is playing instead
+        let events: Vec<BeatEvent> = self.shared.with_state_read(|state| {
+            events
+                .into_iter()
+                .filter(|event| {
+                    let frozen_from = event
+                        .group_path
+                        .as_deref()
+                        .and_then(|path| state.frozen_from_beat(path));
+                    !frozen_from.is_some_and(|beat| beat_time.to_float() >= beat)
+                })
+                .collect()
+        });
+        if events.is_empty() {
+            return;
+        }
+
+        // Drop events whose pattern or melody conditions do not hold right now
+        let events: Vec<BeatEvent> = self.shared.with_state_read(|state| {
+            let now = Instant::now();
+            events
+                .into_iter()
+                .filter(|event| {
+                    let conditions = match (&event.pattern_name, &event.melody_name) {
+                        (Some(name), _) => state.patterns.get(name).map(|p| &p.conditions),
+                        (None, Some(name)) => state.melodies.get(name).map(|m| &m.conditions),
+                        _ => None,
+                    };
+                    conditions.is_none_or(|c| c.iter().all(|c| c.holds(&state.meter_levels, now)))
+                })
+                .collect()
+        });
+        if events.is_empty() {
+            return;
+        }
+
+        // Drop events of low-priority voices while over the CPU budget
+        let before = events.len();
+        let events: Vec<BeatEvent> = self.shared.with_state_read(|state| {
+            let Some(cutoff) = state.drop_priority_cutoff() else {
+                return events;
+            };
+            events
+                .into_iter()
+                .filter(|event| {
+                    event
+                        .voice_name
+                        .as_ref()
+                        .and_then(|name| state.voices.get(name))
+                        .is_none_or(|voice| voice.priority >= cutoff)
+                })
+                .collect()
+        });
+        self.event_stats.dropped += (before - events.len()) as u64;
+        if events.is_empty() {
+            return;
+        }
+
         // Get the Instant when synths will be live (OscSender computes the OSC timestamp internally)
         let (live_instant, _) = self.transport.beat_to_timestamp_and_instant(beat_time, now);
 
+        // Pre-roll in beats for each voice that sends its events early; a
+        // timing feel moves them further (rushing) or later (dragging), and
+        // so does humanizing the pattern or melody
+        let (tempo, pre_rolls, humanized_patterns, humanized_melodies) = self.shared.with_state_read(|state| {
+            let pre_rolls: HashMap<String, (f64, Option<TimingFeel>)> = state
+                .voices
+                .values()
+                .map(|v| (v.name.clone(), (v.pre_roll_ms, state.voice_feel(&v.name))))
+                .filter(|(_, (pre_roll_ms, feel))| *pre_roll_ms > 0.0 || feel.is_some())
+                .collect();
+            let patterns: HashMap<String, Humanize> =
+                state.patterns.values().filter_map(|p| Some((p.name.clone(), p.humanize?))).collect();
+            let melodies: HashMap<String, Humanize> =
+                state.melodies.values().filter_map(|m| Some((m.name.clone(), m.humanize?))).collect();
+            (state.tempo, pre_rolls, patterns, melodies)
+        });
+        let humanize_of = |event: &BeatEvent| match (&event.pattern_name, &event.melody_name) {
+            (Some(name), _) => humanized_patterns.get(name).map(|h| (name.clone(), *h)),
+            (None, Some(name)) => humanized_melodies.get(name).map(|h| (name.clone(), *h)),
+            _ => None,
+        };
+        let pre_roll_beats = |event: &BeatEvent| {
+            let beat = beat_time.to_float();
+            let voice_ms = event
+                .voice_name
+                .as_ref()
+                .and_then(|name| {
+                    let (pre_roll_ms, feel) = pre_rolls.get(name)?;
+                    Some(pre_roll_ms - feel.map_or(0.0, |feel| feel.offset_at(name, beat)))
+                })
+                .unwrap_or(0.0);
+            let humanize_ms = humanize_of(event).map_or(0.0, |(name, h)| h.offset_ms(&name, beat));
+            (voice_ms - humanize_ms) / 1000.0 * tempo / 60.0
+        };
+
+        // MIDI hardware sounds with the audio when its triggers are moved by
+        // the audio output latency less the device's own latency
+        let midi_offsets: HashMap<u32, f64> = self.shared.with_state_read(|state| {
+            let latency = self.transport.latency();
+            state
+                .midi_output_config
+                .devices
+                .keys()
+                .map(|id| {
+                    let device_latency = state.midi_config.routing.device_latency(*id);
+                    (*id, latency.device_offset_ms(device_latency, LOOKAHEAD_MS as f64))
+                })
+                .collect()
+        });
+
         // Build OSC packets for each event
         let mut packets: Vec<OscPacket> = Vec::new();
-        let mut note_offs_to_schedule: Vec<(String, u8, i32, f32)> = Vec::new(); // (voice_name, note, node_id, duration)
+        let mut pre_rolled: Vec<(f64, Vec<OscPacket>)> = Vec::new(); // (pre-roll beats, packets)
+        let mut midi_packets: HashMap<u32, Vec<OscPacket>> = HashMap::new(); // device_id -> MIDI triggers
+        let mut note_offs_to_schedule: Vec<(String, u8, i32, f32, f64)> = Vec::new(); // (voice_name, note, node_id, duration, pre-roll beats)
+
+        for mut event in events {
+            // Humanized loops play each pass at other velocities
+            if let Some((name, humanize)) = humanize_of(&event) {
+                let factor = humanize.amp_factor(&name, beat_time.to_float()) as f32;
+                if let Some((_, amp)) = event.controls.iter_mut().find(|(k, _)| k == "amp") {
+                    *amp = (*amp * factor).max(0.0);
+                }
+            }
+
+            // Patterns sequencing external gear send MIDI without going through a voice
+            let pattern_midi_target = event.pattern_name.as_ref().and_then(|pattern_name| {
+                self.shared.with_state_read(|state| {
+                    state
+                        .patterns
+                        .get(pattern_name)
+                        .and_then(|p| p.midi_target.clone())
+                        .filter(|t| state.midi_output_config.devices.contains_key(&t.device_id))
+                })
+            });
+            if let Some(target) = pattern_midi_target {
+                let velocity = event.controls.iter()
+                    .find(|(k, _)| k == "amp")
+                    .map(|(_, v)| (*v * 127.0).clamp(0.0, 127.0) as u8)
+                    .unwrap_or(100);
+                let packed_note_on = (target.device_id << 21)
+                    | ((target.channel as u32) << 14)
+                    | ((target.note as u32) << 7)
+                    | (velocity as u32);
+                let packed_note_off = (target.device_id << 14)
+                    | ((target.channel as u32) << 7)
+                    | (target.note as u32);
+                let (note_on_node_id, note_off_node_id) = self.shared.with_state_write(|state| {
+                    (state.allocate_synth_node(), state.allocate_synth_node())
+                });
+                let offset_ms = midi_offsets.get(&target.device_id).copied().unwrap_or(0.0);
+                midi_packets
+                    .entry(target.device_id)
+                    .or_default()
+                    .push(midi_trigger_packet("vibelang_midi_note_on", note_on_node_id, packed_note_on));
+
+                // Same re-trigger margin as voice notes: the off must land before a repeated on
+                let note_off_beat = BeatTime::from_float(
+                    (beat_time.to_float() + target.note_length - 0.01).max(beat_time.to_float()),
+                );
+                log::debug!(
+                    "[SC-MIDI] Pattern '{}' ch={} note={} vel={} on_beat={:.2} off_beat={:.2}",
+                    event.pattern_name.as_deref().unwrap_or_default(), target.channel + 1, target.note, velocity,
+                    beat_time.to_float(), note_off_beat.to_float()
+                );
+                if let Err(e) = self.osc_sender.send_bundle_at_beat_with_offset(
+                    note_off_beat,
+                    vec![midi_trigger_packet("vibelang_midi_note_off", note_off_node_id, packed_note_off)],
+                    &self.transport,
+                    now,
+                    offset_ms,
+                ) {
+                    log::error!("[SC-MIDI] Failed to send note-off bundle: {}", e);
+                }
+                continue;
+            }
+
+            // Voices playing a VST instrument send it MIDI notes in the same bundles
+            let vst_target = event.voice_name.as_deref().and_then(|name| self.vst_voice_target(name));
+            if let Some((node_id, channel)) = vst_target {
+                let freq = event.controls.iter()
+                    .find(|(k, _)| k == "freq")
+                    .map(|(_, v)| *v as f64)
+                    .unwrap_or(440.0);
+                let note = pitch::freq_to_midi_note(freq);
+                let velocity = event.controls.iter()
+                    .find(|(k, _)| k == "amp")
+                    .map(|(_, v)| (*v * 127.0).clamp(0.0, 127.0) as u8)
+                    .unwrap_or(100);
+                let duration = event.controls.iter()
+                    .find(|(k, _)| k == "gate")
+                    .map(|(_, v)| *v as f64)
+                    .unwrap_or(0.25);
+                packets.push(crate::vst::note_on_packet(node_id, channel, note, velocity));
+
+                // Same re-trigger margin as MIDI notes: the off must land before a repeated on
+                let note_off_beat = BeatTime::from_float((beat_time.to_float() + duration - 0.01).max(beat_time.to_float()));
+                if let Err(e) = self.osc_sender.send_bundle_at_beat(
+                    note_off_beat,
+                    vec![crate::vst::note_off_packet(node_id, channel, note)],
+                    &self.transport,
+                    now,
+                ) {
+                    log::error!("[VST] Failed to send note-off bundle: {}", e);
+                }
+                continue;
+            }
 
-        for event in events {
             // Check if this event's voice is routed to MIDI output
             let midi_output_info = event.voice_name.as_ref().and_then(|voice_name| {
                 self.shared.with_state_read(|state| {
@@ -3035,7 +5191,7 @@ impl RuntimeThread {
                     .find(|(k, _)| k == "freq")
                     .map(|(_, v)| *v as f64)
                     .unwrap_or(440.0);
-                let note = (69.0 + 12.0 * (freq / 440.0).log2()).round() as u8;
+                let note = pitch::freq_to_midi_note(freq);
                 let velocity = event.controls.iter()
                     .find(|(k, _)| k == "amp")
                     .map(|(_, v)| (*v * 127.0).clamp(0.0, 127.0) as u8)
@@ -3048,6 +5204,7 @@ impl RuntimeThread {
                     .unwrap_or(0.25);
 
                 let off_beat = BeatTime::from_float(beat_time.to_float() + duration as f64);
+                let length = Some(self.beats_to_duration(duration as f64));
 
                 log::info!(
                     "[SC-MIDI] Creating MIDI trigger synths: voice='{}' ch={} note={} vel={} on_beat={:.2} off_beat={:.2}",
@@ -3084,19 +5241,9 @@ impl RuntimeThread {
                         | (steal_note as u32);
 
                     let steal_node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
-                    let steal_packet = rosc::OscPacket::Message(rosc::OscMessage {
-                        addr: "/s_new".to_string(),
-                        args: vec![
-                            rosc::OscType::String("vibelang_midi_note_off".to_string()),
-                            rosc::OscType::Int(steal_node_id),
-                            rosc::OscType::Int(0), // addToHead
-                            rosc::OscType::Int(0), // default group
-                            rosc::OscType::String("packed_data".to_string()),
-                            rosc::OscType::Float(packed_steal as f32),
-                        ],
-                    });
+                    let steal_packet = midi_trigger_packet("vibelang_midi_note_off", steal_node_id, packed_steal);
                     log::debug!("[SC-MIDI-STEAL] Adding stolen note_off packet: note={} packed={}", steal_note, packed_steal);
-                    packets.push(steal_packet);
+                    midi_packets.entry(device_id).or_default().push(steal_packet);
 
                     // Remove from active notes (specifically look for -2 marker for SC-managed MIDI)
                     // AND cancel any orphaned scheduled note-off for this note
@@ -3142,27 +5289,18 @@ impl RuntimeThread {
                     | (velocity as u32);
 
                 let note_on_node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
-                let note_on_packet = rosc::OscPacket::Message(rosc::OscMessage {
-                    addr: "/s_new".to_string(),
-                    args: vec![
-                        rosc::OscType::String("vibelang_midi_note_on".to_string()),
-                        rosc::OscType::Int(note_on_node_id),
-                        rosc::OscType::Int(0), // addToHead
-                        rosc::OscType::Int(0), // default group
-                        rosc::OscType::String("packed_data".to_string()),
-                        rosc::OscType::Float(packed_note_on as f32),
-                    ],
-                });
+                let note_on_packet = midi_trigger_packet("vibelang_midi_note_on", note_on_node_id, packed_note_on);
                 log::debug!("[SC-MIDI] Adding note_on packet to bundle: node_id={} packed={} (device={} ch={} note={} vel={})",
-                    note_on_node_id, packed_note_on, device_id, channel, note, velocity);
-                packets.push(note_on_packet);
+                    note_on_node_id, packed_note_on, device_id, channel, pitch::note_name(note), velocity);
+                midi_packets.entry(device_id).or_default().push(note_on_packet);
 
                 // Track active note for voice stealing
                 // Use -2 as marker for SC-managed MIDI notes (the synth handles note-off via OSC)
                 // This is different from -1 which is used by the direct MIDI path (handle_note_on)
                 self.shared.with_state_write(|state| {
                     if let Some(voice) = state.voices.get_mut(&voice_name) {
-                        voice.active_notes.entry(note).or_default().push(-2); // -2 marker for SC-managed MIDI
+                        // -2 marker for SC-managed MIDI
+                        voice.track_note(note, -2, NoteOrigin::new(live_instant, NoteSource::of_event(&event), length));
                     }
                 });
 
@@ -3173,17 +5311,7 @@ impl RuntimeThread {
                     | (note as u32);
 
                 let note_off_node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
-                let note_off_packet = rosc::OscPacket::Message(rosc::OscMessage {
-                    addr: "/s_new".to_string(),
-                    args: vec![
-                        rosc::OscType::String("vibelang_midi_note_off".to_string()),
-                        rosc::OscType::Int(note_off_node_id),
-                        rosc::OscType::Int(0), // addToHead
-                        rosc::OscType::Int(0), // default group
-                        rosc::OscType::String("packed_data".to_string()),
-                        rosc::OscType::Float(packed_note_off as f32),
-                    ],
-                });
+                let note_off_packet = midi_trigger_packet("vibelang_midi_note_off", note_off_node_id, packed_note_off);
 
                 // Send note-off bundle slightly before off_beat to ensure proper re-triggering.
                 // When the same note is repeated (e.g., "1 1" in a melody), the note-off for
@@ -3193,11 +5321,12 @@ impl RuntimeThread {
                 // This margin accounts for network jitter in the OSC round-trip.
                 let note_off_beat = BeatTime::from_float((off_beat.to_float() - 0.01).max(0.0));
                 log::debug!("[SC-MIDI] Sending note-off bundle at beat {:?} (off_beat={:?})", note_off_beat, off_beat);
-                if let Err(e) = self.osc_sender.send_bundle_at_beat(
+                if let Err(e) = self.osc_sender.send_bundle_at_beat_with_offset(
                     note_off_beat,
                     vec![note_off_packet],
                     &self.transport,
-                    now
+                    now,
+                    midi_offsets.get(&device_id).copied().unwrap_or(0.0),
                 ) {
                     log::error!("[SC-MIDI] Failed to send note-off bundle: {}", e);
                 }
@@ -3218,6 +5347,7 @@ impl RuntimeThread {
                             voice_name: voice_name_clone,
                             note: note_to_schedule,
                             node_id: Some(-2), // -2 marker for SC-managed MIDI (don't send MIDI in handle_note_off)
+                            gate_scheduled: false,
                         });
                     });
                 }
@@ -3225,14 +5355,55 @@ impl RuntimeThread {
                 continue; // Skip regular synth packet building for this event
             }
 
-            if let Some((packet, note_off_info)) = self.build_synth_packet(&event, live_instant) {
-                packets.push(packet);
+            // Mono voices glide their running synth instead of starting another
+            let mono = self.mono_note_on(&event, beat_time.to_float(), now);
+            if matches!(mono, Some(MonoNoteOn::Glide { .. } | MonoNoteOn::Hold)) {
+                continue;
+            }
+
+            if let Some((packet, note_off_info)) = self.build_synth_packet(&event, beat_time, live_instant) {
+                let pre_roll = pre_roll_beats(&event);
+                if pre_roll != 0.0 {
+                    match pre_rolled.iter_mut().find(|(beats, _)| (*beats - pre_roll).abs() < 1e-9) {
+                        Some((_, group)) => group.push(packet),
+                        None => pre_rolled.push((pre_roll, vec![packet])),
+                    }
+                } else {
+                    packets.push(packet);
+                }
                 if let Some((voice_name, note, node_id, duration)) = note_off_info {
-                    note_offs_to_schedule.push((voice_name, note, node_id, duration));
+                    if mono.is_some() {
+                        // Released once the voice holds no note anymore
+                        self.shared.with_state_write(|state| {
+                            if let Some(voice) = state.voices.get_mut(&voice_name) {
+                                voice.mono_state.started(node_id, note);
+                            }
+                        });
+                    } else {
+                        note_offs_to_schedule.push((voice_name, note, node_id, duration, pre_roll));
+                    }
                 }
             }
         }
 
+        // Voices with a pre-roll or timing feel get their own earlier or later bundles
+        for (pre_roll, group) in pre_rolled {
+            let early_beat = BeatTime::from_float((beat_time.to_float() - pre_roll).max(0.0));
+            if let Err(e) = self.osc_sender.send_bundle_at_beat(early_beat, group, &self.transport, now) {
+                log::error!("[BUNDLE] Failed to send pre-rolled bundle: {}", e);
+            }
+        }
+
+        // MIDI triggers of devices with a latency offset get their own bundles
+        for (device_id, group) in midi_packets {
+            let offset_ms = midi_offsets.get(&device_id).copied().unwrap_or(0.0);
+            if offset_ms == 0.0 {
+                packets.extend(group);
+            } else if let Err(e) = self.osc_sender.send_bundle_at_beat_with_offset(beat_time, group, &self.transport, now, offset_ms) {
+                log::error!("[BUNDLE] Failed to send MIDI bundle for device {}: {}", device_id, e);
+            }
+        }
+
         // Send bundle with timetag (via OscSender for centralized handling)
         if !packets.is_empty() {
             log::debug!("[BUNDLE] About to send bundle with {} packets at beat {:?}", packets.len(), beat_time);
@@ -3255,31 +5426,215 @@ impl RuntimeThread {
 
         // Schedule note-offs based on the scheduled beat time (not current time)
         let beat_float = beat_time.to_float();
-        for (voice_name, note, node_id, duration) in note_offs_to_schedule {
-            let off_beat = beat_float + duration as f64;
+        for (voice_name, note, node_id, duration, pre_roll) in note_offs_to_schedule {
+            // Pre-rolled notes keep their length
+            let off_beat = beat_float + duration as f64 - pre_roll;
             log::debug!("[NOTE_OFF] Scheduling note-off for '{}' note {} node {} at beat {} (event_beat={}, duration={})",
-                voice_name, note, node_id, off_beat, beat_float, duration);
+                voice_name, pitch::note_name(note), node_id, off_beat, beat_float, duration);
+            let gate_scheduled = self.schedule_gate_off(node_id, off_beat, now);
             self.shared.with_state_write(|state| {
                 state.scheduled_note_offs.push(ScheduledNoteOff {
                     beat: off_beat,
                     voice_name,
                     note,
                     node_id: Some(node_id),
+                    gate_scheduled,
                 });
             });
         }
     }
 
-    /// Build an OSC packet for a synth event.
-    /// Returns the packet and optional note-off scheduling info (voice_name, note, node_id, duration).
-    /// `live_instant` is when the synth will be live on scsynth (used for pending node tracking).
-    fn build_synth_packet(&mut self, event: &BeatEvent, live_instant: Instant) -> Option<(OscPacket, Option<(String, u8, i32, f32)>)> {
+    /// Press an event's note on its voice if the voice is in mono mode.
+    ///
+    /// Returns `None` for events that play as usual (other voices, SFZ voices
+    /// and gateless triggers), otherwise what the voice's synth does. Glides
+    /// and the releases of notes that ended before `beat` are sent here.
+    fn mono_note_on(&mut self, event: &BeatEvent, beat: f64, now: Instant) -> Option<MonoNoteOn> {
+        let voice_name = event.voice_name.as_ref()?;
+        let (_, duration) = event.controls.iter().find(|(k, _)| k == "gate")?;
+        let freq = event.controls.iter().find(|(k, _)| k == "freq").map_or(440.0, |(_, v)| *v as f64);
+        let note = pitch::freq_to_midi_note(freq);
+        let off_beat = beat + *duration as f64;
+        let (mode, changes, outcome) = self.shared.with_state_write(|state| {
+            let voice = state.voices.get_mut(voice_name)?;
+            let mode = voice.mono.filter(|_| voice.sfz_instrument.is_none())?;
+            let changes = voice.mono_state.release_until(beat, mode.priority);
+            let outcome = voice.mono_state.note_on(note, off_beat, mode.priority);
+            Some((mode, changes, outcome))
+        })?;
+        self.apply_mono_changes(voice_name, &mode, changes, now);
+        if let MonoNoteOn::Glide { node_id, note } = outcome {
+            self.send_mono_glide(voice_name, &mode, node_id, note, beat, now);
+        }
+        Some(outcome)
+    }
+
+    /// End the notes of mono voices released before `current_beat`.
+    fn process_mono_releases(&mut self, current_beat: f64, now: Instant) {
+        let changes: Vec<(String, MonoMode, Vec<MonoChange>)> = self.shared.with_state_write(|state| {
+            state
+                .voices
+                .values_mut()
+                .filter(|voice| voice.mono_state.is_active())
+                .map(|voice| {
+                    let mode = voice.mono.unwrap_or_default();
+                    (voice.name.clone(), mode, voice.mono_state.release_until(current_beat, mode.priority))
+                })
+                .filter(|(_, _, changes)| !changes.is_empty())
+                .collect()
+        });
+        for (voice_name, mode, changes) in changes {
+            self.apply_mono_changes(&voice_name, &mode, changes, now);
+        }
+    }
+
+    /// Send the glides and releases of a mono voice's synth.
+    fn apply_mono_changes(&mut self, voice_name: &str, mode: &MonoMode, changes: Vec<MonoChange>, now: Instant) {
+        for change in changes {
+            match change {
+                MonoChange::Glide { beat, node_id, note } => {
+                    self.send_mono_glide(voice_name, mode, node_id, note, beat, now);
+                }
+                MonoChange::Release { beat, node_id, note } => {
+                    let gate_scheduled = self.schedule_gate_off(node_id, beat, now);
+                    self.shared.with_state_write(|state| {
+                        state.scheduled_note_offs.push(ScheduledNoteOff {
+                            beat,
+                            voice_name: voice_name.to_string(),
+                            note,
+                            node_id: Some(node_id),
+                            gate_scheduled,
+                        });
+                    });
+                }
+            }
+        }
+    }
+
+    /// Glide a mono voice's synth to `note` at `beat`.
+    ///
+    /// Synthdefs with a `freq_lag` control glide over the voice's glide time;
+    /// others jump to the new pitch.
+    fn send_mono_glide(&mut self, voice_name: &str, mode: &MonoMode, node_id: i32, note: u8, beat: f64, now: Instant) {
+        let lag_control = vibelang_dsp::ramp_lag_control("freq");
+        let has_lag = self.shared.with_state_read(|state| {
+            state
+                .voices
+                .get(voice_name)
+                .and_then(|voice| voice.synth_name.as_deref())
+                .is_some_and(|synth| vibelang_dsp::get_synthdef_param_defaults(synth).contains_key(&lag_control))
+        });
+        let freq = pitch::note_to_freq(note as f64) as f32;
+        let mut controls = vec![("freq", freq)];
+        if has_lag {
+            controls.insert(0, (lag_control.as_str(), (mode.glide_ms / 1000.0) as f32));
+        }
+        log::debug!("[MONO] Voice '{}' glides node {} to {} at beat {:.2}", voice_name, node_id, pitch::note_name(note), beat);
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(beat),
+            vec![n_set_packet(node_id, &controls)],
+            &self.transport,
+            now,
+        ) {
+            log::warn!("[MONO] Failed to glide node {}: {}", node_id, e);
+        }
+    }
+
+    /// Send gate=0 for a node as a timed bundle at `off_beat`.
+    ///
+    /// This makes note lengths sample-accurate instead of depending on when the
+    /// tick loop notices the beat has passed. Only gate-offs inside the lookahead
+    /// window go out, since a bundle on the server can't be taken back when the
+    /// transport seeks or the tempo changes; later ones are sent by
+    /// `process_scheduled_note_offs` once they come into range. Returns `false`
+    /// if the bundle was not sent.
+    fn schedule_gate_off(&mut self, node_id: i32, off_beat: f64, now: Instant) -> bool {
+        let tempo = self.shared.with_state_read(|state| state.tempo);
+        let horizon = self.transport.beat_at(now).to_float() + LOOKAHEAD_MS as f64 / 1000.0 * tempo / 60.0;
+        if off_beat > horizon {
+            return false;
+        }
+
+        let packet = OscPacket::Message(OscMessage {
+            addr: "/n_set".to_string(),
+            args: vec![
+                OscType::Int(node_id),
+                OscType::String("gate".to_string()),
+                OscType::Float(0.0),
+            ],
+        });
+        match self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(off_beat),
+            vec![packet],
+            &self.transport,
+            now,
+        ) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("[NOTE_OFF] Failed to schedule gate-off for node {}: {}", node_id, e);
+                false
+            }
+        }
+    }
+
+    /// What a hit of `note` on a drum kit voice plays. `None` if the voice
+    /// is no drum kit; `Some(None)` if no pad of its kit plays `note` or the
+    /// pad's sample isn't loaded.
+    fn drum_pad_hit(&self, voice_name: &str, note: u8) -> Option<Option<PadHit>> {
+        self.shared.with_state_read(|state| {
+            let kit = state.voices.get(voice_name)?.drum_kit.as_ref()?;
+            let Some(pad) = kit.pad_for_note(note) else {
+                log::debug!("[DRUMKIT] No pad of '{}' plays note {}", voice_name, note);
+                return Some(None);
+            };
+            let Some(sample) = state.samples.get(&pad.sample_id) else {
+                log::warn!("[DRUMKIT] Sample '{}' of pad '{}' on '{}' is not loaded", pad.sample_id, pad.name, voice_name);
+                return Some(None);
+            };
+            Some(Some(kit.hit(pad, sample.buffer_id, sample.num_channels)))
+        })
+    }
+
+    /// Release the sounding notes of a drum kit voice's choked pads at
+    /// `beat` (now if `None`), and forget them.
+    fn choke_drum_pads(&mut self, voice_name: &str, notes: &[u8], beat: Option<BeatTime>) {
+        let nodes = self.shared.with_state_write(|state| {
+            let Some(voice) = state.voices.get_mut(voice_name) else {
+                return Vec::new();
+            };
+            let mut nodes = Vec::new();
+            for note in notes {
+                nodes.extend(voice.active_notes.remove(note).unwrap_or_default().into_iter().filter(|&id| id >= 0));
+                voice.note_origins.remove(note);
+            }
+            nodes
+        });
+        if nodes.is_empty() {
+            return;
+        }
+        log::debug!("[DRUMKIT] '{}' chokes nodes {:?}", voice_name, nodes);
+        let packets = nodes.iter().map(|&node_id| n_set_packet(node_id, &[("gate", 0.0)])).collect();
+        let now = Instant::now();
+        let result = match beat {
+            Some(beat) => self.osc_sender.send_bundle_at_beat(beat, packets, &self.transport, now),
+            None => self.osc_sender.send_bundle_now(packets, self.transport.beat_at(now).to_float()),
+        };
+        if let Err(e) = result {
+            log::warn!("[DRUMKIT] Failed to choke pads of '{}': {}", voice_name, e);
+        }
+    }
+
+    /// Build an OSC packet for a synth event.
+    /// Returns the packet and optional note-off scheduling info (voice_name, note, node_id, duration).
+    /// `beat_time` is the beat the synth starts at, recorded in the event log.
+    /// `live_instant` is when the synth will be live on scsynth (used for pending node tracking).
+    fn build_synth_packet(&mut self, event: &BeatEvent, beat_time: BeatTime, live_instant: Instant) -> Option<(OscPacket, Option<(String, u8, i32, f32)>)> {
         // Get note and velocity from event for SFZ region matching
         let freq = event.controls.iter()
             .find(|(k, _)| k == "freq")
             .map(|(_, v)| *v as f64)
             .unwrap_or(440.0);
-        let note = (69.0 + 12.0 * (freq / 440.0).log2()).round() as u8;
+        let note = pitch::freq_to_midi_note(freq);
         let velocity = event.controls.iter()
             .find(|(k, _)| k == "amp")
             .map(|(_, v)| (*v * 127.0) as u8)
@@ -3295,7 +5650,11 @@ impl RuntimeThread {
                             voice_name, state.voices.keys().collect::<Vec<_>>());
                     }
                     voice.map(|v| {
-                        let synth = v.synth_name.clone().unwrap_or_else(|| event.synth_def.clone());
+                        let synth = event
+                            .synth_override
+                            .clone()
+                            .or_else(|| v.synth_name.clone())
+                            .unwrap_or_else(|| event.synth_def.clone());
                         let params = v.params.clone();
                         let gain = v.gain;
 
@@ -3322,8 +5681,8 @@ impl RuntimeThread {
                                     let num_channels = region.num_channels;
                                     let pitch_keycenter = region.opcodes.pitch_keycenter.unwrap_or(note);
                                     // Calculate playback rate: target_freq / sample_root_freq
-                                    let target_freq = 440.0 * 2.0_f64.powf((note as f64 - 69.0) / 12.0);
-                                    let sample_root_freq = 440.0 * 2.0_f64.powf((pitch_keycenter as f64 - 69.0) / 12.0);
+                                    let target_freq = pitch::note_to_freq(note as f64);
+                                    let sample_root_freq = pitch::note_to_freq(pitch_keycenter as f64);
                                     let rate = (target_freq / sample_root_freq) as f32;
                                     log::debug!("[SFZ] Matched region: buf={}, channels={}, pitch_keycenter={}, rate={:.4}", buffer_id, num_channels, pitch_keycenter, rate);
                                     Some((buffer_id as f32, rate, num_channels))
@@ -3344,6 +5703,18 @@ impl RuntimeThread {
             (event.synth_def.clone(), std::collections::HashMap::new(), 1.0, None)
         };
 
+        // Drum kit voices play the sample of the pad mapped to the note
+        let pad_hit = match event.voice_name.as_deref() {
+            Some(voice_name) if event.synth_def == "trigger" || event.synth_def == "melody_note" => {
+                match self.drum_pad_hit(voice_name, note) {
+                    Some(None) => return None,
+                    hit => hit.flatten(),
+                }
+            }
+            _ => None,
+        };
+        let synth_def = pad_hit.as_ref().map_or(synth_def, |hit| hit.synthdef.to_string());
+
         // Get group node ID and audio bus
         let (group_id, audio_bus, group_params) = event
             .group_path
@@ -3386,14 +5757,19 @@ impl RuntimeThread {
             }
         }
 
-        // Output bus
-        merged_controls.push(("out".to_string(), audio_bus as f32));
+        // Output bus (the voice's override skips the group chain)
+        let out_bus = self.voice_out_bus(event.voice_name.as_deref(), audio_bus);
+        merged_controls.push(("out".to_string(), out_bus as f32));
+        if let Some(bus) = event.voice_name.as_ref().and_then(|voice| self.voice_diag_bus(voice, &synth_def)) {
+            merged_controls.push((vibelang_dsp::DIAG_BUS_PARAM.to_string(), bus as f32));
+        }
 
         // Calculate final amp with full multiplication chain
         let event_amp = event.controls.iter().find(|(k, _)| k == "amp").map(|(_, v)| *v).unwrap_or(1.0);
         let voice_fade_amp = voice_params.get("amp").copied().unwrap_or(1.0);
         let group_fade_amp = group_params.get("amp").copied().unwrap_or(1.0);
-        let final_amp = event_amp * voice_gain as f32 * voice_fade_amp * group_fade_amp;
+        let pad_gain = pad_hit.as_ref().map_or(1.0, |hit| hit.gain);
+        let final_amp = event_amp * voice_gain as f32 * voice_fade_amp * group_fade_amp * pad_gain;
 
         if let Some(voice_name) = &event.voice_name {
             log::debug!("[AMP CALC] voice='{}' final={:.4} = event({:.2}) × gain({:.4}) × voice_fade({:.4}) × group_fade({:.4})",
@@ -3414,6 +5790,9 @@ impl RuntimeThread {
                 merged_controls.push((k.clone(), *v));
             }
         }
+        if let Some(hit) = &pad_hit {
+            merged_controls.extend(hit.controls.iter().cloned());
+        }
 
         // Add SFZ parameters (buffer ID and playback rate) if this is an SFZ voice
         // Also override synthdef based on sample channel count
@@ -3432,15 +5811,25 @@ impl RuntimeThread {
             synth_def
         };
 
+        self.clamp_controls(&synth_def, &mut merged_controls);
+        if let Some((_, bufnum)) = merged_controls.iter().rev().find(|(k, _)| k == "bufnum") {
+            self.reload_evicted_sample(*bufnum as i32);
+        }
+
         // Allocate node ID
         let node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
 
+        if let Some(log) = self.event_log.as_mut() {
+            log.record(beat_time.to_float(), &self.transport, event, &synth_def, node_id, &merged_controls);
+        }
+
         log::trace!("[S_NEW] Creating synth '{}' node {} in group {} with controls: {:?}",
             synth_def, node_id, group_id,
             merged_controls.iter().map(|(k, v)| format!("{}={:.3}", k, v)).collect::<Vec<_>>());
 
         // Build OSC message: /s_new synthdef node_id add_action target [controls...]
         // Use AddToHead (0) so voices execute BEFORE effects in the group
+        let synth_name = synth_def.clone();
         let mut args: Vec<OscType> = vec![
             OscType::String(synth_def),
             OscType::Int(node_id),
@@ -3462,20 +5851,13 @@ impl RuntimeThread {
             .find(|(k, _)| k == "freq")
             .map(|(_, v)| *v as f64)
             .unwrap_or(440.0);
-        let note = (69.0 + 12.0 * (freq / 440.0).log2()).round() as u8;
+        let note = pitch::freq_to_midi_note(freq);
+        let length = gate_duration.map(|beats| self.beats_to_duration(beats as f64));
 
         // Track the synth
         self.shared.with_state_write(|state| {
-            state.active_synths.insert(
-                node_id,
-                ActiveSynth {
-                    node_id,
-                    group_paths: event.group_path.iter().cloned().collect(),
-                    voice_names: event.voice_name.iter().cloned().collect(),
-                    pattern_names: event.pattern_name.iter().cloned().collect(),
-                    melody_names: event.melody_name.iter().cloned().collect(),
-                },
-            );
+            let synth = ActiveSynth::for_event(state, node_id, event, &synth_name, beat_time.to_float());
+            state.active_synths.insert(node_id, synth);
 
             // Mark as pending (sent in timed bundle, not yet confirmed on scsynth)
             state.pending_nodes.insert(node_id, live_instant);
@@ -3483,11 +5865,15 @@ impl RuntimeThread {
             // Also track in voice's active_notes for voice parameter fades
             if let Some(voice_name) = &event.voice_name {
                 if let Some(voice) = state.voices.get_mut(voice_name) {
-                    voice.active_notes.entry(note).or_default().push(node_id);
+                    voice.track_note(note, node_id, NoteOrigin::new(live_instant, NoteSource::of_event(event), length));
                 }
             }
         });
 
+        if let (Some(hit), Some(voice_name)) = (&pad_hit, &event.voice_name) {
+            self.choke_drum_pads(voice_name, &hit.chokes, Some(beat_time));
+        }
+
         // Prepare note-off info if needed
         let note_off_info = if let Some(duration) = gate_duration {
             event.voice_name.as_ref().map(|name| (name.clone(), note, node_id, duration))
@@ -3520,7 +5906,7 @@ impl RuntimeThread {
             event.voice_name.as_ref().and_then(|voice_name| {
                 self.shared.with_state_read(|state| {
                     state.voices.get(voice_name).map(|v| (
-                        v.synth_name.clone().unwrap_or_else(|| event.synth_def.clone()),
+                        event.synth_override.clone().or_else(|| v.synth_name.clone()).unwrap_or_else(|| event.synth_def.clone()),
                         v.params.clone(),
                         v.gain,
                     ))
@@ -3563,8 +5949,9 @@ impl RuntimeThread {
             }
         }
 
-        // Add output bus
-        merged_controls.push(("out".to_string(), audio_bus as f32));
+        // Add output bus (the voice's override skips the group chain)
+        let out_bus = self.voice_out_bus(event.voice_name.as_deref(), audio_bus);
+        merged_controls.push(("out".to_string(), out_bus as f32));
 
         // Calculate final amp with full multiplication chain
         let event_amp = event.controls.iter().find(|(k, _)| k == "amp").map(|(_, v)| *v).unwrap_or(1.0);
@@ -3590,6 +5977,7 @@ impl RuntimeThread {
             }
         }
 
+        self.clamp_controls(&synth_def, &mut merged_controls);
         let controls: Vec<(&str, f32)> = merged_controls
             .iter()
             .map(|(k, v)| (k.as_str(), *v))
@@ -3615,16 +6003,8 @@ impl RuntimeThread {
 
         // Track the synth
         self.shared.with_state_write(|state| {
-            state.active_synths.insert(
-                node_id,
-                ActiveSynth {
-                    node_id,
-                    group_paths: event.group_path.iter().cloned().collect(),
-                    voice_names: event.voice_name.iter().cloned().collect(),
-                    pattern_names: event.pattern_name.iter().cloned().collect(),
-                    melody_names: event.melody_name.iter().cloned().collect(),
-                },
-            );
+            let synth = ActiveSynth::for_event(state, node_id, &event, &synth_def, current_beat);
+            state.active_synths.insert(node_id, synth);
         });
 
         // For events with a gate duration, schedule a note-off
@@ -3634,20 +6014,23 @@ impl RuntimeThread {
                 .find(|(k, _)| k == "freq")
                 .map(|(_, v)| *v as f64)
                 .unwrap_or(440.0);
-            // Convert freq back to MIDI note for tracking: note = 69 + 12 * log2(freq / 440)
-            let note = (69.0 + 12.0 * (freq / 440.0).log2()).round() as u8;
+            // Convert freq back to MIDI note for tracking
+            let note = pitch::freq_to_midi_note(freq);
 
             if let Some(voice_name) = &event.voice_name {
-                let current_beat = self.transport.beat_at(std::time::Instant::now()).to_float();
+                let now = Instant::now();
+                let current_beat = self.transport.beat_at(now).to_float();
                 let off_beat = current_beat + duration as f64;
                 log::debug!("[NOTE_OFF] Scheduling note-off for '{}' note {} at beat {} (current={}, duration={})",
-                    voice_name, note, off_beat, current_beat, duration);
+                    voice_name, pitch::note_name(note), off_beat, current_beat, duration);
+                let gate_scheduled = self.schedule_gate_off(node_id, off_beat, now);
                 self.shared.with_state_write(|state| {
                     state.scheduled_note_offs.push(ScheduledNoteOff {
                         beat: off_beat,
                         voice_name: voice_name.clone(),
                         note,
                         node_id: Some(node_id),
+                        gate_scheduled,
                     });
                 });
             }
@@ -3744,7 +6127,179 @@ impl RuntimeThread {
         });
     }
 
+    /// Set parameters of a voice: mapped ones go out as MIDI CC, all of them
+    /// are stored with a single version bump and sent to the voice's sounding
+    /// nodes in one bundle.
+    fn set_voice_params(&mut self, name: &str, params: Vec<(String, f32)>) {
+        let params: Vec<(String, f32)> = params
+            .into_iter()
+            .map(|(param, value)| {
+                let value = self.clamp_target_param(&FadeTargetType::Voice, name, &param, value);
+                (param, value)
+            })
+            .collect();
+
+        for (param, value) in &params {
+            self.send_voice_param_cc(name, param, *value);
+        }
+
+        let nodes = self.shared.with_state_write(|state| {
+            let voice = state.voices.get_mut(name)?;
+            voice.params.extend(params.iter().cloned());
+            let nodes = voice.sounding_nodes();
+            state.bump_version();
+            Some(nodes)
+        });
+        let Some(nodes) = nodes else {
+            return;
+        };
+
+        let controls: Vec<(&str, f32)> = params.iter().map(|(param, value)| (param.as_str(), *value)).collect();
+        let packets: Vec<OscPacket> = nodes.iter().map(|&node_id| n_set_packet(node_id, &controls)).collect();
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if let Err(e) = self.osc_sender.send_bundle_now(packets, current_beat) {
+            log::warn!("[VOICE] Failed to set params of '{}': {}", name, e);
+        }
+    }
+
+    /// Send a voice parameter to its MIDI output if it is mapped to a CC.
+    fn send_voice_param_cc(&self, name: &str, param: &str, value: f32) {
+        let midi_cc_info = self.shared.with_state_read(|state| {
+            let voice = state.voices.get(name)?;
+            let cc_num = *voice.cc_mappings.get(param)?;
+            let device = state.midi_output_config.devices.get(&voice.midi_output_device_id?)?;
+            // Declared ranges map onto the full CC range
+            let position = voice
+                .synth_name
+                .as_deref()
+                .and_then(|synth| state.param_range(synth, param))
+                .map_or(value, |range| range.to_normalized(value));
+            Some((device.event_tx.clone(), voice.midi_channel.unwrap_or(0), cc_num, position))
+        });
+
+        if let Some((event_tx, channel, cc_num, position)) = midi_cc_info {
+            // Convert 0.0-1.0 to 0-127
+            let cc_value = (position.clamp(0.0, 1.0) * 127.0) as u8;
+            let midi_event = crate::midi::QueuedMidiEvent::control_change(channel, cc_num, cc_value);
+            let _ = event_tx.send(midi_event);
+            log::debug!("[MIDI_OUT] Voice '{}' CC: {}={} (param='{}', ch={})",
+                name, cc_num, cc_value, param, channel + 1);
+        }
+    }
+
+    /// Move the sounding nodes of a voice that changed group into its new
+    /// group, so running synths and held notes keep playing through the new
+    /// group's effects instead of being cut off when the old group goes away.
+    fn move_voice_nodes(&mut self, name: &str, old_group: &str) {
+        let moved = self.shared.with_state_write(|state| {
+            let voice = state.voices.get(name)?;
+            let new_group = voice.group_path.clone();
+            let group = state.groups.get(&new_group)?;
+            let group_node = group.node_id?;
+            // An explicit output bus or insert chain doesn't depend on the group
+            let out_bus = (voice.output_bus.is_none() && voice.insert_bus.is_none()).then_some(group.audio_bus);
+            let nodes = voice.sounding_nodes();
+            for node_id in &nodes {
+                if let Some(synth) = state.active_synths.get_mut(node_id) {
+                    synth.group_paths.retain(|path| path != old_group);
+                    if !synth.group_paths.contains(&new_group) {
+                        synth.group_paths.push(new_group.clone());
+                    }
+                }
+            }
+            Some((nodes, group_node, out_bus, new_group))
+        });
+        let Some((nodes, group_node, out_bus, new_group)) = moved else {
+            return;
+        };
+        if nodes.is_empty() {
+            return;
+        }
+
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        for &node_id in &nodes {
+            // Voices sit at the head of their group, before its effects
+            let _ = self.osc_sender.n_move(OscTiming::Now, NodeId::new(node_id), AddAction::AddToHead, Target::from(group_node), current_beat);
+            if let Some(bus) = out_bus {
+                let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("out", bus as f32)], current_beat);
+            }
+        }
+        log::info!("[VOICE] Moved {} node(s) of '{}' from '{}' to '{}'", nodes.len(), name, old_group, new_group);
+    }
+
+    /// Point the sounding nodes of a voice whose output changed at its new bus.
+    fn reroute_voice_nodes(&mut self, name: &str) {
+        let routed = self.shared.with_state_read(|state| {
+            let voice = state.voices.get(name)?;
+            let group_bus = state.groups.get(&voice.group_path).map(|g| g.audio_bus)?;
+            let insert_link = voice.insert_link_node_id.map(|node| (node, voice.mix_bus(group_bus)));
+            Some((voice.sounding_nodes(), voice.out_bus(group_bus), insert_link))
+        });
+        let Some((nodes, bus, insert_link)) = routed else {
+            return;
+        };
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        for &node_id in &nodes {
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("out", bus as f32)], current_beat);
+        }
+        if let Some((link_node, mix_bus)) = insert_link {
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(link_node), &[("outbus", mix_bus as f32)], current_beat);
+        }
+        log::info!("[VOICE] Routed '{}' to bus {}", name, bus);
+    }
+
+    /// Bus the synths of `voice` write to, given its group's bus.
+    fn voice_out_bus(&self, voice: Option<&str>, group_bus: i32) -> i32 {
+        voice
+            .and_then(|name| self.shared.with_state_read(|state| state.voices.get(name).map(|v| v.out_bus(group_bus))))
+            .unwrap_or(group_bus)
+    }
+
     fn handle_set_group_param(&mut self, path_or_name: &str, param: &str, value: f32) {
+        let previous = self.shared.with_state_read(|state| {
+            let path = state.find_group_path(path_or_name).unwrap_or_else(|| path_or_name.to_string());
+            state.groups.get(&path).map(|group| group.params.get(param).copied().unwrap_or(1.0))
+        });
+        if let Some(node_id) = self.set_group_param_state(path_or_name, param, value) {
+            // Ramp the link synth's amp to the new level
+            self.set_node_param_smoothed(node_id, "amp", previous, value, false);
+            log::trace!("[GROUP PARAM] Updated link synth {} amp={}", node_id, value);
+        }
+    }
+
+    /// Move `param` of a running node to `value`, ramping it when the
+    /// parameter has a smoothing time (see [`crate::smoothing`]).
+    ///
+    /// `from` is the value the node has now. Synthdefs with the param's `_lag`
+    /// control ramp on the server; otherwise the runtime sends time-stamped
+    /// intermediate values.
+    fn set_node_param_smoothed(&mut self, node_id: i32, param: &str, from: Option<f32>, value: f32, has_lag: bool) {
+        let seconds = self.shared.with_state_read(|state| state.param_smoothing.seconds(param));
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if seconds > 0.0 && has_lag {
+            let lag_control = vibelang_dsp::ramp_lag_control(param);
+            let controls = [(lag_control.as_str(), seconds as f32), (param, value)];
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &controls, current_beat);
+            return;
+        }
+        let steps = from.map_or_else(Vec::new, |from| crate::smoothing::slew_steps(from, value, seconds));
+        if steps.is_empty() {
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[(param, value)], current_beat);
+            return;
+        }
+        for (offset, step) in steps {
+            let packet = n_set_packet(node_id, &[(param, step)]);
+            if let Err(e) = self.osc_sender.send_bundle_after(offset, vec![packet], current_beat) {
+                log::warn!("[SMOOTH] Failed to ramp node {} {}: {}", node_id, param, e);
+                break;
+            }
+        }
+    }
+
+    /// Store a group param in state.
+    ///
+    /// Returns the link synth node that needs the new value, if any.
+    fn set_group_param_state(&mut self, path_or_name: &str, param: &str, value: f32) -> Option<i32> {
         // Update state - try to find group by path first, then by name
         let actual_path = self.shared.with_state_write(|state| {
             // First try exact path match
@@ -3775,93 +6330,1662 @@ impl RuntimeThread {
 
                 // For amp parameter, also update the link synth in real-time
                 // This allows the mixer fader to have immediate effect on audio output
-                if param == "amp" {
-                    let link_node_id = self.shared.with_state_read(|state| {
-                        state.groups.get(path).and_then(|g| g.link_synth_node_id)
-                    });
-
-                    if let Some(node_id) = link_node_id {
-                        // Send n_set to update the link synth's amp parameter
-                        let current_beat = self.transport.beat_at(Instant::now()).to_float();
-                        let _ = self.osc_sender.n_set(
-                            OscTiming::Now,
-                            NodeId::new(node_id),
-                            &[("amp", value)],
-                            current_beat,
-                        );
-                        log::trace!("[GROUP PARAM] Updated link synth {} amp={}", node_id, value);
-                    }
-                }
-
                 // Note: For other params, we only update state, not running synths.
                 // Group params affect NEW synths - the final amp is calculated as:
                 // event_amp × voice_gain × group_amp × voice_amp
+                if param == "amp" {
+                    self.shared.with_state_read(|state| {
+                        state.groups.get(path).and_then(|g| g.link_synth_node_id)
+                    })
+                } else {
+                    None
+                }
             }
             None => {
                 log::trace!("[GROUP PARAM] Group '{}' not found when setting {}={}", path_or_name, param, value);
+                None
             }
         }
     }
 
-    fn set_group_run_state(&mut self, path: &str, running: bool) {
-        let node_to_set = self.shared.with_state_write(|state| {
-            let node_id = state.groups.get_mut(path).and_then(|group| {
-                group.muted = !running;
-                group.node_id
-            });
-            state.bump_version();
-            node_id
+    /// Apply the actions of a live set scene.
+    fn handle_launch_scene(&mut self, name: &str) {
+        let scene = self.shared.with_state_read(|state| {
+            state.live_set.as_ref().and_then(|live| live.set.scene(name).cloned())
         });
-        if let Some(node_id) = node_to_set {
-            let current_beat = self.transport.beat_at(Instant::now()).to_float();
-            let _ = self.osc_sender.n_run(OscTiming::Now, NodeId::new(node_id), running, current_beat);
+        let Some(scene) = scene else {
+            log::warn!("[LIVESET] Unknown scene '{}'", name);
+            return;
+        };
+
+        log::info!("[LIVESET] Launching scene '{}'", name);
+        for action in &scene.actions {
+            match action {
+                SceneAction::Fade { group, param, value, beats } => {
+                    self.start_scene_fade(name, group, param, *value, *beats);
+                }
+                SceneAction::Eval(code) => {
+                    // Rhai runs on the script thread, which drains this queue
+                    self.shared.with_state_write(|state| {
+                        if let Some(live) = state.live_set.as_mut() {
+                            live.pending_evals.push(code.clone());
+                        }
+                    });
+                }
+                _ => match self.scene_action_message(action) {
+                    Some(msg) => self.handle_message(msg),
+                    None => log::warn!("[LIVESET] Scene '{}': nothing to apply for {:?}", name, action),
+                },
+            }
         }
+
+        self.shared.with_state_write(|state| {
+            if let Some(live) = state.live_set.as_mut() {
+                live.active_scene = Some(name.to_string());
+                // Launching a cued scene directly moves the cue position there
+                if let Some(index) = live.set.cues.iter().position(|cue| cue == name) {
+                    live.cue_index = Some(index);
+                }
+            }
+            state.bump_version();
+        });
     }
 
-    fn finalize_groups(&mut self) {
-        // Get all groups that need link synths, along with their last effect node
-        // IMPORTANT: Sort groups so children are processed BEFORE parents.
-        // This ensures parent link synths execute AFTER children have written to the parent bus.
+    /// Launch the cue `delta` steps from the current one.
+    fn handle_step_cue(&mut self, delta: i32) {
+        let cue = self.shared.with_state_read(|state| {
+            let live = state.live_set.as_ref()?;
+            let index = live.step_cue(delta)?;
+            Some((index, live.set.cues[index].clone()))
+        });
+        let Some((index, scene)) = cue else {
+            log::info!("[LIVESET] No cue {} the current one", if delta > 0 { "after" } else { "before" });
+            return;
+        };
 
-        // Debug: log all groups and their link synth status
-        self.shared.with_state_read(|state| {
-            log::info!("[FINALIZE_GROUPS] Checking {} groups for link synth creation", state.groups.len());
-            for (path, g) in &state.groups {
-                log::info!("[FINALIZE_GROUPS]   '{}': link_synth={:?}, audio_bus={:?}, node_id={:?}",
-                    path, g.link_synth_node_id, g.audio_bus, g.node_id);
+        log::info!("[LIVESET] Cue {}: '{}'", index + 1, scene);
+        self.handle_launch_scene(&scene);
+        self.shared.with_state_write(|state| {
+            if let Some(live) = state.live_set.as_mut() {
+                live.cue_index = Some(index);
             }
         });
+    }
 
-        let mut groups: Vec<(String, i32, Option<String>, i32, Option<i32>)> = self.shared.with_state_read(|state| {
-            state
-                .groups
-                .values()
-                .filter(|g| g.link_synth_node_id.is_none())
-                .map(|g| {
-                    // Find all effects for this group, sorted by position
-                    let mut group_effects: Vec<_> = state
-                        .effects
-                        .values()
-                        .filter(|e| e.group_path == g.path)
-                        .collect();
-                    group_effects.sort_by_key(|e| e.position);
-
-                    // Get the last effect's node ID (if any)
-                    let last_effect_node = group_effects.last().and_then(|e| e.node_id);
+    /// Translate a scene action into the message that performs it.
+    ///
+    /// Names are resolved against the current state: `start`/`stop` accept
+    /// sequences, patterns and melodies, group actions accept paths or names.
+    fn scene_action_message(&self, action: &SceneAction) -> Option<StateMessage> {
+        self.shared.with_state_read(|state| match action {
+            SceneAction::Start(name) | SceneAction::Stop(name) => {
+                let start = matches!(action, SceneAction::Start(_));
+                let name = name.clone();
+                if state.sequences.contains_key(&name) {
+                    Some(if start {
+                        StateMessage::StartSequence { name }
+                    } else {
+                        StateMessage::StopSequence { name }
+                    })
+                } else if state.patterns.contains_key(&name) {
+                    Some(if start {
+                        StateMessage::StartPattern { name }
+                    } else {
+                        StateMessage::StopPattern { name }
+                    })
+                } else if state.melodies.contains_key(&name) {
+                    Some(if start {
+                        StateMessage::StartMelody { name }
+                    } else {
+                        StateMessage::StopMelody { name }
+                    })
+                } else {
+                    None
+                }
+            }
+            SceneAction::Mute(group) => state
+                .find_group_path(group)
+                .map(|path| StateMessage::MuteGroup { path }),
+            SceneAction::Unmute(group) => state
+                .find_group_path(group)
+                .map(|path| StateMessage::UnmuteGroup { path }),
+            SceneAction::Solo(group) | SceneAction::Unsolo(group) => state
+                .find_group_path(group)
+                .map(|path| StateMessage::SoloGroup {
+                    path,
+                    solo: matches!(action, SceneAction::Solo(_)),
+                }),
+            SceneAction::Tempo(bpm) => Some(StateMessage::SetBpm { bpm: *bpm }),
+            SceneAction::CueGraph(graph) => state
+                .playback_graphs
+                .contains_key(graph)
+                .then(|| StateMessage::CueGraph { name: graph.clone() }),
+            SceneAction::Set { group, param, value } => state
+                .find_group_path(group)
+                .map(|path| StateMessage::SetGroupParam {
+                    path,
+                    param: param.clone(),
+                    value: *value,
+                }),
+            SceneAction::SendMidi { device, messages } => Some(StateMessage::MidiOutputSendRaw {
+                device: device.clone(),
+                messages: messages.clone(),
+            }),
+            SceneAction::SysexFile { device, path } => match crate::midi_patch::load_syx(path) {
+                Ok(messages) => Some(StateMessage::MidiOutputSendRaw {
+                    device: device.clone(),
+                    messages,
+                }),
+                Err(e) => {
+                    log::warn!("Scene SysEx for '{}' skipped: {:#}", device, e);
+                    None
+                }
+            },
+            SceneAction::Fade { .. } | SceneAction::Eval(_) => None,
+        })
+    }
 
-                    (
-                        g.path.clone(),
-                        g.audio_bus,  // audio_bus is always set (i32, not Option)
-                        g.parent_path.clone(),
-                        g.node_id.unwrap_or(0),
-                        last_effect_node,
-                    )
-                })
-                .collect()
+    /// Fade a group parameter from its current value as part of a scene.
+    fn start_scene_fade(&mut self, scene: &str, group: &str, param: &str, value: f32, beats: f64) {
+        let from = self.shared.with_state_read(|state| {
+            let path = state.find_group_path(group)?;
+            let current = state.groups.get(&path)?.params.get(param).copied();
+            Some((path, current))
         });
+        let Some((path, current)) = from else {
+            log::warn!("[LIVESET] Scene '{}': unknown group '{}'", scene, group);
+            return;
+        };
 
-        log::info!("[FINALIZE_GROUPS] {} groups need link synths: {:?}",
-            groups.len(), groups.iter().map(|(p, _, _, _, _)| p.as_str()).collect::<Vec<_>>());
+        let fade = crate::sequences::FadeDefinition::new(
+            format!("scene:{}:{}.{}", scene, path, param),
+            FadeTargetType::Group,
+            path,
+            param,
+        )
+        .with_range(current.unwrap_or(value), value)
+        .with_duration(beats);
+        self.start_fade_from_definition(&fade, 0.0);
+    }
+
+    /// Start a playback graph at `section` (its first section by default).
+    fn start_playback_graph(&mut self, name: &str, section: Option<&str>) {
+        let graph = self.shared.with_state_read(|state| state.playback_graphs.get(name).cloned());
+        let Some(graph) = graph else {
+            log::warn!("[GRAPH] Cannot start unknown graph '{}'", name);
+            return;
+        };
+        if let Some(current) = &graph.current {
+            // Re-running the script must not restart a playing graph
+            log::info!("[GRAPH] '{}' already playing section '{}'", name, current);
+            return;
+        }
+        let entry = match section {
+            Some(section) => graph.graph.section(section),
+            None => graph.graph.sections.first(),
+        };
+        let Some(entry) = entry.cloned() else {
+            log::warn!("[GRAPH] '{}': no section '{}'", name, section.unwrap_or("<first>"));
+            return;
+        };
+
+        let quantization = self.shared.with_state_read(|s| s.launch_grid(LaunchKind::Sequence, None));
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let anchor_beat = crate::sequences::next_launch_beat(current_beat, quantization);
+
+        log::info!("[GRAPH] '{}': starting at section '{}' (beat {:.2})", name, entry.name, anchor_beat);
+        self.restore_section_level(name, &entry);
+        self.start_sequence_at(&entry.sequence, false, anchor_beat);
+        self.with_graph(name, |graph| graph.enter(&entry.name, anchor_beat));
+    }
+
+    /// Stop a playback graph, including sections being switched or faded.
+    fn stop_playback_graph(&mut self, name: &str) {
+        let graph = self.shared.with_state_read(|state| state.playback_graphs.get(name).cloned());
+        let Some(graph) = graph else {
+            log::warn!("[GRAPH] Cannot stop unknown graph '{}'", name);
+            return;
+        };
+
+        let playing = graph
+            .current
+            .iter()
+            .chain(graph.pending.as_ref().map(|p| &p.to))
+            .chain(graph.fading_out.as_ref().map(|f| &f.section));
+        for section in playing {
+            if let Some(section) = graph.graph.section(section) {
+                self.handle_message(StateMessage::StopSequence {
+                    name: section.sequence.clone(),
+                });
+            }
+        }
+        for section in graph.graph.sections.iter().filter(|s| graph.faded_amps.contains_key(&s.name)) {
+            self.restore_section_level(name, section);
+        }
+
+        log::info!("[GRAPH] '{}' stopped", name);
+        self.with_graph(name, |graph| {
+            graph.current = None;
+            graph.pending = None;
+            graph.fading_out = None;
+            graph.cued = false;
+        });
+    }
+
+    /// Advance playing graphs: end crossfades, switch pending transitions at
+    /// their bar line and take transitions whose condition holds.
+    fn update_playback_graphs(&mut self, current_beat: f64) {
+        let (names, tempo, beats_per_bar) = self.shared.with_state_read(|state| {
+            let names: Vec<String> = state
+                .playback_graphs
+                .iter()
+                .filter(|(_, graph)| graph.is_playing())
+                .map(|(name, _)| name.clone())
+                .collect();
+            (names, state.tempo, state.time_signature.beats_per_bar())
+        });
+        if names.is_empty() {
+            return;
+        }
+
+        // Sequences are materialized ahead of time, so switch as soon as
+        // the bar line is inside the lookahead window
+        let horizon = current_beat + LOOKAHEAD_MS as f64 / 1000.0 * tempo / 60.0;
+
+        for name in names {
+            let graph = self.shared.with_state_read(|state| state.playback_graphs.get(&name).cloned());
+            let Some(graph) = graph else {
+                continue;
+            };
+
+            if let Some(fading) = &graph.fading_out {
+                if horizon >= fading.until_beat {
+                    self.finish_crossfade(&name, &graph, fading);
+                }
+            }
+
+            if let Some(pending) = &graph.pending {
+                if horizon >= pending.at_beat {
+                    self.complete_transition(&name, &graph, pending);
+                }
+                continue;
+            }
+
+            let Some(current) = graph.current.as_deref() else {
+                continue;
+            };
+            let elapsed = current_beat - graph.entered_beat;
+            if let Some(transition) = graph.graph.next_transition(current, elapsed, graph.cued, &graph.vars) {
+                self.begin_transition(&name, &graph, transition, current_beat, beats_per_bar);
+            }
+        }
+    }
+
+    /// Take a transition: switch right away or anchor the next section at
+    /// the next bar line and wait for it.
+    fn begin_transition(
+        &mut self,
+        name: &str,
+        graph: &PlaybackGraphState,
+        transition: &GraphTransition,
+        current_beat: f64,
+        beats_per_bar: f64,
+    ) {
+        let (Some(from), Some(to)) = (
+            graph.graph.section(&transition.from).cloned(),
+            graph.graph.section(&transition.to).cloned(),
+        ) else {
+            return;
+        };
+        log::info!(
+            "[GRAPH] '{}': {} -> {} ({}, {})",
+            name, from.name, to.name, transition.condition, transition.style.as_str()
+        );
+
+        match transition.style {
+            TransitionStyle::Immediate => {
+                self.handle_message(StateMessage::StopSequence { name: from.sequence });
+                self.restore_section_level(name, &to);
+                self.start_sequence_at(&to.sequence, false, current_beat);
+                self.with_graph(name, |graph| graph.enter(&to.name, current_beat));
+            }
+            TransitionStyle::NextBar | TransitionStyle::Crossfade => {
+                let at_beat = (current_beat / beats_per_bar).ceil() * beats_per_bar;
+                self.start_sequence_at(&to.sequence, false, at_beat);
+                self.with_graph(name, |graph| {
+                    graph.pending = Some(PendingTransition {
+                        from: from.name,
+                        to: to.name,
+                        style: transition.style,
+                        at_beat,
+                    });
+                });
+            }
+        }
+    }
+
+    /// Switch a pending transition once its bar line is due.
+    fn complete_transition(&mut self, name: &str, graph: &PlaybackGraphState, pending: &PendingTransition) {
+        let (Some(from), Some(to)) = (
+            graph.graph.section(&pending.from).cloned(),
+            graph.graph.section(&pending.to).cloned(),
+        ) else {
+            self.with_graph(name, |graph| graph.pending = None);
+            return;
+        };
+
+        let mut fading_out = None;
+        if pending.style == TransitionStyle::Crossfade {
+            // A previous crossfade still running ends now
+            if let Some(fading) = &graph.fading_out {
+                self.finish_crossfade(name, graph, fading);
+            }
+            let beats = graph.graph.crossfade_beats;
+            if let Some(level) = self.section_level(graph, &to) {
+                self.start_section_fade(name, &to, 0.0, level, beats);
+                self.with_graph(name, |graph| {
+                    graph.faded_amps.remove(&to.name);
+                });
+            }
+            if let Some(level) = self.section_level(graph, &from) {
+                self.start_section_fade(name, &from, level, 0.0, beats);
+                self.with_graph(name, |graph| {
+                    graph.faded_amps.insert(from.name.clone(), level);
+                });
+            }
+            fading_out = Some(FadingSection {
+                section: from.name.clone(),
+                until_beat: pending.at_beat + beats,
+            });
+        } else {
+            self.handle_message(StateMessage::StopSequence { name: from.sequence });
+            self.restore_section_level(name, &to);
+        }
+
+        log::info!("[GRAPH] '{}': entered '{}' at beat {:.2}", name, to.name, pending.at_beat);
+        self.with_graph(name, |graph| {
+            graph.pending = None;
+            if fading_out.is_some() {
+                graph.fading_out = fading_out;
+            }
+            graph.enter(&to.name, pending.at_beat);
+        });
+    }
+
+    /// Stop the sequence of a section that finished fading out.
+    fn finish_crossfade(&mut self, name: &str, graph: &PlaybackGraphState, fading: &FadingSection) {
+        // The section may have been entered again in the meantime
+        let still_used = graph.current.as_deref() == Some(fading.section.as_str())
+            || graph.pending.as_ref().is_some_and(|p| p.to == fading.section);
+        if !still_used {
+            if let Some(section) = graph.graph.section(&fading.section) {
+                self.handle_message(StateMessage::StopSequence {
+                    name: section.sequence.clone(),
+                });
+            }
+        }
+        self.with_graph(name, |graph| graph.fading_out = None);
+    }
+
+    /// Level a section's group fades to or from: the level it had before it
+    /// was faded out, or its current `amp`. `None` if it has no group.
+    fn section_level(&self, graph: &PlaybackGraphState, section: &GraphSection) -> Option<f32> {
+        let group = section.group.as_ref()?;
+        if let Some(level) = graph.faded_amps.get(&section.name) {
+            return Some(*level);
+        }
+        self.shared.with_state_read(|state| {
+            let path = state.find_group_path(group)?;
+            Some(state.groups.get(&path)?.params.get("amp").copied().unwrap_or(1.0))
+        })
+    }
+
+    /// Fade the `amp` of a section's group.
+    fn start_section_fade(&mut self, name: &str, section: &GraphSection, from: f32, to: f32, beats: f64) {
+        let Some(path) = section
+            .group
+            .as_ref()
+            .and_then(|group| self.shared.with_state_read(|state| state.find_group_path(group)))
+        else {
+            return;
+        };
+        let fade = crate::sequences::FadeDefinition::new(
+            format!("graph:{}:{}", name, section.name),
+            FadeTargetType::Group,
+            path,
+            "amp",
+        )
+        .with_range(from, to)
+        .with_duration(beats);
+        self.start_fade_from_definition(&fade, 0.0);
+    }
+
+    /// Restore the group level of a section that was faded out.
+    fn restore_section_level(&mut self, name: &str, section: &GraphSection) {
+        let level = self.shared.with_state_write(|state| {
+            state
+                .playback_graphs
+                .get_mut(name)
+                .and_then(|graph| graph.faded_amps.remove(&section.name))
+        });
+        let path = section
+            .group
+            .as_ref()
+            .and_then(|group| self.shared.with_state_read(|state| state.find_group_path(group)));
+        if let (Some(level), Some(path)) = (level, path) {
+            self.handle_message(StateMessage::SetGroupParam {
+                path,
+                param: "amp".to_string(),
+                value: level,
+            });
+        }
+    }
+
+    /// Update a playback graph's state.
+    fn with_graph(&self, name: &str, f: impl FnOnce(&mut PlaybackGraphState)) {
+        self.shared.with_state_write(|state| {
+            if let Some(graph) = state.playback_graphs.get_mut(name) {
+                f(graph);
+                state.bump_version();
+            }
+        });
+    }
+
+    /// Write every parameter mapped by a macro at its current value.
+    fn apply_macro(&mut self, control: &MacroControl) {
+        for (target, value) in control.writes() {
+            self.handle_message(param_message(&target.target_type, target.name.clone(), target.param.clone(), value));
+        }
+    }
+
+    fn set_group_run_state(&mut self, path: &str, running: bool) {
+        let nodes = self.shared.with_state_write(|state| {
+            let seconds = state.param_smoothing.mute_seconds();
+            let nodes = state.groups.get_mut(path).and_then(|group| {
+                group.muted = !running;
+                let amp = group.params.get("amp").copied().unwrap_or(1.0);
+                group.node_id.map(|node_id| (node_id, group.link_synth_node_id, amp))
+            });
+            state.bump_version();
+            nodes.map(|(node_id, link, amp)| (node_id, link.filter(|_| seconds > 0.0), amp, seconds))
+        });
+        let Some((node_id, link_node, amp, seconds)) = nodes else {
+            return;
+        };
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let Some(link_node) = link_node else {
+            let _ = self.osc_sender.n_run(OscTiming::Now, NodeId::new(node_id), running, current_beat);
+            return;
+        };
+
+        // Fade the group's output through its link synth around the pause, so
+        // the toggle doesn't click
+        let (from, to) = if running { (0.0, amp) } else { (amp, 0.0) };
+        let mut steps = crate::smoothing::slew_steps(from, to, seconds);
+        if running {
+            let resume = vec![n_set_packet(link_node, &[("amp", 0.0)]), n_run_packet(node_id, true)];
+            let _ = self.osc_sender.send_bundle_now(resume, current_beat);
+        }
+        if steps.is_empty() {
+            steps.push((seconds, to));
+        }
+        let last = steps.len() - 1;
+        for (i, (offset, value)) in steps.into_iter().enumerate() {
+            let mut packets = vec![n_set_packet(link_node, &[("amp", value)])];
+            if i == last {
+                // Also resumes after a mute that was still fading out
+                packets.push(n_run_packet(node_id, running));
+            }
+            if let Err(e) = self.osc_sender.send_bundle_after(offset, packets, current_beat) {
+                log::warn!("[SMOOTH] Failed to fade group '{}': {}", path, e);
+                let _ = self.osc_sender.n_run(OscTiming::Now, NodeId::new(node_id), running, current_beat);
+                break;
+            }
+        }
+    }
+
+    /// Start bouncing a group's output to a buffer.
+    ///
+    /// Recording starts at the next bar boundary. Once `bars` bars have been
+    /// captured, `process_group_freezes` swaps in the looping player.
+    fn handle_freeze_group(&mut self, path: &str, bars: u32) {
+        let bars = bars.clamp(1, crate::freeze::MAX_FREEZE_BARS);
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+
+        let group_info = self.shared.with_state_read(|state| {
+            state.groups.get(path).map(|g| {
+                (
+                    g.audio_bus,
+                    g.link_synth_node_id,
+                    g.freeze.is_some(),
+                    state.tempo,
+                    state.time_signature.beats_per_bar(),
+                    state.transport_running,
+                )
+            })
+        });
+        let Some((audio_bus, link_node, already_frozen, tempo, beats_per_bar, running)) = group_info else {
+            log::warn!("[FREEZE] Group '{}' not found", path);
+            return;
+        };
+        if already_frozen {
+            log::warn!("[FREEZE] Group '{}' is already frozen", path);
+            return;
+        }
+        // The recorder and player sit right before the link synth, so groups
+        // without one (main) cannot be frozen
+        let Some(link_node) = link_node else {
+            log::warn!("[FREEZE] Group '{}' has no link synth and cannot be frozen", path);
+            return;
+        };
+        if !running {
+            log::warn!("[FREEZE] Cannot freeze '{}' while the transport is stopped", path);
+            return;
+        }
+
+        let start_beat = (current_beat / beats_per_bar).ceil() * beats_per_bar;
+        let end_beat = start_beat + bars as f64 * beats_per_bar;
+        let duration_seconds = crate::freeze::freeze_duration_seconds(bars, beats_per_bar, tempo);
+
+        let (buffer_id, recorder_node_id) = self.shared.with_state_write(|state| {
+            (state.allocate_buffer_id(), state.allocate_synth_node())
+        });
+
+        if let Err(e) = self.osc_sender.b_alloc(
+            OscTiming::Now,
+            BufNum::new(buffer_id),
+            crate::freeze::freeze_buffer_frames(duration_seconds, self.server_sample_rate()),
+            crate::freeze::freeze_buffer_channels(),
+            current_beat,
+        ) {
+            log::error!("[FREEZE] Failed to allocate buffer for '{}': {}", path, e);
+            return;
+        }
+
+        let recorder = OscPacket::Message(OscMessage {
+            addr: "/s_new".to_string(),
+            args: vec![
+                OscType::String(crate::freeze::FREEZE_RECORDER_SYNTHDEF.to_string()),
+                OscType::Int(recorder_node_id),
+                OscType::Int(AddAction::AddBefore.into()),
+                OscType::Int(link_node),
+                OscType::String("inbus".to_string()),
+                OscType::Float(audio_bus as f32),
+                OscType::String("bufnum".to_string()),
+                OscType::Float(buffer_id as f32),
+            ],
+        });
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(start_beat),
+            vec![recorder],
+            &self.transport,
+            now,
+        ) {
+            log::error!("[FREEZE] Failed to start recorder for '{}': {}", path, e);
+            let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(buffer_id), current_beat);
+            return;
+        }
+
+        log::info!(
+            "[FREEZE] Recording '{}' for {} bars (beats {:.1} - {:.1}) into buffer {}",
+            path, bars, start_beat, end_beat, buffer_id
+        );
+
+        self.shared.with_state_write(|state| {
+            if let Some(group) = state.groups.get_mut(path) {
+                group.freeze = Some(GroupFreeze {
+                    bars,
+                    buffer_id,
+                    start_beat,
+                    end_beat,
+                    duration_seconds,
+                    recorder_node_id,
+                    player_node_id: None,
+                    paused_node_ids: Vec::new(),
+                });
+            }
+            state.bump_version();
+        });
+    }
+
+    /// Swap finished recordings for their looping players.
+    ///
+    /// The swap is sent as a timed bundle at the freeze's end beat as soon as
+    /// that beat is within the scheduling lookahead.
+    fn process_group_freezes(&mut self, current_beat: f64) {
+        let (due, lookahead_beats) = self.shared.with_state_read(|state| {
+            let lookahead_beats = LOOKAHEAD_MS as f64 / 1000.0 * state.tempo / 60.0;
+            let due: Vec<String> = state
+                .groups
+                .values()
+                .filter(|g| {
+                    g.freeze
+                        .as_ref()
+                        .is_some_and(|f| !f.is_frozen() && f.end_beat - current_beat <= lookahead_beats)
+                })
+                .map(|g| g.path.clone())
+                .collect();
+            (due, lookahead_beats)
+        });
+        if due.is_empty() {
+            return;
+        }
+        log::debug!("[FREEZE] {} freezes due (lookahead {:.2} beats)", due.len(), lookahead_beats);
+
+        let now = Instant::now();
+        for path in due {
+            let swap = self.shared.with_state_write(|state| {
+                let group = state.groups.get(&path)?;
+                let freeze = group.freeze.clone()?;
+                let link_node = group.link_synth_node_id?;
+                let audio_bus = group.audio_bus;
+
+                // Everything that feeds the group bus: its synths, effects and child groups
+                let mut paused: Vec<i32> = group.synth_node_ids.clone();
+                paused.extend(
+                    state
+                        .effects
+                        .values()
+                        .filter(|e| e.group_path == path)
+                        .filter_map(|e| e.node_id),
+                );
+                paused.extend(
+                    state
+                        .groups
+                        .values()
+                        .filter(|g| g.parent_path.as_deref() == Some(path.as_str()))
+                        .filter_map(|g| g.node_id),
+                );
+
+                let player_node_id = state.allocate_synth_node();
+                if let Some(f) = state.groups.get_mut(&path).and_then(|g| g.freeze.as_mut()) {
+                    f.player_node_id = Some(player_node_id);
+                    f.paused_node_ids = paused.clone();
+                }
+                state.bump_version();
+                Some((freeze, link_node, audio_bus, player_node_id, paused))
+            });
+            let Some((freeze, link_node, audio_bus, player_node_id, paused)) = swap else {
+                continue;
+            };
+
+            let mut packets = vec![
+                OscPacket::Message(OscMessage {
+                    addr: "/n_free".to_string(),
+                    args: vec![OscType::Int(freeze.recorder_node_id)],
+                }),
+                OscPacket::Message(OscMessage {
+                    addr: "/s_new".to_string(),
+                    args: vec![
+                        OscType::String(crate::freeze::FREEZE_PLAYER_SYNTHDEF.to_string()),
+                        OscType::Int(player_node_id),
+                        OscType::Int(AddAction::AddBefore.into()),
+                        OscType::Int(link_node),
+                        OscType::String("out".to_string()),
+                        OscType::Float(audio_bus as f32),
+                        OscType::String("bufnum".to_string()),
+                        OscType::Float(freeze.buffer_id as f32),
+                        OscType::String("dur".to_string()),
+                        OscType::Float(freeze.duration_seconds as f32),
+                    ],
+                }),
+            ];
+            packets.extend(paused.iter().map(|&node_id| {
+                OscPacket::Message(OscMessage {
+                    addr: "/n_run".to_string(),
+                    args: vec![OscType::Int(node_id), OscType::Int(0)],
+                })
+            }));
+
+            if let Err(e) = self.osc_sender.send_bundle_at_beat(
+                BeatTime::from_float(freeze.end_beat),
+                packets,
+                &self.transport,
+                now,
+            ) {
+                log::error!("[FREEZE] Failed to swap in bounce for '{}': {}", path, e);
+                continue;
+            }
+            log::info!(
+                "[FREEZE] '{}' frozen at beat {:.1} ({} nodes paused)",
+                path, freeze.end_beat, paused.len()
+            );
+        }
+    }
+
+    /// Create or update a looper's configuration.
+    ///
+    /// Changing the length or input takes effect on the next recording; gain
+    /// changes apply to a playing loop immediately.
+    fn handle_upsert_looper(&mut self, name: String, group_path: String, input: u32, bars: u32, gain: f32) {
+        let generation = self.shared.with_state_read(|s| s.reload_generation);
+        let player = self.shared.with_state_write(|state| {
+            let looper = state
+                .loopers
+                .entry(name.clone())
+                .or_insert_with(|| LooperState::new(name.clone(), group_path.clone()));
+            looper.group_path = group_path;
+            looper.input = input.max(1);
+            looper.bars = bars.clamp(1, crate::looper::MAX_LOOPER_BARS);
+            looper.gain = gain;
+            looper.generation = generation;
+            let player = looper.player_node_id;
+            state.bump_version();
+            player
+        });
+        if let Some(node_id) = player {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("amp", gain)], current_beat);
+        }
+    }
+
+    /// Create or replace a hook. A hook a reload defines again at the same
+    /// beats keeps its schedule, so it doesn't fire twice.
+    fn handle_set_hook(
+        &mut self,
+        name: String,
+        action: HookAction,
+        beat: f64,
+        every: Option<f64>,
+        timeout: Duration,
+        source_location: crate::api::context::SourceLocation,
+    ) {
+        self.shared.with_state_write(|state| {
+            let generation = state.reload_generation;
+            let next_beat = next_hook_beat(beat, every, state.current_beat);
+            let hook = state.hooks.entry(name.clone()).or_insert_with(|| HookState {
+                name,
+                action: action.clone(),
+                beat,
+                every,
+                timeout,
+                generation,
+                next_beat,
+                fired: 0,
+                last_fired_beat: None,
+                source_location: source_location.clone(),
+            });
+            if hook.beat != beat || hook.every != every {
+                hook.next_beat = next_beat;
+            }
+            hook.action = action;
+            hook.beat = beat;
+            hook.every = every;
+            hook.timeout = timeout;
+            hook.generation = generation;
+            hook.source_location = source_location;
+            state.bump_version();
+        });
+    }
+
+    /// Hand hooks due within the lookahead to worker threads, timed to their
+    /// beat. Hooks the transport jumped past are skipped; ones it jumped back
+    /// before fire again. In virtual time (simulation) they are only counted.
+    fn process_hooks(&mut self, current_beat: f64) {
+        let due = self.shared.with_state_write(|state| {
+            let lookahead_beats = LOOKAHEAD_MS as f64 / 1000.0 * state.tempo / 60.0;
+            let mut due = Vec::new();
+            for hook in state.hooks.values_mut() {
+                let jumped_back = hook.last_fired_beat.is_some_and(|b| b > current_beat + lookahead_beats);
+                let jumped_past = hook.next_beat.is_some_and(|b| b < current_beat - lookahead_beats);
+                if jumped_back || jumped_past {
+                    hook.next_beat = next_hook_beat(hook.beat, hook.every, current_beat);
+                }
+                if jumped_back {
+                    hook.last_fired_beat = None;
+                }
+
+                while let Some(beat) = hook.next_beat.filter(|b| b - current_beat <= lookahead_beats) {
+                    due.push((hook.name.clone(), hook.action.clone(), beat, hook.timeout));
+                    hook.fired += 1;
+                    hook.last_fired_beat = Some(beat);
+                    hook.next_beat = hook.every.filter(|e| *e > 0.0).map(|e| beat + e);
+                }
+            }
+            if !due.is_empty() {
+                state.bump_version();
+            }
+            due
+        });
+
+        let now = Instant::now();
+        for (name, action, beat, timeout) in due {
+            if self.transport.is_virtual() {
+                log::debug!("[HOOK] '{}' ({}) due at beat {:.2}, not run in virtual time", name, action.describe(), beat);
+                continue;
+            }
+            let (at, _) = self.transport.beat_to_timestamp_and_instant(BeatTime::from_float(beat), now);
+            log::debug!("[HOOK] '{}' ({}) fires at beat {:.2}", name, action.describe(), beat);
+            spawn_hook(name, action, at, timeout);
+        }
+    }
+
+    /// Create or update a return channel.
+    ///
+    /// Gain changes apply to the running input synth; a new group, input or
+    /// channel count restarts it.
+    fn handle_upsert_return_channel(&mut self, name: String, group_path: String, input: u32, channels: u32, gain: f32) {
+        let (input, channels) = (input.max(1), channels.clamp(1, 2));
+        let generation = self.shared.with_state_read(|s| s.reload_generation);
+        let (running, restart) = self.shared.with_state_write(|state| {
+            let channel = state
+                .return_channels
+                .entry(name.clone())
+                .or_insert_with(|| ReturnChannelState {
+                    name: name.clone(),
+                    group_path: group_path.clone(),
+                    input,
+                    channels,
+                    gain,
+                    generation,
+                    node_id: None,
+                });
+            let restart = channel.group_path != group_path || channel.input != input || channel.channels != channels;
+            channel.group_path = group_path;
+            channel.input = input;
+            channel.channels = channels;
+            channel.gain = gain;
+            channel.generation = generation;
+            let running = channel.node_id;
+            if restart {
+                channel.node_id = None;
+            }
+            state.bump_version();
+            (running, restart)
+        });
+
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        match running {
+            Some(node_id) if !restart => {
+                let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("amp", gain)], current_beat);
+            }
+            Some(node_id) => {
+                let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(node_id), current_beat);
+                self.start_return_channel(&name);
+            }
+            None => self.start_return_channel(&name),
+        }
+    }
+
+    /// Start the input synth of a return channel at the head of its group,
+    /// ahead of the group's effects, meter and fader.
+    ///
+    /// A group that does not exist yet is retried when the groups are finalized.
+    fn start_return_channel(&mut self, name: &str) {
+        let target = self.shared.with_state_write(|state| {
+            let channel = state.return_channels.get(name)?.clone();
+            let group = state.groups.get(&channel.group_path)?;
+            let (group_node, audio_bus) = (group.node_id?, group.audio_bus);
+            Some((channel, group_node, audio_bus, state.allocate_synth_node()))
+        });
+        let Some((channel, group_node, audio_bus, node_id)) = target else {
+            log::debug!("[RETURN] Group of return channel '{}' not created yet", name);
+            return;
+        };
+
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if let Err(e) = self.osc_sender.s_new(
+            OscTiming::Now,
+            crate::return_channel::return_synthdef(channel.channels),
+            NodeId::new(node_id),
+            AddAction::AddToHead,
+            Target::from(group_node),
+            &[
+                ("out", audio_bus as f32),
+                ("input", (channel.input - 1) as f32),
+                ("amp", channel.gain),
+            ],
+            current_beat,
+        ) {
+            log::error!("[RETURN] Failed to start return channel '{}': {}", name, e);
+            return;
+        }
+        self.shared.with_state_write(|state| {
+            if let Some(c) = state.return_channels.get_mut(name) {
+                c.node_id = Some(node_id);
+            }
+            state.bump_version();
+        });
+        log::info!(
+            "[RETURN] '{}' returns input {} ({} ch) into '{}'",
+            name, channel.input, channel.channels, channel.group_path
+        );
+    }
+
+    /// Record a new loop from the next bar line.
+    ///
+    /// A playing loop keeps playing until the new recording starts.
+    fn handle_looper_record(&mut self, name: &str) {
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+
+        let info = self.shared.with_state_read(|state| {
+            let looper = state.loopers.get(name)?;
+            let group = state.groups.get(&looper.group_path)?;
+            Some((
+                looper.clone(),
+                group.node_id,
+                state.tempo,
+                state.time_signature.beats_per_bar(),
+                state.transport_running,
+            ))
+        });
+        let Some((looper, group_node, tempo, beats_per_bar, running)) = info else {
+            log::warn!("[LOOPER] Looper '{}' (or its group) not found", name);
+            return;
+        };
+        let Some(group_node) = group_node else {
+            log::warn!("[LOOPER] Group '{}' of looper '{}' has no node yet", looper.group_path, name);
+            return;
+        };
+        if looper.status == LooperStatus::Recording {
+            log::warn!("[LOOPER] '{}' is already recording", name);
+            return;
+        }
+        if !running {
+            log::warn!("[LOOPER] Cannot record '{}' while the transport is stopped", name);
+            return;
+        }
+
+        let start_beat = (current_beat / beats_per_bar).ceil() * beats_per_bar;
+        let loop_beats = looper.bars as f64 * beats_per_bar;
+        let duration_seconds = crate::looper::looper_duration_seconds(looper.bars, beats_per_bar, tempo);
+        let frames = crate::looper::looper_buffer_frames(duration_seconds, self.server_sample_rate());
+
+        let mut packets = Vec::new();
+        for node_id in looper.player_node_id.iter().chain(looper.recorder_node_id.iter()) {
+            packets.push(OscPacket::Message(OscMessage {
+                addr: "/n_free".to_string(),
+                args: vec![OscType::Int(*node_id)],
+            }));
+        }
+
+        // Reuse the buffer unless the new loop does not fit
+        let buffer_id = match looper.buffer_id {
+            Some(buffer_id) if looper.buffer_frames >= frames => buffer_id,
+            old => {
+                if let Some(old) = old {
+                    packets.push(OscPacket::Message(OscMessage {
+                        addr: "/b_free".to_string(),
+                        args: vec![OscType::Int(old)],
+                    }));
+                }
+                let buffer_id = self.shared.with_state_write(|state| state.allocate_buffer_id());
+                if let Err(e) = self.osc_sender.b_alloc(OscTiming::Now, BufNum::new(buffer_id), frames, 1, current_beat) {
+                    log::error!("[LOOPER] Failed to allocate buffer for '{}': {}", name, e);
+                    return;
+                }
+                buffer_id
+            }
+        };
+
+        let recorder_node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
+        packets.push(OscPacket::Message(OscMessage {
+            addr: "/s_new".to_string(),
+            args: vec![
+                OscType::String(crate::looper::LOOPER_RECORDER_SYNTHDEF.to_string()),
+                OscType::Int(recorder_node_id),
+                OscType::Int(AddAction::AddToHead.into()),
+                OscType::Int(group_node),
+                OscType::String("input".to_string()),
+                OscType::Float((looper.input - 1) as f32),
+                OscType::String("bufnum".to_string()),
+                OscType::Float(buffer_id as f32),
+                OscType::String("pre".to_string()),
+                OscType::Float(0.0),
+            ],
+        }));
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(start_beat),
+            packets,
+            &self.transport,
+            now,
+        ) {
+            log::error!("[LOOPER] Failed to start recording '{}': {}", name, e);
+            return;
+        }
+
+        log::info!(
+            "[LOOPER] Recording '{}' from input {} for {} bars (beats {:.1} - {:.1})",
+            name, looper.input, looper.bars, start_beat, start_beat + loop_beats
+        );
+
+        self.shared.with_state_write(|state| {
+            if let Some(l) = state.loopers.get_mut(name) {
+                if l.buffer_id != Some(buffer_id) {
+                    l.buffer_frames = frames;
+                }
+                l.buffer_id = Some(buffer_id);
+                l.status = LooperStatus::Recording;
+                l.anchor_beat = start_beat;
+                l.loop_beats = loop_beats;
+                l.duration_seconds = duration_seconds;
+                l.record_start_beat = start_beat;
+                l.record_end_beat = start_beat + loop_beats;
+                l.recorder_node_id = Some(recorder_node_id);
+                l.player_node_id = None;
+            }
+            state.bump_version();
+        });
+    }
+
+    /// Record one more pass on top of a playing loop, from the loop's next start.
+    ///
+    /// An empty looper records its first loop instead.
+    fn handle_looper_overdub(&mut self, name: &str) {
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+
+        let info = self.shared.with_state_read(|state| {
+            let looper = state.loopers.get(name)?;
+            let group_node = state.groups.get(&looper.group_path).and_then(|g| g.node_id);
+            Some((looper.clone(), group_node))
+        });
+        let Some((looper, group_node)) = info else {
+            log::warn!("[LOOPER] Looper '{}' not found", name);
+            return;
+        };
+        match looper.status {
+            LooperStatus::Empty => return self.handle_looper_record(name),
+            LooperStatus::Recording | LooperStatus::Overdubbing => {
+                log::warn!("[LOOPER] '{}' is already recording", name);
+                return;
+            }
+            LooperStatus::Playing => {}
+        }
+        let (Some(group_node), Some(buffer_id)) = (group_node, looper.buffer_id) else {
+            return;
+        };
+
+        let start_beat = crate::looper::next_loop_start(looper.anchor_beat, looper.loop_beats, current_beat);
+        let recorder_node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
+        let recorder = OscPacket::Message(OscMessage {
+            addr: "/s_new".to_string(),
+            args: vec![
+                OscType::String(crate::looper::LOOPER_RECORDER_SYNTHDEF.to_string()),
+                OscType::Int(recorder_node_id),
+                OscType::Int(AddAction::AddToHead.into()),
+                OscType::Int(group_node),
+                OscType::String("input".to_string()),
+                OscType::Float((looper.input - 1) as f32),
+                OscType::String("bufnum".to_string()),
+                OscType::Float(buffer_id as f32),
+                OscType::String("pre".to_string()),
+                OscType::Float(1.0),
+            ],
+        });
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(start_beat),
+            vec![recorder],
+            &self.transport,
+            now,
+        ) {
+            log::error!("[LOOPER] Failed to start overdub on '{}': {}", name, e);
+            return;
+        }
+
+        log::info!(
+            "[LOOPER] Overdubbing '{}' (beats {:.1} - {:.1})",
+            name, start_beat, start_beat + looper.loop_beats
+        );
+
+        self.shared.with_state_write(|state| {
+            if let Some(l) = state.loopers.get_mut(name) {
+                l.status = LooperStatus::Overdubbing;
+                l.record_start_beat = start_beat;
+                l.record_end_beat = start_beat + l.loop_beats;
+                l.recorder_node_id = Some(recorder_node_id);
+            }
+            state.bump_version();
+        });
+    }
+
+    /// Stop a looper and release its buffer.
+    fn handle_looper_clear(&mut self, name: &str) {
+        let looper = self.shared.with_state_write(|state| {
+            let looper = state.loopers.get_mut(name)?;
+            let cleared = looper.clone();
+            looper.status = LooperStatus::Empty;
+            looper.buffer_id = None;
+            looper.buffer_frames = 0;
+            looper.recorder_node_id = None;
+            looper.player_node_id = None;
+            state.bump_version();
+            Some(cleared)
+        });
+        let Some(looper) = looper else {
+            log::warn!("[LOOPER] Looper '{}' not found", name);
+            return;
+        };
+        self.release_looper_nodes(&looper);
+        log::info!("[LOOPER] '{}' cleared", name);
+    }
+
+    /// Free a looper's nodes and buffer.
+    ///
+    /// Sent no earlier than a pending recording start, so a recorder that is
+    /// still scheduled gets freed too.
+    fn release_looper_nodes(&mut self, looper: &LooperState) {
+        let mut packets: Vec<OscPacket> = looper
+            .player_node_id
+            .iter()
+            .chain(looper.recorder_node_id.iter())
+            .map(|&node_id| {
+                OscPacket::Message(OscMessage {
+                    addr: "/n_free".to_string(),
+                    args: vec![OscType::Int(node_id)],
+                })
+            })
+            .collect();
+        if let Some(buffer_id) = looper.buffer_id {
+            packets.push(OscPacket::Message(OscMessage {
+                addr: "/b_free".to_string(),
+                args: vec![OscType::Int(buffer_id)],
+            }));
+        }
+        if packets.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+        let release_beat = if looper.recorder_node_id.is_some() {
+            looper.record_start_beat.max(current_beat)
+        } else {
+            current_beat
+        };
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(release_beat),
+            packets,
+            &self.transport,
+            now,
+        ) {
+            log::error!("[LOOPER] Failed to release '{}': {}", looper.name, e);
+        }
+    }
+
+    /// Finish recording passes that end within the scheduling lookahead.
+    ///
+    /// A first recording swaps its recorder for the looping player at the
+    /// loop's end; an overdub just frees its recorder.
+    fn process_loopers(&mut self, current_beat: f64) {
+        let due: Vec<LooperState> = self.shared.with_state_read(|state| {
+            let lookahead_beats = LOOKAHEAD_MS as f64 / 1000.0 * state.tempo / 60.0;
+            state
+                .loopers
+                .values()
+                .filter(|l| l.recorder_node_id.is_some() && l.record_end_beat - current_beat <= lookahead_beats)
+                .cloned()
+                .collect()
+        });
+
+        let now = Instant::now();
+        for looper in due {
+            let Some(recorder_node_id) = looper.recorder_node_id else {
+                continue;
+            };
+            let mut packets = vec![OscPacket::Message(OscMessage {
+                addr: "/n_free".to_string(),
+                args: vec![OscType::Int(recorder_node_id)],
+            })];
+
+            let player = if looper.status == LooperStatus::Recording {
+                let target = self.shared.with_state_write(|state| {
+                    let group = state.groups.get(&looper.group_path)?;
+                    let (group_node, audio_bus) = (group.node_id?, group.audio_bus);
+                    Some((group_node, audio_bus, state.allocate_synth_node()))
+                });
+                match (target, looper.buffer_id) {
+                    (Some((group_node, audio_bus, player_node_id)), Some(buffer_id)) => {
+                        packets.push(OscPacket::Message(OscMessage {
+                            addr: "/s_new".to_string(),
+                            args: vec![
+                                OscType::String(crate::looper::LOOPER_PLAYER_SYNTHDEF.to_string()),
+                                OscType::Int(player_node_id),
+                                OscType::Int(AddAction::AddToHead.into()),
+                                OscType::Int(group_node),
+                                OscType::String("out".to_string()),
+                                OscType::Float(audio_bus as f32),
+                                OscType::String("bufnum".to_string()),
+                                OscType::Float(buffer_id as f32),
+                                OscType::String("dur".to_string()),
+                                OscType::Float(looper.duration_seconds as f32),
+                                OscType::String("amp".to_string()),
+                                OscType::Float(looper.gain),
+                            ],
+                        }));
+                        Some(player_node_id)
+                    }
+                    _ => None,
+                }
+            } else {
+                looper.player_node_id
+            };
+
+            if let Err(e) = self.osc_sender.send_bundle_at_beat(
+                BeatTime::from_float(looper.record_end_beat),
+                packets,
+                &self.transport,
+                now,
+            ) {
+                log::error!("[LOOPER] Failed to finish recording '{}': {}", looper.name, e);
+            }
+
+            self.shared.with_state_write(|state| {
+                if let Some(l) = state.loopers.get_mut(&looper.name) {
+                    l.recorder_node_id = None;
+                    l.player_node_id = player;
+                    l.status = if player.is_some() { LooperStatus::Playing } else { LooperStatus::Empty };
+                }
+                state.bump_version();
+            });
+            log::info!("[LOOPER] '{}' looping from beat {:.1}", looper.name, looper.record_end_beat);
+        }
+    }
+
+    /// Resample a group's output into the pads of voice `name`.
+    ///
+    /// The recording starts from the next bar line once the transport runs
+    /// (see `process_resamples`). A resample of the same group and length is
+    /// kept as is, so reloads do not record again; otherwise an unfinished
+    /// take is dropped.
+    fn handle_resample(&mut self, name: String, group_path: String, bars: u32) {
+        let bars = bars.clamp(1, crate::resample::MAX_RESAMPLE_BARS);
+        let (group_exists, previous) = self.shared.with_state_read(|state| {
+            (state.groups.contains_key(&group_path), state.resamples.get(&name).cloned())
+        });
+        if !group_exists {
+            log::warn!("[RESAMPLE] Group '{}' not found", group_path);
+            return;
+        }
+        if let Some(previous) = previous {
+            if previous.group_path == group_path && previous.bars == bars && previous.status != ResampleStatus::Failed {
+                log::debug!("[RESAMPLE] '{}' already resampled from '{}'", name, group_path);
+                return;
+            }
+            let now = Instant::now();
+            let current_beat = self.transport.beat_at(now).to_float();
+            self.release_resample_take(&previous, current_beat, now);
+        }
+
+        self.shared.with_state_write(|state| {
+            state.resamples.insert(
+                name.clone(),
+                ResampleState {
+                    name,
+                    group_path,
+                    bars,
+                    status: ResampleStatus::Waiting,
+                    buffer_id: 0,
+                    recorder_node_id: 0,
+                    start_beat: 0.0,
+                    end_beat: 0.0,
+                    duration_seconds: 0.0,
+                    slices: 0,
+                },
+            );
+            state.bump_version();
+        });
+    }
+
+    /// Start recording a waiting resample from the next bar line.
+    ///
+    /// Returns false while its group has no nodes yet.
+    fn start_resample(&mut self, name: &str) -> bool {
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+
+        let info = self.shared.with_state_read(|state| {
+            let resample = state.resamples.get(name)?;
+            let group = state.groups.get(&resample.group_path)?;
+            // Record right before the link synth, or after the main group for the master
+            let placement = match group.link_synth_node_id {
+                Some(link_node) => (AddAction::AddBefore, link_node),
+                None if group.parent_path.is_none() => (AddAction::AddAfter, group.node_id?),
+                None => return None,
+            };
+            Some((
+                resample.group_path.clone(),
+                resample.bars,
+                placement,
+                group.audio_bus,
+                state.tempo,
+                state.time_signature.beats_per_bar(),
+            ))
+        });
+        let Some((group_path, bars, (add_action, target_node), audio_bus, tempo, beats_per_bar)) = info else {
+            return false;
+        };
+
+        let start_beat = (current_beat / beats_per_bar).ceil() * beats_per_bar;
+        let end_beat = start_beat + bars as f64 * beats_per_bar;
+        let duration_seconds = crate::freeze::freeze_duration_seconds(bars, beats_per_bar, tempo);
+
+        let (buffer_id, recorder_node_id) = self.shared.with_state_write(|state| {
+            (state.allocate_buffer_id(), state.allocate_synth_node())
+        });
+
+        if let Err(e) = self.osc_sender.b_alloc(
+            OscTiming::Now,
+            BufNum::new(buffer_id),
+            crate::freeze::freeze_buffer_frames(duration_seconds, self.server_sample_rate()),
+            crate::freeze::freeze_buffer_channels(),
+            current_beat,
+        ) {
+            log::error!("[RESAMPLE] Failed to allocate buffer for '{}': {}", name, e);
+            self.fail_resample(name);
+            return true;
+        }
+
+        // The freeze recorder captures the bus without touching the live synths
+        let recorder = OscPacket::Message(OscMessage {
+            addr: "/s_new".to_string(),
+            args: vec![
+                OscType::String(crate::freeze::FREEZE_RECORDER_SYNTHDEF.to_string()),
+                OscType::Int(recorder_node_id),
+                OscType::Int(add_action.into()),
+                OscType::Int(target_node),
+                OscType::String("inbus".to_string()),
+                OscType::Float(audio_bus as f32),
+                OscType::String("bufnum".to_string()),
+                OscType::Float(buffer_id as f32),
+            ],
+        });
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(start_beat),
+            vec![recorder],
+            &self.transport,
+            now,
+        ) {
+            log::error!("[RESAMPLE] Failed to start recorder for '{}': {}", name, e);
+            let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(buffer_id), current_beat);
+            self.fail_resample(name);
+            return true;
+        }
+
+        log::info!(
+            "[RESAMPLE] Recording '{}' for {} bars (beats {:.1} - {:.1}) into '{}'",
+            group_path, bars, start_beat, end_beat, name
+        );
+
+        self.shared.with_state_write(|state| {
+            if let Some(r) = state.resamples.get_mut(name) {
+                r.status = ResampleStatus::Recording;
+                r.buffer_id = buffer_id;
+                r.recorder_node_id = recorder_node_id;
+                r.start_beat = start_beat;
+                r.end_beat = end_beat;
+                r.duration_seconds = duration_seconds;
+            }
+            state.bump_version();
+        });
+        true
+    }
+
+    /// Mark a resample as failed.
+    fn fail_resample(&mut self, name: &str) {
+        self.shared.with_state_write(|state| {
+            if let Some(r) = state.resamples.get_mut(name) {
+                r.status = ResampleStatus::Failed;
+            }
+            state.bump_version();
+        });
+    }
+
+    /// Free the recorder and buffer of an unfinished resample take.
+    ///
+    /// Sent no earlier than the take's pending recorder start or buffer
+    /// write, so those are undone too.
+    fn release_resample_take(&mut self, take: &ResampleState, current_beat: f64, now: Instant) {
+        let (release_beat, mut packets) = match take.status {
+            ResampleStatus::Recording => (
+                take.start_beat.max(current_beat),
+                vec![OscPacket::Message(OscMessage {
+                    addr: "/n_free".to_string(),
+                    args: vec![OscType::Int(take.recorder_node_id)],
+                })],
+            ),
+            ResampleStatus::Writing => (take.end_beat.max(current_beat), Vec::new()),
+            ResampleStatus::Waiting | ResampleStatus::Done | ResampleStatus::Failed => return,
+        };
+        packets.push(OscPacket::Message(OscMessage {
+            addr: "/b_free".to_string(),
+            args: vec![OscType::Int(take.buffer_id)],
+        }));
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(release_beat),
+            packets,
+            &self.transport,
+            now,
+        ) {
+            log::error!("[RESAMPLE] Failed to release the take of '{}': {}", take.name, e);
+        }
+    }
+
+    /// Start waiting resamples and have finished recordings written to disk.
+    ///
+    /// The recorder is freed and the buffer written in a bundle timed at the
+    /// recording's end, as soon as that beat is within the scheduling
+    /// lookahead; `handle_resample_written` chops the file once it is written.
+    fn process_resamples(&mut self, current_beat: f64) {
+        let waiting: Vec<String> = self.shared.with_state_read(|state| {
+            state
+                .resamples
+                .values()
+                .filter(|r| r.status == ResampleStatus::Waiting)
+                .map(|r| r.name.clone())
+                .collect()
+        });
+        for name in waiting {
+            if !self.start_resample(&name) {
+                log::debug!("[RESAMPLE] '{}' waits for its group", name);
+            }
+        }
+
+        let due: Vec<ResampleState> = self.shared.with_state_read(|state| {
+            let lookahead_beats = LOOKAHEAD_MS as f64 / 1000.0 * state.tempo / 60.0;
+            state
+                .resamples
+                .values()
+                .filter(|r| r.status == ResampleStatus::Recording && r.end_beat - current_beat <= lookahead_beats)
+                .cloned()
+                .collect()
+        });
+        if due.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        for resample in due {
+            let (raw, _) = crate::resample::recording_paths(&resample.name, resample.buffer_id);
+            let written = raw
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| {
+                    self.osc_sender.send_bundle_at_beat(
+                        BeatTime::from_float(resample.end_beat),
+                        vec![
+                            OscPacket::Message(OscMessage {
+                                addr: "/n_free".to_string(),
+                                args: vec![OscType::Int(resample.recorder_node_id)],
+                            }),
+                            OscPacket::Message(OscMessage {
+                                addr: "/b_write".to_string(),
+                                args: vec![
+                                    OscType::Int(resample.buffer_id),
+                                    OscType::String(raw.to_string_lossy().to_string()),
+                                    OscType::String("wav".to_string()),
+                                    OscType::String("float".to_string()),
+                                    OscType::Int(0),
+                                    OscType::Int(-1),
+                                    OscType::Int(0),
+                                ],
+                            }),
+                        ],
+                        &self.transport,
+                        now,
+                    )
+                });
+            if let Err(e) = &written {
+                log::error!("[RESAMPLE] Failed to write the recording of '{}': {}", resample.name, e);
+            }
+
+            self.shared.with_state_write(|state| {
+                if let Some(r) = state.resamples.get_mut(&resample.name) {
+                    r.status = if written.is_ok() { ResampleStatus::Writing } else { ResampleStatus::Failed };
+                }
+                state.bump_version();
+            });
+        }
+    }
+
+    /// Chop a resample recording scsynth has written to disk into the pads
+    /// of its voice.
+    fn handle_resample_written(&mut self, buffer_id: i32) {
+        let resample = self.shared.with_state_read(|state| {
+            state
+                .resamples
+                .values()
+                .find(|r| r.buffer_id == buffer_id && r.status == ResampleStatus::Writing)
+                .cloned()
+        });
+        let Some(resample) = resample else {
+            return;
+        };
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(buffer_id), current_beat);
+
+        let (raw, out) = crate::resample::recording_paths(&resample.name, buffer_id);
+        let chop = crate::resample::chop(&raw, &out, resample.duration_seconds);
+        let _ = std::fs::remove_file(&raw);
+        let chop = match chop {
+            Ok(chop) => chop,
+            Err(e) => {
+                log::error!("[RESAMPLE] Failed to chop '{}': {}", resample.name, e);
+                self.fail_resample(&resample.name);
+                return;
+            }
+        };
+
+        self.handle_load_sample(resample.name.clone(), out.to_string_lossy().to_string());
+        let kit = crate::resample::slice_kit(&resample.name, chop.sample_rate, &chop.slices);
+        let slices = kit.pads.len();
+        self.shared.with_state_write(|state| {
+            match state.voices.get_mut(&resample.name) {
+                Some(voice) => voice.drum_kit = Some(kit),
+                None => log::warn!("[RESAMPLE] Voice '{}' not found", resample.name),
+            }
+            if let Some(r) = state.resamples.get_mut(&resample.name) {
+                r.status = ResampleStatus::Done;
+                r.slices = slices;
+            }
+            state.bump_version();
+        });
+        log::info!("[RESAMPLE] '{}' chopped into {} slices", resample.name, slices);
+    }
+
+    /// Remove a group's bounce and resume its original synths.
+    fn handle_unfreeze_group(&mut self, path: &str) {
+        let freeze = self.shared.with_state_write(|state| {
+            let freeze = state.groups.get_mut(path).and_then(|g| g.freeze.take());
+            if freeze.is_some() {
+                state.bump_version();
+            }
+            freeze
+        });
+        let Some(freeze) = freeze else {
+            log::warn!("[FREEZE] Group '{}' is not frozen", path);
+            return;
+        };
+
+        // Undo in a bundle that cannot run before the pending recorder start or
+        // player swap, so freshly scheduled nodes are freed too
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+        let (undo_beat, mut packets) = match freeze.player_node_id {
+            Some(player_node_id) => {
+                let mut packets = vec![OscPacket::Message(OscMessage {
+                    addr: "/n_free".to_string(),
+                    args: vec![OscType::Int(player_node_id)],
+                })];
+                packets.extend(freeze.paused_node_ids.iter().map(|&node_id| {
+                    OscPacket::Message(OscMessage {
+                        addr: "/n_run".to_string(),
+                        args: vec![OscType::Int(node_id), OscType::Int(1)],
+                    })
+                }));
+                (freeze.end_beat, packets)
+            }
+            None => {
+                let packets = vec![OscPacket::Message(OscMessage {
+                    addr: "/n_free".to_string(),
+                    args: vec![OscType::Int(freeze.recorder_node_id)],
+                })];
+                (freeze.start_beat, packets)
+            }
+        };
+        packets.push(OscPacket::Message(OscMessage {
+            addr: "/b_free".to_string(),
+            args: vec![OscType::Int(freeze.buffer_id)],
+        }));
+        if let Err(e) = self.osc_sender.send_bundle_at_beat(
+            BeatTime::from_float(undo_beat.max(current_beat)),
+            packets,
+            &self.transport,
+            now,
+        ) {
+            log::error!("[FREEZE] Failed to unfreeze '{}': {}", path, e);
+        }
+
+        log::info!("[FREEZE] '{}' unfrozen, live synthesis resumed", path);
+    }
+
+    fn finalize_groups(&mut self) {
+        // Get all groups that need link synths, along with their last effect node
+        // IMPORTANT: Sort groups so children are processed BEFORE parents.
+        // This ensures parent link synths execute AFTER children have written to the parent bus.
+
+        // Debug: log all groups and their link synth status
+        self.shared.with_state_read(|state| {
+            log::info!("[FINALIZE_GROUPS] Checking {} groups for link synth creation", state.groups.len());
+            for (path, g) in &state.groups {
+                log::info!("[FINALIZE_GROUPS]   '{}': link_synth={:?}, audio_bus={:?}, node_id={:?}",
+                    path, g.link_synth_node_id, g.audio_bus, g.node_id);
+            }
+        });
+
+        let mut groups: Vec<(String, i32, Option<String>, i32, Option<i32>)> = self.shared.with_state_read(|state| {
+            state
+                .groups
+                .values()
+                .filter(|g| g.link_synth_node_id.is_none())
+                .map(|g| {
+                    // Find all effects for this group, sorted by position
+                    let mut group_effects: Vec<_> = state
+                        .effects
+                        .values()
+                        .filter(|e| e.group_path == g.path && e.voice.is_none())
+                        .collect();
+                    group_effects.sort_by_key(|e| e.position);
+
+                    // Get the last effect's node ID (if any)
+                    let last_effect_node = group_effects.last().and_then(|e| e.node_id);
+
+                    (
+                        g.path.clone(),
+                        g.audio_bus,  // audio_bus is always set (i32, not Option)
+                        g.parent_path.clone(),
+                        g.node_id.unwrap_or(0),
+                        last_effect_node,
+                    )
+                })
+                .collect()
+        });
+
+        log::info!("[FINALIZE_GROUPS] {} groups need link synths: {:?}",
+            groups.len(), groups.iter().map(|(p, _, _, _, _)| p.as_str()).collect::<Vec<_>>());
 
         // Sort by path depth (descending) so children are processed before parents.
         // Children have more slashes in their path (e.g., "main/Drums" vs "main").
@@ -4069,12 +8193,63 @@ impl RuntimeThread {
                     if let Some(voice) = state.voices.get_mut(name) {
                         voice.running = false;
                         voice.running_node_id = None;
+                        voice.running_synth = None;
                     }
                 }
                 state.bump_version();
             });
         }
 
+        // Release loopers that the script no longer defines
+        let stale_loopers: Vec<LooperState> = self.shared.with_state_write(|state| {
+            let stale: Vec<String> = state
+                .loopers
+                .values()
+                .filter(|l| l.generation != current_generation)
+                .map(|l| l.name.clone())
+                .collect();
+            stale.iter().filter_map(|name| state.loopers.remove(name)).collect()
+        });
+        for looper in &stale_loopers {
+            log::info!("[RELOAD] Removing stale looper '{}'", looper.name);
+            self.release_looper_nodes(looper);
+        }
+
+        // Forget hooks that the script no longer defines
+        self.shared.with_state_write(|state| {
+            state.hooks.retain(|name, hook| {
+                let keep = hook.generation == current_generation;
+                if !keep {
+                    log::info!("[RELOAD] Removing stale hook '{}'", name);
+                }
+                keep
+            });
+        });
+
+        // Stop return channels that the script no longer defines, and start
+        // the ones whose group was defined after them
+        let (stale_returns, waiting_returns) = self.shared.with_state_write(|state| {
+            let stale: Vec<String> = state
+                .return_channels
+                .values()
+                .filter(|c| c.generation != current_generation)
+                .map(|c| c.name.clone())
+                .collect();
+            let stale: Vec<ReturnChannelState> = stale.iter().filter_map(|name| state.return_channels.remove(name)).collect();
+            let waiting: Vec<String> = state.return_channels.values().filter(|c| c.node_id.is_none()).map(|c| c.name.clone()).collect();
+            (stale, waiting)
+        });
+        for channel in &stale_returns {
+            log::info!("[RELOAD] Removing stale return channel '{}'", channel.name);
+            if let Some(node_id) = channel.node_id {
+                let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(node_id), current_beat);
+            }
+        }
+        for name in &waiting_returns {
+            self.start_return_channel(name);
+        }
+
         // NOTE: Old generation-based cleanup is disabled. We now use diff-based cleanup
         // which only removes entities that were actually removed from the script,
         // not just entities with old generations. This preserves unchanged entities.
@@ -4157,6 +8332,7 @@ impl RuntimeThread {
                 });
             }
             EntityKind::Voice => {
+                self.free_voice_inserts(&id);
                 // Release any active synths for this voice first
                 let (synths_to_release, running_node) = self.shared.with_state_write(|state| {
                     let nodes: Vec<i32> = state
@@ -4206,22 +8382,43 @@ impl RuntimeThread {
                     return;
                 }
 
+                // Release any bounce buffer before the group goes away
+                if self.shared.with_state_read(|state| {
+                    state.groups.get(&id).is_some_and(|g| g.freeze.is_some())
+                }) {
+                    self.handle_unfreeze_group(&id);
+                }
+
                 // Get group info before removal
                 let group_info = self.shared.with_state_read(|state| {
-                    state.groups.get(&id).map(|g| (g.node_id, g.link_synth_node_id))
+                    state.groups.get(&id).map(|g| {
+                        let level = if g.muted { 0.0 } else { g.params.get("amp").copied().unwrap_or(1.0) };
+                        (g.node_id, g.link_synth_node_id, level, state.param_smoothing.mute_seconds())
+                    })
                 });
-                if let Some((node_id, link_node_id)) = group_info {
+                if let Some((node_id, link_node_id, level, seconds)) = group_info {
                     let current_beat = self.transport.beat_at(Instant::now()).to_float();
                     // Free link synth first, then group
-                    if let Some(lnid) = link_node_id {
-                        let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(lnid), current_beat);
-                    }
-                    if let Some(nid) = node_id {
-                        let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(nid), current_beat);
+                    let nodes: Vec<i32> = link_node_id.into_iter().chain(node_id).collect();
+                    match link_node_id {
+                        Some(link_node) if seconds > 0.0 => {
+                            // Fade the group out of its parent's mix, then free it
+                            for (offset, value) in crate::smoothing::slew_steps(level, 0.0, seconds) {
+                                let _ = self.osc_sender.send_bundle_after(offset, vec![n_set_packet(link_node, &[("amp", value)])], current_beat);
+                            }
+                            let _ = self.osc_sender.send_bundle_after(seconds, vec![n_free_packet(&nodes)], current_beat);
+                        }
+                        _ => {
+                            for nid in nodes {
+                                let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(nid), current_beat);
+                            }
+                        }
                     }
                 }
                 self.shared.with_state_write(|state| {
                     state.groups.remove(&id);
+                    // Synths inside the group go with it
+                    state.active_synths.retain(|_, synth| !synth.group_paths.contains(&id));
                     state.bump_version();
                 });
             }
@@ -4268,7 +8465,27 @@ impl RuntimeThread {
             return None;
         };
 
-        let synth_def = synth_name.or(default_synth).unwrap_or_else(|| "default".to_string());
+        // Drum kit voices play the pad of the note (the first pad without one)
+        let param = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| *v);
+        let note = param("note")
+            .map(|note| note as u8)
+            .or_else(|| param("freq").map(|freq| pitch::freq_to_midi_note(freq as f64)))
+            .or_else(|| {
+                self.shared.with_state_read(|state| {
+                    state.voices.get(name)?.drum_kit.as_ref()?.pads.first().map(|pad| pad.note)
+                })
+            });
+        let pad_hit = match note.and_then(|note| self.drum_pad_hit(name, note)) {
+            Some(None) => return None,
+            hit => hit.flatten(),
+        };
+
+        let synth_def = pad_hit
+            .as_ref()
+            .map(|hit| hit.synthdef.to_string())
+            .or(synth_name)
+            .or(default_synth)
+            .unwrap_or_else(|| "default".to_string());
         let group = group_path.unwrap_or(default_group);
 
         // Get group node ID and audio bus
@@ -4282,9 +8499,15 @@ impl RuntimeThread {
 
         // Merge params
         let mut all_params: Vec<(String, f32)> = voice_params.into_iter().collect();
-        all_params.push(("amp".to_string(), gain as f32));
-        all_params.push(("out".to_string(), audio_bus as f32));
+        all_params.push(("amp".to_string(), gain as f32 * pad_hit.as_ref().map_or(1.0, |hit| hit.gain)));
+        all_params.push(("out".to_string(), self.voice_out_bus(Some(name), audio_bus) as f32));
+        if let Some(bus) = self.voice_diag_bus(name, &synth_def) {
+            all_params.push((vibelang_dsp::DIAG_BUS_PARAM.to_string(), bus as f32));
+        }
         all_params.extend(params);
+        if let Some(hit) = &pad_hit {
+            all_params.extend(hit.controls.iter().cloned());
+        }
 
         // Debug log the parameters being sent
         log::debug!(
@@ -4315,11 +8538,79 @@ impl RuntimeThread {
             log::error!("Failed to trigger voice '{}': {}", name, e);
             return None;
         }
+        if let Some(hit) = pad_hit {
+            self.choke_drum_pads(name, &hit.chokes, None);
+        }
 
         Some(node_id)
     }
 
-    fn handle_note_on(&mut self, voice_name: &str, note: u8, velocity: u8, duration: Option<f64>) {
+    /// Trigger a voice at the next quantization boundary.
+    ///
+    /// The trigger goes through the regular event path (timed bundle), so it
+    /// lands on the grid exactly like a pattern hit would. Without a usable
+    /// grid (a quantization of zero or less) it fires right away.
+    fn trigger_voice_quantized(
+        &mut self,
+        name: &str,
+        synth_name: Option<String>,
+        group_path: Option<String>,
+        params: Vec<(String, f32)>,
+    ) {
+        let Some(default_group) = self
+            .shared
+            .with_state_read(|state| state.voices.get(name).map(|v| v.group_path.clone()))
+        else {
+            log::warn!("Voice '{}' not found", name);
+            return;
+        };
+
+        let quantization = self.shared.with_state_read(|s| s.quantization_beats);
+        let now = Instant::now();
+        let current_beat = self.transport.beat_at(now).to_float();
+        let next_beat = if quantization > 0.0 {
+            ((current_beat / quantization).ceil() * quantization).max(0.0)
+        } else {
+            current_beat
+        };
+
+        let mut event = BeatEvent::new(next_beat, "trigger")
+            .with_group_path(group_path.unwrap_or(default_group))
+            .with_voice_name(name);
+        event.controls = params;
+        event.synth_override = synth_name;
+
+        log::debug!(
+            "[TRIGGER] Voice '{}' quantized to beat {:.2} (current {:.2})",
+            name,
+            next_beat,
+            current_beat
+        );
+        self.fire_events_bundled(BeatTime::from_float(next_beat), vec![event], now);
+    }
+
+    fn handle_note_on(
+        &mut self,
+        voice_name: &str,
+        note: u8,
+        velocity: u8,
+        duration: Option<f64>,
+        source: NoteSource,
+    ) {
+        let length = duration.map(|beats| self.beats_to_duration(beats));
+
+        if let Some((node_id, channel)) = self.vst_voice_target(voice_name) {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            let _ = self
+                .osc_sender
+                .send_packet(OscTiming::Now, crate::vst::note_on_packet(node_id, channel, note, velocity), current_beat);
+            if let Some(length) = length {
+                let note_off = crate::vst::note_off_packet(node_id, channel, note);
+                let _ = self.osc_sender.send_bundle_after(length.as_secs_f64(), vec![note_off], current_beat);
+            }
+            return;
+        }
+
         // Check if voice is routed to MIDI output
         let midi_output_info = self.shared.with_state_read(|state| {
             if let Some(voice) = state.voices.get(voice_name) {
@@ -4401,13 +8692,13 @@ impl RuntimeThread {
 
             let midi_event = crate::midi::QueuedMidiEvent::note_on(channel, note, velocity);
             let _ = event_tx.send(midi_event);
-            log::debug!("[MIDI_OUT] Voice '{}' note_on: note={}, vel={}, ch={}", voice_name, note, velocity, channel + 1);
+            log::debug!("[MIDI_OUT] Voice '{}' note_on: {} (vel {}) ch={}", voice_name, pitch::note_name(note), velocity, channel + 1);
 
             // Track active MIDI notes for note-off (using negative "node_id" as marker)
             self.shared.with_state_write(|state| {
                 if let Some(voice) = state.voices.get_mut(voice_name) {
                     // Use -1 as a marker for MIDI notes (no actual SuperCollider node)
-                    voice.active_notes.entry(note).or_default().push(-1);
+                    voice.track_note(note, -1, NoteOrigin::new(Instant::now(), source, length));
                 }
             });
 
@@ -4421,6 +8712,7 @@ impl RuntimeThread {
                         voice_name: voice_name.to_string(),
                         note,
                         node_id: Some(-1), // Marker for MIDI note
+                        gate_scheduled: false,
                     });
                 });
             }
@@ -4432,8 +8724,10 @@ impl RuntimeThread {
             if let Some(voice) = state.voices.get(voice_name) {
                 // Count total active voices across all notes
                 let active_count: usize = voice.active_notes.values().map(|v| v.len()).sum();
+                // Halved while over the CPU budget
+                let polyphony = state.effective_polyphony(voice);
 
-                if voice.polyphony > 0 && active_count >= voice.polyphony as usize {
+                if polyphony > 0 && active_count >= polyphony as usize {
                     // Find the oldest voice (lowest node_id) to steal
                     let oldest_node = voice.active_notes.iter()
                         .flat_map(|(n, ids)| ids.iter().map(move |id| (*n, *id)))
@@ -4442,7 +8736,7 @@ impl RuntimeThread {
                     if let Some((steal_note, steal_node_id)) = oldest_node {
                         log::debug!(
                             "[VOICE_STEAL] Voice '{}' at polyphony limit ({}), stealing node {} (note {})",
-                            voice_name, voice.polyphony, steal_node_id, steal_note
+                            voice_name, polyphony, steal_node_id, steal_note
                         );
                         return Some((steal_note, steal_node_id));
                     }
@@ -4460,7 +8754,7 @@ impl RuntimeThread {
         // Full SFZ support will come later
         let params = vec![
             ("note".to_string(), note as f32),
-            ("freq".to_string(), 440.0 * 2.0_f32.powf((note as f32 - 69.0) / 12.0)),
+            ("freq".to_string(), pitch::note_to_freq(note as f64) as f32),
             ("velocity".to_string(), velocity as f32 / 127.0),
             ("gate".to_string(), 1.0),
         ];
@@ -4469,21 +8763,24 @@ impl RuntimeThread {
             // Track the active note for later note-off
             self.shared.with_state_write(|state| {
                 if let Some(voice) = state.voices.get_mut(voice_name) {
-                    voice.active_notes.entry(note).or_default().push(node_id);
-                    log::debug!("[NOTE_ON] Voice '{}' note {} -> node {}", voice_name, note, node_id);
+                    voice.track_note(note, node_id, NoteOrigin::new(Instant::now(), source, length));
+                    log::debug!("[NOTE_ON] Voice '{}' note {} -> node {}", voice_name, pitch::note_name(note), node_id);
                 }
             });
 
             // Schedule note-off if duration specified
             if let Some(dur) = duration {
-                let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                let now = Instant::now();
+                let current_beat = self.transport.beat_at(now).to_float();
                 let off_beat = current_beat + dur;
+                let gate_scheduled = self.schedule_gate_off(node_id, off_beat, now);
                 self.shared.with_state_write(|state| {
                     state.scheduled_note_offs.push(ScheduledNoteOff {
                         beat: off_beat,
                         voice_name: voice_name.to_string(),
                         note,
                         node_id: Some(node_id),
+                        gate_scheduled,
                     });
                 });
             }
@@ -4491,6 +8788,14 @@ impl RuntimeThread {
     }
 
     fn handle_note_off(&mut self, voice_name: &str, note: u8, specific_node_id: Option<i32>) {
+        if let Some((node_id, channel)) = self.vst_voice_target(voice_name) {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            let _ = self
+                .osc_sender
+                .send_packet(OscTiming::Now, crate::vst::note_off_packet(node_id, channel, note), current_beat);
+            return;
+        }
+
         // Check if this is a MIDI note (node_id == -1 or -2) or voice is routed to MIDI output
         // -1 = direct MIDI path (from handle_note_on) - we need to send MIDI note-off
         // -2 = SC-managed MIDI path (from fire_events_bundled) - synth already sends MIDI via OSC
@@ -4565,34 +8870,7 @@ impl RuntimeThread {
             log::debug!("[NOTE_OFF] Releasing specific node {} for voice '{}'", node_id, voice_name);
 
             // Remove from tracking BEFORE sending gate=0, so fades don't try to update it
-            // Also cancel any scheduled note-off for this specific node
-            let voice_name_owned = voice_name.to_string();
-            self.shared.with_state_write(|state| {
-                state.active_synths.remove(&node_id);
-                state.pending_nodes.remove(&node_id);
-                if let Some(voice) = state.voices.get_mut(voice_name) {
-                    // Remove this specific node from the note's node list
-                    if let Some(node_ids) = voice.active_notes.get_mut(&note) {
-                        node_ids.retain(|&id| id != node_id);
-                        if node_ids.is_empty() {
-                            voice.active_notes.remove(&note);
-                        }
-                    }
-                }
-                // Cancel any scheduled note-off for this specific node (prevents orphaned note-offs)
-                state.scheduled_note_offs.retain(|entry| {
-                    let should_remove = entry.voice_name == voice_name_owned
-                        && entry.note == note
-                        && entry.node_id == Some(node_id);
-                    if should_remove {
-                        log::debug!(
-                            "[NOTE_OFF] Canceling scheduled note-off: voice='{}' note={} node={} beat={:.2}",
-                            entry.voice_name, entry.note, node_id, entry.beat
-                        );
-                    }
-                    !should_remove
-                });
-            });
+            self.forget_released_node(voice_name, note, node_id);
 
             let current_beat = self.transport.beat_at(Instant::now()).to_float();
             let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("gate", 0.0f32)], current_beat);
@@ -4639,9 +8917,20 @@ impl RuntimeThread {
         id: String,
         synthdef: String,
         group_path: String,
-        params: std::collections::HashMap<String, f32>,
+        mut params: std::collections::HashMap<String, f32>,
+        vst_plugin: Option<String>,
         source_location: crate::api::context::SourceLocation,
     ) {
+        // VST effects run in the VST host synth and take plugin parameters
+        let synthdef = if vst_plugin.is_some() {
+            crate::vst::VST_EFFECT_SYNTHDEF.to_string()
+        } else {
+            for (param, value) in params.iter_mut() {
+                *value = self.clamp_synth_param(&synthdef, param, *value);
+            }
+            synthdef
+        };
+
         // Check if effect already exists with the same synthdef
         let existing_effect = self.shared.with_state_read(|state| {
             state.effects.get(&id).map(|e| {
@@ -4650,15 +8939,21 @@ impl RuntimeThread {
                     e.synthdef_name.clone(),
                     e.group_path.clone(),
                     e.params.clone(),
+                    e.vst_plugin.clone(),
+                    e.voice.is_some(),
                 )
             })
         });
 
-        if let Some((existing_node_id, existing_synthdef, existing_group, existing_params)) =
+        // Node of an effect that only changed group, moved instead of recreated
+        let mut node_to_move = None;
+        if let Some((existing_node_id, existing_synthdef, existing_group, existing_params, existing_vst, was_insert)) =
             existing_effect
         {
+            // An insert that becomes a group effect is recreated in its new place
+            let same_source = existing_synthdef == synthdef && existing_vst == vst_plugin && !was_insert;
             // Effect already exists - check if we can just update it
-            if existing_synthdef == synthdef && existing_group == group_path {
+            if same_source && existing_group == group_path {
                 // Same synthdef and group - just update generation and params
                 log::debug!(
                     "[EFFECT] Effect '{}' already exists, updating generation and params",
@@ -4666,7 +8961,11 @@ impl RuntimeThread {
                 );
 
                 // Update params that changed
-                if let Some(node_id) = existing_node_id {
+                if let (Some(node_id), true) = (existing_node_id, vst_plugin.is_some()) {
+                    let changed: Vec<_> =
+                        params.iter().filter(|(param, value)| existing_params.get(*param) != Some(*value)).collect();
+                    self.send_vst_params(node_id, crate::vst::VST_EFFECT_UGEN_INDEX, changed);
+                } else if let Some(node_id) = existing_node_id {
                     let current_beat = self.transport.beat_at(Instant::now()).to_float();
                     for (param, value) in &params {
                         if existing_params.get(param) != Some(value) {
@@ -4692,18 +8991,22 @@ impl RuntimeThread {
                 return;
             }
 
-            // Different synthdef or group - need to recreate
-            log::info!(
-                "[EFFECT] Effect '{}' group changed from '{}' to '{}' - will recreate",
-                id, existing_group, group_path
-            );
-            if let Some(nid) = existing_node_id {
+            if same_source && existing_node_id.is_some() {
+                // Same synthdef in another group - move it so its tail isn't cut
+                log::info!(
+                    "[EFFECT] Effect '{}' group changed from '{}' to '{}' - will move",
+                    id, existing_group, group_path
+                );
+                node_to_move = existing_node_id.map(|node_id| (node_id, existing_params));
+            } else if let Some(nid) = existing_node_id {
+                // Different synthdef - need to recreate
                 log::info!(
-                    "[EFFECT] Freeing old node {} for effect '{}'",
-                    nid, id
+                    "[EFFECT] Effect '{}' changed synthdef - freeing old node {}",
+                    id, nid
                 );
                 let current_beat = self.transport.beat_at(Instant::now()).to_float();
                 let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(nid), current_beat);
+                self.open_vst_nodes.remove(&nid);
             }
         }
 
@@ -4717,7 +9020,7 @@ impl RuntimeThread {
                 let mut group_effects: Vec<_> = state
                     .effects
                     .values()
-                    .filter(|e| e.group_path == group_path && e.id != id)
+                    .filter(|e| e.group_path == group_path && e.id != id && e.voice.is_none())
                     .collect();
                 group_effects.sort_by_key(|e| e.position);
 
@@ -4759,7 +9062,9 @@ impl RuntimeThread {
             ("__fx_bus_in".to_string(), bus_in as f32),
             ("__fx_bus_out".to_string(), bus_out as f32),
         ];
-        controls.extend(params.iter().map(|(k, v)| (k.clone(), *v)));
+        if vst_plugin.is_none() {
+            controls.extend(params.iter().map(|(k, v)| (k.clone(), *v)));
+        }
 
         // Create the effect synth with proper ordering
         // - If there are existing effects, add AFTER the last one
@@ -4777,6 +9082,38 @@ impl RuntimeThread {
             (AddAction::AddToTail, Target::from(target_node_id.unwrap()))
         };
 
+        if let Some((node_id, existing_params)) = node_to_move {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            if let Err(e) = self.osc_sender.n_move(OscTiming::Now, NodeId::new(node_id), add_action, target, current_beat) {
+                log::error!("[EFFECT] Failed to move effect '{}': {}", id, e);
+            }
+            let changed: Vec<_> =
+                params.iter().filter(|(param, value)| existing_params.get(*param) != Some(*value)).collect();
+            let mut controls = vec![("__fx_bus_in", bus_in as f32), ("__fx_bus_out", bus_out as f32)];
+            if vst_plugin.is_some() {
+                self.send_vst_params(node_id, crate::vst::VST_EFFECT_UGEN_INDEX, changed);
+            } else {
+                controls.extend(changed.into_iter().map(|(param, value)| (param.as_str(), *value)));
+            }
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &controls, current_beat);
+
+            let generation = self.shared.with_state_read(|s| s.reload_generation);
+            self.shared.with_state_write(|state| {
+                if let Some(effect) = state.effects.get_mut(&id) {
+                    effect.group_path = group_path.clone();
+                    effect.bus_in = bus_in;
+                    effect.bus_out = bus_out;
+                    effect.position = next_position;
+                    effect.params = params;
+                    effect.generation = generation;
+                    effect.source_location = source_location;
+                }
+                state.bump_version();
+            });
+            log::info!("[EFFECT] Moved effect '{}' (node {}) to group '{}'", id, node_id, group_path);
+            return;
+        }
+
         log::debug!(
             "[EFFECT] Adding effect '{}' ({}) to group '{}' at position {} (action: {:?}, bus: {})",
             id,
@@ -4787,49 +9124,458 @@ impl RuntimeThread {
             bus_in
         );
 
-        // OscSender handles both sending to scsynth and score capture
-        // Effects are created during setup, so use Setup timing for score capture
-        let controls_vec: Vec<(String, f32)> = controls.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        // OscSender handles both sending to scsynth and score capture
+        // Effects are created during setup, so use Setup timing for score capture
+        let controls_vec: Vec<(String, f32)> = controls.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        if let Err(e) = self.osc_sender.s_new(
+            OscTiming::Setup,
+            &synthdef,
+            NodeId::new(node_id),
+            add_action,
+            target,
+            &controls_vec,
+            0.0, // current_beat not used for Setup timing
+        ) {
+            log::error!("[EFFECT] Failed to create effect '{}': {}", id, e);
+            return;
+        }
+        if let Some(plugin) = &vst_plugin {
+            // Parameters follow once the plugin reports it is open
+            let packet = crate::vst::open_packet(node_id, crate::vst::VST_EFFECT_UGEN_INDEX, plugin);
+            if let Err(e) = self.osc_sender.send_packet(OscTiming::Setup, packet, 0.0) {
+                log::error!("[VST] Failed to open '{}' for effect '{}': {}", plugin, id, e);
+            }
+        }
+
+        // Store effect state with position
+        let generation = self.shared.with_state_read(|s| s.reload_generation);
+        self.shared.with_state_write(|state| {
+            let effect = EffectState {
+                id: id.clone(),
+                synthdef_name: synthdef,
+                group_path: group_path.clone(),
+                node_id: Some(node_id),
+                bus_in,
+                bus_out,
+                params,
+                generation,
+                position: next_position,
+                vst_plugin,
+                source_location: source_location.clone(),
+                voice: None,
+            };
+            state.effects.insert(id.clone(), effect);
+            state.bump_version();
+        });
+
+        log::info!("[EFFECT] Created effect '{}' (node {}) on bus {}", id, node_id, bus_in);
+    }
+
+    /// Insert an effect on a voice.
+    ///
+    /// The first insert gives the voice a private bus: its synths write
+    /// there, the inserts process it in place in the order they were added,
+    /// and a link synth carries it on to the voice's usual output. The chain
+    /// sits in the voice's group, before the group's own effects.
+    fn handle_add_voice_insert(
+        &mut self,
+        id: String,
+        synthdef: String,
+        voice: String,
+        mut params: std::collections::HashMap<String, f32>,
+        vst_plugin: Option<String>,
+        source_location: crate::api::context::SourceLocation,
+    ) {
+        let synthdef = if vst_plugin.is_some() {
+            crate::vst::VST_EFFECT_SYNTHDEF.to_string()
+        } else {
+            for (param, value) in params.iter_mut() {
+                *value = self.clamp_synth_param(&synthdef, param, *value);
+            }
+            synthdef
+        };
+
+        let existing = self.shared.with_state_read(|state| state.effects.get(&id).cloned());
+        let generation = self.shared.with_state_read(|s| s.reload_generation);
+        if let Some(existing) = existing {
+            let unchanged = existing.synthdef_name == synthdef
+                && existing.vst_plugin == vst_plugin
+                && existing.voice.as_deref() == Some(voice.as_str());
+            if let (true, Some(node_id)) = (unchanged, existing.node_id) {
+                let changed: Vec<_> =
+                    params.iter().filter(|(param, value)| existing.params.get(*param) != Some(*value)).collect();
+                if vst_plugin.is_some() {
+                    self.send_vst_params(node_id, crate::vst::VST_EFFECT_UGEN_INDEX, changed);
+                } else if !changed.is_empty() {
+                    let controls: Vec<_> = changed.into_iter().map(|(param, value)| (param.as_str(), *value)).collect();
+                    let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                    let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &controls, current_beat);
+                }
+                self.shared.with_state_write(|state| {
+                    if let Some(effect) = state.effects.get_mut(&id) {
+                        effect.generation = generation;
+                        effect.params = params;
+                        effect.source_location = source_location;
+                    }
+                    state.bump_version();
+                });
+                return;
+            }
+            if let Some(nid) = existing.node_id {
+                let current_beat = self.transport.beat_at(Instant::now()).to_float();
+                let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(nid), current_beat);
+                self.open_vst_nodes.remove(&nid);
+            }
+            self.shared.with_state_write(|state| {
+                state.effects.remove(&id);
+            });
+        }
+
+        let Some((bus, link_node, group_path)) = self.ensure_voice_insert_chain(&voice) else {
+            log::warn!("[EFFECT] Cannot insert '{}': voice '{}' or its group not found", id, voice);
+            return;
+        };
+
+        let node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
+        let mut controls: Vec<(String, f32)> =
+            vec![("__fx_bus_in".to_string(), bus as f32), ("__fx_bus_out".to_string(), bus as f32)];
+        if vst_plugin.is_none() {
+            controls.extend(params.iter().map(|(k, v)| (k.clone(), *v)));
+        }
+        // Each insert goes last in the chain, right before the voice's link synth
         if let Err(e) = self.osc_sender.s_new(
             OscTiming::Setup,
             &synthdef,
             NodeId::new(node_id),
-            add_action,
-            target,
-            &controls_vec,
-            0.0, // current_beat not used for Setup timing
+            AddAction::AddBefore,
+            Target::from(link_node),
+            &controls,
+            0.0,
         ) {
-            log::error!("[EFFECT] Failed to create effect '{}': {}", id, e);
+            log::error!("[EFFECT] Failed to create insert '{}': {}", id, e);
             return;
         }
+        if let Some(plugin) = &vst_plugin {
+            let packet = crate::vst::open_packet(node_id, crate::vst::VST_EFFECT_UGEN_INDEX, plugin);
+            if let Err(e) = self.osc_sender.send_packet(OscTiming::Setup, packet, 0.0) {
+                log::error!("[VST] Failed to open '{}' for insert '{}': {}", plugin, id, e);
+            }
+        }
 
-        // Store effect state with position
-        let generation = self.shared.with_state_read(|s| s.reload_generation);
         self.shared.with_state_write(|state| {
+            let position = state.effects.values().filter(|e| e.voice.as_deref() == Some(voice.as_str())).count();
             let effect = EffectState {
                 id: id.clone(),
                 synthdef_name: synthdef,
-                group_path: group_path.clone(),
+                group_path,
                 node_id: Some(node_id),
-                bus_in,
-                bus_out,
+                bus_in: bus,
+                bus_out: bus,
                 params,
                 generation,
-                position: next_position,
-                vst_plugin: None,
-                source_location: source_location.clone(),
+                position,
+                vst_plugin,
+                source_location,
+                voice: Some(voice.clone()),
             };
             state.effects.insert(id.clone(), effect);
             state.bump_version();
         });
+        log::info!("[EFFECT] Inserted '{}' (node {}) on voice '{}', bus {}", id, node_id, voice, bus);
+    }
 
-        log::info!("[EFFECT] Created effect '{}' (node {}) on bus {}", id, node_id, bus_in);
+    /// Give a voice its private insert bus and the link synth carrying it to
+    /// the voice's output, if it doesn't have them yet.
+    ///
+    /// Returns the bus, the link synth and the voice's group.
+    fn ensure_voice_insert_chain(&mut self, voice: &str) -> Option<(i32, i32, String)> {
+        let (existing, group_path, group_node, mix_bus) = self.shared.with_state_read(|state| {
+            let voice = state.voices.get(voice)?;
+            let group = state.groups.get(&voice.group_path)?;
+            let existing = voice.insert_bus.zip(voice.insert_link_node_id);
+            Some((existing, voice.group_path.clone(), group.node_id?, voice.mix_bus(group.audio_bus)))
+        })?;
+        if let Some((bus, link_node)) = existing {
+            return Some((bus, link_node, group_path));
+        }
+
+        let (bus, link_node) =
+            self.shared.with_state_write(|state| (state.allocate_audio_bus(), state.allocate_synth_node()));
+        let (add_action, target) = self.voice_insert_anchor(&group_path, group_node);
+        if let Err(e) = self.osc_sender.s_new(
+            OscTiming::Setup,
+            "system_link_audio",
+            NodeId::new(link_node),
+            add_action,
+            target,
+            &[("inbus", bus as f32), ("outbus", mix_bus as f32)],
+            0.0,
+        ) {
+            log::error!("[EFFECT] Failed to create insert link for voice '{}': {}", voice, e);
+            return None;
+        }
+        self.shared.with_state_write(|state| {
+            if let Some(v) = state.voices.get_mut(voice) {
+                v.insert_bus = Some(bus);
+                v.insert_link_node_id = Some(link_node);
+            }
+            state.bump_version();
+        });
+        // Notes already sounding move onto the new bus
+        self.reroute_voice_nodes(voice);
+        log::info!("[EFFECT] Voice '{}' inserts on bus {} (link node {})", voice, bus, link_node);
+        Some((bus, link_node, group_path))
+    }
+
+    /// Where a voice's insert chain goes in its group: after the voices and
+    /// other insert chains, before the group's effects and link synth.
+    fn voice_insert_anchor(&self, group_path: &str, group_node: i32) -> (AddAction, Target) {
+        self.shared.with_state_read(|state| {
+            let first_effect = state
+                .effects
+                .values()
+                .filter(|e| e.group_path == group_path && e.voice.is_none())
+                .min_by_key(|e| e.position)
+                .and_then(|e| e.node_id);
+            let link = state.groups.get(group_path).and_then(|g| g.link_synth_node_id);
+            match first_effect.or(link) {
+                Some(node) => (AddAction::AddBefore, Target::from(node)),
+                None => (AddAction::AddToTail, Target::from(group_node)),
+            }
+        })
+    }
+
+    /// Move a voice's insert chain into the voice's current group, and
+    /// point its link synth and sounding nodes at the voice's buses.
+    fn place_voice_inserts(&mut self, voice: &str) {
+        let placed = self.shared.with_state_write(|state| {
+            let v = state.voices.get(voice)?;
+            let link_node = v.insert_link_node_id?;
+            let group_path = v.group_path.clone();
+            let group_node = state.groups.get(&group_path)?.node_id?;
+            let mut inserts: Vec<_> =
+                state.effects.values_mut().filter(|e| e.voice.as_deref() == Some(voice)).collect();
+            inserts.sort_by_key(|e| e.position);
+            let nodes: Vec<i32> = inserts
+                .into_iter()
+                .filter_map(|e| {
+                    e.group_path = group_path.clone();
+                    e.node_id
+                })
+                .collect();
+            state.bump_version();
+            Some((nodes, link_node, group_path, group_node))
+        });
+        if let Some((nodes, link_node, group_path, group_node)) = placed {
+            let (add_action, target) = self.voice_insert_anchor(&group_path, group_node);
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            for node_id in nodes.into_iter().chain([link_node]) {
+                let _ = self.osc_sender.n_move(OscTiming::Now, NodeId::new(node_id), add_action, target, current_beat);
+            }
+        }
+        self.reroute_voice_nodes(voice);
+    }
+
+    /// Free a voice's inserts and the link synth of its insert chain.
+    fn free_voice_inserts(&mut self, voice: &str) {
+        let nodes = self.shared.with_state_write(|state| {
+            let mut nodes: Vec<i32> = state.voices.get_mut(voice).and_then(|v| {
+                v.insert_bus = None;
+                v.insert_link_node_id.take()
+            }).into_iter().collect();
+            let inserts: Vec<String> =
+                state.effects.values().filter(|e| e.voice.as_deref() == Some(voice)).map(|e| e.id.clone()).collect();
+            for id in inserts {
+                nodes.extend(state.effects.remove(&id).and_then(|e| e.node_id));
+            }
+            state.bump_version();
+            nodes
+        });
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        for node_id in nodes {
+            let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(node_id), current_beat);
+            self.open_vst_nodes.remove(&node_id);
+        }
+    }
+
+    /// Load a VST instrument into a group, replacing the plugin it had.
+    ///
+    /// The host synth sits at the head of the group like voice synths and
+    /// writes to the group's bus. Reloading an unchanged instrument keeps it.
+    fn handle_load_vst_instrument(&mut self, id: String, plugin_key: String, group_path: String) {
+        let (existing, group) = self.shared.with_state_read(|state| {
+            (
+                state.vst_instruments.get(&id).cloned(),
+                state.groups.get(&group_path).and_then(|g| Some((g.node_id?, g.audio_bus))),
+            )
+        });
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if let Some(existing) = &existing {
+            let unchanged = existing.plugin_key == plugin_key && existing.group_path == group_path;
+            match existing.node_id {
+                Some(_) if unchanged => return,
+                Some(node_id) => {
+                    let _ = self.osc_sender.n_free(OscTiming::Now, NodeId::new(node_id), current_beat);
+                    self.open_vst_nodes.remove(&node_id);
+                }
+                None => {}
+            }
+        }
+
+        let node_id = match group {
+            Some((group_node_id, bus)) => {
+                let node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
+                if let Err(e) = self.osc_sender.s_new(
+                    OscTiming::Setup,
+                    crate::vst::VST_INSTRUMENT_SYNTHDEF,
+                    NodeId::new(node_id),
+                    AddAction::AddToHead,
+                    Target::from(group_node_id),
+                    &[("out", bus as f32)],
+                    0.0,
+                ) {
+                    log::error!("[VST] Failed to create instrument '{}': {}", id, e);
+                    return;
+                }
+                let packet = crate::vst::open_packet(node_id, crate::vst::VST_INSTRUMENT_UGEN_INDEX, &plugin_key);
+                if let Err(e) = self.osc_sender.send_packet(OscTiming::Setup, packet, 0.0) {
+                    log::error!("[VST] Failed to open '{}' for instrument '{}': {}", plugin_key, id, e);
+                }
+                log::info!("[VST] Loading '{}' as instrument '{}' (node {}) in group '{}'", plugin_key, id, node_id, group_path);
+                Some(node_id)
+            }
+            None => {
+                log::warn!("[VST] Cannot load instrument '{}': group '{}' has no node yet", id, group_path);
+                None
+            }
+        };
+
+        self.shared.with_state_write(|state| {
+            state.vst_instruments.insert(
+                id.clone(),
+                VstInstrumentInfo {
+                    id,
+                    plugin_key,
+                    group_path,
+                    node_id,
+                    params: existing.map(|e| e.params).unwrap_or_default(),
+                },
+            );
+            state.bump_version();
+        });
+    }
+
+    /// Whether an effect hosts a VST plugin.
+    fn is_vst_effect(&self, id: &str) -> bool {
+        self.shared
+            .with_state_read(|state| state.effects.get(id).is_some_and(|e| e.vst_plugin.is_some()))
+    }
+
+    /// Node of a VST instrument whose plugin is open.
+    fn open_vst_instrument(&self, id: &str) -> Option<i32> {
+        let node_id = self
+            .shared
+            .with_state_read(|state| state.vst_instruments.get(id).and_then(|i| i.node_id))?;
+        if self.open_vst_nodes.contains(&node_id) {
+            Some(node_id)
+        } else {
+            log::debug!("[VST] Instrument '{}' is not open yet", id);
+            None
+        }
+    }
+
+    /// Nodes of every VST instrument whose plugin is open.
+    fn open_vst_instrument_nodes(&self) -> Vec<i32> {
+        self.shared.with_state_read(|state| {
+            state
+                .vst_instruments
+                .values()
+                .filter_map(|i| i.node_id)
+                .filter(|node_id| self.open_vst_nodes.contains(node_id))
+                .collect()
+        })
+    }
+
+    /// Instrument node and MIDI channel of a voice playing a VST instrument.
+    fn vst_voice_target(&self, voice_name: &str) -> Option<(i32, u8)> {
+        let (instrument, channel) = self.shared.with_state_read(|state| {
+            let voice = state.voices.get(voice_name)?;
+            Some((voice.vst_instrument.clone()?, voice.midi_channel.unwrap_or(0)))
+        })?;
+        Some((self.open_vst_instrument(&instrument)?, channel))
+    }
+
+    /// Remember a VST instrument parameter, and set it if the plugin is open.
+    fn set_vst_instrument_param(&mut self, id: &str, param: String, value: f32) {
+        let node_id = self.shared.with_state_write(|state| {
+            let instrument = state.vst_instruments.get_mut(id)?;
+            instrument.params.insert(param.clone(), value);
+            instrument.node_id
+        });
+        match node_id {
+            Some(node_id) => self.send_vst_params(node_id, crate::vst::VST_INSTRUMENT_UGEN_INDEX, [(&param, &value)]),
+            None => log::warn!("[VST] Cannot set '{}': no VST instrument '{}'", param, id),
+        }
+    }
+
+    /// Set plugin parameters of a VST host synth, if its plugin is open.
+    fn send_vst_params<'a>(
+        &mut self,
+        node_id: i32,
+        ugen_index: i32,
+        params: impl IntoIterator<Item = (&'a String, &'a f32)>,
+    ) {
+        if !self.open_vst_nodes.contains(&node_id) {
+            return;
+        }
+        let packets: Vec<OscPacket> = params
+            .into_iter()
+            .map(|(param, value)| crate::vst::set_param_packet(node_id, ugen_index, param, *value))
+            .collect();
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let _ = self.osc_sender.send_bundle_now(packets, current_beat);
+    }
+
+    /// A VST host synth finished opening its plugin: send the parameters set so far.
+    fn handle_vst_opened(&mut self, node_id: i32, success: bool) {
+        let target = self.shared.with_state_read(|state| {
+            let instrument = state
+                .vst_instruments
+                .values()
+                .find(|i| i.node_id == Some(node_id))
+                .map(|i| (i.id.clone(), i.plugin_key.clone(), i.params.clone(), crate::vst::VST_INSTRUMENT_UGEN_INDEX));
+            instrument.or_else(|| {
+                state.effects.values().find(|e| e.node_id == Some(node_id)).and_then(|e| {
+                    Some((e.id.clone(), e.vst_plugin.clone()?, e.params.clone(), crate::vst::VST_EFFECT_UGEN_INDEX))
+                })
+            })
+        });
+        let Some((id, plugin, params, ugen_index)) = target else {
+            return;
+        };
+        if !success {
+            log::error!("[VST] Could not open '{}' for '{}' - is it installed and found by VSTPlugin?", plugin, id);
+            return;
+        }
+        log::info!("[VST] Opened '{}' for '{}'", plugin, id);
+        self.open_vst_nodes.insert(node_id);
+        self.send_vst_params(node_id, ugen_index, &params);
     }
 
     fn queue_loop_start(&mut self, name: &str, kind: LoopKind) {
-        let quantization = self.shared.with_state_read(|s| s.quantization_beats);
+        let launch_kind = match kind {
+            LoopKind::Pattern => LaunchKind::Pattern,
+            LoopKind::Melody => LaunchKind::Melody,
+            LoopKind::Sequence => LaunchKind::Sequence,
+        };
+        // A pattern or melody's own grid lives on the sequence its start() created
+        let quantization = self.shared.with_state_read(|s| {
+            let implicit = format!("{}{}", crate::sequences::IMPLICIT_SEQUENCE_PREFIX, name);
+            let own = s.sequences.get(&implicit).and_then(|d| d.launch_quantization);
+            s.launch_grid(launch_kind, own)
+        });
         let current_beat = self.transport.beat_at(Instant::now()).to_float();
-        let next_beat = ((current_beat / quantization).ceil() * quantization).max(0.0);
+        let next_beat = crate::sequences::next_launch_beat(current_beat, quantization);
 
         self.shared.with_state_write(|state| {
             match kind {
@@ -4877,7 +9623,233 @@ impl RuntimeThread {
         });
     }
 
+    /// Jump to a locator, right away or at the next `quantization` grid point.
+    fn jump_to_marker(&mut self, name: &str, quantization: Option<TimeSpan>) {
+        let (target_beat, grid) = self.shared.with_state_read(|state| {
+            (
+                state.locators.get(name),
+                quantization.map_or_else(
+                    || state.time_signature.beats_per_bar(),
+                    |q| q.to_beats(state.time_signature).0.max(0.0),
+                ),
+            )
+        });
+        let Some(target_beat) = target_beat else {
+            log::warn!("[TRANSPORT] No locator named '{}'", name);
+            return;
+        };
+
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let at_beat = if self.transport.is_running() {
+            crate::sequences::next_launch_beat(current_beat, grid)
+        } else {
+            current_beat
+        };
+        if at_beat - current_beat <= 1e-6 {
+            log::info!("[TRANSPORT] Jumping to '{}' (beat {:.2})", name, target_beat);
+            self.handle_message(StateMessage::SeekTransport { position: Beats(target_beat).into() });
+            return;
+        }
+
+        log::info!(
+            "[TRANSPORT] Jump to '{}' (beat {:.2}) queued for beat {:.2}",
+            name, target_beat, at_beat
+        );
+        self.shared.with_state_write(|state| {
+            state.locators.pending = Some(crate::locators::PendingJump {
+                marker: name.to_string(),
+                at_beat,
+                target_beat,
+            });
+            state.bump_version();
+        });
+    }
+
+    /// Carry out a pending locator jump once the transport reaches it.
+    ///
+    /// Returns the transport position to continue the tick with.
+    fn process_pending_jump(&mut self, current_beat: f64) -> f64 {
+        let due = self.shared.with_state_read(|state| {
+            state.locators.pending.clone().filter(|jump| current_beat >= jump.at_beat)
+        });
+        let Some(jump) = due else {
+            return current_beat;
+        };
+        let landing_beat = jump.landing_beat(current_beat);
+        log::info!("[TRANSPORT] Jumping to '{}' (beat {:.2})", jump.marker, landing_beat);
+        self.handle_message(StateMessage::SeekTransport { position: Beats(landing_beat).into() });
+        landing_beat
+    }
+
     fn start_sequence(&mut self, name: &str, play_once: bool) {
+        let (quantization, legato) = self.shared.with_state_read(|s| {
+            let def = s.sequences.get(name);
+            let kind = def.map_or(LaunchKind::Sequence, LaunchKind::of_sequence);
+            (
+                s.launch_grid(kind, def.and_then(|d| d.launch_quantization)),
+                def.is_some_and(|d| d.legato),
+            )
+        });
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let launch_beat = crate::sequences::next_launch_beat(current_beat, quantization);
+
+        let predecessor = if legato {
+            self.shared.with_state_read(|s| {
+                if s.active_sequences.contains_key(name) {
+                    return None;
+                }
+                s.legato_predecessor(name).map(|p| (p, s.sequences.get(name).map(|d| d.playback_beats()).unwrap_or(0.0)))
+            })
+        } else {
+            None
+        };
+        let Some(((previous, previous_anchor, previous_beats), beats)) = predecessor else {
+            self.start_sequence_at(name, play_once, launch_beat);
+            return;
+        };
+
+        // Legato: join at the phase the replaced sequence has reached and hand over at the launch beat
+        let phase = crate::sequences::legato_phase(launch_beat, previous_anchor, previous_beats, beats);
+        log::info!(
+            "[SEQUENCE] '{}' takes over from '{}' at beat {:.2} (phase {:.2})",
+            name, previous, launch_beat, phase
+        );
+        self.shared.with_state_write(|state| {
+            if let Some(active) = state.active_sequences.get_mut(&previous) {
+                active.stop_beat = Some(launch_beat);
+            }
+        });
+        self.start_sequence_from(name, play_once, launch_beat - phase, launch_beat);
+    }
+
+    /// Follow the network sync leader's transport.
+    ///
+    /// Tempo, meter and start/stop are taken over as they are; the position
+    /// is slewed towards the leader's (see [`TransportClock::correct_phase`]).
+    fn handle_sync_transport(&mut self, beat: f64, at: Instant, bpm: f64, running: bool, signature: (u32, u32)) {
+        use crate::netsync::{MAX_PHASE_ERROR_MS, MAX_SLEW_MS};
+
+        if (self.transport.bpm() - bpm).abs() > 1e-6 {
+            self.handle_message(StateMessage::SetBpm { bpm });
+        }
+        let current = self.transport.time_signature();
+        if (current.numerator, current.denominator) != signature {
+            self.handle_message(StateMessage::SetTimeSignature {
+                numerator: signature.0,
+                denominator: signature.1,
+            });
+        }
+        if running != self.transport.is_running() {
+            log::info!("[SYNC] Leader {} the transport", if running { "started" } else { "stopped" });
+            self.handle_message(if running {
+                StateMessage::StartScheduler
+            } else {
+                StateMessage::StopScheduler
+            });
+        }
+        if !running {
+            return;
+        }
+
+        // The message waited in the queue; extrapolate to now
+        let now = Instant::now();
+        let beats_per_ms = bpm / 60_000.0;
+        let target = beat + now.saturating_duration_since(at).as_secs_f64() * 1000.0 * beats_per_ms;
+        let max_error = MAX_PHASE_ERROR_MS * beats_per_ms;
+        let error = self.transport.correct_phase(
+            BeatTime::from_float(target),
+            now,
+            MAX_SLEW_MS * beats_per_ms,
+            max_error,
+        );
+        if error.abs() > max_error {
+            log::info!("[SYNC] Jumped {:+.2} beats to the leader's position", error);
+            self.scheduler.reset_to_beat(target);
+            if self.clock_out_running {
+                self.restart_clock_output();
+            }
+        }
+
+        let current_beat = self.transport.beat_at(now).to_float();
+        self.shared.with_state_write(|state| {
+            state.current_beat = current_beat;
+            state.net_sync.phase_error_ms = Some(error / beats_per_ms);
+        });
+    }
+
+    /// Pick a session up where a snapshot left it.
+    ///
+    /// Runs after the script has been evaluated: entities the script no longer
+    /// defines are skipped, and loops it started that weren't playing in the
+    /// snapshot are stopped.
+    fn handle_restore_session(&mut self, snapshot: SessionSnapshot) {
+        for msg in snapshot.mixer_messages() {
+            self.handle_message(msg);
+        }
+        // Seeking re-anchors running sequences, so restore their anchors afterwards
+        self.handle_message(StateMessage::SeekTransport { position: Beats(snapshot.beat).into() });
+
+        let sequences: HashMap<&str, f64> = snapshot.sequences.iter().map(|(n, b)| (n.as_str(), *b)).collect();
+        let patterns: HashMap<&str, f64> = snapshot.patterns.iter().map(|(n, b)| (n.as_str(), *b)).collect();
+        let melodies: HashMap<&str, f64> = snapshot.melodies.iter().map(|(n, b)| (n.as_str(), *b)).collect();
+        let restore_loop = |status: &mut LoopStatus, start_beat: Option<&f64>| {
+            *status = match start_beat {
+                Some(&start_beat) => LoopStatus::Playing { start_beat },
+                None => LoopStatus::Stopped,
+            };
+        };
+
+        self.shared.with_state_write(|state| {
+            state.active_sequences.retain(|name, _| sequences.contains_key(name.as_str()));
+            for (name, anchor_beat) in &sequences {
+                if !state.sequences.contains_key(*name) {
+                    log::warn!("[SESSION] Sequence '{}' no longer exists", name);
+                    continue;
+                }
+                state.active_sequences.insert(
+                    name.to_string(),
+                    ActiveSequence {
+                        anchor_beat: *anchor_beat,
+                        paused: false,
+                        triggered_clips: HashMap::new(),
+                        last_iteration: 0,
+                        completed: false,
+                        launch_beat: *anchor_beat,
+                        stop_beat: None,
+                    },
+                );
+            }
+            for pattern in state.patterns.values_mut() {
+                restore_loop(&mut pattern.status, patterns.get(pattern.name.as_str()));
+            }
+            for melody in state.melodies.values_mut() {
+                restore_loop(&mut melody.status, melodies.get(melody.name.as_str()));
+            }
+            for voice in &snapshot.voices {
+                if let Some(state_voice) = state.voices.get_mut(&voice.name) {
+                    state_voice.gain = voice.gain;
+                    state_voice.soloed = voice.soloed;
+                }
+            }
+            state.bump_version();
+        });
+
+        log::info!(
+            "[SESSION] Resumed at beat {:.2} with {} sequence(s), {} pattern(s) and {} melody(ies) playing",
+            snapshot.beat,
+            sequences.len(),
+            patterns.len(),
+            melodies.len()
+        );
+    }
+
+    /// Start a sequence anchored at a specific beat.
+    fn start_sequence_at(&mut self, name: &str, play_once: bool, anchor_beat: f64) {
+        self.start_sequence_from(name, play_once, anchor_beat, anchor_beat);
+    }
+
+    /// Start a sequence anchored at `anchor_beat` whose events fire from `launch_beat` on.
+    fn start_sequence_from(&mut self, name: &str, play_once: bool, anchor_beat: f64, launch_beat: f64) {
         // Check if sequence is already running - if so, preserve its state
         let already_running = self.shared.with_state_read(|state| {
             state.active_sequences.contains_key(name)
@@ -4891,10 +9863,6 @@ impl RuntimeThread {
             return;
         }
 
-        let quantization = self.shared.with_state_read(|s| s.quantization_beats);
-        let current_beat = self.transport.beat_at(Instant::now()).to_float();
-        let anchor_beat = ((current_beat / quantization).ceil() * quantization).max(0.0);
-
         log::info!("[SEQUENCE] Starting sequence '{}' at anchor beat {:.2} (play_once={})", name, anchor_beat, play_once);
 
         self.shared.with_state_write(|state| {
@@ -4913,6 +9881,8 @@ impl RuntimeThread {
                     triggered_clips: HashMap::new(),
                     last_iteration: 0,
                     completed: false,
+                    launch_beat,
+                    stop_beat: None,
                 },
             );
             state.bump_version();
@@ -4926,56 +9896,82 @@ impl RuntimeThread {
         // This function is kept for potential future use (e.g., loop state management).
     }
 
-    /// Start a fade from a FadeDefinition.
-    #[allow(dead_code)]
-    fn start_fade_from_definition(&mut self, fade: &crate::sequences::FadeDefinition) {
+    /// Start fade definition `name` at the next point of its launch grid (`fade.start()`).
+    fn start_fade(&mut self, name: &str) {
+        let fade = self.shared.with_state_read(|s| {
+            let fade = s.fade_defs.get(name)?.clone();
+            let grid = s.launch_grid(LaunchKind::Fade, fade.launch_quantization);
+            Some((fade, grid))
+        });
+        let Some((fade, grid)) = fade else {
+            log::warn!("[FADE] Fade '{}' not found", name);
+            return;
+        };
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        let delay_beats = crate::sequences::next_launch_beat(current_beat, grid) - current_beat;
+        log::info!("[FADE] Starting '{}' in {:.2} beats", name, delay_beats);
+        self.start_fade_from_definition(&fade, delay_beats);
+    }
+
+    /// Start a fade from a FadeDefinition, `delay_beats` from now.
+    fn start_fade_from_definition(&mut self, fade: &crate::sequences::FadeDefinition, delay_beats: f64) {
         use crate::events::FadeTargetType;
 
         let tempo = self.shared.with_state_read(|s| s.tempo);
         let beats_per_second = tempo / 60.0;
         let duration_seconds = fade.duration_beats / beats_per_second;
+        let delay_seconds = delay_beats.max(0.0) / beats_per_second;
+        let from = self.clamp_target_param(&fade.target_type, &fade.target_name, &fade.param_name, fade.from);
+        let to = self.clamp_target_param(&fade.target_type, &fade.target_name, &fade.param_name, fade.to);
 
         let fade_job = ActiveFadeJob {
             target_type: fade.target_type.clone(),
             target_name: fade.target_name.clone(),
             param_name: fade.param_name.clone(),
-            start_value: fade.from,
-            target_value: fade.to,
+            start_value: from,
+            target_value: to,
             start_time: Instant::now(),
             duration_seconds,
-            delay_seconds: 0.0,
+            delay_seconds,
             last_value: None,
             completed: false,
+            server_ramp: false,
+            curve: fade.curve,
+            name: Some(fade.name.clone()),
+            source_location: fade.source_location.clone(),
         };
 
         // Update the parameter in state immediately so synths created at the same beat
-        // will use the fade's start value (they read from state when building the s_new packet)
+        // will use the fade's start value (they read from state when building the s_new packet);
+        // a delayed fade sets it once it starts
         self.shared.with_state_write(|state| {
             // Update state first so new synths get the correct initial value
-            match &fade.target_type {
-                FadeTargetType::Group => {
-                    if let Some(group) = state.groups.get_mut(&fade.target_name) {
-                        group.params.insert(fade.param_name.clone(), fade.from);
+            if delay_seconds <= 0.0 {
+                match &fade.target_type {
+                    FadeTargetType::Group => {
+                        if let Some(group) = state.groups.get_mut(&fade.target_name) {
+                            group.params.insert(fade.param_name.clone(), from);
+                        }
                     }
-                }
-                FadeTargetType::Voice => {
-                    if let Some(voice) = state.voices.get_mut(&fade.target_name) {
-                        voice.params.insert(fade.param_name.clone(), fade.from);
+                    FadeTargetType::Voice => {
+                        if let Some(voice) = state.voices.get_mut(&fade.target_name) {
+                            voice.params.insert(fade.param_name.clone(), from);
+                        }
                     }
-                }
-                FadeTargetType::Pattern => {
-                    if let Some(pattern) = state.patterns.get_mut(&fade.target_name) {
-                        pattern.params.insert(fade.param_name.clone(), fade.from);
+                    FadeTargetType::Pattern => {
+                        if let Some(pattern) = state.patterns.get_mut(&fade.target_name) {
+                            pattern.params.insert(fade.param_name.clone(), from);
+                        }
                     }
-                }
-                FadeTargetType::Melody => {
-                    if let Some(melody) = state.melodies.get_mut(&fade.target_name) {
-                        melody.params.insert(fade.param_name.clone(), fade.from);
+                    FadeTargetType::Melody => {
+                        if let Some(melody) = state.melodies.get_mut(&fade.target_name) {
+                            melody.params.insert(fade.param_name.clone(), from);
+                        }
                     }
-                }
-                FadeTargetType::Effect => {
-                    if let Some(effect) = state.effects.get_mut(&fade.target_name) {
-                        effect.params.insert(fade.param_name.clone(), fade.from);
+                    FadeTargetType::Effect => {
+                        if let Some(effect) = state.effects.get_mut(&fade.target_name) {
+                            effect.params.insert(fade.param_name.clone(), from);
+                        }
                     }
                 }
             }
@@ -4987,10 +9983,20 @@ impl RuntimeThread {
     }
 
     /// Start a fade from a FadeClip (used for scheduled fade events).
-    fn start_fade_from_clip(&mut self, fade: crate::events::FadeClip) {
+    fn start_fade_from_clip(&mut self, mut fade: crate::events::FadeClip) {
+        fade.start_value = self.clamp_target_param(&fade.target_type, &fade.target_name, &fade.param_name, fade.start_value);
+        fade.target_value = self.clamp_target_param(&fade.target_type, &fade.target_name, &fade.param_name, fade.target_value);
         let tempo = self.shared.with_state_read(|s| s.tempo);
         let beats_per_second = tempo / 60.0;
         let duration_seconds = fade.duration_beats / beats_per_second;
+        // Sequence fades come from a fade definition, ctrl slides from their pattern
+        let source_location = self.shared.with_state_read(|s| {
+            s.fade_defs
+                .get(&fade.name)
+                .map(|def| def.source_location.clone())
+                .or_else(|| s.patterns.get(&fade.name).map(|p| p.source_location.clone()))
+                .unwrap_or_default()
+        });
 
         let fade_job = ActiveFadeJob {
             target_type: fade.target_type.clone(),
@@ -5003,6 +10009,10 @@ impl RuntimeThread {
             delay_seconds: 0.0,
             last_value: None,
             completed: false,
+            server_ramp: false,
+            curve: fade.curve,
+            name: Some(fade.name.clone()),
+            source_location,
         };
 
         // Update the parameter in state immediately so synths created at the same beat
@@ -5036,6 +10046,12 @@ impl RuntimeThread {
                     }
                 }
             }
+            // A newer fade of the same parameter takes over
+            state.fades.retain(|f| {
+                !(f.target_type == fade_job.target_type
+                    && f.target_name == fade_job.target_name
+                    && f.param_name == fade_job.param_name)
+            });
             state.fades.push(fade_job);
             state.bump_version();
         });
@@ -5046,13 +10062,47 @@ impl RuntimeThread {
         // 3. The update_fades() function will send n_set to existing synths in subsequent ticks
     }
 
-    /// Apply a fade value to a target.
-    fn apply_fade_value(&mut self, target_type: &crate::events::FadeTargetType, target_name: &str, param_name: &str, value: f32) {
+    /// Apply a fade step to a target.
+    ///
+    /// Node updates are appended to `batch` so a tick's fades go out as one bundle.
+    fn apply_fade_step(
+        &mut self,
+        target_type: &crate::events::FadeTargetType,
+        target_name: &str,
+        param_name: &str,
+        step: FadeStep,
+        batch: &mut Vec<OscPacket>,
+    ) {
         use crate::events::FadeTargetType;
 
+        let value = step.value();
+        let n_set = |node_id: i32, args: Vec<(String, f32)>| {
+            let mut osc_args = vec![OscType::Int(node_id)];
+            for (name, v) in args {
+                osc_args.push(OscType::String(name));
+                osc_args.push(OscType::Float(v));
+            }
+            OscPacket::Message(OscMessage {
+                addr: "/n_set".to_string(),
+                args: osc_args,
+            })
+        };
+        // Messages for one node: ramps carry the lag time along with the target
+        let lag_control = vibelang_dsp::ramp_lag_control(param_name);
+        let node_args = match step {
+            FadeStep::Set(v) => Some(vec![(param_name.to_string(), v)]),
+            FadeStep::Ramp { target, seconds, .. } => {
+                Some(vec![(lag_control, seconds as f32), (param_name.to_string(), target)])
+            }
+            FadeStep::Track(_) => None,
+            FadeStep::RampDone(v) => Some(vec![(lag_control, 0.0), (param_name.to_string(), v)]),
+        };
+
         match target_type {
             FadeTargetType::Group => {
-                self.handle_set_group_param(target_name, param_name, value);
+                if let Some(node_id) = self.set_group_param_state(target_name, param_name, value) {
+                    batch.push(n_set(node_id, vec![("amp".to_string(), value)]));
+                }
             }
             FadeTargetType::Voice => {
                 // Get all active node IDs for this voice - no pending check needed
@@ -5060,23 +10110,19 @@ impl RuntimeThread {
                 let node_ids: Vec<i32> = self.shared.with_state_write(|state| {
                     if let Some(voice) = state.voices.get_mut(target_name) {
                         voice.params.insert(param_name.to_string(), value);
-                        voice.active_notes.values().flatten().copied().collect()
+                        // Negative IDs are MIDI markers, not scsynth nodes
+                        voice.active_notes.values().flatten().copied().filter(|&id| id >= 0).collect()
                     } else {
                         Vec::new()
                     }
                 });
-                log::trace!("[FADE] Voice '{}' param '{}' = {} | {} nodes to update",
-                    target_name, param_name, value, node_ids.len());
-                for node_id in &node_ids {
-                    // OscSender handles both sending and score capture
-                    let current_beat = self.transport.beat_at(std::time::Instant::now()).to_float();
-                    // n_set may fail for nodes not yet live on scsynth - that's OK
-                    let _ = self.osc_sender.n_set(
-                        OscTiming::Now,
-                        NodeId::new(*node_id),
-                        &[(param_name.to_string(), value)],
-                        current_beat,
-                    );
+                log::trace!("[FADE] Voice '{}' param '{}' = {} ({:?}) | {} nodes to update",
+                    target_name, param_name, value, step, node_ids.len());
+                // n_set may fail for nodes not yet live on scsynth - that's OK
+                if let Some(args) = node_args {
+                    for node_id in node_ids {
+                        batch.push(n_set(node_id, args.clone()));
+                    }
                 }
             }
             FadeTargetType::Pattern => {
@@ -5102,21 +10148,72 @@ impl RuntimeThread {
                         None
                     }
                 });
-                if let Some(node_id) = node_to_update {
-                    // OscSender handles both sending and score capture
-                    let current_beat = self.transport.beat_at(std::time::Instant::now()).to_float();
-                    let _ = self.osc_sender.n_set(
-                        OscTiming::Now,
-                        NodeId::new(node_id),
-                        &[(param_name.to_string(), value)],
-                        current_beat,
-                    );
+                if let (Some(node_id), Some(args)) = (node_to_update, node_args) {
+                    batch.push(n_set(node_id, args));
                 }
             }
         }
     }
 
+    /// Remove a released node from note tracking and cancel its scheduled note-offs.
+    fn forget_released_node(&mut self, voice_name: &str, note: u8, node_id: i32) {
+        self.shared.with_state_write(|state| {
+            state.active_synths.remove(&node_id);
+            state.pending_nodes.remove(&node_id);
+            if let Some(voice) = state.voices.get_mut(voice_name) {
+                // Remove this specific node from the note's node list
+                if let Some(node_ids) = voice.active_notes.get_mut(&note) {
+                    node_ids.retain(|&id| id != node_id);
+                    if node_ids.is_empty() {
+                        voice.active_notes.remove(&note);
+                    }
+                }
+            }
+            // Cancel any scheduled note-off for this specific node (prevents orphaned note-offs).
+            // A gate-off already sent as a timed bundle stays on the server; it becomes a
+            // no-op once the node is gone since node IDs are never reused.
+            state.scheduled_note_offs.retain(|entry| {
+                let should_remove = entry.voice_name == voice_name
+                    && entry.note == note
+                    && entry.node_id == Some(node_id);
+                if should_remove {
+                    log::debug!(
+                        "[NOTE_OFF] Canceling scheduled note-off: voice='{}' note={} node={} beat={:.2} gate_scheduled={}",
+                        entry.voice_name, entry.note, node_id, entry.beat, entry.gate_scheduled
+                    );
+                }
+                !should_remove
+            });
+        });
+    }
+
     fn process_scheduled_note_offs(&mut self, current_beat: f64) {
+        // Hand gate-offs that came into the lookahead window to scsynth
+        let upcoming: Vec<(i32, f64)> = self.shared.with_state_read(|state| {
+            let horizon = current_beat + LOOKAHEAD_MS as f64 / 1000.0 * state.tempo / 60.0;
+            state
+                .scheduled_note_offs
+                .iter()
+                .filter(|n| !n.gate_scheduled && n.beat > current_beat && n.beat <= horizon)
+                .filter_map(|n| n.node_id.filter(|&id| id >= 0).map(|id| (id, n.beat)))
+                .collect()
+        });
+        if !upcoming.is_empty() {
+            let now = Instant::now();
+            let sent: Vec<i32> = upcoming
+                .into_iter()
+                .filter(|&(node_id, beat)| self.schedule_gate_off(node_id, beat, now))
+                .map(|(node_id, _)| node_id)
+                .collect();
+            self.shared.with_state_write(|state| {
+                for note_off in &mut state.scheduled_note_offs {
+                    if note_off.node_id.is_some_and(|id| sent.contains(&id)) {
+                        note_off.gate_scheduled = true;
+                    }
+                }
+            });
+        }
+
         let due_offs: Vec<ScheduledNoteOff> = self.shared.with_state_write(|state| {
             let due: Vec<_> = state
                 .scheduled_note_offs
@@ -5134,6 +10231,14 @@ impl RuntimeThread {
                 note_off.voice_name, note_off.note, note_off.node_id, note_off.beat, current_beat
             );
 
+            // gate=0 already went out as a timed bundle; only the tracking is left
+            if note_off.gate_scheduled {
+                if let Some(node_id) = note_off.node_id {
+                    self.forget_released_node(&note_off.voice_name, note_off.note, node_id);
+                }
+                continue;
+            }
+
             // Capture note-off to score at the scheduled beat time (OscSender handles capture)
             // Skip n_set for MIDI notes (node_id == -1 or -2 is a marker, not a real SC node)
             if let Some(node_id) = note_off.node_id {
@@ -5151,10 +10256,24 @@ impl RuntimeThread {
         }
     }
 
+    /// Release every note with a pending note-off right away.
+    ///
+    /// Used before the transport jumps: the pending offs were timed against
+    /// the old position, so waiting for them would cut notes short or leave
+    /// them hanging.
+    fn flush_scheduled_note_offs(&mut self) {
+        let pending = self.shared.with_state_write(|state| std::mem::take(&mut state.scheduled_note_offs));
+        for note_off in pending {
+            self.handle_note_off(&note_off.voice_name, note_off.note, note_off.node_id);
+        }
+    }
+
     fn update_fades(&mut self, now: Instant) {
-        // Get completed fades and update values
-        let updates: Vec<(FadeTargetType, String, String, f32)> = self.shared.with_state_write(|state| {
-            let mut updates = Vec::new();
+        // Advance fades and decide what each one needs this tick
+        let steps: Vec<(FadeTargetType, String, String, FadeStep)> = self.shared.with_state_write(|state| {
+            let mut steps = Vec::new();
+            let voices = &state.voices;
+            let effects = &state.effects;
             for fade in &mut state.fades {
                 if fade.completed {
                     continue;
@@ -5164,15 +10283,38 @@ impl RuntimeThread {
                     continue;
                 }
                 let t = ((elapsed - fade.delay_seconds) / fade.duration_seconds).min(1.0);
-                let value = fade.start_value + (fade.target_value - fade.start_value) * t as f32;
+                let value = fade.curve.interpolate(fade.start_value, fade.target_value, t);
+
+                // VarLag ramps are linear, so only linear fades can be handed to the server
+                let step = if fade.last_value.is_none()
+                    && t < 1.0
+                    && fade.curve == FadeCurve::Linear
+                    && fade_ramp_supported(voices, effects, &fade.target_type, &fade.target_name, &fade.param_name)
+                {
+                    // Hand the rest of the fade to scsynth in one message
+                    fade.server_ramp = true;
+                    Some(FadeStep::Ramp {
+                        value,
+                        target: fade.target_value,
+                        seconds: (1.0 - t) * fade.duration_seconds,
+                    })
+                } else if fade.last_value == Some(value) {
+                    None
+                } else if !fade.server_ramp {
+                    Some(FadeStep::Set(value))
+                } else if t >= 1.0 {
+                    Some(FadeStep::RampDone(value))
+                } else {
+                    Some(FadeStep::Track(value))
+                };
 
-                if fade.last_value != Some(value) {
+                if let Some(step) = step {
                     fade.last_value = Some(value);
-                    updates.push((
+                    steps.push((
                         fade.target_type.clone(),
                         fade.target_name.clone(),
                         fade.param_name.clone(),
-                        value,
+                        step,
                     ));
                 }
 
@@ -5181,12 +10323,22 @@ impl RuntimeThread {
                 }
             }
             state.fades.retain(|f| !f.completed);
-            updates
+            steps
         });
 
-        // Apply fade updates using the shared helper
-        for (target_type, target_name, param_name, value) in updates {
-            self.apply_fade_value(&target_type, &target_name, &param_name, value);
+        if steps.is_empty() {
+            return;
+        }
+
+        // Batch all node updates of this tick into a single bundle
+        let mut batch = Vec::new();
+        for (target_type, target_name, param_name, step) in steps {
+            self.apply_fade_step(&target_type, &target_name, &param_name, step, &mut batch);
+        }
+        // OscSender handles both sending and score capture
+        let current_beat = self.transport.beat_at(now).to_float();
+        if let Err(e) = self.osc_sender.send_bundle_now(batch, current_beat) {
+            log::warn!("[FADE] Failed to send fade updates: {}", e);
         }
     }
 
@@ -5194,55 +10346,190 @@ impl RuntimeThread {
     /// The path should already be resolved (absolute path) by the caller.
     /// If a sample with the same ID and path is already loaded, this is a no-op.
     fn handle_load_sample(&mut self, id: String, path: String) {
-        // Path should already be resolved by the Rhai thread
-        let path_str = path;
+        if self.sample_already_loaded(&id, &path) {
+            return;
+        }
+        let Some(buffer_id) = self.begin_sample_load(&id, &path) else {
+            return;
+        };
 
-        // Check if this sample is already loaded with the same path
-        let existing = self.shared.with_state_read(|state| {
-            state.samples.get(&id).map(|s| s.path.clone())
+        // Wait a moment for buffer to load
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let analysis = Self::analyze_sample(&id, &path);
+        self.finish_sample_load(id, path, buffer_id, analysis);
+    }
+
+    /// Load an SFZ instrument and its region buffers.
+    ///
+    /// An instrument already loaded under the same ID from the same file is
+    /// kept as is.
+    fn handle_load_sfz(&mut self, id: String, sfz_path: PathBuf) {
+        let already_loaded = self.shared.with_state_read(|state| {
+            state
+                .sfz_instruments
+                .get(&id)
+                .is_some_and(|inst| inst.source_file == sfz_path)
         });
+        if already_loaded {
+            log::debug!("SFZ instrument '{}' already loaded from {:?}, skipping reload", id, sfz_path);
+            return;
+        }
 
-        if let Some(existing_path) = existing {
-            if existing_path == path_str {
-                log::debug!(
-                    "[SAMPLE] Sample '{}' already loaded from '{}', skipping reload",
+        log::info!("Loading SFZ instrument '{}' from {:?}", id, sfz_path);
+
+        // Get the next buffer ID from state
+        let mut next_buffer_id = self.shared.with_state_read(|state| state.next_buffer_id);
+
+        // Clone sc for the closure
+        let sc_clone = self.sc.clone();
+
+        // Load the SFZ instrument using the callback-based loader
+        let result = vibelang_sfz::load_sfz_instrument(
+            &sfz_path,
+            id.clone(),
+            &mut |path, buffer_id| {
+                sc_clone.b_alloc_read(BufNum::new(buffer_id), path)
+                    .map_err(|e| anyhow::anyhow!("Buffer load failed: {}", e))
+            },
+            &mut next_buffer_id,
+        );
+
+        match result {
+            Ok(instrument) => {
+                log::info!(
+                    "Loaded SFZ instrument '{}' with {} regions",
                     id,
-                    path_str
+                    instrument.num_regions()
                 );
-                return;
+
+                // Give SuperCollider time to load buffers
+                std::thread::sleep(std::time::Duration::from_millis(500));
+
+                // Store in state
+                self.shared.with_state_write(|state| {
+                    state.sfz_instruments.insert(id.clone(), instrument);
+                    state.next_buffer_id = next_buffer_id;
+                    state.bump_version();
+                });
             }
-            // Different path - need to free the old buffer and reload
-            log::info!(
-                "[SAMPLE] Sample '{}' path changed from '{}' to '{}', reloading",
+            Err(e) => {
+                log::error!("Failed to load SFZ instrument '{}': {}", id, e);
+            }
+        }
+    }
+
+    /// Load the samples and SFZ instruments of a preload manifest.
+    ///
+    /// Buffers are allocated up front and the samples analysed in parallel,
+    /// so a large manifest loads in roughly the time of its slowest files.
+    fn handle_preload_assets(&mut self, samples: Vec<(String, String)>, sfz_instruments: Vec<(String, PathBuf)>) {
+        let started_at = Instant::now();
+        let pending: Vec<(String, String)> = samples
+            .into_iter()
+            .filter(|(id, path)| !self.sample_already_loaded(id, path))
+            .collect();
+        let started: Vec<(String, String, i32)> = pending
+            .into_iter()
+            .filter_map(|(id, path)| self.begin_sample_load(&id, &path).map(|buffer_id| (id, path, buffer_id)))
+            .collect();
+
+        let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        let mut analyses = Vec::with_capacity(started.len());
+        for chunk in started.chunks(workers) {
+            std::thread::scope(|scope| {
+                let jobs: Vec<_> = chunk
+                    .iter()
+                    .map(|(id, path, _)| scope.spawn(move || Self::analyze_sample(id, path)))
+                    .collect();
+                analyses.extend(jobs.into_iter().map(|job| job.join().unwrap_or_default()));
+            });
+        }
+
+        let sample_count = started.len();
+        for ((id, path, buffer_id), analysis) in started.into_iter().zip(analyses) {
+            self.finish_sample_load(id, path, buffer_id, analysis);
+        }
+        log::info!(
+            "[PRELOAD] Loaded {} sample(s) in {:.0} ms",
+            sample_count,
+            started_at.elapsed().as_secs_f64() * 1000.0
+        );
+
+        for (id, sfz_path) in sfz_instruments {
+            self.handle_load_sfz(id, sfz_path);
+        }
+    }
+
+    /// Whether `id` is already loaded from `path`.
+    ///
+    /// A sample loaded under the same ID from a different path is freed so it
+    /// can be reloaded.
+    fn sample_already_loaded(&mut self, id: &str, path: &str) -> bool {
+        let existing = self.shared.with_state_read(|state| {
+            state.samples.get(id).map(|s| s.path.clone())
+        });
+
+        let Some(existing_path) = existing else {
+            return false;
+        };
+        if existing_path == path {
+            log::debug!(
+                "[SAMPLE] Sample '{}' already loaded from '{}', skipping reload",
                 id,
-                existing_path,
-                path_str
+                path
             );
-            // Free the old buffer
-            if let Some(old_buffer) = self.shared.with_state_write(|state| {
-                state.samples.remove(&id).map(|s| s.buffer_id)
-            }) {
-                let current_beat = self.transport.beat_at(Instant::now()).to_float();
-                let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(old_buffer), current_beat);
-            }
+            return true;
+        }
+        // Different path - need to free the old buffer and reload
+        log::info!(
+            "[SAMPLE] Sample '{}' path changed from '{}' to '{}', reloading",
+            id,
+            existing_path,
+            path
+        );
+        if let Some(old_buffer) = self.shared.with_state_write(|state| {
+            state.samples.remove(id).map(|s| s.buffer_id)
+        }) {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            let _ = self.osc_sender.b_free(OscTiming::Now, BufNum::new(old_buffer), current_beat);
         }
+        false
+    }
 
-        // Allocate a buffer ID
+    /// Allocate a buffer and ask scsynth to read the sample into it.
+    ///
+    /// Returns the buffer ID, or `None` if the request could not be sent.
+    fn begin_sample_load(&mut self, id: &str, path: &str) -> Option<i32> {
         let buffer_id = self.shared.with_state_write(|state| state.allocate_buffer_id());
 
         log::info!(
             "[SAMPLE] Loading sample '{}' from '{}' into buffer {}",
             id,
-            path_str,
+            path,
             buffer_id
         );
 
-        // Read WAV metadata using hound
-        let wav_meta = Self::read_wav_metadata(&path_str);
-        let num_channels = wav_meta.as_ref().map(|m| m.num_channels).unwrap_or(2);
-        let num_frames = wav_meta.as_ref().map(|m| m.num_frames).unwrap_or(0);
-        let sample_rate = wav_meta.as_ref().map(|m| m.sample_rate).unwrap_or(44100.0);
+        // Load the sample into the buffer using b_allocRead (OscSender handles capture)
+        let current_beat = self.transport.beat_at(std::time::Instant::now()).to_float();
+        if let Err(e) = self.osc_sender.b_alloc_read(OscTiming::Now, BufNum::new(buffer_id), path, current_beat) {
+            log::error!(
+                "[SAMPLE] Failed to load sample '{}' from '{}': {}",
+                id,
+                path,
+                e
+            );
+            return None;
+        }
+        Some(buffer_id)
+    }
 
+    /// Read a sample file's metadata, leading silence and key.
+    ///
+    /// Doesn't touch runtime state, so several samples can be analysed at once.
+    fn analyze_sample(id: &str, path: &str) -> SampleAnalysis {
+        // Read WAV metadata using hound
+        let wav_meta = Self::read_wav_metadata(path);
         if let Some(meta) = &wav_meta {
             log::debug!(
                 "[SAMPLE] Detected WAV metadata – channels: {}, rate: {}, frames: {}",
@@ -5253,24 +10540,39 @@ impl RuntimeThread {
         } else {
             log::warn!(
                 "[SAMPLE] Could not read WAV metadata for '{}', falling back to stereo @ 44.1kHz",
-                path_str
+                path
             );
         }
 
-        // Load the sample into the buffer using b_allocRead (OscSender handles capture)
-        let current_beat = self.transport.beat_at(std::time::Instant::now()).to_float();
-        if let Err(e) = self.osc_sender.b_alloc_read(OscTiming::Now, BufNum::new(buffer_id), &path_str, current_beat) {
-            log::error!(
-                "[SAMPLE] Failed to load sample '{}' from '{}': {}",
+        // Measure leading silence so sample voices can pre-roll it away
+        let onset_ms = crate::api::sample::detect_onset_from_file(std::path::Path::new(path))
+            .map(|seconds| seconds * 1000.0)
+            .unwrap_or(0.0);
+
+        // Estimate the key of melodic material for key matching
+        let detected_key = crate::api::sample::detect_key_from_file(std::path::Path::new(path));
+        match &detected_key {
+            Some(analysis) => log::info!(
+                "[SAMPLE] Detected key for '{}': {} (confidence: {:.0}%)",
                 id,
-                path_str,
-                e
-            );
-            return;
+                analysis.key,
+                analysis.confidence * 100.0
+            ),
+            None => log::debug!("[SAMPLE] No key detected for '{}' (unpitched or unreadable)", id),
         }
 
-        // Wait a moment for buffer to load
-        std::thread::sleep(std::time::Duration::from_millis(50));
+        SampleAnalysis {
+            wav_meta,
+            onset_ms,
+            detected_key: detected_key.map(|analysis| analysis.key),
+        }
+    }
+
+    /// Store a loaded sample in state.
+    fn finish_sample_load(&mut self, id: String, path: String, buffer_id: i32, analysis: SampleAnalysis) {
+        let num_channels = analysis.wav_meta.as_ref().map(|m| m.num_channels).unwrap_or(2);
+        let num_frames = analysis.wav_meta.as_ref().map(|m| m.num_frames).unwrap_or(0);
+        let sample_rate = analysis.wav_meta.as_ref().map(|m| m.sample_rate).unwrap_or(44100.0);
 
         // Generate the SynthDef name for this sample
         let synthdef_name = format!("__sample_{}", id);
@@ -5278,17 +10580,30 @@ impl RuntimeThread {
         // Store sample info in state
         let sample_info = SampleInfo {
             id: id.clone(),
-            path: path_str.clone(),
+            path,
             buffer_id,
             num_channels,
             num_frames,
             sample_rate,
-            synthdef_name: synthdef_name.clone(),
+            synthdef_name,
             slices: Vec::new(),
+            detected_key: analysis.detected_key,
+            onset_ms: analysis.onset_ms,
         };
 
         self.shared.with_state_write(|state| {
             state.samples.insert(id.clone(), sample_info);
+            state.gc.evicted.remove(&id);
+            // Voices matching this sample's key follow the (re)detected key
+            let matched: Vec<String> = state
+                .voices
+                .values()
+                .filter(|v| v.key_match.as_deref() == Some(id.as_str()))
+                .map(|v| v.name.clone())
+                .collect();
+            for name in matched {
+                state.apply_key_match(&name);
+            }
             state.bump_version();
         });
 
@@ -5301,6 +10616,91 @@ impl RuntimeThread {
         );
     }
 
+    /// Play a sample once through the audition group.
+    ///
+    /// The audition group is created (with its link synth) the first time a
+    /// preview is requested, and again if a reload removed it.
+    fn handle_preview_sample(&mut self, id: &str, amp: f32) {
+        let Some((buffer_id, num_channels)) = self.shared.with_state_read(|state| {
+            state.samples.get(id).map(|s| (s.buffer_id, s.num_channels))
+        }) else {
+            log::warn!("[PREVIEW] Sample '{}' not found", id);
+            return;
+        };
+        self.reload_evicted_sample(buffer_id);
+
+        let has_audition_group = self.shared.with_state_read(|state| {
+            state
+                .groups
+                .get(AUDITION_GROUP_PATH)
+                .is_some_and(|g| g.link_synth_node_id.is_some())
+        });
+        if !has_audition_group {
+            self.handle_register_group(
+                "__audition".to_string(),
+                AUDITION_GROUP_PATH.to_string(),
+                Some("main".to_string()),
+                0,
+                crate::api::context::SourceLocation::default(),
+            );
+            self.finalize_groups();
+        }
+
+        let Some((group_id, audio_bus)) = self.shared.with_state_read(|state| {
+            state
+                .groups
+                .get(AUDITION_GROUP_PATH)
+                .and_then(|g| g.node_id.map(|n| (n, g.audio_bus)))
+        }) else {
+            log::error!("[PREVIEW] Audition group could not be created");
+            return;
+        };
+
+        let synthdef = if num_channels == 1 {
+            "sample_voice_mono"
+        } else {
+            "sample_voice_stereo"
+        };
+        let node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if let Err(e) = self.osc_sender.s_new(
+            OscTiming::Now,
+            synthdef,
+            NodeId::new(node_id),
+            AddAction::AddToHead,
+            Target::from(group_id),
+            &[
+                ("out", audio_bus as f32),
+                ("bufnum", buffer_id as f32),
+                ("amp", amp),
+            ],
+            current_beat,
+        ) {
+            log::error!("[PREVIEW] Failed to preview sample '{}': {}", id, e);
+            return;
+        }
+
+        log::info!("[PREVIEW] Previewing sample '{}' (node {})", id, node_id);
+    }
+
+    /// Mute or unmute a voice.
+    ///
+    /// Muting stops new events; a running synth (an input voice, a drone)
+    /// is silenced too, since it plays no events to hold back.
+    fn set_voice_muted(&mut self, name: &str, muted: bool) {
+        let running = self.shared.with_state_write(|state| {
+            let voice = state.voices.get_mut(name)?;
+            voice.muted = muted;
+            let running = voice.running_node_id.map(|node_id| (node_id, voice.running_amp()));
+            state.bump_version();
+            running
+        });
+        if let Some((node_id, amp)) = running {
+            let current_beat = self.transport.beat_at(Instant::now()).to_float();
+            let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("amp", amp)], current_beat);
+        }
+    }
+
     /// Run a voice continuously (for line-in processing, drones, etc.).
     ///
     /// Unlike melody/pattern triggers, this starts the synth immediately
@@ -5313,14 +10713,14 @@ impl RuntimeThread {
                     v.synth_name.clone(),
                     v.group_path.clone(),
                     v.params.clone(),
-                    v.gain,
-                    v.running,
+                    v.running_amp(),
+                    v.running && v.running_synth == v.synth_name,
                     v.running_node_id,
                 )
             })
         });
 
-        let Some((synth_name, group_path, params, gain, already_running, existing_node)) = voice_info else {
+        let Some((synth_name, group_path, params, amp, already_running, existing_node)) = voice_info else {
             log::warn!("[RUN_VOICE] Voice '{}' not found", name);
             return;
         };
@@ -5340,7 +10740,7 @@ impl RuntimeThread {
                     let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[(param.as_str(), *value)], current_beat);
                 }
                 // Update gain
-                let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("amp", gain as f32)], current_beat);
+                let _ = self.osc_sender.n_set(OscTiming::Now, NodeId::new(node_id), &[("amp", amp)], current_beat);
             }
             // Mark voice as still running and update run_generation
             let generation = self.shared.with_state_read(|s| s.reload_generation);
@@ -5362,11 +10762,12 @@ impl RuntimeThread {
         }
 
         // Get group's audio bus for output routing
-        let (group_node_id, output_bus) = self.shared.with_state_read(|state| {
+        let (group_node_id, group_bus) = self.shared.with_state_read(|state| {
             state.groups.get(&group_path)
                 .map(|g| (g.node_id, g.audio_bus))
                 .unwrap_or((None, 0))
         });
+        let output_bus = self.voice_out_bus(Some(&name), group_bus);
 
         // Allocate a node ID
         let node_id = self.shared.with_state_write(|state| state.allocate_synth_node());
@@ -5376,7 +10777,7 @@ impl RuntimeThread {
             .map(|(k, v)| (k.clone(), *v))
             .collect();
         controls.push(("out".to_string(), output_bus as f32));
-        controls.push(("amp".to_string(), gain as f32));
+        controls.push(("amp".to_string(), amp));
 
         // Create the synth in the group (or root if no group)
         let target = group_node_id
@@ -5403,6 +10804,7 @@ impl RuntimeThread {
             if let Some(voice) = state.voices.get_mut(&name) {
                 voice.running = true;
                 voice.running_node_id = Some(node_id);
+                voice.running_synth = Some(synthdef.clone());
                 voice.run_generation = generation;
             }
             state.bump_version();
@@ -5414,6 +10816,92 @@ impl RuntimeThread {
         );
     }
 
+    /// Stop everything a voice plays: its notes, the synths it started and
+    /// its running node.
+    ///
+    /// Notes are released with gate=0 (the running node, which may have no
+    /// gate, is freed), or everything is freed at once with `choke`. With
+    /// `fade`, `amp` ramps to 0 over that many seconds first — smoothly for
+    /// synthdefs with an `amp_lag` control — and the synths are freed after.
+    /// The voice stays defined and plays its next note as usual.
+    fn handle_stop_voice(&mut self, name: &str, fade: Option<f64>, choke: bool) {
+        let stopped = self.shared.with_state_write(|state| {
+            let voice = state.voices.get_mut(name)?;
+            let mut notes: Vec<i32> = voice.active_notes.values().flatten().copied().filter(|&id| id >= 0).collect();
+            let midi_notes: Vec<u8> = voice.active_notes.keys().copied().collect();
+            let midi_output = voice
+                .midi_output_device_id
+                .and_then(|id| state.midi_output_config.devices.get(&id))
+                .map(|device| (device.event_tx.clone(), voice.midi_channel.unwrap_or(0)));
+            voice.clear_notes();
+            voice.running = false;
+            voice.running_synth = None;
+            let running = voice.running_node_id.take();
+
+            notes.extend(
+                state
+                    .active_synths
+                    .iter()
+                    .filter(|(_, synth)| synth.voice_names.iter().any(|v| v == name))
+                    .map(|(&id, _)| id),
+            );
+            notes.sort_unstable();
+            notes.dedup();
+            for id in notes.iter().chain(&running) {
+                state.active_synths.remove(id);
+                state.pending_nodes.remove(id);
+            }
+            state.scheduled_note_offs.retain(|entry| entry.voice_name != name);
+            state.bump_version();
+            Some((notes, running, midi_notes, midi_output))
+        });
+        let Some((notes, running, midi_notes, midi_output)) = stopped else {
+            log::warn!("[STOP_VOICE] Voice '{}' not found", name);
+            return;
+        };
+
+        if let Some((event_tx, channel)) = midi_output {
+            for note in midi_notes {
+                let _ = event_tx.send(crate::midi::QueuedMidiEvent::note_off(channel, note));
+            }
+        }
+
+        let current_beat = self.transport.beat_at(Instant::now()).to_float();
+        if let Some((node_id, channel)) = self.vst_voice_target(name) {
+            let _ = self
+                .osc_sender
+                .send_packet(OscTiming::Now, crate::vst::all_notes_off_packet(node_id, channel), current_beat);
+        }
+
+        let nodes: Vec<i32> = notes.iter().chain(&running).copied().collect();
+        if nodes.is_empty() {
+            return;
+        }
+        log::info!("[STOP_VOICE] Stopping {} synth(s) of voice '{}'", nodes.len(), name);
+
+        let result = match fade.filter(|&seconds| seconds > 0.0) {
+            Some(seconds) => {
+                let lag = vibelang_dsp::ramp_lag_control("amp");
+                let ramps = nodes
+                    .iter()
+                    .map(|&id| n_set_packet(id, &[(lag.as_str(), seconds as f32), ("amp", 0.0)]))
+                    .collect();
+                self.osc_sender.send_bundle_now(ramps, current_beat).and_then(|_| {
+                    self.osc_sender.send_bundle_after(seconds, vec![n_free_packet(&nodes)], current_beat)
+                })
+            }
+            None if choke => self.osc_sender.send_bundle_now(vec![n_free_packet(&nodes)], current_beat),
+            None => {
+                let mut packets: Vec<OscPacket> = notes.iter().map(|&id| n_set_packet(id, &[("gate", 0.0)])).collect();
+                packets.extend(running.map(|id| n_free_packet(&[id])));
+                self.osc_sender.send_bundle_now(packets, current_beat)
+            }
+        };
+        if let Err(e) = result {
+            log::warn!("[STOP_VOICE] Failed to stop voice '{}': {}", name, e);
+        }
+    }
+
     /// Read WAV metadata from a file.
     fn read_wav_metadata(path: &str) -> Option<WavMetadata> {
         use std::fs::File;
@@ -5434,6 +10922,115 @@ impl RuntimeThread {
     }
 }
 
+/// What a fade sends to scsynth on one tick.
+#[derive(Clone, Copy, Debug)]
+enum FadeStep {
+    /// Set the interpolated value directly.
+    Set(f32),
+    /// Start a server-side ramp to `target` over `seconds`.
+    Ramp { value: f32, target: f32, seconds: f64 },
+    /// The server is ramping; only track the value in state.
+    Track(f32),
+    /// The ramp finished; settle the value and reset the lag.
+    RampDone(f32),
+}
+
+impl FadeStep {
+    /// Current value of the faded parameter.
+    fn value(self) -> f32 {
+        match self {
+            FadeStep::Set(v) | FadeStep::Track(v) | FadeStep::RampDone(v) => v,
+            FadeStep::Ramp { value, .. } => value,
+        }
+    }
+}
+
+/// Whether a fade can run as a server-side `VarLag` ramp.
+///
+/// Only voices and effects whose synthdef has the param's lag control qualify
+/// (see [`vibelang_dsp::RAMP_PARAMS`]). Notes started during the ramp begin at
+/// the value current at note-on and don't follow the rest of the ramp.
+fn fade_ramp_supported(
+    voices: &HashMap<String, VoiceState>,
+    effects: &HashMap<String, EffectState>,
+    target_type: &FadeTargetType,
+    target_name: &str,
+    param_name: &str,
+) -> bool {
+    let lag_control = vibelang_dsp::ramp_lag_control(param_name);
+    match target_type {
+        FadeTargetType::Voice => voices
+            .get(target_name)
+            .and_then(|voice| voice.synth_name.as_deref())
+            .is_some_and(|synth| vibelang_dsp::get_synthdef_param_defaults(synth).contains_key(&lag_control)),
+        FadeTargetType::Effect => effects.get(target_name).is_some_and(|effect| {
+            vibelang_dsp::get_effect_param_defaults(&effect.synthdef_name).contains_key(&lag_control)
+        }),
+        _ => false,
+    }
+}
+
+/// Message setting a parameter of a group, voice, pattern, melody or effect.
+fn param_message(target_type: &FadeTargetType, name: String, param: String, value: f32) -> StateMessage {
+    match target_type {
+        FadeTargetType::Group => StateMessage::SetGroupParam { path: name, param, value },
+        FadeTargetType::Voice => StateMessage::SetVoiceParam { name, param, value },
+        FadeTargetType::Pattern => StateMessage::SetPatternParam { name, param, value },
+        FadeTargetType::Melody => StateMessage::SetMelodyParam { name, param, value },
+        FadeTargetType::Effect => StateMessage::SetEffectParam { id: name, param, value },
+    }
+}
+
+/// `/n_set` of one or more controls of a node.
+fn n_set_packet(node_id: i32, controls: &[(&str, f32)]) -> OscPacket {
+    let mut args = vec![OscType::Int(node_id)];
+    for (name, value) in controls {
+        args.push(OscType::String(name.to_string()));
+        args.push(OscType::Float(*value));
+    }
+    OscPacket::Message(OscMessage { addr: "/n_set".to_string(), args })
+}
+
+/// `/n_free` of several nodes, in order.
+fn n_free_packet(node_ids: &[i32]) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: "/n_free".to_string(),
+        args: node_ids.iter().map(|&id| OscType::Int(id)).collect(),
+    })
+}
+
+/// `/n_run` of a node.
+fn n_run_packet(node_id: i32, running: bool) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: "/n_run".to_string(),
+        args: vec![OscType::Int(node_id), OscType::Int(running as i32)],
+    })
+}
+
+/// `/s_new` of a MIDI trigger synth, which sends its packed MIDI data back
+/// via SendTrig at the bundle's time (see `midi_synthdefs`).
+fn midi_trigger_packet(synthdef: &str, node_id: i32, packed: u32) -> rosc::OscPacket {
+    rosc::OscPacket::Message(rosc::OscMessage {
+        addr: "/s_new".to_string(),
+        args: vec![
+            rosc::OscType::String(synthdef.to_string()),
+            rosc::OscType::Int(node_id),
+            rosc::OscType::Int(0), // addToHead
+            rosc::OscType::Int(0), // default group
+            rosc::OscType::String("packed_data".to_string()),
+            rosc::OscType::Float(packed as f32),
+        ],
+    })
+}
+
+/// File analysis done when a sample is loaded.
+#[derive(Default)]
+struct SampleAnalysis {
+    wav_meta: Option<WavMetadata>,
+    onset_ms: f64,
+    detected_key: Option<MusicalKey>,
+}
+
 /// WAV metadata for sample loading.
 struct WavMetadata {
     num_channels: i32,
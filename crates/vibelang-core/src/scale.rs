@@ -0,0 +1,258 @@
+//! Scales: scale degrees to MIDI notes, and notes snapped into a key.
+//!
+//! A [`Scale`] is a root note and a [`ScaleKind`]. Melodies use it to
+//! resolve scale degrees (`melody("lead").scale("D", "dorian").degrees([1, 3, 5, 7])`)
+//! and, with `.quantize_to_scale()`, to snap notes that don't belong to the
+//! key — their own and those recorded into them from MIDI takes.
+
+use std::fmt;
+
+use crate::musical_key::MusicalKey;
+
+/// A scale's step pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleKind {
+    Major,
+    Minor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    HarmonicMinor,
+    MelodicMinor,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+    Chromatic,
+}
+
+impl ScaleKind {
+    /// Parse a scale name such as "dorian", "minor" or "minor_pentatonic".
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.trim().to_lowercase().replace([' ', '-'], "_").as_str() {
+            "major" | "ionian" => ScaleKind::Major,
+            "minor" | "natural_minor" | "aeolian" => ScaleKind::Minor,
+            "dorian" => ScaleKind::Dorian,
+            "phrygian" => ScaleKind::Phrygian,
+            "lydian" => ScaleKind::Lydian,
+            "mixolydian" => ScaleKind::Mixolydian,
+            "locrian" => ScaleKind::Locrian,
+            "harmonic_minor" => ScaleKind::HarmonicMinor,
+            "melodic_minor" => ScaleKind::MelodicMinor,
+            "pentatonic" | "major_pentatonic" => ScaleKind::MajorPentatonic,
+            "minor_pentatonic" => ScaleKind::MinorPentatonic,
+            "blues" => ScaleKind::Blues,
+            "chromatic" => ScaleKind::Chromatic,
+            _ => return None,
+        })
+    }
+
+    /// Name of the scale, as accepted by [`parse`](Self::parse).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScaleKind::Major => "major",
+            ScaleKind::Minor => "minor",
+            ScaleKind::Dorian => "dorian",
+            ScaleKind::Phrygian => "phrygian",
+            ScaleKind::Lydian => "lydian",
+            ScaleKind::Mixolydian => "mixolydian",
+            ScaleKind::Locrian => "locrian",
+            ScaleKind::HarmonicMinor => "harmonic_minor",
+            ScaleKind::MelodicMinor => "melodic_minor",
+            ScaleKind::MajorPentatonic => "major_pentatonic",
+            ScaleKind::MinorPentatonic => "minor_pentatonic",
+            ScaleKind::Blues => "blues",
+            ScaleKind::Chromatic => "chromatic",
+        }
+    }
+
+    /// Semitones of each step above the root, within one octave.
+    pub fn intervals(&self) -> &'static [u8] {
+        match self {
+            ScaleKind::Major => &[0, 2, 4, 5, 7, 9, 11],
+            ScaleKind::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            ScaleKind::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            ScaleKind::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+            ScaleKind::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+            ScaleKind::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            ScaleKind::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+            ScaleKind::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            ScaleKind::MelodicMinor => &[0, 2, 3, 5, 7, 9, 11],
+            ScaleKind::MajorPentatonic => &[0, 2, 4, 7, 9],
+            ScaleKind::MinorPentatonic => &[0, 3, 5, 7, 10],
+            ScaleKind::Blues => &[0, 3, 5, 6, 7, 10],
+            ScaleKind::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+}
+
+/// A scale on a root note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scale {
+    /// MIDI note of the root (e.g. 62 for D4); degrees count from here.
+    pub root: u8,
+    pub kind: ScaleKind,
+}
+
+impl Scale {
+    /// Create a scale on a MIDI root note.
+    pub fn new(root: u8, kind: ScaleKind) -> Self {
+        Self { root: root.min(127), kind }
+    }
+
+    /// Parse a root ("D", "F#3") and a scale name ("dorian").
+    pub fn parse(root: &str, kind: &str) -> Option<Self> {
+        Some(Self::new(parse_root_note(root), ScaleKind::parse(kind)?))
+    }
+
+    /// Parse a scale as written by its `Display` form, e.g. "D4 dorian".
+    pub fn parse_text(text: &str) -> Option<Self> {
+        let (root, kind) = text.trim().split_once(char::is_whitespace)?;
+        Self::parse(root, kind)
+    }
+
+    /// Scale of a key, with its tonic in octave 4: major, or natural minor.
+    pub fn from_key(key: &MusicalKey) -> Self {
+        let kind = if key.minor { ScaleKind::Minor } else { ScaleKind::Major };
+        Self::new(60 + key.root, kind)
+    }
+
+    /// Note `steps` scale steps above the root (below for negative steps).
+    ///
+    /// Step 0 is the root; a full pass through the scale is an octave.
+    pub fn note(&self, steps: i32) -> u8 {
+        let intervals = self.kind.intervals();
+        let len = intervals.len() as i32;
+        let octave = steps.div_euclid(len);
+        let interval = intervals[steps.rem_euclid(len) as usize] as i32;
+        (self.root as i32 + octave * 12 + interval).clamp(0, 127) as u8
+    }
+
+    /// Whether `note` belongs to the scale, in any octave.
+    pub fn contains(&self, note: u8) -> bool {
+        let pitch_class = (note as i32 - self.root as i32).rem_euclid(12) as u8;
+        self.kind.intervals().contains(&pitch_class)
+    }
+
+    /// The scale note nearest to `note`; between two equally near ones, the lower.
+    pub fn quantize(&self, note: u8) -> u8 {
+        (0..=6u8)
+            .flat_map(|distance| [note.checked_sub(distance), note.checked_add(distance).filter(|n| *n <= 127)])
+            .flatten()
+            .find(|candidate| self.contains(*candidate))
+            .unwrap_or(note)
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", crate::pitch::note_name(self.root), self.kind.as_str())
+    }
+}
+
+/// What notes snap to: the session key as it is when they are snapped, or
+/// a fixed scale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleSnap {
+    SessionKey,
+    Scale(Scale),
+}
+
+impl ScaleSnap {
+    /// The scale to snap to; `None` for the session key while no key is set.
+    pub fn resolve(&self, session_key: Option<MusicalKey>) -> Option<Scale> {
+        match self {
+            ScaleSnap::SessionKey => session_key.map(|key| Scale::from_key(&key)),
+            ScaleSnap::Scale(scale) => Some(*scale),
+        }
+    }
+}
+
+/// Parse a root note name to MIDI note number.
+/// Supports formats like "D", "D4", "F#", "F#3", etc.
+/// Returns the MIDI note number (defaults to octave 4 if not specified).
+pub fn parse_root_note(root: &str) -> u8 {
+    let root = root.trim();
+    if root.is_empty() {
+        return 60; // Default to C4
+    }
+
+    let mut chars = root.chars().peekable();
+
+    // Parse note letter
+    let base: i16 = match chars.next().unwrap_or('C').to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => 0,
+    };
+
+    // Parse accidental
+    let mut accidental: i16 = 0;
+    while let Some(&c) = chars.peek() {
+        match c {
+            '#' | '♯' => {
+                accidental += 1;
+                chars.next();
+            }
+            'b' | '♭' => {
+                accidental -= 1;
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+
+    // Parse octave (default to 4 if not specified)
+    let octave_str: String = {
+        let mut result = String::new();
+        // Optional leading minus for negative octaves (e.g., "C-1")
+        if chars.peek() == Some(&'-') {
+            result.push(chars.next().unwrap());
+        }
+        // Collect digits
+        while chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+            result.push(chars.next().unwrap());
+        }
+        result
+    };
+    let octave: i16 = if octave_str.is_empty() {
+        4 // Default octave
+    } else {
+        octave_str.parse().unwrap_or(4)
+    };
+
+    // Calculate MIDI note: (octave + 1) * 12 + base + accidental
+    let midi = (octave + 1) * 12 + base + accidental;
+    midi.clamp(0, 127) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_degrees_and_quantize() {
+        let dorian = Scale::parse("D", "dorian").unwrap();
+        let notes: Vec<u8> = [0, 2, 4, 6, 7, -1].iter().map(|&s| dorian.note(s)).collect();
+        assert_eq!(notes, vec![62, 65, 69, 72, 74, 60]);
+        assert_eq!(Scale::parse_text(&dorian.to_string()), Some(dorian));
+
+        // F# is out of D dorian: F and G are equally near, F wins
+        assert_eq!(dorian.quantize(66), 65);
+        assert_eq!(dorian.quantize(71), 71);
+        let penta = Scale::parse("C", "minor pentatonic").unwrap();
+        assert_eq!(penta.quantize(61), 60);
+        assert_eq!(penta.quantize(62), 63);
+        assert_eq!(penta.note(5), 72);
+
+        let key = MusicalKey::parse("A minor").unwrap();
+        assert_eq!(ScaleSnap::SessionKey.resolve(Some(key)), Some(Scale::new(69, ScaleKind::Minor)));
+        assert_eq!(ScaleSnap::SessionKey.resolve(None), None);
+    }
+}
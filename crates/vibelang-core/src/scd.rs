@@ -0,0 +1,215 @@
+//! Import of SynthDefs written in sclang.
+//!
+//! `import_scd("synths/bass.scd")` runs `sclang` headless on the file,
+//! writes every SynthDef it defines to `.scsyndef` and loads them like
+//! synthdefs defined in VibeLang. Compiled files are cached in a `.vibe-scd`
+//! directory next to the `.scd` file, keyed by a hash of its source, so
+//! sclang only runs again after the file changes.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::synthdef_dir::read_synthdef_dir;
+
+/// Directory (next to the `.scd` file) holding compiled synthdefs.
+pub const SCD_CACHE_DIR: &str = ".vibe-scd";
+
+/// Longest time sclang may take to compile a file.
+const SCLANG_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Marker written once a cache directory is complete.
+const COMPLETE_MARKER: &str = ".complete";
+
+/// Compile the SynthDefs of an `.scd` file, returning `(name, bytes)` pairs.
+///
+/// Uses the cache when the file is unchanged since the last compile.
+pub fn compile_scd(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let source = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let cache = cache_dir(path, &source);
+    if cache.join(COMPLETE_MARKER).exists() {
+        log::debug!("[SCD] Using cached synthdefs of {}", path.display());
+        return read_synthdef_dir(&cache);
+    }
+
+    let sclang = find_sclang().ok_or_else(|| {
+        anyhow!("sclang not found: install SuperCollider (with sclang) to import {}", path.display())
+    })?;
+    log::info!("[SCD] Compiling {} with {}", path.display(), sclang.display());
+
+    // Older compiles of this file are stale now
+    let stem = cache_stem(path);
+    if let Some(parent) = cache.parent() {
+        if let Ok(entries) = fs::read_dir(parent) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.rsplit_once('-').is_some_and(|(s, _)| s == stem) {
+                    let _ = fs::remove_dir_all(entry.path());
+                }
+            }
+        }
+    }
+    fs::create_dir_all(&cache).with_context(|| format!("Failed to create {}", cache.display()))?;
+
+    let script = cache.join("compile.scd");
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    fs::write(&script, compile_script(&absolute, &cache))?;
+    let output = run_with_timeout(
+        Command::new(&sclang)
+            .arg(&script)
+            .env("QT_QPA_PLATFORM", "offscreen")
+            .stdin(Stdio::null()),
+        SCLANG_TIMEOUT,
+    );
+    let _ = fs::remove_file(&script);
+    let output = output?;
+
+    let synthdefs = read_synthdef_dir(&cache)?;
+    if !output.status.success() || synthdefs.is_empty() {
+        let _ = fs::remove_dir_all(&cache);
+        let log = String::from_utf8_lossy(&output.stdout);
+        let tail: Vec<&str> = log.lines().rev().take(15).collect::<Vec<_>>().into_iter().rev().collect();
+        bail!(
+            "sclang did not produce any SynthDefs from {}:\n{}",
+            path.display(),
+            tail.join("\n")
+        );
+    }
+    fs::write(cache.join(COMPLETE_MARKER), "")?;
+    Ok(synthdefs)
+}
+
+/// Locate the sclang binary.
+pub fn find_sclang() -> Option<PathBuf> {
+    let name = if cfg!(windows) { "sclang.exe" } else { "sclang" };
+    if let Ok(output) = Command::new(if cfg!(windows) { "where" } else { "which" }).arg(name).output() {
+        if output.status.success() {
+            let path = String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or("").trim().to_string();
+            if !path.is_empty() {
+                return Some(PathBuf::from(path));
+            }
+        }
+    }
+    [
+        "/Applications/SuperCollider.app/Contents/MacOS/sclang",
+        "/Applications/SuperCollider/SuperCollider.app/Contents/MacOS/sclang",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .find(|p| cfg!(target_os = "macos") && p.exists())
+}
+
+/// Cache directory of a source: `<dir>/.vibe-scd/<stem>-<hash>`.
+fn cache_dir(path: &Path, source: &[u8]) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    path.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(SCD_CACHE_DIR)
+        .join(format!("{}-{:016x}", cache_stem(path), hasher.finish()))
+}
+
+fn cache_stem(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// sclang program that runs `scd` and writes the SynthDefs it adds to `out_dir`.
+///
+/// SynthDefs are found by comparing the global SynthDescLib before and after,
+/// so files using `.add`, `.store` or `.send` all work without a server.
+fn compile_script(scd: &Path, out_dir: &Path) -> String {
+    format!(
+        r#"(
+var lib = SynthDescLib.global;
+var before = lib.synthDescs.copy;
+var dir = "{out}";
+try {{
+    "{scd}".load;
+    lib.synthDescs.keysValuesDo {{ |name, desc|
+        if(before[name] !== desc and: {{ desc.def.notNil }}) {{
+            desc.def.writeDefFile(dir);
+        }};
+    }};
+    0.exit;
+}} {{ |error|
+    error.reportError;
+    1.exit;
+}};
+)
+"#,
+        scd = sclang_string(&scd.to_string_lossy()),
+        out = sclang_string(&format!("{}/", out_dir.to_string_lossy())),
+    )
+}
+
+/// Escape text for an sclang string literal.
+fn sclang_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Run a command, killing it after `timeout`.
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Result<std::process::Output> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start sclang")?;
+    let started = Instant::now();
+    loop {
+        if child.try_wait()?.is_some() {
+            return Ok(child.wait_with_output()?);
+        }
+        if started.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("sclang did not finish within {} seconds", timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal synthdef file header with one def called `name`.
+    fn synthdef_bytes(name: &str) -> Vec<u8> {
+        let mut bytes = b"SCgf".to_vec();
+        bytes.extend_from_slice(&2i32.to_be_bytes());
+        bytes.extend_from_slice(&1i16.to_be_bytes());
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_scd_cache_and_compile_script() {
+        let dir = std::env::temp_dir().join(format!("vibelang-scd-{}", std::process::id()));
+        let scd = dir.join("bass.scd");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&scd, "SynthDef(\\acid, { Out.ar(0, Saw.ar) }).add;").unwrap();
+
+        // The cache key follows the source
+        let cache = cache_dir(&scd, &fs::read(&scd).unwrap());
+        assert!(cache.starts_with(dir.join(SCD_CACHE_DIR)));
+        assert!(cache.file_name().unwrap().to_string_lossy().starts_with("bass-"));
+        assert_ne!(cache, cache_dir(&scd, b"changed"));
+
+        // A complete cache is used without running sclang
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("acid.scsyndef"), synthdef_bytes("acid")).unwrap();
+        fs::write(cache.join("wobble.scsyndef"), synthdef_bytes("wobble")).unwrap();
+        fs::write(cache.join(COMPLETE_MARKER), "").unwrap();
+        let names: Vec<String> = compile_scd(&scd).unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["acid", "wobble"]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let script = compile_script(Path::new("/synths/my \"bass\".scd"), Path::new("/cache"));
+        assert!(script.contains(r#""/synths/my \"bass\".scd".load"#));
+        assert!(script.contains(r#"var dir = "/cache/";"#));
+    }
+}
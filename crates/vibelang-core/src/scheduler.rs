@@ -29,6 +29,11 @@ pub struct LoopSnapshot {
     pub pattern: Pattern,
     /// Beat when this loop started playing.
     pub start_beat: f64,
+    /// First beat events are fired at; after `start_beat` when the loop
+    /// joined mid-way (legato launch).
+    pub launch_beat: f64,
+    /// Beat from which no more events are fired, if the loop is handing over.
+    pub stop_beat: Option<f64>,
     /// What kind of loop this is.
     pub kind: LoopKind,
     /// Group path for tagging events.
@@ -178,10 +183,14 @@ impl EventScheduler {
                     snapshot.start_beat + pattern.phase_offset + event_beat_in_pattern;
                 let loop_length = pattern.loop_length_beats;
 
-                // Find the first iteration that might be in our window
+                // Find the first iteration that might be in our window; a loop
+                // that joined mid-way skips the occurrences before its launch
+                let first_launched = ((snapshot.launch_beat - first_occurrence) / loop_length - 1e-9)
+                    .ceil()
+                    .max(0.0);
                 let iterations_since_start = ((current.to_float() - first_occurrence) / loop_length)
                     .floor()
-                    .max(0.0);
+                    .max(first_launched);
 
                 let mut iteration = iterations_since_start;
 
@@ -193,6 +202,9 @@ impl EventScheduler {
                     if absolute_beat > window_end.to_float() + 1e-9 {
                         break;
                     }
+                    if snapshot.stop_beat.is_some_and(|stop| absolute_beat >= stop - 1e-9) {
+                        break;
+                    }
 
                     let beat_time = BeatTime::from_float(absolute_beat);
 
@@ -278,12 +290,38 @@ mod tests {
         assert_eq!(scheduler.tracked_loop_count(), 0);
     }
 
+    #[test]
+    fn test_launch_and_stop_beats() {
+        let now = Instant::now();
+        let mut clock = TransportClock::new();
+        clock.seek(BeatTime::from_float(12.0), now);
+
+        // Anchored at 10 but launched at 14 (joined two beats into the loop), handing over at 18
+        let snapshot = LoopSnapshot {
+            name: "b".to_string(),
+            pattern: make_test_pattern(),
+            start_beat: 10.0,
+            launch_beat: 14.0,
+            stop_beat: Some(18.0),
+            kind: LoopKind::Sequence,
+            group_path: None,
+            voice_name: None,
+        };
+        let mut scheduler = EventScheduler::new();
+        scheduler.reset_to_beat(12.0);
+        let due = scheduler.collect_due_events(&clock, now, &[snapshot], &[], 4000);
+        let beats: Vec<f64> = due.iter().map(|(beat, _)| beat.to_float()).collect();
+        assert_eq!(beats, vec![14.0, 15.0, 16.0, 17.0]);
+    }
+
     #[test]
     fn test_loop_snapshot() {
         let snapshot = LoopSnapshot {
             name: "kick_pattern".to_string(),
             pattern: make_test_pattern(),
             start_beat: 0.0,
+            launch_beat: 0.0,
+            stop_beat: None,
             kind: LoopKind::Pattern,
             group_path: Some("main.drums".to_string()),
             voice_name: Some("kick".to_string()),
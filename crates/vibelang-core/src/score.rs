@@ -140,6 +140,16 @@ impl ScoreWriter {
         writer.flush()
     }
 
+    /// Drop the events at or after `time_seconds` that start synths, so a
+    /// score stopped there doesn't play what the scheduler sent ahead.
+    /// Releases and frees are kept. Returns the number of events dropped.
+    pub fn drop_synths_after(&mut self, time_seconds: f64) -> usize {
+        let before = self.events.len();
+        self.events
+            .retain(|e| e.time_seconds < time_seconds - 1e-6 || !starts_synth(&e.packet));
+        before - self.events.len()
+    }
+
     /// Clear all events from the score.
     pub fn clear(&mut self) {
         self.events.clear();
@@ -333,6 +343,14 @@ fn rewrite_sample_paths(packet: &OscPacket, path_map: &std::collections::HashMap
     }
 }
 
+/// Whether a packet holds an /s_new.
+fn starts_synth(packet: &OscPacket) -> bool {
+    match packet {
+        OscPacket::Message(msg) => msg.addr == "/s_new",
+        OscPacket::Bundle(bundle) => bundle.content.iter().any(starts_synth),
+    }
+}
+
 /// Extract synthdef name from raw synthdef bytes.
 ///
 /// SuperCollider synthdef format:
@@ -401,6 +419,24 @@ mod tests {
         assert_eq!(score.duration(), 2.5);
     }
 
+    #[test]
+    fn test_drop_synths_after() {
+        let mut score = ScoreWriter::new();
+        let s_new = |name: &str| {
+            OscPacket::Message(OscMessage {
+                addr: "/s_new".to_string(),
+                args: vec![OscType::String(name.to_string())],
+            })
+        };
+        score.add_bundle(1.0, vec![s_new("in_time")]);
+        score.add_bundle(2.0, vec![s_new("at_stop")]);
+        score.add_message(2.1, "/n_set", vec![OscType::Int(1000), OscType::String("gate".to_string()), OscType::Float(0.0)]);
+        score.add_bundle(2.2, vec![s_new("ahead")]);
+        assert_eq!(score.drop_synths_after(2.0), 2);
+        let times: Vec<f64> = score.events.iter().map(|e| e.time_seconds).collect();
+        assert_eq!(times, vec![1.0, 2.1]);
+    }
+
     #[test]
     fn test_seconds_to_osc_time() {
         let time = seconds_to_osc_time(0.0);
@@ -202,6 +202,16 @@ impl Scsynth {
         self.d_recv_bytes(bytes)
     }
 
+    /// Load all SynthDefs of a directory on the server's file system.
+    pub fn d_load_dir<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let p = path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid path"))?;
+        self.osc.send_msg("/d_loadDir", vec![OscType::String(p.into())])?;
+        Ok(())
+    }
+
     /// Allocate a buffer and read an audio file into it.
     pub fn b_alloc_read<P: AsRef<Path>>(&self, bufnum: BufNum, path: P) -> Result<()> {
         let p = path
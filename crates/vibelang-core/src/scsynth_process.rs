@@ -14,6 +14,9 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// How long to wait for scsynth to exit after `/quit` before killing it.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// Find the scsynth binary path based on the operating system.
 ///
 /// On Linux: Uses `scsynth` from PATH
@@ -431,6 +434,16 @@ impl Drop for ScsynthProcess {
         if let Some(mut child) = self.child.take() {
             log::info!("Stopping scsynth...");
             self.running.store(false, Ordering::Relaxed);
+            // The runtime sends /quit on graceful shutdown; give scsynth a
+            // moment to exit on its own before killing it
+            let deadline = Instant::now() + QUIT_TIMEOUT;
+            while Instant::now() < deadline {
+                if matches!(child.try_wait(), Ok(Some(_))) {
+                    log::info!("scsynth exited");
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
             let _ = child.kill();
             let _ = child.wait();
             log::info!("scsynth stopped");
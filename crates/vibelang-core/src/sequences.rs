@@ -17,7 +17,11 @@
 //! ```
 
 use crate::api::context::SourceLocation;
-use crate::events::FadeTargetType;
+use crate::events::{FadeCurve, FadeTargetType};
+
+/// Prefix of the sequences `pattern.start()` and `melody.start()` create;
+/// they belong to their clip rather than to the arrangement.
+pub const IMPLICIT_SEQUENCE_PREFIX: &str = "_seq_";
 
 /// Source that can be placed into a [`SequenceClip`].
 #[derive(Clone, Debug, PartialEq)]
@@ -86,6 +90,8 @@ pub struct SequenceClip {
     pub source: ClipSource,
     /// Playback mode (loop, once, or loop count).
     pub mode: ClipMode,
+    /// Tempo factor of the source (0.5 plays it at half time).
+    pub speed: f64,
 }
 
 impl SequenceClip {
@@ -96,9 +102,16 @@ impl SequenceClip {
             end,
             source,
             mode,
+            speed: 1.0,
         }
     }
 
+    /// Play the source at `speed` times its tempo.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = sanitize_speed(speed);
+        self
+    }
+
     /// Get the duration of the clip in beats.
     pub fn duration(&self) -> f64 {
         self.end - self.start
@@ -115,6 +128,28 @@ impl SequenceClip {
     }
 }
 
+/// A key change on a sequence timeline.
+///
+/// Melodic events from `start` up to `end` (or the end of the sequence) are
+/// transposed by `semitones`, on top of the sequence's own transposition
+/// and any overlapping key changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyChange {
+    /// Start beat within the sequence.
+    pub start: f64,
+    /// End beat within the sequence (`None` = to the end).
+    pub end: Option<f64>,
+    /// Semitones to transpose by.
+    pub semitones: i32,
+}
+
+impl KeyChange {
+    /// Check if a beat position falls within this key change.
+    pub fn contains_beat(&self, beat: f64) -> bool {
+        beat >= self.start && self.end.is_none_or(|end| beat < end)
+    }
+}
+
 /// Definition of a sequence that can be started and looped.
 ///
 /// Sequences are the primary way to arrange musical material
@@ -134,6 +169,17 @@ pub struct SequenceDefinition {
     pub play_once: bool,
     /// Source location where this sequence was defined.
     pub source_location: SourceLocation,
+    /// Tempo factor of the whole sequence (0.5 plays it at half time).
+    pub speed: f64,
+    /// Launch grid in beats, overriding the global quantization.
+    pub launch_quantization: Option<f64>,
+    /// When launched while another sequence plays the same voices, take
+    /// over at that sequence's phase instead of starting from the top.
+    pub legato: bool,
+    /// Semitones all melodic events of the sequence are transposed by.
+    pub transpose: i32,
+    /// Timed key changes, adding to `transpose` where they apply.
+    pub key_changes: Vec<KeyChange>,
 }
 
 impl SequenceDefinition {
@@ -146,9 +192,19 @@ impl SequenceDefinition {
             generation: 0,
             play_once: false,
             source_location: SourceLocation::default(),
+            speed: 1.0,
+            launch_quantization: None,
+            legato: false,
+            transpose: 0,
+            key_changes: Vec::new(),
         }
     }
 
+    /// Whether `start()` on a pattern, melody or ctrl created this sequence.
+    pub fn is_implicit(&self) -> bool {
+        self.name.starts_with(IMPLICIT_SEQUENCE_PREFIX)
+    }
+
     /// Set source location for this sequence.
     pub fn with_source_location(mut self, source_location: SourceLocation) -> Self {
         self.source_location = source_location;
@@ -161,6 +217,52 @@ impl SequenceDefinition {
         self
     }
 
+    /// Play the sequence at `speed` times its tempo.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = sanitize_speed(speed);
+        self
+    }
+
+    /// Launch on a grid of `beats` instead of the global quantization.
+    pub fn with_launch_quantization(mut self, beats: f64) -> Self {
+        self.launch_quantization = Some(beats.max(0.0));
+        self
+    }
+
+    /// Take over the phase of the sequence this one replaces.
+    pub fn with_legato(mut self, legato: bool) -> Self {
+        self.legato = legato;
+        self
+    }
+
+    /// Length of one iteration on the transport, in beats, after `speed`.
+    pub fn playback_beats(&self) -> f64 {
+        self.loop_beats / self.speed
+    }
+
+    /// Transpose the sequence's melodic events by `semitones`.
+    pub fn with_transpose(mut self, semitones: i32) -> Self {
+        self.transpose = semitones;
+        self
+    }
+
+    /// Add a key change.
+    pub fn with_key_change(mut self, key_change: KeyChange) -> Self {
+        self.key_changes.push(key_change);
+        self
+    }
+
+    /// Semitones melodic events at `beat` (within one iteration) are transposed by.
+    pub fn transposition_at(&self, beat: f64) -> i32 {
+        self.transpose
+            + self
+                .key_changes
+                .iter()
+                .filter(|k| k.contains_beat(beat))
+                .map(|k| k.semitones)
+                .sum::<i32>()
+    }
+
     /// Add a clip to the sequence.
     pub fn with_clip(mut self, clip: SequenceClip) -> Self {
         self.clips.push(clip);
@@ -179,6 +281,44 @@ impl SequenceDefinition {
     }
 }
 
+/// Next launch point at or after `beat` on a grid of `quantization` beats.
+///
+/// A grid of 0 launches immediately.
+pub fn next_launch_beat(beat: f64, quantization: f64) -> f64 {
+    if quantization <= 1e-9 {
+        return beat.max(0.0);
+    }
+    ((beat / quantization).ceil() * quantization).max(0.0)
+}
+
+/// Position a legato launch continues at.
+///
+/// The sequence being replaced (started at `previous_anchor`, one
+/// iteration `previous_beats` long) is at some position at `launch_beat`;
+/// the new sequence picks up the same position, wrapped to its own length.
+pub fn legato_phase(launch_beat: f64, previous_anchor: f64, previous_beats: f64, next_beats: f64) -> f64 {
+    if previous_beats <= 1e-9 || next_beats <= 1e-9 {
+        return 0.0;
+    }
+    let phase = (launch_beat - previous_anchor).max(0.0).rem_euclid(previous_beats).rem_euclid(next_beats);
+    // Landing a hair below the loop point means the top of the loop
+    if next_beats - phase < 1e-9 {
+        0.0
+    } else {
+        phase
+    }
+}
+
+/// Speed factors outside (0, ∞) fall back to normal speed.
+pub fn sanitize_speed(speed: f64) -> f64 {
+    if speed.is_finite() && speed > 0.0 {
+        speed
+    } else {
+        log::warn!("Ignoring invalid playback speed {}", speed);
+        1.0
+    }
+}
+
 /// Definition of a fade automation that can be scheduled from sequences.
 ///
 /// FadeDefinitions are created in scripts and can be placed as clips
@@ -199,6 +339,13 @@ pub struct FadeDefinition {
     pub to: f32,
     /// Duration in beats.
     pub duration_beats: f64,
+    /// Interpolation between `from` and `to`.
+    pub curve: FadeCurve,
+    /// Launch grid in beats when started on its own, overriding the
+    /// defaults.
+    pub launch_quantization: Option<f64>,
+    /// Source location where this fade was defined.
+    pub source_location: SourceLocation,
 }
 
 impl FadeDefinition {
@@ -217,6 +364,9 @@ impl FadeDefinition {
             from: 0.0,
             to: 1.0,
             duration_beats: 4.0,
+            curve: FadeCurve::Linear,
+            launch_quantization: None,
+            source_location: SourceLocation::default(),
         }
     }
 
@@ -232,6 +382,18 @@ impl FadeDefinition {
         self.duration_beats = beats;
         self
     }
+
+    /// Set how the fade interpolates.
+    pub fn with_curve(mut self, curve: FadeCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Set source location for this fade.
+    pub fn with_source_location(mut self, source_location: SourceLocation) -> Self {
+        self.source_location = source_location;
+        self
+    }
 }
 
 // ============================================================================
@@ -249,10 +411,20 @@ impl SequenceDefinition {
         self.name.hash(&mut hasher);
         self.loop_beats.to_bits().hash(&mut hasher);
         self.play_once.hash(&mut hasher);
+        self.speed.to_bits().hash(&mut hasher);
+        self.launch_quantization.map(f64::to_bits).hash(&mut hasher);
+        self.legato.hash(&mut hasher);
+        self.transpose.hash(&mut hasher);
+        for key_change in &self.key_changes {
+            key_change.start.to_bits().hash(&mut hasher);
+            key_change.end.map(f64::to_bits).hash(&mut hasher);
+            key_change.semitones.hash(&mut hasher);
+        }
         // Hash clips in order
         for clip in &self.clips {
             clip.start.to_bits().hash(&mut hasher);
             clip.end.to_bits().hash(&mut hasher);
+            clip.speed.to_bits().hash(&mut hasher);
             // Hash clip source
             match &clip.source {
                 ClipSource::Pattern(name) => {
@@ -333,6 +505,66 @@ mod tests {
         assert_eq!(seq.clips.len(), 2);
     }
 
+    #[test]
+    fn test_sequence_speed() {
+        let seq = SequenceDefinition::new("breakdown")
+            .with_loop_beats(16.0)
+            .with_clip(SequenceClip::new(0.0, 16.0, ClipSource::Pattern("breaks".to_string()), ClipMode::Loop));
+        let normal_hash = seq.content_hash();
+
+        let half = seq.clone().with_speed(0.5);
+        assert_eq!(half.playback_beats(), 32.0);
+        assert_ne!(half.content_hash(), normal_hash);
+        assert_eq!(seq.clone().with_speed(-1.0).speed, 1.0);
+        assert_eq!(seq.clone().with_speed(f64::NAN).playback_beats(), 16.0);
+
+        let mut clip_speed = seq.clone();
+        clip_speed.clips[0] = clip_speed.clips[0].clone().with_speed(2.0);
+        assert_ne!(clip_speed.content_hash(), normal_hash);
+    }
+
+    #[test]
+    fn test_sequence_transposition() {
+        let seq = SequenceDefinition::new("song").with_loop_beats(32.0);
+        let hash = seq.content_hash();
+
+        let seq = seq
+            .with_transpose(2)
+            .with_key_change(KeyChange {
+                start: 8.0,
+                end: Some(16.0),
+                semitones: 5,
+            })
+            .with_key_change(KeyChange {
+                start: 12.0,
+                end: None,
+                semitones: -1,
+            });
+        assert_ne!(seq.content_hash(), hash);
+        assert_eq!(seq.transposition_at(0.0), 2);
+        assert_eq!(seq.transposition_at(8.0), 7);
+        // Overlapping key changes add up
+        assert_eq!(seq.transposition_at(12.0), 6);
+        assert_eq!(seq.transposition_at(16.0), 1);
+        assert_eq!(seq.transposition_at(31.0), 1);
+    }
+
+    #[test]
+    fn test_launch_quantization_and_legato_phase() {
+        assert_eq!(next_launch_beat(5.5, 4.0), 8.0);
+        assert_eq!(next_launch_beat(8.0, 4.0), 8.0);
+        assert_eq!(next_launch_beat(5.5, 0.0), 5.5);
+        assert_eq!(next_launch_beat(5.5, 1.0), 6.0);
+
+        // A started at 0 (8 beats): switching on beat 14 lands 6 beats in
+        assert_eq!(legato_phase(14.0, 0.0, 8.0, 8.0), 6.0);
+        // ...which is beat 2 of a 4-beat loop
+        assert_eq!(legato_phase(14.0, 0.0, 8.0, 4.0), 2.0);
+        // On the previous loop point, the new one starts from the top
+        assert_eq!(legato_phase(16.0, 0.0, 8.0, 8.0), 0.0);
+        assert_eq!(legato_phase(3.0, 0.0, 0.0, 8.0), 0.0);
+    }
+
     #[test]
     fn test_clips_at_beat() {
         let seq = SequenceDefinition::new("test")
@@ -0,0 +1,511 @@
+//! Session snapshots.
+//!
+//! When `vibe run` shuts down (Ctrl+C, SIGTERM, quitting the TUI), it writes
+//! the live state of the set next to the script: transport position, what is
+//! playing and the current group and voice parameters. `--resume` uses the
+//! snapshot to pick the set up where it stopped after an orchestrated restart.
+//!
+//! Snapshots live in a `.vibe-session` directory next to the script:
+//!
+//! ```text
+//! # Session snapshot for song.vibe
+//! tempo 128
+//! beat 412.5
+//! time_signature 4/4
+//! key A minor
+//! checkpoint drop
+//!
+//! [sequences]
+//! main 256
+//!
+//! [patterns]
+//! fill 384
+//!
+//! [groups]
+//! main/Bass amp=0.8 muted
+//!
+//! [voices]
+//! kick gain=0.9 amp=0.5
+//! ```
+//!
+//! Numbers after sequence, pattern and melody names are the beats they were
+//! started at.
+
+use crate::musical_key::MusicalKey;
+use crate::state::{ScriptState, StateMessage};
+use crate::timing::TimeSignature;
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory (next to the script) holding session snapshots.
+pub const SESSION_DIR: &str = ".vibe-session";
+
+/// File extension of session snapshots.
+pub const SESSION_EXTENSION: &str = "session";
+
+/// Mixer state of a group.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupSnapshot {
+    pub path: String,
+    pub muted: bool,
+    pub soloed: bool,
+    /// Parameters sorted by name.
+    pub params: Vec<(String, f32)>,
+}
+
+/// Mixer state of a voice.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoiceSnapshot {
+    pub name: String,
+    pub gain: f64,
+    pub muted: bool,
+    pub soloed: bool,
+    /// Parameters sorted by name.
+    pub params: Vec<(String, f32)>,
+}
+
+/// The live state of a session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionSnapshot {
+    pub tempo: f64,
+    /// Transport position in beats.
+    pub beat: f64,
+    pub time_signature: TimeSignature,
+    pub key: Option<MusicalKey>,
+    /// Selected checkpoint.
+    pub checkpoint: Option<String>,
+    /// Playing sequences and their anchor beats.
+    pub sequences: Vec<(String, f64)>,
+    /// Playing patterns and their start beats.
+    pub patterns: Vec<(String, f64)>,
+    /// Playing melodies and their start beats.
+    pub melodies: Vec<(String, f64)>,
+    pub groups: Vec<GroupSnapshot>,
+    pub voices: Vec<VoiceSnapshot>,
+}
+
+impl Default for SessionSnapshot {
+    fn default() -> Self {
+        Self {
+            tempo: 120.0,
+            beat: 0.0,
+            time_signature: TimeSignature::default(),
+            key: None,
+            checkpoint: None,
+            sequences: Vec::new(),
+            patterns: Vec::new(),
+            melodies: Vec::new(),
+            groups: Vec::new(),
+            voices: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Preamble,
+    Sequences,
+    Patterns,
+    Melodies,
+    Groups,
+    Voices,
+}
+
+impl SessionSnapshot {
+    /// Capture the live state.
+    pub fn capture(state: &ScriptState) -> Self {
+        let mut sequences: Vec<(String, f64)> = state
+            .active_sequences
+            .iter()
+            .filter(|(_, seq)| !seq.paused && !seq.completed)
+            .map(|(name, seq)| (name.clone(), seq.anchor_beat))
+            .collect();
+        let mut patterns: Vec<(String, f64)> = state
+            .patterns
+            .values()
+            .filter_map(|p| p.status.start_beat().map(|beat| (p.name.clone(), beat)))
+            .collect();
+        let mut melodies: Vec<(String, f64)> = state
+            .melodies
+            .values()
+            .filter_map(|m| m.status.start_beat().map(|beat| (m.name.clone(), beat)))
+            .collect();
+        let mut groups: Vec<GroupSnapshot> = state
+            .groups
+            .values()
+            .map(|g| GroupSnapshot {
+                path: g.path.clone(),
+                muted: g.muted,
+                soloed: g.soloed,
+                params: sorted_params(&g.params),
+            })
+            .collect();
+        let mut voices: Vec<VoiceSnapshot> = state
+            .voices
+            .values()
+            .map(|v| VoiceSnapshot {
+                name: v.name.clone(),
+                gain: v.gain,
+                muted: v.muted,
+                soloed: v.soloed,
+                params: sorted_params(&v.params),
+            })
+            .collect();
+
+        sequences.sort_by(|a, b| a.0.cmp(&b.0));
+        patterns.sort_by(|a, b| a.0.cmp(&b.0));
+        melodies.sort_by(|a, b| a.0.cmp(&b.0));
+        groups.sort_by(|a, b| a.path.cmp(&b.path));
+        voices.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            tempo: state.tempo,
+            beat: state.current_beat,
+            time_signature: state.time_signature,
+            key: state.session_key,
+            checkpoint: state.checkpoints.selected.clone(),
+            sequences,
+            patterns,
+            melodies,
+            groups,
+            voices,
+        }
+    }
+
+    /// Messages restoring tempo, meter, key and the group and voice mixer.
+    ///
+    /// Transport position and what is playing are restored by the runtime
+    /// (`StateMessage::RestoreSession`), which also applies voice gain and solo.
+    pub fn mixer_messages(&self) -> Vec<StateMessage> {
+        let mut messages = vec![
+            StateMessage::SetBpm { bpm: self.tempo },
+            StateMessage::SetTimeSignature {
+                numerator: self.time_signature.numerator,
+                denominator: self.time_signature.denominator,
+            },
+            StateMessage::SetSessionKey { key: self.key },
+        ];
+        for group in &self.groups {
+            let path = group.path.clone();
+            messages.extend(group.params.iter().map(|(param, value)| StateMessage::SetGroupParam {
+                path: path.clone(),
+                param: param.clone(),
+                value: *value,
+            }));
+            messages.push(StateMessage::SoloGroup {
+                path: path.clone(),
+                solo: group.soloed,
+            });
+            messages.push(if group.muted {
+                StateMessage::MuteGroup { path }
+            } else {
+                StateMessage::UnmuteGroup { path }
+            });
+        }
+        for voice in &self.voices {
+            let name = voice.name.clone();
+            messages.extend(voice.params.iter().map(|(param, value)| StateMessage::SetVoiceParam {
+                name: name.clone(),
+                param: param.clone(),
+                value: *value,
+            }));
+            messages.push(if voice.muted {
+                StateMessage::MuteVoice { name }
+            } else {
+                StateMessage::UnmuteVoice { name }
+            });
+        }
+        messages
+    }
+
+    /// Path of the snapshot for a script.
+    pub fn snapshot_path(script: &Path) -> PathBuf {
+        let stem = script
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "script".to_string());
+        script
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(SESSION_DIR)
+            .join(stem)
+            .with_extension(SESSION_EXTENSION)
+    }
+
+    /// Load the snapshot for a script, if one was written.
+    pub fn load(script: &Path) -> Result<Option<Self>> {
+        let path = Self::snapshot_path(script);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read session snapshot: {}", path.display()))?;
+        Self::parse(&source)
+            .map(Some)
+            .with_context(|| format!("Invalid session snapshot: {}", path.display()))
+    }
+
+    /// Write the snapshot for a script.
+    ///
+    /// The file is written next to its final path and renamed into place, so
+    /// a crash mid-write leaves the previous snapshot intact.
+    pub fn save(&self, script: &Path) -> Result<PathBuf> {
+        let path = Self::snapshot_path(script);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temp = path.with_extension(format!("{}.tmp", SESSION_EXTENSION));
+        fs::write(&temp, self.to_text(script))
+            .with_context(|| format!("Failed to write session snapshot: {}", temp.display()))?;
+        fs::rename(&temp, &path)
+            .with_context(|| format!("Failed to write session snapshot: {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Render the snapshot as text.
+    pub fn to_text(&self, script: &Path) -> String {
+        let mut out = format!(
+            "# Session snapshot for {}\ntempo {}\nbeat {}\ntime_signature {}/{}\n",
+            script.file_name().unwrap_or_default().to_string_lossy(),
+            self.tempo,
+            self.beat,
+            self.time_signature.numerator,
+            self.time_signature.denominator
+        );
+        if let Some(key) = &self.key {
+            out.push_str(&format!("key {}\n", key));
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            out.push_str(&format!("checkpoint {}\n", checkpoint));
+        }
+        for (header, entries) in [
+            ("sequences", &self.sequences),
+            ("patterns", &self.patterns),
+            ("melodies", &self.melodies),
+        ] {
+            out.push_str(&format!("\n[{}]\n", header));
+            for (name, beat) in entries {
+                out.push_str(&format!("{} {}\n", name, beat));
+            }
+        }
+        out.push_str("\n[groups]\n");
+        for group in &self.groups {
+            out.push_str(&group.path);
+            push_params(&mut out, &group.params);
+            push_flags(&mut out, group.muted, group.soloed);
+            out.push('\n');
+        }
+        out.push_str("\n[voices]\n");
+        for voice in &self.voices {
+            out.push_str(&format!("{} gain={}", voice.name, voice.gain));
+            push_params(&mut out, &voice.params);
+            push_flags(&mut out, voice.muted, voice.soloed);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parse snapshot text.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut snapshot = Self::default();
+        let mut section = Section::Preamble;
+
+        for (index, raw_line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at_line = |e: anyhow::Error| anyhow!("line {}: {}", line_number, e);
+
+            if let Some(header) = line.strip_prefix('[') {
+                section = match header.strip_suffix(']').map(str::trim) {
+                    Some("sequences") => Section::Sequences,
+                    Some("patterns") => Section::Patterns,
+                    Some("melodies") => Section::Melodies,
+                    Some("groups") => Section::Groups,
+                    Some("voices") => Section::Voices,
+                    _ => bail!("line {}: unknown section '{}'", line_number, line),
+                };
+                continue;
+            }
+
+            match section {
+                Section::Preamble => parse_setting(line, &mut snapshot).map_err(at_line)?,
+                Section::Sequences => snapshot.sequences.push(parse_started(line).map_err(at_line)?),
+                Section::Patterns => snapshot.patterns.push(parse_started(line).map_err(at_line)?),
+                Section::Melodies => snapshot.melodies.push(parse_started(line).map_err(at_line)?),
+                Section::Groups => {
+                    let entity = parse_entity(line).map_err(at_line)?;
+                    snapshot.groups.push(GroupSnapshot {
+                        path: entity.name,
+                        muted: entity.muted,
+                        soloed: entity.soloed,
+                        params: entity.params,
+                    });
+                }
+                Section::Voices => {
+                    let entity = parse_entity(line).map_err(at_line)?;
+                    snapshot.voices.push(VoiceSnapshot {
+                        name: entity.name,
+                        gain: entity.gain.unwrap_or(1.0),
+                        muted: entity.muted,
+                        soloed: entity.soloed,
+                        params: entity.params,
+                    });
+                }
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+fn sorted_params(params: &std::collections::HashMap<String, f32>) -> Vec<(String, f32)> {
+    let mut params: Vec<(String, f32)> = params.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    params.sort_by(|a, b| a.0.cmp(&b.0));
+    params
+}
+
+fn push_params(out: &mut String, params: &[(String, f32)]) {
+    for (name, value) in params {
+        out.push_str(&format!(" {}={}", name, value));
+    }
+}
+
+fn push_flags(out: &mut String, muted: bool, soloed: bool) {
+    if muted {
+        out.push_str(" muted");
+    }
+    if soloed {
+        out.push_str(" soloed");
+    }
+}
+
+fn parse_setting(line: &str, snapshot: &mut SessionSnapshot) -> Result<()> {
+    let (key, value) = line
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| anyhow!("expected '<setting> <value>'"))?;
+    let value = value.trim();
+    match key {
+        "tempo" => snapshot.tempo = parse_number(value)?,
+        "beat" => snapshot.beat = parse_number(value)?,
+        "time_signature" => {
+            let (num, den) = value
+                .split_once('/')
+                .ok_or_else(|| anyhow!("expected a time signature like 4/4"))?;
+            snapshot.time_signature = TimeSignature::new(
+                num.trim().parse().context("invalid numerator")?,
+                den.trim().parse().context("invalid denominator")?,
+            );
+        }
+        "key" => {
+            snapshot.key = Some(MusicalKey::parse(value).ok_or_else(|| anyhow!("invalid key '{}'", value))?)
+        }
+        "checkpoint" => snapshot.checkpoint = Some(value.to_string()),
+        other => bail!("unknown setting '{}'", other),
+    }
+    Ok(())
+}
+
+fn parse_number(value: &str) -> Result<f64> {
+    value.parse().map_err(|_| anyhow!("invalid number '{}'", value))
+}
+
+fn parse_started(line: &str) -> Result<(String, f64)> {
+    let (name, beat) = line
+        .rsplit_once(char::is_whitespace)
+        .ok_or_else(|| anyhow!("expected '<name> <start beat>'"))?;
+    Ok((name.trim().to_string(), parse_number(beat.trim())?))
+}
+
+/// A parsed group or voice line.
+struct EntityLine {
+    name: String,
+    gain: Option<f64>,
+    params: Vec<(String, f32)>,
+    muted: bool,
+    soloed: bool,
+}
+
+/// Parse `<name> key=value ... [muted] [soloed]` (`gain` is kept at full precision).
+fn parse_entity(line: &str) -> Result<EntityLine> {
+    let mut words = line.split_whitespace();
+    let mut entity = EntityLine {
+        name: words.next().ok_or_else(|| anyhow!("missing name"))?.to_string(),
+        gain: None,
+        params: Vec::new(),
+        muted: false,
+        soloed: false,
+    };
+    for word in words {
+        match word {
+            "muted" => entity.muted = true,
+            "soloed" => entity.soloed = true,
+            _ => {
+                let (key, value) = word
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected 'param=value', got '{}'", word))?;
+                let value = parse_number(value)?;
+                if key == "gain" {
+                    entity.gain = Some(value);
+                } else {
+                    entity.params.push((key.to_string(), value as f32));
+                }
+            }
+        }
+    }
+    Ok(entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let snapshot = SessionSnapshot {
+            tempo: 128.0,
+            beat: 412.5,
+            time_signature: TimeSignature::new(7, 8),
+            key: MusicalKey::parse("A minor"),
+            checkpoint: Some("drop".to_string()),
+            sequences: vec![("main".to_string(), 256.0)],
+            patterns: vec![("fill".to_string(), 384.0)],
+            melodies: Vec::new(),
+            groups: vec![GroupSnapshot {
+                path: "main/Bass".to_string(),
+                muted: true,
+                soloed: false,
+                params: vec![("amp".to_string(), 0.8)],
+            }],
+            voices: vec![VoiceSnapshot {
+                name: "kick".to_string(),
+                gain: 0.9,
+                muted: false,
+                soloed: true,
+                params: vec![("amp".to_string(), 0.5), ("cutoff".to_string(), 1200.0)],
+            }],
+        };
+        let script = Path::new("/songs/song.vibe");
+        let text = snapshot.to_text(script);
+        assert_eq!(SessionSnapshot::parse(&text).unwrap(), snapshot);
+        assert_eq!(
+            SessionSnapshot::snapshot_path(script),
+            PathBuf::from("/songs/.vibe-session/song.session")
+        );
+
+        let messages = snapshot.mixer_messages();
+        assert!(matches!(messages[0], StateMessage::SetBpm { bpm } if bpm == 128.0));
+        assert!(messages
+            .iter()
+            .any(|m| matches!(m, StateMessage::MuteGroup { path } if path == "main/Bass")));
+        assert!(messages.iter().any(
+            |m| matches!(m, StateMessage::SetVoiceParam { name, param, value } if name == "kick" && param == "cutoff" && *value == 1200.0)
+        ));
+
+        let err = SessionSnapshot::parse("tempo fast").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+}
@@ -0,0 +1,1124 @@
+//! Session files: the full state of a session in one JSON file.
+//!
+//! Unlike [session snapshots](crate::session), which only record what is
+//! playing and need the script to rebuild everything else, a session file
+//! holds the definitions themselves — compiled synthdefs, groups, voices,
+//! patterns, melodies, sequences, fades, effects, samples, instruments and
+//! recorded automation — along with tempo, meter, key, transport position
+//! and what was playing. A set changed live over HTTP or the TUI can be
+//! saved and loaded back after a crash, with or without the script that
+//! started it.
+//!
+//! Loading a file works like a reload: its entities replace the session's,
+//! and entities the file doesn't hold are removed.
+//!
+//! Not stored: MIDI devices and routes, loopers, hooks, return channels,
+//! live sets, playback graphs, macros and meter conditions. Samples and
+//! instruments are stored by path and loaded from disk again.
+//!
+//! ```text
+//! vibe session save set.vibesession     # from a running session
+//! vibe session load set.vibesession     # into a running session
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::api::context::SourceLocation;
+use crate::automation::{parse_target_type, target_type_name, AutomationLane, AutomationPoint};
+use crate::chord::Arpeggiator;
+use crate::drumkit::{DrumKit, DrumPad};
+use crate::events::{BeatEvent, FadeCurve, FadeTargetType, Pattern};
+use crate::groove::{Humanize, JitterDistribution, TimingFeel};
+use crate::mono::{MonoMode, NotePriority};
+use crate::musical_key::MusicalKey;
+use crate::quantization::LaunchKind;
+use crate::scale::{Scale, ScaleSnap};
+use crate::scheduler::LoopKind;
+use crate::sequences::{ClipMode, ClipSource, FadeDefinition, KeyChange, SequenceClip, SequenceDefinition};
+use crate::session::{GroupSnapshot, SessionSnapshot, VoiceSnapshot};
+use crate::state::{ScriptState, StateMessage};
+use crate::timing::{Beats, TimeSignature, TimeSpan};
+use crate::variations::{EventVariation, PatternVariations};
+
+/// Value of the `format` field of a session file.
+pub const SESSION_FILE_FORMAT: &str = "vibelang-session";
+
+/// Version of the session file layout.
+pub const SESSION_FILE_VERSION: u32 = 1;
+
+/// Extension of session files.
+pub const SESSION_FILE_EXTENSION: &str = "vibesession";
+
+/// A compiled synthdef, its bytes hex-encoded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SynthDefRecord {
+    pub name: String,
+    pub bytes: String,
+}
+
+/// A group and its mixer state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GroupRecord {
+    pub name: String,
+    pub path: String,
+    pub parent_path: Option<String>,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub soloed: bool,
+}
+
+/// A sample loaded from a file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SampleRecord {
+    pub id: String,
+    pub path: String,
+}
+
+/// An SFZ instrument loaded from a file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SfzRecord {
+    pub id: String,
+    pub path: String,
+}
+
+/// A VST instrument and the parameters set on it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VstRecord {
+    pub id: String,
+    pub plugin: String,
+    pub group_path: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+}
+
+/// An effect in a group's chain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EffectRecord {
+    pub id: String,
+    pub synthdef: String,
+    pub group_path: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub vst_plugin: Option<String>,
+    /// Voice the effect is inserted on; unset for effects of the group.
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+/// Timing feel of a voice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeelRecord {
+    pub offset_ms: f64,
+    pub jitter_ms: f64,
+    pub distribution: String,
+}
+
+/// Mono mode of a voice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MonoRecord {
+    pub priority: String,
+    pub glide_ms: f64,
+}
+
+/// A pad of a drum kit voice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DrumPadRecord {
+    pub name: String,
+    pub note: u8,
+    pub step: Option<char>,
+    pub sample_id: String,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    pub rate: f32,
+    pub gain: f32,
+    pub pitch: f32,
+    pub choke: Option<i64>,
+}
+
+/// A voice definition and its mixer state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VoiceRecord {
+    pub name: String,
+    pub group_path: String,
+    pub group_name: Option<String>,
+    pub synth: Option<String>,
+    pub polyphony: i64,
+    pub gain: f64,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub soloed: bool,
+    #[serde(default)]
+    pub output_bus: Option<i64>,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub sfz_instrument: Option<String>,
+    #[serde(default)]
+    pub vst_instrument: Option<String>,
+    /// MIDI channel (0-15) of VST and MIDI output voices.
+    #[serde(default)]
+    pub midi_channel: Option<u8>,
+    #[serde(default)]
+    pub cc_mappings: BTreeMap<String, u8>,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default)]
+    pub key_match: Option<String>,
+    #[serde(default)]
+    pub pre_roll_ms: f64,
+    #[serde(default)]
+    pub feel: Option<FeelRecord>,
+    #[serde(default)]
+    pub mono: Option<MonoRecord>,
+    #[serde(default)]
+    pub drum_kit: Option<Vec<DrumPadRecord>>,
+}
+
+/// An event of a pattern or melody.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub beat: f64,
+    pub synth_def: String,
+    #[serde(default)]
+    pub controls: Vec<(String, f32)>,
+    #[serde(default)]
+    pub group_path: Option<String>,
+    #[serde(default)]
+    pub voice_name: Option<String>,
+    #[serde(default)]
+    pub step: Option<usize>,
+    /// Take of a pattern with variations, and the event's beat within it.
+    #[serde(default)]
+    pub variation: Option<(usize, f64)>,
+}
+
+/// A pattern or melody.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoopRecord {
+    pub name: String,
+    pub group_path: String,
+    pub voice_name: Option<String>,
+    pub length: f64,
+    #[serde(default)]
+    pub phase_offset: f64,
+    pub events: Vec<EventRecord>,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    /// Step string of a pattern, for visual editing.
+    #[serde(default)]
+    pub steps: Option<String>,
+    /// Note lanes of a melody, for visual editing.
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// Weights of a pattern's takes.
+    #[serde(default)]
+    pub variation_weights: Option<Vec<f64>>,
+    #[serde(default)]
+    pub variation_seed: u64,
+    /// What a melody's recorded takes snap to: "key" (the session key) or a
+    /// scale such as "D4 dorian".
+    #[serde(default)]
+    pub scale_snap: Option<String>,
+    /// Arpeggiator of a melody, e.g. "updown 0.25 1" (mode, step in beats, octaves).
+    #[serde(default)]
+    pub arp: Option<String>,
+    /// Humanize amount: timing in ms and velocity in percent.
+    #[serde(default)]
+    pub humanize: Option<(f64, f64)>,
+}
+
+/// A clip on a sequence's timeline.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClipRecord {
+    pub start: f64,
+    pub end: f64,
+    /// "pattern", "melody", "fade" or "sequence".
+    pub kind: String,
+    pub source: String,
+    /// "loop", "once" or the number of passes.
+    pub mode: String,
+    pub speed: f64,
+}
+
+/// A key change within a sequence.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyChangeRecord {
+    pub start: f64,
+    pub end: Option<f64>,
+    pub semitones: i32,
+}
+
+/// A sequence definition.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceRecord {
+    pub name: String,
+    pub length: f64,
+    pub clips: Vec<ClipRecord>,
+    #[serde(default)]
+    pub play_once: bool,
+    pub speed: f64,
+    #[serde(default)]
+    pub launch_quantization: Option<f64>,
+    #[serde(default)]
+    pub legato: bool,
+    #[serde(default)]
+    pub transpose: i32,
+    #[serde(default)]
+    pub key_changes: Vec<KeyChangeRecord>,
+}
+
+/// A fade definition.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FadeRecord {
+    pub name: String,
+    /// "group", "voice", "pattern", "melody" or "effect".
+    pub target_type: String,
+    pub target: String,
+    pub param: String,
+    pub from: f32,
+    pub to: f32,
+    pub beats: f64,
+    /// "linear" or "db".
+    pub curve: String,
+    /// Launch grid in beats when started on its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch_quantization: Option<f64>,
+}
+
+/// An automation lane.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AutomationRecord {
+    /// "group", "voice", "pattern", "melody" or "effect".
+    pub target_type: String,
+    pub target: String,
+    pub param: String,
+    pub beats: f64,
+    /// Position in the lane (beats) and value of each point.
+    pub points: Vec<(f64, f32)>,
+}
+
+/// What was playing, with the beats it started at.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayingRecord {
+    #[serde(default)]
+    pub sequences: Vec<(String, f64)>,
+    #[serde(default)]
+    pub patterns: Vec<(String, f64)>,
+    #[serde(default)]
+    pub melodies: Vec<(String, f64)>,
+}
+
+/// The full state of a session.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionFile {
+    pub format: String,
+    pub version: u32,
+    pub tempo: f64,
+    pub time_signature: (u32, u32),
+    pub quantization_beats: f64,
+    /// Launch grids by entity type ("pattern", "melody", "sequence", "fade").
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub launch_quantization: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Transport position in beats.
+    #[serde(default)]
+    pub beat: f64,
+    #[serde(default)]
+    pub synthdefs: Vec<SynthDefRecord>,
+    #[serde(default)]
+    pub groups: Vec<GroupRecord>,
+    #[serde(default)]
+    pub samples: Vec<SampleRecord>,
+    #[serde(default)]
+    pub sfz_instruments: Vec<SfzRecord>,
+    #[serde(default)]
+    pub vst_instruments: Vec<VstRecord>,
+    #[serde(default)]
+    pub voices: Vec<VoiceRecord>,
+    #[serde(default)]
+    pub effects: Vec<EffectRecord>,
+    #[serde(default)]
+    pub fades: Vec<FadeRecord>,
+    #[serde(default)]
+    pub patterns: Vec<LoopRecord>,
+    #[serde(default)]
+    pub melodies: Vec<LoopRecord>,
+    #[serde(default)]
+    pub sequences: Vec<SequenceRecord>,
+    #[serde(default)]
+    pub automation: Vec<AutomationRecord>,
+    #[serde(default)]
+    pub playing: PlayingRecord,
+}
+
+impl SessionFile {
+    /// Capture the state of a session.
+    pub fn capture(state: &ScriptState) -> Self {
+        let snapshot = SessionSnapshot::capture(state);
+
+        let mut synthdefs: Vec<SynthDefRecord> = state
+            .synthdefs
+            .iter()
+            .map(|(name, bytes)| SynthDefRecord {
+                name: name.clone(),
+                bytes: to_hex(bytes),
+            })
+            .collect();
+        synthdefs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut groups: Vec<GroupRecord> = state
+            .groups
+            .values()
+            .map(|g| GroupRecord {
+                name: g.name.clone(),
+                path: g.path.clone(),
+                parent_path: g.parent_path.clone(),
+                params: sorted(&g.params),
+                muted: g.muted,
+                soloed: g.soloed,
+            })
+            .collect();
+        // Parents before their children
+        groups.sort_by(|a, b| (a.path.matches('/').count(), &a.path).cmp(&(b.path.matches('/').count(), &b.path)));
+
+        let mut samples: Vec<SampleRecord> = state
+            .samples
+            .values()
+            .map(|s| SampleRecord {
+                id: s.id.clone(),
+                path: s.path.clone(),
+            })
+            .collect();
+        samples.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut sfz_instruments: Vec<SfzRecord> = state
+            .sfz_instruments
+            .iter()
+            .map(|(id, sfz)| SfzRecord {
+                id: id.clone(),
+                path: sfz.source_file.to_string_lossy().to_string(),
+            })
+            .collect();
+        sfz_instruments.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut vst_instruments: Vec<VstRecord> = state
+            .vst_instruments
+            .values()
+            .map(|v| VstRecord {
+                id: v.id.clone(),
+                plugin: v.plugin_key.clone(),
+                group_path: v.group_path.clone(),
+                params: sorted(&v.params),
+            })
+            .collect();
+        vst_instruments.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut voices: Vec<VoiceRecord> = state
+            .voices
+            .values()
+            .map(|v| VoiceRecord {
+                name: v.name.clone(),
+                group_path: v.group_path.clone(),
+                group_name: v.group_name.clone(),
+                synth: v.synth_name.clone(),
+                polyphony: v.polyphony,
+                gain: v.gain,
+                muted: v.muted,
+                soloed: v.soloed,
+                output_bus: v.output_bus,
+                params: sorted(&v.params),
+                sfz_instrument: v.sfz_instrument.clone(),
+                vst_instrument: v.vst_instrument.clone(),
+                midi_channel: v.midi_channel,
+                cc_mappings: v.cc_mappings.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+                priority: v.priority,
+                key_match: v.key_match.clone(),
+                pre_roll_ms: v.pre_roll_ms,
+                feel: v.feel.map(|feel| FeelRecord {
+                    offset_ms: feel.offset_ms,
+                    jitter_ms: feel.jitter_ms,
+                    distribution: feel.distribution.as_str().to_string(),
+                }),
+                mono: v.mono.map(|mono| MonoRecord {
+                    priority: mono.priority.as_str().to_string(),
+                    glide_ms: mono.glide_ms,
+                }),
+                drum_kit: v.drum_kit.as_ref().map(|kit| kit.pads.iter().map(pad_record).collect()),
+            })
+            .collect();
+        voices.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut effects: Vec<(&String, &Option<String>, usize, EffectRecord)> = state
+            .effects
+            .values()
+            .map(|e| {
+                let record = EffectRecord {
+                    id: e.id.clone(),
+                    synthdef: e.synthdef_name.clone(),
+                    group_path: e.group_path.clone(),
+                    params: sorted(&e.params),
+                    vst_plugin: e.vst_plugin.clone(),
+                    voice: e.voice.clone(),
+                };
+                (&e.group_path, &e.voice, e.position, record)
+            })
+            .collect();
+        // Chain order within each group and each voice's inserts
+        effects.sort_by(|a, b| (a.0, a.1, a.2, &a.3.id).cmp(&(b.0, b.1, b.2, &b.3.id)));
+
+        let mut fades: Vec<FadeRecord> = state.fade_defs.values().map(fade_record).collect();
+        fades.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut patterns: Vec<LoopRecord> = state
+            .patterns
+            .values()
+            .filter_map(|p| {
+                let mut record = loop_record(&p.name, &p.group_path, &p.voice_name, p.loop_pattern.as_ref()?, &p.params);
+                record.steps = p.step_pattern.clone();
+                record.humanize = p.humanize.map(|h| (h.timing_ms, h.velocity_pct));
+                if let Some(variations) = &p.variations {
+                    record.variation_weights = Some(variations.weights.clone());
+                    record.variation_seed = variations.seed;
+                }
+                Some(record)
+            })
+            .collect();
+        patterns.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut melodies: Vec<LoopRecord> = state
+            .melodies
+            .values()
+            .filter_map(|m| {
+                let mut record = loop_record(&m.name, &m.group_path, &m.voice_name, m.loop_pattern.as_ref()?, &m.params);
+                record.notes = m.notes_patterns.clone();
+                record.scale_snap = m.scale_snap.map(|snap| match snap {
+                    ScaleSnap::SessionKey => "key".to_string(),
+                    ScaleSnap::Scale(scale) => scale.to_string(),
+                });
+                record.arp = m.arp.map(|arp| arp.to_string());
+                record.humanize = m.humanize.map(|h| (h.timing_ms, h.velocity_pct));
+                Some(record)
+            })
+            .collect();
+        melodies.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut sequences: Vec<SequenceRecord> = state.sequences.values().map(sequence_record).collect();
+        sequences.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let automation: Vec<AutomationRecord> = state
+            .automation
+            .lanes
+            .values()
+            .map(|lane| AutomationRecord {
+                target_type: target_type_name(&lane.target_type).to_string(),
+                target: lane.target.clone(),
+                param: lane.param.clone(),
+                beats: lane.length_beats,
+                points: lane.points.iter().map(|p| (p.beat, p.value)).collect(),
+            })
+            .collect();
+
+        Self {
+            format: SESSION_FILE_FORMAT.to_string(),
+            version: SESSION_FILE_VERSION,
+            tempo: state.tempo,
+            time_signature: (state.time_signature.numerator, state.time_signature.denominator),
+            quantization_beats: state.quantization_beats,
+            launch_quantization: state
+                .launch_quantization
+                .defaults
+                .iter()
+                .map(|(kind, beats)| (kind.as_str().to_string(), *beats))
+                .collect(),
+            key: state.session_key.map(|key| key.to_string()),
+            beat: snapshot.beat,
+            synthdefs,
+            groups,
+            samples,
+            sfz_instruments,
+            vst_instruments,
+            voices,
+            effects: effects.into_iter().map(|(_, _, _, record)| record).collect(),
+            fades,
+            patterns,
+            melodies,
+            sequences,
+            automation,
+            playing: PlayingRecord {
+                sequences: snapshot.sequences,
+                patterns: snapshot.patterns,
+                melodies: snapshot.melodies,
+            },
+        }
+    }
+
+    /// Messages rebuilding the session in a runtime.
+    ///
+    /// They run as a reload: entities of the runtime that the file doesn't
+    /// hold are removed once the definitions are in, then the transport
+    /// position, the mixer and what was playing are restored.
+    pub fn messages(&self) -> Result<Vec<StateMessage>> {
+        let key = match &self.key {
+            Some(key) => Some(MusicalKey::parse(key).ok_or_else(|| anyhow!("invalid key '{}'", key))?),
+            None => None,
+        };
+        let mut messages = vec![
+            StateMessage::BeginReload,
+            StateMessage::SetBpm { bpm: self.tempo },
+            StateMessage::SetTimeSignature {
+                numerator: self.time_signature.0,
+                denominator: self.time_signature.1,
+            },
+            StateMessage::SetQuantization {
+                grid: TimeSpan::Beats(Beats(self.quantization_beats)),
+            },
+            StateMessage::SetSessionKey { key },
+        ];
+        for name in self.launch_quantization.keys() {
+            if LaunchKind::parse(name).is_none() {
+                bail!("unknown launch quantization type '{}'", name);
+            }
+        }
+        for kind in LaunchKind::ALL {
+            messages.push(StateMessage::SetLaunchQuantization {
+                kind,
+                grid: self.launch_quantization.get(kind.as_str()).map(|beats| TimeSpan::Beats(Beats(*beats))),
+            });
+        }
+
+        for synthdef in &self.synthdefs {
+            messages.push(StateMessage::LoadSynthDef {
+                name: synthdef.name.clone(),
+                bytes: from_hex(&synthdef.bytes).with_context(|| format!("synthdef '{}'", synthdef.name))?,
+            });
+        }
+        for group in &self.groups {
+            messages.push(StateMessage::RegisterGroup {
+                name: group.name.clone(),
+                path: group.path.clone(),
+                parent_path: group.parent_path.clone(),
+                node_id: 0,
+                source_location: SourceLocation::default(),
+            });
+        }
+        for sample in &self.samples {
+            messages.push(StateMessage::LoadSample {
+                id: sample.id.clone(),
+                path: sample.path.clone(),
+                resolved_path: Some(sample.path.clone()),
+                analyze_bpm: false,
+                warp_to_bpm: None,
+            });
+        }
+        for sfz in &self.sfz_instruments {
+            messages.push(StateMessage::LoadSfzInstrument {
+                id: sfz.id.clone(),
+                sfz_path: sfz.path.clone().into(),
+            });
+        }
+        for vst in &self.vst_instruments {
+            messages.push(StateMessage::LoadVstInstrument {
+                id: vst.id.clone(),
+                plugin_key: vst.plugin.clone(),
+                group_path: vst.group_path.clone(),
+            });
+            messages.extend(vst.params.iter().map(|(param, value)| StateMessage::SetVstParamByName {
+                instrument_id: vst.id.clone(),
+                param_name: param.clone(),
+                value: *value,
+            }));
+        }
+        for voice in &self.voices {
+            messages.push(voice_message(voice)?);
+        }
+        for effect in &self.effects {
+            messages.push(StateMessage::AddEffect {
+                id: effect.id.clone(),
+                synthdef: effect.synthdef.clone(),
+                group_path: effect.group_path.clone(),
+                params: unsorted(&effect.params),
+                bus_in: 0,
+                bus_out: 0,
+                vst_plugin: effect.vst_plugin.clone(),
+                voice: effect.voice.clone(),
+                source_location: SourceLocation::default(),
+            });
+        }
+        for fade in &self.fades {
+            messages.push(StateMessage::CreateFadeDefinition {
+                fade: fade_definition(fade)?,
+            });
+        }
+        for pattern in &self.patterns {
+            messages.push(StateMessage::CreatePattern {
+                name: pattern.name.clone(),
+                group_path: pattern.group_path.clone(),
+                voice_name: pattern.voice_name.clone(),
+                pattern: loop_pattern(pattern),
+                source_location: SourceLocation::default(),
+                step_pattern: pattern.steps.clone(),
+                text: None,
+            });
+            messages.extend(pattern.params.iter().map(|(param, value)| StateMessage::SetPatternParam {
+                name: pattern.name.clone(),
+                param: param.clone(),
+                value: *value,
+            }));
+            messages.push(StateMessage::SetPatternVariations {
+                name: pattern.name.clone(),
+                variations: pattern.variation_weights.as_ref().map(|weights| PatternVariations {
+                    weights: weights.clone(),
+                    seed: pattern.variation_seed,
+                }),
+            });
+            messages.push(StateMessage::SetLoopHumanize {
+                name: pattern.name.clone(),
+                kind: LoopKind::Pattern,
+                humanize: pattern.humanize.map(|(timing_ms, velocity_pct)| Humanize::new(timing_ms, velocity_pct)),
+            });
+        }
+        for melody in &self.melodies {
+            messages.push(StateMessage::CreateMelody {
+                name: melody.name.clone(),
+                group_path: melody.group_path.clone(),
+                voice_name: melody.voice_name.clone(),
+                pattern: loop_pattern(melody),
+                source_location: SourceLocation::default(),
+                notes_patterns: melody.notes.clone(),
+            });
+            let snap = match melody.scale_snap.as_deref() {
+                None => None,
+                Some("key") => Some(ScaleSnap::SessionKey),
+                Some(scale) => Some(ScaleSnap::Scale(
+                    Scale::parse_text(scale)
+                        .ok_or_else(|| anyhow!("melody '{}': invalid scale '{}'", melody.name, scale))?,
+                )),
+            };
+            messages.push(StateMessage::SetMelodyScaleSnap {
+                name: melody.name.clone(),
+                snap,
+            });
+            let arp = match melody.arp.as_deref() {
+                None => None,
+                Some(arp) => Some(
+                    Arpeggiator::parse_text(arp)
+                        .ok_or_else(|| anyhow!("melody '{}': invalid arpeggiator '{}'", melody.name, arp))?,
+                ),
+            };
+            messages.push(StateMessage::SetMelodyArp {
+                name: melody.name.clone(),
+                arp,
+            });
+            messages.push(StateMessage::SetLoopHumanize {
+                name: melody.name.clone(),
+                kind: LoopKind::Melody,
+                humanize: melody.humanize.map(|(timing_ms, velocity_pct)| Humanize::new(timing_ms, velocity_pct)),
+            });
+            messages.extend(melody.params.iter().map(|(param, value)| StateMessage::SetMelodyParam {
+                name: melody.name.clone(),
+                param: param.clone(),
+                value: *value,
+            }));
+        }
+        for sequence in &self.sequences {
+            messages.push(StateMessage::CreateSequence {
+                sequence: sequence_definition(sequence)?,
+            });
+        }
+
+        messages.push(StateMessage::ClearAutomation { target: None });
+        for record in &self.automation {
+            let target_type = parse_target_type(&record.target_type).ok_or_else(|| {
+                anyhow!("automation of '{}': unknown target type '{}'", record.target, record.target_type)
+            })?;
+            let mut lane = AutomationLane::new(target_type, record.target.clone(), record.param.clone(), record.beats);
+            lane.points = record.points.iter().map(|&(beat, value)| AutomationPoint { beat, value }).collect();
+            messages.push(StateMessage::SetAutomationLane { lane });
+        }
+
+        messages.push(StateMessage::FinalizeGroups);
+        messages.push(StateMessage::RestoreSession {
+            snapshot: self.snapshot(key),
+        });
+        Ok(messages)
+    }
+
+    /// Live state restored after the definitions.
+    fn snapshot(&self, key: Option<MusicalKey>) -> SessionSnapshot {
+        SessionSnapshot {
+            tempo: self.tempo,
+            beat: self.beat,
+            time_signature: TimeSignature::new(self.time_signature.0, self.time_signature.1),
+            key,
+            checkpoint: None,
+            sequences: self.playing.sequences.clone(),
+            patterns: self.playing.patterns.clone(),
+            melodies: self.playing.melodies.clone(),
+            groups: self
+                .groups
+                .iter()
+                .map(|g| GroupSnapshot {
+                    path: g.path.clone(),
+                    muted: g.muted,
+                    soloed: g.soloed,
+                    params: g.params.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+                })
+                .collect(),
+            voices: self
+                .voices
+                .iter()
+                .map(|v| VoiceSnapshot {
+                    name: v.name.clone(),
+                    gain: v.gain,
+                    muted: v.muted,
+                    soloed: v.soloed,
+                    params: v.params.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Render the session as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("session files always serialize")
+    }
+
+    /// Parse a session from JSON.
+    pub fn from_json(source: &str) -> Result<Self> {
+        let file: Self = serde_json::from_str(source).context("Invalid session file")?;
+        if file.format != SESSION_FILE_FORMAT {
+            bail!("Not a session file (format '{}')", file.format);
+        }
+        if file.version > SESSION_FILE_VERSION {
+            bail!(
+                "Session file version {} is newer than this vibe supports ({})",
+                file.version,
+                SESSION_FILE_VERSION
+            );
+        }
+        Ok(file)
+    }
+
+    /// Write the session to `path`.
+    ///
+    /// The file is written next to its final path and renamed into place, so
+    /// a crash mid-write leaves the previous file intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temp = path.with_extension(format!("{}.tmp", SESSION_FILE_EXTENSION));
+        fs::write(&temp, self.to_json()).with_context(|| format!("Failed to write session file: {}", temp.display()))?;
+        fs::rename(&temp, path).with_context(|| format!("Failed to write session file: {}", path.display()))
+    }
+
+    /// Read a session from `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source =
+            fs::read_to_string(path).with_context(|| format!("Failed to read session file: {}", path.display()))?;
+        Self::from_json(&source).with_context(|| path.display().to_string())
+    }
+}
+
+fn sorted(params: &HashMap<String, f32>) -> BTreeMap<String, f32> {
+    params.iter().map(|(k, v)| (k.clone(), *v)).collect()
+}
+
+fn unsorted(params: &BTreeMap<String, f32>) -> HashMap<String, f32> {
+    params.iter().map(|(k, v)| (k.clone(), *v)).collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        bail!("odd number of hex digits");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow!("invalid hex at offset {}", i))
+        })
+        .collect()
+}
+
+fn pad_record(pad: &DrumPad) -> DrumPadRecord {
+    DrumPadRecord {
+        name: pad.name.clone(),
+        note: pad.note,
+        step: pad.step,
+        sample_id: pad.sample_id.clone(),
+        start_frame: pad.start_frame,
+        end_frame: pad.end_frame,
+        rate: pad.rate,
+        gain: pad.gain,
+        pitch: pad.pitch,
+        choke: pad.choke,
+    }
+}
+
+fn voice_message(voice: &VoiceRecord) -> Result<StateMessage> {
+    let feel = match &voice.feel {
+        Some(feel) => Some(TimingFeel {
+            offset_ms: feel.offset_ms,
+            jitter_ms: feel.jitter_ms,
+            distribution: JitterDistribution::parse(&feel.distribution)
+                .ok_or_else(|| anyhow!("voice '{}': unknown jitter distribution '{}'", voice.name, feel.distribution))?,
+        }),
+        None => None,
+    };
+    let mono = match &voice.mono {
+        Some(mono) => Some(MonoMode {
+            priority: NotePriority::parse(&mono.priority)
+                .ok_or_else(|| anyhow!("voice '{}': unknown note priority '{}'", voice.name, mono.priority))?,
+            glide_ms: mono.glide_ms,
+        }),
+        None => None,
+    };
+    let drum_kit = voice.drum_kit.as_ref().map(|pads| DrumKit {
+        pads: pads
+            .iter()
+            .map(|pad| DrumPad {
+                name: pad.name.clone(),
+                note: pad.note,
+                step: pad.step,
+                sample_id: pad.sample_id.clone(),
+                start_frame: pad.start_frame,
+                end_frame: pad.end_frame,
+                rate: pad.rate,
+                gain: pad.gain,
+                pitch: pad.pitch,
+                choke: pad.choke,
+            })
+            .collect(),
+    });
+    Ok(StateMessage::UpsertVoice {
+        name: voice.name.clone(),
+        group_path: voice.group_path.clone(),
+        group_name: voice.group_name.clone(),
+        synth_name: voice.synth.clone(),
+        polyphony: voice.polyphony,
+        gain: voice.gain,
+        muted: voice.muted,
+        soloed: voice.soloed,
+        output_bus: voice.output_bus,
+        params: unsorted(&voice.params),
+        sfz_instrument: voice.sfz_instrument.clone(),
+        vst_instrument: voice.vst_instrument.clone(),
+        source_location: SourceLocation::default(),
+        midi_output_device_id: None,
+        midi_channel: voice.midi_channel,
+        cc_mappings: voice.cc_mappings.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        priority: voice.priority,
+        key_match: voice.key_match.clone(),
+        pre_roll_ms: voice.pre_roll_ms,
+        feel,
+        mono,
+        drum_kit,
+    })
+}
+
+fn loop_record(
+    name: &str,
+    group_path: &str,
+    voice_name: &Option<String>,
+    pattern: &Pattern,
+    params: &HashMap<String, f32>,
+) -> LoopRecord {
+    LoopRecord {
+        name: name.to_string(),
+        group_path: group_path.to_string(),
+        voice_name: voice_name.clone(),
+        length: pattern.loop_length_beats,
+        phase_offset: pattern.phase_offset,
+        events: pattern
+            .events
+            .iter()
+            .map(|e| EventRecord {
+                beat: e.beat,
+                synth_def: e.synth_def.clone(),
+                controls: e.controls.clone(),
+                group_path: e.group_path.clone(),
+                voice_name: e.voice_name.clone(),
+                step: e.step,
+                variation: e.variation.map(|v| (v.index, v.offset)),
+            })
+            .collect(),
+        params: sorted(params),
+        steps: None,
+        notes: Vec::new(),
+        variation_weights: None,
+        variation_seed: 0,
+        scale_snap: None,
+        arp: None,
+        humanize: None,
+    }
+}
+
+/// Pattern of a pattern or melody record; its events name it as their source.
+fn loop_pattern(record: &LoopRecord) -> Pattern {
+    let mut pattern = Pattern::new(record.name.clone(), record.length).with_phase_offset(record.phase_offset);
+    let melody = !record.notes.is_empty();
+    for event in &record.events {
+        let mut beat_event = BeatEvent::new(event.beat, event.synth_def.clone());
+        beat_event.controls = event.controls.clone();
+        beat_event.group_path = event.group_path.clone();
+        beat_event.voice_name = event.voice_name.clone();
+        beat_event.step = event.step;
+        beat_event.variation = event.variation.map(|(index, offset)| EventVariation { index, offset });
+        if melody {
+            beat_event.melody_name = Some(record.name.clone());
+        } else {
+            beat_event.pattern_name = Some(record.name.clone());
+        }
+        pattern.events.push(beat_event);
+    }
+    pattern
+}
+
+fn sequence_record(sequence: &SequenceDefinition) -> SequenceRecord {
+    SequenceRecord {
+        name: sequence.name.clone(),
+        length: sequence.loop_beats,
+        clips: sequence
+            .clips
+            .iter()
+            .map(|clip| ClipRecord {
+                start: clip.start,
+                end: clip.end,
+                kind: clip.source.type_name().to_string(),
+                source: clip.source.name().to_string(),
+                mode: match clip.mode {
+                    ClipMode::Loop => "loop".to_string(),
+                    ClipMode::Once => "once".to_string(),
+                    ClipMode::LoopCount(count) => count.to_string(),
+                },
+                speed: clip.speed,
+            })
+            .collect(),
+        play_once: sequence.play_once,
+        speed: sequence.speed,
+        launch_quantization: sequence.launch_quantization,
+        legato: sequence.legato,
+        transpose: sequence.transpose,
+        key_changes: sequence
+            .key_changes
+            .iter()
+            .map(|change| KeyChangeRecord {
+                start: change.start,
+                end: change.end,
+                semitones: change.semitones,
+            })
+            .collect(),
+    }
+}
+
+fn sequence_definition(record: &SequenceRecord) -> Result<SequenceDefinition> {
+    let clips = record
+        .clips
+        .iter()
+        .map(|clip| {
+            let source = match clip.kind.as_str() {
+                "pattern" => ClipSource::Pattern(clip.source.clone()),
+                "melody" => ClipSource::Melody(clip.source.clone()),
+                "fade" => ClipSource::Fade(clip.source.clone()),
+                "sequence" => ClipSource::Sequence(clip.source.clone()),
+                other => bail!("sequence '{}': unknown clip kind '{}'", record.name, other),
+            };
+            let mode = match clip.mode.as_str() {
+                "loop" => ClipMode::Loop,
+                "once" => ClipMode::Once,
+                count => ClipMode::LoopCount(
+                    count
+                        .parse()
+                        .map_err(|_| anyhow!("sequence '{}': unknown clip mode '{}'", record.name, count))?,
+                ),
+            };
+            Ok(SequenceClip::new(clip.start, clip.end, source, mode).with_speed(clip.speed))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(SequenceDefinition {
+        name: record.name.clone(),
+        loop_beats: record.length,
+        clips,
+        generation: 0,
+        play_once: record.play_once,
+        source_location: SourceLocation::default(),
+        speed: record.speed,
+        launch_quantization: record.launch_quantization,
+        legato: record.legato,
+        transpose: record.transpose,
+        key_changes: record
+            .key_changes
+            .iter()
+            .map(|change| KeyChange {
+                start: change.start,
+                end: change.end,
+                semitones: change.semitones,
+            })
+            .collect(),
+    })
+}
+
+fn fade_record(fade: &FadeDefinition) -> FadeRecord {
+    FadeRecord {
+        name: fade.name.clone(),
+        target_type: match fade.target_type {
+            FadeTargetType::Group => "group",
+            FadeTargetType::Voice => "voice",
+            FadeTargetType::Pattern => "pattern",
+            FadeTargetType::Melody => "melody",
+            FadeTargetType::Effect => "effect",
+        }
+        .to_string(),
+        target: fade.target_name.clone(),
+        param: fade.param_name.clone(),
+        from: fade.from,
+        to: fade.to,
+        beats: fade.duration_beats,
+        curve: match fade.curve {
+            FadeCurve::Linear => "linear",
+            FadeCurve::Decibels => "db",
+        }
+        .to_string(),
+        launch_quantization: fade.launch_quantization,
+    }
+}
+
+fn fade_definition(record: &FadeRecord) -> Result<FadeDefinition> {
+    let target_type = match record.target_type.as_str() {
+        "group" => FadeTargetType::Group,
+        "voice" => FadeTargetType::Voice,
+        "pattern" => FadeTargetType::Pattern,
+        "melody" => FadeTargetType::Melody,
+        "effect" => FadeTargetType::Effect,
+        other => bail!("fade '{}': unknown target type '{}'", record.name, other),
+    };
+    let curve = match record.curve.as_str() {
+        "linear" => FadeCurve::Linear,
+        "db" => FadeCurve::Decibels,
+        other => bail!("fade '{}': unknown curve '{}'", record.name, other),
+    };
+    let mut fade = FadeDefinition::new(record.name.clone(), target_type, record.target.clone(), record.param.clone());
+    fade.from = record.from;
+    fade.to = record.to;
+    fade.duration_beats = record.beats;
+    fade.curve = curve;
+    fade.launch_quantization = record.launch_quantization;
+    Ok(fade)
+}
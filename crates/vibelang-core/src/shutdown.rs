@@ -0,0 +1,150 @@
+//! Graceful shutdown.
+//!
+//! When the runtime stops (Ctrl+C, SIGTERM, quitting the TUI or `exit()`), it
+//! doesn't just drop the connection to scsynth: a master fader synth is
+//! inserted after the main group and faded to silence, active synths are
+//! gated off, all nodes and buffers are freed and scsynth is asked to quit.
+//! This avoids clicks and hanging notes when a performance ends.
+
+use crate::state::ScriptState;
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the master fader synthdef.
+pub const MASTER_FADER_SYNTHDEF: &str = "system_master_fader";
+
+/// Fixed node ID of the master fader (added after the main group on shutdown).
+pub const MASTER_FADER_NODE_ID: i32 = 3;
+
+/// Length of the master fade-out in seconds.
+pub const SHUTDOWN_FADE_SECONDS: f32 = 0.5;
+
+/// Create the master fader synthdef.
+pub fn create_master_fader_synthdef() -> Option<(String, Vec<u8>)> {
+    match encode_synthdef(&master_fader_graph()) {
+        Ok(bytes) => Some((MASTER_FADER_SYNTHDEF.to_string(), bytes)),
+        Err(e) => {
+            log::error!("[SHUTDOWN] Failed to encode master fader synthdef: {}", e);
+            None
+        }
+    }
+}
+
+/// Signal flow (bus 0/1, replaced in place):
+///   In.ar(0, 2) × Lag.kr(amp, lag) → ReplaceOut.ar(0)
+///
+/// `Lag` starts at the initial `amp`, so the synth is created at 1 and the
+/// fade is started by setting `amp` to 0.
+fn master_fader_graph() -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    builder.add_param("amp".to_string(), vec![1.0], None); // 0
+    builder.add_param("lag".to_string(), vec![SHUTDOWN_FADE_SECONDS], None); // 1
+    builder.create_control_ugen();
+
+    builder.add_constant(0.0);
+
+    let input = builder.add_node(
+        "In".to_string(),
+        Rate::Audio,
+        vec![Input::Constant(0.0)],
+        2,
+        0,
+    );
+    let gain = builder.add_node(
+        "Lag".to_string(),
+        Rate::Control,
+        vec![Input::node(0, 0), Input::node(0, 1)],
+        1,
+        0,
+    );
+    let faded: Vec<Input> = (0..2)
+        .map(|channel| {
+            let scaled = builder.add_node(
+                "BinaryOpUGen".to_string(),
+                Rate::Audio,
+                vec![Input::node(input.0, channel), Input::node(gain.0, 0)],
+                1,
+                2, // multiplication
+            );
+            Input::node(scaled.0, 0)
+        })
+        .collect();
+
+    let mut out_inputs = vec![Input::Constant(0.0)];
+    out_inputs.extend(faded);
+    builder.add_node("ReplaceOut".to_string(), Rate::Audio, out_inputs, 0, 0);
+
+    GraphIR::from_builder(MASTER_FADER_SYNTHDEF.to_string(), builder)
+}
+
+/// All buffers the session has allocated: samples, SFZ regions, group
+/// freezes and loopers (sorted, without duplicates).
+pub fn allocated_buffers(state: &ScriptState) -> Vec<i32> {
+    let mut buffers: Vec<i32> = state.samples.values().map(|s| s.buffer_id).collect();
+    buffers.extend(
+        state
+            .sfz_instruments
+            .values()
+            .flat_map(|inst| inst.regions.iter().map(|r| r.buffer_id)),
+    );
+    buffers.extend(
+        state
+            .groups
+            .values()
+            .filter_map(|g| g.freeze.as_ref().map(|f| f.buffer_id)),
+    );
+    buffers.extend(state.loopers.values().filter_map(|l| l.buffer_id));
+    buffers.sort_unstable();
+    buffers.dedup();
+    buffers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SampleInfo;
+
+    #[test]
+    fn test_master_fader_graph_scales_the_master_bus_in_place() {
+        // Parameter slots: amp 0, lag 1
+        let ir = master_fader_graph();
+        assert!(ir.validate().is_ok());
+        let (input, bus) = ir.ugens("In").next().unwrap();
+        assert_eq!((bus.inputs.clone(), bus.num_outputs), (vec![Input::Constant(0.0)], 2));
+        let (gain, lag) = ir.ugens("Lag").next().unwrap();
+        assert_eq!(lag.inputs, vec![Input::node(0, 0), Input::node(0, 1)]);
+
+        let scaled: Vec<_> = ir.ugens("BinaryOpUGen").collect();
+        for (channel, (_, node)) in scaled.iter().enumerate() {
+            assert_eq!(node.inputs, vec![Input::node(input, channel as u32), Input::node(gain, 0)]);
+        }
+        let (_, out) = ir.ugens("ReplaceOut").next().unwrap();
+        assert_eq!(
+            out.inputs,
+            vec![Input::Constant(0.0), Input::node(scaled[0].0, 0), Input::node(scaled[1].0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_allocated_buffers() {
+        let mut state = ScriptState::default();
+        for (id, buffer_id) in [("kick", 12), ("snare", 10), ("kick_again", 12)] {
+            state.samples.insert(
+                id.to_string(),
+                SampleInfo {
+                    id: id.to_string(),
+                    path: format!("{}.wav", id),
+                    buffer_id,
+                    num_channels: 2,
+                    num_frames: 0,
+                    sample_rate: 44100.0,
+                    synthdef_name: String::new(),
+                    slices: Vec::new(),
+                    detected_key: None,
+                    onset_ms: 0.0,
+                },
+            );
+        }
+        assert_eq!(allocated_buffers(&state), vec![10, 12]);
+    }
+}
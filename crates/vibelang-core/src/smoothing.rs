@@ -0,0 +1,109 @@
+//! Click-free smoothing of parameter changes.
+//!
+//! Jumping an amplitude on a running node (a mixer fader, a mute toggle)
+//! produces an audible click. Parameters with a smoothing time are moved to
+//! their new value over a short ramp instead: through the synthdef's
+//! `<param>_lag` control when it has one (see [`vibelang_dsp::RAMP_PARAMS`]),
+//! otherwise by the runtime sending a few time-stamped intermediate values.
+
+use std::collections::HashMap;
+
+/// Smoothing time of `amp` unless a script changes it.
+pub const DEFAULT_AMP_SMOOTHING_SECONDS: f64 = 0.01;
+
+/// Spacing of runtime slew steps, about one control block at 44.1 kHz.
+pub const SLEW_STEP_SECONDS: f64 = 0.0015;
+
+/// Upper bound on the number of runtime slew steps of one change.
+pub const MAX_SLEW_STEPS: usize = 64;
+
+/// Longest accepted smoothing time.
+pub const MAX_SMOOTHING_SECONDS: f64 = 1.0;
+
+/// Per-parameter smoothing times in seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamSmoothing {
+    seconds: HashMap<String, f64>,
+}
+
+impl Default for ParamSmoothing {
+    fn default() -> Self {
+        let mut seconds = HashMap::new();
+        seconds.insert("amp".to_string(), DEFAULT_AMP_SMOOTHING_SECONDS);
+        Self { seconds }
+    }
+}
+
+impl ParamSmoothing {
+    /// Smoothing time of `param` (0 when changes apply immediately).
+    pub fn seconds(&self, param: &str) -> f64 {
+        self.seconds.get(param).copied().unwrap_or(0.0)
+    }
+
+    /// Set the smoothing time of `param`; 0 turns smoothing off.
+    pub fn set(&mut self, param: &str, seconds: f64) {
+        let seconds = seconds.clamp(0.0, MAX_SMOOTHING_SECONDS);
+        if seconds > 0.0 {
+            self.seconds.insert(param.to_string(), seconds);
+        } else {
+            self.seconds.remove(param);
+        }
+    }
+
+    /// Ramp time of mute and unmute, which fade the group's output level.
+    pub fn mute_seconds(&self) -> f64 {
+        self.seconds("amp")
+    }
+
+    /// All smoothed parameters with their times, sorted by name.
+    pub fn entries(&self) -> Vec<(String, f64)> {
+        let mut entries: Vec<(String, f64)> = self.seconds.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+}
+
+/// Runtime slew from `from` to `to` over `seconds`.
+///
+/// Returns `(offset_seconds, value)` pairs; the last one is `(seconds, to)`.
+/// An empty ramp means the value should be set directly.
+pub fn slew_steps(from: f32, to: f32, seconds: f64) -> Vec<(f64, f32)> {
+    if seconds <= 0.0 || from == to {
+        return Vec::new();
+    }
+    let steps = ((seconds / SLEW_STEP_SECONDS).ceil() as usize).clamp(1, MAX_SLEW_STEPS);
+    (1..=steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            let value = if i == steps { to } else { from + (to - from) * t as f32 };
+            (seconds * t, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_smoothing_and_slew_steps() {
+        let mut smoothing = ParamSmoothing::default();
+        assert_eq!(smoothing.seconds("amp"), DEFAULT_AMP_SMOOTHING_SECONDS);
+        assert_eq!(smoothing.seconds("cutoff"), 0.0);
+        smoothing.set("cutoff", 0.02);
+        smoothing.set("amp", 0.0);
+        assert_eq!(smoothing.entries(), vec![("cutoff".to_string(), 0.02)]);
+        assert_eq!(smoothing.mute_seconds(), 0.0);
+
+        let seconds = SLEW_STEP_SECONDS * 4.0;
+        let steps = slew_steps(1.0, 0.0, seconds);
+        assert_eq!(steps.len(), 4);
+        assert!((steps[0].1 - 0.75).abs() < 1e-6);
+        assert_eq!(steps.last(), Some(&(seconds, 0.0)));
+        assert!(steps.windows(2).all(|w| w[0].0 < w[1].0));
+
+        assert!(slew_steps(0.5, 0.5, 0.01).is_empty());
+        assert!(slew_steps(0.0, 1.0, 0.0).is_empty());
+        assert_eq!(slew_steps(0.0, 1.0, 1.0).len(), MAX_SLEW_STEPS);
+    }
+}
@@ -0,0 +1,123 @@
+//! Parameter randomization and morphing for exploring timbres.
+//!
+//! Both work on the parameter ranges synthdefs declare (`.param_range()`),
+//! in the 0..1 control space along each range's curve: randomizing by 0.2
+//! moves a parameter by up to a fifth of its range, and morphing an
+//! exponential cutoff sweeps it evenly by ear rather than by Hz.
+//!
+//! Randomizing only touches parameters with a declared range, since there is
+//! no telling what a safe value of any other parameter is. Morphing
+//! interpolates parameters without a range linearly.
+
+use std::collections::HashMap;
+
+use rand::Rng;
+use vibelang_dsp::ParamRange;
+
+/// How far `randomize_params()` moves parameters by default, as a fraction
+/// of their range.
+pub const DEFAULT_RANDOMIZE_AMOUNT: f64 = 0.2;
+
+/// New values for the ranged parameters of a synthdef, each moved from its
+/// current value by up to `amount` of its range.
+///
+/// `current` are the values set on the voice; parameters it lacks start
+/// from the synthdef's defaults. `only` limits the parameters touched
+/// (empty = all ranged parameters).
+pub fn randomize(synthdef: &str, current: &HashMap<String, f32>, amount: f64, only: &[String]) -> HashMap<String, f32> {
+    let ranges = vibelang_dsp::get_param_ranges(synthdef);
+    let mut values = vibelang_dsp::get_synthdef_param_defaults(synthdef);
+    values.extend(current.iter().map(|(param, value)| (param.clone(), *value)));
+    randomize_with(&ranges, &values, amount, only, &mut rand::rng())
+}
+
+/// [`randomize`] with explicit ranges, start values and random source.
+pub fn randomize_with(
+    ranges: &HashMap<String, ParamRange>,
+    values: &HashMap<String, f32>,
+    amount: f64,
+    only: &[String],
+    rng: &mut impl Rng,
+) -> HashMap<String, f32> {
+    let amount = amount.clamp(0.0, 1.0) as f32;
+    ranges
+        .iter()
+        .filter(|(param, _)| only.is_empty() || only.contains(param))
+        .map(|(param, range)| {
+            let start = values.get(param).map_or(0.5, |value| range.to_normalized(*value));
+            let position = start + amount * rng.random_range(-1.0f32..=1.0);
+            (param.clone(), range.from_normalized(position))
+        })
+        .collect()
+}
+
+/// Values between `from` (t = 0) and `to` (t = 1) for the parameters both set.
+///
+/// Parameters with a range declared by `synthdef` move along its curve; the
+/// others, and all of them without a synthdef, move linearly.
+pub fn morph(synthdef: Option<&str>, from: &HashMap<String, f32>, to: &HashMap<String, f32>, t: f64) -> HashMap<String, f32> {
+    let ranges = synthdef.map(vibelang_dsp::get_param_ranges).unwrap_or_default();
+    morph_with(&ranges, from, to, t)
+}
+
+/// [`morph`] with explicit ranges.
+pub fn morph_with(
+    ranges: &HashMap<String, ParamRange>,
+    from: &HashMap<String, f32>,
+    to: &HashMap<String, f32>,
+    t: f64,
+) -> HashMap<String, f32> {
+    let t = t.clamp(0.0, 1.0) as f32;
+    from.iter()
+        .filter_map(|(param, a)| {
+            let b = to.get(param)?;
+            let value = match ranges.get(param) {
+                Some(range) => {
+                    let (a, b) = (range.to_normalized(*a), range.to_normalized(*b));
+                    range.from_normalized(a + (b - a) * t)
+                }
+                None => a + (b - a) * t,
+            };
+            Some((param.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use vibelang_dsp::ParamCurve;
+
+    #[test]
+    fn test_randomize_and_morph_follow_declared_ranges() {
+        let ranges = HashMap::from([
+            ("cutoff".to_string(), ParamRange::new(20.0, 20000.0, Some("hz".to_string()), ParamCurve::Exponential).unwrap()),
+            ("res".to_string(), ParamRange::new(0.0, 1.0, None, ParamCurve::Linear).unwrap()),
+        ]);
+        let values = HashMap::from([("cutoff".to_string(), 632.0), ("res".to_string(), 0.95), ("amp".to_string(), 0.5)]);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+        // Values stay within `amount` of the range and inside it; unranged params are left alone
+        for _ in 0..200 {
+            let random = randomize_with(&ranges, &values, 0.2, &[], &mut rng);
+            assert_eq!(random.len(), 2);
+            let cutoff = ranges["cutoff"].to_normalized(random["cutoff"]);
+            assert!((cutoff - 0.5).abs() <= 0.2 + 1e-3, "{}", random["cutoff"]);
+            assert!((0.75 - 1e-3..=1.0).contains(&random["res"]), "{}", random["res"]);
+        }
+        let only = randomize_with(&ranges, &values, 1.0, &["res".to_string()], &mut rng);
+        assert_eq!(only.keys().collect::<Vec<_>>(), vec!["res"]);
+        assert!((randomize_with(&ranges, &values, 0.0, &[], &mut rng)["res"] - 0.95).abs() < 1e-6);
+
+        // Exponential ranges morph evenly in ratio; the rest linearly
+        let from = HashMap::from([("cutoff".to_string(), 100.0), ("amp".to_string(), 0.2), ("pan".to_string(), -1.0)]);
+        let to = HashMap::from([("cutoff".to_string(), 10000.0), ("amp".to_string(), 0.6)]);
+        let half = morph_with(&ranges, &from, &to, 0.5);
+        assert!((half["cutoff"] - 1000.0).abs() < 0.5, "{}", half["cutoff"]);
+        assert!((half["amp"] - 0.4).abs() < 1e-6);
+        assert!(!half.contains_key("pan"));
+        assert_eq!(morph_with(&ranges, &from, &to, 2.0)["amp"], 0.6);
+        assert!((morph_with(&HashMap::new(), &from, &to, 0.5)["cutoff"] - 5050.0).abs() < 1e-3);
+    }
+}
@@ -2,11 +2,16 @@
 //!
 //! The [`StateManager`] provides synchronized access to the central
 //! [`ScriptState`]. It uses an RwLock to allow multiple readers or
-//! a single writer.
+//! a single writer. It also admits messages against the session's
+//! [`Quotas`](super::Quotas).
 
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
 
+use super::messages::StateMessage;
 use super::model::ScriptState;
+use super::quotas::{PendingEntities, QuotaExceeded, QuotaKind, Quotas};
+use crate::session_file::SessionFile;
 
 /// Thread-safe manager for the central state.
 ///
@@ -15,6 +20,7 @@ use super::model::ScriptState;
 #[derive(Clone)]
 pub struct StateManager {
     state: Arc<RwLock<ScriptState>>,
+    pending: Arc<Mutex<PendingEntities>>,
 }
 
 impl Default for StateManager {
@@ -28,6 +34,7 @@ impl StateManager {
     pub fn new() -> Self {
         Self {
             state: Arc::new(RwLock::new(ScriptState::new())),
+            pending: Arc::default(),
         }
     }
 
@@ -35,6 +42,7 @@ impl StateManager {
     pub fn with_state(state: ScriptState) -> Self {
         Self {
             state: Arc::new(RwLock::new(state)),
+            pending: Arc::default(),
         }
     }
 
@@ -68,6 +76,16 @@ impl StateManager {
         self.with_state_read(|s| s.clone())
     }
 
+    /// Capture the full session as a [`SessionFile`].
+    pub fn session_file(&self) -> SessionFile {
+        self.with_state_read(SessionFile::capture)
+    }
+
+    /// Save the full session to `path`.
+    pub fn save_session(&self, path: &Path) -> anyhow::Result<()> {
+        self.session_file().save(path)
+    }
+
     /// Get the current tempo.
     pub fn tempo(&self) -> f64 {
         self.with_state_read(|s| s.tempo)
@@ -87,6 +105,67 @@ impl StateManager {
     pub fn version(&self) -> u64 {
         self.with_state_read(|s| s.version)
     }
+
+    /// Check whether a new entity of `kind` named `name` fits the quotas.
+    ///
+    /// Lets the API fail before building an entity; [`admit`](Self::admit)
+    /// is what enforces the quotas.
+    pub fn check_quota(&self, kind: QuotaKind, name: &str) -> Result<(), QuotaExceeded> {
+        let pending = self.pending.lock().expect("Quota lock poisoned");
+        self.with_state_read(|state| {
+            let quotas = pending.quotas.as_ref().unwrap_or(&state.quotas);
+            let empty = Default::default();
+            quotas.check(state, kind, name, pending.names.get(&kind).unwrap_or(&empty))
+        })
+    }
+
+    /// Admit a message on its way to the runtime thread.
+    ///
+    /// Refuses messages creating an entity beyond the quotas. Admitted
+    /// entities and quotas count until the runtime thread [settles](Self::settle)
+    /// the message, so a script creating entities in a loop cannot outrun
+    /// the check.
+    pub fn admit(&self, msg: &StateMessage) -> Result<(), QuotaExceeded> {
+        let mut pending = self.pending.lock().expect("Quota lock poisoned");
+        if let StateMessage::SetQuotas { quotas } = msg {
+            pending.quotas = Some(quotas.clone());
+            return Ok(());
+        }
+        let Some((kind, name)) = QuotaKind::of_message(msg) else {
+            return Ok(());
+        };
+        self.with_state_read(|state| {
+            let quotas = pending.quotas.as_ref().unwrap_or(&state.quotas);
+            if quotas.is_unlimited() {
+                return Ok(());
+            }
+            let empty = Default::default();
+            quotas.check(state, kind, name, pending.names.get(&kind).unwrap_or(&empty))
+        })?;
+        pending.names.entry(kind).or_default().insert(name.to_string());
+        Ok(())
+    }
+
+    /// Forget an entity admitted by [`admit`](Self::admit) once the runtime
+    /// thread has processed its message.
+    pub fn settle(&self, kind: QuotaKind, name: &str) {
+        let mut pending = self.pending.lock().expect("Quota lock poisoned");
+        if let Some(names) = pending.names.get_mut(&kind) {
+            names.remove(name);
+        }
+    }
+
+    /// Apply quotas admitted by [`admit`](Self::admit) to the state.
+    pub fn set_quotas(&self, quotas: Quotas) {
+        let mut pending = self.pending.lock().expect("Quota lock poisoned");
+        if pending.quotas.as_ref() == Some(&quotas) {
+            pending.quotas = None;
+        }
+        self.with_state_write(|state| {
+            state.quotas = quotas;
+            state.bump_version();
+        });
+    }
 }
 
 impl std::fmt::Debug for StateManager {
@@ -5,12 +5,33 @@
 //! to the audio state.
 
 use crate::api::context::SourceLocation;
+use crate::chord::Arpeggiator;
+use crate::clock_out::ClockOutput;
+use crate::drumkit::DrumKit;
 use crate::events::{BeatEvent, Pattern};
+use crate::groove::{GrooveTemplate, Humanize, TimingFeel};
+use crate::hooks::HookAction;
+use crate::mono::MonoMode;
+use crate::loop_text::LoopText;
+use crate::looper::LooperAction;
+use crate::meter_condition::MeterCondition;
+use crate::musical_key::MusicalKey;
+use crate::quantization::LaunchKind;
+use crate::scale::ScaleSnap;
+use crate::scheduler::LoopKind;
 #[cfg(feature = "native")]
 use crate::midi::{CcRoute, KeyboardRoute, MidiBackend, MidiDeviceInfo, MidiOutputDeviceInfo, NoteRoute, QueuedMidiEvent};
 #[cfg(feature = "native")]
 use crossbeam_channel::Sender;
+#[cfg(feature = "native")]
+use super::model::TakeMode;
+use super::model::NetSyncState;
+use super::model::PatternMidiTarget;
+use super::quotas::Quotas;
 use crate::sequences::{FadeDefinition, SequenceDefinition};
+use crate::session::SessionSnapshot;
+use crate::timing::{ClockSource, TimeSpan};
+use crate::variations::PatternVariations;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -25,14 +46,62 @@ pub enum StateMessage {
     /// Set the tempo in BPM.
     SetBpm { bpm: f64 },
 
-    /// Set the quantization grid in beats.
-    SetQuantization { beats: f64 },
+    /// Set the quantization grid.
+    SetQuantization { grid: TimeSpan },
+
+    /// Set the default launch grid of an entity type (`None` falls back to
+    /// the quantization grid).
+    SetLaunchQuantization { kind: LaunchKind, grid: Option<TimeSpan> },
 
     /// Set the time signature.
     SetTimeSignature { numerator: u32, denominator: u32 },
 
-    /// Seek the transport to an absolute beat position.
-    SeekTransport { beat: f64 },
+    /// Set (or clear) the session key that key-matched sample voices follow.
+    SetSessionKey { key: Option<MusicalKey> },
+
+    /// Apply (or clear) the groove template giving voices their timing feel.
+    SetGroove { groove: Option<GrooveTemplate> },
+
+    /// Seek the transport to an absolute position.
+    SeekTransport { position: TimeSpan },
+
+    /// Set (or move) a named song position locator.
+    SetMarker { name: String, position: TimeSpan },
+
+    /// Remove a locator.
+    RemoveMarker { name: String },
+
+    /// Jump to a locator on the next multiple of `quantization`
+    /// (one bar when `None`, immediately for 0).
+    JumpToMarker { name: String, quantization: Option<TimeSpan> },
+
+    /// Follow a network sync leader: `beat` is where the leader's transport
+    /// was at `at` (see the `netsync` module).
+    SyncTransport {
+        beat: f64,
+        at: std::time::Instant,
+        bpm: f64,
+        running: bool,
+        numerator: u32,
+        denominator: u32,
+    },
+
+    /// Update the network sync status (role, peers and clock estimates).
+    SetNetSyncStatus { status: NetSyncState },
+
+    /// Start, reconfigure or (with `None`) stop the audio-rate clock output.
+    SetClockOutput { config: Option<ClockOutput> },
+
+    /// Take tempo and position from the internal clock or follow an
+    /// external MIDI clock.
+    SetClockSource { source: ClockSource },
+
+    /// Set the output latency of the audio interface, in milliseconds.
+    SetOutputLatency { ms: f64 },
+
+    /// Restore a session snapshot (`--resume`): tempo, mixer, transport
+    /// position and the sequences, patterns and melodies that were playing.
+    RestoreSession { snapshot: SessionSnapshot },
 
     /// Start the scheduler.
     StartScheduler,
@@ -40,15 +109,108 @@ pub enum StateMessage {
     /// Stop the scheduler.
     StopScheduler,
 
+    /// Silence all sounding notes: gate off and free note synths, drop
+    /// scheduled note-offs and send all-notes-off to MIDI outputs.
+    Panic,
+
+    /// Start capturing OSC traffic to a file, or stop with `None`.
+    SetOscCapture { path: Option<PathBuf> },
+
+    /// Start logging fired events to a JSON or CSV file, or stop and write
+    /// the log with `None`.
+    SetEventLog { path: Option<PathBuf> },
+
     /// Begin a reload cycle (increments generation).
     BeginReload,
 
     /// Finalize groups after script execution.
     FinalizeGroups,
 
+    // === Loudness ===
+    /// Set (or clear) the master loudness target in LUFS.
+    SetLoudnessTarget { target_lufs: Option<f64> },
+
+    /// Reset integrated loudness measurement.
+    ResetLoudness,
+
+    // === Smoothing ===
+    /// Set the click-free ramp time of a parameter (0 = immediate).
+    SetParamSmoothing { param: String, seconds: f64 },
+
+    // === Performance ===
+    /// Replace the CPU budget policy.
+    SetCpuPolicy { policy: crate::performance::CpuPolicy },
+    /// Replace the garbage collection policy.
+    SetGcPolicy { policy: crate::gc::GcPolicy },
+
+    // === Live set ===
+    /// Load a live set (scenes, cues and bindings).
+    LoadLiveSet {
+        path: PathBuf,
+        set: crate::liveset::LiveSet,
+    },
+
+    /// Apply the actions of a live set scene.
+    LaunchScene { name: String },
+
+    /// Launch the cue `delta` steps from the current one (1 = GO, -1 = back).
+    StepCue { delta: i32 },
+
+    // === Playback graphs ===
+    /// Create or replace a playback graph (keeps its position if playing).
+    CreatePlaybackGraph { graph: crate::playback_graph::PlaybackGraph },
+
+    /// Start a playback graph at a section (first section if `None`).
+    StartPlaybackGraph { name: String, section: Option<String> },
+
+    /// Stop a playback graph and its sequences.
+    StopPlaybackGraph { name: String },
+
+    /// Cue a playback graph (fires `on_cue` transitions).
+    CueGraph { name: String },
+
+    /// Set a variable tested by a graph's `when` conditions.
+    SetGraphVar { name: String, var: String, value: f64 },
+
+    // === Macros ===
+    /// Create or replace a macro control (keeps its value across reloads).
+    DefineMacro { control: crate::macros::MacroControl },
+
+    /// Set a macro's normalized value and write its mapped parameters.
+    SetMacro { name: String, value: f64 },
+
+    // === Automation ===
+    /// Record live parameter changes into automation lanes of `bars` bars.
+    StartAutomationRecording { bars: u32 },
+
+    /// Stop recording automation; the recorded lanes replay from now on.
+    StopAutomationRecording,
+
+    /// Create or replace an automation lane.
+    SetAutomationLane { lane: crate::automation::AutomationLane },
+
+    /// Remove the automation lanes of an entity (all lanes with `None`).
+    ClearAutomation { target: Option<(crate::FadeTargetType, String)> },
+
+    // === Checkpoints ===
+    /// Set the checkpoint names found in the script.
+    SetCheckpoints { names: Vec<String> },
+
+    /// Stop evaluation at a checkpoint (`None` = whole script) and
+    /// ask the script thread to re-evaluate.
+    SelectCheckpoint { name: Option<String> },
+
     // === SynthDefs ===
     /// Load a synthdef from bytes.
     LoadSynthDef { name: String, bytes: Vec<u8> },
+    /// Load a directory of compiled synthdefs with `/d_loadDir`.
+    ///
+    /// `synthdefs` are the `(name, bytes)` pairs read from the directory,
+    /// kept for validation and score capture.
+    LoadSynthDefDir {
+        path: PathBuf,
+        synthdefs: Vec<(String, Vec<u8>)>,
+    },
 
     // === Samples & Buffers ===
     /// Load a sample from a file.
@@ -64,10 +226,80 @@ pub enum StateMessage {
     /// Free a loaded sample.
     FreeSample { id: String },
 
+    /// Play a loaded sample once through the audition group.
+    ///
+    /// Bypasses voices and patterns; used for browsing samples live.
+    PreviewSample { id: String, amp: f32 },
+
+    // === Loopers ===
+    /// Create or update an audio input looper.
+    UpsertLooper {
+        name: String,
+        group_path: String,
+        /// Hardware input channel (1-based).
+        input: u32,
+        bars: u32,
+        gain: f32,
+    },
+
+    /// Record, overdub or clear a looper.
+    LooperControl { name: String, action: LooperAction },
+
+    // === Resampling ===
+    /// Record `bars` bars of a group's output and chop it into the pads of
+    /// the drum kit voice `name`.
+    Resample { name: String, group_path: String, bars: u32 },
+
+    // === Hooks ===
+    /// Create or replace a hook firing `action` at `beat`, then every
+    /// `every` beats if given.
+    SetHook {
+        name: String,
+        action: HookAction,
+        beat: f64,
+        every: Option<f64>,
+        timeout: std::time::Duration,
+        source_location: SourceLocation,
+    },
+
+    /// Remove a hook.
+    RemoveHook { name: String },
+
+    // === Plugins ===
+    /// A message for the plugin named `plugin` (see [`crate::plugin`]).
+    Plugin {
+        plugin: String,
+        name: String,
+        payload: rhai::Dynamic,
+    },
+
+    // === Return Channels ===
+    /// Create or update a return channel playing hardware inputs into a group.
+    UpsertReturnChannel {
+        name: String,
+        group_path: String,
+        /// First hardware input channel (1-based).
+        input: u32,
+        channels: u32,
+        gain: f32,
+    },
+
     // === SFZ Instruments ===
     /// Load an SFZ instrument.
     LoadSfzInstrument { id: String, sfz_path: PathBuf },
 
+    // === Preloading ===
+    /// Load the samples and SFZ instruments of a preload manifest up front.
+    ///
+    /// Samples are analysed in parallel; assets already loaded from the same
+    /// path are skipped.
+    PreloadAssets {
+        /// (id, resolved path) pairs.
+        samples: Vec<(String, String)>,
+        /// (id, SFZ file) pairs.
+        sfz_instruments: Vec<(String, PathBuf)>,
+    },
+
     // === VST Instruments ===
     /// Load a VST instrument.
     LoadVstInstrument {
@@ -142,6 +374,12 @@ pub enum StateMessage {
     /// Solo/unsolo a group.
     SoloGroup { path: String, solo: bool },
 
+    /// Bounce a group's output for `bars` bars and replace it with the recording.
+    FreezeGroup { path: String, bars: u32 },
+
+    /// Remove a group's bounce and resume live synthesis.
+    UnfreezeGroup { path: String },
+
     // === Voices ===
     /// Create or update a voice.
     UpsertVoice {
@@ -164,6 +402,18 @@ pub enum StateMessage {
         midi_channel: Option<u8>,
         /// CC mappings: parameter_name -> CC number.
         cc_mappings: HashMap<String, u8>,
+        /// Priority when the CPU budget is exceeded.
+        priority: i64,
+        /// Sample whose key is matched to the session key.
+        key_match: Option<String>,
+        /// How far ahead of the beat events are sent, in milliseconds.
+        pre_roll_ms: f64,
+        /// Timing feel (offset and jitter) of the voice's events.
+        feel: Option<TimingFeel>,
+        /// Mono mode (one synth gliding between overlapping notes).
+        mono: Option<MonoMode>,
+        /// Pads of a drum kit voice.
+        drum_kit: Option<DrumKit>,
     },
 
     /// Delete a voice.
@@ -176,6 +426,13 @@ pub enum StateMessage {
         value: f32,
     },
 
+    /// Set several voice parameters at once: one state update, and one
+    /// bundle to the voice's sounding synths.
+    SetVoiceParams {
+        name: String,
+        params: HashMap<String, f32>,
+    },
+
     /// Fade a voice parameter.
     FadeVoiceParam {
         name: String,
@@ -193,15 +450,27 @@ pub enum StateMessage {
     UnmuteVoice { name: String },
 
     /// Trigger a voice (create a synth).
+    ///
+    /// With `quantize` set, the trigger is aligned to the next quantization
+    /// boundary while the transport is running (used for auditioning).
     TriggerVoice {
         name: String,
         synth_name: Option<String>,
         group_path: Option<String>,
         params: Vec<(String, f32)>,
+        quantize: bool,
     },
 
-    /// Stop a voice.
-    StopVoice { name: String },
+    /// Stop everything a voice plays.
+    ///
+    /// Its synths are released (gate=0), or freed right away with `choke`.
+    /// With `fade`, their amplitude ramps down over that many seconds before
+    /// they are freed.
+    StopVoice {
+        name: String,
+        fade: Option<f64>,
+        choke: bool,
+    },
 
     /// Run a voice continuously (for line-in, drones, etc.).
     /// Creates a synth immediately without note triggers.
@@ -235,6 +504,8 @@ pub enum StateMessage {
         source_location: SourceLocation,
         /// Original step pattern string for visual editing.
         step_pattern: Option<String>,
+        /// Shareable text of the pattern, when built from steps.
+        text: Option<LoopText>,
     },
 
     /// Delete a pattern.
@@ -276,9 +547,50 @@ pub enum StateMessage {
         notes_patterns: Vec<String>,
     },
 
+    /// Replace the meter conditions of a pattern or melody.
+    SetLoopConditions {
+        name: String,
+        kind: LoopKind,
+        conditions: Vec<MeterCondition>,
+    },
+
+    /// Set the random timing and velocity variation of a pattern or melody.
+    SetLoopHumanize {
+        name: String,
+        kind: LoopKind,
+        humanize: Option<Humanize>,
+    },
+
+    /// Route a pattern to external MIDI gear (or back to its voice with `None`).
+    SetPatternMidiTarget {
+        name: String,
+        target: Option<PatternMidiTarget>,
+    },
+
+    /// Set the weights of a pattern's takes (`None` for a single take).
+    SetPatternVariations {
+        name: String,
+        variations: Option<PatternVariations>,
+    },
+
+    /// Hold one take of a pattern (`None` picks one per pass again).
+    LockPatternVariation { name: String, index: Option<usize> },
+
     /// Delete a melody.
     DeleteMelody { name: String },
 
+    /// Set what a melody's recorded takes snap their notes to.
+    SetMelodyScaleSnap {
+        name: String,
+        snap: Option<ScaleSnap>,
+    },
+
+    /// Set the arpeggiator a melody plays its held notes with.
+    SetMelodyArp {
+        name: String,
+        arp: Option<Arpeggiator>,
+    },
+
     /// Set a melody parameter.
     SetMelodyParam {
         name: String,
@@ -306,6 +618,13 @@ pub enum StateMessage {
     /// Create a fade definition.
     CreateFadeDefinition { fade: FadeDefinition },
 
+    /// Start a fade on its launch grid.
+    StartFade { name: String },
+
+    // === Quotas ===
+    /// Replace the session's quotas.
+    SetQuotas { quotas: Quotas },
+
     // === Sequences ===
     /// Create a sequence.
     CreateSequence { sequence: SequenceDefinition },
@@ -339,7 +658,7 @@ pub enum StateMessage {
     ScheduleEvent { event: BeatEvent, start_beat: f64 },
 
     // === Effects ===
-    /// Add an effect to a group.
+    /// Add an effect to a group, or insert it on a voice.
     AddEffect {
         id: String,
         synthdef: String,
@@ -347,6 +666,11 @@ pub enum StateMessage {
         params: HashMap<String, f32>,
         bus_in: i32,
         bus_out: i32,
+        /// VST plugin hosted by the effect; `synthdef` is ignored when set.
+        vst_plugin: Option<String>,
+        /// Voice to insert the effect on, between its synths and its group;
+        /// `None` adds it to the group's chain.
+        voice: Option<String>,
         source_location: SourceLocation,
     },
 
@@ -465,6 +789,26 @@ pub enum StateMessage {
     /// Clear all recorded MIDI notes.
     MidiClearRecording,
 
+    #[cfg(feature = "native")]
+    /// Arm a take against a pattern or melody loop.
+    MidiArmTake { target: String, mode: TakeMode },
+
+    #[cfg(feature = "native")]
+    /// Finish the armed take and add it to the take list.
+    MidiStopTake,
+
+    #[cfg(feature = "native")]
+    /// Audition a take against its loop (None ends the audition).
+    MidiAuditionTake { id: Option<u32> },
+
+    #[cfg(feature = "native")]
+    /// Keep a take: apply it to its loop and drop it from the take list.
+    MidiKeepTake { id: u32 },
+
+    #[cfg(feature = "native")]
+    /// Discard a take.
+    MidiDiscardTake { id: u32 },
+
     // === MIDI Output (native only) ===
     #[cfg(feature = "native")]
     /// Open a MIDI output device.
@@ -519,6 +863,13 @@ pub enum StateMessage {
         value: i16,
     },
 
+    #[cfg(feature = "native")]
+    /// Send raw MIDI messages (program changes, SysEx) to an output device by name.
+    MidiOutputSendRaw {
+        device: String,
+        messages: Vec<Vec<u8>>,
+    },
+
     #[cfg(feature = "native")]
     /// Enable/disable MIDI clock output.
     MidiOutputSetClockEnabled { enabled: bool },
@@ -527,6 +878,10 @@ pub enum StateMessage {
     /// Set the device to send MIDI clock to (None = all devices).
     MidiOutputSetClockDevice { device_id: Option<u32> },
 
+    #[cfg(feature = "native")]
+    /// Set the latency of the hardware on an output device, in milliseconds.
+    MidiOutputSetDeviceLatency { device_id: u32, ms: f64 },
+
     #[cfg(feature = "native")]
     /// Send MIDI start message.
     MidiOutputSendStart,
@@ -567,16 +922,62 @@ impl StateMessage {
         match self {
             StateMessage::SetBpm { .. } => "SetBpm",
             StateMessage::SetQuantization { .. } => "SetQuantization",
+            StateMessage::SetLaunchQuantization { .. } => "SetLaunchQuantization",
             StateMessage::SetTimeSignature { .. } => "SetTimeSignature",
+            StateMessage::SetSessionKey { .. } => "SetSessionKey",
+            StateMessage::SetGroove { .. } => "SetGroove",
             StateMessage::SeekTransport { .. } => "SeekTransport",
+            StateMessage::SetMarker { .. } => "SetMarker",
+            StateMessage::RemoveMarker { .. } => "RemoveMarker",
+            StateMessage::JumpToMarker { .. } => "JumpToMarker",
+            StateMessage::SyncTransport { .. } => "SyncTransport",
+            StateMessage::SetClockOutput { .. } => "SetClockOutput",
+            StateMessage::SetClockSource { .. } => "SetClockSource",
+            StateMessage::SetOutputLatency { .. } => "SetOutputLatency",
+            StateMessage::SetNetSyncStatus { .. } => "SetNetSyncStatus",
+            StateMessage::RestoreSession { .. } => "RestoreSession",
             StateMessage::StartScheduler => "StartScheduler",
             StateMessage::StopScheduler => "StopScheduler",
+            StateMessage::Panic => "Panic",
+            StateMessage::SetOscCapture { .. } => "SetOscCapture",
+            StateMessage::SetEventLog { .. } => "SetEventLog",
             StateMessage::BeginReload => "BeginReload",
             StateMessage::FinalizeGroups => "FinalizeGroups",
+            StateMessage::SetLoudnessTarget { .. } => "SetLoudnessTarget",
+            StateMessage::SetParamSmoothing { .. } => "SetParamSmoothing",
+            StateMessage::ResetLoudness => "ResetLoudness",
+            StateMessage::SetCpuPolicy { .. } => "SetCpuPolicy",
+            StateMessage::SetGcPolicy { .. } => "SetGcPolicy",
+            StateMessage::LoadLiveSet { .. } => "LoadLiveSet",
+            StateMessage::LaunchScene { .. } => "LaunchScene",
+            StateMessage::StepCue { .. } => "StepCue",
+            StateMessage::CreatePlaybackGraph { .. } => "CreatePlaybackGraph",
+            StateMessage::StartPlaybackGraph { .. } => "StartPlaybackGraph",
+            StateMessage::StopPlaybackGraph { .. } => "StopPlaybackGraph",
+            StateMessage::CueGraph { .. } => "CueGraph",
+            StateMessage::SetGraphVar { .. } => "SetGraphVar",
+            StateMessage::DefineMacro { .. } => "DefineMacro",
+            StateMessage::SetMacro { .. } => "SetMacro",
+            StateMessage::StartAutomationRecording { .. } => "StartAutomationRecording",
+            StateMessage::StopAutomationRecording => "StopAutomationRecording",
+            StateMessage::SetAutomationLane { .. } => "SetAutomationLane",
+            StateMessage::ClearAutomation { .. } => "ClearAutomation",
+            StateMessage::SetCheckpoints { .. } => "SetCheckpoints",
+            StateMessage::SelectCheckpoint { .. } => "SelectCheckpoint",
             StateMessage::LoadSynthDef { .. } => "LoadSynthDef",
+            StateMessage::LoadSynthDefDir { .. } => "LoadSynthDefDir",
             StateMessage::LoadSample { .. } => "LoadSample",
             StateMessage::FreeSample { .. } => "FreeSample",
+            StateMessage::PreviewSample { .. } => "PreviewSample",
+            StateMessage::UpsertLooper { .. } => "UpsertLooper",
+            StateMessage::LooperControl { .. } => "LooperControl",
+            StateMessage::Resample { .. } => "Resample",
+            StateMessage::SetHook { .. } => "SetHook",
+            StateMessage::RemoveHook { .. } => "RemoveHook",
+            StateMessage::Plugin { .. } => "Plugin",
+            StateMessage::UpsertReturnChannel { .. } => "UpsertReturnChannel",
             StateMessage::LoadSfzInstrument { .. } => "LoadSfzInstrument",
+            StateMessage::PreloadAssets { .. } => "PreloadAssets",
             StateMessage::LoadVstInstrument { .. } => "LoadVstInstrument",
             StateMessage::VstNoteOn { .. } => "VstNoteOn",
             StateMessage::VstNoteOff { .. } => "VstNoteOff",
@@ -590,9 +991,12 @@ impl StateMessage {
             StateMessage::UnmuteGroup { .. } => "UnmuteGroup",
             StateMessage::SetScrubMute { .. } => "SetScrubMute",
             StateMessage::SoloGroup { .. } => "SoloGroup",
+            StateMessage::FreezeGroup { .. } => "FreezeGroup",
+            StateMessage::UnfreezeGroup { .. } => "UnfreezeGroup",
             StateMessage::UpsertVoice { .. } => "UpsertVoice",
             StateMessage::DeleteVoice { .. } => "DeleteVoice",
             StateMessage::SetVoiceParam { .. } => "SetVoiceParam",
+            StateMessage::SetVoiceParams { .. } => "SetVoiceParams",
             StateMessage::FadeVoiceParam { .. } => "FadeVoiceParam",
             StateMessage::MuteVoice { .. } => "MuteVoice",
             StateMessage::UnmuteVoice { .. } => "UnmuteVoice",
@@ -609,12 +1013,21 @@ impl StateMessage {
             StateMessage::StartPattern { .. } => "StartPattern",
             StateMessage::StopPattern { .. } => "StopPattern",
             StateMessage::CreateMelody { .. } => "CreateMelody",
+            StateMessage::SetLoopConditions { .. } => "SetLoopConditions",
+            StateMessage::SetLoopHumanize { .. } => "SetLoopHumanize",
+            StateMessage::SetPatternMidiTarget { .. } => "SetPatternMidiTarget",
+            StateMessage::SetPatternVariations { .. } => "SetPatternVariations",
+            StateMessage::LockPatternVariation { .. } => "LockPatternVariation",
             StateMessage::DeleteMelody { .. } => "DeleteMelody",
+            StateMessage::SetMelodyScaleSnap { .. } => "SetMelodyScaleSnap",
+            StateMessage::SetMelodyArp { .. } => "SetMelodyArp",
             StateMessage::SetMelodyParam { .. } => "SetMelodyParam",
             StateMessage::FadeMelodyParam { .. } => "FadeMelodyParam",
             StateMessage::StartMelody { .. } => "StartMelody",
             StateMessage::StopMelody { .. } => "StopMelody",
             StateMessage::CreateFadeDefinition { .. } => "CreateFadeDefinition",
+            StateMessage::StartFade { .. } => "StartFade",
+            StateMessage::SetQuotas { .. } => "SetQuotas",
             StateMessage::CreateSequence { .. } => "CreateSequence",
             StateMessage::StartSequence { .. } => "StartSequence",
             StateMessage::StartSequenceOnce { .. } => "StartSequenceOnce",
@@ -660,6 +1073,16 @@ impl StateMessage {
             #[cfg(feature = "native")]
             StateMessage::MidiClearRecording => "MidiClearRecording",
             #[cfg(feature = "native")]
+            StateMessage::MidiArmTake { .. } => "MidiArmTake",
+            #[cfg(feature = "native")]
+            StateMessage::MidiStopTake => "MidiStopTake",
+            #[cfg(feature = "native")]
+            StateMessage::MidiAuditionTake { .. } => "MidiAuditionTake",
+            #[cfg(feature = "native")]
+            StateMessage::MidiKeepTake { .. } => "MidiKeepTake",
+            #[cfg(feature = "native")]
+            StateMessage::MidiDiscardTake { .. } => "MidiDiscardTake",
+            #[cfg(feature = "native")]
             StateMessage::MidiOutputOpenDevice { .. } => "MidiOutputOpenDevice",
             #[cfg(feature = "native")]
             StateMessage::MidiOutputCloseDevice { .. } => "MidiOutputCloseDevice",
@@ -674,10 +1097,14 @@ impl StateMessage {
             #[cfg(feature = "native")]
             StateMessage::MidiOutputPitchBend { .. } => "MidiOutputPitchBend",
             #[cfg(feature = "native")]
+            StateMessage::MidiOutputSendRaw { .. } => "MidiOutputSendRaw",
+            #[cfg(feature = "native")]
             StateMessage::MidiOutputSetClockEnabled { .. } => "MidiOutputSetClockEnabled",
             #[cfg(feature = "native")]
             StateMessage::MidiOutputSetClockDevice { .. } => "MidiOutputSetClockDevice",
             #[cfg(feature = "native")]
+            StateMessage::MidiOutputSetDeviceLatency { .. } => "MidiOutputSetDeviceLatency",
+            #[cfg(feature = "native")]
             StateMessage::MidiOutputSendStart => "MidiOutputSendStart",
             #[cfg(feature = "native")]
             StateMessage::MidiOutputSendStop => "MidiOutputSendStop",
@@ -8,27 +8,31 @@
 //!
 //! - [`ScriptState`] - The complete state snapshot
 //! - [`StateMessage`] - All possible state mutations
-//! - [`StateManager`] - Thread-safe state access
+//! - [`StateManager`] - Thread-safe state access and quota checks
 
 mod manager;
 mod messages;
 mod model;
+mod quotas;
 
 pub use manager::StateManager;
 pub use messages::StateMessage;
+pub use quotas::{buffer_mb, QuotaExceeded, QuotaKind, Quotas};
 
 // Platform-independent types
 pub use model::{
-    ActiveFadeJob, ActiveSequence, ActiveSynth, EffectState, GroupState, LoopStatus, MelodyState,
-    MeterLevel, PatternState, SampleInfo, SampleSlice, ScheduledEvent,
-    ScheduledNoteOff, ScriptState, SequenceRunLog, VoiceState, VstInstrumentInfo,
+    ActiveFadeJob, ActiveSequence, ActiveSynth, CheckpointState, EffectState, GroupFreeze, GroupState, GroupTree, HookState, LoopStatus, LooperState, LooperStatus, MelodyState,
+    LiveSetState, LoudnessState, MeterLevel, NetSyncRole, NetSyncState, NoteOrigin, NoteSource, PatternMidiTarget, PatternState, PendingTransition, PerformanceState, PlaybackGraphState,
+    FadingSection, ResampleState, ResampleStatus, ReturnChannelState, SampleInfo, SampleSlice, ScheduledEvent, ScheduledNoteOff, ScriptState, SequenceRunLog, SoundingNote, VoiceState,
+    VstInstrumentInfo,
 };
 
 // Native-only MIDI types
 #[cfg(feature = "native")]
 pub use model::{
-    MidiCallbackInfo, MidiCallbackType, MidiConfiguration, MidiDeviceState,
-    MidiOutputConfiguration, MidiOutputDeviceState, MidiRecordingState, RecordedMidiNote,
+    ArmedTake, MidiCallbackInfo, MidiCallbackType, MidiConfiguration, MidiDeviceState,
+    MidiOutputConfiguration, MidiOutputDeviceState, MidiRecordingState, MidiTake, RecordedMidiNote,
+    TakeAudition, TakeMode, TakeTargetKind,
 };
 
 // Re-export scheduler types that are closely tied to state
@@ -4,14 +4,37 @@
 //! including groups, voices, patterns, melodies, effects, and samples.
 
 use crate::api::context::SourceLocation;
-use crate::events::{BeatEvent, FadeTargetType, Pattern};
+use crate::automation::Automation;
+use crate::chord::Arpeggiator;
+use crate::drumkit::DrumKit;
+use crate::events::{BeatEvent, EventClip, FadeCurve, FadeTargetType, Pattern};
+use crate::groove::{GrooveTemplate, Humanize, TimingFeel};
+use crate::hooks::HookAction;
+use crate::mono::{MonoMode, MonoState};
+use crate::liveset::LiveSet;
+use crate::locators::Locators;
+use crate::loop_text::LoopText;
+use crate::macros::MacroControl;
+use crate::meter_condition::MeterCondition;
+use crate::musical_key::MusicalKey;
+use crate::scale::{Scale, ScaleSnap};
+use crate::playback_graph::{PlaybackGraph, TransitionStyle};
+use crate::quantization::{LaunchKind, LaunchQuantization};
+use crate::performance::{CpuBudget, CpuPolicy, OscStats, ServerStatus};
+use super::quotas::Quotas;
+use crate::gc::GcState;
 #[cfg(feature = "native")]
 use crate::midi::{MidiBackend, MidiDeviceInfo, MidiOutputDeviceInfo, MidiRouting, QueuedMidiEvent};
 #[cfg(feature = "native")]
 use crossbeam_channel::Sender;
+use vibelang_dsp::ParamRange;
 use crate::sequences::SequenceDefinition;
-use crate::timing::TimeSignature;
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::smoothing::ParamSmoothing;
+use crate::timing::{ClockSource, TimeSignature};
+use crate::variations::PatternVariations;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::time::Instant;
 
 // ============================================================================
@@ -218,6 +241,18 @@ pub struct MidiRecordingState {
 
     /// The beat position of the oldest note in history.
     pub oldest_beat: f64,
+
+    /// Take currently being recorded against a pattern or melody loop.
+    pub armed_take: Option<ArmedTake>,
+
+    /// Finished takes awaiting audition, keep or discard.
+    pub takes: Vec<MidiTake>,
+
+    /// Take currently applied to its target loop for auditioning.
+    pub audition: Option<TakeAudition>,
+
+    /// Id assigned to the next finished take.
+    pub next_take_id: u32,
 }
 
 #[cfg(feature = "native")]
@@ -230,6 +265,10 @@ impl Default for MidiRecordingState {
             recording_enabled: true,
             pending_notes: HashMap::new(),
             oldest_beat: 0.0,
+            armed_take: None,
+            takes: Vec::new(),
+            audition: None,
+            next_take_id: 1,
         }
     }
 }
@@ -427,7 +466,7 @@ impl MidiRecordingState {
             .iter()
             .map(|slot| match slot {
                 None => ".".to_string(),
-                Some((note, true)) => midi_note_to_name(*note),
+                Some((note, true)) => crate::pitch::note_name(*note),
                 Some((_, false)) => "-".to_string(),
             })
             .collect();
@@ -497,6 +536,217 @@ impl MidiRecordingState {
     }
 }
 
+/// How a take combines with the loop it was recorded against.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TakeMode {
+    /// Layer the take's notes on top of the loop's events.
+    Overdub,
+    /// Replace the loop's events with the take's notes.
+    Replace,
+}
+
+#[cfg(feature = "native")]
+impl TakeMode {
+    /// Parse a mode name ("overdub" or "replace").
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "overdub" => Some(Self::Overdub),
+            "replace" => Some(Self::Replace),
+            _ => None,
+        }
+    }
+
+    /// Mode name as used by the HTTP API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Overdub => "overdub",
+            Self::Replace => "replace",
+        }
+    }
+}
+
+/// Whether a take targets a pattern or a melody.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TakeTargetKind {
+    /// Step pattern: takes become triggers.
+    Pattern,
+    /// Melody: takes keep pitch and duration.
+    Melody,
+}
+
+/// A take being recorded: notes routed to the target's voice, folded into its loop.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone)]
+pub struct ArmedTake {
+    /// Name of the target pattern or melody.
+    pub target: String,
+    /// Whether the target is a pattern or a melody.
+    pub kind: TakeTargetKind,
+    /// Voice the target plays; only notes routed to it are captured.
+    pub voice_name: Option<String>,
+    /// Overdub or replace.
+    pub mode: TakeMode,
+    /// Loop length of the target in beats.
+    pub loop_beats: f64,
+    /// Captured notes, with beats relative to the loop start.
+    pub notes: Vec<RecordedMidiNote>,
+}
+
+/// A finished take for one target loop.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone)]
+pub struct MidiTake {
+    /// Take id (unique for the session).
+    pub id: u32,
+    /// Name of the target pattern or melody.
+    pub target: String,
+    /// Whether the target is a pattern or a melody.
+    pub kind: TakeTargetKind,
+    /// Overdub or replace.
+    pub mode: TakeMode,
+    /// Loop length of the target in beats.
+    pub loop_beats: f64,
+    /// Recorded notes, with beats relative to the loop start.
+    pub notes: Vec<RecordedMidiNote>,
+}
+
+#[cfg(feature = "native")]
+impl MidiTake {
+    /// This take with its notes snapped into `scale`.
+    pub fn quantized_to(mut self, scale: &Scale) -> Self {
+        for note in &mut self.notes {
+            note.note = scale.quantize(note.note);
+        }
+        self
+    }
+
+    /// Combine this take with a loop's events according to its mode.
+    ///
+    /// New events copy the loop's first event (synth, group, voice) when
+    /// there is one, so the take sounds through the same voice.
+    pub fn apply_to(&self, loop_pattern: &Pattern) -> Pattern {
+        let template = loop_pattern.events.first().cloned().unwrap_or_else(|| match self.kind {
+            TakeTargetKind::Pattern => BeatEvent::new(0.0, "trigger"),
+            TakeTargetKind::Melody => BeatEvent::new(0.0, "melody_note"),
+        });
+
+        let mut pattern = loop_pattern.clone();
+        if self.mode == TakeMode::Replace {
+            pattern.events.clear();
+        }
+        for note in &self.notes {
+            let mut event = template.clone();
+            event.beat = note.beat;
+            event.fade = None;
+            let amp = note.velocity as f32 / 127.0;
+            event.controls = match self.kind {
+                TakeTargetKind::Pattern => vec![("amp".to_string(), amp)],
+                TakeTargetKind::Melody => {
+                    let freq = crate::pitch::note_to_freq(note.note as f64);
+                    vec![
+                        ("freq".to_string(), freq as f32),
+                        ("amp".to_string(), amp),
+                        ("gate".to_string(), note.duration as f32),
+                    ]
+                }
+            };
+            pattern.events.push(event);
+        }
+        pattern.events.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        pattern
+    }
+}
+
+/// A take applied to its target loop, with the loop's events before it.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone)]
+pub struct TakeAudition {
+    /// Id of the auditioned take.
+    pub take_id: u32,
+    /// Name of the target pattern or melody.
+    pub target: String,
+    /// Whether the target is a pattern or a melody.
+    pub kind: TakeTargetKind,
+    /// The target's loop before the take was applied.
+    pub original: Pattern,
+}
+
+#[cfg(feature = "native")]
+impl MidiRecordingState {
+    /// Start recording a take against a target loop, replacing any armed take.
+    pub fn arm_take(
+        &mut self,
+        target: String,
+        kind: TakeTargetKind,
+        voice_name: Option<String>,
+        mode: TakeMode,
+        loop_beats: f64,
+    ) {
+        self.armed_take = Some(ArmedTake {
+            target,
+            kind,
+            voice_name,
+            mode,
+            loop_beats,
+            notes: Vec::new(),
+        });
+    }
+
+    /// Capture a completed note into the armed take, if it is routed to the target's voice.
+    ///
+    /// The note's beat is folded into the loop, so a take can span several
+    /// passes and later passes layer onto earlier ones.
+    pub fn capture_take_note(&mut self, note: &RecordedMidiNote) {
+        let Some(armed) = self.armed_take.as_mut() else {
+            return;
+        };
+        if armed.voice_name.as_ref().is_some_and(|v| *v != note.voice_name) || armed.loop_beats <= 0.0 {
+            return;
+        }
+        let mut note = note.clone();
+        note.beat = note.beat.rem_euclid(armed.loop_beats);
+        note.duration = note.duration.min(armed.loop_beats);
+        armed.notes.push(note);
+    }
+
+    /// Finish the armed take; returns its id, or None if nothing was captured.
+    pub fn finish_take(&mut self) -> Option<u32> {
+        let armed = self.armed_take.take()?;
+        if armed.notes.is_empty() {
+            return None;
+        }
+        let id = self.next_take_id;
+        self.next_take_id += 1;
+        self.takes.push(MidiTake {
+            id,
+            target: armed.target,
+            kind: armed.kind,
+            mode: armed.mode,
+            loop_beats: armed.loop_beats,
+            notes: armed.notes,
+        });
+        Some(id)
+    }
+
+    /// Look up a take by id.
+    pub fn take(&self, id: u32) -> Option<&MidiTake> {
+        self.takes.iter().find(|t| t.id == id)
+    }
+
+    /// Remove a take by id.
+    pub fn remove_take(&mut self, id: u32) -> Option<MidiTake> {
+        let index = self.takes.iter().position(|t| t.id == id)?;
+        Some(self.takes.remove(index))
+    }
+
+    /// Id of the take being auditioned, if any.
+    pub fn auditioning(&self) -> Option<u32> {
+        self.audition.as_ref().map(|a| a.take_id)
+    }
+}
+
 /// Map MIDI velocity (0-127) to step character.
 #[cfg(feature = "native")]
 fn velocity_to_step_char(velocity: u8) -> char {
@@ -515,15 +765,6 @@ fn velocity_to_step_char(velocity: u8) -> char {
     }
 }
 
-/// Convert MIDI note number to note name (e.g., 60 -> "C4").
-#[cfg(feature = "native")]
-fn midi_note_to_name(note: u8) -> String {
-    const NOTES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-    let octave = (note / 12) as i8 - 1;
-    let note_name = NOTES[(note % 12) as usize];
-    format!("{}{}", note_name, octave)
-}
-
 /// Determine the sub-group size for formatting within a bar.
 /// Returns a group size that divides the bar into 2-4 readable chunks.
 #[cfg(feature = "native")]
@@ -596,8 +837,14 @@ pub struct ScriptState {
     pub tempo: f64,
     /// Quantization grid in beats.
     pub quantization_beats: f64,
+    /// Launch grids by entity type, overriding `quantization_beats`.
+    pub launch_quantization: LaunchQuantization,
     /// Current time signature.
     pub time_signature: TimeSignature,
+    /// Session key that key-matched sample voices are transposed to.
+    pub session_key: Option<MusicalKey>,
+    /// Groove template whose timing feels apply to voices without their own.
+    pub groove: Option<GrooveTemplate>,
     /// Whether the transport is running.
     pub transport_running: bool,
     /// Current beat position.
@@ -616,8 +863,20 @@ pub struct ScriptState {
     pub fade_defs: HashMap<String, crate::sequences::FadeDefinition>,
     /// Loaded samples by ID.
     pub samples: HashMap<String, SampleInfo>,
+    /// Audio input loopers by name.
+    pub loopers: HashMap<String, LooperState>,
+    /// Resampled group recordings by the name of their voice.
+    pub resamples: HashMap<String, ResampleState>,
+    /// Commands and webhooks fired from the timeline, by name.
+    pub hooks: HashMap<String, HookState>,
+    /// Return channels of external processing by name.
+    pub return_channels: HashMap<String, ReturnChannelState>,
     /// Loaded synthdefs by name (bytes stored for score capture).
     pub synthdefs: HashMap<String, Vec<u8>>,
+    /// Names of the synthdefs of each directory loaded with `synthdef_dir()`.
+    pub synthdef_dirs: BTreeMap<PathBuf, Vec<String>>,
+    /// Declared parameter ranges by synthdef name, then parameter name.
+    pub param_ranges: HashMap<String, HashMap<String, ParamRange>>,
     /// Loaded SFZ instruments by ID (placeholder type).
     pub sfz_instruments: HashMap<String, SfzInstrument>,
     /// Loaded VST instruments by ID.
@@ -646,6 +905,8 @@ pub struct ScriptState {
     pub next_buffer_id: i32,
     /// Next available audio bus.
     pub next_audio_bus: i32,
+    /// Next available control bus.
+    pub next_control_bus: i32,
     /// Effects by ID.
     pub effects: HashMap<String, EffectState>,
     /// Reload generation counter.
@@ -666,6 +927,41 @@ pub struct ScriptState {
     pub midi_recording: MidiRecordingState,
     /// Audio meter levels by group path.
     pub meter_levels: HashMap<String, MeterLevel>,
+    /// Master bus loudness (LUFS).
+    pub loudness: LoudnessState,
+    /// Click-free ramp times of parameter changes and mutes.
+    pub param_smoothing: ParamSmoothing,
+    /// Named song positions and a pending jump to one.
+    pub locators: Locators,
+    /// Automation lanes recorded from live tweaks, and the recording in progress.
+    pub automation: Automation,
+    /// File the OSC traffic is captured to, while a capture runs.
+    pub osc_capture: Option<PathBuf>,
+    /// File fired events are logged to, while an event log runs.
+    pub event_log: Option<PathBuf>,
+    /// Server CPU load and degradation state.
+    pub performance: PerformanceState,
+    /// Limits on the entities and sample memory of the session.
+    pub quotas: Quotas,
+    /// Garbage collection policy and bookkeeping.
+    pub gc: GcState,
+    /// Loaded live set and cue position.
+    pub live_set: Option<LiveSetState>,
+    /// Playback graphs by name.
+    pub playback_graphs: HashMap<String, PlaybackGraphState>,
+    /// Macro controls by name.
+    pub macros: HashMap<String, MacroControl>,
+    /// Script checkpoints and the selected stop.
+    pub checkpoints: CheckpointState,
+    /// Network transport sync with other machines.
+    pub net_sync: NetSyncState,
+    /// Audio-rate clock output for modular gear (None = off).
+    pub clock_output: Option<crate::clock_out::ClockOutput>,
+    /// Where the transport takes its tempo and position from.
+    pub clock_source: ClockSource,
+    /// Output latency of the audio interface in milliseconds; MIDI hardware
+    /// is scheduled this much later to sound with the audio.
+    pub output_latency_ms: f64,
     /// MIDI output configuration (devices, clock settings) - native only.
     #[cfg(feature = "native")]
     pub midi_output_config: MidiOutputConfiguration,
@@ -693,6 +989,238 @@ pub struct MeterLevel {
     pub last_update: Option<Instant>,
 }
 
+/// Master bus loudness measurement (EBU R128).
+///
+/// Values are `None` while the meter reads silence (-inf LUFS).
+#[derive(Clone, Debug, Default)]
+pub struct LoudnessState {
+    /// Momentary loudness (400ms window).
+    pub momentary_lufs: Option<f64>,
+    /// Short-term loudness (3s window).
+    pub short_term_lufs: Option<f64>,
+    /// Gated integrated loudness since the last reset.
+    pub integrated_lufs: Option<f64>,
+    /// Highest momentary loudness since the last reset.
+    pub max_momentary_lufs: Option<f64>,
+    /// Target loudness; exceeding it in short-term loudness logs a warning.
+    pub target_lufs: Option<f64>,
+    /// Whether short-term loudness is currently above the target.
+    pub over_target: bool,
+    /// Time of last update.
+    pub last_update: Option<Instant>,
+}
+
+/// Server CPU load and the degradation applied to stay within budget.
+#[derive(Clone, Debug, Default)]
+pub struct PerformanceState {
+    /// Most recent `/status` reply from scsynth.
+    pub status: Option<ServerStatus>,
+    /// Degradation policy.
+    pub policy: CpuPolicy,
+    /// Current degradation state.
+    pub budget: CpuBudget,
+    /// Events dropped since the load last went over budget, as of the last status poll.
+    pub dropped_events: u64,
+    /// Events fired late because the tick loop fell behind, as of the last status poll.
+    pub late_events: u64,
+    /// Stale non-anchor events dropped by the tick loop, as of the last status poll.
+    pub stale_dropped_events: u64,
+    /// Sequence fades waiting for the load to recover.
+    pub postponed_fades: usize,
+    /// Timed bundles sent to scsynth, as of the last status poll.
+    pub osc: OscStats,
+    /// Time of last update.
+    pub last_update: Option<Instant>,
+    /// When the runtime thread last polled the server; goes stale when its loop hangs.
+    pub heartbeat: Option<Instant>,
+}
+
+/// A loaded live set and where the performance is in its cue list.
+#[derive(Clone, Debug)]
+pub struct LiveSetState {
+    /// Path of the `.vibeset` file.
+    pub path: PathBuf,
+    /// Parsed live set.
+    pub set: LiveSet,
+    /// Index of the most recently launched cue.
+    pub cue_index: Option<usize>,
+    /// Most recently launched scene.
+    pub active_scene: Option<String>,
+    /// `eval` snippets of launched scenes waiting for the script thread.
+    pub pending_evals: Vec<String>,
+}
+
+impl LiveSetState {
+    /// Create the state for a freshly loaded live set.
+    pub fn new(path: PathBuf, set: LiveSet) -> Self {
+        Self {
+            path,
+            set,
+            cue_index: None,
+            active_scene: None,
+            pending_evals: Vec::new(),
+        }
+    }
+
+    /// Scene that GO launches next.
+    pub fn next_cue(&self) -> Option<&str> {
+        let next = self.cue_index.map_or(0, |i| i + 1);
+        self.set.cues.get(next).map(String::as_str)
+    }
+
+    /// Index of the cue `delta` steps away from the current one.
+    ///
+    /// Before the first GO, stepping forward by one selects the first cue.
+    /// Returns `None` when stepping past either end of the list.
+    pub fn step_cue(&self, delta: i32) -> Option<usize> {
+        let target = match self.cue_index {
+            Some(index) => index as i64 + delta as i64,
+            None if delta > 0 => delta as i64 - 1,
+            None => return None,
+        };
+        if target < 0 || target >= self.set.cues.len() as i64 {
+            None
+        } else {
+            Some(target as usize)
+        }
+    }
+}
+
+/// Which side of a network transport sync this session is on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetSyncRole {
+    /// Broadcasts beat and tempo to followers on this UDP port.
+    Leader { port: u16 },
+    /// Follows the leader at this `host:port`.
+    Follower { leader: String },
+}
+
+/// Network transport sync status.
+#[derive(Clone, Debug, Default)]
+pub struct NetSyncState {
+    /// Sync role (`None` = not syncing).
+    pub role: Option<NetSyncRole>,
+    /// Followers heard from recently (leader), or 1 while the leader answers (follower).
+    pub peers: usize,
+    /// Estimated leader clock minus local clock (follower).
+    pub offset_ms: Option<f64>,
+    /// Network round trip to the leader (follower).
+    pub round_trip_ms: Option<f64>,
+    /// Transport phase error before the last correction (follower).
+    pub phase_error_ms: Option<f64>,
+    /// Time of last update.
+    pub last_update: Option<Instant>,
+}
+
+/// Checkpoints of the script and where evaluation stops.
+///
+/// `checkpoint("name")` ends evaluation when it is the selected checkpoint,
+/// so a file can be staged in parts and revealed progressively.
+#[derive(Clone, Debug, Default)]
+pub struct CheckpointState {
+    /// Checkpoint names in script order.
+    pub names: Vec<String>,
+    /// Evaluation stops at this checkpoint (`None` = whole script).
+    pub selected: Option<String>,
+    /// Set when the selection changed and the script thread should re-evaluate.
+    pub reload_requested: bool,
+}
+
+impl CheckpointState {
+    /// Position of the selection, where `names.len()` is the whole script.
+    pub fn position(&self) -> usize {
+        self.selected
+            .as_ref()
+            .and_then(|name| self.names.iter().position(|n| n == name))
+            .unwrap_or(self.names.len())
+    }
+
+    /// Selection `delta` steps away from the current one.
+    ///
+    /// Stepping past the last checkpoint selects the whole script.
+    /// Returns `None` when stepping past either end.
+    pub fn step(&self, delta: i32) -> Option<Option<String>> {
+        let target = self.position() as i64 + delta as i64;
+        if target < 0 || target > self.names.len() as i64 {
+            None
+        } else {
+            Some(self.names.get(target as usize).cloned())
+        }
+    }
+}
+
+/// A playback graph and which of its sections is playing.
+#[derive(Clone, Debug)]
+pub struct PlaybackGraphState {
+    /// Graph definition.
+    pub graph: PlaybackGraph,
+    /// Variables tested by `when` conditions.
+    pub vars: HashMap<String, f64>,
+    /// Current section, `None` while the graph is stopped.
+    pub current: Option<String>,
+    /// Beat at which the current section started.
+    pub entered_beat: f64,
+    /// Whether the graph was cued since the current section was entered.
+    pub cued: bool,
+    /// Transition waiting for its bar line.
+    pub pending: Option<PendingTransition>,
+    /// Section still playing while it is crossfaded out.
+    pub fading_out: Option<FadingSection>,
+    /// Group `amp` of sections faded out by a crossfade, restored when
+    /// they are entered again.
+    pub faded_amps: HashMap<String, f32>,
+}
+
+/// A graph transition scheduled for a bar line.
+#[derive(Clone, Debug)]
+pub struct PendingTransition {
+    /// Section being left.
+    pub from: String,
+    /// Section being entered (its sequence is already anchored at `at_beat`).
+    pub to: String,
+    /// Transition style.
+    pub style: TransitionStyle,
+    /// Beat at which the sections switch.
+    pub at_beat: f64,
+}
+
+/// A section that keeps playing until its crossfade ends.
+#[derive(Clone, Debug)]
+pub struct FadingSection {
+    /// Section name.
+    pub section: String,
+    /// Beat at which its sequence stops.
+    pub until_beat: f64,
+}
+
+impl PlaybackGraphState {
+    /// Create the state for a stopped graph.
+    pub fn new(graph: PlaybackGraph) -> Self {
+        Self {
+            graph,
+            vars: HashMap::new(),
+            current: None,
+            entered_beat: 0.0,
+            cued: false,
+            pending: None,
+            fading_out: None,
+            faded_amps: HashMap::new(),
+        }
+    }
+
+    /// Whether a section is playing.
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Enter a section starting at `beat`.
+    pub fn enter(&mut self, section: &str, beat: f64) {
+        self.current = Some(section.to_string());
+        self.entered_beat = beat;
+        self.cued = false;
+    }
+}
+
 impl Default for ScriptState {
     fn default() -> Self {
         Self::new()
@@ -706,7 +1234,10 @@ impl ScriptState {
             version: 0,
             tempo: 120.0,
             quantization_beats: 4.0,
+            launch_quantization: LaunchQuantization::default(),
             time_signature: TimeSignature::default(),
+            session_key: None,
+            groove: None,
             transport_running: false,
             current_beat: 0.0,
             groups: HashMap::new(),
@@ -715,7 +1246,13 @@ impl ScriptState {
             melodies: HashMap::new(),
             sequences: HashMap::new(),
             samples: HashMap::new(),
+            loopers: HashMap::new(),
+            resamples: HashMap::new(),
+            hooks: HashMap::new(),
+            return_channels: HashMap::new(),
             synthdefs: HashMap::new(),
+            synthdef_dirs: BTreeMap::new(),
+            param_ranges: HashMap::new(),
             sfz_instruments: HashMap::new(),
             vst_instruments: HashMap::new(),
             scheduled_events: Vec::new(),
@@ -730,6 +1267,7 @@ impl ScriptState {
             next_group_node_id: 1000,
             next_buffer_id: 100,
             next_audio_bus: 16,
+            next_control_bus: 0,
             effects: HashMap::new(),
             reload_generation: 0,
             scrub_muted: false,
@@ -738,6 +1276,23 @@ impl ScriptState {
             next_midi_callback_id: 1,
             midi_recording: MidiRecordingState::new(),
             meter_levels: HashMap::new(),
+            loudness: LoudnessState::default(),
+            param_smoothing: ParamSmoothing::default(),
+            locators: Locators::default(),
+            automation: Automation::default(),
+            osc_capture: None,
+            event_log: None,
+            performance: PerformanceState::default(),
+            quotas: Quotas::default(),
+            gc: GcState::default(),
+            live_set: None,
+            playback_graphs: HashMap::new(),
+            macros: HashMap::new(),
+            checkpoints: CheckpointState::default(),
+            net_sync: NetSyncState::default(),
+            clock_output: None,
+            clock_source: ClockSource::Internal,
+            output_latency_ms: 0.0,
             midi_output_config: MidiOutputConfiguration::new(),
             next_midi_output_device_id: 1,
         }
@@ -769,6 +1324,223 @@ impl ScriptState {
         id
     }
 
+    /// Launch grid in beats of an entity of `kind` with its own grid
+    /// `entity`, if any (see [`LaunchQuantization::resolve`]).
+    pub fn launch_grid(&self, kind: LaunchKind, entity: Option<f64>) -> f64 {
+        self.launch_quantization.resolve(kind, entity, self.quantization_beats)
+    }
+
+    /// Beat from which events in a group are replaced by a frozen bounce.
+    ///
+    /// Checks the group and all of its ancestors and returns the earliest
+    /// freeze point, or `None` if no enclosing group is frozen.
+    pub fn frozen_from_beat(&self, group_path: &str) -> Option<f64> {
+        let mut path = Some(group_path);
+        let mut frozen_from: Option<f64> = None;
+        while let Some(p) = path {
+            let group = self.groups.get(p);
+            if let Some(freeze) = group.and_then(|g| g.freeze.as_ref()) {
+                frozen_from = Some(frozen_from.map_or(freeze.end_beat, |b| b.min(freeze.end_beat)));
+            }
+            path = group.and_then(|g| g.parent_path.as_deref());
+        }
+        frozen_from
+    }
+
+    /// Voices played by a sequence's patterns and melodies, nested sequences included.
+    pub fn sequence_voices(&self, name: &str) -> HashSet<String> {
+        fn collect(state: &ScriptState, name: &str, stack: &mut Vec<String>, voices: &mut HashSet<String>) {
+            let Some(def) = state.sequences.get(name) else { return };
+            if stack.iter().any(|n| n == name) {
+                return;
+            }
+            stack.push(name.to_string());
+            for clip in &def.clips {
+                let voice = match &clip.source {
+                    crate::sequences::ClipSource::Pattern(p) => state.patterns.get(p).and_then(|p| p.voice_name.clone()),
+                    crate::sequences::ClipSource::Melody(m) => state.melodies.get(m).and_then(|m| m.voice_name.clone()),
+                    crate::sequences::ClipSource::Sequence(nested) => {
+                        collect(state, nested, stack, voices);
+                        None
+                    }
+                    crate::sequences::ClipSource::Fade(_) => None,
+                };
+                voices.extend(voice);
+            }
+            stack.pop();
+        }
+
+        let mut voices = HashSet::new();
+        collect(self, name, &mut Vec::new(), &mut voices);
+        voices
+    }
+
+    /// Playing sequence a legato launch of `name` takes over from.
+    ///
+    /// That is another running sequence that plays one of the same voices
+    /// and is not already on its way out. Returns its name, anchor beat and
+    /// iteration length.
+    pub fn legato_predecessor(&self, name: &str) -> Option<(String, f64, f64)> {
+        let voices = self.sequence_voices(name);
+        if voices.is_empty() {
+            return None;
+        }
+        let mut candidates: Vec<_> = self
+            .active_sequences
+            .iter()
+            .filter(|(other, active)| {
+                other.as_str() != name && !active.paused && !active.completed && active.stop_beat.is_none()
+            })
+            .filter_map(|(other, active)| {
+                let def = self.sequences.get(other)?;
+                let shares_voice = self.sequence_voices(other).iter().any(|v| voices.contains(v));
+                shares_voice.then(|| (other.clone(), active.anchor_beat, def.playback_beats()))
+            })
+            .collect();
+        // Most recently started first, then by name for a stable choice
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        candidates.into_iter().next()
+    }
+
+    /// The group hierarchy from `path` down.
+    pub fn group_tree(&self, path: &str) -> Option<GroupTree> {
+        let group = self.groups.get(path)?;
+        let mut effects: Vec<&EffectState> =
+            self.effects.values().filter(|e| e.group_path == path && e.voice.is_none()).collect();
+        effects.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.id.cmp(&b.id)));
+        let mut voices: Vec<String> = self
+            .voices
+            .values()
+            .filter(|v| v.group_path == path)
+            .map(|v| v.name.clone())
+            .collect();
+        voices.sort();
+        let mut children: Vec<&String> = self
+            .groups
+            .values()
+            .filter(|g| g.parent_path.as_deref() == Some(path) && g.path != path)
+            .map(|g| &g.path)
+            .collect();
+        children.sort();
+
+        Some(GroupTree {
+            name: group.name.clone(),
+            path: group.path.clone(),
+            node_id: group.node_id,
+            audio_bus: group.audio_bus,
+            link_synth_node_id: group.link_synth_node_id,
+            effects: effects.into_iter().map(|e| e.id.clone()).collect(),
+            voices,
+            children: children.into_iter().filter_map(|child| self.group_tree(child)).collect(),
+        })
+    }
+
+    /// The hierarchies of all top-level groups (normally just `main`).
+    pub fn group_trees(&self) -> Vec<GroupTree> {
+        let mut roots: Vec<&String> = self
+            .groups
+            .values()
+            .filter(|g| g.parent_path.as_ref().is_none_or(|parent| !self.groups.contains_key(parent)))
+            .map(|g| &g.path)
+            .collect();
+        roots.sort();
+        roots.into_iter().filter_map(|root| self.group_tree(root)).collect()
+    }
+
+    /// Resolve a group path or bare group name to its path.
+    pub fn find_group_path(&self, path_or_name: &str) -> Option<String> {
+        if self.groups.contains_key(path_or_name) {
+            return Some(path_or_name.to_string());
+        }
+        self.groups
+            .iter()
+            .find(|(path, g)| g.name == path_or_name || path.ends_with(&format!(".{}", path_or_name)))
+            .map(|(path, _)| path.clone())
+    }
+
+    /// Declared range of a synthdef parameter.
+    pub fn param_range(&self, synthdef: &str, param: &str) -> Option<&ParamRange> {
+        self.param_ranges.get(synthdef)?.get(param)
+    }
+
+    /// The synthdef whose parameters a voice, pattern, melody or effect sets.
+    ///
+    /// Patterns and melodies play through their voice; groups span several
+    /// synthdefs and have none.
+    pub fn target_synthdef(&self, target_type: &FadeTargetType, name: &str) -> Option<&str> {
+        let voice_synth = |voice: &str| self.voices.get(voice).and_then(|v| v.synth_name.as_deref());
+        match target_type {
+            FadeTargetType::Voice => voice_synth(name),
+            FadeTargetType::Pattern => voice_synth(self.patterns.get(name)?.voice_name.as_deref()?),
+            FadeTargetType::Melody => voice_synth(self.melodies.get(name)?.voice_name.as_deref()?),
+            FadeTargetType::Effect => self.effects.get(name).map(|e| e.synthdef_name.as_str()),
+            FadeTargetType::Group => None,
+        }
+    }
+
+    /// Priority below which voice events are dropped to save CPU.
+    pub fn drop_priority_cutoff(&self) -> Option<i64> {
+        let perf = &self.performance;
+        if !perf.budget.over_budget || !perf.policy.drop_low_priority_voices {
+            return None;
+        }
+        crate::performance::drop_priority_cutoff(
+            self.voices.values().map(|v| v.priority),
+            perf.budget.degrade_level,
+        )
+    }
+
+    /// Polyphony to enforce for a voice, halved while over the CPU budget.
+    pub fn effective_polyphony(&self, voice: &VoiceState) -> i64 {
+        let perf = &self.performance;
+        if perf.budget.over_budget && perf.policy.reduce_polyphony {
+            crate::performance::reduced_polyphony(voice.polyphony)
+        } else {
+            voice.polyphony
+        }
+    }
+
+    /// Timing feel of a voice: its own, or the groove template's.
+    pub fn voice_feel(&self, voice_name: &str) -> Option<TimingFeel> {
+        self.voices
+            .get(voice_name)
+            .and_then(|v| v.feel)
+            .or_else(|| self.groove.as_ref().and_then(|g| g.feel(voice_name)).copied())
+    }
+
+    /// Re-transpose a key-matched voice to the session key.
+    ///
+    /// Scales the voice's `rate` (or `pitch` for warp voices) by the change in
+    /// transposition; a voice whose sample or session has no key plays untransposed.
+    pub fn apply_key_match(&mut self, voice_name: &str) {
+        let Some(voice) = self.voices.get(voice_name) else {
+            return;
+        };
+        let semitones = voice
+            .key_match
+            .as_ref()
+            .and_then(|id| self.samples.get(id))
+            .and_then(|sample| sample.detected_key)
+            .zip(self.session_key)
+            .map(|(sample_key, session_key)| sample_key.semitones_to(&session_key))
+            .unwrap_or(0);
+        let Some(voice) = self.voices.get_mut(voice_name) else {
+            return;
+        };
+        if semitones == voice.key_transpose {
+            return;
+        }
+        let param = if voice.synth_name.as_deref().is_some_and(|s| s.starts_with("warp_voice")) {
+            "pitch"
+        } else {
+            "rate"
+        };
+        let ratio = 2.0f32.powf((semitones - voice.key_transpose) as f32 / 12.0);
+        let value = voice.params.entry(param.to_string()).or_insert(1.0);
+        *value *= ratio;
+        voice.key_transpose = semitones;
+    }
+
     /// Allocate a new audio bus.
     pub fn allocate_audio_bus(&mut self) -> i32 {
         let id = self.next_audio_bus;
@@ -776,6 +1548,13 @@ impl ScriptState {
         id
     }
 
+    /// Allocate `count` consecutive control buses, returning the first.
+    pub fn allocate_control_buses(&mut self, count: i32) -> i32 {
+        let id = self.next_control_bus;
+        self.next_control_bus += count;
+        id
+    }
+
     /// Allocate a new MIDI device ID (native only).
     #[cfg(feature = "native")]
     pub fn allocate_midi_device_id(&mut self) -> u32 {
@@ -820,6 +1599,215 @@ pub struct GroupState {
     pub generation: u64,
     /// Source location where this group was defined.
     pub source_location: SourceLocation,
+    /// Freeze (bounce) state, if the group is being or has been frozen.
+    pub freeze: Option<GroupFreeze>,
+}
+
+/// A group with its effects, voices and sub-groups (see [`ScriptState::group_tree`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupTree {
+    /// Short name of the group.
+    pub name: String,
+    /// Full path.
+    pub path: String,
+    /// SuperCollider node ID.
+    pub node_id: Option<i32>,
+    /// Audio bus the group's synths write to.
+    pub audio_bus: i32,
+    /// Node ID of the link synth routing to the parent.
+    pub link_synth_node_id: Option<i32>,
+    /// IDs of the group's effects, in chain order.
+    pub effects: Vec<String>,
+    /// Names of the voices in the group.
+    pub voices: Vec<String>,
+    /// Sub-groups, by path.
+    pub children: Vec<GroupTree>,
+}
+
+/// Freeze state of a group whose output is bounced to a buffer.
+#[derive(Clone, Debug)]
+pub struct GroupFreeze {
+    /// Number of bars recorded.
+    pub bars: u32,
+    /// Buffer holding the bounce.
+    pub buffer_id: i32,
+    /// Beat at which recording starts.
+    pub start_beat: f64,
+    /// Beat at which the bounce replaces live synthesis.
+    pub end_beat: f64,
+    /// Loop length in seconds.
+    pub duration_seconds: f64,
+    /// Node ID of the recorder synth.
+    pub recorder_node_id: i32,
+    /// Node ID of the looping player (set once the group is frozen).
+    pub player_node_id: Option<i32>,
+    /// Nodes paused while frozen (resumed on unfreeze).
+    pub paused_node_ids: Vec<i32>,
+}
+
+impl GroupFreeze {
+    /// Whether the bounce is playing in place of the original synths.
+    pub fn is_frozen(&self) -> bool {
+        self.player_node_id.is_some()
+    }
+}
+
+/// What a looper is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LooperStatus {
+    /// Nothing recorded.
+    Empty,
+    /// Recording a new loop (or waiting for the bar line to start).
+    Recording,
+    /// Looping the recorded buffer.
+    Playing,
+    /// Looping while recording one more pass on top.
+    Overdubbing,
+}
+
+/// A command or webhook fired from the timeline (see [`crate::hooks`]).
+#[derive(Debug, Clone)]
+pub struct HookState {
+    /// Hook name.
+    pub name: String,
+    /// What the hook does.
+    pub action: HookAction,
+    /// Beat the hook first fires on.
+    pub beat: f64,
+    /// Beats between repeats (`None` fires once).
+    pub every: Option<f64>,
+    /// How long the command or request may take.
+    pub timeout: std::time::Duration,
+    /// Reload generation.
+    pub generation: u64,
+    /// Next beat the hook fires on (`None` once a one-shot hook fired).
+    pub next_beat: Option<f64>,
+    /// Number of times the hook fired.
+    pub fired: u64,
+    /// Beat the hook last fired on.
+    pub last_fired_beat: Option<f64>,
+    /// Where the hook is defined.
+    pub source_location: SourceLocation,
+}
+
+/// An audio input looper (see [`crate::looper`]).
+#[derive(Debug, Clone)]
+pub struct LooperState {
+    /// Looper name.
+    pub name: String,
+    /// Group the loop plays into.
+    pub group_path: String,
+    /// Hardware input channel (1-based).
+    pub input: u32,
+    /// Loop length in bars.
+    pub bars: u32,
+    /// Playback gain (linear).
+    pub gain: f32,
+    /// Reload generation.
+    pub generation: u64,
+    /// Current status.
+    pub status: LooperStatus,
+    /// Buffer holding the loop.
+    pub buffer_id: Option<i32>,
+    /// Number of frames allocated for the buffer.
+    pub buffer_frames: i32,
+    /// Beat at which the loop (and the player's phase) starts.
+    pub anchor_beat: f64,
+    /// Loop length in beats.
+    pub loop_beats: f64,
+    /// Loop length in seconds.
+    pub duration_seconds: f64,
+    /// Beat at which the current recording pass starts.
+    pub record_start_beat: f64,
+    /// Beat at which the current recording pass ends.
+    pub record_end_beat: f64,
+    /// Node ID of the recorder while a pass is recording.
+    pub recorder_node_id: Option<i32>,
+    /// Node ID of the looping player.
+    pub player_node_id: Option<i32>,
+}
+
+impl LooperState {
+    /// Create an empty looper.
+    pub fn new(name: String, group_path: String) -> Self {
+        Self {
+            name,
+            group_path,
+            input: 1,
+            bars: crate::looper::DEFAULT_LOOPER_BARS,
+            gain: 1.0,
+            generation: 0,
+            status: LooperStatus::Empty,
+            buffer_id: None,
+            buffer_frames: 0,
+            anchor_beat: 0.0,
+            loop_beats: 0.0,
+            duration_seconds: 0.0,
+            record_start_beat: 0.0,
+            record_end_beat: 0.0,
+            recorder_node_id: None,
+            player_node_id: None,
+        }
+    }
+}
+
+/// What a resample is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResampleStatus {
+    /// Waiting for the transport (or the group's nodes) to start.
+    Waiting,
+    /// Recording (or waiting for the bar line to start).
+    Recording,
+    /// Writing the recording to disk.
+    Writing,
+    /// Chopped into the pads of its voice.
+    Done,
+    /// Writing or chopping the recording failed.
+    Failed,
+}
+
+/// A group recording chopped into a drum kit voice (see [`crate::resample`]).
+#[derive(Debug, Clone)]
+pub struct ResampleState {
+    /// Name of the voice playing the slices.
+    pub name: String,
+    /// Group recorded.
+    pub group_path: String,
+    /// Recording length in bars.
+    pub bars: u32,
+    /// Current status.
+    pub status: ResampleStatus,
+    /// Buffer recorded into (freed once written to disk).
+    pub buffer_id: i32,
+    /// Node ID of the recorder.
+    pub recorder_node_id: i32,
+    /// Beat at which the recording starts.
+    pub start_beat: f64,
+    /// Beat at which the recording ends.
+    pub end_beat: f64,
+    /// Recording length in seconds.
+    pub duration_seconds: f64,
+    /// Number of slices once chopped.
+    pub slices: usize,
+}
+
+/// A return channel playing hardware inputs into a group (see [`crate::return_channel`]).
+#[derive(Debug, Clone)]
+pub struct ReturnChannelState {
+    /// Return channel name.
+    pub name: String,
+    /// Group the returned audio plays into.
+    pub group_path: String,
+    /// First hardware input channel (1-based).
+    pub input: u32,
+    /// Number of input channels (1 or 2).
+    pub channels: u32,
+    /// Gain (linear).
+    pub gain: f32,
+    /// Reload generation.
+    pub generation: u64,
+    /// Node ID of the input synth while it runs.
+    pub node_id: Option<i32>,
 }
 
 impl GroupState {
@@ -844,6 +1832,7 @@ impl GroupState {
             synth_node_ids: Vec::new(),
             generation: 0,
             source_location: SourceLocation::default(),
+            freeze: None,
         }
     }
 
@@ -881,6 +1870,9 @@ pub struct VoiceState {
     pub sfz_instrument: Option<String>,
     /// Active notes for SFZ playback.
     pub active_notes: HashMap<u8, Vec<i32>>,
+    /// When and from where each pitch in `active_notes` started sounding.
+    /// Entries of pitches no longer in `active_notes` are stale.
+    pub note_origins: HashMap<u8, NoteOrigin>,
     /// Sustained notes.
     pub sustained_notes: HashSet<u8>,
     /// Round-robin state for SFZ.
@@ -893,6 +1885,8 @@ pub struct VoiceState {
     pub running: bool,
     /// Node ID of the running synth (if running).
     pub running_node_id: Option<i32>,
+    /// Synthdef the running synth plays.
+    pub running_synth: Option<String>,
     /// Generation when .run() was last called (for cleanup of stale running voices).
     pub run_generation: u64,
     /// Source location where this voice was defined.
@@ -903,6 +1897,33 @@ pub struct VoiceState {
     pub midi_channel: Option<u8>,
     /// CC mappings: parameter_name -> CC number.
     pub cc_mappings: HashMap<String, u8>,
+    /// Priority when the CPU budget is exceeded (lower tiers are dropped first).
+    pub priority: i64,
+    /// Sample whose detected key is matched to the session key (`.match_key()`).
+    pub key_match: Option<String>,
+    /// Semitones currently applied to the playback rate by key matching.
+    pub key_transpose: i32,
+    /// How far ahead of the beat this voice's events are sent, to compensate
+    /// slow attacks or leading silence.
+    pub pre_roll_ms: f64,
+    /// Timing feel of the voice (`.feel()`), overriding the groove template's.
+    pub feel: Option<TimingFeel>,
+    /// Mono mode (`.mono()`): one synth, gliding between overlapping notes.
+    pub mono: Option<MonoMode>,
+    /// Held notes and running synth of the voice in mono mode.
+    pub mono_state: MonoState,
+    /// First control bus of this voice's diagnostic outputs, once its synthdef
+    /// declared any.
+    pub diag_bus: Option<i32>,
+    /// Latest polled value of each diagnostic output, in bus order.
+    pub diag_values: Vec<(String, f32)>,
+    /// Pads of a drum kit voice (`drumkit()`), playing a sample per note.
+    pub drum_kit: Option<DrumKit>,
+    /// Private bus of a voice with insert effects (`.insert()`), which its
+    /// synths write to and its inserts process.
+    pub insert_bus: Option<i32>,
+    /// Link synth carrying the insert bus to the voice's output.
+    pub insert_link_node_id: Option<i32>,
 }
 
 impl VoiceState {
@@ -921,17 +1942,31 @@ impl VoiceState {
             params: HashMap::new(),
             sfz_instrument: None,
             active_notes: HashMap::new(),
+            note_origins: HashMap::new(),
             sustained_notes: HashSet::new(),
             round_robin_state: RoundRobinState::new(),
             vst_instrument: None,
             generation: 0,
             running: false,
             running_node_id: None,
+            running_synth: None,
             run_generation: 0,
             source_location: SourceLocation::default(),
             midi_output_device_id: None,
             midi_channel: None,
             cc_mappings: HashMap::new(),
+            priority: 0,
+            key_match: None,
+            key_transpose: 0,
+            pre_roll_ms: 0.0,
+            feel: None,
+            mono: None,
+            mono_state: MonoState::default(),
+            diag_bus: None,
+            diag_values: Vec::new(),
+            drum_kit: None,
+            insert_bus: None,
+            insert_link_node_id: None,
         }
     }
 
@@ -940,6 +1975,195 @@ impl VoiceState {
         self.source_location = source_location;
         self
     }
+
+    /// Track a note sounding on `node_id` (negative ids mark MIDI notes).
+    ///
+    /// The origin is kept from the first node of a pitch, so a retriggered
+    /// pitch reports the age of its oldest sounding note.
+    pub fn track_note(&mut self, note: u8, node_id: i32, origin: NoteOrigin) {
+        let nodes = self.active_notes.entry(note).or_default();
+        if nodes.is_empty() || !self.note_origins.contains_key(&note) {
+            self.note_origins.insert(note, origin);
+        }
+        nodes.push(node_id);
+    }
+
+    /// Pitches currently sounding on this voice, lowest first.
+    pub fn sounding_notes(&self, now: Instant) -> Vec<SoundingNote> {
+        let mut notes: Vec<SoundingNote> = self
+            .active_notes
+            .iter()
+            .filter(|(_, nodes)| !nodes.is_empty())
+            .map(|(&note, nodes)| {
+                let origin = self.note_origins.get(&note);
+                SoundingNote {
+                    note,
+                    count: nodes.len(),
+                    age: origin.map(|o| now.saturating_duration_since(o.started)).unwrap_or_default(),
+                    source: origin.map(|o| o.source.clone()),
+                    stuck: origin.is_some_and(|o| o.is_stuck(now)),
+                }
+            })
+            .collect();
+        notes.sort_by_key(|n| n.note);
+        notes
+    }
+
+    /// Flag notes that became stuck since the last check, returning their pitches.
+    pub fn flag_stuck_notes(&mut self, now: Instant) -> Vec<u8> {
+        let mut stuck: Vec<u8> = Vec::new();
+        for (&note, origin) in self.note_origins.iter_mut() {
+            let sounding = self.active_notes.get(&note).is_some_and(|nodes| !nodes.is_empty());
+            if sounding && !origin.flagged && origin.is_stuck(now) {
+                origin.flagged = true;
+                stuck.push(note);
+            }
+        }
+        stuck.sort_unstable();
+        stuck
+    }
+
+    /// Forget all sounding notes (after a panic freed them).
+    pub fn clear_notes(&mut self) {
+        self.active_notes.clear();
+        self.note_origins.clear();
+        self.sustained_notes.clear();
+        self.mono_state = MonoState::default();
+    }
+
+    /// Number of notes sounding on this voice, counting retriggers.
+    pub fn active_note_count(&self) -> usize {
+        self.active_notes.values().map(|nodes| nodes.len()).sum()
+    }
+
+    /// Amp of the voice's running synth: its gain, or silence while muted.
+    pub fn running_amp(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.gain as f32
+        }
+    }
+
+    /// Bus this voice's synths write to: its insert bus, if it has inserts,
+    /// otherwise its [`mix_bus`](Self::mix_bus).
+    pub fn out_bus(&self, group_bus: i32) -> i32 {
+        self.insert_bus.unwrap_or_else(|| self.mix_bus(group_bus))
+    }
+
+    /// Bus the voice's signal leaves to, after its inserts: the output
+    /// override, if set, otherwise its group's bus.
+    pub fn mix_bus(&self, group_bus: i32) -> i32 {
+        self.output_bus.map_or(group_bus, |bus| bus as i32)
+    }
+
+    /// Sounding nodes of this voice: held notes and the running synth.
+    pub fn sounding_nodes(&self) -> Vec<i32> {
+        let mut nodes: Vec<i32> = self.active_notes.values().flatten().copied().filter(|&id| id >= 0).collect();
+        nodes.extend(self.running_node_id);
+        nodes
+    }
+}
+
+/// What started a sounding note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NoteSource {
+    /// A pattern event.
+    Pattern(String),
+    /// A melody event.
+    Melody(String),
+    /// Incoming MIDI.
+    Midi,
+    /// A direct note-on from a script, the HTTP API or the virtual keyboard.
+    Direct,
+}
+
+impl NoteSource {
+    /// Source of a scheduled event.
+    pub fn of_event(event: &BeatEvent) -> Self {
+        if let Some(name) = &event.melody_name {
+            NoteSource::Melody(name.clone())
+        } else if let Some(name) = &event.pattern_name {
+            NoteSource::Pattern(name.clone())
+        } else {
+            NoteSource::Direct
+        }
+    }
+
+    /// Kind of source: "pattern", "melody", "midi" or "direct".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NoteSource::Pattern(_) => "pattern",
+            NoteSource::Melody(_) => "melody",
+            NoteSource::Midi => "midi",
+            NoteSource::Direct => "direct",
+        }
+    }
+
+    /// Name of the pattern or melody, if any.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            NoteSource::Pattern(name) | NoteSource::Melody(name) => Some(name),
+            NoteSource::Midi | NoteSource::Direct => None,
+        }
+    }
+}
+
+/// A note counts as stuck once it sounds this many times its expected length...
+pub const STUCK_NOTE_FACTOR: u32 = 4;
+
+/// ...and at least this much longer than expected.
+pub const STUCK_NOTE_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// When and from where a pitch started sounding.
+#[derive(Clone, Debug)]
+pub struct NoteOrigin {
+    /// When the note starts (may lie slightly ahead for scheduled events).
+    pub started: Instant,
+    /// What started the note.
+    pub source: NoteSource,
+    /// How long the note should sound, when it was started with a length.
+    pub length: Option<std::time::Duration>,
+    /// Whether the stuck-note detector already reported it.
+    pub flagged: bool,
+}
+
+impl NoteOrigin {
+    /// Origin of a note starting at `started`.
+    pub fn new(started: Instant, source: NoteSource, length: Option<std::time::Duration>) -> Self {
+        Self {
+            started,
+            source,
+            length,
+            flagged: false,
+        }
+    }
+
+    /// Whether the note sounds far longer than its expected length.
+    ///
+    /// Notes without a length (held MIDI keys, drones) are never stuck.
+    pub fn is_stuck(&self, now: Instant) -> bool {
+        let Some(length) = self.length else {
+            return false;
+        };
+        let age = now.saturating_duration_since(self.started);
+        age > length * STUCK_NOTE_FACTOR && age > length + STUCK_NOTE_GRACE
+    }
+}
+
+/// A pitch sounding on a voice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundingNote {
+    /// MIDI note number.
+    pub note: u8,
+    /// Notes of this pitch sounding at once.
+    pub count: usize,
+    /// Time since the oldest of them started.
+    pub age: std::time::Duration,
+    /// What started the oldest of them, when known.
+    pub source: Option<NoteSource>,
+    /// Whether it sounds far longer than expected.
+    pub stuck: bool,
 }
 
 /// Round-robin state for SFZ sample selection.
@@ -1020,6 +2244,31 @@ pub struct PatternState {
     pub source_location: SourceLocation,
     /// Original step pattern string (e.g., "x..x..x.|x.x.x.x.") for visual editing.
     pub step_pattern: Option<String>,
+    /// Shareable text of the pattern as defined, when built from steps.
+    pub text: Option<LoopText>,
+    /// Meter conditions that must all hold for an event to fire.
+    pub conditions: Vec<MeterCondition>,
+    /// External MIDI gear this pattern sequences instead of a voice.
+    pub midi_target: Option<PatternMidiTarget>,
+    /// Weights of the takes, if the pattern has variations.
+    pub variations: Option<PatternVariations>,
+    /// Take held by the performer instead of picking one per pass.
+    pub locked_variation: Option<usize>,
+    /// Random timing and velocity variation of the events (`.humanize()`).
+    pub humanize: Option<Humanize>,
+}
+
+/// MIDI output of a pattern that sequences external gear.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternMidiTarget {
+    /// MIDI output device ID.
+    pub device_id: u32,
+    /// MIDI channel (0-15).
+    pub channel: u8,
+    /// Note sent for every hit.
+    pub note: u8,
+    /// Time from note-on to note-off, in beats.
+    pub note_length: f64,
 }
 
 impl PatternState {
@@ -1036,6 +2285,12 @@ impl PatternState {
             generation: 0,
             source_location: SourceLocation::default(),
             step_pattern: None,
+            text: None,
+            conditions: Vec::new(),
+            midi_target: None,
+            variations: None,
+            locked_variation: None,
+            humanize: None,
         }
     }
 
@@ -1070,6 +2325,14 @@ pub struct MelodyState {
     /// Original notes pattern strings for visual editing (one per lane).
     /// Multiple lanes support polyphonic melodies.
     pub notes_patterns: Vec<String>,
+    /// Meter conditions that must all hold for an event to fire.
+    pub conditions: Vec<MeterCondition>,
+    /// What recorded takes snap their notes to (`.quantize_to_scale()`).
+    pub scale_snap: Option<ScaleSnap>,
+    /// Arpeggiator the melody plays its held notes with (`.arp()`).
+    pub arp: Option<Arpeggiator>,
+    /// Random timing and velocity variation of the events (`.humanize()`).
+    pub humanize: Option<Humanize>,
 }
 
 impl MelodyState {
@@ -1086,9 +2349,22 @@ impl MelodyState {
             generation: 0,
             source_location: SourceLocation::default(),
             notes_patterns: Vec::new(),
+            conditions: Vec::new(),
+            scale_snap: None,
+            arp: None,
+            humanize: None,
         }
     }
 
+    /// The pattern the melody plays: its notes, arpeggiated if it has an arpeggiator.
+    pub fn played_pattern(&self) -> Option<Cow<'_, Pattern>> {
+        let pattern = self.loop_pattern.as_ref()?;
+        Some(match &self.arp {
+            Some(arp) => Cow::Owned(arp.expand(pattern)),
+            None => Cow::Borrowed(pattern),
+        })
+    }
+
     /// Create a new melody state with source location.
     pub fn with_source_location(mut self, source_location: SourceLocation) -> Self {
         self.source_location = source_location;
@@ -1097,7 +2373,7 @@ impl MelodyState {
 }
 
 /// Metadata for an active synth node.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ActiveSynth {
     /// SuperCollider node ID.
     pub node_id: i32,
@@ -1109,6 +2385,70 @@ pub struct ActiveSynth {
     pub pattern_names: Vec<String>,
     /// Melody names this synth is triggered by.
     pub melody_names: Vec<String>,
+    /// SynthDef the synth plays.
+    pub synth_def: String,
+    /// Beat the synth started at.
+    pub start_beat: Option<f64>,
+    /// Index of the pattern step or melody note that started it.
+    pub step: Option<usize>,
+    /// Sequence clip that played it.
+    pub clip: Option<EventClip>,
+    /// Where the pattern or melody that started it is defined.
+    pub source_location: SourceLocation,
+}
+
+impl ActiveSynth {
+    /// Metadata of synth `node_id` playing `synth_def`, started by `event`
+    /// at `start_beat`.
+    pub fn for_event(state: &ScriptState, node_id: i32, event: &BeatEvent, synth_def: &str, start_beat: f64) -> Self {
+        let source_location = match (&event.melody_name, &event.pattern_name) {
+            (Some(melody), _) => state.melodies.get(melody).map(|m| m.source_location.clone()),
+            (None, Some(pattern)) => state.patterns.get(pattern).map(|p| p.source_location.clone()),
+            (None, None) => None,
+        };
+        Self {
+            node_id,
+            group_paths: event.group_path.iter().cloned().collect(),
+            voice_names: event.voice_name.iter().cloned().collect(),
+            pattern_names: event.pattern_name.iter().cloned().collect(),
+            melody_names: event.melody_name.iter().cloned().collect(),
+            synth_def: synth_def.to_string(),
+            start_beat: Some(start_beat),
+            step: event.step,
+            clip: event.clip.clone(),
+            source_location: source_location.unwrap_or_default(),
+        }
+    }
+
+    /// Where the synth came from, for people:
+    /// "pattern bass step 5 · verse clip 2 · song.vibe:12". Steps and clips
+    /// count from 1.
+    pub fn provenance(&self) -> String {
+        let mut parts = Vec::new();
+        let origin = match (self.melody_names.first(), self.pattern_names.first()) {
+            (Some(melody), _) => Some(("melody", melody, "note")),
+            (None, Some(pattern)) => Some(("pattern", pattern, "step")),
+            (None, None) => None,
+        };
+        match origin {
+            Some((kind, name, unit)) => match self.step {
+                Some(step) => parts.push(format!("{} {} {} {}", kind, name, unit, step + 1)),
+                None => parts.push(format!("{} {}", kind, name)),
+            },
+            None => parts.push(match self.voice_names.first() {
+                Some(voice) => format!("voice {}", voice),
+                None => "direct".to_string(),
+            }),
+        }
+        if let Some(clip) = &self.clip {
+            parts.push(clip.to_string());
+        }
+        if let (Some(file), Some(line)) = (&self.source_location.file, self.source_location.line) {
+            let file = std::path::Path::new(file).file_name().map_or(file.clone(), |name| name.to_string_lossy().to_string());
+            parts.push(format!("{}:{}", file, line));
+        }
+        parts.join(" · ")
+    }
 }
 
 /// A one-shot scheduled event.
@@ -1131,6 +2471,9 @@ pub struct ScheduledNoteOff {
     pub note: u8,
     /// Specific node ID to release (None = all).
     pub node_id: Option<i32>,
+    /// Whether gate=0 was already sent to scsynth as a timed bundle,
+    /// leaving only the note tracking to clean up at `beat`.
+    pub gate_scheduled: bool,
 }
 
 /// Log entry for a sequence run.
@@ -1159,6 +2502,11 @@ pub struct ActiveSequence {
     pub last_iteration: u64,
     /// Whether this sequence has completed (for play_once mode).
     pub completed: bool,
+    /// First beat events may fire; later than the anchor for a legato
+    /// launch that joins mid-loop.
+    pub launch_beat: f64,
+    /// Beat the sequence stops at when another one took over from it.
+    pub stop_beat: Option<f64>,
 }
 
 /// An active parameter fade job.
@@ -1184,6 +2532,14 @@ pub struct ActiveFadeJob {
     pub completed: bool,
     /// Last value sent (for deduplication).
     pub last_value: Option<f32>,
+    /// Whether the fade was handed to scsynth as a single `VarLag` ramp.
+    pub server_ramp: bool,
+    /// Interpolation between start and target value.
+    pub curve: FadeCurve,
+    /// Fade definition (or ctrl) that started the fade.
+    pub name: Option<String>,
+    /// Where that fade is defined.
+    pub source_location: SourceLocation,
 }
 
 /// Information about a loaded sample.
@@ -1205,6 +2561,10 @@ pub struct SampleInfo {
     pub synthdef_name: String,
     /// Sample slices.
     pub slices: Vec<SampleSlice>,
+    /// Approximate key detected on load (None for unpitched material).
+    pub detected_key: Option<MusicalKey>,
+    /// Time from the start of the file to the first onset, in milliseconds.
+    pub onset_ms: f64,
 }
 
 /// A sample slice.
@@ -1243,6 +2603,8 @@ pub struct EffectState {
     pub position: usize,
     /// VST plugin key if using VST.
     pub vst_plugin: Option<String>,
+    /// Voice the effect is inserted on; `None` for effects of the group.
+    pub voice: Option<String>,
     /// Source location where this effect was defined.
     pub source_location: SourceLocation,
 }
@@ -1255,8 +2617,12 @@ pub struct VstInstrumentInfo {
     pub id: String,
     /// Plugin name/path.
     pub plugin_key: String,
+    /// Group the instrument plays into.
+    pub group_path: String,
     /// Synth node ID when active.
     pub node_id: Option<i32>,
+    /// Plugin parameters set by name (or by index, as a number).
+    pub params: HashMap<String, f32>,
 }
 
 // Re-export the full SFZ instrument type from vibelang-sfz
@@ -1311,6 +2677,11 @@ impl VoiceState {
         self.sfz_instrument.hash(&mut hasher);
         self.vst_instrument.hash(&mut hasher);
         self.running.hash(&mut hasher);
+        self.priority.hash(&mut hasher);
+        for pad in self.drum_kit.iter().flat_map(|kit| &kit.pads) {
+            (&pad.name, pad.note, &pad.sample_id, pad.choke).hash(&mut hasher);
+            [pad.rate, pad.gain, pad.pitch].map(f32::to_bits).hash(&mut hasher);
+        }
         hasher.finish()
     }
 }
@@ -1336,7 +2707,14 @@ impl PatternState {
                     k.hash(&mut hasher);
                     v.to_bits().hash(&mut hasher);
                 }
+                event.variation.map(|v| v.index).hash(&mut hasher);
+            }
+        }
+        if let Some(ref variations) = self.variations {
+            for weight in &variations.weights {
+                weight.to_bits().hash(&mut hasher);
             }
+            variations.seed.hash(&mut hasher);
         }
         hash_params(&self.params, &mut hasher);
         self.is_looping.hash(&mut hasher);
@@ -1384,6 +2762,7 @@ impl EffectState {
         hash_params(&self.params, &mut hasher);
         self.position.hash(&mut hasher);
         self.vst_plugin.hash(&mut hasher);
+        self.voice.hash(&mut hasher);
         hasher.finish()
     }
 }
@@ -1401,6 +2780,54 @@ mod tests {
         assert_eq!(state.version, 0);
     }
 
+    #[test]
+    fn test_checkpoint_steps() {
+        let mut checkpoints = CheckpointState {
+            names: vec!["intro".to_string(), "groove".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(checkpoints.position(), 2);
+        assert_eq!(checkpoints.step(1), None);
+        assert_eq!(checkpoints.step(-1), Some(Some("groove".to_string())));
+
+        checkpoints.selected = Some("intro".to_string());
+        assert_eq!(checkpoints.step(-1), None);
+        assert_eq!(checkpoints.step(2), Some(None));
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_midi_take_overdub_and_replace() {
+        let note = |beat: f64, voice: &str| RecordedMidiNote {
+            beat,
+            note: 60,
+            velocity: 127,
+            duration: 0.25,
+            raw_beat: beat,
+            channel: 1,
+            voice_name: voice.to_string(),
+        };
+        let mut recording = MidiRecordingState::new();
+        recording.arm_take("kick".to_string(), TakeTargetKind::Pattern, Some("kick".to_string()), TakeMode::Overdub, 4.0);
+        recording.capture_take_note(&note(9.0, "kick"));
+        recording.capture_take_note(&note(10.0, "snare"));
+        let id = recording.finish_take().unwrap();
+        assert_eq!(recording.finish_take(), None);
+
+        let take = recording.take(id).unwrap().clone();
+        assert_eq!(take.notes.len(), 1);
+        assert!((take.notes[0].beat - 1.0).abs() < 1e-9);
+
+        let loop_pattern = Pattern::new("kick", 4.0).with_event(BeatEvent::new(0.0, "kick_synth"));
+        let layered = take.apply_to(&loop_pattern);
+        assert_eq!(layered.events.len(), 2);
+        assert_eq!(layered.events[1].synth_def, "kick_synth");
+
+        let replaced = MidiTake { mode: TakeMode::Replace, ..take }.apply_to(&loop_pattern);
+        assert_eq!(replaced.events.len(), 1);
+        assert!((replaced.events[0].beat - 1.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_allocate_ids() {
         let mut state = ScriptState::new();
@@ -1433,6 +2860,102 @@ mod tests {
         assert!(!group.muted);
     }
 
+    #[test]
+    fn test_live_set_cue_stepping() {
+        let set = LiveSet::parse(
+            "composition = \"song.vibe\"\n[scene a]\n[scene b]\n[cues]\na\nb\n",
+        )
+        .unwrap();
+        let mut live = LiveSetState::new(PathBuf::from("show.vibeset"), set);
+        assert_eq!(live.next_cue(), Some("a"));
+        assert_eq!(live.step_cue(-1), None);
+        assert_eq!(live.step_cue(1), Some(0));
+
+        live.cue_index = Some(0);
+        assert_eq!(live.next_cue(), Some("b"));
+        assert_eq!(live.step_cue(1), Some(1));
+        assert_eq!(live.step_cue(-1), None);
+
+        live.cue_index = Some(1);
+        assert_eq!(live.next_cue(), None);
+        assert_eq!(live.step_cue(1), None);
+        assert_eq!(live.step_cue(-1), Some(0));
+    }
+
+    #[test]
+    fn test_frozen_from_beat() {
+        let mut state = ScriptState::new();
+        let mut drums = GroupState::new(
+            "drums".to_string(),
+            "main/drums".to_string(),
+            Some("main".to_string()),
+            16,
+        );
+        drums.freeze = Some(GroupFreeze {
+            bars: 8,
+            buffer_id: 100,
+            start_beat: 4.0,
+            end_beat: 36.0,
+            duration_seconds: 16.0,
+            recorder_node_id: 2000,
+            player_node_id: None,
+            paused_node_ids: Vec::new(),
+        });
+        let kick = GroupState::new(
+            "kick".to_string(),
+            "main/drums/kick".to_string(),
+            Some("main/drums".to_string()),
+            17,
+        );
+        state.groups.insert(drums.path.clone(), drums);
+        state.groups.insert(kick.path.clone(), kick);
+
+        assert_eq!(state.frozen_from_beat("main/drums"), Some(36.0));
+        assert_eq!(state.frozen_from_beat("main/drums/kick"), Some(36.0));
+        assert_eq!(state.frozen_from_beat("main"), None);
+    }
+
+    #[test]
+    fn test_legato_predecessor() {
+        use crate::sequences::{ClipMode, ClipSource, SequenceClip};
+
+        let mut state = ScriptState::new();
+        for (pattern, voice) in [("beat_a", "drums"), ("beat_b", "drums"), ("pad", "keys")] {
+            state.patterns.insert(
+                pattern.to_string(),
+                PatternState::new(pattern.to_string(), "main".to_string(), Some(voice.to_string())),
+            );
+        }
+        for (seq, pattern) in [("a", "beat_a"), ("b", "beat_b"), ("pads", "pad")] {
+            let def = SequenceDefinition::new(seq)
+                .with_loop_beats(8.0)
+                .with_clip(SequenceClip::new(0.0, 8.0, ClipSource::Pattern(pattern.to_string()), ClipMode::Loop));
+            state.sequences.insert(seq.to_string(), def);
+        }
+        let wrapper = SequenceDefinition::new("section")
+            .with_clip(SequenceClip::new(0.0, 16.0, ClipSource::Sequence("b".to_string()), ClipMode::Loop));
+        state.sequences.insert("section".to_string(), wrapper);
+        assert_eq!(state.sequence_voices("section"), HashSet::from(["drums".to_string()]));
+
+        let active = |anchor_beat: f64| ActiveSequence {
+            anchor_beat,
+            paused: false,
+            triggered_clips: HashMap::new(),
+            last_iteration: 0,
+            completed: false,
+            launch_beat: anchor_beat,
+            stop_beat: None,
+        };
+        assert_eq!(state.legato_predecessor("b"), None);
+        state.active_sequences.insert("pads".to_string(), active(0.0));
+        assert_eq!(state.legato_predecessor("b"), None);
+        state.active_sequences.insert("a".to_string(), active(4.0));
+        assert_eq!(state.legato_predecessor("section"), Some(("a".to_string(), 4.0, 8.0)));
+
+        state.active_sequences.get_mut("a").unwrap().stop_beat = Some(12.0);
+        assert_eq!(state.legato_predecessor("b"), None);
+    }
+
     #[test]
     fn test_voice_state() {
         let voice = VoiceState::new("kick".to_string(), "main.drums".to_string());
@@ -1440,4 +2963,104 @@ mod tests {
         assert_eq!(voice.polyphony, 1);
         assert!((voice.gain - 1.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_voice_output_override() {
+        let mut voice = VoiceState::new("bass".to_string(), "main".to_string());
+        voice.track_note(36, 1001, NoteOrigin::new(Instant::now(), NoteSource::Direct, None));
+        voice.track_note(38, -1, NoteOrigin::new(Instant::now(), NoteSource::Midi, None));
+        voice.running_node_id = Some(1002);
+        assert_eq!(voice.sounding_nodes(), vec![1001, 1002]);
+
+        assert_eq!(voice.out_bus(16), 16);
+        voice.output_bus = Some(4);
+        assert_eq!(voice.out_bus(16), 4);
+    }
+
+    #[test]
+    fn test_sounding_notes() {
+        let mut voice = VoiceState::new("lead".to_string(), "main".to_string());
+        let start = Instant::now();
+        let later = start + std::time::Duration::from_millis(500);
+        let origin = |started, source| NoteOrigin::new(started, source, None);
+        voice.track_note(64, 1001, origin(start, NoteSource::Pattern("arp".to_string())));
+        voice.track_note(60, -1, origin(later, NoteSource::Midi));
+        voice.track_note(64, 1002, origin(later, NoteSource::Direct));
+        assert_eq!(voice.active_note_count(), 3);
+
+        let notes = voice.sounding_notes(later);
+        assert_eq!(notes.iter().map(|n| (n.note, n.count)).collect::<Vec<_>>(), vec![(60, 1), (64, 2)]);
+        assert_eq!(notes[1].age, std::time::Duration::from_millis(500));
+        assert_eq!(notes[1].source.as_ref().and_then(|s| s.name()), Some("arp"));
+        assert_eq!(notes[0].source.as_ref().map(|s| s.kind()), Some("midi"));
+
+        // A pitch that stopped sounding and starts again gets a new origin
+        voice.active_notes.remove(&60);
+        assert_eq!(voice.sounding_notes(later).len(), 1);
+        voice.track_note(60, 1003, origin(later, NoteSource::Melody("bass".to_string())));
+        assert_eq!(voice.sounding_notes(later)[0].source, Some(NoteSource::Melody("bass".to_string())));
+    }
+
+    #[test]
+    fn test_stuck_note_detection() {
+        use std::time::Duration;
+        let mut voice = VoiceState::new("pad".to_string(), "main".to_string());
+        let start = Instant::now();
+        let length = Some(Duration::from_millis(500));
+        voice.track_note(60, 1001, NoteOrigin::new(start, NoteSource::Pattern("chords".to_string()), length));
+        voice.track_note(62, -1, NoteOrigin::new(start, NoteSource::Midi, None));
+
+        // Four times the length is not enough while within the grace time
+        assert!(voice.flag_stuck_notes(start + Duration::from_secs(2)).is_empty());
+        let late = start + Duration::from_secs(3);
+        assert_eq!(voice.flag_stuck_notes(late), vec![60]);
+        assert!(voice.flag_stuck_notes(late).is_empty(), "reported once");
+        assert!(voice.sounding_notes(late)[0].stuck);
+        assert!(!voice.sounding_notes(late)[1].stuck, "held notes have no length");
+
+        voice.clear_notes();
+        assert!(voice.sounding_notes(late).is_empty());
+    }
+
+    #[test]
+    fn test_apply_key_match() {
+        let mut state = ScriptState::new();
+        state.samples.insert(
+            "keys".to_string(),
+            SampleInfo {
+                id: "keys".to_string(),
+                path: "keys.wav".to_string(),
+                buffer_id: 1,
+                num_channels: 2,
+                num_frames: 48_000,
+                sample_rate: 48_000.0,
+                synthdef_name: "__sample_keys".to_string(),
+                slices: Vec::new(),
+                detected_key: MusicalKey::parse("D major"),
+                onset_ms: 0.0,
+            },
+        );
+        let mut voice = VoiceState::new("keys".to_string(), "main".to_string());
+        voice.key_match = Some("keys".to_string());
+        voice.params.insert("rate".to_string(), 1.0);
+        state.voices.insert("keys".to_string(), voice);
+
+        // No session key: untransposed
+        state.apply_key_match("keys");
+        assert_eq!(state.voices["keys"].key_transpose, 0);
+
+        // D major → C major is two semitones down
+        state.session_key = MusicalKey::parse("C");
+        state.apply_key_match("keys");
+        let voice = &state.voices["keys"];
+        assert_eq!(voice.key_transpose, -2);
+        assert!((voice.params["rate"] - 2.0f32.powf(-2.0 / 12.0)).abs() < 1e-5);
+
+        // Moving to E minor (relative of G major) re-transposes from the base rate
+        state.session_key = MusicalKey::parse("Em");
+        state.apply_key_match("keys");
+        let voice = &state.voices["keys"];
+        assert_eq!(voice.key_transpose, 5);
+        assert!((voice.params["rate"] - 2.0f32.powf(5.0 / 12.0)).abs() < 1e-5);
+    }
 }
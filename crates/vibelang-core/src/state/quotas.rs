@@ -0,0 +1,288 @@
+//! Per-session quotas on entities and sample memory.
+//!
+//! Shared and teaching servers cap what one session may create, so that a
+//! runaway generative script cannot create thousands of voices or fill the
+//! server's memory with sample buffers. The [`StateManager`](super::StateManager)
+//! checks every message that creates an entity against the [`Quotas`]
+//! before it reaches the runtime thread, counting entities that are still
+//! on their way there.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::messages::StateMessage;
+use super::model::ScriptState;
+use crate::sequences::IMPLICIT_SEQUENCE_PREFIX;
+
+/// Limits on what a session may create (`None` = unlimited).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Quotas {
+    /// Maximum number of voices.
+    pub max_voices: Option<usize>,
+    /// Maximum number of patterns and melodies together.
+    pub max_patterns: Option<usize>,
+    /// Maximum number of sequences.
+    pub max_sequences: Option<usize>,
+    /// Maximum number of loaded samples.
+    pub max_samples: Option<usize>,
+    /// Maximum memory of loaded sample buffers, in MB.
+    ///
+    /// Checked before a sample loads, so the sample that crosses the limit
+    /// still loads; later ones are refused.
+    pub max_buffer_mb: Option<f64>,
+}
+
+impl Quotas {
+    /// Whether no quota is set.
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check a new entity of `kind` named `name` against the quotas.
+    ///
+    /// Existing entities can always be updated. `pending` are the names of
+    /// entities of that kind admitted but not yet created by the runtime.
+    pub fn check(
+        &self,
+        state: &ScriptState,
+        kind: QuotaKind,
+        name: &str,
+        pending: &HashSet<String>,
+    ) -> Result<(), QuotaExceeded> {
+        if kind.exists(state, name) || pending.contains(name) {
+            return Ok(());
+        }
+
+        let limit = match kind {
+            QuotaKind::Voices => self.max_voices,
+            QuotaKind::Patterns => self.max_patterns,
+            QuotaKind::Sequences => self.max_sequences,
+            QuotaKind::Samples => self.max_samples,
+            QuotaKind::BufferMemory => None,
+        };
+        if let Some(limit) = limit {
+            if kind.count(state) + pending.len() >= limit {
+                return Err(QuotaExceeded {
+                    kind,
+                    message: format!(
+                        "{} '{}' exceeds the quota of {} {}",
+                        kind.entity(),
+                        name,
+                        limit,
+                        kind.as_str()
+                    ),
+                });
+            }
+        }
+
+        if kind == QuotaKind::Samples {
+            if let Some(limit) = self.max_buffer_mb {
+                let used = buffer_mb(state);
+                if used >= limit {
+                    return Err(QuotaExceeded {
+                        kind: QuotaKind::BufferMemory,
+                        message: format!(
+                            "sample '{}' exceeds the buffer memory quota: {:.1} of {} MB in use",
+                            name, used, limit
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Kind of entity a quota limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QuotaKind {
+    /// Voices.
+    Voices,
+    /// Patterns and melodies.
+    Patterns,
+    /// Sequences.
+    Sequences,
+    /// Loaded samples.
+    Samples,
+    /// Memory of sample buffers.
+    BufferMemory,
+}
+
+impl QuotaKind {
+    /// Stable identifier, as used by `set_quotas()`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuotaKind::Voices => "voices",
+            QuotaKind::Patterns => "patterns",
+            QuotaKind::Sequences => "sequences",
+            QuotaKind::Samples => "samples",
+            QuotaKind::BufferMemory => "buffer_mb",
+        }
+    }
+
+    /// Kind and name of the entity a message creates or updates, if any.
+    pub fn of_message(msg: &StateMessage) -> Option<(Self, &str)> {
+        match msg {
+            StateMessage::UpsertVoice { name, .. } => Some((QuotaKind::Voices, name)),
+            StateMessage::CreatePattern { name, .. } | StateMessage::CreateMelody { name, .. } => {
+                Some((QuotaKind::Patterns, name))
+            }
+            StateMessage::CreateSequence { sequence } if !sequence.name.starts_with(IMPLICIT_SEQUENCE_PREFIX) => {
+                Some((QuotaKind::Sequences, &sequence.name))
+            }
+            StateMessage::LoadSample { id, .. } => Some((QuotaKind::Samples, id)),
+            _ => None,
+        }
+    }
+
+    fn entity(self) -> &'static str {
+        match self {
+            QuotaKind::Voices => "voice",
+            QuotaKind::Patterns => "pattern",
+            QuotaKind::Sequences => "sequence",
+            QuotaKind::Samples | QuotaKind::BufferMemory => "sample",
+        }
+    }
+
+    fn exists(self, state: &ScriptState, name: &str) -> bool {
+        match self {
+            QuotaKind::Voices => state.voices.contains_key(name),
+            QuotaKind::Patterns => state.patterns.contains_key(name) || state.melodies.contains_key(name),
+            QuotaKind::Sequences => state.sequences.contains_key(name),
+            QuotaKind::Samples | QuotaKind::BufferMemory => state.samples.contains_key(name),
+        }
+    }
+
+    fn count(self, state: &ScriptState) -> usize {
+        match self {
+            QuotaKind::Voices => state.voices.len(),
+            QuotaKind::Patterns => state.patterns.len() + state.melodies.len(),
+            QuotaKind::Sequences => state
+                .sequences
+                .keys()
+                .filter(|name| !name.starts_with(IMPLICIT_SEQUENCE_PREFIX))
+                .count(),
+            QuotaKind::Samples | QuotaKind::BufferMemory => state.samples.len(),
+        }
+    }
+}
+
+/// Memory of the loaded sample buffers in MB (scsynth stores 32-bit floats).
+///
+/// Buffers freed by the garbage collector don't count.
+pub fn buffer_mb(state: &ScriptState) -> f64 {
+    let bytes: f64 = state
+        .samples
+        .values()
+        .filter(|s| !state.gc.evicted.contains(&s.id))
+        .map(|s| s.num_frames.max(0) as f64 * s.num_channels.max(0) as f64 * 4.0)
+        .sum();
+    bytes / (1024.0 * 1024.0)
+}
+
+/// A message refused because it would exceed a quota.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuotaExceeded {
+    /// Quota that was hit.
+    pub kind: QuotaKind,
+    /// Human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "quota exceeded: {}", self.message)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// Quotas and entities admitted by the state manager that the runtime
+/// thread has not processed yet.
+#[derive(Debug, Default)]
+pub(super) struct PendingEntities {
+    /// Quotas set by a message still on its way.
+    pub quotas: Option<Quotas>,
+    /// Names of admitted entities by kind.
+    pub names: HashMap<QuotaKind, HashSet<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::context::SourceLocation;
+    use crate::state::StateManager;
+
+    fn upsert_voice(name: &str) -> StateMessage {
+        StateMessage::UpsertVoice {
+            name: name.to_string(),
+            group_path: "main".to_string(),
+            group_name: None,
+            synth_name: None,
+            polyphony: 1,
+            gain: 1.0,
+            muted: false,
+            soloed: false,
+            output_bus: None,
+            params: HashMap::new(),
+            sfz_instrument: None,
+            vst_instrument: None,
+            source_location: SourceLocation::default(),
+            midi_output_device_id: None,
+            midi_channel: None,
+            cc_mappings: HashMap::new(),
+            priority: 0,
+            key_match: None,
+            pre_roll_ms: 0.0,
+            feel: None,
+            mono: None,
+            drum_kit: None,
+        }
+    }
+
+    #[test]
+    fn test_quotas_count_pending_entities() {
+        let manager = StateManager::new();
+        let quotas = Quotas {
+            max_voices: Some(2),
+            ..Quotas::default()
+        };
+        manager.admit(&StateMessage::SetQuotas { quotas }).unwrap();
+
+        // Admitted voices count before the runtime creates them
+        manager.admit(&upsert_voice("a")).unwrap();
+        manager.admit(&upsert_voice("b")).unwrap();
+        manager.admit(&upsert_voice("a")).unwrap();
+        let err = manager.admit(&upsert_voice("c")).unwrap_err();
+        assert_eq!(err.kind, QuotaKind::Voices);
+        assert_eq!(err.to_string(), "quota exceeded: voice 'c' exceeds the quota of 2 voices");
+
+        // Once processed they are counted from the state instead
+        manager.settle(QuotaKind::Voices, "a");
+        manager.with_state_write(|state| {
+            state.voices.insert("a".to_string(), crate::state::VoiceState::new("a".to_string(), "main".to_string()));
+        });
+        assert!(manager.admit(&upsert_voice("c")).is_err());
+        manager.settle(QuotaKind::Voices, "b");
+        assert!(manager.admit(&upsert_voice("c")).is_ok());
+
+        // Implicit pattern sequences are free; samples are checked for memory
+        let state = ScriptState::new();
+        let none = HashSet::new();
+        let sequences = Quotas {
+            max_sequences: Some(0),
+            max_buffer_mb: Some(0.0),
+            ..Quotas::default()
+        };
+        let seq = |name: &str| StateMessage::CreateSequence {
+            sequence: crate::sequences::SequenceDefinition::new(name.to_string()),
+        };
+        assert!(QuotaKind::of_message(&seq("_seq_kick")).is_none());
+        let song = seq("song");
+        let (kind, name) = QuotaKind::of_message(&song).unwrap();
+        assert!(sequences.check(&state, kind, name, &none).is_err());
+        let err = sequences.check(&state, QuotaKind::Samples, "kick", &none).unwrap_err();
+        assert_eq!(err.kind, QuotaKind::BufferMemory);
+        assert!(Quotas::default().is_unlimited());
+    }
+}
@@ -0,0 +1,153 @@
+//! Directories of precompiled `.scsyndef` files.
+//!
+//! `synthdef_dir("./synthdefs")` loads every synthdef of a directory with
+//! `/d_loadDir` and remembers their names, so voices can use them like
+//! synthdefs defined in VibeLang. In watch mode [`SynthDefDirWatcher`]
+//! reloads a directory when a file in it changes, so a synthdef can be
+//! reworked in the SuperCollider IDE (`.writeDefFile`) while the song plays.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::runtime::RuntimeHandle;
+use crate::score::extract_synthdef_name;
+use crate::state::StateMessage;
+
+/// Modification time and size of a file, compared between polls.
+type FileStamp = (Option<SystemTime>, u64);
+
+/// Read every `.scsyndef` file of a directory, sorted by synthdef name.
+pub fn read_synthdef_dir(dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut synthdefs = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if !is_synthdef_file(&path) {
+            continue;
+        }
+        let bytes = fs::read(&path)?;
+        match extract_synthdef_name(&bytes) {
+            Some(name) => synthdefs.push((name, bytes)),
+            None => log::warn!("[SYNTHDEF] Not a synthdef file: {}", path.display()),
+        }
+    }
+    synthdefs.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(synthdefs)
+}
+
+fn is_synthdef_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "scsyndef")
+}
+
+/// Stamps of the `.scsyndef` files of a directory (empty if it is unreadable).
+fn file_stamps(dir: &Path) -> BTreeMap<PathBuf, FileStamp> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_synthdef_file(path))
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            Some((path, (meta.modified().ok(), meta.len())))
+        })
+        .collect()
+}
+
+/// Files added, changed or removed between two polls.
+fn changed_files(before: &BTreeMap<PathBuf, FileStamp>, after: &BTreeMap<PathBuf, FileStamp>) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|(path, stamp)| before.get(*path) != Some(stamp))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(before.keys().filter(|path| !after.contains_key(*path)).cloned());
+    changed.sort();
+    changed
+}
+
+/// Polls the synthdef directories loaded by the script for changed files.
+#[derive(Default)]
+pub struct SynthDefDirWatcher {
+    stamps: HashMap<PathBuf, BTreeMap<PathBuf, FileStamp>>,
+}
+
+impl SynthDefDirWatcher {
+    /// Reload every directory whose files changed since the last poll.
+    ///
+    /// A directory seen for the first time was just loaded by the script
+    /// and is only remembered.
+    pub fn poll(&mut self, handle: &RuntimeHandle) {
+        let dirs: Vec<PathBuf> = handle.with_state(|state| state.synthdef_dirs.keys().cloned().collect());
+        self.stamps.retain(|dir, _| dirs.contains(dir));
+
+        for dir in dirs {
+            let stamps = file_stamps(&dir);
+            let Some(before) = self.stamps.insert(dir.clone(), stamps.clone()) else {
+                continue;
+            };
+            let changed = changed_files(&before, &stamps);
+            if changed.is_empty() {
+                continue;
+            }
+            for path in &changed {
+                let file = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                log::info!("🔄 Synthdef file changed: {}", file);
+            }
+            match read_synthdef_dir(&dir) {
+                Ok(synthdefs) => {
+                    let _ = handle.send(StateMessage::LoadSynthDefDir { path: dir, synthdefs });
+                }
+                Err(e) => log::error!("Failed to reload synthdefs: {:#}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal synthdef file header with one def called `name`.
+    fn synthdef_bytes(name: &str) -> Vec<u8> {
+        let mut bytes = b"SCgf".to_vec();
+        bytes.extend_from_slice(&2i32.to_be_bytes());
+        bytes.extend_from_slice(&1i16.to_be_bytes());
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_synthdef_dir_scan_and_changes() {
+        let dir = std::env::temp_dir().join(format!("vibelang-synthdef-dir-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("pad.scsyndef"), synthdef_bytes("pad")).unwrap();
+        fs::write(dir.join("bass.scsyndef"), synthdef_bytes("acid_bass")).unwrap();
+        fs::write(dir.join("notes.txt"), "not a synthdef").unwrap();
+
+        let names: Vec<String> = read_synthdef_dir(&dir).unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["acid_bass", "pad"]);
+
+        let before = file_stamps(&dir);
+        assert_eq!(before.len(), 2);
+        assert!(changed_files(&before, &file_stamps(&dir)).is_empty());
+
+        // Rewriting with a different size counts even within one mtime tick
+        fs::write(dir.join("pad.scsyndef"), synthdef_bytes("pad_v2")).unwrap();
+        fs::remove_file(dir.join("bass.scsyndef")).unwrap();
+        fs::write(dir.join("lead.scsyndef"), synthdef_bytes("lead")).unwrap();
+        let changed: Vec<String> = changed_files(&before, &file_stamps(&dir))
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(changed, vec!["bass.scsyndef", "lead.scsyndef", "pad.scsyndef"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(file_stamps(&dir).is_empty());
+        assert!(read_synthdef_dir(&dir).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Double-buffered synthdefs for live sound design.
+//!
+//! scsynth replaces a synthdef under nodes that are still playing it when
+//! a new definition arrives with the same name. To redefine a synth while
+//! it sounds, the runtime loads the new definition under a versioned name
+//! (`pad__v2`) instead, and rewrites every `/s_new` of `pad` to it: held
+//! notes and running effects keep the definition they started with, new
+//! events get the new one. Once the last node of a superseded version has
+//! ended, the version is freed with `/d_free`.
+//!
+//! A synthdef nothing is playing is simply reloaded under its current name.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use rosc::{OscMessage, OscPacket, OscType};
+
+/// Versions of the synthdefs redefined during the session and the nodes
+/// playing each definition.
+#[derive(Debug, Default)]
+pub struct SynthDefVersions {
+    /// Current version of each redefined synthdef; others are at version 0,
+    /// their plain name.
+    current: HashMap<String, u32>,
+    /// Definition (as sent to scsynth) of each live node.
+    nodes: HashMap<i32, String>,
+    /// Number of live nodes of each definition.
+    live: HashMap<String, usize>,
+    /// Superseded definitions waiting for their last node to end.
+    retired: HashSet<String>,
+}
+
+impl SynthDefVersions {
+    /// Name new nodes of `name` are created with.
+    pub fn resolve<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.current.get(name) {
+            Some(&version) => Cow::Owned(versioned_name(name, version)),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    /// Name and bytes to load a definition of `name` with.
+    ///
+    /// A `changed` definition whose current version has live nodes gets a
+    /// new version; otherwise it replaces the current one.
+    pub fn load(&mut self, name: &str, bytes: &[u8], changed: bool) -> (String, Vec<u8>) {
+        let resolved = self.resolve(name).into_owned();
+        if changed && self.live.contains_key(&resolved) {
+            let version = self.current.get(name).map_or(1, |v| v + 1);
+            let next = versioned_name(name, version);
+            if let Some(renamed) = rename_synthdef(bytes, &next) {
+                self.current.insert(name.to_string(), version);
+                self.retired.insert(resolved);
+                return (next, renamed);
+            }
+            log::warn!("[SYNTHDEF] Can't version '{}'; replacing it under sounding nodes", name);
+        }
+        match rename_synthdef(bytes, &resolved) {
+            Some(renamed) if resolved != name => (resolved, renamed),
+            _ => (name.to_string(), bytes.to_vec()),
+        }
+    }
+
+    /// Point the `/s_new` messages of a packet at the current versions and
+    /// remember which definition each new node plays.
+    pub fn rewrite(&mut self, packet: &mut OscPacket) {
+        match packet {
+            OscPacket::Message(msg) => self.rewrite_message(msg),
+            OscPacket::Bundle(bundle) => bundle.content.iter_mut().for_each(|p| self.rewrite(p)),
+        }
+    }
+
+    /// [`rewrite`](Self::rewrite) for a single message.
+    pub fn rewrite_message(&mut self, msg: &mut OscMessage) {
+        if msg.addr != "/s_new" {
+            return;
+        }
+        let Some(OscType::String(def)) = msg.args.first_mut() else {
+            return;
+        };
+        if let Cow::Owned(resolved) = self.resolve(def) {
+            *def = resolved;
+        }
+        let def = def.clone();
+        if let Some(&OscType::Int(node_id)) = msg.args.get(1) {
+            self.node_started(node_id, def);
+        }
+    }
+
+    /// Track a node created from `def` (as sent to scsynth).
+    pub fn node_started(&mut self, node_id: i32, def: String) {
+        // Nodes scsynth numbers itself can't be followed
+        if node_id < 0 {
+            return;
+        }
+        if let Some(previous) = self.nodes.insert(node_id, def.clone()) {
+            self.release(&previous);
+        }
+        *self.live.entry(def).or_default() += 1;
+    }
+
+    /// Forget an ended node; returns the superseded definition to free if
+    /// it was the last node playing it.
+    pub fn node_ended(&mut self, node_id: i32) -> Option<String> {
+        let def = self.nodes.remove(&node_id)?;
+        self.release(&def);
+        (!self.live.contains_key(&def) && self.retired.remove(&def)).then_some(def)
+    }
+
+    fn release(&mut self, def: &str) {
+        if let Some(count) = self.live.get_mut(def) {
+            *count -= 1;
+            if *count == 0 {
+                self.live.remove(def);
+            }
+        }
+    }
+}
+
+/// Name of version `version` of a synthdef.
+pub fn versioned_name(name: &str, version: u32) -> String {
+    format!("{}__v{}", name, version)
+}
+
+/// Compiled synthdef file (`SCgf`) holding one definition, renamed.
+///
+/// Returns `None` for files that aren't a single definition or names longer
+/// than the format's 255 bytes.
+pub fn rename_synthdef(bytes: &[u8], name: &str) -> Option<Vec<u8>> {
+    if bytes.len() < 11 || &bytes[0..4] != b"SCgf" || bytes[8..10] != [0, 1] || name.len() > 255 {
+        return None;
+    }
+    let old_len = bytes[10] as usize;
+    let rest = bytes.get(11 + old_len..)?;
+    let mut renamed = Vec::with_capacity(bytes.len() + name.len());
+    renamed.extend_from_slice(&bytes[..10]);
+    renamed.push(name.len() as u8);
+    renamed.extend_from_slice(name.as_bytes());
+    renamed.extend_from_slice(rest);
+    Some(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthdef(name: &str) -> Vec<u8> {
+        let mut bytes = b"SCgf\0\0\0\x02\0\x01".to_vec();
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 1]);
+        bytes
+    }
+
+    fn s_new(def: &str, node_id: i32) -> OscPacket {
+        OscPacket::Message(OscMessage {
+            addr: "/s_new".to_string(),
+            args: vec![OscType::String(def.to_string()), OscType::Int(node_id)],
+        })
+    }
+
+    fn def_of(packet: &OscPacket) -> String {
+        match packet {
+            OscPacket::Message(msg) => msg.args[0].clone().string().unwrap(),
+            OscPacket::Bundle(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_redefined_synthdefs_keep_sounding_nodes() {
+        let mut versions = SynthDefVersions::default();
+        let (name, _) = versions.load("pad", &synthdef("pad"), false);
+        assert_eq!(name, "pad");
+
+        let mut note = s_new("pad", 1000);
+        versions.rewrite(&mut note);
+        assert_eq!(def_of(&note), "pad");
+
+        // Redefined while node 1000 plays: the new definition gets a version
+        let (name, bytes) = versions.load("pad", &synthdef("pad"), true);
+        assert_eq!(name, "pad__v1");
+        assert_eq!(crate::score::extract_synthdef_name(&bytes).as_deref(), Some("pad__v1"));
+        assert_eq!(&bytes[bytes.len() - 4..], &[0, 0, 0, 1]);
+
+        let mut next = s_new("pad", 1001);
+        versions.rewrite(&mut next);
+        assert_eq!(def_of(&next), "pad__v1");
+
+        // The old definition goes with its last node
+        assert_eq!(versions.node_ended(1001), None);
+        assert_eq!(versions.node_ended(1000).as_deref(), Some("pad"));
+        assert_eq!(versions.node_ended(1000), None);
+
+        // Nothing plays v1 any more: it is reloaded in place
+        assert_eq!(versions.load("pad", &synthdef("pad"), true).0, "pad__v1");
+        assert_eq!(rename_synthdef(b"SCgf", "x"), None);
+    }
+}
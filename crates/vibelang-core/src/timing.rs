@@ -3,12 +3,16 @@
 //! This module provides the fundamental timing types used throughout VibeLang:
 //!
 //! - [`BeatTime`] - Fixed-point beat representation for precise timing
+//! - [`Beats`], [`Bars`] and [`TimeSpan`] - Musical lengths with explicit units
 //! - [`TimeSignature`] - Musical time signature (e.g., 4/4, 3/4)
 //! - [`TransportClock`] - Transport-aware clock for beat/time conversion
 //! - [`LatencyCompensation`] - Configurable latency for network/audio compensation
+//! - [`ClockOffsetEstimator`] - NTP-style offset between two machines' clocks
+//! - [`MidiClockFollower`] - Transport position and tempo of an external MIDI clock
 
 #[cfg(feature = "native")]
 use rosc::OscTime;
+use std::collections::VecDeque;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Fixed-point beat representation with 16 fractional bits.
@@ -74,7 +78,7 @@ impl From<BeatTime> for f64 {
 }
 
 /// Wrapper type for beats (floating-point).
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
 pub struct Beats(pub f64);
 
 impl Beats {
@@ -82,10 +86,21 @@ impl Beats {
     pub fn as_f64(self) -> f64 {
         self.0
     }
+
+    /// Convert beats to bars using the given time signature.
+    pub fn to_bars(self, signature: TimeSignature) -> Bars {
+        Bars(self.0 / signature.beats_per_bar())
+    }
+}
+
+impl std::fmt::Display for Beats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.0, if self.0 == 1.0 { "beat" } else { "beats" })
+    }
 }
 
 /// Wrapper type for bars (floating-point).
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
 pub struct Bars(pub f64);
 
 impl Bars {
@@ -95,6 +110,81 @@ impl Bars {
     }
 }
 
+impl std::fmt::Display for Bars {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.0, if self.0 == 1.0 { "bar" } else { "bars" })
+    }
+}
+
+/// A musical length or position with an explicit unit.
+///
+/// Bars only become beats through a time signature, so a span keeps its
+/// unit until the value is used, and converts with the signature active then.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeSpan {
+    /// A number of beats.
+    Beats(Beats),
+    /// A number of bars.
+    Bars(Bars),
+}
+
+impl TimeSpan {
+    /// Length in beats under the given time signature.
+    pub fn to_beats(self, signature: TimeSignature) -> Beats {
+        match self {
+            TimeSpan::Beats(beats) => beats,
+            TimeSpan::Bars(bars) => bars.to_beats(signature),
+        }
+    }
+
+    /// The same span scaled by `factor`, keeping its unit.
+    pub fn scale(self, factor: f64) -> Self {
+        match self {
+            TimeSpan::Beats(beats) => TimeSpan::Beats(Beats(beats.0 * factor)),
+            TimeSpan::Bars(bars) => TimeSpan::Bars(Bars(bars.0 * factor)),
+        }
+    }
+
+    /// Parse a span like `"4 bars"`, `"1bar"`, `"8 beats"`, `"2.5b"` or a
+    /// plain number of beats.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        let split = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(text.len());
+        let value: f64 = text[..split].parse().ok()?;
+        if !value.is_finite() {
+            return None;
+        }
+        match text[split..].trim() {
+            "" | "b" | "beat" | "beats" => Some(TimeSpan::Beats(Beats(value))),
+            "bar" | "bars" => Some(TimeSpan::Bars(Bars(value))),
+            _ => None,
+        }
+    }
+}
+
+impl From<Beats> for TimeSpan {
+    fn from(beats: Beats) -> Self {
+        TimeSpan::Beats(beats)
+    }
+}
+
+impl From<Bars> for TimeSpan {
+    fn from(bars: Bars) -> Self {
+        TimeSpan::Bars(bars)
+    }
+}
+
+impl std::fmt::Display for TimeSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeSpan::Beats(beats) => beats.fmt(f),
+            TimeSpan::Bars(bars) => bars.fmt(f),
+        }
+    }
+}
+
 /// Musical time signature (numerator/denominator).
 ///
 /// The numerator indicates beats per bar, and the denominator indicates
@@ -136,6 +226,10 @@ impl Default for TimeSignature {
 ///
 /// These values are added to scheduled event times to account for
 /// network transmission, server processing, and audio buffering delays.
+///
+/// The output latency of the audio interface doesn't change when bundles
+/// must reach scsynth, but hardware played over MIDI has to wait for it:
+/// see [`LatencyCompensation::device_offset_ms`].
 #[derive(Clone, Debug)]
 pub struct LatencyCompensation {
     /// Network round-trip latency.
@@ -146,6 +240,8 @@ pub struct LatencyCompensation {
     pub audio_buffer_ms: f64,
     /// Additional safety margin.
     pub safety_margin_ms: f64,
+    /// Time from scsynth rendering a sample to it leaving the audio interface.
+    pub output_latency_ms: f64,
 }
 
 impl Default for LatencyCompensation {
@@ -155,6 +251,7 @@ impl Default for LatencyCompensation {
             server_processing_ms: 10.0,
             audio_buffer_ms: 2.0,
             safety_margin_ms: 20.0,
+            output_latency_ms: 0.0,
         }
     }
 }
@@ -172,18 +269,270 @@ impl LatencyCompensation {
     pub fn total_seconds(&self) -> f64 {
         self.total_ms() / 1000.0
     }
+
+    /// Offset of events for a device that takes `device_latency_ms` to
+    /// sound, relative to scsynth audio: later by the audio output latency,
+    /// earlier by the device's own.
+    ///
+    /// Events are sent `max_advance_ms` ahead of time, so they're never moved
+    /// earlier than that: their bundles would be timestamped in the past.
+    pub fn device_offset_ms(&self, device_latency_ms: f64, max_advance_ms: f64) -> f64 {
+        (self.output_latency_ms - device_latency_ms).max(-max_advance_ms)
+    }
+}
+
+/// One request/reply exchange with a remote clock, NTP style.
+///
+/// All values are wall-clock seconds: `sent` and `received` on the local
+/// machine, `remote_received` and `remote_sent` on the remote one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockSample {
+    pub sent: f64,
+    pub remote_received: f64,
+    pub remote_sent: f64,
+    pub received: f64,
+}
+
+impl ClockSample {
+    /// Remote clock minus local clock, assuming a symmetric network path.
+    pub fn offset(&self) -> f64 {
+        ((self.remote_received - self.sent) + (self.remote_sent - self.received)) / 2.0
+    }
+
+    /// Time spent on the network (excluding the remote's processing time).
+    pub fn round_trip(&self) -> f64 {
+        (self.received - self.sent) - (self.remote_sent - self.remote_received)
+    }
+}
+
+/// Estimates the offset between the local clock and a remote one.
+///
+/// Keeps the most recent exchanges and trusts the one with the shortest
+/// round trip, like NTP's clock filter: queueing delays only ever make a
+/// round trip longer, and the fastest exchange is the least asymmetric.
+#[derive(Clone, Debug)]
+pub struct ClockOffsetEstimator {
+    samples: VecDeque<ClockSample>,
+    capacity: usize,
+}
+
+impl ClockOffsetEstimator {
+    /// Create an estimator over the last `capacity` exchanges.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an exchange (samples with a negative round trip are ignored).
+    pub fn add(&mut self, sample: ClockSample) {
+        if sample.round_trip() < 0.0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    fn best(&self) -> Option<&ClockSample> {
+        self.samples
+            .iter()
+            .min_by(|a, b| a.round_trip().total_cmp(&b.round_trip()))
+    }
+
+    /// Estimated remote clock minus local clock, in seconds.
+    pub fn offset(&self) -> Option<f64> {
+        self.best().map(ClockSample::offset)
+    }
+
+    /// Round trip of the exchange the estimate is based on, in seconds.
+    pub fn round_trip(&self) -> Option<f64> {
+        self.best().map(ClockSample::round_trip)
+    }
+
+    /// Convert a local wall-clock time to the remote clock.
+    pub fn to_remote(&self, local: f64) -> Option<f64> {
+        self.offset().map(|offset| local + offset)
+    }
+}
+
+/// MIDI clock ticks per quarter note.
+pub const MIDI_CLOCK_PPQN: u32 = 24;
+
+/// Largest correction of a following transport per MIDI clock tick, in
+/// milliseconds of transport time.
+pub const MIDI_CLOCK_MAX_SLEW_MS: f64 = 1.0;
+
+/// Phase errors to a MIDI clock above this jump to its position instead of slewing.
+pub const MIDI_CLOCK_MAX_PHASE_ERROR_MS: f64 = 100.0;
+
+/// Measured MIDI clock tempo changes smaller than this are jitter, not a new tempo.
+pub const MIDI_CLOCK_TEMPO_TOLERANCE_BPM: f64 = 0.25;
+
+/// Number of recent ticks the tempo of a MIDI clock is measured over (two beats).
+const MIDI_CLOCK_TEMPO_TICKS: usize = 48;
+
+/// A gap between ticks longer than this restarts the tempo measurement
+/// (slower than 30 BPM, i.e. the clock paused).
+const MIDI_CLOCK_GAP_SECONDS: f64 = 60.0 / 30.0 / MIDI_CLOCK_PPQN as f64;
+
+/// Where the transport takes its tempo and position from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ClockSource {
+    /// The transport's own tempo.
+    #[default]
+    Internal,
+    /// An external MIDI clock on any open MIDI input (24 ticks per quarter
+    /// note, start, stop, continue and song position).
+    Midi,
+}
+
+impl ClockSource {
+    /// Parse a clock source name ("internal" or "midi").
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "internal" => Some(ClockSource::Internal),
+            "midi" => Some(ClockSource::Midi),
+            _ => None,
+        }
+    }
+
+    /// Name of the clock source.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClockSource::Internal => "internal",
+            ClockSource::Midi => "midi",
+        }
+    }
+}
+
+/// What a MIDI clock message means for a transport following it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiClockUpdate {
+    /// The transport starts playing at `beat`.
+    Start { beat: f64 },
+    /// The transport should be at `beat` now.
+    Position { beat: f64 },
+    /// The transport stops.
+    Stop,
+}
+
+/// Follows an external MIDI clock.
+///
+/// Counts clock ticks into a beat position and measures the tempo from their
+/// spacing. As the MIDI spec has it, start and continue only arm the clock:
+/// the transport starts with the next tick, which marks the start position.
+#[derive(Clone, Debug, Default)]
+pub struct MidiClockFollower {
+    /// Index of the next tick since the song start.
+    ticks: u64,
+    /// Whether start or continue was received and the next tick starts playing.
+    armed: bool,
+    /// Whether the clock is playing.
+    running: bool,
+    /// Times of the recent ticks in seconds.
+    tick_times: VecDeque<f64>,
+}
+
+impl MidiClockFollower {
+    /// Create a stopped follower at the song start.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from the song start with the next tick (0xFA).
+    pub fn start(&mut self) {
+        self.ticks = 0;
+        self.armed = true;
+    }
+
+    /// Resume from the current position with the next tick (0xFB).
+    pub fn resume(&mut self) {
+        self.armed = true;
+    }
+
+    /// Stop (0xFC); `None` if the clock wasn't playing.
+    pub fn stop(&mut self) -> Option<MidiClockUpdate> {
+        let playing = self.running;
+        self.running = false;
+        self.armed = false;
+        playing.then_some(MidiClockUpdate::Stop)
+    }
+
+    /// Move a stopped clock to a song position, in sixteenth notes (0xF2).
+    pub fn song_position(&mut self, sixteenths: u16) {
+        if !self.running {
+            self.ticks = sixteenths as u64 * (MIDI_CLOCK_PPQN as u64 / 4);
+        }
+    }
+
+    /// Count a tick (0xF8) received at `at` seconds.
+    ///
+    /// Ticks of a stopped clock only feed the tempo measurement, so the tempo
+    /// is known before the transport starts.
+    pub fn tick(&mut self, at: f64) -> Option<MidiClockUpdate> {
+        if self.tick_times.back().is_some_and(|last| at - last > MIDI_CLOCK_GAP_SECONDS || at < *last) {
+            self.tick_times.clear();
+        }
+        if self.tick_times.len() == MIDI_CLOCK_TEMPO_TICKS {
+            self.tick_times.pop_front();
+        }
+        self.tick_times.push_back(at);
+
+        let beat = self.beat();
+        let update = if self.armed {
+            self.armed = false;
+            self.running = true;
+            MidiClockUpdate::Start { beat }
+        } else if self.running {
+            MidiClockUpdate::Position { beat }
+        } else {
+            return None;
+        };
+        self.ticks += 1;
+        Some(update)
+    }
+
+    /// Beat of the next tick.
+    pub fn beat(&self) -> f64 {
+        self.ticks as f64 / MIDI_CLOCK_PPQN as f64
+    }
+
+    /// Tempo measured over the recent ticks, once there are a few.
+    pub fn bpm(&self) -> Option<f64> {
+        let (first, last) = (self.tick_times.front()?, self.tick_times.back()?);
+        let intervals = self.tick_times.len() - 1;
+        if intervals < MIDI_CLOCK_PPQN as usize / 4 || last <= first {
+            return None;
+        }
+        let tick_seconds = (last - first) / intervals as f64;
+        Some(60.0 / (tick_seconds * MIDI_CLOCK_PPQN as f64))
+    }
+
+    /// Whether the clock is playing.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
 }
 
 /// Transport-aware clock for converting between wall-clock time and beats.
 ///
 /// The clock maintains an anchor point (beat position at a specific instant)
 /// and uses BPM to calculate beat positions at other times.
+///
+/// In virtual time (see [`TransportClock::set_virtual`]) the clock ignores
+/// instants and only moves when [`TransportClock::advance`] is called, so
+/// simulation runs are deterministic.
 #[derive(Clone, Debug)]
 pub struct TransportClock {
     bpm: f64,
     signature: TimeSignature,
     latency: LatencyCompensation,
     running: bool,
+    virtual_time: bool,
+    source: ClockSource,
     anchor_instant: Instant,
     anchor_beat: BeatTime,
 }
@@ -202,6 +551,8 @@ impl TransportClock {
             signature: TimeSignature::default(),
             latency: LatencyCompensation::default(),
             running: false,
+            virtual_time: false,
+            source: ClockSource::Internal,
             anchor_instant: Instant::now(),
             anchor_beat: BeatTime::ZERO,
         }
@@ -251,9 +602,47 @@ impl TransportClock {
         self.anchor_instant = now;
     }
 
+    /// Switch between wall-clock time and virtual time.
+    ///
+    /// The beat position is kept; in virtual time it only changes with
+    /// [`TransportClock::advance`], [`TransportClock::seek`] and phase corrections.
+    pub fn set_virtual(&mut self, enabled: bool, now: Instant) {
+        self.anchor_beat = self.beat_at(now);
+        self.anchor_instant = now;
+        self.virtual_time = enabled;
+    }
+
+    /// Check if the clock runs on virtual time.
+    pub fn is_virtual(&self) -> bool {
+        self.virtual_time
+    }
+
+    /// Set where tempo and position come from.
+    ///
+    /// Following an external clock, the transport still runs on its own
+    /// between ticks; the runtime feeds it the clock's tempo and pulls it
+    /// towards the clock's position (see [`TransportClock::correct_phase`]).
+    pub fn set_clock_source(&mut self, source: ClockSource) {
+        self.source = source;
+    }
+
+    /// Where tempo and position come from.
+    pub fn clock_source(&self) -> ClockSource {
+        self.source
+    }
+
+    /// Move a running virtual-time transport forward by `beats`.
+    ///
+    /// Does nothing on wall-clock time or while the transport is stopped.
+    pub fn advance(&mut self, beats: f64) {
+        if self.virtual_time && self.running && beats > 0.0 {
+            self.anchor_beat = self.anchor_beat + BeatTime::from_float(beats);
+        }
+    }
+
     /// Calculate the beat position at a given instant.
     pub fn beat_at(&self, time: Instant) -> BeatTime {
-        if !self.running || time <= self.anchor_instant {
+        if !self.running || self.virtual_time || time <= self.anchor_instant {
             return self.anchor_beat;
         }
 
@@ -262,6 +651,24 @@ impl TransportClock {
         self.anchor_beat + BeatTime::from_float(beats_elapsed)
     }
 
+    /// Pull the transport towards `target`, the beat it should be at `now`.
+    ///
+    /// Errors larger than `max_error` beats jump straight to the target;
+    /// smaller ones move the transport by at most `max_step` beats, so
+    /// following a remote clock doesn't make playback stutter. Returns the
+    /// error (target minus current beat) before the correction.
+    pub fn correct_phase(&mut self, target: BeatTime, now: Instant, max_step: f64, max_error: f64) -> f64 {
+        let current = self.beat_at(now);
+        let error = target.to_float() - current.to_float();
+        self.anchor_beat = if error.abs() > max_error {
+            target
+        } else {
+            BeatTime::from_float(current.to_float() + error.clamp(-max_step, max_step))
+        };
+        self.anchor_instant = now;
+        error
+    }
+
     /// Update the anchor to the current beat position.
     ///
     /// Call this periodically to prevent drift accumulation.
@@ -290,11 +697,19 @@ impl TransportClock {
     /// The Instant represents when the synth will be "live" on scsynth.
     #[cfg(feature = "native")]
     pub fn beat_to_timestamp_and_instant(&self, beat: BeatTime, now: Instant) -> (Instant, OscTime) {
+        self.beat_to_timestamp_and_instant_with_offset(beat, now, 0.0)
+    }
+
+    /// Like [`Self::beat_to_timestamp_and_instant`], moved by `offset_ms`
+    /// (see [`LatencyCompensation::device_offset_ms`]) so events for a
+    /// slower or faster device sound together with scsynth audio.
+    #[cfg(feature = "native")]
+    pub fn beat_to_timestamp_and_instant_with_offset(&self, beat: BeatTime, now: Instant, offset_ms: f64) -> (Instant, OscTime) {
         let current = self.beat_at(now);
         let beats_until_target = beat.to_float() - current.to_float();
         let beats_per_second = self.bpm / 60.0;
         let mut seconds_until_target = beats_until_target / beats_per_second;
-        seconds_until_target += self.latency.total_seconds();
+        seconds_until_target += self.latency.total_seconds() + offset_ms / 1000.0;
 
         if seconds_until_target < 0.0 {
             seconds_until_target = self.latency.total_seconds().max(0.01);
@@ -367,6 +782,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_time_span_units() {
+        let waltz = TimeSignature::new(3, 4);
+        let six_eight = TimeSignature::new(6, 8);
+        assert_eq!(TimeSpan::parse("4 bars"), Some(TimeSpan::Bars(Bars(4.0))));
+        assert_eq!(TimeSpan::parse("1bar").unwrap().to_beats(waltz), Beats(3.0));
+        assert_eq!(TimeSpan::parse("2.5b"), Some(TimeSpan::Beats(Beats(2.5))));
+        assert_eq!(TimeSpan::parse(" 8 Beats "), Some(TimeSpan::Beats(Beats(8.0))));
+        assert_eq!(TimeSpan::parse("6"), Some(TimeSpan::Beats(Beats(6.0))));
+        assert_eq!(TimeSpan::parse("4 bras"), None);
+        assert_eq!(TimeSpan::parse("bars"), None);
+
+        assert_eq!(TimeSpan::Bars(Bars(2.0)).to_beats(six_eight), Beats(6.0));
+        assert_eq!(Beats(6.0).to_bars(waltz), Bars(2.0));
+        assert_eq!(TimeSpan::Bars(Bars(2.0)).scale(0.5), TimeSpan::Bars(Bars(1.0)));
+        assert_eq!(TimeSpan::Bars(Bars(1.0)).to_string(), "1 bar");
+        assert_eq!(TimeSpan::Beats(Beats(8.0)).to_string(), "8 beats");
+    }
+
     #[test]
     fn test_transport_clock_beat_calculation() {
         let mut clock = TransportClock::new();
@@ -380,10 +814,122 @@ mod tests {
         assert!((beat.to_float() - 1.0).abs() < 0.05);
     }
 
+    #[test]
+    fn test_transport_clock_virtual_time() {
+        let now = Instant::now();
+        let mut clock = TransportClock::new();
+        clock.set_virtual(true, now);
+        clock.advance(4.0);
+        assert_eq!(clock.beat_at(now).to_float(), 0.0, "stopped transport doesn't advance");
+
+        clock.start(now);
+        clock.advance(2.5);
+        // Wall-clock time has no effect
+        assert_eq!(clock.beat_at(now + Duration::from_secs(10)).to_float(), 2.5);
+        clock.set_bpm(60.0, now);
+        clock.advance(1.5);
+        assert_eq!(clock.beat_at(now).to_float(), 4.0);
+
+        // Back on wall-clock time, the clock runs on from the virtual position
+        clock.set_virtual(false, now);
+        let beat = clock.beat_at(now + Duration::from_secs(1)).to_float();
+        assert!((beat - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clock_offset_estimation() {
+        // Remote clock is 10s ahead; the second exchange was delayed on the way back
+        let mut estimator = ClockOffsetEstimator::new(4);
+        assert_eq!(estimator.offset(), None);
+        estimator.add(ClockSample {
+            sent: 100.0,
+            remote_received: 110.002,
+            remote_sent: 110.003,
+            received: 100.005,
+        });
+        estimator.add(ClockSample {
+            sent: 101.0,
+            remote_received: 111.002,
+            remote_sent: 111.003,
+            received: 101.050,
+        });
+        assert!((estimator.offset().unwrap() - 10.0).abs() < 1e-9);
+        assert!((estimator.round_trip().unwrap() - 0.004).abs() < 1e-9);
+        assert!((estimator.to_remote(200.0).unwrap() - 210.0).abs() < 1e-9);
+
+        let mut clock = TransportClock::new();
+        let now = Instant::now();
+        clock.seek(BeatTime::from_float(8.0), now);
+        // Small errors are slewed, large ones jump
+        let error = clock.correct_phase(BeatTime::from_float(8.1), now, 0.02, 1.0);
+        assert!((error - 0.1).abs() < 1e-3);
+        assert!((clock.beat_at(now).to_float() - 8.02).abs() < 1e-3);
+        clock.correct_phase(BeatTime::from_float(16.0), now, 0.02, 1.0);
+        assert!((clock.beat_at(now).to_float() - 16.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_midi_clock_follower() {
+        let tick = 60.0 / 120.0 / MIDI_CLOCK_PPQN as f64;
+        let mut clock = MidiClockFollower::new();
+
+        // A stopped clock is measured but doesn't move
+        for i in 0..30 {
+            assert_eq!(clock.tick(i as f64 * tick), None);
+        }
+        assert!((clock.bpm().unwrap() - 120.0).abs() < 1e-6);
+
+        // Start plays from the song start with the next tick
+        clock.start();
+        assert_eq!(clock.tick(30.0 * tick), Some(MidiClockUpdate::Start { beat: 0.0 }));
+        for i in 31..54 {
+            assert!(matches!(clock.tick(i as f64 * tick), Some(MidiClockUpdate::Position { .. })));
+        }
+        assert_eq!(clock.tick(54.0 * tick), Some(MidiClockUpdate::Position { beat: 1.0 }));
+        assert_eq!(clock.stop(), Some(MidiClockUpdate::Stop));
+        assert_eq!(clock.stop(), None);
+
+        // Song position and continue resume elsewhere; a pause restarts the tempo measurement
+        clock.song_position(16);
+        clock.resume();
+        assert_eq!(clock.tick(100.0), Some(MidiClockUpdate::Start { beat: 4.0 }));
+        assert_eq!(clock.bpm(), None);
+        assert!(clock.is_running());
+        assert_eq!(ClockSource::parse(" MIDI"), Some(ClockSource::Midi));
+    }
+
     #[test]
     fn test_latency_compensation() {
         let latency = LatencyCompensation::default();
         assert!((latency.total_ms() - 52.0).abs() < 0.001);
         assert!((latency.total_seconds() - 0.052).abs() < 0.0001);
     }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_device_latency_offset() {
+        let now = Instant::now();
+        let mut clock = TransportClock::new();
+        clock.set_virtual(true, now);
+        clock.set_bpm(120.0, now);
+        clock.start(now);
+        clock.latency_mut().output_latency_ms = 20.0;
+
+        // A synth that takes 30 ms to sound is sent 10 ms before the audio
+        let offset = clock.latency().device_offset_ms(30.0, 250.0);
+        assert_eq!(offset, -10.0);
+        let (audio, _) = clock.beat_to_timestamp_and_instant(BeatTime::from_float(1.0), now);
+        let (midi, _) = clock.beat_to_timestamp_and_instant_with_offset(BeatTime::from_float(1.0), now, offset);
+        assert!(((audio - midi).as_secs_f64() - 0.010).abs() < 1e-6);
+        assert!(((audio - now).as_secs_f64() - 0.552).abs() < 1e-6);
+
+        // A fast one waits for the audio interface
+        let offset = clock.latency().device_offset_ms(0.0, 250.0);
+        let (midi, _) = clock.beat_to_timestamp_and_instant_with_offset(BeatTime::from_float(1.0), now, offset);
+        assert!(((midi - audio).as_secs_f64() - 0.020).abs() < 1e-6);
+
+        // A slower one than the lookahead allows is only moved by the lookahead
+        assert_eq!(clock.latency().device_offset_ms(400.0, 250.0), -250.0);
+        assert_eq!(clock.latency().device_offset_ms(270.0, 250.0), -250.0);
+    }
 }
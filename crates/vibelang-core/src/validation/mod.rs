@@ -3,16 +3,23 @@
 //! This module provides script validation without requiring a running SuperCollider server.
 //! It executes scripts with a no-op backend and tracks synthdef definitions and references
 //! to detect errors like undefined synthdefs.
+//!
+//! The synthdefs, samples and SFZ instruments a script loads are recorded as
+//! well, so `vibe warmup` can write a preload manifest from them (see
+//! [`crate::preload`]).
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 #[cfg(feature = "native")]
-use std::path::{Path, PathBuf};
+use std::path::Path;
 #[cfg(feature = "native")]
 use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "native")]
 use crossbeam_channel::{unbounded, Receiver};
 
+#[cfg(feature = "native")]
+use crate::api::watchdog::{self, EvalLimits};
 #[cfg(feature = "native")]
 use crate::api::{create_engine_with_paths, init_api};
 #[cfg(feature = "native")]
@@ -39,6 +46,12 @@ pub struct ValidationResult {
     pub referenced_synthdefs: Vec<SynthdefReference>,
     /// All voice names defined in the script.
     pub defined_voices: HashSet<String>,
+    /// Compiled bytes of the synthdefs defined in the script, by name.
+    pub synthdef_bytes: HashMap<String, Vec<u8>>,
+    /// Samples loaded by the script (resolved paths, last load per id).
+    pub samples: Vec<AssetReference>,
+    /// SFZ instruments loaded by the script (last load per id).
+    pub sfz_instruments: Vec<AssetReference>,
 }
 
 impl ValidationResult {
@@ -73,6 +86,15 @@ pub struct SynthdefReference {
     pub column: u32,
 }
 
+/// A sample or SFZ instrument loaded by the script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetReference {
+    /// The id the script loads it under.
+    pub id: String,
+    /// Resolved file path.
+    pub path: PathBuf,
+}
+
 /// An error found during validation.
 #[derive(Debug, Clone)]
 pub struct ValidationError {
@@ -137,6 +159,8 @@ fn builtin_synthdefs() -> HashSet<String> {
         "sfz_voice_stereo",
         "sfz_voice",
         "system_link_audio",
+        crate::input_voice::INPUT_MONO_SYNTHDEF,
+        crate::input_voice::INPUT_STEREO_SYNTHDEF,
     ]
     .iter()
     .map(|s| s.to_string())
@@ -147,7 +171,8 @@ fn builtin_synthdefs() -> HashSet<String> {
 ///
 /// This function:
 /// 1. Creates a no-op runtime (no OSC communication)
-/// 2. Sets up a validation deploy callback to track defined synthdefs
+/// 2. Deploys synthdefs to a validation target that tracks them, leaving the
+///    session's deploy callback and synthdef registry alone
 /// 3. Executes the script with the full VibeLang API
 /// 4. Collects all parse/runtime errors from Rhai
 /// 5. Checks for undefined synthdefs
@@ -165,19 +190,71 @@ pub fn validate_script(
     file_path: Option<&Path>,
     import_paths: &[PathBuf],
 ) -> ValidationResult {
-    let mut result = ValidationResult::default();
+    validate_with_limits(content, file_path, import_paths, None)
+}
+
+/// Validate a script while a session is running, e.g. before a hot reload.
+///
+/// [`validate_script`] installs its own runtime handle and script context.
+/// This runs it on a separate thread, so the caller's thread-local API state
+/// stays untouched. `limits` cancel runaway evaluations like the session's
+/// own engine does.
+#[cfg(feature = "native")]
+pub fn validate_reload(
+    content: &str,
+    file_path: Option<&Path>,
+    import_paths: &[PathBuf],
+    limits: &EvalLimits,
+) -> ValidationResult {
+    let result = std::thread::scope(|scope| {
+        scope
+            .spawn(|| validate_with_limits(content, file_path, import_paths, Some(limits)))
+            .join()
+    });
+
+    result.unwrap_or_else(|_| ValidationResult {
+        runtime_errors: vec![ValidationError {
+            message: "validation panicked".to_string(),
+            file: file_path.map(|p| p.to_string_lossy().to_string()),
+            line: None,
+            column: None,
+        }],
+        ..Default::default()
+    })
+}
 
+#[cfg(feature = "native")]
+fn validate_with_limits(
+    content: &str,
+    file_path: Option<&Path>,
+    import_paths: &[PathBuf],
+    limits: Option<&EvalLimits>,
+) -> ValidationResult {
     // Track defined synthdefs via the deploy callback
-    let defined_synthdefs = Arc::new(Mutex::new(HashSet::new()));
+    let defined_synthdefs = Arc::new(Mutex::new(HashMap::new()));
 
-    // Set up validation deploy callback
+    // Deploy to a validation target instead of the session's scsynth
     let defined = defined_synthdefs.clone();
-    vibelang_dsp::set_deploy_callback(move |bytes| {
+    let deploy = move |bytes: Vec<u8>| {
         if let Some(name) = extract_synthdef_name(&bytes) {
-            defined.lock().unwrap().insert(name);
+            defined.lock().unwrap().insert(name, bytes);
         }
         Ok(())
-    });
+    };
+    vibelang_dsp::with_deploy_target(deploy, || {
+        run_validation(content, file_path, import_paths, limits, &defined_synthdefs)
+    })
+}
+
+#[cfg(feature = "native")]
+fn run_validation(
+    content: &str,
+    file_path: Option<&Path>,
+    import_paths: &[PathBuf],
+    limits: Option<&EvalLimits>,
+    defined_synthdefs: &Mutex<HashMap<String, Vec<u8>>>,
+) -> ValidationResult {
+    let mut result = ValidationResult::default();
 
     // Create validation runtime with no-op scsynth
     let (message_tx, message_rx) = unbounded();
@@ -214,6 +291,10 @@ pub fn validate_script(
     // Register DSP API for synthdef definitions
     vibelang_dsp::register_dsp_api(&mut engine);
 
+    if let Some(limits) = limits {
+        watchdog::install(&mut engine, limits);
+    }
+
     // Compile and run
     match engine.compile(content) {
         Ok(ast) => {
@@ -238,12 +319,16 @@ pub fn validate_script(
     }
 
     // Collect referenced synthdefs and voice names from messages
-    let collected = collect_from_messages(&message_rx);
+    let mut collected = collect_from_messages(&message_rx);
 
-    // Get defined synthdefs
-    result.defined_synthdefs = defined_synthdefs.lock().unwrap().clone();
+    // Get defined synthdefs, including ones loaded from files
+    result.synthdef_bytes = std::mem::take(&mut collected.loaded_synthdefs);
+    result.synthdef_bytes.extend(defined_synthdefs.lock().unwrap().clone());
+    result.defined_synthdefs = result.synthdef_bytes.keys().cloned().collect();
     result.referenced_synthdefs = collected.synthdef_refs.clone();
     result.defined_voices = collected.voice_names;
+    result.samples = collected.samples;
+    result.sfz_instruments = collected.sfz_instruments;
 
     // Check for undefined synthdefs
     let builtin = builtin_synthdefs();
@@ -261,43 +346,75 @@ pub fn validate_script(
 #[cfg(feature = "native")]
 struct CollectedData {
     synthdef_refs: Vec<SynthdefReference>,
+    /// Synthdefs loaded from files (e.g. `import_scd`), by name.
+    loaded_synthdefs: HashMap<String, Vec<u8>>,
     voice_names: HashSet<String>,
+    samples: Vec<AssetReference>,
+    sfz_instruments: Vec<AssetReference>,
 }
 
-/// Collect synthdef references and voice names from state messages (native only).
+/// Collect synthdef references, voice names and loaded assets from state
+/// messages (native only).
 #[cfg(feature = "native")]
 fn collect_from_messages(rx: &Receiver<StateMessage>) -> CollectedData {
     let mut data = CollectedData {
         synthdef_refs: Vec::new(),
+        loaded_synthdefs: HashMap::new(),
         voice_names: HashSet::new(),
+        samples: Vec::new(),
+        sfz_instruments: Vec::new(),
     };
 
     while let Ok(msg) = rx.try_recv() {
-        if let StateMessage::UpsertVoice {
-            name,
-            synth_name,
-            source_location,
-            ..
-        } = msg
-        {
-            // Track the voice name
-            data.voice_names.insert(name.clone());
-
-            if let Some(synthdef_name) = synth_name {
-                data.synthdef_refs.push(SynthdefReference {
-                    name: synthdef_name,
-                    voice_name: name,
-                    file: source_location.file,
-                    line: source_location.line.unwrap_or(0),
-                    column: source_location.column.unwrap_or(0),
-                });
+        match msg {
+            StateMessage::UpsertVoice {
+                name,
+                synth_name,
+                source_location,
+                ..
+            } => {
+                // Track the voice name
+                data.voice_names.insert(name.clone());
+
+                if let Some(synthdef_name) = synth_name {
+                    data.synthdef_refs.push(SynthdefReference {
+                        name: synthdef_name,
+                        voice_name: name,
+                        file: source_location.file,
+                        line: source_location.line.unwrap_or(0),
+                        column: source_location.column.unwrap_or(0),
+                    });
+                }
+            }
+            // Unresolved samples can't be preloaded (the load already logged an error)
+            StateMessage::LoadSample {
+                id,
+                resolved_path: Some(path),
+                ..
+            } => push_asset(&mut data.samples, id, PathBuf::from(path)),
+            StateMessage::LoadSfzInstrument { id, sfz_path } => {
+                push_asset(&mut data.sfz_instruments, id, sfz_path)
             }
+            StateMessage::LoadSynthDef { name, bytes } => {
+                data.loaded_synthdefs.insert(name, bytes);
+            }
+            StateMessage::LoadSynthDefDir { synthdefs, .. } => {
+                data.loaded_synthdefs.extend(synthdefs);
+            }
+            _ => {}
         }
     }
 
     data
 }
 
+/// Record an asset load; a later load under the same id replaces it.
+#[cfg(feature = "native")]
+fn push_asset(assets: &mut Vec<AssetReference>, id: String, path: PathBuf) {
+    assets.retain(|asset| asset.id != id);
+    assets.push(AssetReference { id, path });
+}
+
 #[cfg(all(test, feature = "native"))]
 mod tests {
     use super::*;
@@ -322,6 +439,39 @@ mod tests {
         assert!(!result.runtime_errors.is_empty());
     }
 
+    #[test]
+    fn test_validate_reload_keeps_caller_state() {
+        let limits = EvalLimits {
+            max_operations: 10_000,
+            max_eval_time: None,
+        };
+        // Runaway scripts are cancelled instead of hanging the reload
+        let result = validate_reload("while true {}", None, &[], &limits);
+        assert_eq!(result.runtime_errors.len(), 1);
+        // The validation handle stayed on the validation thread
+        assert!(crate::api::get_handle().is_none());
+
+        assert!(validate_reload("let x = 1;", None, &[], &limits).is_ok());
+    }
+
+    #[test]
+    fn test_validate_reload_keeps_session_synthdefs() {
+        let limits = EvalLimits {
+            max_operations: 1_000_000,
+            max_eval_time: None,
+        };
+        let script = r#"
+            define_synthdef("validation_only")
+                .param("freq", 220.0)
+                .body(|freq| sin_osc_ar(freq, 0.0));
+        "#;
+        let result = validate_reload(script, None, &[], &limits);
+        assert!(result.is_ok(), "{:?}", result.all_errors());
+        assert!(result.synthdef_bytes.contains_key("validation_only"));
+        // Rejected or not, a validated synthdef is never registered for the session
+        assert!(!vibelang_dsp::synthdef_exists("validation_only"));
+    }
+
     #[test]
     fn test_builtin_synthdefs() {
         let builtins = builtin_synthdefs();
@@ -0,0 +1,110 @@
+//! Weighted pattern variations.
+//!
+//! A pattern can hold several takes of its steps ("variations"); each pass
+//! through the loop plays one of them, picked by weight. Every event of a
+//! take carries an [`EventVariation`] tag, and the scheduler keeps only the
+//! events of the take picked for the pass they belong to.
+//!
+//! The pick only depends on the pattern, the beat its pass starts on and the
+//! seed, so a song plays (and simulates) the same every time. Performers can
+//! hold a take with `lock_variation(n)`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Which take of a pattern an event belongs to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EventVariation {
+    /// Index of the take.
+    pub index: usize,
+    /// Beat of the event within its pass through the pattern.
+    pub offset: f64,
+}
+
+impl EventVariation {
+    /// Beat the pass of an event firing at `beat` started on.
+    pub fn pass_start(&self, beat: f64) -> f64 {
+        beat - self.offset
+    }
+}
+
+/// Weights of a pattern's takes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternVariations {
+    /// Relative weight of each take (missing weights count as 1).
+    pub weights: Vec<f64>,
+    /// Seed of the picks; another seed gives another order of takes.
+    pub seed: u64,
+}
+
+impl PatternVariations {
+    /// Takes with equal weights.
+    pub fn new(count: usize) -> Self {
+        Self {
+            weights: vec![1.0; count],
+            seed: 0,
+        }
+    }
+
+    /// Set the weights of the takes; negative weights count as 0.
+    pub fn with_weights(mut self, weights: &[f64]) -> Self {
+        for (i, weight) in self.weights.iter_mut().enumerate() {
+            *weight = weights.get(i).copied().unwrap_or(1.0).max(0.0);
+        }
+        self
+    }
+
+    /// Take played by the pass of `pattern` starting at `pass_start`.
+    pub fn pick(&self, pattern: &str, pass_start: f64) -> usize {
+        let total: f64 = self.weights.iter().sum();
+        if total <= 0.0 {
+            return 0;
+        }
+        let mut hasher = DefaultHasher::new();
+        pattern.hash(&mut hasher);
+        // Events of a pass can be a hair apart after time scaling
+        ((pass_start * 1024.0).round() as i64).hash(&mut hasher);
+        self.seed.hash(&mut hasher);
+        let unit = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+
+        let mut target = unit * total;
+        for (index, weight) in self.weights.iter().enumerate() {
+            if target < *weight {
+                return index;
+            }
+            target -= weight;
+        }
+        // Rounding can leave the target past the last take with weight
+        self.weights.iter().rposition(|w| *w > 0.0).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_variation_picks() {
+        let variations = PatternVariations::new(3).with_weights(&[0.6, 0.3, 0.1]);
+        let mut counts = [0usize; 3];
+        for pass in 0..4000 {
+            counts[variations.pick("hats", pass as f64 * 4.0)] += 1;
+        }
+        assert!((2200..2600).contains(&counts[0]), "{:?}", counts);
+        assert!((1000..1400).contains(&counts[1]), "{:?}", counts);
+        assert!((250..550).contains(&counts[2]), "{:?}", counts);
+
+        // The same pass always plays the same take
+        assert_eq!(variations.pick("hats", 32.0), variations.pick("hats", 32.0));
+        let reseeded = PatternVariations { seed: 7, ..variations.clone() };
+        assert!((0..64).any(|pass| reseeded.pick("hats", pass as f64 * 4.0) != variations.pick("hats", pass as f64 * 4.0)));
+
+        // Takes without weight are never picked; missing weights count as 1
+        let only_last = PatternVariations::new(3).with_weights(&[0.0, -1.0]);
+        assert!((0..100).all(|pass| only_last.pick("kick", pass as f64) == 2));
+        assert_eq!(PatternVariations::new(2).with_weights(&[0.0, 0.0]).pick("kick", 0.0), 0);
+
+        let event = EventVariation { index: 1, offset: 1.5 };
+        assert_eq!(event.pass_start(9.5), 8.0);
+    }
+}
@@ -0,0 +1,224 @@
+//! VST plugin hosting through SuperCollider's VSTPlugin UGen.
+//!
+//! A voice created with `.vst(plugin)` plays through a synth of
+//! [`VST_INSTRUMENT_SYNTHDEF`] in its group: notes become MIDI messages sent
+//! to the plugin with `/u_cmd`, timed in bundles like any other event. An
+//! effect created with `.vst(plugin)` runs a [`VST_EFFECT_SYNTHDEF`] synth
+//! that processes its group's bus in place.
+//!
+//! The plugin is opened once its synth exists. scsynth answers with
+//! `/vst_open`, after which the parameters set from the script are sent by
+//! name. Plugins are given by path, or by a name VSTPlugin's plugin search
+//! found. This needs the VSTPlugin extension (v0.5 or later) installed for
+//! scsynth; without it the synthdefs fail to load and VST voices stay silent.
+
+use rosc::{OscMessage, OscPacket, OscType};
+use vibelang_dsp::{encode_synthdef, GraphBuilderInner, GraphIR, Input, Rate};
+
+/// Name of the synthdef hosting a VST instrument.
+pub const VST_INSTRUMENT_SYNTHDEF: &str = "system_vst_instrument";
+
+/// Name of the synthdef hosting a VST effect.
+pub const VST_EFFECT_SYNTHDEF: &str = "system_vst_effect";
+
+/// Index of the VSTPlugin UGen in [`VST_INSTRUMENT_SYNTHDEF`], addressed by `/u_cmd`.
+pub const VST_INSTRUMENT_UGEN_INDEX: i32 = 1;
+
+/// Index of the VSTPlugin UGen in [`VST_EFFECT_SYNTHDEF`], addressed by `/u_cmd`.
+pub const VST_EFFECT_UGEN_INDEX: i32 = 2;
+
+/// MIDI channel messages sent to instruments.
+const NOTE_OFF: u8 = 0x80;
+const NOTE_ON: u8 = 0x90;
+const CONTROL_CHANGE: u8 = 0xB0;
+
+/// Controller that silences all notes of a channel.
+const ALL_NOTES_OFF: u8 = 123;
+
+/// Create the VST instrument and effect synthdefs.
+pub fn create_vst_synthdefs() -> Vec<(String, Vec<u8>)> {
+    [
+        (VST_INSTRUMENT_SYNTHDEF, build_vst_synthdef(false)),
+        (VST_EFFECT_SYNTHDEF, build_vst_synthdef(true)),
+    ]
+    .into_iter()
+    .filter_map(|(name, ir)| match encode_synthdef(&ir) {
+        Ok(bytes) => Some((name.to_string(), bytes)),
+        Err(e) => {
+            log::error!("[VST] Failed to encode {} synthdef: {}", name, e);
+            None
+        }
+    })
+    .collect()
+}
+
+/// Build a stereo VSTPlugin host.
+///
+/// Signal flow:
+///   instrument: VSTPlugin.ar(nil, 2) → Out.ar(out)
+///   effect:     In.ar(__fx_bus_in, 2) → VSTPlugin.ar(_, 2) → ReplaceOut.ar(__fx_bus_out)
+///
+/// VSTPlugin's inputs are `flags, blockSize, bypass`, the input busses
+/// (count, then channel count and signals of each), the output busses
+/// (count, then channel count of each) and the parameter controls (count,
+/// then index/value pairs).
+fn build_vst_synthdef(effect: bool) -> GraphIR {
+    let mut builder = GraphBuilderInner::new();
+
+    if effect {
+        builder.add_param("__fx_bus_in".to_string(), vec![0.0], None); // 0
+        builder.add_param("__fx_bus_out".to_string(), vec![0.0], None); // 1
+    } else {
+        builder.add_param("out".to_string(), vec![0.0], None); // 0
+    }
+    builder.create_control_ugen();
+
+    for constant in [0.0, 1.0, 2.0] {
+        builder.add_constant(constant);
+    }
+
+    let node = |id: u32, output_index: u32| Input::Node {
+        node_id: id,
+        output_index,
+    };
+
+    let mut plugin_inputs = vec![Input::Constant(0.0), Input::Constant(0.0), Input::Constant(0.0)];
+    if effect {
+        let input = builder.add_node("In".to_string(), Rate::Audio, vec![node(0, 0)], 2, 0);
+        plugin_inputs.extend([Input::Constant(1.0), Input::Constant(2.0), node(input.0, 0), node(input.0, 1)]);
+    } else {
+        plugin_inputs.push(Input::Constant(0.0));
+    }
+    plugin_inputs.extend([Input::Constant(1.0), Input::Constant(2.0), Input::Constant(0.0)]);
+
+    let plugin = builder.add_node("VSTPlugin".to_string(), Rate::Audio, plugin_inputs, 2, 0);
+    let expected_index = if effect { VST_EFFECT_UGEN_INDEX } else { VST_INSTRUMENT_UGEN_INDEX };
+    debug_assert_eq!(plugin.0 as i32, expected_index);
+
+    let (writer, bus) = if effect { ("ReplaceOut", node(0, 1)) } else { ("Out", node(0, 0)) };
+    builder.add_node(
+        writer.to_string(),
+        Rate::Audio,
+        vec![bus, node(plugin.0, 0), node(plugin.0, 1)],
+        0,
+        0,
+    );
+
+    let name = if effect { VST_EFFECT_SYNTHDEF } else { VST_INSTRUMENT_SYNTHDEF };
+    GraphIR::from_builder(name.to_string(), builder)
+}
+
+/// A `/u_cmd` to the VSTPlugin UGen `ugen_index` of `node_id`.
+fn u_cmd(node_id: i32, ugen_index: i32, command: &str, args: Vec<OscType>) -> OscPacket {
+    let mut all = vec![
+        OscType::Int(node_id),
+        OscType::Int(ugen_index),
+        OscType::String(command.to_string()),
+    ];
+    all.extend(args);
+    OscPacket::Message(OscMessage {
+        addr: "/u_cmd".to_string(),
+        args: all,
+    })
+}
+
+/// Open `plugin` in a VSTPlugin UGen, without editor, synchronously.
+pub fn open_packet(node_id: i32, ugen_index: i32, plugin: &str) -> OscPacket {
+    u_cmd(
+        node_id,
+        ugen_index,
+        "/open",
+        vec![
+            OscType::String(plugin.to_string()),
+            OscType::Int(0), // editor
+            OscType::Int(0), // threaded
+            OscType::Int(0), // mode
+        ],
+    )
+}
+
+/// Set a plugin parameter by name, or by index when `param` is a number.
+pub fn set_param_packet(node_id: i32, ugen_index: i32, param: &str, value: f32) -> OscPacket {
+    let param = match param.parse::<i32>() {
+        Ok(index) => OscType::Int(index),
+        Err(_) => OscType::String(param.to_string()),
+    };
+    u_cmd(node_id, ugen_index, "/set", vec![param, OscType::Float(value)])
+}
+
+/// Send a MIDI channel message to a plugin.
+fn midi_packet(node_id: i32, ugen_index: i32, bytes: [u8; 3]) -> OscPacket {
+    u_cmd(node_id, ugen_index, "/midi_msg", vec![OscType::Blob(bytes.to_vec())])
+}
+
+/// Note-on for an instrument.
+pub fn note_on_packet(node_id: i32, channel: u8, note: u8, velocity: u8) -> OscPacket {
+    midi_packet(
+        node_id,
+        VST_INSTRUMENT_UGEN_INDEX,
+        [NOTE_ON | (channel & 0x0F), note.min(127), velocity.min(127)],
+    )
+}
+
+/// Note-off for an instrument.
+pub fn note_off_packet(node_id: i32, channel: u8, note: u8) -> OscPacket {
+    midi_packet(node_id, VST_INSTRUMENT_UGEN_INDEX, [NOTE_OFF | (channel & 0x0F), note.min(127), 0])
+}
+
+/// Control change for an instrument.
+pub fn control_change_packet(node_id: i32, channel: u8, controller: u8, value: u8) -> OscPacket {
+    midi_packet(
+        node_id,
+        VST_INSTRUMENT_UGEN_INDEX,
+        [CONTROL_CHANGE | (channel & 0x0F), controller.min(127), value.min(127)],
+    )
+}
+
+/// Silence every note an instrument plays on `channel`.
+pub fn all_notes_off_packet(node_id: i32, channel: u8) -> OscPacket {
+    midi_packet(
+        node_id,
+        VST_INSTRUMENT_UGEN_INDEX,
+        [CONTROL_CHANGE | (channel & 0x0F), ALL_NOTES_OFF, 0],
+    )
+}
+
+/// Node and success of a `/vst_open` reply (`nodeID, ugenIndex, success, ...`).
+pub fn parse_open_reply(args: &[OscType]) -> Option<(i32, bool)> {
+    let node_id = match args.first()? {
+        OscType::Int(id) => *id,
+        OscType::Float(id) => *id as i32,
+        _ => return None,
+    };
+    let success = match args.get(2)? {
+        OscType::Int(v) => *v != 0,
+        OscType::Float(v) => *v != 0.0,
+        _ => return None,
+    };
+    Some((node_id, success))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vst_synthdefs_and_commands() {
+        let synthdefs = create_vst_synthdefs();
+        let names: Vec<&str> = synthdefs.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec![VST_INSTRUMENT_SYNTHDEF, VST_EFFECT_SYNTHDEF]);
+        assert!(synthdefs.iter().all(|(_, bytes)| bytes.starts_with(b"SCgf")));
+
+        let OscPacket::Message(msg) = note_on_packet(1200, 17, 60, 200) else {
+            panic!("expected a message");
+        };
+        assert_eq!(msg.addr, "/u_cmd");
+        assert_eq!(msg.args[..3], [OscType::Int(1200), OscType::Int(1), OscType::String("/midi_msg".to_string())]);
+        // Channels wrap to 0-15 and velocities clamp to 127
+        assert_eq!(msg.args[3], OscType::Blob(vec![0x91, 60, 127]));
+
+        let reply = [OscType::Int(1200), OscType::Int(1), OscType::Float(1.0), OscType::Float(0.0)];
+        assert_eq!(parse_open_reply(&reply), Some((1200, true)));
+        assert_eq!(parse_open_reply(&reply[..2]), None);
+    }
+}
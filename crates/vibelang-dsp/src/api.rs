@@ -9,13 +9,14 @@
 use crate::builder::SynthDef;
 use crate::encoder::encode_synthdef;
 use crate::errors::SynthDefError;
-use crate::graph::GraphIR;
+use crate::graph::{GraphIR, ParamCurve, ParamRange};
 use rhai::{Dynamic, Engine, EvalAltResult, ImmutableString, NativeCallContext, Position};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, OnceLock};
 
 /// Type alias for the deploy callback function
-type DeployCallback = Arc<dyn Fn(Vec<u8>) -> Result<(), String> + Send + Sync>;
+pub type DeployCallback = Arc<dyn Fn(Vec<u8>) -> Result<(), String> + Send + Sync>;
 
 // Global registry of synthdefs
 static SYNTHDEF_REGISTRY: OnceLock<Mutex<HashMap<String, GraphIR>>> = OnceLock::new();
@@ -24,6 +25,17 @@ static EFFECT_REGISTRY: OnceLock<Mutex<HashMap<String, GraphIR>>> = OnceLock::ne
 // Callback for deploying synthdef bytes to scsynth
 static DEPLOY_CALLBACK: OnceLock<Mutex<Option<DeployCallback>>> = OnceLock::new();
 
+/// A deploy target private to one thread, see [`with_deploy_target`].
+struct LocalTarget {
+    deploy: DeployCallback,
+    synthdefs: HashMap<String, GraphIR>,
+    effects: HashMap<String, GraphIR>,
+}
+
+thread_local! {
+    static LOCAL_TARGET: RefCell<Option<LocalTarget>> = const { RefCell::new(None) };
+}
+
 fn get_synthdef_registry() -> &'static Mutex<HashMap<String, GraphIR>> {
     SYNTHDEF_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
 }
@@ -46,7 +58,70 @@ where
     *cb = Some(Arc::new(callback));
 }
 
+/// Run `f` with synthdefs defined on this thread deployed through `callback`
+/// and registered in a registry of their own, e.g. for dry runs.
+///
+/// The global deploy callback and registries stay untouched, so other
+/// threads keep deploying to scsynth meanwhile. Lookups still see globally
+/// registered synthdefs.
+pub fn with_deploy_target<F, R>(callback: F, f: impl FnOnce() -> R) -> R
+where
+    F: Fn(Vec<u8>) -> Result<(), String> + Send + Sync + 'static,
+{
+    struct Restore(Option<LocalTarget>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LOCAL_TARGET.with(|t| *t.borrow_mut() = self.0.take());
+        }
+    }
+
+    let target = LocalTarget {
+        deploy: Arc::new(callback),
+        synthdefs: HashMap::new(),
+        effects: HashMap::new(),
+    };
+    let _restore = Restore(LOCAL_TARGET.with(|t| t.borrow_mut().replace(target)));
+    f()
+}
+
+/// Look up a synthdef or effect IR, in this thread's target first.
+fn lookup_ir<R>(effect: bool, name: &str, f: impl Fn(&GraphIR) -> R) -> Option<R> {
+    let local = LOCAL_TARGET.with(|t| {
+        let t = t.borrow();
+        let t = t.as_ref()?;
+        let registry = if effect { &t.effects } else { &t.synthdefs };
+        registry.get(name).map(&f)
+    });
+    local.or_else(|| {
+        let registry = if effect { get_effect_registry() } else { get_synthdef_registry() };
+        let registry = registry.lock().unwrap();
+        registry.get(name).map(&f)
+    })
+}
+
+/// Register an IR in this thread's target if there is one, globally otherwise.
+fn register_ir(effect: bool, name: String, ir: GraphIR) {
+    let rest = LOCAL_TARGET.with(|t| match t.borrow_mut().as_mut() {
+        Some(t) if effect => {
+            t.effects.insert(name, ir);
+            None
+        }
+        Some(t) => {
+            t.synthdefs.insert(name, ir);
+            None
+        }
+        None => Some((name, ir)),
+    });
+    if let Some((name, ir)) = rest {
+        let registry = if effect { get_effect_registry() } else { get_synthdef_registry() };
+        registry.lock().unwrap().insert(name, ir);
+    }
+}
+
 fn deploy_bytes(bytes: Vec<u8>) -> Result<(), SynthDefError> {
+    if let Some(cb) = LOCAL_TARGET.with(|t| t.borrow().as_ref().map(|t| t.deploy.clone())) {
+        return cb(bytes).map_err(SynthDefError::OscError);
+    }
     let callback = get_deploy_callback().lock().unwrap();
     if let Some(ref cb) = *callback {
         cb(bytes).map_err(SynthDefError::OscError)
@@ -63,10 +138,7 @@ fn synthdef_error_to_eval(err: SynthDefError) -> Box<EvalAltResult> {
 }
 
 fn deploy_synthdef_ir(name: &str, ir: GraphIR) -> crate::errors::Result<()> {
-    {
-        let mut registry = get_synthdef_registry().lock().unwrap();
-        registry.insert(name.to_string(), ir.clone());
-    }
+    register_ir(false, name.to_string(), ir.clone());
 
     log::debug!(
         "[SYNTHDEF] Building synthdef '{}' with {} nodes",
@@ -97,10 +169,7 @@ fn deploy_synthdef_ir(name: &str, ir: GraphIR) -> crate::errors::Result<()> {
 }
 
 fn deploy_fx_ir(name: &str, ir: GraphIR) -> crate::errors::Result<()> {
-    {
-        let mut registry = get_effect_registry().lock().unwrap();
-        registry.insert(name.to_string(), ir.clone());
-    }
+    register_ir(true, name.to_string(), ir.clone());
 
     let bytes = encode_synthdef(&ir)?;
     deploy_bytes(bytes)?;
@@ -109,6 +178,41 @@ fn deploy_fx_ir(name: &str, ir: GraphIR) -> crate::errors::Result<()> {
     Ok(())
 }
 
+/// A numeric Rhai argument (int or float).
+fn number(value: &Dynamic, what: &str) -> Result<f64, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|i| i as f64))
+        .map_err(|_| format!("{} must be a number, got {}", what, value.type_name()).into())
+}
+
+/// Default and range of a ranged `param(...)` call.
+///
+/// `param(name, default, min, max[, unit[, curve]])`, or the short form
+/// `param(name, min, max, unit, curve)` which defaults to `min`.
+fn ranged_param(args: &[Dynamic]) -> Result<(f64, ParamRange), Box<EvalAltResult>> {
+    let short_form = args.len() == 4 && args[2].is_string();
+    let (default, numbers) = if short_form {
+        (None, &args[..2])
+    } else {
+        (Some(&args[0]), &args[1..3])
+    };
+    let min = number(&numbers[0], "param min")?;
+    let max = number(&numbers[1], "param max")?;
+    let rest = &args[if short_form { 2 } else { 3 }..];
+    let unit = rest.first().map(|u| u.to_string()).filter(|u| !u.is_empty());
+    let curve = match rest.get(1) {
+        Some(curve) => ParamCurve::parse(&curve.to_string()).map_err(synthdef_error_to_eval)?,
+        None => ParamCurve::Linear,
+    };
+    let range = ParamRange::new(min as f32, max as f32, unit, curve).map_err(synthdef_error_to_eval)?;
+    let default = match default {
+        Some(default) => number(default, "param default")?,
+        None => min,
+    };
+    Ok((default, range))
+}
+
 /// Builder handle for SynthDef creation via method chaining.
 #[derive(Clone, Debug)]
 pub struct SynthDefBuilderHandle {
@@ -127,6 +231,51 @@ impl SynthDefBuilderHandle {
         self
     }
 
+    pub fn param_range(
+        mut self,
+        name: ImmutableString,
+        default: Dynamic,
+        min: Dynamic,
+        max: Dynamic,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (default, range) = ranged_param(&[default, min, max])?;
+        self.synthdef
+            .arg_range(name.into_owned(), default, range)
+            .map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
+    pub fn param_range_unit(
+        mut self,
+        name: ImmutableString,
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+        d: ImmutableString,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (default, range) = ranged_param(&[a, b, c, d.into()])?;
+        self.synthdef
+            .arg_range(name.into_owned(), default, range)
+            .map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
+    pub fn param_range_curve(
+        mut self,
+        name: ImmutableString,
+        default: Dynamic,
+        min: Dynamic,
+        max: Dynamic,
+        unit: ImmutableString,
+        curve: ImmutableString,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (default, range) = ranged_param(&[default, min, max, unit.into(), curve.into()])?;
+        self.synthdef
+            .arg_range(name.into_owned(), default, range)
+            .map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
     pub fn glide_ms(mut self, name: ImmutableString, ms: f64) -> Self {
         self.synthdef.glide_ms(name.into_owned(), ms);
         self
@@ -137,6 +286,11 @@ impl SynthDefBuilderHandle {
         self
     }
 
+    pub fn diag(mut self, name: ImmutableString) -> Result<Self, Box<EvalAltResult>> {
+        self.synthdef.diag(name.into_owned()).map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
     fn build(self, closure: rhai::FnPtr) -> crate::errors::Result<GraphIR> {
         self.synthdef.build_body_closure_with_options(closure, true)
     }
@@ -168,6 +322,51 @@ impl FxBuilderHandle {
         self
     }
 
+    pub fn param_range(
+        mut self,
+        name: ImmutableString,
+        default: Dynamic,
+        min: Dynamic,
+        max: Dynamic,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (default, range) = ranged_param(&[default, min, max])?;
+        self.synthdef
+            .arg_range(name.into_owned(), default, range)
+            .map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
+    pub fn param_range_unit(
+        mut self,
+        name: ImmutableString,
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+        d: ImmutableString,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (default, range) = ranged_param(&[a, b, c, d.into()])?;
+        self.synthdef
+            .arg_range(name.into_owned(), default, range)
+            .map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
+    pub fn param_range_curve(
+        mut self,
+        name: ImmutableString,
+        default: Dynamic,
+        min: Dynamic,
+        max: Dynamic,
+        unit: ImmutableString,
+        curve: ImmutableString,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let (default, range) = ranged_param(&[default, min, max, unit.into(), curve.into()])?;
+        self.synthdef
+            .arg_range(name.into_owned(), default, range)
+            .map_err(synthdef_error_to_eval)?;
+        Ok(self)
+    }
+
     pub fn glide_ms(mut self, name: ImmutableString, ms: f64) -> Self {
         self.synthdef.glide_ms(name.into_owned(), ms);
         self
@@ -197,12 +396,12 @@ impl FxBuilderHandle {
 
 /// Check if a SynthDef exists in the registry.
 pub fn synthdef_exists(name: &str) -> bool {
-    get_synthdef_registry().lock().unwrap().contains_key(name)
+    lookup_ir(false, name, |_| ()).is_some()
 }
 
 /// Check if an Effect exists in the registry.
 pub fn effect_exists(name: &str) -> bool {
-    get_effect_registry().lock().unwrap().contains_key(name)
+    lookup_ir(true, name, |_| ()).is_some()
 }
 
 /// Check if a name exists as either a synthdef or effect.
@@ -212,14 +411,24 @@ pub fn synthdef_or_effect_exists(name: &str) -> bool {
 
 /// Register a SynthDef IR in the registry (for auto-generated synthdefs).
 pub fn register_synthdef_ir(name: String, ir: GraphIR) {
-    let mut registry = get_synthdef_registry().lock().unwrap();
-    registry.insert(name, ir);
+    register_ir(false, name, ir);
+}
+
+/// Diagnostic outputs declared by a synthdef, in control bus order.
+pub fn get_synthdef_diag_outputs(name: &str) -> Vec<String> {
+    lookup_ir(false, name, |ir| ir.diag_outputs.clone()).unwrap_or_default()
+}
+
+/// Declared parameter ranges of a synthdef or effect.
+pub fn get_param_ranges(name: &str) -> HashMap<String, ParamRange> {
+    lookup_ir(false, name, |ir| ir.param_ranges.clone())
+        .or_else(|| lookup_ir(true, name, |ir| ir.param_ranges.clone()))
+        .unwrap_or_default()
 }
 
 /// Get default parameter values for a synthdef.
 pub fn get_synthdef_param_defaults(name: &str) -> HashMap<String, f32> {
-    let registry = get_synthdef_registry().lock().unwrap();
-    if let Some(ir) = registry.get(name) {
+    lookup_ir(false, name, |ir| {
         let mut defaults = HashMap::new();
         for param in &ir.params {
             if param.default.len() == 1 {
@@ -227,15 +436,13 @@ pub fn get_synthdef_param_defaults(name: &str) -> HashMap<String, f32> {
             }
         }
         defaults
-    } else {
-        HashMap::new()
-    }
+    })
+    .unwrap_or_default()
 }
 
 /// Get default parameter values for an effect.
 pub fn get_effect_param_defaults(name: &str) -> HashMap<String, f32> {
-    let registry = get_effect_registry().lock().unwrap();
-    if let Some(ir) = registry.get(name) {
+    lookup_ir(true, name, |ir| {
         let mut defaults = HashMap::new();
         for param in &ir.params {
             if param.default.len() == 1 {
@@ -243,9 +450,8 @@ pub fn get_effect_param_defaults(name: &str) -> HashMap<String, f32> {
             }
         }
         defaults
-    } else {
-        HashMap::new()
-    }
+    })
+    .unwrap_or_default()
 }
 
 /// Register the SynthDef and FX builder types and functions with a Rhai engine.
@@ -254,13 +460,20 @@ pub fn register_synthdef_api(engine: &mut Engine) {
     engine
         .register_type::<SynthDefBuilderHandle>()
         .register_fn("param", SynthDefBuilderHandle::param)
+        .register_fn("param", SynthDefBuilderHandle::param_range)
+        .register_fn("param", SynthDefBuilderHandle::param_range_unit)
+        .register_fn("param", SynthDefBuilderHandle::param_range_curve)
         .register_fn("glide_ms", SynthDefBuilderHandle::glide_ms)
         .register_fn("out_bus", SynthDefBuilderHandle::out_bus)
+        .register_fn("diag", SynthDefBuilderHandle::diag)
         .register_fn("body", SynthDefBuilderHandle::body);
 
     engine
         .register_type::<FxBuilderHandle>()
         .register_fn("param", FxBuilderHandle::param)
+        .register_fn("param", FxBuilderHandle::param_range)
+        .register_fn("param", FxBuilderHandle::param_range_unit)
+        .register_fn("param", FxBuilderHandle::param_range_curve)
         .register_fn("glide_ms", FxBuilderHandle::glide_ms)
         .register_fn("channels", FxBuilderHandle::channels)
         .register_fn("body", FxBuilderHandle::body);
@@ -5,17 +5,66 @@
 
 use crate::encoder::encode_synthdef;
 use crate::errors::{Result, SynthDefError};
-use crate::graph::{clear_active_builder, set_active_builder, GraphBuilderInner, GraphIR, Input, Rate};
+use crate::graph::{
+    clear_active_builder, set_active_builder, GraphBuilderInner, GraphIR, Input, ParamRange, Rate,
+};
+use std::collections::HashMap;
 use crate::helpers;
 use crate::rhainodes::{self, NodeRef};
 use crate::ugens::register_generated_ugens;
 
+/// Parameters that generated synthdefs can ramp on the server.
+///
+/// Each one gets a hidden `<name>_lag` control (seconds, default 0) and the body
+/// sees the value through a linear `VarLag`, so the runtime can send a fade as a
+/// single `n_set <name>_lag <secs> <name> <target>` instead of one per tick.
+/// Mono voices glide between notes through `freq_lag`.
+pub const RAMP_PARAMS: &[&str] = &["amp", "cutoff", "freq"];
+
+/// Control holding the first control bus of a synth's diagnostic outputs.
+///
+/// Synthdefs that declare diagnostic outputs (`.diag("env")`) get this hidden
+/// control; `diag(name, signal)` in the body writes the signal to
+/// `diag_bus + <declaration index>` with `ReplaceOut.kr`, so the runtime can
+/// read it back with `/c_getn`.
+pub const DIAG_BUS_PARAM: &str = "diag_bus";
+
+/// Default `diag_bus`: scratch control buses near the top of scsynth's default
+/// 16384, written by synths nobody is monitoring.
+pub const DIAG_SCRATCH_BUS: f32 = 16256.0;
+
+/// Most diagnostic outputs one synthdef can declare.
+pub const MAX_DIAG_OUTPUTS: usize = 8;
+
+/// Name of the lag control that ramps `param`.
+pub fn ramp_lag_control(param: &str) -> String {
+    format!("{}_lag", param)
+}
+
+/// Route a parameter through `VarLag`, driven by its lag control.
+///
+/// The lag starts at the parameter's initial value so new synths don't ramp in.
+fn ramp_param(builder: &mut GraphBuilderInner, name: &str, param: NodeRef) -> Option<NodeRef> {
+    let lag_name = ramp_lag_control(name);
+    let lag = builder.params.iter().find(|p| p.name == lag_name)?;
+    let lag_ref = NodeRef(0xFFFFFFFF - lag.index as u32);
+    Some(builder.add_node(
+        "VarLag".to_string(),
+        Rate::Control,
+        vec![param.to_input(), lag_ref.to_input(), param.to_input()],
+        1,
+        0,
+    ))
+}
+
 /// SynthDef builder.
 #[derive(Clone, Debug)]
 pub struct SynthDef {
     pub name: String,
     pub params: Vec<(String, f32, Option<f32>)>, // (name, default, lag_ms)
     pub out_bus_tag: Option<String>,
+    pub diag_outputs: Vec<String>,
+    pub param_ranges: HashMap<String, ParamRange>,
 }
 
 impl SynthDef {
@@ -25,6 +74,8 @@ impl SynthDef {
             name,
             params: Vec::new(),
             out_bus_tag: None,
+            diag_outputs: Vec::new(),
+            param_ranges: HashMap::new(),
         }
     }
 
@@ -34,6 +85,26 @@ impl SynthDef {
         self
     }
 
+    /// Add a float parameter with a declared range.
+    ///
+    /// Fails if the default lies outside the range.
+    pub fn arg_range(&mut self, name: String, default: f64, range: ParamRange) -> Result<&mut Self> {
+        if !range.contains(default as f32) {
+            return Err(SynthDefError::ValidationError(format!(
+                "Default {} of parameter '{}' is outside its range {}",
+                default,
+                name,
+                range.describe()
+            )));
+        }
+        match self.params.iter_mut().find(|(existing, _, _)| *existing == name) {
+            Some(param) => param.1 = default as f32,
+            None => self.params.push((name.clone(), default as f32, None)),
+        }
+        self.param_ranges.insert(name, range);
+        Ok(self)
+    }
+
     /// Set glide/lag time for a parameter in milliseconds.
     pub fn glide_ms(&mut self, name: String, ms: f64) -> &mut Self {
         // Find the param and set its lag
@@ -54,6 +125,36 @@ impl SynthDef {
         self
     }
 
+    /// Declare a diagnostic output, written in the body with `diag(name, signal)`.
+    pub fn diag(&mut self, name: String) -> Result<&mut Self> {
+        if !self.diag_outputs.contains(&name) {
+            if self.diag_outputs.len() >= MAX_DIAG_OUTPUTS {
+                return Err(SynthDefError::ValidationError(format!(
+                    "Too many diagnostic outputs (max {})",
+                    MAX_DIAG_OUTPUTS
+                )));
+            }
+            self.diag_outputs.push(name);
+        }
+        Ok(self)
+    }
+
+    /// Declared parameters that get a server-side ramp (see [`RAMP_PARAMS`]).
+    ///
+    /// A parameter is skipped if its lag control is declared explicitly.
+    fn ramped_params(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .map(|(name, _, _)| name)
+            .filter(|name| RAMP_PARAMS.contains(&name.as_str()))
+            .filter(|name| {
+                let lag_name = ramp_lag_control(name);
+                !self.params.iter().any(|(other, _, _)| *other == lag_name)
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Execute the body using a Rhai closure.
     /// The closure receives the parameters as arguments (as NodeRefs).
     pub fn build_body_closure(self, closure: rhai::FnPtr) -> Result<GraphIR> {
@@ -90,6 +191,13 @@ impl SynthDef {
             builder.add_param(name.clone(), vec![*default], *lag_ms);
         }
 
+        // Hidden lag controls for server-side fades
+        let ramped = self.ramped_params();
+        for name in &ramped {
+            builder.add_param(ramp_lag_control(name), vec![0.0], None);
+        }
+        builder.param_ranges = self.param_ranges.clone();
+
         builder.create_control_ugen();
 
         // Helper to get encoded NodeRefs for parameters
@@ -117,7 +225,11 @@ impl SynthDef {
                 .ok_or_else(|| {
                     SynthDefError::ValidationError(format!("Missing FX param {}", name))
                 })?;
-            param_nodes.push(rhai::Dynamic::from(NodeRef(0xFFFFFFFF - spec.index as u32)));
+            let mut node = NodeRef(0xFFFFFFFF - spec.index as u32);
+            if ramped.contains(name) {
+                node = ramp_param(&mut builder, name, node).unwrap_or(node);
+            }
+            param_nodes.push(rhai::Dynamic::from(node));
         }
 
         // Activate builder and construct the fixed In.ar input
@@ -343,6 +455,19 @@ impl SynthDef {
             builder.add_param("out".to_string(), vec![0.0], None);
         }
 
+        // Hidden lag controls for server-side fades
+        let ramped = self.ramped_params();
+        for name in &ramped {
+            builder.add_param(ramp_lag_control(name), vec![0.0], None);
+        }
+
+        // Hidden bus control for diagnostic outputs
+        if !self.diag_outputs.is_empty() {
+            builder.add_param(DIAG_BUS_PARAM.to_string(), vec![DIAG_SCRATCH_BUS], None);
+            builder.diag_outputs = self.diag_outputs.clone();
+        }
+        builder.param_ranges = self.param_ranges.clone();
+
         // Create the Control UGen node for parameters (must be first node, at index 0)
         builder.create_control_ugen();
 
@@ -351,11 +476,16 @@ impl SynthDef {
         // The slot_index is the parameter's index in the flattened parameter array, not its array index
         // This will be handled specially when converting to Input::Node
         let mut param_nodes = Vec::new();
-        for (i, _) in params.iter().enumerate() {
+        for (i, (name, _, _)) in params.iter().enumerate() {
             // Get the slot index from the ParamSpec (not the array index i)
             let slot_index = builder.params[i].index;
             // Use a special encoding: 0xFFFFFFFF - slot_index to indicate this is a parameter
-            param_nodes.push(rhai::Dynamic::from(NodeRef(0xFFFFFFFF - slot_index as u32)));
+            let mut node = NodeRef(0xFFFFFFFF - slot_index as u32);
+            // Ramped params reach the body through their VarLag
+            if ramped.contains(name) {
+                node = ramp_param(&mut builder, name, node).unwrap_or(node);
+            }
+            param_nodes.push(rhai::Dynamic::from(node));
         }
 
         // Set as active
@@ -653,3 +783,82 @@ pub fn get_param_defaults(ir: &GraphIR) -> std::collections::HashMap<String, f32
     }
     defaults
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramped_params() {
+        let mut def = SynthDef::new("pad".to_string());
+        def.arg_f("freq".to_string(), 440.0)
+            .arg_f("amp".to_string(), 0.5)
+            .arg_f("cutoff".to_string(), 2000.0)
+            .arg_f("cutoff_lag".to_string(), 0.1);
+        // cutoff declares its own lag control, so only freq and amp are ramped
+        assert_eq!(def.ramped_params(), vec!["freq".to_string(), "amp".to_string()]);
+    }
+
+    #[test]
+    fn test_ramp_param_adds_varlag() {
+        let mut builder = GraphBuilderInner::new();
+        let amp = builder.add_param("amp".to_string(), vec![0.5], None);
+        builder.add_param(ramp_lag_control("amp"), vec![0.0], None);
+        builder.create_control_ugen();
+
+        let amp_ref = NodeRef(0xFFFFFFFF - builder.params[amp as usize].index as u32);
+        let ramped = ramp_param(&mut builder, "amp", amp_ref).expect("lag control exists");
+        let node = &builder.nodes[ramped.id() as usize];
+        assert_eq!(node.name, "VarLag");
+        assert!(matches!(node.inputs[0], Input::Node { node_id: 0, output_index: 0 }));
+        assert!(matches!(node.inputs[1], Input::Node { node_id: 0, output_index: 1 }));
+        assert!(matches!(node.inputs[2], Input::Node { node_id: 0, output_index: 0 }));
+
+        assert!(ramp_param(&mut builder, "cutoff", amp_ref).is_none());
+    }
+
+    #[test]
+    fn test_diag_writes_control_bus() {
+        let mut def = SynthDef::new("lead".to_string());
+        def.diag("env".to_string()).unwrap().diag("trig".to_string()).unwrap();
+        assert_eq!(def.diag_outputs, vec!["env".to_string(), "trig".to_string()]);
+
+        let mut builder = GraphBuilderInner::new();
+        builder.add_param(DIAG_BUS_PARAM.to_string(), vec![DIAG_SCRATCH_BUS], None);
+        builder.diag_outputs = def.diag_outputs.clone();
+        builder.create_control_ugen();
+        let signal = builder.add_node("SinOsc".to_string(), Rate::Audio, vec![], 1, 0);
+        set_active_builder(builder);
+        let result = helpers::diag("trig", signal);
+        let undeclared = helpers::diag("lfo", signal);
+        let builder = clear_active_builder().unwrap();
+
+        assert_eq!(result.unwrap(), signal);
+        assert!(undeclared.is_err());
+        let names: Vec<&str> = builder.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["Control", "SinOsc", "BinaryOpUGen", "A2K", "ReplaceOut"]);
+        let out = builder.nodes.last().unwrap();
+        assert_eq!(out.rate, Rate::Control);
+        assert!(matches!(out.inputs[0], Input::Node { node_id: 2, output_index: 0 }));
+    }
+
+    #[test]
+    fn test_param_ranges() {
+        use crate::graph::ParamCurve;
+
+        let cutoff = ParamRange::new(20.0, 20000.0, Some("hz".to_string()), ParamCurve::Exponential).unwrap();
+        assert_eq!(cutoff.clamp(50000.0), 20000.0);
+        assert_eq!(cutoff.clamp(f32::NAN), 20.0);
+        assert!((cutoff.from_normalized(0.5) - 632.46).abs() < 0.1);
+        assert!((cutoff.to_normalized(632.46) - 0.5).abs() < 1e-4);
+        assert!(ParamRange::new(0.0, 1.0, None, ParamCurve::Exponential).is_err());
+        assert!(ParamRange::new(1.0, 1.0, None, ParamCurve::Linear).is_err());
+
+        let mut def = SynthDef::new("filter".to_string());
+        def.arg_f("cutoff".to_string(), 100.0);
+        def.arg_range("cutoff".to_string(), 1200.0, cutoff.clone()).unwrap();
+        assert_eq!(def.params, vec![("cutoff".to_string(), 1200.0, None)]);
+        assert!(def.arg_range("res".to_string(), 2.0, ParamRange::new(0.0, 1.0, None, ParamCurve::Linear).unwrap()).is_err());
+        assert_eq!(def.param_ranges.get("cutoff"), Some(&cutoff));
+    }
+}
@@ -7,6 +7,7 @@
 //! - [`Input`] - Input to a UGen (constant or node output)
 //! - [`UGenNode`] - A node in the synthesis graph
 //! - [`ParamSpec`] - Parameter specification with defaults
+//! - [`ParamRange`] - Declared value range of a parameter
 //! - [`GraphBuilderInner`] - Mutable graph construction state
 //! - [`GraphIR`] - Immutable graph ready for encoding
 
@@ -36,7 +37,7 @@ impl Rate {
 }
 
 /// Input to a UGen - either a constant or another node's output.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Input {
     /// A constant value.
     Constant(f32),
@@ -49,8 +50,21 @@ pub enum Input {
     },
 }
 
+impl Input {
+    /// Output `output_index` of node `node_id`.
+    ///
+    /// In graphs with parameters, node 0 is the Control UGen, so
+    /// `Input::node(0, i)` reads parameter slot `i`.
+    pub fn node(node_id: u32, output_index: u32) -> Self {
+        Input::Node {
+            node_id,
+            output_index,
+        }
+    }
+}
+
 /// A UGen node in the graph.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UGenNode {
     /// UGen class name (e.g., "SinOsc", "EnvGen").
     pub name: String,
@@ -77,6 +91,113 @@ pub struct ParamSpec {
     pub lag_ms: Option<f32>,
 }
 
+/// How a parameter's range maps onto a 0..1 control (MIDI CC, UI slider).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParamCurve {
+    /// Even steps across the range.
+    #[default]
+    Linear,
+    /// Even steps per octave/ratio, for frequencies and times.
+    Exponential,
+}
+
+impl ParamCurve {
+    /// Parse a curve name ("lin"/"linear", "exp"/"exponential").
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "lin" | "linear" => Ok(ParamCurve::Linear),
+            "exp" | "exponential" => Ok(ParamCurve::Exponential),
+            other => Err(SynthDefError::ValidationError(format!(
+                "Unknown parameter curve '{}' (expected \"lin\" or \"exp\")",
+                other
+            ))),
+        }
+    }
+
+    /// Short name ("lin" or "exp").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParamCurve::Linear => "lin",
+            ParamCurve::Exponential => "exp",
+        }
+    }
+}
+
+/// Declared value range of a parameter.
+///
+/// Incoming values are clamped to it, and 0..1 controls are scaled along
+/// its curve.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamRange {
+    /// Lowest accepted value.
+    pub min: f32,
+    /// Highest accepted value.
+    pub max: f32,
+    /// Display unit, e.g. "hz" or "ms".
+    pub unit: Option<String>,
+    /// Mapping from 0..1 controls.
+    pub curve: ParamCurve,
+}
+
+impl ParamRange {
+    /// Create a range, checking that it is usable with its curve.
+    pub fn new(min: f32, max: f32, unit: Option<String>, curve: ParamCurve) -> Result<Self> {
+        if !min.is_finite() || !max.is_finite() || min >= max {
+            return Err(SynthDefError::ValidationError(format!(
+                "Invalid parameter range {}..{} (min must be below max)",
+                min, max
+            )));
+        }
+        if curve == ParamCurve::Exponential && min <= 0.0 {
+            return Err(SynthDefError::ValidationError(format!(
+                "Exponential parameter range {}..{} must be above zero",
+                min, max
+            )));
+        }
+        Ok(Self { min, max, unit, curve })
+    }
+
+    /// Whether a value lies within the range.
+    pub fn contains(&self, value: f32) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+
+    /// Clamp a value into the range.
+    pub fn clamp(&self, value: f32) -> f32 {
+        if value.is_nan() {
+            self.min
+        } else {
+            value.clamp(self.min, self.max)
+        }
+    }
+
+    /// Map a 0..1 control position onto the range.
+    pub fn from_normalized(&self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        match self.curve {
+            ParamCurve::Linear => self.min + position * (self.max - self.min),
+            ParamCurve::Exponential => self.min * (self.max / self.min).powf(position),
+        }
+    }
+
+    /// The 0..1 control position of a value.
+    pub fn to_normalized(&self, value: f32) -> f32 {
+        let value = self.clamp(value);
+        match self.curve {
+            ParamCurve::Linear => (value - self.min) / (self.max - self.min),
+            ParamCurve::Exponential => (value / self.min).ln() / (self.max / self.min).ln(),
+        }
+    }
+
+    /// Human-readable form, e.g. "20..20000 hz".
+    pub fn describe(&self) -> String {
+        match &self.unit {
+            Some(unit) => format!("{}..{} {}", self.min, self.max, unit),
+            None => format!("{}..{}", self.min, self.max),
+        }
+    }
+}
+
 /// The mutable state of a graph builder.
 ///
 /// This is used during synthdef construction to accumulate nodes,
@@ -94,6 +215,10 @@ pub struct GraphBuilderInner {
     pub out_bus: i32,
     /// Optional tag for the output bus (for routing).
     pub out_bus_tag: Option<String>,
+    /// Declared diagnostic outputs, in control bus order.
+    pub diag_outputs: Vec<String>,
+    /// Declared parameter ranges by parameter name.
+    pub param_ranges: HashMap<String, ParamRange>,
 }
 
 impl Default for GraphBuilderInner {
@@ -112,6 +237,8 @@ impl GraphBuilderInner {
             param_map: HashMap::new(),
             out_bus: 0,
             out_bus_tag: None,
+            diag_outputs: Vec::new(),
+            param_ranges: HashMap::new(),
         }
     }
 
@@ -204,6 +331,23 @@ impl GraphBuilderInner {
         max_rate
     }
 
+    /// Add `In.ar(NumOutputBuses.ir + input, channels)`, the graph of sclang's
+    /// `SoundIn.ar`: hardware inputs follow the hardware outputs on the bus array.
+    ///
+    /// `input` is the first hardware input channel, 0-based. Returns the `In`
+    /// node, with one output per channel.
+    pub fn add_sound_in(&mut self, input: Input, channels: u32) -> NodeRef {
+        let num_outputs = self.add_node("NumOutputBuses".to_string(), Rate::Scalar, vec![], 1, 0);
+        let bus = self.add_node(
+            "BinaryOpUGen".to_string(),
+            Rate::Control,
+            vec![Input::node(num_outputs.0, 0), input],
+            1,
+            0, // addition
+        );
+        self.add_node("In".to_string(), Rate::Audio, vec![Input::node(bus.0, 0)], channels, 0)
+    }
+
     /// Create the Control UGen node for parameters.
     ///
     /// This should be called after all parameters are added, before any
@@ -279,6 +423,10 @@ pub struct GraphIR {
     pub nodes: Vec<UGenNode>,
     /// Output bus number.
     pub out_bus: i32,
+    /// Diagnostic outputs written to control buses from `diag_bus` on.
+    pub diag_outputs: Vec<String>,
+    /// Declared parameter ranges by parameter name.
+    pub param_ranges: HashMap<String, ParamRange>,
 }
 
 impl GraphIR {
@@ -290,6 +438,8 @@ impl GraphIR {
             params: builder.params,
             nodes: builder.nodes,
             out_bus: builder.out_bus,
+            diag_outputs: builder.diag_outputs,
+            param_ranges: builder.param_ranges,
         }
     }
 
@@ -298,6 +448,15 @@ impl GraphIR {
         self.params.iter().map(|p| p.default.len()).sum()
     }
 
+    /// UGens named `name` with their node indices, in graph order.
+    pub fn ugens<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (u32, &'a UGenNode)> + 'a {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.name == name)
+            .map(|(id, node)| (id as u32, node))
+    }
+
     /// Validate the graph structure before encoding.
     pub fn validate(&self) -> Result<()> {
         // Check that Control UGen is at index 0 if params exist
@@ -434,6 +593,25 @@ mod tests {
         assert_eq!(builder.total_param_slots(), 2);
     }
 
+    #[test]
+    fn test_add_sound_in() {
+        let mut builder = GraphBuilderInner::new();
+        builder.add_param("input".to_string(), vec![0.0], None);
+        builder.create_control_ugen();
+        let input = builder.add_sound_in(Input::node(0, 0), 2);
+
+        let ir = GraphIR::from_builder("sound_in".to_string(), builder);
+        assert!(ir.validate().is_ok());
+        let (num_outputs, _) = ir.ugens("NumOutputBuses").next().unwrap();
+        let (bus, add) = ir.ugens("BinaryOpUGen").next().unwrap();
+        assert_eq!(add.inputs, vec![Input::node(num_outputs, 0), Input::node(0, 0)]);
+        assert_eq!(add.special_index, 0);
+        let (id, node) = ir.ugens("In").next().unwrap();
+        assert_eq!(id, input.0);
+        assert_eq!((node.rate, node.num_outputs), (Rate::Audio, 2));
+        assert_eq!(node.inputs, vec![Input::node(bus, 0)]);
+    }
+
     #[test]
     fn test_graph_ir_validation() {
         let builder = GraphBuilderInner::new();
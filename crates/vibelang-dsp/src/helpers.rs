@@ -917,6 +917,44 @@ pub fn replace_out_ar_n(bus: NodeRef, channels: Array) -> Result<NodeRef> {
     with_builder(|builder| builder.add_node("ReplaceOut".to_string(), Rate::Audio, inputs, 0, 0))
 }
 
+/// Write a diagnostic signal (envelope, trigger pulses, ...) to its control bus.
+///
+/// The name must be declared on the synthdef with `.diag(name)`; the bus is
+/// `diag_bus` plus the declaration index. Returns the signal unchanged so the
+/// call can wrap an expression.
+pub fn diag(name: &str, signal: NodeRef) -> Result<NodeRef> {
+    with_builder(|builder| {
+        let index = builder.diag_outputs.iter().position(|n| n == name).ok_or_else(|| {
+            SynthDefError::ValidationError(format!(
+                "Diagnostic output '{}' is not declared (add .diag(\"{}\") to the synthdef)",
+                name, name
+            ))
+        })?;
+        let bus_param = builder
+            .get_param(crate::builder::DIAG_BUS_PARAM)
+            .ok_or_else(|| SynthDefError::ValidationError("Synthdef has no diagnostic bus".to_string()))?;
+        let slot = builder.params[bus_param as usize].index as u32;
+        let mut bus = Input::Node { node_id: 0, output_index: slot };
+        if index > 0 {
+            builder.add_constant(index as f32);
+            let offset = builder.add_node(
+                "BinaryOpUGen".to_string(),
+                Rate::Control,
+                vec![bus, Input::Constant(index as f32)],
+                1,
+                0, // Add
+            );
+            bus = offset.to_input();
+        }
+        let mut value = signal.to_input();
+        if builder.max_rate_from_inputs(std::slice::from_ref(&value)) == Rate::Audio {
+            value = builder.add_node("A2K".to_string(), Rate::Control, vec![value], 1, 0).to_input();
+        }
+        builder.add_node("ReplaceOut".to_string(), Rate::Control, vec![bus, value], 0, 0);
+        Ok(signal)
+    })?
+}
+
 /// Register all helper functions with the Rhai engine.
 pub fn register_helpers(engine: &mut rhai::Engine) {
     // Register Env type
@@ -947,6 +985,9 @@ pub fn register_helpers(engine: &mut rhai::Engine) {
     engine.register_fn("in_ar", |bus: NodeRef, num_channels: i64| in_ar_n(bus, num_channels as f64).unwrap());
     engine.register_fn("replace_out_ar", |bus: f64, channels: Array| replace_out_ar(bus, channels).unwrap());
     engine.register_fn("replace_out_ar", |bus: NodeRef, channels: Array| replace_out_ar_n(bus, channels).unwrap());
+    engine.register_fn("diag", |name: &str, signal: NodeRef| -> std::result::Result<NodeRef, Box<rhai::EvalAltResult>> {
+        diag(name, signal).map_err(|e| e.to_string().into())
+    });
 
     // Hardware audio input (line-in, microphone)
     engine.register_fn("sound_in", |num_channels: f64| sound_in(num_channels).unwrap());
@@ -34,19 +34,21 @@ mod ugens {
 }
 
 pub use api::{
-    register_synthdef_api, set_deploy_callback, synthdef_exists, effect_exists,
-    synthdef_or_effect_exists, get_synthdef_param_defaults, get_effect_param_defaults,
+    register_synthdef_api, set_deploy_callback, with_deploy_target,
+    DeployCallback, synthdef_exists, effect_exists,
+    synthdef_or_effect_exists, get_synthdef_param_defaults, get_synthdef_diag_outputs,
+    get_effect_param_defaults, get_param_ranges,
     register_synthdef_ir, SynthDefBuilderHandle, FxBuilderHandle,
 };
-pub use builder::SynthDef;
+pub use builder::{ramp_lag_control, SynthDef, DIAG_BUS_PARAM, DIAG_SCRATCH_BUS, MAX_DIAG_OUTPUTS, RAMP_PARAMS};
 pub use encoder::encode_synthdef;
 pub use errors::{Result, SynthDefError};
 pub use graph::{
     clear_active_builder, set_active_builder, with_builder, GraphBuilderInner, GraphIR, Input,
-    ParamSpec, Rate, UGenNode,
+    ParamCurve, ParamRange, ParamSpec, Rate, UGenNode,
 };
 pub use helpers::{
-    amp_to_db, channel, channels, db_to_amp, detune_spread, dup, env_gen, env_gen_with_env,
+    amp_to_db, channel, channels, db_to_amp, detune_spread, diag, dup, env_gen, env_gen_with_env,
     env_gen_with_env_n, in_ar, in_ar_n, mix, replace_out_ar, replace_out_ar_n, Env, EnvGenBuilder,
 };
 pub use rhainodes::{register_node_ref, NodeRef};
@@ -0,0 +1,199 @@
+//! Zeroconf (mDNS/DNS-SD) discovery of running sessions.
+//!
+//! A session whose HTTP API was bound to a LAN address advertises itself as
+//! `_vibelang._tcp` with its name, port and version, so `vibe list-sessions`
+//! and the editor extensions find it without a host and port being
+//! configured. Only sessions that sandbox `/eval` are advertised, with the
+//! name of their sandbox profile; a session running any script it is sent
+//! stays off the network's radar.
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::routes::schema::API_VERSION;
+
+/// DNS-SD service type of VibeLang sessions.
+pub const SERVICE_TYPE: &str = "_vibelang._tcp.local.";
+
+/// A session advertised on the network; withdrawn when dropped.
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Advertisement {
+    /// The service instance name other hosts see.
+    pub fn fullname(&self) -> &str {
+        &self.fullname
+    }
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        // Say goodbye, so browsers drop the session right away
+        if let Ok(status) = self.daemon.unregister(&self.fullname) {
+            let _ = status.recv_timeout(Duration::from_millis(500));
+        }
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Whether a session whose API listens on `addr` is advertised: only when
+/// it was bound to an address other machines can reach and `/eval`
+/// requests without a token run in the `sandbox` profile.
+pub fn should_advertise(addr: SocketAddr, sandbox: Option<&str>) -> bool {
+    !addr.ip().is_loopback() && sandbox.is_some()
+}
+
+/// Advertise a session named `name` whose API listens on `addr`.
+///
+/// `version` is the VibeLang version; the API version is added as `api`.
+/// `sandbox` is the profile `/eval` requests without a token run in. A
+/// session bound to all interfaces is advertised with all of the machine's
+/// addresses.
+pub fn advertise(name: &str, addr: SocketAddr, version: &str, sandbox: &str) -> mdns_sd::Result<Advertisement> {
+    let daemon = ServiceDaemon::new()?;
+    let host = local_host_name();
+    let api = API_VERSION.to_string();
+    let ip = if addr.ip().is_unspecified() { String::new() } else { addr.ip().to_string() };
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &instance_name(name, &host, addr.port()),
+        &format!("{}.local.", host),
+        ip.as_str(),
+        addr.port(),
+        &[("name", name), ("version", version), ("api", api.as_str()), ("sandbox", sandbox)][..],
+    )?;
+    let info = if addr.ip().is_unspecified() { info.enable_addr_auto() } else { info };
+    let fullname = info.get_fullname().to_string();
+    daemon.register(info)?;
+    Ok(Advertisement { daemon, fullname })
+}
+
+/// A running session found on the network.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscoveredSession {
+    /// Session name (the script's name).
+    pub name: String,
+    /// Host name of the machine running it.
+    pub host: String,
+    /// Addresses of that machine.
+    pub addresses: Vec<String>,
+    /// Port of the session's HTTP API.
+    pub port: u16,
+    /// VibeLang version of the session.
+    pub version: Option<String>,
+    /// API version of the session.
+    pub api_version: Option<String>,
+    /// Sandbox profile of `/eval` requests without a token.
+    pub sandbox: Option<String>,
+}
+
+impl DiscoveredSession {
+    fn from_info(info: &ServiceInfo) -> Self {
+        let mut addresses: Vec<_> = info.get_addresses().iter().collect();
+        // IPv4 first, it is what most clients expect to paste
+        addresses.sort_by_key(|addr| (addr.is_ipv6(), **addr));
+        let instance = info.get_fullname().trim_end_matches(SERVICE_TYPE).trim_end_matches('.');
+        Self {
+            name: info.get_property_val_str("name").unwrap_or(instance).to_string(),
+            host: info.get_hostname().trim_end_matches('.').to_string(),
+            addresses: addresses.iter().map(|addr| addr.to_string()).collect(),
+            port: info.get_port(),
+            version: info.get_property_val_str("version").map(str::to_string),
+            api_version: info.get_property_val_str("api").map(str::to_string),
+            sandbox: info.get_property_val_str("sandbox").map(str::to_string),
+        }
+    }
+
+    /// Base URL of the session's HTTP API.
+    pub fn url(&self) -> String {
+        match self.addresses.first() {
+            Some(addr) if addr.contains(':') => format!("http://[{}]:{}", addr, self.port),
+            Some(addr) => format!("http://{}:{}", addr, self.port),
+            None => format!("http://{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// Browse the network for sessions for `timeout`.
+pub fn browse(timeout: Duration) -> mdns_sd::Result<Vec<DiscoveredSession>> {
+    let daemon = ServiceDaemon::new()?;
+    let events = daemon.browse(SERVICE_TYPE)?;
+    let deadline = Instant::now() + timeout;
+
+    let mut sessions = BTreeMap::new();
+    while let Ok(event) = events.recv_deadline(deadline) {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                sessions.insert(info.get_fullname().to_string(), DiscoveredSession::from_info(&info));
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                sessions.remove(&fullname);
+            }
+            _ => {}
+        }
+    }
+
+    let _ = daemon.shutdown();
+    Ok(sessions.into_values().collect())
+}
+
+/// Instance name, unique per host and port (DNS-SD names can't contain dots).
+fn instance_name(name: &str, host: &str, port: u16) -> String {
+    format!("{} on {} ({})", name, host, port).replace('.', "_")
+}
+
+/// This machine's host name, without domain.
+fn local_host_name() -> String {
+    short_host_name(&gethostname::gethostname().to_string_lossy())
+}
+
+/// First label of a host name, `vibelang` if there is none.
+fn short_host_name(host: &str) -> String {
+    match host.trim().split('.').next() {
+        Some(label) if !label.is_empty() => label.to_string(),
+        _ => "vibelang".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovered_session_from_service_info() {
+        let instance = instance_name("set.v2", "studio", 1607);
+        assert_eq!(instance, "set_v2 on studio (1607)");
+
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            "studio.local.",
+            "fe80::1,192.168.1.20",
+            1607,
+            &[("name", "set.v2"), ("version", "0.2.0"), ("api", "1"), ("sandbox", "workshop")][..],
+        )
+        .unwrap();
+        let session = DiscoveredSession::from_info(&info);
+        assert_eq!(session.name, "set.v2");
+        assert_eq!(session.host, "studio.local");
+        assert_eq!(session.addresses, vec!["192.168.1.20", "fe80::1"]);
+        assert_eq!(session.version.as_deref(), Some("0.2.0"));
+        assert_eq!(session.api_version.as_deref(), Some("1"));
+        assert_eq!(session.sandbox.as_deref(), Some("workshop"));
+        assert_eq!(session.url(), "http://192.168.1.20:1607");
+
+        // Only sandboxed sessions reachable from other machines are advertised
+        let lan = SocketAddr::from(([0, 0, 0, 0], 1606));
+        assert!(!should_advertise(SocketAddr::from(([127, 0, 0, 1], 1606)), Some("workshop")));
+        assert!(!should_advertise(lan, None));
+        assert!(should_advertise(lan, Some("workshop")));
+
+        assert_eq!(short_host_name("studio.example.com\n"), "studio");
+        assert_eq!(short_host_name(""), "vibelang");
+    }
+}
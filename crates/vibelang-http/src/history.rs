@@ -0,0 +1,310 @@
+//! Session history: an audit log of all API mutations.
+//!
+//! Every non-GET request is recorded with its payload, response status and
+//! the transport position at which it arrived. Entries are kept in memory for
+//! `GET /history` and optionally appended to a JSONL file so a set can be
+//! analysed (or reconstructed) after the show.
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::models::{HistoryEntry, HistoryQuery};
+use crate::websocket::WebSocketEvent;
+use crate::AppState;
+
+/// Maximum number of entries kept in memory.
+const MAX_IN_MEMORY_ENTRIES: usize = 10_000;
+
+/// Maximum body size of a mutating request; larger ones are refused with 413.
+const MAX_PAYLOAD_BYTES: usize = 1024 * 1024;
+
+/// In-memory history with optional JSONL persistence.
+pub struct HistoryLog {
+    entries: Mutex<VecDeque<HistoryEntry>>,
+    file: Option<Mutex<File>>,
+    path: Option<PathBuf>,
+}
+
+impl HistoryLog {
+    /// Create a history log that only keeps entries in memory.
+    pub fn in_memory() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            file: None,
+            path: None,
+        }
+    }
+
+    /// Create a history log that also appends every entry to `path`.
+    ///
+    /// Falls back to in-memory only if the file cannot be opened.
+    pub fn with_file(path: &Path) -> Self {
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => {
+                log::info!("[HISTORY] Recording API mutations to {}", path.display());
+                Self {
+                    entries: Mutex::new(VecDeque::new()),
+                    file: Some(Mutex::new(file)),
+                    path: Some(path.to_path_buf()),
+                }
+            }
+            Err(e) => {
+                log::error!(
+                    "[HISTORY] Failed to open history file {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::in_memory()
+            }
+        }
+    }
+
+    /// Path of the JSONL file, if persisting.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Record an entry.
+    pub fn record(&self, entry: HistoryEntry) {
+        if let Some(file) = &self.file {
+            match serde_json::to_string(&entry) {
+                Ok(line) => {
+                    let mut file = file.lock().unwrap();
+                    if let Err(e) = writeln!(file, "{}", line) {
+                        log::warn!("[HISTORY] Failed to write history entry: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("[HISTORY] Failed to serialize history entry: {}", e),
+            }
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.push_back(entry);
+        if entries.len() > MAX_IN_MEMORY_ENTRIES {
+            entries.pop_front();
+        }
+    }
+
+    /// Query the in-memory entries.
+    pub fn query(&self, query: &HistoryQuery) -> Vec<HistoryEntry> {
+        let entries = self.entries.lock().unwrap();
+        filter_entries(entries.iter().cloned(), query)
+    }
+}
+
+/// Apply a history query to a sequence of entries (oldest first).
+pub fn filter_entries(
+    entries: impl Iterator<Item = HistoryEntry>,
+    query: &HistoryQuery,
+) -> Vec<HistoryEntry> {
+    let mut matched: Vec<HistoryEntry> = entries
+        .filter(|e| query.since_bar.is_none_or(|bar| e.bar >= bar))
+        .filter(|e| query.until_bar.is_none_or(|bar| e.bar <= bar))
+        .filter(|e| {
+            query
+                .path
+                .as_ref()
+                .is_none_or(|p| e.path.contains(p.as_str()))
+        })
+        .collect();
+
+    if let Some(limit) = query.limit {
+        if matched.len() > limit {
+            matched.drain(..matched.len() - limit);
+        }
+    }
+    matched
+}
+
+/// Read history entries from a JSONL file.
+///
+/// Malformed lines are skipped with a warning.
+pub fn read_history_file(path: &Path) -> std::io::Result<Vec<HistoryEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<HistoryEntry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!("[HISTORY] Skipping line {}: {}", idx + 1, e),
+        }
+    }
+    Ok(entries)
+}
+
+/// Buffer a request body, refusing bodies over [`MAX_PAYLOAD_BYTES`].
+///
+/// The body can't be passed on once partly read, so a body that can't be
+/// buffered fails the request instead of reaching the handler empty.
+async fn read_body(body: Body) -> Result<Bytes, Response> {
+    to_bytes(body, MAX_PAYLOAD_BYTES).await.map_err(|e| {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("request body could not be read (limit {} bytes): {}", MAX_PAYLOAD_BYTES, e),
+        )
+            .into_response()
+    })
+}
+
+/// Middleware that records every mutating request.
+pub async fn record_mutations(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let method = request.method().to_string();
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    // Buffer the body so it can be logged and still passed on
+    let (parts, body) = request.into_parts();
+    let bytes = match read_body(body).await {
+        Ok(bytes) => bytes,
+        Err(response) => {
+            log::warn!("[HISTORY] Refused {} {}: request body could not be read", method, path);
+            return response;
+        }
+    };
+    let payload = if bytes.is_empty() {
+        None
+    } else {
+        Some(
+            serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned())
+            }),
+        )
+    };
+
+    let (beat, beats_per_bar) = state
+        .handle
+        .with_state(|s| (s.current_beat, s.time_signature.beats_per_bar()));
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0);
+    let entry = HistoryEntry {
+        timestamp,
+        beat,
+        bar: (beat / beats_per_bar).floor() as i64 + 1,
+        method,
+        path,
+        status: response.status().as_u16(),
+        payload,
+    };
+
+    let _ = state.ws_tx.send(WebSocketEvent {
+        event_type: "history.entry".to_string(),
+        timestamp,
+        data: serde_json::to_value(&entry).ok(),
+    });
+    state.history.record(entry);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(bar: i64, path: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: 0.0,
+            beat: (bar - 1) as f64 * 4.0,
+            bar,
+            method: "POST".to_string(),
+            path: path.to_string(),
+            status: 200,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_by_bar_range() {
+        let entries = vec![entry(90, "/a"), entry(92, "/b"), entry(95, "/c")];
+        let q = HistoryQuery {
+            since_bar: Some(91),
+            until_bar: Some(93),
+            ..Default::default()
+        };
+        let result = filter_entries(entries.into_iter(), &q);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "/b");
+    }
+
+    #[test]
+    fn test_filter_by_path_and_limit() {
+        let entries = vec![
+            entry(1, "/voices/kick/trigger"),
+            entry(2, "/groups/drums/mute"),
+            entry(3, "/voices/bass/trigger"),
+            entry(4, "/voices/kick/mute"),
+        ];
+        let q = HistoryQuery {
+            path: Some("/voices".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let result = filter_entries(entries.into_iter(), &q);
+        let paths: Vec<_> = result.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/voices/bass/trigger", "/voices/kick/mute"]);
+    }
+
+    #[test]
+    fn test_history_file_roundtrip() {
+        let path = std::env::temp_dir().join(format!(
+            "vibelang-history-test-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let log = HistoryLog::with_file(&path);
+        let mut e = entry(3, "/transport/start");
+        e.payload = Some(serde_json::json!({ "bpm": 128 }));
+        log.record(e);
+        log.record(entry(4, "/transport/stop"));
+
+        let read = read_history_file(&path).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].payload, Some(serde_json::json!({ "bpm": 128 })));
+        assert_eq!(read[1].path, "/transport/stop");
+        assert_eq!(log.query(&HistoryQuery::default()).len(), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_oversized_body_is_refused() {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let small = rt.block_on(read_body(Body::from(r#"{"bpm":128}"#))).unwrap();
+        assert_eq!(&small[..], br#"{"bpm":128}"#);
+
+        let large = Body::from(vec![b'x'; MAX_PAYLOAD_BYTES + 1]);
+        let response = rt.block_on(read_body(large)).unwrap_err();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
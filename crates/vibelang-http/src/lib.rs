@@ -6,11 +6,35 @@
 //! # Features
 //!
 //! - Full CRUD operations for voices, patterns, melodies, sequences
+//! - Idempotent upserts (`PUT /voices/{name}` etc.) with versioned schemas
+//!   at `GET /schema`, used by the Python client in `clients/python`
 //! - Transport control (play, stop, seek, tempo)
 //! - Effect and sample management
-//! - MIDI routing and recording
-//! - Real-time WebSocket events
+//! - MIDI routing and recording, with per-pattern takes
+//! - Real-time WebSocket events, including full state snapshots for
+//!   read-only mirrors (`vibe mirror`)
 //! - Live state queries (active synths, meters)
+//! - Browser-based control surface at `/ui`
+//! - Session history of all API mutations (`GET /history`, optional JSONL file)
+//! - Dry runs (`?dry_run=true`) of deletions and stopping evals, previewing
+//!   the synths they would stop, and optional confirmation tokens
+//!   (`PUT /safety`) so UIs can warn before sound is cut off
+//! - Live set cue list with GO (`POST /cues/next`)
+//! - Session files: the full session as JSON (`GET`/`PUT /session`), saved
+//!   and loaded on the server (`POST /session/save`, `POST /session/load`)
+//! - Playback graph control (cue, variables) for adaptive music
+//! - Macro controls (`PUT /macros/{name}`) driving many parameters at once
+//! - Automation recording of live parameter tweaks (`POST /automation/record`),
+//!   replayed on every loop pass
+//! - Liveness and readiness probes (`/healthz`, `/readyz`) with scsynth,
+//!   stdlib and message queue detail
+//! - Sandboxed `/eval` for untrusted clients, selected per server or per
+//!   bearer token; violations are reported as structured 403 errors
+//! - Port auto-selection: [`bind_available_port`] moves on to the next port
+//!   when the requested one is busy, so several sessions can run side by side;
+//!   the CLI binds the loopback interface unless told otherwise
+//! - Zeroconf discovery: sandboxed sessions served on the LAN advertise
+//!   `_vibelang._tcp` with their sandbox profile ([`discovery`])
 //!
 //! # Usage
 //!
@@ -20,31 +44,53 @@
 //!
 //! let (eval_tx, eval_rx) = std::sync::mpsc::channel();
 //! tokio::spawn(async move {
-//!     start_server(handle, 1606, Some(eval_tx)).await;
+//!     if let Err(e) = start_server(handle, 1606, Some(eval_tx), None).await {
+//!         log::error!("HTTP API server failed: {}", e);
+//!     }
 //! });
 //! ```
 
+pub mod discovery;
+mod history;
+mod mirror;
 mod models;
+mod peaks;
 mod routes;
+mod safety;
 mod websocket;
 
 use axum::{
     routing::{delete, get, patch, post, put},
     Router,
 };
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tower_http::cors::{Any, CorsLayer};
 use vibelang_core::RuntimeHandle;
 
+pub use history::{filter_entries, read_history_file, HistoryLog};
+pub use mirror::*;
 pub use models::*;
+pub use peaks::PeaksCache;
 pub use routes::eval::{EvalJob, EvalResult};
+pub use routes::schema::API_VERSION;
+pub use safety::Safety;
 pub use websocket::WebSocketEvent;
 
 /// Sender type for eval requests.
 pub type EvalSender = std::sync::mpsc::Sender<EvalJob>;
 
+/// Port the API is served on unless it is busy or another is chosen.
+pub const DEFAULT_PORT: u16 = 1606;
+
+/// Address the API is served on unless another is chosen: only this machine.
+pub const DEFAULT_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+/// How many ports [`bind_available_port`] tries before giving up.
+pub const PORT_ATTEMPTS: u16 = 16;
+
 /// Shared application state for HTTP handlers.
 pub struct AppState {
     /// Runtime handle for state access and message sending.
@@ -53,15 +99,24 @@ pub struct AppState {
     pub ws_tx: broadcast::Sender<WebSocketEvent>,
     /// Channel to send eval requests to the main thread (optional).
     pub eval_tx: Option<EvalSender>,
+    /// Log of API mutations.
+    pub history: HistoryLog,
+    /// Cached waveform peaks, keyed by sample file path and resolution.
+    pub peaks_cache: PeaksCache,
+    /// Confirmation of destructive requests.
+    pub safety: Safety,
 }
 
-/// Start the HTTP server on the specified port.
+/// Start the HTTP server on the specified port of [`DEFAULT_HOST`].
+///
+/// Returns when the server stops; fails if the port can't be bound.
 ///
 /// # Arguments
 ///
 /// * `handle` - The VibeLang runtime handle for accessing state
 /// * `port` - The port to listen on
 /// * `eval_tx` - Optional channel to send code evaluation requests to the main thread
+/// * `history_path` - Optional JSONL file to append API mutations to
 ///
 /// # Example
 ///
@@ -69,10 +124,64 @@ pub struct AppState {
 /// let handle = runtime.handle();
 /// let (eval_tx, eval_rx) = std::sync::mpsc::channel();
 /// tokio::spawn(async move {
-///     start_server(handle, 1606, Some(eval_tx)).await;
+///     if let Err(e) = start_server(handle, 1606, Some(eval_tx), None).await {
+///         log::error!("HTTP API server failed: {}", e);
+///     }
 /// });
 /// ```
-pub async fn start_server(handle: RuntimeHandle, port: u16, eval_tx: Option<EvalSender>) {
+pub async fn start_server(
+    handle: RuntimeHandle,
+    port: u16,
+    eval_tx: Option<EvalSender>,
+    history_path: Option<PathBuf>,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(SocketAddr::new(DEFAULT_HOST, port)).await?;
+    serve(listener, handle, eval_tx, history_path).await
+}
+
+/// Bind the first free port of `host` from `port` on, trying
+/// [`PORT_ATTEMPTS`] ports.
+///
+/// Binding before the server starts lets the caller report the chosen port.
+/// The listener is non-blocking, ready for [`serve_std`].
+pub fn bind_available_port(host: IpAddr, port: u16) -> std::io::Result<std::net::TcpListener> {
+    let mut last_error = None;
+    for candidate in (port..=u16::MAX).take(PORT_ATTEMPTS as usize) {
+        match std::net::TcpListener::bind(SocketAddr::new(host, candidate)) {
+            Ok(listener) => {
+                listener.set_nonblocking(true)?;
+                return Ok(listener);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                log::debug!("HTTP port {} is busy, trying the next one", candidate);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| std::io::Error::from(std::io::ErrorKind::AddrInUse)))
+}
+
+/// Serve the API on a listener from [`bind_available_port`].
+///
+/// Returns when the server stops, with the I/O error that stopped it.
+pub async fn serve_std(
+    listener: std::net::TcpListener,
+    handle: RuntimeHandle,
+    eval_tx: Option<EvalSender>,
+    history_path: Option<PathBuf>,
+) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    serve(listener, handle, eval_tx, history_path).await
+}
+
+/// Serve the API on a bound listener.
+async fn serve(
+    listener: tokio::net::TcpListener,
+    handle: RuntimeHandle,
+    eval_tx: Option<EvalSender>,
+    history_path: Option<PathBuf>,
+) -> std::io::Result<()> {
     // Create broadcast channel for WebSocket events
     let (ws_tx, _) = broadcast::channel::<WebSocketEvent>(1024);
 
@@ -80,6 +189,12 @@ pub async fn start_server(handle: RuntimeHandle, port: u16, eval_tx: Option<Eval
         handle: handle.clone(),
         ws_tx: ws_tx.clone(),
         eval_tx,
+        history: match history_path {
+            Some(path) => HistoryLog::with_file(&path),
+            None => HistoryLog::in_memory(),
+        },
+        peaks_cache: PeaksCache::new(),
+        safety: Safety::new(),
     });
 
     // Start the event broadcaster in the background
@@ -91,104 +206,122 @@ pub async fn start_server(handle: RuntimeHandle, port: u16, eval_tx: Option<Eval
 
     // Build the router with all routes
     let app = Router::new()
+        // Liveness and readiness probes
+        .route("/healthz", get(routes::health::healthz))
+        .route("/readyz", get(routes::health::readyz))
+        // Schema of the object API
+        .route("/schema", get(routes::schema::get_schema))
         // Transport
         .route("/transport", get(routes::transport::get_transport))
         .route("/transport", patch(routes::transport::update_transport))
         .route("/transport/start", post(routes::transport::start_transport))
         .route("/transport/stop", post(routes::transport::stop_transport))
         .route("/transport/seek", post(routes::transport::seek_transport))
+        .route("/transport/jump", post(routes::transport::jump_transport))
         // Groups
         .route("/groups", get(routes::groups::list_groups))
         .route("/groups", post(routes::groups::create_group))
-        .route("/groups/:path", get(routes::groups::get_group))
-        .route("/groups/:path", patch(routes::groups::update_group))
-        .route("/groups/:path", delete(routes::groups::delete_group))
-        .route("/groups/:path/mute", post(routes::groups::mute_group))
-        .route("/groups/:path/unmute", post(routes::groups::unmute_group))
-        .route("/groups/:path/solo", post(routes::groups::solo_group))
-        .route("/groups/:path/unsolo", post(routes::groups::unsolo_group))
+        .route("/groups/tree", get(routes::groups::group_tree))
+        .route("/groups/{path}", get(routes::groups::get_group))
+        .route("/groups/{path}", patch(routes::groups::update_group))
+        .route("/groups/{path}", delete(routes::groups::delete_group))
+        .route("/groups/{path}/mute", post(routes::groups::mute_group))
+        .route("/groups/{path}/unmute", post(routes::groups::unmute_group))
+        .route("/groups/{path}/solo", post(routes::groups::solo_group))
+        .route("/groups/{path}/unsolo", post(routes::groups::unsolo_group))
+        .route("/groups/{path}/freeze", post(routes::groups::freeze_group))
+        .route("/groups/{path}/unfreeze", post(routes::groups::unfreeze_group))
         .route(
-            "/groups/:path/params/:param",
+            "/groups/{path}/params/{param}",
             put(routes::groups::set_group_param),
         )
         // Voices
         .route("/voices", get(routes::voices::list_voices))
         .route("/voices", post(routes::voices::create_voice))
-        .route("/voices/:name", get(routes::voices::get_voice))
-        .route("/voices/:name", patch(routes::voices::update_voice))
-        .route("/voices/:name", delete(routes::voices::delete_voice))
-        .route("/voices/:name/trigger", post(routes::voices::trigger_voice))
-        .route("/voices/:name/stop", post(routes::voices::stop_voice))
-        .route("/voices/:name/note-on", post(routes::voices::note_on))
-        .route("/voices/:name/note-off", post(routes::voices::note_off))
+        .route("/voices/{name}", get(routes::voices::get_voice))
+        .route("/voices/{name}", put(routes::voices::put_voice))
+        .route("/voices/{name}", patch(routes::voices::update_voice))
+        .route("/voices/{name}", delete(routes::voices::delete_voice))
+        .route("/voices/{name}/trigger", post(routes::voices::trigger_voice))
+        .route("/voices/{name}/stop", post(routes::voices::stop_voice))
+        .route("/voices/{name}/note-on", post(routes::voices::note_on))
+        .route("/voices/{name}/note-off", post(routes::voices::note_off))
+        .route("/voices/{name}/params", patch(routes::voices::set_voice_params))
+        .route("/voices/{name}/randomize", post(routes::voices::randomize_voice_params))
+        .route("/voices/{name}/morph", post(routes::voices::morph_voice_params))
         .route(
-            "/voices/:name/params/:param",
+            "/voices/{name}/params/{param}",
             put(routes::voices::set_voice_param),
         )
-        .route("/voices/:name/mute", post(routes::voices::mute_voice))
-        .route("/voices/:name/unmute", post(routes::voices::unmute_voice))
+        .route("/voices/{name}/mute", post(routes::voices::mute_voice))
+        .route("/voices/{name}/unmute", post(routes::voices::unmute_voice))
         // Patterns
         .route("/patterns", get(routes::patterns::list_patterns))
         .route("/patterns", post(routes::patterns::create_pattern))
-        .route("/patterns/:name", get(routes::patterns::get_pattern))
-        .route("/patterns/:name", patch(routes::patterns::update_pattern))
-        .route("/patterns/:name", delete(routes::patterns::delete_pattern))
-        .route("/patterns/:name/start", post(routes::patterns::start_pattern))
-        .route("/patterns/:name/stop", post(routes::patterns::stop_pattern))
+        .route("/patterns/{name}", get(routes::patterns::get_pattern))
+        .route("/patterns/{name}", put(routes::patterns::put_pattern))
+        .route("/patterns/{name}", patch(routes::patterns::update_pattern))
+        .route("/patterns/{name}", delete(routes::patterns::delete_pattern))
+        .route("/patterns/{name}/start", post(routes::patterns::start_pattern))
+        .route("/patterns/{name}/stop", post(routes::patterns::stop_pattern))
         // Melodies
         .route("/melodies", get(routes::melodies::list_melodies))
         .route("/melodies", post(routes::melodies::create_melody))
-        .route("/melodies/:name", get(routes::melodies::get_melody))
-        .route("/melodies/:name", patch(routes::melodies::update_melody))
-        .route("/melodies/:name", delete(routes::melodies::delete_melody))
-        .route("/melodies/:name/start", post(routes::melodies::start_melody))
-        .route("/melodies/:name/stop", post(routes::melodies::stop_melody))
+        .route("/melodies/{name}", get(routes::melodies::get_melody))
+        .route("/melodies/{name}", put(routes::melodies::put_melody))
+        .route("/melodies/{name}", patch(routes::melodies::update_melody))
+        .route("/melodies/{name}", delete(routes::melodies::delete_melody))
+        .route("/melodies/{name}/start", post(routes::melodies::start_melody))
+        .route("/melodies/{name}/stop", post(routes::melodies::stop_melody))
         // Sequences
         .route("/sequences", get(routes::sequences::list_sequences))
         .route("/sequences", post(routes::sequences::create_sequence))
-        .route("/sequences/:name", get(routes::sequences::get_sequence))
-        .route("/sequences/:name", patch(routes::sequences::update_sequence))
-        .route("/sequences/:name", delete(routes::sequences::delete_sequence))
+        .route("/sequences/{name}", get(routes::sequences::get_sequence))
+        .route("/sequences/{name}", put(routes::sequences::put_sequence))
+        .route("/sequences/{name}", patch(routes::sequences::update_sequence))
+        .route("/sequences/{name}", delete(routes::sequences::delete_sequence))
         .route(
-            "/sequences/:name/start",
+            "/sequences/{name}/start",
             post(routes::sequences::start_sequence),
         )
         .route(
-            "/sequences/:name/stop",
+            "/sequences/{name}/stop",
             post(routes::sequences::stop_sequence),
         )
         .route(
-            "/sequences/:name/pause",
+            "/sequences/{name}/pause",
             post(routes::sequences::pause_sequence),
         )
         // Effects
         .route("/effects", get(routes::effects::list_effects))
         .route("/effects", post(routes::effects::create_effect))
-        .route("/effects/:id", get(routes::effects::get_effect))
-        .route("/effects/:id", patch(routes::effects::update_effect))
-        .route("/effects/:id", delete(routes::effects::delete_effect))
+        .route("/effects/{id}", get(routes::effects::get_effect))
+        .route("/effects/{id}", patch(routes::effects::update_effect))
+        .route("/effects/{id}", delete(routes::effects::delete_effect))
         .route(
-            "/effects/:id/params/:param",
+            "/effects/{id}/params/{param}",
             put(routes::effects::set_effect_param),
         )
         // Samples
         .route("/samples", get(routes::samples::list_samples))
         .route("/samples", post(routes::samples::load_sample))
-        .route("/samples/:id", get(routes::samples::get_sample))
-        .route("/samples/:id", delete(routes::samples::free_sample))
+        .route("/samples/{id}", get(routes::samples::get_sample))
+        .route("/samples/{id}", delete(routes::samples::free_sample))
+        .route("/samples/{id}/preview", post(routes::samples::preview_sample))
+        .route("/samples/{id}/peaks", get(routes::samples::get_sample_peaks))
         // SynthDefs
         .route("/synthdefs", get(routes::synthdefs::list_synthdefs))
-        .route("/synthdefs/:name", get(routes::synthdefs::get_synthdef))
+        .route("/synthdefs/{name}", get(routes::synthdefs::get_synthdef))
         // Eval
         .route("/eval", post(routes::eval::eval_code))
         // Fades
         .route("/fades", get(routes::fades::list_fades))
         .route("/fades", post(routes::fades::create_fade))
-        .route("/fades/:id", delete(routes::fades::cancel_fade))
+        .route("/fades/{id}", delete(routes::fades::cancel_fade))
         // MIDI
         .route("/midi/devices", get(routes::midi::list_devices))
-        .route("/midi/devices/:id", post(routes::midi::connect_device))
-        .route("/midi/devices/:id", delete(routes::midi::disconnect_device))
+        .route("/midi/devices/{id}", post(routes::midi::connect_device))
+        .route("/midi/devices/{id}", delete(routes::midi::disconnect_device))
         .route("/midi/routing", get(routes::midi::get_routing))
         .route("/midi/routing/keyboard", get(routes::midi::list_keyboard_routes))
         .route("/midi/routing/keyboard", post(routes::midi::add_keyboard_route))
@@ -201,6 +334,13 @@ pub async fn start_server(handle: RuntimeHandle, port: u16, eval_tx: Option<Eval
         .route("/midi/recording", patch(routes::midi::update_recording_settings))
         .route("/midi/recording/notes", get(routes::midi::get_recorded_notes))
         .route("/midi/recording/export", get(routes::midi::export_recording))
+        .route("/midi/takes", get(routes::midi::list_takes))
+        .route("/midi/takes", post(routes::midi::arm_take))
+        .route("/midi/takes/stop", post(routes::midi::stop_take))
+        .route("/midi/takes/audition", delete(routes::midi::end_audition))
+        .route("/midi/takes/{id}", delete(routes::midi::discard_take))
+        .route("/midi/takes/{id}/audition", post(routes::midi::audition_take))
+        .route("/midi/takes/{id}/keep", post(routes::midi::keep_take))
         .route("/midi/monitor", post(routes::midi::set_monitor))
         // Live state
         .route("/live", get(routes::live::get_live_state))
@@ -208,8 +348,50 @@ pub async fn start_server(handle: RuntimeHandle, port: u16, eval_tx: Option<Eval
         .route("/live/sequences", get(routes::live::get_active_sequences))
         .route("/live/notes", get(routes::live::get_active_notes))
         .route("/live/meters", get(routes::live::get_meters))
+        .route("/live/loudness", get(routes::live::get_loudness))
+        .route("/live/loudness/target", put(routes::live::set_loudness_target))
+        .route("/live/loudness/reset", post(routes::live::reset_loudness))
+        .route("/live/performance", get(routes::live::get_performance))
+        .route("/live/performance/policy", patch(routes::live::update_cpu_policy))
+        // Panic: silence all sounding notes
+        .route("/panic", post(routes::live::panic))
+        // Playback graphs
+        .route("/graphs", get(routes::graphs::list_graphs))
+        .route("/graphs/{name}", get(routes::graphs::get_graph))
+        .route("/graphs/{name}/start", post(routes::graphs::start_graph))
+        .route("/graphs/{name}/stop", post(routes::graphs::stop_graph))
+        .route("/graphs/{name}/cue", post(routes::graphs::cue_graph))
+        .route("/graphs/{name}/vars/{var}", put(routes::graphs::set_graph_var))
+        // Macro controls
+        .route("/macros", get(routes::macros::list_macros))
+        .route("/macros/{name}", get(routes::macros::get_macro).put(routes::macros::set_macro))
+        // Automation recorded from live tweaks
+        .route("/automation", get(routes::automation::get_automation).delete(routes::automation::clear_automation))
+        .route("/automation/record", post(routes::automation::start_recording))
+        .route("/automation/stop", post(routes::automation::stop_recording))
+        // Cues (live set)
+        .route("/cues", get(routes::cues::get_cues))
+        .route("/cues/next", post(routes::cues::next_cue))
+        .route("/cues/back", post(routes::cues::previous_cue))
+        // Session files
+        .route("/session", get(routes::session::get_session).put(routes::session::put_session))
+        .route("/session/save", post(routes::session::save_session))
+        .route("/session/load", post(routes::session::load_session))
+        // History
+        .route("/history", get(routes::history::get_history))
+        // Confirmation of destructive requests
+        .route("/safety", get(routes::safety::get_safety).put(routes::safety::put_safety))
         // WebSocket
         .route("/ws", get(websocket::ws_handler))
+        // Web UI
+        .route("/ui", get(routes::ui::index))
+        .route("/ui/", get(routes::ui::index))
+        .route("/ui/{*path}", get(routes::ui::asset))
+        // Record mutations to the session history
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            history::record_mutations,
+        ))
         // Add shared state
         .with_state(state)
         // Add CORS middleware
@@ -220,13 +402,32 @@ pub async fn start_server(handle: RuntimeHandle, port: u16, eval_tx: Option<Eval
                 .allow_headers(Any),
         );
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    log::info!(
-        "HTTP API server starting on http://{}:{}",
-        addr.ip(),
-        addr.port()
-    );
+    if let Ok(addr) = listener.local_addr() {
+        log::info!(
+            "HTTP API server starting on http://{}:{}",
+            addr.ip(),
+            addr.port()
+        );
+    }
+
+    axum::serve(listener, app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_available_port_skips_busy_ports() {
+        let busy = std::net::TcpListener::bind(SocketAddr::new(DEFAULT_HOST, 0)).unwrap();
+        let busy_port = busy.local_addr().unwrap().port();
+        if busy_port > u16::MAX - PORT_ATTEMPTS {
+            return;
+        }
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+        let listener = bind_available_port(DEFAULT_HOST, busy_port).unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(addr.port() > busy_port && addr.port() < busy_port + PORT_ATTEMPTS);
+        assert!(addr.ip().is_loopback());
+    }
 }
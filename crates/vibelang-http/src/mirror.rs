@@ -0,0 +1,494 @@
+//! Read-only session mirroring.
+//!
+//! Clients subscribed to `state.snapshot` on the WebSocket receive a
+//! [`MirrorSnapshot`] of what is displayed about the session: transport,
+//! the group/voice/pattern/melody/effect tree, sequences and the meters.
+//! `vibe mirror` applies these snapshots to a local [`ScriptState`] to show
+//! the TUI on another machine (e.g. a venue screen) without any audio.
+//!
+//! Audio-side details (synth nodes, buffers, samples, patterns' events) are
+//! not part of a snapshot.
+
+use crate::WebSocketEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+use vibelang_core::api::context::SourceLocation;
+use vibelang_core::performance::ServerStatus;
+use vibelang_core::state::{
+    ActiveSequence, EffectState, GroupState, LoopStatus, LoudnessState, MelodyState, MeterLevel,
+    PatternState, ScriptState, VoiceState,
+};
+use vibelang_core::{ClipMode, ClipSource, SequenceClip, SequenceDefinition, TimeSignature};
+
+/// WebSocket event type carrying a [`MirrorSnapshot`].
+pub const SNAPSHOT_EVENT: &str = "state.snapshot";
+
+/// Displayable state of a running session.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MirrorSnapshot {
+    /// State version of the mirrored session.
+    pub version: u64,
+    pub tempo: f64,
+    pub current_beat: f64,
+    pub transport_running: bool,
+    pub numerator: u32,
+    pub denominator: u32,
+    pub groups: Vec<MirrorGroup>,
+    pub voices: Vec<MirrorVoice>,
+    pub patterns: Vec<MirrorLoop>,
+    pub melodies: Vec<MirrorLoop>,
+    pub effects: Vec<MirrorEffect>,
+    pub sequences: Vec<MirrorSequence>,
+    /// Group meters by group path: peak left/right, RMS left/right.
+    pub meters: HashMap<String, [f32; 4]>,
+    pub loudness: MirrorLoudness,
+    pub performance: MirrorPerformance,
+    pub next_buffer_id: i32,
+    pub next_audio_bus: i32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorGroup {
+    pub name: String,
+    pub path: String,
+    pub parent_path: Option<String>,
+    pub params: HashMap<String, f32>,
+    pub muted: bool,
+    pub soloed: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorVoice {
+    pub name: String,
+    pub group_path: String,
+    pub synth_name: Option<String>,
+    pub polyphony: i64,
+    pub gain: f64,
+    pub params: HashMap<String, f32>,
+    pub muted: bool,
+    pub soloed: bool,
+}
+
+/// A pattern or melody.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorLoop {
+    pub name: String,
+    pub group_path: String,
+    pub voice_name: Option<String>,
+    pub params: HashMap<String, f32>,
+    pub status: MirrorLoopStatus,
+}
+
+/// Serializable [`LoopStatus`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum MirrorLoopStatus {
+    Stopped,
+    Queued { start_beat: f64 },
+    Playing { start_beat: f64 },
+    QueuedStop { start_beat: f64, stop_beat: f64 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorEffect {
+    pub id: String,
+    pub synthdef_name: String,
+    pub group_path: String,
+    pub position: usize,
+    pub params: HashMap<String, f32>,
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorSequence {
+    pub name: String,
+    pub loop_beats: f64,
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+    pub play_once: bool,
+    pub clips: Vec<MirrorClip>,
+    /// Anchor beat, if the sequence is playing.
+    pub anchor_beat: Option<f64>,
+    pub paused: bool,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorClip {
+    pub start: f64,
+    pub end: f64,
+    /// Source type ("pattern", "melody", "fade" or "sequence").
+    pub kind: String,
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MirrorLoudness {
+    pub momentary_lufs: Option<f64>,
+    pub short_term_lufs: Option<f64>,
+    pub integrated_lufs: Option<f64>,
+    pub target_lufs: Option<f64>,
+    pub over_target: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MirrorPerformance {
+    pub avg_cpu: Option<f32>,
+    pub peak_cpu: Option<f32>,
+    pub over_budget: bool,
+    pub late_events: u64,
+    pub stale_dropped_events: u64,
+}
+
+impl From<&LoopStatus> for MirrorLoopStatus {
+    fn from(status: &LoopStatus) -> Self {
+        match *status {
+            LoopStatus::Stopped => Self::Stopped,
+            LoopStatus::Queued { start_beat } => Self::Queued { start_beat },
+            LoopStatus::Playing { start_beat } => Self::Playing { start_beat },
+            LoopStatus::QueuedStop { start_beat, stop_beat } => Self::QueuedStop { start_beat, stop_beat },
+        }
+    }
+}
+
+impl From<MirrorLoopStatus> for LoopStatus {
+    fn from(status: MirrorLoopStatus) -> Self {
+        match status {
+            MirrorLoopStatus::Stopped => Self::Stopped,
+            MirrorLoopStatus::Queued { start_beat } => Self::Queued { start_beat },
+            MirrorLoopStatus::Playing { start_beat } => Self::Playing { start_beat },
+            MirrorLoopStatus::QueuedStop { start_beat, stop_beat } => Self::QueuedStop { start_beat, stop_beat },
+        }
+    }
+}
+
+impl MirrorSnapshot {
+    /// Capture the displayable state of a session.
+    pub fn capture(state: &ScriptState) -> Self {
+        let loop_of = |name: &String, group_path: &String, voice_name: &Option<String>, params: &HashMap<String, f32>, status: &LoopStatus| MirrorLoop {
+            name: name.clone(),
+            group_path: group_path.clone(),
+            voice_name: voice_name.clone(),
+            params: params.clone(),
+            status: status.into(),
+        };
+
+        Self {
+            version: state.version,
+            tempo: state.tempo,
+            current_beat: state.current_beat,
+            transport_running: state.transport_running,
+            numerator: state.time_signature.numerator,
+            denominator: state.time_signature.denominator,
+            groups: state
+                .groups
+                .values()
+                .map(|g| MirrorGroup {
+                    name: g.name.clone(),
+                    path: g.path.clone(),
+                    parent_path: g.parent_path.clone(),
+                    params: g.params.clone(),
+                    muted: g.muted,
+                    soloed: g.soloed,
+                })
+                .collect(),
+            voices: state
+                .voices
+                .values()
+                .map(|v| MirrorVoice {
+                    name: v.name.clone(),
+                    group_path: v.group_path.clone(),
+                    synth_name: v.synth_name.clone(),
+                    polyphony: v.polyphony,
+                    gain: v.gain,
+                    params: v.params.clone(),
+                    muted: v.muted,
+                    soloed: v.soloed,
+                })
+                .collect(),
+            patterns: state
+                .patterns
+                .values()
+                .map(|p| loop_of(&p.name, &p.group_path, &p.voice_name, &p.params, &p.status))
+                .collect(),
+            melodies: state
+                .melodies
+                .values()
+                .map(|m| loop_of(&m.name, &m.group_path, &m.voice_name, &m.params, &m.status))
+                .collect(),
+            effects: state
+                .effects
+                .values()
+                .map(|e| MirrorEffect {
+                    id: e.id.clone(),
+                    synthdef_name: e.synthdef_name.clone(),
+                    group_path: e.group_path.clone(),
+                    position: e.position,
+                    params: e.params.clone(),
+                    voice: e.voice.clone(),
+                })
+                .collect(),
+            sequences: state
+                .sequences
+                .values()
+                .map(|s| {
+                    let active = state.active_sequences.get(&s.name);
+                    MirrorSequence {
+                        name: s.name.clone(),
+                        loop_beats: s.loop_beats,
+                        speed: s.speed,
+                        play_once: s.play_once,
+                        clips: s
+                            .clips
+                            .iter()
+                            .map(|c| MirrorClip {
+                                start: c.start,
+                                end: c.end,
+                                kind: c.source.type_name().to_string(),
+                                source: c.source.name().to_string(),
+                            })
+                            .collect(),
+                        anchor_beat: active.map(|a| a.anchor_beat),
+                        paused: active.is_some_and(|a| a.paused),
+                    }
+                })
+                .collect(),
+            meters: state
+                .meter_levels
+                .iter()
+                .map(|(path, m)| (path.clone(), [m.peak_left, m.peak_right, m.rms_left, m.rms_right]))
+                .collect(),
+            loudness: MirrorLoudness {
+                momentary_lufs: state.loudness.momentary_lufs,
+                short_term_lufs: state.loudness.short_term_lufs,
+                integrated_lufs: state.loudness.integrated_lufs,
+                target_lufs: state.loudness.target_lufs,
+                over_target: state.loudness.over_target,
+            },
+            performance: MirrorPerformance {
+                avg_cpu: state.performance.status.as_ref().map(|s| s.avg_cpu),
+                peak_cpu: state.performance.status.as_ref().map(|s| s.peak_cpu),
+                over_budget: state.performance.budget.over_budget,
+                late_events: state.performance.late_events,
+                stale_dropped_events: state.performance.stale_dropped_events,
+            },
+            next_buffer_id: state.next_buffer_id,
+            next_audio_bus: state.next_audio_bus,
+        }
+    }
+
+    /// Parse a WebSocket message, returning the snapshot if it carries one.
+    pub fn from_event(text: &str) -> Option<Self> {
+        let event: WebSocketEvent = serde_json::from_str(text).ok()?;
+        if event.event_type != SNAPSHOT_EVENT {
+            return None;
+        }
+        serde_json::from_value(event.data?).ok()
+    }
+
+    /// Replace the displayable state of `state` with this snapshot.
+    pub fn apply(self, state: &mut ScriptState) {
+        let now = Some(Instant::now());
+
+        state.tempo = self.tempo;
+        state.current_beat = self.current_beat;
+        state.transport_running = self.transport_running;
+        state.time_signature = TimeSignature::new(self.numerator, self.denominator);
+
+        state.groups = self
+            .groups
+            .into_iter()
+            .map(|g| {
+                let mut group = GroupState::new(g.name, g.path.clone(), g.parent_path, 0);
+                group.params = g.params;
+                group.muted = g.muted;
+                group.soloed = g.soloed;
+                (g.path, group)
+            })
+            .collect();
+
+        state.voices = self
+            .voices
+            .into_iter()
+            .map(|v| {
+                let mut voice = VoiceState::new(v.name.clone(), v.group_path);
+                voice.synth_name = v.synth_name;
+                voice.polyphony = v.polyphony;
+                voice.gain = v.gain;
+                voice.params = v.params;
+                voice.muted = v.muted;
+                voice.soloed = v.soloed;
+                (v.name, voice)
+            })
+            .collect();
+
+        state.patterns = self
+            .patterns
+            .into_iter()
+            .map(|p| {
+                let mut pattern = PatternState::new(p.name.clone(), p.group_path, p.voice_name);
+                pattern.params = p.params;
+                pattern.status = p.status.into();
+                (p.name, pattern)
+            })
+            .collect();
+
+        state.melodies = self
+            .melodies
+            .into_iter()
+            .map(|m| {
+                let mut melody = MelodyState::new(m.name.clone(), m.group_path, m.voice_name);
+                melody.params = m.params;
+                melody.status = m.status.into();
+                (m.name, melody)
+            })
+            .collect();
+
+        state.effects = self
+            .effects
+            .into_iter()
+            .map(|e| {
+                let effect = EffectState {
+                    id: e.id.clone(),
+                    synthdef_name: e.synthdef_name,
+                    group_path: e.group_path,
+                    node_id: None,
+                    bus_in: 0,
+                    bus_out: 0,
+                    params: e.params,
+                    generation: 0,
+                    position: e.position,
+                    vst_plugin: None,
+                    source_location: SourceLocation::default(),
+                    voice: e.voice,
+                };
+                (e.id, effect)
+            })
+            .collect();
+
+        state.sequences.clear();
+        state.active_sequences.clear();
+        for s in self.sequences {
+            let mut sequence = SequenceDefinition::new(s.name.clone());
+            sequence.loop_beats = s.loop_beats;
+            sequence.speed = s.speed;
+            sequence.play_once = s.play_once;
+            sequence.clips = s
+                .clips
+                .into_iter()
+                .map(|c| {
+                    let source = match c.kind.as_str() {
+                        "melody" => ClipSource::Melody(c.source),
+                        "fade" => ClipSource::Fade(c.source),
+                        "sequence" => ClipSource::Sequence(c.source),
+                        _ => ClipSource::Pattern(c.source),
+                    };
+                    SequenceClip::new(c.start, c.end, source, ClipMode::Loop)
+                })
+                .collect();
+            if let Some(anchor_beat) = s.anchor_beat {
+                state.active_sequences.insert(
+                    s.name.clone(),
+                    ActiveSequence {
+                        anchor_beat,
+                        paused: s.paused,
+                        triggered_clips: HashMap::new(),
+                        last_iteration: 0,
+                        completed: false,
+                        launch_beat: anchor_beat,
+                        stop_beat: None,
+                    },
+                );
+            }
+            state.sequences.insert(s.name, sequence);
+        }
+
+        state.meter_levels = self
+            .meters
+            .into_iter()
+            .map(|(path, [peak_left, peak_right, rms_left, rms_right])| {
+                let level = MeterLevel {
+                    peak_left,
+                    peak_right,
+                    rms_left,
+                    rms_right,
+                    last_update: now,
+                };
+                (path, level)
+            })
+            .collect();
+
+        state.loudness = LoudnessState {
+            momentary_lufs: self.loudness.momentary_lufs,
+            short_term_lufs: self.loudness.short_term_lufs,
+            integrated_lufs: self.loudness.integrated_lufs,
+            target_lufs: self.loudness.target_lufs,
+            over_target: self.loudness.over_target,
+            last_update: now,
+            ..Default::default()
+        };
+
+        let perf = self.performance;
+        state.performance.status = perf.avg_cpu.map(|avg_cpu| ServerStatus {
+            avg_cpu,
+            peak_cpu: perf.peak_cpu.unwrap_or(avg_cpu),
+            ..Default::default()
+        });
+        state.performance.budget.over_budget = perf.over_budget;
+        state.performance.late_events = perf.late_events;
+        state.performance.stale_dropped_events = perf.stale_dropped_events;
+        state.performance.last_update = now;
+
+        // Resource counts in the TUI are derived from the allocators
+        state.next_buffer_id = self.next_buffer_id;
+        state.next_audio_bus = self.next_audio_bus;
+        state.bump_version();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut state = ScriptState::new();
+        state.tempo = 128.0;
+        state.current_beat = 33.5;
+        state.transport_running = true;
+        let mut group = GroupState::new("drums".into(), "main.drums".into(), Some("main".into()), 16);
+        group.muted = true;
+        state.groups.insert(group.path.clone(), group);
+        let mut pattern = PatternState::new("kick".into(), "main.drums".into(), Some("kick".into()));
+        pattern.status = LoopStatus::Playing { start_beat: 32.0 };
+        state.patterns.insert("kick".into(), pattern);
+        let mut sequence = SequenceDefinition::new("intro");
+        sequence.clips.push(SequenceClip::new(0.0, 8.0, ClipSource::Melody("lead".into()), ClipMode::Once));
+        state.sequences.insert("intro".into(), sequence);
+
+        let event = WebSocketEvent {
+            event_type: SNAPSHOT_EVENT.to_string(),
+            timestamp: 0.0,
+            data: serde_json::to_value(MirrorSnapshot::capture(&state)).ok(),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        let snapshot = MirrorSnapshot::from_event(&json).expect("snapshot event");
+        let mut mirror = ScriptState::new();
+        snapshot.apply(&mut mirror);
+
+        assert_eq!(mirror.tempo, 128.0);
+        assert_eq!(mirror.current_beat, 33.5);
+        assert!(mirror.transport_running);
+        assert!(mirror.groups["main.drums"].muted);
+        assert_eq!(mirror.groups["main.drums"].parent_path.as_deref(), Some("main"));
+        assert_eq!(mirror.patterns["kick"].status.start_beat(), Some(32.0));
+        assert!(mirror.patterns["kick"].status.is_playing());
+        assert_eq!(mirror.sequences["intro"].clips[0].source, ClipSource::Melody("lead".into()));
+        assert!(!mirror.active_sequences.contains_key("intro"));
+    }
+}
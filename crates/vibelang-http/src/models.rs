@@ -2,6 +2,49 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use vibelang_core::timing::{Bars, Beats, TimeSpan};
+
+// =============================================================================
+// Times
+// =============================================================================
+
+/// A time in a request: a number of beats (`8`), a string with a unit
+/// (`"4 bars"`, `"8 beats"`) or an object (`{"bars": 4}`).
+///
+/// Bars convert to beats with the session's time signature.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "TimeInputRepr")]
+pub struct TimeInput(pub TimeSpan);
+
+impl TimeInput {
+    /// Beats under the given time signature.
+    pub fn to_beats(self, signature: vibelang_core::TimeSignature) -> f64 {
+        self.0.to_beats(signature).0
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TimeInputRepr {
+    Number(f64),
+    Text(String),
+    Bars { bars: f64 },
+    Beats { beats: f64 },
+}
+
+impl TryFrom<TimeInputRepr> for TimeInput {
+    type Error = String;
+
+    fn try_from(repr: TimeInputRepr) -> Result<Self, Self::Error> {
+        let span = match repr {
+            TimeInputRepr::Number(beats) | TimeInputRepr::Beats { beats } => TimeSpan::Beats(Beats(beats)),
+            TimeInputRepr::Bars { bars } => TimeSpan::Bars(Bars(bars)),
+            TimeInputRepr::Text(text) => TimeSpan::parse(&text)
+                .ok_or_else(|| format!("invalid time '{}' (expected e.g. 8, \"8 beats\" or \"4 bars\")", text))?,
+        };
+        Ok(TimeInput(span))
+    }
+}
 
 // =============================================================================
 // Source Location (for navigation to code)
@@ -25,6 +68,9 @@ pub struct TransportState {
     pub running: bool,
     pub current_beat: f64,
     pub quantization_beats: f64,
+    /// Launch grids by entity type ("pattern", "melody", "sequence",
+    /// "fade"), overriding `quantization_beats`.
+    pub launch_quantization: HashMap<String, f64>,
     /// The loop length in beats (from the longest active sequence), or None if no sequences.
     /// UI can use this to calculate loop position for display.
     pub loop_beats: Option<f64>,
@@ -34,6 +80,27 @@ pub struct TransportState {
     /// Server timestamp when this state was captured (milliseconds since Unix epoch).
     /// Clients can use this to compensate for network latency.
     pub server_time_ms: u64,
+    /// Song position locators in song order.
+    pub markers: Vec<Marker>,
+    /// Jump to a locator waiting for its launch point.
+    pub pending_jump: Option<PendingJump>,
+    /// Clock the transport follows: "internal" or "midi".
+    pub clock_source: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Marker {
+    pub name: String,
+    pub beat: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingJump {
+    pub marker: String,
+    /// Transport beat the jump happens at.
+    pub at_beat: f64,
+    /// Beat the transport continues from.
+    pub target_beat: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,12 +113,24 @@ pub struct TimeSignature {
 pub struct TransportUpdate {
     pub bpm: Option<f32>,
     pub time_signature: Option<TimeSignature>,
-    pub quantization_beats: Option<f64>,
+    pub quantization_beats: Option<TimeInput>,
+    /// Launch grids to set by entity type; `null` falls back to
+    /// `quantization_beats`.
+    pub launch_quantization: Option<HashMap<String, Option<TimeInput>>>,
+    /// "internal", or "midi" to follow an external MIDI clock.
+    pub clock_source: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SeekRequest {
-    pub beat: f64,
+    pub beat: TimeInput,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JumpRequest {
+    pub marker: String,
+    /// Launch grid; one bar when omitted, 0 jumps immediately.
+    pub quantization_beats: Option<TimeInput>,
 }
 
 // =============================================================================
@@ -72,6 +151,55 @@ pub struct Group {
     pub params: HashMap<String, f32>,
     pub synth_node_ids: Vec<i32>,
     pub source_location: Option<SourceLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freeze: Option<GroupFreeze>,
+}
+
+/// A group with its effects, voices and sub-groups (`GET /groups/tree`).
+#[derive(Debug, Serialize)]
+pub struct GroupTreeNode {
+    pub name: String,
+    pub path: String,
+    pub node_id: Option<i32>,
+    pub audio_bus: i32,
+    pub link_synth_node_id: Option<i32>,
+    /// Effects in chain order.
+    pub effects: Vec<GroupTreeEffect>,
+    pub voices: Vec<GroupTreeVoice>,
+    pub children: Vec<GroupTreeNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupTreeEffect {
+    pub id: String,
+    pub synthdef_name: String,
+    pub node_id: Option<i32>,
+    pub bus_in: i32,
+    pub bus_out: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupTreeVoice {
+    pub name: String,
+    pub synth_name: Option<String>,
+    /// Bus the voice's synths write to.
+    pub out_bus: i32,
+}
+
+/// Bounce state of a frozen group.
+#[derive(Debug, Serialize)]
+pub struct GroupFreeze {
+    pub bars: u32,
+    pub start_beat: f64,
+    pub end_beat: f64,
+    /// False while the bounce is still being recorded.
+    pub frozen: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupFreezeRequest {
+    /// Number of bars to record (default: 8).
+    pub bars: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -96,7 +224,7 @@ pub struct GroupUpdate {
 #[derive(Debug, Deserialize)]
 pub struct ParamSet {
     pub value: f32,
-    pub fade_beats: Option<f64>,
+    pub fade_beats: Option<TimeInput>,
 }
 
 // =============================================================================
@@ -122,6 +250,9 @@ pub struct Voice {
     pub running: bool,
     pub running_node_id: Option<i32>,
     pub source_location: Option<SourceLocation>,
+    pub priority: i64,
+    /// How far ahead of the beat events are sent, in milliseconds.
+    pub pre_roll_ms: f64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -148,6 +279,25 @@ fn default_gain() -> f32 {
     1.0
 }
 
+/// Request to randomize a voice's parameters within their declared ranges.
+#[derive(Debug, Deserialize)]
+pub struct RandomizeParamsRequest {
+    /// How far to move each parameter, as a fraction of its range (default: 0.2).
+    pub amount: Option<f64>,
+    /// Parameters to randomize (default: all with a declared range).
+    #[serde(default)]
+    pub only: Vec<String>,
+}
+
+/// Request to set a voice's parameters between two parameter sets.
+#[derive(Debug, Deserialize)]
+pub struct MorphParamsRequest {
+    pub from: HashMap<String, f32>,
+    pub to: HashMap<String, f32>,
+    /// Position between `from` (0) and `to` (1).
+    pub t: f64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct VoiceUpdate {
     pub synth_name: Option<String>,
@@ -163,6 +313,15 @@ pub struct TriggerRequest {
     pub params: HashMap<String, f32>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct StopVoiceRequest {
+    /// Seconds to fade out over before the synths are freed.
+    pub fade: Option<f64>,
+    /// Free the synths at once instead of releasing them.
+    #[serde(default)]
+    pub choke: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct NoteOnRequest {
     pub note: u8,
@@ -217,8 +376,8 @@ pub struct PatternCreate {
     pub name: String,
     pub voice_name: String,
     pub group_path: Option<String>,
-    #[serde(default = "default_loop_beats")]
-    pub loop_beats: f64,
+    /// Inferred from the content when omitted.
+    pub loop_beats: Option<TimeInput>,
     #[serde(default)]
     pub events: Vec<PatternEvent>,
     pub pattern_string: Option<String>,
@@ -226,15 +385,11 @@ pub struct PatternCreate {
     pub params: HashMap<String, f32>,
 }
 
-fn default_loop_beats() -> f64 {
-    4.0
-}
-
 #[derive(Debug, Deserialize)]
 pub struct PatternUpdate {
     pub events: Option<Vec<PatternEvent>>,
     pub pattern_string: Option<String>,
-    pub loop_beats: Option<f64>,
+    pub loop_beats: Option<TimeInput>,
     #[serde(default)]
     pub params: HashMap<String, f32>,
 }
@@ -276,6 +431,9 @@ pub struct MelodyEvent {
     pub frequency: Option<f32>,
     pub duration: Option<f64>,
     pub velocity: Option<f32>,
+    /// Pitch and velocity for display, e.g. "C#3 (vel 96)" (read-only).
+    #[serde(default)]
+    pub label: Option<String>,
     #[serde(default)]
     pub params: HashMap<String, f32>,
 }
@@ -285,8 +443,8 @@ pub struct MelodyCreate {
     pub name: String,
     pub voice_name: String,
     pub group_path: Option<String>,
-    #[serde(default = "default_loop_beats")]
-    pub loop_beats: f64,
+    /// Inferred from the content when omitted.
+    pub loop_beats: Option<TimeInput>,
     #[serde(default)]
     pub events: Vec<MelodyEvent>,
     /// Single melody string (backward compatible).
@@ -305,7 +463,7 @@ pub struct MelodyUpdate {
     /// Multiple lanes for polyphonic melodies.
     /// If provided, replaces all existing lanes.
     pub lanes: Option<Vec<String>>,
-    pub loop_beats: Option<f64>,
+    pub loop_beats: Option<TimeInput>,
     #[serde(default)]
     pub params: HashMap<String, f32>,
 }
@@ -318,6 +476,11 @@ pub struct MelodyUpdate {
 pub struct Sequence {
     pub name: String,
     pub loop_beats: f64,
+    /// Tempo factor of the whole sequence (0.5 = half time).
+    pub speed: f64,
+    /// Semitones the sequence's melodies are transposed by.
+    pub transpose: i32,
+    pub key_changes: Vec<KeyChange>,
     pub clips: Vec<SequenceClip>,
     pub play_once: bool,
     pub active: bool,
@@ -332,24 +495,50 @@ pub struct SequenceClip {
     pub start_beat: f64,
     pub end_beat: f64,
     pub mode: String,
+    /// Tempo factor of the clip's source.
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+/// A timed key change inside a sequence.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyChange {
+    pub start_beat: f64,
+    /// End beat (omitted = to the end of the sequence).
+    #[serde(default)]
+    pub end_beat: Option<f64>,
+    pub semitones: i32,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SequenceCreate {
     pub name: String,
     #[serde(default = "default_sequence_loop_beats")]
-    pub loop_beats: f64,
+    pub loop_beats: TimeInput,
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+    #[serde(default)]
+    pub transpose: i32,
+    #[serde(default)]
+    pub key_changes: Vec<KeyChange>,
     #[serde(default)]
     pub clips: Vec<SequenceClip>,
 }
 
-fn default_sequence_loop_beats() -> f64 {
-    16.0
+fn default_sequence_loop_beats() -> TimeInput {
+    TimeInput(TimeSpan::Beats(Beats(16.0)))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SequenceUpdate {
-    pub loop_beats: Option<f64>,
+    pub loop_beats: Option<TimeInput>,
+    pub speed: Option<f64>,
+    pub transpose: Option<i32>,
+    pub key_changes: Option<Vec<KeyChange>>,
     pub clips: Option<Vec<SequenceClip>>,
 }
 
@@ -374,6 +563,8 @@ pub struct Effect {
     pub params: HashMap<String, f32>,
     pub position: usize,
     pub vst_plugin: Option<String>,
+    /// Voice the effect is inserted on; unset for effects of the group.
+    pub voice: Option<String>,
     pub source_location: Option<SourceLocation>,
 }
 
@@ -385,6 +576,10 @@ pub struct EffectCreate {
     #[serde(default)]
     pub params: HashMap<String, f32>,
     pub position: Option<usize>,
+    /// VST plugin to host instead of `synthdef_name`.
+    pub vst_plugin: Option<String>,
+    /// Voice to insert the effect on, instead of the group's chain.
+    pub voice: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -407,6 +602,8 @@ pub struct Sample {
     pub sample_rate: f32,
     pub synthdef_name: String,
     pub slices: Vec<SampleSlice>,
+    /// Approximate key detected on load (e.g. "A minor"), if pitched.
+    pub detected_key: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -423,6 +620,48 @@ pub struct SampleLoad {
     pub path: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SamplePreviewRequest {
+    /// Playback amplitude (default: 1.0).
+    pub amp: Option<f32>,
+}
+
+/// Min/max waveform peaks for drawing a sample.
+#[derive(Debug, Serialize)]
+pub struct SamplePeaks {
+    pub id: String,
+    pub num_channels: usize,
+    pub num_frames: usize,
+    pub sample_rate: f32,
+    /// Number of peak bins per channel.
+    pub resolution: usize,
+    /// Number of source frames covered by each bin.
+    pub frames_per_peak: f64,
+    /// Per-channel minimum of each bin.
+    pub min: Vec<Vec<f32>>,
+    /// Per-channel maximum of each bin.
+    pub max: Vec<Vec<f32>>,
+    /// Slice markers, positioned in both frames and peak bins.
+    pub slices: Vec<SlicePosition>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SlicePosition {
+    pub index: usize,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    /// Start position as a (fractional) peak bin index.
+    pub start_peak: f64,
+    /// End position as a (fractional) peak bin index.
+    pub end_peak: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PeaksQuery {
+    /// Number of peak bins per channel (default: 1024).
+    pub resolution: Option<usize>,
+}
+
 // =============================================================================
 // SynthDefs
 // =============================================================================
@@ -432,6 +671,25 @@ pub struct SynthDef {
     pub name: String,
     pub params: Vec<SynthDefParam>,
     pub source: String,
+    /// Whether the synthdef is loaded on the server (false for stdlib
+    /// definitions that haven't been imported yet).
+    pub loaded: bool,
+    /// Stdlib category, e.g. "drums".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Stdlib header description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Path to import to load a stdlib definition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_path: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SynthDefsQuery {
+    /// Also list stdlib definitions that aren't loaded yet.
+    #[serde(default)]
+    pub stdlib: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -440,6 +698,12 @@ pub struct SynthDefParam {
     pub default_value: f32,
     pub min_value: Option<f32>,
     pub max_value: Option<f32>,
+    /// Declared unit, e.g. "hz".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Declared slider curve ("lin" or "exp").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
 }
 
 // =============================================================================
@@ -459,6 +723,10 @@ pub struct ActiveFade {
     pub duration_beats: f64,
     pub start_beat: f64,
     pub progress: f32,
+    /// Interpolation: "linear" or "db".
+    pub curve: String,
+    /// Where the fade definition (or ctrl) that started this fade is defined.
+    pub source_location: Option<SourceLocation>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -556,6 +824,9 @@ pub struct CcRoute {
     pub min_value: f32,
     #[serde(default = "default_gain")]
     pub max_value: f32,
+    /// Scale over the parameter's declared range instead of min/max.
+    #[serde(default)]
+    pub param_range: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -622,6 +893,47 @@ pub struct MonitorRequest {
     pub enabled: bool,
 }
 
+#[derive(Debug, Serialize)]
+pub struct MidiTakesResponse {
+    pub armed: Option<ArmedMidiTake>,
+    pub auditioning: Option<u32>,
+    pub takes: Vec<MidiTake>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArmedMidiTake {
+    pub target: String,
+    pub mode: String,
+    pub loop_beats: f64,
+    pub note_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MidiTake {
+    pub id: u32,
+    pub target: String,
+    /// "pattern" or "melody".
+    pub kind: String,
+    /// "overdub" or "replace".
+    pub mode: String,
+    pub loop_beats: f64,
+    /// Notes with beats relative to the loop start.
+    pub notes: Vec<RecordedMidiNote>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArmTakeRequest {
+    /// Pattern or melody to record against.
+    pub target: String,
+    /// "overdub" (default) or "replace".
+    #[serde(default = "default_overdub")]
+    pub mode: String,
+}
+
+fn default_overdub() -> String {
+    "overdub".to_string()
+}
+
 // =============================================================================
 // Live State
 // =============================================================================
@@ -644,6 +956,26 @@ pub struct ActiveSynth {
     pub voice_name: Option<String>,
     pub group_path: Option<String>,
     pub created_at_beat: Option<f64>,
+    /// Pattern that started the synth.
+    pub pattern_name: Option<String>,
+    /// Melody that started the synth.
+    pub melody_name: Option<String>,
+    /// Index of the pattern step or melody note that started it (from 0).
+    pub step: Option<usize>,
+    /// Sequence clip that played it.
+    pub clip: Option<SynthClip>,
+    /// Where its pattern or melody is defined.
+    pub source_location: Option<SourceLocation>,
+    /// Human-readable origin ("pattern bass step 5 · verse clip 2 · song.vibe:12").
+    pub provenance: String,
+}
+
+/// Sequence clip that played a synth.
+#[derive(Debug, Serialize)]
+pub struct SynthClip {
+    pub sequence: String,
+    /// Index of the clip in the sequence (from 0).
+    pub index: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -656,6 +988,36 @@ pub struct ActiveSequence {
     pub play_once: bool,
 }
 
+/// Notes sounding on a voice.
+#[derive(Debug, Serialize)]
+pub struct VoiceNotes {
+    /// Sounding notes, counting retriggers of the same pitch.
+    pub active_count: usize,
+    /// Polyphony limit of the voice.
+    pub polyphony: i64,
+    /// Sounding pitches, lowest first.
+    pub notes: Vec<SoundingNote>,
+}
+
+/// A pitch sounding on a voice.
+#[derive(Debug, Serialize)]
+pub struct SoundingNote {
+    /// MIDI note number.
+    pub note: u8,
+    /// Note name (e.g. "C4").
+    pub name: String,
+    /// Notes of this pitch sounding at once.
+    pub count: usize,
+    /// Milliseconds since the oldest of them started.
+    pub age_ms: f64,
+    /// What started it: "pattern", "melody", "midi" or "direct".
+    pub source: Option<String>,
+    /// Pattern or melody that started it.
+    pub source_name: Option<String>,
+    /// Whether it sounds far longer than expected (a stuck note).
+    pub stuck: bool,
+}
+
 // =============================================================================
 // Audio Metering
 // =============================================================================
@@ -673,6 +1035,402 @@ pub struct MeterLevel {
     pub rms_right: f32,
 }
 
+/// Master bus loudness (EBU R128). Levels are null while silent.
+#[derive(Debug, Clone, Serialize)]
+pub struct Loudness {
+    /// Momentary loudness (400ms window) in LUFS.
+    pub momentary_lufs: Option<f64>,
+    /// Short-term loudness (3s window) in LUFS.
+    pub short_term_lufs: Option<f64>,
+    /// Gated integrated loudness since the last reset in LUFS.
+    pub integrated_lufs: Option<f64>,
+    /// Highest momentary loudness since the last reset in LUFS.
+    pub max_momentary_lufs: Option<f64>,
+    /// Target loudness in LUFS, if set.
+    pub target_lufs: Option<f64>,
+    /// Whether short-term loudness is above the target.
+    pub over_target: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoudnessTargetUpdate {
+    /// New target in LUFS, or null to clear it.
+    pub target_lufs: Option<f64>,
+}
+
+/// Server CPU load and automatic degradation state.
+#[derive(Debug, Clone, Serialize)]
+pub struct Performance {
+    /// Average DSP load in percent (null until the first status reply).
+    pub avg_cpu: Option<f32>,
+    /// Peak DSP load in percent.
+    pub peak_cpu: Option<f32>,
+    pub num_ugens: Option<i32>,
+    pub num_synths: Option<i32>,
+    /// Measured server sample rate.
+    pub sample_rate: Option<f64>,
+    /// Whether the load is over the CPU budget.
+    pub over_budget: bool,
+    /// Number of voice priority tiers currently dropped.
+    pub degrade_level: u32,
+    /// Events dropped since the load last went over budget.
+    pub dropped_events: u64,
+    /// Events fired late because the tick loop fell behind.
+    pub late_events: u64,
+    /// Stale non-anchor events dropped because the tick loop fell behind.
+    pub stale_dropped_events: u64,
+    /// Sequence fades waiting for the load to recover.
+    pub postponed_fades: usize,
+    pub policy: CpuPolicy,
+}
+
+/// What to do when the CPU budget is exceeded.
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuPolicy {
+    /// Average CPU load (percent) above which the mix is degraded.
+    pub threshold: f32,
+    /// Load must fall this many percent below the threshold to recover.
+    pub recovery_margin: f32,
+    pub drop_low_priority_voices: bool,
+    pub reduce_polyphony: bool,
+    pub postpone_fades: bool,
+    /// Drop non-anchor events the tick loop reaches too late.
+    pub drop_stale_events: bool,
+    /// Lateness (ms) after which an event counts as stale.
+    pub stale_threshold_ms: f32,
+    /// Events quieter than this fraction of the loudest event of their
+    /// pattern or melody are ghost notes.
+    pub ghost_ratio: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CpuPolicyUpdate {
+    pub threshold: Option<f32>,
+    pub recovery_margin: Option<f32>,
+    pub drop_low_priority_voices: Option<bool>,
+    pub reduce_polyphony: Option<bool>,
+    pub postpone_fades: Option<bool>,
+    pub drop_stale_events: Option<bool>,
+    pub stale_threshold_ms: Option<f32>,
+    pub ghost_ratio: Option<f32>,
+}
+
+// =============================================================================
+// Cues (live set cue list)
+// =============================================================================
+
+/// Cue list of the loaded live set.
+#[derive(Debug, Clone, Serialize)]
+pub struct CueList {
+    /// Path of the `.vibeset` file.
+    pub set_path: String,
+    /// Cues in order.
+    pub cues: Vec<Cue>,
+    /// Index of the most recently launched cue.
+    pub current: Option<usize>,
+    /// Index of the cue that the next GO launches.
+    pub standby: Option<usize>,
+    /// Most recently launched scene (cued or launched directly).
+    pub active_scene: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cue {
+    pub index: usize,
+    /// Scene launched by this cue.
+    pub scene: String,
+    /// Number of actions in the scene.
+    pub action_count: usize,
+}
+
+// =============================================================================
+// Session files
+// =============================================================================
+
+/// Body of `POST /session/save` and `POST /session/load`.
+#[derive(Debug, Deserialize)]
+pub struct SessionPathRequest {
+    /// Path of the session file on the server.
+    pub path: String,
+}
+
+/// A session file written or read on the server.
+#[derive(Debug, Serialize)]
+pub struct SessionFileInfo {
+    pub path: String,
+    pub voices: usize,
+    pub patterns: usize,
+    pub melodies: usize,
+    pub sequences: usize,
+}
+
+impl SessionFileInfo {
+    pub fn new(path: &str, file: &vibelang_core::session_file::SessionFile) -> Self {
+        Self {
+            path: path.to_string(),
+            voices: file.voices.len(),
+            patterns: file.patterns.len(),
+            melodies: file.melodies.len(),
+            sequences: file.sequences.len(),
+        }
+    }
+}
+
+// =============================================================================
+// Playback Graphs
+// =============================================================================
+
+/// A playback graph and its current section.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackGraphInfo {
+    pub name: String,
+    pub sections: Vec<GraphSectionInfo>,
+    pub transitions: Vec<GraphTransitionInfo>,
+    /// Length of crossfade transitions in beats.
+    pub crossfade_beats: f64,
+    /// Current section, null while stopped.
+    pub current: Option<String>,
+    /// Section waiting for its bar line.
+    pub pending: Option<String>,
+    /// Beat at which the pending section comes in.
+    pub pending_beat: Option<f64>,
+    /// Variables tested by `when` conditions.
+    pub vars: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphSectionInfo {
+    pub name: String,
+    pub sequence: String,
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphTransitionInfo {
+    pub from: String,
+    pub to: String,
+    /// Condition, e.g. "on_cue", "after 16 beats" or "intensity > 0.7".
+    pub condition: String,
+    /// "immediate", "next_bar" or "crossfade".
+    pub style: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphVarUpdate {
+    pub value: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphStart {
+    /// Section to start at (first section if omitted).
+    pub section: Option<String>,
+}
+
+// =============================================================================
+// Macros
+// =============================================================================
+
+/// A macro control and the parameters it drives.
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroInfo {
+    pub name: String,
+    /// Normalized value (0..1).
+    pub value: f64,
+    pub targets: Vec<MacroTargetInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroTargetInfo {
+    /// "group", "voice", "effect", "pattern" or "melody".
+    pub target_type: String,
+    pub target_name: String,
+    pub param_name: String,
+    /// Parameter value at macro value 0.
+    pub min_value: f32,
+    /// Parameter value at macro value 1.
+    pub max_value: f32,
+    /// Parameter value at the current macro value.
+    pub current_value: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MacroUpdate {
+    /// Normalized value, clamped to 0..1.
+    pub value: f64,
+}
+
+// =============================================================================
+// Automation
+// =============================================================================
+
+/// Recorded automation and the recording in progress.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationInfo {
+    /// Whether live parameter changes are being recorded.
+    pub recording: bool,
+    /// Length in beats of the lanes being recorded.
+    pub recording_beats: Option<f64>,
+    pub lanes: Vec<AutomationLaneInfo>,
+}
+
+/// The recorded values of one parameter.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationLaneInfo {
+    /// "group", "voice", "effect", "pattern" or "melody".
+    pub target_type: String,
+    pub target_name: String,
+    pub param_name: String,
+    /// Lane length in beats; the lane repeats from beat 0.
+    pub length_beats: f64,
+    /// Position (beats into the lane) and value of each point.
+    pub points: Vec<(f64, f32)>,
+    /// Whether the lane is being recorded into (and not replaying).
+    pub recording: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AutomationRecordRequest {
+    /// Lane length in bars (default 4).
+    #[serde(default)]
+    pub bars: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AutomationClearQuery {
+    /// "group", "voice", "effect", "pattern" or "melody"; all lanes without it.
+    pub target_type: Option<String>,
+    pub target_name: Option<String>,
+}
+
+// =============================================================================
+// Safety (dry runs and confirmation of destructive requests)
+// =============================================================================
+
+/// Query of a destructive request (deleting an entity, eval).
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfirmQuery {
+    /// Only preview what the request would stop.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Confirmation token from the preview, when confirmation is required.
+    pub confirm: Option<String>,
+}
+
+/// What a destructive request stops, returned by dry runs and by requests
+/// refused for lack of confirmation (428).
+#[derive(Debug, Serialize)]
+pub struct DestructivePreview {
+    /// "delete_voice", "delete_pattern", "delete_melody", "delete_sequence",
+    /// "delete_group" or "eval".
+    pub action: String,
+    /// Name of the entity deleted, or the code evaluated.
+    pub target: String,
+    /// Stopping functions the code calls (eval only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_calls: Vec<String>,
+    /// Entities that go silent, as `kind:name` ("pattern:bass").
+    pub affected: Vec<String>,
+    /// Sounding synths that are stopped.
+    pub stopped_synths: Vec<ActiveSynth>,
+    /// Token to pass as `?confirm=` to carry out the request.
+    pub confirm_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SafetySettings {
+    /// Whether destructive requests need a confirmation token.
+    pub require_confirmation: bool,
+}
+
+// =============================================================================
+// History (audit log of API mutations)
+// =============================================================================
+
+/// A recorded API mutation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Wall-clock time in milliseconds since the Unix epoch.
+    pub timestamp: f64,
+    /// Transport beat when the request arrived.
+    pub beat: f64,
+    /// Bar number (1-based) when the request arrived.
+    pub bar: i64,
+    /// HTTP method.
+    pub method: String,
+    /// Request path including the query string.
+    pub path: String,
+    /// Response status code.
+    pub status: u16,
+    /// Request body (parsed JSON if possible, raw text otherwise).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// Only entries at or after this bar.
+    pub since_bar: Option<i64>,
+    /// Only entries at or before this bar.
+    pub until_bar: Option<i64>,
+    /// Only entries whose path contains this string.
+    pub path: Option<String>,
+    /// Return at most this many (most recent) entries.
+    pub limit: Option<usize>,
+}
+
+// =============================================================================
+// Health (liveness and readiness probes)
+// =============================================================================
+
+/// Health of the session (`GET /healthz`, `GET /readyz`).
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    /// "ok", or "unavailable" when the probe fails.
+    pub status: String,
+    /// Why the probe fails; empty when it passes.
+    pub problems: Vec<String>,
+    pub runtime: RuntimeHealth,
+    pub scsynth: ScsynthHealth,
+    pub stdlib: StdlibHealth,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeHealth {
+    /// Whether the runtime thread is running its loop.
+    pub alive: bool,
+    /// Milliseconds since the runtime thread last checked in (null until its first check-in).
+    pub heartbeat_age_ms: Option<u64>,
+    /// Messages sent to the runtime thread and not processed yet.
+    pub queued_messages: usize,
+    /// Events fired late because the tick loop fell behind.
+    pub late_events: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScsynthHealth {
+    /// Whether scsynth answered a status poll recently.
+    pub connected: bool,
+    /// Milliseconds since the last `/status.reply` (null until the first one).
+    pub last_status_age_ms: Option<u64>,
+    /// Average DSP load in percent.
+    pub avg_cpu: Option<f32>,
+    /// Peak DSP load in percent.
+    pub peak_cpu: Option<f32>,
+    pub num_ugens: Option<i32>,
+    pub num_synths: Option<i32>,
+    pub num_groups: Option<i32>,
+    pub num_synthdefs: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StdlibHealth {
+    /// Whether the standard library is installed where imports look for it.
+    pub available: bool,
+    pub path: String,
+    /// Synthdefs and effects in the stdlib index.
+    pub definitions: usize,
+}
+
 // =============================================================================
 // Error Response
 // =============================================================================
@@ -707,3 +1465,25 @@ impl ErrorResponse {
         Self::new("internal_error", message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_inputs_accept_units() {
+        let parse = |json: &str| serde_json::from_str::<TimeInput>(json);
+        let waltz = vibelang_core::TimeSignature::new(3, 4);
+        assert_eq!(parse("6").unwrap().to_beats(waltz), 6.0);
+        assert_eq!(parse("\"2 bars\"").unwrap().to_beats(waltz), 6.0);
+        assert_eq!(parse("{\"bars\": 1}").unwrap().to_beats(waltz), 3.0);
+        assert_eq!(parse("{\"beats\": 1.5}").unwrap().to_beats(waltz), 1.5);
+        assert!(parse("\"2 parsecs\"").is_err());
+
+        // Plain numbers keep working where requests took beats
+        let seek: SeekRequest = serde_json::from_str("{\"beat\": 16}").unwrap();
+        assert_eq!(seek.beat.0, TimeSpan::Beats(Beats(16.0)));
+        let sequence: SequenceCreate = serde_json::from_str("{\"name\": \"song\", \"loop_beats\": \"8 bars\"}").unwrap();
+        assert_eq!(sequence.loop_beats.0, TimeSpan::Bars(Bars(8.0)));
+    }
+}
@@ -0,0 +1,107 @@
+//! Cache of waveform peaks served by `GET /samples/{id}/peaks`.
+//!
+//! Computing peaks reads the whole sample file, so results are kept per file
+//! and resolution. The cache holds at most [`MAX_CACHED_PEAKS`] entries and
+//! evicts the least recently used one when full.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use vibelang_core::api::WaveformPeaks;
+
+/// Maximum number of peak sets kept in memory.
+const MAX_CACHED_PEAKS: usize = 64;
+
+/// Sample file path and resolution the peaks were computed for.
+type PeaksKey = (String, usize);
+
+/// Least recently used cache of waveform peaks.
+pub struct PeaksCache {
+    /// Entries ordered from least to most recently used.
+    entries: Mutex<VecDeque<(PeaksKey, Arc<WaveformPeaks>)>>,
+    capacity: usize,
+}
+
+impl PeaksCache {
+    /// Create an empty cache holding at most [`MAX_CACHED_PEAKS`] entries.
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_CACHED_PEAKS)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Look up cached peaks, marking them as most recently used.
+    pub fn get(&self, path: &str, resolution: usize) -> Option<Arc<WaveformPeaks>> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries
+            .iter()
+            .position(|((p, r), _)| p == path && *r == resolution)?;
+        let entry = entries.remove(index)?;
+        let peaks = entry.1.clone();
+        entries.push_back(entry);
+        Some(peaks)
+    }
+
+    /// Cache peaks, evicting the least recently used entry when full.
+    pub fn insert(&self, path: String, resolution: usize, peaks: Arc<WaveformPeaks>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|((p, r), _)| !(*p == path && *r == resolution));
+        while entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(((path, resolution), peaks));
+    }
+
+    /// Number of cached peak sets.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for PeaksCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peaks() -> Arc<WaveformPeaks> {
+        Arc::new(WaveformPeaks {
+            resolution: 0,
+            num_frames: 0,
+            frames_per_peak: 0.0,
+            min: Vec::new(),
+            max: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = PeaksCache::with_capacity(2);
+        cache.insert("a.wav".into(), 1024, peaks());
+        cache.insert("b.wav".into(), 1024, peaks());
+        assert!(cache.get("a.wav", 1024).is_some());
+
+        cache.insert("c.wav".into(), 1024, peaks());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b.wav", 1024).is_none());
+        assert!(cache.get("a.wav", 1024).is_some());
+        assert!(cache.get("c.wav", 1024).is_some());
+
+        cache.insert("c.wav".into(), 1024, peaks());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a.wav", 512).is_none());
+    }
+}
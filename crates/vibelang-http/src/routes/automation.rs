@@ -0,0 +1,93 @@
+//! Automation recording endpoint handlers.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use vibelang_core::automation::{parse_target_type, target_type_name, DEFAULT_AUTOMATION_BARS};
+use vibelang_core::state::StateMessage;
+
+use crate::{
+    models::{AutomationClearQuery, AutomationInfo, AutomationLaneInfo, AutomationRecordRequest, ErrorResponse},
+    AppState,
+};
+
+fn send_automation_message(
+    state: &AppState,
+    msg: StateMessage,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    state.handle.send(msg).map(|_| StatusCode::OK).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to update automation: {}", e))),
+        )
+    })
+}
+
+/// GET /automation - Recorded lanes and the recording in progress
+pub async fn get_automation(State(state): State<Arc<AppState>>) -> Json<AutomationInfo> {
+    Json(state.handle.with_state(|s| {
+        let automation = &s.automation;
+        let recording = automation.recording.as_ref();
+        AutomationInfo {
+            recording: recording.is_some(),
+            recording_beats: recording.map(|r| r.length_beats),
+            lanes: automation
+                .lanes
+                .iter()
+                .map(|(key, lane)| AutomationLaneInfo {
+                    target_type: target_type_name(&lane.target_type).to_string(),
+                    target_name: lane.target.clone(),
+                    param_name: lane.param.clone(),
+                    length_beats: lane.length_beats,
+                    points: lane.points.iter().map(|p| (p.beat, p.value)).collect(),
+                    recording: recording.is_some_and(|r| r.touched.contains_key(key)),
+                })
+                .collect(),
+        }
+    }))
+}
+
+/// POST /automation/record - Record live parameter changes into lanes
+pub async fn start_recording(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<AutomationRecordRequest>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let bars = body.and_then(|Json(req)| req.bars).unwrap_or(DEFAULT_AUTOMATION_BARS);
+    send_automation_message(&state, StateMessage::StartAutomationRecording { bars })
+}
+
+/// POST /automation/stop - Stop recording; the lanes replay from now on
+pub async fn stop_recording(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    send_automation_message(&state, StateMessage::StopAutomationRecording)
+}
+
+/// DELETE /automation - Remove the lanes of an entity, or all lanes
+pub async fn clear_automation(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AutomationClearQuery>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let target = match (query.target_type, query.target_name) {
+        (None, None) => None,
+        (Some(target_type), Some(name)) => {
+            let Some(target_type) = parse_target_type(&target_type) else {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::bad_request(&format!("Unknown target type '{}'", target_type))),
+                ));
+            };
+            Some((target_type, name))
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request("target_type and target_name go together")),
+            ))
+        }
+    };
+    send_automation_message(&state, StateMessage::ClearAutomation { target })
+}
@@ -0,0 +1,91 @@
+//! Cue list endpoint handlers.
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::sync::Arc;
+use vibelang_core::state::{LiveSetState, StateMessage};
+
+use crate::{
+    models::{Cue, CueList, ErrorResponse},
+    AppState,
+};
+
+fn cue_list_to_api(live: &LiveSetState) -> CueList {
+    let cues = live
+        .set
+        .cues
+        .iter()
+        .enumerate()
+        .map(|(index, scene)| Cue {
+            index,
+            scene: scene.clone(),
+            action_count: live.set.scene(scene).map(|s| s.actions.len()).unwrap_or(0),
+        })
+        .collect();
+
+    CueList {
+        set_path: live.path.to_string_lossy().to_string(),
+        cues,
+        current: live.cue_index,
+        standby: live.step_cue(1),
+        active_scene: live.active_scene.clone(),
+    }
+}
+
+fn no_live_set() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found("No live set loaded")),
+    )
+}
+
+/// GET /cues - Get the cue list with the current and standby cue
+pub async fn get_cues(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CueList>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .handle
+        .with_state(|s| s.live_set.as_ref().map(cue_list_to_api))
+        .map(Json)
+        .ok_or_else(no_live_set)
+}
+
+/// POST /cues/next - GO: launch the standby cue
+pub async fn next_cue(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    step_cue(&state, 1)
+}
+
+/// POST /cues/back - Relaunch the cue before the current one
+pub async fn previous_cue(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    step_cue(&state, -1)
+}
+
+fn step_cue(state: &AppState, delta: i32) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let target = state.handle.with_state(|s| s.live_set.as_ref().map(|live| live.step_cue(delta)));
+    match target {
+        None => return Err(no_live_set()),
+        Some(None) => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::conflict(if delta > 0 {
+                    "No cue after the current one"
+                } else {
+                    "No cue before the current one"
+                })),
+            ));
+        }
+        Some(Some(_)) => {}
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::StepCue { delta }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to step cue: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
@@ -39,6 +39,7 @@ fn effect_to_api(es: &vibelang_core::state::EffectState) -> Effect {
         params: es.params.clone(),
         position: es.position,
         vst_plugin: es.vst_plugin.clone(),
+        voice: es.voice.clone(),
         source_location: source_location_to_api(&es.source_location),
     }
 }
@@ -82,6 +83,16 @@ pub async fn create_effect(
         ));
     }
 
+    if let Some(voice) = &req.voice {
+        let voice_exists = state.handle.with_state(|s| s.voices.contains_key(voice));
+        if !voice_exists {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found(&format!("Voice '{}' not found", voice))),
+            ));
+        }
+    }
+
     // Get the group's audio bus for effect routing
     let bus = state.handle.with_state(|s| {
         s.groups.get(&req.group_path).map(|g| g.audio_bus).unwrap_or(0)
@@ -95,6 +106,8 @@ pub async fn create_effect(
         params: req.params.iter().map(|(k, v)| (k.clone(), *v)).collect(),
         bus_in: bus,
         bus_out: bus,
+        vst_plugin: req.vst_plugin.clone(),
+        voice: req.voice.clone(),
         source_location: SourceLocation::new(None, None, None),
     }) {
         return Err((
@@ -202,7 +215,8 @@ pub async fn set_effect_param(
     }
 
     // If fade_beats is specified, use a fade; otherwise set immediately
-    if let Some(duration_beats) = req.fade_beats {
+    if let Some(duration) = req.fade_beats {
+        let duration_beats = duration.to_beats(state.handle.with_state(|s| s.time_signature));
         let duration_str = format!("{}b", duration_beats);
         if let Err(e) = state.handle.send(StateMessage::FadeEffectParam {
             id: id.clone(),
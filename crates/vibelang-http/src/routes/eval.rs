@@ -1,14 +1,19 @@
 //! Eval endpoint handler for executing Rhai code dynamically.
 
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::oneshot;
 
+use vibelang_core::api::sandbox::SandboxViolation;
+
+use crate::models::ConfirmQuery;
+use crate::safety::{eval_preview, stop_calls};
 use crate::AppState;
 
 /// Request body for code evaluation
@@ -27,11 +32,24 @@ pub struct EvalResponse {
     pub result: Option<String>,
     /// Error message (if failed)
     pub error: Option<String>,
+    /// Sandbox limit that stopped the evaluation (if any)
+    pub violation: Option<EvalViolation>,
+}
+
+/// A sandbox limit hit by an evaluation
+#[derive(Debug, Serialize)]
+pub struct EvalViolation {
+    /// "operations", "timeout", "file_access", "process_control",
+    /// "voice_limit" or "synth_limit"
+    pub kind: String,
+    pub message: String,
 }
 
 /// Internal request sent to the main thread for evaluation
 pub struct EvalJob {
     pub code: String,
+    /// Bearer token of the request, used to pick a sandbox profile
+    pub token: Option<String>,
     pub response_tx: oneshot::Sender<EvalResult>,
 }
 
@@ -40,13 +58,27 @@ pub struct EvalResult {
     pub success: bool,
     pub result: Option<String>,
     pub error: Option<String>,
+    pub violation: Option<SandboxViolation>,
 }
 
 /// POST /eval - Evaluate Rhai code
+///
+/// Code that calls stopping functions is destructive: `?dry_run=true`
+/// previews what it stops without running it, and it needs a confirmation
+/// token when confirmation is required.
 pub async fn eval_code(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<ConfirmQuery>,
+    headers: HeaderMap,
     Json(req): Json<EvalRequest>,
-) -> (StatusCode, Json<EvalResponse>) {
+) -> Response {
+    if query.dry_run || !stop_calls(&req.code).is_empty() {
+        let preview = state.handle.with_state(|s| eval_preview(s, &req.code));
+        if let Some(response) = state.safety.guard(preview, &query) {
+            return response;
+        }
+    }
+
     // Check if eval channel is available
     let eval_tx = match &state.eval_tx {
         Some(tx) => tx,
@@ -57,8 +89,10 @@ pub async fn eval_code(
                     success: false,
                     result: None,
                     error: Some("Eval not available in this mode".to_string()),
+                    violation: None,
                 }),
-            );
+            )
+                .into_response();
         }
     };
 
@@ -66,8 +100,14 @@ pub async fn eval_code(
     let (response_tx, response_rx) = oneshot::channel();
 
     // Send the eval job to the main thread
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string());
     let job = EvalJob {
         code: req.code,
+        token,
         response_tx,
     };
 
@@ -78,27 +118,42 @@ pub async fn eval_code(
                 success: false,
                 result: None,
                 error: Some("Failed to send eval request".to_string()),
+                violation: None,
             }),
-        );
+        )
+            .into_response();
     }
 
     // Wait for the result
     match response_rx.await {
         Ok(result) => (
-            if result.success { StatusCode::OK } else { StatusCode::BAD_REQUEST },
+            if result.success {
+                StatusCode::OK
+            } else if result.violation.is_some() {
+                StatusCode::FORBIDDEN
+            } else {
+                StatusCode::BAD_REQUEST
+            },
             Json(EvalResponse {
                 success: result.success,
                 result: result.result,
                 error: result.error,
+                violation: result.violation.map(|v| EvalViolation {
+                    kind: v.kind.as_str().to_string(),
+                    message: v.message,
+                }),
             }),
-        ),
+        )
+            .into_response(),
         Err(_) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(EvalResponse {
                 success: false,
                 result: None,
                 error: Some("Eval request cancelled".to_string()),
+                violation: None,
             }),
-        ),
+        )
+            .into_response(),
     }
 }
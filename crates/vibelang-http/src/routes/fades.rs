@@ -7,7 +7,7 @@ use axum::{
 };
 use std::sync::Arc;
 use vibelang_core::state::StateMessage;
-use vibelang_core::FadeTargetType;
+use vibelang_core::{FadeCurve, FadeTargetType};
 
 use crate::{
     models::{ActiveFade, ErrorResponse, FadeCreate},
@@ -26,7 +26,7 @@ fn fade_to_api(id: &str, fo: &vibelang_core::state::ActiveFadeJob, tempo: f64) -
         1.0
     };
 
-    let current_value = fo.start_value + (fo.target_value - fo.start_value) * progress;
+    let current_value = fo.curve.interpolate(fo.start_value, fo.target_value, progress as f64);
 
     let target_type = match fo.target_type {
         FadeTargetType::Group => "group",
@@ -38,7 +38,7 @@ fn fade_to_api(id: &str, fo: &vibelang_core::state::ActiveFadeJob, tempo: f64) -
 
     ActiveFade {
         id: id.to_string(),
-        name: None,
+        name: fo.name.clone(),
         target_type: target_type.to_string(),
         target_name: fo.target_name.clone(),
         param_name: fo.param_name.clone(),
@@ -48,6 +48,8 @@ fn fade_to_api(id: &str, fo: &vibelang_core::state::ActiveFadeJob, tempo: f64) -
         duration_beats,
         start_beat: 0.0, // We don't have the start beat, so use 0
         progress,
+        curve: fo.curve.as_str().to_string(),
+        source_location: super::voices::source_location_to_api(&fo.source_location),
     }
 }
 
@@ -207,6 +209,8 @@ pub async fn create_fade(
         duration_beats: req.duration_beats,
         start_beat: current_beat,
         progress: 0.0,
+        curve: FadeCurve::Linear.as_str().to_string(),
+        source_location: None,
     };
 
     Ok((StatusCode::CREATED, Json(fade)))
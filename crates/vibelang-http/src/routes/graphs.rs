@@ -0,0 +1,140 @@
+//! Playback graph endpoint handlers.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use vibelang_core::state::{PlaybackGraphState, StateMessage};
+
+use crate::{
+    models::{
+        ErrorResponse, GraphSectionInfo, GraphStart, GraphTransitionInfo, GraphVarUpdate,
+        PlaybackGraphInfo,
+    },
+    AppState,
+};
+
+fn graph_to_api(graph: &PlaybackGraphState) -> PlaybackGraphInfo {
+    PlaybackGraphInfo {
+        name: graph.graph.name.clone(),
+        sections: graph
+            .graph
+            .sections
+            .iter()
+            .map(|s| GraphSectionInfo {
+                name: s.name.clone(),
+                sequence: s.sequence.clone(),
+                group: s.group.clone(),
+            })
+            .collect(),
+        transitions: graph
+            .graph
+            .transitions
+            .iter()
+            .map(|t| GraphTransitionInfo {
+                from: t.from.clone(),
+                to: t.to.clone(),
+                condition: t.condition.to_string(),
+                style: t.style.as_str().to_string(),
+            })
+            .collect(),
+        crossfade_beats: graph.graph.crossfade_beats,
+        current: graph.current.clone(),
+        pending: graph.pending.as_ref().map(|p| p.to.clone()),
+        pending_beat: graph.pending.as_ref().map(|p| p.at_beat),
+        vars: graph.vars.clone(),
+    }
+}
+
+fn graph_not_found(name: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found(&format!("Playback graph '{}' not found", name))),
+    )
+}
+
+/// Send a message for an existing graph.
+fn send_for_graph(
+    state: &AppState,
+    name: &str,
+    msg: StateMessage,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if !state.handle.with_state(|s| s.playback_graphs.contains_key(name)) {
+        return Err(graph_not_found(name));
+    }
+
+    if let Err(e) = state.handle.send(msg) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to update playback graph: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// GET /graphs - List all playback graphs
+pub async fn list_graphs(State(state): State<Arc<AppState>>) -> Json<Vec<PlaybackGraphInfo>> {
+    let mut graphs = state
+        .handle
+        .with_state(|s| s.playback_graphs.values().map(graph_to_api).collect::<Vec<_>>());
+    graphs.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(graphs)
+}
+
+/// GET /graphs/:name - Get a playback graph
+pub async fn get_graph(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<PlaybackGraphInfo>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .handle
+        .with_state(|s| s.playback_graphs.get(&name).map(graph_to_api))
+        .map(Json)
+        .ok_or_else(|| graph_not_found(&name))
+}
+
+/// POST /graphs/:name/start - Start a playback graph
+pub async fn start_graph(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    body: Option<Json<GraphStart>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let section = body.and_then(|Json(req)| req.section);
+    send_for_graph(&state, &name, StateMessage::StartPlaybackGraph { name: name.clone(), section })
+}
+
+/// POST /graphs/:name/stop - Stop a playback graph
+pub async fn stop_graph(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    send_for_graph(&state, &name, StateMessage::StopPlaybackGraph { name: name.clone() })
+}
+
+/// POST /graphs/:name/cue - Fire the current section's on_cue transitions
+pub async fn cue_graph(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    send_for_graph(&state, &name, StateMessage::CueGraph { name: name.clone() })
+}
+
+/// PUT /graphs/:name/vars/:var - Set a variable tested by when conditions
+pub async fn set_graph_var(
+    State(state): State<Arc<AppState>>,
+    Path((name, var)): Path<(String, String)>,
+    Json(req): Json<GraphVarUpdate>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    send_for_graph(
+        &state,
+        &name,
+        StateMessage::SetGraphVar {
+            name: name.clone(),
+            var,
+            value: req.value,
+        },
+    )
+}
@@ -1,17 +1,22 @@
 //! Groups endpoint handlers.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
 use vibelang_core::api::context::SourceLocation;
-use vibelang_core::state::StateMessage;
+use vibelang_core::state::{GroupTree, StateMessage};
 
 use crate::{
-    models::{ErrorResponse, Group, GroupCreate, GroupUpdate, ParamSet, SourceLocation as ApiSourceLocation},
+    models::{
+        ConfirmQuery, ErrorResponse, Group, GroupCreate, GroupFreeze, GroupFreezeRequest, GroupTreeEffect, GroupTreeNode,
+        GroupTreeVoice, GroupUpdate, ParamSet, SourceLocation as ApiSourceLocation,
+    },
+    safety::{deletion_preview, Deletion},
     AppState,
 };
 
@@ -44,6 +49,12 @@ fn group_to_api(gs: &vibelang_core::state::GroupState, children: Vec<String>) ->
         params: gs.params.clone(),
         synth_node_ids: gs.synth_node_ids.clone(),
         source_location: source_location_to_api(&gs.source_location),
+        freeze: gs.freeze.as_ref().map(|f| GroupFreeze {
+            bars: f.bars,
+            start_beat: f.start_beat,
+            end_beat: f.end_beat,
+            frozen: f.is_frozen(),
+        }),
     }
 }
 
@@ -56,6 +67,51 @@ fn find_children(groups: &HashMap<String, vibelang_core::state::GroupState>, par
         .collect()
 }
 
+/// Convert a group hierarchy to the API model, looking up its effects and voices
+fn tree_to_api(s: &vibelang_core::state::ScriptState, tree: GroupTree) -> GroupTreeNode {
+    GroupTreeNode {
+        effects: tree
+            .effects
+            .iter()
+            .filter_map(|id| s.effects.get(id))
+            .map(|e| GroupTreeEffect {
+                id: e.id.clone(),
+                synthdef_name: e.synthdef_name.clone(),
+                node_id: e.node_id,
+                bus_in: e.bus_in,
+                bus_out: e.bus_out,
+            })
+            .collect(),
+        voices: tree
+            .voices
+            .iter()
+            .filter_map(|name| s.voices.get(name))
+            .map(|v| GroupTreeVoice {
+                name: v.name.clone(),
+                synth_name: v.synth_name.clone(),
+                out_bus: v.out_bus(tree.audio_bus),
+            })
+            .collect(),
+        children: tree.children.into_iter().map(|child| tree_to_api(s, child)).collect(),
+        name: tree.name,
+        path: tree.path,
+        node_id: tree.node_id,
+        audio_bus: tree.audio_bus,
+        link_synth_node_id: tree.link_synth_node_id,
+    }
+}
+
+/// GET /groups/tree - Group hierarchy with effects and voices
+pub async fn group_tree(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<GroupTreeNode>> {
+    let roots = state.handle.with_state(|s| {
+        s.group_trees().into_iter().map(|tree| tree_to_api(s, tree)).collect::<Vec<_>>()
+    });
+
+    Json(roots)
+}
+
 /// GET /groups - List all groups
 pub async fn list_groups(
     State(state): State<Arc<AppState>>,
@@ -201,7 +257,8 @@ pub async fn update_group(
 pub async fn delete_group(
     State(state): State<Arc<AppState>>,
     Path(path): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    Query(query): Query<ConfirmQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
     // Cannot delete main group
     if path == "main" {
         return Err((
@@ -219,6 +276,11 @@ pub async fn delete_group(
         ));
     }
 
+    let preview = state.handle.with_state(|s| deletion_preview(s, Deletion::Group, &path));
+    if let Some(response) = state.safety.guard(preview, &query) {
+        return Ok(response);
+    }
+
     // Unregister the group
     if let Err(e) = state.handle.send(StateMessage::UnregisterGroup { path: path.clone() }) {
         return Err((
@@ -227,7 +289,7 @@ pub async fn delete_group(
         ));
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 /// POST /groups/*path/mute - Mute a group
@@ -323,6 +385,92 @@ pub async fn unsolo_group(
     get_group(State(state), Path(path)).await
 }
 
+/// POST /groups/*path/freeze - Bounce a group to audio and play the bounce
+pub async fn freeze_group(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<String>,
+    Json(req): Json<Option<GroupFreezeRequest>>,
+) -> Result<Json<Group>, (StatusCode, Json<ErrorResponse>)> {
+    let info = state.handle.with_state(|s| {
+        s.groups.get(&path).map(|g| (g.link_synth_node_id.is_some(), g.freeze.is_some()))
+    });
+    match info {
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found(&format!("Group '{}' not found", path))),
+            ));
+        }
+        Some((false, _)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request(&format!("Group '{}' cannot be frozen", path))),
+            ));
+        }
+        Some((_, true)) => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::conflict(&format!("Group '{}' is already frozen", path))),
+            ));
+        }
+        Some(_) => {}
+    }
+
+    let bars = req
+        .and_then(|r| r.bars)
+        .unwrap_or(vibelang_core::freeze::DEFAULT_FREEZE_BARS);
+    if bars == 0 || bars > vibelang_core::freeze::MAX_FREEZE_BARS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!(
+                "bars must be between 1 and {}",
+                vibelang_core::freeze::MAX_FREEZE_BARS
+            ))),
+        ));
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::FreezeGroup { path: path.clone(), bars }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to freeze group: {}", e))),
+        ));
+    }
+
+    get_group(State(state), Path(path)).await
+}
+
+/// POST /groups/*path/unfreeze - Remove a group's bounce and resume live synthesis
+pub async fn unfreeze_group(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<String>,
+) -> Result<Json<Group>, (StatusCode, Json<ErrorResponse>)> {
+    let frozen = state.handle.with_state(|s| s.groups.get(&path).map(|g| g.freeze.is_some()));
+    match frozen {
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::not_found(&format!("Group '{}' not found", path))),
+            ));
+        }
+        Some(false) => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::conflict(&format!("Group '{}' is not frozen", path))),
+            ));
+        }
+        Some(true) => {}
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::UnfreezeGroup { path: path.clone() }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to unfreeze group: {}", e))),
+        ));
+    }
+
+    get_group(State(state), Path(path)).await
+}
+
 /// PUT /groups/:path/params/:param - Set a group parameter
 pub async fn set_group_param(
     State(state): State<Arc<AppState>>,
@@ -339,7 +487,8 @@ pub async fn set_group_param(
     }
 
     // If fade_beats is specified, use a fade; otherwise set immediately
-    if let Some(duration_beats) = req.fade_beats {
+    if let Some(duration) = req.fade_beats {
+        let duration_beats = duration.to_beats(state.handle.with_state(|s| s.time_signature));
         let duration_str = format!("{}b", duration_beats);
         if let Err(e) = state.handle.send(StateMessage::FadeGroupParam {
             path: path.clone(),
@@ -0,0 +1,170 @@
+//! Health endpoint handlers.
+//!
+//! `/healthz` is a liveness probe: it fails only when the runtime thread
+//! stopped or hangs. `/readyz` also requires scsynth to answer its status
+//! polls, the standard library to be installed and the message queue to
+//! keep up, so orchestrators and front-of-house dashboards can tell a
+//! session that plays from one that merely runs.
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use vibelang_core::state::ScriptState;
+
+use crate::{
+    models::{Health, RuntimeHealth, ScsynthHealth, StdlibHealth},
+    AppState,
+};
+
+/// The runtime thread checks in once per status poll; a few missed ones mean it hangs.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// scsynth is polled once per second; a few missed replies mean it is gone.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Queued messages beyond which the runtime is considered not to keep up.
+const MAX_QUEUED_MESSAGES: usize = 1000;
+
+/// Milliseconds since `then`.
+fn age_ms(then: Option<Instant>, now: Instant) -> Option<u64> {
+    then.map(|t| now.saturating_duration_since(t).as_millis() as u64)
+}
+
+/// Assess the session. Liveness only looks at the runtime thread;
+/// readiness also at scsynth, the stdlib and the message queue.
+fn assess(
+    s: &ScriptState,
+    stopped: bool,
+    queued_messages: usize,
+    stdlib: StdlibHealth,
+    readiness: bool,
+    now: Instant,
+) -> Health {
+    let perf = &s.performance;
+    let status = perf.status.as_ref();
+
+    let heartbeat_age = perf.heartbeat.map(|t| now.saturating_duration_since(t));
+    // Before its first check-in the thread is still starting up
+    let alive = !stopped && heartbeat_age.is_none_or(|age| age < HEARTBEAT_TIMEOUT);
+    let connected = perf
+        .last_update
+        .is_some_and(|t| now.saturating_duration_since(t) < STATUS_TIMEOUT);
+
+    let mut problems = Vec::new();
+    if stopped {
+        problems.push("runtime thread has stopped".to_string());
+    } else if !alive {
+        problems.push(format!(
+            "runtime thread has not checked in for {:.1}s",
+            heartbeat_age.unwrap_or_default().as_secs_f64()
+        ));
+    }
+    if readiness {
+        if !connected {
+            problems.push(match perf.last_update {
+                Some(t) => format!(
+                    "scsynth has not answered a status poll for {:.1}s",
+                    now.saturating_duration_since(t).as_secs_f64()
+                ),
+                None => "scsynth has not answered a status poll yet".to_string(),
+            });
+        }
+        if !stdlib.available {
+            problems.push(format!("standard library not found at {}", stdlib.path));
+        }
+        if queued_messages > MAX_QUEUED_MESSAGES {
+            problems.push(format!("{} messages queued for the runtime thread", queued_messages));
+        }
+    }
+
+    Health {
+        status: if problems.is_empty() { "ok" } else { "unavailable" }.to_string(),
+        problems,
+        runtime: RuntimeHealth {
+            alive,
+            heartbeat_age_ms: age_ms(perf.heartbeat, now),
+            queued_messages,
+            late_events: perf.late_events,
+        },
+        scsynth: ScsynthHealth {
+            connected,
+            last_status_age_ms: age_ms(perf.last_update, now),
+            avg_cpu: status.map(|st| st.avg_cpu),
+            peak_cpu: status.map(|st| st.peak_cpu),
+            num_ugens: status.map(|st| st.num_ugens),
+            num_synths: status.map(|st| st.num_synths),
+            num_groups: status.map(|st| st.num_groups),
+            num_synthdefs: status.map(|st| st.num_synthdefs),
+        },
+        stdlib,
+    }
+}
+
+/// Where the standard library is installed and how much of it is indexed.
+fn stdlib_health() -> StdlibHealth {
+    let path = vibelang_std::stdlib_path();
+    StdlibHealth {
+        available: Path::new(path).is_dir(),
+        path: path.to_string(),
+        definitions: vibelang_std::stdlib_index().len(),
+    }
+}
+
+/// Run a probe: 200 when it passes, 503 with the problems otherwise.
+fn probe(state: &AppState, readiness: bool) -> (StatusCode, Json<Health>) {
+    let stopped = state.handle.is_stopped();
+    let queued_messages = state.handle.queued_messages();
+    let health = state.handle.with_state(|s| {
+        assess(s, stopped, queued_messages, stdlib_health(), readiness, Instant::now())
+    });
+    let code = if health.problems.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(health))
+}
+
+/// GET /healthz - Liveness: whether the runtime thread is running
+pub async fn healthz(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Health>) {
+    probe(&state, false)
+}
+
+/// GET /readyz - Readiness: whether the session can play (runtime, scsynth, stdlib, queue)
+pub async fn readyz(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Health>) {
+    probe(&state, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness_needs_scsynth_and_a_live_runtime() {
+        let stdlib = || StdlibHealth { available: true, path: "/stdlib".to_string(), definitions: 1 };
+        let now = Instant::now();
+        let mut s = ScriptState::new();
+
+        // Starting up: alive, but scsynth has not answered yet
+        assert!(assess(&s, false, 0, stdlib(), false, now).problems.is_empty());
+        let ready = assess(&s, false, 0, stdlib(), true, now);
+        assert_eq!(ready.problems, vec!["scsynth has not answered a status poll yet"]);
+
+        s.performance.heartbeat = Some(now - Duration::from_secs(1));
+        s.performance.last_update = Some(now - Duration::from_millis(500));
+        s.performance.status = Some(vibelang_core::performance::ServerStatus { num_synths: 12, ..Default::default() });
+        let ready = assess(&s, false, 0, stdlib(), true, now);
+        assert_eq!(ready.status, "ok");
+        assert_eq!(ready.scsynth.num_synths, Some(12));
+        assert_eq!(ready.runtime.heartbeat_age_ms, Some(1000));
+
+        // A hung loop fails liveness; a backed-up queue only readiness
+        s.performance.heartbeat = Some(now - Duration::from_secs(30));
+        assert!(!assess(&s, false, 0, stdlib(), false, now).runtime.alive);
+        s.performance.heartbeat = Some(now);
+        assert!(assess(&s, false, 5000, stdlib(), false, now).problems.is_empty());
+        assert_eq!(assess(&s, false, 5000, stdlib(), true, now).problems.len(), 1);
+        assert_eq!(assess(&s, true, 0, stdlib(), false, now).problems, vec!["runtime thread has stopped"]);
+    }
+}
@@ -0,0 +1,22 @@
+//! History endpoint handlers.
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    models::{HistoryEntry, HistoryQuery},
+    AppState,
+};
+
+/// GET /history - Get recorded API mutations
+///
+/// Supports `since_bar`, `until_bar`, `path` (substring) and `limit` filters.
+pub async fn get_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> Json<Vec<HistoryEntry>> {
+    Json(state.history.query(&query))
+}
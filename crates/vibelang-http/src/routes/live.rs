@@ -2,22 +2,46 @@
 
 use axum::{
     extract::State,
+    http::StatusCode,
     Json,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use vibelang_core::pitch;
 use vibelang_core::state::LoopStatus as InternalLoopStatus;
+use vibelang_core::state::StateMessage;
 use vibelang_core::FadeTargetType;
 
 use crate::{
     models::{
-        ActiveFade, ActiveSequence, ActiveSynth, LiveState, LoopStatus, MeterLevel, TimeSignature,
-        TransportState,
+        ActiveFade, ActiveSequence, ActiveSynth, CpuPolicy, CpuPolicyUpdate, ErrorResponse,
+        LiveState, LoopStatus, Loudness, LoudnessTargetUpdate, MeterLevel, Performance,
+        SoundingNote, SynthClip, TimeSignature, TransportState, VoiceNotes,
     },
     AppState,
 };
 
+/// Convert an internal ActiveSynth to the API model
+pub(crate) fn active_synth_to_api(info: &vibelang_core::state::ActiveSynth) -> ActiveSynth {
+    ActiveSynth {
+        node_id: info.node_id,
+        synthdef_name: info.synth_def.clone(),
+        voice_name: info.voice_names.first().cloned(),
+        group_path: info.group_paths.first().cloned(),
+        created_at_beat: info.start_beat,
+        pattern_name: info.pattern_names.first().cloned(),
+        melody_name: info.melody_names.first().cloned(),
+        step: info.step,
+        clip: info.clip.as_ref().map(|clip| SynthClip {
+            sequence: clip.sequence.clone(),
+            index: clip.index,
+        }),
+        source_location: super::voices::source_location_to_api(&info.source_location),
+        provenance: info.provenance(),
+    }
+}
+
 /// Convert internal LoopStatus to API model
 fn loop_status_to_api(status: &InternalLoopStatus) -> LoopStatus {
     match status {
@@ -55,7 +79,7 @@ pub async fn get_live_state(
             .filter_map(|(seq_name, _)| {
                 s.sequences.get(seq_name)
                     .filter(|seq| seq.loop_beats > 0.0)
-                    .map(|seq| seq.loop_beats)
+                    .map(|seq| seq.playback_beats())
             })
             .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
@@ -84,25 +108,21 @@ pub async fn get_live_state(
             running: s.transport_running,
             current_beat: s.current_beat,
             quantization_beats: s.quantization_beats,
+            launch_quantization: super::transport::launch_quantization_to_api(&s.launch_quantization),
             loop_beats,
             loop_beat,
             server_time_ms,
+            markers: super::transport::markers_to_api(&s.locators),
+            pending_jump: super::transport::pending_jump_to_api(&s.locators),
+            clock_source: s.clock_source.as_str().to_string(),
         };
 
         // Active synths
-        let active_synths: Vec<ActiveSynth> = s.active_synths.iter().map(|(node_id, info)| {
-            ActiveSynth {
-                node_id: *node_id,
-                synthdef_name: String::new(), // Not stored in ActiveSynth
-                voice_name: info.voice_names.first().cloned(),
-                group_path: info.group_paths.first().cloned(),
-                created_at_beat: None, // Not stored in ActiveSynth
-            }
-        }).collect();
+        let active_synths: Vec<ActiveSynth> = s.active_synths.values().map(active_synth_to_api).collect();
 
         // Active sequences - check if sequences exist (may be None if using sequence definitions directly)
         let active_sequences: Vec<ActiveSequence> = s.active_sequences.iter().map(|(name, seq_state)| {
-            let loop_beats = s.sequences.get(name).map(|sd| sd.loop_beats).unwrap_or(16.0);
+            let loop_beats = s.sequences.get(name).map(|sd| sd.playback_beats()).unwrap_or(16.0);
             let current_position = (s.current_beat - seq_state.anchor_beat) % loop_beats;
             let iteration = ((s.current_beat - seq_state.anchor_beat) / loop_beats).floor() as u32;
 
@@ -130,7 +150,7 @@ pub async fn get_live_state(
             } else {
                 1.0
             };
-            let current_value = fo.start_value + (fo.target_value - fo.start_value) * progress;
+            let current_value = fo.curve.interpolate(fo.start_value, fo.target_value, progress as f64);
 
             let target_type = match fo.target_type {
                 FadeTargetType::Group => "group",
@@ -146,7 +166,7 @@ pub async fn get_live_state(
 
             ActiveFade {
                 id,
-                name: None,
+                name: fo.name.clone(),
                 target_type: target_type.to_string(),
                 target_name: fo.target_name.clone(),
                 param_name: fo.param_name.clone(),
@@ -156,6 +176,8 @@ pub async fn get_live_state(
                 duration_beats,
                 start_beat: 0.0, // We don't have the original start beat
                 progress,
+                curve: fo.curve.as_str().to_string(),
+                source_location: super::voices::source_location_to_api(&fo.source_location),
             }
         }).collect();
 
@@ -194,15 +216,7 @@ pub async fn get_active_synths(
     State(state): State<Arc<AppState>>,
 ) -> Json<Vec<ActiveSynth>> {
     let synths = state.handle.with_state(|s| {
-        s.active_synths.iter().map(|(node_id, info)| {
-            ActiveSynth {
-                node_id: *node_id,
-                synthdef_name: String::new(), // Not stored in ActiveSynth
-                voice_name: info.voice_names.first().cloned(),
-                group_path: info.group_paths.first().cloned(),
-                created_at_beat: None, // Not stored in ActiveSynth
-            }
-        }).collect::<Vec<_>>()
+        s.active_synths.values().map(active_synth_to_api).collect::<Vec<_>>()
     });
 
     Json(synths)
@@ -214,7 +228,7 @@ pub async fn get_active_sequences(
 ) -> Json<Vec<ActiveSequence>> {
     let sequences = state.handle.with_state(|s| {
         s.active_sequences.iter().map(|(name, seq_state)| {
-            let loop_beats = s.sequences.get(name).map(|sd| sd.loop_beats).unwrap_or(16.0);
+            let loop_beats = s.sequences.get(name).map(|sd| sd.playback_beats()).unwrap_or(16.0);
             let current_position = (s.current_beat - seq_state.anchor_beat) % loop_beats;
             let iteration = ((s.current_beat - seq_state.anchor_beat) / loop_beats).floor() as u32;
             let play_once = s.sequences.get(name).map(|sd| sd.play_once).unwrap_or(false);
@@ -233,14 +247,30 @@ pub async fn get_active_sequences(
     Json(sequences)
 }
 
-/// GET /live/notes - Get currently active notes
+/// GET /live/notes - Get the pitches sounding on each voice with their age and source
 pub async fn get_active_notes(
     State(state): State<Arc<AppState>>,
-) -> Json<HashMap<String, Vec<u8>>> {
+) -> Json<HashMap<String, VoiceNotes>> {
+    let now = Instant::now();
     let notes = state.handle.with_state(|s| {
         s.voices.iter()
             .filter(|(_, v)| !v.active_notes.is_empty())
-            .map(|(name, v)| (name.clone(), v.active_notes.keys().copied().collect()))
+            .map(|(name, v)| {
+                let notes = v.sounding_notes(now).into_iter().map(|n| SoundingNote {
+                    note: n.note,
+                    name: pitch::note_name(n.note),
+                    count: n.count,
+                    age_ms: n.age.as_secs_f64() * 1000.0,
+                    source: n.source.as_ref().map(|s| s.kind().to_string()),
+                    source_name: n.source.as_ref().and_then(|s| s.name()).map(str::to_string),
+                    stuck: n.stuck,
+                }).collect();
+                (name.clone(), VoiceNotes {
+                    active_count: v.active_note_count(),
+                    polyphony: v.polyphony,
+                    notes,
+                })
+            })
             .collect::<HashMap<_, _>>()
     });
 
@@ -298,3 +328,156 @@ pub async fn get_meters(
 
     Json(meters)
 }
+
+/// GET /live/loudness - Get master bus loudness (momentary/short-term/integrated LUFS)
+pub async fn get_loudness(
+    State(state): State<Arc<AppState>>,
+) -> Json<Loudness> {
+    let loudness = state.handle.with_state(|s| {
+        let l = &s.loudness;
+        Loudness {
+            momentary_lufs: l.momentary_lufs,
+            short_term_lufs: l.short_term_lufs,
+            integrated_lufs: l.integrated_lufs,
+            max_momentary_lufs: l.max_momentary_lufs,
+            target_lufs: l.target_lufs,
+            over_target: l.over_target,
+        }
+    });
+
+    Json(loudness)
+}
+
+/// PUT /live/loudness/target - Set or clear the loudness target
+pub async fn set_loudness_target(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<LoudnessTargetUpdate>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if let Err(e) = state.handle.send(StateMessage::SetLoudnessTarget {
+        target_lufs: req.target_lufs,
+    }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to set loudness target: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// POST /live/loudness/reset - Restart the integrated loudness measurement
+pub async fn reset_loudness(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if let Err(e) = state.handle.send(StateMessage::ResetLoudness) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to reset loudness: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// POST /panic - Gate off and free all sounding notes and send MIDI all-notes-off
+pub async fn panic(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if let Err(e) = state.handle.send(StateMessage::Panic) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to send panic: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+fn cpu_policy_to_api(policy: &vibelang_core::performance::CpuPolicy) -> CpuPolicy {
+    CpuPolicy {
+        threshold: policy.threshold,
+        recovery_margin: policy.recovery_margin,
+        drop_low_priority_voices: policy.drop_low_priority_voices,
+        reduce_polyphony: policy.reduce_polyphony,
+        postpone_fades: policy.postpone_fades,
+        drop_stale_events: policy.drop_stale_events,
+        stale_threshold_ms: policy.stale_threshold_ms,
+        ghost_ratio: policy.ghost_ratio,
+    }
+}
+
+fn performance_to_api(s: &vibelang_core::state::ScriptState) -> Performance {
+    let perf = &s.performance;
+    let status = perf.status.as_ref();
+    Performance {
+        avg_cpu: status.map(|st| st.avg_cpu),
+        peak_cpu: status.map(|st| st.peak_cpu),
+        num_ugens: status.map(|st| st.num_ugens),
+        num_synths: status.map(|st| st.num_synths),
+        sample_rate: status.map(|st| st.actual_sample_rate),
+        over_budget: perf.budget.over_budget,
+        degrade_level: perf.budget.degrade_level,
+        dropped_events: perf.dropped_events,
+        late_events: perf.late_events,
+        stale_dropped_events: perf.stale_dropped_events,
+        postponed_fades: perf.postponed_fades,
+        policy: cpu_policy_to_api(&perf.policy),
+    }
+}
+
+/// GET /live/performance - Get server CPU load and degradation state
+pub async fn get_performance(
+    State(state): State<Arc<AppState>>,
+) -> Json<Performance> {
+    Json(state.handle.with_state(performance_to_api))
+}
+
+/// PATCH /live/performance/policy - Update the CPU budget policy
+pub async fn update_cpu_policy(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CpuPolicyUpdate>,
+) -> Result<Json<Performance>, (StatusCode, Json<ErrorResponse>)> {
+    let mut policy = state.handle.with_state(|s| s.performance.policy.clone());
+    if let Some(threshold) = req.threshold {
+        if !(0.0..=100.0).contains(&threshold) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request("threshold must be between 0 and 100")),
+            ));
+        }
+        policy.threshold = threshold;
+    }
+    if let Some(margin) = req.recovery_margin {
+        policy.recovery_margin = margin.max(0.0);
+    }
+    if let Some(v) = req.drop_low_priority_voices {
+        policy.drop_low_priority_voices = v;
+    }
+    if let Some(v) = req.reduce_polyphony {
+        policy.reduce_polyphony = v;
+    }
+    if let Some(v) = req.postpone_fades {
+        policy.postpone_fades = v;
+    }
+    if let Some(v) = req.drop_stale_events {
+        policy.drop_stale_events = v;
+    }
+    if let Some(v) = req.stale_threshold_ms {
+        policy.stale_threshold_ms = v.max(0.0);
+    }
+    if let Some(v) = req.ghost_ratio {
+        policy.ghost_ratio = v.clamp(0.0, 1.0);
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::SetCpuPolicy { policy: policy.clone() }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to update CPU policy: {}", e))),
+        ));
+    }
+
+    // Reflect the new policy even if the runtime has not applied it yet
+    let mut performance = state.handle.with_state(performance_to_api);
+    performance.policy = cpu_policy_to_api(&policy);
+    Ok(Json(performance))
+}
@@ -0,0 +1,89 @@
+//! Macro control endpoint handlers.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use vibelang_core::macros::MacroControl;
+use vibelang_core::state::StateMessage;
+use vibelang_core::FadeTargetType;
+
+use crate::{
+    models::{ErrorResponse, MacroInfo, MacroTargetInfo, MacroUpdate},
+    AppState,
+};
+
+fn macro_to_api(control: &MacroControl) -> MacroInfo {
+    MacroInfo {
+        name: control.name.clone(),
+        value: control.value,
+        targets: control
+            .writes()
+            .map(|(t, current_value)| MacroTargetInfo {
+                target_type: match t.target_type {
+                    FadeTargetType::Group => "group",
+                    FadeTargetType::Voice => "voice",
+                    FadeTargetType::Effect => "effect",
+                    FadeTargetType::Pattern => "pattern",
+                    FadeTargetType::Melody => "melody",
+                }
+                .to_string(),
+                target_name: t.name.clone(),
+                param_name: t.param.clone(),
+                min_value: t.min,
+                max_value: t.max,
+                current_value,
+            })
+            .collect(),
+    }
+}
+
+fn macro_not_found(name: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse::not_found(&format!("Macro '{}' not found", name))),
+    )
+}
+
+/// GET /macros - List all macro controls
+pub async fn list_macros(State(state): State<Arc<AppState>>) -> Json<Vec<MacroInfo>> {
+    let mut macros = state
+        .handle
+        .with_state(|s| s.macros.values().map(macro_to_api).collect::<Vec<_>>());
+    macros.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(macros)
+}
+
+/// GET /macros/:name - Get a macro control
+pub async fn get_macro(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<MacroInfo>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .handle
+        .with_state(|s| s.macros.get(&name).map(macro_to_api))
+        .map(Json)
+        .ok_or_else(|| macro_not_found(&name))
+}
+
+/// PUT /macros/:name - Set a macro's normalized value
+pub async fn set_macro(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(req): Json<MacroUpdate>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if !state.handle.with_state(|s| s.macros.contains_key(&name)) {
+        return Err(macro_not_found(&name));
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::SetMacro { name, value: req.value }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to set macro: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
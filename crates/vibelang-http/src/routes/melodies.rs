@@ -1,16 +1,19 @@
 //! Melodies endpoint handlers.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
 use vibelang_core::api::context::SourceLocation;
+use vibelang_core::pitch;
 use vibelang_core::state::{LoopStatus as InternalLoopStatus, StateMessage};
 
 use crate::{
-    models::{ErrorResponse, LoopStatus, Melody, MelodyCreate, MelodyEvent, MelodyUpdate, SourceLocation as ApiSourceLocation, StartRequest, StopRequest},
+    models::{ConfirmQuery, ErrorResponse, LoopStatus, Melody, MelodyCreate, MelodyEvent, MelodyUpdate, SourceLocation as ApiSourceLocation, StartRequest, StopRequest},
+    safety::{deletion_preview, Deletion},
     AppState,
 };
 
@@ -71,6 +74,7 @@ fn melody_to_api(ms: &vibelang_core::state::MelodyState) -> Melody {
                 frequency: freq,
                 duration,
                 velocity: None,
+                label: pitch::describe_controls(&e.controls),
                 params: e.controls.iter().cloned().collect(),
             }
         }).collect();
@@ -93,16 +97,12 @@ fn melody_to_api(ms: &vibelang_core::state::MelodyState) -> Melody {
     }
 }
 
-/// Convert frequency to note name (approximate)
+/// Convert frequency to note name, with cents when between notes
 fn freq_to_note_name(freq: f32) -> String {
-    let notes = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-    let midi_note = (12.0 * (freq / 440.0).log2() + 69.0).round() as i32;
-    if !(0..=127).contains(&midi_note) {
-        return format!("{:.1}Hz", freq);
+    match pitch::Pitch::from_freq(freq as f64) {
+        Some(p) => p.to_string(),
+        None => format!("{:.1}Hz", freq),
     }
-    let octave = (midi_note / 12) - 1;
-    let note_index = (midi_note % 12) as usize;
-    format!("{}{}", notes[note_index], octave)
 }
 
 /// GET /melodies - List all melodies
@@ -130,6 +130,23 @@ pub async fn create_melody(
         ));
     }
 
+    write_melody(&state, req).map(|m| (StatusCode::CREATED, Json(m)))
+}
+
+/// PUT /melodies/:name - Create or replace a melody (idempotent)
+pub async fn put_melody(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Melody>), (StatusCode, Json<ErrorResponse>)> {
+    let req: MelodyCreate = super::named_body(body, "name", &name)?;
+    let exists = state.handle.with_state(|s| s.melodies.contains_key(&name));
+    let m = write_melody(&state, req)?;
+    Ok((if exists { StatusCode::OK } else { StatusCode::CREATED }, Json(m)))
+}
+
+/// Create or replace a melody from a create request.
+fn write_melody(state: &AppState, req: MelodyCreate) -> Result<Melody, (StatusCode, Json<ErrorResponse>)> {
     // Check if voice exists and get its synthdef name
     let voice_info = state.handle.with_state(|s| {
         s.voices.get(&req.voice_name).map(|v| (v.group_path.clone(), v.synth_name.clone()))
@@ -156,11 +173,19 @@ pub async fn create_melody(
         vec![]
     };
 
+    let bars = notes_patterns.iter().map(|lane| vibelang_core::api::count_bars(lane)).max().unwrap_or(0);
+    let loop_beats = super::loop_length(
+        req.loop_beats,
+        bars,
+        req.events.iter().map(|e| (e.beat, e.duration)),
+        state.handle.with_state(|s| s.time_signature),
+    );
+
     // Build events from either events array or lanes/melody_string
     let beat_events: Vec<vibelang_core::events::BeatEvent> = if !notes_patterns.is_empty() {
         // Parse all lanes and combine events
         notes_patterns.iter()
-            .flat_map(|lane| parse_melody_string(lane, req.loop_beats, &voice_synth_name))
+            .flat_map(|lane| parse_melody_string(lane, loop_beats, &voice_synth_name))
             .collect()
     } else {
         req.events.iter().map(|e| {
@@ -183,7 +208,7 @@ pub async fn create_melody(
     let pattern = vibelang_core::events::Pattern {
         name: req.name.clone(),
         events: beat_events,
-        loop_length_beats: req.loop_beats,
+        loop_length_beats: loop_beats,
         phase_offset: 0.0,
     };
 
@@ -215,7 +240,7 @@ pub async fn create_melody(
     let melody = state.handle.with_state(|s| s.melodies.get(&req.name).map(melody_to_api));
 
     match melody {
-        Some(m) => Ok((StatusCode::CREATED, Json(m))),
+        Some(m) => Ok(m),
         None => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::internal("Melody created but not found in state")),
@@ -223,7 +248,9 @@ pub async fn create_melody(
     }
 }
 
-/// Parse a simple melody string like "C4 D4 E4 F4"
+/// Parse a simple melody string like "C4 ~ E4 r"
+///
+/// `-` and `~` hold the previous note for another step, `.`, `_` and `r` are rests.
 fn parse_melody_string(melody: &str, loop_beats: f64, synth_def: &str) -> Vec<vibelang_core::events::BeatEvent> {
     let notes: Vec<&str> = melody.split_whitespace().collect();
     if notes.is_empty() {
@@ -231,20 +258,29 @@ fn parse_melody_string(melody: &str, loop_beats: f64, synth_def: &str) -> Vec<vi
     }
 
     let beat_per_note = loop_beats / notes.len() as f64;
-    notes.iter().enumerate()
-        .filter_map(|(i, note)| {
-            if *note == "-" || *note == "." || note.is_empty() {
-                return None;
+    let mut events: Vec<vibelang_core::events::BeatEvent> = Vec::new();
+    // Whether a tie extends the last event (no rest since)
+    let mut tied = false;
+    for (i, note) in notes.iter().enumerate() {
+        if matches!(*note, "-" | "~") {
+            if let Some(gate) = events.last_mut().filter(|_| tied).and_then(|e| e.controls.iter_mut().find(|(k, _)| k == "gate")) {
+                gate.1 += beat_per_note as f32;
             }
-            let freq = note_name_to_freq(note)?;
-            let mut evt = vibelang_core::events::BeatEvent::new(i as f64 * beat_per_note, synth_def);
-            evt.controls = vec![
-                ("freq".to_string(), freq),
-                ("gate".to_string(), beat_per_note as f32 * 0.9),
-            ];
-            Some(evt)
-        })
-        .collect()
+            continue;
+        }
+        let Some(freq) = note_name_to_freq(note) else {
+            tied = false;
+            continue;
+        };
+        let mut evt = vibelang_core::events::BeatEvent::new(i as f64 * beat_per_note, synth_def);
+        evt.controls = vec![
+            ("freq".to_string(), freq),
+            ("gate".to_string(), beat_per_note as f32 * 0.9),
+        ];
+        events.push(evt);
+        tied = true;
+    }
+    events
 }
 
 /// Convert note name to frequency
@@ -282,8 +318,7 @@ fn note_name_to_freq(note: &str) -> Option<f32> {
     };
 
     let midi_note = (octave + 1) * 12 + base_semitone + accidental;
-    let freq = 440.0 * 2.0_f32.powf((midi_note as f32 - 69.0) / 12.0);
-    Some(freq)
+    Some(pitch::note_to_freq(midi_note as f64) as f32)
 }
 
 /// GET /melodies/:name - Get melody by name
@@ -340,7 +375,8 @@ pub async fn update_melody(
 
     // Get current loop_length_beats from loop_pattern
     let current_loop_beats = current.loop_pattern.as_ref().map(|lp| lp.loop_length_beats).unwrap_or(4.0);
-    let loop_beats = update.loop_beats.unwrap_or(current_loop_beats);
+    let signature = state.handle.with_state(|s| s.time_signature);
+    let loop_beats = update.loop_beats.map_or(current_loop_beats, |l| l.to_beats(signature));
 
     // Determine notes_patterns from lanes, melody_string, or existing
     let notes_patterns: Vec<String> = if let Some(lanes) = &update.lanes {
@@ -424,7 +460,8 @@ pub async fn update_melody(
 pub async fn delete_melody(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    Query(query): Query<ConfirmQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
     let exists = state.handle.with_state(|s| s.melodies.contains_key(&name));
     if !exists {
         return Err((
@@ -433,6 +470,11 @@ pub async fn delete_melody(
         ));
     }
 
+    let preview = state.handle.with_state(|s| deletion_preview(s, Deletion::Melody, &name));
+    if let Some(response) = state.safety.guard(preview, &query) {
+        return Ok(response);
+    }
+
     if let Err(e) = state.handle.send(StateMessage::DeleteMelody { name: name.clone() }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -440,7 +482,7 @@ pub async fn delete_melody(
         ));
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 /// POST /melodies/:name/start - Start a melody
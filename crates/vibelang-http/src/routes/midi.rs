@@ -6,14 +6,15 @@ use axum::{
     Json,
 };
 use std::sync::Arc;
-use vibelang_core::state::StateMessage;
+use vibelang_core::pitch;
+use vibelang_core::state::{StateMessage, TakeMode, TakeTargetKind};
 
 use crate::{
     models::{
-        CcRoute, ErrorResponse, ExportQuery, KeyboardRoute, MidiCallback, MidiConnectRequest,
-        MidiDeviceInfo, MidiDeviceState, MidiDevicesResponse, MidiRecordingState,
-        MidiRecordingUpdate, MidiRouting, MonitorRequest, NoteRoute, RecordedMidiNote,
-        RecordedNotesQuery,
+        ArmTakeRequest, ArmedMidiTake, CcRoute, ErrorResponse, ExportQuery, KeyboardRoute,
+        MidiCallback, MidiConnectRequest, MidiDeviceInfo, MidiDeviceState, MidiDevicesResponse,
+        MidiRecordingState, MidiRecordingUpdate, MidiRouting, MidiTake, MidiTakesResponse,
+        MonitorRequest, NoteRoute, RecordedMidiNote, RecordedNotesQuery,
     },
     AppState,
 };
@@ -126,6 +127,7 @@ fn cc_target_to_parts(target: &vibelang_core::midi::CcTarget) -> (String, String
         vibelang_core::midi::CcTarget::Effect(name) => ("effect".to_string(), name.clone()),
         vibelang_core::midi::CcTarget::Group(name) => ("group".to_string(), name.clone()),
         vibelang_core::midi::CcTarget::Global(name) => ("global".to_string(), name.clone()),
+        vibelang_core::midi::CcTarget::Macro(name) => ("macro".to_string(), name.clone()),
     }
 }
 
@@ -166,6 +168,7 @@ pub async fn get_routing(
                     param_name: r.param_name.clone(),
                     min_value: r.min_value,
                     max_value: r.max_value,
+                    param_range: r.param_range,
                 }
             })
         }).collect();
@@ -290,6 +293,7 @@ pub async fn list_cc_routes(
                     param_name: r.param_name.clone(),
                     min_value: r.min_value,
                     max_value: r.max_value,
+                    param_range: r.param_range,
                 }
             })
         }).collect::<Vec<_>>()
@@ -309,6 +313,7 @@ pub async fn add_cc_route(
         "voice" => vibelang_core::midi::CcTarget::Voice(req.target_name.clone()),
         "effect" => vibelang_core::midi::CcTarget::Effect(req.target_name.clone()),
         "global" => vibelang_core::midi::CcTarget::Global(req.target_name.clone()),
+        "macro" => vibelang_core::midi::CcTarget::Macro(req.target_name.clone()),
         _ => {
             return Err((
                 StatusCode::BAD_REQUEST,
@@ -324,6 +329,7 @@ pub async fn add_cc_route(
         max_value: req.max_value,
         curve: vibelang_core::midi::ParameterCurve::Linear,
         channel: Some(req.channel),
+        param_range: req.param_range,
     };
 
     if let Err(e) = state.handle.send(StateMessage::MidiAddCcRoute {
@@ -511,18 +517,154 @@ fn export_as_pattern(notes: &[RecordedMidiNote], _loop_beats: f64) -> String {
 }
 
 fn export_as_melody(notes: &[RecordedMidiNote], _loop_beats: f64) -> String {
-    let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-
     let mut output = String::from("// Melody export\n\"");
     for note in notes {
-        let octave = (note.note / 12) as i32 - 1;
-        let note_idx = (note.note % 12) as usize;
-        output.push_str(&format!("{}{} ", note_names[note_idx], octave));
+        output.push_str(&format!("{} ", pitch::note_name(note.note)));
     }
     output.push('"');
     output
 }
 
+/// GET /midi/takes - List recorded takes and the armed take
+pub async fn list_takes(
+    State(state): State<Arc<AppState>>,
+) -> Json<MidiTakesResponse> {
+    let response = state.handle.with_state(|s| {
+        let recording = &s.midi_recording;
+        MidiTakesResponse {
+            armed: recording.armed_take.as_ref().map(|a| ArmedMidiTake {
+                target: a.target.clone(),
+                mode: a.mode.as_str().to_string(),
+                loop_beats: a.loop_beats,
+                note_count: a.notes.len(),
+            }),
+            auditioning: recording.auditioning(),
+            takes: recording
+                .takes
+                .iter()
+                .map(|t| MidiTake {
+                    id: t.id,
+                    target: t.target.clone(),
+                    kind: match t.kind {
+                        TakeTargetKind::Pattern => "pattern",
+                        TakeTargetKind::Melody => "melody",
+                    }
+                    .to_string(),
+                    mode: t.mode.as_str().to_string(),
+                    loop_beats: t.loop_beats,
+                    notes: t
+                        .notes
+                        .iter()
+                        .map(|n| RecordedMidiNote {
+                            beat: n.beat,
+                            note: n.note,
+                            velocity: n.velocity,
+                            duration: n.duration,
+                            raw_beat: n.raw_beat,
+                            channel: n.channel,
+                            voice_name: n.voice_name.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    });
+
+    Json(response)
+}
+
+/// POST /midi/takes - Arm a take against a pattern or melody loop
+pub async fn arm_take(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ArmTakeRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let Some(mode) = TakeMode::parse(&req.mode) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("Mode must be 'overdub' or 'replace'")),
+        ));
+    };
+
+    let exists = state.handle.with_state(|s| {
+        s.patterns.contains_key(&req.target) || s.melodies.contains_key(&req.target)
+    });
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!(
+                "No pattern or melody named '{}'",
+                req.target
+            ))),
+        ));
+    }
+
+    send_take_message(&state, StateMessage::MidiArmTake { target: req.target, mode })
+}
+
+/// POST /midi/takes/stop - Finish the armed take
+pub async fn stop_take(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    send_take_message(&state, StateMessage::MidiStopTake)
+}
+
+/// POST /midi/takes/{id}/audition - Play a take against its loop
+pub async fn audition_take(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    require_take(&state, id)?;
+    send_take_message(&state, StateMessage::MidiAuditionTake { id: Some(id) })
+}
+
+/// DELETE /midi/takes/audition - End the audition, restoring the loop
+pub async fn end_audition(
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    send_take_message(&state, StateMessage::MidiAuditionTake { id: None })
+}
+
+/// POST /midi/takes/{id}/keep - Apply a take to its loop
+pub async fn keep_take(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    require_take(&state, id)?;
+    send_take_message(&state, StateMessage::MidiKeepTake { id })
+}
+
+/// DELETE /midi/takes/{id} - Discard a take
+pub async fn discard_take(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<u32>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    require_take(&state, id)?;
+    send_take_message(&state, StateMessage::MidiDiscardTake { id })
+}
+
+fn require_take(state: &AppState, id: u32) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if state.handle.with_state(|s| s.midi_recording.take(id).is_some()) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Take {} not found", id))),
+        ))
+    }
+}
+
+fn send_take_message(
+    state: &AppState,
+    msg: StateMessage,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    state.handle.send(msg).map(|_| StatusCode::OK).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to update takes: {}", e))),
+        )
+    })
+}
+
 /// POST /midi/monitor - Enable/disable MIDI monitoring
 pub async fn set_monitor(
     State(state): State<Arc<AppState>>,
@@ -1,15 +1,113 @@
 //! Route handlers for the HTTP API.
 
+pub mod automation;
+pub mod cues;
 pub mod effects;
 pub mod eval;
 pub mod fades;
+pub mod graphs;
 pub mod groups;
+pub mod health;
+pub mod history;
 pub mod live;
+pub mod macros;
 pub mod melodies;
 pub mod midi;
 pub mod patterns;
+pub mod safety;
 pub mod samples;
+pub mod schema;
 pub mod sequences;
+pub mod session;
 pub mod synthdefs;
 pub mod transport;
+pub mod ui;
 pub mod voices;
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::models::{ErrorResponse, TimeInput};
+
+/// Beats an event without a duration takes when inferring a loop length (a sixteenth).
+const EVENT_STEP_BEATS: f64 = 0.25;
+
+/// Loop length of a new pattern or melody: the requested one, or the end
+/// of its content rounded up to whole bars.
+///
+/// `bars` are the bars its step strings span; `events` the start and
+/// duration of its explicit events.
+pub(crate) fn loop_length(
+    requested: Option<TimeInput>,
+    bars: usize,
+    events: impl Iterator<Item = (f64, Option<f64>)>,
+    signature: vibelang_core::TimeSignature,
+) -> f64 {
+    if let Some(requested) = requested {
+        return requested.to_beats(signature);
+    }
+    let beats_per_bar = signature.beats_per_bar();
+    let content_end = events
+        .map(|(beat, duration)| beat + duration.unwrap_or(EVENT_STEP_BEATS))
+        .fold(bars as f64 * beats_per_bar, f64::max);
+    vibelang_core::api::auto_loop_length(content_end, beats_per_bar)
+}
+
+/// Whether the client asked for the shareable text format (`Accept: text/plain`).
+pub(crate) fn accepts_text(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/plain"))
+}
+
+/// Whether a request body is in the shareable text format (`Content-Type: text/plain`).
+pub(crate) fn is_text_body(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/plain"))
+}
+
+/// A `text/plain` response.
+pub(crate) fn text_response(status: StatusCode, text: String) -> Response {
+    (status, [(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response()
+}
+
+/// Parse a JSON request body read as raw bytes.
+pub(crate) fn json_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, (StatusCode, Json<ErrorResponse>)> {
+    serde_json::from_slice(body).map_err(|e| {
+        (StatusCode::BAD_REQUEST, Json(ErrorResponse::bad_request(&format!("Invalid request body: {}", e))))
+    })
+}
+
+/// Parse a `PUT /{kind}/{name}` body as the kind's create request, taking
+/// the name from the path (a name in the body must match it).
+pub(crate) fn named_body<T: DeserializeOwned>(
+    mut body: serde_json::Value,
+    key: &str,
+    name: &str,
+) -> Result<T, (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse::bad_request(&message)));
+
+    let fields = body
+        .as_object_mut()
+        .ok_or_else(|| bad_request("Request body must be a JSON object".to_string()))?;
+    match fields.get(key).and_then(|v| v.as_str()) {
+        Some(body_name) if body_name != name => {
+            return Err(bad_request(format!(
+                "'{}' in body ('{}') does not match the path ('{}')",
+                key, body_name, name
+            )));
+        }
+        _ => {
+            fields.insert(key.to_string(), serde_json::Value::String(name.to_string()));
+        }
+    }
+
+    serde_json::from_value(body).map_err(|e| bad_request(format!("Invalid request body: {}", e)))
+}
@@ -1,16 +1,27 @@
 //! Patterns endpoint handlers.
+//!
+//! Besides JSON, a pattern can be read (`Accept: text/plain`) and written
+//! (`Content-Type: text/plain`) in the shareable text format of
+//! [`vibelang_core::loop_text`].
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
 use vibelang_core::api::context::SourceLocation;
+use vibelang_core::api::count_bars;
+use vibelang_core::api::pattern::pattern_events;
+use vibelang_core::loop_text::{LoopText, LoopTextKind};
 use vibelang_core::state::{LoopStatus as InternalLoopStatus, StateMessage};
+use vibelang_core::variations::PatternVariations;
 
 use crate::{
-    models::{ErrorResponse, LoopStatus, Pattern, PatternCreate, PatternEvent, PatternUpdate, SourceLocation as ApiSourceLocation, StartRequest, StopRequest},
+    models::{ConfirmQuery, ErrorResponse, LoopStatus, Pattern, PatternCreate, PatternEvent, PatternUpdate, SourceLocation as ApiSourceLocation, StartRequest, StopRequest},
+    safety::{deletion_preview, Deletion},
     AppState,
 };
 
@@ -81,6 +92,89 @@ fn pattern_to_api(ps: &vibelang_core::state::PatternState) -> Pattern {
     }
 }
 
+/// Convert internal PatternState to the shareable text format, with its
+/// current params and held take.
+fn pattern_to_text(ps: &vibelang_core::state::PatternState) -> LoopText {
+    let mut text = ps.text.clone().unwrap_or_else(|| {
+        // Patterns written as JSON only keep their steps and length
+        let mut text = LoopText::new(LoopTextKind::Pattern, &ps.name);
+        text.lanes = ps.step_pattern.iter().cloned().collect();
+        text.length = ps.loop_pattern.as_ref().map(|lp| lp.loop_length_beats);
+        text
+    });
+    text.name = ps.name.clone();
+    text.voice = ps.voice_name.clone();
+    // The shortest decimal of each f32, so 0.8 reads back as 0.8
+    text.params.extend(
+        ps.params
+            .iter()
+            .map(|(param, value)| (param.clone(), value.to_string().parse().unwrap_or(*value as f64))),
+    );
+    if text.lanes.len() > 1 {
+        text.locked = ps.locked_variation;
+    }
+    text
+}
+
+/// Respond with a pattern as JSON, or in the text format when the client accepts it.
+fn pattern_response(state: &AppState, headers: &HeaderMap, status: StatusCode, pattern: Pattern) -> Response {
+    if !super::accepts_text(headers) {
+        return (status, Json(pattern)).into_response();
+    }
+    let text = state.handle.with_state(|s| {
+        s.patterns.get(&pattern.name).map(|ps| pattern_to_text(ps).to_string()).unwrap_or_default()
+    });
+    super::text_response(status, text)
+}
+
+/// A pattern to write: a JSON create request or a text definition.
+enum PatternBody {
+    Json(PatternCreate),
+    Text(LoopText),
+}
+
+/// Parse a pattern body by its content type. `PUT` passes the name of its
+/// path, which a name in the body must match.
+fn parse_pattern_body(headers: &HeaderMap, body: &[u8], name: Option<&str>) -> Result<PatternBody, (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse::bad_request(&message)));
+
+    if !super::is_text_body(headers) {
+        return match name {
+            Some(name) => super::named_body(super::json_body(body)?, "name", name).map(PatternBody::Json),
+            None => super::json_body(body).map(PatternBody::Json),
+        };
+    }
+
+    let text = std::str::from_utf8(body).map_err(|_| bad_request("Pattern text must be UTF-8".to_string()))?;
+    let text = LoopText::parse(text).map_err(|e| bad_request(format!("Invalid pattern text: {:#}", e)))?;
+    if text.kind != LoopTextKind::Pattern {
+        return Err(bad_request(format!("'{}' is a {}, not a pattern", text.name, text.kind.as_str())));
+    }
+    match name {
+        Some(name) if text.name != name => Err(bad_request(format!(
+            "'name' in body ('{}') does not match the path ('{}')",
+            text.name, name
+        ))),
+        _ => Ok(PatternBody::Text(text)),
+    }
+}
+
+impl PatternBody {
+    fn name(&self) -> &str {
+        match self {
+            PatternBody::Json(req) => &req.name,
+            PatternBody::Text(text) => &text.name,
+        }
+    }
+
+    fn write(self, state: &AppState) -> Result<Pattern, (StatusCode, Json<ErrorResponse>)> {
+        match self {
+            PatternBody::Json(req) => write_pattern(state, req),
+            PatternBody::Text(text) => write_pattern_text(state, text),
+        }
+    }
+}
+
 /// GET /patterns - List all patterns
 pub async fn list_patterns(
     State(state): State<Arc<AppState>>,
@@ -95,17 +189,39 @@ pub async fn list_patterns(
 /// POST /patterns - Create a new pattern
 pub async fn create_pattern(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<PatternCreate>,
-) -> Result<(StatusCode, Json<Pattern>), (StatusCode, Json<ErrorResponse>)> {
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let req = parse_pattern_body(&headers, &body, None)?;
+
     // Check if pattern already exists
-    let exists = state.handle.with_state(|s| s.patterns.contains_key(&req.name));
+    let exists = state.handle.with_state(|s| s.patterns.contains_key(req.name()));
     if exists {
         return Err((
             StatusCode::CONFLICT,
-            Json(ErrorResponse::conflict(&format!("Pattern '{}' already exists", req.name))),
+            Json(ErrorResponse::conflict(&format!("Pattern '{}' already exists", req.name()))),
         ));
     }
 
+    let p = req.write(&state)?;
+    Ok(pattern_response(&state, &headers, StatusCode::CREATED, p))
+}
+
+/// PUT /patterns/:name - Create or replace a pattern (idempotent)
+pub async fn put_pattern(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let req = parse_pattern_body(&headers, &body, Some(&name))?;
+    let exists = state.handle.with_state(|s| s.patterns.contains_key(&name));
+    let p = req.write(&state)?;
+    Ok(pattern_response(&state, &headers, if exists { StatusCode::OK } else { StatusCode::CREATED }, p))
+}
+
+/// Create or replace a pattern from a create request.
+fn write_pattern(state: &AppState, req: PatternCreate) -> Result<Pattern, (StatusCode, Json<ErrorResponse>)> {
     // Check if voice exists
     let voice_exists = state.handle.with_state(|s| s.voices.contains_key(&req.voice_name));
     if !voice_exists {
@@ -131,10 +247,18 @@ pub async fn create_pattern(
             .unwrap_or_default()
     });
 
+    let bars = req.pattern_string.as_deref().map_or(0, |p| p.split('|').count());
+    let loop_beats = super::loop_length(
+        req.loop_beats,
+        bars,
+        req.events.iter().map(|e| (e.beat, None)),
+        state.handle.with_state(|s| s.time_signature),
+    );
+
     // Build events from either events array or pattern_string
     let beat_events: Vec<vibelang_core::events::BeatEvent> = if let Some(pattern_str) = &req.pattern_string {
         // Parse pattern string (e.g., "x...x...x...x...")
-        parse_pattern_string(pattern_str, req.loop_beats, &synthdef_name)
+        parse_pattern_string(pattern_str, loop_beats, &synthdef_name)
     } else {
         req.events.iter().map(|e| {
             let mut evt = vibelang_core::events::BeatEvent::new(e.beat, &synthdef_name);
@@ -147,7 +271,7 @@ pub async fn create_pattern(
     let pattern = vibelang_core::events::Pattern {
         name: req.name.clone(),
         events: beat_events,
-        loop_length_beats: req.loop_beats,
+        loop_length_beats: loop_beats,
         phase_offset: 0.0,
     };
 
@@ -159,6 +283,7 @@ pub async fn create_pattern(
         pattern,
         source_location: SourceLocation::new(None, None, None),
         step_pattern: req.pattern_string.clone(),
+        text: None,
     }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -179,7 +304,7 @@ pub async fn create_pattern(
     let pattern = state.handle.with_state(|s| s.patterns.get(&req.name).map(pattern_to_api));
 
     match pattern {
-        Some(p) => Ok((StatusCode::CREATED, Json(p))),
+        Some(p) => Ok(p),
         None => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::internal("Pattern created but not found in state")),
@@ -187,6 +312,76 @@ pub async fn create_pattern(
     }
 }
 
+/// Create or replace a pattern from its text format.
+///
+/// Steps are read like scripts read them: bars of the time signature,
+/// with swing, drum kit pads and one take per `steps` line.
+fn write_pattern_text(state: &AppState, text: LoopText) -> Result<Pattern, (StatusCode, Json<ErrorResponse>)> {
+    let Some(voice_name) = text.voice.clone() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("Pattern text needs an 'on <voice>' line")),
+        ));
+    };
+    let voice = state.handle.with_state(|s| s.voices.get(&voice_name).map(|v| (v.group_path.clone(), v.drum_kit.clone())));
+    let Some((group_path, kit)) = voice else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Voice '{}' not found", voice_name))),
+        ));
+    };
+
+    let signature = state.handle.with_state(|s| s.time_signature);
+    let (steps, takes) = match text.lanes.as_slice() {
+        [steps] => (Some(steps.as_str()), &[][..]),
+        takes => (None, takes),
+    };
+    let events = pattern_events(steps, takes, signature.beats_per_bar(), text.swing, kit.as_ref());
+    let bars = text.lanes.iter().map(|lane| count_bars(lane)).max().unwrap_or(0);
+    let loop_beats = text
+        .length
+        .unwrap_or_else(|| super::loop_length(None, bars, std::iter::empty(), signature));
+
+    let name = text.name.clone();
+    let variations = (!takes.is_empty()).then(|| PatternVariations {
+        seed: text.seed,
+        ..PatternVariations::new(takes.len()).with_weights(&text.weights)
+    });
+    let locked = text.locked;
+    let params: Vec<(String, f64)> = text.params.iter().map(|(k, v)| (k.clone(), *v)).collect();
+
+    if let Err(e) = state.handle.send(StateMessage::CreatePattern {
+        name: name.clone(),
+        group_path,
+        voice_name: Some(voice_name),
+        pattern: vibelang_core::events::Pattern {
+            name: name.clone(),
+            events,
+            loop_length_beats: loop_beats,
+            phase_offset: 0.0,
+        },
+        source_location: SourceLocation::new(None, None, None),
+        step_pattern: text.lanes.first().cloned(),
+        text: Some(text),
+    }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to create pattern: {}", e))),
+        ));
+    }
+    for (param, value) in params {
+        let _ = state.handle.send(StateMessage::SetPatternParam {
+            name: name.clone(),
+            param,
+            value: value as f32,
+        });
+    }
+    let _ = state.handle.send(StateMessage::SetPatternVariations { name: name.clone(), variations });
+    let _ = state.handle.send(StateMessage::LockPatternVariation { name: name.clone(), index: locked });
+
+    find_pattern(state, &name).map(|Json(p)| p)
+}
+
 /// Parse a pattern string like "x...x...|x.x.x.x." with bar separators
 /// Supports: x/X = hit, 1-9 = velocity levels, . = rest, | = bar separator
 /// Each bar is 4 beats. Steps per bar determined by character count in each bar.
@@ -256,8 +451,15 @@ fn parse_pattern_string(pattern: &str, loop_beats: f64, synthdef_name: &str) ->
 pub async fn get_pattern(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> Result<Json<Pattern>, (StatusCode, Json<ErrorResponse>)> {
-    let pattern = state.handle.with_state(|s| s.patterns.get(&name).map(pattern_to_api));
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let Json(p) = find_pattern(&state, &name)?;
+    Ok(pattern_response(&state, &headers, StatusCode::OK, p))
+}
+
+/// Look up a pattern by name.
+fn find_pattern(state: &AppState, name: &str) -> Result<Json<Pattern>, (StatusCode, Json<ErrorResponse>)> {
+    let pattern = state.handle.with_state(|s| s.patterns.get(name).map(pattern_to_api));
 
     match pattern {
         Some(p) => Ok(Json(p)),
@@ -295,7 +497,8 @@ pub async fn update_pattern(
 
     // Get current loop_length_beats from loop_pattern
     let current_loop_beats = current.loop_pattern.as_ref().map(|lp| lp.loop_length_beats).unwrap_or(4.0);
-    let loop_beats = update.loop_beats.unwrap_or(current_loop_beats);
+    let signature = state.handle.with_state(|s| s.time_signature);
+    let loop_beats = update.loop_beats.map_or(current_loop_beats, |l| l.to_beats(signature));
 
     // Get synthdef name from original events (we need to preserve this)
     let synthdef_name = current.loop_pattern.as_ref()
@@ -343,6 +546,10 @@ pub async fn update_pattern(
         pattern,
         source_location,
         step_pattern: update.pattern_string.clone().or(current.step_pattern),
+        // New steps or a new length outdate the text the pattern was written as
+        text: current
+            .text
+            .filter(|_| update.pattern_string.is_none() && update.events.is_none() && update.loop_beats.is_none()),
     }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -380,14 +587,15 @@ pub async fn update_pattern(
     }
     // If was_playing && has_active_sequence: the sequence will pick up the updated pattern automatically
 
-    get_pattern(State(state), Path(name)).await
+    find_pattern(&state, &name)
 }
 
 /// DELETE /patterns/:name - Delete a pattern
 pub async fn delete_pattern(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    Query(query): Query<ConfirmQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
     let exists = state.handle.with_state(|s| s.patterns.contains_key(&name));
     if !exists {
         return Err((
@@ -396,6 +604,11 @@ pub async fn delete_pattern(
         ));
     }
 
+    let preview = state.handle.with_state(|s| deletion_preview(s, Deletion::Pattern, &name));
+    if let Some(response) = state.safety.guard(preview, &query) {
+        return Ok(response);
+    }
+
     if let Err(e) = state.handle.send(StateMessage::DeletePattern { name: name.clone() }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -403,7 +616,7 @@ pub async fn delete_pattern(
         ));
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 /// POST /patterns/:name/start - Start a pattern
@@ -427,7 +640,7 @@ pub async fn start_pattern(
         ));
     }
 
-    get_pattern(State(state), Path(name)).await
+    find_pattern(&state, &name)
 }
 
 /// POST /patterns/:name/stop - Stop a pattern
@@ -451,5 +664,5 @@ pub async fn stop_pattern(
         ));
     }
 
-    get_pattern(State(state), Path(name)).await
+    find_pattern(&state, &name)
 }
@@ -0,0 +1,22 @@
+//! Safety settings endpoint handlers.
+
+use axum::{extract::State, Json};
+use std::sync::Arc;
+
+use crate::{models::SafetySettings, AppState};
+
+/// GET /safety - Whether destructive requests need confirmation
+pub async fn get_safety(State(state): State<Arc<AppState>>) -> Json<SafetySettings> {
+    Json(SafetySettings {
+        require_confirmation: state.safety.require_confirmation(),
+    })
+}
+
+/// PUT /safety - Require (or stop requiring) confirmation of destructive requests
+pub async fn put_safety(
+    State(state): State<Arc<AppState>>,
+    Json(settings): Json<SafetySettings>,
+) -> Json<SafetySettings> {
+    state.safety.set_require_confirmation(settings.require_confirmation);
+    Json(settings)
+}
@@ -1,7 +1,7 @@
 //! Samples endpoint handlers.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
@@ -9,10 +9,19 @@ use std::sync::Arc;
 use vibelang_core::state::StateMessage;
 
 use crate::{
-    models::{ErrorResponse, Sample, SampleLoad, SampleSlice},
+    models::{
+        ErrorResponse, PeaksQuery, Sample, SampleLoad, SamplePeaks, SamplePreviewRequest,
+        SampleSlice, SlicePosition,
+    },
     AppState,
 };
 
+/// Default number of peak bins per channel.
+const DEFAULT_PEAKS_RESOLUTION: usize = 1024;
+
+/// Upper bound for the requested peak resolution.
+const MAX_PEAKS_RESOLUTION: usize = 65536;
+
 /// Convert internal SampleInfo to API Sample model
 fn sample_to_api(si: &vibelang_core::state::SampleInfo) -> Sample {
     let slices = si.slices.iter().map(|s| SampleSlice {
@@ -31,6 +40,7 @@ fn sample_to_api(si: &vibelang_core::state::SampleInfo) -> Sample {
         sample_rate: si.sample_rate,
         synthdef_name: si.synthdef_name.clone(),
         slices,
+        detected_key: si.detected_key.map(|key| key.to_string()),
     }
 }
 
@@ -135,3 +145,109 @@ pub async fn free_sample(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// POST /samples/:id/preview - Play a sample once through the audition group
+pub async fn preview_sample(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<Option<SamplePreviewRequest>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let exists = state.handle.with_state(|s| s.samples.contains_key(&id));
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Sample '{}' not found", id))),
+        ));
+    }
+
+    let amp = req.and_then(|r| r.amp).unwrap_or(1.0).max(0.0);
+    if let Err(e) = state.handle.send(StateMessage::PreviewSample { id, amp }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to preview sample: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// GET /samples/:id/peaks - Get min/max waveform peaks and slice positions
+pub async fn get_sample_peaks(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<PeaksQuery>,
+) -> Result<Json<SamplePeaks>, (StatusCode, Json<ErrorResponse>)> {
+    let resolution = query.resolution.unwrap_or(DEFAULT_PEAKS_RESOLUTION);
+    if resolution == 0 || resolution > MAX_PEAKS_RESOLUTION {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!(
+                "resolution must be between 1 and {}",
+                MAX_PEAKS_RESOLUTION
+            ))),
+        ));
+    }
+
+    let Some(info) = state.handle.with_state(|s| s.samples.get(&id).cloned()) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Sample '{}' not found", id))),
+        ));
+    };
+
+    let cached = state.peaks_cache.get(&info.path, resolution);
+    let peaks = match cached {
+        Some(peaks) => peaks,
+        None => {
+            let path = info.path.clone();
+            let computed = tokio::task::spawn_blocking(move || {
+                vibelang_core::api::compute_peaks_from_file(std::path::Path::new(&path), resolution)
+            })
+            .await
+            .ok()
+            .flatten();
+
+            let Some(computed) = computed else {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::internal(&format!(
+                        "Failed to read audio data from '{}'",
+                        info.path
+                    ))),
+                ));
+            };
+            let computed = Arc::new(computed);
+            state
+                .peaks_cache
+                .insert(info.path.clone(), resolution, computed.clone());
+            computed
+        }
+    };
+
+    let to_peak = |frame: i32| {
+        if peaks.frames_per_peak > 0.0 {
+            frame as f64 / peaks.frames_per_peak
+        } else {
+            0.0
+        }
+    };
+    let slices = info.slices.iter().map(|s| SlicePosition {
+        index: s.index,
+        start_frame: s.start_frame,
+        end_frame: s.end_frame,
+        start_peak: to_peak(s.start_frame),
+        end_peak: to_peak(s.end_frame),
+    }).collect();
+
+    Ok(Json(SamplePeaks {
+        id: info.id,
+        num_channels: peaks.min.len(),
+        num_frames: peaks.num_frames,
+        sample_rate: info.sample_rate,
+        resolution: peaks.resolution,
+        frames_per_peak: peaks.frames_per_peak,
+        min: peaks.min.clone(),
+        max: peaks.max.clone(),
+        slices,
+    }))
+}
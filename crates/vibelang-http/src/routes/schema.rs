@@ -0,0 +1,167 @@
+//! JSON schemas of the upsert request bodies.
+//!
+//! Clients (such as the `pyvibelang` module in `clients/python`) check
+//! `api_version` and build request bodies from these schemas. Bump
+//! [`API_VERSION`] whenever a field is removed or changes meaning; adding
+//! optional fields is compatible.
+
+use axum::Json;
+use serde_json::{json, Value};
+
+/// Version of the object API described by `GET /schema`.
+pub const API_VERSION: u32 = 1;
+
+fn number_map() -> Value {
+    json!({ "type": "object", "additionalProperties": { "type": "number" } })
+}
+
+/// A time: beats, a string with a unit ("4 bars") or `{"bars": 4}`;
+/// inferred from the content without a default.
+fn time(default: Option<f64>) -> Value {
+    match default {
+        Some(default) => json!({ "type": ["number", "string", "object"], "default": default }),
+        None => json!({ "type": ["number", "string", "object", "null"] }),
+    }
+}
+
+/// Schemas of the `PUT /{kind}/{name}` bodies, keyed by kind.
+pub fn object_schemas() -> Value {
+    json!({
+        "voice": {
+            "path": "/voices/{name}",
+            "schema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "synth_name": { "type": ["string", "null"] },
+                    "polyphony": { "type": "integer", "minimum": 1, "default": 8 },
+                    "gain": { "type": "number", "default": 1.0 },
+                    "group_path": { "type": "string", "default": "main" },
+                    "params": number_map(),
+                    "sample": { "type": ["string", "null"] },
+                    "sfz": { "type": ["string", "null"] }
+                },
+                "required": ["name"]
+            },
+            "example": { "name": "lead", "synth_name": "saw_lead", "params": { "cutoff": 1200.0 } }
+        },
+        "pattern": {
+            "path": "/patterns/{name}",
+            "schema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "voice_name": { "type": "string" },
+                    "group_path": { "type": ["string", "null"] },
+                    "loop_beats": time(None),
+                    "pattern_string": { "type": ["string", "null"] },
+                    "events": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": { "beat": { "type": "number" }, "params": number_map() },
+                            "required": ["beat"]
+                        }
+                    },
+                    "params": number_map()
+                },
+                "required": ["name", "voice_name"]
+            },
+            "example": { "name": "kick_loop", "voice_name": "kick", "pattern_string": "x...x...x...x..." }
+        },
+        "melody": {
+            "path": "/melodies/{name}",
+            "schema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "voice_name": { "type": "string" },
+                    "group_path": { "type": ["string", "null"] },
+                    "loop_beats": time(None),
+                    "melody_string": { "type": ["string", "null"] },
+                    "lanes": { "type": ["array", "null"], "items": { "type": "string" } },
+                    "events": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "beat": { "type": "number" },
+                                "note": { "type": "string" },
+                                "frequency": { "type": ["number", "null"] },
+                                "duration": { "type": ["number", "null"] },
+                                "velocity": { "type": ["number", "null"] },
+                                "params": number_map()
+                            },
+                            "required": ["beat", "note"]
+                        }
+                    },
+                    "params": number_map()
+                },
+                "required": ["name", "voice_name"]
+            },
+            "example": { "name": "hook", "voice_name": "lead", "loop_beats": 8.0, "melody_string": "C4 - E4 - | G4 - - -" }
+        },
+        "sequence": {
+            "path": "/sequences/{name}",
+            "schema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "loop_beats": time(Some(16.0)),
+                    "clips": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "type": { "enum": ["pattern", "melody", "fade", "sequence"] },
+                                "name": { "type": "string" },
+                                "start_beat": { "type": "number" },
+                                "end_beat": { "type": "number" },
+                                "mode": { "type": "string", "description": "\"loop\", \"once\" or \"loop:N\"" }
+                            },
+                            "required": ["type", "name", "start_beat", "end_beat", "mode"]
+                        }
+                    }
+                },
+                "required": ["name"]
+            },
+            "example": {
+                "name": "intro",
+                "loop_beats": 16.0,
+                "clips": [{ "type": "pattern", "name": "kick_loop", "start_beat": 0.0, "end_beat": 16.0, "mode": "loop" }]
+            }
+        }
+    })
+}
+
+/// GET /schema - API version and object schemas
+pub async fn get_schema() -> Json<Value> {
+    Json(json!({
+        "api_version": API_VERSION,
+        "objects": object_schemas(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{MelodyCreate, PatternCreate, SequenceCreate, VoiceCreate};
+
+    #[test]
+    fn test_examples_match_request_models() {
+        let schemas = object_schemas();
+        let example = |kind: &str| schemas[kind]["example"].clone();
+
+        assert!(serde_json::from_value::<VoiceCreate>(example("voice")).is_ok());
+        assert!(serde_json::from_value::<PatternCreate>(example("pattern")).is_ok());
+        assert!(serde_json::from_value::<MelodyCreate>(example("melody")).is_ok());
+        assert!(serde_json::from_value::<SequenceCreate>(example("sequence")).is_ok());
+
+        // Every property a request model reads is described by its schema
+        let properties = |kind: &str| schemas[kind]["schema"]["properties"].as_object().unwrap().len();
+        assert_eq!(properties("voice"), 8);
+        assert_eq!(properties("pattern"), 7);
+        assert_eq!(properties("melody"), 8);
+        assert_eq!(properties("sequence"), 3);
+    }
+}
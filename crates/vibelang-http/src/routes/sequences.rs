@@ -1,15 +1,17 @@
 //! Sequences endpoint handlers.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
 use vibelang_core::state::StateMessage;
 
 use crate::{
-    models::{ErrorResponse, Sequence, SequenceClip, SequenceCreate, SequenceStartRequest, SequenceUpdate, SourceLocation as ApiSourceLocation},
+    models::{ConfirmQuery, ErrorResponse, KeyChange, Sequence, SequenceClip, SequenceCreate, SequenceStartRequest, SequenceUpdate, SourceLocation as ApiSourceLocation},
+    safety::{deletion_preview, Deletion},
     AppState,
 };
 
@@ -48,12 +50,16 @@ fn sequence_to_api(sd: &vibelang_core::sequences::SequenceDefinition, active: bo
             start_beat: c.start,
             end_beat: c.end,
             mode,
+            speed: c.speed,
         }
     }).collect();
 
     Sequence {
         name: sd.name.clone(),
         loop_beats: sd.loop_beats,
+        speed: sd.speed,
+        transpose: sd.transpose,
+        key_changes: sd.key_changes.iter().map(key_change_to_api).collect(),
         clips,
         play_once: sd.play_once,
         active,
@@ -61,6 +67,22 @@ fn sequence_to_api(sd: &vibelang_core::sequences::SequenceDefinition, active: bo
     }
 }
 
+fn key_change_to_api(k: &vibelang_core::sequences::KeyChange) -> KeyChange {
+    KeyChange {
+        start_beat: k.start,
+        end_beat: k.end,
+        semitones: k.semitones,
+    }
+}
+
+fn key_change_from_api(k: &KeyChange) -> vibelang_core::sequences::KeyChange {
+    vibelang_core::sequences::KeyChange {
+        start: k.start_beat,
+        end: k.end_beat,
+        semitones: k.semitones,
+    }
+}
+
 /// Parse clip mode string to ClipMode enum
 fn parse_clip_mode(mode: &str) -> vibelang_core::sequences::ClipMode {
     if mode == "loop" {
@@ -103,6 +125,23 @@ pub async fn create_sequence(
         ));
     }
 
+    write_sequence(&state, req).map(|seq| (StatusCode::CREATED, Json(seq)))
+}
+
+/// PUT /sequences/:name - Create or replace a sequence (idempotent)
+pub async fn put_sequence(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Sequence>), (StatusCode, Json<ErrorResponse>)> {
+    let req: SequenceCreate = super::named_body(body, "name", &name)?;
+    let exists = state.handle.with_state(|s| s.sequences.contains_key(&name));
+    let seq = write_sequence(&state, req)?;
+    Ok((if exists { StatusCode::OK } else { StatusCode::CREATED }, Json(seq)))
+}
+
+/// Create or replace a sequence from a create request.
+fn write_sequence(state: &AppState, req: SequenceCreate) -> Result<Sequence, (StatusCode, Json<ErrorResponse>)> {
     // Build clips
     let clips: Vec<vibelang_core::sequences::SequenceClip> = req.clips.iter().map(|c| {
         let source = match c.clip_type.as_str() {
@@ -120,17 +159,23 @@ pub async fn create_sequence(
             end: c.end_beat,
             source,
             mode,
+            speed: vibelang_core::sequences::sanitize_speed(c.speed),
         }
     }).collect();
 
     // Create the sequence definition
     let sequence = vibelang_core::sequences::SequenceDefinition {
         name: req.name.clone(),
-        loop_beats: req.loop_beats,
+        loop_beats: req.loop_beats.to_beats(state.handle.with_state(|s| s.time_signature)),
         clips,
         generation: 0,
         play_once: false,
         source_location: vibelang_core::api::context::SourceLocation::unknown(),
+        speed: vibelang_core::sequences::sanitize_speed(req.speed),
+        launch_quantization: None,
+        legato: false,
+        transpose: req.transpose,
+        key_changes: req.key_changes.iter().map(key_change_from_api).collect(),
     };
 
     // Create the sequence
@@ -150,7 +195,7 @@ pub async fn create_sequence(
     });
 
     match sequence {
-        Some(seq) => Ok((StatusCode::CREATED, Json(seq))),
+        Some(seq) => Ok(seq),
         None => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::internal("Sequence created but not found in state")),
@@ -197,7 +242,8 @@ pub async fn update_sequence(
         }
     };
 
-    let loop_beats = update.loop_beats.unwrap_or(current.loop_beats);
+    let signature = state.handle.with_state(|s| s.time_signature);
+    let loop_beats = update.loop_beats.map_or(current.loop_beats, |l| l.to_beats(signature));
     let clips: Vec<vibelang_core::sequences::SequenceClip> = if let Some(new_clips) = &update.clips {
         new_clips.iter().map(|c| {
             let source = match c.clip_type.as_str() {
@@ -215,6 +261,7 @@ pub async fn update_sequence(
                 end: c.end_beat,
                 source,
                 mode,
+                speed: vibelang_core::sequences::sanitize_speed(c.speed),
             }
         }).collect()
     } else {
@@ -232,6 +279,14 @@ pub async fn update_sequence(
         generation: 0,
         play_once: current.play_once,
         source_location: current.source_location.clone(),
+        speed: update.speed.map(vibelang_core::sequences::sanitize_speed).unwrap_or(current.speed),
+        launch_quantization: current.launch_quantization,
+        legato: current.legato,
+        transpose: update.transpose.unwrap_or(current.transpose),
+        key_changes: match &update.key_changes {
+            Some(key_changes) => key_changes.iter().map(key_change_from_api).collect(),
+            None => current.key_changes.clone(),
+        },
     };
 
     if let Err(e) = state.handle.send(StateMessage::CreateSequence { sequence }) {
@@ -248,7 +303,8 @@ pub async fn update_sequence(
 pub async fn delete_sequence(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    Query(query): Query<ConfirmQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
     let exists = state.handle.with_state(|s| s.sequences.contains_key(&name));
     if !exists {
         return Err((
@@ -257,6 +313,11 @@ pub async fn delete_sequence(
         ));
     }
 
+    let preview = state.handle.with_state(|s| deletion_preview(s, Deletion::Sequence, &name));
+    if let Some(response) = state.safety.guard(preview, &query) {
+        return Ok(response);
+    }
+
     if let Err(e) = state.handle.send(StateMessage::DeleteSequence { name: name.clone() }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -264,7 +325,7 @@ pub async fn delete_sequence(
         ));
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 /// POST /sequences/:name/start - Start a sequence
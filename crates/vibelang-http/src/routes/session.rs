@@ -0,0 +1,79 @@
+//! Session file endpoint handlers.
+//!
+//! A session file holds the full state of the session (see
+//! [`vibelang_core::session_file`]). Loading one replaces the session.
+
+use axum::{body::Bytes, extract::State, http::StatusCode, Json};
+use std::path::Path;
+use std::sync::Arc;
+use vibelang_core::session_file::SessionFile;
+
+use crate::{
+    models::{ErrorResponse, SessionFileInfo, SessionPathRequest},
+    AppState,
+};
+
+fn load(state: &AppState, file: &SessionFile) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    state.handle.load_session(file).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!("Failed to load session: {:#}", e))),
+        )
+    })
+}
+
+/// GET /session - Get the full session as a session file
+pub async fn get_session(State(state): State<Arc<AppState>>) -> Json<SessionFile> {
+    Json(state.handle.state().session_file())
+}
+
+/// PUT /session - Replace the session with the session file in the body
+pub async fn put_session(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let source = std::str::from_utf8(&body).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("Session file must be UTF-8")),
+        )
+    })?;
+    let file = SessionFile::from_json(source).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!("{:#}", e))),
+        )
+    })?;
+    load(&state, &file)?;
+    Ok(StatusCode::OK)
+}
+
+/// POST /session/save - Write the session to a file on the server
+pub async fn save_session(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SessionPathRequest>,
+) -> Result<Json<SessionFileInfo>, (StatusCode, Json<ErrorResponse>)> {
+    let file = state.handle.state().session_file();
+    file.save(Path::new(&req.path)).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("{:#}", e))),
+        )
+    })?;
+    Ok(Json(SessionFileInfo::new(&req.path, &file)))
+}
+
+/// POST /session/load - Replace the session with a file on the server
+pub async fn load_session(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SessionPathRequest>,
+) -> Result<Json<SessionFileInfo>, (StatusCode, Json<ErrorResponse>)> {
+    let file = SessionFile::load(Path::new(&req.path)).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!("{:#}", e))),
+        )
+    })?;
+    load(&state, &file)?;
+    Ok(Json(SessionFileInfo::new(&req.path, &file)))
+}
@@ -1,75 +1,166 @@
 //! SynthDefs endpoint handlers.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
+use vibelang_dsp::ParamRange;
+use vibelang_std::StdlibEntry;
 
 use crate::{
-    models::{ErrorResponse, SynthDef, SynthDefParam},
+    models::{ErrorResponse, SynthDef, SynthDefParam, SynthDefsQuery},
     AppState,
 };
 
 /// Convert internal SynthDefInfo to API SynthDef model
-fn synthdef_to_api(name: &str, _bytes: &[u8]) -> SynthDef {
-    // Note: We can't easily extract params from compiled synthdef bytes
-    // In a full implementation, we'd store param metadata separately
-    SynthDef {
+fn synthdef_to_api(name: &str, ranges: Option<&HashMap<String, ParamRange>>) -> SynthDef {
+    // Stdlib definitions carry their real parameters in the index
+    let mut synthdef = match vibelang_std::find_definition(name) {
+        Some(entry) => stdlib_to_api(entry, true),
+        None => SynthDef {
+            name: name.to_string(),
+            params: registered_params(name).unwrap_or_else(common_params),
+            source: "user".to_string(),
+            loaded: true,
+            category: None,
+            description: None,
+            import_path: None,
+        },
+    };
+    if let Some(ranges) = ranges {
+        apply_param_ranges(&mut synthdef.params, ranges);
+    }
+    synthdef
+}
+
+/// Parameters of a synthdef or effect defined in this process, without the
+/// hidden routing and ramp controls.
+fn registered_params(name: &str) -> Option<Vec<SynthDefParam>> {
+    let mut defaults = vibelang_dsp::get_synthdef_param_defaults(name);
+    if defaults.is_empty() {
+        defaults = vibelang_dsp::get_effect_param_defaults(name);
+    }
+    if defaults.is_empty() {
+        return None;
+    }
+    let hidden = |param: &str| {
+        param.starts_with("__")
+            || param == vibelang_dsp::DIAG_BUS_PARAM
+            || vibelang_dsp::RAMP_PARAMS.iter().any(|p| vibelang_dsp::ramp_lag_control(p) == param)
+    };
+    let mut params: Vec<SynthDefParam> = defaults
+        .into_iter()
+        .filter(|(param, _)| !hidden(param))
+        .map(|(param, default)| SynthDefParam {
+            name: param,
+            default_value: default,
+            min_value: None,
+            max_value: None,
+            unit: None,
+            curve: None,
+        })
+        .collect();
+    params.sort_by(|a, b| a.name.cmp(&b.name));
+    Some(params)
+}
+
+/// Params most synthdefs have, for synthdefs loaded from bytes.
+fn common_params() -> Vec<SynthDefParam> {
+    let param = |name: &str, default_value: f32, range: Option<(f32, f32)>| SynthDefParam {
         name: name.to_string(),
-        params: vec![
-            // Common params that most synthdefs have
-            SynthDefParam {
-                name: "freq".to_string(),
-                default_value: 440.0,
-                min_value: Some(20.0),
-                max_value: Some(20000.0),
-            },
-            SynthDefParam {
-                name: "amp".to_string(),
-                default_value: 0.5,
-                min_value: Some(0.0),
-                max_value: Some(1.0),
-            },
-            SynthDefParam {
-                name: "gate".to_string(),
-                default_value: 1.0,
-                min_value: Some(0.0),
-                max_value: Some(1.0),
-            },
-            SynthDefParam {
-                name: "out".to_string(),
-                default_value: 0.0,
+        default_value,
+        min_value: range.map(|(min, _)| min),
+        max_value: range.map(|(_, max)| max),
+        unit: None,
+        curve: None,
+    };
+    vec![
+        param("freq", 440.0, Some((20.0, 20000.0))),
+        param("amp", 0.5, Some((0.0, 1.0))),
+        param("gate", 1.0, Some((0.0, 1.0))),
+        param("out", 0.0, None),
+    ]
+}
+
+/// Fill in the declared ranges (`param("cutoff", 1200, 20, 20000, "hz", "exp")`).
+fn apply_param_ranges(params: &mut [SynthDefParam], ranges: &HashMap<String, ParamRange>) {
+    for param in params.iter_mut() {
+        if let Some(range) = ranges.get(&param.name) {
+            param.min_value = Some(range.min);
+            param.max_value = Some(range.max);
+            param.unit = range.unit.clone();
+            param.curve = Some(range.curve.as_str().to_string());
+        }
+    }
+}
+
+/// Convert a stdlib index entry to API SynthDef model
+fn stdlib_to_api(entry: &StdlibEntry, loaded: bool) -> SynthDef {
+    SynthDef {
+        name: entry.name.to_string(),
+        params: entry
+            .params
+            .iter()
+            .map(|(name, default)| SynthDefParam {
+                name: name.to_string(),
+                default_value: *default as f32,
                 min_value: None,
                 max_value: None,
-            },
-        ],
-        source: "user".to_string(),
+                unit: None,
+                curve: None,
+            })
+            .collect(),
+        source: "stdlib".to_string(),
+        loaded,
+        category: Some(entry.category.to_string()),
+        description: Some(entry.description.to_string()),
+        import_path: Some(entry.import_path()),
     }
 }
 
 /// GET /synthdefs - List all synthdefs
+///
+/// With `?stdlib=true`, stdlib definitions that aren't loaded yet are listed
+/// too (`loaded: false`, with the path to import).
 pub async fn list_synthdefs(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<SynthDefsQuery>,
 ) -> Json<Vec<SynthDef>> {
-    let synthdefs = state.handle.with_state(|s| {
-        s.synthdefs.iter()
-            .map(|(name, bytes)| synthdef_to_api(name, bytes))
+    let mut synthdefs = state.handle.with_state(|s| {
+        s.synthdefs
+            .keys()
+            .map(|name| synthdef_to_api(name, s.param_ranges.get(name)))
             .collect::<Vec<_>>()
     });
 
+    if query.stdlib {
+        let unloaded: Vec<SynthDef> = vibelang_std::stdlib_index()
+            .iter()
+            .filter(|entry| !synthdefs.iter().any(|sd| sd.name == entry.name))
+            .map(|entry| stdlib_to_api(entry, false))
+            .collect();
+        synthdefs.extend(unloaded);
+    }
+
     Json(synthdefs)
 }
 
-/// GET /synthdefs/:name - Get synthdef by name
+/// GET /synthdefs/:name - Get synthdef by name (falls back to the stdlib index)
 pub async fn get_synthdef(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
 ) -> Result<Json<SynthDef>, (StatusCode, Json<ErrorResponse>)> {
-    let synthdef = state.handle.with_state(|s| {
-        s.synthdefs.get(&name).map(|bytes| synthdef_to_api(&name, bytes))
-    });
+    let synthdef = state
+        .handle
+        .with_state(|s| {
+            s.synthdefs
+                .contains_key(&name)
+                .then(|| synthdef_to_api(&name, s.param_ranges.get(&name)))
+        })
+        .or_else(|| vibelang_std::find_definition(&name).map(|entry| stdlib_to_api(entry, false)));
 
     match synthdef {
         Some(sd) => Ok(Json(sd)),
@@ -79,3 +170,28 @@ pub async fn get_synthdef(
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stdlib_synthdef_params() {
+        let kick = synthdef_to_api("kick_909", None);
+        assert_eq!(kick.source, "stdlib");
+        assert!(kick.loaded);
+        assert_eq!(kick.import_path.as_deref(), Some("stdlib/drums/kicks/kick_909.vibe"));
+        assert!(kick.params.iter().any(|p| p.name == "freq" && p.default_value == 65.0));
+
+        let ranges = HashMap::from([(
+            "freq".to_string(),
+            ParamRange::new(20.0, 2000.0, Some("hz".to_string()), vibelang_dsp::ParamCurve::Exponential).unwrap(),
+        )]);
+        let user = synthdef_to_api("my_own_synth", Some(&ranges));
+        assert_eq!(user.source, "user");
+        assert!(user.category.is_none());
+        let freq = user.params.iter().find(|p| p.name == "freq").unwrap();
+        assert_eq!((freq.min_value, freq.max_value), (Some(20.0), Some(2000.0)));
+        assert_eq!((freq.unit.as_deref(), freq.curve.as_deref()), (Some("hz"), Some("exp")));
+    }
+}
@@ -5,15 +5,46 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use vibelang_core::locators::Locators;
+use vibelang_core::quantization::{LaunchKind, LaunchQuantization};
 use vibelang_core::state::StateMessage;
+use vibelang_core::timing::ClockSource;
 
 use crate::{
-    models::{ErrorResponse, SeekRequest, TimeSignature, TransportState, TransportUpdate},
+    models::{ErrorResponse, JumpRequest, Marker, PendingJump, SeekRequest, TimeSignature, TransportState, TransportUpdate},
     AppState,
 };
 
+/// Convert locators to API models, in song order
+pub(crate) fn markers_to_api(locators: &Locators) -> Vec<Marker> {
+    locators
+        .sorted()
+        .into_iter()
+        .map(|(name, beat)| Marker { name, beat })
+        .collect()
+}
+
+/// Convert a pending locator jump to its API model
+pub(crate) fn pending_jump_to_api(locators: &Locators) -> Option<PendingJump> {
+    locators.pending.as_ref().map(|jump| PendingJump {
+        marker: jump.marker.clone(),
+        at_beat: jump.at_beat,
+        target_beat: jump.target_beat,
+    })
+}
+
+/// Convert launch grids by entity type to their API model
+pub(crate) fn launch_quantization_to_api(launch: &LaunchQuantization) -> HashMap<String, f64> {
+    launch
+        .defaults
+        .iter()
+        .map(|(kind, beats)| (kind.as_str().to_string(), *beats))
+        .collect()
+}
+
 /// GET /transport - Get current transport state
 pub async fn get_transport(
     State(state): State<Arc<AppState>>,
@@ -25,7 +56,7 @@ pub async fn get_transport(
             .filter_map(|(seq_name, _)| {
                 s.sequences.get(seq_name)
                     .filter(|seq| seq.loop_beats > 0.0)
-                    .map(|seq| seq.loop_beats)
+                    .map(|seq| seq.playback_beats())
             })
             .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
@@ -53,9 +84,13 @@ pub async fn get_transport(
             running: s.transport_running,
             current_beat: s.current_beat,
             quantization_beats: s.quantization_beats,
+            launch_quantization: launch_quantization_to_api(&s.launch_quantization),
             loop_beats,
             loop_beat,
             server_time_ms,
+            markers: markers_to_api(&s.locators),
+            pending_jump: pending_jump_to_api(&s.locators),
+            clock_source: s.clock_source.as_str().to_string(),
         }
     });
 
@@ -98,7 +133,7 @@ pub async fn update_transport(
 
     // Apply quantization change
     if let Some(q) = update.quantization_beats {
-        if let Err(e) = state.handle.send(StateMessage::SetQuantization { beats: q }) {
+        if let Err(e) = state.handle.send(StateMessage::SetQuantization { grid: q.0 }) {
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse::internal(&format!("Failed to set quantization: {}", e))),
@@ -106,6 +141,50 @@ pub async fn update_transport(
         }
     }
 
+    // Apply launch grid changes
+    if let Some(grids) = update.launch_quantization {
+        let mut messages = Vec::new();
+        for (name, grid) in grids {
+            let Some(kind) = LaunchKind::parse(&name) else {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse::bad_request(&format!(
+                        "Unknown launch quantization type '{}' (expected pattern, melody, sequence or fade)",
+                        name
+                    ))),
+                ));
+            };
+            messages.push(StateMessage::SetLaunchQuantization {
+                kind,
+                grid: grid.map(|g| g.0),
+            });
+        }
+        for message in messages {
+            if let Err(e) = state.handle.send(message) {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::internal(&format!("Failed to set launch quantization: {}", e))),
+                ));
+            }
+        }
+    }
+
+    // Apply clock source change
+    if let Some(name) = update.clock_source {
+        let Some(source) = ClockSource::parse(&name) else {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::bad_request("clock_source must be \"internal\" or \"midi\"")),
+            ));
+        };
+        if let Err(e) = state.handle.send(StateMessage::SetClockSource { source }) {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal(&format!("Failed to set clock source: {}", e))),
+            ));
+        }
+    }
+
     // Return updated state
     Ok(get_transport(State(state)).await)
 }
@@ -143,14 +222,15 @@ pub async fn seek_transport(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SeekRequest>,
 ) -> Result<Json<TransportState>, (StatusCode, Json<ErrorResponse>)> {
-    if req.beat < 0.0 {
+    let signature = state.handle.with_state(|s| s.time_signature);
+    if req.beat.to_beats(signature) < 0.0 {
         return Err((
             StatusCode::BAD_REQUEST,
             Json(ErrorResponse::bad_request("Beat position cannot be negative")),
         ));
     }
 
-    if let Err(e) = state.handle.send(StateMessage::SeekTransport { beat: req.beat }) {
+    if let Err(e) = state.handle.send(StateMessage::SeekTransport { position: req.beat.0 }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::internal(&format!("Failed to seek: {}", e))),
@@ -159,3 +239,35 @@ pub async fn seek_transport(
 
     Ok(get_transport(State(state)).await)
 }
+
+/// POST /transport/jump - Jump to a locator, by default at the next bar line
+pub async fn jump_transport(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<JumpRequest>,
+) -> Result<Json<TransportState>, (StatusCode, Json<ErrorResponse>)> {
+    let (missing, signature) = state.handle.with_state(|s| (s.locators.get(&req.marker).is_none(), s.time_signature));
+    if missing {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Marker '{}' not found", req.marker))),
+        ));
+    }
+    if req.quantization_beats.is_some_and(|q| q.to_beats(signature) < 0.0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request("Quantization cannot be negative")),
+        ));
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::JumpToMarker {
+        name: req.marker,
+        quantization: req.quantization_beats.map(|q| q.0),
+    }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to jump: {}", e))),
+        ));
+    }
+
+    Ok(get_transport(State(state)).await)
+}
@@ -0,0 +1,31 @@
+//! Web UI handlers.
+//!
+//! Serves the browser control surface embedded in `vibelang-web`.
+
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// Build a response for an embedded asset.
+fn asset_response(asset: vibelang_web::Asset) -> Response {
+    ([(header::CONTENT_TYPE, asset.content_type)], asset.contents).into_response()
+}
+
+/// GET /ui - Serve the web UI entry point
+pub async fn index() -> Response {
+    asset_response(vibelang_web::index())
+}
+
+/// GET /ui/*path - Serve a web UI asset
+///
+/// Unknown paths without a file extension fall back to the index page so the
+/// SPA can handle client-side routes.
+pub async fn asset(Path(path): Path<String>) -> Response {
+    match vibelang_web::asset(&path) {
+        Some(asset) => asset_response(asset),
+        None if !path.contains('.') => asset_response(vibelang_web::index()),
+        None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+}
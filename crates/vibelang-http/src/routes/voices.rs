@@ -1,21 +1,28 @@
 //! Voices endpoint handlers.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 use vibelang_core::api::context::SourceLocation;
 use vibelang_core::state::{StateMessage, VoiceState};
 
 use crate::{
-    models::{ErrorResponse, NoteOffRequest, NoteOnRequest, ParamSet, SourceLocation as ApiSourceLocation, TriggerRequest, Voice, VoiceCreate, VoiceUpdate},
+    models::{
+        ConfirmQuery, ErrorResponse, MorphParamsRequest, NoteOffRequest, NoteOnRequest, ParamSet,
+        RandomizeParamsRequest, SourceLocation as ApiSourceLocation, StopVoiceRequest, TriggerRequest, Voice, VoiceCreate,
+        VoiceUpdate,
+    },
+    safety::{deletion_preview, Deletion},
     AppState,
 };
 
 /// Convert internal SourceLocation to API model
-fn source_location_to_api(sl: &vibelang_core::api::context::SourceLocation) -> Option<ApiSourceLocation> {
+pub(super) fn source_location_to_api(sl: &vibelang_core::api::context::SourceLocation) -> Option<ApiSourceLocation> {
     if sl.file.is_some() || sl.line.is_some() {
         Some(ApiSourceLocation {
             file: sl.file.clone(),
@@ -47,6 +54,8 @@ fn voice_to_api(vs: &vibelang_core::state::VoiceState) -> Voice {
         running: vs.running,
         running_node_id: vs.running_node_id,
         source_location: source_location_to_api(&vs.source_location),
+        priority: vs.priority,
+        pre_roll_ms: vs.pre_roll_ms,
     }
 }
 
@@ -75,6 +84,23 @@ pub async fn create_voice(
         ));
     }
 
+    Ok((StatusCode::CREATED, Json(write_voice(&state, req))))
+}
+
+/// PUT /voices/:name - Create or replace a voice (idempotent)
+pub async fn put_voice(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Voice>), (StatusCode, Json<ErrorResponse>)> {
+    let req: VoiceCreate = super::named_body(body, "name", &name)?;
+    let exists = state.handle.with_state(|s| s.voices.contains_key(&name));
+    let voice = write_voice(&state, req);
+    Ok((if exists { StatusCode::OK } else { StatusCode::CREATED }, Json(voice)))
+}
+
+/// Create or replace a voice from a create request.
+fn write_voice(state: &AppState, req: VoiceCreate) -> Voice {
     // Get group name from path
     let group_name = req.group_path.split('/').next_back().unwrap_or("main").to_string();
 
@@ -110,7 +136,7 @@ pub async fn create_voice(
     });
 
     log::debug!("Voice '{}' created successfully", req.name);
-    Ok((StatusCode::CREATED, Json(voice)))
+    voice
 }
 
 /// GET /voices/:name - Get voice by name
@@ -145,15 +171,14 @@ pub async fn update_voice(
     }
 
     // Update params
-    for (param_name, value) in update.params {
-        if let Err(e) = state.handle.send(StateMessage::SetVoiceParam {
+    if !update.params.is_empty() {
+        if let Err(e) = state.handle.send(StateMessage::SetVoiceParams {
             name: name.clone(),
-            param: param_name,
-            value,
+            params: update.params,
         }) {
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::internal(&format!("Failed to update param: {}", e))),
+                Json(ErrorResponse::internal(&format!("Failed to update params: {}", e))),
             ));
         }
     }
@@ -166,7 +191,8 @@ pub async fn update_voice(
 pub async fn delete_voice(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    Query(query): Query<ConfirmQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
     let exists = state.handle.with_state(|s| s.voices.contains_key(&name));
     if !exists {
         return Err((
@@ -175,6 +201,11 @@ pub async fn delete_voice(
         ));
     }
 
+    let preview = state.handle.with_state(|s| deletion_preview(s, Deletion::Voice, &name));
+    if let Some(response) = state.safety.guard(preview, &query) {
+        return Ok(response);
+    }
+
     if let Err(e) = state.handle.send(StateMessage::DeleteVoice { name: name.clone() }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -182,7 +213,7 @@ pub async fn delete_voice(
         ));
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 /// POST /voices/:name/trigger - Trigger a voice
@@ -208,6 +239,7 @@ pub async fn trigger_voice(
         synth_name: None,
         group_path: None,
         params,
+        quantize: false,
     }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -219,9 +251,13 @@ pub async fn trigger_voice(
 }
 
 /// POST /voices/:name/stop - Stop a running voice
+///
+/// Releases its synths; an optional body fades them out (`fade` seconds)
+/// or frees them at once (`choke`).
 pub async fn stop_voice(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
+    body: Option<Json<StopVoiceRequest>>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
     let exists = state.handle.with_state(|s| s.voices.contains_key(&name));
     if !exists {
@@ -231,7 +267,12 @@ pub async fn stop_voice(
         ));
     }
 
-    if let Err(e) = state.handle.send(StateMessage::StopVoice { name: name.clone() }) {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    if let Err(e) = state.handle.send(StateMessage::StopVoice {
+        name: name.clone(),
+        fade: req.fade,
+        choke: req.choke,
+    }) {
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::internal(&format!("Failed to stop voice: {}", e))),
@@ -313,7 +354,8 @@ pub async fn set_voice_param(
     }
 
     // If fade_beats is specified, use a fade; otherwise set immediately
-    if let Some(duration_beats) = req.fade_beats {
+    if let Some(duration) = req.fade_beats {
+        let duration_beats = duration.to_beats(state.handle.with_state(|s| s.time_signature));
         let duration_str = format!("{}b", duration_beats);
         if let Err(e) = state.handle.send(StateMessage::FadeVoiceParam {
             name: name.clone(),
@@ -342,6 +384,105 @@ pub async fn set_voice_param(
     Ok(StatusCode::OK)
 }
 
+/// PATCH /voices/:name/params - Set several voice parameters at once
+pub async fn set_voice_params(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(params): Json<HashMap<String, f32>>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let exists = state.handle.with_state(|s| s.voices.contains_key(&name));
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Voice '{}' not found", name))),
+        ));
+    }
+
+    if let Err(e) = state.handle.send(StateMessage::SetVoiceParams { name, params }) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::internal(&format!("Failed to set params: {}", e))),
+        ));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// POST /voices/:name/randomize - Randomize parameters within their declared ranges
+///
+/// Returns the new values.
+pub async fn randomize_voice_params(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(req): Json<RandomizeParamsRequest>,
+) -> Result<Json<HashMap<String, f32>>, (StatusCode, Json<ErrorResponse>)> {
+    let voice = state.handle.with_state(|s| s.voices.get(&name).map(|v| (v.synth_name.clone(), v.params.clone())));
+    let Some((synth_name, params)) = voice else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Voice '{}' not found", name))),
+        ));
+    };
+    let Some(synth_name) = synth_name else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!("Voice '{}' has no synth", name))),
+        ));
+    };
+
+    let amount = req.amount.unwrap_or(vibelang_core::sound_design::DEFAULT_RANDOMIZE_AMOUNT);
+    let values = vibelang_core::sound_design::randomize(&synth_name, &params, amount, &req.only);
+    if values.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::bad_request(&format!(
+                "Synth '{}' declares no parameter ranges to randomize",
+                synth_name
+            ))),
+        ));
+    }
+    send_voice_params(&state, name, values.clone())?;
+    Ok(Json(values))
+}
+
+/// POST /voices/:name/morph - Set parameters between two parameter sets
+///
+/// Returns the new values.
+pub async fn morph_voice_params(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(req): Json<MorphParamsRequest>,
+) -> Result<Json<HashMap<String, f32>>, (StatusCode, Json<ErrorResponse>)> {
+    let voice = state.handle.with_state(|s| s.voices.get(&name).map(|v| v.synth_name.clone()));
+    let Some(synth_name) = voice else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::not_found(&format!("Voice '{}' not found", name))),
+        ));
+    };
+
+    let values = vibelang_core::sound_design::morph(synth_name.as_deref(), &req.from, &req.to, req.t);
+    send_voice_params(&state, name, values.clone())?;
+    Ok(Json(values))
+}
+
+/// Apply parameter values to a voice in one update.
+fn send_voice_params(
+    state: &AppState,
+    name: String,
+    params: HashMap<String, f32>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    state
+        .handle
+        .send(StateMessage::SetVoiceParams { name, params })
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::internal(&format!("Failed to set params: {}", e))),
+            )
+        })
+}
+
 /// POST /voices/:name/mute - Mute a voice
 pub async fn mute_voice(
     State(state): State<Arc<AppState>>,
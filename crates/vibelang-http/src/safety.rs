@@ -0,0 +1,398 @@
+//! Dry runs and confirmation of destructive requests.
+//!
+//! Deleting a voice, pattern, melody, sequence or group, and evaluating code
+//! that stops things, can cut off sound in the middle of a show. These
+//! requests accept `?dry_run=true` to get a [`DestructivePreview`] of the
+//! entities and sounding synths they would stop, without doing anything.
+//!
+//! With confirmation required (`PUT /safety`), they are refused with 428 and
+//! the same preview until repeated with its token as `?confirm=<token>`.
+//! Tokens are bound to the action, its target (the code, for eval) and the
+//! server instance, so a UI has to show the preview before it can confirm.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::BTreeSet;
+use std::hash::{BuildHasher, DefaultHasher, Hash, Hasher, RandomState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use vibelang_core::state::{ActiveSynth, ScriptState};
+
+use crate::models::{ConfirmQuery, DestructivePreview};
+use crate::routes::live::active_synth_to_api;
+
+/// Functions whose calls stop sound in evaluated code.
+const STOP_CALLS: &[&str] = &["stop", "stop_all", "choke", "panic"];
+
+/// Stop calls that silence everything rather than what they are called on.
+const GLOBAL_STOP_CALLS: &[&str] = &["panic"];
+
+/// Kind of entity a destructive request deletes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deletion {
+    Voice,
+    Pattern,
+    Melody,
+    Sequence,
+    Group,
+}
+
+impl Deletion {
+    /// Action name in previews ("delete_voice").
+    pub fn action(self) -> &'static str {
+        match self {
+            Deletion::Voice => "delete_voice",
+            Deletion::Pattern => "delete_pattern",
+            Deletion::Melody => "delete_melody",
+            Deletion::Sequence => "delete_sequence",
+            Deletion::Group => "delete_group",
+        }
+    }
+}
+
+/// Whether destructive requests need confirmation, and the tokens that
+/// confirm them.
+pub struct Safety {
+    require_confirmation: AtomicBool,
+    secret: u64,
+}
+
+impl Default for Safety {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Safety {
+    /// Settings of a new server: no confirmation required.
+    pub fn new() -> Self {
+        Self {
+            require_confirmation: AtomicBool::new(false),
+            secret: RandomState::new().hash_one(std::process::id()),
+        }
+    }
+
+    /// Whether destructive requests need a confirmation token.
+    pub fn require_confirmation(&self) -> bool {
+        self.require_confirmation.load(Ordering::Relaxed)
+    }
+
+    /// Require (or stop requiring) confirmation tokens.
+    pub fn set_require_confirmation(&self, required: bool) {
+        self.require_confirmation.store(required, Ordering::Relaxed);
+    }
+
+    /// Token confirming `action` on `target`.
+    pub fn token(&self, action: &str, target: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        (self.secret, action, target).hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// The response to give instead of carrying out a destructive request:
+    /// the preview for a dry run or a missing or wrong confirmation token,
+    /// `None` to go ahead.
+    pub fn guard(&self, mut preview: DestructivePreview, query: &ConfirmQuery) -> Option<Response> {
+        preview.confirm_token = self.token(&preview.action, &preview.target);
+        if query.dry_run {
+            return Some((StatusCode::OK, Json(preview)).into_response());
+        }
+        if !self.require_confirmation() || query.confirm.as_deref() == Some(preview.confirm_token.as_str()) {
+            return None;
+        }
+        Some((StatusCode::PRECONDITION_REQUIRED, Json(preview)).into_response())
+    }
+}
+
+/// Preview of deleting entity `name` (a group path for groups).
+pub fn deletion_preview(state: &ScriptState, deletion: Deletion, name: &str) -> DestructivePreview {
+    let in_group = |path: &str| path == name || path.strip_prefix(name).is_some_and(|rest| rest.starts_with('/'));
+    let stops = |synth: &ActiveSynth| match deletion {
+        Deletion::Voice => synth.voice_names.iter().any(|v| v == name),
+        Deletion::Pattern => synth.pattern_names.iter().any(|p| p == name),
+        Deletion::Melody => synth.melody_names.iter().any(|m| m == name),
+        Deletion::Sequence => synth.clip.as_ref().is_some_and(|clip| clip.sequence == name),
+        Deletion::Group => synth.group_paths.iter().any(|path| in_group(path)),
+    };
+
+    let mut affected = BTreeSet::new();
+    match deletion {
+        Deletion::Voice => {
+            affected.insert(format!("voice:{}", name));
+            affected.extend(loops_playing(state, |voice| voice == name));
+        }
+        Deletion::Pattern => {
+            affected.insert(format!("pattern:{}", name));
+        }
+        Deletion::Melody => {
+            affected.insert(format!("melody:{}", name));
+        }
+        Deletion::Sequence => {
+            affected.insert(format!("sequence:{}", name));
+        }
+        Deletion::Group => {
+            affected.insert(format!("group:{}", name));
+            let voices: BTreeSet<&str> = state
+                .voices
+                .values()
+                .filter(|v| in_group(&v.group_path))
+                .map(|v| v.name.as_str())
+                .collect();
+            affected.extend(voices.iter().map(|v| format!("voice:{}", v)));
+            affected.extend(loops_playing(state, |voice| voices.contains(voice)));
+        }
+    }
+    preview(state, deletion.action(), name, Vec::new(), affected, stops)
+}
+
+/// Preview of evaluating `code`: the stop calls it makes and what they may
+/// stop.
+///
+/// The code is not run, so this is an estimate: `panic()` stops every synth,
+/// other stop calls the synths of the entities named in the code's string
+/// literals (`pattern("bass").stop()`).
+pub fn eval_preview(state: &ScriptState, code: &str) -> DestructivePreview {
+    let calls = stop_calls(code);
+    let everything = calls.iter().any(|c| GLOBAL_STOP_CALLS.contains(&c.as_str()));
+    let names: BTreeSet<String> = if calls.is_empty() { BTreeSet::new() } else { string_literals(code) };
+
+    let mut affected = BTreeSet::new();
+    for name in &names {
+        if state.voices.contains_key(name) {
+            affected.insert(format!("voice:{}", name));
+        }
+        if state.patterns.contains_key(name) {
+            affected.insert(format!("pattern:{}", name));
+        }
+        if state.melodies.contains_key(name) {
+            affected.insert(format!("melody:{}", name));
+        }
+        if state.sequences.contains_key(name) {
+            affected.insert(format!("sequence:{}", name));
+        }
+    }
+    let stops = |synth: &ActiveSynth| {
+        everything
+            || synth
+                .voice_names
+                .iter()
+                .chain(&synth.pattern_names)
+                .chain(&synth.melody_names)
+                .chain(synth.clip.as_ref().map(|clip| &clip.sequence))
+                .any(|n| names.contains(n))
+    };
+    preview(state, "eval", code, calls, affected, stops)
+}
+
+/// Patterns and melodies playing a voice that matches.
+fn loops_playing(state: &ScriptState, voice: impl Fn(&str) -> bool) -> Vec<String> {
+    let patterns = state
+        .patterns
+        .values()
+        .filter(|p| p.voice_name.as_deref().is_some_and(&voice))
+        .map(|p| format!("pattern:{}", p.name));
+    let melodies = state
+        .melodies
+        .values()
+        .filter(|m| m.voice_name.as_deref().is_some_and(&voice))
+        .map(|m| format!("melody:{}", m.name));
+    patterns.chain(melodies).collect()
+}
+
+fn preview(
+    state: &ScriptState,
+    action: &str,
+    target: &str,
+    stop_calls: Vec<String>,
+    mut affected: BTreeSet<String>,
+    stops: impl Fn(&ActiveSynth) -> bool,
+) -> DestructivePreview {
+    let mut stopped: Vec<&ActiveSynth> = state.active_synths.values().filter(|s| stops(s)).collect();
+    stopped.sort_by_key(|s| s.node_id);
+    for synth in &stopped {
+        affected.extend(synth.pattern_names.iter().map(|p| format!("pattern:{}", p)));
+        affected.extend(synth.melody_names.iter().map(|m| format!("melody:{}", m)));
+    }
+    DestructivePreview {
+        action: action.to_string(),
+        target: target.to_string(),
+        stop_calls,
+        affected: affected.into_iter().collect(),
+        stopped_synths: stopped.into_iter().map(active_synth_to_api).collect(),
+        confirm_token: String::new(),
+    }
+}
+
+/// Stopping functions called in `code`, in order of first call; string
+/// literals and comments are skipped.
+pub fn stop_calls(code: &str) -> Vec<String> {
+    let mut calls: Vec<String> = Vec::new();
+    // The identifier before the current position, and whether whitespace followed it
+    let mut identifier: Option<String> = None;
+    let mut spaced = false;
+    for token in tokens(code) {
+        match token {
+            Token::Char(c) if c.is_alphanumeric() || c == '_' => {
+                if spaced {
+                    identifier = None;
+                    spaced = false;
+                }
+                identifier.get_or_insert_with(String::new).push(c);
+            }
+            Token::Char(c) if c.is_whitespace() => spaced = identifier.is_some(),
+            Token::Char('(') => {
+                spaced = false;
+                if let Some(name) = identifier.take() {
+                    if STOP_CALLS.contains(&name.as_str()) && !calls.contains(&name) {
+                        calls.push(name);
+                    }
+                }
+            }
+            _ => {
+                identifier = None;
+                spaced = false;
+            }
+        }
+    }
+    calls
+}
+
+/// Contents of the string literals in `code`.
+fn string_literals(code: &str) -> BTreeSet<String> {
+    tokens(code)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Str(s) => Some(s),
+            _ => None,
+        })
+        .collect()
+}
+
+enum Token {
+    Char(char),
+    Str(String),
+}
+
+/// Characters of `code` outside strings and comments (a comment becomes a
+/// space), and its strings.
+fn tokens(code: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut s = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => s.extend(chars.next()),
+                        '"' => break,
+                        c => s.push(c),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                tokens.push(Token::Char(' '));
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                tokens.push(Token::Char(' '));
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vibelang_core::events::EventClip;
+    use vibelang_core::state::{PatternState, VoiceState};
+
+    fn synth(node_id: i32, voice: &str, pattern: &str, sequence: Option<&str>) -> ActiveSynth {
+        ActiveSynth {
+            node_id,
+            group_paths: vec!["main/drums".to_string()],
+            voice_names: vec![voice.to_string()],
+            pattern_names: vec![pattern.to_string()],
+            melody_names: Vec::new(),
+            synth_def: "kick".to_string(),
+            start_beat: Some(0.0),
+            step: Some(0),
+            clip: sequence.map(|s| EventClip {
+                sequence: s.to_string(),
+                index: 0,
+            }),
+            source_location: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_destructive_previews_and_confirmation() {
+        let mut state = ScriptState::new();
+        state.voices.insert("kick".into(), VoiceState::new("kick".into(), "main/drums".into()));
+        state.patterns.insert(
+            "four".into(),
+            PatternState::new("four".into(), "main/drums".into(), Some("kick".into())),
+        );
+        state.active_synths.insert(1001, synth(1001, "kick", "four", Some("verse")));
+        state.active_synths.insert(1002, synth(1002, "hat", "offbeat", None));
+
+        // Deleting a voice stops its synths and the loops playing it
+        let preview = deletion_preview(&state, Deletion::Voice, "kick");
+        assert_eq!(preview.affected, vec!["pattern:four", "voice:kick"]);
+        assert_eq!(preview.stopped_synths.len(), 1);
+        let preview = deletion_preview(&state, Deletion::Group, "main");
+        assert_eq!(preview.stopped_synths.len(), 2);
+        assert!(preview.affected.contains(&"pattern:offbeat".to_string()));
+        let preview = deletion_preview(&state, Deletion::Sequence, "verse");
+        assert_eq!(preview.stopped_synths[0].node_id, 1001);
+
+        // Stop calls are found outside strings and comments
+        assert_eq!(stop_calls(r#"pattern("stop").start(); // stop()"#), Vec::<String>::new());
+        assert_eq!(stop_calls("voice(\"kick\").stop ();\nstop_all()"), vec!["stop", "stop_all"]);
+        assert!(stop_calls("stop x(1)").is_empty());
+        let preview = eval_preview(&state, r#"pattern("four").stop()"#);
+        assert_eq!(preview.affected, vec!["pattern:four"]);
+        assert_eq!(preview.stopped_synths.len(), 1);
+        assert_eq!(eval_preview(&state, "panic()").stopped_synths.len(), 2);
+
+        // Dry runs preview; confirmation needs the preview's token
+        let safety = Safety::new();
+        let dry_run = ConfirmQuery {
+            dry_run: true,
+            confirm: None,
+        };
+        let preview = || deletion_preview(&state, Deletion::Voice, "kick");
+        assert_eq!(safety.guard(preview(), &dry_run).unwrap().status(), StatusCode::OK);
+        assert!(safety.guard(preview(), &ConfirmQuery::default()).is_none());
+        safety.set_require_confirmation(true);
+        let refused = safety.guard(preview(), &ConfirmQuery::default()).unwrap();
+        assert_eq!(refused.status(), StatusCode::PRECONDITION_REQUIRED);
+        let confirmed = ConfirmQuery {
+            dry_run: false,
+            confirm: Some(safety.token("delete_voice", "kick")),
+        };
+        assert!(safety.guard(preview(), &confirmed).is_none());
+        let wrong_target = ConfirmQuery {
+            dry_run: false,
+            confirm: Some(safety.token("delete_voice", "snare")),
+        };
+        assert!(safety.guard(preview(), &wrong_target).is_some());
+    }
+}
@@ -1,4 +1,8 @@
 //! WebSocket handler for real-time updates.
+//!
+//! Besides the broadcast events, clients that explicitly subscribe to
+//! `state.snapshot` (or `state.*`) receive a [`MirrorSnapshot`] right away
+//! and then whenever the session changes; `*` doesn't include them.
 
 use axum::{
     extract::{
@@ -14,10 +18,14 @@ use std::time::Duration;
 use tokio::sync::broadcast;
 use vibelang_core::RuntimeHandle;
 
+use crate::mirror::{MirrorSnapshot, SNAPSHOT_EVENT};
 use crate::AppState;
 
+/// How often state snapshots are sent to subscribed clients.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+
 /// WebSocket event sent to clients.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketEvent {
     #[serde(rename = "type")]
     pub event_type: String,
@@ -55,9 +63,13 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     // Default subscription patterns (all events)
     let initial_subscriptions = vec!["*".to_string()];
 
+    let handle = state.handle.clone();
+
     // Spawn task to send events to client
     let send_task = tokio::spawn(async move {
         let mut subscriptions = initial_subscriptions;
+        let mut snapshot_interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+        let mut last_snapshot: Option<u64> = None;
         loop {
             tokio::select! {
                 result = rx.recv() => {
@@ -73,8 +85,31 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                         Err(_) => break,
                     }
                 }
+                _ = snapshot_interval.tick(), if wants_snapshots(&subscriptions) => {
+                    // Send while the transport moves, otherwise only on changes
+                    let snapshot = handle.with_state(|s| {
+                        (last_snapshot != Some(s.version) || s.transport_running)
+                            .then(|| MirrorSnapshot::capture(s))
+                    });
+                    let Some(snapshot) = snapshot else {
+                        continue;
+                    };
+                    last_snapshot = Some(snapshot.version);
+                    let event = WebSocketEvent {
+                        event_type: SNAPSHOT_EVENT.to_string(),
+                        timestamp: timestamp_ms(),
+                        data: serde_json::to_value(&snapshot).ok(),
+                    };
+                    let msg = serde_json::to_string(&event).unwrap_or_default();
+                    if sender.send(Message::Text(msg.into())).await.is_err() {
+                        break;
+                    }
+                }
                 Some(new_subs) = sub_rx.recv() => {
                     subscriptions = new_subs;
+                    // New snapshot subscribers get the full state right away
+                    last_snapshot = None;
+                    snapshot_interval.reset_immediately();
                 }
             }
         }
@@ -133,11 +168,26 @@ fn is_subscribed(event_type: &str, subscriptions: &[String]) -> bool {
     false
 }
 
+/// Check if state snapshots were subscribed to explicitly.
+fn wants_snapshots(subscriptions: &[String]) -> bool {
+    subscriptions
+        .iter()
+        .any(|pattern| pattern == SNAPSHOT_EVENT || pattern == "state.*")
+}
+
+fn timestamp_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
 /// Background task that polls state and broadcasts events.
 pub async fn run_event_broadcaster(handle: RuntimeHandle, tx: broadcast::Sender<WebSocketEvent>) {
     let mut last_beat: Option<f64> = None;
     let mut last_running: Option<bool> = None;
     let mut last_bpm: Option<f64> = None;
+    let mut last_over_budget = false;
 
     let mut interval = tokio::time::interval(Duration::from_millis(50)); // 20 Hz update rate
 
@@ -145,14 +195,17 @@ pub async fn run_event_broadcaster(handle: RuntimeHandle, tx: broadcast::Sender<
         interval.tick().await;
 
         // Read current state
-        let (current_beat, running, bpm) = handle.with_state(|s| {
-            (s.current_beat, s.transport_running, s.tempo)
+        let (current_beat, running, bpm, over_budget, cpu) = handle.with_state(|s| {
+            (
+                s.current_beat,
+                s.transport_running,
+                s.tempo,
+                s.performance.budget.over_budget,
+                s.performance.status.as_ref().map(|st| (st.avg_cpu, st.peak_cpu)),
+            )
         });
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs_f64() * 1000.0)
-            .unwrap_or(0.0);
+        let now = timestamp_ms();
 
         // Check for beat changes (emit on each beat)
         if let Some(last) = last_beat {
@@ -196,5 +249,19 @@ pub async fn run_event_broadcaster(handle: RuntimeHandle, tx: broadcast::Sender<
             }
             last_bpm = Some(bpm);
         }
+
+        // Warn when the server goes over (or comes back under) its CPU budget
+        if over_budget != last_over_budget {
+            let event_type = if over_budget { "performance.over_budget" } else { "performance.recovered" };
+            let _ = tx.send(WebSocketEvent {
+                event_type: event_type.to_string(),
+                timestamp: now,
+                data: Some(serde_json::json!({
+                    "avg_cpu": cpu.map(|(avg, _)| avg),
+                    "peak_cpu": cpu.map(|(_, peak)| peak),
+                })),
+            });
+            last_over_budget = over_budget;
+        }
     }
 }
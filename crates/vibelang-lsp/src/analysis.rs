@@ -31,7 +31,8 @@ pub struct AnalysisResult {
     pub effect_refs: Vec<EffectRef>,
     /// Variable definitions (let name = ...).
     pub variable_defs: Vec<VariableDef>,
-    /// Local synthdef definitions (define_synthdef("name")).
+    /// Local synthdef definitions (define_synthdef("name") and the
+    /// synthdefs of synthdef_dir("path") directories).
     pub local_synthdefs: HashSet<String>,
 }
 
@@ -134,6 +135,7 @@ pub fn analyze_document(
     parse_effect_refs(content, &mut result);
     parse_variable_defs(content, &mut result);
     parse_local_synthdefs(content, &mut result);
+    parse_synthdef_dirs(content, file_path, &mut result);
 
     // Run linting passes
     lint_melodies(content, &mut result.lint_diagnostics);
@@ -342,6 +344,28 @@ fn parse_local_synthdefs(content: &str, result: &mut AnalysisResult) {
     }
 }
 
+/// Add the synthdefs of synthdef_dir("path") directories, resolved relative
+/// to the current file.
+fn parse_synthdef_dirs(content: &str, file_path: Option<&PathBuf>, result: &mut AnalysisResult) {
+    let dir_pattern = regex::Regex::new(r#"synthdef_dir\s*\(\s*["']([^"']+)["']"#).ok();
+
+    if let Some(re) = dir_pattern {
+        for cap in re.captures_iter(content) {
+            let Some(path_match) = cap.get(1) else {
+                continue;
+            };
+            let path = PathBuf::from(path_match.as_str());
+            let dir = match file_path.and_then(|p| p.parent()) {
+                Some(base) if path.is_relative() => base.join(path),
+                _ => path,
+            };
+            if let Ok(synthdefs) = vibelang_core::synthdef_dir::read_synthdef_dir(&dir) {
+                result.local_synthdefs.extend(synthdefs.into_iter().map(|(name, _)| name));
+            }
+        }
+    }
+}
+
 /// Parse voice definitions from voice("name") calls.
 /// This is used to supplement the runtime-collected voice names since voices
 /// may be defined without calling .apply() or .run().
@@ -782,7 +806,7 @@ fn lint_melody_content(
                 code_description: None,
                 source: Some("vibelang".to_string()),
                 message: format!(
-                    "Invalid melody token '{}'. Expected a note (e.g., C4, F#3), chord (e.g., C4:maj7), scale degree (1-7), or rest (-, ~, .).",
+                    "Invalid melody token '{}'. Expected a note (e.g., C4, F#3), chord (e.g., C4:maj7), scale degree (1-7), tie (-, ~) or rest (., _, r).",
                     token_str
                 ),
                 related_information: None,
@@ -810,7 +834,7 @@ fn tokenize_melody_content(content: &str) -> Vec<(String, usize, bool)> {
             }
 
             // Rest markers - valid single-character tokens
-            '.' | '_' => {
+            '.' | '_' | 'r' | 'R' => {
                 tokens.push((c.to_string(), idx, true));
             }
 
@@ -894,7 +918,7 @@ fn tokenize_melody_content(content: &str) -> Vec<(String, usize, bool)> {
 
 /// Check if a token is a valid melody token.
 fn is_valid_melody_token(token: &str) -> bool {
-    if token == "-" || token == "~" || token == "_" || token == "." || token == "|" {
+    if matches!(token, "-" | "~" | "_" | "." | "r" | "R" | "|") {
         return true;
     }
 
@@ -1091,14 +1115,16 @@ pub fn lint_variables(content: &str, diagnostics: &mut Vec<Diagnostic>) {
         "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh", "asinh", "acosh", "atanh",
         "min", "max", "clamp", "pow", "hypot", "atan2", "random", "rand",
         // VibeLang core API
-        "voice", "pattern", "melody", "sequence", "group", "define_group", "fx", "fade", "sample",
-        "define_synthdef", "define_fx", "load_sfz", "load_sample", "load_vst_instrument", "load_vst_effect",
-        "set_tempo", "get_tempo", "set_quantization", "set_time_signature", "get_current_beat", "get_current_bar",
+        "voice", "drumkit", "pattern", "melody", "ctrl", "pattern_from_string", "melody_from_string", "sequence", "group", "define_group", "namespace", "namespaced", "exported", "fx", "fade", "sample", "looper", "return_channel", "groove", "load_groove", "clear_groove", "meter", "clock_out", "clock_out_stop", "set_clock_source", "clock_source",
+        "playback_graph", "on_cue", "after", "when", "macro", "set_macro", "checkpoint",
+        "define_synthdef", "define_fx", "import_scd", "synthdef_dir", "load_sfz", "load_sample", "preview", "load_vst_instrument", "load_vst_effect",
+        "set_tempo", "get_tempo", "set_quantization", "set_key", "get_key", "clear_key", "set_loudness_target", "clear_loudness_target", "reset_loudness", "set_param_smoothing", "set_cpu_budget", "set_cpu_policy", "get_cpu_usage", "set_quotas", "enable_gc", "set_gc_policy", "set_unit_checks", "set_time_signature", "get_current_beat", "get_current_bar",
         "db", "bars", "note", "sleep", "sleep_secs", "exit", "exit_with_code",
         "all_group_names", "all_voice_names", "all_pattern_names", "all_melody_names", "all_effect_names",
         "get_voice", "get_pattern", "get_melody", "get_effect", "active_synth_count", "jump_to_start",
+        "marker", "remove_marker", "jump_to", "panic", "capture_osc", "stop_osc_capture", "log_events", "stop_event_log",
         "record", "stop_recording", "nudge_transport", "fade_group_gain", "fade_param",
-        "define_macro", "trigger_macro", "define_send", "melody_gen", "detect_bpm", "set_group_gain",
+        "define_macro", "trigger_macro", "define_send", "melody_gen", "detect_bpm", "set_group_gain", "group_tree",
         "automation", "scene", "scene_morph", "midi_device", "midi_map", "midi_devices",
         // DSP functions
         "envelope", "env_perc", "env_adsr", "env_asr", "env_triangle", "Env",
@@ -1123,9 +1149,12 @@ pub fn lint_variables(content: &str, diagnostics: &mut Vec<Diagnostic>) {
         "dc_ar", "dc_kr", "kr", "ar", "a2k", "k2a", "t2a", "t2k",
         // Builder method names (common)
         "synth", "param", "body", "on", "step", "notes", "start", "stop", "apply", "run",
-        "gain", "poly", "mute", "unmute", "solo", "trigger", "note_on", "note_off",
-        "scale", "root", "gate", "transpose", "len", "swing", "quantize", "lane", "values",
-        "euclid", "clip", "clip_once", "clip_loops", "loop_bars", "loop_beats",
+        "gain", "poly", "mono", "glide_ms", "pad", "set_params", "randomize_params", "morph", "match_key", "pre_roll_ms", "auto_pre_roll", "feel", "clear_feel", "output", "mute", "unmute", "solo", "trigger", "note_on", "note_off",
+        "scale", "root", "gate", "transpose", "len", "auto_length", "swing", "quantize", "lane", "values", "only_when",
+        "midi", "note_length", "program_change", "send_sysex", "send_sysex_file", "diag",
+        "ppqn", "width", "level", "run_gate", "stereo", "into",
+        "steps", "target", "target_group", "target_effect", "mode", "slide",
+        "euclid", "variations", "weights", "seed", "lock_variation", "unlock_variation", "clip", "clip_once", "clip_loops", "loop_bars", "loop_beats", "speed", "half_time", "double_time", "launch_quantize", "legato", "key_change",
         "from", "to", "over", "over_bars", "on_group", "on_voice", "on_effect", "on_pattern", "on_melody",
         "attack", "decay", "sustain", "release", "adsr", "perc", "asr", "triangle",
         "cleanup_on_finish", "build", "time_scale", "level_scale",
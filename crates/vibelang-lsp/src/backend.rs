@@ -11,11 +11,12 @@ use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
 use crate::analysis::{analyze_document, get_completion_context, get_word_at_position, AnalysisResult};
-use crate::completion::get_completions;
+use crate::completion::{get_completions, stdlib_import_edit};
 use crate::definition::{get_import_definition, get_variable_definition};
 use crate::diagnostics::all_diagnostics;
 use crate::document::DocumentStore;
-use crate::hover::{get_hover, SynthdefInfo};
+use crate::hover::{get_hover, ParamInfo, SynthdefInfo};
+use vibelang_std::EntryKind;
 
 /// VibeLang Language Server.
 pub struct VibeLangServer {
@@ -82,126 +83,36 @@ impl VibeLangServer {
         }
     }
 
-    /// Load known synthdefs and effects from stdlib.
+    /// Load known synthdefs and effects from the stdlib index.
     fn load_stdlib_definitions(&self) {
-        let stdlib_path = PathBuf::from(vibelang_std::stdlib_path());
-
-        if !stdlib_path.exists() {
-            return;
-        }
-
         let mut synthdefs = self.known_synthdefs.write().unwrap();
         let mut effects = self.known_effects.write().unwrap();
         let mut synthdef_info_map = self.synthdef_info.write().unwrap();
         let mut effect_info_map = self.effect_info.write().unwrap();
 
-        // Scan stdlib directory for synthdef and effect definitions
-        if let Ok(entries) = std::fs::read_dir(&stdlib_path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    // Recurse into subdirectories
-                    self.scan_directory_for_defs(
-                        &path,
-                        &mut synthdefs,
-                        &mut effects,
-                        &mut synthdef_info_map,
-                        &mut effect_info_map,
-                    );
-                } else if path.extension().is_some_and(|e| e == "vibe") {
-                    self.scan_file_for_defs(
-                        &path,
-                        &mut synthdefs,
-                        &mut effects,
-                        &mut synthdef_info_map,
-                        &mut effect_info_map,
-                    );
+        for entry in vibelang_std::stdlib_index() {
+            let info = SynthdefInfo {
+                name: entry.name.to_string(),
+                description: (!entry.description.is_empty()).then(|| entry.description.to_string()),
+                parameters: entry
+                    .params
+                    .iter()
+                    .map(|(name, default)| ParamInfo {
+                        name: name.to_string(),
+                        default: *default,
+                        description: None,
+                    })
+                    .collect(),
+                category: Some(entry.category.to_string()),
+            };
+            match entry.kind {
+                EntryKind::Synth => {
+                    synthdefs.insert(info.name.clone());
+                    synthdef_info_map.insert(info.name.clone(), info);
                 }
-            }
-        }
-    }
-
-    fn scan_directory_for_defs(
-        &self,
-        dir: &PathBuf,
-        synthdefs: &mut HashSet<String>,
-        effects: &mut HashSet<String>,
-        synthdef_info: &mut std::collections::HashMap<String, SynthdefInfo>,
-        effect_info: &mut std::collections::HashMap<String, SynthdefInfo>,
-    ) {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    self.scan_directory_for_defs(&path, synthdefs, effects, synthdef_info, effect_info);
-                } else if path.extension().is_some_and(|e| e == "vibe") {
-                    self.scan_file_for_defs(&path, synthdefs, effects, synthdef_info, effect_info);
-                }
-            }
-        }
-    }
-
-    fn scan_file_for_defs(
-        &self,
-        path: &PathBuf,
-        synthdefs: &mut HashSet<String>,
-        effects: &mut HashSet<String>,
-        synthdef_info: &mut std::collections::HashMap<String, SynthdefInfo>,
-        effect_info: &mut std::collections::HashMap<String, SynthdefInfo>,
-    ) {
-        if let Ok(content) = std::fs::read_to_string(path) {
-            // Look for define_synthdef("name") patterns
-            let synthdef_re = regex::Regex::new(r#"define_synthdef\s*\(\s*["']([^"']+)["']"#).ok();
-            if let Some(re) = synthdef_re {
-                for cap in re.captures_iter(&content) {
-                    if let Some(m) = cap.get(1) {
-                        let name = m.as_str().to_string();
-                        synthdefs.insert(name.clone());
-
-                        // Extract category from path
-                        let category = path
-                            .parent()
-                            .and_then(|p| p.file_name())
-                            .and_then(|n| n.to_str())
-                            .map(|s| s.to_string());
-
-                        synthdef_info.insert(
-                            name.clone(),
-                            SynthdefInfo {
-                                name: name.clone(),
-                                description: None,
-                                parameters: Vec::new(),
-                                category,
-                            },
-                        );
-                    }
-                }
-            }
-
-            // Look for define_fx("name") patterns
-            let fx_re = regex::Regex::new(r#"define_fx\s*\(\s*["']([^"']+)["']"#).ok();
-            if let Some(re) = fx_re {
-                for cap in re.captures_iter(&content) {
-                    if let Some(m) = cap.get(1) {
-                        let name = m.as_str().to_string();
-                        effects.insert(name.clone());
-
-                        let category = path
-                            .parent()
-                            .and_then(|p| p.file_name())
-                            .and_then(|n| n.to_str())
-                            .map(|s| s.to_string());
-
-                        effect_info.insert(
-                            name.clone(),
-                            SynthdefInfo {
-                                name: name.clone(),
-                                description: None,
-                                parameters: Vec::new(),
-                                category,
-                            },
-                        );
-                    }
+                EntryKind::Effect => {
+                    effects.insert(info.name.clone());
+                    effect_info_map.insert(info.name.clone(), info);
                 }
             }
         }
@@ -261,6 +172,7 @@ impl LanguageServer for VibeLangServer {
                 }),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
                 definition_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
                 diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
                     DiagnosticOptions {
                         identifier: Some("vibelang".to_string()),
@@ -334,9 +246,18 @@ impl LanguageServer for VibeLangServer {
         let context = get_completion_context(&content, position.line as usize, position.character as usize);
 
         let import_paths = self.import_paths.read().unwrap().clone();
-        let known_synthdefs = self.known_synthdefs.read().unwrap().clone();
+        let mut known_synthdefs = self.known_synthdefs.read().unwrap().clone();
         let known_effects = self.known_effects.read().unwrap().clone();
         let file_path = uri.to_file_path().ok();
+        let (imports, local_synthdefs) = self
+            .analysis_cache
+            .read()
+            .unwrap()
+            .get(&uri)
+            .map(|analysis| (analysis.imports.clone(), analysis.local_synthdefs.clone()))
+            .unwrap_or_default();
+        // Synthdefs defined or loaded by the document itself
+        known_synthdefs.extend(local_synthdefs);
 
         let completions = get_completions(
             &context,
@@ -344,6 +265,7 @@ impl LanguageServer for VibeLangServer {
             &known_effects,
             &import_paths,
             file_path.as_ref(),
+            &imports,
         );
 
         Ok(Some(CompletionResponse::Array(completions)))
@@ -413,6 +335,62 @@ impl LanguageServer for VibeLangServer {
         Ok(None)
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+
+        let analysis = {
+            let cache = self.analysis_cache.read().unwrap();
+            cache.get(&uri).cloned()
+        };
+        let analysis = match analysis {
+            Some(a) => a,
+            None => return Ok(None),
+        };
+
+        // Offer to import stdlib definitions referenced in the selection
+        let selection = params.range;
+        let overlaps = |range: &Range| range.start <= selection.end && selection.start <= range.end;
+        let names: Vec<&String> = analysis
+            .synthdef_refs
+            .iter()
+            .filter(|r| overlaps(&r.range))
+            .map(|r| &r.name)
+            .chain(
+                analysis
+                    .effect_refs
+                    .iter()
+                    .filter(|r| overlaps(&r.range))
+                    .map(|r| &r.name),
+            )
+            .collect();
+
+        let mut actions = Vec::new();
+        for name in names {
+            if analysis.local_synthdefs.contains(name) {
+                continue;
+            }
+            let (Some(entry), Some(edit)) = (
+                vibelang_std::find_definition(name),
+                stdlib_import_edit(name, &analysis.imports),
+            ) else {
+                continue;
+            };
+            let mut changes = std::collections::HashMap::new();
+            changes.insert(uri.clone(), vec![edit]);
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Import '{}' from {}", name, entry.import_path()),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(changes),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+
+        Ok(Some(actions))
+    }
+
     async fn diagnostic(
         &self,
         params: DocumentDiagnosticParams,
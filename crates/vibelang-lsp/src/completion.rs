@@ -2,7 +2,7 @@
 //!
 //! Provides intelligent code completion for:
 //! - API functions (voice, pattern, melody, etc.)
-//! - Synthdef names
+//! - Synthdef names, with auto-import of stdlib definitions
 //! - Effect names
 //! - Import paths
 //! - Method chains
@@ -12,10 +12,10 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 use tower_lsp::lsp_types::{
     CompletionItem, CompletionItemKind, CompletionItemLabelDetails, Documentation,
-    InsertTextFormat, MarkupContent, MarkupKind,
+    InsertTextFormat, MarkupContent, MarkupKind, Position, Range, TextEdit,
 };
 
-use crate::analysis::CompletionContext;
+use crate::analysis::{CompletionContext, ImportInfo};
 
 /// API function documentation.
 #[derive(Debug, Clone)]
@@ -33,11 +33,14 @@ pub fn get_completions(
     known_effects: &HashSet<String>,
     import_paths: &[PathBuf],
     current_file: Option<&PathBuf>,
+    imports: &[ImportInfo],
 ) -> Vec<CompletionItem> {
     match context {
         CompletionContext::TopLevel => get_top_level_completions(),
-        CompletionContext::SynthdefName => get_synthdef_completions(known_synthdefs, known_effects),
-        CompletionContext::EffectName => get_effect_completions(known_effects),
+        CompletionContext::SynthdefName => {
+            get_synthdef_completions(known_synthdefs, known_effects, imports)
+        }
+        CompletionContext::EffectName => get_effect_completions(known_effects, imports),
         CompletionContext::ImportPath => get_import_completions(import_paths, current_file),
         CompletionContext::ParamName { synthdef } => get_param_completions(synthdef.as_deref()),
         CompletionContext::NotePattern => get_note_pattern_completions(),
@@ -79,8 +82,12 @@ fn get_top_level_completions() -> Vec<CompletionItem> {
 fn get_snippet_for_function(name: &str) -> String {
     match name {
         "voice" => "voice(\"$1\")$0".to_string(),
+        "drumkit" => "drumkit(\"$1\")$0".to_string(),
         "pattern" => "pattern(\"$1\")$0".to_string(),
         "melody" => "melody(\"$1\")$0".to_string(),
+        "ctrl" => "ctrl(\"$1\")$0".to_string(),
+        "pattern_from_string" => "pattern_from_string($1)$0".to_string(),
+        "melody_from_string" => "melody_from_string($1)$0".to_string(),
         "sequence" => "sequence(\"$1\")$0".to_string(),
         "define_group" => "define_group(\"$1\", || {\n\t$0\n})".to_string(),
         "group" => "group(\"$1\")$0".to_string(),
@@ -109,24 +116,19 @@ fn get_snippet_for_function(name: &str) -> String {
 fn get_synthdef_completions(
     known_synthdefs: &HashSet<String>,
     known_effects: &HashSet<String>,
+    imports: &[ImportInfo],
 ) -> Vec<CompletionItem> {
     let mut items: Vec<CompletionItem> = known_synthdefs
         .iter()
-        .map(|name| CompletionItem {
-            label: name.clone(),
-            kind: Some(CompletionItemKind::CLASS),
-            detail: Some("synthdef".to_string()),
-            ..Default::default()
-        })
+        .map(|name| definition_completion(name, "synthdef", imports))
         .collect();
 
     // Also include effects as they can be used with .synth()
-    items.extend(known_effects.iter().map(|name| CompletionItem {
-        label: name.clone(),
-        kind: Some(CompletionItemKind::CLASS),
-        detail: Some("effect".to_string()),
-        ..Default::default()
-    }));
+    items.extend(
+        known_effects
+            .iter()
+            .map(|name| definition_completion(name, "effect", imports)),
+    );
 
     // Sort alphabetically
     items.sort_by(|a, b| a.label.cmp(&b.label));
@@ -134,21 +136,71 @@ fn get_synthdef_completions(
 }
 
 /// Effect name completions.
-fn get_effect_completions(known_effects: &HashSet<String>) -> Vec<CompletionItem> {
+fn get_effect_completions(known_effects: &HashSet<String>, imports: &[ImportInfo]) -> Vec<CompletionItem> {
     let mut items: Vec<CompletionItem> = known_effects
         .iter()
-        .map(|name| CompletionItem {
-            label: name.clone(),
-            kind: Some(CompletionItemKind::CLASS),
-            detail: Some("effect".to_string()),
-            ..Default::default()
-        })
+        .map(|name| definition_completion(name, "effect", imports))
         .collect();
 
     items.sort_by(|a, b| a.label.cmp(&b.label));
     items
 }
 
+/// Completion item for a synthdef or effect name.
+///
+/// Stdlib definitions show their description and bring their import along
+/// when the document doesn't import them yet.
+fn definition_completion(name: &str, detail: &str, imports: &[ImportInfo]) -> CompletionItem {
+    let mut item = CompletionItem {
+        label: name.to_string(),
+        kind: Some(CompletionItemKind::CLASS),
+        detail: Some(detail.to_string()),
+        ..Default::default()
+    };
+
+    if let Some(entry) = vibelang_std::find_definition(name) {
+        item.label_details = Some(CompletionItemLabelDetails {
+            detail: None,
+            description: Some(entry.import_path()),
+        });
+        if !entry.description.is_empty() {
+            item.documentation = Some(Documentation::String(entry.description.to_string()));
+        }
+        item.additional_text_edits = stdlib_import_edit(name, imports).map(|edit| vec![edit]);
+    }
+
+    item
+}
+
+/// Edit adding the import of a stdlib definition below the existing imports.
+///
+/// Returns `None` for names outside the stdlib and for definitions whose file
+/// is already imported.
+pub fn stdlib_import_edit(name: &str, imports: &[ImportInfo]) -> Option<TextEdit> {
+    let entry = vibelang_std::find_definition(name)?;
+    let import_path = entry.import_path();
+    let already_imported = imports.iter().any(|import| {
+        import.path == import_path
+            || import
+                .resolved_path
+                .as_ref()
+                .is_some_and(|p| p.ends_with(entry.path))
+    });
+    if already_imported {
+        return None;
+    }
+
+    let line = imports.iter().map(|i| i.range.start.line + 1).max().unwrap_or(0);
+    let position = Position { line, character: 0 };
+    Some(TextEdit {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        new_text: format!("{}\n", entry.import_statement()),
+    })
+}
+
 /// Import path completions.
 fn get_import_completions(
     import_paths: &[PathBuf],
@@ -209,6 +261,21 @@ fn get_import_completions(
 
 /// Parameter name completions based on synthdef.
 fn get_param_completions(synthdef: Option<&str>) -> Vec<CompletionItem> {
+    // Stdlib definitions know their parameters
+    if let Some(entry) = synthdef.and_then(vibelang_std::find_definition) {
+        return entry
+            .params
+            .iter()
+            .map(|(name, default)| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                detail: Some(format!("default: {}", default)),
+                insert_text: Some(format!("\"{}\", ", name)),
+                ..Default::default()
+            })
+            .collect();
+    }
+
     // Common parameters that most synthdefs have
     let common_params = vec![
         ("freq", "Frequency in Hz"),
@@ -229,9 +296,6 @@ fn get_param_completions(synthdef: Option<&str>) -> Vec<CompletionItem> {
         ("detune", "Detuning amount"),
     ];
 
-    // TODO: Get actual parameters from synthdef registry
-    let _ = synthdef; // Suppress unused warning
-
     common_params
         .into_iter()
         .map(|(name, desc)| CompletionItem {
@@ -339,10 +403,19 @@ fn get_voice_methods() -> Vec<CompletionItem> {
         method_item("synth", "(name: string)", "Set the synthdef to use"),
         method_item("on", "(source)", "Set the sound source"),
         method_item("poly", "(count: int)", "Set polyphony"),
+        method_item("mono", "(priority: string)", "Play one note at a time: last, low or high note priority"),
+        method_item("glide_ms", "(ms: float)", "Set the glide time of a mono voice"),
+        method_item("pad", "(name: string, sample, options?: map)", "Add a drum kit pad playing a sample"),
+        method_item("priority", "(level: int)", "Priority when over the CPU budget"),
         method_item("gain", "(level: float)", "Set gain level"),
         method_item("param", "(name: string, value: float)", "Set a parameter"),
+        method_item("set_params", "(params: map)", "Set several parameters in one update"),
+        method_item("randomize_params", "(amount?: float, only?: array)", "Randomize parameters within their declared ranges"),
+        method_item("morph", "(from: map, to: map, t: float)", "Set parameters between two parameter sets"),
         method_item("pan", "(value: float)", "Set pan position (-1 to 1)"),
         method_item("send", "(bus: string, level: float)", "Send to aux bus"),
+        method_item("output", "(bus: int)", "Route straight to a (hardware) output bus"),
+        method_item("feel", "(offset_ms, jitter_ms)", "Play late (or early) with timing jitter"),
         method_item("apply", "()", "Apply the voice configuration"),
     ]
 }
@@ -352,7 +425,13 @@ fn get_pattern_methods() -> Vec<CompletionItem> {
         method_item("on", "(voice)", "Set the voice to trigger"),
         method_item("step", "(pattern: string)", "Set step pattern"),
         method_item("euclid", "(hits: int, steps: int)", "Generate Euclidean rhythm"),
+        method_item("variations", "(takes: array)", "Play one of several takes per pass"),
+        method_item("weights", "(weights: array)", "Set the relative weight of each take"),
+        method_item("lock_variation", "(index: int)", "Hold one take"),
+        method_item("unlock_variation", "()", "Pick a take per pass again"),
+        method_item("to_string", "()", "Write the pattern in the shareable text format"),
         method_item("length", "(bars: float)", "Set pattern length in bars"),
+        method_item("auto_length", "()", "Infer the loop length from the steps"),
         method_item("swing", "(amount: float)", "Set swing amount (0-1)"),
         method_item("velocity", "(v: float)", "Set velocity (0-1)"),
         method_item("probability", "(p: float)", "Set trigger probability (0-1)"),
@@ -365,11 +444,13 @@ fn get_melody_methods() -> Vec<CompletionItem> {
     vec![
         method_item("on", "(voice)", "Set the voice to play"),
         method_item("notes", "(notes: string)", "Set note sequence"),
+        method_item("to_string", "()", "Write the melody in the shareable text format"),
         method_item("scale", "(name: string)", "Set scale"),
         method_item("root", "(note: string)", "Set root note"),
         method_item("gate", "(duration: float)", "Set gate duration"),
         method_item("transpose", "(semitones: int)", "Transpose notes"),
         method_item("length", "(bars: float)", "Set melody length in bars"),
+        method_item("auto_length", "()", "Infer the loop length from the notes"),
         method_item("start", "()", "Start the melody"),
         method_item("stop", "()", "Stop the melody"),
     ]
@@ -380,6 +461,13 @@ fn get_sequence_methods() -> Vec<CompletionItem> {
         method_item("loop_bars", "(bars: int)", "Set loop length in bars"),
         method_item("clip", "(range, source)", "Add a clip to the sequence"),
         method_item("clip_once", "(range, source)", "Add a one-shot clip"),
+        method_item("speed", "(factor: float)", "Play the sequence at a tempo factor"),
+        method_item("half_time", "()", "Play the sequence at half time"),
+        method_item("double_time", "()", "Play the sequence at double time"),
+        method_item("launch_quantize", "(beats: float)", "Launch on its own beat grid"),
+        method_item("legato", "()", "Take over the phase of what plays the same voices"),
+        method_item("transpose", "(semitones: int)", "Transpose the melodies the sequence plays"),
+        method_item("key_change", "(at, semitones: int)", "Change key from a beat or within a range"),
         method_item("start", "()", "Start the sequence"),
         method_item("stop", "()", "Stop the sequence"),
         method_item("pause", "()", "Pause the sequence"),
@@ -404,6 +492,8 @@ fn get_group_methods() -> Vec<CompletionItem> {
         method_item("solo", "()", "Solo the group"),
         method_item("unsolo", "()", "Unsolo the group"),
         method_item("send", "(bus: string, level: float)", "Send to aux bus"),
+        method_item("freeze", "(bars: int)", "Bounce to audio and pause the synths"),
+        method_item("unfreeze", "()", "Resume live synthesis"),
         method_item("now", "()", "Execute immediately"),
     ]
 }
@@ -472,6 +562,12 @@ fn get_api_functions() -> Vec<ApiFunctionDoc> {
             description: "Create a voice builder for a synth or sample voice.",
             example: "let kick = voice(\"kick\").synth(\"kick_909\").gain(db(-6));",
         },
+        ApiFunctionDoc {
+            name: "drumkit",
+            signature: "(name: string) -> Voice",
+            description: "Create a drum kit voice playing a sample per pad.",
+            example: "let kit = drumkit(\"drums\").pad(\"kick\", \"kick.wav\").pad(\"snare\", \"snare.wav\");",
+        },
         ApiFunctionDoc {
             name: "pattern",
             signature: "(name: string) -> Pattern",
@@ -484,6 +580,24 @@ fn get_api_functions() -> Vec<ApiFunctionDoc> {
             description: "Create a melodic sequence builder.",
             example: "melody(\"bass\").on(bass).notes(\"E1 - - - | G1 - - -\").start();",
         },
+        ApiFunctionDoc {
+            name: "ctrl",
+            signature: "(name: string) -> Ctrl",
+            description: "Create a control pattern that steps or slides a parameter.",
+            example: "ctrl(\"acid_sweep\").steps([200, 400, 800, 1600]).target(bass, \"cutoff\").slide().start();",
+        },
+        ApiFunctionDoc {
+            name: "pattern_from_string",
+            signature: "(text: string) -> Pattern",
+            description: "Read a pattern from the text written by pattern.to_string().",
+            example: "pattern_from_string(\"pattern hats\\non hat\\nsteps x.x.x.x.\").start();",
+        },
+        ApiFunctionDoc {
+            name: "melody_from_string",
+            signature: "(text: string) -> Melody",
+            description: "Read a melody from the text written by melody.to_string().",
+            example: "melody_from_string(\"melody bass\\non bass\\nnotes E1 - - - | G1 - - -\").start();",
+        },
         ApiFunctionDoc {
             name: "sequence",
             signature: "(name: string) -> Sequence",
@@ -546,8 +660,8 @@ fn get_api_functions() -> Vec<ApiFunctionDoc> {
         },
         ApiFunctionDoc {
             name: "bars",
-            signature: "(count: float) -> int",
-            description: "Convert bars to beats (assuming 4/4 time).",
+            signature: "(count: float) -> float",
+            description: "Convert bars to beats with the current time signature.",
             example: "sequence(\"s\").clip(0..bars(8), pattern);",
         },
         ApiFunctionDoc {
@@ -558,3 +672,27 @@ fn get_api_functions() -> Vec<ApiFunctionDoc> {
         },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stdlib_import_edit() {
+        let import = |path: &str, line: u32| ImportInfo {
+            path: path.to_string(),
+            range: Range {
+                start: Position { line, character: 8 },
+                end: Position { line, character: 8 + path.len() as u32 },
+            },
+            resolved_path: None,
+        };
+
+        let edit = stdlib_import_edit("kick_909", &[import("stdlib/bass/sub/sub_bass.vibe", 2)]).unwrap();
+        assert_eq!(edit.range.start, Position { line: 3, character: 0 });
+        assert_eq!(edit.new_text, "import \"stdlib/drums/kicks/kick_909.vibe\";\n");
+
+        assert!(stdlib_import_edit("kick_909", &[import("stdlib/drums/kicks/kick_909.vibe", 0)]).is_none());
+        assert!(stdlib_import_edit("my_own_synth", &[]).is_none());
+    }
+}
@@ -422,8 +422,8 @@ fn get_api_function_hover(name: &str) -> Option<MarkupContent> {
         ),
         (
             "bars",
-            "Convert bars to beats (assuming 4/4 time). Essential for sequence clip ranges.",
-            "bars(count: float) -> int",
+            "Convert bars to beats with the current time signature. `8.bars` keeps its unit instead.",
+            "bars(count: float) -> float",
             "```rhai\nsequence(\"intro\")\n    .clip(0..bars(8), pattern)\n    .clip(bars(8)..bars(16), melody);\n```",
         ),
         (
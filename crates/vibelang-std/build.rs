@@ -0,0 +1,65 @@
+//! Generates the stdlib index from the headers of the embedded `.vibe` files.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+#[path = "src/parse.rs"]
+#[allow(dead_code)]
+mod parse;
+
+fn main() {
+    println!("cargo:rerun-if-changed=stdlib");
+
+    let root = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("stdlib");
+    let mut files = Vec::new();
+    collect_vibe_files(&root, &mut files);
+    files.sort();
+
+    let mut definitions = Vec::new();
+    for file in &files {
+        let relative = file
+            .strip_prefix(&root)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let source = std::fs::read_to_string(file).unwrap_or_default();
+        definitions.extend(parse::parse_definitions(&relative, &source));
+    }
+
+    let mut out = String::from("&[\n");
+    for def in &definitions {
+        let params: String = def.params.iter().map(|(n, v)| format!("({:?}, {:?}), ", n, v)).collect();
+        let genres: String = def.genres.iter().map(|g| format!("{:?}, ", g)).collect();
+        let _ = writeln!(
+            out,
+            "    StdlibEntry {{ name: {:?}, kind: {}, path: {:?}, category: {:?}, description: {:?}, genres: &[{}], params: &[{}] }},",
+            def.name,
+            if def.is_fx { "EntryKind::Effect" } else { "EntryKind::Synth" },
+            def.path,
+            def.category,
+            def.description,
+            genres,
+            params,
+        );
+    }
+    out.push(']');
+
+    let dest = Path::new(&std::env::var("OUT_DIR").unwrap()).join("stdlib_index.rs");
+    std::fs::write(dest, out).expect("failed to write stdlib index");
+}
+
+fn collect_vibe_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_vibe_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "vibe") {
+            files.push(path);
+        }
+    }
+}
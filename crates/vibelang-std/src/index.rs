@@ -0,0 +1,166 @@
+//! Index of the synthdefs and effects in the standard library.
+//!
+//! Generated at build time from the embedded `.vibe` files: the name and
+//! parameters of every `define_synthdef`/`define_fx`, the directory it lives
+//! in and the description from its header comment
+//! (`// Genre: House, Techno | Character: Classic 909, tight and punchy`).
+//! Lets tools suggest imports and list definitions without evaluating the
+//! files.
+
+/// Whether an entry is an instrument or an effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Defined with `define_synthdef`.
+    Synth,
+    /// Defined with `define_fx`.
+    Effect,
+}
+
+impl EntryKind {
+    /// The name of the defining function's kind ("synthdef" or "fx").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Synth => "synthdef",
+            EntryKind::Effect => "fx",
+        }
+    }
+}
+
+/// A synthdef or effect defined in the standard library.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StdlibEntry {
+    /// Synthdef name.
+    pub name: &'static str,
+    /// Instrument or effect.
+    pub kind: EntryKind,
+    /// File path relative to the stdlib root, e.g. "drums/kicks/kick_909.vibe".
+    pub path: &'static str,
+    /// Top-level directory, e.g. "drums".
+    pub category: &'static str,
+    /// One-line description from the header comment.
+    pub description: &'static str,
+    /// Genres listed in the header comment.
+    pub genres: &'static [&'static str],
+    /// Parameters with their default values.
+    pub params: &'static [(&'static str, f64)],
+}
+
+impl StdlibEntry {
+    /// The path to import, e.g. "stdlib/drums/kicks/kick_909.vibe".
+    pub fn import_path(&self) -> String {
+        format!("stdlib/{}", self.path)
+    }
+
+    /// The `import` statement that loads this definition.
+    pub fn import_statement(&self) -> String {
+        format!("import \"{}\";", self.import_path())
+    }
+
+    /// How well this entry matches lowercase search terms (0 = no match).
+    fn score(&self, terms: &[String]) -> u32 {
+        let name = self.name.to_lowercase();
+        let description = self.description.to_lowercase();
+        let mut score = 0;
+        for term in terms {
+            score += if name == *term {
+                100
+            } else if name.starts_with(term.as_str()) {
+                50
+            } else if name.contains(term.as_str()) {
+                20
+            } else if self.category == term || self.genres.iter().any(|g| g.to_lowercase() == *term) {
+                10
+            } else if self.path.contains(term.as_str()) || description.contains(term.as_str()) {
+                5
+            } else {
+                // Every term has to match
+                return 0;
+            };
+        }
+        score
+    }
+}
+
+static STDLIB_INDEX: &[StdlibEntry] = include!(concat!(env!("OUT_DIR"), "/stdlib_index.rs"));
+
+/// All synthdefs and effects of the standard library, ordered by path.
+pub fn stdlib_index() -> &'static [StdlibEntry] {
+    STDLIB_INDEX
+}
+
+/// Look up a definition by synthdef name.
+pub fn find_definition(name: &str) -> Option<&'static StdlibEntry> {
+    STDLIB_INDEX.iter().find(|e| e.name == name)
+}
+
+/// Search names, categories, genres and descriptions.
+///
+/// Every whitespace-separated term has to match; results are ordered best
+/// match first (exact name, name prefix, name, then the other fields).
+pub fn search(query: &str) -> Vec<&'static StdlibEntry> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if terms.is_empty() {
+        return STDLIB_INDEX.iter().collect();
+    }
+    let mut matches: Vec<(u32, &'static StdlibEntry)> = STDLIB_INDEX
+        .iter()
+        .map(|e| (e.score(&terms), e))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    matches.sort_by(|(a, ea), (b, eb)| b.cmp(a).then_with(|| ea.name.cmp(eb.name)));
+    matches.into_iter().map(|(_, e)| e).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_definitions;
+
+    #[test]
+    fn test_parse_headers() {
+        let source = r#"// Saxophone Family
+// Jazz/Pop | Character: Warm, expressive, brassy
+
+// Alto Saxophone
+define_synthdef("alto_sax", |builder| {
+    builder
+        .param("freq", 440.0)
+        .param("amp", 0.5)
+        .param("cutoff", 1200.0, 20.0, 20000.0, "hz", "exp")
+        .body(|freq, amp, cutoff| { saw_ar(freq) * amp })
+});
+
+define_fx("sax_room")
+    .param("mix", 0.3)
+    .body(|input, mix| input);
+"#;
+        let defs = parse_definitions("woodwinds/saxophone.vibe", source);
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs[0].name, "alto_sax");
+        assert_eq!(defs[0].description, "Alto Saxophone");
+        assert_eq!(defs[0].genres, vec!["Jazz", "Pop"]);
+        assert_eq!(
+            defs[0].params,
+            vec![("freq".to_string(), 440.0), ("amp".to_string(), 0.5), ("cutoff".to_string(), 1200.0)]
+        );
+        assert_eq!(defs[0].category, "woodwinds");
+        assert!(defs[1].is_fx);
+        assert_eq!(defs[1].description, "Warm, expressive, brassy");
+        assert_eq!(defs[1].params, vec![("mix".to_string(), 0.3)]);
+    }
+
+    #[test]
+    fn test_index_and_search() {
+        let kick = find_definition("kick_909").expect("kick_909 is in the stdlib");
+        assert_eq!(kick.kind, EntryKind::Synth);
+        assert_eq!(kick.import_path(), "stdlib/drums/kicks/kick_909.vibe");
+        assert_eq!(kick.category, "drums");
+        assert!(kick.genres.contains(&"Techno"));
+        assert!(kick.params.iter().any(|(name, _)| *name == "freq"));
+
+        assert_eq!(search("kick_909")[0].name, "kick_909");
+        assert!(search("kick 909").iter().all(|e| e.name.contains("kick") || e.path.contains("kick")));
+        assert!(search("compressor").iter().any(|e| e.kind == EntryKind::Effect));
+        assert!(search("no_such_sound_xyz").is_empty());
+    }
+}
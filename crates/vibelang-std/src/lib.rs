@@ -23,12 +23,23 @@
 //! - `stdlib/fx/` - Sound design elements (impacts, risers, subdrops, sweeps)
 //! - `stdlib/theory/` - Music theory tools (scales, chords, progressions, etc.)
 //!
+//! # Index
+//!
+//! [`stdlib_index`] lists every synthdef and effect with its parameters and
+//! header description, generated at build time; [`search`] queries it.
+//!
 //! # Installation
 //!
 //! When installed via `cargo install vibelang-cli`, the stdlib is automatically
 //! extracted to `~/.local/share/vibelang/stdlib/` (Linux/macOS) or the equivalent
 //! user data directory on Windows.
 
+pub mod index;
+#[cfg(test)]
+mod parse;
+
+pub use index::{find_definition, search, stdlib_index, EntryKind, StdlibEntry};
+
 use include_dir::{include_dir, Dir};
 use std::path::PathBuf;
 use std::sync::OnceLock;
@@ -0,0 +1,145 @@
+//! Parsing of synthdef definitions and their header comments.
+//!
+//! Shared by the build script, which generates the stdlib index from it, and
+//! the crate's tests. Only depends on `std`.
+
+/// A synthdef or effect found in a `.vibe` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    /// Synthdef name.
+    pub name: String,
+    /// Whether it was defined with `define_fx`.
+    pub is_fx: bool,
+    /// Path relative to the stdlib root, e.g. "drums/kicks/kick_909.vibe".
+    pub path: String,
+    /// Top-level stdlib directory, e.g. "drums".
+    pub category: String,
+    /// One-line description from the header comment.
+    pub description: String,
+    /// Genres from a `Genre: House, Techno | Character: ...` header.
+    pub genres: Vec<String>,
+    /// Parameters with their default values, in definition order.
+    pub params: Vec<(String, f64)>,
+}
+
+/// Description and genres of a comment block.
+#[derive(Debug, Default, Clone)]
+struct Header {
+    description: String,
+    genres: Vec<String>,
+}
+
+/// Find all `define_synthdef`/`define_fx` definitions of a file.
+pub fn parse_definitions(path: &str, source: &str) -> Vec<Definition> {
+    let lines: Vec<&str> = source.lines().collect();
+    let file_header = parse_header(
+        &lines
+            .iter()
+            .take_while(|l| l.trim_start().starts_with("//"))
+            .copied()
+            .collect::<Vec<_>>(),
+    );
+    let category = path.split('/').next().unwrap_or_default().to_string();
+
+    let mut definitions: Vec<Definition> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if let Some((name, is_fx)) = define_name(trimmed) {
+            let header = parse_header(&comment_block_above(&lines, i));
+            definitions.push(Definition {
+                name,
+                is_fx,
+                path: path.to_string(),
+                category: category.clone(),
+                description: if header.description.is_empty() {
+                    file_header.description.clone()
+                } else {
+                    header.description
+                },
+                genres: if header.genres.is_empty() {
+                    file_header.genres.clone()
+                } else {
+                    header.genres
+                },
+                params: Vec::new(),
+            });
+        } else if let (Some(def), Some(param)) = (definitions.last_mut(), param(trimmed)) {
+            def.params.push(param);
+        }
+    }
+    definitions
+}
+
+/// Name of a `define_synthdef("name"` / `define_fx("name"` line.
+fn define_name(line: &str) -> Option<(String, bool)> {
+    let (rest, is_fx) = if let Some(rest) = line.strip_prefix("define_synthdef(") {
+        (rest, false)
+    } else {
+        (line.strip_prefix("define_fx(")?, true)
+    };
+    Some((quoted(rest)?.0, is_fx))
+}
+
+/// A `.param("name", default)` line.
+fn param(line: &str) -> Option<(String, f64)> {
+    let rest = line.strip_prefix(".param(")?;
+    let (name, rest) = quoted(rest)?;
+    let value = rest.trim_start().strip_prefix(',')?;
+    // Ranged params continue with min, max, unit and curve
+    let value = value.split([',', ')']).next()?.trim();
+    Some((name, value.parse().ok()?))
+}
+
+/// The leading string literal of `text` and what follows it.
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let rest = text.trim_start().strip_prefix('"')?;
+    let end = rest.find('"')?;
+    Some((rest[..end].to_string(), &rest[end + 1..]))
+}
+
+/// The comment lines directly above line `index`.
+fn comment_block_above<'a>(lines: &[&'a str], index: usize) -> Vec<&'a str> {
+    let mut block: Vec<&str> = lines[..index]
+        .iter()
+        .rev()
+        .skip_while(|l| l.trim().is_empty())
+        .take_while(|l| l.trim_start().starts_with("//"))
+        .copied()
+        .collect();
+    block.reverse();
+    block
+}
+
+/// Description and genres of a comment block.
+///
+/// Understands `Genre: A, B | Character: text` lines; otherwise the first
+/// comment line is the description.
+fn parse_header(block: &[&str]) -> Header {
+    let text: Vec<&str> = block
+        .iter()
+        .map(|l| l.trim_start().trim_start_matches('/').trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    if let Some(line) = text.iter().find(|l| l.contains("Character:")) {
+        let mut header = Header::default();
+        for part in line.split('|').map(str::trim) {
+            if let Some(character) = part.strip_prefix("Character:") {
+                header.description = character.trim().to_string();
+            } else {
+                let genres = part.strip_prefix("Genre:").unwrap_or(part);
+                header.genres = genres
+                    .split([',', '/'])
+                    .map(|g| g.trim().to_string())
+                    .filter(|g| !g.is_empty())
+                    .collect();
+            }
+        }
+        return header;
+    }
+
+    Header {
+        description: text.first().map(|l| l.to_string()).unwrap_or_default(),
+        genres: Vec::new(),
+    }
+}
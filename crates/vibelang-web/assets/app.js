@@ -0,0 +1,353 @@
+// VibeLang web control surface.
+//
+// Talks to the REST API served from the same origin and listens on /ws for
+// transport events. Structural data (groups, loops, sequences) is polled at a
+// low rate; meters and the playhead are polled faster.
+
+"use strict";
+
+const STRUCTURE_POLL_MS = 1000;
+const LIVE_POLL_MS = 100;
+
+const ui = {
+  transport: null,
+  groups: [],
+  patterns: [],
+  melodies: [],
+  sequences: [],
+  activeSequences: [],
+  meters: {},
+  // Faders the user is currently dragging (don't overwrite them from polls)
+  dragging: new Set(),
+};
+
+// ---------------------------------------------------------------------------
+// API helpers
+// ---------------------------------------------------------------------------
+
+async function api(method, path, body) {
+  const opts = { method, headers: {} };
+  if (body !== undefined) {
+    opts.headers["Content-Type"] = "application/json";
+    opts.body = JSON.stringify(body);
+  }
+  const res = await fetch(path, opts);
+  if (!res.ok) {
+    const text = await res.text();
+    throw new Error(`${method} ${path}: ${res.status} ${text}`);
+  }
+  const type = res.headers.get("content-type") || "";
+  return type.includes("application/json") ? res.json() : null;
+}
+
+const enc = encodeURIComponent;
+
+function el(tag, attrs = {}, ...children) {
+  const node = document.createElement(tag);
+  for (const [key, value] of Object.entries(attrs)) {
+    if (key === "class") node.className = value;
+    else if (key.startsWith("on")) node.addEventListener(key.slice(2), value);
+    else node.setAttribute(key, value);
+  }
+  for (const child of children) {
+    node.append(child instanceof Node ? child : document.createTextNode(String(child)));
+  }
+  return node;
+}
+
+function report(err) {
+  console.error(err);
+}
+
+// ---------------------------------------------------------------------------
+// Transport
+// ---------------------------------------------------------------------------
+
+function beatsPerBar() {
+  const ts = ui.transport && ui.transport.time_signature;
+  return ts ? ts.numerator * (4 / ts.denominator) : 4;
+}
+
+function renderTransport() {
+  const t = ui.transport;
+  if (!t) return;
+  const bpb = beatsPerBar();
+  const bar = Math.floor(t.current_beat / bpb) + 1;
+  const beat = Math.floor(t.current_beat % bpb) + 1;
+  document.getElementById("position").textContent = `${bar}.${beat}`;
+  document.getElementById("timesig").textContent =
+    `${t.time_signature.numerator}/${t.time_signature.denominator}`;
+  const play = document.getElementById("play");
+  play.textContent = t.running ? "■" : "▶";
+  play.classList.toggle("active", t.running);
+  const bpm = document.getElementById("bpm");
+  if (document.activeElement !== bpm) bpm.value = Math.round(t.bpm * 100) / 100;
+}
+
+function togglePlay() {
+  const running = ui.transport && ui.transport.running;
+  api("POST", running ? "/transport/stop" : "/transport/start")
+    .then((t) => { ui.transport = t; renderTransport(); })
+    .catch(report);
+}
+
+function setBpm(value) {
+  const bpm = parseFloat(value);
+  if (!(bpm >= 20 && bpm <= 999)) return;
+  api("PATCH", "/transport", { bpm })
+    .then((t) => { ui.transport = t; renderTransport(); })
+    .catch(report);
+}
+
+// ---------------------------------------------------------------------------
+// Mixer
+// ---------------------------------------------------------------------------
+
+function renderMixer() {
+  const mixer = document.getElementById("mixer");
+  const groups = [...ui.groups].sort((a, b) => a.path.localeCompare(b.path));
+  const existing = new Map([...mixer.children].map((s) => [s.dataset.path, s]));
+
+  for (const group of groups) {
+    let strip = existing.get(group.path);
+    if (!strip) {
+      strip = buildStrip(group);
+      mixer.append(strip);
+    }
+    existing.delete(group.path);
+    updateStrip(strip, group);
+  }
+  for (const stale of existing.values()) stale.remove();
+}
+
+function buildStrip(group) {
+  const fader = el("input", { type: "range", min: "0", max: "1.5", step: "0.01" });
+  fader.addEventListener("pointerdown", () => ui.dragging.add(group.path));
+  fader.addEventListener("pointerup", () => ui.dragging.delete(group.path));
+  fader.addEventListener("input", () => {
+    api("PUT", `/groups/${enc(group.path)}/params/amp`, { value: parseFloat(fader.value) })
+      .catch(report);
+  });
+
+  const strip = el("div", { class: "strip" },
+    el("div", { class: "name", title: group.path }, group.name),
+    el("div", { class: "fader-row" },
+      fader,
+      el("div", { class: "meter" }, el("div")),
+      el("div", { class: "meter" }, el("div")),
+    ),
+    el("div", { class: "value" }),
+    el("div", { class: "buttons" },
+      el("button", { class: "mute", title: "Mute", onclick: () => toggleGroup(group.path, "mute") }, "M"),
+      el("button", { class: "solo", title: "Solo", onclick: () => toggleGroup(group.path, "solo") }, "S"),
+    ),
+  );
+  strip.dataset.path = group.path;
+  return strip;
+}
+
+function updateStrip(strip, group) {
+  const amp = group.params.amp !== undefined ? group.params.amp : 1.0;
+  const fader = strip.querySelector("input[type=range]");
+  if (!ui.dragging.has(group.path)) fader.value = amp;
+  strip.querySelector(".value").textContent = ampToDb(amp);
+  strip.querySelector("button.mute").classList.toggle("active", group.muted);
+  strip.querySelector("button.solo").classList.toggle("active", group.soloed);
+}
+
+function updateMeters() {
+  for (const strip of document.querySelectorAll("#mixer .strip")) {
+    const level = ui.meters[strip.dataset.path];
+    const bars = strip.querySelectorAll(".meter div");
+    const values = level ? [level.peak_left, level.peak_right] : [0, 0];
+    values.forEach((v, i) => {
+      bars[i].style.height = `${Math.min(v, 1) * 100}%`;
+      bars[i].classList.toggle("hot", v >= 1);
+    });
+  }
+}
+
+function ampToDb(amp) {
+  if (amp <= 0.0001) return "-inf dB";
+  return `${(20 * Math.log10(amp)).toFixed(1)} dB`;
+}
+
+function toggleGroup(path, kind) {
+  const group = ui.groups.find((g) => g.path === path);
+  if (!group) return;
+  const on = kind === "mute" ? group.muted : group.soloed;
+  const action = on ? `un${kind}` : kind;
+  api("POST", `/groups/${enc(path)}/${action}`).then(refreshStructure).catch(report);
+}
+
+// ---------------------------------------------------------------------------
+// Patterns & melodies
+// ---------------------------------------------------------------------------
+
+function renderLoops() {
+  const rows = [
+    ...ui.patterns.map((p) => ({ kind: "patterns", ...p })),
+    ...ui.melodies.map((m) => ({ kind: "melodies", ...m })),
+  ].sort((a, b) => a.name.localeCompare(b.name));
+
+  const tbody = document.querySelector("#loops tbody");
+  tbody.replaceChildren(...rows.map((loop) => {
+    const active = loop.status.state === "playing" || loop.status.state === "queued";
+    const button = el("button", {
+      class: active ? "active" : "",
+      title: active ? "Stop" : "Start",
+      onclick: () => api("POST", `/${loop.kind}/${enc(loop.name)}/${active ? "stop" : "start"}`, null)
+        .then(refreshStructure)
+        .catch(report),
+    }, active ? "■" : "▶");
+    return el("tr", {},
+      el("td", {}, button),
+      el("td", {}, loop.name),
+      el("td", {}, loop.voice_name),
+      el("td", {}, loop.group_path),
+      el("td", { class: "mono" }, loop.loop_beats),
+      el("td", { class: `state-${loop.status.state}` }, loop.status.state.replace("_", " ")),
+    );
+  }));
+}
+
+// ---------------------------------------------------------------------------
+// Sequence timeline
+// ---------------------------------------------------------------------------
+
+function renderSequences() {
+  const container = document.getElementById("sequences");
+  const sequences = [...ui.sequences].sort((a, b) => a.name.localeCompare(b.name));
+
+  container.replaceChildren(...sequences.map((seq) => {
+    const length = Math.max(seq.loop_beats, ...seq.clips.map((c) => c.end_beat), 1);
+    const pct = (beat) => `${(beat / length) * 100}%`;
+
+    const lanes = new Map();
+    for (const clip of seq.clips) {
+      const key = `${clip.clip_type}:${clip.name}`;
+      if (!lanes.has(key)) lanes.set(key, []);
+      lanes.get(key).push(clip);
+    }
+
+    const timeline = el("div", { class: "timeline" },
+      ...[...lanes.values()].map((clips) => el("div", { class: "lane" },
+        ...clips.map((clip) => {
+          const node = el("div", {
+            class: `clip ${clip.clip_type}`,
+            title: `${clip.name} [${clip.start_beat}–${clip.end_beat}] ${clip.mode}`,
+          }, clip.name);
+          node.style.left = pct(clip.start_beat);
+          node.style.width = pct(clip.end_beat - clip.start_beat);
+          return node;
+        }),
+      )),
+      el("div", { class: "playhead", "data-sequence": seq.name }),
+    );
+
+    const action = seq.active ? "stop" : "start";
+    return el("div", { class: "sequence", "data-length": length },
+      el("div", { class: "title" },
+        el("button", {
+          class: seq.active ? "active" : "",
+          onclick: () => api("POST", `/sequences/${enc(seq.name)}/${action}`, action === "start" ? null : undefined)
+            .then(refreshStructure)
+            .catch(report),
+        }, seq.active ? "■" : "▶"),
+        el("strong", {}, seq.name),
+        el("span", { class: "meta" }, `${seq.loop_beats} beats${seq.play_once ? " · once" : ""}`),
+      ),
+      timeline,
+    );
+  }));
+  updatePlayheads();
+}
+
+function updatePlayheads() {
+  const positions = new Map(ui.activeSequences.map((s) => [s.name, s.current_position]));
+  for (const head of document.querySelectorAll(".playhead")) {
+    const name = head.dataset.sequence;
+    const length = parseFloat(head.closest(".sequence").dataset.length);
+    if (positions.has(name)) {
+      head.style.display = "";
+      head.style.left = `${(Math.max(positions.get(name), 0) / length) * 100}%`;
+    } else {
+      head.style.display = "none";
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Polling & WebSocket
+// ---------------------------------------------------------------------------
+
+async function refreshStructure() {
+  try {
+    const [groups, patterns, melodies, sequences] = await Promise.all([
+      api("GET", "/groups"),
+      api("GET", "/patterns"),
+      api("GET", "/melodies"),
+      api("GET", "/sequences"),
+    ]);
+    Object.assign(ui, { groups, patterns, melodies, sequences });
+    renderMixer();
+    renderLoops();
+    renderSequences();
+  } catch (err) {
+    report(err);
+  }
+}
+
+async function refreshLive() {
+  try {
+    const [transport, meters, activeSequences] = await Promise.all([
+      api("GET", "/transport"),
+      api("GET", "/live/meters"),
+      api("GET", "/live/sequences"),
+    ]);
+    Object.assign(ui, { transport, meters, activeSequences });
+    renderTransport();
+    updateMeters();
+    updatePlayheads();
+  } catch (err) {
+    report(err);
+  }
+}
+
+function connectWebSocket() {
+  const status = document.getElementById("connection");
+  const proto = location.protocol === "https:" ? "wss:" : "ws:";
+  const ws = new WebSocket(`${proto}//${location.host}/ws`);
+
+  ws.onopen = () => {
+    status.textContent = "online";
+    status.className = "status online";
+    ws.send(JSON.stringify({ action: "subscribe", events: ["transport.*"] }));
+  };
+  ws.onmessage = (msg) => {
+    const event = JSON.parse(msg.data);
+    if (event.type === "transport.started" || event.type === "transport.stopped" || event.type === "transport.bpm") {
+      refreshLive();
+    }
+  };
+  ws.onclose = () => {
+    status.textContent = "offline";
+    status.className = "status offline";
+    setTimeout(connectWebSocket, 2000);
+  };
+}
+
+document.getElementById("play").addEventListener("click", togglePlay);
+document.getElementById("bpm").addEventListener("change", (e) => setBpm(e.target.value));
+document.addEventListener("keydown", (e) => {
+  if (e.code === "Space" && e.target === document.body) {
+    e.preventDefault();
+    togglePlay();
+  }
+});
+
+refreshStructure();
+refreshLive();
+setInterval(refreshStructure, STRUCTURE_POLL_MS);
+setInterval(refreshLive, LIVE_POLL_MS);
+connectWebSocket();
@@ -0,0 +1,91 @@
+//! Browser-based control surface for VibeLang.
+//!
+//! This crate embeds a small single-page app (plain HTML/CSS/JS, no build
+//! step) that talks to a running session through the existing REST and
+//! WebSocket API of `vibelang-http`. The HTTP server mounts it at `/ui`.
+//!
+//! The UI shows:
+//!
+//! - Transport (play/stop, tempo, position)
+//! - Mixer (group faders, mute/solo, meters)
+//! - Patterns and melodies (start/stop)
+//! - Sequence timeline with playheads
+//!
+//! # Usage
+//!
+//! ```ignore
+//! if let Some(asset) = vibelang_web::asset("app.js") {
+//!     respond(asset.content_type, asset.contents);
+//! }
+//! ```
+
+use include_dir::{include_dir, Dir};
+
+/// Embedded UI assets (compiled into the binary)
+static ASSETS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/assets");
+
+/// Name of the SPA entry point.
+pub const INDEX: &str = "index.html";
+
+/// An embedded UI file.
+#[derive(Debug, Clone, Copy)]
+pub struct Asset {
+    /// File contents.
+    pub contents: &'static [u8],
+    /// MIME type derived from the file extension.
+    pub content_type: &'static str,
+}
+
+/// Look up an embedded asset by its path relative to the UI root.
+///
+/// An empty path resolves to the index page. Leading slashes are ignored.
+pub fn asset(path: &str) -> Option<Asset> {
+    let path = path.trim_start_matches('/');
+    let path = if path.is_empty() { INDEX } else { path };
+
+    ASSETS_DIR.get_file(path).map(|file| Asset {
+        contents: file.contents(),
+        content_type: content_type_for(path),
+    })
+}
+
+/// The SPA entry point.
+pub fn index() -> Asset {
+    asset(INDEX).expect("index.html is embedded")
+}
+
+/// Determine the MIME type for a file name.
+fn content_type_for(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or("") {
+        "html" => "text/html; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_is_embedded() {
+        let index = index();
+        assert!(!index.contents.is_empty());
+        assert_eq!(index.content_type, "text/html; charset=utf-8");
+        assert!(asset("").is_some());
+        assert!(asset("/").is_some());
+    }
+
+    #[test]
+    fn test_asset_lookup() {
+        assert_eq!(asset("app.js").unwrap().content_type, "text/javascript; charset=utf-8");
+        assert_eq!(asset("/style.css").unwrap().content_type, "text/css; charset=utf-8");
+        assert!(asset("missing.js").is_none());
+        assert!(asset("../Cargo.toml").is_none());
+    }
+}
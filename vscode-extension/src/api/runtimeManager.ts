@@ -468,8 +468,8 @@ export class RuntimeManager {
         await this.post(`/voices/${encodeURIComponent(name)}/trigger`, { params });
     }
 
-    async stopVoice(name: string): Promise<void> {
-        await this.post(`/voices/${encodeURIComponent(name)}/stop`);
+    async stopVoice(name: string, options?: { fade?: number; choke?: boolean }): Promise<void> {
+        await this.post(`/voices/${encodeURIComponent(name)}/stop`, options);
     }
 
     async noteOn(name: string, note: number, velocity = 100): Promise<void> {
@@ -0,0 +1,47 @@
+/**
+ * VibeLang Session Discovery
+ *
+ * Finds running sessions on the LAN through `vibe list-sessions`, which
+ * browses for the sessions' zeroconf (`_vibelang._tcp`) advertisements.
+ */
+
+import * as cp from 'child_process';
+import { DiscoveredSession } from './types';
+
+const BROWSE_SECONDS = 2;
+
+/**
+ * List the sessions advertised on the network.
+ * @param binaryPath Path of the `vibe` binary
+ */
+export function discoverSessions(binaryPath: string): Promise<DiscoveredSession[]> {
+    return new Promise((resolve, reject) => {
+        cp.execFile(
+            binaryPath,
+            ['list-sessions', '--json', '--timeout', String(BROWSE_SECONDS)],
+            { timeout: (BROWSE_SECONDS + 5) * 1000 },
+            (error, stdout, stderr) => {
+                if (error) {
+                    reject(new Error(stderr.trim() || error.message));
+                    return;
+                }
+                try {
+                    resolve(JSON.parse(stdout) as DiscoveredSession[]);
+                } catch (e) {
+                    reject(e instanceof Error ? e : new Error(String(e)));
+                }
+            }
+        );
+    });
+}
+
+/**
+ * Host to connect to: the first advertised address, IPv4 preferred.
+ */
+export function sessionHost(session: DiscoveredSession): string {
+    const address = session.addresses[0];
+    if (!address) {
+        return session.host;
+    }
+    return address.includes(':') ? `[${address}]` : address;
+}
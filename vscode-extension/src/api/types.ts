@@ -26,6 +26,21 @@ export interface TransportState {
     loop_beat?: number;
     /** Server timestamp when this state was captured (ms since Unix epoch) */
     server_time_ms?: number;
+    /** Song position locators in song order */
+    markers?: Marker[];
+    /** Jump to a locator waiting for its launch point */
+    pending_jump?: PendingJump | null;
+}
+
+export interface Marker {
+    name: string;
+    beat: number;
+}
+
+export interface PendingJump {
+    marker: string;
+    at_beat: number;
+    target_beat: number;
 }
 
 export interface TransportUpdate {
@@ -173,6 +188,8 @@ export interface MelodyEvent {
     frequency?: number;
     duration?: number;
     velocity?: number;
+    /** Pitch and velocity for display, e.g. "C#3 (vel 96)". */
+    label?: string;
     params?: Record<string, number>;
 }
 
@@ -226,11 +243,13 @@ export interface SequenceClip {
     end_beat?: number;
     duration_beats?: number;
     once?: boolean;
+    speed?: number;
 }
 
 export interface Sequence {
     name: string;
     loop_beats: number;
+    speed?: number;
     clips: SequenceClip[];
     play_once?: boolean;
     active?: boolean;
@@ -245,6 +264,7 @@ export interface SequenceCreate {
 
 export interface SequenceUpdate {
     loop_beats?: number;
+    speed?: number;
     clips?: SequenceClip[];
 }
 
@@ -271,6 +291,7 @@ export interface EffectCreate {
     group_path: string;
     params?: Record<string, number>;
     position?: number;
+    vst_plugin?: string;
 }
 
 export interface EffectUpdate {
@@ -312,6 +333,10 @@ export interface SynthDefParam {
     default_value: number;
     min_value?: number;
     max_value?: number;
+    /** Declared unit, e.g. "hz". */
+    unit?: string;
+    /** Declared knob/slider curve. */
+    curve?: 'lin' | 'exp';
 }
 
 export type SynthDefSource = 'builtin' | 'user' | 'stdlib';
@@ -320,6 +345,11 @@ export interface SynthDef {
     name: string;
     params: SynthDefParam[];
     source?: SynthDefSource;
+    /** False for stdlib definitions listed with `?stdlib=true` that aren't imported yet. */
+    loaded?: boolean;
+    category?: string;
+    description?: string;
+    import_path?: string;
 }
 
 // =============================================================================
@@ -340,6 +370,8 @@ export interface ActiveFade {
     duration_beats: number;
     start_beat?: number;
     progress: number;
+    curve?: 'linear' | 'db';
+    source_location?: SourceLocation;
 }
 
 export interface FadeCreate {
@@ -361,6 +393,17 @@ export interface ActiveSynth {
     voice_name?: string;
     group_path?: string;
     created_at_beat?: number;
+    pattern_name?: string;
+    melody_name?: string;
+    step?: number;
+    clip?: SynthClip;
+    source_location?: SourceLocation;
+    provenance: string;
+}
+
+export interface SynthClip {
+    sequence: string;
+    index: number;
 }
 
 export interface ActiveSequence {
@@ -382,6 +425,23 @@ export interface LiveState {
     melodies_status?: Record<string, LoopStatus>;
 }
 
+/** Notes sounding on a voice (`GET /live/notes`, keyed by voice name). */
+export interface VoiceNotes {
+    active_count: number;
+    polyphony: number;
+    notes: SoundingNote[];
+}
+
+export interface SoundingNote {
+    note: number;
+    name: string;
+    count: number;
+    age_ms: number;
+    source?: 'pattern' | 'melody' | 'midi' | 'direct';
+    source_name?: string;
+    stuck: boolean;
+}
+
 // =============================================================================
 // Metering
 // =============================================================================
@@ -395,6 +455,21 @@ export interface MeterLevel {
 
 export type MeterLevels = Record<string, MeterLevel>;
 
+// =============================================================================
+// Session Discovery (`vibe list-sessions --json`)
+// =============================================================================
+
+export interface DiscoveredSession {
+    name: string;
+    host: string;
+    addresses: string[];
+    port: number;
+    version?: string;
+    api_version?: string;
+    /** Sandbox profile of /eval requests without a token */
+    sandbox?: string;
+}
+
 // =============================================================================
 // Full Session State (aggregated for the extension)
 // =============================================================================
@@ -750,21 +750,41 @@ ${paramsCode};`;
             \`;
         }
 
+        // Knob position (0..1) of a value, following the param's declared curve
+        function toNormalized(value, min, max, curve) {
+            const clamped = Math.max(min, Math.min(max, value));
+            if (curve === 'exp' && min > 0) {
+                return Math.log(clamped / min) / Math.log(max / min);
+            }
+            return (clamped - min) / (max - min);
+        }
+
+        function fromNormalized(position, min, max, curve) {
+            const p = Math.max(0, Math.min(1, position));
+            if (curve === 'exp' && min > 0) {
+                return min * Math.pow(max / min, p);
+            }
+            return min + p * (max - min);
+        }
+
         function renderParamKnob(effectId, paramName, value, synthdef) {
             const paramDef = synthdef?.params.find(p => p.name === paramName);
             const min = paramDef?.min_value ?? 0;
             const max = paramDef?.max_value ?? 1;
-            const normalized = (value - min) / (max - min);
+            const curve = paramDef?.curve ?? 'lin';
+            const unit = paramDef?.unit ? ' ' + paramDef.unit : '';
+            const normalized = toNormalized(value, min, max, curve);
             const rotation = -135 + normalized * 270; // -135 to 135 degrees
 
             return \`
                 <div class="param-control">
                     <div class="knob-container">
                         <div class="knob" data-effect-id="\${effectId}" data-param="\${paramName}"
-                             data-min="\${min}" data-max="\${max}" data-value="\${value}">
+                             data-min="\${min}" data-max="\${max}" data-curve="\${curve}"
+                             data-unit="\${unit}" data-value="\${value}">
                             <div class="knob-indicator" style="transform: translateX(-50%) rotate(\${rotation}deg)"></div>
                         </div>
-                        <span class="knob-value">\${formatValue(value)}</span>
+                        <span class="knob-value">\${formatValue(value)}\${unit}</span>
                     </div>
                     <span class="param-name">\${paramName}</span>
                 </div>
@@ -790,17 +810,17 @@ ${paramsCode};`;
                     const deltaY = startY - e.clientY;
                     const min = parseFloat(knob.dataset.min);
                     const max = parseFloat(knob.dataset.max);
-                    const range = max - min;
-                    const sensitivity = range / 100;
+                    const curve = knob.dataset.curve;
 
-                    let newValue = startValue + deltaY * sensitivity;
-                    newValue = Math.max(min, Math.min(max, newValue));
+                    // Drag in knob positions so exponential params get even steps
+                    const normalized = Math.max(0, Math.min(1,
+                        toNormalized(startValue, min, max, curve) + deltaY / 100));
+                    const newValue = fromNormalized(normalized, min, max, curve);
 
                     // Update visual
-                    const normalized = (newValue - min) / range;
                     const rotation = -135 + normalized * 270;
                     knob.querySelector('.knob-indicator').style.transform = \`translateX(-50%) rotate(\${rotation}deg)\`;
-                    knob.parentElement.querySelector('.knob-value').textContent = formatValue(newValue);
+                    knob.parentElement.querySelector('.knob-value').textContent = formatValue(newValue) + knob.dataset.unit;
                     knob.dataset.value = newValue;
 
                     // Send update
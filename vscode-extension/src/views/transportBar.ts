@@ -8,7 +8,8 @@
 import * as vscode from 'vscode';
 import { StateStore } from '../state/stateStore';
 import { ConnectionStatus } from '../api/runtimeManager';
-import { TransportState } from '../api/types';
+import { DiscoveredSession, TransportState } from '../api/types';
+import { discoverSessions, sessionHost } from '../api/sessionDiscovery';
 
 /**
  * Transport bar providing play/stop controls and beat display.
@@ -311,6 +312,42 @@ export class TransportBar {
                 await store.connect(host, port);
             })
         );
+
+        // Attach to a session found on the LAN
+        context.subscriptions.push(
+            vscode.commands.registerCommand('vibelang.attachToSession', async () => {
+                const binaryPath = vscode.workspace
+                    .getConfiguration('vibelang')
+                    .get<string>('runtime.binaryPath', 'vibe');
+                let sessions: DiscoveredSession[];
+                try {
+                    sessions = await vscode.window.withProgress(
+                        { location: vscode.ProgressLocation.Notification, title: 'Looking for VibeLang sessions...' },
+                        () => discoverSessions(binaryPath)
+                    );
+                } catch (e) {
+                    vscode.window.showErrorMessage(`Failed to look for sessions: ${e instanceof Error ? e.message : e}`);
+                    return;
+                }
+                if (sessions.length === 0) {
+                    vscode.window.showInformationMessage('No VibeLang sessions found on the network.');
+                    return;
+                }
+
+                const picked = await vscode.window.showQuickPick(
+                    sessions.map(session => ({
+                        label: session.name,
+                        description: `${session.host}:${session.port}`,
+                        detail: session.version ? `VibeLang ${session.version}` : undefined,
+                        session,
+                    })),
+                    { placeHolder: 'Select a session to attach to' }
+                );
+                if (!picked) return;
+
+                await store.connect(sessionHost(picked.session), picked.session.port);
+            })
+        );
     }
 
     // ==========================================================================